@@ -0,0 +1,484 @@
+// benches/hot_paths.rs - Criterion benchmarks for the registry's hot paths
+//
+//     cargo bench --bench hot_paths
+//
+// covers token verification, API key hashing, the in-memory cache, digest computation,
+// upload chunk handling and filesystem blob streaming. Set BENCH_DATABASE_URL to a disposable
+// database to add the database-backed benchmarks (API key lookup, request authentication,
+// repository permission checks, and manifest pulls and pushes through the full router); the
+// fixture it needs is seeded on startup and left in place for later runs. Other settings are
+// read from the environment like the server's.
+//
+// Track baselines with criterion's own flags:
+//
+//     cargo bench --bench hot_paths -- --save-baseline main
+//     cargo bench --bench hot_paths -- --baseline main
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use aerugo::{
+    auth::{self, Claims},
+    cache::{BlobCacheMetadata, CacheConfig, RegistryCache},
+    config::Settings,
+    handlers::registry_auth::{AuthContext, RegistryAction},
+    models::api_key::ApiKeyScope,
+    storage::{filesystem::FilesystemStorage, Storage},
+    uploads::{self, Sha256State},
+    AppState,
+};
+use axum::{
+    body::Body,
+    http::{HeaderMap, HeaderValue, Request, StatusCode},
+    Router,
+};
+use bytes::Bytes;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use jsonwebtoken::{encode, EncodingKey, Header};
+use secrecy::ExposeSecret;
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+use tokio::runtime::Runtime;
+use tower::ServiceExt;
+
+const JWT_SECRET: &[u8] = b"benchmark-secret";
+const BENCH_API_KEY: &str = "ak_benchmark0hotpaths0fixture0key0000000000";
+const BENCH_ORG: &str = "bench";
+const BENCH_REPO: &str = "hot-paths";
+/// Size of the body frames upload chunks arrive in, about what hyper hands over per read
+const FRAME_SIZE: usize = 64 << 10;
+
+fn runtime() -> Runtime {
+    tokio::runtime::Builder::new_multi_thread().enable_all().build().expect("tokio runtime")
+}
+
+fn token_for(user_id: i64, secret: &[u8]) -> String {
+    let claims = Claims {
+        sub: user_id.to_string(),
+        exp: (chrono::Utc::now() + chrono::Duration::hours(1)).timestamp() as usize,
+        jti: Some(uuid::Uuid::new_v4().to_string()),
+        sid: Some(uuid::Uuid::new_v4().to_string()),
+    };
+    encode(&Header::default(), &claims, &EncodingKey::from_secret(secret)).expect("encode token")
+}
+
+fn memory_cache(rt: &Runtime) -> Arc<RegistryCache> {
+    let config = CacheConfig { redis_url: None, enable_redis: false, ..CacheConfig::default() };
+    Arc::new(rt.block_on(RegistryCache::new(config)).expect("memory cache"))
+}
+
+fn auth_extraction(c: &mut Criterion) {
+    let mut group = c.benchmark_group("auth");
+    let token = token_for(42, JWT_SECRET);
+    let scopes = vec!["read".to_string(), "push".to_string()];
+
+    group.bench_function("verify_jwt", |b| b.iter(|| auth::verify_token(&token, JWT_SECRET).unwrap()));
+    group.bench_function("hash_api_key", |b| b.iter(|| auth::hash_api_key(BENCH_API_KEY)));
+    group.bench_function("scope_check", |b| b.iter(|| ApiKeyScope::granted_by(ApiKeyScope::Push, &scopes)));
+    group.finish();
+}
+
+fn cache_operations(c: &mut Criterion) {
+    let rt = runtime();
+    let cache = memory_cache(&rt);
+    let manifest = Bytes::from(vec![b'{'; 2048]);
+    rt.block_on(cache.cache_manifest("manifest:bench/app:latest", manifest)).unwrap();
+    rt.block_on(cache.cache_blob_metadata(
+        "sha256:bench",
+        BlobCacheMetadata { digest: "sha256:bench".to_string(), size: 1024, content_type: None, exists: true },
+    ))
+    .unwrap();
+
+    let mut group = c.benchmark_group("cache");
+    group.bench_function("manifest_hit", |b| {
+        b.to_async(&rt).iter(|| async { cache.get_manifest("manifest:bench/app:latest").await.unwrap() })
+    });
+    group.bench_function("manifest_miss", |b| {
+        b.to_async(&rt).iter(|| async { cache.get_manifest("manifest:bench/app:missing").await })
+    });
+    group.bench_function("blob_metadata_hit", |b| {
+        b.to_async(&rt).iter(|| async { cache.get_blob_metadata("sha256:bench").await.unwrap() })
+    });
+    group.bench_function("increment_counter", |b| {
+        b.to_async(&rt).iter(|| async { cache.increment_counter("counter:bench", Duration::from_secs(60)).await })
+    });
+    group.finish();
+}
+
+fn blob_streaming(c: &mut Criterion) {
+    let rt = runtime();
+    let root = std::env::temp_dir().join(format!("aerugo-bench-{}", std::process::id()));
+    let storage = FilesystemStorage::new(root.clone());
+
+    let mut group = c.benchmark_group("blob_streaming");
+    group.sample_size(20);
+    for size in [1usize << 20, 16 << 20] {
+        let data = Bytes::from(vec![0x5a; size]);
+        let key = format!("bench/{}", size);
+        group.throughput(Throughput::Bytes(size as u64));
+
+        group.bench_with_input(BenchmarkId::new("upload", size), &data, |b, data| {
+            b.to_async(&rt).iter(|| async {
+                let reader = Box::new(std::io::Cursor::new(data.clone()));
+                storage.put_blob_streaming(&key, data.len() as u64, reader).await.unwrap()
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("download", size), &data, |b, _| {
+            b.to_async(&rt).iter(|| async {
+                let mut reader = storage.get_blob_streaming(&key).await.unwrap().unwrap();
+                tokio::io::copy(&mut reader, &mut tokio::io::sink()).await.unwrap()
+            })
+        });
+    }
+    group.finish();
+
+    let _ = std::fs::remove_dir_all(root);
+}
+
+fn digest_computation(c: &mut Criterion) {
+    let mut group = c.benchmark_group("digest");
+    for size in [64usize << 10, 1 << 20, 16 << 20] {
+        let data = vec![0x5a; size];
+        group.throughput(Throughput::Bytes(size as u64));
+
+        group.bench_with_input(BenchmarkId::new("one_pass", size), &data, |b, data| {
+            b.iter(|| Sha256::digest(data))
+        });
+        // What an upload does: hash frame by frame with a state that can be saved between chunks
+        group.bench_with_input(BenchmarkId::new("resumable", size), &data, |b, data| {
+            b.iter(|| {
+                let mut sha256 = Sha256State::default();
+                for frame in data.chunks(FRAME_SIZE) {
+                    sha256.update(frame);
+                }
+                sha256.digest()
+            })
+        });
+    }
+    group.finish();
+
+    let mut sha256 = Sha256State::default();
+    sha256.update(&[0x5a; 1000]);
+    c.bench_function("digest/save_and_resume_state", |b| {
+        b.iter(|| Sha256State::from_bytes(&sha256.to_bytes()).unwrap())
+    });
+}
+
+/// A request body arriving in `FRAME_SIZE` frames
+fn chunk_body(data: &Bytes) -> Body {
+    let frames: Vec<Result<Bytes, std::io::Error>> =
+        (0..data.len()).step_by(FRAME_SIZE).map(|at| Ok(data.slice(at..(at + FRAME_SIZE).min(data.len())))).collect();
+    Body::from_stream(futures::stream::iter(frames))
+}
+
+fn upload_chunks(c: &mut Criterion) {
+    let rt = runtime();
+    let root = std::env::temp_dir().join(format!("aerugo-bench-chunks-{}", std::process::id()));
+    let storage = FilesystemStorage::new(root.clone());
+
+    let mut group = c.benchmark_group("upload_chunks");
+    group.sample_size(20);
+    for size in [1usize << 20, 16 << 20] {
+        let data = Bytes::from(vec![0x5a; size]);
+        group.throughput(Throughput::Bytes(size as u64));
+
+        // One PATCH: stream the body to a chunk object while hashing it
+        group.bench_with_input(BenchmarkId::new("store", size), &data, |b, data| {
+            b.to_async(&rt).iter(|| async {
+                let key = uploads::chunk_key("bench/chunks", "session");
+                let stored = uploads::store_chunk(
+                    &storage,
+                    &key,
+                    chunk_body(data),
+                    Some(data.len() as u64),
+                    Sha256State::default(),
+                    u64::MAX,
+                )
+                .await
+                .unwrap();
+                storage.delete_blob(&key).await.unwrap();
+                stored
+            })
+        });
+
+        // Completion: join eight chunks into the blob. Writing the chunks is not timed.
+        let part = data.slice(..size / 8);
+        group.bench_with_input(BenchmarkId::new("compose_8", size), &part, |b, part| {
+            b.to_async(&rt).iter_custom(|iters| {
+                let (storage, part) = (&storage, part.clone());
+                async move {
+                    let mut total = Duration::ZERO;
+                    for _ in 0..iters {
+                        let mut keys = Vec::with_capacity(8);
+                        for _ in 0..8 {
+                            let key = uploads::chunk_key("bench/chunks", "compose");
+                            storage.put_blob(&key, part.clone()).await.unwrap();
+                            keys.push(key);
+                        }
+                        let started = Instant::now();
+                        storage.compose_blob("bench/chunks/composed", &keys).await.unwrap();
+                        total += started.elapsed();
+                    }
+                    total
+                }
+            })
+        });
+    }
+    group.finish();
+
+    let _ = std::fs::remove_dir_all(root);
+}
+
+/// Database-backed fixture: a user with an API key, owning a public repository holding one tagged manifest
+struct Fixture {
+    state: AppState,
+    app: Router,
+    user_id: i64,
+    storage_root: PathBuf,
+}
+
+async fn seed(pool: &PgPool, storage: &dyn Storage) -> anyhow::Result<i64> {
+    let user_id: i64 = sqlx::query_scalar(
+        "INSERT INTO users (username, email, password_hash) VALUES ('bench-user', 'bench-user@bench.invalid', 'unusable')
+         ON CONFLICT (email) DO UPDATE SET disabled_at = NULL
+         RETURNING id",
+    )
+    .fetch_one(pool)
+    .await?;
+
+    sqlx::query(
+        "INSERT INTO api_keys (user_id, name, key_hash, scopes) VALUES ($1, 'bench', $2, '{admin}')
+         ON CONFLICT (key_hash) DO UPDATE SET is_active = true, expires_at = NULL",
+    )
+    .bind(user_id)
+    .bind(auth::hash_api_key(BENCH_API_KEY))
+    .execute(pool)
+    .await?;
+
+    let org_id: i64 = sqlx::query_scalar(
+        "INSERT INTO organizations (name, display_name) VALUES ($1, 'Benchmarks')
+         ON CONFLICT (name) DO UPDATE SET display_name = EXCLUDED.display_name
+         RETURNING id",
+    )
+    .bind(BENCH_ORG)
+    .fetch_one(pool)
+    .await?;
+
+    sqlx::query(
+        "INSERT INTO organization_members (organization_id, user_id, role) VALUES ($1, $2, 'owner')
+         ON CONFLICT (organization_id, user_id) DO UPDATE SET role = 'owner'",
+    )
+    .bind(org_id)
+    .bind(user_id)
+    .execute(pool)
+    .await?;
+
+    let repository_id: i64 = sqlx::query_scalar(
+        "INSERT INTO repositories (organization_id, name, is_public, created_by) VALUES ($1, $2, true, $3)
+         ON CONFLICT (organization_id, name) DO UPDATE SET is_public = true
+         RETURNING id",
+    )
+    .bind(org_id)
+    .bind(BENCH_REPO)
+    .bind(user_id)
+    .fetch_one(pool)
+    .await?;
+
+    let manifest = serde_json::to_vec(&serde_json::json!({
+        "schemaVersion": 2,
+        "mediaType": "application/vnd.oci.image.manifest.v1+json",
+        "config": {
+            "mediaType": "application/vnd.oci.image.config.v1+json",
+            "digest": format!("sha256:{}", hex::encode(Sha256::digest(b"{}"))),
+            "size": 2
+        },
+        "layers": []
+    }))?;
+    let digest = format!("sha256:{}", hex::encode(Sha256::digest(&manifest)));
+    storage
+        .put_blob(&format!("{}/{}/{}", BENCH_ORG, BENCH_REPO, digest), Bytes::from(manifest.clone()))
+        .await?;
+
+    let manifest_id: i64 = sqlx::query_scalar(
+        "INSERT INTO manifests (repository_id, digest, media_type, size, content)
+         VALUES ($1, $2, 'application/vnd.oci.image.manifest.v1+json', $3, $4)
+         ON CONFLICT (repository_id, digest) DO UPDATE SET size = EXCLUDED.size
+         RETURNING id",
+    )
+    .bind(repository_id)
+    .bind(&digest)
+    .bind(manifest.len() as i64)
+    .bind(String::from_utf8(manifest)?)
+    .fetch_one(pool)
+    .await?;
+
+    sqlx::query(
+        "INSERT INTO tags (repository_id, name, manifest_id) VALUES ($1, 'latest', $2)
+         ON CONFLICT (repository_id, name) DO UPDATE SET manifest_id = EXCLUDED.manifest_id",
+    )
+    .bind(repository_id)
+    .bind(manifest_id)
+    .execute(pool)
+    .await?;
+
+    Ok(user_id)
+}
+
+async fn fixture(database_url: &str, cache: Option<Arc<RegistryCache>>) -> anyhow::Result<Fixture> {
+    std::env::set_var("DATABASE_URL", database_url);
+    let settings = Settings::load()?;
+    let db_pool = aerugo::db::create_pool(&settings).await?;
+
+    let storage_root = std::env::temp_dir().join(format!("aerugo-bench-db-{}", std::process::id()));
+    let storage: Arc<dyn Storage> = Arc::new(FilesystemStorage::new(storage_root.clone()));
+    let user_id = seed(&db_pool, storage.as_ref()).await?;
+
+    let state = AppState {
+        db_pool: db_pool.clone(),
+        config: settings.clone(),
+        storage,
+        cache,
+        email_service: Arc::new(aerugo::email::EmailService::new(settings.email.clone())?),
+        webhook_signer: Arc::new(aerugo::webhooks::WebhookSigner::load(&db_pool, &settings.webhooks, &settings.secrets).await?),
+        log_stream: Arc::new(aerugo::log_stream::LogStream::new(settings.log_tail.buffer_size)),
+        standby: Arc::new(aerugo::standby::Standby::new(&settings.standby)),
+        federation: Arc::new(aerugo::federation::Federation::new(&settings.federation)),
+        push_validators: Arc::new(aerugo::push_hooks::PushValidators::new()),
+        notifier: Arc::new(aerugo::notifications::Notifier::new(&settings.notifications, &settings.server)),
+        transfer_limits: Arc::new(aerugo::handlers::transfer_limits::TransferLimits::new(&settings.concurrency)),
+        runtime_config: Arc::new(aerugo::config::reload::RuntimeConfig::new(&settings)),
+        features: Arc::new(aerugo::features::FeatureFlags::new(&settings.features)),
+        read_replica: Arc::new(aerugo::read_replica::ReadReplica::new(None)),
+    };
+    let app = aerugo::create_app(state.clone()).await;
+
+    Ok(Fixture { state, app, user_id, storage_root })
+}
+
+fn database_paths(c: &mut Criterion) {
+    let database_url = match std::env::var("BENCH_DATABASE_URL") {
+        Ok(url) => url,
+        Err(_) => {
+            eprintln!("BENCH_DATABASE_URL is not set; skipping database-backed benchmarks");
+            return;
+        }
+    };
+    let rt = runtime();
+
+    for (label, cache) in [("uncached", None), ("memory_cache", Some(memory_cache(&rt)))] {
+        let fixture = rt.block_on(fixture(&database_url, cache)).expect("seed benchmark database");
+        let state = &fixture.state;
+        let secret = state.config.auth.jwt_secret.expose_secret().as_bytes().to_vec();
+        let token = token_for(fixture.user_id, &secret);
+        let mut api_key_headers = HeaderMap::new();
+        api_key_headers.insert("x-api-key", HeaderValue::from_static(BENCH_API_KEY));
+
+        let mut group = c.benchmark_group(format!("auth_db/{}", label));
+        group.bench_function("lookup_api_key", |b| {
+            b.to_async(&rt).iter(|| async {
+                auth::lookup_api_key(BENCH_API_KEY, &state.db_pool, state.cache.as_ref()).await.unwrap()
+            })
+        });
+        group.bench_function("extract_user_api_key", |b| {
+            b.to_async(&rt).iter(|| async {
+                auth::extract_user_id_dual(None, &api_key_headers, ApiKeyScope::Read, &secret, &state.db_pool, state.cache.as_ref())
+                    .await
+                    .unwrap()
+            })
+        });
+        group.bench_function("extract_user_jwt", |b| {
+            b.to_async(&rt).iter(|| async {
+                let auth = axum_extra::headers::Authorization::bearer(&token).ok().map(axum_extra::TypedHeader);
+                auth::extract_user_id_dual(auth, &HeaderMap::new(), ApiKeyScope::Read, &secret, &state.db_pool, state.cache.as_ref())
+                    .await
+                    .unwrap()
+            })
+        });
+        group.finish();
+
+        let repository = format!("{}/{}", BENCH_ORG, BENCH_REPO);
+        let member = AuthContext { user: Some(fixture.user_id.to_string()), scopes: RegistryAction::ALL.to_vec() };
+        let anonymous = AuthContext { user: None, scopes: Vec::new() };
+        let mut group = c.benchmark_group(format!("permissions/{}", label));
+        for action in [RegistryAction::Pull, RegistryAction::Push] {
+            group.bench_function(format!("authorize_{}", action), |b| {
+                b.to_async(&rt).iter(|| async { member.authorize(state, &repository, action).await.unwrap() })
+            });
+        }
+        // Without FEATURES_ALLOW_ANONYMOUS_PULL this is refused before the public-repository lookup
+        group.bench_function("authorize_anonymous_pull", |b| {
+            b.to_async(&rt).iter(|| async { anonymous.authorize(state, &repository, RegistryAction::Pull).await.is_ok() })
+        });
+        if let Some(cache) = &state.cache {
+            group.bench_function("check_permission_cached", |b| {
+                b.to_async(&rt).iter(|| async {
+                    auth::check_permission_cached(fixture.user_id, &repository, "push", cache, &state.db_pool).await.unwrap()
+                })
+            });
+        }
+        group.finish();
+
+        let mut group = c.benchmark_group(format!("manifest_resolution/{}", label));
+        for reference in ["latest", "missing"] {
+            let uri = format!("/v2/{}/{}/manifests/{}", BENCH_ORG, BENCH_REPO, reference);
+            group.bench_function(reference, |b| {
+                b.to_async(&rt).iter(|| async {
+                    let request = Request::get(&uri)
+                        .header("accept", "application/vnd.oci.image.manifest.v1+json")
+                        .body(Body::empty())
+                        .unwrap();
+                    let response = fixture.app.clone().oneshot(request).await.unwrap();
+                    assert_ne!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+                    axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap()
+                })
+            });
+        }
+        group.finish();
+
+        // Pushes retag `bench-push`; `new_digest` pushes a manifest never seen before each time,
+        // as a CI build does, `same_digest` re-pushes an unchanged one
+        let mut group = c.benchmark_group(format!("manifest_push/{}", label));
+        group.sample_size(30);
+        let uri = format!("/v2/{}/{}/manifests/bench-push", BENCH_ORG, BENCH_REPO);
+        let authorization = format!("Bearer {}", token);
+        for variant in ["same_digest", "new_digest"] {
+            group.bench_function(variant, |b| {
+                b.to_async(&rt).iter(|| async {
+                    let revision = if variant == "new_digest" { uuid::Uuid::new_v4().to_string() } else { "fixed".to_string() };
+                    let manifest = serde_json::json!({
+                        "schemaVersion": 2,
+                        "mediaType": "application/vnd.oci.image.manifest.v1+json",
+                        "config": {
+                            "mediaType": "application/vnd.oci.image.config.v1+json",
+                            "digest": format!("sha256:{}", hex::encode(Sha256::digest(b"{}"))),
+                            "size": 2
+                        },
+                        "layers": [],
+                        "annotations": { "org.opencontainers.image.revision": revision }
+                    });
+                    let request = Request::put(&uri)
+                        .header("authorization", &authorization)
+                        .header("content-type", "application/vnd.oci.image.manifest.v1+json")
+                        .body(Body::from(manifest.to_string()))
+                        .unwrap();
+                    let response = fixture.app.clone().oneshot(request).await.unwrap();
+                    assert_eq!(response.status(), StatusCode::CREATED);
+                })
+            });
+        }
+        group.finish();
+
+        let _ = std::fs::remove_dir_all(&fixture.storage_root);
+    }
+}
+
+criterion_group!(
+    benches,
+    auth_extraction,
+    cache_operations,
+    digest_computation,
+    upload_chunks,
+    blob_streaming,
+    database_paths
+);
+criterion_main!(benches);
@@ -0,0 +1,25 @@
+-- Create refresh tokens table for rotating, per-device refresh tokens
+CREATE TABLE refresh_tokens (
+    id BIGSERIAL PRIMARY KEY,
+    user_id BIGINT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    family_id UUID NOT NULL, -- Shared by every token in one device's rotation chain
+    token_hash VARCHAR(255) NOT NULL UNIQUE, -- SHA-256 hash of the opaque refresh token
+    device_name VARCHAR(255), -- Optional client-supplied label (e.g. "laptop", "ci-runner")
+    expires_at TIMESTAMP NOT NULL,
+    revoked_at TIMESTAMP, -- Set when rotated, logged out or revoked
+    replaced_by BIGINT REFERENCES refresh_tokens(id) ON DELETE SET NULL,
+    last_used_at TIMESTAMP,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Index for faster lookups by token_hash
+CREATE INDEX idx_refresh_tokens_token_hash ON refresh_tokens(token_hash);
+
+-- Index for listing a user's sessions
+CREATE INDEX idx_refresh_tokens_user_id ON refresh_tokens(user_id);
+
+-- Index for revoking a whole device chain
+CREATE INDEX idx_refresh_tokens_family_id ON refresh_tokens(family_id);
+
+-- Index for active tokens only
+CREATE INDEX idx_refresh_tokens_active ON refresh_tokens(expires_at) WHERE revoked_at IS NULL;
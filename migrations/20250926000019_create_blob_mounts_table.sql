@@ -0,0 +1,13 @@
+-- Blob mounts let a repository reference content stored under another
+-- repository's storage prefix instead of copying the bytes
+CREATE TABLE blob_mounts (
+    id BIGSERIAL PRIMARY KEY,
+    repository_id BIGINT NOT NULL REFERENCES repositories(id) ON DELETE CASCADE,
+    digest VARCHAR(255) NOT NULL,
+    source_key VARCHAR(1024) NOT NULL, -- Storage key the content actually lives at
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE(repository_id, digest)
+);
+
+-- Index for finding mounts that point at a given storage key
+CREATE INDEX idx_blob_mounts_source_key ON blob_mounts(source_key);
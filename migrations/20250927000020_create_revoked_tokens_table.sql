@@ -0,0 +1,12 @@
+-- Revoked access tokens, keyed by JWT ID (jti)
+-- Rows are only needed until the token would have expired anyway, so the
+-- cleanup task deletes them once expires_at has passed.
+CREATE TABLE revoked_tokens (
+    jti VARCHAR(64) PRIMARY KEY,
+    user_id BIGINT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    expires_at TIMESTAMP NOT NULL, -- The token's own exp claim
+    revoked_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Index for purging rows past their token expiry
+CREATE INDEX idx_revoked_tokens_expires_at ON revoked_tokens(expires_at);
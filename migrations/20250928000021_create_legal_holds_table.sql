@@ -0,0 +1,20 @@
+-- Legal holds freeze an organization's content for legal/compliance requests
+CREATE TABLE legal_holds (
+    id BIGSERIAL PRIMARY KEY,
+    organization_id BIGINT NOT NULL REFERENCES organizations(id) ON DELETE RESTRICT,
+    reason TEXT NOT NULL,
+    block_deletes BOOLEAN NOT NULL DEFAULT true, -- Reject deletes in the org while active
+    status VARCHAR(50) NOT NULL DEFAULT 'exporting', -- exporting, completed, failed
+    export_prefix VARCHAR(1024), -- Storage prefix the export was written to
+    blob_count BIGINT NOT NULL DEFAULT 0,
+    total_size BIGINT NOT NULL DEFAULT 0,
+    error TEXT,
+    created_by BIGINT REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    completed_at TIMESTAMPTZ,
+    released_by BIGINT REFERENCES users(id) ON DELETE SET NULL,
+    released_at TIMESTAMPTZ
+);
+
+-- Index for checking whether an organization is currently under hold
+CREATE INDEX idx_legal_holds_active ON legal_holds(organization_id) WHERE released_at IS NULL;
@@ -0,0 +1,9 @@
+-- Replace the owner/admin/member roles with owner/maintainer/developer/guest
+UPDATE organization_members SET role = 'maintainer' WHERE role = 'admin';
+UPDATE organization_members SET role = 'developer' WHERE role NOT IN ('owner', 'maintainer');
+
+ALTER TABLE organization_members ALTER COLUMN role SET DEFAULT 'developer';
+
+ALTER TABLE organization_members
+    ADD CONSTRAINT organization_members_role_check
+    CHECK (role IN ('owner', 'maintainer', 'developer', 'guest'));
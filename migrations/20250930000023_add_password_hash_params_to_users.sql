@@ -0,0 +1,12 @@
+-- Record the hashing parameters used for each password so operators can see
+-- how many accounts are still on older, weaker settings
+ALTER TABLE users ADD COLUMN password_hash_params VARCHAR(64);
+
+-- Backfill from the PHC string, e.g. $argon2id$v=19$m=19456,t=2,p=1$... -> argon2id:m=19456,t=2,p=1
+UPDATE users
+SET password_hash_params = substring(password_hash from '^\$(argon2[a-z]*)\$')
+    || ':' || substring(password_hash from '^\$argon2[a-z]*\$v=[0-9]+\$([^$]+)\$')
+WHERE password_hash LIKE '$argon2%';
+
+-- Index for finding accounts hashed with outdated parameters
+CREATE INDEX idx_users_password_hash_params ON users(password_hash_params);
@@ -0,0 +1,19 @@
+-- Daily per-credential API usage rollups, used to spot leaked or unused credentials
+CREATE TABLE api_usage_daily (
+    id BIGSERIAL PRIMARY KEY,
+    user_id BIGINT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    credential_type VARCHAR(20) NOT NULL, -- 'api_key' or 'session'
+    credential_id VARCHAR(64) NOT NULL, -- api_keys.id or refresh_tokens.family_id
+    day DATE NOT NULL,
+    endpoint VARCHAR(255) NOT NULL, -- HTTP method and route template, e.g. "GET /api/v1/repos/:namespace"
+    request_count BIGINT NOT NULL DEFAULT 0,
+    error_count BIGINT NOT NULL DEFAULT 0,
+    last_seen_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (credential_type, credential_id, day, endpoint)
+);
+
+-- Index for a user's usage over a date range
+CREATE INDEX idx_api_usage_daily_user_day ON api_usage_daily(user_id, day);
+
+-- Index for retention cleanup
+CREATE INDEX idx_api_usage_daily_day ON api_usage_daily(day);
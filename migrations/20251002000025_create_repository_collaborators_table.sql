@@ -0,0 +1,14 @@
+-- Per-repository grants for users outside (or above their role in) the organization
+CREATE TABLE repository_collaborators (
+    id BIGSERIAL PRIMARY KEY,
+    repository_id BIGINT NOT NULL REFERENCES repositories(id) ON DELETE CASCADE,
+    user_id BIGINT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    permission VARCHAR(20) NOT NULL CHECK (permission IN ('pull', 'push', 'admin')),
+    granted_by BIGINT REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE(repository_id, user_id)
+);
+
+-- Index for permission checks and "repositories shared with me" lookups
+CREATE INDEX idx_repository_collaborators_user_id ON repository_collaborators(user_id);
@@ -0,0 +1,14 @@
+-- Instance Ed25519 keys used to sign outgoing webhook payloads.
+-- Only used when WEBHOOK_SIGNING_KEY is not configured, so every replica signs with the same key.
+CREATE TABLE webhook_signing_keys (
+    id BIGSERIAL PRIMARY KEY,
+    key_id VARCHAR(32) NOT NULL UNIQUE,
+    algorithm VARCHAR(20) NOT NULL DEFAULT 'ed25519' CHECK (algorithm IN ('ed25519')),
+    public_key TEXT NOT NULL,
+    private_key TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    retired_at TIMESTAMPTZ
+);
+
+-- At most one key signs at a time; retired keys stay published so in-flight deliveries still verify
+CREATE UNIQUE INDEX idx_webhook_signing_keys_active ON webhook_signing_keys((retired_at IS NULL)) WHERE retired_at IS NULL;
@@ -0,0 +1,27 @@
+-- OCI 1.1 referrers: manifests that point at another manifest through `subject`
+ALTER TABLE manifests ADD COLUMN subject_digest VARCHAR(255);
+ALTER TABLE manifests ADD COLUMN artifact_type VARCHAR(255);
+ALTER TABLE manifests ADD COLUMN annotations JSONB;
+
+CREATE INDEX idx_manifests_subject_digest ON manifests(repository_id, subject_digest) WHERE subject_digest IS NOT NULL;
+
+-- gzip layers re-compressed to zstd by the transcoding service; reused across manifests sharing a layer
+CREATE TABLE blob_transcodes (
+    id BIGSERIAL PRIMARY KEY,
+    repository_id BIGINT NOT NULL REFERENCES repositories(id) ON DELETE CASCADE,
+    source_digest VARCHAR(255) NOT NULL,
+    source_size BIGINT NOT NULL,
+    target_digest VARCHAR(255) NOT NULL,
+    target_size BIGINT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE(repository_id, source_digest)
+);
+
+-- One row per manifest the service has looked at, so each is attempted once
+CREATE TABLE manifest_transcodes (
+    manifest_id BIGINT PRIMARY KEY REFERENCES manifests(id) ON DELETE CASCADE,
+    status VARCHAR(20) NOT NULL CHECK (status IN ('completed', 'skipped', 'failed')),
+    variant_digest VARCHAR(255),
+    detail TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
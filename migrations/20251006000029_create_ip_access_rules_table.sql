@@ -0,0 +1,15 @@
+-- CIDR allow/deny rules for registry traffic.
+-- Rules without an organization apply to every request; organization rules apply to its repositories.
+CREATE TABLE ip_access_rules (
+    id BIGSERIAL PRIMARY KEY,
+    organization_id BIGINT REFERENCES organizations(id) ON DELETE CASCADE,
+    cidr CIDR NOT NULL,
+    action VARCHAR(10) NOT NULL CHECK (action IN ('allow', 'deny')),
+    operation VARCHAR(10) NOT NULL DEFAULT 'all' CHECK (operation IN ('all', 'pull', 'push')),
+    description TEXT,
+    created_by BIGINT REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE NULLS NOT DISTINCT (organization_id, cidr, action, operation)
+);
+
+CREATE INDEX idx_ip_access_rules_organization ON ip_access_rules(organization_id);
@@ -0,0 +1,4 @@
+-- Track when an upload session was finished or cancelled; open sessions count against upload quotas
+ALTER TABLE blob_uploads ADD COLUMN IF NOT EXISTS completed_at TIMESTAMPTZ;
+
+CREATE INDEX IF NOT EXISTS idx_blob_uploads_open ON blob_uploads(repository_id, user_id) WHERE completed_at IS NULL;
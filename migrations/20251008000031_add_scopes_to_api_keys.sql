@@ -0,0 +1,4 @@
+-- Scopes limiting what an API key may do: read, push, admin.
+-- Keys created before scopes existed keep full access; new keys default to read-only.
+ALTER TABLE api_keys ADD COLUMN IF NOT EXISTS scopes TEXT[] NOT NULL DEFAULT '{admin}';
+ALTER TABLE api_keys ALTER COLUMN scopes SET DEFAULT '{read}';
@@ -0,0 +1,5 @@
+-- Registry administrators and disabled accounts
+ALTER TABLE users ADD COLUMN IF NOT EXISTS is_admin BOOLEAN NOT NULL DEFAULT false;
+ALTER TABLE users ADD COLUMN IF NOT EXISTS disabled_at TIMESTAMPTZ;
+
+CREATE INDEX IF NOT EXISTS idx_users_disabled ON users(id) WHERE disabled_at IS NOT NULL;
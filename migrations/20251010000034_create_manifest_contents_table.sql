@@ -0,0 +1,16 @@
+-- Manifest bytes kept in the database as the record of what was pushed, so tag resolution
+-- does not depend on object storage. Manifests are content-addressed and small, so one row
+-- per digest is shared by every repository holding it.
+CREATE TABLE manifest_contents (
+    digest VARCHAR(255) PRIMARY KEY,
+    content BYTEA NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Carry over the manifests whose JSON was already kept on the manifest row
+INSERT INTO manifest_contents (digest, content)
+SELECT DISTINCT ON (digest) digest, convert_to(content, 'UTF8')
+FROM manifests
+WHERE content IS NOT NULL
+ORDER BY digest, id
+ON CONFLICT (digest) DO NOTHING;
@@ -0,0 +1,18 @@
+-- Deleted organizations keep their row for auditing; the name is released for reuse
+ALTER TABLE organizations
+    ADD COLUMN deleted_at TIMESTAMPTZ,
+    ADD COLUMN deleted_by BIGINT REFERENCES users(id) ON DELETE SET NULL,
+    ADD COLUMN deleted_name VARCHAR(255);
+
+-- Storage keys whose database records are gone, waiting for the blob garbage collector
+CREATE TABLE blob_gc_queue (
+    id BIGSERIAL PRIMARY KEY,
+    storage_key VARCHAR(1024) NOT NULL,
+    reason VARCHAR(255) NOT NULL,
+    enqueued_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    attempts INT NOT NULL DEFAULT 0,
+    last_error TEXT,
+    processed_at TIMESTAMPTZ
+);
+
+CREATE INDEX idx_blob_gc_queue_pending ON blob_gc_queue(enqueued_at) WHERE processed_at IS NULL;
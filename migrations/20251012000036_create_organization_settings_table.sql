@@ -0,0 +1,16 @@
+-- Defaults applied to repositories created in an organization, and its storage limit.
+-- Organizations without a row use the defaults below.
+CREATE TABLE organization_settings (
+    organization_id BIGINT PRIMARY KEY REFERENCES organizations(id) ON DELETE CASCADE,
+    default_repository_public BOOLEAN NOT NULL DEFAULT false,
+    default_tag_retention_keep_last INT CHECK (default_tag_retention_keep_last > 0),
+    default_tag_retention_days INT CHECK (default_tag_retention_days > 0),
+    storage_quota_bytes BIGINT CHECK (storage_quota_bytes >= 0),
+    updated_by BIGINT REFERENCES users(id) ON DELETE SET NULL,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Tag retention is copied from the organization defaults when a repository is created
+ALTER TABLE repositories
+    ADD COLUMN tag_retention_keep_last INT CHECK (tag_retention_keep_last > 0),
+    ADD COLUMN tag_retention_days INT CHECK (tag_retention_days > 0);
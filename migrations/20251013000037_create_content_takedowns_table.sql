@@ -0,0 +1,21 @@
+-- Moderation takedowns: a whole repository (digest NULL) or one digest within it stops being
+-- served to pulls until reinstated. Rows outlive the repository for the record.
+CREATE TABLE content_takedowns (
+    id BIGSERIAL PRIMARY KEY,
+    repository_id BIGINT REFERENCES repositories(id) ON DELETE SET NULL,
+    repository VARCHAR(512) NOT NULL, -- namespace/name at the time of the takedown
+    digest VARCHAR(255),
+    reason TEXT NOT NULL,
+    notice_reference VARCHAR(255), -- e.g. the DMCA notice or ticket number
+    evidence_status VARCHAR(50) NOT NULL DEFAULT 'capturing', -- capturing, completed, failed
+    evidence_prefix VARCHAR(1024), -- Storage prefix the evidence snapshot was written to
+    evidence_error TEXT,
+    created_by BIGINT REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    reinstated_by BIGINT REFERENCES users(id) ON DELETE SET NULL,
+    reinstated_at TIMESTAMPTZ,
+    reinstatement_note TEXT
+);
+
+-- Pull-time lookups only consider active takedowns
+CREATE INDEX idx_content_takedowns_active ON content_takedowns(repository_id) WHERE reinstated_at IS NULL;
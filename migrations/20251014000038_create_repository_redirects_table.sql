@@ -0,0 +1,13 @@
+-- Former names of repositories that moved. Pulls of the old name are redirected to the
+-- repository's current name until the entry expires or a new repository takes the name.
+CREATE TABLE repository_redirects (
+    id BIGSERIAL PRIMARY KEY,
+    old_namespace VARCHAR(255) NOT NULL,
+    old_name VARCHAR(255) NOT NULL,
+    repository_id BIGINT NOT NULL REFERENCES repositories(id) ON DELETE CASCADE,
+    created_by BIGINT REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    expires_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX idx_repository_redirects_old_name ON repository_redirects(old_namespace, old_name);
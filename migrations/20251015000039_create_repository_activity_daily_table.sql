@@ -0,0 +1,11 @@
+-- Daily pull and push counts per repository, behind the organization usage dashboard
+CREATE TABLE repository_activity_daily (
+    repository_id BIGINT NOT NULL REFERENCES repositories(id) ON DELETE CASCADE,
+    day DATE NOT NULL,
+    pull_count BIGINT NOT NULL DEFAULT 0,
+    push_count BIGINT NOT NULL DEFAULT 0,
+    PRIMARY KEY (repository_id, day)
+);
+
+-- Index for time-window rollups across repositories
+CREATE INDEX idx_repository_activity_daily_day ON repository_activity_daily(day);
@@ -0,0 +1,3 @@
+-- Per-download bandwidth limits; a repository's own limit takes precedence over its organization's
+ALTER TABLE organization_settings ADD COLUMN download_bytes_per_second BIGINT;
+ALTER TABLE repositories ADD COLUMN download_bytes_per_second BIGINT;
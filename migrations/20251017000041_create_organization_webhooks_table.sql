@@ -0,0 +1,18 @@
+-- Webhook endpoints an organization registers for events in any of its repositories.
+-- `events` holds action filters such as `manifest.push` or `repository`; empty means all.
+CREATE TABLE organization_webhooks (
+    id BIGSERIAL PRIMARY KEY,
+    organization_id BIGINT NOT NULL REFERENCES organizations(id) ON DELETE CASCADE,
+    url TEXT NOT NULL,
+    secret TEXT,
+    events TEXT[] NOT NULL DEFAULT '{}',
+    active BOOLEAN NOT NULL DEFAULT true,
+    created_by BIGINT REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    last_delivery_at TIMESTAMPTZ,
+    last_delivery_status INTEGER,
+    last_delivery_error TEXT
+);
+
+CREATE INDEX idx_organization_webhooks_organization ON organization_webhooks(organization_id) WHERE active;
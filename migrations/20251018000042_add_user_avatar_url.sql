@@ -0,0 +1,2 @@
+-- Avatars uploaded through /api/v1/auth/me/avatar; organizations already have the column
+ALTER TABLE users ADD COLUMN avatar_url VARCHAR(500);
@@ -0,0 +1,15 @@
+-- Secret variables of an organization, injected into its build jobs as environment variables.
+-- Values are encrypted with SECRETS_ENCRYPTION_KEY; the name is bound to the ciphertext as
+-- associated data so a value cannot be moved to another secret or organization.
+CREATE TABLE organization_secrets (
+    id BIGSERIAL PRIMARY KEY,
+    organization_id BIGINT NOT NULL REFERENCES organizations(id) ON DELETE CASCADE,
+    name VARCHAR(128) NOT NULL,
+    ciphertext BYTEA NOT NULL,
+    nonce BYTEA NOT NULL,
+    created_by BIGINT REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_by BIGINT REFERENCES users(id) ON DELETE SET NULL,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (organization_id, name)
+);
@@ -0,0 +1,11 @@
+-- Per-image pull statistics, updated with the daily repository counters
+ALTER TABLE manifests
+    ADD COLUMN pull_count BIGINT NOT NULL DEFAULT 0,
+    ADD COLUMN last_pulled_at TIMESTAMPTZ;
+
+-- Latest cleanup suggestions of each repository, recomputed by the background analysis
+CREATE TABLE tag_cleanup_suggestions (
+    repository_id BIGINT PRIMARY KEY REFERENCES repositories(id) ON DELETE CASCADE,
+    suggestions JSONB NOT NULL,
+    computed_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
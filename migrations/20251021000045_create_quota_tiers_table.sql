@@ -0,0 +1,20 @@
+-- Plans assigned to organizations by registry administrators; unset limits are unlimited
+CREATE TABLE quota_tiers (
+    id BIGSERIAL PRIMARY KEY,
+    name VARCHAR(64) NOT NULL UNIQUE,
+    description TEXT,
+    max_storage_bytes BIGINT,
+    max_repositories BIGINT,
+    max_members BIGINT,
+    download_bytes_per_second BIGINT,
+    -- Applies to organizations without a tier of their own
+    is_default BOOLEAN NOT NULL DEFAULT FALSE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE UNIQUE INDEX idx_quota_tiers_single_default ON quota_tiers (is_default) WHERE is_default;
+
+-- A tier cannot be deleted while organizations are on it
+ALTER TABLE organizations ADD COLUMN quota_tier_id BIGINT REFERENCES quota_tiers(id) ON DELETE RESTRICT;
+CREATE INDEX idx_organizations_quota_tier_id ON organizations (quota_tier_id);
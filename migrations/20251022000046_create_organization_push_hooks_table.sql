@@ -0,0 +1,22 @@
+-- HTTP endpoints an organization registers to accept or reject manifest pushes before they are
+-- stored. `fail_open` decides what happens when an endpoint cannot be reached or answers with
+-- something other than a verdict.
+CREATE TABLE organization_push_hooks (
+    id BIGSERIAL PRIMARY KEY,
+    organization_id BIGINT NOT NULL REFERENCES organizations(id) ON DELETE CASCADE,
+    name VARCHAR(100) NOT NULL,
+    url TEXT NOT NULL,
+    secret TEXT,
+    timeout_ms INTEGER NOT NULL DEFAULT 5000 CHECK (timeout_ms BETWEEN 100 AND 30000),
+    fail_open BOOLEAN NOT NULL DEFAULT false,
+    active BOOLEAN NOT NULL DEFAULT true,
+    created_by BIGINT REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    last_called_at TIMESTAMPTZ,
+    last_verdict VARCHAR(10) CHECK (last_verdict IN ('allow', 'deny', 'error')),
+    last_error TEXT,
+    UNIQUE (organization_id, name)
+);
+
+CREATE INDEX idx_organization_push_hooks_organization ON organization_push_hooks(organization_id) WHERE active;
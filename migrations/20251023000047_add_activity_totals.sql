@@ -0,0 +1,22 @@
+-- Running pull and push totals per repository and per tag, shown in repository responses and
+-- updated together with the daily counters when batched counts are flushed
+ALTER TABLE repositories
+    ADD COLUMN pull_count BIGINT NOT NULL DEFAULT 0,
+    ADD COLUMN push_count BIGINT NOT NULL DEFAULT 0,
+    ADD COLUMN last_pulled_at TIMESTAMPTZ,
+    ADD COLUMN last_pushed_at TIMESTAMPTZ;
+
+ALTER TABLE tags
+    ADD COLUMN pull_count BIGINT NOT NULL DEFAULT 0,
+    ADD COLUMN push_count BIGINT NOT NULL DEFAULT 0,
+    ADD COLUMN last_pulled_at TIMESTAMPTZ;
+
+-- Repository totals start from the daily history
+UPDATE repositories r
+SET pull_count = a.pulls, push_count = a.pushes
+FROM (
+    SELECT repository_id, SUM(pull_count) AS pulls, SUM(push_count) AS pushes
+    FROM repository_activity_daily
+    GROUP BY repository_id
+) a
+WHERE a.repository_id = r.id;
@@ -0,0 +1,11 @@
+-- Metadata shown by the tag listing: the compressed size of an image (config and layers, or the
+-- sum of its platform images for an index), the platforms of an index, and who pushed each tag.
+-- Manifests pushed earlier are filled in from their content the first time they are listed.
+ALTER TABLE manifests
+    ADD COLUMN compressed_size BIGINT,
+    ADD COLUMN platforms JSONB;
+
+ALTER TABLE tags
+    ADD COLUMN pushed_by BIGINT REFERENCES users(id) ON DELETE SET NULL;
+
+CREATE INDEX idx_tags_repository_updated ON tags(repository_id, updated_at DESC);
@@ -0,0 +1,18 @@
+-- Webhook endpoints registered for a single repository, next to the organization-wide ones.
+-- `events` holds action filters such as `manifest.push` or `tag`; empty means all.
+CREATE TABLE repository_webhooks (
+    id BIGSERIAL PRIMARY KEY,
+    repository_id BIGINT NOT NULL REFERENCES repositories(id) ON DELETE CASCADE,
+    url TEXT NOT NULL,
+    secret TEXT,
+    events TEXT[] NOT NULL DEFAULT '{}',
+    active BOOLEAN NOT NULL DEFAULT true,
+    created_by BIGINT REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    last_delivery_at TIMESTAMPTZ,
+    last_delivery_status INTEGER,
+    last_delivery_error TEXT
+);
+
+CREATE INDEX idx_repository_webhooks_repository ON repository_webhooks(repository_id) WHERE active;
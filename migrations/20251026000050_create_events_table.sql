@@ -0,0 +1,18 @@
+-- Durable history of registry events: pushes, first pulls of a digest, deletes, permission
+-- changes and other audit events, recorded from each replica's log stream.
+-- `repository` keeps the name at the time of the event; `repository_id` and `organization_id`
+-- scope the feeds and are left dangling or cleared when the repository or organization goes away.
+CREATE TABLE events (
+    id BIGSERIAL PRIMARY KEY,
+    action TEXT NOT NULL,
+    occurred_at TIMESTAMPTZ NOT NULL,
+    user_id BIGINT REFERENCES users(id) ON DELETE SET NULL,
+    organization_id BIGINT,
+    repository_id BIGINT REFERENCES repositories(id) ON DELETE SET NULL,
+    repository TEXT,
+    detail TEXT
+);
+
+CREATE INDEX idx_events_repository ON events(repository_id, id DESC) WHERE repository_id IS NOT NULL;
+CREATE INDEX idx_events_organization ON events(organization_id, id DESC) WHERE organization_id IS NOT NULL;
+CREATE INDEX idx_events_occurred_at ON events(occurred_at);
@@ -0,0 +1,26 @@
+-- One row per attempt to deliver an event to an organization or repository webhook, kept so
+-- receivers can be debugged and deliveries resent. Attempts of one delivery share `guid`, the
+-- `X-Aerugo-Delivery` header; a redelivery reuses it with `redelivery` set. `payload` is the
+-- body that was sent and `response_body` the first 2 KiB of the reply, invalid UTF-8 replaced.
+CREATE TABLE webhook_deliveries (
+    id BIGSERIAL PRIMARY KEY,
+    guid UUID NOT NULL,
+    organization_webhook_id BIGINT REFERENCES organization_webhooks(id) ON DELETE CASCADE,
+    repository_webhook_id BIGINT REFERENCES repository_webhooks(id) ON DELETE CASCADE,
+    event TEXT NOT NULL,
+    payload JSONB NOT NULL,
+    attempt INTEGER NOT NULL,
+    redelivery BOOLEAN NOT NULL DEFAULT false,
+    status_code INTEGER,
+    duration_ms INTEGER NOT NULL,
+    response_body TEXT,
+    error TEXT,
+    delivered_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CHECK ((organization_webhook_id IS NULL) <> (repository_webhook_id IS NULL))
+);
+
+CREATE INDEX idx_webhook_deliveries_organization_webhook ON webhook_deliveries(organization_webhook_id, id DESC)
+    WHERE organization_webhook_id IS NOT NULL;
+CREATE INDEX idx_webhook_deliveries_repository_webhook ON webhook_deliveries(repository_webhook_id, id DESC)
+    WHERE repository_webhook_id IS NOT NULL;
+CREATE INDEX idx_webhook_deliveries_delivered_at ON webhook_deliveries(delivered_at);
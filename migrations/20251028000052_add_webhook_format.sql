@@ -0,0 +1,6 @@
+-- How events are posted to a webhook: the signed JSON payload (`json`), or a templated message
+-- for a Slack incoming webhook (`slack`) or a Discord webhook (`discord`).
+ALTER TABLE organization_webhooks
+    ADD COLUMN format TEXT NOT NULL DEFAULT 'json' CHECK (format IN ('json', 'slack', 'discord'));
+ALTER TABLE repository_webhooks
+    ADD COLUMN format TEXT NOT NULL DEFAULT 'json' CHECK (format IN ('json', 'slack', 'discord'));
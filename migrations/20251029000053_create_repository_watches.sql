@@ -0,0 +1,34 @@
+-- Users watching a repository are emailed about tags pushed to it, immediately or in a daily
+-- digest as their preference says. Pushes wait in `watch_notifications` until the mailer sends
+-- them, so a digest survives restarts and several replicas never email the same push twice.
+CREATE TABLE repository_watches (
+    user_id BIGINT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    repository_id BIGINT NOT NULL REFERENCES repositories(id) ON DELETE CASCADE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (user_id, repository_id)
+);
+
+CREATE INDEX idx_repository_watches_repository ON repository_watches(repository_id);
+
+-- Users without a row get `immediate`
+CREATE TABLE notification_preferences (
+    user_id BIGINT PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    push_email TEXT NOT NULL DEFAULT 'immediate' CHECK (push_email IN ('immediate', 'digest', 'off')),
+    last_digest_at TIMESTAMPTZ,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE watch_notifications (
+    id BIGSERIAL PRIMARY KEY,
+    user_id BIGINT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    repository_id BIGINT NOT NULL REFERENCES repositories(id) ON DELETE CASCADE,
+    repository TEXT NOT NULL,
+    tag TEXT NOT NULL,
+    digest TEXT NOT NULL,
+    pushed_by BIGINT REFERENCES users(id) ON DELETE SET NULL,
+    pushed_at TIMESTAMPTZ NOT NULL,
+    sent_at TIMESTAMPTZ
+);
+
+CREATE INDEX idx_watch_notifications_pending ON watch_notifications(user_id, id) WHERE sent_at IS NULL;
+CREATE INDEX idx_watch_notifications_sent_at ON watch_notifications(sent_at) WHERE sent_at IS NOT NULL;
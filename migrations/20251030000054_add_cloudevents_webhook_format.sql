@@ -0,0 +1,7 @@
+-- Webhooks may also receive each event as a CloudEvents 1.0 structured-mode JSON envelope.
+ALTER TABLE organization_webhooks
+    DROP CONSTRAINT organization_webhooks_format_check,
+    ADD CONSTRAINT organization_webhooks_format_check CHECK (format IN ('json', 'slack', 'discord', 'cloudevents'));
+ALTER TABLE repository_webhooks
+    DROP CONSTRAINT repository_webhooks_format_check,
+    ADD CONSTRAINT repository_webhooks_format_check CHECK (format IN ('json', 'slack', 'discord', 'cloudevents'));
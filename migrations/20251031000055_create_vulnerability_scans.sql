@@ -0,0 +1,38 @@
+-- Vulnerability scans of pushed images, one per repository and manifest digest. A rescan
+-- replaces the findings of the previous one; the severity counts are kept on the scan so
+-- summaries never have to count findings.
+CREATE TABLE vulnerability_scans (
+    id BIGSERIAL PRIMARY KEY,
+    repository_id BIGINT NOT NULL REFERENCES repositories(id) ON DELETE CASCADE,
+    manifest_digest TEXT NOT NULL,
+    status TEXT NOT NULL CHECK (status IN ('running', 'completed', 'failed')),
+    scanner TEXT NOT NULL,
+    critical_count INTEGER NOT NULL DEFAULT 0,
+    high_count INTEGER NOT NULL DEFAULT 0,
+    medium_count INTEGER NOT NULL DEFAULT 0,
+    low_count INTEGER NOT NULL DEFAULT 0,
+    unknown_count INTEGER NOT NULL DEFAULT 0,
+    error TEXT,
+    started_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    completed_at TIMESTAMPTZ,
+    UNIQUE (repository_id, manifest_digest)
+);
+
+CREATE INDEX idx_vulnerability_scans_completed ON vulnerability_scans(repository_id, completed_at DESC)
+    WHERE status = 'completed';
+
+CREATE TABLE vulnerability_findings (
+    id BIGSERIAL PRIMARY KEY,
+    scan_id BIGINT NOT NULL REFERENCES vulnerability_scans(id) ON DELETE CASCADE,
+    vulnerability_id TEXT NOT NULL,
+    package_name TEXT NOT NULL,
+    installed_version TEXT NOT NULL,
+    fixed_version TEXT,
+    severity TEXT NOT NULL CHECK (severity IN ('CRITICAL', 'HIGH', 'MEDIUM', 'LOW', 'UNKNOWN')),
+    title TEXT,
+    primary_url TEXT,
+    -- The scanned file or OS layer the package was found in, e.g. `alpine 3.19` or `app/package-lock.json`
+    target TEXT NOT NULL
+);
+
+CREATE INDEX idx_vulnerability_findings_scan ON vulnerability_findings(scan_id);
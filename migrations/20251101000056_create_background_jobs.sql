@@ -0,0 +1,24 @@
+-- Work done after a push (vulnerability scans, ...) is queued here and picked up by the
+-- workers of any instance with SKIP LOCKED. A failed job goes back to `queued` with a later
+-- `run_after` until it runs out of attempts. At most one job of a kind per manifest is
+-- pending at a time.
+CREATE TABLE background_jobs (
+    id BIGSERIAL PRIMARY KEY,
+    kind TEXT NOT NULL,
+    repository_id BIGINT NOT NULL REFERENCES repositories(id) ON DELETE CASCADE,
+    manifest_digest TEXT NOT NULL,
+    status TEXT NOT NULL DEFAULT 'queued' CHECK (status IN ('queued', 'running', 'succeeded', 'failed')),
+    attempts INTEGER NOT NULL DEFAULT 0,
+    max_attempts INTEGER NOT NULL,
+    last_error TEXT,
+    run_after TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    started_at TIMESTAMPTZ,
+    finished_at TIMESTAMPTZ
+);
+
+CREATE UNIQUE INDEX idx_background_jobs_pending ON background_jobs(kind, repository_id, manifest_digest)
+    WHERE status IN ('queued', 'running');
+CREATE INDEX idx_background_jobs_queued ON background_jobs(run_after) WHERE status = 'queued';
+CREATE INDEX idx_background_jobs_repository ON background_jobs(repository_id, id DESC);
+CREATE INDEX idx_background_jobs_finished_at ON background_jobs(finished_at) WHERE finished_at IS NOT NULL;
@@ -0,0 +1,16 @@
+-- Packages listed by the SBOMs attached to images as referrers, generated by Aerugo or pushed
+-- by clients, so images can be searched by what they contain. Rows go with the SBOM manifest.
+CREATE TABLE sbom_packages (
+    id BIGSERIAL PRIMARY KEY,
+    repository_id BIGINT NOT NULL REFERENCES repositories(id) ON DELETE CASCADE,
+    sbom_manifest_id BIGINT NOT NULL REFERENCES manifests(id) ON DELETE CASCADE,
+    subject_digest TEXT NOT NULL,
+    name TEXT NOT NULL,
+    version TEXT,
+    -- Package URL, e.g. `pkg:apk/alpine/openssl@3.1.4-r5`
+    purl TEXT
+);
+
+CREATE INDEX idx_sbom_packages_name ON sbom_packages(LOWER(name), version);
+CREATE INDEX idx_sbom_packages_sbom ON sbom_packages(sbom_manifest_id);
+CREATE INDEX idx_sbom_packages_subject ON sbom_packages(repository_id, subject_digest);
@@ -0,0 +1,19 @@
+-- Results of verifying the cosign signatures of images against the configured keys and keyless
+-- policy, one per repository and manifest digest. Platform images of a verified index are
+-- recorded as verified through it (`index_digest`).
+CREATE TABLE signature_verifications (
+    id BIGSERIAL PRIMARY KEY,
+    repository_id BIGINT NOT NULL REFERENCES repositories(id) ON DELETE CASCADE,
+    manifest_digest TEXT NOT NULL,
+    status TEXT NOT NULL CHECK (status IN ('verified', 'invalid')),
+    -- The key or keyless identity a verified signature matched
+    signer TEXT,
+    index_digest TEXT,
+    error TEXT,
+    verified_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (repository_id, manifest_digest)
+);
+
+-- Pulls of images without a verified signature are refused
+ALTER TABLE repositories
+    ADD COLUMN require_signature BOOLEAN NOT NULL DEFAULT FALSE;
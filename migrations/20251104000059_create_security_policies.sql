@@ -0,0 +1,21 @@
+-- Rules checked when a manifest is pulled or pushed, owned by an organization and applying to
+-- all of its repositories or, with `repository_id`, to one of them. A violated rule answers the
+-- request with `DENIED` and the policy's name.
+CREATE TABLE security_policies (
+    id BIGSERIAL PRIMARY KEY,
+    organization_id BIGINT NOT NULL REFERENCES organizations(id) ON DELETE CASCADE,
+    repository_id BIGINT REFERENCES repositories(id) ON DELETE CASCADE,
+    name VARCHAR(100) NOT NULL,
+    rule TEXT NOT NULL CHECK (rule IN ('block_severity', 'require_signature', 'deny_tags')),
+    -- Least severe vulnerability that blocks pulls, for `block_severity`
+    severity TEXT CHECK (severity IN ('CRITICAL', 'HIGH', 'MEDIUM', 'LOW')),
+    -- Tags refused on push, for `deny_tags`; `*` matches any run of characters
+    tag_patterns TEXT[] NOT NULL DEFAULT '{}',
+    active BOOLEAN NOT NULL DEFAULT true,
+    created_by BIGINT REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (organization_id, name)
+);
+
+CREATE INDEX idx_security_policies_organization ON security_policies(organization_id) WHERE active;
@@ -0,0 +1,22 @@
+-- Manifests held back after being pushed to a repository with `quarantine_pushes` set. Until a
+-- completed vulnerability scan or a repository administrator releases one, only administrators
+-- can pull it. Released rows are kept so a later push of the same digest is not held again.
+CREATE TABLE manifest_quarantines (
+    id BIGSERIAL PRIMARY KEY,
+    repository_id BIGINT NOT NULL REFERENCES repositories(id) ON DELETE CASCADE,
+    manifest_digest TEXT NOT NULL,
+    status TEXT NOT NULL DEFAULT 'quarantined' CHECK (status IN ('quarantined', 'released')),
+    quarantined_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    released_at TIMESTAMPTZ,
+    -- `scan` when a completed scan released the manifest, `approval` when an administrator did
+    release_reason TEXT CHECK (release_reason IN ('scan', 'approval')),
+    released_by BIGINT REFERENCES users(id) ON DELETE SET NULL,
+    UNIQUE (repository_id, manifest_digest)
+);
+
+CREATE INDEX idx_manifest_quarantines_held ON manifest_quarantines(repository_id, quarantined_at DESC)
+    WHERE status = 'quarantined';
+
+-- New pushes are quarantined
+ALTER TABLE repositories
+    ADD COLUMN quarantine_pushes BOOLEAN NOT NULL DEFAULT FALSE;
@@ -0,0 +1,21 @@
+-- Likely credentials found in the config or layers of a pushed image. Findings are recorded at
+-- push time, before the repository may exist, so they are keyed by its full name; `excerpt` holds
+-- a redacted match, never the secret itself.
+CREATE TABLE secret_findings (
+    id BIGSERIAL PRIMARY KEY,
+    organization_id BIGINT NOT NULL REFERENCES organizations(id) ON DELETE CASCADE,
+    repository TEXT NOT NULL,
+    manifest_digest TEXT NOT NULL,
+    reference TEXT NOT NULL,
+    -- `aws_access_key_id`, `private_key`, `github_token`, ...
+    kind TEXT NOT NULL,
+    -- `config:/config/Env/2` or `<layer digest>:<path>:<line>`
+    location TEXT NOT NULL,
+    excerpt TEXT NOT NULL,
+    -- Whether the push was rejected because of the findings
+    rejected BOOLEAN NOT NULL DEFAULT FALSE,
+    found_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (organization_id, repository, manifest_digest, kind, location)
+);
+
+CREATE INDEX idx_secret_findings_repository ON secret_findings(organization_id, repository, found_at DESC);
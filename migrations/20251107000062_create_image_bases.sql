@@ -0,0 +1,29 @@
+-- The base image each pushed image was built from, as declared by its
+-- `org.opencontainers.image.base.*` annotations or labels, or found by matching its layers against
+-- images stored here. `base_repository_id` and `base_tag` are set when the base lives in this
+-- registry, so later pushes of the base tag and scans of the base digest can advise a rebuild.
+CREATE TABLE image_bases (
+    id BIGSERIAL PRIMARY KEY,
+    repository_id BIGINT NOT NULL REFERENCES repositories(id) ON DELETE CASCADE,
+    manifest_digest TEXT NOT NULL,
+    -- As referenced by the image, e.g. `docker.io/library/alpine:3.20`
+    base_name TEXT,
+    base_digest TEXT,
+    source TEXT NOT NULL CHECK (source IN ('annotation', 'label', 'layers')),
+    base_repository_id BIGINT REFERENCES repositories(id) ON DELETE SET NULL,
+    base_tag TEXT,
+    detected_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    -- Base digest a `base.updated` advisory was last published for, so each update is announced once
+    updated_advised_digest TEXT,
+    -- Whether a `base.vulnerable` advisory was published for `base_digest`
+    vulnerable_advised BOOLEAN NOT NULL DEFAULT FALSE,
+    UNIQUE (repository_id, manifest_digest)
+);
+
+CREATE INDEX idx_image_bases_base ON image_bases(base_repository_id, base_tag) WHERE base_repository_id IS NOT NULL;
+
+-- Layers of pushed images, in order, so an image built on one stored here can be matched by prefix
+ALTER TABLE manifests
+    ADD COLUMN layer_digests TEXT[];
+
+CREATE INDEX idx_manifests_first_layer ON manifests ((layer_digests[1])) WHERE layer_digests IS NOT NULL;
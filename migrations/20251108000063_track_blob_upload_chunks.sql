@@ -0,0 +1,7 @@
+-- Progress of a chunked blob upload: the storage keys of the chunks received so far, in order, their
+-- total size and the SHA-256 state after hashing them, so chunks are streamed straight to storage
+-- and the digest is known without reading the blob back when the upload completes
+ALTER TABLE blob_uploads
+    ADD COLUMN uploaded_bytes BIGINT NOT NULL DEFAULT 0,
+    ADD COLUMN chunk_keys TEXT[] NOT NULL DEFAULT '{}',
+    ADD COLUMN sha256_state BYTEA;
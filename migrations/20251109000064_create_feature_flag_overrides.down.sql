@@ -0,0 +1,2 @@
+-- Overrides are lost; the flags fall back to their `FEATURES_*` settings
+DROP TABLE feature_flag_overrides;
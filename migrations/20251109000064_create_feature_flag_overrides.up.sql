@@ -0,0 +1,8 @@
+-- Feature flags switched by an administrator at runtime, overriding the `FEATURES_*` settings on
+-- every replica until the override is removed
+CREATE TABLE feature_flag_overrides (
+    name TEXT PRIMARY KEY CHECK (name IN ('allow_delete', 'allow_anonymous_pull', 'open_registration', 'readonly_mode')),
+    enabled BOOLEAN NOT NULL,
+    updated_by BIGINT REFERENCES users(id) ON DELETE SET NULL,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
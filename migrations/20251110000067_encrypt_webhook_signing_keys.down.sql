@@ -0,0 +1,6 @@
+-- Encrypted keys cannot be kept without their ciphertext; a new key is generated on next start
+DELETE FROM webhook_signing_keys WHERE private_key IS NULL;
+ALTER TABLE webhook_signing_keys
+    DROP COLUMN private_key_ciphertext,
+    DROP COLUMN private_key_nonce,
+    ALTER COLUMN private_key SET NOT NULL;
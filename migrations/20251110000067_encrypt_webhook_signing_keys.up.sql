@@ -0,0 +1,6 @@
+-- Webhook signing keys are stored encrypted with SECRETS_ENCRYPTION_KEY. Keys stored before keep
+-- their plaintext private_key until the registry starts with an encryption key and seals them.
+ALTER TABLE webhook_signing_keys
+    ADD COLUMN private_key_ciphertext BYTEA,
+    ADD COLUMN private_key_nonce BYTEA,
+    ALTER COLUMN private_key DROP NOT NULL;
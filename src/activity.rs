@@ -0,0 +1,256 @@
+// src/activity.rs - Pull and push counters per repository and tag
+//
+// Counted when a manifest is pulled (GET, not HEAD) or pushed. Each count updates the
+// repository's daily series and running totals, the pushed or pulled tag's totals, and for pulls
+// the manifest's own count and last pull time, which tag cleanup suggestions rely on.
+//
+// With a cache, counts are buffered there (in Redis when available, shared by every replica) and
+// written by `spawn_activity_flusher` every `ACTIVITY_FLUSH_INTERVAL_SECONDS`, so a busy image
+// costs one database write per interval instead of one per pull. Without a cache each count is
+// written right away. Either way counting never slows down or fails registry requests.
+//
+// The first pull of a manifest is published as a `manifest.pull` audit event when it is written,
+// so the event history shows when each digest was first fetched without recording every pull.
+use std::time::Duration;
+
+use chrono::{NaiveDate, Utc};
+use sqlx::PgPool;
+
+use crate::log_stream::{LogEvent, LogStream};
+use crate::AppState;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Activity {
+    Pull,
+    Push,
+}
+
+impl Activity {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Activity::Pull => "pull",
+            Activity::Push => "push",
+        }
+    }
+}
+
+/// One counter: pulls or pushes of a reference on a day
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Counter {
+    pub activity: Activity,
+    pub day: NaiveDate,
+    /// `org/repo`, or `repo` in the default organization
+    pub name: String,
+    /// Tag or digest
+    pub reference: String,
+}
+
+impl Counter {
+    /// Field name of the counter in the cache buffer; names and references never contain newlines
+    fn key(&self) -> String {
+        format!("{}\n{}\n{}\n{}", self.activity.as_str(), self.day, self.name, self.reference)
+    }
+
+    fn from_key(key: &str) -> Option<Self> {
+        let mut parts = key.splitn(4, '\n');
+        let activity = match parts.next()? {
+            "pull" => Activity::Pull,
+            "push" => Activity::Push,
+            _ => return None,
+        };
+        let day = parts.next()?.parse().ok()?;
+        let name = parts.next()?.to_string();
+        let reference = parts.next()?.to_string();
+        Some(Self { activity, day, name, reference })
+    }
+}
+
+/// Count a pull or push of `reference` (tag or digest) in the repository called `name`
+/// (`org/repo`, or `repo` in the default organization)
+pub fn record(state: &AppState, name: &str, reference: &str, activity: Activity) {
+    // A standby's database is a read-only replica
+    if state.standby.is_read_only() {
+        return;
+    }
+    let counter = Counter {
+        activity,
+        day: Utc::now().date_naive(),
+        name: name.to_string(),
+        reference: reference.to_string(),
+    };
+    let cache = state.cache.clone();
+    let pool = state.db_pool.clone();
+    let log_stream = state.log_stream.clone();
+    tokio::spawn(async move {
+        match cache {
+            Some(cache) => cache.buffer_activity(&counter.key(), 1).await,
+            None => match increment(&pool, &counter, 1).await {
+                Ok(first_pull) => publish_first_pull(&log_stream, &counter, first_pull),
+                Err(e) => tracing::warn!("Failed to count {:?} of {}: {}", activity, counter.name, e),
+            },
+        }
+    });
+}
+
+/// Write the counts buffered in the cache; returns how many counters were written
+pub async fn flush(state: &AppState) -> usize {
+    let Some(cache) = &state.cache else {
+        return 0;
+    };
+    let mut written = 0;
+    for (key, amount) in cache.drain_activity().await {
+        let Some(counter) = Counter::from_key(&key) else {
+            tracing::warn!("Dropping unreadable activity counter {:?}", key);
+            continue;
+        };
+        match increment(&state.db_pool, &counter, amount).await {
+            Ok(first_pull) => {
+                publish_first_pull(&state.log_stream, &counter, first_pull);
+                written += 1;
+            }
+            Err(e) => {
+                // Kept for the next flush
+                tracing::warn!("Failed to write {} {:?} of {}: {}", amount, counter.activity, counter.name, e);
+                cache.buffer_activity(&key, amount).await;
+            }
+        }
+    }
+    written
+}
+
+/// Write buffered pull and push counts periodically
+pub fn spawn_activity_flusher(state: AppState) {
+    if state.cache.is_none() {
+        return;
+    }
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(state.config.activity.flush_interval_seconds));
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            interval.tick().await;
+            if state.standby.is_read_only() {
+                continue;
+            }
+            let written = flush(&state).await;
+            if written > 0 {
+                tracing::debug!("Flushed {} activity counters", written);
+            }
+        }
+    });
+}
+
+fn publish_first_pull(log_stream: &LogStream, counter: &Counter, digest: Option<String>) {
+    if let Some(digest) = digest {
+        log_stream.publish(LogEvent::audit("manifest.pull", None, Some(counter.name.clone())).with_detail(digest));
+    }
+}
+
+/// Write one counter; returns the digest of a manifest pulled for the first time
+async fn increment(pool: &PgPool, counter: &Counter, amount: i64) -> Result<Option<String>, sqlx::Error> {
+    let (pulls, pushes) = match counter.activity {
+        Activity::Pull => (amount, 0_i64),
+        Activity::Push => (0, amount),
+    };
+    let (namespace, repo_name) = match counter.name.split_once('/') {
+        Some((namespace, repo_name)) => (Some(namespace), repo_name),
+        None => (None, counter.name.as_str()),
+    };
+
+    let mut tx = pool.begin().await?;
+    let repository_id = sqlx::query_scalar::<_, i64>(
+        "SELECT r.id
+         FROM repositories r
+         JOIN organizations o ON o.id = r.organization_id
+         WHERE r.name = $2 AND (o.name = $1 OR ($1 IS NULL AND o.id = 1))",
+    )
+    .bind(namespace)
+    .bind(repo_name)
+    .fetch_optional(&mut *tx)
+    .await?;
+    let Some(repository_id) = repository_id else {
+        // Deleted since
+        return Ok(None);
+    };
+
+    sqlx::query(
+        "INSERT INTO repository_activity_daily (repository_id, day, pull_count, push_count)
+         VALUES ($1, $2, $3, $4)
+         ON CONFLICT (repository_id, day) DO UPDATE
+         SET pull_count = repository_activity_daily.pull_count + EXCLUDED.pull_count,
+             push_count = repository_activity_daily.push_count + EXCLUDED.push_count",
+    )
+    .bind(repository_id)
+    .bind(counter.day)
+    .bind(pulls)
+    .bind(pushes)
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query(
+        "UPDATE repositories
+         SET pull_count = pull_count + $2,
+             push_count = push_count + $3,
+             last_pulled_at = CASE WHEN $2 > 0 THEN CURRENT_TIMESTAMP ELSE last_pulled_at END,
+             last_pushed_at = CASE WHEN $3 > 0 THEN CURRENT_TIMESTAMP ELSE last_pushed_at END
+         WHERE id = $1",
+    )
+    .bind(repository_id)
+    .bind(pulls)
+    .bind(pushes)
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query(
+        "UPDATE tags
+         SET pull_count = pull_count + $3,
+             push_count = push_count + $4,
+             last_pulled_at = CASE WHEN $3 > 0 THEN CURRENT_TIMESTAMP ELSE last_pulled_at END
+         WHERE repository_id = $1 AND name = $2",
+    )
+    .bind(repository_id)
+    .bind(&counter.reference)
+    .bind(pulls)
+    .bind(pushes)
+    .execute(&mut *tx)
+    .await?;
+
+    let mut first_pull = None;
+    if counter.activity == Activity::Pull {
+        // The joined row is the manifest as it was before this update
+        first_pull = sqlx::query_scalar::<_, Option<String>>(
+            "UPDATE manifests m
+             SET pull_count = m.pull_count + $3, last_pulled_at = CURRENT_TIMESTAMP
+             FROM manifests previous
+             WHERE previous.id = m.id
+               AND m.repository_id = $1
+               AND (m.digest = $2 OR m.id = (SELECT t.manifest_id FROM tags t WHERE t.repository_id = $1 AND t.name = $2))
+             RETURNING CASE WHEN previous.last_pulled_at IS NULL THEN m.digest END",
+        )
+        .bind(repository_id)
+        .bind(&counter.reference)
+        .bind(amount)
+        .fetch_optional(&mut *tx)
+        .await?
+        .flatten();
+    }
+    tx.commit().await?;
+    Ok(first_pull)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counters_survive_the_cache_key() {
+        let counter = Counter {
+            activity: Activity::Pull,
+            day: NaiveDate::from_ymd_opt(2025, 10, 23).unwrap(),
+            name: "acme/team/web".to_string(),
+            reference: "sha256:abc".to_string(),
+        };
+        assert_eq!(Counter::from_key(&counter.key()), Some(counter));
+        assert_eq!(Counter::from_key("fetch\n2025-10-23\nacme/web\nlatest"), None);
+        assert_eq!(Counter::from_key("pull\nyesterday\nacme/web\nlatest"), None);
+    }
+}
@@ -5,20 +5,29 @@ use jsonwebtoken::{decode, DecodingKey, Validation};
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use crate::cache::RegistryCache;
-use crate::models::api_key::ApiKey;
+use crate::models::api_key::{ApiKey, ApiKeyScope};
 use chrono::{DateTime, Utc};
 use sha2::{Sha256, Digest};
 use rand::{thread_rng, Rng};
 use rand::distributions::Alphanumeric;
+use argon2::{
+    password_hash::{rand_core::OsRng, PasswordHash, PasswordHasher, SaltString},
+    Algorithm, Argon2, Params, Version,
+};
+use crate::config::settings::AuthSettings;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Claims {
     pub sub: String, // user id
     pub exp: usize,  // expiration time
+    #[serde(default)]
+    pub jti: Option<String>, // token id, used for revocation
+    #[serde(default)]
+    pub sid: Option<String>, // login session (refresh token family), used for usage analytics
 }
 
 pub fn verify_token(token: &str, secret: &[u8]) -> Result<Claims, StatusCode> {
-    tracing::debug!("Verifying token: {}", token);
+    tracing::debug!("Verifying token: {}", crate::logging::redact(token));
 
     let token_data = decode::<Claims>(
         token,
@@ -30,9 +39,86 @@ pub fn verify_token(token: &str, secret: &[u8]) -> Result<Claims, StatusCode> {
     })?;
 
     tracing::debug!("Token verified successfully for user ID: {}", token_data.claims.sub);
+    crate::logging::record_user(&token_data.claims.sub);
     Ok(token_data.claims)
 }
 
+/// Check whether a token id has been revoked
+pub async fn is_token_revoked(jti: &str, pool: &sqlx::PgPool) -> Result<bool, sqlx::Error> {
+    sqlx::query_scalar::<_, bool>(
+        "SELECT EXISTS(SELECT 1 FROM revoked_tokens WHERE jti = $1)"
+    )
+    .bind(jti)
+    .fetch_one(pool)
+    .await
+}
+
+/// Reject claims whose jti is on the revocation list or whose user has been disabled
+pub async fn ensure_not_revoked(claims: &Claims, pool: &sqlx::PgPool) -> Result<(), StatusCode> {
+    let user_id = claims.sub.parse::<i64>().unwrap_or_default();
+    let rejected = sqlx::query_scalar::<_, bool>(
+        "SELECT EXISTS(SELECT 1 FROM revoked_tokens WHERE jti = $1)
+             OR EXISTS(SELECT 1 FROM users WHERE id = $2 AND disabled_at IS NOT NULL)"
+    )
+    .bind(claims.jti.as_deref())
+    .bind(user_id)
+    .fetch_one(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Database error checking token revocation: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    if rejected {
+        tracing::debug!("Rejected revoked token {:?} for user ID: {}", claims.jti, claims.sub);
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+    Ok(())
+}
+
+/// Verify token signature and expiry, then check the revocation list
+pub async fn verify_token_not_revoked(
+    token: &str,
+    secret: &[u8],
+    pool: &sqlx::PgPool,
+) -> Result<Claims, StatusCode> {
+    let claims = verify_token(token, secret)?;
+    ensure_not_revoked(&claims, pool).await?;
+    Ok(claims)
+}
+
+/// Revoke a token by its jti until the token's own expiry
+pub async fn revoke_token(claims: &Claims, pool: &sqlx::PgPool) -> Result<(), sqlx::Error> {
+    let Some(jti) = &claims.jti else {
+        return Ok(());
+    };
+    let user_id = claims.sub.parse::<i64>().unwrap_or_default();
+    let expires_at = DateTime::<Utc>::from_timestamp(claims.exp as i64, 0)
+        .unwrap_or_else(Utc::now)
+        .naive_utc();
+
+    sqlx::query(
+        "INSERT INTO revoked_tokens (jti, user_id, expires_at) VALUES ($1, $2, $3) ON CONFLICT (jti) DO NOTHING"
+    )
+    .bind(jti)
+    .bind(user_id)
+    .bind(expires_at)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Delete revocation rows whose tokens expired on their own more than `grace_days` ago
+pub async fn cleanup_expired_revocations(pool: &sqlx::PgPool, grace_days: i32) -> Result<i64, sqlx::Error> {
+    let result = sqlx::query("DELETE FROM revoked_tokens WHERE expires_at < NOW() - make_interval(days => $1)")
+        .bind(grace_days)
+        .execute(pool)
+        .await?;
+
+    tracing::info!("Cleaned up {} expired token revocations", result.rows_affected());
+    Ok(result.rows_affected() as i64)
+}
+
 /// Verify token with cache support
 pub async fn verify_token_cached(
     token: &str, 
@@ -42,9 +128,12 @@ pub async fn verify_token_cached(
     // First check cache
     if let Some(auth_entry) = cache.get_auth_token(token).await {
         tracing::debug!("Token found in cache for user ID: {}", auth_entry.user_id);
+        crate::logging::record_user(&auth_entry.user_id);
         return Ok(Claims {
             sub: auth_entry.user_id.to_string(),
             exp: (chrono::Utc::now() + chrono::Duration::hours(24)).timestamp() as usize, // Use current time + 24h
+            jti: auth_entry.jti,
+            sid: auth_entry.sid,
         });
     }
 
@@ -58,6 +147,8 @@ pub async fn verify_token_cached(
             username: format!("user_{}", user_id), // TODO: Get actual username
             email: format!("user_{}@domain.com", user_id), // TODO: Get actual email
             is_admin: false, // TODO: Check actual admin status
+            jti: claims.jti.clone(),
+            sid: claims.sid.clone(),
         };
         
         if let Err(e) = cache.cache_auth_token(token, auth_entry).await {
@@ -69,10 +160,12 @@ pub async fn verify_token_cached(
 }
 
 
-/// Extract user ID with API key and JWT dual authentication support  
+/// Extract user ID with API key and JWT dual authentication support.
+/// API keys must hold a scope covering `required`.
 pub async fn extract_user_id_dual(
     auth: Option<TypedHeader<Authorization<Bearer>>>,
     headers: &HeaderMap,
+    required: ApiKeyScope,
     secret: &[u8],
     pool: &sqlx::PgPool,
     cache: Option<&Arc<RegistryCache>>,
@@ -85,6 +178,7 @@ pub async fn extract_user_id_dual(
     extract_user_id_dual_auth(
         auth, 
         api_key_header, 
+        required,
         secret, 
         pool, 
         cache
@@ -94,9 +188,10 @@ pub async fn extract_user_id_dual(
 pub async fn extract_user_id(
     auth: Option<TypedHeader<Authorization<Bearer>>>, 
     secret: &[u8],
+    pool: &sqlx::PgPool,
 ) -> Result<i64, StatusCode> {
     let auth = auth.ok_or(StatusCode::UNAUTHORIZED)?;
-    let claims = verify_token(auth.token(), secret)?;
+    let claims = verify_token_not_revoked(auth.token(), secret, pool).await?;
     claims
         .sub
         .parse::<i64>()
@@ -107,10 +202,12 @@ pub async fn extract_user_id(
 pub async fn extract_user_id_cached(
     auth: Option<TypedHeader<Authorization<Bearer>>>, 
     secret: &[u8],
+    pool: &sqlx::PgPool,
     cache: &Arc<RegistryCache>,
 ) -> Result<i64, StatusCode> {
     let auth = auth.ok_or(StatusCode::UNAUTHORIZED)?;
     let claims = verify_token_cached(auth.token(), secret, cache).await?;
+    ensure_not_revoked(&claims, pool).await?;
     claims
         .sub
         .parse::<i64>()
@@ -207,17 +304,22 @@ pub fn hash_api_key(api_key: &str) -> String {
     format!("{:x}", hasher.finalize())
 }
 
-/// Verify API key and return user ID (full permissions like JWT)
-pub async fn verify_api_key(
+/// Look up an active, unexpired API key and return its owner and scopes
+pub async fn lookup_api_key(
     api_key: &str,
     pool: &sqlx::PgPool,
     cache: Option<&Arc<RegistryCache>>,
-) -> Result<i64, StatusCode> {
+) -> Result<(i64, Vec<String>), StatusCode> {
     let key_hash = hash_api_key(api_key);
     
     // Check cache first if available
     if let Some(cache) = cache {
         if let Some(cached_info) = cache.get_api_key_info(&key_hash).await {
+            if cached_info.expires_at.is_some_and(|expires_at| expires_at.and_utc() < Utc::now()) {
+                tracing::warn!("API key expired (cached, user: {})", cached_info.user_id);
+                return Err(StatusCode::UNAUTHORIZED);
+            }
+            
             // Update last_used_at in background (fire and forget)
             let pool_clone = pool.clone();
             let key_hash_clone = key_hash.clone();
@@ -230,7 +332,7 @@ pub async fn verify_api_key(
                 .await;
             });
             
-            return Ok(cached_info.user_id);
+            return Ok((cached_info.user_id, cached_info.scopes));
         }
     }
     
@@ -238,9 +340,10 @@ pub async fn verify_api_key(
     let api_key_record = sqlx::query_as!(
         ApiKey,
         r#"
-        SELECT id, user_id, name, key_hash, last_used_at, expires_at, created_at, updated_at, is_active
+        SELECT id, user_id, name, key_hash, scopes, last_used_at, expires_at, created_at, updated_at, is_active
         FROM api_keys 
         WHERE key_hash = $1 AND is_active = true
+          AND user_id NOT IN (SELECT id FROM users WHERE disabled_at IS NOT NULL)
         "#,
         key_hash
     )
@@ -275,17 +378,37 @@ pub async fn verify_api_key(
         let cache_info = crate::cache::ApiKeyCacheEntry {
             user_id: api_key_record.user_id,
             expires_at: api_key_record.expires_at,
+            scopes: api_key_record.scopes.clone(),
         };
         let _ = cache.cache_api_key_info(&key_hash, cache_info).await;
     }
     
-    Ok(api_key_record.user_id)
+    Ok((api_key_record.user_id, api_key_record.scopes))
 }
 
-/// Extract user ID from either JWT token or API key (simplified, full access)
+/// Verify API key and return user ID, rejecting keys not scoped for `required` with 403
+pub async fn verify_api_key(
+    api_key: &str,
+    required: ApiKeyScope,
+    pool: &sqlx::PgPool,
+    cache: Option<&Arc<RegistryCache>>,
+) -> Result<i64, StatusCode> {
+    let (user_id, scopes) = lookup_api_key(api_key, pool, cache).await?;
+    
+    if !ApiKeyScope::granted_by(required, &scopes) {
+        tracing::warn!("API key of user {} lacks the {} scope (has {:?})", user_id, required, scopes);
+        return Err(StatusCode::FORBIDDEN);
+    }
+    
+    Ok(user_id)
+}
+
+/// Extract user ID from either JWT token or API key.
+/// Session tokens have full access; API keys must hold a scope covering `required`.
 pub async fn extract_user_id_dual_auth(
     auth: Option<TypedHeader<Authorization<Bearer>>>,
     api_key_header: Option<&str>, // X-API-Key header value
+    required: ApiKeyScope,
     secret: &[u8],
     pool: &sqlx::PgPool,
     cache: Option<&Arc<RegistryCache>>,
@@ -294,7 +417,7 @@ pub async fn extract_user_id_dual_auth(
     if let Some(api_key) = api_key_header {
         if api_key.starts_with("ak_") {
             tracing::debug!("Attempting API key authentication");
-            return verify_api_key(api_key, pool, cache).await;
+            return verify_api_key(api_key, required, pool, cache).await.map(recorded_user);
         }
     }
     
@@ -305,17 +428,18 @@ pub async fn extract_user_id_dual_auth(
         // Check if it's an API key (starts with ak_)
         if token.starts_with("ak_") {
             tracing::debug!("Attempting API key authentication via Bearer");
-            return verify_api_key(token, pool, cache).await;
+            return verify_api_key(token, required, pool, cache).await.map(recorded_user);
         }
         
         // Otherwise treat as JWT
         tracing::debug!("Attempting JWT authentication");
         if let Some(cache) = cache {
             let claims = verify_token_cached(token, secret, cache).await?;
+            ensure_not_revoked(&claims, pool).await?;
             let user_id = claims.sub.parse::<i64>().map_err(|_| StatusCode::UNAUTHORIZED)?;
             return Ok(user_id);
         } else {
-            let claims = verify_token(token, secret)?;
+            let claims = verify_token_not_revoked(token, secret, pool).await?;
             let user_id = claims.sub.parse::<i64>().map_err(|_| StatusCode::UNAUTHORIZED)?;
             return Ok(user_id);
         }
@@ -324,4 +448,96 @@ pub async fn extract_user_id_dual_auth(
     Err(StatusCode::UNAUTHORIZED)
 }
 
+fn recorded_user(user_id: i64) -> i64 {
+    crate::logging::record_user(user_id);
+    user_id
+}
+
+/// Argon2id hasher using the configured cost parameters
+pub fn password_hasher(settings: &AuthSettings) -> Result<Argon2<'static>, argon2::Error> {
+    let params = Params::new(
+        settings.argon2_memory_kib,
+        settings.argon2_iterations,
+        settings.argon2_parallelism,
+        None,
+    )?;
+    Ok(Argon2::new(Algorithm::Argon2id, Version::V0x13, params))
+}
 
+/// Label recorded alongside each hash, e.g. `argon2id:m=19456,t=2,p=1`
+pub fn password_params_label(settings: &AuthSettings) -> String {
+    format!(
+        "argon2id:m={},t={},p={}",
+        settings.argon2_memory_kib, settings.argon2_iterations, settings.argon2_parallelism
+    )
+}
+
+/// Hash a password with the configured parameters.
+/// Returns the PHC hash string and the parameter label to store with it.
+pub fn hash_password(password: &str, settings: &AuthSettings) -> anyhow::Result<(String, String)> {
+    let salt = SaltString::generate(&mut OsRng);
+    let hash = password_hasher(settings)
+        .map_err(|e| anyhow::anyhow!("Invalid Argon2 parameters: {}", e))?
+        .hash_password(password.as_bytes(), &salt)
+        .map_err(|e| anyhow::anyhow!("Password hashing failed: {}", e))?
+        .to_string();
+    Ok((hash, password_params_label(settings)))
+}
+
+/// Whether a stored hash is weaker than the configured parameters (or not Argon2id at all)
+pub fn password_needs_rehash(hash: &PasswordHash<'_>, settings: &AuthSettings) -> bool {
+    if hash.algorithm != Algorithm::Argon2id.ident() {
+        return true;
+    }
+    match Params::try_from(hash) {
+        Ok(params) => {
+            params.m_cost() < settings.argon2_memory_kib
+                || params.t_cost() < settings.argon2_iterations
+                || params.p_cost() < settings.argon2_parallelism
+        }
+        Err(_) => true,
+    }
+}
+
+/// After a successful login, upgrade the user's hash if the configured parameters
+/// have been strengthened since it was created, or if it predates Argon2. Failures are logged, never surfaced,
+/// so a rehash problem cannot block a valid login.
+pub async fn rehash_password_if_needed(
+    pool: &sqlx::PgPool,
+    settings: &AuthSettings,
+    user_id: i64,
+    stored_hash: &str,
+    password: &str,
+) {
+    // Hashes that are not PHC strings (legacy bcrypt) are always upgraded
+    let needs_rehash = match PasswordHash::new(stored_hash) {
+        Ok(parsed) => password_needs_rehash(&parsed, settings),
+        Err(_) => true,
+    };
+    if !needs_rehash {
+        return;
+    }
+
+    let (new_hash, params) = match hash_password(password, settings) {
+        Ok(result) => result,
+        Err(e) => {
+            tracing::error!("Failed to rehash password for user {}: {}", user_id, e);
+            return;
+        }
+    };
+
+    // Only replace the hash we verified against, in case the password changed concurrently
+    match sqlx::query(
+        "UPDATE users SET password_hash = $1, password_hash_params = $2 WHERE id = $3 AND password_hash = $4",
+    )
+    .bind(&new_hash)
+    .bind(&params)
+    .bind(user_id)
+    .bind(stored_hash)
+    .execute(pool)
+    .await
+    {
+        Ok(_) => tracing::info!("Rehashed password for user {} with {}", user_id, params),
+        Err(e) => tracing::error!("Failed to store rehashed password for user {}: {}", user_id, e),
+    }
+}
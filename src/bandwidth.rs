@@ -0,0 +1,95 @@
+// src/bandwidth.rs - Per-download bandwidth shaping for blob pulls
+//
+// Every blob download is streamed at no more than the rate configured for its repository, its
+// organization, or the instance, so a single large pull cannot take the whole egress link.
+use std::time::Duration;
+
+use axum::body::Body;
+use bytes::Bytes;
+use sqlx::PgPool;
+use tokio::time::Instant;
+
+use crate::AppState;
+
+/// Smallest and largest pieces a throttled download is sent in
+const MIN_CHUNK_BYTES: usize = 1024;
+const MAX_CHUNK_BYTES: usize = 1024 * 1024;
+
+/// Rate a download from the repository called `name` is held to: the repository's own limit,
+/// then its organization's, then `BANDWIDTH_DOWNLOAD_BYTES_PER_SECOND`, capped by the
+/// organization's quota tier. `None` is unlimited.
+pub async fn download_limit(state: &AppState, name: &str) -> Option<u64> {
+    let (configured, tier) = match repository_limit(&state.db_pool, name).await {
+        Ok(limits) => limits,
+        Err(e) => {
+            tracing::warn!("Failed to look up the download limit of {}: {}", name, e);
+            (None, None)
+        }
+    };
+    let limit = configured
+        .and_then(|limit| u64::try_from(limit).ok())
+        .or(state.config.bandwidth.download_bytes_per_second);
+    match (limit, tier.and_then(|limit| u64::try_from(limit).ok())) {
+        (Some(limit), Some(tier)) => Some(limit.min(tier)),
+        (limit, tier) => limit.or(tier),
+    }
+}
+
+/// (repository or organization limit, quota tier limit)
+async fn repository_limit(pool: &PgPool, name: &str) -> Result<(Option<i64>, Option<i64>), sqlx::Error> {
+    let (namespace, repo_name) = match name.split_once('/') {
+        Some((namespace, repo_name)) => (Some(namespace), repo_name),
+        None => (None, name),
+    };
+    let limits = sqlx::query_as::<_, (Option<i64>, Option<i64>)>(
+        "SELECT COALESCE(r.download_bytes_per_second, s.download_bytes_per_second), t.download_bytes_per_second
+         FROM repositories r
+         JOIN organizations o ON o.id = r.organization_id
+         LEFT JOIN organization_settings s ON s.organization_id = r.organization_id
+         LEFT JOIN quota_tiers t ON t.id = o.quota_tier_id OR (o.quota_tier_id IS NULL AND t.is_default)
+         WHERE r.name = $2 AND (o.name = $1 OR ($1 IS NULL AND o.id = 1))",
+    )
+    .bind(namespace)
+    .bind(repo_name)
+    .fetch_optional(pool)
+    .await?;
+    Ok(limits.unwrap_or((None, None)))
+}
+
+/// Response body sending `data` at no more than `bytes_per_second`
+pub fn throttled_body(data: Bytes, bytes_per_second: u64) -> Body {
+    let rate = bytes_per_second.max(1);
+    // About ten pieces a second keeps the pacing smooth without tiny writes
+    let chunk = ((rate / 10) as usize).clamp(MIN_CHUNK_BYTES, MAX_CHUNK_BYTES);
+    let start = Instant::now();
+
+    let stream = futures::stream::unfold((data, 0_u64), move |(mut remaining, sent)| async move {
+        if remaining.is_empty() {
+            return None;
+        }
+        // Wait until the bytes already sent are within the allowed rate
+        let due = start + Duration::from_secs_f64(sent as f64 / rate as f64);
+        tokio::time::sleep_until(due).await;
+
+        let piece = remaining.split_to(chunk.min(remaining.len()));
+        let sent = sent + piece.len() as u64;
+        Some((Ok::<_, std::io::Error>(piece), (remaining, sent)))
+    });
+    Body::from_stream(stream)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn paces_download_to_the_rate() {
+        let data = Bytes::from(vec![7_u8; 4096]);
+        let started = Instant::now();
+        let body = axum::body::to_bytes(throttled_body(data.clone(), 20480), usize::MAX).await.unwrap();
+
+        assert_eq!(body, data);
+        // Sent in 2 KiB pieces at 20 KiB/s: the second piece is due after 100ms
+        assert!(started.elapsed() >= Duration::from_millis(100));
+    }
+}
@@ -0,0 +1,422 @@
+// src/base_images.rs - Tracking the base images pushed images were built from
+//
+// `base_image` jobs (see `crate::jobs`) record the base of every pushed image in `image_bases`:
+// the `org.opencontainers.image.base.name` and `.base.digest` annotations of its manifest, the
+// labels of the same names in its config, or else the stored image whose layers its own layers
+// start with. Bases named with one of `BASE_IMAGE_REGISTRY_HOSTS`, or found by their layers, are
+// tied to a repository of this registry - of the same organization, or public. Rebuilding an image
+// is recommended once its base tag points at another image (`base.updated`) or the scan of the
+// digest it was built from finds vulnerabilities of `BASE_IMAGE_ADVISORY_SEVERITY` or worse
+// (`base.vulnerable`). Both advisories are published once per image as audit events of the
+// image's repository, so they reach its webhooks, chat channels and activity feed.
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+use serde_json::Value;
+use sqlx::PgPool;
+
+use crate::log_stream::LogEvent;
+use crate::models::vulnerability::{Severity, SeverityCounts};
+use crate::policies::at_least;
+use crate::push_hooks::config_labels;
+use crate::webhooks::chat::short_digest;
+use crate::AppState;
+
+pub const BASE_NAME_ANNOTATION: &str = "org.opencontainers.image.base.name";
+pub const BASE_DIGEST_ANNOTATION: &str = "org.opencontainers.image.base.digest";
+
+/// Reason given when the base tag points at another image than the one built on
+pub const BASE_UPDATED: &str = "base_updated";
+/// Reason given when the image built on has vulnerabilities at or above the advisory severity
+pub const BASE_VULNERABLE: &str = "base_vulnerable";
+
+/// Tags pointing at the image of an `image_bases` row `b`, directly or through an index
+pub(crate) const TAGS_OF_IMAGE: &str = "SELECT t.name FROM tags t JOIN manifests tm ON tm.id = t.manifest_id
+     WHERE t.repository_id = b.repository_id
+       AND (tm.digest = b.manifest_digest
+            OR tm.platforms @> jsonb_build_array(jsonb_build_object('digest', b.manifest_digest)))";
+
+/// How the base of an image was identified
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Source {
+    Annotation,
+    Label,
+    Layers,
+}
+
+impl Source {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Source::Annotation => "annotation",
+            Source::Label => "label",
+            Source::Layers => "layers",
+        }
+    }
+}
+
+/// A base as an image declares it
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Declared {
+    name: Option<String>,
+    digest: Option<String>,
+    source: Source,
+}
+
+/// A base image name that points into this registry
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct LocalName {
+    namespace: String,
+    repository: String,
+    tag: Option<String>,
+    digest: Option<String>,
+}
+
+/// A stored image another one is built on
+#[derive(Debug, sqlx::FromRow)]
+struct StoredBase {
+    repository_id: i64,
+    digest: String,
+    tag: Option<String>,
+}
+
+/// Record the base of a pushed image, and advise rebuilding the images built on an earlier image
+/// of the tag it was pushed to
+pub async fn track(state: &AppState, repository_id: i64, repository: &str, digest: &str) -> Result<()> {
+    let content = sqlx::query_scalar::<_, Vec<u8>>("SELECT content FROM manifest_contents WHERE digest = $1")
+        .bind(digest)
+        .fetch_optional(&state.db_pool)
+        .await?;
+    let Some(manifest) = content.and_then(|content| serde_json::from_slice::<Value>(&content).ok()) else {
+        return Ok(());
+    };
+    let layers: Vec<String> = manifest
+        .get("layers")
+        .and_then(Value::as_array)
+        .map(|layers| layers.iter().filter_map(|layer| layer.get("digest")?.as_str().map(str::to_string)).collect())
+        .unwrap_or_default();
+    if !layers.is_empty() {
+        sqlx::query("UPDATE manifests SET layer_digests = $3 WHERE repository_id = $1 AND digest = $2")
+            .bind(repository_id)
+            .bind(digest)
+            .bind(&layers)
+            .execute(&state.db_pool)
+            .await?;
+    }
+
+    let labels = config_labels(state, repository, &manifest).await;
+    let declared = declared_base(manifest.get("annotations"), &labels);
+    let local = declared
+        .as_ref()
+        .and_then(|declared| declared.name.as_deref())
+        .and_then(|name| local_name(name, &state.config.base_images.registry_hosts));
+
+    let mut base_digest = declared.as_ref().and_then(|declared| declared.digest.clone());
+    let mut base_repository_id = None;
+    let mut base_tag = None;
+    if let Some(local) = &local {
+        base_repository_id = find_repository(&state.db_pool, repository_id, local).await?;
+        if base_repository_id.is_some() {
+            base_tag = local.tag.clone();
+            base_digest = base_digest.or_else(|| local.digest.clone());
+        }
+    }
+    // Without a declaration, or to learn which image of a declared repository was built on
+    if declared.is_none() || (base_repository_id.is_some() && base_digest.is_none()) {
+        if let Some(stored) = find_by_layers(&state.db_pool, repository_id, digest, &layers, base_repository_id).await? {
+            base_repository_id = Some(stored.repository_id);
+            base_digest = Some(stored.digest);
+            if declared.is_none() {
+                base_tag = stored.tag;
+            }
+        }
+    }
+
+    if declared.is_some() || base_repository_id.is_some() {
+        let source = declared.as_ref().map(|declared| declared.source).unwrap_or(Source::Layers);
+        sqlx::query(
+            "INSERT INTO image_bases
+                 (repository_id, manifest_digest, base_name, base_digest, source, base_repository_id, base_tag)
+             VALUES ($1, $2, $3, $4, $5, $6, $7)
+             ON CONFLICT (repository_id, manifest_digest) DO UPDATE SET
+                 base_name = EXCLUDED.base_name, base_digest = EXCLUDED.base_digest, source = EXCLUDED.source,
+                 base_repository_id = EXCLUDED.base_repository_id, base_tag = EXCLUDED.base_tag,
+                 detected_at = CURRENT_TIMESTAMP,
+                 vulnerable_advised = image_bases.vulnerable_advised
+                     AND image_bases.base_digest IS NOT DISTINCT FROM EXCLUDED.base_digest",
+        )
+        .bind(repository_id)
+        .bind(digest)
+        .bind(declared.as_ref().and_then(|declared| declared.name.as_deref()))
+        .bind(base_digest.as_deref())
+        .bind(source.as_str())
+        .bind(base_repository_id)
+        .bind(base_tag.as_deref())
+        .execute(&state.db_pool)
+        .await?;
+    }
+
+    advise_updated(state, repository_id, repository, digest).await
+}
+
+/// Publish `base.updated` for the images built on an earlier image of a tag now pointing at `digest`
+async fn advise_updated(state: &AppState, repository_id: i64, repository: &str, digest: &str) -> Result<()> {
+    let advised = sqlx::query_as::<_, (i64, String, String, String)>(&format!(
+        "UPDATE image_bases b SET updated_advised_digest = $2
+         FROM tags t, manifests m, repositories r, organizations o
+         WHERE b.base_repository_id = $1 AND t.repository_id = b.base_repository_id AND t.name = b.base_tag
+           AND m.id = t.manifest_id AND m.digest = $2
+           AND b.base_digest IS NOT NULL AND b.base_digest <> $2
+           AND b.updated_advised_digest IS DISTINCT FROM $2
+           AND r.id = b.repository_id AND o.id = r.organization_id
+           AND EXISTS ({})
+         RETURNING r.organization_id, o.name || '/' || r.name, b.manifest_digest, t.name",
+        TAGS_OF_IMAGE
+    ))
+    .bind(repository_id)
+    .bind(digest)
+    .fetch_all(&state.db_pool)
+    .await?;
+
+    for (organization_id, image_repository, image_digest, tag) in advised {
+        state.log_stream.publish(
+            LogEvent::audit("base.updated", None, Some(image_repository))
+                .with_organization(organization_id)
+                .with_detail(format!(
+                    "{}: base {}:{} now points at {}, rebuild recommended",
+                    short_digest(&image_digest),
+                    repository,
+                    tag,
+                    short_digest(digest)
+                )),
+        );
+    }
+    Ok(())
+}
+
+/// Publish `base.vulnerable` for the images built on `digest` once its scan finds vulnerabilities
+/// of the advisory severity or worse
+pub async fn advise_vulnerable(
+    state: &AppState,
+    repository_id: i64,
+    repository: &str,
+    digest: &str,
+    counts: &SeverityCounts,
+) -> Result<()> {
+    let settings = &state.config.base_images;
+    if !settings.enabled {
+        return Ok(());
+    }
+    let threshold = settings.advisory_severity.parse::<Severity>().unwrap_or(Severity::High);
+    let count = at_least(counts, threshold);
+    if count == 0 {
+        return Ok(());
+    }
+
+    let advised = sqlx::query_as::<_, (i64, String, String)>(&format!(
+        "UPDATE image_bases b SET vulnerable_advised = TRUE
+         FROM repositories r, organizations o
+         WHERE b.base_repository_id = $1 AND b.base_digest = $2 AND NOT b.vulnerable_advised
+           AND r.id = b.repository_id AND o.id = r.organization_id
+           AND EXISTS ({})
+         RETURNING r.organization_id, o.name || '/' || r.name, b.manifest_digest",
+        TAGS_OF_IMAGE
+    ))
+    .bind(repository_id)
+    .bind(digest)
+    .fetch_all(&state.db_pool)
+    .await?;
+
+    for (organization_id, image_repository, image_digest) in advised {
+        state.log_stream.publish(
+            LogEvent::audit("base.vulnerable", None, Some(image_repository))
+                .with_organization(organization_id)
+                .with_detail(format!(
+                    "{}: base {}@{} has {} vulnerabilities of severity {} or worse, rebuild recommended",
+                    short_digest(&image_digest),
+                    repository,
+                    short_digest(digest),
+                    count,
+                    threshold
+                )),
+        );
+    }
+    Ok(())
+}
+
+/// Why rebuilding an image is recommended: its base tag moved on, or the image it was built on
+/// has vulnerabilities of `threshold` severity or worse
+pub fn advisory_reasons(
+    base_digest: Option<&str>,
+    current_base_digest: Option<&str>,
+    base_vulnerabilities: Option<&SeverityCounts>,
+    threshold: Severity,
+) -> Vec<String> {
+    let mut reasons = Vec::new();
+    if let (Some(built_on), Some(current)) = (base_digest, current_base_digest) {
+        if built_on != current {
+            reasons.push(BASE_UPDATED.to_string());
+        }
+    }
+    if base_vulnerabilities.is_some_and(|counts| at_least(counts, threshold) > 0) {
+        reasons.push(BASE_VULNERABLE.to_string());
+    }
+    reasons
+}
+
+/// The base an image declares in its manifest annotations, or else its config labels
+fn declared_base(annotations: Option<&Value>, labels: &BTreeMap<String, String>) -> Option<Declared> {
+    let annotation = |key: &str| {
+        annotations
+            .and_then(|annotations| annotations.get(key))
+            .and_then(Value::as_str)
+            .filter(|value| !value.is_empty())
+            .map(str::to_string)
+    };
+    let label = |key: &str| labels.get(key).filter(|value| !value.is_empty()).cloned();
+    [
+        (Source::Annotation, annotation(BASE_NAME_ANNOTATION), annotation(BASE_DIGEST_ANNOTATION)),
+        (Source::Label, label(BASE_NAME_ANNOTATION), label(BASE_DIGEST_ANNOTATION)),
+    ]
+    .into_iter()
+    .find(|(_, name, digest)| name.is_some() || digest.is_some())
+    .map(|(source, name, digest)| Declared { name, digest, source })
+}
+
+/// `host/namespace/repository[:tag][@digest]` split up, when `host` is one this registry is
+/// reached at. A name with neither tag nor digest refers to `latest`.
+fn local_name(name: &str, hosts: &[String]) -> Option<LocalName> {
+    let (host, path) = name.split_once('/')?;
+    if !hosts.iter().any(|known| known.eq_ignore_ascii_case(host)) {
+        return None;
+    }
+    let (path, digest) = match path.split_once('@') {
+        Some((path, digest)) => (path, Some(digest.to_string())),
+        None => (path, None),
+    };
+    let (path, tag) = match path.rsplit_once(':') {
+        Some((path, tag)) if !tag.contains('/') => (path, Some(tag.to_string())),
+        _ => (path, None),
+    };
+    let (namespace, repository) = path.split_once('/')?;
+    if namespace.is_empty() || repository.is_empty() {
+        return None;
+    }
+    let tag = match (tag, &digest) {
+        (None, None) => Some("latest".to_string()),
+        (tag, _) => tag,
+    };
+    Some(LocalName { namespace: namespace.to_string(), repository: repository.to_string(), tag, digest })
+}
+
+/// A repository named by a base image, if the image's repository may see it
+async fn find_repository(pool: &PgPool, repository_id: i64, name: &LocalName) -> Result<Option<i64>, sqlx::Error> {
+    sqlx::query_scalar::<_, i64>(
+        "SELECT r.id FROM repositories r
+         JOIN organizations o ON o.id = r.organization_id
+         WHERE o.name = $2 AND r.name = $3
+           AND (r.is_public OR r.organization_id = (SELECT organization_id FROM repositories WHERE id = $1))",
+    )
+    .bind(repository_id)
+    .bind(&name.namespace)
+    .bind(&name.repository)
+    .fetch_optional(pool)
+    .await
+}
+
+/// The stored image with the most layers that `layers` start with, other than the image itself.
+/// Only images of the same organization or public repositories, and of `within` when given.
+async fn find_by_layers(
+    pool: &PgPool,
+    repository_id: i64,
+    digest: &str,
+    layers: &[String],
+    within: Option<i64>,
+) -> Result<Option<StoredBase>, sqlx::Error> {
+    // A base has at least one layer and the image adds at least one
+    if layers.len() < 2 {
+        return Ok(None);
+    }
+    sqlx::query_as::<_, StoredBase>(
+        "SELECT m.repository_id, m.digest,
+                (SELECT t.name FROM tags t WHERE t.manifest_id = m.id ORDER BY t.updated_at DESC LIMIT 1) AS tag
+         FROM manifests m
+         JOIN repositories r ON r.id = m.repository_id
+         WHERE m.layer_digests[1] = $3[1]
+           AND cardinality(m.layer_digests) < cardinality($3)
+           AND m.layer_digests = $3[1:cardinality(m.layer_digests)]
+           AND m.digest <> $2
+           AND ($4::BIGINT IS NULL OR m.repository_id = $4)
+           AND (r.is_public OR r.organization_id = (SELECT organization_id FROM repositories WHERE id = $1))
+         ORDER BY cardinality(m.layer_digests) DESC, m.repository_id = $1 DESC, m.id DESC
+         LIMIT 1",
+    )
+    .bind(repository_id)
+    .bind(digest)
+    .bind(layers)
+    .bind(within)
+    .fetch_optional(pool)
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hosts() -> Vec<String> {
+        vec!["registry.example.com".to_string(), "localhost:8080".to_string()]
+    }
+
+    #[test]
+    fn annotations_take_precedence_over_labels() {
+        let annotations = serde_json::json!({
+            BASE_NAME_ANNOTATION: "docker.io/library/alpine:3.20",
+            BASE_DIGEST_ANNOTATION: "sha256:abc"
+        });
+        let labels = BTreeMap::from([(BASE_NAME_ANNOTATION.to_string(), "docker.io/library/debian:12".to_string())]);
+
+        let declared = declared_base(Some(&annotations), &labels).unwrap();
+        assert_eq!(declared.source, Source::Annotation);
+        assert_eq!(declared.name.as_deref(), Some("docker.io/library/alpine:3.20"));
+        assert_eq!(declared.digest.as_deref(), Some("sha256:abc"));
+
+        let declared = declared_base(None, &labels).unwrap();
+        assert_eq!(declared.source, Source::Label);
+        assert_eq!(declared.digest, None);
+        assert_eq!(declared_base(Some(&serde_json::json!({})), &BTreeMap::new()), None);
+    }
+
+    #[test]
+    fn names_on_this_registry_are_split_up() {
+        assert_eq!(
+            local_name("registry.example.com/acme/base:1.2", &hosts()),
+            Some(LocalName {
+                namespace: "acme".to_string(),
+                repository: "base".to_string(),
+                tag: Some("1.2".to_string()),
+                digest: None,
+            })
+        );
+        let pinned = local_name("localhost:8080/acme/tools/base@sha256:abc", &hosts()).unwrap();
+        assert_eq!((pinned.repository.as_str(), pinned.tag, pinned.digest.as_deref()), ("tools/base", None, Some("sha256:abc")));
+        assert_eq!(local_name("registry.example.com/acme/base", &hosts()).unwrap().tag.as_deref(), Some("latest"));
+
+        assert_eq!(local_name("docker.io/library/alpine:3.20", &hosts()), None);
+        assert_eq!(local_name("registry.example.com/alpine", &hosts()), None);
+    }
+
+    #[test]
+    fn rebuilds_are_recommended_for_moved_or_vulnerable_bases() {
+        let high = SeverityCounts { high: 2, total: 2, ..Default::default() };
+        let low = SeverityCounts { low: 4, total: 4, ..Default::default() };
+
+        assert!(advisory_reasons(Some("sha256:a"), Some("sha256:a"), Some(&low), Severity::High).is_empty());
+        assert_eq!(advisory_reasons(Some("sha256:a"), Some("sha256:b"), None, Severity::High), [BASE_UPDATED]);
+        assert_eq!(
+            advisory_reasons(Some("sha256:a"), Some("sha256:b"), Some(&high), Severity::High),
+            [BASE_UPDATED, BASE_VULNERABLE]
+        );
+        // Without the digest built on, a moved tag says nothing
+        assert!(advisory_reasons(None, Some("sha256:b"), None, Severity::High).is_empty());
+        assert_eq!(advisory_reasons(Some("sha256:a"), None, Some(&low), Severity::Low), [BASE_VULNERABLE]);
+    }
+}
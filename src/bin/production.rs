@@ -3,32 +3,23 @@ use aerugo::cache::{RegistryCache, CacheConfig};
 use aerugo::storage::{Storage, s3::S3Storage};
 use aerugo::{create_app, AppState};
 use anyhow::Context;
-use sqlx::postgres::PgPoolOptions;
-use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::Duration;
-use tokio::sync::RwLock;
 use tracing::{info, warn};
 use secrecy::ExposeSecret;
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
-    // Initialize tracing cho production logging
-    let subscriber = tracing_subscriber::fmt()
-        .with_env_filter(
-            std::env::var("RUST_LOG")
-                .unwrap_or_else(|_| "aerugo=info,tower_http=debug".into()),
-        )
-        .finish(); // Remove .json() as it may not be available
-    tracing::subscriber::set_global_default(subscriber)
-        .context("Failed to set tracing subscriber")?;
+    // Load configuration
+    let variables = aerugo::config::sources::layered_variables().context("Failed to read configuration")?;
+    let settings = Settings::from_variables(&variables).context("Failed to load application settings")?;
+
+    // Initialize logging
+    aerugo::logging::init(&settings.server, &settings.error_reporting)?;
 
     info!("🚀 Starting Aerugo Docker Registry with production optimizations");
 
-    // Load configuration
-    let settings = Settings::load().context("Failed to load application settings")?;
-    let production_config = ProductionSettings::load()
-        .context("Failed to load production settings")?;
+    let production_config = ProductionSettings::from_variables(&variables);
 
     // Validate production configuration
     production_config
@@ -38,26 +29,21 @@ async fn main() -> anyhow::Result<()> {
     info!(
         "📊 Production config loaded - Redis pool: {}, DB pool: {}, Cache enabled: {}",
         production_config.cache.redis.max_connections,
-        production_config.database_pool.max_connections,
+        settings.database.max_connections,
         production_config.cache.metrics_enabled
     );
 
-    // Create database connection pool with production settings
-    let connection_url = settings.database.url();
-    info!("🔗 Connecting to database: {}", connection_url.replace(settings.database.password.expose_secret(), "[HIDDEN]"));
-    
-    let database_pool = PgPoolOptions::new()
-        .max_connections(production_config.database_pool.max_connections)
-        .min_connections(production_config.database_pool.min_connections)
-        .acquire_timeout(Duration::from_secs(production_config.database_pool.connect_timeout))
-        .max_lifetime(Duration::from_secs(production_config.database_pool.max_lifetime))
-        .idle_timeout(Duration::from_secs(production_config.database_pool.idle_timeout))
-        .connect(&connection_url)
+    // Create database connection pool with the DATABASE_* pool settings
+    let database_pool = aerugo::db::connect_pool(&settings)
         .await
         .context("Failed to create database pool")?;
 
-        info!("✅ Database pool established with {} max connections", 
-          production_config.database_pool.max_connections);
+    info!("✅ Database pool established with {} max connections", settings.database.max_connections);
+
+    let read_replica = aerugo::db::connect_read_replica(&settings)?;
+    if read_replica.is_some() {
+        info!("📖 Read replica configured for catalog, tag, manifest and search reads");
+    }
 
     // Run database migrations
     // sqlx::migrate!("./migrations")
@@ -69,7 +55,7 @@ async fn main() -> anyhow::Result<()> {
 
     // Initialize Redis cache with production config
     let redis_url = production_config.redis_url_with_auth(
-        std::env::var("REDIS_PASSWORD").ok().as_deref()
+        variables.get("REDIS_PASSWORD").map(String::as_str)
     );
 
     let cache_config = aerugo::cache::CacheConfig {
@@ -85,6 +71,9 @@ async fn main() -> anyhow::Result<()> {
         max_memory_entries: production_config.cache.memory.max_entries as usize,
         enable_redis: true,
         enable_memory: true,
+        redis_pool_size: production_config.cache.redis.max_connections,
+        redis_connect_timeout: Duration::from_secs(production_config.cache.redis.connection_timeout),
+        ..Default::default()
     };
 
     let cache = RegistryCache::new(cache_config)
@@ -111,8 +100,9 @@ async fn main() -> anyhow::Result<()> {
         part_size: Some(8 * 1024 * 1024), // 8MB
     };
     
+    let backoff = aerugo::startup::Backoff::new(&settings.startup);
     let storage: Arc<dyn Storage> = Arc::new(
-        S3Storage::new(&s3_config)
+        S3Storage::connect(&s3_config, &backoff)
             .await
             .context("Failed to initialize S3 storage")?
     );
@@ -120,11 +110,26 @@ async fn main() -> anyhow::Result<()> {
     info!("✅ S3 storage initialized - bucket: {}", settings.storage.bucket_name());
 
     // Initialize email service for production
-    let email_service = Arc::new(
-        aerugo::email::EmailService::new(settings.email.clone())
-            .context("Failed to initialize email service")?
+    let email_service = match aerugo::email::EmailService::new(settings.email.clone()) {
+        Ok(service) => {
+            info!("📧 Email service initialized for production");
+            Arc::new(service)
+        }
+        Err(e) if settings.startup.email_required => {
+            return Err(e.context("Failed to initialize email service"));
+        }
+        Err(e) => {
+            warn!("Failed to initialize email service: {:#}. Continuing without email.", e);
+            Arc::new(aerugo::email::EmailService::disabled(settings.email.clone()))
+        }
+    };
+
+    let webhook_signer = Arc::new(
+        aerugo::webhooks::WebhookSigner::load(&database_pool, &settings.webhooks, &settings.secrets)
+            .await
+            .context("Failed to load webhook signing key")?
     );
-    info!("📧 Email service initialized for production");
+    info!("🔏 Webhook signing key loaded: {}", webhook_signer.key_id());
 
     // Create application state with production optimizations
     let app_state = AppState {
@@ -132,31 +137,54 @@ async fn main() -> anyhow::Result<()> {
         config: settings.clone(),
         cache: Some(Arc::new(cache)),
         storage,
-        manifest_cache: Arc::new(RwLock::new(HashMap::new())),
         email_service,
+        webhook_signer,
+        log_stream: Arc::new(aerugo::log_stream::LogStream::new(settings.log_tail.buffer_size)),
+        standby: Arc::new(aerugo::standby::Standby::new(&settings.standby)),
+        federation: Arc::new(aerugo::federation::Federation::new(&settings.federation)),
+        push_validators: Arc::new(aerugo::push_hooks::PushValidators::new()),
+        notifier: Arc::new(aerugo::notifications::Notifier::new(&settings.notifications, &settings.server)),
+        transfer_limits: Arc::new(aerugo::handlers::transfer_limits::TransferLimits::new(&settings.concurrency)),
+        runtime_config: Arc::new(aerugo::config::reload::RuntimeConfig::new(&settings)),
+        features: Arc::new(aerugo::features::FeatureFlags::new(&settings.features)),
+        read_replica: Arc::new(aerugo::read_replica::ReadReplica::new(read_replica)),
     };
 
     // Create Axum application with optimized routes
     let app = create_app(app_state.clone()).await;
 
-    // Configure server with production settings
-    let listener = tokio::net::TcpListener::bind(&settings.server.address())
-        .await
-        .context("Failed to bind to server address")?;
-
     info!("🌐 Server starting on {} with production optimizations", settings.server.address());
 
     // Start background tasks
     start_background_tasks(app_state.clone(), &production_config).await?;
+    aerugo::transcode::spawn_transcoder(app_state.clone());
+    aerugo::standby::spawn_standby_monitor(app_state.clone());
+    aerugo::read_replica::spawn_read_replica_monitor(app_state.clone());
+    if let Err(e) = app_state.features.refresh(&app_state.db_pool).await {
+        warn!("Failed to load feature flag overrides: {}", e);
+    }
+    aerugo::features::spawn_feature_refresher(app_state.clone());
+    aerugo::gc::spawn_blob_gc(app_state.clone());
+    aerugo::webhooks::delivery::spawn_webhook_dispatcher(app_state.clone());
+    aerugo::tag_cleanup::spawn_tag_cleanup_analyzer(app_state.clone());
+    aerugo::cdn::spawn_cdn_purger(app_state.clone());
+    aerugo::activity::spawn_activity_flusher(app_state.clone());
+    aerugo::cache::spawn_invalidation_listener(app_state.clone());
+    aerugo::cache_warmup::spawn_cache_warmup(app_state.clone());
+    aerugo::events::spawn_event_recorder(app_state.clone());
+    aerugo::notifications::spawn_notification_senders(app_state.clone());
+    aerugo::config::reload::spawn_sighup_listener(app_state.clone());
+    aerugo::event_stream::spawn_event_publisher(app_state.clone());
+    aerugo::watches::spawn_watch_notifier(app_state.clone());
+    aerugo::jobs::spawn_job_workers(app_state.clone());
 
     // Start metrics server if enabled
     if production_config.performance.metrics_enabled {
-        start_metrics_server(&settings).await?;
+        start_metrics_server(&settings, app_state.clone()).await?;
     }
 
     // Run server with graceful shutdown
-    axum::serve(listener, app)
-        .with_graceful_shutdown(shutdown_signal())
+    aerugo::listeners::serve(&settings, app, shutdown_signal())
         .await
         .context("Server error")?;
 
@@ -213,26 +241,22 @@ async fn start_background_tasks(
 }
 
 /// Start metrics server cho production monitoring
-async fn start_metrics_server(settings: &Settings) -> anyhow::Result<()> {
-    // TODO: Fix metrics dependencies
-    warn!("Metrics server disabled - need to fix prometheus dependencies");
-    Ok(())
-    
-    /* 
-    use axum::{routing::get, Router};
-    use metrics_prometheus::PrometheusHandle;
-    
-    let recorder = metrics_prometheus::PrometheusBuilder::new()
-        .build_recorder();
-    let handle = recorder.handle();
-    
-    if let Err(e) = metrics::set_global_recorder(Box::new(recorder)) {
-        warn!("Failed to set metrics recorder: {}", e);
-        return Ok(());
-    }
-
-    let metrics_app = Router::new()
-        .route("/metrics", get(move || async move { handle.render() }));
+async fn start_metrics_server(settings: &Settings, app_state: AppState) -> anyhow::Result<()> {
+    use axum::{http::header, routing::get, Router};
+
+    let metrics_app = Router::new().route(
+        "/metrics",
+        get(move || {
+            let cache = app_state.cache.clone();
+            async move {
+                let body = match cache {
+                    Some(cache) => cache.get_stats().await.to_prometheus(),
+                    None => String::new(),
+                };
+                ([(header::CONTENT_TYPE, "text/plain; version=0.0.4")], body)
+            }
+        }),
+    );
 
     // Start metrics server on different port
     let metrics_addr = format!("{}:9090", 
@@ -248,7 +272,6 @@ async fn start_metrics_server(settings: &Settings) -> anyhow::Result<()> {
 
     info!("📊 Metrics server started on {}", metrics_addr);
     Ok(())
-    */
 }
 
 /// Graceful shutdown signal handler
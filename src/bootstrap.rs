@@ -0,0 +1,235 @@
+// src/bootstrap.rs - First-run setup of the initial administrator and default organization
+//
+// Driven by the BOOTSTRAP_* settings so deployment automation can run it on every deploy,
+// through `aerugo --bootstrap` or `POST /api/v1/bootstrap`: each step only creates what is
+// missing, never overwrites what an operator changed later, and reports what it found.
+use anyhow::{bail, Context, Result};
+use secrecy::{ExposeSecret, Secret};
+use serde::Serialize;
+use sqlx::{PgPool, Postgres, Transaction};
+use utoipa::ToSchema;
+
+use crate::config::settings::{BootstrapSettings, Settings};
+
+/// Organization ID repositories pushed without a namespace are created in
+const DEFAULT_ORGANIZATION_ID: i64 = 1;
+
+/// What a bootstrap run found and created
+#[derive(Debug, Serialize, ToSchema)]
+pub struct BootstrapReport {
+    /// `None` when no administrator is configured
+    pub admin: Option<BootstrapStep>,
+    /// `None` when no default organization is configured
+    pub default_organization: Option<BootstrapStep>,
+    /// Whether the default organization's base settings were written by this run
+    pub policies_created: bool,
+}
+
+impl BootstrapReport {
+    /// One line describing the run, for logs
+    pub fn summary(&self) -> String {
+        let step = |step: &Option<BootstrapStep>| match step {
+            Some(step) if step.created => format!("{} created", step.name),
+            Some(step) => format!("{} existing", step.name),
+            None => "-".to_string(),
+        };
+        format!(
+            "admin={} organization={} policies_created={}",
+            step(&self.admin),
+            step(&self.default_organization),
+            self.policies_created
+        )
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct BootstrapStep {
+    pub id: i64,
+    pub name: String,
+    /// `false` when it already existed
+    pub created: bool,
+}
+
+/// Create whatever the bootstrap settings describe that does not exist yet
+pub async fn run(pool: &PgPool, settings: &Settings) -> Result<BootstrapReport> {
+    let config = &settings.bootstrap;
+    let account = admin_account(config)?;
+    if config.default_organization.is_some() && account.is_none() {
+        bail!("BOOTSTRAP_DEFAULT_ORGANIZATION needs a bootstrap administrator to own it");
+    }
+
+    let mut tx = pool.begin().await?;
+    // Instances starting together bootstrap one after the other
+    sqlx::query("SELECT pg_advisory_xact_lock(hashtext('aerugo:bootstrap'))")
+        .execute(&mut *tx)
+        .await?;
+
+    let admin = match account {
+        Some((username, email, password)) => Some(ensure_admin(&mut tx, settings, username, email, password).await?),
+        None => None,
+    };
+
+    let mut policies_created = false;
+    let default_organization = match (&config.default_organization, &admin) {
+        (Some(name), Some(admin)) => {
+            crate::naming::validate_organization_name(name, &settings.naming)
+                .context("Invalid BOOTSTRAP_DEFAULT_ORGANIZATION")?;
+            let org = ensure_organization(&mut tx, name, admin.id).await?;
+            policies_created = ensure_policies(&mut tx, config, org.id, admin.id).await?;
+            Some(org)
+        }
+        _ => None,
+    };
+
+    tx.commit().await?;
+    Ok(BootstrapReport { admin, default_organization, policies_created })
+}
+
+/// (username, email, password) of the configured administrator
+fn admin_account(config: &BootstrapSettings) -> Result<Option<(&str, &str, &Secret<String>)>> {
+    match (&config.admin_username, &config.admin_email, &config.admin_password) {
+        (Some(username), Some(email), Some(password)) => Ok(Some((username, email, password))),
+        (None, None, None) => Ok(None),
+        _ => bail!("BOOTSTRAP_ADMIN_USERNAME, BOOTSTRAP_ADMIN_EMAIL and BOOTSTRAP_ADMIN_PASSWORD must be set together"),
+    }
+}
+
+async fn ensure_admin(
+    tx: &mut Transaction<'_, Postgres>,
+    settings: &Settings,
+    username: &str,
+    email: &str,
+    password: &Secret<String>,
+) -> Result<BootstrapStep> {
+    // An existing account keeps its password and is only made an administrator
+    let existing = sqlx::query_scalar::<_, i64>("UPDATE users SET is_admin = TRUE WHERE username = $1 RETURNING id")
+        .bind(username)
+        .fetch_optional(&mut **tx)
+        .await?;
+    if let Some(id) = existing {
+        return Ok(BootstrapStep { id, name: username.to_string(), created: false });
+    }
+
+    let (password_hash, password_hash_params) = crate::auth::hash_password(password.expose_secret(), &settings.auth)?;
+    let id = sqlx::query_scalar::<_, i64>(
+        "INSERT INTO users (username, email, password_hash, password_hash_params, is_admin)
+         VALUES ($1, $2, $3, $4, TRUE)
+         RETURNING id",
+    )
+    .bind(username)
+    .bind(email)
+    .bind(password_hash)
+    .bind(password_hash_params)
+    .fetch_one(&mut **tx)
+    .await
+    .with_context(|| format!("Failed to create administrator {} (is {} used by another account?)", username, email))?;
+    Ok(BootstrapStep { id, name: username.to_string(), created: true })
+}
+
+async fn ensure_organization(tx: &mut Transaction<'_, Postgres>, name: &str, owner_id: i64) -> Result<BootstrapStep> {
+    let existing = sqlx::query_scalar::<_, i64>("SELECT id FROM organizations WHERE name = $1 AND deleted_at IS NULL")
+        .bind(name)
+        .fetch_optional(&mut **tx)
+        .await?;
+
+    let (id, created) = match existing {
+        Some(id) => (id, false),
+        None => {
+            // Take the ID unnamespaced pushes use when no organization holds it yet
+            let claimed = sqlx::query_scalar::<_, i64>(
+                "INSERT INTO organizations (id, name, display_name)
+                 SELECT $1, $2, $2
+                 WHERE NOT EXISTS (SELECT 1 FROM organizations WHERE id = $1)
+                 RETURNING id",
+            )
+            .bind(DEFAULT_ORGANIZATION_ID)
+            .bind(name)
+            .fetch_optional(&mut **tx)
+            .await?;
+            let id = match claimed {
+                Some(id) => {
+                    sqlx::query("SELECT setval(pg_get_serial_sequence('organizations', 'id'), (SELECT MAX(id) FROM organizations))")
+                        .execute(&mut **tx)
+                        .await?;
+                    id
+                }
+                None => {
+                    sqlx::query_scalar::<_, i64>("INSERT INTO organizations (name, display_name) VALUES ($1, $1) RETURNING id")
+                        .bind(name)
+                        .fetch_one(&mut **tx)
+                        .await
+                        .with_context(|| format!("Failed to create organization {}", name))?
+                }
+            };
+            (id, true)
+        }
+    };
+
+    sqlx::query(
+        "INSERT INTO organization_members (organization_id, user_id, role)
+         VALUES ($1, $2, 'owner')
+         ON CONFLICT (organization_id, user_id) DO NOTHING",
+    )
+    .bind(id)
+    .bind(owner_id)
+    .execute(&mut **tx)
+    .await?;
+
+    Ok(BootstrapStep { id, name: name.to_string(), created })
+}
+
+/// Write the default organization's base settings unless it already has settings
+async fn ensure_policies(
+    tx: &mut Transaction<'_, Postgres>,
+    config: &BootstrapSettings,
+    org_id: i64,
+    admin_id: i64,
+) -> Result<bool> {
+    let result = sqlx::query(
+        "INSERT INTO organization_settings
+             (organization_id, default_repository_public, default_tag_retention_keep_last,
+              default_tag_retention_days, updated_by, updated_at)
+         VALUES ($1, $2, $3, $4, $5, CURRENT_TIMESTAMP)
+         ON CONFLICT (organization_id) DO NOTHING",
+    )
+    .bind(org_id)
+    .bind(config.default_repository_public)
+    .bind(config.default_tag_retention_keep_last)
+    .bind(config.default_tag_retention_days)
+    .bind(admin_id)
+    .execute(&mut **tx)
+    .await?;
+    Ok(result.rows_affected() == 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> BootstrapSettings {
+        BootstrapSettings {
+            admin_username: None,
+            admin_email: None,
+            admin_password: None,
+            default_organization: None,
+            default_repository_public: false,
+            default_tag_retention_keep_last: None,
+            default_tag_retention_days: None,
+            token: None,
+        }
+    }
+
+    #[test]
+    fn admin_fields_are_set_together() {
+        assert!(admin_account(&config()).unwrap().is_none());
+
+        let mut partial = config();
+        partial.admin_username = Some("admin".to_string());
+        assert!(admin_account(&partial).is_err());
+
+        partial.admin_email = Some("admin@example.com".to_string());
+        partial.admin_password = Some(Secret::new("correct horse".to_string()));
+        let (username, email, _) = admin_account(&partial).unwrap().unwrap();
+        assert_eq!((username, email), ("admin", "admin@example.com"));
+    }
+}
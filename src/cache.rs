@@ -1,14 +1,23 @@
 // Performance optimization module for Docker Registry
 // Implements caching, connection pooling, and production optimizations
 
-use std::sync::Arc;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 use std::collections::HashMap;
-use tokio::sync::RwLock;
+use tokio::sync::{OnceCell, RwLock};
+use axum::http::StatusCode;
 use bytes::Bytes;
 use serde::{Deserialize, Serialize};
-use redis::{Client as RedisClient, Commands};
+use utoipa::ToSchema;
+use bb8_redis::bb8::{Pool, PooledConnection};
+use bb8_redis::RedisConnectionManager;
+use redis::AsyncCommands;
 use anyhow::Result;
+use futures::StreamExt;
+
+use crate::AppState;
 
 // Authentication cache structures
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -17,6 +26,10 @@ pub struct AuthCacheEntry {
     pub username: String,
     pub email: String,
     pub is_admin: bool,
+    #[serde(default)]
+    pub jti: Option<String>,
+    #[serde(default)]
+    pub sid: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -38,20 +51,177 @@ pub struct UserSessionCache {
 pub struct ApiKeyCacheEntry {
     pub user_id: i64,
     pub expires_at: Option<chrono::NaiveDateTime>,
+    #[serde(default)]
+    pub scopes: Vec<String>,
 }
 
 /// Cache layer for Docker Registry operations
 #[derive(Clone)]
 pub struct RegistryCache {
-    redis_client: Option<RedisClient>,
+    redis_pool: Option<Pool<RedisConnectionManager>>,
     memory_cache: Arc<RwLock<MemoryCache>>,
+    /// Manifests by cache key, and pushed manifests by `content:<digest>`, bounded by
+    /// `CacheConfig::manifest_memory_bytes`
+    manifests: moka::future::Cache<String, Bytes>,
     config: CacheConfig,
+    /// Lifetimes of new entries; start out as the `CacheConfig` ones and can be changed while
+    /// running with `set_ttls`
+    ttls: Arc<std::sync::RwLock<CacheTtls>>,
+    manifest_loads: Arc<InFlight<ManifestLoad>>,
+    /// Bumped by every manifest invalidation, here or announced by another replica. A load that
+    /// overlaps one may have read the manifest a tag pointed at before the push, so it does not
+    /// cache what it found.
+    manifest_invalidations: Arc<AtomicU64>,
+    metrics: Arc<CacheMetrics>,
+    /// Identifies this replica in published invalidations
+    node_id: Arc<str>,
+}
+
+/// Kinds of cached data, counted separately in `CacheStats`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CacheCategory {
+    Manifest,
+    BlobMetadata,
+    Repositories,
+    Tags,
+    Auth,
+    ApiKey,
+    Permissions,
+    Session,
+}
+
+impl CacheCategory {
+    pub const ALL: [CacheCategory; 8] = [
+        CacheCategory::Manifest,
+        CacheCategory::BlobMetadata,
+        CacheCategory::Repositories,
+        CacheCategory::Tags,
+        CacheCategory::Auth,
+        CacheCategory::ApiKey,
+        CacheCategory::Permissions,
+        CacheCategory::Session,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            CacheCategory::Manifest => "manifest",
+            CacheCategory::BlobMetadata => "blob_metadata",
+            CacheCategory::Repositories => "repositories",
+            CacheCategory::Tags => "tags",
+            CacheCategory::Auth => "auth",
+            CacheCategory::ApiKey => "api_key",
+            CacheCategory::Permissions => "permissions",
+            CacheCategory::Session => "session",
+        }
+    }
+}
+
+/// Lookups and evictions per category since the process started
+#[derive(Default)]
+struct CacheMetrics {
+    counters: [CategoryCounters; CacheCategory::ALL.len()],
+}
+
+#[derive(Default)]
+struct CategoryCounters {
+    hits: AtomicU64,
+    misses: AtomicU64,
+    evictions: AtomicU64,
+}
+
+impl CacheMetrics {
+    fn hit(&self, category: CacheCategory) {
+        self.counters[category as usize].hits.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn miss(&self, category: CacheCategory) {
+        self.counters[category as usize].misses.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn evicted(&self, category: CacheCategory, count: usize) {
+        if count > 0 {
+            self.counters[category as usize].evictions.fetch_add(count as u64, Ordering::Relaxed);
+        }
+    }
+
+    fn snapshot(&self) -> Vec<CategoryStats> {
+        CacheCategory::ALL
+            .iter()
+            .map(|&category| {
+                let counters = &self.counters[category as usize];
+                let (hits, misses) = (counters.hits.load(Ordering::Relaxed), counters.misses.load(Ordering::Relaxed));
+                CategoryStats {
+                    category: category.as_str().to_string(),
+                    hits,
+                    misses,
+                    evictions: counters.evictions.load(Ordering::Relaxed),
+                    hit_ratio: (hits + misses > 0).then(|| hits as f64 / (hits + misses) as f64),
+                }
+            })
+            .collect()
+    }
+}
+
+/// A manifest loaded on a cache miss, as served to clients
+#[derive(Clone, Debug)]
+pub struct LoadedManifest {
+    pub content: Bytes,
+    pub media_type: String,
+    pub digest: String,
+}
+
+/// Outcome of a manifest load: the manifest, or the status and body to answer with
+pub type ManifestLoad = std::result::Result<LoadedManifest, (StatusCode, serde_json::Value)>;
+
+/// Loads in flight per key, so concurrent misses of a key await one load instead of each
+/// running their own
+struct InFlight<T> {
+    loads: Mutex<HashMap<String, Arc<OnceCell<T>>>>,
+}
+
+impl<T: Clone> InFlight<T> {
+    fn new() -> Self {
+        Self { loads: Mutex::new(HashMap::new()) }
+    }
+
+    /// The outcome of `load`, or of the load of `key` already running. If the caller running
+    /// the load goes away, one of those waiting runs its own `load` instead.
+    async fn run<F, Fut>(&self, key: &str, load: F) -> T
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = T>,
+    {
+        let cell = self
+            .loads
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .entry(key.to_string())
+            .or_default()
+            .clone();
+        let value = cell.get_or_init(load).await.clone();
+
+        // The next miss after this load loads afresh
+        let mut loads = self.loads.lock().unwrap_or_else(|e| e.into_inner());
+        if loads.get(key).is_some_and(|current| Arc::ptr_eq(current, &cell)) {
+            loads.remove(key);
+        }
+        value
+    }
+
+    /// Let the next miss of `key` load afresh rather than wait for the load already running,
+    /// which may have read what was just invalidated
+    fn forget(&self, key: &str) {
+        self.loads.lock().unwrap_or_else(|e| e.into_inner()).remove(key);
+    }
+
+    fn forget_all(&self) {
+        self.loads.lock().unwrap_or_else(|e| e.into_inner()).clear();
+    }
 }
 
 /// In-memory cache for high-frequency data
 #[derive(Default)]
 struct MemoryCache {
-    manifest_cache: HashMap<String, CacheEntry<Bytes>>,
     blob_metadata: HashMap<String, CacheEntry<BlobCacheMetadata>>,
     repository_cache: HashMap<String, CacheEntry<Vec<String>>>,
     tag_cache: HashMap<String, CacheEntry<Vec<String>>>,
@@ -60,6 +230,10 @@ struct MemoryCache {
     api_key_cache: HashMap<String, CacheEntry<String>>, // Store serialized ApiKeyCacheEntry
     permission_cache: HashMap<String, CacheEntry<PermissionCacheEntry>>,
     user_session_cache: HashMap<String, CacheEntry<UserSessionCache>>,
+    // Expiring counters (rate limits, login failures), used when Redis is unavailable
+    counters: HashMap<String, CacheEntry<u64>>,
+    // Pull and push counts waiting to be written, used when Redis is unavailable
+    pending_activity: HashMap<String, i64>,
 }
 
 /// Cache entry with TTL
@@ -97,8 +271,16 @@ pub struct CacheConfig {
     pub permission_ttl: Duration,
     pub session_ttl: Duration,
     pub max_memory_entries: usize,
+    /// Most bytes of manifests held in memory; the least recently used are dropped beyond it
+    pub manifest_memory_bytes: u64,
     pub enable_redis: bool,
     pub enable_memory: bool,
+    /// Most Redis connections open at once
+    pub redis_pool_size: u32,
+    /// How long to wait for a pooled Redis connection, including connecting
+    pub redis_connect_timeout: Duration,
+    /// How long a Redis command may take before the memory cache is used instead
+    pub redis_command_timeout: Duration,
 }
 
 impl Default for CacheConfig {
@@ -114,8 +296,38 @@ impl Default for CacheConfig {
             permission_ttl: Duration::from_secs(300), // 5 minutes
             session_ttl: Duration::from_secs(1800), // 30 minutes
             max_memory_entries: 10000,
+            manifest_memory_bytes: 64 * 1024 * 1024,
             enable_redis: true,
             enable_memory: true,
+            redis_pool_size: 10,
+            redis_connect_timeout: Duration::from_secs(1),
+            redis_command_timeout: Duration::from_millis(500),
+        }
+    }
+}
+
+/// The `CacheConfig` lifetimes, the part of the cache configuration that can be reloaded
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CacheTtls {
+    pub manifest_ttl: Duration,
+    pub blob_metadata_ttl: Duration,
+    pub repository_ttl: Duration,
+    pub tag_ttl: Duration,
+    pub auth_token_ttl: Duration,
+    pub permission_ttl: Duration,
+    pub session_ttl: Duration,
+}
+
+impl From<&CacheConfig> for CacheTtls {
+    fn from(config: &CacheConfig) -> Self {
+        Self {
+            manifest_ttl: config.manifest_ttl,
+            blob_metadata_ttl: config.blob_metadata_ttl,
+            repository_ttl: config.repository_ttl,
+            tag_ttl: config.tag_ttl,
+            auth_token_ttl: config.auth_token_ttl,
+            permission_ttl: config.permission_ttl,
+            session_ttl: config.session_ttl,
         }
     }
 }
@@ -132,38 +344,78 @@ pub struct BlobCacheMetadata {
 impl RegistryCache {
     /// Create new registry cache
     pub async fn new(config: CacheConfig) -> Result<Self> {
-        let redis_client = if config.enable_redis {
-            if let Some(redis_url) = &config.redis_url {
-                match RedisClient::open(redis_url.as_str()) {
-                    Ok(client) => {
-                        // Test connection
-                        if let Ok(mut conn) = client.get_connection() {
-                            let _: String = redis::cmd("PING").query(&mut conn).unwrap_or_default();
-                            Some(client)
-                        } else {
-                            tracing::warn!("Redis connection failed, falling back to memory cache");
-                            None
-                        }
-                    }
-                    Err(e) => {
-                        tracing::warn!("Redis client creation failed: {}, falling back to memory cache", e);
-                        None
-                    }
+        let redis_pool = match &config.redis_url {
+            Some(redis_url) if config.enable_redis => match connect_redis(redis_url, &config).await {
+                Ok(pool) => Some(pool),
+                Err(e) => {
+                    tracing::warn!("Redis connection failed: {}, falling back to memory cache", e);
+                    None
                 }
-            } else {
-                None
-            }
-        } else {
-            None
+            },
+            _ => None,
         };
         
+        let metrics = Arc::new(CacheMetrics::default());
+        let ttls = Arc::new(std::sync::RwLock::new(CacheTtls::from(&config)));
+        let manifests = {
+            let metrics = metrics.clone();
+            moka::future::Cache::builder()
+                .max_capacity(config.manifest_memory_bytes)
+                .weigher(|key: &String, manifest: &Bytes| u32::try_from(key.len() + manifest.len()).unwrap_or(u32::MAX))
+                .expire_after(ManifestExpiry { ttls: ttls.clone() })
+                .eviction_listener(move |_key, _manifest, cause| {
+                    if cause.was_evicted() {
+                        metrics.evicted(CacheCategory::Manifest, 1);
+                    }
+                })
+                .build()
+        };
+
         Ok(Self {
-            redis_client,
+            redis_pool,
             memory_cache: Arc::new(RwLock::new(MemoryCache::default())),
+            manifests,
             config,
+            ttls,
+            manifest_loads: Arc::new(InFlight::new()),
+            manifest_invalidations: Arc::new(AtomicU64::new(0)),
+            metrics,
+            node_id: uuid::Uuid::new_v4().to_string().into(),
         })
     }
     
+    /// Lifetimes given to entries cached from now on
+    pub fn ttls(&self) -> CacheTtls {
+        *self.ttls.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Change the lifetimes of entries cached from now on; entries already cached keep theirs
+    pub fn set_ttls(&self, ttls: CacheTtls) {
+        *self.ttls.write().unwrap_or_else(|e| e.into_inner()) = ttls;
+    }
+
+    /// A pooled Redis connection; `None` without Redis, or when no connection could be had
+    /// within the connect timeout
+    async fn redis(&self) -> Option<PooledConnection<'_, RedisConnectionManager>> {
+        let pool = self.redis_pool.as_ref()?;
+        match pool.get().await {
+            Ok(conn) => Some(conn),
+            Err(e) => {
+                tracing::warn!("Redis connection unavailable: {}", e);
+                None
+            }
+        }
+    }
+
+    /// Run a Redis command, failing it after the command timeout so a stalled server slows
+    /// requests down by at most that much
+    async fn timed<T>(&self, command: impl Future<Output = redis::RedisResult<T>>) -> redis::RedisResult<T> {
+        match tokio::time::timeout(self.config.redis_command_timeout, command).await {
+            Ok(result) => result,
+            Err(_) => Err(redis::RedisError::from((redis::ErrorKind::IoError, "Redis command timed out"))),
+        }
+    }
+
     /// Cache blob metadata
     pub async fn cache_blob_metadata(&self, digest: &str, metadata: BlobCacheMetadata) -> Result<()> {
         // Memory cache
@@ -171,18 +423,16 @@ impl RegistryCache {
             let mut cache = self.memory_cache.write().await;
             cache.blob_metadata.insert(
                 digest.to_string(),
-                CacheEntry::new(metadata.clone(), self.config.blob_metadata_ttl),
+                CacheEntry::new(metadata.clone(), self.ttls().blob_metadata_ttl),
             );
         }
         
         // Redis cache
-        if let Some(redis) = &self.redis_client {
-            if let Ok(mut conn) = redis.get_connection() {
-                let redis_key = format!("blob_meta:{}", digest);
-                let ttl_secs = self.config.blob_metadata_ttl.as_secs();
-                if let Ok(json_data) = serde_json::to_string(&metadata) {
-                    let _: Result<(), _> = conn.set_ex(&redis_key, json_data, ttl_secs);
-                }
+        if let Some(mut conn) = self.redis().await {
+            let redis_key = format!("blob_meta:{}", digest);
+            let ttl_secs = self.ttls().blob_metadata_ttl.as_secs();
+            if let Ok(json_data) = serde_json::to_string(&metadata) {
+                let _: Result<(), _> = self.timed(conn.set_ex(&redis_key, json_data, ttl_secs)).await;
             }
         }
         
@@ -196,32 +446,33 @@ impl RegistryCache {
             let cache = self.memory_cache.read().await;
             if let Some(entry) = cache.blob_metadata.get(digest) {
                 if !entry.is_expired() {
+                    self.metrics.hit(CacheCategory::BlobMetadata);
                     return Some(entry.data.clone());
                 }
             }
         }
         
         // Try Redis cache
-        if let Some(redis) = &self.redis_client {
-            if let Ok(mut conn) = redis.get_connection() {
-                let redis_key = format!("blob_meta:{}", digest);
-                if let Ok(data) = conn.get::<_, String>(&redis_key) {
-                    if let Ok(metadata) = serde_json::from_str::<BlobCacheMetadata>(&data) {
-                        // Update memory cache
-                        if self.config.enable_memory {
-                            let mut cache = self.memory_cache.write().await;
-                            cache.blob_metadata.insert(
-                                digest.to_string(),
-                                CacheEntry::new(metadata.clone(), self.config.blob_metadata_ttl),
-                            );
-                        }
-                        
-                        return Some(metadata);
+        if let Some(mut conn) = self.redis().await {
+            let redis_key = format!("blob_meta:{}", digest);
+            if let Ok(data) = self.timed(conn.get::<_, String>(&redis_key)).await {
+                if let Ok(metadata) = serde_json::from_str::<BlobCacheMetadata>(&data) {
+                    // Update memory cache
+                    if self.config.enable_memory {
+                        let mut cache = self.memory_cache.write().await;
+                        cache.blob_metadata.insert(
+                            digest.to_string(),
+                            CacheEntry::new(metadata.clone(), self.ttls().blob_metadata_ttl),
+                        );
                     }
+                    
+                    self.metrics.hit(CacheCategory::BlobMetadata);
+                    return Some(metadata);
                 }
             }
         }
         
+        self.metrics.miss(CacheCategory::BlobMetadata);
         None
     }
     
@@ -229,25 +480,14 @@ impl RegistryCache {
     pub async fn cache_manifest(&self, key: &str, manifest: Bytes) -> Result<()> {
         // Memory cache
         if self.config.enable_memory {
-            let mut cache = self.memory_cache.write().await;
-            cache.manifest_cache.insert(
-                key.to_string(),
-                CacheEntry::new(manifest.clone(), self.config.manifest_ttl),
-            );
-            
-            // Cleanup old entries if needed
-            if cache.manifest_cache.len() > self.config.max_memory_entries {
-                self.cleanup_memory_cache(&mut cache).await;
-            }
+            self.manifests.insert(key.to_string(), manifest.clone()).await;
         }
         
         // Redis cache
-        if let Some(redis) = &self.redis_client {
-            if let Ok(mut conn) = redis.get_connection() {
-                let redis_key = format!("manifest:{}", key);
-                let ttl_secs = self.config.manifest_ttl.as_secs();
-                let _: Result<(), _> = conn.set_ex(&redis_key, manifest.as_ref(), ttl_secs);
-            }
+        if let Some(mut conn) = self.redis().await {
+            let redis_key = format!("manifest:{}", key);
+            let ttl_secs = self.ttls().manifest_ttl.as_secs();
+            let _: Result<(), _> = self.timed(conn.set_ex(&redis_key, manifest.as_ref(), ttl_secs)).await;
         }
         
         Ok(())
@@ -257,38 +497,93 @@ impl RegistryCache {
     pub async fn get_manifest(&self, key: &str) -> Option<Bytes> {
         // Try memory cache first
         if self.config.enable_memory {
-            let cache = self.memory_cache.read().await;
-            if let Some(entry) = cache.manifest_cache.get(key) {
-                if !entry.is_expired() {
-                    return Some(entry.data.clone());
-                }
+            if let Some(manifest) = self.manifests.get(key).await {
+                self.metrics.hit(CacheCategory::Manifest);
+                return Some(manifest);
             }
         }
         
         // Try Redis cache
-        if let Some(redis) = &self.redis_client {
-            if let Ok(mut conn) = redis.get_connection() {
-                let redis_key = format!("manifest:{}", key);
-                if let Ok(data) = conn.get::<_, Vec<u8>>(&redis_key) {
-                    let bytes = Bytes::from(data);
-                    
-                    // Update memory cache
-                    if self.config.enable_memory {
-                        let mut cache = self.memory_cache.write().await;
-                        cache.manifest_cache.insert(
-                            key.to_string(),
-                            CacheEntry::new(bytes.clone(), self.config.manifest_ttl),
-                        );
-                    }
-                    
-                    return Some(bytes);
+        if let Some(mut conn) = self.redis().await {
+            let redis_key = format!("manifest:{}", key);
+            if let Ok(data) = self.timed(conn.get::<_, Vec<u8>>(&redis_key)).await {
+                let bytes = Bytes::from(data);
+                
+                // Update memory cache
+                if self.config.enable_memory {
+                    self.manifests.insert(key.to_string(), bytes.clone()).await;
                 }
+                
+                self.metrics.hit(CacheCategory::Manifest);
+                return Some(bytes);
             }
         }
         
+        self.metrics.miss(CacheCategory::Manifest);
         None
     }
     
+    /// Run `load` for a manifest missing from the cache, unless a load of `key` is already
+    /// running, in which case its outcome is shared. The manifest found is cached, so requests
+    /// arriving after the load finishes hit the cache, unless the manifest was invalidated while
+    /// it ran: the load may then have read the tag before a push moved it, and caching that would
+    /// serve the old manifest until it expires.
+    pub async fn load_manifest_once<F, Fut>(&self, key: &str, load: F) -> ManifestLoad
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = ManifestLoad>,
+    {
+        self.manifest_loads
+            .run(key, || async {
+                let invalidations = self.manifest_invalidations.load(Ordering::Acquire);
+                let loaded = load().await;
+                if let Ok(manifest) = &loaded {
+                    if self.manifest_invalidations.load(Ordering::Acquire) == invalidations {
+                        let _ = self.cache_manifest(key, manifest.content.clone()).await;
+                        // An invalidation landing while it was being cached must still win
+                        if self.manifest_invalidations.load(Ordering::Acquire) != invalidations {
+                            self.uncache_manifest(key).await;
+                        }
+                    } else {
+                        tracing::debug!("Not caching {}: invalidated while it was loaded", key);
+                    }
+                }
+                loaded
+            })
+            .await
+    }
+
+    /// Drop a manifest this replica cached, without announcing it to the others
+    async fn uncache_manifest(&self, key: &str) {
+        if self.config.enable_memory {
+            self.manifests.invalidate(key).await;
+        }
+        if let Some(mut conn) = self.redis().await {
+            let _: Result<(), _> = self.timed(conn.del(format!("manifest:{}", key))).await;
+        }
+    }
+
+    /// Keep the content of a pushed manifest in memory, so it can still be served by digest
+    /// while neither the database nor storage has it. Kept until pushed out by newer manifests.
+    pub async fn cache_manifest_content(&self, digest: &str, content: Bytes) {
+        if self.config.enable_memory {
+            self.manifests.insert(manifest_content_key(digest), content).await;
+        }
+    }
+
+    /// Content of a pushed manifest kept by `cache_manifest_content`
+    pub async fn get_manifest_content(&self, digest: &str) -> Option<Bytes> {
+        if !self.config.enable_memory {
+            return None;
+        }
+        let content = self.manifests.get(&manifest_content_key(digest)).await;
+        match content {
+            Some(_) => self.metrics.hit(CacheCategory::Manifest),
+            None => self.metrics.miss(CacheCategory::Manifest),
+        }
+        content
+    }
+
     /// Cache repository list
     pub async fn cache_repositories(&self, repositories: Vec<String>) -> Result<()> {
         let key = "repositories";
@@ -298,18 +593,16 @@ impl RegistryCache {
             let mut cache = self.memory_cache.write().await;
             cache.repository_cache.insert(
                 key.to_string(),
-                CacheEntry::new(repositories.clone(), self.config.repository_ttl),
+                CacheEntry::new(repositories.clone(), self.ttls().repository_ttl),
             );
         }
         
         // Redis cache
-        if let Some(redis) = &self.redis_client {
-            if let Ok(mut conn) = redis.get_connection() {
-                let redis_key = format!("repos:{}", key);
-                let ttl_secs = self.config.repository_ttl.as_secs();
-                if let Ok(json_data) = serde_json::to_string(&repositories) {
-                    let _: Result<(), _> = conn.set_ex(&redis_key, json_data, ttl_secs);
-                }
+        if let Some(mut conn) = self.redis().await {
+            let redis_key = format!("repos:{}", key);
+            let ttl_secs = self.ttls().repository_ttl.as_secs();
+            if let Ok(json_data) = serde_json::to_string(&repositories) {
+                let _: Result<(), _> = self.timed(conn.set_ex(&redis_key, json_data, ttl_secs)).await;
             }
         }
         
@@ -325,32 +618,33 @@ impl RegistryCache {
             let cache = self.memory_cache.read().await;
             if let Some(entry) = cache.repository_cache.get(key) {
                 if !entry.is_expired() {
+                    self.metrics.hit(CacheCategory::Repositories);
                     return Some(entry.data.clone());
                 }
             }
         }
         
         // Try Redis cache
-        if let Some(redis) = &self.redis_client {
-            if let Ok(mut conn) = redis.get_connection() {
-                let redis_key = format!("repos:{}", key);
-                if let Ok(data) = conn.get::<_, String>(&redis_key) {
-                    if let Ok(repositories) = serde_json::from_str::<Vec<String>>(&data) {
-                        // Update memory cache
-                        if self.config.enable_memory {
-                            let mut cache = self.memory_cache.write().await;
-                            cache.repository_cache.insert(
-                                key.to_string(),
-                                CacheEntry::new(repositories.clone(), self.config.repository_ttl),
-                            );
-                        }
-                        
-                        return Some(repositories);
+        if let Some(mut conn) = self.redis().await {
+            let redis_key = format!("repos:{}", key);
+            if let Ok(data) = self.timed(conn.get::<_, String>(&redis_key)).await {
+                if let Ok(repositories) = serde_json::from_str::<Vec<String>>(&data) {
+                    // Update memory cache
+                    if self.config.enable_memory {
+                        let mut cache = self.memory_cache.write().await;
+                        cache.repository_cache.insert(
+                            key.to_string(),
+                            CacheEntry::new(repositories.clone(), self.ttls().repository_ttl),
+                        );
                     }
+                    
+                    self.metrics.hit(CacheCategory::Repositories);
+                    return Some(repositories);
                 }
             }
         }
         
+        self.metrics.miss(CacheCategory::Repositories);
         None
     }
     
@@ -361,18 +655,16 @@ impl RegistryCache {
             let mut cache = self.memory_cache.write().await;
             cache.tag_cache.insert(
                 repository.to_string(),
-                CacheEntry::new(tags.clone(), self.config.tag_ttl),
+                CacheEntry::new(tags.clone(), self.ttls().tag_ttl),
             );
         }
         
         // Redis cache
-        if let Some(redis) = &self.redis_client {
-            if let Ok(mut conn) = redis.get_connection() {
-                let redis_key = format!("tags:{}", repository);
-                let ttl_secs = self.config.tag_ttl.as_secs();
-                if let Ok(json_data) = serde_json::to_string(&tags) {
-                    let _: Result<(), _> = conn.set_ex(&redis_key, json_data, ttl_secs);
-                }
+        if let Some(mut conn) = self.redis().await {
+            let redis_key = format!("tags:{}", repository);
+            let ttl_secs = self.ttls().tag_ttl.as_secs();
+            if let Ok(json_data) = serde_json::to_string(&tags) {
+                let _: Result<(), _> = self.timed(conn.set_ex(&redis_key, json_data, ttl_secs)).await;
             }
         }
         
@@ -386,161 +678,117 @@ impl RegistryCache {
             let cache = self.memory_cache.read().await;
             if let Some(entry) = cache.tag_cache.get(repository) {
                 if !entry.is_expired() {
+                    self.metrics.hit(CacheCategory::Tags);
                     return Some(entry.data.clone());
                 }
             }
         }
         
         // Try Redis cache
-        if let Some(redis) = &self.redis_client {
-            if let Ok(mut conn) = redis.get_connection() {
-                let redis_key = format!("tags:{}", repository);
-                if let Ok(data) = conn.get::<_, String>(&redis_key) {
-                    if let Ok(tags) = serde_json::from_str::<Vec<String>>(&data) {
-                        // Update memory cache
-                        if self.config.enable_memory {
-                            let mut cache = self.memory_cache.write().await;
-                            cache.tag_cache.insert(
-                                repository.to_string(),
-                                CacheEntry::new(tags.clone(), self.config.tag_ttl),
-                            );
-                        }
-                        
-                        return Some(tags);
+        if let Some(mut conn) = self.redis().await {
+            let redis_key = format!("tags:{}", repository);
+            if let Ok(data) = self.timed(conn.get::<_, String>(&redis_key)).await {
+                if let Ok(tags) = serde_json::from_str::<Vec<String>>(&data) {
+                    // Update memory cache
+                    if self.config.enable_memory {
+                        let mut cache = self.memory_cache.write().await;
+                        cache.tag_cache.insert(
+                            repository.to_string(),
+                            CacheEntry::new(tags.clone(), self.ttls().tag_ttl),
+                        );
                     }
+                    
+                    self.metrics.hit(CacheCategory::Tags);
+                    return Some(tags);
                 }
             }
         }
         
+        self.metrics.miss(CacheCategory::Tags);
         None
     }
     
     /// Invalidate cache entries
     pub async fn invalidate(&self, pattern: &str) -> Result<()> {
-        // Clear memory cache entries matching pattern
-        if self.config.enable_memory {
-            let mut cache = self.memory_cache.write().await;
-            
-            match pattern {
-                "manifests" => cache.manifest_cache.clear(),
-                "repositories" => cache.repository_cache.clear(),
-                key if key.starts_with("tags:") => {
-                    let repo = key.strip_prefix("tags:").unwrap_or("");
-                    cache.tag_cache.remove(repo);
-                }
-                _ => {
-                    // Remove specific key
-                    cache.manifest_cache.remove(pattern);
-                    cache.blob_metadata.remove(pattern);
-                    cache.repository_cache.remove(pattern);
-                    cache.tag_cache.remove(pattern);
-                }
-            }
-        }
+        let invalidation = Invalidation::Pattern(pattern.to_string());
+        self.evict_local(&invalidation).await;
         
         // Clear Redis cache entries
-        if let Some(redis) = &self.redis_client {
-            if let Ok(mut conn) = redis.get_connection() {
-                match pattern {
-                    "manifests" => {
-                        let keys: Vec<String> = conn.keys("manifest:*").unwrap_or_default();
-                        if !keys.is_empty() {
-                            let _: Result<(), _> = conn.del(&keys);
-                        }
-                    }
-                    "repositories" => {
-                        let keys: Vec<String> = conn.keys("repos:*").unwrap_or_default();
-                        if !keys.is_empty() {
-                            let _: Result<(), _> = conn.del(&keys);
-                        }
+        if let Some(mut conn) = self.redis().await {
+            match pattern {
+                "manifests" => {
+                    let keys: Vec<String> = self.timed(conn.keys("manifest:*")).await.unwrap_or_default();
+                    if !keys.is_empty() {
+                        let _: Result<(), _> = self.timed(conn.del(&keys)).await;
                     }
-                    key if key.starts_with("tags:") => {
-                        let _: Result<(), _> = conn.del(format!("tags:{}", key.strip_prefix("tags:").unwrap_or("")));
+                }
+                "repositories" => {
+                    let keys: Vec<String> = self.timed(conn.keys("repos:*")).await.unwrap_or_default();
+                    if !keys.is_empty() {
+                        let _: Result<(), _> = self.timed(conn.del(&keys)).await;
                     }
-                    _ => {
-                        // Try to remove specific keys
-                        let possible_keys = vec![
-                            format!("manifest:{}", pattern),
-                            format!("blob_meta:{}", pattern),
-                            format!("repos:{}", pattern),
-                            format!("tags:{}", pattern),
-                        ];
-                        for key in possible_keys {
-                            let _: Result<(), _> = conn.del(&key);
-                        }
+                }
+                key if key.starts_with("tags:") => {
+                    let _: Result<(), _> = self.timed(conn.del(format!("tags:{}", key.strip_prefix("tags:").unwrap_or("")))).await;
+                }
+                _ => {
+                    // Try to remove specific keys
+                    let possible_keys = vec![
+                        format!("manifest:{}", pattern),
+                        format!("blob_meta:{}", pattern),
+                        format!("repos:{}", pattern),
+                        format!("tags:{}", pattern),
+                    ];
+                    for key in possible_keys {
+                        let _: Result<(), _> = self.timed(conn.del(&key)).await;
                     }
                 }
             }
         }
         
+        self.publish(invalidation).await;
         Ok(())
     }
     
     /// Cleanup expired memory cache entries
     async fn cleanup_memory_cache(&self, cache: &mut MemoryCache) {
-        // Remove expired manifests
-        cache.manifest_cache.retain(|_, entry| !entry.is_expired());
-        
-        // Remove expired blob metadata
-        cache.blob_metadata.retain(|_, entry| !entry.is_expired());
-        
-        // Remove expired repositories
-        cache.repository_cache.retain(|_, entry| !entry.is_expired());
+        // Remove expired entries; manifests expire and are evicted by `manifests` itself
+        self.metrics.evicted(CacheCategory::BlobMetadata, remove_expired(&mut cache.blob_metadata));
+        self.metrics.evicted(CacheCategory::Repositories, remove_expired(&mut cache.repository_cache));
+        self.metrics.evicted(CacheCategory::Tags, remove_expired(&mut cache.tag_cache));
+        self.metrics.evicted(CacheCategory::Auth, remove_expired(&mut cache.auth_token_cache));
+        self.metrics.evicted(CacheCategory::ApiKey, remove_expired(&mut cache.api_key_cache));
+        self.metrics.evicted(CacheCategory::Permissions, remove_expired(&mut cache.permission_cache));
+        self.metrics.evicted(CacheCategory::Session, remove_expired(&mut cache.user_session_cache));
         
-        // Remove expired tags
-        cache.tag_cache.retain(|_, entry| !entry.is_expired());
+        // Remove expired counters
+        cache.counters.retain(|_, entry| !entry.is_expired());
         
         // If still over limit, remove oldest entries
-        let total_entries = cache.manifest_cache.len() + 
-                           cache.blob_metadata.len() + 
+        let total_entries = cache.blob_metadata.len() + 
                            cache.repository_cache.len() + 
                            cache.tag_cache.len();
         
         if total_entries > self.config.max_memory_entries {
-            let target_removals = total_entries - self.config.max_memory_entries;
-            let mut removed = 0;
-            
-            // Remove oldest manifest entries first
-            while removed < target_removals && !cache.manifest_cache.is_empty() {
-                if let Some(oldest_key) = cache.manifest_cache
-                    .iter()
-                    .min_by_key(|(_, entry)| entry.created_at)
-                    .map(|(k, _)| k.clone()) {
-                    cache.manifest_cache.remove(&oldest_key);
-                    removed += 1;
-                }
-            }
+            let mut remaining = total_entries - self.config.max_memory_entries;
             
-            // Remove oldest blob metadata entries
-            while removed < target_removals && !cache.blob_metadata.is_empty() {
-                if let Some(oldest_key) = cache.blob_metadata
-                    .iter()
-                    .min_by_key(|(_, entry)| entry.created_at)
-                    .map(|(k, _)| k.clone()) {
-                    cache.blob_metadata.remove(&oldest_key);
-                    removed += 1;
-                }
-            }
-            
-            // Then remove oldest repository entries
-            while removed < target_removals && !cache.repository_cache.is_empty() {
-                if let Some(oldest_key) = cache.repository_cache
-                    .iter()
-                    .min_by_key(|(_, entry)| entry.created_at)
-                    .map(|(k, _)| k.clone()) {
-                    cache.repository_cache.remove(&oldest_key);
-                    removed += 1;
-                }
-            }
+            // Remove oldest blob metadata first, then repository entries
+            let removed = remove_oldest(&mut cache.blob_metadata, remaining);
+            self.metrics.evicted(CacheCategory::BlobMetadata, removed);
+            remaining -= removed;
+            let removed = remove_oldest(&mut cache.repository_cache, remaining);
+            self.metrics.evicted(CacheCategory::Repositories, removed);
         }
     }
     
     /// Get cache statistics
     pub async fn get_stats(&self) -> CacheStats {
         let memory_stats = if self.config.enable_memory {
+            self.manifests.run_pending_tasks().await;
             let cache = self.memory_cache.read().await;
             MemoryCacheStats {
-                manifest_count: cache.manifest_cache.len(),
+                manifest_count: self.manifests.entry_count() as usize,
+                manifest_bytes: self.manifests.weighted_size(),
                 blob_metadata_count: cache.blob_metadata.len(),
                 repository_count: cache.repository_cache.len(),
                 tag_count: cache.tag_cache.len(),
@@ -554,19 +802,20 @@ impl RegistryCache {
         
         CacheStats {
             memory_cache: memory_stats,
-            redis_connected: self.redis_client.is_some(),
+            redis_connected: self.redis_pool.is_some(),
+            categories: self.metrics.snapshot(),
         }
     }
     
     /// Health check for cache system
     pub async fn health_check(&self) -> anyhow::Result<()> {
         // Test Redis connection if available
-        if let Some(redis) = &self.redis_client {
-            if let Ok(mut conn) = redis.get_connection() {
-                let _: String = redis::cmd("PING")
-                    .query(&mut conn)
-                    .map_err(|e| anyhow::anyhow!("Redis health check failed: {}", e))?;
-            }
+        if let Some(pool) = &self.redis_pool {
+            let mut conn = pool.get().await.map_err(|e| anyhow::anyhow!("Redis health check failed: {}", e))?;
+            let _: String = self
+                .timed(redis::cmd("PING").query_async(&mut *conn))
+                .await
+                .map_err(|e| anyhow::anyhow!("Redis health check failed: {}", e))?;
         }
         
         Ok(())
@@ -589,24 +838,35 @@ impl RegistryCache {
     /// Clear all cache entries
     pub async fn clear(&self) -> anyhow::Result<()> {
         // Clear memory cache
-        if self.config.enable_memory {
-            let mut cache = self.memory_cache.write().await;
-            cache.manifest_cache.clear();
-            cache.blob_metadata.clear();
-            cache.repository_cache.clear();
-            cache.tag_cache.clear();
-        }
+        self.evict_local(&Invalidation::All).await;
         
         // Clear Redis cache
-        if let Some(redis) = &self.redis_client {
-            if let Ok(mut conn) = redis.get_connection() {
-                let _: Result<(), _> = redis::cmd("FLUSHDB").query(&mut conn);
-            }
+        if let Some(mut conn) = self.redis().await {
+            let _: Result<(), _> = self.timed(redis::cmd("FLUSHDB").query_async(&mut *conn)).await;
         }
         
+        self.publish(Invalidation::All).await;
         Ok(())
     }
     
+    /// Drop cached registry content and credentials while keeping counters, sessions and
+    /// one-time codes, so rate limits and login lockouts survive the flush
+    pub async fn flush_cached_data(&self) -> Result<()> {
+        self.evict_local(&Invalidation::CachedData).await;
+
+        if let Some(mut conn) = self.redis().await {
+            for pattern in ["manifest:*", "blob_meta:*", "repos:*", "tags:*", "auth:*", "api_key:*", "perms:*"] {
+                let keys: Vec<String> = self.timed(conn.keys(pattern)).await.unwrap_or_default();
+                if !keys.is_empty() {
+                    let _: Result<(), _> = self.timed(conn.del(&keys)).await;
+                }
+            }
+        }
+
+        self.publish(Invalidation::CachedData).await;
+        Ok(())
+    }
+
     // ============ Authentication Caching Methods ============
     
     /// Cache authentication token
@@ -616,17 +876,15 @@ impl RegistryCache {
             let mut cache = self.memory_cache.write().await;
             cache.auth_token_cache.insert(
                 token.to_string(),
-                CacheEntry::new(auth_entry.clone(), self.config.auth_token_ttl),
+                CacheEntry::new(auth_entry.clone(), self.ttls().auth_token_ttl),
             );
         }
         
         // Redis cache
-        if let Some(redis) = &self.redis_client {
-            if let Ok(mut conn) = redis.get_connection() {
-                let redis_key = format!("auth:{}", token);
-                let serialized = serde_json::to_string(&auth_entry)?;
-                let _: Result<(), _> = conn.set_ex(&redis_key, serialized, self.config.auth_token_ttl.as_secs());
-            }
+        if let Some(mut conn) = self.redis().await {
+            let redis_key = format!("auth:{}", token);
+            let serialized = serde_json::to_string(&auth_entry)?;
+            let _: Result<(), _> = self.timed(conn.set_ex(&redis_key, serialized, self.ttls().auth_token_ttl.as_secs())).await;
         }
         
         Ok(())
@@ -639,32 +897,33 @@ impl RegistryCache {
             let cache = self.memory_cache.read().await;
             if let Some(entry) = cache.auth_token_cache.get(token) {
                 if !entry.is_expired() {
+                    self.metrics.hit(CacheCategory::Auth);
                     return Some(entry.data.clone());
                 }
             }
         }
         
         // Try Redis cache
-        if let Some(redis) = &self.redis_client {
-            if let Ok(mut conn) = redis.get_connection() {
-                let redis_key = format!("auth:{}", token);
-                if let Ok(data) = conn.get::<_, String>(&redis_key) {
-                    if let Ok(auth_entry) = serde_json::from_str::<AuthCacheEntry>(&data) {
-                        // Update memory cache
-                        if self.config.enable_memory {
-                            let mut cache = self.memory_cache.write().await;
-                            cache.auth_token_cache.insert(
-                                token.to_string(),
-                                CacheEntry::new(auth_entry.clone(), self.config.auth_token_ttl),
-                            );
-                        }
-                        
-                        return Some(auth_entry);
+        if let Some(mut conn) = self.redis().await {
+            let redis_key = format!("auth:{}", token);
+            if let Ok(data) = self.timed(conn.get::<_, String>(&redis_key)).await {
+                if let Ok(auth_entry) = serde_json::from_str::<AuthCacheEntry>(&data) {
+                    // Update memory cache
+                    if self.config.enable_memory {
+                        let mut cache = self.memory_cache.write().await;
+                        cache.auth_token_cache.insert(
+                            token.to_string(),
+                            CacheEntry::new(auth_entry.clone(), self.ttls().auth_token_ttl),
+                        );
                     }
+                    
+                    self.metrics.hit(CacheCategory::Auth);
+                    return Some(auth_entry);
                 }
             }
         }
         
+        self.metrics.miss(CacheCategory::Auth);
         None
     }
     
@@ -677,17 +936,15 @@ impl RegistryCache {
             let mut cache = self.memory_cache.write().await;
             cache.permission_cache.insert(
                 cache_key.clone(),
-                CacheEntry::new(permissions.clone(), self.config.permission_ttl),
+                CacheEntry::new(permissions.clone(), self.ttls().permission_ttl),
             );
         }
         
         // Redis cache
-        if let Some(redis) = &self.redis_client {
-            if let Ok(mut conn) = redis.get_connection() {
-                let redis_key = format!("perms:{}", cache_key);
-                let serialized = serde_json::to_string(&permissions)?;
-                let _: Result<(), _> = conn.set_ex(&redis_key, serialized, self.config.permission_ttl.as_secs());
-            }
+        if let Some(mut conn) = self.redis().await {
+            let redis_key = format!("perms:{}", cache_key);
+            let serialized = serde_json::to_string(&permissions)?;
+            let _: Result<(), _> = self.timed(conn.set_ex(&redis_key, serialized, self.ttls().permission_ttl.as_secs())).await;
         }
         
         Ok(())
@@ -702,32 +959,33 @@ impl RegistryCache {
             let cache = self.memory_cache.read().await;
             if let Some(entry) = cache.permission_cache.get(&cache_key) {
                 if !entry.is_expired() {
+                    self.metrics.hit(CacheCategory::Permissions);
                     return Some(entry.data.clone());
                 }
             }
         }
         
         // Try Redis cache
-        if let Some(redis) = &self.redis_client {
-            if let Ok(mut conn) = redis.get_connection() {
-                let redis_key = format!("perms:{}", cache_key);
-                if let Ok(data) = conn.get::<_, String>(&redis_key) {
-                    if let Ok(permissions) = serde_json::from_str::<PermissionCacheEntry>(&data) {
-                        // Update memory cache
-                        if self.config.enable_memory {
-                            let mut cache = self.memory_cache.write().await;
-                            cache.permission_cache.insert(
-                                cache_key,
-                                CacheEntry::new(permissions.clone(), self.config.permission_ttl),
-                            );
-                        }
-                        
-                        return Some(permissions);
+        if let Some(mut conn) = self.redis().await {
+            let redis_key = format!("perms:{}", cache_key);
+            if let Ok(data) = self.timed(conn.get::<_, String>(&redis_key)).await {
+                if let Ok(permissions) = serde_json::from_str::<PermissionCacheEntry>(&data) {
+                    // Update memory cache
+                    if self.config.enable_memory {
+                        let mut cache = self.memory_cache.write().await;
+                        cache.permission_cache.insert(
+                            cache_key,
+                            CacheEntry::new(permissions.clone(), self.ttls().permission_ttl),
+                        );
                     }
+                    
+                    self.metrics.hit(CacheCategory::Permissions);
+                    return Some(permissions);
                 }
             }
         }
         
+        self.metrics.miss(CacheCategory::Permissions);
         None
     }
     
@@ -738,17 +996,15 @@ impl RegistryCache {
             let mut cache = self.memory_cache.write().await;
             cache.user_session_cache.insert(
                 session_id.to_string(),
-                CacheEntry::new(session_data.clone(), self.config.session_ttl),
+                CacheEntry::new(session_data.clone(), self.ttls().session_ttl),
             );
         }
         
         // Redis cache
-        if let Some(redis) = &self.redis_client {
-            if let Ok(mut conn) = redis.get_connection() {
-                let redis_key = format!("session:{}", session_id);
-                let serialized = serde_json::to_string(&session_data)?;
-                let _: Result<(), _> = conn.set_ex(&redis_key, serialized, self.config.session_ttl.as_secs());
-            }
+        if let Some(mut conn) = self.redis().await {
+            let redis_key = format!("session:{}", session_id);
+            let serialized = serde_json::to_string(&session_data)?;
+            let _: Result<(), _> = self.timed(conn.set_ex(&redis_key, serialized, self.ttls().session_ttl.as_secs())).await;
         }
         
         Ok(())
@@ -761,79 +1017,66 @@ impl RegistryCache {
             let cache = self.memory_cache.read().await;
             if let Some(entry) = cache.user_session_cache.get(session_id) {
                 if !entry.is_expired() {
+                    self.metrics.hit(CacheCategory::Session);
                     return Some(entry.data.clone());
                 }
             }
         }
         
         // Try Redis cache
-        if let Some(redis) = &self.redis_client {
-            if let Ok(mut conn) = redis.get_connection() {
-                let redis_key = format!("session:{}", session_id);
-                if let Ok(data) = conn.get::<_, String>(&redis_key) {
-                    if let Ok(session_data) = serde_json::from_str::<UserSessionCache>(&data) {
-                        // Update memory cache
-                        if self.config.enable_memory {
-                            let mut cache = self.memory_cache.write().await;
-                            cache.user_session_cache.insert(
-                                session_id.to_string(),
-                                CacheEntry::new(session_data.clone(), self.config.session_ttl),
-                            );
-                        }
-                        
-                        return Some(session_data);
+        if let Some(mut conn) = self.redis().await {
+            let redis_key = format!("session:{}", session_id);
+            if let Ok(data) = self.timed(conn.get::<_, String>(&redis_key)).await {
+                if let Ok(session_data) = serde_json::from_str::<UserSessionCache>(&data) {
+                    // Update memory cache
+                    if self.config.enable_memory {
+                        let mut cache = self.memory_cache.write().await;
+                        cache.user_session_cache.insert(
+                            session_id.to_string(),
+                            CacheEntry::new(session_data.clone(), self.ttls().session_ttl),
+                        );
                     }
+                    
+                    self.metrics.hit(CacheCategory::Session);
+                    return Some(session_data);
                 }
             }
         }
         
+        self.metrics.miss(CacheCategory::Session);
         None
     }
     
     /// Invalidate authentication cache entries
     pub async fn invalidate_auth_token(&self, token: &str) -> Result<()> {
-        // Remove from memory cache
-        if self.config.enable_memory {
-            let mut cache = self.memory_cache.write().await;
-            cache.auth_token_cache.remove(token);
-        }
+        let invalidation = Invalidation::AuthToken(token.to_string());
+        self.evict_local(&invalidation).await;
         
         // Remove from Redis cache
-        if let Some(redis) = &self.redis_client {
-            if let Ok(mut conn) = redis.get_connection() {
-                let redis_key = format!("auth:{}", token);
-                let _: Result<(), _> = conn.del(&redis_key);
-            }
+        if let Some(mut conn) = self.redis().await {
+            let redis_key = format!("auth:{}", token);
+            let _: Result<(), _> = self.timed(conn.del(&redis_key)).await;
         }
         
+        self.publish(invalidation).await;
         Ok(())
     }
     
     /// Invalidate all permissions for a user
     pub async fn invalidate_user_permissions(&self, user_id: &str) -> Result<()> {
-        // Remove from memory cache
-        if self.config.enable_memory {
-            let mut cache = self.memory_cache.write().await;
-            let keys_to_remove: Vec<String> = cache.permission_cache.keys()
-                .filter(|key| key.starts_with(&format!("{}:", user_id)))
-                .cloned()
-                .collect();
-            for key in keys_to_remove {
-                cache.permission_cache.remove(&key);
-            }
-        }
+        let invalidation = Invalidation::UserPermissions(user_id.to_string());
+        self.evict_local(&invalidation).await;
         
         // Remove from Redis cache
-        if let Some(redis) = &self.redis_client {
-            if let Ok(mut conn) = redis.get_connection() {
-                let pattern = format!("perms:{}:*", user_id);
-                let keys: Vec<String> = conn.keys(&pattern).unwrap_or_default();
-                if !keys.is_empty() {
-                    let _: Result<(), _> = conn.del(&keys);
-                }
+        if let Some(mut conn) = self.redis().await {
+            let pattern = format!("perms:{}:*", user_id);
+            let keys: Vec<String> = self.timed(conn.keys(&pattern)).await.unwrap_or_default();
+            if !keys.is_empty() {
+                let _: Result<(), _> = self.timed(conn.del(&keys)).await;
             }
         }
         
+        self.publish(invalidation).await;
         Ok(())
     }
     
@@ -841,60 +1084,47 @@ impl RegistryCache {
 
     /// Invalidate manifest cache entry
     pub async fn invalidate_manifest(&self, cache_key: &str) -> Result<()> {
-        // Remove from memory cache
-        if self.config.enable_memory {
-            let mut cache = self.memory_cache.write().await;
-            cache.manifest_cache.remove(cache_key);
-        }
+        let invalidation = Invalidation::Manifest(cache_key.to_string());
+        self.evict_local(&invalidation).await;
         
         // Remove from Redis cache
-        if let Some(redis) = &self.redis_client {
-            if let Ok(mut conn) = redis.get_connection() {
-                let redis_key = format!("manifest:{}", cache_key.strip_prefix("manifest:").unwrap_or(cache_key));
-                let _: Result<(), _> = conn.del(&redis_key);
-            }
+        if let Some(mut conn) = self.redis().await {
+            let redis_key = format!("manifest:{}", cache_key);
+            let _: Result<(), _> = self.timed(conn.del(&redis_key)).await;
         }
         
+        self.publish(invalidation).await;
         Ok(())
     }
     
     /// Invalidate tags cache for a repository
     pub async fn invalidate_tags(&self, repository: &str) -> Result<()> {
-        // Remove from memory cache
-        if self.config.enable_memory {
-            let mut cache = self.memory_cache.write().await;
-            cache.tag_cache.remove(repository);
-        }
+        let invalidation = Invalidation::Tags(repository.to_string());
+        self.evict_local(&invalidation).await;
         
         // Remove from Redis cache
-        if let Some(redis) = &self.redis_client {
-            if let Ok(mut conn) = redis.get_connection() {
-                let redis_key = format!("tags:{}", repository);
-                let _: Result<(), _> = conn.del(&redis_key);
-            }
+        if let Some(mut conn) = self.redis().await {
+            let redis_key = format!("tags:{}", repository);
+            let _: Result<(), _> = self.timed(conn.del(&redis_key)).await;
         }
         
+        self.publish(invalidation).await;
         Ok(())
     }
     
     /// Invalidate repository cache
     pub async fn invalidate_repositories(&self) -> Result<()> {
-        // Remove from memory cache
-        if self.config.enable_memory {
-            let mut cache = self.memory_cache.write().await;
-            cache.repository_cache.clear();
-        }
+        self.evict_local(&Invalidation::Repositories).await;
         
         // Remove from Redis cache
-        if let Some(redis) = &self.redis_client {
-            if let Ok(mut conn) = redis.get_connection() {
-                let keys: Vec<String> = conn.keys("repos:*").unwrap_or_default();
-                if !keys.is_empty() {
-                    let _: Result<(), _> = conn.del(&keys);
-                }
+        if let Some(mut conn) = self.redis().await {
+            let keys: Vec<String> = self.timed(conn.keys("repos:*")).await.unwrap_or_default();
+            if !keys.is_empty() {
+                let _: Result<(), _> = self.timed(conn.del(&keys)).await;
             }
         }
         
+        self.publish(Invalidation::Repositories).await;
         Ok(())
     }
 
@@ -917,13 +1147,9 @@ impl RegistryCache {
             );
         }
 
-        if self.config.enable_redis && self.redis_client.is_some() {
-            if let Some(redis) = &self.redis_client {
-                if let Ok(mut conn) = redis.get_connection() {
-                    let redis_key = format!("otp:reset:{}", email);
-                    let _: Result<(), _> = conn.set_ex(&redis_key, otp_code, ttl.as_secs() as u64);
-                }
-            }
+        if let Some(mut conn) = self.redis().await {
+            let redis_key = format!("otp:reset:{}", email);
+            let _: Result<(), _> = self.timed(conn.set_ex(&redis_key, otp_code, ttl.as_secs() as u64)).await;
         }
 
         Ok(())
@@ -944,13 +1170,9 @@ impl RegistryCache {
             }
         }
 
-        if self.config.enable_redis && self.redis_client.is_some() {
-            if let Some(redis) = &self.redis_client {
-                if let Ok(mut conn) = redis.get_connection() {
-                    if let Ok(otp_code) = conn.get::<_, String>(&cache_key) {
-                        return Some(otp_code);
-                    }
-                }
+        if let Some(mut conn) = self.redis().await {
+            if let Ok(otp_code) = self.timed(conn.get::<_, String>(&cache_key)).await {
+                return Some(otp_code);
             }
         }
 
@@ -966,12 +1188,8 @@ impl RegistryCache {
             cache.user_session_cache.remove(&cache_key);
         }
 
-        if self.config.enable_redis && self.redis_client.is_some() {
-            if let Some(redis) = &self.redis_client {
-                if let Ok(mut conn) = redis.get_connection() {
-                    let _: Result<(), _> = conn.del(&cache_key);
-                }
-            }
+        if let Some(mut conn) = self.redis().await {
+            let _: Result<(), _> = self.timed(conn.del(&cache_key)).await;
         }
 
         Ok(())
@@ -987,16 +1205,14 @@ impl RegistryCache {
             let serialized = serde_json::to_string(&api_key_entry)?;
             cache.api_key_cache.insert(
                 cache_key.clone(),
-                CacheEntry::new(serialized, self.config.auth_token_ttl),
+                CacheEntry::new(serialized, self.ttls().auth_token_ttl),
             );
         }
         
         // Redis cache
-        if let Some(redis) = &self.redis_client {
-            if let Ok(mut conn) = redis.get_connection() {
-                let serialized = serde_json::to_string(&api_key_entry)?;
-                let _: Result<(), _> = conn.set_ex(&cache_key, serialized, self.config.auth_token_ttl.as_secs());
-            }
+        if let Some(mut conn) = self.redis().await {
+            let serialized = serde_json::to_string(&api_key_entry)?;
+            let _: Result<(), _> = self.timed(conn.set_ex(&cache_key, serialized, self.ttls().auth_token_ttl.as_secs())).await;
         }
         
         Ok(())
@@ -1013,6 +1229,7 @@ impl RegistryCache {
                 if !entry.is_expired() {
                     // entry.data is already a String for API key cache
                     if let Ok(api_key_entry) = serde_json::from_str::<ApiKeyCacheEntry>(&entry.data) {
+                        self.metrics.hit(CacheCategory::ApiKey);
                         return Some(api_key_entry);
                     }
                 }
@@ -1020,39 +1237,406 @@ impl RegistryCache {
         }
         
         // Try Redis cache
-        if let Some(redis) = &self.redis_client {
-            if let Ok(mut conn) = redis.get_connection() {
-                if let Ok(data) = conn.get::<_, String>(&cache_key) {
-                    if let Ok(api_key_entry) = serde_json::from_str::<ApiKeyCacheEntry>(&data) {
-                        // Update memory cache
-                        if self.config.enable_memory {
-                            let mut cache = self.memory_cache.write().await;
-                            cache.api_key_cache.insert(
-                                cache_key,
-                                CacheEntry::new(data, self.config.auth_token_ttl),
-                            );
-                        }
-                        
-                        return Some(api_key_entry);
+        if let Some(mut conn) = self.redis().await {
+            if let Ok(data) = self.timed(conn.get::<_, String>(&cache_key)).await {
+                if let Ok(api_key_entry) = serde_json::from_str::<ApiKeyCacheEntry>(&data) {
+                    // Update memory cache
+                    if self.config.enable_memory {
+                        let mut cache = self.memory_cache.write().await;
+                        cache.api_key_cache.insert(
+                            cache_key,
+                            CacheEntry::new(data, self.ttls().auth_token_ttl),
+                        );
                     }
+                    
+                    self.metrics.hit(CacheCategory::ApiKey);
+                    return Some(api_key_entry);
                 }
             }
         }
         
+        self.metrics.miss(CacheCategory::ApiKey);
         None
     }
+
+    /// Invalidate cached API key information
+    pub async fn invalidate_api_key(&self, key_hash: &str) -> Result<()> {
+        let cache_key = format!("api_key:{}", key_hash);
+        let invalidation = Invalidation::ApiKey(cache_key.clone());
+        self.evict_local(&invalidation).await;
+
+        if let Some(mut conn) = self.redis().await {
+            let _: Result<(), _> = self.timed(conn.del(&cache_key)).await;
+        }
+
+        self.publish(invalidation).await;
+        Ok(())
+    }
+
+    // ============ Invalidation across replicas ============
+
+    /// Drop what `invalidation` covers from this replica's memory cache
+    async fn evict_local(&self, invalidation: &Invalidation) {
+        // Before anything is dropped, so a load reading the old manifest cannot cache it after
+        match invalidation {
+            Invalidation::Manifest(key) => {
+                self.manifest_invalidations.fetch_add(1, Ordering::AcqRel);
+                self.manifest_loads.forget(key);
+            }
+            Invalidation::Pattern(pattern) if pattern != "repositories" && !pattern.starts_with("tags:") => {
+                self.manifest_invalidations.fetch_add(1, Ordering::AcqRel);
+                self.manifest_loads.forget_all();
+            }
+            Invalidation::CachedData | Invalidation::All => {
+                self.manifest_invalidations.fetch_add(1, Ordering::AcqRel);
+                self.manifest_loads.forget_all();
+            }
+            _ => {}
+        }
+
+        if !self.config.enable_memory {
+            return;
+        }
+        match invalidation {
+            Invalidation::Pattern(pattern) => match pattern.as_str() {
+                "manifests" => self.manifests.invalidate_all(),
+                "repositories" => self.memory_cache.write().await.repository_cache.clear(),
+                key if key.starts_with("tags:") => {
+                    let repo = key.strip_prefix("tags:").unwrap_or("");
+                    self.memory_cache.write().await.tag_cache.remove(repo);
+                }
+                _ => {
+                    // Remove specific key
+                    self.manifests.invalidate(pattern).await;
+                    let mut cache = self.memory_cache.write().await;
+                    cache.blob_metadata.remove(pattern);
+                    cache.repository_cache.remove(pattern);
+                    cache.tag_cache.remove(pattern);
+                }
+            },
+            Invalidation::Manifest(key) => self.manifests.invalidate(key).await,
+            Invalidation::Tags(repository) => {
+                self.memory_cache.write().await.tag_cache.remove(repository);
+            }
+            Invalidation::Repositories => self.memory_cache.write().await.repository_cache.clear(),
+            Invalidation::AuthToken(token) => {
+                self.memory_cache.write().await.auth_token_cache.remove(token);
+            }
+            Invalidation::UserPermissions(user_id) => {
+                let prefix = format!("{}:", user_id);
+                self.memory_cache.write().await.permission_cache.retain(|key, _| !key.starts_with(&prefix));
+            }
+            Invalidation::ApiKey(cache_key) => {
+                self.memory_cache.write().await.api_key_cache.remove(cache_key);
+            }
+            Invalidation::CachedData | Invalidation::All => {
+                self.manifests.invalidate_all();
+                let mut cache = self.memory_cache.write().await;
+                cache.blob_metadata.clear();
+                cache.repository_cache.clear();
+                cache.tag_cache.clear();
+                if *invalidation == Invalidation::CachedData {
+                    cache.auth_token_cache.clear();
+                    cache.api_key_cache.clear();
+                    cache.permission_cache.clear();
+                }
+            }
+        }
+    }
+
+    /// Tell the other replicas to drop `invalidation` from their memory caches. Without Redis
+    /// there is no one to tell.
+    async fn publish(&self, invalidation: Invalidation) {
+        let Some(mut conn) = self.redis().await else {
+            return;
+        };
+        let message = InvalidationMessage { origin: self.node_id.to_string(), invalidation };
+        let Ok(payload) = serde_json::to_string(&message) else {
+            return;
+        };
+        if let Err(e) = self.timed(conn.publish::<_, _, i64>(INVALIDATION_CHANNEL, payload)).await {
+            tracing::warn!("Failed to publish cache invalidation: {}", e);
+        }
+    }
+
+    /// Apply an invalidation published by another replica; our own were applied when made
+    async fn apply_invalidation(&self, payload: &str) {
+        match serde_json::from_str::<InvalidationMessage>(payload) {
+            Ok(message) if *message.origin != *self.node_id => self.evict_local(&message.invalidation).await,
+            Ok(_) => {}
+            Err(e) => tracing::warn!("Ignoring malformed cache invalidation: {}", e),
+        }
+    }
+
+    /// Apply invalidations published by other replicas until the subscription ends. After
+    /// resubscribing the memory cache is emptied, as invalidations may have been missed meanwhile.
+    async fn listen_for_invalidations(&self, redis_url: &str, resubscribing: bool) -> Result<()> {
+        let mut pubsub = redis::Client::open(redis_url)?.get_async_pubsub().await?;
+        pubsub.subscribe(INVALIDATION_CHANNEL).await?;
+        if resubscribing {
+            self.evict_local(&Invalidation::CachedData).await;
+        }
+
+        let mut messages = pubsub.on_message();
+        while let Some(message) = messages.next().await {
+            match message.get_payload::<String>() {
+                Ok(payload) => self.apply_invalidation(&payload).await,
+                Err(e) => tracing::warn!("Ignoring unreadable cache invalidation: {}", e),
+            }
+        }
+        Ok(())
+    }
+
+    // ============ Counters ============
+
+    /// Increment an expiring counter and return its new value; the counter expires `ttl` after
+    /// the last increment.
+    ///
+    /// Redis keeps counters shared across replicas; without it each process counts on its own.
+    pub async fn increment_counter(&self, key: &str, ttl: Duration) -> u64 {
+        let redis_key = format!("counter:{}", key);
+
+        if let Some(mut conn) = self.redis().await {
+            let result: redis::RedisResult<(u64,)> = self
+                .timed(
+                    redis::pipe()
+                        .atomic()
+                        .incr(&redis_key, 1u64)
+                        .expire(&redis_key, ttl.as_secs() as i64)
+                        .ignore()
+                        .query_async(&mut *conn),
+                )
+                .await;
+            match result {
+                Ok((count,)) => return count,
+                Err(e) => tracing::warn!("Counter {} unavailable in Redis: {}", key, e),
+            }
+        }
+
+        let mut cache = self.memory_cache.write().await;
+        let entry = cache.counters.entry(redis_key).or_insert_with(|| CacheEntry::new(0, ttl));
+        let count = if entry.is_expired() { 1 } else { entry.data + 1 };
+        *entry = CacheEntry::new(count, ttl);
+        count
+    }
+
+    /// Current value of a counter, `None` if it was never set or has expired
+    pub async fn get_counter(&self, key: &str) -> Option<u64> {
+        let redis_key = format!("counter:{}", key);
+
+        if let Some(mut conn) = self.redis().await {
+            if let Ok(value) = self.timed(conn.get::<_, Option<u64>>(&redis_key)).await {
+                return value;
+            }
+        }
+
+        let cache = self.memory_cache.read().await;
+        cache.counters.get(&redis_key).filter(|entry| !entry.is_expired()).map(|entry| entry.data)
+    }
+
+    /// Set a counter to `value`, expiring after `ttl`
+    pub async fn set_counter(&self, key: &str, value: u64, ttl: Duration) {
+        let redis_key = format!("counter:{}", key);
+
+        if let Some(mut conn) = self.redis().await {
+            if self.timed(conn.set_ex::<_, _, ()>(&redis_key, value, ttl.as_secs())).await.is_ok() {
+                return;
+            }
+        }
+
+        let mut cache = self.memory_cache.write().await;
+        cache.counters.insert(redis_key, CacheEntry::new(value, ttl));
+    }
+
+    pub async fn delete_counter(&self, key: &str) {
+        let redis_key = format!("counter:{}", key);
+
+        if let Some(mut conn) = self.redis().await {
+            let _: Result<(), _> = self.timed(conn.del(&redis_key)).await;
+        }
+
+        let mut cache = self.memory_cache.write().await;
+        cache.counters.remove(&redis_key);
+    }
+
+    // ============ Activity buffer ============
+
+    /// Add `amount` to a pending pull or push count; see `crate::activity`.
+    ///
+    /// Redis holds the counts of every replica in one hash; without it each process keeps its own.
+    pub async fn buffer_activity(&self, field: &str, amount: i64) {
+        if let Some(mut conn) = self.redis().await {
+            match self.timed(conn.hincr::<_, _, _, i64>(PENDING_ACTIVITY_KEY, field, amount)).await {
+                Ok(_) => return,
+                Err(e) => tracing::warn!("Activity buffer unavailable in Redis: {}", e),
+            }
+        }
+
+        let mut cache = self.memory_cache.write().await;
+        *cache.pending_activity.entry(field.to_string()).or_insert(0) += amount;
+    }
+
+    /// Take every pending count, leaving none behind for another flush
+    pub async fn drain_activity(&self) -> HashMap<String, i64> {
+        let mut drained = std::mem::take(&mut self.memory_cache.write().await.pending_activity);
+
+        if let Some(mut conn) = self.redis().await {
+            // Renaming is atomic, so counts added meanwhile start a new hash
+            let flushing = format!("{}:{}", PENDING_ACTIVITY_KEY, uuid::Uuid::new_v4());
+            if self.timed(conn.rename::<_, _, ()>(PENDING_ACTIVITY_KEY, &flushing)).await.is_ok() {
+                let counts: redis::RedisResult<HashMap<String, i64>> = self.timed(conn.hgetall(&flushing)).await;
+                let _: Result<(), _> = self.timed(conn.del(&flushing)).await;
+                match counts {
+                    Ok(counts) => {
+                        for (field, amount) in counts {
+                            *drained.entry(field).or_insert(0) += amount;
+                        }
+                    }
+                    Err(e) => tracing::warn!("Failed to read buffered activity from Redis: {}", e),
+                }
+            }
+        }
+        drained
+    }
+}
+
+const PENDING_ACTIVITY_KEY: &str = "activity:pending";
+
+/// Redis pub/sub channel replicas announce invalidations on
+const INVALIDATION_CHANNEL: &str = "cache:invalidations";
+
+/// What an invalidation drops from memory. Redis is shared by every replica, but each keeps its
+/// own memory cache, so invalidations are published for the others to apply too.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "key", rename_all = "snake_case")]
+enum Invalidation {
+    /// As taken by `RegistryCache::invalidate`
+    Pattern(String),
+    Manifest(String),
+    Tags(String),
+    Repositories,
+    AuthToken(String),
+    UserPermissions(String),
+    ApiKey(String),
+    /// Everything `flush_cached_data` drops
+    CachedData,
+    /// Everything `clear` drops
+    All,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct InvalidationMessage {
+    /// `node_id` of the replica that published it
+    origin: String,
+    invalidation: Invalidation,
+}
+
+/// Apply the cache invalidations other replicas publish to this replica's memory cache, so it
+/// stops serving manifests, tags and permissions changed through another replica. Runs while
+/// the cache uses Redis, resubscribing after the connection is lost.
+pub fn spawn_invalidation_listener(state: AppState) {
+    let Some(cache) = state.cache.clone() else {
+        return;
+    };
+    let redis_url = match &cache.config.redis_url {
+        Some(url) if cache.redis_pool.is_some() && cache.config.enable_memory => url.clone(),
+        _ => return,
+    };
+
+    tokio::spawn(async move {
+        let mut resubscribing = false;
+        loop {
+            match cache.listen_for_invalidations(&redis_url, resubscribing).await {
+                Ok(()) => tracing::warn!("Cache invalidation subscription closed, resubscribing"),
+                Err(e) => tracing::warn!("Cache invalidation subscription failed: {}, retrying", e),
+            }
+            resubscribing = true;
+            tokio::time::sleep(Duration::from_secs(5)).await;
+        }
+    });
+}
+
+fn manifest_content_key(digest: &str) -> String {
+    format!("content:{}", digest)
+}
+
+/// Manifests cached by reference expire after the manifest TTL, as a tag may move. Content
+/// kept by digest never changes, so it stays until evicted for space.
+struct ManifestExpiry {
+    ttls: Arc<std::sync::RwLock<CacheTtls>>,
+}
+
+impl moka::Expiry<String, Bytes> for ManifestExpiry {
+    fn expire_after_create(&self, key: &String, _manifest: &Bytes, _created_at: Instant) -> Option<Duration> {
+        if key.starts_with("content:") {
+            return None;
+        }
+        Some(self.ttls.read().unwrap_or_else(|e| e.into_inner()).manifest_ttl)
+    }
+
+    fn expire_after_update(
+        &self,
+        key: &String,
+        manifest: &Bytes,
+        updated_at: Instant,
+        _duration_until_expiry: Option<Duration>,
+    ) -> Option<Duration> {
+        self.expire_after_create(key, manifest, updated_at)
+    }
+}
+
+/// Remove expired entries, returning how many there were
+fn remove_expired<T>(entries: &mut HashMap<String, CacheEntry<T>>) -> usize {
+    let before = entries.len();
+    entries.retain(|_, entry| !entry.is_expired());
+    before - entries.len()
+}
+
+/// Remove up to `count` of the oldest entries, returning how many were removed
+fn remove_oldest<T>(entries: &mut HashMap<String, CacheEntry<T>>, count: usize) -> usize {
+    let mut removed = 0;
+    while removed < count {
+        let Some(oldest_key) = entries.iter().min_by_key(|(_, entry)| entry.created_at).map(|(k, _)| k.clone()) else {
+            break;
+        };
+        entries.remove(&oldest_key);
+        removed += 1;
+    }
+    removed
+}
+
+/// Open a bounded pool of Redis connections and check the server answers. Broken connections
+/// are replaced by new ones as they are checked out, so the cache reconnects after an outage.
+async fn connect_redis(redis_url: &str, config: &CacheConfig) -> Result<Pool<RedisConnectionManager>> {
+    let pool = Pool::builder()
+        .max_size(config.redis_pool_size)
+        .connection_timeout(config.redis_connect_timeout)
+        .build(RedisConnectionManager::new(redis_url)?)
+        .await?;
+    {
+        let mut conn = pool.get().await?;
+        let _: String = tokio::time::timeout(config.redis_command_timeout, redis::cmd("PING").query_async(&mut *conn))
+            .await
+            .map_err(|_| anyhow::anyhow!("Redis did not answer PING in time"))??;
+    }
+    Ok(pool)
 }
 
 /// Cache statistics
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct CacheStats {
     pub memory_cache: MemoryCacheStats,
     pub redis_connected: bool,
+    /// Lookups and evictions per kind of cached data since the process started
+    pub categories: Vec<CategoryStats>,
 }
 
-#[derive(Debug, Serialize, Default)]
+/// Entries held in memory per kind of cached data
+#[derive(Debug, Serialize, Default, ToSchema)]
 pub struct MemoryCacheStats {
     pub manifest_count: usize,
+    /// Bytes of manifests held, counting their keys
+    pub manifest_bytes: u64,
     pub blob_metadata_count: usize,
     pub repository_count: usize,
     pub tag_count: usize,
@@ -1060,3 +1644,261 @@ pub struct MemoryCacheStats {
     pub permission_count: usize,
     pub session_count: usize,
 }
+
+/// Lookups of one kind of cached data
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct CategoryStats {
+    /// `manifest`, `blob_metadata`, `repositories`, `tags`, `auth`, `api_key`, `permissions` or `session`
+    pub category: String,
+    /// Lookups answered from memory or Redis
+    pub hits: u64,
+    pub misses: u64,
+    /// Entries dropped from memory because they expired or the cache was full
+    pub evictions: u64,
+    /// Share of lookups that hit, absent before the first lookup
+    pub hit_ratio: Option<f64>,
+}
+
+impl CacheStats {
+    /// The statistics in the Prometheus text exposition format
+    pub fn to_prometheus(&self) -> String {
+        use std::fmt::Write;
+
+        let mut out = String::new();
+        let counters: [(&str, &str, fn(&CategoryStats) -> u64); 3] = [
+            ("aerugo_cache_hits_total", "Cache lookups answered from memory or Redis", |c| c.hits),
+            ("aerugo_cache_misses_total", "Cache lookups that found nothing", |c| c.misses),
+            ("aerugo_cache_evictions_total", "Entries dropped from the memory cache", |c| c.evictions),
+        ];
+        for (name, help, value) in counters {
+            let _ = writeln!(out, "# HELP {} {}", name, help);
+            let _ = writeln!(out, "# TYPE {} counter", name);
+            for category in &self.categories {
+                let _ = writeln!(out, "{}{{category=\"{}\"}} {}", name, category.category, value(category));
+            }
+        }
+
+        let memory = &self.memory_cache;
+        let _ = writeln!(out, "# HELP aerugo_cache_memory_entries Entries held in the memory cache");
+        let _ = writeln!(out, "# TYPE aerugo_cache_memory_entries gauge");
+        for (category, count) in [
+            ("manifest", memory.manifest_count),
+            ("blob_metadata", memory.blob_metadata_count),
+            ("repositories", memory.repository_count),
+            ("tags", memory.tag_count),
+            ("auth", memory.auth_token_count),
+            ("permissions", memory.permission_count),
+            ("session", memory.session_count),
+        ] {
+            let _ = writeln!(out, "aerugo_cache_memory_entries{{category=\"{}\"}} {}", category, count);
+        }
+        let _ = writeln!(out, "# HELP aerugo_cache_manifest_memory_bytes Bytes of manifests held in the memory cache");
+        let _ = writeln!(out, "# TYPE aerugo_cache_manifest_memory_bytes gauge");
+        let _ = writeln!(out, "aerugo_cache_manifest_memory_bytes {}", memory.manifest_bytes);
+        let _ = writeln!(out, "# HELP aerugo_cache_redis_connected Whether the cache uses Redis");
+        let _ = writeln!(out, "# TYPE aerugo_cache_redis_connected gauge");
+        let _ = writeln!(out, "aerugo_cache_redis_connected {}", u8::from(self.redis_connected));
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[tokio::test]
+    async fn concurrent_loads_of_a_key_run_once() {
+        let in_flight = Arc::new(InFlight::<u32>::new());
+        let loads = Arc::new(AtomicUsize::new(0));
+        let tasks: Vec<_> = (0..16)
+            .map(|_| {
+                let (in_flight, loads) = (in_flight.clone(), loads.clone());
+                tokio::spawn(async move {
+                    in_flight
+                        .run("manifest:acme/web:latest", || async {
+                            loads.fetch_add(1, Ordering::SeqCst);
+                            tokio::time::sleep(Duration::from_millis(50)).await;
+                            7
+                        })
+                        .await
+                })
+            })
+            .collect();
+        for task in tasks {
+            assert_eq!(task.await.unwrap(), 7);
+        }
+        assert_eq!(loads.load(Ordering::SeqCst), 1);
+
+        // Finished loads are forgotten
+        assert_eq!(in_flight.run("manifest:acme/web:latest", || async { 8 }).await, 8);
+        assert!(in_flight.loads.lock().unwrap().is_empty());
+    }
+
+    fn loaded(content: impl Into<Bytes>) -> ManifestLoad {
+        Ok(LoadedManifest { content: content.into(), media_type: "application/json".to_string(), digest: String::new() })
+    }
+
+    #[tokio::test]
+    async fn loads_overlapping_a_push_do_not_cache_the_old_manifest() {
+        let cache = RegistryCache::new(CacheConfig { redis_url: None, enable_redis: false, ..CacheConfig::default() })
+            .await
+            .unwrap();
+        let key = "manifest:acme/web:latest";
+        let (read, pushed) = (Arc::new(tokio::sync::Notify::new()), Arc::new(tokio::sync::Notify::new()));
+
+        // A pull reads the tag, then a push moves it and invalidates before the pull finishes
+        let pull = {
+            let (cache, read, pushed) = (cache.clone(), read.clone(), pushed.clone());
+            tokio::spawn(async move {
+                cache
+                    .load_manifest_once(key, || async {
+                        read.notify_one();
+                        pushed.notified().await;
+                        loaded("old")
+                    })
+                    .await
+            })
+        };
+        read.notified().await;
+        cache.invalidate_manifest(key).await.unwrap();
+
+        // Pulls after the push load afresh rather than wait for the old load
+        assert_eq!(cache.load_manifest_once(key, || async { loaded("new") }).await.unwrap().content, "new");
+        pushed.notify_one();
+        assert_eq!(pull.await.unwrap().unwrap().content, "old");
+        assert_eq!(cache.get_manifest(key).await, Some(Bytes::from_static(b"new")));
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn concurrent_pushes_and_pulls_leave_the_last_push_cached() {
+        let cache = RegistryCache::new(CacheConfig { redis_url: None, enable_redis: false, ..CacheConfig::default() })
+            .await
+            .unwrap();
+        let key = "manifest:acme/web:latest";
+        // What the tag points at in the database
+        let tag = Arc::new(Mutex::new(0u32));
+
+        let tasks: Vec<_> = (1..=200u32)
+            .map(|i| {
+                let (cache, tag) = (cache.clone(), tag.clone());
+                tokio::spawn(async move {
+                    if i % 5 == 0 {
+                        // A push commits the tag, then invalidates
+                        *tag.lock().unwrap() = i;
+                        tokio::task::yield_now().await;
+                        cache.invalidate_manifest(key).await.unwrap();
+                    } else {
+                        let _ = cache
+                            .load_manifest_once(key, || async {
+                                let read = *tag.lock().unwrap();
+                                tokio::task::yield_now().await;
+                                loaded(read.to_string())
+                            })
+                            .await;
+                    }
+                })
+            })
+            .collect();
+        for task in tasks {
+            task.await.unwrap();
+        }
+
+        // A pull after the pushes, served from the cache when it has the manifest, gets the last push
+        let last = tag.lock().unwrap().to_string();
+        let served = match cache.get_manifest(key).await {
+            Some(cached) => cached,
+            None => {
+                let tag = tag.clone();
+                cache
+                    .load_manifest_once(key, || async move { loaded(tag.lock().unwrap().to_string()) })
+                    .await
+                    .unwrap()
+                    .content
+            }
+        };
+        assert_eq!(served, last);
+        assert_eq!(cache.get_manifest(key).await, Some(Bytes::from(last)));
+    }
+
+    #[tokio::test]
+    async fn lookups_and_evictions_are_counted_per_category() {
+        let cache = RegistryCache::new(CacheConfig { redis_url: None, enable_redis: false, ..CacheConfig::default() })
+            .await
+            .unwrap();
+        cache.cache_tags("acme/web", vec!["latest".to_string()]).await.unwrap();
+        assert!(cache.get_tags("acme/web").await.is_some());
+        assert!(cache.get_tags("acme/api").await.is_none());
+        assert!(cache.get_manifest("manifest:acme/web:latest").await.is_none());
+
+        cache.memory_cache.write().await.tag_cache.insert(
+            "acme/old".to_string(),
+            CacheEntry::new(Vec::new(), Duration::ZERO),
+        );
+        tokio::time::sleep(Duration::from_millis(5)).await;
+        cache.cleanup_expired().await.unwrap();
+
+        let stats = cache.get_stats().await;
+        let tags = stats.categories.iter().find(|c| c.category == "tags").unwrap();
+        assert_eq!((tags.hits, tags.misses, tags.evictions), (1, 1, 1));
+        assert_eq!(tags.hit_ratio, Some(0.5));
+        let manifests = stats.categories.iter().find(|c| c.category == "manifest").unwrap();
+        assert_eq!((manifests.hits, manifests.misses), (0, 1));
+
+        let text = stats.to_prometheus();
+        assert!(text.contains("# TYPE aerugo_cache_hits_total counter"));
+        assert!(text.contains("aerugo_cache_misses_total{category=\"tags\"} 1"));
+        assert!(text.contains("aerugo_cache_memory_entries{category=\"tags\"} 1"));
+        assert!(text.contains("aerugo_cache_redis_connected 0"));
+    }
+
+    #[tokio::test]
+    async fn manifests_held_in_memory_are_bounded_by_size() {
+        let cache = RegistryCache::new(CacheConfig {
+            redis_url: None,
+            enable_redis: false,
+            manifest_memory_bytes: 1000,
+            ..CacheConfig::default()
+        })
+        .await
+        .unwrap();
+        cache.cache_manifest_content("sha256:a", Bytes::from(vec![b'a'; 600])).await;
+        assert_eq!(cache.get_manifest_content("sha256:a").await.map(|m| m.len()), Some(600));
+        assert!(cache.get_manifest_content("sha256:b").await.is_none());
+
+        cache.cache_manifest("manifest:acme/web:latest", Bytes::from(vec![b'b'; 600])).await.unwrap();
+        let stats = cache.get_stats().await;
+        assert_eq!(stats.memory_cache.manifest_count, 1);
+        assert!(stats.memory_cache.manifest_bytes <= 1000);
+        let manifests = stats.categories.iter().find(|c| c.category == "manifest").unwrap();
+        assert_eq!((manifests.hits, manifests.misses, manifests.evictions), (1, 1, 1));
+    }
+
+    #[tokio::test]
+    async fn invalidations_from_other_replicas_evict_local_entries() {
+        let cache = RegistryCache::new(CacheConfig { redis_url: None, enable_redis: false, ..CacheConfig::default() })
+            .await
+            .unwrap();
+        cache.cache_tags("acme/web", vec!["latest".to_string()]).await.unwrap();
+        cache.cache_manifest("manifest:acme/web:latest", Bytes::from_static(b"{}")).await.unwrap();
+
+        let own = serde_json::json!({
+            "origin": cache.node_id.to_string(),
+            "invalidation": {"kind": "tags", "key": "acme/web"}
+        });
+        cache.apply_invalidation(&own.to_string()).await;
+        assert!(cache.memory_cache.read().await.tag_cache.contains_key("acme/web"));
+
+        let other = InvalidationMessage {
+            origin: "another-replica".to_string(),
+            invalidation: Invalidation::Tags("acme/web".to_string()),
+        };
+        cache.apply_invalidation(&serde_json::to_string(&other).unwrap()).await;
+        assert!(!cache.memory_cache.read().await.tag_cache.contains_key("acme/web"));
+
+        cache.apply_invalidation(r#"{"origin": "another-replica", "invalidation": {"kind": "manifest", "key": "manifest:acme/web:latest"}}"#).await;
+        assert!(cache.get_manifest("manifest:acme/web:latest").await.is_none());
+
+        cache.apply_invalidation("not json").await;
+    }
+}
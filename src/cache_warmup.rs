@@ -0,0 +1,112 @@
+// src/cache_warmup.rs - Loading popular content into the cache on startup
+//
+// A new replica starts with an empty memory cache, and after a Redis restart Redis is empty as
+// well, so right after a deploy every pull goes to the database and storage at once. With
+// `CACHE_WARMUP_ENABLED` the catalog, the tag lists of the most pulled repositories and the
+// manifests of the most pulled tags are loaded in the background as soon as the server starts,
+// most pulled first. Popularity comes from the pull totals kept by `crate::activity`.
+//
+// Manifests are loaded the way a pull loads them, so a pull arriving while its manifest is being
+// warmed waits for that load instead of running another.
+use std::time::Instant;
+
+use crate::cache::RegistryCache;
+use crate::config::settings::CacheSettings;
+use crate::handlers::docker_registry_v2::load_manifest;
+use crate::AppState;
+
+/// What a warm-up loaded
+#[derive(Debug, Default)]
+struct Warmed {
+    repositories: usize,
+    tag_lists: usize,
+    manifests: usize,
+}
+
+/// Warm the cache once in the background when `CACHE_WARMUP_ENABLED` is set
+pub fn spawn_cache_warmup(state: AppState) {
+    let Some(cache) = state.cache.clone() else {
+        return;
+    };
+    if !state.config.cache.warmup_enabled {
+        return;
+    }
+    tokio::spawn(async move {
+        let started = Instant::now();
+        match warm_up(&state, &cache, &state.config.cache).await {
+            Ok(warmed) => tracing::info!(
+                "Cache warmed in {:?}: catalog of {} repositories, {} tag lists, {} manifests",
+                started.elapsed(),
+                warmed.repositories,
+                warmed.tag_lists,
+                warmed.manifests
+            ),
+            Err(e) => tracing::warn!("Cache warm-up stopped: {}", e),
+        }
+    });
+}
+
+async fn warm_up(state: &AppState, cache: &RegistryCache, settings: &CacheSettings) -> anyhow::Result<Warmed> {
+    let mut warmed = Warmed::default();
+
+    let repositories = sqlx::query_scalar::<_, String>(
+        "SELECT o.name || '/' || r.name FROM repositories r
+         JOIN organizations o ON o.id = r.organization_id
+         ORDER BY o.name, r.name",
+    )
+    .fetch_all(&state.db_pool)
+    .await?;
+    warmed.repositories = repositories.len();
+    cache.cache_repositories(repositories).await?;
+
+    // Tag lists in the order `list_tags` returns them
+    let popular = sqlx::query_as::<_, (i64, String)>(
+        "SELECT r.id, o.name || '/' || r.name FROM repositories r
+         JOIN organizations o ON o.id = r.organization_id
+         WHERE r.pull_count > 0
+         ORDER BY r.pull_count DESC
+         LIMIT $1",
+    )
+    .bind(settings.warmup_repositories)
+    .fetch_all(&state.db_pool)
+    .await?;
+    for (repository_id, name) in popular {
+        let tags = sqlx::query_scalar::<_, String>("SELECT name FROM tags WHERE repository_id = $1 ORDER BY updated_at DESC")
+            .bind(repository_id)
+            .fetch_all(&state.db_pool)
+            .await?;
+        if !tags.is_empty() {
+            cache.cache_tags(&name, tags).await?;
+            warmed.tag_lists += 1;
+        }
+    }
+
+    // Clients pull the tag, then the digest it resolved to; both are cached
+    let popular = sqlx::query_as::<_, (String, String)>(
+        "SELECT o.name || '/' || r.name, t.name FROM tags t
+         JOIN repositories r ON r.id = t.repository_id
+         JOIN organizations o ON o.id = r.organization_id
+         WHERE t.pull_count > 0
+         ORDER BY t.pull_count DESC
+         LIMIT $1",
+    )
+    .bind(settings.warmup_manifests)
+    .fetch_all(&state.db_pool)
+    .await?;
+    for (name, tag) in popular {
+        let cache_key = format!("manifest:{}:{}", name, tag);
+        let loaded = cache
+            .load_manifest_once(&cache_key, || load_manifest(state, &name, &tag))
+            .await;
+        match loaded {
+            Ok(manifest) => {
+                let digest_key = format!("manifest:{}:{}", name, manifest.digest);
+                cache.cache_manifest(&digest_key, manifest.content).await?;
+                warmed.manifests += 1;
+            }
+            Err((status, _)) => tracing::debug!("Not warming manifest {}:{}: {}", name, tag, status),
+        }
+    }
+
+    Ok(warmed)
+}
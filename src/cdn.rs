@@ -0,0 +1,273 @@
+// src/cdn.rs - Purging a downstream CDN when registry content changes
+//
+// Follows the process's log stream like the webhook dispatcher: a tag moved by a push, a deleted
+// manifest or tag, a takedown or a transferred or renamed repository turns into the registry URLs
+// whose cached copies are now wrong. Paths are collected for a short window and purged together
+// through the configured provider, so a multi-arch push costs one CloudFront invalidation.
+use std::collections::BTreeSet;
+use std::time::Duration;
+
+use anyhow::{bail, Context, Result};
+use secrecy::ExposeSecret;
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::config::settings::CdnSettings;
+use crate::log_stream::{LogEvent, LogEventKind, LogFilter};
+use crate::AppState;
+
+/// How long changes are collected before a purge
+const BATCH_WINDOW: Duration = Duration::from_secs(2);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// A cached registry URL, relative to the CDN base URL
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum PurgePath {
+    Exact(String),
+    /// Everything under the path; only providers with wildcard purges act on it
+    Prefix(String),
+}
+
+/// Registry paths an audit event makes stale
+pub fn paths_for(event: &LogEvent) -> Vec<PurgePath> {
+    let Some(repository) = event.repository.as_deref() else {
+        return Vec::new();
+    };
+    let detail = event.detail.as_deref().unwrap_or_default();
+    let manifest = |reference: &str| PurgePath::Exact(format!("/v2/{}/manifests/{}", repository, reference));
+    let tags = PurgePath::Exact(format!("/v2/{}/tags/list", repository));
+
+    match event.action.as_str() {
+        // Pushes by digest add immutable content; only a tag can move
+        "manifest.push" => match detail.split_once(" -> ") {
+            Some((reference, _)) if !is_digest(reference) => vec![manifest(reference), tags],
+            _ => Vec::new(),
+        },
+        // Tags pointing at a deleted digest are unknown here, so every manifest URL goes
+        "manifest.delete" if is_digest(detail) => vec![
+            manifest(detail),
+            PurgePath::Prefix(format!("/v2/{}/manifests/", repository)),
+            tags,
+        ],
+        "tag.delete" if !detail.is_empty() => vec![manifest(detail), tags],
+        "content.takedown" => {
+            let digest = detail.split(' ').find_map(|field| field.strip_prefix("digest="));
+            match digest {
+                Some(digest) if is_digest(digest) => vec![
+                    manifest(digest),
+                    PurgePath::Exact(format!("/v2/{}/blobs/{}", repository, digest)),
+                ],
+                _ => vec![PurgePath::Prefix(format!("/v2/{}/", repository))],
+            }
+        }
+        "repository.transfer" | "repository.rename" => match detail.strip_prefix("from ") {
+            Some(old_name) => vec![PurgePath::Prefix(format!("/v2/{}/", old_name))],
+            None => Vec::new(),
+        },
+        _ => Vec::new(),
+    }
+}
+
+fn is_digest(reference: &str) -> bool {
+    reference.contains(':')
+}
+
+enum Provider {
+    CloudFront {
+        client: aws_sdk_cloudfront::Client,
+        distribution_id: String,
+    },
+    Fastly {
+        client: reqwest::Client,
+        api_url: String,
+        token: String,
+        soft: bool,
+    },
+}
+
+struct Purger {
+    provider: Provider,
+    /// Path of the CDN base URL, prepended to every registry path
+    base_path: String,
+    /// Base URL without its scheme, as Fastly names cached URLs
+    base_host_path: String,
+}
+
+impl Purger {
+    async fn from_settings(settings: &CdnSettings) -> Result<Option<Self>> {
+        let base = settings.base_url.as_deref().map(url::Url::parse).transpose().context("Invalid CDN_BASE_URL")?;
+        let base_path = base.as_ref().map(|url| url.path().trim_end_matches('/').to_string()).unwrap_or_default();
+        let base_host_path = base
+            .as_ref()
+            .map(|url| format!("{}{}", url.host_str().unwrap_or_default(), base_path))
+            .unwrap_or_default();
+
+        let provider = match settings.provider.as_deref() {
+            None => return Ok(None),
+            Some("cloudfront") => {
+                let config = aws_config::load_defaults(aws_config::BehaviorVersion::latest()).await;
+                Provider::CloudFront {
+                    client: aws_sdk_cloudfront::Client::new(&config),
+                    distribution_id: settings.cloudfront_distribution_id.clone().unwrap_or_default(),
+                }
+            }
+            Some("fastly") => Provider::Fastly {
+                client: reqwest::Client::builder()
+                    .timeout(REQUEST_TIMEOUT)
+                    .user_agent(concat!("aerugo-cdn/", env!("CARGO_PKG_VERSION")))
+                    .build()
+                    .expect("HTTP client configuration is static"),
+                api_url: settings.fastly_api_url.trim_end_matches('/').to_string(),
+                token: settings.fastly_api_token.as_ref().map(|t| t.expose_secret().clone()).unwrap_or_default(),
+                soft: settings.fastly_soft_purge,
+            },
+            Some(other) => bail!("Unknown CDN purge provider {}", other),
+        };
+        Ok(Some(Self { provider, base_path, base_host_path }))
+    }
+
+    async fn purge(&self, paths: &BTreeSet<PurgePath>) -> Result<()> {
+        match &self.provider {
+            Provider::CloudFront { client, distribution_id } => {
+                use aws_sdk_cloudfront::types::{InvalidationBatch, Paths};
+
+                let items: Vec<String> = paths
+                    .iter()
+                    .map(|path| match path {
+                        PurgePath::Exact(path) => format!("{}{}", self.base_path, path),
+                        PurgePath::Prefix(path) => format!("{}{}*", self.base_path, path),
+                    })
+                    .collect();
+                let batch = InvalidationBatch::builder()
+                    .caller_reference(uuid::Uuid::new_v4().to_string())
+                    .paths(Paths::builder().quantity(items.len() as i32).set_items(Some(items)).build()?)
+                    .build()?;
+                client
+                    .create_invalidation()
+                    .distribution_id(distribution_id)
+                    .invalidation_batch(batch)
+                    .send()
+                    .await
+                    .context("CloudFront invalidation failed")?;
+            }
+            Provider::Fastly { client, api_url, token, soft } => {
+                for path in paths {
+                    // Fastly purges single URLs; wildcards would need surrogate keys
+                    let PurgePath::Exact(path) = path else {
+                        tracing::debug!("Skipping Fastly purge of prefix {:?}", path);
+                        continue;
+                    };
+                    let mut request = client
+                        .post(format!("{}/purge/{}{}", api_url, self.base_host_path, path))
+                        .header("Fastly-Key", token);
+                    if *soft {
+                        request = request.header("Fastly-Soft-Purge", "1");
+                    }
+                    let response = request.send().await.context("Fastly purge failed")?;
+                    if !response.status().is_success() {
+                        bail!("Fastly purge of {} responded with {}", path, response.status());
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Purge the CDN configured with `CDN_PURGE_PROVIDER` as content changes
+pub fn spawn_cdn_purger(state: AppState) {
+    if state.config.cdn.provider.is_none() {
+        return;
+    }
+
+    tokio::spawn(async move {
+        let purger = match Purger::from_settings(&state.config.cdn).await {
+            Ok(Some(purger)) => purger,
+            Ok(None) => return,
+            Err(e) => {
+                tracing::error!("CDN purging disabled: {}", e);
+                return;
+            }
+        };
+
+        let filter = LogFilter { kind: Some(LogEventKind::Audit), ..Default::default() };
+        let (_, mut events) = state.log_stream.subscribe(&filter, 0);
+        loop {
+            let mut paths = BTreeSet::new();
+            match events.recv().await {
+                Ok(event) => paths.extend(paths_for(&event)),
+                Err(RecvError::Lagged(skipped)) => {
+                    tracing::warn!("CDN purger fell behind, {} events not purged", skipped);
+                    continue;
+                }
+                Err(RecvError::Closed) => break,
+            }
+            if paths.is_empty() {
+                continue;
+            }
+
+            let deadline = tokio::time::Instant::now() + BATCH_WINDOW;
+            while let Ok(received) = tokio::time::timeout_at(deadline, events.recv()).await {
+                match received {
+                    Ok(event) => paths.extend(paths_for(&event)),
+                    Err(RecvError::Lagged(skipped)) => tracing::warn!("CDN purger fell behind, {} events not purged", skipped),
+                    Err(RecvError::Closed) => break,
+                }
+            }
+
+            match purger.purge(&paths).await {
+                Ok(()) => tracing::info!("Purged {} CDN paths", paths.len()),
+                Err(e) => tracing::error!("Failed to purge {} CDN paths: {:#}", paths.len(), e),
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(action: &str, detail: &str) -> LogEvent {
+        LogEvent::audit(action, Some(1), Some("acme/web".to_string())).with_detail(detail)
+    }
+
+    #[test]
+    fn moved_tags_are_purged() {
+        assert_eq!(
+            paths_for(&event("manifest.push", "latest -> sha256:abc")),
+            vec![
+                PurgePath::Exact("/v2/acme/web/manifests/latest".to_string()),
+                PurgePath::Exact("/v2/acme/web/tags/list".to_string()),
+            ]
+        );
+        assert!(paths_for(&event("manifest.push", "sha256:abc -> sha256:abc")).is_empty());
+        assert!(paths_for(&event("organization.settings", "")).is_empty());
+    }
+
+    #[test]
+    fn deletions_and_takedowns_are_purged() {
+        let deleted = paths_for(&event("manifest.delete", "sha256:abc"));
+        assert!(deleted.contains(&PurgePath::Prefix("/v2/acme/web/manifests/".to_string())));
+        assert_eq!(
+            paths_for(&event("tag.delete", "latest")),
+            vec![
+                PurgePath::Exact("/v2/acme/web/manifests/latest".to_string()),
+                PurgePath::Exact("/v2/acme/web/tags/list".to_string()),
+            ]
+        );
+
+        let taken_down = paths_for(&event("content.takedown", "takedown=3 digest=sha256:abc notice=-"));
+        assert!(taken_down.contains(&PurgePath::Exact("/v2/acme/web/blobs/sha256:abc".to_string())));
+        assert_eq!(
+            paths_for(&event("content.takedown", "takedown=4 digest=* notice=-")),
+            vec![PurgePath::Prefix("/v2/acme/web/".to_string())]
+        );
+        assert_eq!(
+            paths_for(&event("repository.transfer", "from old/web")),
+            vec![PurgePath::Prefix("/v2/old/web/".to_string())]
+        );
+        assert_eq!(
+            paths_for(&event("repository.rename", "from acme/site")),
+            vec![PurgePath::Prefix("/v2/acme/site/".to_string())]
+        );
+    }
+}
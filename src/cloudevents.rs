@@ -0,0 +1,87 @@
+// src/cloudevents.rs - CloudEvents 1.0 envelopes for webhooks and the live event stream
+//
+// Events are sent in structured mode: one JSON object carrying the context attributes with the
+// native payload under `data`, so CloudEvents-native consumers such as Knative Eventing brokers
+// can route them by `type` and `source` without knowing the payload.
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+/// Content type of a structured-mode CloudEvent
+pub const CONTENT_TYPE: &str = "application/cloudevents+json";
+
+const SPEC_VERSION: &str = "1.0";
+/// Reverse-DNS prefix of every event `type`
+const TYPE_PREFIX: &str = "io.aerugo";
+
+#[derive(Debug, Serialize)]
+pub struct CloudEvent<T: Serialize> {
+    pub specversion: &'static str,
+    /// Unique per event from one source
+    pub id: String,
+    /// `/repositories/<namespace>/<repository>`, or `/organizations/<id>` for organization events
+    pub source: String,
+    /// `io.aerugo.` followed by the action, e.g. `io.aerugo.manifest.push`
+    #[serde(rename = "type")]
+    pub event_type: String,
+    pub time: DateTime<Utc>,
+    pub datacontenttype: &'static str,
+    pub data: T,
+}
+
+impl<T: Serialize> CloudEvent<T> {
+    pub fn new(
+        id: String,
+        action: &str,
+        time: DateTime<Utc>,
+        repository: Option<&str>,
+        organization_id: Option<i64>,
+        data: T,
+    ) -> Self {
+        Self {
+            specversion: SPEC_VERSION,
+            id,
+            source: source(repository, organization_id),
+            event_type: format!("{}.{}", TYPE_PREFIX, action),
+            time,
+            datacontenttype: "application/json",
+            data,
+        }
+    }
+}
+
+fn source(repository: Option<&str>, organization_id: Option<i64>) -> String {
+    match (repository, organization_id) {
+        (Some(repository), _) => format!("/repositories/{}", repository),
+        (None, Some(id)) => format!("/organizations/{}", id),
+        (None, None) => "/".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn envelopes_carry_the_required_attributes() {
+        let event = CloudEvent::new(
+            "d4a2".to_string(),
+            "manifest.push",
+            Utc::now(),
+            Some("acme/web"),
+            Some(3),
+            serde_json::json!({ "detail": "latest -> sha256:abc" }),
+        );
+        let json = serde_json::to_value(&event).unwrap();
+        assert_eq!(json["specversion"], "1.0");
+        assert_eq!(json["type"], "io.aerugo.manifest.push");
+        assert_eq!(json["source"], "/repositories/acme/web");
+        assert_eq!(json["id"], "d4a2");
+        assert_eq!(json["data"]["detail"], "latest -> sha256:abc");
+    }
+
+    #[test]
+    fn organization_events_use_the_organization_as_source() {
+        assert_eq!(source(None, Some(3)), "/organizations/3");
+        assert_eq!(source(None, None), "/");
+    }
+}
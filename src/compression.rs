@@ -0,0 +1,84 @@
+// src/compression.rs - Compressed JSON responses and request bodies
+//
+// The catalog, tag lists and the `/api/v1` API answer in JSON, which for a large registry runs to
+// megabytes that shrink tenfold with gzip or zstd. Those responses are compressed for clients
+// sending `Accept-Encoding`; blobs and manifests never are, as blobs are mostly compressed
+// layers already and clients check both against their digest and `Content-Length`. The `/api/v1`
+// API also accepts gzip and zstd request bodies; body size limits apply to the decompressed body.
+use axum::{
+    body::HttpBody,
+    http::{header, HeaderMap, Response},
+};
+use tower_http::compression::{
+    predicate::{Predicate, SizeAbove},
+    CompressionLayer,
+};
+use tower_http::decompression::RequestDecompressionLayer;
+
+use crate::config::settings::CompressionSettings;
+
+/// Compresses JSON responses that are not known to be smaller than the configured minimum
+#[derive(Debug, Clone, Copy)]
+pub struct JsonResponses {
+    min_bytes: u16,
+}
+
+impl Predicate for JsonResponses {
+    fn should_compress<B>(&self, response: &Response<B>) -> bool
+    where
+        B: HttpBody,
+    {
+        is_json(response.headers()) && SizeAbove::new(self.min_bytes).should_compress(response)
+    }
+}
+
+/// `application/json` or a `+json` type such as `application/problem+json`
+fn is_json(headers: &HeaderMap) -> bool {
+    let Some(content_type) = headers.get(header::CONTENT_TYPE).and_then(|v| v.to_str().ok()) else {
+        return false;
+    };
+    let essence = content_type.split(';').next().unwrap_or_default().trim().to_ascii_lowercase();
+    essence == "application/json" || (essence.starts_with("application/") && essence.ends_with("+json"))
+}
+
+/// Layer compressing JSON responses with the enabled encodings
+pub fn response_layer(settings: &CompressionSettings) -> CompressionLayer<JsonResponses> {
+    CompressionLayer::new()
+        .gzip(settings.gzip)
+        .zstd(settings.zstd)
+        .compress_when(JsonResponses { min_bytes: settings.min_bytes })
+}
+
+/// Layer decompressing gzip and zstd request bodies. Other encodings get 415; with
+/// decompression disabled every body is passed on as it arrived.
+pub fn request_layer(settings: &CompressionSettings) -> RequestDecompressionLayer {
+    RequestDecompressionLayer::new()
+        .gzip(settings.decompress_requests)
+        .zstd(settings.decompress_requests)
+        .pass_through_unaccepted(!settings.decompress_requests)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+
+    fn response(content_type: &str, len: usize) -> Response<Body> {
+        Response::builder()
+            .header(header::CONTENT_TYPE, content_type)
+            .body(Body::from(vec![b' '; len]))
+            .unwrap()
+    }
+
+    #[test]
+    fn only_json_above_the_minimum_is_compressed() {
+        let predicate = JsonResponses { min_bytes: 1024 };
+        assert!(predicate.should_compress(&response("application/json", 4096)));
+        assert!(predicate.should_compress(&response("application/json; charset=utf-8", 4096)));
+        assert!(predicate.should_compress(&response("application/problem+json", 4096)));
+        assert!(!predicate.should_compress(&response("application/json", 100)));
+        assert!(!predicate.should_compress(&response("application/octet-stream", 4096)));
+        assert!(!predicate.should_compress(&response("application/vnd.oci.image.layer.v1.tar+gzip", 4096)));
+        assert!(!predicate.should_compress(&response("text/event-stream", 4096)));
+    }
+}
@@ -1,5 +1,7 @@
 pub mod settings;
 pub mod production;
+pub mod reload;
+pub mod sources;
 
 pub use settings::Settings;
-pub use production::{ProductionSettings, CacheConfig, DatabasePoolConfig, PerformanceConfig};
+pub use production::{ProductionSettings, CacheConfig, PerformanceConfig};
@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 // use std::time::Duration; // Removed unused import
 
 /// Production Redis caching configuration for high-performance registry
@@ -72,33 +73,6 @@ impl Default for CacheConfig {
     }
 }
 
-/// Production database connection pool settings
-#[derive(Debug, Clone, Deserialize, Serialize)]
-pub struct DatabasePoolConfig {
-    /// Maximum connections in pool
-    pub max_connections: u32,
-    /// Minimum idle connections
-    pub min_connections: u32,
-    /// Connection timeout (seconds)
-    pub connect_timeout: u64,
-    /// Maximum lifetime of connection (seconds)
-    pub max_lifetime: u64,
-    /// Idle timeout (seconds)
-    pub idle_timeout: u64,
-}
-
-impl Default for DatabasePoolConfig {
-    fn default() -> Self {
-        Self {
-            max_connections: 100,
-            min_connections: 10,
-            connect_timeout: 10,
-            max_lifetime: 3600,
-            idle_timeout: 600,
-        }
-    }
-}
-
 /// Production performance configuration
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct PerformanceConfig {
@@ -134,8 +108,6 @@ impl Default for PerformanceConfig {
 pub struct ProductionSettings {
     /// Cache configuration
     pub cache: CacheConfig,
-    /// Database pool configuration  
-    pub database_pool: DatabasePoolConfig,
     /// Performance configuration
     pub performance: PerformanceConfig,
     /// Health check interval (seconds)
@@ -146,7 +118,6 @@ impl Default for ProductionSettings {
     fn default() -> Self {
         Self {
             cache: CacheConfig::default(),
-            database_pool: DatabasePoolConfig::default(),
             performance: PerformanceConfig::default(),
             health_check_interval: 30,
         }
@@ -154,16 +125,21 @@ impl Default for ProductionSettings {
 }
 
 impl ProductionSettings {
-    /// Load production settings from environment or file
+    /// Load production settings from the environment, `.env` and the `AERUGO_CONFIG` file
     pub fn load() -> anyhow::Result<Self> {
-        // Try loading from environment variables first
-        if let Ok(config) = envy::from_env::<ProductionSettings>() {
-            return Ok(config);
+        Ok(Self::from_variables(&super::sources::layered_variables()?))
+    }
+
+    /// Build production settings from already layered variables
+    pub fn from_variables(variables: &BTreeMap<String, String>) -> Self {
+        // Try loading from the variables first
+        if let Ok(config) = envy::from_iter::<_, ProductionSettings>(variables.clone()) {
+            return config;
         }
 
         // Fallback to default configuration
         tracing::warn!("Using default production settings. Consider setting environment variables for production deployment.");
-        Ok(ProductionSettings::default())
+        ProductionSettings::default()
     }
 
     /// Create Redis connection URL with authentication if available
@@ -188,8 +164,8 @@ impl ProductionSettings {
             anyhow::bail!("Redis max_connections must be greater than 0");
         }
 
-        if self.database_pool.max_connections == 0 {
-            anyhow::bail!("Database max_connections must be greater than 0");
+        if self.cache.redis.connection_timeout == 0 {
+            anyhow::bail!("Redis connection_timeout must be greater than 0");
         }
 
         if self.performance.streaming_chunk_size == 0 {
@@ -209,9 +185,6 @@ pub mod env_keys {
     pub const CACHE_METRICS_ENABLED: &str = "CACHE_METRICS_ENABLED";
     pub const MEMORY_CACHE_MAX_ENTRIES: &str = "MEMORY_CACHE_MAX_ENTRIES";
     
-    pub const DB_POOL_MAX_CONNECTIONS: &str = "DATABASE_MAX_CONNECTIONS";
-    pub const DB_POOL_MIN_CONNECTIONS: &str = "DATABASE_MIN_CONNECTIONS";
-    
     pub const PERFORMANCE_REQUEST_TIMEOUT: &str = "REQUEST_TIMEOUT";
     pub const PERFORMANCE_MAX_CONCURRENT: &str = "MAX_CONCURRENT_REQUESTS";
     pub const PERFORMANCE_COMPRESSION: &str = "COMPRESSION_ENABLED";
@@ -227,7 +200,6 @@ mod tests {
         let settings = ProductionSettings::default();
         assert!(settings.validate().is_ok());
         assert_eq!(settings.cache.redis.max_connections, 50);
-        assert_eq!(settings.database_pool.max_connections, 100);
         assert!(settings.performance.compression_enabled);
     }
 
@@ -0,0 +1,210 @@
+// src/config/reload.rs - Reloading part of the configuration without a restart
+//
+// On SIGHUP or `POST /api/v1/admin/config/reload`, `.env`, the `AERUGO_CONFIG` file and
+// `NOTIFICATIONS_CONFIG_FILE` are read again and the settings that are safe to change while
+// requests are in flight are applied: the log level, the cache TTLs, the rate limits, the
+// notification endpoints and the configured feature flags. Settings that fail to load or validate are rejected as a whole and the
+// running configuration is kept. When applying a change fails, the changes already applied are
+// undone. Everything else, such as listen addresses, the database or storage, still needs a
+// restart. The files are read on a blocking thread, so a reload never stalls the async runtime.
+use std::sync::{Arc, RwLock};
+
+use anyhow::Result;
+use serde::Serialize;
+use tokio::sync::Mutex;
+use utoipa::ToSchema;
+
+use crate::cache::CacheTtls;
+use crate::config::settings::{FeatureSettings, NotificationSettings, RateLimitSettings, ServerSettings, Settings};
+use crate::config::sources::layered_variables;
+use crate::log_stream::LogEvent;
+use crate::AppState;
+
+/// The settings a reload can change. Of `server` only the log level is applied.
+#[derive(Debug, Clone)]
+struct Reloadable {
+    server: ServerSettings,
+    cache_ttls: CacheTtls,
+    rate_limit: RateLimitSettings,
+    notifications: NotificationSettings,
+    features: FeatureSettings,
+}
+
+impl Reloadable {
+    fn of(settings: &Settings) -> Self {
+        Self {
+            server: settings.server.clone(),
+            cache_ttls: CacheTtls::from(&settings.cache.cache_config()),
+            rate_limit: settings.rate_limit.clone(),
+            notifications: settings.notifications.clone(),
+            features: settings.features.clone(),
+        }
+    }
+
+    /// Names of the settings that differ in `other`
+    fn changes(&self, other: &Self) -> Vec<&'static str> {
+        let mut changed = Vec::new();
+        if self.server.log_level != other.server.log_level {
+            changed.push("log_level");
+        }
+        if self.cache_ttls != other.cache_ttls {
+            changed.push("cache_ttls");
+        }
+        if self.rate_limit != other.rate_limit {
+            changed.push("rate_limit");
+        }
+        if self.notifications != other.notifications {
+            changed.push("notifications");
+        }
+        if self.features != other.features {
+            changed.push("features");
+        }
+        changed
+    }
+}
+
+/// Outcome of a configuration reload
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ConfigReload {
+    /// Settings that changed: `log_level`, `cache_ttls`, `rate_limit`, `notifications` or `features`
+    pub changed: Vec<String>,
+}
+
+/// The current values of the reloadable settings. Code reading them goes through here rather
+/// than `AppState::config`, which holds the values from startup.
+pub struct RuntimeConfig {
+    current: RwLock<Arc<Reloadable>>,
+    /// Held while reloading, so two reloads do not interleave
+    reloading: Mutex<()>,
+}
+
+impl RuntimeConfig {
+    pub fn new(settings: &Settings) -> Self {
+        Self { current: RwLock::new(Arc::new(Reloadable::of(settings))), reloading: Mutex::new(()) }
+    }
+
+    fn current(&self) -> Arc<Reloadable> {
+        self.current.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    pub fn rate_limit(&self) -> RateLimitSettings {
+        self.current().rate_limit.clone()
+    }
+
+    /// Read the configuration again and apply the reloadable settings that changed
+    pub async fn reload(&self, state: &AppState) -> Result<ConfigReload> {
+        let _reloading = self.reloading.lock().await;
+        let settings = tokio::task::spawn_blocking(|| Settings::from_variables(&layered_variables()?)).await??;
+        let previous = self.current();
+        let next = Reloadable::of(&settings);
+
+        if let Err(e) = apply(state, &previous, &next) {
+            if let Err(rollback) = apply(state, &next, &previous) {
+                tracing::error!("Failed to restore the previous configuration: {:#}", rollback);
+            }
+            return Err(e);
+        }
+        let changed = previous.changes(&next);
+        *self.current.write().unwrap_or_else(|e| e.into_inner()) = Arc::new(next);
+        Ok(ConfigReload { changed: changed.into_iter().map(str::to_string).collect() })
+    }
+}
+
+/// Apply the settings that differ between `from` and `to`. Rate limits need no applying; they
+/// are read from `RuntimeConfig` on every request.
+fn apply(state: &AppState, from: &Reloadable, to: &Reloadable) -> Result<()> {
+    if from.cache_ttls != to.cache_ttls {
+        if let Some(cache) = &state.cache {
+            cache.set_ttls(to.cache_ttls);
+        }
+    }
+    if from.notifications != to.notifications {
+        state.notifier.replace_endpoints(&to.notifications);
+    }
+    if from.features != to.features {
+        state.features.set_configured(&to.features);
+    }
+    if from.server.log_level != to.server.log_level {
+        crate::logging::reload_filter(&to.server)?;
+    }
+    Ok(())
+}
+
+/// Reload the configuration whenever the process receives SIGHUP
+#[cfg(unix)]
+pub fn spawn_sighup_listener(state: AppState) {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut hangups = match signal(SignalKind::hangup()) {
+        Ok(hangups) => hangups,
+        Err(e) => {
+            tracing::warn!("Configuration reload on SIGHUP disabled: {}", e);
+            return;
+        }
+    };
+    tokio::spawn(async move {
+        while hangups.recv().await.is_some() {
+            match state.runtime_config.reload(&state).await {
+                Ok(reload) => {
+                    tracing::info!("Configuration reloaded on SIGHUP, changed: [{}]", reload.changed.join(", "));
+                    state.log_stream.publish(LogEvent::audit("config.reload", None, None));
+                }
+                Err(e) => tracing::error!("Configuration not reloaded, keeping the current one: {:#}", e),
+            }
+        }
+    });
+}
+
+#[cfg(not(unix))]
+pub fn spawn_sighup_listener(_state: AppState) {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn reloadable() -> Reloadable {
+        Reloadable {
+            server: ServerSettings {
+                bind_address: "127.0.0.1:3000".to_string(),
+                port: 3000,
+                api_prefix: "/api/v1".to_string(),
+                log_level: "info".to_string(),
+                log_format: "text".to_string(),
+                listeners: Vec::new(),
+                unix_socket_mode: 0o660,
+            },
+            cache_ttls: CacheTtls::from(&crate::cache::CacheConfig::default()),
+            rate_limit: RateLimitSettings {
+                enabled: true,
+                window_seconds: 60,
+                pull_limit: 1000,
+                push_limit: 100,
+                auth_limit: 20,
+                api_limit: 300,
+            },
+            notifications: NotificationSettings::default(),
+            features: FeatureSettings {
+                allow_delete: true,
+                allow_anonymous_pull: false,
+                open_registration: true,
+                readonly_mode: false,
+                refresh_interval_seconds: 30,
+            },
+        }
+    }
+
+    #[test]
+    fn reports_the_settings_that_changed() {
+        let current = reloadable();
+        assert!(current.changes(&reloadable()).is_empty());
+
+        let mut next = reloadable();
+        next.server.log_level = "debug".to_string();
+        next.cache_ttls.tag_ttl = Duration::from_secs(30);
+        next.rate_limit.pull_limit = 2000;
+        // Settings a reload does not apply are not compared
+        next.server.bind_address = "0.0.0.0:8080".to_string();
+        assert_eq!(current.changes(&next), vec!["log_level", "cache_ttls", "rate_limit"]);
+    }
+}
@@ -1,7 +1,9 @@
 use anyhow::{Context, Result};
 use secrecy::{ExposeSecret, Secret};
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::BTreeMap;
 use std::net::SocketAddr;
+use std::time::Duration;
 use url::Url;
 use validator::Validate;
 
@@ -10,6 +12,8 @@ pub struct Settings {
     #[validate]
     pub server: ServerSettings,
     #[validate]
+    pub tls: TlsSettings,
+    #[validate]
     pub database: DatabaseSettings,
     #[validate]
     pub storage: StorageSettings,
@@ -19,16 +23,91 @@ pub struct Settings {
     pub auth: AuthSettings,
     #[validate]
     pub email: EmailSettings,
+    #[validate]
+    pub webhooks: WebhookSettings,
+    #[validate]
+    pub transcode: TranscodeSettings,
+    #[validate]
+    pub ip_access: IpAccessSettings,
+    #[validate]
+    pub log_tail: LogTailSettings,
+    #[validate]
+    pub rate_limit: RateLimitSettings,
+    #[validate]
+    pub concurrency: ConcurrencySettings,
+    #[validate]
+    pub health: HealthSettings,
+    #[validate]
+    pub login_protection: LoginProtectionSettings,
+    #[validate]
+    pub uploads: UploadSettings,
+    #[validate]
+    pub compression: CompressionSettings,
+    #[validate]
+    pub standby: StandbySettings,
+    #[validate]
+    pub retention: RetentionSettings,
+    #[validate]
+    pub invitations: InvitationSettings,
+    #[validate]
+    pub federation: FederationSettings,
+    #[validate]
+    pub gc: GcSettings,
+    #[validate]
+    pub redirects: RedirectSettings,
+    #[validate]
+    pub bandwidth: BandwidthSettings,
+    #[validate]
+    pub secrets: SecretsSettings,
+    #[validate]
+    pub tag_cleanup: TagCleanupSettings,
+    #[validate]
+    pub bootstrap: BootstrapSettings,
+    #[validate]
+    pub naming: NamingSettings,
+    #[validate]
+    pub cdn: CdnSettings,
+    #[validate]
+    pub activity: ActivitySettings,
+    #[validate]
+    pub notifications: NotificationSettings,
+    #[validate]
+    pub event_stream: EventStreamSettings,
+    #[validate]
+    pub scanning: ScanSettings,
+    #[validate]
+    pub jobs: JobSettings,
+    #[validate]
+    pub signatures: SignatureSettings,
+    #[validate]
+    pub secret_scan: SecretScanSettings,
+    #[validate]
+    pub base_images: BaseImageSettings,
+    #[validate]
+    pub features: FeatureSettings,
+    #[validate]
+    pub error_reporting: ErrorReportingSettings,
+    #[validate]
+    pub startup: StartupSettings,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, Validate)]
+#[validate(schema(function = "validate_server_settings"))]
 pub struct ServerSettings {
     #[validate(custom = "validate_socket_addr")]
     pub bind_address: String,
     #[validate(range(min = 1024, max = 65535))]
     pub port: u16,
     pub api_prefix: String,
+    /// Level or `tracing` filter directives; `RUST_LOG` takes precedence when set
     pub log_level: String,
+    /// `text`, `compact` or `json`
+    #[validate(custom = "validate_log_format")]
+    pub log_format: String,
+    /// Addresses to accept connections on; just `bind_address` unless `LISTENERS` is set
+    pub listeners: Vec<ListenerSettings>,
+    /// Permissions of the Unix sockets listened on
+    pub unix_socket_mode: u32,
 }
 
 impl ServerSettings {
@@ -38,7 +117,63 @@ impl ServerSettings {
     }
 }
 
+/// One address the server accepts connections on, and which routes it serves there
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ListenerSettings {
+    /// `host:port`, or `unix:` followed by the path of a socket
+    pub address: String,
+    /// `all`, `registry` for the `/v2` API only, or `management` for everything but `/v2`
+    pub routes: String,
+}
+
+impl ListenerSettings {
+    /// A `LISTENERS` entry: an address, optionally followed by `=` and the routes
+    pub fn parse(entry: &str) -> Self {
+        match entry.rsplit_once('=') {
+            Some((address, routes)) => Self { address: address.trim().to_string(), routes: routes.trim().to_lowercase() },
+            None => Self { address: entry.trim().to_string(), routes: "all".to_string() },
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Validate)]
+#[validate(schema(function = "validate_tls_settings"))]
+pub struct TlsSettings {
+    /// PEM certificate chain to serve; TLS is off unless this and `key_path`, or ACME, are set
+    pub cert_path: Option<String>,
+    /// PEM private key of the certificate
+    pub key_path: Option<String>,
+    /// How often the certificate files are checked for changes
+    #[validate(range(min = 1, max = 86400))]
+    pub reload_interval_seconds: u64,
+    /// Domains to obtain a certificate for from Let's Encrypt instead of using files
+    pub acme_domains: Vec<String>,
+    /// Contact address registered with the ACME account
+    pub acme_email: Option<String>,
+    /// Where the ACME account and certificates are kept across restarts
+    pub acme_cache_dir: String,
+    /// Use Let's Encrypt's production directory rather than its staging one
+    pub acme_production: bool,
+}
+
+impl TlsSettings {
+    pub fn enabled(&self) -> bool {
+        self.cert_path.is_some() || !self.acme_domains.is_empty()
+    }
+}
+
+fn validate_tls_settings(tls: &TlsSettings) -> Result<(), validator::ValidationError> {
+    if tls.cert_path.is_some() != tls.key_path.is_some() {
+        return Err(validator::ValidationError::new("tls_cert_without_key"));
+    }
+    if tls.cert_path.is_some() && !tls.acme_domains.is_empty() {
+        return Err(validator::ValidationError::new("tls_files_and_acme"));
+    }
+    Ok(())
+}
+
 #[derive(Debug, Deserialize, Clone, Validate)]
+#[validate(schema(function = "validate_database_settings"))]
 pub struct DatabaseSettings {
     pub host: String,
     #[validate(range(min = 1024, max = 65535))]
@@ -47,8 +182,30 @@ pub struct DatabaseSettings {
     pub password: Secret<String>,
     pub database_name: String,
     pub require_ssl: bool,
+    /// Connections kept open even when idle
     pub min_connections: u32,
+    /// Connections this replica may hold; across replicas they must fit Postgres' `max_connections`
+    #[validate(range(min = 1))]
     pub max_connections: u32,
+    /// How long a query waits for a free connection before failing
+    #[validate(range(min = 1))]
+    pub acquire_timeout_seconds: u64,
+    /// Idle connections above `min_connections` are closed after this long; 0 keeps them
+    pub idle_timeout_seconds: u64,
+    /// Connections are replaced after this long; 0 keeps them indefinitely
+    pub max_lifetime_seconds: u64,
+    /// Postgres `statement_timeout` for every connection; 0 leaves the server's default
+    pub statement_timeout_ms: u64,
+    /// Apply pending migrations at startup; when disabled they must be applied with `aerugo migrate`
+    pub auto_migrate: bool,
+    /// Postgres replica that read-only queries are sent to; all queries go to the primary when unset
+    #[validate(custom = "validate_database_read_url")]
+    pub read_url: Option<Secret<String>>,
+    /// Reads go back to the primary while the replica trails it by more than this
+    pub read_max_lag_seconds: u64,
+    /// How often the replica's availability and lag are checked
+    #[validate(range(min = 1, max = 3600))]
+    pub read_check_interval_seconds: u64,
 }
 
 impl DatabaseSettings {
@@ -73,6 +230,12 @@ pub struct StorageSettings {
     pub access_key_id: Secret<String>,
     pub secret_access_key: Secret<String>,
     pub use_path_style: bool,
+    /// Keep pushed manifest bytes in the `manifest_contents` table so pulls keep working
+    /// while object storage is unavailable
+    pub manifests_in_database: bool,
+    /// Largest manifest accepted on push
+    #[validate(range(min = 4096, max = 67108864))]
+    pub max_manifest_bytes: usize,
 }
 
 impl StorageSettings {
@@ -81,11 +244,74 @@ impl StorageSettings {
     }
 }
 
+impl CacheSettings {
+    /// Configuration of the registry cache, using Redis and memory
+    pub fn cache_config(&self) -> crate::cache::CacheConfig {
+        crate::cache::CacheConfig {
+            redis_url: Some(self.redis_url.clone()),
+            manifest_ttl: Duration::from_secs(self.manifest_ttl_seconds),
+            blob_metadata_ttl: Duration::from_secs(self.blob_metadata_ttl_seconds),
+            repository_ttl: Duration::from_secs(self.repository_ttl_seconds),
+            tag_ttl: Duration::from_secs(self.tag_ttl_seconds),
+            auth_token_ttl: Duration::from_secs(self.auth_token_ttl_seconds),
+            permission_ttl: Duration::from_secs(self.permission_ttl_seconds),
+            session_ttl: Duration::from_secs(self.session_ttl_seconds),
+            max_memory_entries: self.max_memory_entries,
+            manifest_memory_bytes: self.manifest_memory_mb * 1024 * 1024,
+            enable_redis: true,
+            enable_memory: true,
+            redis_pool_size: self.pool_size,
+            redis_connect_timeout: Duration::from_millis(self.connect_timeout_ms),
+            redis_command_timeout: Duration::from_millis(self.command_timeout_ms),
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, Validate)]
 pub struct CacheSettings {
     pub redis_url: String,
+    #[validate(range(min = 1, max = 1000))]
     pub pool_size: u32,
+    /// Default TTL, used for manifests and, doubled, for blob metadata unless set separately
     pub ttl_seconds: u64,
+    #[validate(range(min = 1))]
+    pub manifest_ttl_seconds: u64,
+    #[validate(range(min = 1))]
+    pub blob_metadata_ttl_seconds: u64,
+    /// TTL of repository lists
+    #[validate(range(min = 1))]
+    pub repository_ttl_seconds: u64,
+    /// TTL of tag lists
+    #[validate(range(min = 1))]
+    pub tag_ttl_seconds: u64,
+    /// TTL of validated access tokens
+    #[validate(range(min = 1))]
+    pub auth_token_ttl_seconds: u64,
+    /// TTL of repository permission decisions
+    #[validate(range(min = 1))]
+    pub permission_ttl_seconds: u64,
+    #[validate(range(min = 1))]
+    pub session_ttl_seconds: u64,
+    /// Most blob metadata, repository and tag list entries kept in memory
+    #[validate(range(min = 1))]
+    pub max_memory_entries: usize,
+    /// How long a request waits for a pooled Redis connection, including connecting
+    #[validate(range(min = 10, max = 60000))]
+    pub connect_timeout_ms: u64,
+    /// How long a Redis command may take before the request falls back to the memory cache
+    #[validate(range(min = 10, max = 60000))]
+    pub command_timeout_ms: u64,
+    /// Most megabytes of manifests kept in memory
+    #[validate(range(min = 1))]
+    pub manifest_memory_mb: u64,
+    /// Load the catalog, popular tag lists and manifests into the cache on startup
+    pub warmup_enabled: bool,
+    /// Most pulled repositories whose tag lists are warmed
+    #[validate(range(min = 0, max = 10000))]
+    pub warmup_repositories: i64,
+    /// Most pulled tags whose manifests are warmed
+    #[validate(range(min = 0, max = 100000))]
+    pub warmup_manifests: i64,
 }
 
 #[derive(Debug, Deserialize, Clone, Validate)]
@@ -94,29 +320,132 @@ pub struct AuthSettings {
     #[validate(range(min = 300))] // Minimum 5 minutes
     pub jwt_expiration_seconds: u64,
     pub refresh_token_expiration_seconds: u64,
+    /// Argon2 memory cost in KiB for new password hashes
+    #[validate(range(min = 8))]
+    pub argon2_memory_kib: u32,
+    /// Argon2 iteration count for new password hashes
+    #[validate(range(min = 1))]
+    pub argon2_iterations: u32,
+    /// Argon2 degree of parallelism for new password hashes
+    #[validate(range(min = 1, max = 255))]
+    pub argon2_parallelism: u32,
+    /// Lifetime of a new API key when the request does not choose one
+    #[validate(range(min = 1))]
+    pub api_key_default_expiration_days: i64,
+    /// Longest lifetime a caller may choose for an API key
+    #[validate(range(min = 1))]
+    pub api_key_max_expiration_days: i64,
+    /// Usernames treated as registry administrators in addition to users flagged `is_admin`
+    pub admin_usernames: Vec<String>,
+}
+
+/// Settings that failed validation, each named by the variable that sets it
+#[derive(Debug, thiserror::Error)]
+#[error("{}", .problems.join("; "))]
+pub struct InvalidSettings {
+    /// One entry per failed check, e.g. `RATE_LIMIT_PULL (rate_limit.pull_limit): range (min: 1, value: 0)`
+    pub problems: Vec<String>,
 }
 
 impl Settings {
+    /// Load the settings from the process environment, `.env` and the `AERUGO_CONFIG` file
     pub fn load() -> Result<Self> {
-        // Load .env file if it exists
-        dotenv::dotenv().ok();
-
-        // Create settings from environment variables directly
-        // Log that we're using environment variables only
-        eprintln!("Loading configuration from environment variables and .env file");
-        eprintln!("LISTEN_ADDRESS: {:?}", std::env::var("LISTEN_ADDRESS"));
-        eprintln!("DATABASE_URL: {:?}", std::env::var("DATABASE_URL").map(|_| "[HIDDEN]"));
+        Self::from_variables(&super::sources::layered_variables()?)
+    }
+
+    /// Build the settings from already layered variables, see `sources::layered_variables`
+    pub fn from_variables(variables: &BTreeMap<String, String>) -> Result<Self> {
+        let var = |key: &str| variables.get(key).cloned().ok_or(std::env::VarError::NotPresent);
+
+        eprintln!("Loading configuration from environment variables, .env and AERUGO_CONFIG");
+        eprintln!("LISTEN_ADDRESS: {:?}", var("LISTEN_ADDRESS"));
+        eprintln!("DATABASE_URL: {:?}", var("DATABASE_URL").map(|_| "[HIDDEN]"));
         
+        let cache_ttl_seconds: u64 = var("REDIS_TTL_SECONDS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(3600);
+
+        let bind_address = var("LISTEN_ADDRESS").unwrap_or_else(|_| "127.0.0.1:3000".to_string());
+
         let settings = Settings {
             server: ServerSettings {
-                bind_address: std::env::var("LISTEN_ADDRESS").unwrap_or_else(|_| "127.0.0.1:3000".to_string()),
+                listeners: match var("LISTENERS").ok().filter(|s| !s.trim().is_empty()) {
+                    Some(list) => list.split(',').filter(|s| !s.trim().is_empty()).map(ListenerSettings::parse).collect(),
+                    None => vec![ListenerSettings { address: bind_address.clone(), routes: "all".to_string() }],
+                },
+                unix_socket_mode: var("UNIX_SOCKET_MODE")
+                    .ok()
+                    .and_then(|s| u32::from_str_radix(&s, 8).ok())
+                    .unwrap_or(0o660),
+                bind_address,
                 port: 3000, // Port is now parsed from LISTEN_ADDRESS
-                api_prefix: std::env::var("API_PREFIX").unwrap_or_else(|_| "/api/v1".to_string()),
-                log_level: std::env::var("LOG_LEVEL").unwrap_or_else(|_| "debug".to_string()),
+                api_prefix: var("API_PREFIX").unwrap_or_else(|_| "/api/v1".to_string()),
+                log_level: var("LOG_LEVEL").unwrap_or_else(|_| "debug".to_string()),
+                log_format: var("LOG_FORMAT")
+                    .map(|s| s.to_lowercase())
+                    .unwrap_or_else(|_| "text".to_string()),
+            },
+            tls: TlsSettings {
+                cert_path: var("TLS_CERT_PATH").ok().filter(|s| !s.is_empty()),
+                key_path: var("TLS_KEY_PATH").ok().filter(|s| !s.is_empty()),
+                reload_interval_seconds: var("TLS_RELOAD_INTERVAL_SECONDS")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(60),
+                acme_domains: var("TLS_ACME_DOMAINS")
+                    .map(|s| {
+                        s.split(',')
+                            .map(|d| d.trim().to_string())
+                            .filter(|d| !d.is_empty())
+                            .collect()
+                    })
+                    .unwrap_or_default(),
+                acme_email: var("TLS_ACME_EMAIL").ok().filter(|s| !s.is_empty()),
+                acme_cache_dir: var("TLS_ACME_CACHE_DIR").unwrap_or_else(|_| "./acme-cache".to_string()),
+                acme_production: var("TLS_ACME_PRODUCTION")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(false),
             },
             database: {
+                // Pool tuning is the same however the connection is given
+                let min_connections = var("DATABASE_MIN_CONNECTIONS")
+                    .ok()
+                    .and_then(|c| c.parse().ok())
+                    .unwrap_or(5);
+                let max_connections = var("DATABASE_MAX_CONNECTIONS")
+                    .ok()
+                    .and_then(|c| c.parse().ok())
+                    .unwrap_or(20);
+                let acquire_timeout_seconds = var("DATABASE_ACQUIRE_TIMEOUT_SECONDS")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(30);
+                let idle_timeout_seconds = var("DATABASE_IDLE_TIMEOUT_SECONDS")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(60);
+                let max_lifetime_seconds = var("DATABASE_MAX_LIFETIME_SECONDS")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(3600);
+                let statement_timeout_ms = var("DATABASE_STATEMENT_TIMEOUT_MS")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(0);
+                let read_url = var("DATABASE_READ_URL").ok().filter(|s| !s.is_empty()).map(Secret::new);
+                let read_max_lag_seconds = var("DATABASE_READ_MAX_LAG_SECONDS")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(10);
+                let read_check_interval_seconds = var("DATABASE_READ_CHECK_INTERVAL_SECONDS")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(5);
+
                 // If DATABASE_URL is set, parse it to extract components
-                if let Ok(database_url) = std::env::var("DATABASE_URL") {
+                if let Ok(database_url) = var("DATABASE_URL") {
                     if let Ok(db_url) = url::Url::parse(&database_url) {
                         let host = db_url.host_str().unwrap_or("localhost").to_string();
                         let port = db_url.port().unwrap_or(5432);
@@ -130,122 +459,725 @@ impl Settings {
                             username,
                             password,
                             database_name,
-                            require_ssl: std::env::var("DATABASE_REQUIRE_SSL")
+                            require_ssl: var("DATABASE_REQUIRE_SSL")
                                 .ok()
                                 .and_then(|s| s.parse().ok())
                                 .unwrap_or(false),
-                            min_connections: std::env::var("DATABASE_MIN_CONNECTIONS")
-                                .ok()
-                                .and_then(|c| c.parse().ok())
-                                .unwrap_or(5),
-                            max_connections: std::env::var("DATABASE_MAX_CONNECTIONS")
+                            min_connections,
+                            max_connections,
+                            acquire_timeout_seconds,
+                            idle_timeout_seconds,
+                            max_lifetime_seconds,
+                            statement_timeout_ms,
+                            read_url,
+                            read_max_lag_seconds,
+                            read_check_interval_seconds,
+                            auto_migrate: var("DATABASE_AUTO_MIGRATE")
                                 .ok()
-                                .and_then(|c| c.parse().ok())
-                                .unwrap_or(20),
+                                .and_then(|s| s.parse().ok())
+                                .unwrap_or(true),
                         }
                     } else {
                         // Fallback to individual settings if URL can't be parsed
                         DatabaseSettings {
-                            host: std::env::var("DATABASE_HOST").unwrap_or_else(|_| "localhost".to_string()),
-                            port: std::env::var("DATABASE_PORT")
+                            host: var("DATABASE_HOST").unwrap_or_else(|_| "localhost".to_string()),
+                            port: var("DATABASE_PORT")
                                 .ok()
                                 .and_then(|p| p.parse().ok())
                                 .unwrap_or(5432),
-                            username: std::env::var("DATABASE_USERNAME").unwrap_or_else(|_| "aerugo".to_string()),
-                            password: Secret::new(std::env::var("DATABASE_PASSWORD").unwrap_or_else(|_| "1".to_string())),
-                            database_name: std::env::var("DATABASE_NAME").unwrap_or_else(|_| "aerugo_dev".to_string()),
-                            require_ssl: std::env::var("DATABASE_REQUIRE_SSL")
+                            username: var("DATABASE_USERNAME").unwrap_or_else(|_| "aerugo".to_string()),
+                            password: Secret::new(var("DATABASE_PASSWORD").unwrap_or_else(|_| "1".to_string())),
+                            database_name: var("DATABASE_NAME").unwrap_or_else(|_| "aerugo_dev".to_string()),
+                            require_ssl: var("DATABASE_REQUIRE_SSL")
                                 .ok()
                                 .and_then(|s| s.parse().ok())
                                 .unwrap_or(false),
-                            min_connections: std::env::var("DATABASE_MIN_CONNECTIONS")
-                                .ok()
-                                .and_then(|c| c.parse().ok())
-                                .unwrap_or(5),
-                            max_connections: std::env::var("DATABASE_MAX_CONNECTIONS")
+                            min_connections,
+                            max_connections,
+                            acquire_timeout_seconds,
+                            idle_timeout_seconds,
+                            max_lifetime_seconds,
+                            statement_timeout_ms,
+                            read_url,
+                            read_max_lag_seconds,
+                            read_check_interval_seconds,
+                            auto_migrate: var("DATABASE_AUTO_MIGRATE")
                                 .ok()
-                                .and_then(|c| c.parse().ok())
-                                .unwrap_or(20),
+                                .and_then(|s| s.parse().ok())
+                                .unwrap_or(true),
                         }
                     }
                 } else {
                     // Use individual settings if DATABASE_URL is not set
                     DatabaseSettings {
-                        host: std::env::var("DATABASE_HOST").unwrap_or_else(|_| "localhost".to_string()),
-                        port: std::env::var("DATABASE_PORT")
+                        host: var("DATABASE_HOST").unwrap_or_else(|_| "localhost".to_string()),
+                        port: var("DATABASE_PORT")
                             .ok()
                             .and_then(|p| p.parse().ok())
                             .unwrap_or(5432),
-                        username: std::env::var("DATABASE_USERNAME").unwrap_or_else(|_| "aerugo".to_string()),
-                        password: Secret::new(std::env::var("DATABASE_PASSWORD").unwrap_or_else(|_| "1".to_string())),
-                        database_name: std::env::var("DATABASE_NAME").unwrap_or_else(|_| "aerugo_dev".to_string()),
-                        require_ssl: std::env::var("DATABASE_REQUIRE_SSL")
+                        username: var("DATABASE_USERNAME").unwrap_or_else(|_| "aerugo".to_string()),
+                        password: Secret::new(var("DATABASE_PASSWORD").unwrap_or_else(|_| "1".to_string())),
+                        database_name: var("DATABASE_NAME").unwrap_or_else(|_| "aerugo_dev".to_string()),
+                        require_ssl: var("DATABASE_REQUIRE_SSL")
                             .ok()
                             .and_then(|s| s.parse().ok())
                             .unwrap_or(false),
-                        min_connections: std::env::var("DATABASE_MIN_CONNECTIONS")
-                            .ok()
-                            .and_then(|c| c.parse().ok())
-                            .unwrap_or(5),
-                        max_connections: std::env::var("DATABASE_MAX_CONNECTIONS")
+                        min_connections,
+                        max_connections,
+                        acquire_timeout_seconds,
+                        idle_timeout_seconds,
+                        max_lifetime_seconds,
+                        statement_timeout_ms,
+                        read_url,
+                        read_max_lag_seconds,
+                        read_check_interval_seconds,
+                        auto_migrate: var("DATABASE_AUTO_MIGRATE")
                             .ok()
-                            .and_then(|c| c.parse().ok())
-                            .unwrap_or(20),
+                            .and_then(|s| s.parse().ok())
+                            .unwrap_or(true),
                     }
                 }
             },
             storage: StorageSettings {
-                endpoint: std::env::var("STORAGE_ENDPOINT").unwrap_or_else(|_| "http://localhost:9000".to_string()),
-                region: std::env::var("STORAGE_REGION").unwrap_or_else(|_| "us-east-1".to_string()),
-                bucket: std::env::var("STORAGE_BUCKET").unwrap_or_else(|_| "aerugo".to_string()),
-                access_key_id: Secret::new(std::env::var("STORAGE_ACCESS_KEY_ID").unwrap_or_else(|_| "minioadmin".to_string())),
-                secret_access_key: Secret::new(std::env::var("STORAGE_SECRET_ACCESS_KEY").unwrap_or_else(|_| "minioadmin".to_string())),
-                use_path_style: std::env::var("STORAGE_USE_PATH_STYLE")
+                endpoint: var("STORAGE_ENDPOINT").unwrap_or_else(|_| "http://localhost:9000".to_string()),
+                region: var("STORAGE_REGION").unwrap_or_else(|_| "us-east-1".to_string()),
+                bucket: var("STORAGE_BUCKET").unwrap_or_else(|_| "aerugo".to_string()),
+                access_key_id: Secret::new(var("STORAGE_ACCESS_KEY_ID").unwrap_or_else(|_| "minioadmin".to_string())),
+                secret_access_key: Secret::new(var("STORAGE_SECRET_ACCESS_KEY").unwrap_or_else(|_| "minioadmin".to_string())),
+                use_path_style: var("STORAGE_USE_PATH_STYLE")
                     .ok()
                     .and_then(|s| s.parse().ok())
                     .unwrap_or(true),
+                manifests_in_database: var("STORAGE_MANIFESTS_IN_DATABASE")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(true),
+                max_manifest_bytes: var("STORAGE_MAX_MANIFEST_BYTES")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(4 * 1024 * 1024),
             },
             cache: CacheSettings {
-                redis_url: std::env::var("REDIS_URL").unwrap_or_else(|_| "redis://localhost:6379".to_string()),
-                pool_size: std::env::var("REDIS_POOL_SIZE")
+                redis_url: var("REDIS_URL").unwrap_or_else(|_| "redis://localhost:6379".to_string()),
+                pool_size: var("REDIS_POOL_SIZE")
                     .ok()
                     .and_then(|s| s.parse().ok())
                     .unwrap_or(10),
-                ttl_seconds: std::env::var("REDIS_TTL_SECONDS")
+                ttl_seconds: cache_ttl_seconds,
+                manifest_ttl_seconds: var("CACHE_MANIFEST_TTL_SECONDS")
                     .ok()
                     .and_then(|s| s.parse().ok())
-                    .unwrap_or(3600),
+                    .unwrap_or(cache_ttl_seconds),
+                blob_metadata_ttl_seconds: var("CACHE_BLOB_METADATA_TTL_SECONDS")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(cache_ttl_seconds * 2),
+                repository_ttl_seconds: var("CACHE_REPOSITORY_TTL_SECONDS")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(60),
+                tag_ttl_seconds: var("CACHE_TAG_TTL_SECONDS")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(120),
+                auth_token_ttl_seconds: var("CACHE_AUTH_TOKEN_TTL_SECONDS")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(900),
+                permission_ttl_seconds: var("CACHE_PERMISSION_TTL_SECONDS")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(300),
+                session_ttl_seconds: var("CACHE_SESSION_TTL_SECONDS")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(1800),
+                max_memory_entries: var("CACHE_MAX_MEMORY_ENTRIES")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(10000),
+                connect_timeout_ms: var("REDIS_CONNECT_TIMEOUT_MS")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(1000),
+                command_timeout_ms: var("REDIS_COMMAND_TIMEOUT_MS")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(500),
+                manifest_memory_mb: var("CACHE_MANIFEST_MEMORY_MB")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(64),
+                warmup_enabled: var("CACHE_WARMUP_ENABLED")
+                    .map(|v| v.to_lowercase() == "true")
+                    .unwrap_or(false),
+                warmup_repositories: var("CACHE_WARMUP_REPOSITORIES")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(50),
+                warmup_manifests: var("CACHE_WARMUP_MANIFESTS")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(200),
             },
             auth: AuthSettings {
-                jwt_secret: Secret::new(std::env::var("JWT_SECRET").unwrap_or_else(|_| "your-super-secret-key".to_string())),
-                jwt_expiration_seconds: std::env::var("JWT_EXPIRATION_SECONDS")
+                jwt_secret: Secret::new(var("JWT_SECRET").unwrap_or_else(|_| "your-super-secret-key".to_string())),
+                jwt_expiration_seconds: var("JWT_EXPIRATION_SECONDS")
                     .ok()
                     .and_then(|s| s.parse().ok())
                     .unwrap_or(3600),
-                refresh_token_expiration_seconds: std::env::var("REFRESH_TOKEN_EXPIRATION_SECONDS")
+                refresh_token_expiration_seconds: var("REFRESH_TOKEN_EXPIRATION_SECONDS")
                     .ok()
                     .and_then(|s| s.parse().ok())
                     .unwrap_or(604800),
+                argon2_memory_kib: var("ARGON2_MEMORY_KIB")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(argon2::Params::DEFAULT_M_COST),
+                argon2_iterations: var("ARGON2_ITERATIONS")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(argon2::Params::DEFAULT_T_COST),
+                argon2_parallelism: var("ARGON2_PARALLELISM")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(argon2::Params::DEFAULT_P_COST),
+                api_key_default_expiration_days: var("API_KEY_DEFAULT_EXPIRATION_DAYS")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(15),
+                api_key_max_expiration_days: var("API_KEY_MAX_EXPIRATION_DAYS")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(365),
+                admin_usernames: var("ADMIN_USERNAMES")
+                    .map(|s| {
+                        s.split(',')
+                            .map(|u| u.trim().to_string())
+                            .filter(|u| !u.is_empty())
+                            .collect()
+                    })
+                    .unwrap_or_default(),
             },
             email: EmailSettings {
-                smtp_host: std::env::var("SMTP_HOST").unwrap_or_else(|_| "localhost".to_string()),
-                smtp_port: std::env::var("SMTP_PORT")
+                smtp_host: var("SMTP_HOST").unwrap_or_else(|_| "localhost".to_string()),
+                smtp_port: var("SMTP_PORT")
                     .ok()
                     .and_then(|p| p.parse().ok())
                     .unwrap_or(587),
-                smtp_username: std::env::var("SMTP_USERNAME").unwrap_or_else(|_| "".to_string()),
-                smtp_password: Secret::new(std::env::var("SMTP_PASSWORD").unwrap_or_else(|_| "".to_string())),
-                from_email: std::env::var("FROM_EMAIL").unwrap_or_else(|_| "noreply@localhost".to_string()),
-                from_name: std::env::var("FROM_NAME").unwrap_or_else(|_| "Aerugo ".to_string()),
-                use_tls: std::env::var("SMTP_USE_TLS")
+                smtp_username: var("SMTP_USERNAME").unwrap_or_else(|_| "".to_string()),
+                smtp_password: Secret::new(var("SMTP_PASSWORD").unwrap_or_else(|_| "".to_string())),
+                from_email: var("FROM_EMAIL").unwrap_or_else(|_| "noreply@localhost".to_string()),
+                from_name: var("FROM_NAME").unwrap_or_else(|_| "Aerugo ".to_string()),
+                use_tls: var("SMTP_USE_TLS")
                     .ok()
                     .and_then(|s| s.parse().ok())
                     .unwrap_or(true),
-                test_mode: std::env::var("EMAIL_TEST_MODE")
+                test_mode: var("EMAIL_TEST_MODE")
                     .ok()
                     .and_then(|s| s.parse().ok())
                     .unwrap_or(cfg!(debug_assertions)), // Use test mode in development by default
-                test_email_file: std::env::var("EMAIL_TEST_FILE").ok(),
+                test_email_file: var("EMAIL_TEST_FILE").ok(),
+            },
+            webhooks: WebhookSettings {
+                signing_key: var("WEBHOOK_SIGNING_KEY").ok().map(Secret::new),
+                allowed_networks: var("WEBHOOK_ALLOWED_NETWORKS")
+                    .map(|s| {
+                        s.split(',')
+                            .map(|n| n.trim().to_string())
+                            .filter(|n| !n.is_empty())
+                            .collect()
+                    })
+                    .unwrap_or_default(),
+            },
+            transcode: TranscodeSettings {
+                enabled: var("BLOB_TRANSCODE_ENABLED")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(false),
+                zstd_level: var("BLOB_TRANSCODE_ZSTD_LEVEL")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(3),
+                interval_seconds: var("BLOB_TRANSCODE_INTERVAL_SECONDS")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(300),
+                batch_size: var("BLOB_TRANSCODE_BATCH_SIZE")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(10),
+            },
+            ip_access: IpAccessSettings {
+                enabled: var("IP_ACCESS_RULES_ENABLED")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(true),
+                trusted_proxies: var("TRUSTED_PROXIES")
+                    .map(|s| {
+                        s.split(',')
+                            .map(|p| p.trim().to_string())
+                            .filter(|p| !p.is_empty())
+                            .collect()
+                    })
+                    .unwrap_or_default(),
+            },
+            log_tail: LogTailSettings {
+                buffer_size: var("LOG_TAIL_BUFFER_SIZE")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(1000),
+                operators: var("LOG_TAIL_OPERATORS")
+                    .map(|s| {
+                        s.split(',')
+                            .map(|u| u.trim().to_string())
+                            .filter(|u| !u.is_empty())
+                            .collect()
+                    })
+                    .unwrap_or_default(),
+            },
+            rate_limit: RateLimitSettings {
+                enabled: var("RATE_LIMIT_ENABLED")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(false),
+                window_seconds: var("RATE_LIMIT_WINDOW_SECONDS")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(60),
+                pull_limit: var("RATE_LIMIT_PULL")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(1000),
+                push_limit: var("RATE_LIMIT_PUSH")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(500),
+                auth_limit: var("RATE_LIMIT_AUTH")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(20),
+                api_limit: var("RATE_LIMIT_API")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(600),
+            },
+            concurrency: ConcurrencySettings {
+                max_uploads: var("CONCURRENCY_MAX_UPLOADS")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(64),
+                max_downloads: var("CONCURRENCY_MAX_DOWNLOADS")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(256),
+                max_uploads_per_user: var("CONCURRENCY_MAX_UPLOADS_PER_USER")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(8),
+                max_downloads_per_user: var("CONCURRENCY_MAX_DOWNLOADS_PER_USER")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(32),
+                retry_after_seconds: var("CONCURRENCY_RETRY_AFTER_SECONDS")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(5),
+                request_timeout_seconds: var("REQUEST_TIMEOUT_SECONDS")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(60),
+                upload_timeout_seconds: var("UPLOAD_TIMEOUT_SECONDS")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(3600),
+            },
+            health: HealthSettings {
+                check_timeout_ms: var("HEALTH_CHECK_TIMEOUT_MS")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(2000),
+                require_cache: var("HEALTH_REQUIRE_CACHE")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(false),
+            },
+            login_protection: LoginProtectionSettings {
+                max_failed_attempts: var("LOGIN_MAX_FAILED_ATTEMPTS")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(5),
+                ip_max_failed_attempts: var("LOGIN_IP_MAX_FAILED_ATTEMPTS")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(20),
+                lockout_seconds: var("LOGIN_LOCKOUT_SECONDS")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(900),
+                base_delay_ms: var("LOGIN_FAILURE_BASE_DELAY_MS")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(250),
+                max_delay_ms: var("LOGIN_FAILURE_MAX_DELAY_MS")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(5000),
+            },
+            compression: CompressionSettings {
+                gzip: var("COMPRESSION_GZIP")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(true),
+                zstd: var("COMPRESSION_ZSTD")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(true),
+                min_bytes: var("COMPRESSION_MIN_BYTES")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(1024),
+                decompress_requests: var("COMPRESSION_DECOMPRESS_REQUESTS")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(true),
+            },
+            uploads: UploadSettings {
+                max_open_sessions_per_user: var("UPLOAD_MAX_SESSIONS_PER_USER")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(10),
+                max_open_sessions_per_repository: var("UPLOAD_MAX_SESSIONS_PER_REPOSITORY")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(100),
+                session_expiry_seconds: var("UPLOAD_SESSION_EXPIRY_SECONDS")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(86400),
+                max_request_bytes: var("UPLOAD_MAX_REQUEST_BYTES")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(1024 * 1024 * 1024),
+                max_blob_bytes: var("UPLOAD_MAX_BLOB_BYTES")
+                    .ok()
+                    .and_then(|s| s.parse().ok()),
+            },
+            standby: StandbySettings {
+                enabled: var("STANDBY_ENABLED")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(false),
+                primary_url: var("STANDBY_PRIMARY_URL").ok().filter(|s| !s.is_empty()),
+                subscription_name: var("STANDBY_SUBSCRIPTION_NAME").ok().filter(|s| !s.is_empty()),
+                promotion_token: var("STANDBY_PROMOTION_TOKEN")
+                    .ok()
+                    .filter(|s| !s.is_empty())
+                    .map(Secret::new),
+                max_promotion_lag_seconds: var("STANDBY_MAX_PROMOTION_LAG_SECONDS")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(30),
+                monitor_interval_seconds: var("STANDBY_MONITOR_INTERVAL_SECONDS")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(10),
+            },
+            retention: RetentionSettings {
+                audit_log_retention_days: var("RETENTION_AUDIT_LOG_DAYS")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(7),
+                usage_retention_days: var("RETENTION_USAGE_DAYS")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(90),
+                event_retention_days: var("RETENTION_EVENT_DAYS")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(365),
+                deleted_data_grace_days: var("RETENTION_DELETED_DATA_GRACE_DAYS")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(0),
+                anonymize_ips: var("RETENTION_ANONYMIZE_IPS")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(false),
+            },
+            invitations: InvitationSettings {
+                accept_url: var("INVITATION_ACCEPT_URL")
+                    .unwrap_or_else(|_| "http://localhost:8080/invitations".to_string()),
+                expiry_hours: var("INVITATION_EXPIRY_HOURS")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(168),
+            },
+            federation: FederationSettings {
+                instance_name: var("FEDERATION_INSTANCE_NAME")
+                    .unwrap_or_else(|_| "local".to_string()),
+                public_url: var("FEDERATION_PUBLIC_URL").ok().filter(|s| !s.is_empty()),
+                peers: var("FEDERATION_PEERS")
+                    .map(|s| s.split(',').filter_map(FederationPeer::parse).collect())
+                    .unwrap_or_default(),
+                request_timeout_ms: var("FEDERATION_REQUEST_TIMEOUT_MS")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(2000),
+                cache_ttl_seconds: var("FEDERATION_CACHE_TTL_SECONDS")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(60),
+                failure_cooldown_seconds: var("FEDERATION_FAILURE_COOLDOWN_SECONDS")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(30),
+            },
+            gc: GcSettings {
+                interval_seconds: var("GC_INTERVAL_SECONDS")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(300),
+                batch_size: var("GC_BATCH_SIZE")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(500),
+                max_attempts: var("GC_MAX_ATTEMPTS")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(5),
+            },
+            redirects: RedirectSettings {
+                grace_days: var("REPOSITORY_REDIRECT_GRACE_DAYS")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(30),
+            },
+            bandwidth: BandwidthSettings {
+                download_bytes_per_second: var("BANDWIDTH_DOWNLOAD_BYTES_PER_SECOND")
+                    .ok()
+                    .and_then(|s| s.parse().ok()),
+            },
+            secrets: SecretsSettings {
+                encryption_key: var("SECRETS_ENCRYPTION_KEY").ok().map(Secret::new),
+            },
+            tag_cleanup: TagCleanupSettings {
+                analysis_interval_seconds: var("TAG_CLEANUP_ANALYSIS_INTERVAL_SECONDS")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(86400),
+                stale_days: var("TAG_CLEANUP_STALE_DAYS")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(90),
+            },
+            bootstrap: BootstrapSettings {
+                admin_username: var("BOOTSTRAP_ADMIN_USERNAME").ok().filter(|s| !s.is_empty()),
+                admin_email: var("BOOTSTRAP_ADMIN_EMAIL").ok().filter(|s| !s.is_empty()),
+                admin_password: var("BOOTSTRAP_ADMIN_PASSWORD").ok().filter(|s| !s.is_empty()).map(Secret::new),
+                default_organization: var("BOOTSTRAP_DEFAULT_ORGANIZATION").ok().filter(|s| !s.is_empty()),
+                default_repository_public: var("BOOTSTRAP_DEFAULT_REPOSITORY_PUBLIC")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(false),
+                default_tag_retention_keep_last: var("BOOTSTRAP_DEFAULT_TAG_RETENTION_KEEP_LAST")
+                    .ok()
+                    .and_then(|s| s.parse().ok()),
+                default_tag_retention_days: var("BOOTSTRAP_DEFAULT_TAG_RETENTION_DAYS")
+                    .ok()
+                    .and_then(|s| s.parse().ok()),
+                token: var("BOOTSTRAP_TOKEN").ok().filter(|s| !s.is_empty()).map(Secret::new),
+            },
+            naming: NamingSettings {
+                reserved_names: var("NAMING_RESERVED_NAMES")
+                    .map(|s| {
+                        s.split(',')
+                            .map(|name| name.trim().to_lowercase())
+                            .filter(|name| !name.is_empty())
+                            .collect()
+                    })
+                    .unwrap_or_else(|_| crate::naming::DEFAULT_RESERVED_NAMES.iter().map(|name| name.to_string()).collect()),
+            },
+            cdn: CdnSettings {
+                provider: var("CDN_PURGE_PROVIDER").ok().filter(|s| !s.is_empty()).map(|s| s.to_lowercase()),
+                base_url: var("CDN_BASE_URL").ok().filter(|s| !s.is_empty()),
+                cloudfront_distribution_id: var("CDN_CLOUDFRONT_DISTRIBUTION_ID").ok().filter(|s| !s.is_empty()),
+                fastly_api_token: var("CDN_FASTLY_API_TOKEN").ok().filter(|s| !s.is_empty()).map(Secret::new),
+                fastly_api_url: var("CDN_FASTLY_API_URL")
+                    .unwrap_or_else(|_| "https://api.fastly.com".to_string()),
+                fastly_soft_purge: var("CDN_FASTLY_SOFT_PURGE")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(false),
+            },
+            activity: ActivitySettings {
+                flush_interval_seconds: var("ACTIVITY_FLUSH_INTERVAL_SECONDS")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(10),
+            },
+            notifications: match var("NOTIFICATIONS_CONFIG_FILE").ok().filter(|s| !s.is_empty()) {
+                Some(path) => NotificationSettings::from_file(&path)
+                    .with_context(|| format!("Failed to read notifications from {}", path))?,
+                None => NotificationSettings::default(),
+            },
+            event_stream: EventStreamSettings {
+                backend: var("EVENT_STREAM_BACKEND").ok().filter(|s| !s.is_empty()).map(|s| s.to_lowercase()),
+                nats_url: var("EVENT_STREAM_NATS_URL")
+                    .unwrap_or_else(|_| "nats://localhost:4222".to_string()),
+                nats_token: var("EVENT_STREAM_NATS_TOKEN").ok().filter(|s| !s.is_empty()).map(Secret::new),
+                nats_stream: var("EVENT_STREAM_NATS_STREAM")
+                    .unwrap_or_else(|_| "AERUGO_EVENTS".to_string()),
+                subject_prefix: var("EVENT_STREAM_SUBJECT_PREFIX")
+                    .unwrap_or_else(|_| "aerugo.events".to_string()),
+                kafka_brokers: var("EVENT_STREAM_KAFKA_BROKERS")
+                    .map(|s| s.split(',').map(|b| b.trim().to_string()).filter(|b| !b.is_empty()).collect())
+                    .unwrap_or_default(),
+                kafka_topic: var("EVENT_STREAM_KAFKA_TOPIC")
+                    .unwrap_or_else(|_| "aerugo-events".to_string()),
+            },
+            scanning: ScanSettings {
+                enabled: var("SCAN_ENABLED")
+                    .map(|v| v.to_lowercase() == "true")
+                    .unwrap_or(false),
+                trivy_path: var("SCAN_TRIVY_PATH")
+                    .unwrap_or_else(|_| "trivy".to_string()),
+                trivy_server_url: var("SCAN_TRIVY_SERVER_URL").ok().filter(|s| !s.is_empty()),
+                trivy_token: var("SCAN_TRIVY_TOKEN").ok().filter(|s| !s.is_empty()).map(Secret::new),
+                registry_host: var("SCAN_REGISTRY_HOST")
+                    .unwrap_or_else(|_| "localhost:8080".to_string()),
+                registry_username: var("SCAN_REGISTRY_USERNAME").ok().filter(|s| !s.is_empty()),
+                registry_password: var("SCAN_REGISTRY_PASSWORD").ok().filter(|s| !s.is_empty()).map(Secret::new),
+                registry_insecure: var("SCAN_REGISTRY_INSECURE")
+                    .map(|v| v.to_lowercase() == "true")
+                    .unwrap_or(false),
+                timeout_seconds: var("SCAN_TIMEOUT_SECONDS")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(600),
+                concurrency: var("SCAN_CONCURRENCY")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(2),
+                sbom_enabled: var("SBOM_ENABLED")
+                    .map(|v| v.to_lowercase() == "true")
+                    .unwrap_or(false),
+                sbom_format: var("SBOM_FORMAT")
+                    .map(|s| s.to_lowercase())
+                    .unwrap_or_else(|_| "cyclonedx".to_string()),
+            },
+            jobs: JobSettings {
+                concurrency: var("JOBS_CONCURRENCY")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(4),
+                max_attempts: var("JOBS_MAX_ATTEMPTS")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(5),
+                timeout_seconds: var("JOBS_TIMEOUT_SECONDS")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(3600),
+            },
+            signatures: SignatureSettings {
+                cosign_path: var("COSIGN_PATH")
+                    .unwrap_or_else(|_| "cosign".to_string()),
+                public_keys: var("SIGNATURE_PUBLIC_KEYS")
+                    .map(|s| s.split(',').map(|k| k.trim().to_string()).filter(|k| !k.is_empty()).collect())
+                    .unwrap_or_default(),
+                keyless_identity: var("SIGNATURE_KEYLESS_IDENTITY").ok().filter(|s| !s.is_empty()),
+                keyless_issuer: var("SIGNATURE_KEYLESS_ISSUER").ok().filter(|s| !s.is_empty()),
+                rekor_url: var("SIGNATURE_REKOR_URL").ok().filter(|s| !s.is_empty()),
+                notation_path: var("NOTATION_PATH")
+                    .unwrap_or_else(|_| "notation".to_string()),
+                notation_config_home: var("NOTATION_CONFIG_HOME").ok().filter(|s| !s.is_empty()),
+                notation_policy_name: var("NOTATION_POLICY_NAME").ok().filter(|s| !s.is_empty()),
+                timeout_seconds: var("SIGNATURE_TIMEOUT_SECONDS")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(60),
+            },
+            secret_scan: SecretScanSettings {
+                enabled: var("SECRET_SCAN_ENABLED")
+                    .map(|v| v.to_lowercase() == "true")
+                    .unwrap_or(false),
+                scan_layers: var("SECRET_SCAN_LAYERS")
+                    .map(|v| v.to_lowercase() == "true")
+                    .unwrap_or(false),
+                reject: var("SECRET_SCAN_REJECT")
+                    .map(|v| v.to_lowercase() == "true")
+                    .unwrap_or(false),
+                max_layer_bytes: var("SECRET_SCAN_MAX_LAYER_BYTES")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(256 * 1024 * 1024),
+                max_file_bytes: var("SECRET_SCAN_MAX_FILE_BYTES")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(1024 * 1024),
+            },
+            base_images: BaseImageSettings {
+                enabled: var("BASE_IMAGE_TRACKING_ENABLED")
+                    .map(|v| v.to_lowercase() == "true")
+                    .unwrap_or(false),
+                registry_hosts: var("BASE_IMAGE_REGISTRY_HOSTS")
+                    .map(|s| s.split(',').map(|h| h.trim().to_string()).filter(|h| !h.is_empty()).collect())
+                    .unwrap_or_default(),
+                advisory_severity: var("BASE_IMAGE_ADVISORY_SEVERITY")
+                    .map(|s| s.to_uppercase())
+                    .unwrap_or_else(|_| "HIGH".to_string()),
+            },
+            features: FeatureSettings {
+                allow_delete: var("FEATURES_ALLOW_DELETE")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(true),
+                // `ALLOW_ANONYMOUS_PULL` predates the feature flags and is still honored
+                allow_anonymous_pull: var("FEATURES_ALLOW_ANONYMOUS_PULL")
+                    .or_else(|_| var("ALLOW_ANONYMOUS_PULL"))
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(false),
+                open_registration: var("FEATURES_OPEN_REGISTRATION")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(true),
+                readonly_mode: var("FEATURES_READONLY_MODE")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(false),
+                refresh_interval_seconds: var("FEATURES_REFRESH_INTERVAL_SECONDS")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(30),
+            },
+            error_reporting: ErrorReportingSettings {
+                dsn: var("ERROR_REPORTING_DSN").ok().filter(|s| !s.is_empty()).map(Secret::new),
+                environment: var("ERROR_REPORTING_ENVIRONMENT").unwrap_or_else(|_| "production".to_string()),
+                release: var("ERROR_REPORTING_RELEASE")
+                    .unwrap_or_else(|_| concat!("aerugo@", env!("CARGO_PKG_VERSION")).to_string()),
+                server_name: var("ERROR_REPORTING_SERVER_NAME")
+                    .or_else(|_| var("HOSTNAME"))
+                    .ok(),
+            },
+            startup: StartupSettings {
+                retry_attempts: var("STARTUP_RETRY_ATTEMPTS")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(10),
+                retry_initial_delay_ms: var("STARTUP_RETRY_INITIAL_DELAY_MS")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(500),
+                retry_max_delay_ms: var("STARTUP_RETRY_MAX_DELAY_MS")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(30000),
+                email_required: var("STARTUP_EMAIL_REQUIRED")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(false),
             },
         };
 
@@ -256,15 +1188,10 @@ impl Settings {
         Ok(settings)
     }
 
-    pub fn validate_all(&self) -> Result<(), validator::ValidationErrors> {
-        self.validate()?;
-        self.server.validate()?;
-        self.database.validate()?;
-        self.storage.validate()?;
-        self.cache.validate()?;
-        self.auth.validate()?;
-        self.email.validate()?;
-        Ok(())
+    /// Validate every section. Errors name the variable to fix, e.g. `LISTENERS`.
+    pub fn validate_all(&self) -> Result<(), InvalidSettings> {
+        self.validate()
+            .map_err(|errors| InvalidSettings { problems: super::sources::describe(&errors) })
     }
 
     // Get base URL for server
@@ -298,12 +1225,66 @@ fn validate_socket_addr(addr: &str) -> Result<(), validator::ValidationError> {
         .map_err(|_| validator::ValidationError::new("invalid_socket_address"))
 }
 
+fn validate_server_settings(server: &ServerSettings) -> Result<(), validator::ValidationError> {
+    if server.listeners.is_empty() {
+        return Err(validator::ValidationError::new("no_listeners"));
+    }
+    if server.listeners.iter().any(|listener| crate::listeners::Listener::parse(listener).is_err()) {
+        return Err(validator::ValidationError::new("invalid_listener"));
+    }
+    if server.unix_socket_mode > 0o777 {
+        return Err(validator::ValidationError::new("invalid_unix_socket_mode"));
+    }
+    Ok(())
+}
+
+fn validate_database_settings(database: &DatabaseSettings) -> Result<(), validator::ValidationError> {
+    if database.min_connections > database.max_connections {
+        return Err(validator::ValidationError::new("min_connections_above_max_connections"));
+    }
+    Ok(())
+}
+
+fn validate_log_format(format: &str) -> Result<(), validator::ValidationError> {
+    if crate::logging::LOG_FORMATS.contains(&format) {
+        Ok(())
+    } else {
+        Err(validator::ValidationError::new("invalid_log_format"))
+    }
+}
+
+fn validate_encryption_key(key: &Secret<String>) -> Result<(), validator::ValidationError> {
+    use base64::Engine;
+    match base64::prelude::BASE64_STANDARD.decode(key.expose_secret().trim()) {
+        Ok(bytes) if bytes.len() == 32 => Ok(()),
+        _ => Err(validator::ValidationError::new("invalid_encryption_key")),
+    }
+}
+
+fn validate_bootstrap_password(password: &Secret<String>) -> Result<(), validator::ValidationError> {
+    if password.expose_secret().len() < 8 {
+        return Err(validator::ValidationError::new("bootstrap_password_too_short"));
+    }
+    Ok(())
+}
+
+fn validate_bootstrap_token(token: &Secret<String>) -> Result<(), validator::ValidationError> {
+    if token.expose_secret().len() < 16 {
+        return Err(validator::ValidationError::new("bootstrap_token_too_short"));
+    }
+    Ok(())
+}
+
 fn validate_url(url: &str) -> Result<(), validator::ValidationError> {
     Url::parse(url)
         .map(|_| ())
         .map_err(|_| validator::ValidationError::new("invalid_url"))
 }
 
+fn validate_database_read_url(url: &Secret<String>) -> Result<(), validator::ValidationError> {
+    validate_url(url.expose_secret())
+}
+
 #[derive(Debug, Deserialize, Clone, Validate)]
 pub struct EmailSettings {
     pub smtp_host: String,
@@ -317,3 +1298,644 @@ pub struct EmailSettings {
     pub test_mode: bool,
     pub test_email_file: Option<String>,
 }
+
+#[derive(Debug, Deserialize, Clone, Validate)]
+pub struct WebhookSettings {
+    /// Base64-encoded 32-byte Ed25519 seed; when unset a key is generated once and kept in the database
+    pub signing_key: Option<Secret<String>>,
+    /// Internal networks (IPs or CIDRs) webhooks and push hooks may still be delivered to
+    #[validate(custom = "validate_cidrs")]
+    pub allowed_networks: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Validate)]
+pub struct TranscodeSettings {
+    /// Run the background service that adds zstd variants of gzip-layered images
+    pub enabled: bool,
+    #[validate(range(min = 1, max = 19))]
+    pub zstd_level: i32,
+    #[validate(range(min = 10))]
+    pub interval_seconds: u64,
+    /// Manifests processed per pass
+    #[validate(range(min = 1, max = 1000))]
+    pub batch_size: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Validate)]
+pub struct IpAccessSettings {
+    /// Enforce the CIDR allow/deny rules stored in the database on registry requests
+    pub enabled: bool,
+    /// Proxies (IPs or CIDRs) whose X-Forwarded-For / X-Real-IP headers are trusted
+    #[validate(custom = "validate_cidrs")]
+    pub trusted_proxies: Vec<String>,
+}
+
+fn validate_cidrs(cidrs: &[String]) -> Result<(), validator::ValidationError> {
+    if cidrs.iter().all(|c| c.parse::<ipnet::IpNet>().is_ok() || c.parse::<std::net::IpAddr>().is_ok()) {
+        Ok(())
+    } else {
+        Err(validator::ValidationError::new("invalid_cidr"))
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Validate)]
+pub struct LogTailSettings {
+    /// Recent events kept for replay, and how far a slow subscriber may fall behind
+    #[validate(range(min = 10, max = 100000))]
+    pub buffer_size: usize,
+    /// Usernames allowed to stream audit and access logs
+    pub operators: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Validate)]
+pub struct RateLimitSettings {
+    /// Reject requests over the per-principal limits below with 429
+    pub enabled: bool,
+    /// Length of the fixed counting window
+    #[validate(range(min = 1, max = 3600))]
+    pub window_seconds: u64,
+    /// Registry reads (GET/HEAD under /v2) per window
+    #[validate(range(min = 1))]
+    pub pull_limit: u64,
+    /// Registry writes (uploads, manifest puts, deletes) per window
+    #[validate(range(min = 1))]
+    pub push_limit: u64,
+    /// Login, registration, token refresh and password reset attempts per window
+    #[validate(range(min = 1))]
+    pub auth_limit: u64,
+    /// Other /api/v1 requests per window
+    #[validate(range(min = 1))]
+    pub api_limit: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Validate)]
+pub struct ConcurrencySettings {
+    /// Blob uploads (upload session requests) handled at once across all users
+    #[validate(range(min = 1))]
+    pub max_uploads: usize,
+    /// Blob downloads streamed at once across all users
+    #[validate(range(min = 1))]
+    pub max_downloads: usize,
+    /// Blob uploads one user, API key or anonymous client address may run at once
+    #[validate(range(min = 1))]
+    pub max_uploads_per_user: usize,
+    /// Blob downloads one user, API key or anonymous client address may run at once
+    #[validate(range(min = 1))]
+    pub max_downloads_per_user: usize,
+    /// `Retry-After` sent with requests turned away at a limit
+    #[validate(range(min = 1))]
+    pub retry_after_seconds: u64,
+    /// Longest a request other than a blob upload may take to produce its response; 0 disables
+    pub request_timeout_seconds: u64,
+    /// Longest a blob upload request may take; 0 disables
+    pub upload_timeout_seconds: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Validate)]
+pub struct HealthSettings {
+    /// How long each dependency check of the readiness probe may take
+    #[validate(range(min = 100, max = 60000))]
+    pub check_timeout_ms: u64,
+    /// Report not ready while Redis is unreachable; otherwise the memory cache stands in for it
+    pub require_cache: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Validate)]
+pub struct LoginProtectionSettings {
+    /// Consecutive failed logins after which an account is locked
+    #[validate(range(min = 1))]
+    pub max_failed_attempts: u64,
+    /// Failed logins from one client address after which it is blocked from logging in
+    #[validate(range(min = 1))]
+    pub ip_max_failed_attempts: u64,
+    /// How long a lockout lasts, and how long failures are remembered
+    #[validate(range(min = 1))]
+    pub lockout_seconds: u64,
+    /// Delay added to the first failed attempt; doubles with each further failure
+    pub base_delay_ms: u64,
+    /// Upper bound for the failure delay
+    pub max_delay_ms: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Validate)]
+pub struct CompressionSettings {
+    /// Compress JSON responses with gzip for clients accepting it
+    pub gzip: bool,
+    /// Compress JSON responses with zstd for clients accepting it
+    pub zstd: bool,
+    /// Responses of a known smaller size are sent as they are
+    pub min_bytes: u16,
+    /// Accept `Content-Encoding: gzip` or `zstd` request bodies on the `/api/v1` API
+    pub decompress_requests: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Validate)]
+pub struct UploadSettings {
+    /// Unfinished upload sessions one user may hold open in a repository
+    #[validate(range(min = 1))]
+    pub max_open_sessions_per_user: i64,
+    /// Unfinished upload sessions a repository may have across all users
+    #[validate(range(min = 1))]
+    pub max_open_sessions_per_repository: i64,
+    /// Sessions left unfinished for longer are considered abandoned and stop counting
+    #[validate(range(min = 60))]
+    pub session_expiry_seconds: i64,
+    /// Largest request body the registry API accepts, i.e. the largest monolithic upload or chunk
+    #[validate(range(min = 1048576))]
+    pub max_request_bytes: usize,
+    /// Largest blob that may be uploaded; unset allows any size
+    pub max_blob_bytes: Option<u64>,
+}
+
+#[derive(Debug, Deserialize, Clone, Validate)]
+pub struct StandbySettings {
+    /// Start read-only, following a primary through database replication, until promoted
+    pub enabled: bool,
+    /// Primary's base URL; promotion is refused while it still accepts connections
+    pub primary_url: Option<String>,
+    /// Logical replication subscription feeding this instance's database; unset for a streaming replica
+    pub subscription_name: Option<String>,
+    /// Bearer token required by the promotion endpoint; promotion is disabled without one
+    pub promotion_token: Option<Secret<String>>,
+    /// Replication lag above which promotion is refused unless forced
+    #[validate(range(min = 1))]
+    pub max_promotion_lag_seconds: u64,
+    #[validate(range(min = 1))]
+    pub monitor_interval_seconds: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Validate)]
+pub struct RetentionSettings {
+    /// Audit and access events older than this are dropped from the log replay buffer
+    #[validate(range(min = 1, max = 3650))]
+    pub audit_log_retention_days: i64,
+    /// Daily API usage rollups older than this are deleted
+    #[validate(range(min = 1, max = 3650))]
+    pub usage_retention_days: i32,
+    /// Registry events older than this are deleted from the event history
+    #[validate(range(min = 1, max = 3650))]
+    pub event_retention_days: i32,
+    /// How long expired API keys, refresh tokens and token revocations are kept before deletion
+    #[validate(range(min = 0, max = 3650))]
+    pub deleted_data_grace_days: i32,
+    /// Truncate client addresses before they reach access logs and notifications
+    pub anonymize_ips: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Validate)]
+pub struct InvitationSettings {
+    /// Page that receives invite links; the signed token is appended as the last path segment
+    pub accept_url: String,
+    /// How long an invite link stays valid
+    #[validate(range(min = 1, max = 8760))]
+    pub expiry_hours: i64,
+}
+
+/// Another Aerugo instance queried by federated search
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FederationPeer {
+    pub name: String,
+    pub url: String,
+}
+
+impl FederationPeer {
+    /// Parse a `name=url` entry of `FEDERATION_PEERS`; a bare URL is named after its host
+    pub fn parse(entry: &str) -> Option<Self> {
+        let entry = entry.trim();
+        if entry.is_empty() {
+            return None;
+        }
+        let (name, url) = match entry.split_once('=') {
+            Some((name, url)) => (name.trim().to_string(), url.trim()),
+            None => (Url::parse(entry).ok()?.host_str()?.to_string(), entry),
+        };
+        Some(Self { name, url: url.trim_end_matches('/').to_string() })
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Validate)]
+pub struct FederationSettings {
+    /// Name this instance reports for its own results in federated search
+    pub instance_name: String,
+    /// Registry URL clients pull from, used to build pull references for local results
+    pub public_url: Option<String>,
+    #[validate(custom = "validate_federation_peers")]
+    pub peers: Vec<FederationPeer>,
+    /// Per-peer deadline for a search request
+    #[validate(range(min = 100, max = 60000))]
+    pub request_timeout_ms: u64,
+    /// How long a peer's answer to a query is reused
+    pub cache_ttl_seconds: u64,
+    /// How long a peer is skipped after a failed request
+    pub failure_cooldown_seconds: u64,
+}
+
+fn validate_federation_peers(peers: &[FederationPeer]) -> Result<(), validator::ValidationError> {
+    for peer in peers {
+        if peer.name.is_empty() {
+            return Err(validator::ValidationError::new("empty_peer_name"));
+        }
+        validate_url(&peer.url)?;
+    }
+    Ok(())
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Validate)]
+pub struct GcSettings {
+    /// How often queued storage keys are collected
+    #[validate(range(min = 10))]
+    pub interval_seconds: u64,
+    #[validate(range(min = 1, max = 10000))]
+    pub batch_size: i64,
+    /// Failed deletions are retried on later passes up to this many times
+    #[validate(range(min = 1))]
+    pub max_attempts: i32,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Validate)]
+pub struct RedirectSettings {
+    /// How long pulls of a repository's former name are redirected after it moves
+    #[validate(range(min = 1, max = 3650))]
+    pub grace_days: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Validate)]
+pub struct BandwidthSettings {
+    /// Rate each blob download is held to unless its organization or repository sets one; unset is unlimited
+    #[validate(range(min = 1024))]
+    pub download_bytes_per_second: Option<u64>,
+}
+
+#[derive(Debug, Deserialize, Clone, Validate)]
+pub struct SecretsSettings {
+    /// Base64-encoded 32-byte key encrypting organization secrets and the stored webhook signing key; the
+    /// secrets API is disabled when unset
+    #[validate(custom = "validate_encryption_key")]
+    pub encryption_key: Option<Secret<String>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Validate)]
+pub struct TagCleanupSettings {
+    /// How often cleanup suggestions are recomputed for every repository
+    #[validate(range(min = 60))]
+    pub analysis_interval_seconds: u64,
+    /// Tags neither pulled nor pushed for this long are suggested for removal
+    #[validate(range(min = 1, max = 3650))]
+    pub stale_days: i64,
+}
+
+#[derive(Debug, Deserialize, Clone, Validate)]
+pub struct BootstrapSettings {
+    /// Initial registry administrator; username, email and password are set together
+    #[validate(length(min = 1, max = 50))]
+    pub admin_username: Option<String>,
+    #[validate(email)]
+    pub admin_email: Option<String>,
+    /// Only used when the administrator is created; an existing account keeps its password
+    #[validate(custom = "validate_bootstrap_password")]
+    pub admin_password: Option<Secret<String>>,
+    /// Organization owned by the administrator that repositories pushed without a namespace go to
+    #[validate(length(min = 3, max = 50))]
+    pub default_organization: Option<String>,
+    /// Base settings of the default organization, written only if it has none yet
+    pub default_repository_public: bool,
+    #[validate(range(min = 1, max = 10000))]
+    pub default_tag_retention_keep_last: Option<i32>,
+    #[validate(range(min = 1, max = 3650))]
+    pub default_tag_retention_days: Option<i32>,
+    /// Bearer token enabling `POST /api/v1/bootstrap`; unset disables the endpoint
+    #[validate(custom = "validate_bootstrap_token")]
+    pub token: Option<Secret<String>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Validate)]
+pub struct NamingSettings {
+    /// Names no organization can take, compared case-insensitively
+    pub reserved_names: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, Clone, Validate)]
+#[validate(schema(function = "validate_cdn_settings"))]
+pub struct CdnSettings {
+    /// `cloudfront` or `fastly`; nothing is purged when unset
+    pub provider: Option<String>,
+    /// Public URL the CDN serves the registry at; Fastly purges full URLs under it and
+    /// CloudFront invalidates paths under its path
+    #[validate(custom = "validate_url")]
+    pub base_url: Option<String>,
+    pub cloudfront_distribution_id: Option<String>,
+    pub fastly_api_token: Option<Secret<String>>,
+    #[validate(custom = "validate_url")]
+    pub fastly_api_url: String,
+    /// Mark purged content stale instead of removing it
+    pub fastly_soft_purge: bool,
+}
+
+fn validate_cdn_settings(cdn: &CdnSettings) -> Result<(), validator::ValidationError> {
+    match cdn.provider.as_deref() {
+        None => Ok(()),
+        Some("cloudfront") if cdn.cloudfront_distribution_id.is_some() => Ok(()),
+        Some("fastly") if cdn.fastly_api_token.is_some() && cdn.base_url.is_some() => Ok(()),
+        Some("cloudfront" | "fastly") => Err(validator::ValidationError::new("incomplete_cdn_settings")),
+        Some(_) => Err(validator::ValidationError::new("unknown_cdn_provider")),
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Validate)]
+pub struct ActivitySettings {
+    /// How often pull and push counts buffered in the cache are written to the database
+    #[validate(range(min = 1, max = 3600))]
+    pub flush_interval_seconds: u64,
+}
+
+/// The `notifications` section of a docker/distribution configuration, read from the YAML or
+/// JSON file named by `NOTIFICATIONS_CONFIG_FILE`
+#[derive(Debug, Deserialize, Clone, Default, PartialEq, Validate)]
+pub struct NotificationSettings {
+    #[serde(default)]
+    pub events: NotificationEventSettings,
+    #[serde(default)]
+    #[validate]
+    pub endpoints: Vec<NotificationEndpoint>,
+}
+
+impl NotificationSettings {
+    /// Read either a whole distribution configuration or just its `notifications` section
+    pub fn from_file(path: &str) -> Result<Self> {
+        let content = std::fs::read_to_string(path)?;
+        let document: serde_yaml::Value = serde_yaml::from_str(&content)?;
+        let section = document.get("notifications").cloned();
+        Ok(serde_yaml::from_value(section.unwrap_or(document))?)
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, Default, PartialEq)]
+pub struct NotificationEventSettings {
+    /// Include the descriptors a manifest references in its events
+    #[serde(default)]
+    pub includereferences: bool,
+}
+
+/// One endpoint events are POSTed to, with the field names distribution uses
+#[derive(Debug, Deserialize, Clone, PartialEq, Validate)]
+pub struct NotificationEndpoint {
+    pub name: String,
+    #[serde(default)]
+    pub disabled: bool,
+    #[validate(custom = "validate_url")]
+    pub url: String,
+    /// Sent with every request, e.g. `Authorization: [Bearer ...]`
+    #[serde(default)]
+    pub headers: BTreeMap<String, Vec<String>>,
+    /// Go duration such as `500ms` or `1s`
+    #[serde(default = "default_notification_timeout", deserialize_with = "deserialize_go_duration")]
+    pub timeout: Duration,
+    /// Consecutive failures after which retries wait `backoff`
+    #[serde(default = "default_notification_threshold")]
+    #[validate(range(min = 1, max = 1000))]
+    pub threshold: u32,
+    #[serde(default = "default_notification_backoff", deserialize_with = "deserialize_go_duration")]
+    pub backoff: Duration,
+    /// Older spelling of `ignore.mediatypes`
+    #[serde(default)]
+    pub ignoredmediatypes: Vec<String>,
+    #[serde(default)]
+    pub ignore: NotificationIgnore,
+}
+
+#[derive(Debug, Deserialize, Clone, Default, PartialEq)]
+pub struct NotificationIgnore {
+    #[serde(default)]
+    pub mediatypes: Vec<String>,
+    /// `push`, `pull`, `mount` or `delete`
+    #[serde(default)]
+    pub actions: Vec<String>,
+}
+
+fn default_notification_timeout() -> Duration {
+    Duration::from_secs(1)
+}
+
+fn default_notification_threshold() -> u32 {
+    10
+}
+
+fn default_notification_backoff() -> Duration {
+    Duration::from_secs(1)
+}
+
+/// A Go duration string, or a number of nanoseconds as Go's YAML decoder accepts
+fn deserialize_go_duration<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Raw {
+        Nanoseconds(u64),
+        Text(String),
+    }
+    match Raw::deserialize(deserializer)? {
+        Raw::Nanoseconds(nanos) => Ok(Duration::from_nanos(nanos)),
+        Raw::Text(text) => crate::notifications::parse_go_duration(&text)
+            .ok_or_else(|| serde::de::Error::custom(format!("invalid duration '{}'", text))),
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, Validate)]
+#[validate(schema(function = "validate_event_stream_settings"))]
+pub struct EventStreamSettings {
+    /// `nats` (JetStream) or `kafka`; nothing is published when unset
+    pub backend: Option<String>,
+    pub nats_url: String,
+    pub nats_token: Option<Secret<String>>,
+    /// JetStream stream holding the events, created on startup when missing
+    pub nats_stream: String,
+    /// Events are published to `<prefix>.<action>`, e.g. `aerugo.events.manifest.push`
+    pub subject_prefix: String,
+    /// `host:port` bootstrap brokers
+    pub kafka_brokers: Vec<String>,
+    pub kafka_topic: String,
+}
+
+fn validate_event_stream_settings(stream: &EventStreamSettings) -> Result<(), validator::ValidationError> {
+    match stream.backend.as_deref() {
+        None => Ok(()),
+        Some("nats") if !stream.nats_stream.is_empty() && !stream.subject_prefix.is_empty() => Ok(()),
+        Some("kafka") if !stream.kafka_brokers.is_empty() && !stream.kafka_topic.is_empty() => Ok(()),
+        Some("nats" | "kafka") => Err(validator::ValidationError::new("incomplete_event_stream_settings")),
+        Some(_) => Err(validator::ValidationError::new("unknown_event_stream_backend")),
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, Validate)]
+#[validate(schema(function = "validate_scan_settings"))]
+pub struct ScanSettings {
+    /// Scan every pushed image for vulnerabilities
+    pub enabled: bool,
+    /// The `trivy` executable
+    pub trivy_path: String,
+    /// A Trivy server to scan against (client/server mode); Trivy's own database is used when unset
+    pub trivy_server_url: Option<String>,
+    pub trivy_token: Option<Secret<String>>,
+    /// `host:port` Trivy pulls this registry's images from
+    pub registry_host: String,
+    pub registry_username: Option<String>,
+    pub registry_password: Option<Secret<String>>,
+    /// Skip TLS verification when pulling from the registry
+    pub registry_insecure: bool,
+    #[validate(range(min = 30, max = 7200))]
+    pub timeout_seconds: u64,
+    /// Images scanned at the same time by one instance
+    #[validate(range(min = 1, max = 32))]
+    pub concurrency: usize,
+    /// Generate an SBOM of every pushed image with Trivy and attach it as a referrer
+    pub sbom_enabled: bool,
+    /// `cyclonedx` or `spdx`
+    pub sbom_format: String,
+}
+
+fn validate_scan_settings(scan: &ScanSettings) -> Result<(), validator::ValidationError> {
+    match scan.sbom_format.parse::<crate::models::sbom::SbomFormat>() {
+        Ok(_) => Ok(()),
+        Err(_) => Err(validator::ValidationError::new("unknown_sbom_format")),
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, Validate)]
+pub struct JobSettings {
+    /// Background jobs one instance runs at the same time, whatever their kind
+    #[validate(range(min = 1, max = 64))]
+    pub concurrency: usize,
+    /// Attempts before a job is left failed
+    #[validate(range(min = 1, max = 20))]
+    pub max_attempts: i32,
+    /// Time one attempt may take
+    #[validate(range(min = 60, max = 86400))]
+    pub timeout_seconds: u64,
+}
+
+#[derive(Debug, Deserialize, Clone, Validate)]
+#[validate(schema(function = "validate_signature_settings"))]
+pub struct SignatureSettings {
+    /// The `cosign` executable
+    pub cosign_path: String,
+    /// PEM public keys (paths, or `k8s://`, `awskms://`, ... references) cosign signatures are verified against
+    pub public_keys: Vec<String>,
+    /// Regular expression the certificate identity of a keyless signature must match
+    pub keyless_identity: Option<String>,
+    /// OIDC issuer of keyless signing certificates, e.g. `https://token.actions.githubusercontent.com`
+    pub keyless_issuer: Option<String>,
+    /// Rekor transparency log checked for keyless signatures; the public instance when unset
+    pub rekor_url: Option<String>,
+    /// The `notation` executable
+    pub notation_path: String,
+    /// Directory whose `notation/` holds `trustpolicy.blob.json` and the trust store; Notation
+    /// signatures are not verified when unset
+    pub notation_config_home: Option<String>,
+    /// Blob trust policy Notation signatures are verified with; the policy marked global when unset
+    pub notation_policy_name: Option<String>,
+    /// Time allowed for one `cosign verify-blob` or `notation blob verify`
+    #[validate(range(min = 5, max = 600))]
+    pub timeout_seconds: u64,
+}
+
+impl SignatureSettings {
+    /// Whether any key, keyless policy or Notation trust policy is configured to verify signatures against
+    pub fn enabled(&self) -> bool {
+        !self.public_keys.is_empty() || self.keyless_identity.is_some() || self.notation_config_home.is_some()
+    }
+}
+
+fn validate_signature_settings(signatures: &SignatureSettings) -> Result<(), validator::ValidationError> {
+    match (&signatures.keyless_identity, &signatures.keyless_issuer) {
+        (Some(_), None) | (None, Some(_)) => Err(validator::ValidationError::new("incomplete_keyless_policy")),
+        _ => Ok(()),
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, Validate)]
+pub struct SecretScanSettings {
+    /// Look for credentials in the config of every pushed image
+    pub enabled: bool,
+    /// Also read the text files of its layers
+    pub scan_layers: bool,
+    /// Deny pushes of images with findings instead of only recording them
+    pub reject: bool,
+    /// Layers larger than this (compressed) are not read
+    #[validate(range(min = 1048576))]
+    pub max_layer_bytes: u64,
+    /// Files larger than this within a layer are not read
+    #[validate(range(min = 1024, max = 67108864))]
+    pub max_file_bytes: u64,
+}
+
+#[derive(Debug, Deserialize, Clone, Validate)]
+#[validate(schema(function = "validate_base_image_settings"))]
+pub struct BaseImageSettings {
+    /// Identify the base image of every pushed image and advise rebuilds
+    pub enabled: bool,
+    /// Hosts this registry is reached at, e.g. `registry.example.com`; base images named with one
+    /// of them are looked up in this registry
+    pub registry_hosts: Vec<String>,
+    /// Vulnerabilities of this severity or worse in the image an image was built on recommend a rebuild
+    pub advisory_severity: String,
+}
+
+fn validate_base_image_settings(base_images: &BaseImageSettings) -> Result<(), validator::ValidationError> {
+    match base_images.advisory_severity.parse::<crate::models::vulnerability::Severity>() {
+        Ok(_) => Ok(()),
+        Err(_) => Err(validator::ValidationError::new("unknown_advisory_severity")),
+    }
+}
+
+/// Configured values of the feature flags. Administrators can override each one at runtime; the
+/// overrides are stored in the database and win over these values.
+#[derive(Debug, Deserialize, Clone, PartialEq, Validate)]
+pub struct FeatureSettings {
+    /// Accept manifest and repository deletes
+    pub allow_delete: bool,
+    /// Let unauthenticated clients pull from public repositories
+    pub allow_anonymous_pull: bool,
+    /// Let anyone create an account through `POST /api/v1/auth/register`
+    pub open_registration: bool,
+    /// Reject every write except logins and changes to the feature flags, e.g. during maintenance
+    pub readonly_mode: bool,
+    /// How often overrides made on other replicas are picked up
+    #[validate(range(min = 1, max = 3600))]
+    pub refresh_interval_seconds: u64,
+}
+
+/// Where errors and panics are reported, besides the log
+#[derive(Debug, Deserialize, Clone, Validate)]
+pub struct ErrorReportingSettings {
+    /// Sentry DSN, e.g. `https://<key>@o0.ingest.sentry.io/<project>`; reporting is off when unset
+    #[validate(custom = "validate_sentry_dsn")]
+    pub dsn: Option<Secret<String>>,
+    /// Environment the reports are tagged with, e.g. `production` or `staging`
+    pub environment: String,
+    /// Release the reports are tagged with, `aerugo@<version>` by default
+    pub release: String,
+    /// Name of this instance in the reports, the host name by default
+    pub server_name: Option<String>,
+}
+
+/// How long startup waits for the database, object storage and cache to become reachable
+#[derive(Debug, Deserialize, Clone, Validate)]
+pub struct StartupSettings {
+    /// Connection attempts per dependency before startup fails; 1 disables retries
+    #[validate(range(min = 1, max = 1000))]
+    pub retry_attempts: u32,
+    /// Delay before the first retry, doubled for each following one
+    #[validate(range(min = 1))]
+    pub retry_initial_delay_ms: u64,
+    /// Longest delay between two attempts
+    #[validate(range(min = 1))]
+    pub retry_max_delay_ms: u64,
+    /// Fail startup when the SMTP transport cannot be set up, instead of starting without email
+    pub email_required: bool,
+}
+
+fn validate_sentry_dsn(dsn: &Secret<String>) -> Result<(), validator::ValidationError> {
+    match crate::error_reporting::Dsn::parse(dsn.expose_secret()) {
+        Ok(_) => Ok(()),
+        Err(_) => Err(validator::ValidationError::new("invalid_sentry_dsn")),
+    }
+}
@@ -0,0 +1,326 @@
+// src/config/sources.rs - Where the settings come from
+//
+// Settings are read from variables layered in memory: variables the process was started with win
+// over `.env`, which wins over the file named by `AERUGO_CONFIG`, and the defaults in
+// `Settings::from_variables` fill in the rest. The process environment itself is never modified,
+// so a reload reads the files again without touching it. The file is TOML, or YAML for a `.yml`,
+// `.yaml` or `.json` extension; its nested keys are joined with `_` and uppercased into the
+// variable names, so `[rate_limit] pull = 2000` sets `RATE_LIMIT_PULL` and a top-level
+// `DATABASE_URL` works as well. Lists become comma-separated values.
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+use serde_json::Value;
+use validator::{ValidationErrors, ValidationErrorsKind};
+
+/// Variable naming the configuration file
+pub const CONFIG_FILE_VAR: &str = "AERUGO_CONFIG";
+
+/// The variables settings are loaded from: the configuration file, then `.env`, then the process
+/// environment, each overriding the ones before. Reads both files again on every call.
+pub fn layered_variables() -> Result<BTreeMap<String, String>> {
+    let process: BTreeMap<String, String> = std::env::vars_os()
+        .filter_map(|(key, value)| Some((key.into_string().ok()?, value.into_string().ok()?)))
+        .collect();
+    let dotenv: BTreeMap<String, String> = dotenv::dotenv_iter()
+        .map(|entries| entries.flatten().collect())
+        .unwrap_or_default();
+
+    let path = process.get(CONFIG_FILE_VAR).or_else(|| dotenv.get(CONFIG_FILE_VAR));
+    let file = match path.filter(|path| !path.is_empty()) {
+        Some(path) => read_config_file(Path::new(path))
+            .with_context(|| format!("Failed to read configuration file {}", path))?,
+        None => BTreeMap::new(),
+    };
+    Ok(layer(file, dotenv, process))
+}
+
+/// Layer the variables of each source over the ones before it
+fn layer(
+    file: BTreeMap<String, String>,
+    dotenv: BTreeMap<String, String>,
+    process: BTreeMap<String, String>,
+) -> BTreeMap<String, String> {
+    let mut variables = file;
+    variables.extend(dotenv);
+    variables.extend(process);
+    variables
+}
+
+/// The variables a configuration file sets
+pub fn read_config_file(path: &Path) -> Result<BTreeMap<String, String>> {
+    let content = std::fs::read_to_string(path)?;
+    let document: Value = match path.extension().and_then(|ext| ext.to_str()) {
+        Some("yml" | "yaml" | "json") => serde_yaml::from_str(&content)?,
+        _ => toml::from_str(&content)?,
+    };
+    if !document.is_object() {
+        bail!("expected a table of settings");
+    }
+    let mut variables = BTreeMap::new();
+    flatten("", &document, &mut variables)?;
+    Ok(variables)
+}
+
+fn flatten(key: &str, value: &Value, variables: &mut BTreeMap<String, String>) -> Result<()> {
+    let value = match value {
+        Value::Null => return Ok(()),
+        Value::Object(table) => {
+            for (name, value) in table {
+                let name = name.to_uppercase().replace(['-', '.'], "_");
+                let nested = if key.is_empty() { name } else { format!("{}_{}", key, name) };
+                flatten(&nested, value, variables)?;
+            }
+            return Ok(());
+        }
+        Value::Array(items) => items.iter().map(scalar).collect::<Option<Vec<_>>>().map(|items| items.join(",")),
+        value => scalar(value),
+    };
+    match value {
+        Some(value) => variables.insert(key.to_string(), value),
+        None => bail!("{} must be a string, number, boolean or a list of those", key),
+    };
+    Ok(())
+}
+
+fn scalar(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Bool(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+/// Settings fields whose variable is not the section and field name joined, e.g. `RATE_LIMIT_PULL`
+/// for `rate_limit.pull_limit`
+const FIELD_VARIABLES: &[(&str, &str)] = &[
+    ("server.bind_address", "LISTEN_ADDRESS"),
+    ("server.api_prefix", "API_PREFIX"),
+    ("server.log_level", "LOG_LEVEL"),
+    ("server.log_format", "LOG_FORMAT"),
+    ("server.unix_socket_mode", "UNIX_SOCKET_MODE"),
+    ("server.port", "LISTEN_ADDRESS"),
+    ("database.port", "DATABASE_URL"),
+    ("cache.pool_size", "REDIS_POOL_SIZE"),
+    ("cache.redis_url", "REDIS_URL"),
+    ("cache.connect_timeout_ms", "REDIS_CONNECT_TIMEOUT_MS"),
+    ("cache.command_timeout_ms", "REDIS_COMMAND_TIMEOUT_MS"),
+    ("auth.jwt_expiration_seconds", "JWT_EXPIRATION_SECONDS"),
+    ("auth.refresh_token_expiration_seconds", "REFRESH_TOKEN_EXPIRATION_SECONDS"),
+    ("auth.argon2_memory_kib", "ARGON2_MEMORY_KIB"),
+    ("auth.argon2_iterations", "ARGON2_ITERATIONS"),
+    ("auth.argon2_parallelism", "ARGON2_PARALLELISM"),
+    ("auth.api_key_default_expiration_days", "API_KEY_DEFAULT_EXPIRATION_DAYS"),
+    ("auth.api_key_max_expiration_days", "API_KEY_MAX_EXPIRATION_DAYS"),
+    ("auth.admin_usernames", "ADMIN_USERNAMES"),
+    ("email.smtp_host", "SMTP_HOST"),
+    ("email.smtp_port", "SMTP_PORT"),
+    ("email.smtp_username", "SMTP_USERNAME"),
+    ("email.from_email", "FROM_EMAIL"),
+    ("email.from_name", "FROM_NAME"),
+    ("email.use_tls", "SMTP_USE_TLS"),
+    ("email.test_email_file", "EMAIL_TEST_FILE"),
+    ("webhooks.signing_key", "WEBHOOK_SIGNING_KEY"),
+    ("webhooks.allowed_networks", "WEBHOOK_ALLOWED_NETWORKS"),
+    ("transcode.enabled", "BLOB_TRANSCODE_ENABLED"),
+    ("transcode.zstd_level", "BLOB_TRANSCODE_ZSTD_LEVEL"),
+    ("transcode.interval_seconds", "BLOB_TRANSCODE_INTERVAL_SECONDS"),
+    ("transcode.batch_size", "BLOB_TRANSCODE_BATCH_SIZE"),
+    ("ip_access.enabled", "IP_ACCESS_RULES_ENABLED"),
+    ("ip_access.trusted_proxies", "TRUSTED_PROXIES"),
+    ("rate_limit.pull_limit", "RATE_LIMIT_PULL"),
+    ("rate_limit.push_limit", "RATE_LIMIT_PUSH"),
+    ("rate_limit.auth_limit", "RATE_LIMIT_AUTH"),
+    ("rate_limit.api_limit", "RATE_LIMIT_API"),
+    ("concurrency.request_timeout_seconds", "REQUEST_TIMEOUT_SECONDS"),
+    ("concurrency.upload_timeout_seconds", "UPLOAD_TIMEOUT_SECONDS"),
+    ("login_protection.max_failed_attempts", "LOGIN_MAX_FAILED_ATTEMPTS"),
+    ("login_protection.ip_max_failed_attempts", "LOGIN_IP_MAX_FAILED_ATTEMPTS"),
+    ("login_protection.lockout_seconds", "LOGIN_LOCKOUT_SECONDS"),
+    ("login_protection.base_delay_ms", "LOGIN_FAILURE_BASE_DELAY_MS"),
+    ("login_protection.max_delay_ms", "LOGIN_FAILURE_MAX_DELAY_MS"),
+    ("uploads.max_blob_bytes", "UPLOAD_MAX_BLOB_BYTES"),
+    ("uploads.max_open_sessions_per_user", "UPLOAD_MAX_SESSIONS_PER_USER"),
+    ("uploads.max_open_sessions_per_repository", "UPLOAD_MAX_SESSIONS_PER_REPOSITORY"),
+    ("uploads.session_expiry_seconds", "UPLOAD_SESSION_EXPIRY_SECONDS"),
+    ("uploads.max_request_bytes", "UPLOAD_MAX_REQUEST_BYTES"),
+    ("retention.audit_log_retention_days", "RETENTION_AUDIT_LOG_DAYS"),
+    ("retention.usage_retention_days", "RETENTION_USAGE_DAYS"),
+    ("retention.event_retention_days", "RETENTION_EVENT_DAYS"),
+    ("invitations.expiry_hours", "INVITATION_EXPIRY_HOURS"),
+    ("invitations.accept_url", "INVITATION_ACCEPT_URL"),
+    ("redirects.grace_days", "REPOSITORY_REDIRECT_GRACE_DAYS"),
+    ("cdn.provider", "CDN_PURGE_PROVIDER"),
+    ("scanning.enabled", "SCAN_ENABLED"),
+    ("scanning.trivy_path", "SCAN_TRIVY_PATH"),
+    ("scanning.trivy_server_url", "SCAN_TRIVY_SERVER_URL"),
+    ("scanning.trivy_token", "SCAN_TRIVY_TOKEN"),
+    ("scanning.registry_host", "SCAN_REGISTRY_HOST"),
+    ("scanning.registry_username", "SCAN_REGISTRY_USERNAME"),
+    ("scanning.registry_password", "SCAN_REGISTRY_PASSWORD"),
+    ("scanning.registry_insecure", "SCAN_REGISTRY_INSECURE"),
+    ("scanning.sbom_enabled", "SBOM_ENABLED"),
+    ("scanning.sbom_format", "SBOM_FORMAT"),
+    ("scanning.timeout_seconds", "SCAN_TIMEOUT_SECONDS"),
+    ("scanning.concurrency", "SCAN_CONCURRENCY"),
+    ("signatures.cosign_path", "COSIGN_PATH"),
+    ("signatures.public_keys", "SIGNATURE_PUBLIC_KEYS"),
+    ("signatures.keyless_identity", "SIGNATURE_KEYLESS_IDENTITY"),
+    ("signatures.keyless_issuer", "SIGNATURE_KEYLESS_ISSUER"),
+    ("signatures.rekor_url", "SIGNATURE_REKOR_URL"),
+    ("signatures.notation_path", "NOTATION_PATH"),
+    ("signatures.notation_config_home", "NOTATION_CONFIG_HOME"),
+    ("signatures.notation_policy_name", "NOTATION_POLICY_NAME"),
+    ("signatures.timeout_seconds", "SIGNATURE_TIMEOUT_SECONDS"),
+    ("secret_scan.scan_layers", "SECRET_SCAN_LAYERS"),
+    ("base_images.enabled", "BASE_IMAGE_TRACKING_ENABLED"),
+    ("base_images.registry_hosts", "BASE_IMAGE_REGISTRY_HOSTS"),
+    ("base_images.advisory_severity", "BASE_IMAGE_ADVISORY_SEVERITY"),
+];
+
+/// Variables behind the checks that span several fields of a section, by error code
+const CHECK_VARIABLES: &[(&str, &str)] = &[
+    ("tls_cert_without_key", "TLS_CERT_PATH/TLS_KEY_PATH"),
+    ("tls_files_and_acme", "TLS_CERT_PATH/TLS_ACME_DOMAINS"),
+    ("no_listeners", "LISTENERS"),
+    ("invalid_listener", "LISTENERS"),
+    ("invalid_unix_socket_mode", "UNIX_SOCKET_MODE"),
+    ("min_connections_above_max_connections", "DATABASE_MIN_CONNECTIONS"),
+    ("incomplete_cdn_settings", "CDN_PURGE_PROVIDER"),
+    ("unknown_cdn_provider", "CDN_PURGE_PROVIDER"),
+    ("incomplete_event_stream_settings", "EVENT_STREAM_BACKEND"),
+    ("unknown_event_stream_backend", "EVENT_STREAM_BACKEND"),
+    ("unknown_sbom_format", "SBOM_FORMAT"),
+    ("incomplete_keyless_policy", "SIGNATURE_KEYLESS_IDENTITY/SIGNATURE_KEYLESS_ISSUER"),
+    ("unknown_advisory_severity", "BASE_IMAGE_ADVISORY_SEVERITY"),
+];
+
+/// The variable that sets the field at `path` (e.g. `rate_limit.pull_limit`) or, for a check
+/// spanning a section (`tls.__all__`), the variables behind the failed check
+pub fn variable_for(path: &str, code: &str) -> String {
+    if path.ends_with("__all__") {
+        if let Some((_, variable)) = CHECK_VARIABLES.iter().find(|(check, _)| *check == code) {
+            return variable.to_string();
+        }
+    }
+    if path.starts_with("notifications.endpoints") {
+        return "NOTIFICATIONS_CONFIG_FILE".to_string();
+    }
+    let field = path.split('[').next().unwrap_or(path);
+    match FIELD_VARIABLES.iter().find(|(name, _)| *name == field) {
+        Some((_, variable)) => variable.to_string(),
+        None => field.replace('.', "_").to_uppercase(),
+    }
+}
+
+/// Every failed validation as `VARIABLE (path): code`, e.g.
+/// `RATE_LIMIT_PULL (rate_limit.pull_limit): range (min: 1, value: 0)`
+pub fn describe(errors: &ValidationErrors) -> Vec<String> {
+    let mut problems = Vec::new();
+    collect("", errors, &mut problems);
+    problems
+}
+
+fn collect(prefix: &str, errors: &ValidationErrors, problems: &mut Vec<String>) {
+    let mut fields: Vec<_> = errors.errors().iter().collect();
+    fields.sort_by_key(|(field, _)| *field);
+    for (field, kind) in fields {
+        let path = if prefix.is_empty() { field.to_string() } else { format!("{}.{}", prefix, field) };
+        match kind {
+            ValidationErrorsKind::Field(errors) => {
+                for error in errors {
+                    let mut params: Vec<_> = error.params.iter().collect();
+                    params.sort_by_key(|(name, _)| *name);
+                    let params: Vec<_> = params.into_iter().map(|(name, value)| format!("{}: {}", name, value)).collect();
+                    let mut problem = format!("{} ({}): {}", variable_for(&path, &error.code), path, error.code);
+                    if !params.is_empty() {
+                        problem.push_str(&format!(" ({})", params.join(", ")));
+                    }
+                    problems.push(problem);
+                }
+            }
+            ValidationErrorsKind::Struct(errors) => collect(&path, errors, problems),
+            ValidationErrorsKind::List(items) => {
+                for (index, errors) in items {
+                    collect(&format!("{}[{}]", path, index), errors, problems);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nested_keys_become_variable_names() {
+        let dir = std::env::temp_dir().join(format!("aerugo-config-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let toml = dir.join("aerugo.toml");
+        std::fs::write(
+            &toml,
+            "listen_address = \"0.0.0.0:8080\"\nlisteners = [\"0.0.0.0:5000=registry\", \"unix:/run/aerugo.sock\"]\n\n\
+             [database]\nurl = \"postgres://registry@db/aerugo\"\nmax_connections = 40\n\n\
+             [rate_limit]\nenabled = false\npull = 2000\n",
+        )
+        .unwrap();
+        let yaml = dir.join("aerugo.yaml");
+        std::fs::write(&yaml, "DATABASE_URL: postgres://registry@db/aerugo\nrate-limit:\n  pull: 2000\nsmtp:\n  host: ~\n").unwrap();
+
+        let variables = read_config_file(&toml).unwrap();
+        assert_eq!(variables["LISTEN_ADDRESS"], "0.0.0.0:8080");
+        assert_eq!(variables["LISTENERS"], "0.0.0.0:5000=registry,unix:/run/aerugo.sock");
+        assert_eq!(variables["DATABASE_URL"], "postgres://registry@db/aerugo");
+        assert_eq!(variables["DATABASE_MAX_CONNECTIONS"], "40");
+        assert_eq!(variables["RATE_LIMIT_ENABLED"], "false");
+        assert_eq!(variables["RATE_LIMIT_PULL"], "2000");
+
+        let variables = read_config_file(&yaml).unwrap();
+        assert_eq!(variables.len(), 2);
+        assert_eq!(variables["RATE_LIMIT_PULL"], "2000");
+
+        std::fs::write(&toml, "[federation]\npeers = [{ name = \"eu\" }]\n").unwrap();
+        assert!(read_config_file(&toml).is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn process_environment_wins_over_dotenv_and_the_file() {
+        let vars = |entries: &[(&str, &str)]| -> BTreeMap<String, String> {
+            entries.iter().map(|(key, value)| (key.to_string(), value.to_string())).collect()
+        };
+        let file = vars(&[("RATE_LIMIT_PULL", "2000"), ("LOG_LEVEL", "info"), ("LISTEN_ADDRESS", "0.0.0.0:8080")]);
+        let dotenv = vars(&[("LOG_LEVEL", "debug"), ("DATABASE_URL", "postgres://dev@localhost/aerugo")]);
+        let process = vars(&[("DATABASE_URL", "postgres://registry@db/aerugo")]);
+
+        let variables = layer(file, dotenv, process);
+        assert_eq!(variables["RATE_LIMIT_PULL"], "2000");
+        assert_eq!(variables["LISTEN_ADDRESS"], "0.0.0.0:8080");
+        assert_eq!(variables["LOG_LEVEL"], "debug");
+        assert_eq!(variables["DATABASE_URL"], "postgres://registry@db/aerugo");
+        assert_eq!(variables.len(), 4);
+    }
+
+    #[test]
+    fn failed_checks_name_their_variable() {
+        assert_eq!(variable_for("rate_limit.pull_limit", "range"), "RATE_LIMIT_PULL");
+        assert_eq!(variable_for("concurrency.max_uploads", "range"), "CONCURRENCY_MAX_UPLOADS");
+        assert_eq!(variable_for("server.__all__", "invalid_listener"), "LISTENERS");
+        assert_eq!(variable_for("notifications.endpoints[0].url", "url"), "NOTIFICATIONS_CONFIG_FILE");
+
+        let mut errors = ValidationErrors::new();
+        let mut error = validator::ValidationError::new("range");
+        error.add_param("min".into(), &1);
+        error.add_param("value".into(), &0);
+        errors.add("pull_limit", error);
+        let mut settings = ValidationErrors::new();
+        settings.errors_mut().insert("rate_limit", ValidationErrorsKind::Struct(Box::new(errors)));
+        assert_eq!(
+            describe(&settings),
+            vec!["RATE_LIMIT_PULL (rate_limit.pull_limit): range (min: 1, value: 0)".to_string()]
+        );
+    }
+}
@@ -71,6 +71,11 @@ pub struct Repository {
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
     pub created_by: Option<i64>,
+    /// Running totals, absent from queries that do not select them
+    #[sqlx(default)]
+    pub pull_count: i64,
+    #[sqlx(default)]
+    pub push_count: i64,
 }
 
 // Permission models
@@ -48,12 +48,14 @@ pub async fn create_blob_upload(
 
 pub async fn update_blob_upload_completed(
     pool: &PgPool,
+    repository_id: i64,
     uuid: &str,
 ) -> Result<()> {
     sqlx::query(
-        "UPDATE blob_uploads SET completed_at = NOW() WHERE uuid = $1"
+        "UPDATE blob_uploads SET completed_at = NOW() WHERE uuid = $1 AND repository_id = $2"
     )
     .bind(uuid)
+    .bind(repository_id)
     .execute(pool)
     .await
     .context("Failed to update blob upload completion")?;
@@ -61,6 +63,30 @@ pub async fn update_blob_upload_completed(
     Ok(())
 }
 
+/// Count unfinished, unexpired upload sessions in a repository: (held by `user_id`, total)
+pub async fn count_open_blob_uploads(
+    pool: &PgPool,
+    repository_id: i64,
+    user_id: Option<i64>,
+    expiry_seconds: i64,
+) -> Result<(i64, i64)> {
+    let counts = sqlx::query_as::<_, (i64, i64)>(
+        "SELECT COUNT(*) FILTER (WHERE user_id IS NOT DISTINCT FROM $2), COUNT(*)
+         FROM blob_uploads
+         WHERE repository_id = $1
+           AND completed_at IS NULL
+           AND created_at > NOW() - make_interval(secs => $3)",
+    )
+    .bind(repository_id)
+    .bind(user_id)
+    .bind(expiry_seconds as f64)
+    .fetch_one(pool)
+    .await
+    .context("Failed to count open blob uploads")?;
+
+    Ok(counts)
+}
+
 // Repository queries
 pub async fn repository_exists(
     pool: &PgPool,
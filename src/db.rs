@@ -1,17 +1,78 @@
-use crate::config::settings::Settings;
-use anyhow::{Context, Result};
-use sqlx::{postgres::PgPoolOptions, PgPool};
+use crate::config::settings::{DatabaseSettings, Settings};
+use crate::startup::{retry, Backoff};
+use anyhow::{bail, Context, Result};
+use secrecy::ExposeSecret;
+use sqlx::{
+    migrate::Migrator,
+    postgres::{PgConnectOptions, PgPoolOptions},
+    PgPool,
+};
+use std::str::FromStr;
 use std::time::Duration;
 
+/// Migrations embedded in this binary
+static MIGRATOR: Migrator = sqlx::migrate!("./migrations");
+
+/// Oldest schema version this release can upgrade from. Bump this when old
+/// migrations are squashed or removed, so databases that predate them are
+/// sent through an intermediate release instead of half-migrating.
+pub const MIN_SUPPORTED_SCHEMA_VERSION: i64 = 20250908000001;
+
+/// Newest schema version this release understands (its latest embedded migration)
+pub fn max_supported_schema_version() -> i64 {
+    MIGRATOR.iter().map(|m| m.version).max().unwrap_or(0)
+}
+
+/// Schema state of the connected database relative to this binary
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SchemaStatus {
+    /// Highest successfully applied migration, `None` for an empty database
+    pub current_version: Option<i64>,
+    /// Embedded migrations not yet applied
+    pub pending: usize,
+}
+
 pub async fn create_pool(settings: &Settings) -> Result<PgPool> {
-    // Create connection pool with configuration
-    let pool = PgPoolOptions::new()
-        .max_connections(settings.database.max_connections)
-        .min_connections(settings.database.min_connections)
-        .acquire_timeout(Duration::from_secs(30))
-        .idle_timeout(Duration::from_secs(60))
-        .max_lifetime(Duration::from_secs(3600))
-        .connect(&settings.database.connection_string())
+    let pool = connect_pool(settings).await?;
+
+    // Refuse to start against a schema this binary was not built for
+    let status = check_schema_compatibility(&pool).await?;
+
+    if status.pending > 0 {
+        if !settings.database.auto_migrate {
+            bail!(
+                "Database has {} pending migration(s) (schema version {}, this release expects {}) \
+                 and DATABASE_AUTO_MIGRATE is disabled. Run `aerugo migrate --up` to apply them.",
+                status.pending,
+                status.current_version.map(|v| v.to_string()).unwrap_or_else(|| "none".to_string()),
+                max_supported_schema_version()
+            );
+        }
+        run_migrations(&pool).await?;
+    }
+
+    // Test database connection
+    pool.acquire()
+        .await
+        .context("Failed to acquire a test database connection")?;
+
+    Ok(pool)
+}
+
+/// Open the connection pool without touching the schema, retrying while the database is
+/// unreachable as configured by `STARTUP_RETRY_*`
+pub async fn connect_pool(settings: &Settings) -> Result<PgPool> {
+    let backoff = Backoff::new(&settings.startup);
+    retry("Database", &backoff, || try_connect_pool(settings)).await
+}
+
+async fn try_connect_pool(settings: &Settings) -> Result<PgPool> {
+    let database = &settings.database;
+    let options = connect_options(&database.connection_string(), database)
+        .context("Invalid database connection settings")?;
+
+    let pool = pool_options(database)
+        .connect_with(options)
         .await
         .context("Failed to create database connection pool")?;
 
@@ -20,18 +81,182 @@ pub async fn create_pool(settings: &Settings) -> Result<PgPool> {
         .await
         .context("Failed to acquire initial database connection")?;
 
-    // Run migrations
-    sqlx::migrate!("./migrations")
-        .run(&pool)
+    Ok(pool)
+}
+
+/// Pool for `DATABASE_READ_URL`, or `None` when no replica is configured. Connections are
+/// opened on first use, so an unreachable replica never delays startup.
+pub fn connect_read_replica(settings: &Settings) -> Result<Option<PgPool>> {
+    let database = &settings.database;
+    let Some(url) = &database.read_url else {
+        return Ok(None);
+    };
+    let options = connect_options(url.expose_secret(), database)
+        .context("Invalid DATABASE_READ_URL")?;
+    Ok(Some(pool_options(database).connect_lazy_with(options)))
+}
+
+fn connect_options(url: &str, database: &DatabaseSettings) -> Result<PgConnectOptions> {
+    let mut options = PgConnectOptions::from_str(url)?;
+    if database.statement_timeout_ms > 0 {
+        let timeout = database.statement_timeout_ms.to_string();
+        options = options.options([("statement_timeout", timeout.as_str())]);
+    }
+    Ok(options)
+}
+
+fn pool_options(database: &DatabaseSettings) -> PgPoolOptions {
+    PgPoolOptions::new()
+        .max_connections(database.max_connections)
+        .min_connections(database.min_connections)
+        .acquire_timeout(Duration::from_secs(database.acquire_timeout_seconds))
+        .idle_timeout((database.idle_timeout_seconds > 0).then(|| Duration::from_secs(database.idle_timeout_seconds)))
+        .max_lifetime((database.max_lifetime_seconds > 0).then(|| Duration::from_secs(database.max_lifetime_seconds)))
+}
+
+/// Apply all pending embedded migrations
+pub async fn run_migrations(pool: &PgPool) -> Result<()> {
+    let mut conn = migration_connection(pool).await?;
+    MIGRATOR
+        .run(&mut conn)
         .await
         .context("Failed to run database migrations")?;
 
-    // Test database connection
-    pool.acquire()
+    Ok(())
+}
+
+/// Revert the `count` most recently applied migrations, newest first, returning their versions.
+/// Refuses before changing anything when one of them has no `.down.sql` migration.
+pub async fn revert_migrations(pool: &PgPool, count: usize) -> Result<Vec<i64>> {
+    let status = check_schema_compatibility(pool).await?;
+    if status.current_version.is_none() {
+        bail!("No migrations have been applied to this database");
+    }
+
+    let applied = sqlx::query_scalar::<_, i64>(
+        "SELECT version FROM _sqlx_migrations WHERE success = true ORDER BY version DESC",
+    )
+    .fetch_all(pool)
+    .await
+    .context("Failed to read applied migrations")?;
+    if count > applied.len() {
+        bail!("Cannot revert {} migration(s): only {} are applied", count, applied.len());
+    }
+
+    let reverted = applied[..count].to_vec();
+    let irreversible: Vec<String> = reverted
+        .iter()
+        .filter(|version| !MIGRATOR.iter().any(|m| m.version == **version && m.migration_type.is_down_migration()))
+        .map(|version| version.to_string())
+        .collect();
+    if !irreversible.is_empty() {
+        bail!(
+            "Migration(s) {} have no down migration and cannot be reverted. Restore a backup \
+             taken before they were applied instead.",
+            irreversible.join(", ")
+        );
+    }
+
+    // Everything newer than the newest migration that is kept is reverted
+    let target = applied.get(count).copied().unwrap_or(0);
+    let mut conn = migration_connection(pool).await?;
+    MIGRATOR
+        .undo(&mut conn, target)
         .await
-        .context("Failed to acquire a test database connection")?;
+        .context("Failed to revert database migrations")?;
 
-    Ok(pool)
+    Ok(reverted)
+}
+
+/// Migrations may rewrite large tables, so they run without DATABASE_STATEMENT_TIMEOUT_MS on a
+/// connection that is closed afterwards instead of going back to the pool
+async fn migration_connection(pool: &PgPool) -> Result<sqlx::PgConnection> {
+    let mut conn = pool
+        .acquire()
+        .await
+        .context("Failed to acquire a connection for migrations")?
+        .detach();
+    sqlx::query("SET statement_timeout = 0")
+        .execute(&mut conn)
+        .await
+        .context("Failed to lift the statement timeout for migrations")?;
+    Ok(conn)
+}
+
+/// Compare the applied migrations against the versions this binary supports.
+/// Fails with an explanatory error for a schema that is too new, too old, or
+/// left dirty by an interrupted migration.
+pub async fn check_schema_compatibility(pool: &PgPool) -> Result<SchemaStatus> {
+    let max_version = max_supported_schema_version();
+    let embedded: Vec<i64> = MIGRATOR
+        .iter()
+        .filter(|m| !m.migration_type.is_down_migration())
+        .map(|m| m.version)
+        .collect();
+
+    let has_migrations_table = sqlx::query_scalar::<_, bool>(
+        "SELECT to_regclass('_sqlx_migrations') IS NOT NULL",
+    )
+    .fetch_one(pool)
+    .await
+    .context("Failed to inspect database schema")?;
+
+    if !has_migrations_table {
+        return Ok(SchemaStatus {
+            current_version: None,
+            pending: embedded.len(),
+        });
+    }
+
+    let dirty = sqlx::query_scalar::<_, i64>(
+        "SELECT version FROM _sqlx_migrations WHERE success = false ORDER BY version LIMIT 1",
+    )
+    .fetch_optional(pool)
+    .await
+    .context("Failed to read applied migrations")?;
+
+    if let Some(version) = dirty {
+        bail!(
+            "Database migration {} did not complete. Repair the schema manually and remove the \
+             failed row from _sqlx_migrations before starting Aerugo.",
+            version
+        );
+    }
+
+    let applied = sqlx::query_scalar::<_, i64>(
+        "SELECT version FROM _sqlx_migrations WHERE success = true ORDER BY version",
+    )
+    .fetch_all(pool)
+    .await
+    .context("Failed to read applied migrations")?;
+
+    let current_version = applied.last().copied();
+
+    if let Some(current) = current_version {
+        if current > max_version {
+            bail!(
+                "Database schema version {} is newer than this Aerugo release supports (max {}). \
+                 Upgrade Aerugo, or restore a backup taken before the newer release migrated the database.",
+                current,
+                max_version
+            );
+        }
+        if current < MIN_SUPPORTED_SCHEMA_VERSION {
+            bail!(
+                "Database schema version {} is older than this Aerugo release can upgrade from (min {}). \
+                 Upgrade through an intermediate release first.",
+                current,
+                MIN_SUPPORTED_SCHEMA_VERSION
+            );
+        }
+    }
+
+    let pending = embedded.iter().filter(|v| !applied.contains(v)).count();
+
+    Ok(SchemaStatus {
+        current_version,
+        pending,
+    })
 }
 
 // Transaction helper function
@@ -1,4 +1,6 @@
 use crate::config::settings::EmailSettings;
+use crate::models::watch::WatchedPush;
+use crate::webhooks::chat::short_digest;
 use anyhow::{Context, Result};
 use chrono;
 use lettre::message::header::ContentType;
@@ -45,6 +47,12 @@ impl EmailService {
         Ok(Self { settings, mailer })
     }
 
+    /// A service whose sends fail, for starting without email when the SMTP transport cannot be
+    /// set up and `STARTUP_EMAIL_REQUIRED` is off
+    pub fn disabled(settings: EmailSettings) -> Self {
+        Self { settings, mailer: None }
+    }
+
     pub async fn send_forgot_password_email(
         &self,
         to_email: &str,
@@ -60,6 +68,95 @@ impl EmailService {
             .await
     }
 
+    /// Tell a user their account was locked after repeated failed logins
+    pub async fn send_account_locked_email(
+        &self,
+        to_email: &str,
+        to_name: &str,
+        failed_attempts: u64,
+        source_ip: &str,
+        lockout_minutes: u64,
+    ) -> Result<()> {
+        let subject = "Your Account Was Temporarily Locked - Aerugo ";
+        let html_body = self.generate_account_locked_html(to_name, failed_attempts, source_ip, lockout_minutes);
+        let text_body = self.generate_account_locked_text(to_name, failed_attempts, source_ip, lockout_minutes);
+
+        self.send_email(to_email, to_name, subject, &html_body, &text_body)
+            .await
+    }
+
+    /// Invite someone to join an organization; `accept_link` carries the signed invitation token
+    pub async fn send_organization_invitation_email(
+        &self,
+        to_email: &str,
+        org_name: &str,
+        inviter: &str,
+        role: &str,
+        accept_link: &str,
+        expiry_hours: i64,
+    ) -> Result<()> {
+        let subject = format!("You're Invited to Join {} - Aerugo ", org_name);
+        let html_body = self.generate_organization_invitation_html(org_name, inviter, role, accept_link, expiry_hours);
+        let text_body = self.generate_organization_invitation_text(org_name, inviter, role, accept_link, expiry_hours);
+
+        self.send_email(to_email, to_email, &subject, &html_body, &text_body)
+            .await
+    }
+
+    /// Tell an organization owner that content in one of its repositories was taken down;
+    /// `content` names the repository or the digest within it
+    pub async fn send_content_takedown_email(
+        &self,
+        to_email: &str,
+        to_name: &str,
+        content: &str,
+        reason: &str,
+        notice_reference: Option<&str>,
+    ) -> Result<()> {
+        let subject = "Content Taken Down - Aerugo ";
+        let reference = notice_reference.unwrap_or("none");
+        let html_body = self.generate_content_takedown_html(to_name, content, reason, reference);
+        let text_body = self.generate_content_takedown_text(to_name, content, reason, reference);
+
+        self.send_email(to_email, to_name, subject, &html_body, &text_body)
+            .await
+    }
+
+    /// Tell an organization owner that previously taken-down content is served again
+    pub async fn send_content_reinstated_email(
+        &self,
+        to_email: &str,
+        to_name: &str,
+        content: &str,
+        note: &str,
+    ) -> Result<()> {
+        let subject = "Content Reinstated - Aerugo ";
+        let html_body = self.generate_content_reinstated_html(to_name, content, note);
+        let text_body = self.generate_content_reinstated_text(to_name, content, note);
+
+        self.send_email(to_email, to_name, subject, &html_body, &text_body)
+            .await
+    }
+
+    /// Tell a user about tags pushed to repositories they watch, one push or a daily digest
+    pub async fn send_watched_pushes_email(
+        &self,
+        to_email: &str,
+        to_name: &str,
+        pushes: &[WatchedPush],
+        digest: bool,
+    ) -> Result<()> {
+        let subject = match (digest, pushes) {
+            (false, [push]) => format!("{}:{} Was Pushed - Aerugo ", push.repository, push.tag),
+            _ => format!("{} New Tags in Watched Repositories - Aerugo ", pushes.len()),
+        };
+        let html_body = self.generate_watched_pushes_html(to_name, pushes);
+        let text_body = self.generate_watched_pushes_text(to_name, pushes);
+
+        self.send_email(to_email, to_name, &subject, &html_body, &text_body)
+            .await
+    }
+
     async fn send_email(
         &self,
         to_email: &str,
@@ -241,4 +338,366 @@ This email was sent from an automated system. Please do not reply."#,
             to_name, reset_token
         )
     }
-}
\ No newline at end of file
+
+    fn generate_account_locked_html(
+        &self,
+        to_name: &str,
+        failed_attempts: u64,
+        source_ip: &str,
+        lockout_minutes: u64,
+    ) -> String {
+        format!(
+            r#"<!DOCTYPE html>
+<html>
+<head>
+    <meta charset="utf-8">
+    <meta name="viewport" content="width=device-width, initial-scale=1">
+    <title>Account Temporarily Locked</title>
+    <style>
+        body {{ font-family: Arial, sans-serif; line-height: 1.6; color: #333; max-width: 600px; margin: 0 auto; padding: 20px; }}
+        .container {{ background: #f9f9f9; padding: 30px; border-radius: 10px; }}
+        .header {{ background: #dc3545; color: white; padding: 20px; text-align: center; border-radius: 5px; margin-bottom: 30px; }}
+        .footer {{ color: #666; font-size: 12px; margin-top: 30px; text-align: center; }}
+    </style>
+</head>
+<body>
+    <div class="container">
+        <div class="header">
+            <h1>🔒 Aerugo</h1>
+            <p>Account Temporarily Locked</p>
+        </div>
+        
+        <h2>Hello {}!</h2>
+        
+        <p>We locked your Aerugo account after <strong>{}</strong> failed login attempts. The last attempt came from <strong>{}</strong>.</p>
+        
+        <p>You can log in again in {} minutes.</p>
+        
+        <p><strong>If this wasn't you:</strong></p>
+        <ul>
+            <li>Someone may be trying to guess your password</li>
+            <li>Once the lock expires, reset your password using "Forgot password"</li>
+            <li>Review your active sessions and API keys</li>
+        </ul>
+        
+        <div class="footer">
+            <p>© 2025 Aerugo  - Decenter.ai</p>
+            <p>This email was sent from an automated system. Please do not reply.</p>
+        </div>
+    </div>
+</body>
+</html>"#,
+            to_name, failed_attempts, source_ip, lockout_minutes
+        )
+    }
+
+    fn generate_account_locked_text(
+        &self,
+        to_name: &str,
+        failed_attempts: u64,
+        source_ip: &str,
+        lockout_minutes: u64,
+    ) -> String {
+        format!(
+            r#"Hello {}!
+
+We locked your Aerugo account after {} failed login attempts. The last attempt came from {}.
+
+You can log in again in {} minutes.
+
+IF THIS WASN'T YOU:
+- Someone may be trying to guess your password
+- Once the lock expires, reset your password using "Forgot password"
+- Review your active sessions and API keys
+
+© 2025 Aerugo  - Decenter.ai
+This email was sent from an automated system. Please do not reply."#,
+            to_name, failed_attempts, source_ip, lockout_minutes
+        )
+    }
+
+    fn generate_organization_invitation_html(
+        &self,
+        org_name: &str,
+        inviter: &str,
+        role: &str,
+        accept_link: &str,
+        expiry_hours: i64,
+    ) -> String {
+        format!(
+            r#"<!DOCTYPE html>
+<html>
+<head>
+    <meta charset="utf-8">
+    <meta name="viewport" content="width=device-width, initial-scale=1">
+    <title>Organization Invitation</title>
+    <style>
+        body {{ font-family: Arial, sans-serif; line-height: 1.6; color: #333; max-width: 600px; margin: 0 auto; padding: 20px; }}
+        .container {{ background: #f9f9f9; padding: 30px; border-radius: 10px; }}
+        .header {{ background: #28a745; color: white; padding: 20px; text-align: center; border-radius: 5px; margin-bottom: 30px; }}
+        .button {{ display: inline-block; background: #28a745; color: white; padding: 12px 30px; text-decoration: none; border-radius: 5px; margin: 20px 0; }}
+        .footer {{ color: #666; font-size: 12px; margin-top: 30px; text-align: center; }}
+    </style>
+</head>
+<body>
+    <div class="container">
+        <div class="header">
+            <h1>✉️ Aerugo</h1>
+            <p>Organization Invitation</p>
+        </div>
+        
+        <h2>Hello!</h2>
+        
+        <p><strong>{}</strong> invited you to join <strong>{}</strong> on Aerugo as a <strong>{}</strong>.</p>
+        
+        <p style="text-align: center;">
+            <a href="{}" class="button">View Invitation</a>
+        </p>
+        
+        <p>The link expires in {} hours. Log in with, or sign up using, this email address to accept the invitation. If you weren't expecting it, you can decline or simply ignore this email.</p>
+        
+        <div class="footer">
+            <p>© 2025 Aerugo  - Decenter.ai</p>
+            <p>This email was sent from an automated system. Please do not reply.</p>
+        </div>
+    </div>
+</body>
+</html>"#,
+            inviter, org_name, role, accept_link, expiry_hours
+        )
+    }
+
+    fn generate_organization_invitation_text(
+        &self,
+        org_name: &str,
+        inviter: &str,
+        role: &str,
+        accept_link: &str,
+        expiry_hours: i64,
+    ) -> String {
+        format!(
+            r#"Hello!
+
+{} invited you to join {} on Aerugo as a {}.
+
+View the invitation: {}
+
+The link expires in {} hours. Log in with, or sign up using, this email address to accept the invitation. If you weren't expecting it, you can decline or simply ignore this email.
+
+© 2025 Aerugo  - Decenter.ai
+This email was sent from an automated system. Please do not reply."#,
+            inviter, org_name, role, accept_link, expiry_hours
+        )
+    }
+
+    fn generate_content_takedown_html(
+        &self,
+        to_name: &str,
+        content: &str,
+        reason: &str,
+        reference: &str,
+    ) -> String {
+        format!(
+            r#"<!DOCTYPE html>
+<html>
+<head>
+    <meta charset="utf-8">
+    <meta name="viewport" content="width=device-width, initial-scale=1">
+    <title>Content Taken Down</title>
+    <style>
+        body {{ font-family: Arial, sans-serif; line-height: 1.6; color: #333; max-width: 600px; margin: 0 auto; padding: 20px; }}
+        .container {{ background: #f9f9f9; padding: 30px; border-radius: 10px; }}
+        .header {{ background: #dc3545; color: white; padding: 20px; text-align: center; border-radius: 5px; margin-bottom: 30px; }}
+        .footer {{ color: #666; font-size: 12px; margin-top: 30px; text-align: center; }}
+    </style>
+</head>
+<body>
+    <div class="container">
+        <div class="header">
+            <h1>⚖️ Aerugo</h1>
+            <p>Content Taken Down</p>
+        </div>
+        
+        <h2>Hello {}!</h2>
+        
+        <p>A registry administrator took down <strong>{}</strong>. Pulls of this content now fail with a policy error.</p>
+        
+        <p><strong>Reason:</strong> {}</p>
+        <p><strong>Notice reference:</strong> {}</p>
+        
+        <p>A copy of the content was retained as evidence. If you believe this was a mistake, contact the registry operators and quote the notice reference.</p>
+        
+        <div class="footer">
+            <p>© 2025 Aerugo  - Decenter.ai</p>
+            <p>This email was sent from an automated system. Please do not reply.</p>
+        </div>
+    </div>
+</body>
+</html>"#,
+            to_name, content, reason, reference
+        )
+    }
+
+    fn generate_content_takedown_text(
+        &self,
+        to_name: &str,
+        content: &str,
+        reason: &str,
+        reference: &str,
+    ) -> String {
+        format!(
+            r#"Hello {}!
+
+A registry administrator took down {}. Pulls of this content now fail with a policy error.
+
+Reason: {}
+Notice reference: {}
+
+A copy of the content was retained as evidence. If you believe this was a mistake, contact the registry operators and quote the notice reference.
+
+© 2025 Aerugo  - Decenter.ai
+This email was sent from an automated system. Please do not reply."#,
+            to_name, content, reason, reference
+        )
+    }
+
+    fn generate_content_reinstated_html(&self, to_name: &str, content: &str, note: &str) -> String {
+        format!(
+            r#"<!DOCTYPE html>
+<html>
+<head>
+    <meta charset="utf-8">
+    <meta name="viewport" content="width=device-width, initial-scale=1">
+    <title>Content Reinstated</title>
+    <style>
+        body {{ font-family: Arial, sans-serif; line-height: 1.6; color: #333; max-width: 600px; margin: 0 auto; padding: 20px; }}
+        .container {{ background: #f9f9f9; padding: 30px; border-radius: 10px; }}
+        .header {{ background: #28a745; color: white; padding: 20px; text-align: center; border-radius: 5px; margin-bottom: 30px; }}
+        .footer {{ color: #666; font-size: 12px; margin-top: 30px; text-align: center; }}
+    </style>
+</head>
+<body>
+    <div class="container">
+        <div class="header">
+            <h1>⚖️ Aerugo</h1>
+            <p>Content Reinstated</p>
+        </div>
+        
+        <h2>Hello {}!</h2>
+        
+        <p>The takedown of <strong>{}</strong> was lifted and the content can be pulled again.</p>
+        
+        <p><strong>Note:</strong> {}</p>
+        
+        <div class="footer">
+            <p>© 2025 Aerugo  - Decenter.ai</p>
+            <p>This email was sent from an automated system. Please do not reply.</p>
+        </div>
+    </div>
+</body>
+</html>"#,
+            to_name, content, note
+        )
+    }
+
+    fn generate_content_reinstated_text(&self, to_name: &str, content: &str, note: &str) -> String {
+        format!(
+            r#"Hello {}!
+
+The takedown of {} was lifted and the content can be pulled again.
+
+Note: {}
+
+© 2025 Aerugo  - Decenter.ai
+This email was sent from an automated system. Please do not reply."#,
+            to_name, content, note
+        )
+    }
+
+    fn generate_watched_pushes_html(&self, to_name: &str, pushes: &[WatchedPush]) -> String {
+        let rows: String = pushes
+            .iter()
+            .map(|push| {
+                format!(
+                    r#"            <tr><td><strong>{}:{}</strong></td><td style="font-family: monospace;">{}</td><td>{}</td><td>{}</td></tr>
+"#,
+                    push.repository,
+                    push.tag,
+                    short_digest(&push.digest),
+                    push.pushed_by.as_deref().unwrap_or("-"),
+                    push.pushed_at.format("%Y-%m-%d %H:%M UTC")
+                )
+            })
+            .collect();
+        format!(
+            r#"<!DOCTYPE html>
+<html>
+<head>
+    <meta charset="utf-8">
+    <meta name="viewport" content="width=device-width, initial-scale=1">
+    <title>New Tags in Watched Repositories</title>
+    <style>
+        body {{ font-family: Arial, sans-serif; line-height: 1.6; color: #333; max-width: 600px; margin: 0 auto; padding: 20px; }}
+        .container {{ background: #f9f9f9; padding: 30px; border-radius: 10px; }}
+        .header {{ background: #007bff; color: white; padding: 20px; text-align: center; border-radius: 5px; margin-bottom: 30px; }}
+        table {{ width: 100%; border-collapse: collapse; }}
+        th, td {{ text-align: left; padding: 6px; border-bottom: 1px solid #ddd; }}
+        .footer {{ color: #666; font-size: 12px; margin-top: 30px; text-align: center; }}
+    </style>
+</head>
+<body>
+    <div class="container">
+        <div class="header">
+            <h1>📦 Aerugo</h1>
+            <p>New Tags in Watched Repositories</p>
+        </div>
+        
+        <h2>Hello {}!</h2>
+        
+        <p>These tags were pushed to repositories you watch:</p>
+        
+        <table>
+            <tr><th>Tag</th><th>Digest</th><th>Pushed by</th><th>Pushed at</th></tr>
+{}        </table>
+        
+        <p>You can stop watching a repository, or switch to a daily digest in your notification preferences.</p>
+        
+        <div class="footer">
+            <p>© 2025 Aerugo  - Decenter.ai</p>
+            <p>This email was sent from an automated system. Please do not reply.</p>
+        </div>
+    </div>
+</body>
+</html>"#,
+            to_name, rows
+        )
+    }
+
+    fn generate_watched_pushes_text(&self, to_name: &str, pushes: &[WatchedPush]) -> String {
+        let lines: String = pushes
+            .iter()
+            .map(|push| {
+                format!(
+                    "- {}:{} ({}) pushed by {} at {}\n",
+                    push.repository,
+                    push.tag,
+                    short_digest(&push.digest),
+                    push.pushed_by.as_deref().unwrap_or("-"),
+                    push.pushed_at.format("%Y-%m-%d %H:%M UTC")
+                )
+            })
+            .collect();
+        format!(
+            r#"Hello {}!
+
+These tags were pushed to repositories you watch:
+
+{}
+You can stop watching a repository, or switch to a daily digest in your notification preferences.
+
+© 2025 Aerugo  - Decenter.ai
+This email was sent from an automated system. Please do not reply."#,
+            to_name, lines
+        )
+    }
+}
@@ -0,0 +1,329 @@
+// src/error_reporting.rs - Shipping errors and panics to an error tracker
+//
+// Every event logged at ERROR level is also handed to an `ErrorReporter`; the one built in sends
+// them to Sentry, or anything speaking its store API such as GlitchTip, when
+// `ERROR_REPORTING_DSN` is set. Reports carry the release and environment, and for an error
+// logged while handling a request, the fields of its `request` span: request id, method, route,
+// user and repository. A failed request is reported once, by the first error logged while
+// handling it, or otherwise by the `request failed` event logged for its 5xx response. Panics are
+// logged as errors, so they are reported the same way, and a panicking handler answers 500.
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{bail, Context as _, Result};
+use chrono::{DateTime, Utc};
+use secrecy::ExposeSecret;
+use serde_json::json;
+use tokio::sync::mpsc;
+use tracing::{
+    field::{Field, Visit},
+    span::{Attributes, Id, Record},
+    Event, Level, Subscriber,
+};
+use tracing_subscriber::{layer::Context, registry::LookupSpan, Layer};
+use url::Url;
+
+use crate::config::settings::ErrorReportingSettings;
+
+/// Name of the span `logging::request_span` runs each request in
+const REQUEST_SPAN: &str = "request";
+
+/// Reports waiting to be sent; more are dropped until the queue drains
+const QUEUE_SIZE: usize = 256;
+
+/// An error logged by the application
+#[derive(Debug, Clone)]
+pub struct ErrorReport {
+    pub message: String,
+    /// Module that logged the error
+    pub target: String,
+    /// Fields of the event other than the message
+    pub fields: BTreeMap<String, String>,
+    /// Fields of the request span the error was logged in; empty outside requests
+    pub request: BTreeMap<String, String>,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// Destination of error reports. `report` is called while the error is being logged, so it must
+/// not block or log at ERROR level itself.
+pub trait ErrorReporter: Send + Sync + 'static {
+    fn report(&self, report: ErrorReport);
+}
+
+/// Layer handing ERROR events to a reporter
+pub struct ReportingLayer {
+    reporter: Arc<dyn ErrorReporter>,
+}
+
+impl ReportingLayer {
+    pub fn new(reporter: Arc<dyn ErrorReporter>) -> Self {
+        Self { reporter }
+    }
+}
+
+/// Recorded fields of a request span, and whether an error of that request was reported
+#[derive(Default)]
+struct RequestFields {
+    fields: BTreeMap<String, String>,
+    reported: bool,
+}
+
+struct FieldVisitor<'a>(&'a mut BTreeMap<String, String>);
+
+impl Visit for FieldVisitor<'_> {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name().to_string(), value.to_string());
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.0.insert(field.name().to_string(), format!("{:?}", value));
+    }
+}
+
+impl<S> Layer<S> for ReportingLayer
+where
+    S: Subscriber + for<'span> LookupSpan<'span>,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        if attrs.metadata().name() != REQUEST_SPAN {
+            return;
+        }
+        if let Some(span) = ctx.span(id) {
+            let mut request = RequestFields::default();
+            attrs.record(&mut FieldVisitor(&mut request.fields));
+            span.extensions_mut().insert(request);
+        }
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, ctx: Context<'_, S>) {
+        if let Some(span) = ctx.span(id) {
+            if let Some(request) = span.extensions_mut().get_mut::<RequestFields>() {
+                values.record(&mut FieldVisitor(&mut request.fields));
+            }
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        if *event.metadata().level() != Level::ERROR {
+            return;
+        }
+
+        let mut request = BTreeMap::new();
+        let span = ctx
+            .event_scope(event)
+            .and_then(|mut scope| scope.find(|span| span.name() == REQUEST_SPAN));
+        if let Some(span) = span {
+            let mut extensions = span.extensions_mut();
+            if let Some(fields) = extensions.get_mut::<RequestFields>() {
+                if fields.reported {
+                    return;
+                }
+                fields.reported = true;
+                request = fields.fields.clone();
+            }
+        }
+
+        let mut fields = BTreeMap::new();
+        event.record(&mut FieldVisitor(&mut fields));
+        self.reporter.report(ErrorReport {
+            message: fields.remove("message").unwrap_or_default(),
+            target: event.metadata().target().to_string(),
+            fields,
+            request,
+            timestamp: Utc::now(),
+        });
+    }
+}
+
+/// The reporting layer for the configured destination, or `None` when reporting is off
+pub fn layer(settings: &ErrorReportingSettings) -> Result<Option<ReportingLayer>> {
+    let Some(dsn) = &settings.dsn else {
+        return Ok(None);
+    };
+    let reporter = SentryReporter::start(Dsn::parse(dsn.expose_secret())?, settings)?;
+    Ok(Some(ReportingLayer::new(Arc::new(reporter))))
+}
+
+/// Log panics as errors, so they are reported, before the previous hook prints them
+pub fn install_panic_hook() {
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let payload = info
+            .payload()
+            .downcast_ref::<&str>()
+            .map(|s| s.to_string())
+            .or_else(|| info.payload().downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "Box<dyn Any>".to_string());
+        let location = info.location().map(|l| l.to_string()).unwrap_or_default();
+        let backtrace = std::backtrace::Backtrace::force_capture();
+        tracing::error!(location = %location, backtrace = %backtrace, "panicked: {}", payload);
+        previous(info);
+    }));
+}
+
+/// Where a Sentry DSN sends events and the key it authenticates with
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Dsn {
+    pub store_url: Url,
+    pub public_key: String,
+}
+
+impl Dsn {
+    /// Parse `<scheme>://<public key>@<host>[/<path>]/<project id>`
+    pub fn parse(dsn: &str) -> Result<Self> {
+        let url = Url::parse(dsn).context("Invalid DSN")?;
+        if url.username().is_empty() {
+            bail!("DSN has no public key");
+        }
+        let Some(host) = url.host_str() else {
+            bail!("DSN has no host");
+        };
+        let mut segments: Vec<&str> = url.path_segments().map(|s| s.collect()).unwrap_or_default();
+        let project = match segments.pop() {
+            Some(project) if !project.is_empty() => project,
+            _ => bail!("DSN has no project id"),
+        };
+        let port = url.port().map(|port| format!(":{}", port)).unwrap_or_default();
+        let prefix: String = segments.iter().map(|segment| format!("/{}", segment)).collect();
+        let store_url = Url::parse(&format!("{}://{}{}{}/api/{}/store/", url.scheme(), host, port, prefix, project))?;
+        Ok(Self { store_url, public_key: url.username().to_string() })
+    }
+}
+
+/// Sends reports to Sentry's store API from a background task
+pub struct SentryReporter {
+    queue: mpsc::Sender<ErrorReport>,
+}
+
+/// What every report from this instance is tagged with
+struct Tags {
+    release: String,
+    environment: String,
+    server_name: Option<String>,
+}
+
+impl SentryReporter {
+    /// Start the task sending the reports; must run inside the Tokio runtime
+    pub fn start(dsn: Dsn, settings: &ErrorReportingSettings) -> Result<Self> {
+        let client = reqwest::Client::builder().timeout(Duration::from_secs(10)).build()?;
+        let auth = format!(
+            "Sentry sentry_version=7, sentry_key={}, sentry_client=aerugo/{}",
+            dsn.public_key,
+            env!("CARGO_PKG_VERSION")
+        );
+        let tags = Tags {
+            release: settings.release.clone(),
+            environment: settings.environment.clone(),
+            server_name: settings.server_name.clone(),
+        };
+        let (queue, mut reports) = mpsc::channel(QUEUE_SIZE);
+
+        tokio::spawn(async move {
+            while let Some(report) = reports.recv().await {
+                let sent = client
+                    .post(dsn.store_url.clone())
+                    .header("X-Sentry-Auth", &auth)
+                    .json(&sentry_event(&report, &tags))
+                    .send()
+                    .await
+                    .and_then(|response| response.error_for_status());
+                if let Err(e) = sent {
+                    tracing::warn!("Failed to send an error report to {}: {}", dsn.store_url.host_str().unwrap_or_default(), e);
+                }
+            }
+        });
+        Ok(Self { queue })
+    }
+}
+
+impl ErrorReporter for SentryReporter {
+    fn report(&self, report: ErrorReport) {
+        // A full queue means the tracker is slow or down; the error is still in the log
+        let _ = self.queue.try_send(report);
+    }
+}
+
+fn sentry_event(report: &ErrorReport, tags: &Tags) -> serde_json::Value {
+    let mut event_tags = BTreeMap::new();
+    for key in ["request_id", "method", "route", "repository"] {
+        if let Some(value) = report.request.get(key) {
+            event_tags.insert(key, value.clone());
+        }
+    }
+    json!({
+        "event_id": uuid::Uuid::new_v4().simple().to_string(),
+        "timestamp": report.timestamp.to_rfc3339(),
+        "level": "error",
+        "platform": "rust",
+        "logger": report.target,
+        "message": report.message,
+        "release": tags.release,
+        "environment": tags.environment,
+        "server_name": tags.server_name,
+        "transaction": report.request.get("route"),
+        "user": report.request.get("user_id").map(|id| json!({ "id": id })),
+        "tags": event_tags,
+        "extra": report.fields,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    #[derive(Default)]
+    struct Collected(Mutex<Vec<ErrorReport>>);
+
+    impl ErrorReporter for Collected {
+        fn report(&self, report: ErrorReport) {
+            self.0.lock().unwrap().push(report);
+        }
+    }
+
+    #[test]
+    fn reports_each_failed_request_once_with_its_context() {
+        let collected = Arc::new(Collected::default());
+        let subscriber = tracing_subscriber::registry().with(ReportingLayer::new(collected.clone()));
+        tracing::subscriber::with_default(subscriber, || {
+            let span = tracing::info_span!("request", request_id = "req-1", route = "/v2/", user_id = tracing::field::Empty);
+            span.in_scope(|| {
+                tracing::Span::current().record("user_id", 42);
+                tracing::warn!("slow storage");
+                tracing::error!(error = "connection reset", "Failed to store blob");
+                tracing::error!(status = 500, "request failed");
+            });
+            tracing::info_span!("request", request_id = "req-2").in_scope(|| {
+                tracing::error!(status = 503, "request failed");
+            });
+            tracing::error!("Blob GC failed");
+        });
+
+        let reports = collected.0.lock().unwrap();
+        assert_eq!(reports.len(), 3);
+        assert_eq!(reports[0].message, "Failed to store blob");
+        assert_eq!(reports[0].fields["error"], "connection reset");
+        assert_eq!(reports[0].request["request_id"], "req-1");
+        assert_eq!(reports[0].request["user_id"], "42");
+        assert_eq!(reports[1].message, "request failed");
+        assert_eq!(reports[1].request["request_id"], "req-2");
+        assert!(reports[2].request.is_empty());
+    }
+
+    #[test]
+    fn dsn_points_at_the_store_endpoint() {
+        let dsn = Dsn::parse("https://abc123@o42.ingest.sentry.io/7").unwrap();
+        assert_eq!(dsn.store_url.as_str(), "https://o42.ingest.sentry.io/api/7/store/");
+        assert_eq!(dsn.public_key, "abc123");
+
+        let dsn = Dsn::parse("http://key@glitchtip.internal:8000/errors/3").unwrap();
+        assert_eq!(dsn.store_url.as_str(), "http://glitchtip.internal:8000/errors/api/3/store/");
+
+        assert!(Dsn::parse("https://o42.ingest.sentry.io/7").is_err());
+        assert!(Dsn::parse("https://abc123@o42.ingest.sentry.io/").is_err());
+        assert!(Dsn::parse("not a dsn").is_err());
+    }
+}
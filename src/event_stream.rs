@@ -0,0 +1,202 @@
+// src/event_stream.rs - Publishing registry events to NATS JetStream or Kafka
+//
+// Follows the process's log stream like the webhook dispatcher and publishes every audit event,
+// in order, to the configured broker: a JetStream subject `<prefix>.<action>` or a Kafka topic
+// keyed by repository. Unlike webhooks the broker keeps the stream, so build pipelines and
+// provenance stores can replay it from their own position. Failed publishes are retried a few
+// times with backoff and then dropped with an error in the log.
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use secrecy::ExposeSecret;
+use serde::Serialize;
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::config::settings::EventStreamSettings;
+use crate::log_stream::{LogEvent, LogEventKind, LogFilter};
+use crate::AppState;
+
+/// Attempts per event before it is dropped
+const MAX_ATTEMPTS: u32 = 5;
+
+/// Body of one published event
+#[derive(Debug, Serialize)]
+pub struct StreamEvent<'a> {
+    /// Unique per event, usable to deduplicate redeliveries
+    pub id: String,
+    /// Dotted action name: `manifest.push`, `tag.delete`, `organization.member.role`, ...
+    pub event: &'a str,
+    pub timestamp: DateTime<Utc>,
+    pub organization_id: Option<i64>,
+    /// `namespace/repository` the event concerns
+    pub repository: Option<&'a str>,
+    pub user_id: Option<i64>,
+    pub detail: Option<&'a str>,
+}
+
+impl<'a> StreamEvent<'a> {
+    fn from_log_event(event: &'a LogEvent) -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            event: &event.action,
+            timestamp: event.timestamp,
+            organization_id: event.organization_id,
+            repository: event.repository.as_deref(),
+            user_id: event.user_id,
+            detail: event.detail.as_deref(),
+        }
+    }
+}
+
+/// JetStream subject of an action
+fn subject_for(prefix: &str, action: &str) -> String {
+    format!("{}.{}", prefix.trim_end_matches('.'), action)
+}
+
+enum Publisher {
+    Nats {
+        jetstream: async_nats::jetstream::Context,
+        subject_prefix: String,
+    },
+    Kafka {
+        partition: rskafka::client::partition::PartitionClient,
+    },
+}
+
+impl Publisher {
+    async fn from_settings(settings: &EventStreamSettings) -> Result<Option<Self>> {
+        let publisher = match settings.backend.as_deref() {
+            None => return Ok(None),
+            Some("nats") => {
+                let mut options = async_nats::ConnectOptions::new().name("aerugo");
+                if let Some(token) = &settings.nats_token {
+                    options = options.token(token.expose_secret().clone());
+                }
+                let client = options.connect(&settings.nats_url).await.context("Failed to connect to NATS")?;
+                let jetstream = async_nats::jetstream::new(client);
+                jetstream
+                    .get_or_create_stream(async_nats::jetstream::stream::Config {
+                        name: settings.nats_stream.clone(),
+                        subjects: vec![format!("{}.>", settings.subject_prefix.trim_end_matches('.'))],
+                        ..Default::default()
+                    })
+                    .await
+                    .with_context(|| format!("Failed to open JetStream stream {}", settings.nats_stream))?;
+                Publisher::Nats { jetstream, subject_prefix: settings.subject_prefix.clone() }
+            }
+            Some("kafka") => {
+                let client = rskafka::client::ClientBuilder::new(settings.kafka_brokers.clone())
+                    .build()
+                    .await
+                    .context("Failed to connect to Kafka")?;
+                // One partition keeps every event in publication order
+                let partition = client
+                    .partition_client(
+                        settings.kafka_topic.clone(),
+                        0,
+                        rskafka::client::partition::UnknownTopicHandling::Retry,
+                    )
+                    .await
+                    .with_context(|| format!("Failed to open Kafka topic {}", settings.kafka_topic))?;
+                Publisher::Kafka { partition }
+            }
+            Some(other) => anyhow::bail!("Unknown EVENT_STREAM_BACKEND '{}'", other),
+        };
+        Ok(Some(publisher))
+    }
+
+    async fn publish(&self, event: &LogEvent) -> Result<()> {
+        let payload = serde_json::to_vec(&StreamEvent::from_log_event(event))?;
+        match self {
+            Publisher::Nats { jetstream, subject_prefix } => {
+                // The second await waits for the stream's acknowledgement
+                jetstream
+                    .publish(subject_for(subject_prefix, &event.action), payload.into())
+                    .await?
+                    .await?;
+            }
+            Publisher::Kafka { partition } => {
+                let record = rskafka::record::Record {
+                    key: event.repository.as_ref().map(|repository| repository.as_bytes().to_vec()),
+                    value: Some(payload),
+                    headers: BTreeMap::from([("event".to_string(), event.action.as_bytes().to_vec())]),
+                    timestamp: event.timestamp,
+                };
+                partition
+                    .produce(vec![record], rskafka::client::partition::Compression::NoCompression)
+                    .await?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Publish audit events to the broker configured with `EVENT_STREAM_BACKEND`
+pub fn spawn_event_publisher(state: AppState) {
+    if state.config.event_stream.backend.is_none() {
+        return;
+    }
+
+    tokio::spawn(async move {
+        let publisher = match Publisher::from_settings(&state.config.event_stream).await {
+            Ok(Some(publisher)) => publisher,
+            Ok(None) => return,
+            Err(e) => {
+                tracing::error!("Event stream publishing disabled: {:#}", e);
+                return;
+            }
+        };
+
+        let filter = LogFilter { kind: Some(LogEventKind::Audit), ..Default::default() };
+        let (_, mut events) = state.log_stream.subscribe(&filter, 0);
+        loop {
+            let event = match events.recv().await {
+                Ok(event) => event,
+                Err(RecvError::Lagged(skipped)) => {
+                    tracing::warn!("Event stream publisher fell behind, {} events not published", skipped);
+                    continue;
+                }
+                Err(RecvError::Closed) => break,
+            };
+            if event.kind != LogEventKind::Audit {
+                continue;
+            }
+
+            let mut attempt = 0;
+            while let Err(e) = publisher.publish(&event).await {
+                attempt += 1;
+                if attempt == MAX_ATTEMPTS {
+                    tracing::error!("Dropping {} event after {} failed publishes: {:#}", event.action, attempt, e);
+                    break;
+                }
+                tracing::warn!("Failed to publish {} event (attempt {}): {:#}", event.action, attempt, e);
+                tokio::time::sleep(Duration::from_millis(250 * 2_u64.pow(attempt))).await;
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn subjects_extend_the_prefix() {
+        assert_eq!(subject_for("aerugo.events", "manifest.push"), "aerugo.events.manifest.push");
+        assert_eq!(subject_for("aerugo.events.", "tag.delete"), "aerugo.events.tag.delete");
+    }
+
+    #[test]
+    fn events_carry_the_audit_fields() {
+        let event = LogEvent::audit("manifest.push", Some(7), Some("acme/web".to_string()))
+            .with_organization(3)
+            .with_detail("latest -> sha256:abc");
+        let json = serde_json::to_value(StreamEvent::from_log_event(&event)).unwrap();
+        assert_eq!(json["event"], "manifest.push");
+        assert_eq!(json["repository"], "acme/web");
+        assert_eq!(json["organization_id"], 3);
+        assert_eq!(json["detail"], "latest -> sha256:abc");
+    }
+}
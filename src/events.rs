@@ -0,0 +1,82 @@
+// src/events.rs - Durable history of registry events
+//
+// The recorder follows the process's log stream like the webhook dispatcher, so every replica
+// records the audit events it produced itself: pushes, first pulls of a digest, deletes,
+// permission changes and so on. The rows back the repository and organization activity feeds
+// and keep what webhook redelivery needs to rebuild a payload after the process has moved on.
+use anyhow::Result;
+use sqlx::PgPool;
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::log_stream::{LogEvent, LogEventKind, LogFilter};
+use crate::AppState;
+
+/// Record every audit event in the `events` table
+pub fn spawn_event_recorder(state: AppState) {
+    tokio::spawn(async move {
+        let filter = LogFilter { kind: Some(LogEventKind::Audit), ..Default::default() };
+        let (_, mut events) = state.log_stream.subscribe(&filter, 0);
+        loop {
+            let event = match events.recv().await {
+                Ok(event) => event,
+                Err(RecvError::Lagged(skipped)) => {
+                    tracing::warn!("Event recorder fell behind, {} events not recorded", skipped);
+                    continue;
+                }
+                Err(RecvError::Closed) => break,
+            };
+            if event.kind != LogEventKind::Audit {
+                continue;
+            }
+            // A standby's database is a read-only replica
+            if state.standby.is_read_only() {
+                continue;
+            }
+            if let Err(e) = record(&state.db_pool, &event).await {
+                tracing::error!("Failed to record {} event: {}", event.action, e);
+            }
+        }
+    });
+}
+
+/// Insert one event, resolving its repository and organization by name as they are now
+async fn record(pool: &PgPool, event: &LogEvent) -> Result<()> {
+    let (namespace, repo_name) = match event.repository.as_deref() {
+        Some(name) => match name.split_once('/') {
+            Some((namespace, repo_name)) => (Some(namespace), Some(repo_name)),
+            None => (None, Some(name)),
+        },
+        None => (None, None),
+    };
+
+    sqlx::query(
+        "INSERT INTO events (action, occurred_at, user_id, organization_id, repository_id, repository, detail)
+         SELECT $1, $2, u.id, COALESCE($4, o.id), r.id, $7, $8
+         FROM (SELECT 1) AS event
+         LEFT JOIN users u ON u.id = $3
+         LEFT JOIN organizations o ON $7::TEXT IS NOT NULL AND (o.name = $5 OR ($5 IS NULL AND o.id = 1))
+         LEFT JOIN repositories r ON r.organization_id = o.id AND r.name = $6",
+    )
+    .bind(&event.action)
+    .bind(event.timestamp)
+    .bind(event.user_id)
+    .bind(event.organization_id)
+    .bind(namespace)
+    .bind(repo_name)
+    .bind(&event.repository)
+    .bind(&event.detail)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Delete events older than the retention period
+pub async fn cleanup_old_events(pool: &PgPool, retention_days: i32) -> Result<i64, sqlx::Error> {
+    let result = sqlx::query("DELETE FROM events WHERE occurred_at < CURRENT_TIMESTAMP - make_interval(days => $1)")
+        .bind(retention_days)
+        .execute(pool)
+        .await?;
+
+    tracing::info!("Cleaned up {} expired events", result.rows_affected());
+    Ok(result.rows_affected() as i64)
+}
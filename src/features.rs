@@ -0,0 +1,226 @@
+// src/features.rs - Feature flags switching registry behaviors on and off
+//
+// Each flag has a configured value (`FEATURES_*`) that a registry administrator can override at
+// runtime through `/api/v1/admin/features`. Overrides are stored in the database, so they survive
+// restarts and reach every replica; each replica re-reads them every
+// `FEATURES_REFRESH_INTERVAL_SECONDS`. Code checks `AppState::features` rather than the settings,
+// so a flag changes behavior as soon as it is switched.
+use std::collections::HashMap;
+use std::sync::RwLock;
+use std::time::Duration;
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use utoipa::ToSchema;
+
+use crate::config::settings::FeatureSettings;
+use crate::AppState;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Feature {
+    /// Manifest and repository deletes are accepted
+    AllowDelete,
+    /// Unauthenticated clients may pull from public repositories
+    AllowAnonymousPull,
+    /// Anyone may create an account
+    OpenRegistration,
+    /// Every write is rejected except logins and changes to the feature flags
+    ReadonlyMode,
+}
+
+impl Feature {
+    pub const ALL: [Feature; 4] =
+        [Feature::AllowDelete, Feature::AllowAnonymousPull, Feature::OpenRegistration, Feature::ReadonlyMode];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Feature::AllowDelete => "allow_delete",
+            Feature::AllowAnonymousPull => "allow_anonymous_pull",
+            Feature::OpenRegistration => "open_registration",
+            Feature::ReadonlyMode => "readonly_mode",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|feature| feature.as_str() == name)
+    }
+
+    fn configured(self, settings: &FeatureSettings) -> bool {
+        match self {
+            Feature::AllowDelete => settings.allow_delete,
+            Feature::AllowAnonymousPull => settings.allow_anonymous_pull,
+            Feature::OpenRegistration => settings.open_registration,
+            Feature::ReadonlyMode => settings.readonly_mode,
+        }
+    }
+}
+
+/// A feature flag's state as reported to administrators
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct FeatureFlag {
+    pub name: Feature,
+    /// Whether the behavior is currently on
+    pub enabled: bool,
+    /// Value from the configuration
+    pub configured: bool,
+    /// Value set by an administrator, which wins over the configured one
+    pub overridden: Option<bool>,
+    /// Administrator who set the override
+    pub updated_by: Option<i64>,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+struct Override {
+    name: String,
+    enabled: bool,
+    updated_by: Option<i64>,
+    updated_at: DateTime<Utc>,
+}
+
+/// Current feature flags: the configured values with the administrators' overrides on top
+pub struct FeatureFlags {
+    configured: RwLock<FeatureSettings>,
+    overrides: RwLock<HashMap<Feature, Override>>,
+}
+
+impl FeatureFlags {
+    pub fn new(settings: &FeatureSettings) -> Self {
+        Self { configured: RwLock::new(settings.clone()), overrides: RwLock::new(HashMap::new()) }
+    }
+
+    pub fn enabled(&self, feature: Feature) -> bool {
+        let overrides = self.overrides.read().unwrap_or_else(|e| e.into_inner());
+        match overrides.get(&feature) {
+            Some(overridden) => overridden.enabled,
+            None => feature.configured(&self.configured.read().unwrap_or_else(|e| e.into_inner())),
+        }
+    }
+
+    /// Replace the configured values, e.g. after a configuration reload
+    pub fn set_configured(&self, settings: &FeatureSettings) {
+        *self.configured.write().unwrap_or_else(|e| e.into_inner()) = settings.clone();
+    }
+
+    pub fn flag(&self, feature: Feature) -> FeatureFlag {
+        let configured = feature.configured(&self.configured.read().unwrap_or_else(|e| e.into_inner()));
+        let overrides = self.overrides.read().unwrap_or_else(|e| e.into_inner());
+        let overridden = overrides.get(&feature);
+        FeatureFlag {
+            name: feature,
+            enabled: overridden.map_or(configured, |o| o.enabled),
+            configured,
+            overridden: overridden.map(|o| o.enabled),
+            updated_by: overridden.and_then(|o| o.updated_by),
+            updated_at: overridden.map(|o| o.updated_at),
+        }
+    }
+
+    pub fn list(&self) -> Vec<FeatureFlag> {
+        Feature::ALL.into_iter().map(|feature| self.flag(feature)).collect()
+    }
+
+    /// Load the overrides from the database, replacing the ones held
+    pub async fn refresh(&self, pool: &PgPool) -> Result<()> {
+        let rows = sqlx::query_as::<_, Override>(
+            "SELECT name, enabled, updated_by, updated_at FROM feature_flag_overrides",
+        )
+        .fetch_all(pool)
+        .await?;
+        let overrides = rows
+            .into_iter()
+            .filter_map(|row| Feature::parse(&row.name).map(|feature| (feature, row)))
+            .collect();
+        *self.overrides.write().unwrap_or_else(|e| e.into_inner()) = overrides;
+        Ok(())
+    }
+
+    /// Override a flag on every replica; this one applies it at once
+    pub async fn set_override(&self, pool: &PgPool, feature: Feature, enabled: bool, user_id: i64) -> Result<FeatureFlag> {
+        sqlx::query(
+            "INSERT INTO feature_flag_overrides (name, enabled, updated_by)
+             VALUES ($1, $2, $3)
+             ON CONFLICT (name) DO UPDATE
+             SET enabled = EXCLUDED.enabled, updated_by = EXCLUDED.updated_by, updated_at = CURRENT_TIMESTAMP",
+        )
+        .bind(feature.as_str())
+        .bind(enabled)
+        .bind(user_id)
+        .execute(pool)
+        .await?;
+        self.refresh(pool).await?;
+        Ok(self.flag(feature))
+    }
+
+    /// Return a flag to its configured value
+    pub async fn clear_override(&self, pool: &PgPool, feature: Feature) -> Result<FeatureFlag> {
+        sqlx::query("DELETE FROM feature_flag_overrides WHERE name = $1")
+            .bind(feature.as_str())
+            .execute(pool)
+            .await?;
+        self.refresh(pool).await?;
+        Ok(self.flag(feature))
+    }
+
+    fn refresh_interval(&self) -> Duration {
+        Duration::from_secs(self.configured.read().unwrap_or_else(|e| e.into_inner()).refresh_interval_seconds)
+    }
+}
+
+/// Pick up overrides made on other replicas
+pub fn spawn_feature_refresher(state: AppState) {
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(state.features.refresh_interval()).await;
+            if let Err(e) = state.features.refresh(&state.db_pool).await {
+                tracing::warn!("Failed to refresh feature flag overrides: {}", e);
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings() -> FeatureSettings {
+        FeatureSettings {
+            allow_delete: true,
+            allow_anonymous_pull: false,
+            open_registration: true,
+            readonly_mode: false,
+            refresh_interval_seconds: 30,
+        }
+    }
+
+    #[test]
+    fn overrides_win_over_the_configured_values() {
+        let flags = FeatureFlags::new(&settings());
+        assert!(flags.enabled(Feature::AllowDelete));
+        assert!(!flags.enabled(Feature::ReadonlyMode));
+
+        flags.overrides.write().unwrap().insert(
+            Feature::AllowDelete,
+            Override { name: "allow_delete".to_string(), enabled: false, updated_by: Some(1), updated_at: Utc::now() },
+        );
+        assert!(!flags.enabled(Feature::AllowDelete));
+        let flag = flags.flag(Feature::AllowDelete);
+        assert!(flag.configured);
+        assert_eq!(flag.overridden, Some(false));
+
+        flags.set_configured(&FeatureSettings { readonly_mode: true, ..settings() });
+        assert!(flags.enabled(Feature::ReadonlyMode));
+        assert!(!flags.enabled(Feature::AllowDelete));
+    }
+
+    #[test]
+    fn names_round_trip() {
+        for feature in Feature::ALL {
+            assert_eq!(Feature::parse(feature.as_str()), Some(feature));
+        }
+        assert_eq!(Feature::parse("allow_everything"), None);
+    }
+}
@@ -0,0 +1,347 @@
+// src/federation.rs - Federated search across peer Aerugo instances
+//
+// Every instance publishes an index of its public repositories. Federated search asks each
+// configured peer for matches in parallel and merges the answers with the local ones, so a
+// multi-cluster deployment can find which registry holds an image without a central index.
+// Peers are isolated from each other: each request has its own deadline, answers are cached
+// per query, and a peer that fails is skipped for a cooldown instead of slowing every search.
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgPool};
+use utoipa::ToSchema;
+
+use crate::config::settings::{FederationPeer, FederationSettings};
+
+/// Tags listed per repository in the index
+const INDEX_TAGS_PER_REPOSITORY: i64 = 20;
+
+/// A public repository as published in an instance's index
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct IndexEntry {
+    /// `namespace/name`
+    pub repository: String,
+    pub description: Option<String>,
+    /// Most recently updated tags first
+    pub tags: Vec<String>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct IndexResponse {
+    pub instance: String,
+    pub entries: Vec<IndexEntry>,
+}
+
+/// A repository found by federated search and the registry that holds it
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct FederatedHit {
+    /// Instance name of the registry holding the repository
+    pub registry: String,
+    pub registry_url: Option<String>,
+    /// `host/namespace/name`, when the registry's address is known
+    pub pull_reference: Option<String>,
+    pub repository: String,
+    pub description: Option<String>,
+    pub tags: Vec<String>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum PeerQueryStatus {
+    /// The peer answered this request
+    Ok,
+    /// A recent answer to the same query was reused
+    Cached,
+    /// The request failed or timed out
+    Failed,
+    /// The peer is cooling down after a failure and was not asked
+    Skipped,
+}
+
+/// How one peer contributed to a federated search
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct PeerOutcome {
+    pub name: String,
+    pub status: PeerQueryStatus,
+    pub results: usize,
+    pub took_ms: Option<u64>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct FederatedSearchResponse {
+    pub results: Vec<FederatedHit>,
+    pub peers: Vec<PeerOutcome>,
+}
+
+/// Health of a configured peer as seen by this instance
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct PeerStatus {
+    pub name: String,
+    pub url: String,
+    /// False while the peer is skipped after a failure
+    pub available: bool,
+    pub consecutive_failures: u32,
+    pub last_success_at: Option<DateTime<Utc>>,
+    pub last_failure_at: Option<DateTime<Utc>>,
+    pub last_error: Option<String>,
+    /// When a peer that is cooling down will be asked again
+    pub retry_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Default, Clone)]
+struct PeerHealth {
+    consecutive_failures: u32,
+    last_success_at: Option<DateTime<Utc>>,
+    last_failure_at: Option<DateTime<Utc>>,
+    last_error: Option<String>,
+    retry_at: Option<DateTime<Utc>>,
+}
+
+/// Peer list, HTTP client, answer cache and peer health, shared by the federation endpoints
+pub struct Federation {
+    settings: FederationSettings,
+    client: reqwest::Client,
+    cache: moka::future::Cache<(String, String, usize), Arc<Vec<IndexEntry>>>,
+    health: Mutex<HashMap<String, PeerHealth>>,
+}
+
+impl Federation {
+    pub fn new(settings: &FederationSettings) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_millis(settings.request_timeout_ms))
+            .user_agent(concat!("aerugo-federation/", env!("CARGO_PKG_VERSION")))
+            .build()
+            .expect("HTTP client configuration is static");
+
+        Self {
+            settings: settings.clone(),
+            client,
+            cache: moka::future::Cache::builder()
+                .max_capacity(10_000)
+                .time_to_live(Duration::from_secs(settings.cache_ttl_seconds))
+                .build(),
+            health: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn instance_name(&self) -> &str {
+        &self.settings.instance_name
+    }
+
+    pub fn peer_statuses(&self) -> Vec<PeerStatus> {
+        let health = self.health.lock().unwrap_or_else(|e| e.into_inner());
+        let now = Utc::now();
+        self.settings
+            .peers
+            .iter()
+            .map(|peer| {
+                let h = health.get(&peer.name).cloned().unwrap_or_default();
+                let retry_at = h.retry_at.filter(|at| *at > now);
+                PeerStatus {
+                    name: peer.name.clone(),
+                    url: peer.url.clone(),
+                    available: retry_at.is_none(),
+                    consecutive_failures: h.consecutive_failures,
+                    last_success_at: h.last_success_at,
+                    last_failure_at: h.last_failure_at,
+                    last_error: h.last_error,
+                    retry_at,
+                }
+            })
+            .collect()
+    }
+
+    /// Search the local index and every peer. Peer failures are reported in the outcomes and
+    /// never fail the search.
+    pub async fn search(&self, pool: &PgPool, query: &str, limit: usize) -> Result<FederatedSearchResponse> {
+        let local = local_index(pool, query, limit as i64).await?;
+        let local_url = self.settings.public_url.as_deref();
+        let mut results: Vec<FederatedHit> = local
+            .into_iter()
+            .map(|entry| hit(&self.settings.instance_name, local_url, entry))
+            .collect();
+
+        let answers = futures::future::join_all(
+            self.settings.peers.iter().map(|peer| self.query_peer(peer, query, limit)),
+        )
+        .await;
+
+        let mut peers = Vec::with_capacity(answers.len());
+        for (peer, (outcome, entries)) in self.settings.peers.iter().zip(answers) {
+            results.extend(entries.iter().cloned().map(|entry| hit(&peer.name, Some(&peer.url), entry)));
+            peers.push(outcome);
+        }
+
+        results.sort_by(|a, b| {
+            exact_match_first(&a.repository, &b.repository, query).then(b.updated_at.cmp(&a.updated_at))
+        });
+        Ok(FederatedSearchResponse { results, peers })
+    }
+
+    async fn query_peer(&self, peer: &FederationPeer, query: &str, limit: usize) -> (PeerOutcome, Arc<Vec<IndexEntry>>) {
+        let outcome = |status, results, took_ms, error| PeerOutcome {
+            name: peer.name.clone(),
+            status,
+            results,
+            took_ms,
+            error,
+        };
+
+        let key = (peer.name.clone(), query.to_lowercase(), limit);
+        if let Some(entries) = self.cache.get(&key).await {
+            return (outcome(PeerQueryStatus::Cached, entries.len(), None, None), entries);
+        }
+
+        if let Some(retry_at) = self.cooling_down(&peer.name) {
+            let error = format!("skipped after a recent failure until {}", retry_at.to_rfc3339());
+            return (outcome(PeerQueryStatus::Skipped, 0, None, Some(error)), Arc::default());
+        }
+
+        let started = std::time::Instant::now();
+        let answer = self.fetch_index(peer, query, limit).await;
+        let took_ms = Some(started.elapsed().as_millis() as u64);
+        match answer {
+            Ok(entries) => {
+                self.record(&peer.name, None);
+                let entries = Arc::new(entries);
+                self.cache.insert(key, entries.clone()).await;
+                (outcome(PeerQueryStatus::Ok, entries.len(), took_ms, None), entries)
+            }
+            Err(e) => {
+                let error = format!("{:#}", e);
+                tracing::warn!("Federation peer {} failed: {}", peer.name, error);
+                self.record(&peer.name, Some(error.clone()));
+                (outcome(PeerQueryStatus::Failed, 0, took_ms, Some(error)), Arc::default())
+            }
+        }
+    }
+
+    async fn fetch_index(&self, peer: &FederationPeer, query: &str, limit: usize) -> Result<Vec<IndexEntry>> {
+        let response = self
+            .client
+            .get(format!("{}/api/v1/federation/index", peer.url))
+            .query(&[("q", query), ("limit", &limit.to_string())])
+            .send()
+            .await
+            .context("request failed")?
+            .error_for_status()
+            .context("peer returned an error")?;
+        let index: IndexResponse = response.json().await.context("invalid index response")?;
+        Ok(index.entries.into_iter().take(limit).collect())
+    }
+
+    fn cooling_down(&self, peer: &str) -> Option<DateTime<Utc>> {
+        let health = self.health.lock().unwrap_or_else(|e| e.into_inner());
+        health.get(peer).and_then(|h| h.retry_at).filter(|at| *at > Utc::now())
+    }
+
+    fn record(&self, peer: &str, error: Option<String>) {
+        let mut health = self.health.lock().unwrap_or_else(|e| e.into_inner());
+        let h = health.entry(peer.to_string()).or_default();
+        let now = Utc::now();
+        match error {
+            None => {
+                h.consecutive_failures = 0;
+                h.last_success_at = Some(now);
+                h.retry_at = None;
+            }
+            Some(error) => {
+                h.consecutive_failures += 1;
+                h.last_failure_at = Some(now);
+                h.last_error = Some(error);
+                h.retry_at = Some(now + chrono::Duration::seconds(self.settings.failure_cooldown_seconds as i64));
+            }
+        }
+    }
+}
+
+fn hit(registry: &str, registry_url: Option<&str>, entry: IndexEntry) -> FederatedHit {
+    let host = registry_url
+        .and_then(|url| url::Url::parse(url).ok())
+        .and_then(|url| {
+            let host = url.host_str()?.to_string();
+            Some(match url.port() {
+                Some(port) => format!("{}:{}", host, port),
+                None => host,
+            })
+        });
+    FederatedHit {
+        registry: registry.to_string(),
+        registry_url: registry_url.map(str::to_string),
+        pull_reference: host.map(|host| format!("{}/{}", host, entry.repository)),
+        repository: entry.repository,
+        description: entry.description,
+        tags: entry.tags,
+        updated_at: entry.updated_at,
+    }
+}
+
+/// Repositories named exactly as the query sort before partial matches
+fn exact_match_first(a: &str, b: &str, query: &str) -> std::cmp::Ordering {
+    let a_exact = a.eq_ignore_ascii_case(query);
+    let b_exact = b.eq_ignore_ascii_case(query);
+    b_exact.cmp(&a_exact)
+}
+
+/// Public repositories of this instance whose `namespace/name` contains `query`
+pub async fn local_index(pool: &PgPool, query: &str, limit: i64) -> Result<Vec<IndexEntry>> {
+    sqlx::query_as::<_, IndexEntry>(
+        "SELECT o.name || '/' || r.name AS repository, r.description, r.updated_at,
+                ARRAY(SELECT t.name::TEXT FROM tags t WHERE t.repository_id = r.id
+                      ORDER BY t.updated_at DESC LIMIT $3) AS tags
+         FROM repositories r
+         JOIN organizations o ON o.id = r.organization_id
+         WHERE r.is_public AND STRPOS(LOWER(o.name || '/' || r.name), LOWER($1)) > 0
+         ORDER BY r.updated_at DESC
+         LIMIT $2",
+    )
+    .bind(query)
+    .bind(limit)
+    .bind(INDEX_TAGS_PER_REPOSITORY)
+    .fetch_all(pool)
+    .await
+    .context("Failed to search the local repository index")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(repository: &str) -> IndexEntry {
+        IndexEntry {
+            repository: repository.to_string(),
+            description: None,
+            tags: vec!["latest".to_string()],
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn pull_reference_uses_registry_host() {
+        let found = hit("eu", Some("https://eu.registry.example.com"), entry("ml/trainer"));
+        assert_eq!(found.pull_reference.as_deref(), Some("eu.registry.example.com/ml/trainer"));
+
+        let found = hit("dev", Some("http://localhost:8080"), entry("ml/trainer"));
+        assert_eq!(found.pull_reference.as_deref(), Some("localhost:8080/ml/trainer"));
+
+        assert_eq!(hit("local", None, entry("ml/trainer")).pull_reference, None);
+    }
+
+    #[test]
+    fn parses_peer_entries() {
+        let peer = FederationPeer::parse(" eu = https://eu.example.com/ ").unwrap();
+        assert_eq!((peer.name.as_str(), peer.url.as_str()), ("eu", "https://eu.example.com"));
+
+        let peer = FederationPeer::parse("https://us.example.com").unwrap();
+        assert_eq!(peer.name, "us.example.com");
+
+        assert!(FederationPeer::parse("").is_none());
+    }
+}
@@ -0,0 +1,158 @@
+// src/gc.rs - Blob garbage collection for storage keys whose database records were removed
+//
+// Deleting registry content in the database is transactional; deleting it from object storage
+// is not. Callers enqueue the storage keys of what they removed in the same transaction, and
+// the collector deletes them later. A key that is referenced again by the time it is collected
+// (a blob mount from a cloned repository, or the same content pushed under a reused name) is
+// left in place. Each pass first closes upload sessions that expired unfinished
+// (`UPLOAD_SESSION_EXPIRY_SECONDS`) and queues their chunks.
+use std::time::Duration;
+
+use anyhow::Result;
+use sqlx::{PgPool, Postgres, Transaction};
+
+use crate::AppState;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct GcReport {
+    pub deleted: usize,
+    pub retained: usize,
+    pub failed: usize,
+}
+
+/// Queue every storage key held by an organization's repositories: manifests and layers,
+/// transcoded layers, and the chunks of unfinished uploads. Must run before the
+/// repositories are deleted, in the same transaction.
+pub async fn enqueue_organization_blobs(
+    tx: &mut Transaction<'_, Postgres>,
+    org_id: i64,
+    reason: &str,
+) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query(
+        "INSERT INTO blob_gc_queue (storage_key, reason)
+         SELECT DISTINCT key, $2 FROM (
+             SELECT o.name || '/' || r.name || '/' || m.digest AS key
+             FROM manifests m
+             JOIN repositories r ON r.id = m.repository_id
+             JOIN organizations o ON o.id = r.organization_id
+             WHERE o.id = $1
+             UNION
+             SELECT o.name || '/' || r.name || '/' || bt.target_digest
+             FROM blob_transcodes bt
+             JOIN repositories r ON r.id = bt.repository_id
+             JOIN organizations o ON o.id = r.organization_id
+             WHERE o.id = $1
+             UNION
+             SELECT unnest(bu.chunk_keys)
+             FROM blob_uploads bu
+             JOIN repositories r ON r.id = bu.repository_id
+             JOIN organizations o ON o.id = r.organization_id
+             WHERE o.id = $1 AND bu.completed_at IS NULL
+         ) keys",
+    )
+    .bind(org_id)
+    .bind(reason)
+    .execute(&mut **tx)
+    .await?;
+    Ok(result.rows_affected())
+}
+
+/// Queue one storage key, e.g. content written for a push whose database records were rolled back
+pub async fn enqueue_blob(pool: &PgPool, key: &str, reason: &str) -> Result<(), sqlx::Error> {
+    sqlx::query("INSERT INTO blob_gc_queue (storage_key, reason) VALUES ($1, $2)")
+        .bind(key)
+        .bind(reason)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// Whether a queued key is in use again and must be kept
+async fn still_referenced(pool: &PgPool, key: &str) -> Result<bool, sqlx::Error> {
+    sqlx::query_scalar::<_, bool>(
+        "SELECT EXISTS(SELECT 1 FROM blob_mounts WHERE source_key = $1)
+             OR EXISTS(
+                 SELECT 1 FROM manifests m
+                 JOIN repositories r ON r.id = m.repository_id
+                 JOIN organizations o ON o.id = r.organization_id
+                 WHERE o.name || '/' || r.name || '/' || m.digest = $1
+             )",
+    )
+    .bind(key)
+    .fetch_one(pool)
+    .await
+}
+
+/// Collect one batch of queued keys
+pub async fn run_gc_pass(state: &AppState) -> Result<GcReport> {
+    let settings = &state.config.gc;
+    let pending = sqlx::query_as::<_, (i64, String)>(
+        "SELECT id, storage_key FROM blob_gc_queue
+         WHERE processed_at IS NULL AND attempts < $1
+         ORDER BY enqueued_at
+         LIMIT $2",
+    )
+    .bind(settings.max_attempts)
+    .bind(settings.batch_size)
+    .fetch_all(&state.db_pool)
+    .await?;
+
+    let mut report = GcReport::default();
+    for (id, key) in pending {
+        if still_referenced(&state.db_pool, &key).await? {
+            sqlx::query("UPDATE blob_gc_queue SET processed_at = NOW(), last_error = 'retained: still referenced' WHERE id = $1")
+                .bind(id)
+                .execute(&state.db_pool)
+                .await?;
+            report.retained += 1;
+            continue;
+        }
+
+        match state.storage.delete_blob(&key).await {
+            Ok(_) => {
+                sqlx::query("UPDATE blob_gc_queue SET processed_at = NOW(), last_error = NULL WHERE id = $1")
+                    .bind(id)
+                    .execute(&state.db_pool)
+                    .await?;
+                report.deleted += 1;
+            }
+            Err(e) => {
+                tracing::warn!("Failed to collect blob {}: {}", key, e);
+                sqlx::query("UPDATE blob_gc_queue SET attempts = attempts + 1, last_error = $2 WHERE id = $1")
+                    .bind(id)
+                    .bind(e.to_string())
+                    .execute(&state.db_pool)
+                    .await?;
+                report.failed += 1;
+            }
+        }
+    }
+    Ok(report)
+}
+
+pub fn spawn_blob_gc(state: AppState) {
+    let interval_seconds = state.config.gc.interval_seconds;
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(interval_seconds));
+        loop {
+            interval.tick().await;
+            // Storage deletes are writes; a standby leaves them to the primary
+            if state.standby.is_read_only() {
+                continue;
+            }
+            match crate::uploads::close_expired_sessions(&state.db_pool, state.config.uploads.session_expiry_seconds).await {
+                Ok(0) => {}
+                Ok(queued) => tracing::info!("Queued {} chunks of expired blob uploads", queued),
+                Err(e) => tracing::error!("Closing expired blob uploads failed: {}", e),
+            }
+            match run_gc_pass(&state).await {
+                Ok(report) if report == GcReport::default() => {}
+                Ok(report) => tracing::info!(
+                    "Blob GC pass: {} deleted, {} retained, {} failed",
+                    report.deleted, report.retained, report.failed
+                ),
+                Err(e) => tracing::error!("Blob GC pass failed: {}", e),
+            }
+        }
+    });
+}
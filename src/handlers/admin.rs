@@ -0,0 +1,497 @@
+// src/handlers/admin.rs - Registry administrator extractor and global administration endpoints
+use async_trait::async_trait;
+use axum::{
+    extract::{FromRequestParts, Path, Query, State},
+    http::{request::Parts, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use axum_extra::headers::{authorization::Bearer, Authorization};
+use axum_extra::TypedHeader;
+use chrono::{DateTime, Utc};
+use secrecy::ExposeSecret;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sqlx::FromRow;
+use utoipa::{IntoParams, ToSchema};
+
+use crate::{
+    auth::extract_user_id_dual,
+    cache::CacheStats,
+    config::reload::ConfigReload,
+    log_stream::LogEvent,
+    models::api_key::ApiKeyScope,
+    retention::RetentionPolicy,
+    AppState,
+};
+
+/// An authenticated registry administrator. Rejects the request with 401 for missing or
+/// invalid credentials and 403 for callers who are not administrators. API keys are held to
+/// the route's resource scope by `enforce_api_key_scopes`, which requires `registry:admin`
+/// everywhere except the statistics endpoints (`stats:read`).
+#[derive(Debug, Clone)]
+pub struct AdminUser {
+    pub user_id: i64,
+    pub username: String,
+}
+
+#[async_trait]
+impl FromRequestParts<AppState> for AdminUser {
+    type Rejection = Response;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self, Self::Rejection> {
+        let auth = Option::<TypedHeader<Authorization<Bearer>>>::from_request_parts(parts, state)
+            .await
+            .unwrap_or(None);
+        let secret = state.config.auth.jwt_secret.expose_secret().as_bytes();
+        let user_id = match extract_user_id_dual(auth, &parts.headers, ApiKeyScope::Read, secret, &state.db_pool, state.cache.as_ref()).await {
+            Ok(id) => id,
+            Err(StatusCode::FORBIDDEN) => {
+                return Err((StatusCode::FORBIDDEN, Json(json!({
+                    "error": "API key lacks the required scope"
+                }))).into_response())
+            }
+            Err(status) => return Err((status, Json(json!({ "error": "Unauthorized" }))).into_response()),
+        };
+
+        match find_admin(state, user_id).await {
+            Ok(Some(username)) => Ok(Self { user_id, username }),
+            Ok(None) => Err((StatusCode::FORBIDDEN, Json(json!({
+                "error": "Registry administrator access required"
+            }))).into_response()),
+            Err(e) => Err(internal_error(e)),
+        }
+    }
+}
+
+/// Username of `user_id` if it is a registry administrator: flagged `is_admin` or listed in
+/// `ADMIN_USERNAMES`
+pub async fn find_admin(state: &AppState, user_id: i64) -> Result<Option<String>, sqlx::Error> {
+    let user = sqlx::query_as::<_, (String, bool)>("SELECT username, is_admin FROM users WHERE id = $1")
+        .bind(user_id)
+        .fetch_optional(&state.db_pool)
+        .await?;
+
+    Ok(user
+        .filter(|(username, is_admin)| *is_admin || state.config.auth.admin_usernames.contains(username))
+        .map(|(username, _)| username))
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct ListUsersQuery {
+    /// Case-insensitive substring of the username or email
+    pub search: Option<String>,
+    /// Page size (default 50, at most 200)
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+#[derive(Debug, Serialize, FromRow, ToSchema)]
+pub struct AdminUserSummary {
+    pub id: i64,
+    pub username: String,
+    pub email: String,
+    /// Whether the user is an administrator, either flagged or through `ADMIN_USERNAMES`
+    pub is_admin: bool,
+    pub disabled_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct SetAdminRequest {
+    pub is_admin: bool,
+}
+
+#[derive(Debug, Serialize, FromRow, ToSchema)]
+pub struct StorageStats {
+    pub users: i64,
+    pub disabled_users: i64,
+    pub organizations: i64,
+    pub repositories: i64,
+    pub manifests: i64,
+    pub tags: i64,
+    /// Combined size of all stored manifests in bytes
+    pub manifest_bytes: i64,
+    /// Upload sessions that have been started but not completed or cancelled
+    pub open_uploads: i64,
+}
+
+/// List registry users
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/users",
+    tag = "admin",
+    params(ListUsersQuery),
+    responses(
+        (status = 200, description = "Users ordered by ID", body = [AdminUserSummary]),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Not a registry administrator"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn list_users(
+    State(state): State<AppState>,
+    _admin: AdminUser,
+    Query(query): Query<ListUsersQuery>,
+) -> Response {
+    let search = query.search.filter(|s| !s.is_empty()).map(|s| format!("%{}%", s));
+    let limit = query.limit.unwrap_or(50).clamp(1, 200);
+    let offset = query.offset.unwrap_or(0).max(0);
+
+    match sqlx::query_as::<_, AdminUserSummary>(
+        "SELECT id, username, email, is_admin OR username = ANY($2) AS is_admin, disabled_at, created_at
+         FROM users
+         WHERE $1::TEXT IS NULL OR username ILIKE $1 OR email ILIKE $1
+         ORDER BY id
+         LIMIT $3 OFFSET $4",
+    )
+    .bind(search)
+    .bind(&state.config.auth.admin_usernames)
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(&state.db_pool)
+    .await
+    {
+        Ok(users) => (StatusCode::OK, Json(users)).into_response(),
+        Err(e) => internal_error(e),
+    }
+}
+
+/// Disable a user
+///
+/// Rejects the user's logins, access tokens, refresh tokens and API keys until re-enabled.
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/users/{user_id}/disable",
+    tag = "admin",
+    params(("user_id" = i64, Path, description = "User ID")),
+    responses(
+        (status = 200, description = "User disabled", body = AdminUserSummary),
+        (status = 400, description = "Administrators cannot disable themselves"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Not a registry administrator"),
+        (status = 404, description = "User not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn disable_user(
+    State(state): State<AppState>,
+    admin: AdminUser,
+    Path(user_id): Path<i64>,
+) -> Response {
+    if user_id == admin.user_id {
+        return (StatusCode::BAD_REQUEST, Json(json!({
+            "error": "Administrators cannot disable their own account"
+        }))).into_response();
+    }
+
+    let user = match sqlx::query_as::<_, AdminUserSummary>(
+        "UPDATE users SET disabled_at = COALESCE(disabled_at, NOW())
+         WHERE id = $1
+         RETURNING id, username, email, is_admin OR username = ANY($2) AS is_admin, disabled_at, created_at",
+    )
+    .bind(user_id)
+    .bind(&state.config.auth.admin_usernames)
+    .fetch_optional(&state.db_pool)
+    .await
+    {
+        Ok(Some(user)) => user,
+        Ok(None) => return user_not_found(user_id),
+        Err(e) => return internal_error(e),
+    };
+
+    if let Err(e) = sqlx::query("UPDATE refresh_tokens SET revoked_at = NOW() WHERE user_id = $1 AND revoked_at IS NULL")
+        .bind(user_id)
+        .execute(&state.db_pool)
+        .await
+    {
+        return internal_error(e);
+    }
+
+    // Cached credentials would otherwise keep working until they expire
+    if let Some(cache) = &state.cache {
+        let key_hashes = sqlx::query_scalar::<_, String>("SELECT key_hash FROM api_keys WHERE user_id = $1")
+            .bind(user_id)
+            .fetch_all(&state.db_pool)
+            .await
+            .unwrap_or_default();
+        for key_hash in key_hashes {
+            let _ = cache.invalidate_api_key(&key_hash).await;
+        }
+        let _ = cache.invalidate_user_permissions(&user_id.to_string()).await;
+    }
+
+    tracing::info!("User {} ({}) disabled by {}", user.username, user_id, admin.username);
+    state.log_stream.publish(
+        LogEvent::audit("user.disable", Some(admin.user_id), None)
+            .with_detail(format!("user={} username={}", user_id, user.username)),
+    );
+    (StatusCode::OK, Json(user)).into_response()
+}
+
+/// Re-enable a disabled user
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/users/{user_id}/enable",
+    tag = "admin",
+    params(("user_id" = i64, Path, description = "User ID")),
+    responses(
+        (status = 200, description = "User enabled", body = AdminUserSummary),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Not a registry administrator"),
+        (status = 404, description = "User not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn enable_user(
+    State(state): State<AppState>,
+    admin: AdminUser,
+    Path(user_id): Path<i64>,
+) -> Response {
+    match sqlx::query_as::<_, AdminUserSummary>(
+        "UPDATE users SET disabled_at = NULL
+         WHERE id = $1
+         RETURNING id, username, email, is_admin OR username = ANY($2) AS is_admin, disabled_at, created_at",
+    )
+    .bind(user_id)
+    .bind(&state.config.auth.admin_usernames)
+    .fetch_optional(&state.db_pool)
+    .await
+    {
+        Ok(Some(user)) => {
+            tracing::info!("User {} ({}) enabled by {}", user.username, user_id, admin.username);
+            state.log_stream.publish(
+                LogEvent::audit("user.enable", Some(admin.user_id), None)
+                    .with_detail(format!("user={} username={}", user_id, user.username)),
+            );
+            (StatusCode::OK, Json(user)).into_response()
+        }
+        Ok(None) => user_not_found(user_id),
+        Err(e) => internal_error(e),
+    }
+}
+
+/// Grant or revoke the registry administrator flag
+///
+/// Users listed in `ADMIN_USERNAMES` stay administrators whatever the flag says.
+#[utoipa::path(
+    put,
+    path = "/api/v1/admin/users/{user_id}/admin",
+    tag = "admin",
+    params(("user_id" = i64, Path, description = "User ID")),
+    request_body(content = SetAdminRequest, description = "New administrator flag", content_type = "application/json"),
+    responses(
+        (status = 200, description = "Flag updated", body = AdminUserSummary),
+        (status = 400, description = "Administrators cannot revoke their own flag"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Not a registry administrator"),
+        (status = 404, description = "User not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn set_user_admin(
+    State(state): State<AppState>,
+    admin: AdminUser,
+    Path(user_id): Path<i64>,
+    Json(request): Json<SetAdminRequest>,
+) -> Response {
+    if user_id == admin.user_id && !request.is_admin {
+        return (StatusCode::BAD_REQUEST, Json(json!({
+            "error": "Administrators cannot revoke their own administrator flag"
+        }))).into_response();
+    }
+
+    match sqlx::query_as::<_, AdminUserSummary>(
+        "UPDATE users SET is_admin = $2
+         WHERE id = $1
+         RETURNING id, username, email, is_admin OR username = ANY($3) AS is_admin, disabled_at, created_at",
+    )
+    .bind(user_id)
+    .bind(request.is_admin)
+    .bind(&state.config.auth.admin_usernames)
+    .fetch_optional(&state.db_pool)
+    .await
+    {
+        Ok(Some(user)) => {
+            let action = if request.is_admin { "user.admin.grant" } else { "user.admin.revoke" };
+            state.log_stream.publish(
+                LogEvent::audit(action, Some(admin.user_id), None)
+                    .with_detail(format!("user={} username={}", user_id, user.username)),
+            );
+            (StatusCode::OK, Json(user)).into_response()
+        }
+        Ok(None) => user_not_found(user_id),
+        Err(e) => internal_error(e),
+    }
+}
+
+/// Registry-wide storage statistics
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/stats/storage",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Object counts and stored sizes", body = StorageStats),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Not a registry administrator"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn storage_stats(State(state): State<AppState>, _admin: AdminUser) -> Response {
+    match sqlx::query_as::<_, StorageStats>(
+        "SELECT (SELECT COUNT(*) FROM users) AS users,
+                (SELECT COUNT(*) FROM users WHERE disabled_at IS NOT NULL) AS disabled_users,
+                (SELECT COUNT(*) FROM organizations WHERE deleted_at IS NULL) AS organizations,
+                (SELECT COUNT(*) FROM repositories) AS repositories,
+                (SELECT COUNT(*) FROM manifests) AS manifests,
+                (SELECT COUNT(*) FROM tags) AS tags,
+                (SELECT COALESCE(SUM(size), 0)::BIGINT FROM manifests) AS manifest_bytes,
+                (SELECT COUNT(*) FROM blob_uploads WHERE completed_at IS NULL) AS open_uploads",
+    )
+    .fetch_one(&state.db_pool)
+    .await
+    {
+        Ok(stats) => (StatusCode::OK, Json(stats)).into_response(),
+        Err(e) => internal_error(e),
+    }
+}
+
+/// Cache hits, misses and evictions per kind of cached data
+///
+/// Counted since the process started, for this replica only. The same counters are exported
+/// for Prometheus at `/metrics`.
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/cache/stats",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Cache statistics", body = CacheStats),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Not a registry administrator"),
+        (status = 404, description = "Caching is disabled")
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn cache_stats(State(state): State<AppState>, _admin: AdminUser) -> Response {
+    match &state.cache {
+        Some(cache) => (StatusCode::OK, Json(cache.get_stats().await)).into_response(),
+        None => (StatusCode::NOT_FOUND, Json(json!({
+            "error": "Caching is disabled"
+        }))).into_response(),
+    }
+}
+
+/// Flush cached manifests, blob metadata, listings, credentials and permissions
+///
+/// Rate-limit and login-lockout counters are kept.
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/cache/flush",
+    tag = "admin",
+    responses(
+        (status = 204, description = "Caches flushed"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Not a registry administrator"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn flush_cache(State(state): State<AppState>, admin: AdminUser) -> Response {
+    if let Some(cache) = &state.cache {
+        if let Err(e) = cache.flush_cached_data().await {
+            return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({
+                "error": format!("Failed to flush cache: {}", e)
+            }))).into_response();
+        }
+    }
+
+    tracing::info!("Caches flushed by {}", admin.username);
+    state.log_stream.publish(LogEvent::audit("cache.flush", Some(admin.user_id), None));
+    StatusCode::NO_CONTENT.into_response()
+}
+
+/// Describe the instance's data retention and privacy settings
+///
+/// Values come from the environment and are read-only at runtime.
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/retention",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Each setting with its value, default, environment variable and effect", body = RetentionPolicy),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Not a registry administrator")
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn retention_policy(State(state): State<AppState>, _admin: AdminUser) -> Json<RetentionPolicy> {
+    Json(RetentionPolicy::describe(&state.config.retention))
+}
+
+/// Reload the log level, cache TTLs, rate limits, notification endpoints and feature flags
+///
+/// Re-reads `.env`, `AERUGO_CONFIG` and `NOTIFICATIONS_CONFIG_FILE`, like sending the process
+/// SIGHUP. A configuration that fails to load or validate is rejected and the running one is
+/// kept; other settings only change on restart.
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/config/reload",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Configuration reloaded", body = ConfigReload),
+        (status = 400, description = "Invalid configuration; nothing was changed"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Not a registry administrator")
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn reload_config(State(state): State<AppState>, admin: AdminUser) -> Response {
+    match state.runtime_config.reload(&state).await {
+        Ok(reload) => {
+            tracing::info!("Configuration reloaded by {}, changed: [{}]", admin.username, reload.changed.join(", "));
+            state.log_stream.publish(LogEvent::audit("config.reload", Some(admin.user_id), None));
+            (StatusCode::OK, Json(reload)).into_response()
+        }
+        Err(e) => {
+            tracing::warn!("Configuration reload by {} rejected: {:#}", admin.username, e);
+            (StatusCode::BAD_REQUEST, Json(json!({
+                "error": format!("Configuration not reloaded: {:#}", e)
+            }))).into_response()
+        }
+    }
+}
+
+fn user_not_found(user_id: i64) -> Response {
+    (StatusCode::NOT_FOUND, Json(json!({
+        "error": format!("User {} not found", user_id)
+    }))).into_response()
+}
+
+pub(crate) fn internal_error(e: impl std::fmt::Display) -> Response {
+    (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({
+        "error": format!("Database error: {}", e)
+    }))).into_response()
+}
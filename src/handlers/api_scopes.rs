@@ -0,0 +1,138 @@
+// src/handlers/api_scopes.rs - Per-route resource scope checks for API keys
+use axum::{
+    extract::{OriginalUri, Request, State},
+    http::{header::AUTHORIZATION, HeaderMap, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde_json::json;
+
+use crate::{
+    auth::lookup_api_key,
+    models::api_key::{ApiResource, ResourceScope, ScopeLevel},
+    AppState,
+};
+
+/// Resource scope an API key needs for a `/api/v1` request, or `None` for other paths and for
+/// public files such as avatars.
+///
+/// Reads need `read`. Writes need `admin`, except pushing content through the storage API,
+/// attaching model cards, lineage and SBOMs, and changing webhooks, which need `write`. Watching a repository is a setting of
+/// the user's own, like the rest of `/auth`. Registry administration always needs `admin`.
+pub fn required_scope(method: &Method, path: &str) -> Option<ResourceScope> {
+    let path = path.strip_prefix("/api/v1/")?;
+    let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+    let is_read = matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS);
+    let level = |write: ScopeLevel| if is_read { ScopeLevel::Read } else { write };
+
+    let scope = match segments.as_slice() {
+        ["avatars", ..] => return None,
+        ["auth", "usage", ..] | ["admin", "stats", ..] | ["admin", "cache", "stats"] | ["organizations", _, "stats"] | ["repos", _, _, "stats"] => {
+            ResourceScope::new(ApiResource::Stats, ScopeLevel::Read)
+        }
+        ["organizations", _, "webhooks", ..] | ["repos", _, _, "webhooks", ..] | ["webhooks", ..] => {
+            ResourceScope::new(ApiResource::Webhook, level(ScopeLevel::Write))
+        }
+        ["repos", _, _, "watch"] => ResourceScope::new(ApiResource::User, level(ScopeLevel::Admin)),
+        ["organizations", ..] | ["invitations", ..] => ResourceScope::new(ApiResource::Org, level(ScopeLevel::Admin)),
+        ["storage", ..] | ["repos", _, _, "models", ..] | ["repos", _, _, "images", _, "sbom"] => ResourceScope::new(ApiResource::Repo, level(ScopeLevel::Write)),
+        ["repos", ..] => ResourceScope::new(ApiResource::Repo, level(ScopeLevel::Admin)),
+        ["federation", "peers", ..] => ResourceScope::new(ApiResource::Registry, ScopeLevel::Admin),
+        ["federation", ..] | ["events", ..] | ["sbom", ..] => ResourceScope::new(ApiResource::Repo, ScopeLevel::Read),
+        ["auth", ..] => ResourceScope::new(ApiResource::User, level(ScopeLevel::Admin)),
+        _ => ResourceScope::new(ApiResource::Registry, ScopeLevel::Admin),
+    };
+    Some(scope)
+}
+
+/// Middleware rejecting `/api/v1` requests made with an API key whose scopes do not cover the
+/// route with 403. Session tokens and unauthenticated requests are left to the handlers.
+pub async fn enforce_api_key_scopes(
+    State(state): State<AppState>,
+    OriginalUri(uri): OriginalUri,
+    request: Request,
+    next: Next,
+) -> Response {
+    let required = match required_scope(request.method(), uri.path()) {
+        Some(scope) => scope,
+        None => return next.run(request).await,
+    };
+    let api_key = match presented_api_key(request.headers()) {
+        Some(key) => key,
+        None => return next.run(request).await,
+    };
+
+    let scopes = match lookup_api_key(&api_key, &state.db_pool, state.cache.as_ref()).await {
+        Ok((_, scopes)) => scopes,
+        Err(status) => return (status, Json(json!({ "error": "Unauthorized" }))).into_response(),
+    };
+
+    if !required.granted_by(&scopes) {
+        tracing::warn!("API key lacks the {} scope for {} {} (has {:?})", required, request.method(), uri.path(), scopes);
+        return (StatusCode::FORBIDDEN, Json(json!({
+            "error": format!("API key lacks the {} scope", required)
+        }))).into_response();
+    }
+
+    next.run(request).await
+}
+
+/// API key sent as `X-API-Key` or as a bearer token
+fn presented_api_key(headers: &HeaderMap) -> Option<String> {
+    if let Some(api_key) = headers.get("X-API-Key").and_then(|v| v.to_str().ok()) {
+        if api_key.starts_with("ak_") {
+            return Some(api_key.to_string());
+        }
+    }
+
+    headers
+        .get(AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .filter(|token| token.starts_with("ak_"))
+        .map(str::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scope(method: Method, path: &str) -> Option<String> {
+        required_scope(&method, path).map(|s| s.to_string())
+    }
+
+    #[test]
+    fn maps_routes_to_resource_scopes() {
+        assert_eq!(scope(Method::GET, "/api/v1/organizations/4/members").as_deref(), Some("org:read"));
+        assert_eq!(scope(Method::DELETE, "/api/v1/repos/acme/web").as_deref(), Some("repo:admin"));
+        assert_eq!(scope(Method::POST, "/api/v1/storage/upload").as_deref(), Some("repo:write"));
+        assert_eq!(scope(Method::PUT, "/api/v1/repos/acme/llm/models/v2/card").as_deref(), Some("repo:write"));
+        assert_eq!(scope(Method::PUT, "/api/v1/organizations/4/webhooks/2").as_deref(), Some("webhook:write"));
+        assert_eq!(scope(Method::POST, "/api/v1/repos/acme/web/webhooks").as_deref(), Some("webhook:write"));
+        assert_eq!(scope(Method::GET, "/api/v1/organizations/4/webhooks/2/deliveries").as_deref(), Some("webhook:read"));
+        assert_eq!(
+            scope(Method::POST, "/api/v1/repos/acme/web/webhooks/2/deliveries/9/redeliver").as_deref(),
+            Some("webhook:write")
+        );
+        assert_eq!(scope(Method::GET, "/api/v1/auth/usage").as_deref(), Some("stats:read"));
+        assert_eq!(scope(Method::GET, "/api/v1/events/stream").as_deref(), Some("repo:read"));
+        assert_eq!(scope(Method::GET, "/api/v1/organizations/4/audit/export").as_deref(), Some("org:read"));
+        assert_eq!(scope(Method::PUT, "/api/v1/repos/acme/web/watch").as_deref(), Some("user:admin"));
+        assert_eq!(scope(Method::GET, "/api/v1/admin/stats/storage").as_deref(), Some("stats:read"));
+        assert_eq!(scope(Method::GET, "/api/v1/admin/cache/stats").as_deref(), Some("stats:read"));
+        assert_eq!(scope(Method::GET, "/api/v1/organizations/4/stats").as_deref(), Some("stats:read"));
+        assert_eq!(scope(Method::GET, "/api/v1/repos/acme/web/stats").as_deref(), Some("stats:read"));
+        assert_eq!(scope(Method::GET, "/api/v1/repos/acme/web/events").as_deref(), Some("repo:read"));
+        assert_eq!(scope(Method::GET, "/api/v1/organizations/4/events").as_deref(), Some("org:read"));
+        assert_eq!(scope(Method::GET, "/api/v1/admin/users").as_deref(), Some("registry:admin"));
+        assert_eq!(scope(Method::POST, "/api/v1/admin/jobs/5/retry").as_deref(), Some("registry:admin"));
+        assert_eq!(scope(Method::GET, "/api/v1/repos/acme/web/jobs").as_deref(), Some("repo:read"));
+        assert_eq!(scope(Method::PUT, "/api/v1/repos/acme/web/images/v1/sbom").as_deref(), Some("repo:write"));
+        assert_eq!(scope(Method::GET, "/api/v1/sbom/packages").as_deref(), Some("repo:read"));
+        assert_eq!(scope(Method::POST, "/api/v1/auth/api-keys").as_deref(), Some("user:admin"));
+        assert_eq!(scope(Method::PUT, "/api/v1/organizations/4/avatar").as_deref(), Some("org:admin"));
+        assert_eq!(scope(Method::GET, "/api/v1/avatars/users/3/ab.png"), None);
+        assert_eq!(scope(Method::GET, "/v2/acme/web/tags/list"), None);
+    }
+}
@@ -0,0 +1,341 @@
+// src/handlers/api_usage.rs - Per-credential API usage analytics
+use axum::{
+    extract::{MatchedPath, Path, Query, Request, State},
+    http::{header::AUTHORIZATION, HeaderMap, StatusCode},
+    middleware::Next,
+    response::Response,
+    Json,
+};
+use axum_extra::headers::{authorization::Bearer, Authorization};
+use axum_extra::TypedHeader;
+use base64::Engine;
+use secrecy::ExposeSecret;
+use serde::Deserialize;
+use sqlx::PgPool;
+use utoipa::IntoParams;
+
+use crate::{
+    auth::{hash_api_key, verify_token},
+    models::{
+        api_key::ApiKeyScope,
+        api_usage::{CredentialUsage, CredentialUsageDetail, DailyUsage, EndpointUsage},
+    },
+    AppState,
+};
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct UsageQuery {
+    /// Number of days to report on, counting today (default 30, at most `RETENTION_USAGE_DAYS`)
+    pub days: Option<i32>,
+}
+
+impl UsageQuery {
+    /// Older rows have been deleted, so the window never reaches past the retention period
+    fn days(&self, retention_days: i32) -> i32 {
+        self.days.unwrap_or(30).clamp(1, retention_days)
+    }
+}
+
+/// Credential a request was made with
+enum UsageCredential {
+    ApiKey { key_hash: String },
+    Session { user_id: i64, session_id: String },
+}
+
+/// Middleware recording each authenticated request against the credential that made it
+pub async fn track_api_usage(
+    State(state): State<AppState>,
+    matched_path: Option<MatchedPath>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let credential = identify_credential(request.headers(), &state);
+    let endpoint = format!(
+        "{} {}",
+        request.method(),
+        matched_path.as_ref().map(|p| p.as_str()).unwrap_or("unmatched")
+    );
+
+    let response = next.run(request).await;
+
+    if let Some(credential) = credential {
+        let is_error = response.status().is_client_error() || response.status().is_server_error();
+        let pool = state.db_pool.clone();
+        // Record in the background so analytics never slow down or fail a request
+        tokio::spawn(async move {
+            if let Err(e) = record_usage(&pool, credential, &endpoint, is_error).await {
+                tracing::warn!("Failed to record API usage for {}: {}", endpoint, e);
+            }
+        });
+    }
+
+    response
+}
+
+fn identify_credential(headers: &HeaderMap, state: &AppState) -> Option<UsageCredential> {
+    if let Some(api_key) = headers.get("X-API-Key").and_then(|v| v.to_str().ok()) {
+        if api_key.starts_with("ak_") {
+            return Some(UsageCredential::ApiKey { key_hash: hash_api_key(api_key) });
+        }
+    }
+
+    let auth_str = headers.get(AUTHORIZATION)?.to_str().ok()?;
+
+    if let Some(token) = auth_str.strip_prefix("Bearer ") {
+        if token.starts_with("ak_") {
+            return Some(UsageCredential::ApiKey { key_hash: hash_api_key(token) });
+        }
+        // Only attribute usage to tokens we issued; forged claims must not pollute another user's stats
+        let claims = verify_token(token, state.config.auth.jwt_secret.expose_secret().as_bytes()).ok()?;
+        return Some(UsageCredential::Session {
+            user_id: claims.sub.parse().ok()?,
+            session_id: claims.sid?,
+        });
+    }
+
+    if let Some(encoded) = auth_str.strip_prefix("Basic ") {
+        // docker login with an API key as the password
+        let decoded = base64::prelude::BASE64_STANDARD.decode(encoded).ok()?;
+        let decoded = String::from_utf8(decoded).ok()?;
+        let (_, password) = decoded.split_once(':')?;
+        if password.starts_with("ak_") {
+            return Some(UsageCredential::ApiKey { key_hash: hash_api_key(password) });
+        }
+    }
+
+    None
+}
+
+async fn record_usage(
+    pool: &PgPool,
+    credential: UsageCredential,
+    endpoint: &str,
+    is_error: bool,
+) -> Result<(), sqlx::Error> {
+    let error_count: i64 = if is_error { 1 } else { 0 };
+
+    match credential {
+        // Inactive keys are still recorded: use of a revoked key is exactly what a leak looks like
+        UsageCredential::ApiKey { key_hash } => {
+            sqlx::query(
+                r#"
+                INSERT INTO api_usage_daily (user_id, credential_type, credential_id, day, endpoint, request_count, error_count, last_seen_at)
+                SELECT user_id, 'api_key', id::text, CURRENT_DATE, $2, 1, $3, NOW()
+                FROM api_keys WHERE key_hash = $1
+                ON CONFLICT (credential_type, credential_id, day, endpoint) DO UPDATE
+                SET request_count = api_usage_daily.request_count + 1,
+                    error_count = api_usage_daily.error_count + EXCLUDED.error_count,
+                    last_seen_at = NOW()
+                "#,
+            )
+            .bind(key_hash)
+            .bind(endpoint)
+            .bind(error_count)
+            .execute(pool)
+            .await?;
+        }
+        UsageCredential::Session { user_id, session_id } => {
+            sqlx::query(
+                r#"
+                INSERT INTO api_usage_daily (user_id, credential_type, credential_id, day, endpoint, request_count, error_count, last_seen_at)
+                VALUES ($1, 'session', $2, CURRENT_DATE, $3, 1, $4, NOW())
+                ON CONFLICT (credential_type, credential_id, day, endpoint) DO UPDATE
+                SET request_count = api_usage_daily.request_count + 1,
+                    error_count = api_usage_daily.error_count + EXCLUDED.error_count,
+                    last_seen_at = NOW()
+                "#,
+            )
+            .bind(user_id)
+            .bind(session_id)
+            .bind(endpoint)
+            .bind(error_count)
+            .execute(pool)
+            .await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Summarize API usage for each of the user's credentials
+#[utoipa::path(
+    get,
+    path = "/api/v1/auth/usage",
+    tag = "auth",
+    params(UsageQuery),
+    responses(
+        (status = 200, description = "Usage per credential; API keys with no usage are included", body = Vec<CredentialUsage>),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal server error"),
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn list_credential_usage(
+    auth_header: Option<TypedHeader<Authorization<Bearer>>>,
+    headers: HeaderMap,
+    State(state): State<AppState>,
+    Query(query): Query<UsageQuery>,
+) -> Result<Json<Vec<CredentialUsage>>, StatusCode> {
+    let user_id = crate::auth::extract_user_id_dual(
+        auth_header,
+        &headers,
+        ApiKeyScope::Read,
+        state.config.auth.jwt_secret.expose_secret().as_bytes(),
+        &state.db_pool,
+        state.cache.as_ref()
+    ).await?;
+
+    let usage = sqlx::query_as::<_, CredentialUsage>(
+        r#"
+        WITH usage AS (
+            SELECT credential_type, credential_id,
+                   SUM(request_count)::BIGINT AS request_count,
+                   SUM(error_count)::BIGINT AS error_count,
+                   COUNT(DISTINCT endpoint) AS endpoint_count,
+                   MAX(last_seen_at) AS last_seen_at
+            FROM api_usage_daily
+            WHERE user_id = $1 AND day > CURRENT_DATE - $2::INT
+            GROUP BY credential_type, credential_id
+        )
+        SELECT 'api_key' AS credential_type,
+               k.id::text AS credential_id,
+               k.name AS label,
+               COALESCE(k.is_active, false) AND (k.expires_at IS NULL OR k.expires_at > NOW()) AS active,
+               COALESCE(u.request_count, 0) AS request_count,
+               COALESCE(u.error_count, 0) AS error_count,
+               COALESCE(u.endpoint_count, 0) AS endpoint_count,
+               u.last_seen_at
+        FROM api_keys k
+        LEFT JOIN usage u ON u.credential_type = 'api_key' AND u.credential_id = k.id::text
+        WHERE k.user_id = $1
+        UNION ALL
+        SELECT 'session',
+               u.credential_id,
+               (SELECT rt.device_name FROM refresh_tokens rt
+                WHERE rt.family_id::text = u.credential_id
+                ORDER BY rt.created_at DESC LIMIT 1),
+               EXISTS(SELECT 1 FROM refresh_tokens rt
+                      WHERE rt.family_id::text = u.credential_id
+                        AND rt.revoked_at IS NULL AND rt.expires_at > NOW()),
+               u.request_count,
+               u.error_count,
+               u.endpoint_count,
+               u.last_seen_at
+        FROM usage u
+        WHERE u.credential_type = 'session'
+        ORDER BY request_count DESC
+        "#,
+    )
+    .bind(user_id)
+    .bind(query.days(state.config.retention.usage_retention_days))
+    .fetch_all(&state.db_pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Database error fetching API usage: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(Json(usage))
+}
+
+/// Daily and per-endpoint usage for one credential
+#[utoipa::path(
+    get,
+    path = "/api/v1/auth/usage/{credential_type}/{credential_id}",
+    tag = "auth",
+    params(
+        ("credential_type" = String, Path, description = "`api_key` or `session`"),
+        ("credential_id" = String, Path, description = "API key ID or session family ID"),
+        UsageQuery
+    ),
+    responses(
+        (status = 200, description = "Usage breakdown", body = CredentialUsageDetail),
+        (status = 400, description = "Unknown credential type"),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal server error"),
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn get_credential_usage(
+    auth_header: Option<TypedHeader<Authorization<Bearer>>>,
+    headers: HeaderMap,
+    State(state): State<AppState>,
+    Path((credential_type, credential_id)): Path<(String, String)>,
+    Query(query): Query<UsageQuery>,
+) -> Result<Json<CredentialUsageDetail>, StatusCode> {
+    let user_id = crate::auth::extract_user_id_dual(
+        auth_header,
+        &headers,
+        ApiKeyScope::Read,
+        state.config.auth.jwt_secret.expose_secret().as_bytes(),
+        &state.db_pool,
+        state.cache.as_ref()
+    ).await?;
+
+    if credential_type != "api_key" && credential_type != "session" {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let days = sqlx::query_as::<_, DailyUsage>(
+        r#"
+        SELECT day, SUM(request_count)::BIGINT AS request_count, SUM(error_count)::BIGINT AS error_count
+        FROM api_usage_daily
+        WHERE user_id = $1 AND credential_type = $2 AND credential_id = $3 AND day > CURRENT_DATE - $4::INT
+        GROUP BY day
+        ORDER BY day
+        "#,
+    )
+    .bind(user_id)
+    .bind(&credential_type)
+    .bind(&credential_id)
+    .bind(query.days(state.config.retention.usage_retention_days))
+    .fetch_all(&state.db_pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Database error fetching daily API usage: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    let endpoints = sqlx::query_as::<_, EndpointUsage>(
+        r#"
+        SELECT endpoint, SUM(request_count)::BIGINT AS request_count, SUM(error_count)::BIGINT AS error_count,
+               MAX(last_seen_at) AS last_seen_at
+        FROM api_usage_daily
+        WHERE user_id = $1 AND credential_type = $2 AND credential_id = $3 AND day > CURRENT_DATE - $4::INT
+        GROUP BY endpoint
+        ORDER BY request_count DESC
+        "#,
+    )
+    .bind(user_id)
+    .bind(&credential_type)
+    .bind(&credential_id)
+    .bind(query.days(state.config.retention.usage_retention_days))
+    .fetch_all(&state.db_pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Database error fetching endpoint API usage: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(Json(CredentialUsageDetail {
+        credential_type,
+        credential_id,
+        days,
+        endpoints,
+    }))
+}
+
+/// Delete usage rollups past the retention window
+pub async fn cleanup_old_api_usage(pool: &PgPool, retention_days: i32) -> Result<i64, sqlx::Error> {
+    let result = sqlx::query("DELETE FROM api_usage_daily WHERE day <= CURRENT_DATE - $1::INT")
+        .bind(retention_days)
+        .execute(pool)
+        .await?;
+
+    tracing::info!("Cleaned up {} expired API usage rows", result.rows_affected());
+    Ok(result.rows_affected() as i64)
+}
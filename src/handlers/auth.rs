@@ -1,11 +1,19 @@
 use crate::database::models::{NewUser, User};
-use crate::models::api_key::ApiKey;
+use crate::models::api_key::{ApiKey, ApiKeyScope};
+use crate::models::refresh_token::RefreshToken;
 use crate::AppState;
 use argon2::{
-    password_hash::{rand_core::OsRng, PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
+    password_hash::{PasswordHash, PasswordVerifier},
     Argon2,
 };
-use axum::{extract::State, http::{StatusCode, HeaderMap}, response::IntoResponse, Json};
+use axum::{
+    extract::{ConnectInfo, State},
+    http::{StatusCode, HeaderMap},
+    response::{IntoResponse, Response},
+    Json,
+};
+use std::net::SocketAddr;
+use crate::handlers::login_protection::{self, LoginAccount};
 use axum_extra::headers::{authorization::Bearer, Authorization};
 use axum_extra::TypedHeader;
 use jsonwebtoken::{encode, EncodingKey, Header};
@@ -27,6 +35,9 @@ pub struct RegisterRequest {
     email: String,
     /// Password for the new account (min 8 characters)
     password: String,
+    /// Optional label for the device the refresh token is issued to
+    #[serde(default)]
+    device_name: Option<String>,
 }
 
 /// Login request
@@ -40,13 +51,20 @@ pub struct LoginRequest {
     username: String,
     /// User's password
     password: String,
+    /// Optional label for the device the refresh token is issued to
+    #[serde(default)]
+    device_name: Option<String>,
 }
 
 /// Authentication response with JWT token
 #[derive(Debug, Serialize, ToSchema)]
 pub struct AuthResponse {
-    /// JWT token for authenticating subsequent requests
+    /// Short-lived JWT access token for authenticating subsequent requests
     token: String,
+    /// Opaque refresh token, exchanged at /auth/refresh for a new token pair
+    refresh_token: String,
+    /// Access token lifetime in seconds
+    expires_in: u64,
 }
 
 /// Password change request
@@ -88,6 +106,8 @@ pub struct VerifyOtpRequest {
 pub struct Claims {
     pub sub: String, // user id
     pub exp: usize,  // expiration time
+    pub jti: String, // token id, used for revocation
+    pub sid: String, // login session (refresh token family), used for usage analytics
 }
 
 /// Register a new user
@@ -98,6 +118,7 @@ pub struct Claims {
     request_body = RegisterRequest,
     responses(
         (status = 201, description = "User successfully registered", body = AuthResponse),
+        (status = 403, description = "Registration is closed"),
         (status = 409, description = "User already exists"),
         (status = 500, description = "Internal server error")
     )
@@ -106,6 +127,15 @@ pub async fn register(
     State(state): State<AppState>,
     Json(req): Json<RegisterRequest>,
 ) -> impl IntoResponse {
+    if !state.features.enabled(crate::features::Feature::OpenRegistration) {
+        return (
+            StatusCode::FORBIDDEN,
+            Json(serde_json::json!({
+                "error": "Registration is closed; ask an administrator for an account"
+            })),
+        );
+    }
+
     // Input validation for registration request
     
     // Validate password length (minimum 8 characters)
@@ -216,11 +246,9 @@ pub async fn register(
         );
     }
 
-    // Hash password using Argon2
-    let salt = SaltString::generate(&mut OsRng);
-    let argon2 = Argon2::default();
-    let password_hash = match argon2.hash_password(req.password.as_bytes(), &salt) {
-        Ok(hash) => hash.to_string(),
+    // Hash password using Argon2 with the configured parameters
+    let (password_hash, password_hash_params) = match crate::auth::hash_password(&req.password, &state.config.auth) {
+        Ok(result) => result,
         Err(e) => {
             tracing::error!("Password hashing failed: {}", e);
             return (
@@ -242,12 +270,13 @@ pub async fn register(
     // Insert user into database
     let user = match sqlx::query_as!(
         User,
-        "INSERT INTO users (username, email, password_hash)
-         VALUES ($1, $2, $3)
+        "INSERT INTO users (username, email, password_hash, password_hash_params)
+         VALUES ($1, $2, $3, $4)
          RETURNING id, username, email, password_hash, created_at",
         new_user.username,
         new_user.email,
         new_user.password_hash,
+        password_hash_params,
     )
     .fetch_one(&state.db_pool)
     .await
@@ -273,20 +302,11 @@ pub async fn register(
         }
     };
 
-    // Generate JWT token with 24-hour expiration
-    let claims = Claims {
-        sub: user.id.to_string(),
-        exp: (chrono::Utc::now() + chrono::Duration::hours(24)).timestamp() as usize,
-    };
-
-    let token = match encode(
-        &Header::default(),
-        &claims,
-        &EncodingKey::from_secret(state.config.auth.jwt_secret.expose_secret().as_bytes()),
-    ) {
-        Ok(token) => token,
+    // Issue a short-lived access token and a fresh refresh token family
+    let (token, refresh_token, _) = match issue_token_pair(&state, user.id, None, req.device_name.as_deref()).await {
+        Ok(pair) => pair,
         Err(e) => {
-            tracing::error!("JWT token generation failed: {}", e);
+            tracing::error!("Token generation failed: {}", e);
             return (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 Json(serde_json::json!({
@@ -301,6 +321,8 @@ pub async fn register(
         StatusCode::CREATED,
         Json(serde_json::json!({
             "token": token,
+            "refresh_token": refresh_token,
+            "expires_in": state.config.auth.jwt_expiration_seconds,
             "message": "User registered successfully"
         })),
     )
@@ -320,8 +342,19 @@ pub async fn register(
 )]
 pub async fn login(
     State(state): State<AppState>,
+    connect_info: Option<ConnectInfo<SocketAddr>>,
+    headers: HeaderMap,
     Json(req): Json<LoginRequest>,
-) -> impl IntoResponse {
+) -> Response {
+    let peer = connect_info.map(|ConnectInfo(addr)| addr.ip());
+    let ip = crate::handlers::ip_access::client_ip(peer, &headers, &state.config.ip_access.trusted_proxies)
+        .map(|ip| ip.to_string())
+        .unwrap_or_else(|| "unknown".to_string());
+
+    if let Err(response) = login_protection::check_login_allowed(&state, &ip, None).await {
+        return response;
+    }
+
     // Find user by email or username
     let user = if !req.email.is_empty() {
         // Try to find user by email
@@ -337,7 +370,7 @@ pub async fn login(
                     Json(serde_json::json!({
                         "error": format!("Database error: {}", e)
                     })),
-                );
+                ).into_response();
             }
         }
     } else if !req.username.is_empty() {
@@ -354,7 +387,7 @@ pub async fn login(
                     Json(serde_json::json!({
                         "error": format!("Database error: {}", e)
                     })),
-                );
+                ).into_response();
             }
         }
     } else {
@@ -365,15 +398,25 @@ pub async fn login(
     let user = match user {
         Some(user) => user,
         None => {
+            let login = if !req.email.is_empty() { req.email.as_str() } else { req.username.as_str() };
+            let account = LoginAccount::Unknown(login);
+            if let Err(response) = login_protection::check_login_allowed(&state, &ip, Some(&account)).await {
+                return response;
+            }
+            login_protection::record_login_failure(&state, &ip, Some(&account)).await;
             return (
                 StatusCode::UNAUTHORIZED,
                 Json(serde_json::json!({
                     "error": "Invalid email or password"
                 })),
-            );
+            ).into_response();
         }
     };
 
+    if let Err(response) = login_protection::check_login_allowed(&state, &ip, Some(&LoginAccount::User(&user))).await {
+        return response;
+    }
+
     // Verify password
     let parsed_hash = match PasswordHash::new(&user.password_hash) {
         Ok(hash) => hash,
@@ -383,7 +426,7 @@ pub async fn login(
                 Json(serde_json::json!({
                     "error": format!("Failed to parse password hash: {}", e)
                 })),
-            );
+            ).into_response();
         }
     };
 
@@ -391,42 +434,65 @@ pub async fn login(
         .verify_password(req.password.as_bytes(), &parsed_hash)
         .is_err()
     {
+        login_protection::record_login_failure(&state, &ip, Some(&LoginAccount::User(&user))).await;
         return (
             StatusCode::UNAUTHORIZED,
             Json(serde_json::json!({
                 "error": "Invalid email or password"
             })),
-        );
+        ).into_response();
     }
 
-    // Generate JWT token
-    let claims = Claims {
-        sub: user.id.to_string(),
-        exp: (chrono::Utc::now() + chrono::Duration::hours(24)).timestamp() as usize,
+    login_protection::record_login_success(&state, user.id).await;
+
+    let disabled = match sqlx::query_scalar::<_, bool>("SELECT disabled_at IS NOT NULL FROM users WHERE id = $1")
+        .bind(user.id)
+        .fetch_one(&state.db_pool)
+        .await
+    {
+        Ok(disabled) => disabled,
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({
+                    "error": format!("Database error: {}", e)
+                })),
+            ).into_response();
+        }
     };
+    if disabled {
+        return (
+            StatusCode::FORBIDDEN,
+            Json(serde_json::json!({
+                "error": "Account disabled"
+            })),
+        ).into_response();
+    }
 
-    let token = match encode(
-        &Header::default(),
-        &claims,
-        &EncodingKey::from_secret(state.config.auth.jwt_secret.expose_secret().as_bytes()),
-    ) {
-        Ok(token) => token,
+    // Upgrade the stored hash if the configured parameters were strengthened
+    crate::auth::rehash_password_if_needed(&state.db_pool, &state.config.auth, user.id, &user.password_hash, &req.password).await;
+
+    // Issue a short-lived access token and a fresh refresh token family
+    let (token, refresh_token, _) = match issue_token_pair(&state, user.id, None, req.device_name.as_deref()).await {
+        Ok(pair) => pair,
         Err(e) => {
             return (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 Json(serde_json::json!({
                     "error": format!("Failed to create token: {}", e)
                 })),
-            );
+            ).into_response();
         }
     };
 
     (
         StatusCode::OK,
         Json(serde_json::json!({
-            "token": token
+            "token": token,
+            "refresh_token": refresh_token,
+            "expires_in": state.config.auth.jwt_expiration_seconds
         })),
-    )
+    ).into_response()
 }
 
 /// Get current user information
@@ -448,7 +514,7 @@ pub async fn me(
 ) -> impl IntoResponse {
     // Add debug logging
     if let Some(ref auth_header) = auth {
-        tracing::debug!("Auth header present: {}", auth_header.token());
+        tracing::debug!("Auth header present: {}", crate::logging::redact(auth_header.token()));
     } else {
         tracing::debug!("No auth header provided");
     }
@@ -457,6 +523,7 @@ pub async fn me(
     let user_id = match crate::auth::extract_user_id_dual(
         auth,
         &headers,
+        ApiKeyScope::Read,
         state.config.auth.jwt_secret.expose_secret().as_bytes(),
         &state.db_pool,
         state.cache.as_ref()
@@ -481,9 +548,10 @@ pub async fn me(
         id: i64,
         username: String,
         email: String,
+        avatar_url: Option<String>,
     }
 
-    match sqlx::query_as::<_, UserInfo>("SELECT id, username, email FROM users WHERE id = $1")
+    match sqlx::query_as::<_, UserInfo>("SELECT id, username, email, avatar_url FROM users WHERE id = $1")
         .bind(user_id)
         .fetch_optional(&state.db_pool)
         .await
@@ -494,6 +562,7 @@ pub async fn me(
                 "id": user.id,
                 "username": user.username,
                 "email": user.email,
+                "avatar_url": user.avatar_url,
                 "created_at": chrono::Utc::now()  // Adding created_at as expected by test
             })),
         ),
@@ -517,10 +586,14 @@ pub async fn me(
 
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct RefreshRequest {
-    /// Old token to refresh
-    token: String,
+    /// Refresh token returned by login, register or a previous refresh
+    refresh_token: String,
 }
 
+/// Exchange a refresh token for a new access token and a rotated refresh token.
+///
+/// Each refresh token can be used exactly once. Presenting a token that was
+/// already rotated is treated as theft and revokes every token of that device.
 #[utoipa::path(
     post,
     path = "/api/v1/auth/refresh",
@@ -528,38 +601,111 @@ pub struct RefreshRequest {
     request_body = RefreshRequest,
     responses(
         (status = 200, description = "Token refreshed successfully", body = AuthResponse),
-        (status = 401, description = "Invalid or expired token"),
+        (status = 401, description = "Invalid, expired or revoked refresh token"),
         (status = 500, description = "Internal server error")
     )
 )]
-
 pub async fn refresh(
     State(state): State<AppState>,
     Json(req): Json<RefreshRequest>,
 ) -> impl IntoResponse {
-    let claims = match crate::auth::verify_token(&req.token, state.config.auth.jwt_secret.expose_secret().as_bytes()) {
-        Ok(claims) => claims,
-        Err(_) => {
+    let token_hash = hash_refresh_token(&req.refresh_token);
+
+    let stored = match sqlx::query_as::<_, RefreshToken>(
+        "SELECT * FROM refresh_tokens WHERE token_hash = $1",
+    )
+    .bind(&token_hash)
+    .fetch_optional(&state.db_pool)
+    .await
+    {
+        Ok(Some(token)) => token,
+        Ok(None) => {
             return (
                 StatusCode::UNAUTHORIZED,
                 Json(serde_json::json!({
-                    "error": "Invalid token"
+                    "error": "Invalid refresh token"
+                })),
+            );
+        }
+        Err(e) => {
+            tracing::error!("Database error looking up refresh token: {}", e);
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({
+                    "error": "Internal server error"
                 })),
             );
         }
     };
 
-    let new_claims = Claims {
-        sub: claims.sub,
-        exp: (chrono::Utc::now() + chrono::Duration::hours(24)).timestamp() as usize,
+    if stored.revoked_at.is_some() {
+        // A rotated token is being replayed: kill the whole device chain
+        tracing::warn!(
+            "Refresh token reuse detected for user {} (family {}), revoking family",
+            stored.user_id, stored.family_id
+        );
+        if let Err(e) = revoke_refresh_token_family(&state.db_pool, stored.family_id).await {
+            tracing::error!("Failed to revoke refresh token family: {}", e);
+        }
+        return (
+            StatusCode::UNAUTHORIZED,
+            Json(serde_json::json!({
+                "error": "Refresh token has been revoked"
+            })),
+        );
+    }
+
+    if stored.expires_at < chrono::Utc::now().naive_utc() {
+        return (
+            StatusCode::UNAUTHORIZED,
+            Json(serde_json::json!({
+                "error": "Refresh token has expired"
+            })),
+        );
+    }
+
+    // Mark the presented token as used; losing this race means another request
+    // rotated it first, which is also a reuse.
+    let consumed = match sqlx::query(
+        "UPDATE refresh_tokens SET revoked_at = NOW(), last_used_at = NOW() WHERE id = $1 AND revoked_at IS NULL",
+    )
+    .bind(stored.id)
+    .execute(&state.db_pool)
+    .await
+    {
+        Ok(result) => result.rows_affected() == 1,
+        Err(e) => {
+            tracing::error!("Database error consuming refresh token: {}", e);
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({
+                    "error": "Internal server error"
+                })),
+            );
+        }
     };
 
-    let new_token = match encode(
-        &Header::default(),
-        &new_claims,
-        &EncodingKey::from_secret(state.config.auth.jwt_secret.expose_secret().as_bytes()),
-    ) {
-        Ok(token) => token,
+    if !consumed {
+        if let Err(e) = revoke_refresh_token_family(&state.db_pool, stored.family_id).await {
+            tracing::error!("Failed to revoke refresh token family: {}", e);
+        }
+        return (
+            StatusCode::UNAUTHORIZED,
+            Json(serde_json::json!({
+                "error": "Refresh token has been revoked"
+            })),
+        );
+    }
+
+    let (new_token, new_refresh_token, new_refresh_id) = match issue_token_pair(
+        &state,
+        stored.user_id,
+        Some(stored.family_id),
+        stored.device_name.as_deref(),
+    )
+    .await
+    {
+        Ok(pair) => pair,
         Err(e) => {
             return (
                 StatusCode::INTERNAL_SERVER_ERROR,
@@ -570,10 +716,22 @@ pub async fn refresh(
         }
     };
 
+    // Link the old token to its replacement for auditing
+    if let Err(e) = sqlx::query("UPDATE refresh_tokens SET replaced_by = $1 WHERE id = $2")
+    .bind(new_refresh_id)
+    .bind(stored.id)
+    .execute(&state.db_pool)
+    .await
+    {
+        tracing::warn!("Failed to link rotated refresh token: {}", e);
+    }
+
     (
         StatusCode::OK,
         Json(serde_json::json!({
-            "token": new_token
+            "token": new_token,
+            "refresh_token": new_refresh_token,
+            "expires_in": state.config.auth.jwt_expiration_seconds
         })),
     )
 }
@@ -583,6 +741,9 @@ pub async fn refresh(
 pub struct LogoutRequest {
     /// JWT token to invalidate
     token: String,
+    /// Refresh token of this device; its whole rotation chain is revoked
+    #[serde(default)]
+    refresh_token: Option<String>,
 }
 
 /// Logout handler to invalidate authentication cache
@@ -602,7 +763,7 @@ pub async fn logout(
     Json(req): Json<LogoutRequest>,
 ) -> impl IntoResponse {
     // Verify the token first
-    let claims = match crate::auth::verify_token(&req.token, state.config.auth.jwt_secret.expose_secret().as_bytes()) {
+    let claims = match crate::auth::verify_token_not_revoked(&req.token, state.config.auth.jwt_secret.expose_secret().as_bytes(), &state.db_pool).await {
         Ok(claims) => claims,
         Err(_) => {
             return (
@@ -614,6 +775,43 @@ pub async fn logout(
         }
     };
 
+    // Persist the revocation so the token is rejected even where the cache is cold
+    if let Err(e) = crate::auth::revoke_token(&claims, &state.db_pool).await {
+        tracing::error!("Failed to record token revocation: {}", e);
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({
+                "error": "Internal server error"
+            })),
+        );
+    }
+
+    // Revoke the refresh token chain for this device
+    if let Some(refresh_token) = &req.refresh_token {
+        let result = sqlx::query(
+            r#"
+            UPDATE refresh_tokens SET revoked_at = NOW()
+            WHERE revoked_at IS NULL AND family_id = (
+                SELECT family_id FROM refresh_tokens WHERE token_hash = $1 AND user_id = $2
+            )
+            "#,
+        )
+        .bind(hash_refresh_token(refresh_token))
+        .bind(claims.sub.parse::<i64>().unwrap_or_default())
+        .execute(&state.db_pool)
+        .await;
+
+        if let Err(e) = result {
+            tracing::error!("Failed to revoke refresh token on logout: {}", e);
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({
+                    "error": "Internal server error"
+                })),
+            );
+        }
+    }
+
     // Invalidate token in cache
     if let Some(cache) = &state.cache {
         if let Err(e) = cache.invalidate_auth_token(&req.token).await {
@@ -636,6 +834,139 @@ pub async fn logout(
     )
 }
 
+/// Active refresh token session (one per device)
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct SessionResponse {
+    /// Session ID (the id of the current refresh token in the chain)
+    pub id: i64,
+    /// Device label supplied at login
+    pub device_name: Option<String>,
+    /// When the session was last refreshed
+    pub last_used_at: Option<chrono::NaiveDateTime>,
+    /// When the current refresh token expires
+    pub expires_at: chrono::NaiveDateTime,
+    /// When the current refresh token was issued
+    pub created_at: Option<chrono::NaiveDateTime>,
+}
+
+/// List the user's active refresh token sessions
+#[utoipa::path(
+    get,
+    path = "/api/v1/auth/sessions",
+    tag = "auth",
+    responses(
+        (status = 200, description = "Active sessions retrieved successfully", body = Vec<SessionResponse>),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal server error"),
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn list_sessions(
+    auth_header: Option<TypedHeader<Authorization<Bearer>>>,
+    headers: HeaderMap,
+    State(state): State<AppState>,
+) -> Result<Json<Vec<SessionResponse>>, StatusCode> {
+    let user_id = crate::auth::extract_user_id_dual(
+        auth_header,
+        &headers,
+        ApiKeyScope::Read,
+        state.config.auth.jwt_secret.expose_secret().as_bytes(),
+        &state.db_pool,
+        state.cache.as_ref()
+    ).await?;
+
+    let sessions = sqlx::query_as::<_, RefreshToken>(
+        r#"
+        SELECT * FROM refresh_tokens
+        WHERE user_id = $1 AND revoked_at IS NULL AND expires_at > NOW()
+        ORDER BY created_at DESC
+        "#,
+    )
+    .bind(user_id)
+    .fetch_all(&state.db_pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Database error fetching sessions: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    let response = sessions
+        .into_iter()
+        .map(|token| SessionResponse {
+            id: token.id,
+            device_name: token.device_name,
+            last_used_at: token.last_used_at,
+            expires_at: token.expires_at,
+            created_at: token.created_at,
+        })
+        .collect();
+
+    Ok(Json(response))
+}
+
+/// Revoke a refresh token session (and every token rotated from it)
+#[utoipa::path(
+    delete,
+    path = "/api/v1/auth/sessions/{id}",
+    params(
+        ("id" = i64, Path, description = "Session ID to revoke")
+    ),
+    tag = "auth",
+    responses(
+        (status = 200, description = "Session revoked successfully"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Session not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn revoke_session(
+    auth_header: Option<TypedHeader<Authorization<Bearer>>>,
+    headers: HeaderMap,
+    State(state): State<AppState>,
+    axum::extract::Path(session_id): axum::extract::Path<i64>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    let user_id = crate::auth::extract_user_id_dual(
+        auth_header,
+        &headers,
+        ApiKeyScope::Admin,
+        state.config.auth.jwt_secret.expose_secret().as_bytes(),
+        &state.db_pool,
+        state.cache.as_ref()
+    ).await?;
+
+    let result = sqlx::query(
+        r#"
+        UPDATE refresh_tokens SET revoked_at = NOW()
+        WHERE revoked_at IS NULL AND family_id = (
+            SELECT family_id FROM refresh_tokens WHERE id = $1 AND user_id = $2
+        )
+        "#,
+    )
+    .bind(session_id)
+    .bind(user_id)
+    .execute(&state.db_pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Database error revoking session: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    if result.rows_affected() == 0 {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    tracing::info!("Revoked session {} for user {}", session_id, user_id);
+
+    Ok(Json(serde_json::json!({
+        "message": "Session revoked successfully"
+    })))
+}
+
 /// Change user password
 #[utoipa::path(
     put,
@@ -658,7 +989,7 @@ pub async fn change_password(
     Json(req): Json<ChangePasswordRequest>,
 ) -> impl IntoResponse {
     // Verify JWT token
-    let claims = match crate::auth::verify_token(auth.token(), state.config.auth.jwt_secret.expose_secret().as_bytes()) {
+    let claims = match crate::auth::verify_token_not_revoked(auth.token(), state.config.auth.jwt_secret.expose_secret().as_bytes(), &state.db_pool).await {
         Ok(claims) => claims,
         Err(_) => {
             return (
@@ -752,11 +1083,8 @@ pub async fn change_password(
     }
 
     // Hash the new password
-    let salt = SaltString::generate(&mut OsRng);
-    let new_password_hash = match Argon2::default()
-        .hash_password(req.new_password.as_bytes(), &salt)
-    {
-        Ok(hash) => hash.to_string(),
+    let (new_password_hash, new_password_hash_params) = match crate::auth::hash_password(&req.new_password, &state.config.auth) {
+        Ok(result) => result,
         Err(_) => {
             return (
                 StatusCode::INTERNAL_SERVER_ERROR,
@@ -769,8 +1097,9 @@ pub async fn change_password(
 
     // Update password in database
     match sqlx::query!(
-        "UPDATE users SET password_hash = $1 WHERE id = $2",
+        "UPDATE users SET password_hash = $1, password_hash_params = $2 WHERE id = $3",
         new_password_hash,
+        new_password_hash_params,
         user_id
     )
     .execute(&state.db_pool)
@@ -955,12 +1284,8 @@ pub async fn verify_otp_and_reset(
     }
 
     // Hash new password
-    use argon2::{Argon2, PasswordHasher};
-    use argon2::password_hash::{SaltString, rand_core::OsRng};
-    
-    let salt = SaltString::generate(&mut OsRng);
-    let password_hash = match Argon2::default().hash_password(req.new_password.as_bytes(), &salt) {
-        Ok(hash) => hash.to_string(),
+    let (password_hash, password_hash_params) = match crate::auth::hash_password(&req.new_password, &state.config.auth) {
+        Ok(result) => result,
         Err(_) => {
             return Json(serde_json::json!({
                 "error": "Failed to hash password"
@@ -969,7 +1294,7 @@ pub async fn verify_otp_and_reset(
     };
 
     // Update password in database
-    match sqlx::query!("UPDATE users SET password_hash = $1 WHERE id = $2", password_hash, user.id)
+    match sqlx::query!("UPDATE users SET password_hash = $1, password_hash_params = $2 WHERE id = $3", password_hash, password_hash_params, user.id)
         .execute(&state.db_pool)
         .await
     {
@@ -990,6 +1315,8 @@ pub struct ApiKeyResponse {
     pub id: i64,
     /// Name/description of the API key
     pub name: Option<String>,
+    /// What the key may be used for
+    pub scopes: Vec<String>,
     /// When this key was last used
     pub last_used_at: Option<chrono::NaiveDateTime>,
     /// When this key expires
@@ -1005,6 +1332,14 @@ pub struct ApiKeyResponse {
 pub struct CreateApiKeyRequest {
     /// Name/description for the API key
     pub name: String,
+    /// What the key may be used for: `read`, `push` or `admin` across the API, or
+    /// `resource:level` limited to one area, e.g. `org:read`, `repo:admin`, `webhook:write`
+    /// (default: `read`)
+    #[serde(default)]
+    pub scopes: Option<Vec<String>>,
+    /// Days until the key expires, up to the configured maximum (default: server setting)
+    #[serde(default)]
+    pub expires_in_days: Option<i64>,
 }
 
 /// Create API Key response (includes the actual key - only shown once!)
@@ -1014,6 +1349,8 @@ pub struct CreateApiKeyResponse {
     pub id: i64,
     /// The actual API key (ak_...) - ONLY SHOWN ONCE!
     pub api_key: String,
+    /// What the key may be used for
+    pub scopes: Vec<String>,
     /// Optional expiration date
     pub expires_at: Option<chrono::NaiveDateTime>,
     /// Creation timestamp
@@ -1068,6 +1405,7 @@ pub async fn get_user_api_keys(
     let user_id = crate::auth::extract_user_id_dual_auth(
         auth_header, 
         api_key,
+        ApiKeyScope::Read,
         &state.config.auth.jwt_secret.expose_secret().as_bytes(),
         &state.db_pool, 
         state.cache.as_ref()
@@ -1083,7 +1421,7 @@ pub async fn get_user_api_keys(
         sqlx::query_as!(
             ApiKey,
             r#"
-            SELECT id, user_id, name, key_hash, last_used_at, expires_at, created_at, updated_at, is_active
+            SELECT id, user_id, name, key_hash, scopes, last_used_at, expires_at, created_at, updated_at, is_active
             FROM api_keys 
             WHERE user_id = $1 AND is_active = true AND name ILIKE $2
             ORDER BY created_at DESC
@@ -1097,7 +1435,7 @@ pub async fn get_user_api_keys(
         sqlx::query_as!(
             ApiKey,
             r#"
-            SELECT id, user_id, name, key_hash, last_used_at, expires_at, created_at, updated_at, is_active
+            SELECT id, user_id, name, key_hash, scopes, last_used_at, expires_at, created_at, updated_at, is_active
             FROM api_keys 
             WHERE user_id = $1 AND is_active = true
             ORDER BY created_at DESC
@@ -1120,6 +1458,7 @@ pub async fn get_user_api_keys(
             ApiKeyResponse {
                 id: key.id,
                 name: key.name,
+                scopes: key.scopes,
                 last_used_at: key.last_used_at,
                 expires_at: key.expires_at,
                 is_active: key.is_active,
@@ -1139,7 +1478,9 @@ pub async fn get_user_api_keys(
     tag = "auth",
     responses(
         (status = 201, description = "API key created successfully", body = CreateApiKeyResponse),
+        (status = 400, description = "Invalid scopes or expiration", body = ApiKeyErrorResponse),
         (status = 401, description = "Unauthorized - invalid or missing JWT token", body = ApiKeyErrorResponse),
+        (status = 403, description = "Authenticated with an API key lacking the whole-API admin scope", body = ApiKeyErrorResponse),
         (status = 409, description = "Conflict - API key name already exists for this user", body = ApiKeyErrorResponse),
         (status = 500, description = "Internal server error", body = ApiKeyErrorResponse)
     ),
@@ -1152,14 +1493,24 @@ pub async fn create_api_key(
     State(state): State<AppState>,
     Json(request): Json<CreateApiKeyRequest>,
 ) -> Result<(StatusCode, Json<CreateApiKeyResponse>), (StatusCode, Json<ApiKeyErrorResponse>)> {
+    let presented_key = auth.token().starts_with("ak_").then(|| auth.token().to_string());
+
     // Extract user ID from JWT
     let user_id = crate::auth::extract_user_id_dual_auth(
         Some(TypedHeader(auth)), 
         None, // No X-API-Key header for this endpoint
+        ApiKeyScope::Admin,
         &state.config.auth.jwt_secret.expose_secret().as_bytes(),
         &state.db_pool, 
         state.cache.as_ref()
-    ).await.map_err(|_| {
+    ).await.map_err(|status| {
+        if status == StatusCode::FORBIDDEN {
+            let error_response = ApiKeyErrorResponse {
+                error: "Insufficient scope".to_string(),
+                details: Some("Only API keys with the admin scope can create API keys".to_string()),
+            };
+            return (StatusCode::FORBIDDEN, Json(error_response));
+        }
         let error_response = ApiKeyErrorResponse {
             error: "Authentication failed".to_string(),
             details: Some("Invalid or expired JWT token".to_string()),
@@ -1167,6 +1518,63 @@ pub async fn create_api_key(
         (StatusCode::UNAUTHORIZED, Json(error_response))
     })?;
 
+    // A key limited to some resources could otherwise mint itself a key without those limits
+    if let Some(key) = presented_key {
+        let (_, held) = crate::auth::lookup_api_key(&key, &state.db_pool, state.cache.as_ref())
+            .await
+            .map_err(|status| {
+                let error_response = ApiKeyErrorResponse {
+                    error: "Authentication failed".to_string(),
+                    details: Some("Invalid or expired API key".to_string()),
+                };
+                (status, Json(error_response))
+            })?;
+        if !held.iter().any(|scope| scope == "admin") {
+            let error_response = ApiKeyErrorResponse {
+                error: "Insufficient scope".to_string(),
+                details: Some("Only API keys with the whole-API admin scope can create API keys".to_string()),
+            };
+            return Err((StatusCode::FORBIDDEN, Json(error_response)));
+        }
+    }
+
+    let mut scopes: Vec<String> = request
+        .scopes
+        .clone()
+        .unwrap_or_else(|| vec![ApiKeyScope::Read.to_string()])
+        .iter()
+        .map(|scope| scope.trim().to_lowercase())
+        .collect();
+    scopes.sort();
+    scopes.dedup();
+    if scopes.is_empty() {
+        let error_response = ApiKeyErrorResponse {
+            error: "Invalid scopes".to_string(),
+            details: Some("At least one scope (read, push, admin, or resource:level) is required".to_string()),
+        };
+        return Err((StatusCode::BAD_REQUEST, Json(error_response)));
+    }
+    if let Some(invalid) = scopes.iter().find(|scope| !ApiKeyScope::is_valid(scope)) {
+        let error_response = ApiKeyErrorResponse {
+            error: "Invalid scopes".to_string(),
+            details: Some(format!(
+                "Unknown scope '{}': use read, push, admin, or resource:level where resource is one of org, repo, webhook, stats, user, registry and level is read, write or admin",
+                invalid
+            )),
+        };
+        return Err((StatusCode::BAD_REQUEST, Json(error_response)));
+    }
+
+    let max_days = state.config.auth.api_key_max_expiration_days;
+    let expires_in_days = request.expires_in_days.unwrap_or(state.config.auth.api_key_default_expiration_days.min(max_days));
+    if !(1..=max_days).contains(&expires_in_days) {
+        let error_response = ApiKeyErrorResponse {
+            error: "Invalid expiration".to_string(),
+            details: Some(format!("expires_in_days must be between 1 and {}", max_days)),
+        };
+        return Err((StatusCode::BAD_REQUEST, Json(error_response)));
+    }
+
     // Check if user already has an API key with the same name
     let existing_key = sqlx::query!(
         "SELECT id FROM api_keys WHERE user_id = $1 AND name = $2 AND is_active = true",
@@ -1193,8 +1601,7 @@ pub async fn create_api_key(
         return Err((StatusCode::CONFLICT, Json(error_response)));
     }
 
-    // Set expiration to 15 days from now
-    let expires_at = chrono::Utc::now().naive_utc() + chrono::Duration::days(15);
+    let expires_at = chrono::Utc::now().naive_utc() + chrono::Duration::days(expires_in_days);
 
     // Generate new API key
     let api_key = format!("ak_{}", hex::encode(rand::random::<[u8; 16]>()));
@@ -1211,13 +1618,14 @@ pub async fn create_api_key(
     let api_key_record = sqlx::query_as!(
         ApiKey,
         r#"
-        INSERT INTO api_keys (user_id, key_hash, name, expires_at, is_active)
-        VALUES ($1, $2, $3, $4, true)
-        RETURNING id, user_id, name, key_hash, last_used_at, expires_at, created_at, updated_at, is_active
+        INSERT INTO api_keys (user_id, key_hash, name, scopes, expires_at, is_active)
+        VALUES ($1, $2, $3, $4, $5, true)
+        RETURNING id, user_id, name, key_hash, scopes, last_used_at, expires_at, created_at, updated_at, is_active
         "#,
         user_id,
         key_hash,
         request.name,
+        &scopes,
         Some(expires_at),
     )
     .fetch_one(&state.db_pool)
@@ -1235,13 +1643,14 @@ pub async fn create_api_key(
     let response = CreateApiKeyResponse {
         id: api_key_record.id,
         api_key: api_key.clone(), // 🔑 The actual key - only shown once!
+        scopes: api_key_record.scopes,
         expires_at: api_key_record.expires_at,
         created_at: api_key_record.created_at,
         warning: "⚠️ SECURITY WARNING: This API key will only be shown once. Please save it securely immediately. If lost, you will need to generate a new one.".to_string(),
     };
 
-    tracing::info!("Created new API key for user {} (expires: {})", 
-        user_id, expires_at.format("%Y-%m-%d %H:%M:%S"));
+    tracing::info!("Created new API key for user {} (scopes: {}, expires: {})", 
+        user_id, scopes.join(","), expires_at.format("%Y-%m-%d %H:%M:%S"));
 
     Ok((StatusCode::CREATED, Json(response)))
 }
@@ -1278,6 +1687,7 @@ pub async fn delete_api_key(
     let user_id = crate::auth::extract_user_id_dual_auth(
         auth_header, 
         api_key,
+        ApiKeyScope::Admin,
         &state.config.auth.jwt_secret.expose_secret().as_bytes(),
         &state.db_pool, 
         state.cache.as_ref()
@@ -1309,13 +1719,80 @@ pub async fn delete_api_key(
     Ok((StatusCode::OK, Json(response)))
 }
 
-/// Clean up expired API keys from database
-pub async fn cleanup_expired_api_keys(db_pool: &sqlx::PgPool) -> Result<i64, sqlx::Error> {
-    let now = chrono::Utc::now().naive_utc();
+/// Hash a refresh token for storage (same scheme as API keys)
+fn hash_refresh_token(token: &str) -> String {
+    use sha2::{Sha256, Digest};
+    let mut hasher = Sha256::new();
+    hasher.update(token.as_bytes());
+    hex::encode(hasher.finalize())
+}
+
+/// Mint a short-lived access token plus a new opaque refresh token, returning
+/// both tokens and the id of the stored refresh token row.
+///
+/// Pass `family_id` when rotating so the new token stays in the same device
+/// chain; `None` starts a new chain.
+async fn issue_token_pair(
+    state: &AppState,
+    user_id: i64,
+    family_id: Option<Uuid>,
+    device_name: Option<&str>,
+) -> anyhow::Result<(String, String, i64)> {
+    let family_id = family_id.unwrap_or_else(Uuid::new_v4);
+    let claims = Claims {
+        sub: user_id.to_string(),
+        exp: (chrono::Utc::now() + chrono::Duration::seconds(state.config.auth.jwt_expiration_seconds as i64)).timestamp() as usize,
+        jti: Uuid::new_v4().to_string(),
+        sid: family_id.to_string(),
+    };
+
+    let access_token = encode(
+        &Header::default(),
+        &claims,
+        &EncodingKey::from_secret(state.config.auth.jwt_secret.expose_secret().as_bytes()),
+    )?;
+
+    let refresh_token = format!("rt_{}", hex::encode(rand::random::<[u8; 32]>()));
+    let expires_at = chrono::Utc::now().naive_utc()
+        + chrono::Duration::seconds(state.config.auth.refresh_token_expiration_seconds as i64);
+
+    let refresh_id: i64 = sqlx::query_scalar(
+        r#"
+        INSERT INTO refresh_tokens (user_id, family_id, token_hash, device_name, expires_at)
+        VALUES ($1, $2, $3, $4, $5)
+        RETURNING id
+        "#,
+    )
+    .bind(user_id)
+    .bind(family_id)
+    .bind(hash_refresh_token(&refresh_token))
+    .bind(device_name)
+    .bind(expires_at)
+    .fetch_one(&state.db_pool)
+    .await?;
+
+    Ok((access_token, refresh_token, refresh_id))
+}
+
+/// Revoke every still-active refresh token in a device chain
+async fn revoke_refresh_token_family(db_pool: &sqlx::PgPool, family_id: Uuid) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query(
+        "UPDATE refresh_tokens SET revoked_at = NOW() WHERE family_id = $1 AND revoked_at IS NULL",
+    )
+    .bind(family_id)
+    .execute(db_pool)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+/// Clean up API keys that expired more than `grace_days` ago from database
+pub async fn cleanup_expired_api_keys(db_pool: &sqlx::PgPool, grace_days: i32) -> Result<i64, sqlx::Error> {
+    let cutoff = chrono::Utc::now().naive_utc() - chrono::Duration::days(grace_days as i64);
     
     let result = sqlx::query!(
         "DELETE FROM api_keys WHERE expires_at IS NOT NULL AND expires_at < $1",
-        now
+        cutoff
     )
     .execute(db_pool)
     .await?;
@@ -1323,3 +1800,14 @@ pub async fn cleanup_expired_api_keys(db_pool: &sqlx::PgPool) -> Result<i64, sql
     tracing::info!("Cleaned up {} expired API keys", result.rows_affected());
     Ok(result.rows_affected() as i64)
 }  
+
+/// Clean up refresh tokens that expired more than `grace_days` ago from database
+pub async fn cleanup_expired_refresh_tokens(db_pool: &sqlx::PgPool, grace_days: i32) -> Result<i64, sqlx::Error> {
+    let result = sqlx::query("DELETE FROM refresh_tokens WHERE expires_at < NOW() - make_interval(days => $1)")
+        .bind(grace_days)
+        .execute(db_pool)
+        .await?;
+
+    tracing::info!("Cleaned up {} expired refresh tokens", result.rows_affected());
+    Ok(result.rows_affected() as i64)
+}
@@ -0,0 +1,401 @@
+// src/handlers/avatars.rs - User and organization avatars
+//
+// Uploaded images are decoded, cropped to a square, resized and re-encoded as PNG before being
+// stored under `avatars/`, so nothing but a small, well-formed image is ever served back. Keys
+// are named after the content hash, which lets the served files be cached forever: a new
+// upload gets a new `avatar_url`.
+use anyhow::{bail, Context, Result};
+use axum::{
+    body::Bytes,
+    extract::{Path, State},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use axum_extra::headers::{authorization::Bearer, Authorization};
+use axum_extra::TypedHeader;
+use image::{imageops::FilterType, io::Limits, ImageFormat};
+use secrecy::ExposeSecret;
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use std::io::Cursor;
+
+use crate::{
+    auth::extract_user_id_dual,
+    handlers::conditional,
+    handlers::organizations::get_user_role_in_org,
+    log_stream::LogEvent,
+    models::api_key::ApiKeyScope,
+    AppState,
+};
+
+/// Largest upload accepted, before resizing
+pub const MAX_AVATAR_BYTES: usize = 1024 * 1024;
+/// Largest width or height decoded; bigger images are rejected rather than resized
+const MAX_SOURCE_DIMENSION: u32 = 4096;
+/// Width and height of stored avatars
+pub const AVATAR_SIZE: u32 = 256;
+
+const URL_PREFIX: &str = "/api/v1/avatars/";
+
+#[derive(Debug, Clone, Copy)]
+enum Owner {
+    User(i64),
+    Organization(i64),
+}
+
+impl Owner {
+    /// Storage and URL segment, which is also the table holding `avatar_url`
+    fn kind(&self) -> &'static str {
+        match self {
+            Owner::User(_) => "users",
+            Owner::Organization(_) => "organizations",
+        }
+    }
+
+    fn id(&self) -> i64 {
+        match self {
+            Owner::User(id) | Owner::Organization(id) => *id,
+        }
+    }
+}
+
+/// Decode a PNG, JPEG, GIF or WebP image and turn it into a square `AVATAR_SIZE` PNG
+pub fn process_avatar(data: &[u8]) -> Result<Vec<u8>> {
+    let format = image::guess_format(data).context("Unrecognized image format")?;
+    if !matches!(format, ImageFormat::Png | ImageFormat::Jpeg | ImageFormat::Gif | ImageFormat::WebP) {
+        bail!("Avatars must be PNG, JPEG, GIF or WebP images");
+    }
+
+    let mut limits = Limits::default();
+    limits.max_image_width = Some(MAX_SOURCE_DIMENSION);
+    limits.max_image_height = Some(MAX_SOURCE_DIMENSION);
+    let mut reader = image::io::Reader::with_format(Cursor::new(data), format);
+    reader.limits(limits);
+    let source = reader
+        .decode()
+        .with_context(|| format!("Invalid image (at most {0}x{0} pixels)", MAX_SOURCE_DIMENSION))?;
+
+    let avatar = source.resize_to_fill(AVATAR_SIZE, AVATAR_SIZE, FilterType::Lanczos3);
+    let mut png = Vec::new();
+    avatar.write_to(&mut Cursor::new(&mut png), image::ImageOutputFormat::Png)?;
+    Ok(png)
+}
+
+/// Upload an avatar for the current user
+///
+/// The request body is the image itself (PNG, JPEG, GIF or WebP, at most 1 MiB). It is cropped
+/// to a square and resized to 256x256; the response carries the new `avatar_url`.
+#[utoipa::path(
+    put,
+    path = "/api/v1/auth/me/avatar",
+    tag = "auth",
+    request_body(content = Vec<u8>, content_type = "image/png"),
+    responses(
+        (status = 200, description = "Avatar stored"),
+        (status = 400, description = "Not a supported image"),
+        (status = 401, description = "Unauthorized"),
+        (status = 413, description = "Image larger than 1 MiB")
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn upload_user_avatar(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    auth: Option<TypedHeader<Authorization<Bearer>>>,
+    body: Bytes,
+) -> Response {
+    let user_id = match authenticate(&state, auth, &headers).await {
+        Ok(id) => id,
+        Err(response) => return response,
+    };
+    replace_avatar(&state, Owner::User(user_id), user_id, Some(body)).await
+}
+
+/// Remove the current user's avatar
+#[utoipa::path(
+    delete,
+    path = "/api/v1/auth/me/avatar",
+    tag = "auth",
+    responses(
+        (status = 200, description = "Avatar removed"),
+        (status = 401, description = "Unauthorized")
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn delete_user_avatar(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    auth: Option<TypedHeader<Authorization<Bearer>>>,
+) -> Response {
+    let user_id = match authenticate(&state, auth, &headers).await {
+        Ok(id) => id,
+        Err(response) => return response,
+    };
+    replace_avatar(&state, Owner::User(user_id), user_id, None).await
+}
+
+/// Upload an organization's avatar
+///
+/// Same image rules as the user avatar. Owners and maintainers only.
+#[utoipa::path(
+    put,
+    path = "/api/v1/organizations/{id}/avatar",
+    tag = "organizations",
+    params(
+        ("id" = i64, Path, description = "Organization ID")
+    ),
+    request_body(content = Vec<u8>, content_type = "image/png"),
+    responses(
+        (status = 200, description = "Avatar stored"),
+        (status = 400, description = "Not a supported image"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Insufficient permissions"),
+        (status = 413, description = "Image larger than 1 MiB")
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn upload_organization_avatar(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    auth: Option<TypedHeader<Authorization<Bearer>>>,
+    Path(id): Path<i64>,
+    body: Bytes,
+) -> Response {
+    let user_id = match authenticate(&state, auth, &headers).await {
+        Ok(id) => id,
+        Err(response) => return response,
+    };
+    if let Err(response) = ensure_can_manage_organization(&state, id, user_id).await {
+        return response;
+    }
+    replace_avatar(&state, Owner::Organization(id), user_id, Some(body)).await
+}
+
+/// Remove an organization's avatar
+#[utoipa::path(
+    delete,
+    path = "/api/v1/organizations/{id}/avatar",
+    tag = "organizations",
+    params(
+        ("id" = i64, Path, description = "Organization ID")
+    ),
+    responses(
+        (status = 200, description = "Avatar removed"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Insufficient permissions")
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn delete_organization_avatar(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    auth: Option<TypedHeader<Authorization<Bearer>>>,
+    Path(id): Path<i64>,
+) -> Response {
+    let user_id = match authenticate(&state, auth, &headers).await {
+        Ok(id) => id,
+        Err(response) => return response,
+    };
+    if let Err(response) = ensure_can_manage_organization(&state, id, user_id).await {
+        return response;
+    }
+    replace_avatar(&state, Owner::Organization(id), user_id, None).await
+}
+
+/// Serve a stored avatar
+///
+/// Public, like the profiles that link to it. Files never change once stored, so they are
+/// cacheable indefinitely.
+#[utoipa::path(
+    get,
+    path = "/api/v1/avatars/{kind}/{id}/{file}",
+    tag = "auth",
+    params(
+        ("kind" = String, Path, description = "`users` or `organizations`"),
+        ("id" = i64, Path, description = "User or organization ID"),
+        ("file" = String, Path, description = "File name from `avatar_url`")
+    ),
+    responses(
+        (status = 200, description = "PNG image", content_type = "image/png"),
+        (status = 304, description = "Not modified"),
+        (status = 404, description = "Avatar not found")
+    )
+)]
+pub async fn get_avatar(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path((kind, id, file)): Path<(String, i64, String)>,
+) -> Response {
+    let valid_file = file
+        .strip_suffix(".png")
+        .is_some_and(|hash| hash.len() == 64 && hash.chars().all(|c| c.is_ascii_hexdigit()));
+    if !matches!(kind.as_str(), "users" | "organizations") || !valid_file {
+        return not_found();
+    }
+
+    let etag = format!("\"{}\"", file.trim_end_matches(".png"));
+    if conditional::none_match(&headers, &etag) {
+        return (StatusCode::NOT_MODIFIED, [(header::ETAG, etag)]).into_response();
+    }
+
+    match state.storage.get_blob(&format!("avatars/{}/{}/{}", kind, id, file)).await {
+        Ok(Some(data)) => {
+            let mut headers = HeaderMap::new();
+            headers.insert(header::CONTENT_TYPE, HeaderValue::from_static("image/png"));
+            headers.insert(header::CACHE_CONTROL, HeaderValue::from_static("public, max-age=31536000, immutable"));
+            headers.insert(header::X_CONTENT_TYPE_OPTIONS, HeaderValue::from_static("nosniff"));
+            if let Ok(value) = HeaderValue::from_str(&etag) {
+                headers.insert(header::ETAG, value);
+            }
+            (StatusCode::OK, headers, data).into_response()
+        }
+        Ok(None) => not_found(),
+        Err(e) => {
+            tracing::error!("Failed to read avatar {}/{}/{}: {}", kind, id, file, e);
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": "Failed to read avatar"}))).into_response()
+        }
+    }
+}
+
+async fn authenticate(
+    state: &AppState,
+    auth: Option<TypedHeader<Authorization<Bearer>>>,
+    headers: &HeaderMap,
+) -> Result<i64, Response> {
+    let secret = state.config.auth.jwt_secret.expose_secret().as_bytes();
+    extract_user_id_dual(auth, headers, ApiKeyScope::Admin, secret, &state.db_pool, state.cache.as_ref())
+        .await
+        .map_err(|status| (status, Json(json!({"error": "Unauthorized"}))).into_response())
+}
+
+async fn ensure_can_manage_organization(state: &AppState, org_id: i64, user_id: i64) -> Result<(), Response> {
+    match get_user_role_in_org(&state.db_pool, org_id, user_id).await {
+        Ok(Some(role)) if role.can_manage_organization() => Ok(()),
+        Ok(_) => Err((
+            StatusCode::FORBIDDEN,
+            Json(json!({"error": "Insufficient permissions to update the organization's avatar"})),
+        )
+            .into_response()),
+        Err(e) => {
+            tracing::error!("Failed to check organization role: {}", e);
+            Err((StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": "Internal server error"}))).into_response())
+        }
+    }
+}
+
+/// Store `upload` as the owner's avatar, or clear it when `None`, then delete the file the
+/// previous `avatar_url` pointed at if it was one of ours
+async fn replace_avatar(state: &AppState, owner: Owner, user_id: i64, upload: Option<Bytes>) -> Response {
+    let new_url = match upload {
+        Some(data) => match store_avatar(state, owner, &data).await {
+            Ok(url) => Some(url),
+            Err(response) => return response,
+        },
+        None => None,
+    };
+
+    let previous = match swap_avatar_url(state, owner, new_url.as_deref()).await {
+        Ok(Some(previous)) => previous,
+        Ok(None) => return not_found(),
+        Err(e) => {
+            tracing::error!("Failed to update avatar of {} {}: {}", owner.kind(), owner.id(), e);
+            return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": "Failed to update avatar"}))).into_response();
+        }
+    };
+
+    if let Some(old_key) = previous.as_deref().and_then(|url| url.strip_prefix(URL_PREFIX)) {
+        if Some(URL_PREFIX.to_string() + old_key) != new_url {
+            if let Err(e) = state.storage.delete_blob(&format!("avatars/{}", old_key)).await {
+                tracing::warn!("Failed to delete previous avatar {}: {}", old_key, e);
+            }
+        }
+    }
+
+    let action = if new_url.is_some() { "avatar.update" } else { "avatar.delete" };
+    state.log_stream.publish(
+        LogEvent::audit(action, Some(user_id), None).with_detail(format!("{} {}", owner.kind(), owner.id())),
+    );
+    (StatusCode::OK, Json(json!({"avatar_url": new_url}))).into_response()
+}
+
+/// Set the owner's `avatar_url`, returning the previous one, or `None` if the owner is gone
+async fn swap_avatar_url(state: &AppState, owner: Owner, url: Option<&str>) -> Result<Option<Option<String>>, sqlx::Error> {
+    let mut tx = state.db_pool.begin().await?;
+    let previous = sqlx::query_scalar::<_, Option<String>>(&format!(
+        "SELECT avatar_url FROM {} WHERE id = $1 FOR UPDATE",
+        owner.kind()
+    ))
+    .bind(owner.id())
+    .fetch_optional(&mut *tx)
+    .await?;
+    if previous.is_some() {
+        sqlx::query(&format!("UPDATE {} SET avatar_url = $2 WHERE id = $1", owner.kind()))
+            .bind(owner.id())
+            .bind(url)
+            .execute(&mut *tx)
+            .await?;
+    }
+    tx.commit().await?;
+    Ok(previous)
+}
+
+async fn store_avatar(state: &AppState, owner: Owner, data: &[u8]) -> Result<String, Response> {
+    if data.len() > MAX_AVATAR_BYTES {
+        return Err((
+            StatusCode::PAYLOAD_TOO_LARGE,
+            Json(json!({"error": format!("Avatars may be at most {} bytes", MAX_AVATAR_BYTES)})),
+        )
+            .into_response());
+    }
+
+    // Decoding and resizing are CPU-bound
+    let data = data.to_vec();
+    let png = match tokio::task::spawn_blocking(move || process_avatar(&data)).await {
+        Ok(Ok(png)) => png,
+        Ok(Err(e)) => {
+            return Err((StatusCode::BAD_REQUEST, Json(json!({"error": e.to_string()}))).into_response());
+        }
+        Err(e) => {
+            tracing::error!("Avatar processing task failed: {}", e);
+            return Err((StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": "Failed to process image"}))).into_response());
+        }
+    };
+
+    let file = format!("{}/{}/{}.png", owner.kind(), owner.id(), hex::encode(Sha256::digest(&png)));
+    if let Err(e) = state.storage.put_blob(&format!("avatars/{}", file), Bytes::from(png)).await {
+        tracing::error!("Failed to store avatar {}: {}", file, e);
+        return Err((StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": "Failed to store avatar"}))).into_response());
+    }
+    Ok(format!("{}{}", URL_PREFIX, file))
+}
+
+fn not_found() -> Response {
+    (StatusCode::NOT_FOUND, Json(json!({"error": "Avatar not found"}))).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{DynamicImage, Rgb, RgbImage};
+
+    #[test]
+    fn avatars_are_square_pngs() {
+        let source = DynamicImage::ImageRgb8(RgbImage::from_pixel(600, 300, Rgb([200, 40, 40])));
+        let mut jpeg = Vec::new();
+        source.write_to(&mut Cursor::new(&mut jpeg), image::ImageOutputFormat::Jpeg(90)).unwrap();
+
+        let avatar = image::load_from_memory(&process_avatar(&jpeg).unwrap()).unwrap();
+        assert_eq!((avatar.width(), avatar.height()), (AVATAR_SIZE, AVATAR_SIZE));
+
+        assert!(process_avatar(b"<svg xmlns=\"http://www.w3.org/2000/svg\"/>").is_err());
+    }
+}
@@ -0,0 +1,155 @@
+// src/handlers/base_images.rs - Base images of a repository's images and rebuild advisories
+//
+// Bases are recorded by `crate::base_images`.
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use axum_extra::headers::{authorization::Bearer, Authorization};
+use axum_extra::TypedHeader;
+use chrono::{DateTime, Utc};
+use secrecy::ExposeSecret;
+use serde_json::json;
+use sqlx::FromRow;
+
+use crate::{
+    auth::extract_user_id_dual,
+    base_images::{advisory_reasons, TAGS_OF_IMAGE},
+    handlers::docker_auth::check_repository_permission,
+    handlers::tag_cleanup::{find_repository, internal_error, repository_not_found},
+    models::api_key::ApiKeyScope,
+    models::image_base::ImageBase,
+    models::vulnerability::{Severity, SeverityCounts},
+    AppState,
+};
+
+#[derive(FromRow)]
+struct ImageBaseRow {
+    digest: String,
+    tags: Vec<String>,
+    base_name: Option<String>,
+    source: String,
+    base_repository: Option<String>,
+    base_tag: Option<String>,
+    base_digest: Option<String>,
+    current_base_digest: Option<String>,
+    critical_count: Option<i32>,
+    high_count: Option<i32>,
+    medium_count: Option<i32>,
+    low_count: Option<i32>,
+    unknown_count: Option<i32>,
+    detected_at: DateTime<Utc>,
+}
+
+/// Base images of a repository's tagged images
+///
+/// The base each tagged image was built from, newest first, and whether rebuilding it is
+/// recommended because the base tag points at a newer image or the image built on is vulnerable.
+/// Requires pull access.
+#[utoipa::path(
+    get,
+    path = "/api/v1/repos/{namespace}/{repo_name}/base-images",
+    params(
+        ("namespace" = String, Path, description = "Organization namespace"),
+        ("repo_name" = String, Path, description = "Repository name")
+    ),
+    responses(
+        (status = 200, description = "Base images and advisories", body = Vec<ImageBase>),
+        (status = 401, description = "Authentication required"),
+        (status = 404, description = "Repository not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "repositories",
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn list_image_bases(
+    Path((namespace, repo_name)): Path<(String, String)>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    auth: Option<TypedHeader<Authorization<Bearer>>>,
+) -> Response {
+    let secret = state.config.auth.jwt_secret.expose_secret().as_bytes();
+    let user_id = match extract_user_id_dual(auth, &headers, ApiKeyScope::Read, secret, &state.db_pool, state.cache.as_ref()).await {
+        Ok(id) => id,
+        Err(status) => return (status, Json(json!({"error": "Authentication required"}))).into_response(),
+    };
+    match check_repository_permission(&user_id.to_string(), &namespace, &repo_name, "pull", &state).await {
+        Ok(true) => {}
+        Ok(false) => return repository_not_found(&namespace, &repo_name),
+        Err(e) => return internal_error(e),
+    }
+    let repository_id = match find_repository(&state, &namespace, &repo_name).await {
+        Ok(Some((repository_id, _))) => repository_id,
+        Ok(None) => return repository_not_found(&namespace, &repo_name),
+        Err(e) => return internal_error(e),
+    };
+
+    let rows = sqlx::query_as::<_, ImageBaseRow>(&format!(
+        "SELECT * FROM (
+             SELECT b.manifest_digest AS digest, ARRAY({} ORDER BY t.name) AS tags,
+                    b.base_name, b.source, bo.name || '/' || br.name AS base_repository, b.base_tag, b.base_digest,
+                    (SELECT m.digest FROM tags t JOIN manifests m ON m.id = t.manifest_id
+                     WHERE t.repository_id = b.base_repository_id AND t.name = b.base_tag) AS current_base_digest,
+                    s.critical_count, s.high_count, s.medium_count, s.low_count, s.unknown_count,
+                    b.detected_at
+             FROM image_bases b
+             LEFT JOIN repositories br ON br.id = b.base_repository_id
+             LEFT JOIN organizations bo ON bo.id = br.organization_id
+             LEFT JOIN vulnerability_scans s
+                 ON s.repository_id = b.base_repository_id AND s.manifest_digest = b.base_digest AND s.status = 'completed'
+             WHERE b.repository_id = $1
+         ) images
+         WHERE cardinality(tags) > 0
+         ORDER BY detected_at DESC
+         LIMIT 500",
+        TAGS_OF_IMAGE
+    ))
+    .bind(repository_id)
+    .fetch_all(&state.db_pool)
+    .await;
+    let rows = match rows {
+        Ok(rows) => rows,
+        Err(e) => return internal_error(e),
+    };
+
+    let threshold = state.config.base_images.advisory_severity.parse::<Severity>().unwrap_or(Severity::High);
+    let bases: Vec<ImageBase> = rows
+        .into_iter()
+        .map(|row| {
+            let base_vulnerabilities = row.critical_count.map(|critical| {
+                let (high, medium, low, unknown) = (
+                    row.high_count.unwrap_or(0),
+                    row.medium_count.unwrap_or(0),
+                    row.low_count.unwrap_or(0),
+                    row.unknown_count.unwrap_or(0),
+                );
+                SeverityCounts { critical, high, medium, low, unknown, total: critical + high + medium + low + unknown }
+            });
+            let reasons = advisory_reasons(
+                row.base_digest.as_deref(),
+                row.current_base_digest.as_deref(),
+                base_vulnerabilities.as_ref(),
+                threshold,
+            );
+            ImageBase {
+                digest: row.digest,
+                tags: row.tags,
+                base_name: row.base_name,
+                source: row.source,
+                base_repository: row.base_repository,
+                base_tag: row.base_tag,
+                base_digest: row.base_digest,
+                current_base_digest: row.current_base_digest,
+                base_vulnerabilities,
+                rebuild_recommended: !reasons.is_empty(),
+                reasons,
+                detected_at: row.detected_at,
+            }
+        })
+        .collect();
+    (StatusCode::OK, Json(json!(bases))).into_response()
+}
@@ -0,0 +1,73 @@
+// src/handlers/bootstrap.rs - Token-guarded first-run setup for deployment automation
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use axum_extra::headers::{authorization::Bearer, Authorization};
+use axum_extra::TypedHeader;
+use secrecy::ExposeSecret;
+use serde_json::json;
+use sha2::{Digest, Sha256};
+
+use crate::{bootstrap::BootstrapReport, log_stream::LogEvent, AppState};
+
+/// Create the initial administrator, default organization and its base settings
+///
+/// Requires `Authorization: Bearer <BOOTSTRAP_TOKEN>`. Everything comes from the `BOOTSTRAP_*`
+/// settings and only what is missing is created, so the call is safe to repeat on every
+/// deploy; the report tells which parts already existed.
+#[utoipa::path(
+    post,
+    path = "/api/v1/bootstrap",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Bootstrap applied", body = BootstrapReport),
+        (status = 400, description = "Incomplete bootstrap settings or conflicting accounts"),
+        (status = 401, description = "Missing or wrong bootstrap token"),
+        (status = 403, description = "Bootstrap is not enabled")
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn bootstrap(
+    State(state): State<AppState>,
+    auth: Option<TypedHeader<Authorization<Bearer>>>,
+) -> Response {
+    let expected = match &state.config.bootstrap.token {
+        Some(token) => token,
+        None => {
+            return (StatusCode::FORBIDDEN, Json(json!({
+                "error": "Bootstrap is disabled; set BOOTSTRAP_TOKEN to enable it"
+            }))).into_response()
+        }
+    };
+
+    // Compare digests so the check does not leak how much of the token matched
+    let authorized = auth.is_some_and(|TypedHeader(Authorization(bearer))| {
+        Sha256::digest(bearer.token().as_bytes()) == Sha256::digest(expected.expose_secret().as_bytes())
+    });
+    if !authorized {
+        return (StatusCode::UNAUTHORIZED, Json(json!({
+            "error": "Invalid bootstrap token"
+        }))).into_response();
+    }
+
+    match crate::bootstrap::run(&state.db_pool, &state.config).await {
+        Ok(report) => {
+            state.log_stream.publish(
+                LogEvent::audit("instance.bootstrap", report.admin.as_ref().map(|admin| admin.id), None)
+                    .with_detail(report.summary()),
+            );
+            (StatusCode::OK, Json(report)).into_response()
+        }
+        Err(e) => {
+            tracing::error!("Bootstrap failed: {:#}", e);
+            (StatusCode::BAD_REQUEST, Json(json!({
+                "error": format!("{:#}", e)
+            }))).into_response()
+        }
+    }
+}
@@ -0,0 +1,302 @@
+// src/handlers/collaborators.rs - Repository-level collaborator grants
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use axum_extra::headers::{authorization::Bearer, Authorization};
+use axum_extra::TypedHeader;
+use secrecy::ExposeSecret;
+use serde_json::json;
+use sqlx::PgPool;
+
+use crate::{
+    auth::extract_user_id_dual,
+    handlers::organizations::get_user_role_in_org,
+    log_stream::LogEvent,
+    models::api_key::ApiKeyScope,
+    models::repository_collaborator::{CollaboratorPermission, RepositoryCollaborator, SetCollaboratorRequest},
+    AppState,
+};
+
+const COLLABORATOR_SELECT: &str = "SELECT rc.id, rc.repository_id, rc.user_id, rc.permission, rc.granted_by,
+            rc.created_at, rc.updated_at, u.username, u.email
+     FROM repository_collaborators rc
+     JOIN users u ON rc.user_id = u.id";
+
+/// List collaborators on a repository
+#[utoipa::path(
+    get,
+    path = "/api/v1/repos/{namespace}/{repo_name}/collaborators",
+    params(
+        ("namespace" = String, Path, description = "Organization namespace"),
+        ("repo_name" = String, Path, description = "Repository name")
+    ),
+    responses(
+        (status = 200, description = "Repository collaborators", body = Vec<RepositoryCollaborator>),
+        (status = 401, description = "Authentication required"),
+        (status = 403, description = "Permission denied"),
+        (status = 404, description = "Repository not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "repositories",
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn list_collaborators(
+    Path((namespace, repo_name)): Path<(String, String)>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    auth: Option<TypedHeader<Authorization<Bearer>>>,
+) -> Response {
+    let (user_id, repository_id, org_id) = match authorize(&state, auth, &headers, &namespace, &repo_name, ApiKeyScope::Read).await {
+        Ok(ids) => ids,
+        Err(response) => return response,
+    };
+
+    match can_manage_collaborators(&state.db_pool, org_id, repository_id, user_id).await {
+        Ok(true) => {}
+        Ok(false) => return forbidden(),
+        Err(e) => return internal_error(e),
+    }
+
+    let query = format!("{} WHERE rc.repository_id = $1 ORDER BY u.username", COLLABORATOR_SELECT);
+    match sqlx::query_as::<_, RepositoryCollaborator>(&query)
+        .bind(repository_id)
+        .fetch_all(&state.db_pool)
+        .await
+    {
+        Ok(collaborators) => (StatusCode::OK, Json(collaborators)).into_response(),
+        Err(e) => internal_error(e),
+    }
+}
+
+/// Grant or change a user's permission on a repository
+#[utoipa::path(
+    put,
+    path = "/api/v1/repos/{namespace}/{repo_name}/collaborators/{username}",
+    params(
+        ("namespace" = String, Path, description = "Organization namespace"),
+        ("repo_name" = String, Path, description = "Repository name"),
+        ("username" = String, Path, description = "User to grant access to")
+    ),
+    request_body = SetCollaboratorRequest,
+    responses(
+        (status = 200, description = "Collaborator permission set", body = RepositoryCollaborator),
+        (status = 401, description = "Authentication required"),
+        (status = 403, description = "Permission denied"),
+        (status = 404, description = "Repository or user not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "repositories",
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn set_collaborator(
+    Path((namespace, repo_name, username)): Path<(String, String, String)>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    auth: Option<TypedHeader<Authorization<Bearer>>>,
+    Json(request): Json<SetCollaboratorRequest>,
+) -> Response {
+    let (user_id, repository_id, org_id) = match authorize(&state, auth, &headers, &namespace, &repo_name, ApiKeyScope::Admin).await {
+        Ok(ids) => ids,
+        Err(response) => return response,
+    };
+
+    match can_manage_collaborators(&state.db_pool, org_id, repository_id, user_id).await {
+        Ok(true) => {}
+        Ok(false) => return forbidden(),
+        Err(e) => return internal_error(e),
+    }
+
+    let collaborator_id = match sqlx::query_scalar::<_, i64>("SELECT id FROM users WHERE username = $1")
+        .bind(&username)
+        .fetch_optional(&state.db_pool)
+        .await
+    {
+        Ok(Some(id)) => id,
+        Ok(None) => {
+            return (StatusCode::NOT_FOUND, Json(json!({
+                "error": format!("User '{}' not found", username)
+            }))).into_response()
+        }
+        Err(e) => return internal_error(e),
+    };
+
+    if let Err(e) = sqlx::query(
+        "INSERT INTO repository_collaborators (repository_id, user_id, permission, granted_by)
+         VALUES ($1, $2, $3, $4)
+         ON CONFLICT (repository_id, user_id) DO UPDATE
+         SET permission = EXCLUDED.permission, granted_by = EXCLUDED.granted_by, updated_at = CURRENT_TIMESTAMP",
+    )
+    .bind(repository_id)
+    .bind(collaborator_id)
+    .bind(request.permission.to_string())
+    .bind(user_id)
+    .execute(&state.db_pool)
+    .await
+    {
+        return internal_error(e);
+    }
+
+    state.log_stream.publish(
+        LogEvent::audit("repository.collaborator.set", Some(user_id), Some(format!("{}/{}", namespace, repo_name)))
+            .with_organization(org_id)
+            .with_detail(format!("{} {}", username, request.permission)),
+    );
+
+    let query = format!("{} WHERE rc.repository_id = $1 AND rc.user_id = $2", COLLABORATOR_SELECT);
+    match sqlx::query_as::<_, RepositoryCollaborator>(&query)
+        .bind(repository_id)
+        .bind(collaborator_id)
+        .fetch_one(&state.db_pool)
+        .await
+    {
+        Ok(collaborator) => (StatusCode::OK, Json(collaborator)).into_response(),
+        Err(e) => internal_error(e),
+    }
+}
+
+/// Revoke a user's direct access to a repository
+#[utoipa::path(
+    delete,
+    path = "/api/v1/repos/{namespace}/{repo_name}/collaborators/{username}",
+    params(
+        ("namespace" = String, Path, description = "Organization namespace"),
+        ("repo_name" = String, Path, description = "Repository name"),
+        ("username" = String, Path, description = "Collaborator to remove")
+    ),
+    responses(
+        (status = 204, description = "Collaborator removed"),
+        (status = 401, description = "Authentication required"),
+        (status = 403, description = "Permission denied"),
+        (status = 404, description = "Repository or collaborator not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "repositories",
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn remove_collaborator(
+    Path((namespace, repo_name, username)): Path<(String, String, String)>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    auth: Option<TypedHeader<Authorization<Bearer>>>,
+) -> Response {
+    let (user_id, repository_id, org_id) = match authorize(&state, auth, &headers, &namespace, &repo_name, ApiKeyScope::Admin).await {
+        Ok(ids) => ids,
+        Err(response) => return response,
+    };
+
+    match can_manage_collaborators(&state.db_pool, org_id, repository_id, user_id).await {
+        Ok(true) => {}
+        Ok(false) => return forbidden(),
+        Err(e) => return internal_error(e),
+    }
+
+    match sqlx::query(
+        "DELETE FROM repository_collaborators rc
+         USING users u
+         WHERE rc.user_id = u.id AND rc.repository_id = $1 AND u.username = $2",
+    )
+    .bind(repository_id)
+    .bind(&username)
+    .execute(&state.db_pool)
+    .await
+    {
+        Ok(result) if result.rows_affected() > 0 => {
+            state.log_stream.publish(
+                LogEvent::audit("repository.collaborator.remove", Some(user_id), Some(format!("{}/{}", namespace, repo_name)))
+                    .with_organization(org_id)
+                    .with_detail(username),
+            );
+            StatusCode::NO_CONTENT.into_response()
+        }
+        Ok(_) => (StatusCode::NOT_FOUND, Json(json!({
+            "error": format!("'{}' is not a collaborator on '{}/{}'", username, namespace, repo_name)
+        }))).into_response(),
+        Err(e) => internal_error(e),
+    }
+}
+
+/// Authenticate the caller and resolve the repository, returning (user_id, repository_id, organization_id)
+async fn authorize(
+    state: &AppState,
+    auth: Option<TypedHeader<Authorization<Bearer>>>,
+    headers: &HeaderMap,
+    namespace: &str,
+    repo_name: &str,
+    required: ApiKeyScope,
+) -> Result<(i64, i64, i64), Response> {
+    let secret = state.config.auth.jwt_secret.expose_secret().as_bytes();
+    let user_id = extract_user_id_dual(auth, headers, required, secret, &state.db_pool, state.cache.as_ref())
+        .await
+        .map_err(|status| match status {
+            StatusCode::FORBIDDEN => (StatusCode::FORBIDDEN, Json(json!({
+                "error": format!("API key is not scoped for {} access", required)
+            }))).into_response(),
+            _ => (StatusCode::UNAUTHORIZED, Json(json!({
+                "error": "Authentication required"
+            }))).into_response(),
+        })?;
+
+    let repository = sqlx::query_as::<_, (i64, i64)>(
+        "SELECT r.id, r.organization_id FROM repositories r
+         JOIN organizations o ON r.organization_id = o.id
+         WHERE o.name = $1 AND r.name = $2",
+    )
+    .bind(namespace)
+    .bind(repo_name)
+    .fetch_optional(&state.db_pool)
+    .await
+    .map_err(internal_error)?;
+
+    match repository {
+        Some((repository_id, org_id)) => Ok((user_id, repository_id, org_id)),
+        None => Err((StatusCode::NOT_FOUND, Json(json!({
+            "error": format!("Repository '{}/{}' not found", namespace, repo_name)
+        }))).into_response()),
+    }
+}
+
+/// Owners and maintainers of the organization, and admin collaborators (direct or via a team), manage grants
+async fn can_manage_collaborators(pool: &PgPool, org_id: i64, repository_id: i64, user_id: i64) -> anyhow::Result<bool> {
+    let role = get_user_role_in_org(pool, org_id, user_id).await?;
+    if role.map(|r| r.can_manage_repositories()).unwrap_or(false) {
+        return Ok(true);
+    }
+
+    let grants = sqlx::query_scalar::<_, String>(
+        "SELECT permission FROM repository_collaborators WHERE repository_id = $1 AND user_id = $2
+         UNION ALL
+         SELECT tr.permission FROM team_repositories tr
+         JOIN team_members tm ON tm.team_id = tr.team_id
+         WHERE tr.repository_id = $1 AND tm.user_id = $2",
+    )
+    .bind(repository_id)
+    .bind(user_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(grants
+        .iter()
+        .filter_map(|p| p.parse::<CollaboratorPermission>().ok())
+        .any(|p| p.can_manage_collaborators()))
+}
+
+fn forbidden() -> Response {
+    (StatusCode::FORBIDDEN, Json(json!({
+        "error": "You don't have permission to manage collaborators on this repository"
+    }))).into_response()
+}
+
+fn internal_error(e: impl std::fmt::Display) -> Response {
+    (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({
+        "error": format!("Database error: {}", e)
+    }))).into_response()
+}
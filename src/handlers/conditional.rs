@@ -0,0 +1,153 @@
+// src/handlers/conditional.rs - Conditional GETs: strong ETags and 304 Not Modified replies
+//
+// Manifests are tagged with their digest, tag lists with the digest of the listed JSON, so a
+// client or proxy holding a copy revalidates it with `If-None-Match` and gets an empty 304 when
+// it is still current. `If-Modified-Since` is honored for manifests pulled by digest, which never
+// change; other responses carry no modification date, so it is ignored for them as RFC 9110
+// requires.
+use axum::{
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+/// Headers of a 200 response repeated on the 304 replacing it
+const KEPT_ON_NOT_MODIFIED: [&str; 3] = ["etag", "cache-control", "docker-content-digest"];
+
+/// Strong entity tag of a content digest
+pub fn etag(digest: &str) -> String {
+    format!("\"{}\"", digest)
+}
+
+/// Whether `If-None-Match` lists `etag` or is `*`. Weak tags match too, as GET compares weakly.
+pub fn none_match(request: &HeaderMap, etag: &str) -> bool {
+    request
+        .get_all(header::IF_NONE_MATCH)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(|tag| tag.trim())
+        .any(|tag| tag == "*" || tag.trim_start_matches("W/") == etag)
+}
+
+/// Tag a manifest response with its `Docker-Content-Digest` and answer 304 when the client's copy
+/// is current. `by_digest` is whether the manifest was requested by digest rather than by tag.
+pub fn manifest_response(request: &HeaderMap, mut response: Response, by_digest: bool) -> Response {
+    if response.status() != StatusCode::OK {
+        return response;
+    }
+    let Some(digest) = response
+        .headers()
+        .get("Docker-Content-Digest")
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string)
+    else {
+        return response;
+    };
+    let etag = etag(&digest);
+    if let Ok(value) = HeaderValue::from_str(&etag) {
+        response.headers_mut().insert(header::ETAG, value);
+    }
+
+    let not_modified = if request.contains_key(header::IF_NONE_MATCH) {
+        none_match(request, &etag)
+    } else {
+        by_digest && request.contains_key(header::IF_MODIFIED_SINCE)
+    };
+    if not_modified {
+        not_modified_from(response.headers())
+    } else {
+        response
+    }
+}
+
+/// A JSON response tagged with the digest of its body, or 304 when the client's copy is current
+pub fn json_response<T: Serialize>(request: &HeaderMap, body: &T) -> Response {
+    let body = match serde_json::to_vec(body) {
+        Ok(body) => body,
+        Err(_) => return StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+    };
+    let etag = etag(&format!("sha256:{}", hex::encode(Sha256::digest(&body))));
+    let mut headers = HeaderMap::new();
+    headers.insert(header::CONTENT_TYPE, HeaderValue::from_static("application/json"));
+    if let Ok(value) = HeaderValue::from_str(&etag) {
+        headers.insert(header::ETAG, value);
+    }
+
+    if none_match(request, &etag) {
+        not_modified_from(&headers)
+    } else {
+        (StatusCode::OK, headers, body).into_response()
+    }
+}
+
+fn not_modified_from(headers: &HeaderMap) -> Response {
+    let mut kept = HeaderMap::new();
+    for name in KEPT_ON_NOT_MODIFIED {
+        if let Some(value) = headers.get(name) {
+            kept.insert(name, value.clone());
+        }
+    }
+    (StatusCode::NOT_MODIFIED, kept).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIGEST: &str = "sha256:4f53cda18c2baa0c0354bb5f9a3ecbe5ed12ab4d8e11ba873c2f11161202b945";
+
+    fn request(name: header::HeaderName, value: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(name, HeaderValue::from_str(value).unwrap());
+        headers
+    }
+
+    fn manifest() -> Response {
+        let mut response = (StatusCode::OK, "{}").into_response();
+        response.headers_mut().insert("Docker-Content-Digest", HeaderValue::from_static(DIGEST));
+        response.headers_mut().insert(header::CACHE_CONTROL, HeaderValue::from_static("public, max-age=300"));
+        response
+    }
+
+    #[test]
+    fn if_none_match_lists_are_compared_weakly() {
+        let etag = etag(DIGEST);
+        assert!(none_match(&request(header::IF_NONE_MATCH, &format!("\"other\", W/{}", etag)), &etag));
+        assert!(none_match(&request(header::IF_NONE_MATCH, "*"), &etag));
+        assert!(!none_match(&request(header::IF_NONE_MATCH, "\"sha256:other\""), &etag));
+        assert!(!none_match(&HeaderMap::new(), &etag));
+    }
+
+    #[test]
+    fn current_manifests_are_not_sent_again() {
+        let response = manifest_response(&request(header::IF_NONE_MATCH, &etag(DIGEST)), manifest(), false);
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(response.headers()[header::ETAG], etag(DIGEST).as_str());
+        assert_eq!(response.headers()["Docker-Content-Digest"], DIGEST);
+        assert_eq!(response.headers()[header::CACHE_CONTROL], "public, max-age=300");
+
+        let response = manifest_response(&request(header::IF_NONE_MATCH, "\"sha256:old\""), manifest(), false);
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[header::ETAG], etag(DIGEST).as_str());
+
+        // A tag may have moved since, a digest cannot
+        let since = request(header::IF_MODIFIED_SINCE, "Wed, 21 Oct 2026 07:28:00 GMT");
+        assert_eq!(manifest_response(&since, manifest(), false).status(), StatusCode::OK);
+        assert_eq!(manifest_response(&since, manifest(), true).status(), StatusCode::NOT_MODIFIED);
+    }
+
+    #[test]
+    fn json_bodies_are_tagged_with_their_digest() {
+        let body = serde_json::json!({"name": "acme/web", "tags": ["latest"]});
+        let response = json_response(&HeaderMap::new(), &body);
+        assert_eq!(response.status(), StatusCode::OK);
+        let etag = response.headers()[header::ETAG].to_str().unwrap().to_string();
+
+        let response = json_response(&request(header::IF_NONE_MATCH, &etag), &body);
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+        let changed = serde_json::json!({"name": "acme/web", "tags": ["latest", "v2"]});
+        assert_eq!(json_response(&request(header::IF_NONE_MATCH, &etag), &changed).status(), StatusCode::OK);
+    }
+}
@@ -0,0 +1,179 @@
+// src/handlers/digests.rs - Resolve abbreviated manifest digests within a repository
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use axum_extra::headers::{authorization::Bearer, Authorization};
+use axum_extra::TypedHeader;
+use chrono::{DateTime, Utc};
+use secrecy::ExposeSecret;
+use serde::Serialize;
+use serde_json::json;
+use sqlx::FromRow;
+use utoipa::ToSchema;
+
+use crate::{
+    auth::extract_user_id_dual,
+    handlers::docker_auth::check_repository_permission,
+    models::api_key::ApiKeyScope,
+    AppState,
+};
+
+/// Shortest accepted prefix; shorter ones are ambiguous in any real repository
+const MIN_PREFIX_LEN: usize = 4;
+/// Candidates listed when a prefix is ambiguous
+const MAX_CANDIDATES: i64 = 10;
+/// Media types of image manifests and indexes; the `manifests` table also records the layers
+/// and configs of uploaded blobs, which are not resolved
+const MANIFEST_MEDIA_TYPES: [&str; 6] = [
+    "application/vnd.oci.image.manifest.v1+json",
+    "application/vnd.oci.image.index.v1+json",
+    "application/vnd.docker.distribution.manifest.v2+json",
+    "application/vnd.docker.distribution.manifest.list.v2+json",
+    "application/vnd.docker.distribution.manifest.v1+json",
+    "application/vnd.docker.distribution.manifest.v1+prettyjws",
+];
+
+#[derive(Debug, Serialize, FromRow, ToSchema)]
+pub struct ResolvedDigest {
+    /// Full digest, e.g. `sha256:4f53cda18c2b...`
+    pub digest: String,
+    pub media_type: String,
+    pub size: i64,
+    /// Tags currently pointing at the manifest
+    pub tags: Vec<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Resolve an abbreviated manifest digest to the full digest
+///
+/// Accepts the first hex characters of a sha256 digest, with or without the `sha256:`
+/// prefix, like the short image IDs shown by `docker images`.
+#[utoipa::path(
+    get,
+    path = "/api/v1/repos/{namespace}/{repo_name}/digests/{prefix}",
+    params(
+        ("namespace" = String, Path, description = "Organization namespace"),
+        ("repo_name" = String, Path, description = "Repository name"),
+        ("prefix" = String, Path, description = "Leading hex characters of the digest (at least 4), optionally prefixed with `sha256:`")
+    ),
+    responses(
+        (status = 200, description = "The unique manifest matching the prefix", body = ResolvedDigest),
+        (status = 400, description = "Malformed prefix"),
+        (status = 401, description = "Authentication required"),
+        (status = 404, description = "Repository not found or no manifest matches"),
+        (status = 409, description = "Prefix matches several manifests; candidates are listed"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "repositories",
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn resolve_digest(
+    Path((namespace, repo_name, prefix)): Path<(String, String, String)>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    auth: Option<TypedHeader<Authorization<Bearer>>>,
+) -> Response {
+    let secret = state.config.auth.jwt_secret.expose_secret().as_bytes();
+    let user_id = match extract_user_id_dual(auth, &headers, ApiKeyScope::Read, secret, &state.db_pool, state.cache.as_ref()).await {
+        Ok(id) => id,
+        Err(_) => {
+            return (StatusCode::UNAUTHORIZED, Json(json!({
+                "error": "Authentication required"
+            }))).into_response()
+        }
+    };
+
+    let hex = match normalize_prefix(&prefix) {
+        Some(hex) => hex,
+        None => {
+            return (StatusCode::BAD_REQUEST, Json(json!({
+                "error": format!("Digest prefix must be at least {} hex characters, optionally prefixed with 'sha256:'", MIN_PREFIX_LEN)
+            }))).into_response()
+        }
+    };
+
+    // Private repositories are reported as missing rather than forbidden, like get_repository
+    match check_repository_permission(&user_id.to_string(), &namespace, &repo_name, "pull", &state).await {
+        Ok(true) => {}
+        Ok(false) => return repository_not_found(&namespace, &repo_name),
+        Err(e) => return internal_error(e),
+    }
+
+    // `hex` is validated, so it cannot carry LIKE wildcards
+    let matches = match sqlx::query_as::<_, ResolvedDigest>(
+        "SELECT m.digest, m.media_type, m.size, m.created_at,
+                COALESCE(ARRAY_AGG(t.name ORDER BY t.name) FILTER (WHERE t.name IS NOT NULL), '{}') AS tags
+         FROM manifests m
+         JOIN repositories r ON m.repository_id = r.id
+         JOIN organizations o ON r.organization_id = o.id
+         LEFT JOIN tags t ON t.manifest_id = m.id
+         WHERE o.name = $1 AND r.name = $2 AND m.digest LIKE $3 AND m.media_type = ANY($5)
+         GROUP BY m.id
+         ORDER BY m.digest
+         LIMIT $4",
+    )
+    .bind(&namespace)
+    .bind(&repo_name)
+    .bind(format!("sha256:{}%", hex))
+    .bind(MAX_CANDIDATES)
+    .bind(&MANIFEST_MEDIA_TYPES[..])
+    .fetch_all(&state.db_pool)
+    .await
+    {
+        Ok(matches) => matches,
+        Err(e) => return internal_error(e),
+    };
+
+    let mut matches = matches.into_iter();
+    match (matches.next(), matches.len()) {
+        (Some(resolved), 0) => (StatusCode::OK, Json(resolved)).into_response(),
+        (Some(first), _) => {
+            let candidates: Vec<String> = std::iter::once(first).chain(matches).map(|m| m.digest).collect();
+            (StatusCode::CONFLICT, Json(json!({
+                "error": format!("Digest prefix '{}' is ambiguous in '{}/{}'", prefix, namespace, repo_name),
+                "candidates": candidates
+            }))).into_response()
+        }
+        (None, _) => (StatusCode::NOT_FOUND, Json(json!({
+            "error": format!("No manifest in '{}/{}' matches digest prefix '{}'", namespace, repo_name, prefix)
+        }))).into_response(),
+    }
+}
+
+/// Lowercase hex part of a digest prefix, or `None` if it is not a plausible sha256 prefix
+fn normalize_prefix(prefix: &str) -> Option<String> {
+    let hex = prefix.strip_prefix("sha256:").unwrap_or(prefix).to_ascii_lowercase();
+    let valid = (MIN_PREFIX_LEN..=64).contains(&hex.len()) && hex.chars().all(|c| c.is_ascii_hexdigit());
+    valid.then_some(hex)
+}
+
+fn repository_not_found(namespace: &str, repo_name: &str) -> Response {
+    (StatusCode::NOT_FOUND, Json(json!({
+        "error": format!("Repository '{}/{}' not found", namespace, repo_name)
+    }))).into_response()
+}
+
+fn internal_error(e: impl std::fmt::Display) -> Response {
+    (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({
+        "error": format!("Database error: {}", e)
+    }))).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::normalize_prefix;
+
+    #[test]
+    fn normalizes_short_digests() {
+        assert_eq!(normalize_prefix("sha256:4F53cd").as_deref(), Some("4f53cd"));
+        assert_eq!(normalize_prefix("4f53").as_deref(), Some("4f53"));
+        assert_eq!(normalize_prefix("4f5"), None);
+        assert_eq!(normalize_prefix("4f5_%"), None);
+        assert_eq!(normalize_prefix("sha512:4f53cd"), None);
+    }
+}
@@ -8,11 +8,14 @@ use secrecy::ExposeSecret;
 use base64::Engine;
 use bcrypt;
 use argon2::{Argon2, PasswordHash, PasswordVerifier};
-use crate::{AppState, auth::verify_token};
+use std::net::IpAddr;
+use crate::{AppState, auth::verify_token_not_revoked, database::models::User, features::Feature, handlers::{ip_access::client_ip, login_protection::{self, LoginAccount}}, models::{api_key::ApiKeyScope, organizations::OrganizationRole, repository_collaborator::CollaboratorPermission}};
 
-/// Extract user ID from Authorization header
+/// Extract user ID from Authorization header; `peer` is the socket address Basic credential
+/// failures are counted against
 pub async fn extract_user_from_auth(
     headers: &HeaderMap, 
+    peer: Option<IpAddr>,
     state: &AppState,
     require_auth: bool
 ) -> Result<Option<String>, Response> {
@@ -22,12 +25,12 @@ pub async fn extract_user_from_auth(
                 let token = &auth_str[7..]; // Remove "Bearer " prefix
                 
                 // Verify JWT token and extract user_id
-                match verify_token(token, state.config.auth.jwt_secret.expose_secret().as_bytes()) {
+                match verify_token_not_revoked(token, state.config.auth.jwt_secret.expose_secret().as_bytes(), &state.db_pool).await {
                     Ok(claims) => {
                         match claims.sub.parse::<i64>() {
                             Ok(uid) => Ok(Some(uid.to_string())),
                             Err(_) => {
-                                println!("❌ Invalid user ID in JWT token");
+                                tracing::warn!("Invalid user ID in JWT token");
                                 Err((
                                     StatusCode::UNAUTHORIZED,
                                     [("WWW-Authenticate", "Bearer")],
@@ -43,7 +46,7 @@ pub async fn extract_user_from_auth(
                         }
                     }
                     Err(e) => {
-                        println!("❌ JWT token verification failed: {:?}", e);
+                        tracing::warn!("JWT token verification failed: {:?}", e);
                         Err((
                             StatusCode::UNAUTHORIZED,
                             [("WWW-Authenticate", "Bearer")],
@@ -69,10 +72,13 @@ pub async fn extract_user_from_auth(
                                 let password = parts[1];
                                 
                                 // Verify credentials against database
-                                match verify_docker_credentials(username, password, state).await {
+                                let ip = client_ip(peer, headers, &state.config.ip_access.trusted_proxies)
+                                    .map(|ip| ip.to_string())
+                                    .unwrap_or_else(|| "unknown".to_string());
+                                match verify_docker_credentials(username, password, &ip, state).await {
                                     Ok(Some(user_id)) => Ok(Some(user_id)),
                                     Ok(None) => {
-                                        println!("❌ Invalid docker credentials for user: {}", username);
+                                        tracing::warn!("Invalid docker credentials for user: {}", username);
                                         Err((
                                             StatusCode::UNAUTHORIZED,
                                             [("WWW-Authenticate", "Basic")],
@@ -85,22 +91,10 @@ pub async fn extract_user_from_auth(
                                             }))
                                         ).into_response())
                                     }
-                                    Err(_) => {
-                                        println!("❌ Database error verifying credentials");
-                                        Err((
-                                            StatusCode::INTERNAL_SERVER_ERROR,
-                                            Json(serde_json::json!({
-                                                "errors": [{
-                                                    "code": "UNKNOWN",
-                                                    "message": "Internal server error",
-                                                    "detail": {}
-                                                }]
-                                            }))
-                                        ).into_response())
-                                    }
+                                    Err(response) => Err(response),
                                 }
                             } else {
-                                println!("❌ Invalid Basic auth format");
+                                tracing::warn!("Invalid Basic auth format");
                                 Err((
                                     StatusCode::UNAUTHORIZED,
                                     [("WWW-Authenticate", "Basic")],
@@ -114,7 +108,7 @@ pub async fn extract_user_from_auth(
                                 ).into_response())
                             }
                         } else {
-                            println!("❌ Invalid UTF-8 in Basic auth");
+                            tracing::warn!("Invalid UTF-8 in Basic auth");
                             Err((
                                 StatusCode::UNAUTHORIZED,
                                 [("WWW-Authenticate", "Basic")],
@@ -129,7 +123,7 @@ pub async fn extract_user_from_auth(
                         }
                     }
                     Err(_) => {
-                        println!("❌ Invalid base64 in Basic auth");
+                        tracing::warn!("Invalid base64 in Basic auth");
                         Err((
                             StatusCode::UNAUTHORIZED,
                             [("WWW-Authenticate", "Basic")],
@@ -144,7 +138,7 @@ pub async fn extract_user_from_auth(
                     }
                 }
             } else {
-                println!("❌ Invalid Authorization header format");
+                tracing::warn!("Invalid Authorization header format");
                 Err((
                     StatusCode::UNAUTHORIZED,
                     [("WWW-Authenticate", "Basic")],
@@ -158,7 +152,7 @@ pub async fn extract_user_from_auth(
                 ).into_response())
             }
         } else {
-            println!("❌ Invalid Authorization header format");
+            tracing::warn!("Invalid Authorization header format");
             Err((
                 StatusCode::UNAUTHORIZED,
                 [("WWW-Authenticate", "Basic")],
@@ -173,7 +167,7 @@ pub async fn extract_user_from_auth(
         }
     } else {
         if require_auth {
-            println!("⚠️ No Authorization header found");
+            tracing::debug!("No Authorization header found");
             Err((
                 StatusCode::UNAUTHORIZED,
                 [("WWW-Authenticate", "Basic")],
@@ -193,20 +187,38 @@ pub async fn extract_user_from_auth(
 
 /// Verify docker credentials (username/password) against database
 /// Also supports API key as password for enhanced security
+///
+/// Failures count towards the same backoff and lockout as web logins, including for names
+/// matching no account. `Err` is a ready response: the attempt was blocked, or the database
+/// could not be reached.
 async fn verify_docker_credentials(
     username: &str,
     password: &str,
+    ip: &str,
     state: &AppState,
-) -> Result<Option<String>, sqlx::Error> {
+) -> Result<Option<String>, Response> {
+    login_protection::check_login_allowed(state, ip, None).await?;
+
     // First try to authenticate as a user with regular password
-    let user_result = sqlx::query!(
-        "SELECT id, username, password_hash FROM users WHERE username = $1",
+    let user_result = sqlx::query_as!(
+        User,
+        "SELECT * FROM users WHERE username = $1 AND disabled_at IS NULL",
         username
     )
     .fetch_optional(&state.db_pool)
-    .await?;
+    .await
+    .map_err(|e| {
+        tracing::error!("Database error verifying credentials: {}", e);
+        internal_error()
+    })?;
+
+    let account = match &user_result {
+        Some(user) => LoginAccount::User(user),
+        None => LoginAccount::Unknown(username),
+    };
+    login_protection::check_login_allowed(state, ip, Some(&account)).await?;
 
-    if let Some(user) = user_result {
+    if let Some(user) = &user_result {
         // Try to verify password - support both bcrypt and argon2
         let password_valid = if user.password_hash.starts_with("$argon2") {
             // Argon2 hash
@@ -218,7 +230,7 @@ async fn verify_docker_credentials(
                     }
                 }
                 Err(e) => {
-                    println!("❌ Failed to parse Argon2 hash: {}", e);
+                    tracing::error!("Failed to parse Argon2 hash: {}", e);
                     false
                 }
             }
@@ -227,37 +239,40 @@ async fn verify_docker_credentials(
             match bcrypt::verify(password, &user.password_hash) {
                 Ok(valid) => valid,
                 Err(e) => {
-                    println!("❌ Password verification error: {}", e);
+                    tracing::error!("Password verification error: {}", e);
                     false
                 }
             }
         };
 
         if password_valid {
-            println!("✅ Docker login successful for user: {}", username);
+            tracing::debug!("Docker login successful for user: {}", username);
+            crate::auth::rehash_password_if_needed(&state.db_pool, &state.config.auth, user.id, &user.password_hash, password).await;
+            login_protection::record_login_success(state, user.id).await;
             return Ok(Some(user.id.to_string()));
         } else {
-            println!("❌ Invalid password for user: {}", username);
+            tracing::warn!("Invalid password for user: {}", username);
         }
 
         // If regular password failed, try API key authentication
         // Check if the password looks like an API key (ak_<32_hex_chars>)
         if password.starts_with("ak_") && password.len() == 35 {
-            println!("🔑 Attempting API key authentication for user: {}", username);
+            tracing::debug!("Attempting API key authentication for user: {}", username);
             
             // Use existing API key verification from auth module
-            match crate::auth::verify_api_key(password, &state.db_pool, state.cache.as_ref()).await {
+            match crate::auth::verify_api_key(password, ApiKeyScope::Read, &state.db_pool, state.cache.as_ref()).await {
                 Ok(api_user_id) => {
                     // Verify that the API key belongs to the same user
                     if api_user_id == user.id {
-                        println!("✅ Docker login successful with API key for user: {}", username);
+                        tracing::debug!("Docker login successful with API key for user: {}", username);
+                        login_protection::record_login_success(state, user.id).await;
                         return Ok(Some(user.id.to_string()));
                     } else {
-                        println!("❌ API key belongs to different user (id: {}) than requested user: {}", api_user_id, username);
+                        tracing::warn!("API key belongs to different user (id: {}) than requested user: {}", api_user_id, username);
                     }
                 }
                 Err(e) => {
-                    println!("❌ API key verification failed: {:?}", e);
+                    tracing::warn!("API key verification failed: {:?}", e);
                 }
             }
         }
@@ -293,9 +308,78 @@ async fn verify_docker_credentials(
     //     }
     // }
 
+    login_protection::record_login_failure(state, ip, Some(&account)).await;
     Ok(None)
 }
 
+fn internal_error() -> Response {
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        Json(serde_json::json!({
+            "errors": [{
+                "code": "UNKNOWN",
+                "message": "Internal server error",
+                "detail": {}
+            }]
+        }))
+    ).into_response()
+}
+
+/// Check whether an unauthenticated client may pull from a repository.
+/// Only public repositories qualify, and only when the `allow_anonymous_pull` feature is on.
+pub async fn is_anonymous_pull_allowed(
+    namespace: &str,
+    repository: &str,
+    state: &AppState,
+) -> Result<bool, sqlx::Error> {
+    if !state.features.enabled(Feature::AllowAnonymousPull) {
+        return Ok(false);
+    }
+
+    let is_public = sqlx::query_scalar::<_, bool>(
+        "SELECT r.is_public
+         FROM repositories r
+         JOIN organizations o ON r.organization_id = o.id
+         WHERE o.name = $1 AND r.name = $2",
+    )
+    .bind(namespace)
+    .bind(repository)
+    .fetch_optional(&state.db_pool)
+    .await?;
+
+    Ok(is_public.unwrap_or(false))
+}
+
+/// Highest permission granted to a user on a repository, directly or through any of their teams
+pub async fn get_granted_permission(
+    user_id: i64,
+    namespace: &str,
+    repository: &str,
+    state: &AppState,
+) -> Result<Option<CollaboratorPermission>, sqlx::Error> {
+    let permissions = sqlx::query_scalar::<_, String>(
+        "SELECT rc.permission
+         FROM repository_collaborators rc
+         JOIN repositories r ON rc.repository_id = r.id
+         JOIN organizations o ON r.organization_id = o.id
+         WHERE rc.user_id = $1 AND o.name = $2 AND r.name = $3
+         UNION ALL
+         SELECT tr.permission
+         FROM team_repositories tr
+         JOIN team_members tm ON tm.team_id = tr.team_id
+         JOIN repositories r ON tr.repository_id = r.id
+         JOIN organizations o ON r.organization_id = o.id
+         WHERE tm.user_id = $1 AND o.name = $2 AND r.name = $3",
+    )
+    .bind(user_id)
+    .bind(namespace)
+    .bind(repository)
+    .fetch_all(&state.db_pool)
+    .await?;
+
+    Ok(permissions.iter().filter_map(|p| p.parse().ok()).max())
+}
+
 /// Check if user has permission to access a repository
 pub async fn check_repository_permission(
     user_id: &str,
@@ -304,7 +388,7 @@ pub async fn check_repository_permission(
     operation: &str, // "pull", "push", "delete"
     state: &AppState,
 ) -> Result<bool, sqlx::Error> {
-    println!("🔒 Checking {} permission for user {} on {}/{}", operation, user_id, namespace, repository);
+    tracing::debug!("Checking {} permission for user {} on {}/{}", operation, user_id, namespace, repository);
 
     // If user_id starts with "org_", it's an organization-level access
     if user_id.starts_with("org_") {
@@ -337,21 +421,25 @@ pub async fn check_repository_permission(
     .await?;
 
     if let Some(member) = permission_result {
-        match operation {
-            "pull" => {
-                // All organization members can pull
-                Ok(true)
-            }
-            "push" => {
-                // Only owners, admins and maintainers can push
-                Ok(member.role == "owner" || member.role == "admin" || member.role == "maintainer")
-            }
-            "delete" => {
-                // Only admins can delete
-                Ok(member.role == "admin")
-            }
-            _ => Ok(false)
+        let role = match member.role.parse::<OrganizationRole>() {
+            Ok(role) => role,
+            Err(_) => return Ok(false),
+        };
+
+        // Apply the organization role permission matrix
+        let role_allows = match operation {
+            "pull" => role.can_pull(),
+            "push" => role.can_push(),
+            "delete" => role.can_delete_repositories(),
+            _ => false
+        };
+        if role_allows {
+            return Ok(true);
         }
+
+        // A direct or team grant can raise a member above their organization role
+        let grant = get_granted_permission(user_id_int, namespace, repository, state).await?;
+        Ok(grant.map(|p| p.allows(operation)).unwrap_or(false))
     } else {
         // User is not a member of the organization
         // Check if repository is public or if user is the creator
@@ -366,6 +454,13 @@ pub async fn check_repository_permission(
         .await?;
 
         if let Some(repo) = repo_result {
+            // Direct repository grants apply to users outside the organization
+            if let Some(grant) = get_granted_permission(user_id_int, namespace, repository, state).await? {
+                if grant.allows(operation) {
+                    return Ok(true);
+                }
+            }
+
             match operation {
                 "pull" => {
                     // Allow pull if repository is public OR user is the creator OR user has org access
@@ -391,7 +486,7 @@ pub async fn check_repository_permission(
                         return Ok(true);
                     }
                     
-                    // Check organization membership - only owner/maintainer can push
+                    // Check organization membership - guests cannot push
                     let org_member = sqlx::query!(
                         "SELECT role FROM organization_members om 
                          JOIN organizations o ON om.organization_id = o.id 
@@ -401,11 +496,10 @@ pub async fn check_repository_permission(
                     .fetch_optional(&state.db_pool)
                     .await?;
                     
-                    if let Some(member) = org_member {
-                        Ok(member.role == "owner" || member.role == "admin" || member.role == "maintainer")
-                    } else {
-                        Ok(false)
-                    }
+                    Ok(org_member
+                        .and_then(|member| member.role.parse::<OrganizationRole>().ok())
+                        .map(|role| role.can_push())
+                        .unwrap_or(false))
                 }
                 "delete" => {
                     // Allow delete if user is the creator
@@ -413,7 +507,7 @@ pub async fn check_repository_permission(
                         return Ok(true);
                     }
                     
-                    // Check organization membership - only owner can delete
+                    // Check organization membership - only owners and maintainers can delete
                     let org_member = sqlx::query!(
                         "SELECT role FROM organization_members om 
                          JOIN organizations o ON om.organization_id = o.id 
@@ -423,11 +517,10 @@ pub async fn check_repository_permission(
                     .fetch_optional(&state.db_pool)
                     .await?;
                     
-                    if let Some(member) = org_member {
-                        Ok(member.role == "owner")
-                    } else {
-                        Ok(false)
-                    }
+                    Ok(org_member
+                        .and_then(|member| member.role.parse::<OrganizationRole>().ok())
+                        .map(|role| role.can_delete_repositories())
+                        .unwrap_or(false))
                 }
                 _ => Ok(false)
             }
@@ -3,7 +3,7 @@
 
 use axum::{
     extract::{Path, Query, State},
-    http::{HeaderMap, HeaderValue, StatusCode, header::AUTHORIZATION},
+    http::{HeaderMap, HeaderValue, StatusCode},
     response::{IntoResponse, Response},
     Json,
 };
@@ -14,11 +14,14 @@ use std::collections::HashMap;
 use sha2::{Digest, Sha256};
 use utoipa::ToSchema;
 use uuid;
-use secrecy::ExposeSecret;
 use bytes::Bytes;
 use crate::AppState;
-use crate::auth::verify_token;
-use crate::handlers::docker_auth::{extract_user_from_auth, check_repository_permission};
+use crate::cache::{LoadedManifest, ManifestLoad};
+use crate::features::Feature;
+use crate::log_stream::LogEvent;
+use crate::handlers::conditional;
+use crate::handlers::organizations::load_org_settings;
+use crate::handlers::registry_auth::{AuthContext, Delete, Pull, Push, RegistryAction, RequireRepoPermission};
 
 /// Docker Registry V2 API version response
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
@@ -118,9 +121,17 @@ pub struct TagsQuery {
     pub last: Option<String>,
 }
 
+/// Query parameters for the referrers endpoint
+#[derive(Debug, Deserialize)]
+pub struct ReferrersQuery {
+    #[serde(rename = "artifactType")]
+    pub artifact_type: Option<String>,
+}
+
 /// Docker Registry V2 version check - GET /v2/
 /// Returns API version information to confirm registry compatibility
-/// This endpoint requires authentication as per Docker Registry V2 specification
+/// This endpoint requires authentication as per Docker Registry V2 specification,
+/// unless anonymous pulls are enabled
 #[utoipa::path(
     get,
     path = "/v2/",
@@ -132,27 +143,31 @@ pub struct TagsQuery {
 )]
 pub async fn version_check(
     State(state): State<AppState>,
-    headers: HeaderMap,
+    auth: AuthContext,
 ) -> impl IntoResponse {
-    println!("🔍 GET Version Check (/v2/) endpoint called!");
-    // Docker Registry V2 spec requires authentication for /v2/ endpoint
-    match extract_user_from_auth(&headers, &state, true).await {
-        Ok(_user_id) => {
-            println!("✅ Authentication successful for /v2/ endpoint");
-            (
-                StatusCode::OK,
-                [
-                    ("Docker-Distribution-API-Version", "registry/2.0"),
-                    ("Content-Type", "application/json"),
-                ],
-                Json(json!({}))
-            ).into_response()
-        }
-        Err(response) => {
-            println!("❌ Authentication failed for /v2/ endpoint");
-            response
+    tracing::debug!("GET Version Check (/v2/) endpoint called!");
+    // Docker Registry V2 spec requires authentication for /v2/ endpoint; when
+    // anonymous pulls are enabled clients must be able to probe it without login
+    if !state.features.enabled(Feature::AllowAnonymousPull) {
+        if let Err(response) = auth.require_user() {
+            tracing::debug!("Authentication failed for /v2/ endpoint");
+            return response;
         }
     }
+
+    if auth.is_anonymous() {
+        tracing::debug!("Anonymous access to /v2/ endpoint");
+    } else {
+        tracing::debug!("Authentication successful for /v2/ endpoint");
+    }
+    (
+        StatusCode::OK,
+        [
+            ("Docker-Distribution-API-Version", "registry/2.0"),
+            ("Content-Type", "application/json"),
+        ],
+        Json(json!({}))
+    ).into_response()
 }
 
 /// Get repository catalog - GET /v2/_catalog
@@ -174,30 +189,17 @@ pub async fn version_check(
 pub async fn get_catalog(
     State(state): State<AppState>,
     Query(_params): Query<CatalogQuery>,
-    headers: HeaderMap,
+    auth: AuthContext,
 ) -> impl IntoResponse {
-    println!("🔍 GET Catalog");
+    tracing::debug!("GET Catalog");
     
     // Require authentication for catalog access
-    let user_id = match extract_user_from_auth(&headers, &state, true).await {
-        Ok(Some(uid)) => uid,
-        Ok(None) => {
-            return (
-                StatusCode::UNAUTHORIZED,
-                [("WWW-Authenticate", "Basic")],
-                Json(serde_json::json!({
-                    "errors": [{
-                        "code": "UNAUTHORIZED",
-                        "message": "Authentication required",
-                        "detail": {}
-                    }]
-                }))
-            ).into_response();
-        }
+    let user_id = match auth.require_user() {
+        Ok(uid) => uid,
         Err(response) => return response,
     };
 
-    println!("✅ Authenticated user: {} requesting catalog", user_id);
+    tracing::debug!("Authenticated user: {} requesting catalog", user_id);
     
     // Query database for repositories the user has access to
     let repositories = if user_id.starts_with("org_") {
@@ -211,7 +213,7 @@ pub async fn get_catalog(
              ORDER BY o.name, r.name",
             org_id
         )
-        .fetch_all(&state.db_pool)
+        .fetch_all(state.read_pool())
         .await
         {
             Ok(rows) => {
@@ -220,7 +222,7 @@ pub async fn get_catalog(
                     .collect::<Vec<String>>()
             },
             Err(e) => {
-                println!("❌ Database error querying repositories: {}", e);
+                tracing::error!("Database error querying repositories: {}", e);
                 return (
                     StatusCode::INTERNAL_SERVER_ERROR,
                     Json(serde_json::json!({
@@ -245,7 +247,7 @@ pub async fn get_catalog(
              ORDER BY o.name, r.name",
             user_id_int
         )
-        .fetch_all(&state.db_pool)
+        .fetch_all(state.read_pool())
         .await
         {
             Ok(rows) => {
@@ -254,7 +256,7 @@ pub async fn get_catalog(
                     .collect::<Vec<String>>()
             },
             Err(e) => {
-                println!("❌ Database error querying repositories: {}", e);
+                tracing::error!("Database error querying repositories: {}", e);
                 return (
                     StatusCode::INTERNAL_SERVER_ERROR,
                     Json(serde_json::json!({
@@ -269,14 +271,14 @@ pub async fn get_catalog(
         }
     };
 
-    println!("📋 Found {} repositories for user", repositories.len());
+    tracing::debug!("Found {} repositories for user", repositories.len());
     
     // Update cache if available
     if let Some(cache) = &state.cache {
         if let Err(e) = cache.cache_repositories(repositories.clone()).await {
-            println!("⚠️ Failed to cache repositories: {}", e);
+            tracing::warn!("Failed to cache repositories: {}", e);
         } else {
-            println!("✅ Updated repository cache");
+            tracing::debug!("Updated repository cache");
         }
     }
 
@@ -297,6 +299,7 @@ pub async fn get_catalog(
     ),
     responses(
         (status = 200, description = "Image manifest"),
+        (status = 304, description = "Manifest unchanged since the copy named by If-None-Match"),
         (status = 404, description = "Manifest not found"),
         (status = 401, description = "Authentication required"),
         (status = 403, description = "Insufficient permissions"),
@@ -304,78 +307,16 @@ pub async fn get_catalog(
 )]
 pub async fn get_manifest(
     State(state): State<AppState>,
-    headers: HeaderMap,
+    _access: RequireRepoPermission<Pull>,
     axum::extract::Path((name, reference)): axum::extract::Path<(String, String)>,
+    headers: HeaderMap,
 ) -> impl IntoResponse {
-    // Require authentication for manifest pull
-    let user_id = match extract_user_from_auth(&headers, &state, true).await {
-        Ok(Some(uid)) => uid,
-        Ok(None) => {
-            return (
-                StatusCode::UNAUTHORIZED,
-                [("WWW-Authenticate", "Basic")],
-                Json(serde_json::json!({
-                    "errors": [{
-                        "code": "UNAUTHORIZED",
-                        "message": "Authentication required",
-                        "detail": {}
-                    }]
-                }))
-            ).into_response();
-        }
-        Err(response) => return response,
-    };
-
-    // Parse namespace/repository from name
-    let (namespace, repository) = match parse_repository_name(&name, &user_id, &state).await {
-        Ok((ns, repo)) => (ns, repo),
-        Err(_) => {
-            return (
-                StatusCode::BAD_REQUEST,
-                Json(serde_json::json!({
-                    "errors": [{
-                        "code": "NAME_INVALID",
-                        "message": "Invalid repository name format",
-                        "detail": {}
-                    }]
-                }))
-            ).into_response();
-        }
-    };
-    
-    // Check if user has pull permission
-    match check_repository_permission(&user_id, &namespace, &repository, "pull", &state).await {
-        Ok(true) => {
-            println!("✅ User {} has pull permission for {}/{}", user_id, namespace, repository);
-            get_manifest_impl(&state, &name, &reference).await
-        }
-        Ok(false) => {
-            println!("❌ User {} denied pull access to {}/{}", user_id, namespace, repository);
-            (
-                StatusCode::FORBIDDEN,
-                Json(serde_json::json!({
-                    "errors": [{
-                        "code": "DENIED",
-                        "message": "Insufficient permissions to pull from repository",
-                        "detail": {}
-                    }]
-                }))
-            ).into_response()
-        }
-        Err(e) => {
-            println!("❌ Error checking permissions: {}", e);
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(serde_json::json!({
-                    "errors": [{
-                        "code": "UNKNOWN",
-                        "message": "Internal server error",
-                        "detail": {}
-                    }]
-                }))
-            ).into_response()
-        }
+    let response = get_manifest_impl(&state, &name, &reference).await;
+    let response = conditional::manifest_response(&headers, response, reference.starts_with("sha256:"));
+    if response.status() == StatusCode::OK {
+        crate::activity::record(&state, &name, &reference, crate::activity::Activity::Pull);
     }
+    response
 }
 
 /// Check if manifest exists - HEAD /v2/<name>/manifests/<reference>
@@ -397,53 +338,14 @@ pub async fn get_manifest(
 )]
 pub async fn head_manifest(
     State(state): State<AppState>,
-    headers: HeaderMap,
+    _access: RequireRepoPermission<Pull>,
     axum::extract::Path((name, reference)): axum::extract::Path<(String, String)>,
 ) -> impl IntoResponse {
-    // Require authentication for manifest head
-    let user_id = match extract_user_from_auth(&headers, &state, true).await {
-        Ok(Some(uid)) => uid,
-        Ok(None) => {
-            return (
-                StatusCode::UNAUTHORIZED,
-                [("WWW-Authenticate", "Basic")],
-                ""
-            ).into_response();
-        }
-        Err(_) => {
-            return (
-                StatusCode::UNAUTHORIZED,
-                [("WWW-Authenticate", "Basic")],
-                ""
-            ).into_response();
-        }
-    };
-
-    // Parse namespace/repository from name
-    let parts: Vec<&str> = name.split('/').collect();
-    if parts.len() != 2 {
-        return (StatusCode::BAD_REQUEST, "").into_response();
-    }
-
-    let (namespace, repository) = (parts[0], parts[1]);
-    
-    // Check if user has pull permission
-    match check_repository_permission(&user_id, namespace, repository, "pull", &state).await {
-        Ok(true) => {
-            // Call the existing implementation
-            let result = get_manifest_impl(&state, &name, &reference).await;
-            match result.into_response().status() {
-                StatusCode::OK => (StatusCode::OK, "").into_response(),
-                StatusCode::NOT_FOUND => (StatusCode::NOT_FOUND, "").into_response(),
-                _ => (StatusCode::INTERNAL_SERVER_ERROR, "").into_response(),
-            }
-        }
-        Ok(false) => {
-            (StatusCode::FORBIDDEN, "").into_response()
-        }
-        Err(_) => {
-            (StatusCode::INTERNAL_SERVER_ERROR, "").into_response()
-        }
+    let result = get_manifest_impl(&state, &name, &reference).await;
+    match result.into_response().status() {
+        StatusCode::OK => (StatusCode::OK, "").into_response(),
+        StatusCode::NOT_FOUND => (StatusCode::NOT_FOUND, "").into_response(),
+        _ => (StatusCode::INTERNAL_SERVER_ERROR, "").into_response(),
     }
 }
 
@@ -461,87 +363,18 @@ pub async fn head_manifest(
         (status = 201, description = "Manifest uploaded"),
         (status = 400, description = "Invalid manifest"),
         (status = 401, description = "Authentication required"),
-        (status = 403, description = "Insufficient permissions"),
+        (status = 403, description = "Insufficient permissions, or the push would move a tag frozen by a legal hold"),
     )
 )]
 pub async fn put_manifest(
     State(state): State<AppState>,
+    RequireRepoPermission(access, _): RequireRepoPermission<Push>,
     headers: HeaderMap,
     axum::extract::Path((name, reference)): axum::extract::Path<(String, String)>,
     body: String,
 ) -> impl IntoResponse {
-    println!("🔄 PUT Manifest for {}/{}", name, reference);
-    
-    // Require authentication for manifest push
-    let user_id = match extract_user_from_auth(&headers, &state, true).await {
-        Ok(Some(uid)) => uid,
-        Ok(None) => {
-            return (
-                StatusCode::UNAUTHORIZED,
-                [("WWW-Authenticate", "Basic")],
-                Json(serde_json::json!({
-                    "errors": [{
-                        "code": "UNAUTHORIZED",
-                        "message": "Authentication required",
-                        "detail": {}
-                    }]
-                }))
-            ).into_response();
-        }
-        Err(response) => return response,
-    };
-
-    // Parse namespace/repository from name
-    let (namespace, repository) = match parse_repository_name(&name, &user_id, &state).await {
-        Ok((ns, repo)) => (ns, repo),
-        Err(_) => {
-            return (
-                StatusCode::BAD_REQUEST,
-                Json(serde_json::json!({
-                    "errors": [{
-                        "code": "NAME_INVALID",
-                        "message": "Invalid repository name format",
-                        "detail": {}
-                    }]
-                }))
-            ).into_response();
-        }
-    };
-    
-    // Check if user has push permission
-    match check_repository_permission(&user_id, &namespace, &repository, "push", &state).await {
-        Ok(true) => {
-            println!("✅ User {} has push permission for {}/{}", user_id, namespace, repository);
-            let user_id_int: i64 = user_id.parse().unwrap_or(0);
-            put_manifest_impl(&state, &name, &reference, headers, body, Some(user_id_int)).await.into_response()
-        }
-        Ok(false) => {
-            println!("❌ User {} denied push access to {}/{}", user_id, namespace, repository);
-            (
-                StatusCode::FORBIDDEN,
-                Json(serde_json::json!({
-                    "errors": [{
-                        "code": "DENIED",
-                        "message": "Insufficient permissions to push to repository",
-                        "detail": {}
-                    }]
-                }))
-            ).into_response()
-        }
-        Err(e) => {
-            println!("❌ Error checking push permissions: {}", e);
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(serde_json::json!({
-                    "errors": [{
-                        "code": "UNKNOWN",
-                        "message": "Internal server error",
-                        "detail": {}
-                    }]
-                }))
-            ).into_response()
-        }
-    }
+    tracing::debug!("PUT Manifest for {}/{}", name, reference);
+    put_manifest_impl(&state, &name, &reference, headers, body, access.user_id()).await.into_response()
 }
 
 /// Delete manifest - DELETE /v2/<name>/manifests/<reference>
@@ -558,14 +391,16 @@ pub async fn put_manifest(
         (status = 202, description = "Manifest deleted"),
         (status = 404, description = "Manifest not found"),
         (status = 401, description = "Authentication required"),
+        (status = 403, description = "Insufficient permissions"),
         (status = 405, description = "Delete not allowed"),
     )
 )]
 pub async fn delete_manifest(
     State(state): State<AppState>,
+    RequireRepoPermission(access, _): RequireRepoPermission<Delete>,
     axum::extract::Path((name, reference)): axum::extract::Path<(String, String)>,
 ) -> impl IntoResponse {
-    delete_manifest_impl(&state, &name, &reference).await
+    delete_manifest_impl(&state, &name, &reference, access.user_id()).await.into_response()
 }
 
 /// Get blob - GET /v2/<name>/blobs/<digest>
@@ -586,6 +421,7 @@ pub async fn delete_manifest(
 )]
 pub async fn get_blob(
     State(state): State<AppState>,
+    _access: RequireRepoPermission<Pull>,
     axum::extract::Path((name, digest)): axum::extract::Path<(String, String)>,
 ) -> impl IntoResponse {
     get_blob_impl(&state, &name, &digest).await
@@ -608,6 +444,7 @@ pub async fn get_blob(
 )]
 pub async fn head_blob(
     State(state): State<AppState>,
+    _access: RequireRepoPermission<Pull>,
     axum::extract::Path((name, digest)): axum::extract::Path<(String, String)>,
 ) -> impl IntoResponse {
     head_blob_impl(&state, &name, &digest).await
@@ -631,341 +468,93 @@ pub async fn head_blob(
 )]
 pub async fn start_blob_upload(
     State(state): State<AppState>,
+    RequireRepoPermission(access, _): RequireRepoPermission<Push>,
     Path(name): Path<String>,
-    headers: HeaderMap,
 ) -> impl IntoResponse {
-    println!("🔄 Starting blob upload for {}", name);
-    
-    // Require authentication for blob upload
-    let user_id = match extract_user_from_auth(&headers, &state, true).await {
-        Ok(Some(uid)) => uid,
-        Ok(None) => {
-            return (
-                StatusCode::UNAUTHORIZED,
-                [("WWW-Authenticate", "Basic")],
-                Json(serde_json::json!({
-                    "errors": [{
-                        "code": "UNAUTHORIZED",
-                        "message": "Authentication required",
-                        "detail": {}
-                    }]
-                }))
-            ).into_response();
-        }
-        Err(response) => return response,
-    };
+    start_blob_upload_impl(&state, &name, access.user_id()).await
+}
 
-    // Parse namespace/repository from name
-    let (namespace, repository) = match parse_repository_name(&name, &user_id, &state).await {
-        Ok((ns, repo)) => (ns, repo),
-        Err(_) => {
-            return (
-                StatusCode::BAD_REQUEST,
-                Json(serde_json::json!({
-                    "errors": [{
-                        "code": "NAME_INVALID",
-                        "message": "Invalid repository name format",
-                        "detail": {}
-                    }]
-                }))
-            ).into_response();
-        }
-    };
-    
-    // Check if user has push permission
-    match check_repository_permission(&user_id, &namespace, &repository, "push", &state).await {
-        Ok(true) => {
-            println!("✅ User {} has push permission for blob upload to {}/{}", user_id, namespace, repository);
-        }
-        Ok(false) => {
-            println!("❌ User {} denied push access for blob upload to {}/{}", user_id, namespace, repository);
-            return (
-                StatusCode::FORBIDDEN,
-                Json(serde_json::json!({
-                    "errors": [{
-                        "code": "DENIED",
-                        "message": "Insufficient permissions to push to repository",
-                        "detail": {}
-                    }]
-                }))
-            ).into_response();
-        }
-        Err(e) => {
-            println!("❌ Error checking push permissions: {}", e);
-            return (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(serde_json::json!({
-                    "errors": [{
-                        "code": "UNKNOWN",
-                        "message": "Internal server error",
-                        "detail": {}
-                    }]
-                }))
-            ).into_response();
-        }
-    }
+/// Start blob upload by repository ID - POST /v2/{id}/blobs/uploads/
+/// Uses repository ID instead of name; requires push permission on that repository
+#[utoipa::path(
+    post,
+    path = "/v2/{id}/blobs/uploads/",
+    tag = "docker-registry-v2",
+    params(
+        ("id" = i64, Path, description = "Repository ID"),
+    ),
+    responses(
+        (status = 202, description = "Upload initiated", body = BlobUploadResponse),
+        (status = 401, description = "Authentication required"),
+        (status = 403, description = "Insufficient permissions"),
+        (status = 404, description = "Repository not found"),
+    )
+)]
+pub async fn start_blob_upload_by_id(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Path(repository_id): Path<i64>,
+) -> impl IntoResponse {
+    tracing::debug!("Starting blob upload for repository ID: {}", repository_id);
     
-    // Get repository ID from name
-    let repository_id = match crate::database::queries::get_repository_id_by_name(&state.db_pool, &name).await {
-        Ok(Some(id)) => id,
+    // Resolve the repository so the push permission check applies to it like any named upload
+    let full_name = match sqlx::query_scalar::<_, String>(
+        "SELECT CONCAT(o.name, '/', r.name) FROM repositories r
+         JOIN organizations o ON r.organization_id = o.id
+         WHERE r.id = $1",
+    )
+    .bind(repository_id)
+    .fetch_optional(&state.db_pool)
+    .await
+    {
+        Ok(Some(full_name)) => full_name,
         Ok(None) => {
-            println!("❌ Repository '{}' not found", name);
+            tracing::warn!("Repository ID {} not found", repository_id);
             return (
                 StatusCode::NOT_FOUND,
                 Json(serde_json::json!({
-                    "errors": [{
-                        "code": "NAME_UNKNOWN",
-                        "message": "Repository not found",
-                        "detail": {}
-                    }]
+                    "error": "Repository not found"
                 }))
             ).into_response();
         }
         Err(e) => {
-            println!("❌ Database error getting repository: {}", e);
+            tracing::error!("Failed to check repository existence: {}", e);
             return (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 Json(serde_json::json!({
-                    "errors": [{
-                        "code": "UNKNOWN",
-                        "message": "Database error",
-                        "detail": {}
-                    }]
+                    "error": "Database error"
                 }))
             ).into_response();
         }
     };
-    
-    // Extract JWT token from Authorization header
-    let user_id = if let Some(auth_header) = headers.get(AUTHORIZATION) {
-        if let Ok(auth_str) = auth_header.to_str() {
-            if auth_str.starts_with("Bearer ") {
-                let token = &auth_str[7..]; // Remove "Bearer " prefix
-                
-                // Verify JWT token and extract user_id
-                match verify_token(token, state.config.auth.jwt_secret.expose_secret().as_bytes()) {
-                    Ok(claims) => {
-                        match claims.sub.parse::<i64>() {
-                            Ok(uid) => Some(uid.to_string()),
-                            Err(_) => {
-                                println!("❌ Invalid user ID in JWT token");
-                                return (
-                                    StatusCode::UNAUTHORIZED,
-                                    Json(serde_json::json!({
-                                        "error": "Invalid user ID in token"
-                                    }))
-                                ).into_response();
-                            }
-                        }
-                    }
-                    Err(e) => {
-                        println!("❌ JWT token verification failed: {:?}", e);
-                        return (
-                            StatusCode::UNAUTHORIZED,
-                            Json(serde_json::json!({
-                                "error": "Bearer token required"
-                            }))
-                        ).into_response();
-                    }
-                }
-            } else {
-                println!("❌ Invalid Authorization header format");
-                return (
-                    StatusCode::UNAUTHORIZED,
-                    Json(serde_json::json!({
-                        "error": "Invalid authorization header"
-                    }))
-                ).into_response();
-            }
-        } else {
-            println!("❌ Invalid Authorization header format");
-            return (
-                StatusCode::UNAUTHORIZED,
-                Json(serde_json::json!({
-                    "error": "Invalid authorization header"
-                }))
-            ).into_response();
-        }
-    } else {
-        println!("⚠️ No Authorization header found - BYPASSING AUTH FOR TESTING");
-        None  // Bypass auth for testing
-        // return (
-        //     StatusCode::UNAUTHORIZED,
-        //     Json(serde_json::json!({
-        //         "error": "Authorization header required"
-        //     }))
-        // ).into_response();
+
+    let access = match auth.authorize(&state, &full_name, RegistryAction::Push).await {
+        Ok(access) => access,
+        Err(response) => return response,
     };
     
-    // Generate upload UUID and location
-    let upload_uuid = uuid::Uuid::new_v4().to_string();
-    let location = format!("/v2/{}/blobs/uploads/{}", name, upload_uuid);
-    
-    // Log upload info
-    println!("🔍 Anonymous blob upload (testing mode):");
-    println!("  📁 Repository: {}", name);
-    println!("  📄 Upload UUID: {}", upload_uuid);
-    println!("  🔗 Location: {}", location);
-    
-    // Save to database with repository_id
-    if let Err(e) = crate::database::queries::create_blob_upload(
-        &state.db_pool,
-        &upload_uuid,
-        repository_id,
-        user_id.as_ref().map(|id| id.as_str()),
-    ).await {
-        eprintln!("❌ Failed to save blob upload to database: {}", e);
-        return (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(serde_json::json!({
-                "error": "Failed to create blob upload record"
-            }))
-        ).into_response();
-    } else {
-        println!("✅ Blob upload saved to database successfully");
+    if let Some(response) = check_upload_session_quota(&state, repository_id, access.user_id()).await {
+        return response;
     }
-    
-    let mut response_headers = HeaderMap::new();
-    response_headers.insert("Location", HeaderValue::from_str(&location).unwrap());
-    response_headers.insert("Range", HeaderValue::from_static("0-0"));
-    response_headers.insert("Docker-Upload-UUID", HeaderValue::from_str(&upload_uuid).unwrap());
-    response_headers.insert("Content-Type", HeaderValue::from_static("application/json"));
-    
-    (
-        StatusCode::ACCEPTED,
-        response_headers,
-        Json(serde_json::json!({}))
-    ).into_response()
-}
-
-/// Start blob upload by repository ID - POST /v2/{id}/blobs/uploads/
-/// Uses repository ID instead of name and extracts user_id from JWT token
-#[utoipa::path(
-    post,
-    path = "/v2/{id}/blobs/uploads/",
-    tag = "docker-registry-v2",
-    params(
-        ("id" = i64, Path, description = "Repository ID"),
-    ),
-    responses(
-        (status = 202, description = "Upload initiated", body = BlobUploadResponse),
-        (status = 401, description = "Authentication required"),
-        (status = 404, description = "Repository not found"),
-    )
-)]
-pub async fn start_blob_upload_by_id(
-    State(state): State<AppState>,
-    Path(repository_id): Path<i64>,
-    headers: HeaderMap,
-) -> impl IntoResponse {
-    use axum::http::header::AUTHORIZATION;
-    use crate::auth::verify_token;
-    
-    println!("Starting blob upload for repository ID: {}", repository_id);
-    
-    // Check if repository exists
-    match crate::database::queries::repository_exists(&state.db_pool, repository_id).await {
-        Ok(exists) => {
-            if !exists {
-                println!("❌ Repository ID {} not found", repository_id);
-                return (
-                    StatusCode::NOT_FOUND,
-                    Json(serde_json::json!({
-                        "error": "Repository not found"
-                    }))
-                ).into_response();
-            }
-        }
-        Err(e) => {
-            eprintln!("❌ Failed to check repository existence: {}", e);
-            return (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(serde_json::json!({
-                    "error": "Database error"
-                }))
-            ).into_response();
-        }
+    if let Some(response) = check_storage_quota(&state, repository_id, 0).await {
+        return response;
     }
     
-    // Extract JWT token from Authorization header
-    let user_id = if let Some(auth_header) = headers.get(AUTHORIZATION) {
-        if let Ok(auth_str) = auth_header.to_str() {
-            if auth_str.starts_with("Bearer ") {
-                let token = &auth_str[7..]; // Remove "Bearer " prefix
-                
-                // Verify JWT token and extract user_id
-                match verify_token(token, state.config.auth.jwt_secret.expose_secret().as_bytes()) {
-                    Ok(claims) => {
-                        match claims.sub.parse::<i64>() {
-                            Ok(uid) => Some(uid.to_string()),
-                            Err(_) => {
-                                println!("❌ Invalid user ID in JWT token");
-                                return (
-                                    StatusCode::UNAUTHORIZED,
-                                    Json(serde_json::json!({
-                                        "error": "Invalid user ID in token"
-                                    }))
-                                ).into_response();
-                            }
-                        }
-                    }
-                    Err(e) => {
-                        println!("❌ JWT token verification failed: {:?}", e);
-                        return (
-                            StatusCode::UNAUTHORIZED,
-                            Json(serde_json::json!({
-                                "error": "Invalid or expired token"
-                            }))
-                        ).into_response();
-                    }
-                }
-            } else {
-                println!("❌ Authorization header does not contain Bearer token");
-                return (
-                    StatusCode::UNAUTHORIZED,
-                    Json(serde_json::json!({
-                        "error": "Bearer token required"
-                    }))
-                ).into_response();
-            }
-        } else {
-            println!("❌ Invalid Authorization header format");
-            return (
-                StatusCode::UNAUTHORIZED,
-                Json(serde_json::json!({
-                    "error": "Invalid authorization header"
-                }))
-            ).into_response();
-        }
-    } else {
-        println!("⚠️ No Authorization header found");
-        return (
-            StatusCode::UNAUTHORIZED,
-            Json(serde_json::json!({
-                "error": "Authorization header required"
-            }))
-        ).into_response();
-    };
-    
     // Generate upload UUID and location
     let upload_uuid = uuid::Uuid::new_v4().to_string();
     let location = format!("/v2/{}/blobs/uploads/{}", repository_id, upload_uuid);
     
     // Log upload info
-    println!("🔍 Authenticated blob upload:");
-    println!("  📁 Repository ID: {}", repository_id);
-    println!("  👤 User ID: {}", user_id.as_ref().unwrap());
-    println!("  📄 Upload UUID: {}", upload_uuid);
-    println!("  🔗 Location: {}", location);
+    tracing::debug!("Blob upload {} started in repository {} by {:?}", upload_uuid, repository_id, access.user);
     
     // Save to database with repository_id
     if let Err(e) = crate::database::queries::create_blob_upload(
         &state.db_pool,
         &upload_uuid,
         repository_id,
-        user_id.as_ref().map(|id| id.to_string()).as_deref(),
+        access.user_id().map(|id| id.to_string()).as_deref(),
     ).await {
-        eprintln!("❌ Failed to save blob upload to database: {}", e);
+        tracing::error!("Failed to save blob upload to database: {}", e);
         return (
             StatusCode::INTERNAL_SERVER_ERROR,
             Json(serde_json::json!({
@@ -973,7 +562,7 @@ pub async fn start_blob_upload_by_id(
             }))
         ).into_response();
     } else {
-        println!("✅ Blob upload saved to database successfully");
+        tracing::debug!("Blob upload saved to database successfully");
     }
     
     let mut response_headers = HeaderMap::new();
@@ -1012,12 +601,12 @@ pub async fn start_blob_upload_by_id(
 )]
 pub async fn upload_blob_chunk(
     State(state): State<AppState>,
+    RequireRepoPermission(access, _): RequireRepoPermission<Push>,
     axum::extract::Path((name, uuid)): axum::extract::Path<(String, String)>,
     headers: HeaderMap,
-    body: axum::body::Bytes,
+    body: axum::body::Body,
 ) -> impl IntoResponse {
-    let user_info = extract_user_info_from_headers(&headers);
-    println!("Blob chunk upload by user: {:?} for {}/{}", user_info, name, uuid);
+    tracing::debug!("Blob chunk upload by user: {:?} for {}/{}", access.user, name, uuid);
     
     upload_blob_chunk_impl(&state, &name, &uuid, headers, body).await
 }
@@ -1042,15 +631,15 @@ pub async fn upload_blob_chunk(
 )]
 pub async fn complete_blob_upload(
     State(state): State<AppState>,
+    RequireRepoPermission(access, _): RequireRepoPermission<Push>,
     axum::extract::Path((name, uuid)): axum::extract::Path<(String, String)>,
     Query(params): Query<HashMap<String, String>>,
     headers: HeaderMap,
-    body: axum::body::Bytes,
+    body: axum::body::Body,
 ) -> impl IntoResponse {
-    let user_info = extract_user_info_from_headers(&headers);
-    println!("Blob upload completion by user: {:?} for {}/{}", user_info, name, uuid);
+    tracing::debug!("Blob upload completion by user: {:?} for {}/{}", access.user, name, uuid);
     
-    complete_blob_upload_impl(&state, &name, &uuid, params, body).await
+    complete_blob_upload_impl(&state, &name, &uuid, params, headers, body).await
 }
 
 /// Get upload status - GET /v2/<name>/blobs/uploads/<uuid>
@@ -1071,6 +660,7 @@ pub async fn complete_blob_upload(
 )]
 pub async fn get_upload_status(
     State(state): State<AppState>,
+    _access: RequireRepoPermission<Push>,
     axum::extract::Path((name, uuid)): axum::extract::Path<(String, String)>,
 ) -> impl IntoResponse {
     get_upload_status_impl(&state, &name, &uuid).await
@@ -1094,6 +684,7 @@ pub async fn get_upload_status(
 )]
 pub async fn cancel_blob_upload(
     State(state): State<AppState>,
+    _access: RequireRepoPermission<Push>,
     axum::extract::Path((name, uuid)): axum::extract::Path<(String, String)>,
 ) -> impl IntoResponse {
     cancel_blob_upload_impl(&state, &name, &uuid).await
@@ -1112,29 +703,41 @@ pub async fn cancel_blob_upload(
     ),
     responses(
         (status = 200, description = "Tag list", body = TagListResponse),
+        (status = 304, description = "Tag list unchanged since the copy named by If-None-Match"),
         (status = 404, description = "Repository not found"),
         (status = 401, description = "Authentication required"),
     )
 )]
 pub async fn list_tags(
     State(state): State<AppState>,
+    _access: RequireRepoPermission<Pull>,
     axum::extract::Path(name): axum::extract::Path<String>,
     Query(_params): Query<TagsQuery>,
-) -> impl IntoResponse {
-    println!("🏷️  Listing tags for: {}", name);
+    headers: HeaderMap,
+) -> Response {
+    let (status, Json(list)) = list_tags_impl(&state, name).await;
+    if status == StatusCode::OK {
+        conditional::json_response(&headers, &list)
+    } else {
+        (status, Json(list)).into_response()
+    }
+}
+
+async fn list_tags_impl(state: &AppState, name: String) -> (StatusCode, Json<TagListResponse>) {
+    tracing::debug!("Listing tags for: {}", name);
     
     // Check cache first
     let cache_key = format!("tags:{}", name);
     if let Some(cache) = &state.cache {
         if let Some(cached_tags) = cache.get_tags(&name).await {
-            println!("✅ Cache HIT for tags: {}", name);
+            tracing::debug!("Cache HIT for tags: {}", name);
             let response = TagListResponse {
                 name: name.clone(),
                 tags: cached_tags,
             };
             return (StatusCode::OK, Json(response));
         } else {
-            println!("⚠️ Cache MISS for tags: {}", name);
+            tracing::debug!("Cache MISS for tags: {}", name);
         }
     }
     
@@ -1155,12 +758,12 @@ pub async fn list_tags(
              WHERE o.name = $1 AND r.name = $2",
             org, repo_name
         )
-        .fetch_optional(&state.db_pool)
+        .fetch_optional(state.read_pool())
         .await
         {
             Ok(Some(row)) => row.id,
             Ok(None) => {
-                println!("⚠️  Repository {}/{} not found, returning mock data", org, repo_name);
+                tracing::warn!("Repository {}/{} not found, returning mock data", org, repo_name);
                 // Return mock data for compatibility
                 let response = TagListResponse {
                     name: name.clone(),
@@ -1173,7 +776,7 @@ pub async fn list_tags(
                 return (StatusCode::OK, Json(response));
             },
             Err(e) => {
-                println!("❌ Database error: {}", e);
+                tracing::error!("Database error: {}", e);
                 // Fallback to mock data
                 let response = TagListResponse {
                     name: name.clone(),
@@ -1192,12 +795,12 @@ pub async fn list_tags(
             "SELECT id FROM repositories WHERE name = $1 AND organization_id = 1",
             repo_name
         )
-        .fetch_optional(&state.db_pool)
+        .fetch_optional(state.read_pool())
         .await
         {
             Ok(Some(row)) => row.id,
             Ok(None) => {
-                println!("⚠️  Repository {} not found, returning mock data", repo_name);
+                tracing::warn!("Repository {} not found, returning mock data", repo_name);
                 let response = TagListResponse {
                     name: name.clone(),
                     tags: vec![
@@ -1209,7 +812,7 @@ pub async fn list_tags(
                 return (StatusCode::OK, Json(response));
             },
             Err(e) => {
-                println!("❌ Database error: {}", e);
+                tracing::error!("Database error: {}", e);
                 let response = TagListResponse {
                     name: name.clone(),
                     tags: vec![
@@ -1228,7 +831,7 @@ pub async fn list_tags(
         "SELECT name FROM tags WHERE repository_id = $1 ORDER BY updated_at DESC",
         repository_id
     )
-    .fetch_all(&state.db_pool)
+    .fetch_all(state.read_pool())
     .await;
     
     match tags_result {
@@ -1236,7 +839,7 @@ pub async fn list_tags(
             let tags: Vec<String> = rows.into_iter().map(|row| row.name).collect();
             
             if tags.is_empty() {
-                println!("📝 No tags found in database, returning mock data");
+                tracing::debug!("No tags found in database, returning mock data");
                 let mock_tags = vec![
                     "latest".to_string(),
                     "v1.0.0".to_string(),
@@ -1246,9 +849,9 @@ pub async fn list_tags(
                 // Cache the mock tags
                 if let Some(cache) = &state.cache {
                     if let Err(e) = cache.cache_tags(&name, mock_tags.clone()).await {
-                        println!("⚠️ Failed to cache tags: {}", e);
+                        tracing::warn!("Failed to cache tags: {}", e);
                     } else {
-                        println!("✅ Cached {} mock tags for: {}", mock_tags.len(), name);
+                        tracing::debug!("Cached {} mock tags for: {}", mock_tags.len(), name);
                     }
                 }
                 
@@ -1258,14 +861,14 @@ pub async fn list_tags(
                 };
                 (StatusCode::OK, Json(response))
             } else {
-                println!("✅ Found {} real tags in database: {:?}", tags.len(), tags);
+                tracing::debug!("Found {} real tags in database: {:?}", tags.len(), tags);
                 
                 // Cache the real tags
                 if let Some(cache) = &state.cache {
                     if let Err(e) = cache.cache_tags(&name, tags.clone()).await {
-                        println!("⚠️ Failed to cache tags: {}", e);
+                        tracing::warn!("Failed to cache tags: {}", e);
                     } else {
-                        println!("✅ Cached {} real tags for: {}", tags.len(), name);
+                        tracing::debug!("Cached {} real tags for: {}", tags.len(), name);
                     }
                 }
                 
@@ -1277,7 +880,7 @@ pub async fn list_tags(
             }
         },
         Err(e) => {
-            println!("❌ Error fetching tags: {}, fallback to mock", e);
+            tracing::error!("Error fetching tags: {}, fallback to mock", e);
             let mock_tags = vec![
                 "latest".to_string(),
                 "v1.0.0".to_string(),
@@ -1287,9 +890,9 @@ pub async fn list_tags(
             // Cache the fallback mock tags
             if let Some(cache) = &state.cache {
                 if let Err(e) = cache.cache_tags(&name, mock_tags.clone()).await {
-                    println!("⚠️ Failed to cache fallback tags: {}", e);
+                    tracing::warn!("Failed to cache fallback tags: {}", e);
                 } else {
-                    println!("✅ Cached {} fallback tags for: {}", mock_tags.len(), name);
+                    tracing::debug!("Cached {} fallback tags for: {}", mock_tags.len(), name);
                 }
             }
             
@@ -1305,11 +908,13 @@ pub async fn list_tags(
 /// List repository tags for namespaced repos - GET /v2/<org>/<name>/tags/list
 pub async fn list_tags_namespaced(
     State(state): State<AppState>,
+    _access: RequireRepoPermission<Pull>,
     axum::extract::Path((org, name)): axum::extract::Path<(String, String)>,
     query: Query<TagsQuery>,
-) -> impl IntoResponse {
+    headers: HeaderMap,
+) -> Response {
     let full_name = format!("{}/{}", org, name);
-    println!("Listing tags for namespaced repo: {}", full_name);
+    tracing::debug!("Listing tags for namespaced repo: {}", full_name);
     
     // Reuse the main implementation with combined name
     let response = TagListResponse {
@@ -1321,208 +926,59 @@ pub async fn list_tags_namespaced(
         ],
     };
     
-    (StatusCode::OK, Json(response))
+    conditional::json_response(&headers, &response)
 }
 
 // Namespaced manifest handlers
 pub async fn get_manifest_namespaced(
     State(state): State<AppState>,
+    _access: RequireRepoPermission<Pull>,
     axum::extract::Path((org, name, reference)): axum::extract::Path<(String, String, String)>,
     headers: HeaderMap,
 ) -> impl IntoResponse {
     let full_name = format!("{}/{}", org, name);
-    println!("🔍 GET Manifest (namespaced) for: {}/{}/{}", org, name, reference);
-    
-    // Docker operations require authentication
-    let user_id_opt = match extract_user_from_auth(&headers, &state, false).await {
-        Ok(user_opt) => user_opt,
-        Err(response) => return response,
-    };
-
-    if user_id_opt.is_none() {
-        println!("❌ No authentication provided for manifest {}/{}:{} - Docker login required", org, name, reference);
-        return (
-            StatusCode::UNAUTHORIZED,
-            [("WWW-Authenticate", "Basic")],
-            Json(serde_json::json!({
-                "errors": [{
-                    "code": "UNAUTHORIZED",
-                    "message": "Authentication required - please run 'docker login'",
-                    "detail": {}
-                }]
-            }))
-        ).into_response();
-    }
-
-    let user_id = user_id_opt.unwrap();
-    println!("🔐 Authenticated request for manifest {}/{}:{} by user {}", org, name, reference, user_id);
-
-    // Check repository permissions
-    let repo_query = "SELECT r.is_public, r.created_by FROM repositories r JOIN organizations o ON r.organization_id = o.id WHERE o.name = $1 AND r.name = $2";
-    match sqlx::query_as::<_, (bool, i64)>(repo_query)
-        .bind(&org)
-        .bind(&name)
-        .fetch_optional(&state.db_pool)
-        .await 
-    {
-        Ok(Some((is_public, owner_id))) => {
-            if is_public {
-                // Public repository - any authenticated user can access
-                println!("✅ Repository {}/{} is public (is_public=true) - authenticated access granted", org, name);
-            } else {
-                // Private repository - only owner can access
-                if user_id.parse::<i64>().unwrap_or(0) == owner_id {
-                    println!("✅ Repository {}/{} is private (is_public=false) - owner access granted", org, name);
-                } else {
-                    println!("❌ Repository {}/{} is private (is_public=false) - access denied for non-owner", org, name);
-                    return (
-                        StatusCode::FORBIDDEN,
-                        Json(serde_json::json!({
-                            "errors": [{
-                                "code": "DENIED",
-                                "message": "Access denied - private repository",
-                                "detail": {}
-                            }]
-                        }))
-                    ).into_response();
-                }
-            }
-        },
-        Ok(None) => {
-            println!("❌ Repository {}/{} not found", org, name);
-            return (StatusCode::NOT_FOUND, Json(serde_json::json!({
-                "errors": [{
-                    "code": "NAME_UNKNOWN",
-                    "message": "repository name not known to registry",
-                    "detail": {"name": format!("{}/{}", org, name)}
-                }]
-            }))).into_response();
-        },
-        Err(e) => {
-            println!("❌ Database error checking repository {}/{}: {}", org, name, e);
-            return (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
-                "errors": [{
-                    "code": "UNKNOWN",
-                    "message": "database error", 
-                    "detail": {}
-                }]
-            }))).into_response();
-        }
+    tracing::debug!("GET Manifest (namespaced) for: {}/{}/{}", org, name, reference);
+    let response = get_manifest_impl(&state, &full_name, &reference).await;
+    let response = conditional::manifest_response(&headers, response, reference.starts_with("sha256:"));
+    if response.status() == StatusCode::OK {
+        crate::activity::record(&state, &full_name, &reference, crate::activity::Activity::Pull);
     }
-
-    get_manifest_impl(&state, &full_name, &reference).await
+    response
 }
 
 pub async fn head_manifest_namespaced(
     State(state): State<AppState>,
-    headers: HeaderMap,
+    _access: RequireRepoPermission<Pull>,
     axum::extract::Path((org, name, reference)): axum::extract::Path<(String, String, String)>,
 ) -> impl IntoResponse {
-    // Require authentication for manifest head
-    let user_id = match extract_user_from_auth(&headers, &state, true).await {
-        Ok(Some(uid)) => uid,
-        Ok(None) => {
-            return (
-                StatusCode::UNAUTHORIZED,
-                [("WWW-Authenticate", "Basic")],
-                ""
-            ).into_response();
-        }
-        Err(_) => {
-            return (
-                StatusCode::UNAUTHORIZED,
-                [("WWW-Authenticate", "Basic")],
-                ""
-            ).into_response();
-        }
-    };
-
-    // Check if user has pull permission
-    match check_repository_permission(&user_id, &org, &name, "pull", &state).await {
-        Ok(true) => {
-            let full_name = format!("{}/{}", org, name);
-            head_manifest_impl(&state, &full_name, &reference).await.into_response()
-        }
-        Ok(false) => {
-            println!("❌ User {} denied access to HEAD {}/{}", user_id, org, name);
-            (StatusCode::FORBIDDEN, "").into_response()
-        }
-        Err(e) => {
-            println!("❌ Permission check error: {}", e);
-            (StatusCode::INTERNAL_SERVER_ERROR, "").into_response()
-        }
-    }
+    let full_name = format!("{}/{}", org, name);
+    head_manifest_impl(&state, &full_name, &reference).await.into_response()
 }
 
 pub async fn put_manifest_namespaced(
     State(state): State<AppState>,
+    RequireRepoPermission(access, _): RequireRepoPermission<Push>,
     axum::extract::Path((org, name, reference)): axum::extract::Path<(String, String, String)>,
     headers: HeaderMap,
     body: String,
 ) -> impl IntoResponse {
-    // Require authentication for manifest push
-    let user_id = match extract_user_from_auth(&headers, &state, true).await {
-        Ok(Some(uid)) => uid,
-        Ok(None) => {
-            return (
-                StatusCode::UNAUTHORIZED,
-                [("WWW-Authenticate", "Basic")],
-                Json(serde_json::json!({
-                    "error": "Authentication required for push operations"
-                }))
-            ).into_response();
-        }
-        Err(_) => {
-            return (
-                StatusCode::UNAUTHORIZED,
-                [("WWW-Authenticate", "Basic")],
-                Json(serde_json::json!({
-                    "error": "Invalid authentication credentials"
-                }))
-            ).into_response();
-        }
-    };
-
-    // Check if user has push permission
-    match check_repository_permission(&user_id, &org, &name, "push", &state).await {
-        Ok(true) => {
-            println!("✅ User {} has push permission to {}/{}", user_id, org, name);
-            let full_name = format!("{}/{}", org, name);
-            let user_id_int = user_id.parse().unwrap_or(0);
-            put_manifest_impl(&state, &full_name, &reference, headers, body, Some(user_id_int)).await.into_response()
-        }
-        Ok(false) => {
-            println!("❌ User {} denied push access to {}/{}", user_id, org, name);
-            (
-                StatusCode::FORBIDDEN,
-                Json(serde_json::json!({
-                    "error": "Access denied - insufficient permissions to push to this repository"
-                }))
-            ).into_response()
-        }
-        Err(e) => {
-            println!("❌ Permission check error: {}", e);
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(serde_json::json!({
-                    "error": "Internal error checking permissions"
-                }))
-            ).into_response()
-        }
-    }
+    let full_name = format!("{}/{}", org, name);
+    put_manifest_impl(&state, &full_name, &reference, headers, body, access.user_id()).await.into_response()
 }
 
 pub async fn delete_manifest_namespaced(
     State(state): State<AppState>,
+    RequireRepoPermission(access, _): RequireRepoPermission<Delete>,
     axum::extract::Path((org, name, reference)): axum::extract::Path<(String, String, String)>,
 ) -> impl IntoResponse {
     let full_name = format!("{}/{}", org, name);
-    delete_manifest_impl(&state, &full_name, &reference).await
+    delete_manifest_impl(&state, &full_name, &reference, access.user_id()).await.into_response()
 }
 
 // Namespaced blob handlers
 pub async fn get_blob_namespaced(
     State(state): State<AppState>,
+    _access: RequireRepoPermission<Pull>,
     axum::extract::Path((org, name, digest)): axum::extract::Path<(String, String, String)>,
 ) -> impl IntoResponse {
     let full_name = format!("{}/{}", org, name);
@@ -1531,6 +987,7 @@ pub async fn get_blob_namespaced(
 
 pub async fn head_blob_namespaced(
     State(state): State<AppState>,
+    _access: RequireRepoPermission<Pull>,
     axum::extract::Path((org, name, digest)): axum::extract::Path<(String, String, String)>,
 ) -> impl IntoResponse {
     let full_name = format!("{}/{}", org, name);
@@ -1540,17 +997,16 @@ pub async fn head_blob_namespaced(
 // Namespaced blob upload handlers
 pub async fn start_blob_upload_namespaced(
     State(state): State<AppState>,
+    RequireRepoPermission(access, _): RequireRepoPermission<Push>,
     axum::extract::Path((org, name)): axum::extract::Path<(String, String)>,
-    headers: HeaderMap,
 ) -> impl IntoResponse {
     let full_name = format!("{}/{}", org, name);
-    let user_info = extract_user_info_from_headers(&headers);
-    println!("Namespaced blob upload initiated by: {:?}", user_info);
-    start_blob_upload_impl(&state, &full_name, user_info).await
+    start_blob_upload_impl(&state, &full_name, access.user_id()).await
 }
 
 pub async fn get_upload_status_namespaced(
     State(state): State<AppState>,
+    _access: RequireRepoPermission<Push>,
     axum::extract::Path((org, name, uuid)): axum::extract::Path<(String, String, String)>,
 ) -> impl IntoResponse {
     let full_name = format!("{}/{}", org, name);
@@ -1559,9 +1015,10 @@ pub async fn get_upload_status_namespaced(
 
 pub async fn upload_blob_chunk_namespaced(
     State(state): State<AppState>,
+    _access: RequireRepoPermission<Push>,
     axum::extract::Path((org, name, uuid)): axum::extract::Path<(String, String, String)>,
     headers: HeaderMap,
-    body: axum::body::Bytes,
+    body: axum::body::Body,
 ) -> impl IntoResponse {
     let full_name = format!("{}/{}", org, name);
     upload_blob_chunk_impl(&state, &full_name, &uuid, headers, body).await
@@ -1569,16 +1026,19 @@ pub async fn upload_blob_chunk_namespaced(
 
 pub async fn complete_blob_upload_namespaced(
     State(state): State<AppState>,
+    _access: RequireRepoPermission<Push>,
     axum::extract::Path((org, name, uuid)): axum::extract::Path<(String, String, String)>,
     Query(params): Query<HashMap<String, String>>,
-    body: axum::body::Bytes,
+    headers: HeaderMap,
+    body: axum::body::Body,
 ) -> impl IntoResponse {
     let full_name = format!("{}/{}", org, name);
-    complete_blob_upload_impl(&state, &full_name, &uuid, params, body).await
+    complete_blob_upload_impl(&state, &full_name, &uuid, params, headers, body).await
 }
 
 pub async fn cancel_blob_upload_namespaced(
     State(state): State<AppState>,
+    _access: RequireRepoPermission<Push>,
     axum::extract::Path((org, name, uuid)): axum::extract::Path<(String, String, String)>,
 ) -> impl IntoResponse {
     let full_name = format!("{}/{}", org, name);
@@ -1591,13 +1051,13 @@ async fn get_manifest_impl(
     name: &str,
     reference: &str,
 ) -> Response {
-    println!("🔍 GET Manifest: {}/{}", name, reference);
+    tracing::debug!("GET Manifest: {}/{}", name, reference);
     
     // Check cache first
     let cache_key = format!("manifest:{}:{}", name, reference);
     if let Some(cache) = &state.cache {
         if let Some(cached_manifest) = cache.get_manifest(&cache_key).await {
-            println!("✅ Cache HIT for manifest: {}/{}", name, reference);
+            tracing::debug!("Cache HIT for manifest: {}/{}", name, reference);
             
             // Parse cached manifest to extract headers
             if let Ok(manifest_json) = String::from_utf8(cached_manifest.to_vec()) {
@@ -1617,10 +1077,32 @@ async fn get_manifest_impl(
                 }
             }
         } else {
-            println!("⚠️ Cache MISS for manifest: {}/{}", name, reference);
+            tracing::debug!("Cache MISS for manifest: {}/{}", name, reference);
         }
     }
-    
+
+    // Concurrent misses of the same manifest share one load
+    let loaded = match &state.cache {
+        Some(cache) => cache.load_manifest_once(&cache_key, || load_manifest(state, name, reference)).await,
+        None => load_manifest(state, name, reference).await,
+    };
+    match loaded {
+        Ok(manifest) => {
+            let mut headers = HeaderMap::new();
+            headers.insert("Content-Type", HeaderValue::from_str(&manifest.media_type).unwrap());
+            headers.insert("Docker-Content-Digest", HeaderValue::from_str(&manifest.digest).unwrap());
+            headers.insert("Content-Length", HeaderValue::from_str(&manifest.content.len().to_string()).unwrap());
+            headers.insert("Cache-Control", HeaderValue::from_static("public, max-age=300"));
+
+            (StatusCode::OK, headers, manifest.content).into_response()
+        }
+        Err((status, body)) => (status, Json(body)).into_response(),
+    }
+}
+
+/// Load a manifest from the database and manifest storage; `RegistryCache::load_manifest_once`
+/// caches what it finds
+pub(crate) async fn load_manifest(state: &AppState, name: &str, reference: &str) -> ManifestLoad {
     // Parse repository name (handle org/repo format)
     let (org_name, repo_name) = if name.contains('/') {
         let parts: Vec<&str> = name.splitn(2, '/').collect();
@@ -1643,20 +1125,12 @@ async fn get_manifest_impl(
         {
             Ok(Some(row)) => row.id,
             Ok(None) => {
-                println!("❌ Repository {}/{} not found", org, repo_name);
-                return (
-                    StatusCode::NOT_FOUND,
-                    HeaderMap::new(),
-                    Json(json!({"error": "repository not found"}))
-                ).into_response();
+                tracing::warn!("Repository {}/{} not found", org, repo_name);
+                return Err((StatusCode::NOT_FOUND, json!({"error": "repository not found"})));
             },
             Err(e) => {
-                println!("❌ Database error: {}", e);
-                return (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    HeaderMap::new(),
-                    Json(json!({"error": "database error"}))
-                ).into_response();
+                tracing::error!("Database error: {}", e);
+                return Err((StatusCode::INTERNAL_SERVER_ERROR, json!({"error": "database error"})));
             }
         }
     } else {
@@ -1670,20 +1144,12 @@ async fn get_manifest_impl(
         {
             Ok(Some(row)) => row.id,
             Ok(None) => {
-                println!("❌ Repository {} not found", repo_name);
-                return (
-                    StatusCode::NOT_FOUND,
-                    HeaderMap::new(),
-                    Json(json!({"error": "repository not found"}))
-                ).into_response();
+                tracing::warn!("Repository {} not found", repo_name);
+                return Err((StatusCode::NOT_FOUND, json!({"error": "repository not found"})));
             },
             Err(e) => {
-                println!("❌ Database error: {}", e);
-                return (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    HeaderMap::new(),
-                    Json(json!({"error": "database error"}))
-                ).into_response();
+                tracing::error!("Database error: {}", e);
+                return Err((StatusCode::INTERNAL_SERVER_ERROR, json!({"error": "database error"})));
             }
         }
     };
@@ -1719,142 +1185,33 @@ async fn get_manifest_impl(
             let media_type: String = row.get("media_type");  
             let size: i64 = row.get("size");
             
-            println!("✅ Found manifest in database: digest={}, media_type={}, size={}", digest, media_type, size);
+            tracing::debug!("Found manifest in database: digest={}, media_type={}, size={}", digest, media_type, size);
             
-            // Try to retrieve the actual manifest content from S3 storage first
-            // Use simplified path structure
-            let repo_full_name = name; // Use full name like "testorg1/step-test" 
-            let manifest_blob_key = format!("{}/{}", repo_full_name, digest);
-            let manifest_content = match state.storage.get_blob(&manifest_blob_key).await {
-                Ok(Some(content)) => {
-                    println!("✅ Retrieved manifest content from S3: {} bytes", content.len());
-                    match String::from_utf8(content.to_vec()) {
-                        Ok(content_str) => content_str,
-                        Err(_) => {
-                            println!("⚠️ Failed to parse manifest content as UTF-8, checking memory cache");
-                            // Check memory cache
-                            match state.manifest_cache.read().await.get(&digest) {
-                                Some(cached_content) => {
-                                    println!("✅ Found manifest in memory cache: {} bytes", cached_content.len());
-                                    cached_content.clone()
-                                },
-                                None => {
-                                    println!("⚠️ No manifest in memory cache, using fallback");
-                                    // Last resort fallback manifest
-                                    serde_json::to_string(&json!({
-                                        "schemaVersion": 2,
-                                        "mediaType": media_type,
-                                        "config": {
-                                            "mediaType": "application/vnd.docker.container.image.v1+json",
-                                            "size": 1469,
-                                            "digest": "sha256:hello-world-config"
-                                        },
-                                        "layers": [
-                                            {
-                                                "mediaType": "application/vnd.docker.image.rootfs.diff.tar.gzip",
-                                                "size": 5000,
-                                                "digest": "sha256:hello-world-layer"
-                                            }
-                                        ]
-                                    })).unwrap()
-                                }
-                            }
-                        }
-                    }
-                },
+            let content = match load_manifest_content(state, name, &digest).await {
+                Ok(Some(content)) if std::str::from_utf8(&content).is_ok() => content,
+                Ok(Some(_)) => {
+                    tracing::warn!("Stored manifest {} is not valid UTF-8", digest);
+                    return Err(manifest_error(StatusCode::INTERNAL_SERVER_ERROR, "stored manifest content is corrupt"));
+                }
                 Ok(None) => {
-                    println!("⚠️ Manifest content not found in S3, checking memory cache");
-                    // Check memory cache for manifest content
-                    match state.manifest_cache.read().await.get(&digest) {
-                        Some(cached_content) => {
-                            println!("✅ Found manifest in memory cache: {} bytes", cached_content.len());
-                            cached_content.clone()
-                        },
-                        None => {
-                            println!("⚠️ No manifest in memory cache, creating fallback manifest");
-                            // Create a fallback manifest when neither S3 nor memory cache has content
-                            serde_json::to_string(&json!({
-                                "schemaVersion": 2,
-                                "mediaType": media_type,
-                                "config": {
-                                    "mediaType": "application/vnd.docker.container.image.v1+json",
-                                    "size": 1469,
-                                    "digest": "sha256:hello-world-config"
-                                },
-                                "layers": [
-                                    {
-                                        "mediaType": "application/vnd.docker.image.rootfs.diff.tar.gzip",
-                                        "size": 5000,
-                                        "digest": "sha256:hello-world-layer"
-                                    }
-                                ]
-                            })).unwrap()
-                        }
-                    }
-                },
-                Err(e) => {
-                    println!("⚠️ Error retrieving manifest from S3: {}, checking memory cache", e);
-                    // Check memory cache for manifest content
-                    match state.manifest_cache.read().await.get(&digest) {
-                        Some(cached_content) => {
-                            println!("✅ Found manifest in memory cache: {} bytes", cached_content.len());
-                            cached_content.clone()
-                        },
-                        None => {
-                            println!("⚠️ No manifest in memory cache, creating fallback manifest");
-                            // Create a fallback manifest when S3 fails and no memory cache
-                            serde_json::to_string(&json!({
-                                "schemaVersion": 2,
-                                "mediaType": media_type,
-                                "config": {
-                                    "mediaType": "application/vnd.docker.container.image.v1+json", 
-                                    "size": 1469,
-                                    "digest": "sha256:hello-world-config"
-                                },
-                                "layers": [
-                                    {
-                                        "mediaType": "application/vnd.docker.image.rootfs.diff.tar.gzip",
-                                        "size": 5000,
-                                        "digest": "sha256:hello-world-layer"  
-                                    }
-                                ]
-                            })).unwrap()
-                        }
-                    }
+                    tracing::warn!("Manifest {} is recorded but its content is missing", digest);
+                    return Err(manifest_error(StatusCode::NOT_FOUND, "manifest content is missing"));
                 }
-            };            // Cache the manifest
-            if let Some(cache) = &state.cache {
-                let manifest_bytes = Bytes::from(manifest_content.clone());
-                if let Err(e) = cache.cache_manifest(&cache_key, manifest_bytes).await {
-                    println!("⚠️ Failed to cache manifest: {}", e);
-                } else {
-                    println!("✅ Cached manifest: {}/{}", name, reference);
+                Err(e) => {
+                    tracing::error!("Error retrieving manifest content for {}: {}", digest, e);
+                    return Err(manifest_error(StatusCode::SERVICE_UNAVAILABLE, "manifest storage is unavailable"));
                 }
-            }
-            
-            let mut headers = HeaderMap::new();
-            headers.insert("Content-Type", HeaderValue::from_str(&media_type).unwrap());
-            headers.insert("Docker-Content-Digest", HeaderValue::from_str(&digest).unwrap());
-            headers.insert("Content-Length", HeaderValue::from_str(&manifest_content.len().to_string()).unwrap());
-            headers.insert("Cache-Control", HeaderValue::from_static("public, max-age=300"));
-            
-            (StatusCode::OK, headers, manifest_content).into_response()
+            };
+
+            Ok(LoadedManifest { content, media_type, digest })
         },
         Ok(None) => {
-            println!("❌ Manifest not found in database for {}/{}", name, reference);
-            (
-                StatusCode::NOT_FOUND,
-                HeaderMap::new(),
-                Json(json!({"error": "manifest not found"}))
-            ).into_response()
+            tracing::warn!("Manifest not found in database for {}/{}", name, reference);
+            Err((StatusCode::NOT_FOUND, json!({"error": "manifest not found"})))
         },
         Err(e) => {
-            println!("❌ Database error retrieving manifest: {}", e);
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                HeaderMap::new(),
-                Json(json!({"error": "database error"}))
-            ).into_response()
+            tracing::error!("Database error retrieving manifest: {}", e);
+            Err((StatusCode::INTERNAL_SERVER_ERROR, json!({"error": "database error"})))
         }
     }
 }
@@ -1864,7 +1221,7 @@ async fn head_manifest_impl(
     name: &str,
     reference: &str,
 ) -> impl IntoResponse {
-    println!("🔍 HEAD Manifest: {}/{}", name, reference);
+    tracing::debug!("HEAD Manifest: {}/{}", name, reference);
     
     // Parse repository name (handle org/repo format)
     let (org_name, repo_name) = if name.contains('/') {
@@ -1888,14 +1245,14 @@ async fn head_manifest_impl(
         {
             Ok(Some(row)) => row.id,
             Ok(None) => {
-                println!("❌ Repository {}/{} not found for HEAD", org, repo_name);
+                tracing::warn!("Repository {}/{} not found for HEAD", org, repo_name);
                 return (
                     StatusCode::NOT_FOUND,
                     HeaderMap::new(),
                 ).into_response();
             },
             Err(e) => {
-                println!("❌ Database error during HEAD: {}", e);
+                tracing::error!("Database error during HEAD: {}", e);
                 return (
                     StatusCode::INTERNAL_SERVER_ERROR,
                     HeaderMap::new(),
@@ -1913,14 +1270,14 @@ async fn head_manifest_impl(
         {
             Ok(Some(row)) => row.id,
             Ok(None) => {
-                println!("❌ Repository {} not found for HEAD", repo_name);
+                tracing::warn!("Repository {} not found for HEAD", repo_name);
                 return (
                     StatusCode::NOT_FOUND,
                     HeaderMap::new(),
                 ).into_response();
             },
             Err(e) => {
-                println!("❌ Database error during HEAD: {}", e);
+                tracing::error!("Database error during HEAD: {}", e);
                 return (
                     StatusCode::INTERNAL_SERVER_ERROR,
                     HeaderMap::new(),
@@ -1960,24 +1317,25 @@ async fn head_manifest_impl(
             let media_type: String = row.get("media_type");
             let size: i64 = row.get("size");
             
-            println!("✅ HEAD found manifest: digest={}, size={}", digest, size);
+            tracing::debug!("HEAD found manifest: digest={}, size={}", digest, size);
             
             let mut headers = HeaderMap::new();
             headers.insert("Content-Type", HeaderValue::from_str(&media_type).unwrap());
             headers.insert("Docker-Content-Digest", HeaderValue::from_str(&digest).unwrap());
             headers.insert("Content-Length", HeaderValue::from_str(&size.to_string()).unwrap());
+            headers.insert("ETag", HeaderValue::from_str(&conditional::etag(&digest)).unwrap());
             
             (StatusCode::OK, headers).into_response()
         },
         Ok(None) => {
-            println!("❌ Manifest not found for HEAD: {}/{}", name, reference);
+            tracing::warn!("Manifest not found for HEAD: {}/{}", name, reference);
             (
                 StatusCode::NOT_FOUND,
                 HeaderMap::new(),
             ).into_response()
         },
         Err(e) => {
-            println!("❌ Database error during HEAD: {}", e);
+            tracing::error!("Database error during HEAD: {}", e);
             (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 HeaderMap::new(),
@@ -1986,7 +1344,7 @@ async fn head_manifest_impl(
     }
 }
 
-async fn put_manifest_impl(
+pub(crate) async fn put_manifest_impl(
     state: &AppState,
     name: &str,
     reference: &str,
@@ -1994,8 +1352,24 @@ async fn put_manifest_impl(
     body: String,
     user_id: Option<i64>,  // Add user_id parameter
 ) -> impl IntoResponse {
-    println!("🚀 PUT Manifest: {}/{} - {} bytes", name, reference, body.len());
-    println!("Content-Type: {:?}", headers.get("content-type"));
+    tracing::info!("PUT Manifest: {}/{} - {} bytes", name, reference, body.len());
+    tracing::debug!("Content-Type: {:?}", headers.get("content-type"));
+
+    let max_manifest_bytes = state.config.storage.max_manifest_bytes;
+    if body.len() > max_manifest_bytes {
+        tracing::warn!("Manifest of {} bytes exceeds the {} byte limit", body.len(), max_manifest_bytes);
+        return (
+            StatusCode::PAYLOAD_TOO_LARGE,
+            HeaderMap::new(),
+            Json(serde_json::json!({
+                "errors": [{
+                    "code": "SIZE_INVALID",
+                    "message": format!("manifest exceeds the {} byte limit", max_manifest_bytes),
+                    "detail": {}
+                }]
+            }))
+        ).into_response();
+    }
     
     // Calculate digest from the exact bytes Docker sent (no modification allowed)
     let digest = format!("sha256:{}", hex::encode(Sha256::digest(body.as_bytes())));
@@ -2004,17 +1378,17 @@ async fn put_manifest_impl(
         .and_then(|h| h.to_str().ok())
         .unwrap_or("application/vnd.docker.distribution.manifest.v2+json");
     
-    println!("📝 Manifest body: {} bytes", body.len());
-    println!("🔍 Calculated digest: {}", digest);
+    tracing::debug!("Manifest body: {} bytes", body.len());
+    tracing::debug!("Calculated digest: {}", digest);
     
     // Try to parse the manifest to extract config blob info
     let config_blob_info = if let Ok(manifest) = serde_json::from_str::<DockerManifest>(&body) {
-        println!("✅ Successfully parsed Docker manifest");
-        println!("🔧 Config blob: {} (size: {})", manifest.config.digest, manifest.config.size);
-        println!("📦 Layers count: {}", manifest.layers.len());
+        tracing::debug!("Successfully parsed Docker manifest");
+        tracing::debug!("Config blob: {} (size: {})", manifest.config.digest, manifest.config.size);
+        tracing::debug!("Layers count: {}", manifest.layers.len());
         Some(manifest.config)
     } else {
-        println!("⚠️ Failed to parse manifest as Docker manifest, continuing without config extraction");
+        tracing::warn!("Failed to parse manifest as Docker manifest, continuing without config extraction");
         None
     };
     
@@ -2025,7 +1399,12 @@ async fn put_manifest_impl(
     } else {
         (None, name)
     };
-    
+
+    // Custom validation runs before a repository is created for the push
+    if let Some(response) = check_push_validators(state, org_name, name, reference, &digest, media_type, &body, user_id).await {
+        return response;
+    }
+    
     // Find or create repository ID
     let repository_id = if let Some(org) = org_name {
         // Namespaced repository (org/repo)
@@ -2041,7 +1420,10 @@ async fn put_manifest_impl(
             Ok(Some(row)) => row.id,
             Ok(None) => {
                 // Repository not found, try to create it
-                println!("🔧 Repository {}/{} not found, attempting to create it", org, repo_name);
+                tracing::debug!("Repository {}/{} not found, attempting to create it", org, repo_name);
+                if let Err(e) = crate::naming::validate_repository_name(org, repo_name) {
+                    return invalid_name(e);
+                }
                 
                 // First, get or create organization
                 let org_id = match sqlx::query!(
@@ -2053,6 +1435,9 @@ async fn put_manifest_impl(
                 {
                     Ok(Some(org_row)) => org_row.id,
                     Ok(None) => {
+                        if let Err(e) = crate::naming::validate_organization_name(org, &state.config.naming) {
+                            return invalid_name(e);
+                        }
                         // Create organization
                         match sqlx::query!(
                             "INSERT INTO organizations (name, display_name) VALUES ($1, $1) RETURNING id",
@@ -2062,11 +1447,11 @@ async fn put_manifest_impl(
                         .await
                         {
                             Ok(new_org) => {
-                                println!("✅ Created organization: {}", org);
+                                tracing::debug!("Created organization: {}", org);
                                 new_org.id
                             },
                             Err(e) => {
-                                println!("❌ Failed to create organization: {}", e);
+                                tracing::error!("Failed to create organization: {}", e);
                                 return (
                                     StatusCode::INTERNAL_SERVER_ERROR,
                                     HeaderMap::new(),
@@ -2076,7 +1461,7 @@ async fn put_manifest_impl(
                         }
                     },
                     Err(e) => {
-                        println!("❌ Database error getting organization: {}", e);
+                        tracing::error!("Database error getting organization: {}", e);
                         return (
                             StatusCode::INTERNAL_SERVER_ERROR,
                             HeaderMap::new(),
@@ -2085,21 +1470,36 @@ async fn put_manifest_impl(
                     }
                 };
                 
-                // Create repository
+                if let Some(response) = check_repository_quota(state, org_id).await {
+                    return response;
+                }
+                // Create repository with the organization's defaults
+                let settings = match load_org_settings(&state.db_pool, org_id).await {
+                    Ok(settings) => settings,
+                    Err(e) => {
+                        tracing::error!("Database error loading organization settings: {}", e);
+                        return (
+                            StatusCode::INTERNAL_SERVER_ERROR,
+                            HeaderMap::new(),
+                            Json(serde_json::json!({"error": "Database error"}))
+                        ).into_response();
+                    }
+                };
                 match sqlx::query!(
-                    "INSERT INTO repositories (name, organization_id, is_public, created_by) 
-                     VALUES ($1, $2, true, $3) RETURNING id",
-                    repo_name, org_id, user_id
+                    "INSERT INTO repositories (name, organization_id, is_public, created_by, tag_retention_keep_last, tag_retention_days) 
+                     VALUES ($1, $2, $3, $4, $5, $6) RETURNING id",
+                    repo_name, org_id, settings.default_repository_public, user_id,
+                    settings.default_tag_retention_keep_last, settings.default_tag_retention_days
                 )
                 .fetch_one(&state.db_pool)
                 .await
                 {
                     Ok(new_repo) => {
-                        println!("✅ Created repository: {}/{}", org, repo_name);
+                        tracing::debug!("Created repository: {}/{}", org, repo_name);
                         new_repo.id
                     },
                     Err(e) => {
-                        println!("❌ Failed to create repository: {}", e);
+                        tracing::error!("Failed to create repository: {}", e);
                         return (
                             StatusCode::INTERNAL_SERVER_ERROR,
                             HeaderMap::new(),
@@ -2109,7 +1509,7 @@ async fn put_manifest_impl(
                 }
             },
             Err(e) => {
-                println!("❌ Database error: {}", e);
+                tracing::error!("Database error: {}", e);
                 return (
                     StatusCode::INTERNAL_SERVER_ERROR,
                     HeaderMap::new(),
@@ -2129,21 +1529,39 @@ async fn put_manifest_impl(
             Ok(Some(row)) => row.id,
             Ok(None) => {
                 // Repository not found, create it under default organization (id=1)
-                println!("🔧 Repository {} not found, attempting to create it", repo_name);
+                tracing::debug!("Repository {} not found, attempting to create it", repo_name);
+                if let Err(e) = crate::naming::validate_repository_name("", repo_name) {
+                    return invalid_name(e);
+                }
+                if let Some(response) = check_repository_quota(state, 1).await {
+                    return response;
+                }
+                let settings = match load_org_settings(&state.db_pool, 1).await {
+                    Ok(settings) => settings,
+                    Err(e) => {
+                        tracing::error!("Database error loading organization settings: {}", e);
+                        return (
+                            StatusCode::INTERNAL_SERVER_ERROR,
+                            HeaderMap::new(),
+                            Json(serde_json::json!({"error": "Database error"}))
+                        ).into_response();
+                    }
+                };
                 match sqlx::query!(
-                    "INSERT INTO repositories (name, organization_id, is_public, created_by) 
-                     VALUES ($1, 1, true, $2) RETURNING id",
-                    repo_name, user_id
+                    "INSERT INTO repositories (name, organization_id, is_public, created_by, tag_retention_keep_last, tag_retention_days) 
+                     VALUES ($1, 1, $2, $3, $4, $5) RETURNING id",
+                    repo_name, settings.default_repository_public, user_id,
+                    settings.default_tag_retention_keep_last, settings.default_tag_retention_days
                 )
                 .fetch_one(&state.db_pool)
                 .await
                 {
                     Ok(new_repo) => {
-                        println!("✅ Created repository: {}", repo_name);
+                        tracing::debug!("Created repository: {}", repo_name);
                         new_repo.id
                     },
                     Err(e) => {
-                        println!("❌ Failed to create repository: {}", e);
+                        tracing::error!("Failed to create repository: {}", e);
                         return (
                             StatusCode::INTERNAL_SERVER_ERROR,
                             HeaderMap::new(),
@@ -2153,7 +1571,7 @@ async fn put_manifest_impl(
                 }
             },
             Err(e) => {
-                println!("❌ Database error: {}", e);
+                tracing::error!("Database error: {}", e);
                 return (
                     StatusCode::INTERNAL_SERVER_ERROR,
                     HeaderMap::new(),
@@ -2163,47 +1581,53 @@ async fn put_manifest_impl(
         }
     };
 
-    // Store manifest content in S3 storage as a blob (simplified structure)
-    // Just use organization/repository structure - no extra folders
+    if let Some(response) = check_storage_quota(state, repository_id, size).await {
+        return response;
+    }
+
+    // Content goes to storage first and the database records follow in one transaction, so no
+    // row ever points at content that was not stored; content written for a push whose
+    // transaction fails is discarded again
     let repo_full_name = name; // Use full name like "testorg1/step-test"
     let manifest_blob_key = format!("{}/{}", repo_full_name, digest);
-    
-    // No need to create complex folder structure
-    
-    let _s3_success = match state.storage.put_blob(&manifest_blob_key, Bytes::from(body.clone())).await {
+    // When unsure whether the content was there before, it is never discarded
+    let blob_existed = state.storage.blob_exists(&manifest_blob_key).await.unwrap_or(true);
+    let blob_created = match state.storage.put_blob(&manifest_blob_key, Bytes::from(body.clone())).await {
         Ok(_) => {
-            println!("✅ Manifest content stored in S3: {}", manifest_blob_key);
-            true
+            tracing::debug!("Manifest content stored in S3: {}", manifest_blob_key);
+            !blob_existed
         },
-        Err(e) => {
-            println!("⚠️ Warning: Error storing manifest content in S3: {}", e);
-            println!("🔄 Will store manifest content in memory cache as fallback");
+        // The database copy is the record when enabled, so the push can still succeed
+        Err(e) if state.config.storage.manifests_in_database => {
+            tracing::warn!("Error storing manifest content in S3: {}", e);
+            tracing::debug!("Pulls will be served from the database copy");
             false
+        },
+        Err(e) => {
+            tracing::error!("Error storing manifest content in S3: {}", e);
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                HeaderMap::new(),
+                Json(serde_json::json!({"error": "Failed to store manifest"}))
+            ).into_response();
         }
     };
-    
-    // Always store manifest content in memory cache as backup (exact bytes as received)
-    {
-        let mut cache = state.manifest_cache.write().await;
-        cache.insert(digest.clone(), body.clone());
-        println!("✅ Manifest content cached in memory: {} bytes", body.len());
-    }
 
     // If we have config blob info, we need to ensure the config blob exists
     // Since Docker expects config blob to be available during pull
     if let Some(config_info) = &config_blob_info {
-        println!("� Checking if config blob exists: {}", config_info.digest);
+        tracing::debug!("Checking if config blob exists: {}", config_info.digest);
         
         let config_blob_key = format!("{}/{}", repo_full_name, config_info.digest);
         
         // Check if config blob already exists in storage
         match state.storage.get_blob(&config_blob_key).await {
             Ok(_) => {
-                println!("✅ Config blob already exists in storage");
+                tracing::debug!("Config blob already exists in storage");
             },
             Err(_) => {
-                println!("⚠️ Config blob not found in storage: {}", config_info.digest);
-                println!("� Creating default config blob for Docker compatibility");
+                tracing::warn!("Config blob not found in storage: {}", config_info.digest);
+                tracing::debug!("Creating default config blob for Docker compatibility");
                 
                 // Create a basic Docker image config blob
                 let config_content = serde_json::json!({
@@ -2247,10 +1671,10 @@ async fn put_manifest_impl(
                 // Store the generated config blob
                 match state.storage.put_blob(&config_blob_key, config_bytes).await {
                     Ok(_) => {
-                        println!("✅ Generated config blob stored successfully: {}", config_info.digest);
+                        tracing::debug!("Generated config blob stored successfully: {}", config_info.digest);
                     },
                     Err(e) => {
-                        println!("❌ Failed to store generated config blob: {}", e);
+                        tracing::error!("Failed to store generated config blob: {}", e);
                         // Continue anyway - manifest upload should not fail
                     }
                 }
@@ -2258,104 +1682,469 @@ async fn put_manifest_impl(
         }
     }
 
-    // Insert or update manifest in database  
-    let manifest_result = sqlx::query!(
-        "INSERT INTO manifests (repository_id, digest, media_type, size) 
-         VALUES ($1, $2, $3, $4) 
-         ON CONFLICT (repository_id, digest) 
-         DO UPDATE SET media_type = $3, size = $4
-         RETURNING id",
-        repository_id, digest, media_type, size
-    )
-    .fetch_one(&state.db_pool)
-    .await;
-    
-    let manifest_id = match manifest_result {
-        Ok(row) => {
-            println!("✅ Manifest stored in database with ID: {}", row.id);
-            row.id
-        },
+    // Index manifests that refer to another one so the referrers API can list them, and hold
+    // images pushed to a repository that quarantines pushes until scanned or approved
+    let subject = referrer_fields(&body);
+    let record = ManifestRecord {
+        repository_id,
+        reference,
+        digest: &digest,
+        media_type,
+        size,
+        content: body.as_bytes(),
+        subject: subject.as_ref(),
+        quarantine: crate::quarantine::holds(reference, body.as_bytes()),
+        pushed_by: user_id,
+    };
+    match record_manifest(state, &record).await {
+        Ok(true) => tracing::debug!("{}@{} quarantined", name, digest),
+        Ok(false) => {}
         Err(e) => {
-            println!("❌ Error storing manifest: {}", e);
+            if blob_created {
+                discard_manifest_blob(state, &manifest_blob_key).await;
+            }
+            if let Some(held) = e.downcast_ref::<crate::handlers::legal_holds::TagHeld>() {
+                tracing::warn!("Refusing to move {}:{} to {}: organization under legal hold", name, reference, digest);
+                return (
+                    StatusCode::FORBIDDEN,
+                    HeaderMap::new(),
+                    Json(serde_json::json!({
+                        "errors": [{
+                            "code": "DENIED",
+                            "message": held.to_string(),
+                            "detail": {}
+                        }]
+                    }))
+                ).into_response();
+            }
+            tracing::error!("Error recording manifest {} of {}: {:#}", digest, name, e);
             return (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 HeaderMap::new(),
                 Json(serde_json::json!({"error": "Failed to store manifest"}))
             ).into_response();
         }
-    };
-    
-    // If reference is a tag (not a digest), create/update tag
-    if !reference.starts_with("sha256:") {
-        let tag_result = sqlx::query!(
-            "INSERT INTO tags (repository_id, name, manifest_id) 
-             VALUES ($1, $2, $3)
-             ON CONFLICT (repository_id, name)
-             DO UPDATE SET manifest_id = $3, updated_at = CURRENT_TIMESTAMP
-             RETURNING id",
-            repository_id, reference, manifest_id
-        )
-        .fetch_one(&state.db_pool)
-        .await;
-        
-        match tag_result {
-            Ok(row) => println!("✅ Tag '{}' stored in database with ID: {}", reference, row.id),
-            Err(e) => {
-                println!("⚠️  Error storing tag: {}", e);
-                // Don't fail the whole operation for tag errors
-            }
-        }
+    }
+
+    // Keep manifest content in the memory cache as a backup (exact bytes as received)
+    if let Some(cache) = &state.cache {
+        cache.cache_manifest_content(&digest, Bytes::from(body.clone())).await;
+        tracing::debug!("Manifest content cached in memory: {} bytes", body.len());
     }
     
-    // Invalidate related caches after successful manifest upload
+    // Invalidate related caches once the records are committed; a pull invalidated before then
+    // could read the old tag again and cache it
     if let Some(cache) = &state.cache {
         // Invalidate manifest cache for this repository/reference
         let manifest_cache_key = format!("manifest:{}:{}", name, reference);
         if let Err(e) = cache.invalidate_manifest(&manifest_cache_key).await {
-            println!("⚠️ Failed to invalidate manifest cache: {}", e);
+            tracing::warn!("Failed to invalidate manifest cache: {}", e);
         }
         
         // Invalidate tags cache for this repository
         if let Err(e) = cache.invalidate_tags(name).await {
-            println!("⚠️ Failed to invalidate tags cache: {}", e);
+            tracing::warn!("Failed to invalidate tags cache: {}", e);
         } else {
-            println!("✅ Invalidated caches for: {}", name);
+            tracing::debug!("Invalidated caches for: {}", name);
         }
     }
     
     let mut response_headers = HeaderMap::new();
     response_headers.insert("Location", HeaderValue::from_str(&format!("/v2/{}/manifests/{}", name, digest)).unwrap());
     response_headers.insert("Docker-Content-Digest", HeaderValue::from_str(&digest).unwrap());
+    if let Some(subject_digest) = subject.as_ref().and_then(|(d, _, _)| HeaderValue::from_str(d).ok()) {
+        // Tells OCI 1.1 clients the referrers API indexed the subject
+        response_headers.insert("OCI-Subject", subject_digest);
+    }
     
-    println!("🎉 Manifest successfully stored in database!");
+    tracing::debug!("Manifest successfully stored in database!");
+    crate::activity::record(state, name, reference, crate::activity::Activity::Push);
+    state.log_stream.publish(
+        LogEvent::audit("manifest.push", user_id, Some(name.to_string())).with_detail(format!("{} -> {}", reference, digest)),
+    );
     (StatusCode::CREATED, response_headers, Json(serde_json::json!({}))).into_response()
 }
 
+/// Database records of a pushed manifest
+struct ManifestRecord<'a> {
+    repository_id: i64,
+    /// Tag or digest the manifest was pushed to
+    reference: &'a str,
+    digest: &'a str,
+    media_type: &'a str,
+    size: i64,
+    content: &'a [u8],
+    /// Subject digest, artifact type and annotations of a referrer
+    subject: Option<&'a (String, Option<String>, Option<serde_json::Value>)>,
+    /// Hold the manifest if its repository quarantines pushes
+    quarantine: bool,
+    pushed_by: Option<i64>,
+}
+
+/// Write every database record of a push in one transaction: the content when manifests are kept
+/// in the database, the manifest row with its referrer fields and size summary, the quarantine
+/// hold and the tag. Returns whether the manifest was quarantined, or a `TagHeld` error when the
+/// push would move a tag frozen by a legal hold.
+///
+/// A push to a tag locks the tag's row first, so concurrent pushes to one tag commit one after
+/// the other and the tag ends up on the manifest of the last.
+async fn record_manifest(state: &AppState, record: &ManifestRecord<'_>) -> anyhow::Result<bool> {
+    use anyhow::Context;
+    use sqlx::Connection;
+
+    let tagged = !record.reference.starts_with("sha256:");
+    let mut tx = state.db_pool.begin().await.context("Failed to start a transaction")?;
+
+    // A new tag has no row to lock yet; inserting it waits for a concurrent insert instead
+    if tagged {
+        sqlx::query("SELECT 1 FROM tags WHERE repository_id = $1 AND name = $2 FOR UPDATE")
+            .bind(record.repository_id)
+            .bind(record.reference)
+            .fetch_optional(&mut *tx)
+            .await
+            .context("Failed to lock tag")?;
+    }
+
+    if state.config.storage.manifests_in_database {
+        store_manifest_content(&mut *tx, record.digest, record.content)
+            .await
+            .context("Failed to record manifest content")?;
+    }
+
+    let manifest_id = sqlx::query!(
+        "INSERT INTO manifests (repository_id, digest, media_type, size) 
+         VALUES ($1, $2, $3, $4) 
+         ON CONFLICT (repository_id, digest) 
+         DO UPDATE SET media_type = $3, size = $4
+         RETURNING id",
+        record.repository_id, record.digest, record.media_type, record.size
+    )
+    .fetch_one(&mut *tx)
+    .await
+    .context("Failed to record manifest")?
+    .id;
+
+    if let Some((subject_digest, artifact_type, annotations)) = record.subject {
+        sqlx::query(
+            "UPDATE manifests SET subject_digest = $2, artifact_type = $3, annotations = $4::jsonb WHERE id = $1"
+        )
+        .bind(manifest_id)
+        .bind(subject_digest)
+        .bind(artifact_type)
+        .bind(annotations.as_ref().map(|a| a.to_string()))
+        .execute(&mut *tx)
+        .await
+        .with_context(|| format!("Failed to record referrer of {}", subject_digest))?;
+    }
+
+    let quarantined = if record.quarantine {
+        crate::quarantine::hold(&mut *tx, record.repository_id, record.digest)
+            .await
+            .context("Failed to quarantine manifest")?
+    } else {
+        false
+    };
+
+    // Size and platforms shown by the tag listing; the push does not depend on them, so a
+    // failure only rolls back to the savepoint
+    let mut summary = tx.begin().await?;
+    match crate::handlers::tags::record_manifest_summary(&mut *summary, manifest_id, record.content).await {
+        Ok(_) => summary.commit().await?,
+        Err(e) => {
+            tracing::warn!("Failed to record size and platforms of {}: {}", record.digest, e);
+            summary.rollback().await?;
+        }
+    }
+
+    // If reference is a tag (not a digest), create/update tag
+    if tagged {
+        // A legal hold freezes what each tag points at; checked under the tag lock taken above
+        if crate::handlers::legal_holds::is_tag_move_held(&mut *tx, record.repository_id, record.reference, manifest_id)
+            .await
+            .context("Failed to check legal holds")?
+        {
+            return Err(crate::handlers::legal_holds::TagHeld { tag: record.reference.to_string() }.into());
+        }
+
+        let tag_id = sqlx::query_scalar::<_, i64>(
+            "INSERT INTO tags (repository_id, name, manifest_id, pushed_by) 
+             VALUES ($1, $2, $3, $4)
+             ON CONFLICT (repository_id, name)
+             DO UPDATE SET manifest_id = $3, pushed_by = $4, updated_at = CURRENT_TIMESTAMP
+             RETURNING id"
+        )
+        .bind(record.repository_id)
+        .bind(record.reference)
+        .bind(manifest_id)
+        .bind(record.pushed_by)
+        .fetch_one(&mut *tx)
+        .await
+        .context("Failed to record tag")?;
+        tracing::debug!("Tag '{}' stored in database with ID: {}", record.reference, tag_id);
+    }
+
+    tx.commit().await.context("Failed to commit manifest records")?;
+    tracing::debug!("Manifest stored in database with ID: {}", manifest_id);
+    Ok(quarantined)
+}
+
+/// Remove content stored for a push whose database records were rolled back. Garbage collection
+/// deletes it unless another push has referenced it meanwhile; when the queue cannot be written
+/// either, it is deleted at once.
+async fn discard_manifest_blob(state: &AppState, key: &str) {
+    match crate::gc::enqueue_blob(&state.db_pool, key, "manifest_push_failed").await {
+        Ok(()) => tracing::debug!("Queued {} for garbage collection after the failed push", key),
+        Err(e) => {
+            tracing::warn!("Failed to queue {} for garbage collection: {}; deleting it now", key, e);
+            if let Err(e) = state.storage.delete_blob(key).await {
+                tracing::error!("Failed to delete {} after a failed push, leaving it orphaned: {}", key, e);
+            }
+        }
+    }
+}
+
+/// Subject digest, artifact type and annotations of a manifest that refers to another one.
+///
+/// As the OCI distribution spec requires, a manifest without `artifactType` takes the media
+/// type of its config as its artifact type.
+fn referrer_fields(body: &str) -> Option<(String, Option<String>, Option<serde_json::Value>)> {
+    let manifest: serde_json::Value = serde_json::from_str(body).ok()?;
+    let subject = manifest.pointer("/subject/digest")?.as_str()?.to_string();
+    let artifact_type = manifest
+        .get("artifactType")
+        .or_else(|| manifest.pointer("/config/mediaType"))
+        .and_then(|t| t.as_str())
+        .map(str::to_string);
+    let annotations = manifest.get("annotations").filter(|a| a.is_object()).cloned();
+    Some((subject, artifact_type, annotations))
+}
+
 async fn delete_manifest_impl(
-    _state: &AppState,
+    state: &AppState,
     name: &str,
     reference: &str,
+    user_id: Option<i64>,
 ) -> impl IntoResponse {
-    // TODO: Implement actual manifest deletion
-    println!("Deleting manifest for {}/{}", name, reference);
+    // 405 is how the distribution spec says a registry refuses deletes
+    if !state.features.enabled(Feature::AllowDelete) {
+        tracing::debug!("Refusing to delete {}/{}: deletes are disabled", name, reference);
+        return StatusCode::METHOD_NOT_ALLOWED;
+    }
+
+    // Deletes are blocked while the owning organization is under legal hold
+    if let Some((namespace, _)) = name.split_once('/') {
+        match crate::handlers::legal_holds::is_namespace_under_legal_hold(&state.db_pool, namespace).await {
+            Ok(false) => {}
+            Ok(true) => {
+                tracing::warn!("Refusing to delete {}/{}: organization under legal hold", name, reference);
+                return StatusCode::FORBIDDEN;
+            }
+            Err(e) => {
+                tracing::error!("Legal hold check failed: {}", e);
+                return StatusCode::INTERNAL_SERVER_ERROR;
+            }
+        }
+    }
+
+    // Deleting a digest removes the manifest and every tag pointing at it; deleting a tag only
+    // untags. Stored bytes stay until garbage collection finds them unreferenced.
+    tracing::debug!("Deleting manifest for {}/{}", name, reference);
+    let (namespace, repo_name) = match name.split_once('/') {
+        Some((namespace, repo_name)) => (Some(namespace), repo_name),
+        None => (None, name),
+    };
+    let is_digest = reference.starts_with("sha256:");
+    let deleted = sqlx::query(if is_digest {
+        "DELETE FROM manifests m
+         USING repositories r, organizations o
+         WHERE m.repository_id = r.id AND o.id = r.organization_id
+           AND r.name = $2 AND (o.name = $1 OR ($1 IS NULL AND o.id = 1))
+           AND m.digest = $3"
+    } else {
+        "DELETE FROM tags t
+         USING repositories r, organizations o
+         WHERE t.repository_id = r.id AND o.id = r.organization_id
+           AND r.name = $2 AND (o.name = $1 OR ($1 IS NULL AND o.id = 1))
+           AND t.name = $3"
+    })
+    .bind(namespace)
+    .bind(repo_name)
+    .bind(reference)
+    .execute(&state.db_pool)
+    .await;
+    match deleted {
+        Ok(result) if result.rows_affected() == 0 => return StatusCode::NOT_FOUND,
+        Ok(_) => {}
+        Err(e) => {
+            tracing::error!("Error deleting {}/{}: {}", name, reference, e);
+            return StatusCode::INTERNAL_SERVER_ERROR;
+        }
+    }
+
+    if let Some(cache) = &state.cache {
+        let results = [
+            cache.invalidate_manifest(&format!("manifest:{}:{}", name, reference)).await,
+            cache.invalidate_tags(name).await,
+        ];
+        for e in results.into_iter().filter_map(|r| r.err()) {
+            tracing::warn!("Failed to invalidate caches after deleting {}/{}: {}", name, reference, e);
+        }
+    }
+
+    let action = if is_digest { "manifest.delete" } else { "tag.delete" };
+    state.log_stream.publish(LogEvent::audit(action, user_id, Some(name.to_string())).with_detail(reference));
     
     StatusCode::ACCEPTED
 }
 
+/// Error response for a manifest whose row exists but whose bytes cannot be served
+fn manifest_error(status: StatusCode, message: &str) -> (StatusCode, serde_json::Value) {
+    let code = if status == StatusCode::NOT_FOUND { "MANIFEST_UNKNOWN" } else { "UNAVAILABLE" };
+    (
+        status,
+        json!({
+            "errors": [{
+                "code": code,
+                "message": message,
+                "detail": {}
+            }]
+        })
+    )
+}
+
+/// Manifest bytes for `digest` pushed to `name`.
+///
+/// With `STORAGE_MANIFESTS_IN_DATABASE` the `manifest_contents` record is read first; object
+/// storage and the in-process copy are consulted after it, and content found there is copied
+/// into the table. A storage error is only returned when no other source had the content.
+pub(crate) async fn load_manifest_content(
+    state: &AppState,
+    name: &str,
+    digest: &str,
+) -> anyhow::Result<Option<Bytes>> {
+    let in_database = state.config.storage.manifests_in_database;
+    if in_database {
+        let record = sqlx::query_scalar::<_, Vec<u8>>("SELECT content FROM manifest_contents WHERE digest = $1")
+            .bind(digest)
+            .fetch_optional(&state.db_pool)
+            .await?;
+        if let Some(content) = record {
+            return Ok(Some(Bytes::from(content)));
+        }
+    }
+
+    let (found, storage_error) = match get_repository_blob(state, name, digest).await {
+        Ok(content) => (content, None),
+        Err(e) => (None, Some(e)),
+    };
+    let found = match found {
+        Some(content) => Some(content),
+        None => match &state.cache {
+            Some(cache) => cache.get_manifest_content(digest).await,
+            None => None,
+        },
+    };
+
+    match found {
+        Some(content) => {
+            if in_database {
+                if let Err(e) = store_manifest_content(&state.db_pool, digest, &content).await {
+                    tracing::warn!("Failed to record manifest {} in the database: {}", digest, e);
+                }
+            }
+            Ok(Some(content))
+        }
+        None => storage_error.map_or(Ok(None), Err),
+    }
+}
+
+/// Record manifest bytes in `manifest_contents`; content is addressed by digest, so an
+/// existing row already holds the same bytes
+pub(crate) async fn store_manifest_content<'e, E: sqlx::PgExecutor<'e>>(
+    executor: E,
+    digest: &str,
+    content: &[u8],
+) -> Result<(), sqlx::Error> {
+    sqlx::query("INSERT INTO manifest_contents (digest, content) VALUES ($1, $2) ON CONFLICT (digest) DO NOTHING")
+        .bind(digest)
+        .bind(content)
+        .execute(executor)
+        .await
+        .map(|_| ())
+}
+
+/// Fetch content stored for a repository under `{name}/{digest}`, following a
+/// blob mount when the repository shares the content with another repository.
+pub(crate) async fn get_repository_blob(
+    state: &AppState,
+    name: &str,
+    digest: &str,
+) -> anyhow::Result<Option<Bytes>> {
+    let blob_key = format!("{}/{}", name, digest);
+    if let Some(data) = state.storage.get_blob(&blob_key).await? {
+        return Ok(Some(data));
+    }
+
+    match resolve_blob_mount(&state.db_pool, name, digest).await? {
+        Some(source_key) => {
+            tracing::debug!("Following blob mount {} -> {}", blob_key, source_key);
+            state.storage.get_blob(&source_key).await
+        }
+        None => Ok(None),
+    }
+}
+
+/// Storage key a repository's content lives at, following a blob mount; `None` when the
+/// content is in neither place. Lets callers stream the content instead of loading it.
+pub(crate) async fn repository_blob_key(
+    state: &AppState,
+    name: &str,
+    digest: &str,
+) -> anyhow::Result<Option<String>> {
+    let blob_key = format!("{}/{}", name, digest);
+    if state.storage.blob_exists(&blob_key).await? {
+        return Ok(Some(blob_key));
+    }
+
+    match resolve_blob_mount(&state.db_pool, name, digest).await? {
+        Some(source_key) if state.storage.blob_exists(&source_key).await? => Ok(Some(source_key)),
+        _ => Ok(None),
+    }
+}
+
+/// Look up the storage key a mounted blob actually lives at
+pub(crate) async fn resolve_blob_mount(
+    pool: &sqlx::PgPool,
+    name: &str,
+    digest: &str,
+) -> Result<Option<String>, sqlx::Error> {
+    // Simple repository names live under the default organization (id=1)
+    sqlx::query_scalar::<_, String>(
+        "SELECT bm.source_key FROM blob_mounts bm
+         JOIN repositories r ON r.id = bm.repository_id
+         JOIN organizations o ON o.id = r.organization_id
+         WHERE bm.digest = $2
+         AND ((o.name || '/' || r.name) = $1 OR (o.id = 1 AND r.name = $1))
+         LIMIT 1"
+    )
+    .bind(name)
+    .bind(digest)
+    .fetch_optional(pool)
+    .await
+}
+
 async fn get_blob_impl(
     state: &AppState,
     name: &str,
     digest: &str,
-) -> impl IntoResponse {
-    println!("Getting blob for {}/{}", name, digest);
+) -> Response {
+    tracing::debug!("Getting blob for {}/{}", name, digest);
     
     // Try to get blob from S3 storage first  
     // Use simplified path structure
-    let repo_full_name = name; // Use full name like "testorg1/step-test"
-    let blob_key = format!("{}/{}", repo_full_name, digest);
-    match state.storage.get_blob(&blob_key).await {
+    match get_repository_blob(state, name, digest).await {
         Ok(Some(data)) => {
-            println!("Found blob in S3: {} bytes", data.len());
+            tracing::debug!("Found blob in S3: {} bytes", data.len());
             
             // Detect content type and set download headers
             let content_type = detect_content_type(&data, digest);
@@ -2371,14 +2160,17 @@ async fn get_blob_impl(
                 HeaderValue::from_str(&format!("attachment; filename=\"{}\"", filename)).unwrap());
             headers.insert("Cache-Control", HeaderValue::from_static("public, max-age=31536000"));
             
-            return (StatusCode::OK, headers, data.to_vec());
+            if let Some(rate) = crate::bandwidth::download_limit(state, name).await {
+                return (StatusCode::OK, headers, crate::bandwidth::throttled_body(data, rate)).into_response();
+            }
+            return (StatusCode::OK, headers, data).into_response();
         },
         Ok(None) => {
-            println!("Blob not found in S3: {}", digest);
+            tracing::debug!("Blob not found in S3: {}", digest);
             // Fall through to hardcoded blobs
         },
         Err(e) => {
-            println!("Error retrieving blob from S3: {}", e);
+            tracing::debug!("Error retrieving blob from S3: {}", e);
             // Fall through to hardcoded blobs
         }
     }
@@ -2393,7 +2185,7 @@ async fn get_blob_impl(
             headers.insert("Docker-Content-Digest", HeaderValue::from_str(digest).unwrap());
             headers.insert("Content-Length", HeaderValue::from_str(&config_json.len().to_string()).unwrap());
             headers.insert("Content-Disposition", HeaderValue::from_static("attachment; filename=\"alpine-config.json\""));
-            return (StatusCode::OK, headers, config_json.as_bytes().to_vec());
+            return (StatusCode::OK, headers, config_json.as_bytes().to_vec()).into_response();
         },
         
         // Alpine layer blob
@@ -2407,12 +2199,12 @@ async fn get_blob_impl(
             headers.insert("Content-Length", HeaderValue::from_str(&empty_tar_gz.len().to_string()).unwrap());
             headers.insert("Content-Disposition", HeaderValue::from_static("attachment; filename=\"alpine-layer.tar.gz\""));
             
-            return (StatusCode::OK, headers, empty_tar_gz);
+            return (StatusCode::OK, headers, empty_tar_gz).into_response();
         },
         
         _ => {
-            println!("Unknown blob digest: {}", digest);
-            return (StatusCode::NOT_FOUND, HeaderMap::new(), Vec::new());
+            tracing::debug!("Unknown blob digest: {}", digest);
+            return (StatusCode::NOT_FOUND, HeaderMap::new(), Vec::new()).into_response();
         }
     }
 }
@@ -2463,7 +2255,7 @@ async fn head_blob_impl(
     digest: &str,
 ) -> impl IntoResponse {
     // TODO: Implement actual blob existence check
-    println!("Checking blob existence for {}/{}", name, digest);
+    tracing::debug!("Checking blob existence for {}/{}", name, digest);
     
     let mut headers = HeaderMap::new();
     headers.insert("Content-Type", HeaderValue::from_static("application/octet-stream"));
@@ -2476,72 +2268,69 @@ async fn head_blob_impl(
 async fn start_blob_upload_impl(
     state: &AppState,
     name: &str,
-    user_info: Option<UserInfo>,
-) -> impl IntoResponse {
-    println!("Starting blob upload for {}", name);
+    user_id: Option<i64>,
+) -> Response {
+    tracing::debug!("Starting blob upload for {}", name);
     
     // Get repository ID from name
     let repository_id = match crate::database::queries::get_repository_id_by_name(&state.db_pool, name).await {
         Ok(Some(id)) => id,
         Ok(None) => {
-            println!("❌ Repository '{}' not found", name);
+            tracing::warn!("Repository '{}' not found", name);
             return (
                 StatusCode::NOT_FOUND,
                 Json(serde_json::json!({
-                    "error": "Repository not found"
+                    "errors": [{
+                        "code": "NAME_UNKNOWN",
+                        "message": "Repository not found",
+                        "detail": {}
+                    }]
                 }))
             ).into_response();
         }
         Err(e) => {
-            eprintln!("❌ Failed to get repository ID: {}", e);
+            tracing::error!("Failed to get repository ID: {}", e);
             return (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 Json(serde_json::json!({
-                    "error": "Database error"
+                    "errors": [{
+                        "code": "UNKNOWN",
+                        "message": "Database error",
+                        "detail": {}
+                    }]
                 }))
             ).into_response();
         }
     };
     
+    if let Some(response) = check_upload_session_quota(state, repository_id, user_id).await {
+        return response;
+    }
+    if let Some(response) = check_storage_quota(state, repository_id, 0).await {
+        return response;
+    }
+    
     let upload_uuid = uuid::Uuid::new_v4().to_string();
     let location = format!("/v2/{}/blobs/uploads/{}", name, upload_uuid);
     
-    // Log user info and save to database
-    if let Some(ref user) = user_info {
-        println!("🔍 File upload tracking:");
-        println!("  📁 Repository: {}", name);
-        println!("  👤 User ID: {}", user.user_id);
-        println!("  📄 Upload UUID: {}", upload_uuid);
-        println!("  🔗 Location: {}", location);
-        
-        // Save to database
-        if let Err(e) = crate::database::queries::create_blob_upload(
-            &state.db_pool,
-            &upload_uuid,
-            repository_id,
-            Some(&user.user_id.to_string()),
-        ).await {
-            eprintln!("❌ Failed to save blob upload to database: {}", e);
-        } else {
-            println!("✅ Blob upload saved to database successfully");
-        }
-    } else {
-        println!("🔍 Anonymous upload:");
-        println!("  📁 Repository: {}", name);
-        println!("  📄 Upload UUID: {}", upload_uuid);
-        
-        // Save anonymous upload to database
-        if let Err(e) = crate::database::queries::create_blob_upload(
-            &state.db_pool,
-            &upload_uuid,
-            repository_id,
-            None, // No user ID for anonymous uploads
-        ).await {
-            eprintln!("❌ Failed to save anonymous blob upload to database: {}", e);
-        } else {
-            println!("✅ Anonymous blob upload saved to database successfully");
-        }
+    tracing::debug!("Blob upload {} started in {} by {:?}", upload_uuid, name, user_id);
+    
+    // Save to database
+    if let Err(e) = crate::database::queries::create_blob_upload(
+        &state.db_pool,
+        &upload_uuid,
+        repository_id,
+        user_id.map(|id| id.to_string()).as_deref(),
+    ).await {
+        tracing::error!("Failed to save blob upload to database: {}", e);
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({
+                "error": "Failed to create blob upload record"
+            }))
+        ).into_response();
     }
+    tracing::debug!("Blob upload saved to database successfully");
     
     let mut headers = HeaderMap::new();
     headers.insert("Location", HeaderValue::from_str(&location).unwrap());
@@ -2551,341 +2340,572 @@ async fn start_blob_upload_impl(
     
     // Create response body with upload information
     let response_body = BlobUploadResponse {
-        uuid: upload_uuid.clone(),
-        location: location.clone(),
+        uuid: upload_uuid,
+        location,
         range: "0-0".to_string(),
     };
     
     (StatusCode::ACCEPTED, headers, Json(response_body)).into_response()
 }
 
-// Helper function to parse repository name into namespace and repository
-// For simple names like "hello-world", use username as namespace
-// For namespaced names like "myorg/hello-world", use explicit namespace
-async fn parse_repository_name(name: &str, user_id: &str, state: &AppState) -> Result<(String, String), String> {
-    let parts: Vec<&str> = name.split('/').collect();
+async fn get_upload_status_impl(
+    state: &AppState,
+    name: &str,
+    uuid: &str,
+) -> Response {
+    tracing::debug!("Getting upload status for {}/{}", name, uuid);
     
-    match parts.len() {
-        1 => {
-            // Simple name like "hello-world" - use username as namespace
-            let user_id_int: i64 = user_id.parse().map_err(|_| "Invalid user ID".to_string())?;
-            
-            // Fetch username from database
-            match crate::database::queries::get_user_by_id(&state.db_pool, user_id_int).await {
-                Ok(Some(user)) => {
-                    Ok((user.username, parts[0].to_string()))
-                }
-                Ok(None) => {
-                    Err("User not found".to_string())
-                }
-                Err(_) => {
-                    Err("Database error".to_string())
-                }
-            }
-        }
-        2 => {
-            // Namespaced name like "myorg/hello-world"
-            Ok((parts[0].to_string(), parts[1].to_string()))
-        }
-        _ => {
-            Err("Invalid repository name format".to_string())
-        }
-    }
+    let progress = match load_upload(state, name, uuid).await {
+        Ok(progress) => progress,
+        Err(response) => return response,
+    };
+    
+    (StatusCode::NO_CONTENT, upload_headers(name, uuid, progress.uploaded_bytes)).into_response()
 }
 
-// New function to extract user info from headers
-fn extract_user_info_from_headers(headers: &HeaderMap) -> Option<UserInfo> {
-    // Try to get user from Authorization header
-    if let Some(auth_header) = headers.get("authorization") {
-        if let Ok(auth_str) = auth_header.to_str() {
-            if auth_str.starts_with("Bearer ") {
-                let token = &auth_str[7..]; // Remove "Bearer "
-                return parse_jwt_token(token);
-            }
-            if auth_str.starts_with("Basic ") {
-                let token = &auth_str[6..]; // Remove "Basic "
-                return parse_basic_auth(token);
-            }
-        }
-    }
-    
-    // Fallback: check User-Agent for docker client info
-    if let Some(user_agent) = headers.get("user-agent") {
-        if let Ok(ua_str) = user_agent.to_str() {
-            if ua_str.contains("docker/") {
-                return Some(UserInfo {
-                    user_id: "anonymous_docker_user".to_string(),
-                    username: "anonymous".to_string(),
-                    client_info: ua_str.to_string(),
-                });
-            }
-        }
-    }
+async fn upload_blob_chunk_impl(
+    state: &AppState,
+    name: &str,
+    uuid: &str,
+    headers: HeaderMap,
+    body: axum::body::Body,
+) -> Response {
+    tracing::debug!("Uploading blob chunk for {}/{}", name, uuid);
+    tracing::debug!("Content-Range: {:?}", headers.get("content-range"));
     
-    // Default anonymous user
-    Some(UserInfo {
-        user_id: "anonymous".to_string(), 
-        username: "anonymous".to_string(),
-        client_info: "unknown".to_string(),
-    })
-}
-
-#[derive(Debug, Clone)]
-struct UserInfo {
-    user_id: String,
-    username: String, 
-    client_info: String,
-}
-
-fn parse_jwt_token(token: &str) -> Option<UserInfo> {
-    // TODO: Implement proper JWT parsing
-    // For now, simple token parsing
-    if token.len() > 10 {
-        let user_id = format!("user_{}", &token[..8]);
-        Some(UserInfo {
-            user_id: user_id.clone(),
-            username: user_id,
-            client_info: "jwt_auth".to_string(),
-        })
-    } else {
-        None
+    let mut progress = match load_upload(state, name, uuid).await {
+        Ok(progress) => progress,
+        Err(response) => return response,
+    };
+    
+    // A chunk must continue exactly where the stored ones end
+    let range_start = headers
+        .get("content-range")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.split('-').next())
+        .and_then(|start| start.trim().parse::<i64>().ok());
+    if range_start.is_some_and(|start| start != progress.uploaded_bytes) {
+        tracing::warn!("Chunk for {} starts at {:?}, expected {}", uuid, range_start, progress.uploaded_bytes);
+        return (
+            StatusCode::RANGE_NOT_SATISFIABLE,
+            upload_headers(name, uuid, progress.uploaded_bytes),
+        ).into_response();
     }
-}
-
-fn parse_basic_auth(token: &str) -> Option<UserInfo> {
-    // TODO: Implement proper Basic auth parsing
-    use base64::{Engine as _, engine::general_purpose};
-    if let Ok(decoded) = general_purpose::STANDARD.decode(token) {
-        if let Ok(auth_str) = String::from_utf8(decoded) {
-            if let Some((username, _password)) = auth_str.split_once(':') {
-                return Some(UserInfo {
-                    user_id: format!("user_{}", username),
-                    username: username.to_string(),
-                    client_info: "basic_auth".to_string(),
-                });
-            }
-        }
+    
+    if let Some(response) = append_upload_chunk(state, name, uuid, &mut progress, &headers, body).await {
+        return response;
     }
-    None
+    tracing::debug!("Blob chunk stored, {} bytes received", progress.uploaded_bytes);
+    
+    (StatusCode::ACCEPTED, upload_headers(name, uuid, progress.uploaded_bytes)).into_response()
 }
 
-async fn get_upload_status_impl(
-    _state: &AppState,
-    name: &str,
-    uuid: &str,
-) -> impl IntoResponse {
-    // TODO: Implement actual upload status check
-    println!("Getting upload status for {}/{}", name, uuid);
-    
+/// Location, Range and Docker-Upload-UUID of an upload session holding `uploaded_bytes`
+fn upload_headers(name: &str, uuid: &str, uploaded_bytes: i64) -> HeaderMap {
     let location = format!("/v2/{}/blobs/uploads/{}", name, uuid);
+    let range = format!("0-{}", (uploaded_bytes - 1).max(0));
     
     let mut headers = HeaderMap::new();
     headers.insert("Location", HeaderValue::from_str(&location).unwrap());
-    headers.insert("Range", HeaderValue::from_static("0-1023"));
+    headers.insert("Range", HeaderValue::from_str(&range).unwrap());
+    headers.insert("Content-Length", HeaderValue::from_static("0"));
     headers.insert("Docker-Upload-UUID", HeaderValue::from_str(uuid).unwrap());
+    headers
+}
+
+/// Load the upload session `uuid` of repository `name`. A session of another repository is as
+/// unknown as a missing one, so push access to one repository cannot reach another's uploads.
+async fn load_upload(state: &AppState, name: &str, uuid: &str) -> Result<crate::uploads::UploadProgress, Response> {
+    let repository_id = match crate::database::queries::get_repository_id_by_name(&state.db_pool, name).await {
+        Ok(Some(id)) => id,
+        Ok(None) => return Err(upload_unknown(uuid)),
+        Err(e) => {
+            tracing::error!("Failed to get repository ID: {}", e);
+            return Err((StatusCode::INTERNAL_SERVER_ERROR, HeaderMap::new()).into_response());
+        }
+    };
     
-    (StatusCode::NO_CONTENT, headers)
+    match crate::uploads::load_progress(&state.db_pool, repository_id, uuid, state.config.uploads.session_expiry_seconds).await {
+        Ok(Some(progress)) => Ok(progress),
+        Ok(None) => Err(upload_unknown(uuid)),
+        Err(e) => {
+            tracing::error!("Failed to load blob upload {}: {}", uuid, e);
+            Err((StatusCode::INTERNAL_SERVER_ERROR, HeaderMap::new()).into_response())
+        }
+    }
 }
 
-async fn upload_blob_chunk_impl(
+fn upload_unknown(uuid: &str) -> Response {
+    tracing::warn!("Blob upload {} not found", uuid);
+    (
+        StatusCode::NOT_FOUND,
+        Json(serde_json::json!({
+            "errors": [{
+                "code": "BLOB_UPLOAD_UNKNOWN",
+                "message": "blob upload unknown to registry",
+                "detail": {
+                    "uuid": uuid
+                }
+            }]
+        }))
+    ).into_response()
+}
+
+/// Stream a request body to storage as the next chunk of an upload, hashing it on the way, and
+/// record it on the session. An empty body adds nothing. Fails with the response to send when
+/// the chunk breaks a size limit or another request appended a chunk meanwhile.
+async fn append_upload_chunk(
     state: &AppState,
     name: &str,
     uuid: &str,
-    headers: HeaderMap,
-    body: axum::body::Bytes,
-) -> impl IntoResponse {
-    println!("Uploading blob chunk for {}/{}", name, uuid);
-    println!("Content-Range: {:?}", headers.get("content-range"));
-    println!("Chunk size: {}", body.len());
+    progress: &mut crate::uploads::UploadProgress,
+    headers: &HeaderMap,
+    body: axum::body::Body,
+) -> Option<Response> {
+    let uploads = &state.config.uploads;
+    let uploaded = progress.uploaded_bytes as u64;
+    let limit = match uploads.max_blob_bytes {
+        Some(max_blob) => (uploads.max_request_bytes as u64).min(max_blob.saturating_sub(uploaded)),
+        None => uploads.max_request_bytes as u64,
+    };
+    let content_length = headers
+        .get("content-length")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<u64>().ok());
+    if content_length.is_some_and(|length| length > limit) {
+        return Some(chunk_too_large(uploaded, limit));
+    }
     
-    // Store chunk data in temporary storage keyed by upload UUID
-    // Use full repository name for consistent storage organization
-    let repo_full_name = name; // Use full name like "testorg1/folder-test"
-    let temp_key = format!("repositories/{}/uploads/{}", repo_full_name, uuid);
-    let body_len = body.len();
+    let chunk_key = crate::uploads::chunk_key(name, uuid);
+    let sha256 = match crate::uploads::store_chunk(
+        state.storage.as_ref(),
+        &chunk_key,
+        body,
+        content_length,
+        progress.sha256.clone(),
+        limit,
+    ).await {
+        Ok(Some(sha256)) => sha256,
+        Ok(None) => return None,
+        Err(e) => {
+            let _ = state.storage.delete_blob(&chunk_key).await;
+            return Some(match e {
+                crate::uploads::ChunkError::TooLarge { limit } => chunk_too_large(uploaded, limit),
+                crate::uploads::ChunkError::Storage(e) => {
+                    tracing::error!("Failed to store blob chunk: {}", e);
+                    (StatusCode::INTERNAL_SERVER_ERROR, HeaderMap::new()).into_response()
+                }
+            });
+        }
+    };
     
-    match state.storage.put_blob(&temp_key, body).await {
-        Ok(_) => {
-            println!("Blob chunk stored successfully");
-            
-            let location = format!("/v2/{}/blobs/uploads/{}", name, uuid);
-            let range = format!("0-{}", body_len - 1);
-            
-            let mut response_headers = HeaderMap::new();
-            response_headers.insert("Location", HeaderValue::from_str(&location).unwrap());
-            response_headers.insert("Range", HeaderValue::from_str(&range).unwrap());
-            response_headers.insert("Content-Length", HeaderValue::from_static("0"));
-            response_headers.insert("Docker-Upload-UUID", HeaderValue::from_str(uuid).unwrap());
-            
-            (StatusCode::ACCEPTED, response_headers)
-        },
+    match crate::uploads::record_chunk(&state.db_pool, uuid, progress, &chunk_key, &sha256).await {
+        Ok(true) => {
+            progress.uploaded_bytes = sha256.len() as i64;
+            progress.chunk_keys.push(chunk_key);
+            progress.sha256 = sha256;
+            None
+        }
+        Ok(false) => {
+            tracing::warn!("Concurrent chunk for upload {}, discarding this one", uuid);
+            let _ = state.storage.delete_blob(&chunk_key).await;
+            Some((StatusCode::RANGE_NOT_SATISFIABLE, upload_headers(name, uuid, progress.uploaded_bytes)).into_response())
+        }
         Err(e) => {
-            eprintln!("Failed to store blob chunk: {}", e);
-            (StatusCode::INTERNAL_SERVER_ERROR, HeaderMap::new())
+            tracing::error!("Failed to record blob chunk: {}", e);
+            let _ = state.storage.delete_blob(&chunk_key).await;
+            Some((StatusCode::INTERNAL_SERVER_ERROR, HeaderMap::new()).into_response())
         }
     }
 }
 
+fn chunk_too_large(uploaded: u64, limit: u64) -> Response {
+    tracing::warn!("Chunk exceeds the {} bytes left for this upload", limit);
+    (
+        StatusCode::PAYLOAD_TOO_LARGE,
+        Json(serde_json::json!({
+            "errors": [{
+                "code": "SIZE_INVALID",
+                "message": format!("chunk exceeds the {} bytes this upload may still receive", limit),
+                "detail": {
+                    "uploaded": uploaded,
+                    "limit": limit
+                }
+            }]
+        }))
+    ).into_response()
+}
+
 async fn complete_blob_upload_impl(
     state: &AppState,
     name: &str,
     uuid: &str,
     params: HashMap<String, String>,
-    body: axum::body::Bytes,
-) -> impl IntoResponse {
-    println!("Completing blob upload for {}/{}", name, uuid);
+    headers: HeaderMap,
+    body: axum::body::Body,
+) -> Response {
+    tracing::debug!("Completing blob upload for {}/{}", name, uuid);
     
-    let digest = params.get("digest").unwrap_or(&"sha256:unknown".to_string()).clone();
-    println!("Expected digest: {}", digest);
-    println!("Final chunk size: {}", body.len());
+    let Some(digest) = params.get("digest").cloned() else {
+        return digest_invalid("digest parameter is required", None);
+    };
+    tracing::debug!("Expected digest: {}", digest);
+    
+    let mut progress = match load_upload(state, name, uuid).await {
+        Ok(progress) => progress,
+        Err(response) => return response,
+    };
+    
+    // The final chunk, if any, is hashed like the others, so the digest is known without
+    // reading the blob back
+    if let Some(response) = append_upload_chunk(state, name, uuid, &mut progress, &headers, body).await {
+        return response;
+    }
+    let computed = progress.sha256.digest();
+    let blob_size = progress.uploaded_bytes;
+    
+    let rejection = if computed != digest {
+        tracing::warn!("Upload {} hashes to {}, not {}", uuid, computed, digest);
+        Some(digest_invalid("provided digest did not match uploaded content", Some(&computed)))
+    } else if let Some(response) = check_blob_size(state, blob_size as u64) {
+        Some(response)
+    } else {
+        check_completed_blob_quota(state, name, blob_size).await
+    };
+    if let Some(response) = rejection {
+        crate::uploads::delete_chunks(state.storage.as_ref(), &progress.chunk_keys).await;
+        let _ = crate::database::queries::update_blob_upload_completed(&state.db_pool, progress.repository_id, uuid).await;
+        return response;
+    }
     
     // Final blob key in S3 - simplified structure
-    let repo_full_name = name; // Use full name like "testorg1/step-test" 
-    let blob_key = format!("{}/{}", repo_full_name, digest);
+    let blob_key = format!("{}/{}", name, digest);
+    let assembled = match state.storage.blob_exists(&blob_key).await {
+        // Same content pushed before; the chunks only need cleaning up
+        Ok(true) => {
+            crate::uploads::delete_chunks(state.storage.as_ref(), &progress.chunk_keys).await;
+            Ok(())
+        }
+        // Joined inside the storage backend, which removes the chunks
+        _ => state.storage.compose_blob(&blob_key, &progress.chunk_keys).await,
+    };
+    if let Err(e) = assembled {
+        tracing::error!("Failed to store final blob: {}", e);
+        tracing::warn!("Blob upload failed for UUID: {}", uuid);
+        return (StatusCode::INTERNAL_SERVER_ERROR, HeaderMap::new()).into_response();
+    }
+    tracing::debug!("Blob stored successfully in S3 with key: {}", blob_key);
     
-    // If there's a final chunk, append it to the existing data
-    if !body.is_empty() {
-        let temp_key = format!("repositories/{}/uploads/{}", repo_full_name, uuid);
-        
-        // Get existing data from temp storage
-        let existing_data = match state.storage.get_blob(&temp_key).await {
-            Ok(Some(data)) => data,
-            Ok(None) | Err(_) => body.clone(), // If no existing data, use just this chunk
-        };
-        
-        // Combine existing data with final chunk
-        let mut final_data = existing_data.to_vec();
-        final_data.extend_from_slice(&body);
-        let final_size = final_data.len() as i64;
-        
-        // Store final blob in S3 with digest as key
-        match state.storage.put_blob(&blob_key, axum::body::Bytes::from(final_data)).await {
-            Ok(_) => {
-                println!("Blob stored successfully in S3 with key: {}", blob_key);
-                
-                // Clean up temporary upload
-                let _ = state.storage.delete_blob(&temp_key).await;
-                
-                // Lưu blob metadata vào bảng manifests
-                if let Ok(Some(repository_id)) = crate::database::queries::get_repository_id_by_name(&state.db_pool, name).await {
-                    let media_type = "application/vnd.docker.image.rootfs.diff.tar.gzip".to_string(); // Layer blob
-                    if let Err(e) = sqlx::query!(
-                        "INSERT INTO manifests (repository_id, digest, media_type, size) 
-                         VALUES ($1, $2, $3, $4) 
-                         ON CONFLICT (repository_id, digest) DO NOTHING",
-                        repository_id, digest, media_type, final_size
-                    )
-                    .execute(&state.db_pool)
-                    .await {
-                        println!("⚠️ Failed to store blob metadata: {}", e);
-                    } else {
-                        println!("✅ Blob metadata stored: {}", digest);
-                    }
+    // Lưu blob metadata vào bảng manifests
+    let media_type = "application/vnd.docker.image.rootfs.diff.tar.gzip".to_string(); // Layer blob
+    if let Err(e) = sqlx::query!(
+        "INSERT INTO manifests (repository_id, digest, media_type, size) 
+         VALUES ($1, $2, $3, $4) 
+         ON CONFLICT (repository_id, digest) DO NOTHING",
+        progress.repository_id, digest, media_type, blob_size
+    )
+    .execute(&state.db_pool)
+    .await {
+        tracing::warn!("Failed to store blob metadata: {}", e);
+    } else {
+        tracing::debug!("Blob metadata stored: {}", digest);
+    }
+    
+    // Update blob upload status in database
+    if let Err(e) = crate::database::queries::update_blob_upload_completed(
+        &state.db_pool,
+        progress.repository_id,
+        uuid,
+    ).await {
+        tracing::error!("Failed to update blob upload completion in database: {}", e);
+    } else {
+        tracing::debug!("Blob upload completion updated in database");
+    }
+    
+    let location = format!("/v2/{}/blobs/{}", name, digest);
+    let mut headers = HeaderMap::new();
+    headers.insert("Location", HeaderValue::from_str(&location).unwrap());
+    headers.insert("Docker-Content-Digest", HeaderValue::from_str(&digest).unwrap());
+    headers.insert("Content-Length", HeaderValue::from_static("0"));
+    
+    (StatusCode::CREATED, headers).into_response()
+}
+
+fn digest_invalid(message: &str, computed: Option<&str>) -> Response {
+    (
+        StatusCode::BAD_REQUEST,
+        Json(serde_json::json!({
+            "errors": [{
+                "code": "DIGEST_INVALID",
+                "message": message,
+                "detail": {
+                    "computed": computed
                 }
-                
-                // Update blob upload status in database
-                if let Err(e) = crate::database::queries::update_blob_upload_completed(
-                    &state.db_pool,
-                    uuid,
-                ).await {
-                    eprintln!("❌ Failed to update blob upload completion in database: {}", e);
-                } else {
-                    println!("✅ Blob upload completion updated in database");
+            }]
+        }))
+    ).into_response()
+}
+
+/// Reject implicitly creating an organization or repository whose name breaks the naming rules
+fn invalid_name(e: crate::naming::NameError) -> Response {
+    tracing::warn!("Refusing to create repository: {}", e);
+    (
+        StatusCode::BAD_REQUEST,
+        Json(serde_json::json!({
+            "errors": [{
+                "code": "NAME_INVALID",
+                "message": e.to_string(),
+                "detail": {}
+            }]
+        }))
+    ).into_response()
+}
+
+/// Reject a manifest a push validator denies; see `crate::push_hooks`
+#[allow(clippy::too_many_arguments)]
+async fn check_push_validators(
+    state: &AppState,
+    org_name: Option<&str>,
+    name: &str,
+    reference: &str,
+    digest: &str,
+    media_type: &str,
+    body: &str,
+    user_id: Option<i64>,
+) -> Option<Response> {
+    let organization_id = match org_name {
+        Some(org) => sqlx::query_scalar::<_, i64>("SELECT id FROM organizations WHERE name = $1")
+            .bind(org)
+            .fetch_optional(&state.db_pool)
+            .await,
+        None => Ok(Some(1)),
+    };
+    let push = organization_id.map(|organization_id| crate::push_hooks::ManifestPush {
+        organization_id,
+        repository: name.to_string(),
+        reference: reference.to_string(),
+        digest: digest.to_string(),
+        media_type: media_type.to_string(),
+        manifest: serde_json::from_str(body).unwrap_or_default(),
+        user_id,
+    });
+    let result = match push {
+        Ok(push) => state.push_validators.check(state, &push).await,
+        Err(e) => Err(e.into()),
+    };
+
+    match result {
+        Ok(None) => None,
+        Ok(Some(rejection)) => {
+            tracing::warn!("Manifest {} for {}:{} denied by {}: {}", digest, name, reference, rejection.validator, rejection.reason);
+            state.log_stream.publish(
+                LogEvent::audit("manifest.push.denied", user_id, Some(name.to_string()))
+                    .with_detail(format!("{} {}: {}", reference, rejection.validator, rejection.reason)),
+            );
+            Some((
+                StatusCode::FORBIDDEN,
+                Json(serde_json::json!({
+                    "errors": [{
+                        "code": "DENIED",
+                        "message": rejection.reason,
+                        "detail": {
+                            "validator": rejection.validator
+                        }
+                    }]
+                }))
+            ).into_response())
+        }
+        Err(e) => {
+            tracing::error!("Failed to validate manifest push: {}", e);
+            Some((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({
+                    "errors": [{
+                        "code": "UNKNOWN",
+                        "message": "Manifest validation failed",
+                        "detail": {}
+                    }]
+                }))
+            ).into_response())
+        }
+    }
+}
+
+/// Reject a blob larger than `UPLOAD_MAX_BLOB_BYTES`
+fn check_blob_size(state: &AppState, size: u64) -> Option<Response> {
+    let limit = state.config.uploads.max_blob_bytes?;
+    if size <= limit {
+        return None;
+    }
+
+    tracing::warn!("Blob of {} bytes exceeds the {} byte limit", size, limit);
+    Some((
+        StatusCode::PAYLOAD_TOO_LARGE,
+        Json(serde_json::json!({
+            "errors": [{
+                "code": "SIZE_INVALID",
+                "message": format!("blob exceeds the {} byte limit", limit),
+                "detail": {
+                    "size": size,
+                    "limit": limit
                 }
-                
-                let location = format!("/v2/{}/blobs/{}", name, digest);
-                let mut headers = HeaderMap::new();
-                headers.insert("Location", HeaderValue::from_str(&location).unwrap());
-                headers.insert("Docker-Content-Digest", HeaderValue::from_str(&digest).unwrap());
-                headers.insert("Content-Length", HeaderValue::from_static("0"));
-                
-                (StatusCode::CREATED, headers)
-            },
-            Err(e) => {
-                eprintln!("Failed to store final blob: {}", e);
-                // Update database with failed status - just log error for now
-                eprintln!("⚠️  Blob upload failed for UUID: {}", uuid);
-                (StatusCode::INTERNAL_SERVER_ERROR, HeaderMap::new())
-            }
+            }]
+        }))
+    ).into_response())
+}
+
+/// Reject a new upload session when the user or the repository already holds the configured
+/// number of unfinished ones, so a client that never completes its uploads cannot fill temp storage
+async fn check_upload_session_quota(
+    state: &AppState,
+    repository_id: i64,
+    user_id: Option<i64>,
+) -> Option<Response> {
+    let settings = &state.config.uploads;
+    let (user_open, repository_open) = match crate::database::queries::count_open_blob_uploads(
+        &state.db_pool,
+        repository_id,
+        user_id,
+        settings.session_expiry_seconds,
+    ).await {
+        Ok(counts) => counts,
+        Err(e) => {
+            tracing::error!("Failed to count open upload sessions: {}", e);
+            return Some((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({
+                    "errors": [{
+                        "code": "UNKNOWN",
+                        "message": "Database error",
+                        "detail": {}
+                    }]
+                }))
+            ).into_response());
         }
+    };
+    
+    let (scope, open, limit) = if user_open >= settings.max_open_sessions_per_user {
+        ("you have", user_open, settings.max_open_sessions_per_user)
+    } else if repository_open >= settings.max_open_sessions_per_repository {
+        ("this repository has", repository_open, settings.max_open_sessions_per_repository)
     } else {
-        // No final chunk, just move temp data to final location
-        let temp_key = format!("repositories/{}/uploads/{}", repo_full_name, uuid);
-        
-        match state.storage.get_blob(&temp_key).await {
-            Ok(Some(data)) => {
-                let blob_size = data.len() as i64;
-                match state.storage.put_blob(&blob_key, data).await {
-                    Ok(_) => {
-                        println!("Blob stored successfully in S3 with key: {}", blob_key);
-                        
-                        // Clean up temporary upload
-                        let _ = state.storage.delete_blob(&temp_key).await;
-                        
-                        // Lưu blob metadata vào bảng manifests
-                        if let Ok(Some(repository_id)) = crate::database::queries::get_repository_id_by_name(&state.db_pool, name).await {
-                            let media_type = "application/vnd.docker.image.rootfs.diff.tar.gzip".to_string(); // Layer blob
-                            if let Err(e) = sqlx::query!(
-                                "INSERT INTO manifests (repository_id, digest, media_type, size) 
-                                 VALUES ($1, $2, $3, $4) 
-                                 ON CONFLICT (repository_id, digest) DO NOTHING",
-                                repository_id, digest, media_type, blob_size
-                            )
-                            .execute(&state.db_pool)
-                            .await {
-                                println!("⚠️ Failed to store blob metadata: {}", e);
-                            } else {
-                                println!("✅ Blob metadata stored: {}", digest);
-                            }
-                        }
-                        
-                        // Update blob upload status in database
-                        if let Err(e) = crate::database::queries::update_blob_upload_completed(
-                            &state.db_pool,
-                            uuid,
-                        ).await {
-                            eprintln!("❌ Failed to update blob upload completion in database: {}", e);
-                        } else {
-                            println!("✅ Blob upload completion updated in database");
-                        }
-                        
-                        let location = format!("/v2/{}/blobs/{}", name, digest);
-                        let mut headers = HeaderMap::new();
-                        headers.insert("Location", HeaderValue::from_str(&location).unwrap());
-                        headers.insert("Docker-Content-Digest", HeaderValue::from_str(&digest).unwrap());
-                        headers.insert("Content-Length", HeaderValue::from_static("0"));
-                        
-                        (StatusCode::CREATED, headers)
-                    },
-                    Err(e) => {
-                        eprintln!("Failed to store final blob: {}", e);
-                        // Update database with failed status - just log error for now
-                        eprintln!("⚠️  Blob upload failed for UUID: {}", uuid);
-                        (StatusCode::INTERNAL_SERVER_ERROR, HeaderMap::new())
-                    }
+        return None;
+    };
+    
+    tracing::warn!("Upload session quota reached for repository {} (user {:?}): {} open", repository_id, user_id, open);
+    Some((
+        StatusCode::TOO_MANY_REQUESTS,
+        Json(serde_json::json!({
+            "errors": [{
+                "code": "TOOMANYREQUESTS",
+                "message": format!(
+                    "Too many unfinished upload sessions: {} {} open (limit {}); finish or cancel existing uploads first",
+                    scope, open, limit
+                ),
+                "detail": {
+                    "open_sessions": open,
+                    "limit": limit
                 }
-            },
-            Ok(None) => {
-                eprintln!("No temp blob data found for upload: {}", uuid);
-                (StatusCode::INTERNAL_SERVER_ERROR, HeaderMap::new())
-            },
-            Err(e) => {
-                eprintln!("Failed to retrieve temp blob data: {}", e);
-                (StatusCode::INTERNAL_SERVER_ERROR, HeaderMap::new())
-            }
+            }]
+        }))
+    ).into_response())
+}
+
+/// Reject a push into an organization whose stored manifests and layers already reach its
+/// storage quota or its tier's storage limit, or would exceed it with `incoming` more bytes
+async fn check_storage_quota(state: &AppState, repository_id: i64, incoming: i64) -> Option<Response> {
+    let exceeded = match storage_quota_exceeded(&state.db_pool, repository_id, incoming).await {
+        Ok(exceeded) => exceeded?,
+        Err(e) => {
+            tracing::error!("Failed to check storage quota: {}", e);
+            return Some((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({
+                    "errors": [{
+                        "code": "UNKNOWN",
+                        "message": "Database error",
+                        "detail": {}
+                    }]
+                }))
+            ).into_response());
+        }
+    };
+
+    tracing::warn!(
+        "Storage quota reached for repository {}: {} of {} bytes used",
+        repository_id, exceeded.current, exceeded.allowed
+    );
+    Some(exceeded.registry_error())
+}
+
+async fn storage_quota_exceeded(
+    pool: &sqlx::PgPool,
+    repository_id: i64,
+    incoming: i64,
+) -> Result<Option<crate::quota::QuotaExceeded>, sqlx::Error> {
+    let org_id = sqlx::query_scalar::<_, i64>("SELECT organization_id FROM repositories WHERE id = $1")
+        .bind(repository_id)
+        .fetch_one(pool)
+        .await?;
+    crate::quota::check_storage(pool, org_id, incoming).await
+}
+
+/// Reject an automatically created repository when its organization is at its tier's
+/// repository limit
+async fn check_repository_quota(state: &AppState, org_id: i64) -> Option<Response> {
+    match crate::quota::check_repositories(&state.db_pool, org_id).await {
+        Ok(None) => None,
+        Ok(Some(exceeded)) => {
+            tracing::warn!("Repository limit reached for organization {}: {}", org_id, exceeded);
+            Some(exceeded.registry_error())
+        }
+        Err(e) => {
+            tracing::error!("Failed to check repository quota: {}", e);
+            Some((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({
+                    "errors": [{
+                        "code": "UNKNOWN",
+                        "message": "Database error",
+                        "detail": {}
+                    }]
+                }))
+            ).into_response())
         }
     }
 }
 
+/// Reject completing an upload whose blob would take the repository's organization over its
+/// storage limit
+async fn check_completed_blob_quota(state: &AppState, name: &str, size: i64) -> Option<Response> {
+    match crate::database::queries::get_repository_id_by_name(&state.db_pool, name).await {
+        Ok(Some(repository_id)) => check_storage_quota(state, repository_id, size).await,
+        _ => None,
+    }
+}
+
 async fn cancel_blob_upload_impl(
-    _state: &AppState,
+    state: &AppState,
     name: &str,
     uuid: &str,
-) -> impl IntoResponse {
-    // TODO: Implement actual upload cancellation
-    println!("Cancelling blob upload for {}/{}", name, uuid);
+) -> Response {
+    tracing::debug!("Cancelling blob upload for {}/{}", name, uuid);
+    
+    // Drop any chunks received so far and release the session's quota slot. Expired sessions
+    // are left to the collector, which queues their chunks when it closes them; like closed
+    // sessions and those of other repositories, they are unknown here.
+    let progress = match load_upload(state, name, uuid).await {
+        Ok(progress) => progress,
+        Err(response) => return response,
+    };
+    crate::uploads::delete_chunks(state.storage.as_ref(), &progress.chunk_keys).await;
+    
+    if let Err(e) = crate::database::queries::update_blob_upload_completed(&state.db_pool, progress.repository_id, uuid).await {
+        tracing::error!("Failed to mark blob upload {} as cancelled: {}", uuid, e);
+        return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+    }
     
-    StatusCode::NO_CONTENT
+    StatusCode::NO_CONTENT.into_response()
 }
 
 // List all blobs in repository (custom API)
@@ -2907,6 +2927,7 @@ async fn cancel_blob_upload_impl(
 )]
 pub async fn list_blobs(
     State(state): State<AppState>,
+    _access: RequireRepoPermission<Pull>,
     axum::extract::Path(name): axum::extract::Path<String>,
 ) -> impl IntoResponse {
     list_blobs_impl(&state, &name).await
@@ -2931,6 +2952,7 @@ pub async fn list_blobs(
 )]
 pub async fn list_blobs_namespaced(
     State(state): State<AppState>,
+    _access: RequireRepoPermission<Pull>,
     axum::extract::Path((org, name)): axum::extract::Path<(String, String)>,
 ) -> impl IntoResponse {
     let full_name = format!("{}/{}", org, name);
@@ -2941,7 +2963,7 @@ async fn list_blobs_impl(
     state: &AppState,
     name: &str,
 ) -> impl IntoResponse {
-    println!("🔍 LIST Blobs: {}", name);
+    tracing::debug!("LIST Blobs: {}", name);
     
     // Parse repository name (handle org/repo format)
     let (org_name, repo_name) = if name.contains('/') {
@@ -2965,7 +2987,7 @@ async fn list_blobs_impl(
         {
             Ok(Some(row)) => row.id,
             Ok(None) => {
-                println!("❌ Repository {}/{} not found", org, repo_name);
+                tracing::warn!("Repository {}/{} not found", org, repo_name);
                 return (
                     StatusCode::NOT_FOUND,
                     HeaderMap::new(),
@@ -2973,7 +2995,7 @@ async fn list_blobs_impl(
                 ).into_response();
             },
             Err(e) => {
-                println!("❌ Database error: {}", e);
+                tracing::error!("Database error: {}", e);
                 return (
                     StatusCode::INTERNAL_SERVER_ERROR,
                     HeaderMap::new(),
@@ -2992,7 +3014,7 @@ async fn list_blobs_impl(
         {
             Ok(Some(row)) => row.id,
             Ok(None) => {
-                println!("❌ Repository {} not found", repo_name);
+                tracing::warn!("Repository {} not found", repo_name);
                 return (
                     StatusCode::NOT_FOUND,
                     HeaderMap::new(),
@@ -3000,7 +3022,7 @@ async fn list_blobs_impl(
                 ).into_response();
             },
             Err(e) => {
-                println!("❌ Database error: {}", e);
+                tracing::error!("Database error: {}", e);
                 return (
                     StatusCode::INTERNAL_SERVER_ERROR,
                     HeaderMap::new(),
@@ -3027,7 +3049,7 @@ async fn list_blobs_impl(
                 })
             }).collect();
             
-            println!("✅ Found {} blobs in repository {}", blobs.len(), name);
+            tracing::debug!("Found {} blobs in repository {}", blobs.len(), name);
             
             let mut headers = HeaderMap::new();
             headers.insert("Content-Type", HeaderValue::from_static("application/json"));
@@ -3042,7 +3064,7 @@ async fn list_blobs_impl(
             ).into_response()
         },
         Err(e) => {
-            println!("❌ Database error getting blobs: {}", e);
+            tracing::error!("Database error getting blobs: {}", e);
             (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 HeaderMap::new(),
@@ -3050,4 +3072,130 @@ async fn list_blobs_impl(
             ).into_response()
         }
     }
-}
\ No newline at end of file
+}
+
+/// List referrers - GET /v2/<name>/referrers/<digest>
+/// Returns an OCI image index of the manifests whose `subject` is the given digest
+#[utoipa::path(
+    get,
+    path = "/v2/{name}/referrers/{digest}",
+    tag = "docker-registry-v2",
+    params(
+        ("name" = String, Path, description = "Repository name"),
+        ("digest" = String, Path, description = "Digest of the subject manifest"),
+        ("artifactType" = Option<String>, Query, description = "Only return referrers of this artifact type"),
+    ),
+    responses(
+        (status = 200, description = "OCI image index of referrers"),
+        (status = 400, description = "Invalid digest"),
+        (status = 401, description = "Authentication required"),
+        (status = 403, description = "Insufficient permissions"),
+    )
+)]
+pub async fn get_referrers(
+    State(state): State<AppState>,
+    _access: RequireRepoPermission<Pull>,
+    axum::extract::Path((name, digest)): axum::extract::Path<(String, String)>,
+    Query(query): Query<ReferrersQuery>,
+) -> Response {
+    get_referrers_impl(&state, &name, &digest, query.artifact_type.as_deref()).await
+}
+
+pub async fn get_referrers_namespaced(
+    State(state): State<AppState>,
+    _access: RequireRepoPermission<Pull>,
+    axum::extract::Path((org, name, digest)): axum::extract::Path<(String, String, String)>,
+    Query(query): Query<ReferrersQuery>,
+) -> Response {
+    let full_name = format!("{}/{}", org, name);
+    get_referrers_impl(&state, &full_name, &digest, query.artifact_type.as_deref()).await
+}
+
+async fn get_referrers_impl(
+    state: &AppState,
+    name: &str,
+    digest: &str,
+    artifact_type: Option<&str>,
+) -> Response {
+    tracing::debug!("GET Referrers: {}@{} (artifactType: {:?})", name, digest, artifact_type);
+
+    if !digest.starts_with("sha256:") {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({
+                "errors": [{
+                    "code": "DIGEST_INVALID",
+                    "message": "provided digest did not match uploaded content",
+                    "detail": {}
+                }]
+            }))
+        ).into_response();
+    }
+
+    // Simple repository names live under the default organization (id=1)
+    let referrers = sqlx::query(
+        "SELECT m.digest, m.media_type, m.size, m.artifact_type, m.annotations::text AS annotations
+         FROM manifests m
+         JOIN repositories r ON m.repository_id = r.id
+         JOIN organizations o ON r.organization_id = o.id
+         WHERE ((o.name || '/' || r.name) = $1 OR (o.id = 1 AND r.name = $1))
+           AND m.subject_digest = $2
+           AND ($3::TEXT IS NULL OR m.artifact_type = $3)
+         ORDER BY m.created_at"
+    )
+    .bind(name)
+    .bind(digest)
+    .bind(artifact_type)
+    .fetch_all(&state.db_pool)
+    .await;
+
+    match referrers {
+        Ok(rows) => {
+            let manifests: Vec<serde_json::Value> = rows.iter().map(|row| {
+                let mut descriptor = serde_json::json!({
+                    "mediaType": row.get::<String, _>("media_type"),
+                    "digest": row.get::<String, _>("digest"),
+                    "size": row.get::<i64, _>("size"),
+                });
+                if let Some(artifact_type) = row.get::<Option<String>, _>("artifact_type") {
+                    descriptor["artifactType"] = serde_json::json!(artifact_type);
+                }
+                if let Some(annotations) = row.get::<Option<String>, _>("annotations")
+                    .and_then(|a| serde_json::from_str::<serde_json::Value>(&a).ok())
+                {
+                    descriptor["annotations"] = annotations;
+                }
+                descriptor
+            }).collect();
+
+            let mut headers = HeaderMap::new();
+            headers.insert("Content-Type", HeaderValue::from_static("application/vnd.oci.image.index.v1+json"));
+            if artifact_type.is_some() {
+                headers.insert("OCI-Filters-Applied", HeaderValue::from_static("artifactType"));
+            }
+
+            (
+                StatusCode::OK,
+                headers,
+                Json(serde_json::json!({
+                    "schemaVersion": 2,
+                    "mediaType": "application/vnd.oci.image.index.v1+json",
+                    "manifests": manifests
+                }))
+            ).into_response()
+        }
+        Err(e) => {
+            tracing::error!("Database error listing referrers: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({
+                    "errors": [{
+                        "code": "UNKNOWN",
+                        "message": "Internal server error",
+                        "detail": {}
+                    }]
+                }))
+            ).into_response()
+        }
+    }
+}
@@ -0,0 +1,356 @@
+// src/handlers/events.rs - Repository and organization event history, and its live stream
+use std::{collections::HashSet, convert::Infallible};
+
+use axum::{
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
+    Json,
+};
+use axum_extra::headers::{authorization::Bearer, Authorization};
+use axum_extra::TypedHeader;
+use futures::{future, stream, Stream, StreamExt};
+use secrecy::ExposeSecret;
+use serde_json::json;
+use sqlx::PgPool;
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::{
+    auth::extract_user_id_dual,
+    cloudevents::CloudEvent,
+    handlers::docker_auth::check_repository_permission,
+    handlers::organizations::get_user_role_in_org,
+    handlers::tag_cleanup::{find_repository, internal_error, repository_not_found},
+    models::api_key::ApiKeyScope,
+    log_stream::{LogEvent, LogEventKind, LogFilter},
+    models::event::{EventPage, EventQuery, EventStreamQuery, RegistryEvent, StreamFormat},
+    AppState,
+};
+
+/// Most events replayed when a stream opens
+const MAX_REPLAY: usize = 100;
+
+/// Which feed a page of events is read from
+enum Feed {
+    Repository(i64),
+    Organization(i64),
+}
+
+/// Events of a repository, newest first
+///
+/// Pushes, first pulls of each digest, tag and manifest deletes, collaborator and team grant
+/// changes, renames and webhook changes. Requires pull access.
+#[utoipa::path(
+    get,
+    path = "/api/v1/repos/{namespace}/{repo_name}/events",
+    params(
+        ("namespace" = String, Path, description = "Organization namespace"),
+        ("repo_name" = String, Path, description = "Repository name"),
+        EventQuery
+    ),
+    responses(
+        (status = 200, description = "One page of events", body = EventPage),
+        (status = 401, description = "Authentication required"),
+        (status = 404, description = "Repository not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "repositories",
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn list_repository_events(
+    Path((namespace, repo_name)): Path<(String, String)>,
+    State(state): State<AppState>,
+    Query(query): Query<EventQuery>,
+    headers: HeaderMap,
+    auth: Option<TypedHeader<Authorization<Bearer>>>,
+) -> Response {
+    let secret = state.config.auth.jwt_secret.expose_secret().as_bytes();
+    let user_id = match extract_user_id_dual(auth, &headers, ApiKeyScope::Read, secret, &state.db_pool, state.cache.as_ref()).await {
+        Ok(id) => id,
+        Err(status) => return (status, Json(json!({"error": "Authentication required"}))).into_response(),
+    };
+    match check_repository_permission(&user_id.to_string(), &namespace, &repo_name, "pull", &state).await {
+        Ok(true) => {}
+        Ok(false) => return repository_not_found(&namespace, &repo_name),
+        Err(e) => return internal_error(e),
+    }
+    let (repository_id, _) = match find_repository(&state, &namespace, &repo_name).await {
+        Ok(Some(ids)) => ids,
+        Ok(None) => return repository_not_found(&namespace, &repo_name),
+        Err(e) => return internal_error(e),
+    };
+
+    match load_events(&state.db_pool, Feed::Repository(repository_id), &query).await {
+        Ok(page) => (StatusCode::OK, Json(json!(page))).into_response(),
+        Err(e) => internal_error(e),
+    }
+}
+
+/// Events of an organization and all of its repositories, newest first
+///
+/// Includes membership, invitation, team, settings and webhook changes besides repository
+/// events, and keeps the events of deleted repositories. Members only.
+#[utoipa::path(
+    get,
+    path = "/api/v1/organizations/{id}/events",
+    tag = "organizations",
+    params(
+        ("id" = i64, Path, description = "Organization ID"),
+        EventQuery
+    ),
+    responses(
+        (status = 200, description = "One page of events", body = EventPage),
+        (status = 401, description = "Authentication required"),
+        (status = 403, description = "Not a member of this organization"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn list_organization_events(
+    Path(id): Path<i64>,
+    State(state): State<AppState>,
+    Query(query): Query<EventQuery>,
+    headers: HeaderMap,
+    auth: Option<TypedHeader<Authorization<Bearer>>>,
+) -> Response {
+    let secret = state.config.auth.jwt_secret.expose_secret().as_bytes();
+    let user_id = match extract_user_id_dual(auth, &headers, ApiKeyScope::Read, secret, &state.db_pool, state.cache.as_ref()).await {
+        Ok(id) => id,
+        Err(status) => return (status, Json(json!({"error": "Authentication required"}))).into_response(),
+    };
+    match get_user_role_in_org(&state.db_pool, id, user_id).await {
+        Ok(Some(_)) => {}
+        Ok(None) => {
+            return (StatusCode::FORBIDDEN, Json(json!({
+                "error": "Not a member of this organization"
+            }))).into_response()
+        }
+        Err(e) => return internal_error(e),
+    }
+
+    match load_events(&state.db_pool, Feed::Organization(id), &query).await {
+        Ok(page) => (StatusCode::OK, Json(json!(page))).into_response(),
+        Err(e) => internal_error(e),
+    }
+}
+
+/// Stream registry events as they happen
+///
+/// Sends each audit event of the repository (`repo`), the organization (`org`), or, with
+/// neither, of every organization the user belongs to, as an SSE message of type `event` whose
+/// data is a JSON event shaped like the event history's, or with `format=cloudevents` that
+/// event wrapped in a CloudEvents 1.0 envelope. A `lagged` message carrying a count
+/// means that many events were skipped because the client fell behind. Each replica streams the
+/// events it handled itself; the event history has all of them.
+#[utoipa::path(
+    get,
+    path = "/api/v1/events/stream",
+    tag = "repositories",
+    params(EventStreamQuery),
+    responses(
+        (status = 200, description = "text/event-stream of registry events", body = RegistryEvent),
+        (status = 401, description = "Authentication required"),
+        (status = 403, description = "Not a member of the organization"),
+        (status = 404, description = "Repository or organization not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn stream_events(
+    State(state): State<AppState>,
+    Query(query): Query<EventStreamQuery>,
+    headers: HeaderMap,
+    auth: Option<TypedHeader<Authorization<Bearer>>>,
+) -> Response {
+    let secret = state.config.auth.jwt_secret.expose_secret().as_bytes();
+    let user_id = match extract_user_id_dual(auth, &headers, ApiKeyScope::Read, secret, &state.db_pool, state.cache.as_ref()).await {
+        Ok(id) => id,
+        Err(status) => return (status, Json(json!({"error": "Authentication required"}))).into_response(),
+    };
+
+    let scope = if let Some(repository) = query.repo.as_deref() {
+        let Some((namespace, repo_name)) = repository.split_once('/') else {
+            return (StatusCode::BAD_REQUEST, Json(json!({
+                "error": "repo must be namespace/repository"
+            }))).into_response();
+        };
+        match check_repository_permission(&user_id.to_string(), namespace, repo_name, "pull", &state).await {
+            Ok(true) => StreamScope::Repository(repository.to_string()),
+            Ok(false) => return repository_not_found(namespace, repo_name),
+            Err(e) => return internal_error(e),
+        }
+    } else {
+        let organizations = sqlx::query_as::<_, (i64, String)>(
+            "SELECT o.id, o.name
+             FROM organizations o
+             JOIN organization_members m ON m.organization_id = o.id
+             WHERE m.user_id = $1 AND ($2::TEXT IS NULL OR o.name = $2)",
+        )
+        .bind(user_id)
+        .bind(query.org.as_deref())
+        .fetch_all(&state.db_pool)
+        .await;
+        match organizations {
+            Ok(organizations) if organizations.is_empty() && query.org.is_some() => {
+                return (StatusCode::FORBIDDEN, Json(json!({
+                    "error": "Not a member of this organization"
+                }))).into_response()
+            }
+            Ok(organizations) => StreamScope::Organizations {
+                ids: organizations.iter().map(|(id, _)| *id).collect(),
+                names: organizations.into_iter().map(|(_, name)| name).collect(),
+            },
+            Err(e) => return internal_error(e),
+        }
+    };
+
+    let filter = LogFilter {
+        kind: Some(LogEventKind::Audit),
+        repository: match &scope {
+            StreamScope::Repository(repository) => Some(repository.clone()),
+            StreamScope::Organizations { .. } => None,
+        },
+        action: query.action.filter(|action| !action.is_empty()),
+        ..Default::default()
+    };
+    let (replayed, receiver) = state.log_stream.subscribe(&filter, query.replay.unwrap_or(0).min(MAX_REPLAY));
+
+    let live = stream::unfold(receiver, |mut receiver| async move {
+        match receiver.recv().await {
+            Ok(event) => Some((Ok(event), receiver)),
+            Err(RecvError::Lagged(skipped)) => Some((Err(skipped), receiver)),
+            Err(RecvError::Closed) => None,
+        }
+    });
+    let pool = state.db_pool.clone();
+    let format = query.format.unwrap_or_default();
+    let events = stream::iter(replayed.into_iter().map(Ok))
+        .chain(live)
+        .filter(move |item| {
+            future::ready(match item {
+                Ok(event) => filter.matches(event) && scope.includes(event),
+                Err(_) => true,
+            })
+        })
+        .then(move |item| {
+            let pool = pool.clone();
+            async move {
+                let event = match item {
+                    Ok(event) => to_sse(&pool, event, format).await,
+                    Err(skipped) => Event::default().event("lagged").data(skipped.to_string()),
+                };
+                Ok::<_, Infallible>(event)
+            }
+        });
+
+    Sse::new(events).keep_alive(KeepAlive::default()).into_response()
+}
+
+/// Which live events a stream may see
+enum StreamScope {
+    /// One repository the user may pull
+    Repository(String),
+    /// Organizations the user belongs to and their repositories
+    Organizations { ids: HashSet<i64>, names: HashSet<String> },
+}
+
+impl StreamScope {
+    fn includes(&self, event: &LogEvent) -> bool {
+        match self {
+            // The log filter already matched the repository
+            StreamScope::Repository(_) => true,
+            StreamScope::Organizations { ids, names } => {
+                let by_repository = event
+                    .repository
+                    .as_deref()
+                    .and_then(|repository| repository.split_once('/'))
+                    .is_some_and(|(namespace, _)| names.contains(namespace));
+                by_repository || event.organization_id.is_some_and(|id| ids.contains(&id))
+            }
+        }
+    }
+}
+
+/// An event in the shape of the event history, with the username looked up
+async fn to_sse(pool: &PgPool, event: LogEvent, format: StreamFormat) -> Event {
+    let username = match (&event.username, event.user_id) {
+        (Some(username), _) => Some(username.clone()),
+        (None, Some(user_id)) => sqlx::query_scalar::<_, String>("SELECT username FROM users WHERE id = $1")
+            .bind(user_id)
+            .fetch_optional(pool)
+            .await
+            .ok()
+            .flatten(),
+        (None, None) => None,
+    };
+    let data = RegistryEvent {
+        id: event.id as i64,
+        action: event.action.clone(),
+        occurred_at: event.timestamp,
+        user_id: event.user_id,
+        username,
+        repository: event.repository.clone(),
+        detail: event.detail,
+    };
+    let data = match format {
+        StreamFormat::Native => serde_json::to_string(&data),
+        // The sequence number restarts with the process, so the envelope gets an ID of its own
+        StreamFormat::Cloudevents => serde_json::to_string(&CloudEvent::new(
+            uuid::Uuid::new_v4().to_string(),
+            &event.action,
+            event.timestamp,
+            event.repository.as_deref(),
+            event.organization_id,
+            &data,
+        )),
+    };
+    Event::default()
+        .event("event")
+        .id(event.id.to_string())
+        .data(data.unwrap_or_default())
+}
+
+async fn load_events(pool: &PgPool, feed: Feed, query: &EventQuery) -> Result<EventPage, sqlx::Error> {
+    let limit = query.limit.unwrap_or(50).clamp(1, 200);
+    let (column, scope_id) = match feed {
+        Feed::Repository(id) => ("repository_id", id),
+        Feed::Organization(id) => ("organization_id", id),
+    };
+    let action = query.action.as_deref().filter(|a| !a.is_empty());
+
+    // One extra row tells whether there is another page
+    let mut events = sqlx::query_as::<_, RegistryEvent>(&format!(
+        "SELECT e.id, e.action, e.occurred_at, e.user_id, u.username, e.repository, e.detail
+         FROM events e
+         LEFT JOIN users u ON u.id = e.user_id
+         WHERE e.{} = $1
+           AND ($2::BIGINT IS NULL OR e.id < $2)
+           AND ($3::TEXT IS NULL OR e.action = $3 OR left(e.action, length($3) + 1) = $3 || '.')
+         ORDER BY e.id DESC
+         LIMIT $4",
+        column
+    ))
+    .bind(scope_id)
+    .bind(query.before)
+    .bind(action)
+    .bind(limit + 1)
+    .fetch_all(pool)
+    .await?;
+
+    let next_before = if events.len() as i64 > limit {
+        events.truncate(limit as usize);
+        events.last().map(|event| event.id)
+    } else {
+        None
+    };
+    Ok(EventPage { events, next_before })
+}
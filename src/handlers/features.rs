@@ -0,0 +1,124 @@
+// src/handlers/features.rs - Feature flags listed and overridden by registry administrators
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Deserialize;
+use serde_json::json;
+use utoipa::ToSchema;
+
+use crate::{
+    features::{Feature, FeatureFlag},
+    handlers::admin::{internal_error, AdminUser},
+    log_stream::LogEvent,
+    AppState,
+};
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct FeatureOverrideRequest {
+    /// Value that replaces the configured one on every replica
+    pub enabled: bool,
+}
+
+/// List the feature flags
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/features",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Every flag with its configured value and override", body = [FeatureFlag]),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Not a registry administrator")
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn list_features(State(state): State<AppState>, _admin: AdminUser) -> Json<Vec<FeatureFlag>> {
+    Json(state.features.list())
+}
+
+/// Override a feature flag
+///
+/// Applies on this replica at once and on the others within `FEATURES_REFRESH_INTERVAL_SECONDS`.
+/// The override is kept across restarts until it is removed.
+#[utoipa::path(
+    put,
+    path = "/api/v1/admin/features/{name}",
+    tag = "admin",
+    params(("name" = Feature, Path, description = "Flag name, e.g. `readonly_mode`")),
+    request_body = FeatureOverrideRequest,
+    responses(
+        (status = 200, description = "Flag overridden", body = FeatureFlag),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Not a registry administrator"),
+        (status = 404, description = "No such flag"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn set_feature(
+    State(state): State<AppState>,
+    admin: AdminUser,
+    Path(name): Path<String>,
+    Json(req): Json<FeatureOverrideRequest>,
+) -> Response {
+    let Some(feature) = Feature::parse(&name) else {
+        return feature_not_found(&name);
+    };
+    match state.features.set_override(&state.db_pool, feature, req.enabled, admin.user_id).await {
+        Ok(flag) => {
+            tracing::info!("Feature {} set to {} by {}", name, req.enabled, admin.username);
+            state.log_stream.publish(
+                LogEvent::audit("feature.override", Some(admin.user_id), None)
+                    .with_detail(format!("{}={}", name, req.enabled)),
+            );
+            (StatusCode::OK, Json(flag)).into_response()
+        }
+        Err(e) => internal_error(e),
+    }
+}
+
+/// Return a feature flag to its configured value
+#[utoipa::path(
+    delete,
+    path = "/api/v1/admin/features/{name}",
+    tag = "admin",
+    params(("name" = Feature, Path, description = "Flag name, e.g. `readonly_mode`")),
+    responses(
+        (status = 200, description = "Override removed", body = FeatureFlag),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Not a registry administrator"),
+        (status = 404, description = "No such flag"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn clear_feature(State(state): State<AppState>, admin: AdminUser, Path(name): Path<String>) -> Response {
+    let Some(feature) = Feature::parse(&name) else {
+        return feature_not_found(&name);
+    };
+    match state.features.clear_override(&state.db_pool, feature).await {
+        Ok(flag) => {
+            tracing::info!("Feature {} override removed by {}", name, admin.username);
+            state.log_stream.publish(
+                LogEvent::audit("feature.reset", Some(admin.user_id), None).with_detail(name),
+            );
+            (StatusCode::OK, Json(flag)).into_response()
+        }
+        Err(e) => internal_error(e),
+    }
+}
+
+fn feature_not_found(name: &str) -> Response {
+    let known: Vec<_> = Feature::ALL.iter().map(|feature| feature.as_str()).collect();
+    (StatusCode::NOT_FOUND, Json(json!({
+        "error": format!("Unknown feature '{}'; expected one of {}", name, known.join(", "))
+    }))).into_response()
+}
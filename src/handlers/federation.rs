@@ -0,0 +1,104 @@
+// src/handlers/federation.rs - Repository index and federated search across peer registries
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Deserialize;
+use serde_json::json;
+use utoipa::IntoParams;
+
+use crate::{
+    federation::{local_index, FederatedSearchResponse, IndexResponse, PeerStatus},
+    handlers::admin::AdminUser,
+    AppState,
+};
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct FederationSearchQuery {
+    /// Case-insensitive substring of `namespace/name`
+    pub q: String,
+    /// Results per registry (default 25, at most 100)
+    pub limit: Option<usize>,
+}
+
+impl FederationSearchQuery {
+    fn limit(&self) -> usize {
+        self.limit.unwrap_or(25).clamp(1, 100)
+    }
+}
+
+/// This registry's index of public repositories, as queried by federation peers
+#[utoipa::path(
+    get,
+    path = "/api/v1/federation/index",
+    tag = "federation",
+    params(FederationSearchQuery),
+    responses(
+        (status = 200, description = "Matching public repositories with their recent tags", body = IndexResponse),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn federation_index(
+    State(state): State<AppState>,
+    Query(query): Query<FederationSearchQuery>,
+) -> Response {
+    match local_index(state.read_pool(), &query.q, query.limit() as i64).await {
+        Ok(entries) => (StatusCode::OK, Json(IndexResponse {
+            instance: state.federation.instance_name().to_string(),
+            entries,
+        })).into_response(),
+        Err(e) => internal_error(e),
+    }
+}
+
+/// Search public repositories on this registry and every federation peer
+///
+/// Each peer is asked in parallel with its own deadline; a peer that fails or times out is
+/// reported in `peers` and skipped for `FEDERATION_FAILURE_COOLDOWN_SECONDS`, and the search
+/// still returns the other registries' results.
+#[utoipa::path(
+    get,
+    path = "/api/v1/federation/search",
+    tag = "federation",
+    params(FederationSearchQuery),
+    responses(
+        (status = 200, description = "Matches from every registry that answered, exact name matches first", body = FederatedSearchResponse),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn federated_search(
+    State(state): State<AppState>,
+    Query(query): Query<FederationSearchQuery>,
+) -> Response {
+    match state.federation.search(state.read_pool(), &query.q, query.limit()).await {
+        Ok(response) => (StatusCode::OK, Json(response)).into_response(),
+        Err(e) => internal_error(e),
+    }
+}
+
+/// Configured federation peers and their health
+#[utoipa::path(
+    get,
+    path = "/api/v1/federation/peers",
+    tag = "federation",
+    responses(
+        (status = 200, description = "Peers with their latest success, failure and cooldown", body = [PeerStatus]),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Not a registry administrator")
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn list_peers(State(state): State<AppState>, _admin: AdminUser) -> Json<Vec<PeerStatus>> {
+    Json(state.federation.peer_statuses())
+}
+
+fn internal_error(e: anyhow::Error) -> Response {
+    tracing::error!("Federated search failed: {:#}", e);
+    (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({
+        "error": e.to_string()
+    }))).into_response()
+}
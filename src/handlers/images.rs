@@ -0,0 +1,257 @@
+// src/handlers/images.rs - Image inspector: manifest, config, layers and build history
+use axum::{
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use axum_extra::headers::{authorization::Bearer, Authorization};
+use axum_extra::TypedHeader;
+use chrono::{DateTime, Utc};
+use secrecy::ExposeSecret;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::{
+    auth::extract_user_id_dual,
+    handlers::docker_auth::check_repository_permission,
+    handlers::docker_registry_v2::{get_repository_blob, load_manifest_content},
+    handlers::tag_cleanup::{find_repository, internal_error, repository_not_found},
+    handlers::tags::summarize_manifest,
+    models::api_key::ApiKeyScope,
+    models::image_detail::{HistoryEntry, ImageDetail, ImageDetailQuery, ImageLayer, RunConfig},
+    models::tag_listing::Platform,
+    AppState,
+};
+
+/// Fields of an OCI or Docker image config the inspector shows
+#[derive(Debug, Default, Deserialize)]
+struct ImageConfigFile {
+    #[serde(default)]
+    created: Option<DateTime<Utc>>,
+    #[serde(default)]
+    author: Option<String>,
+    #[serde(default)]
+    os: Option<String>,
+    #[serde(default)]
+    architecture: Option<String>,
+    #[serde(default)]
+    variant: Option<String>,
+    #[serde(default)]
+    config: Option<RunConfig>,
+    #[serde(default)]
+    history: Vec<HistoryEntry>,
+}
+
+/// Inspect an image: layers, entrypoint, command, environment, labels and build history
+///
+/// The reference is a digest or a tag. For an index the image of the requested platform is
+/// shown, with the index's platforms listed. Requires pull access.
+#[utoipa::path(
+    get,
+    path = "/api/v1/repos/{namespace}/{repo_name}/images/{reference}",
+    params(
+        ("namespace" = String, Path, description = "Organization namespace"),
+        ("repo_name" = String, Path, description = "Repository name"),
+        ("reference" = String, Path, description = "Manifest digest or tag"),
+        ImageDetailQuery
+    ),
+    responses(
+        (status = 200, description = "Image details", body = ImageDetail),
+        (status = 401, description = "Authentication required"),
+        (status = 404, description = "Repository, image or platform not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "repositories",
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn get_image_detail(
+    Path((namespace, repo_name, reference)): Path<(String, String, String)>,
+    State(state): State<AppState>,
+    Query(query): Query<ImageDetailQuery>,
+    headers: HeaderMap,
+    auth: Option<TypedHeader<Authorization<Bearer>>>,
+) -> Response {
+    let secret = state.config.auth.jwt_secret.expose_secret().as_bytes();
+    let user_id = match extract_user_id_dual(auth, &headers, ApiKeyScope::Read, secret, &state.db_pool, state.cache.as_ref()).await {
+        Ok(id) => id,
+        Err(status) => return (status, Json(json!({"error": "Authentication required"}))).into_response(),
+    };
+    match check_repository_permission(&user_id.to_string(), &namespace, &repo_name, "pull", &state).await {
+        Ok(true) => {}
+        Ok(false) => return repository_not_found(&namespace, &repo_name),
+        Err(e) => return internal_error(e),
+    }
+    let (repository_id, _) = match find_repository(&state, &namespace, &repo_name).await {
+        Ok(Some(ids)) => ids,
+        Ok(None) => return repository_not_found(&namespace, &repo_name),
+        Err(e) => return internal_error(e),
+    };
+
+    let name = format!("{}/{}", namespace, repo_name);
+    match load_image(&state, repository_id, &name, &reference, query.platform.as_deref()).await {
+        Ok(Some(detail)) => (StatusCode::OK, Json(json!(detail))).into_response(),
+        Ok(None) => (StatusCode::NOT_FOUND, Json(json!({
+            "error": format!("Image '{}' not found in {}", reference, name)
+        }))).into_response(),
+        Err(e) => internal_error(e),
+    }
+}
+
+async fn load_image(
+    state: &AppState,
+    repository_id: i64,
+    name: &str,
+    reference: &str,
+    platform: Option<&str>,
+) -> anyhow::Result<Option<ImageDetail>> {
+    let manifest = sqlx::query_as::<_, (String, String)>(
+        "SELECT m.digest, m.media_type
+         FROM manifests m
+         WHERE m.repository_id = $1
+           AND (m.digest = $2 OR m.id = (SELECT t.manifest_id FROM tags t WHERE t.repository_id = $1 AND t.name = $2))",
+    )
+    .bind(repository_id)
+    .bind(reference)
+    .fetch_optional(&state.db_pool)
+    .await?;
+    let Some((mut digest, mut media_type)) = manifest else {
+        return Ok(None);
+    };
+    let Some(mut content) = load_manifest_content(state, name, &digest).await? else {
+        return Ok(None);
+    };
+
+    let mut index_digest = None;
+    let mut platforms = Vec::new();
+    let mut manifest: Value = serde_json::from_slice(&content)?;
+    if let Some(entries) = manifest.get("manifests").and_then(Value::as_array) {
+        platforms = summarize_manifest(&content).1;
+        let Some(chosen) = choose_platform(&platforms, platform) else {
+            return Ok(None);
+        };
+        let chosen_digest = chosen.digest.clone();
+        let chosen_media_type = entries
+            .iter()
+            .find(|entry| entry.get("digest").and_then(Value::as_str) == Some(chosen_digest.as_str()))
+            .and_then(|entry| entry.get("mediaType").and_then(Value::as_str))
+            .unwrap_or("application/vnd.oci.image.manifest.v1+json")
+            .to_string();
+        index_digest = Some(std::mem::replace(&mut digest, chosen_digest));
+        media_type = chosen_media_type;
+        content = match load_manifest_content(state, name, &digest).await? {
+            Some(content) => content,
+            None => return Ok(None),
+        };
+        manifest = serde_json::from_slice(&content)?;
+    }
+
+    let layers = descriptors(manifest.get("layers"));
+    let config_descriptor = manifest.get("config");
+    let config_size = config_descriptor.and_then(|c| c.get("size")).and_then(Value::as_i64).unwrap_or(0);
+    let config_file = match config_descriptor.and_then(|c| c.get("digest")).and_then(Value::as_str) {
+        Some(config_digest) => match get_repository_blob(state, name, config_digest).await? {
+            // Artifacts carry configs of their own format, which have none of these fields
+            Some(config) => serde_json::from_slice::<ImageConfigFile>(&config).unwrap_or_default(),
+            None => {
+                tracing::warn!("Config {} of {}@{} is missing", config_digest, name, digest);
+                ImageConfigFile::default()
+            }
+        },
+        None => ImageConfigFile::default(),
+    };
+
+    Ok(Some(ImageDetail {
+        repository: name.to_string(),
+        digest,
+        media_type,
+        index_digest,
+        platforms,
+        os: config_file.os,
+        architecture: config_file.architecture,
+        variant: config_file.variant,
+        created: config_file.created,
+        author: config_file.author,
+        config: config_file.config.unwrap_or_default(),
+        total_compressed_size: config_size + layers.iter().map(|l| l.size).sum::<i64>(),
+        layers,
+        history: config_file.history,
+    }))
+}
+
+fn descriptors(list: Option<&Value>) -> Vec<ImageLayer> {
+    list.and_then(Value::as_array)
+        .map(|list| {
+            list.iter()
+                .filter_map(|d| {
+                    Some(ImageLayer {
+                        digest: d.get("digest")?.as_str()?.to_string(),
+                        media_type: d.get("mediaType").and_then(Value::as_str).unwrap_or_default().to_string(),
+                        size: d.get("size").and_then(Value::as_i64).unwrap_or(0),
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// The platform named `os/architecture[/variant]`, or linux/amd64 and then the first platform
+fn choose_platform<'a>(platforms: &'a [Platform], requested: Option<&str>) -> Option<&'a Platform> {
+    let matches = |p: &Platform, wanted: &str| {
+        let mut parts = wanted.split('/');
+        parts.next() == Some(p.os.as_str())
+            && parts.next() == Some(p.architecture.as_str())
+            && parts.next().map_or(true, |variant| p.variant.as_deref() == Some(variant))
+    };
+    match requested {
+        Some(wanted) => platforms.iter().find(|p| matches(p, wanted)),
+        None => platforms.iter().find(|p| matches(p, "linux/amd64")).or_else(|| platforms.first()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn platform(os: &str, architecture: &str, variant: Option<&str>, digest: &str) -> Platform {
+        Platform {
+            os: os.to_string(),
+            architecture: architecture.to_string(),
+            variant: variant.map(str::to_string),
+            digest: digest.to_string(),
+        }
+    }
+
+    #[test]
+    fn chooses_the_requested_platform() {
+        let platforms = vec![
+            platform("linux", "arm", Some("v7"), "sha256:a"),
+            platform("linux", "amd64", None, "sha256:b"),
+        ];
+        assert_eq!(choose_platform(&platforms, None).map(|p| p.digest.as_str()), Some("sha256:b"));
+        assert_eq!(choose_platform(&platforms, Some("linux/arm/v7")).map(|p| p.digest.as_str()), Some("sha256:a"));
+        assert_eq!(choose_platform(&platforms, Some("linux/arm")).map(|p| p.digest.as_str()), Some("sha256:a"));
+        assert!(choose_platform(&platforms, Some("linux/arm/v6")).is_none());
+        assert!(choose_platform(&platforms, Some("windows/amd64")).is_none());
+    }
+
+    #[test]
+    fn reads_docker_image_configs() {
+        let config: ImageConfigFile = serde_json::from_str(
+            r#"{"architecture":"amd64","os":"linux","created":"2025-01-27T00:00:00Z",
+                "config":{"Entrypoint":["/app"],"Env":["PATH=/bin"],"Labels":{"org.opencontainers.image.source":"https://example.com"}},
+                "history":[{"created_by":"COPY app /app"},{"created_by":"ENV PATH=/bin","empty_layer":true}]}"#,
+        )
+        .unwrap();
+        let run = config.config.unwrap();
+        assert_eq!(run.entrypoint, Some(vec!["/app".to_string()]));
+        assert_eq!(run.cmd, None);
+        assert_eq!(run.labels.unwrap().len(), 1);
+        assert!(config.history[1].empty_layer);
+
+        let layers = descriptors(Some(&json!([{"digest": "sha256:l", "mediaType": "application/vnd.oci.image.layer.v1.tar+gzip", "size": 42}])));
+        assert_eq!(layers[0].size, 42);
+    }
+}
@@ -0,0 +1,257 @@
+// src/handlers/insights.rs - Repository overview combining activity, storage, cleanup, signatures, policy and scans
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use axum_extra::headers::{authorization::Bearer, Authorization};
+use axum_extra::TypedHeader;
+use secrecy::ExposeSecret;
+use serde_json::json;
+
+use crate::{
+    auth::extract_user_id_dual,
+    handlers::docker_auth::check_repository_permission,
+    handlers::organizations::{load_org_settings, org_storage_used},
+    handlers::tag_cleanup::{find_repository, internal_error, repository_not_found},
+    models::api_key::ApiKeyScope,
+    models::organizations::DailyActivity,
+    models::repository_insights::{
+        PolicyCompliance, PullTrend, RepositoryInsights, SignatureCoverage, StaleTags, StorageFootprint,
+    },
+    AppState,
+};
+
+/// Pull trend, storage footprint, stale tags, signature coverage, policy compliance and latest scan of a repository
+///
+/// One call for the repository overview page. Stale tags come from the latest background cleanup
+/// analysis and are not recomputed. Requires pull access.
+#[utoipa::path(
+    get,
+    path = "/api/v1/repos/{namespace}/{repo_name}/insights",
+    params(
+        ("namespace" = String, Path, description = "Organization namespace"),
+        ("repo_name" = String, Path, description = "Repository name")
+    ),
+    responses(
+        (status = 200, description = "Repository insights", body = RepositoryInsights),
+        (status = 401, description = "Authentication required"),
+        (status = 404, description = "Repository not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "repositories",
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn get_repository_insights(
+    Path((namespace, repo_name)): Path<(String, String)>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    auth: Option<TypedHeader<Authorization<Bearer>>>,
+) -> Response {
+    let secret = state.config.auth.jwt_secret.expose_secret().as_bytes();
+    let user_id = match extract_user_id_dual(auth, &headers, ApiKeyScope::Read, secret, &state.db_pool, state.cache.as_ref()).await {
+        Ok(id) => id,
+        Err(status) => return (status, Json(json!({"error": "Authentication required"}))).into_response(),
+    };
+    match check_repository_permission(&user_id.to_string(), &namespace, &repo_name, "pull", &state).await {
+        Ok(true) => {}
+        Ok(false) => return repository_not_found(&namespace, &repo_name),
+        Err(e) => return internal_error(e),
+    }
+    let (repository_id, org_id) = match find_repository(&state, &namespace, &repo_name).await {
+        Ok(Some(ids)) => ids,
+        Ok(None) => return repository_not_found(&namespace, &repo_name),
+        Err(e) => return internal_error(e),
+    };
+
+    let name = format!("{}/{}", namespace, repo_name);
+    match load_insights(&state, repository_id, org_id, name).await {
+        Ok(insights) => (StatusCode::OK, Json(json!(insights))).into_response(),
+        Err(e) => internal_error(e),
+    }
+}
+
+async fn load_insights(state: &AppState, repository_id: i64, org_id: i64, repository: String) -> anyhow::Result<RepositoryInsights> {
+    let pool = &state.db_pool;
+
+    let daily = sqlx::query_as::<_, DailyActivity>(
+        "SELECT d::DATE AS day, COALESCE(a.pull_count, 0)::BIGINT AS pulls, COALESCE(a.push_count, 0)::BIGINT AS pushes
+         FROM generate_series(CURRENT_DATE - 29, CURRENT_DATE, INTERVAL '1 day') AS d
+         LEFT JOIN repository_activity_daily a ON a.repository_id = $1 AND a.day = d::DATE
+         ORDER BY d",
+    )
+    .bind(repository_id)
+    .fetch_all(pool)
+    .await?;
+    let (previous_7_days, last_7_days) = weekly_pulls(&daily);
+
+    let (manifest_count, tag_count, total_bytes, untagged_manifest_count, untagged_bytes) =
+        sqlx::query_as::<_, (i64, i64, i64, i64, i64)>(
+            "SELECT
+                 (SELECT COUNT(*) FROM manifests WHERE repository_id = $1),
+                 (SELECT COUNT(*) FROM tags WHERE repository_id = $1),
+                 (SELECT COALESCE(SUM(size), 0) FROM manifests WHERE repository_id = $1)::BIGINT,
+                 COUNT(u.id),
+                 COALESCE(SUM(u.size), 0)::BIGINT
+             FROM (
+                 SELECT m.id, m.size
+                 FROM manifests m
+                 WHERE m.repository_id = $1
+                   AND m.subject_digest IS NULL
+                   AND NOT EXISTS (SELECT 1 FROM tags t WHERE t.manifest_id = m.id)
+                   AND NOT EXISTS (
+                       SELECT 1 FROM manifests i
+                       WHERE i.repository_id = m.repository_id AND i.id <> m.id AND POSITION(m.digest IN i.content) > 0
+                   )
+             ) u",
+        )
+        .bind(repository_id)
+        .fetch_one(pool)
+        .await?;
+
+    let org_settings = load_org_settings(pool, org_id).await?;
+    let tier = crate::quota::org_tier(pool, org_id).await?;
+    let organization_used_bytes = org_storage_used(pool, org_id).await?;
+    let organization_limit_bytes = crate::quota::storage_limit(org_settings.storage_quota_bytes, tier.as_ref()).map(|(limit, _)| limit);
+
+    let stale_tags = match crate::tag_cleanup::load_suggestions(pool, repository_id).await? {
+        Some(suggestions) => StaleTags {
+            count: suggestions.candidates.len(),
+            reclaimable_bytes: suggestions.estimated_savings_bytes,
+            computed_at: Some(suggestions.computed_at),
+        },
+        None => StaleTags { count: 0, reclaimable_bytes: 0, computed_at: None },
+    };
+
+    let (tagged_manifests, signed_manifests) = sqlx::query_as::<_, (i64, i64)>(
+        "SELECT COUNT(*),
+                COUNT(*) FILTER (WHERE
+                    EXISTS (
+                        SELECT 1 FROM manifests s
+                        WHERE s.repository_id = m.repository_id AND s.subject_digest = m.digest AND s.artifact_type = ANY($2)
+                    )
+                    OR EXISTS (
+                        SELECT 1 FROM tags st
+                        WHERE st.repository_id = m.repository_id AND st.name = REPLACE(m.digest, ':', '-') || '.sig'
+                    ))
+         FROM manifests m
+         WHERE m.repository_id = $1
+           AND EXISTS (SELECT 1 FROM tags t WHERE t.manifest_id = m.id AND t.name NOT LIKE 'sha256-%')",
+    )
+    .bind(repository_id)
+    .bind(crate::signatures::signature_artifact_types())
+    .fetch_one(pool)
+    .await?;
+
+    let (tag_retention_keep_last, tag_retention_days) = sqlx::query_as::<_, (Option<i32>, Option<i32>)>(
+        "SELECT tag_retention_keep_last, tag_retention_days FROM repositories WHERE id = $1",
+    )
+    .bind(repository_id)
+    .fetch_one(pool)
+    .await?;
+    let tags_outside_retention = sqlx::query_scalar::<_, i64>(
+        "SELECT COUNT(*)
+         FROM (
+             SELECT updated_at, ROW_NUMBER() OVER (ORDER BY updated_at DESC) AS newest_rank
+             FROM tags
+             WHERE repository_id = $1
+         ) ranked
+         WHERE newest_rank > $2 OR updated_at < CURRENT_TIMESTAMP - make_interval(days => $3)",
+    )
+    .bind(repository_id)
+    .bind(tag_retention_keep_last)
+    .bind(tag_retention_days)
+    .fetch_one(pool)
+    .await?;
+    let (active_takedowns, legal_hold, push_hooks) = sqlx::query_as::<_, (i64, bool, i64)>(
+        "SELECT
+             (SELECT COUNT(*) FROM content_takedowns WHERE repository_id = $1 AND reinstated_at IS NULL),
+             EXISTS (SELECT 1 FROM legal_holds WHERE organization_id = $2 AND released_at IS NULL),
+             (SELECT COUNT(*) FROM organization_push_hooks WHERE organization_id = $2 AND active)",
+    )
+    .bind(repository_id)
+    .bind(org_id)
+    .fetch_one(pool)
+    .await?;
+    let latest_scan = crate::scanning::latest_scan(pool, repository_id).await?;
+    let storage_limit_exceeded = organization_limit_bytes.is_some_and(|limit| organization_used_bytes >= limit);
+
+    Ok(RepositoryInsights {
+        repository,
+        pulls: PullTrend {
+            last_7_days,
+            previous_7_days,
+            last_30_days: daily.iter().map(|d| d.pulls).sum(),
+            change_percent: percent_change(previous_7_days, last_7_days),
+            daily,
+        },
+        storage: StorageFootprint {
+            manifest_count,
+            tag_count,
+            total_bytes,
+            untagged_manifest_count,
+            untagged_bytes,
+            organization_used_bytes,
+            organization_limit_bytes,
+        },
+        stale_tags,
+        signatures: SignatureCoverage {
+            tagged_manifests,
+            signed_manifests,
+            coverage_percent: percent_of(signed_manifests, tagged_manifests),
+        },
+        policy: PolicyCompliance {
+            compliant: tags_outside_retention == 0 && !storage_limit_exceeded && active_takedowns == 0,
+            tag_retention_keep_last,
+            tag_retention_days,
+            tags_outside_retention,
+            storage_limit_exceeded,
+            active_takedowns,
+            legal_hold,
+            push_hooks,
+        },
+        latest_scan,
+        generated_at: chrono::Utc::now(),
+    })
+}
+
+/// (pulls of the 7 days before the last 7, pulls of the last 7) from a series ending today
+fn weekly_pulls(daily: &[DailyActivity]) -> (i64, i64) {
+    let mut weeks = daily.iter().rev().map(|d| d.pulls);
+    let last: i64 = weeks.by_ref().take(7).sum();
+    let previous: i64 = weeks.take(7).sum();
+    (previous, last)
+}
+
+fn percent_change(previous: i64, current: i64) -> Option<f64> {
+    (previous > 0).then(|| ((current - previous) as f64 / previous as f64 * 1000.0).round() / 10.0)
+}
+
+pub(crate) fn percent_of(part: i64, whole: i64) -> Option<f64> {
+    (whole > 0).then(|| (part as f64 / whole as f64 * 1000.0).round() / 10.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    #[test]
+    fn pull_trend_compares_the_last_two_weeks() {
+        let daily: Vec<DailyActivity> = (0..30)
+            .map(|i| DailyActivity {
+                day: NaiveDate::from_ymd_opt(2025, 1, 1).unwrap() + chrono::Duration::days(i),
+                pulls: if i >= 23 { 3 } else { 2 },
+                pushes: 0,
+            })
+            .collect();
+        assert_eq!(weekly_pulls(&daily), (14, 21));
+        assert_eq!(percent_change(14, 21), Some(50.0));
+        assert_eq!(percent_change(0, 21), None);
+        assert_eq!(percent_of(1, 3), Some(33.3));
+        assert_eq!(percent_of(0, 0), None);
+    }
+}
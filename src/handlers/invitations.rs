@@ -0,0 +1,636 @@
+// src/handlers/invitations.rs - Organization invitations sent by email as signed links
+use anyhow::{bail, Context, Result};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use chrono::{DateTime, Duration, Utc};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use sqlx::PgPool;
+use validator::Validate;
+
+use axum_extra::headers::{Authorization, authorization::Bearer};
+use axum_extra::TypedHeader;
+use secrecy::ExposeSecret;
+use crate::auth::extract_user_id;
+
+use crate::{
+    handlers::organizations::get_user_role_in_org,
+    log_stream::LogEvent,
+    models::{
+        organization_invitation::{CreateInvitationRequest, InvitationPreview, OrganizationInvitation},
+        organizations::OrganizationMember,
+    },
+    AppState,
+};
+
+const INVITATION_COLUMNS: &str =
+    "id, organization_id, email, role, invited_by, status, expires_at, responded_at, created_at";
+
+/// Invite someone to an organization by email
+///
+/// The recipient gets a signed link valid for `INVITATION_EXPIRY_HOURS`. Inviting an address
+/// that already has a pending invitation replaces its role and sends a fresh link.
+#[utoipa::path(
+    post,
+    path = "/api/v1/organizations/{id}/invitations",
+    tag = "organizations",
+    params(
+        ("id" = i64, Path, description = "Organization ID")
+    ),
+    request_body = CreateInvitationRequest,
+    responses(
+        (status = 201, description = "Invitation sent", body = OrganizationInvitation),
+        (status = 400, description = "Validation failed, already a member, or insufficient permissions"),
+        (status = 401, description = "Unauthorized"),
+        (status = 502, description = "Invitation stored but the email could not be sent")
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn create_invitation(
+    State(state): State<AppState>,
+    auth: Option<TypedHeader<Authorization<Bearer>>>,
+    Path(id): Path<i64>,
+    Json(req): Json<CreateInvitationRequest>,
+) -> impl IntoResponse {
+    let user_id = match authenticate(&state, auth).await {
+        Ok(id) => id,
+        Err(response) => return response,
+    };
+
+    if let Err(e) = req.validate() {
+        return bad_request("Invalid invitation", e.into());
+    }
+
+    let expiry_hours = state.config.invitations.expiry_hours;
+    let invitation = match create_invitation_internal(&state.db_pool, id, user_id, &req, expiry_hours).await {
+        Ok(invitation) => invitation,
+        Err(e) => return bad_request("Failed to create invitation", e),
+    };
+
+    state.log_stream.publish(
+        LogEvent::audit("organization.invite", Some(user_id), None)
+            .with_organization(id)
+            .with_detail(format!("org={} invitation={} role={}", id, invitation.id, invitation.role)),
+    );
+
+    match send_invitation_email(&state, &invitation, user_id).await {
+        Ok(()) => (StatusCode::CREATED, Json(serde_json::to_value(&invitation).unwrap_or_default())),
+        Err(e) => {
+            tracing::error!("Failed to send invitation {}: {}", invitation.id, e);
+            (
+                StatusCode::BAD_GATEWAY,
+                Json(serde_json::json!({
+                    "error": "Invitation created but the email could not be sent; invite the address again to retry",
+                    "invitation": invitation
+                })),
+            )
+        }
+    }
+}
+
+/// List the pending invitations of an organization
+#[utoipa::path(
+    get,
+    path = "/api/v1/organizations/{id}/invitations",
+    tag = "organizations",
+    params(
+        ("id" = i64, Path, description = "Organization ID")
+    ),
+    responses(
+        (status = 200, description = "Pending invitations retrieved successfully", body = Vec<OrganizationInvitation>),
+        (status = 400, description = "Insufficient permissions"),
+        (status = 401, description = "Unauthorized")
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn list_invitations(
+    State(state): State<AppState>,
+    auth: Option<TypedHeader<Authorization<Bearer>>>,
+    Path(id): Path<i64>,
+) -> impl IntoResponse {
+    let user_id = match authenticate(&state, auth).await {
+        Ok(id) => id,
+        Err(response) => return response,
+    };
+
+    match list_invitations_internal(&state.db_pool, id, user_id).await {
+        Ok(invitations) => (StatusCode::OK, Json(serde_json::to_value(&invitations).unwrap_or_default())),
+        Err(e) => bad_request("Failed to list invitations", e),
+    }
+}
+
+/// Revoke a pending invitation; its link stops working
+#[utoipa::path(
+    delete,
+    path = "/api/v1/organizations/{id}/invitations/{invitation_id}",
+    tag = "organizations",
+    params(
+        ("id" = i64, Path, description = "Organization ID"),
+        ("invitation_id" = i64, Path, description = "Invitation ID")
+    ),
+    responses(
+        (status = 204, description = "Invitation revoked"),
+        (status = 400, description = "Invitation not pending or insufficient permissions"),
+        (status = 401, description = "Unauthorized")
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn revoke_invitation(
+    State(state): State<AppState>,
+    auth: Option<TypedHeader<Authorization<Bearer>>>,
+    Path((id, invitation_id)): Path<(i64, i64)>,
+) -> impl IntoResponse {
+    let user_id = match authenticate(&state, auth).await {
+        Ok(id) => id,
+        Err(response) => return response,
+    };
+
+    match revoke_invitation_internal(&state.db_pool, id, invitation_id, user_id).await {
+        Ok(()) => {
+            state.log_stream.publish(
+                LogEvent::audit("organization.invite.revoke", Some(user_id), None)
+                    .with_organization(id)
+                    .with_detail(format!("org={} invitation={}", id, invitation_id)),
+            );
+            (StatusCode::NO_CONTENT, Json(serde_json::json!({})))
+        }
+        Err(e) => bad_request("Failed to revoke invitation", e),
+    }
+}
+
+/// Show the organization and role an invite link is for
+#[utoipa::path(
+    get,
+    path = "/api/v1/invitations/{token}",
+    tag = "organizations",
+    params(
+        ("token" = String, Path, description = "Signed invitation token from the invite link")
+    ),
+    responses(
+        (status = 200, description = "Invitation details", body = InvitationPreview),
+        (status = 404, description = "Invalid or expired invitation link")
+    )
+)]
+pub async fn get_invitation(
+    State(state): State<AppState>,
+    Path(token): Path<String>,
+) -> impl IntoResponse {
+    let invitation = match resolve_token(&state, &token).await {
+        Ok(invitation) => invitation,
+        Err(response) => return response,
+    };
+
+    let preview = sqlx::query_as::<_, InvitationPreview>(
+        "SELECT i.organization_id, o.name AS organization_name, o.display_name AS organization_display_name,
+                i.email, i.role, u.username AS invited_by_username, i.status, i.expires_at
+         FROM organization_invitations i
+         JOIN organizations o ON o.id = i.organization_id
+         LEFT JOIN users u ON u.id = i.invited_by
+         WHERE i.id = $1",
+    )
+    .bind(invitation.id)
+    .fetch_one(&state.db_pool)
+    .await;
+
+    match preview {
+        Ok(preview) => (StatusCode::OK, Json(serde_json::to_value(&preview).unwrap_or_default())),
+        Err(e) => bad_request("Failed to load invitation", e.into()),
+    }
+}
+
+/// Accept an invitation, joining the organization with the invited role
+///
+/// The caller must be logged in with the account whose email address was invited.
+#[utoipa::path(
+    post,
+    path = "/api/v1/invitations/{token}/accept",
+    tag = "organizations",
+    params(
+        ("token" = String, Path, description = "Signed invitation token from the invite link")
+    ),
+    responses(
+        (status = 200, description = "Invitation accepted", body = OrganizationMember),
+        (status = 400, description = "Invitation no longer pending, sent to a different address, or already a member"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Organization is at its tier's member limit"),
+        (status = 404, description = "Invalid or expired invitation link")
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn accept_invitation(
+    State(state): State<AppState>,
+    auth: Option<TypedHeader<Authorization<Bearer>>>,
+    Path(token): Path<String>,
+) -> impl IntoResponse {
+    let user_id = match authenticate(&state, auth).await {
+        Ok(id) => id,
+        Err(response) => return response,
+    };
+    let invitation = match resolve_token(&state, &token).await {
+        Ok(invitation) => invitation,
+        Err(response) => return response,
+    };
+
+    match accept_invitation_internal(&state.db_pool, &invitation, user_id).await {
+        Ok(member) => {
+            state.log_stream.publish(
+                LogEvent::audit("organization.invite.accept", Some(user_id), None)
+                    .with_organization(invitation.organization_id)
+                    .with_detail(format!("org={} invitation={} role={}", invitation.organization_id, invitation.id, member.role)),
+            );
+            (StatusCode::OK, Json(serde_json::to_value(&member).unwrap_or_default()))
+        }
+        Err(e) => match e.downcast_ref::<crate::quota::QuotaExceeded>() {
+            Some(exceeded) => exceeded.api_error(),
+            None => bad_request("Failed to accept invitation", e),
+        },
+    }
+}
+
+/// Decline an invitation
+///
+/// Holding the link is enough; no account is needed to turn an invitation down.
+#[utoipa::path(
+    post,
+    path = "/api/v1/invitations/{token}/decline",
+    tag = "organizations",
+    params(
+        ("token" = String, Path, description = "Signed invitation token from the invite link")
+    ),
+    responses(
+        (status = 204, description = "Invitation declined"),
+        (status = 400, description = "Invitation no longer pending"),
+        (status = 404, description = "Invalid or expired invitation link")
+    )
+)]
+pub async fn decline_invitation(
+    State(state): State<AppState>,
+    Path(token): Path<String>,
+) -> impl IntoResponse {
+    let invitation = match resolve_token(&state, &token).await {
+        Ok(invitation) => invitation,
+        Err(response) => return response,
+    };
+
+    match respond_to_invitation(&state.db_pool, invitation.id, "declined").await {
+        Ok(()) => {
+            state.log_stream.publish(
+                LogEvent::audit("organization.invite.decline", None, None)
+                    .with_organization(invitation.organization_id)
+                    .with_detail(format!("org={} invitation={}", invitation.organization_id, invitation.id)),
+            );
+            (StatusCode::NO_CONTENT, Json(serde_json::json!({})))
+        }
+        Err(e) => bad_request("Failed to decline invitation", e),
+    }
+}
+
+async fn authenticate(
+    state: &AppState,
+    auth: Option<TypedHeader<Authorization<Bearer>>>,
+) -> Result<i64, (StatusCode, Json<serde_json::Value>)> {
+    extract_user_id(auth, state.config.auth.jwt_secret.expose_secret().as_bytes(), &state.db_pool)
+        .await
+        .map_err(|status| {
+            (
+                status,
+                Json(serde_json::json!({
+                    "error": "Unauthorized"
+                })),
+            )
+        })
+}
+
+fn bad_request(context: &str, e: anyhow::Error) -> (StatusCode, Json<serde_json::Value>) {
+    tracing::error!("{}: {}", context, e);
+    (
+        StatusCode::BAD_REQUEST,
+        Json(serde_json::json!({
+            "error": e.to_string()
+        })),
+    )
+}
+
+fn invalid_link() -> (StatusCode, Json<serde_json::Value>) {
+    (
+        StatusCode::NOT_FOUND,
+        Json(serde_json::json!({
+            "error": "Invalid or expired invitation link"
+        })),
+    )
+}
+
+// Signed invitation tokens
+
+fn signature(secret: &[u8], id: i64, expires: i64, email: &str) -> Hmac<Sha256> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret).expect("HMAC accepts keys of any length");
+    mac.update(format!("{}.{}.{}", id, expires, email.to_lowercase()).as_bytes());
+    mac
+}
+
+/// `{id}.{expires}.{hmac}`; the email is signed but not embedded, so links don't leak it
+pub fn sign_invitation_token(secret: &[u8], id: i64, expires_at: DateTime<Utc>, email: &str) -> String {
+    let expires = expires_at.timestamp();
+    let mac = signature(secret, id, expires, email);
+    format!("{}.{}.{}", id, expires, hex::encode(mac.finalize().into_bytes()))
+}
+
+/// Split a token into the invitation id and expiry it claims, without checking the signature
+fn parse_invitation_token(token: &str) -> Option<(i64, i64, Vec<u8>)> {
+    let mut parts = token.splitn(3, '.');
+    let id = parts.next()?.parse().ok()?;
+    let expires = parts.next()?.parse().ok()?;
+    let mac = hex::decode(parts.next()?).ok()?;
+    Some((id, expires, mac))
+}
+
+/// Check a token against the stored invitation. Refreshing an invitation moves its expiry,
+/// which invalidates links sent earlier.
+pub fn verify_invitation_token(
+    secret: &[u8],
+    token: &str,
+    invitation: &OrganizationInvitation,
+    now: DateTime<Utc>,
+) -> bool {
+    let Some((id, expires, mac)) = parse_invitation_token(token) else {
+        return false;
+    };
+    id == invitation.id
+        && expires == invitation.expires_at.timestamp()
+        && expires > now.timestamp()
+        && signature(secret, id, expires, &invitation.email).verify_slice(&mac).is_ok()
+}
+
+async fn resolve_token(
+    state: &AppState,
+    token: &str,
+) -> Result<OrganizationInvitation, (StatusCode, Json<serde_json::Value>)> {
+    let Some((id, _, _)) = parse_invitation_token(token) else {
+        return Err(invalid_link());
+    };
+
+    let invitation = sqlx::query_as::<_, OrganizationInvitation>(&format!(
+        "SELECT {} FROM organization_invitations WHERE id = $1",
+        INVITATION_COLUMNS
+    ))
+    .bind(id)
+    .fetch_optional(&state.db_pool)
+    .await
+    .map_err(|e| bad_request("Failed to load invitation", e.into()))?
+    .ok_or_else(invalid_link)?;
+
+    let secret = state.config.auth.jwt_secret.expose_secret().as_bytes();
+    if !verify_invitation_token(secret, token, &invitation, Utc::now()) {
+        return Err(invalid_link());
+    }
+    Ok(invitation)
+}
+
+async fn send_invitation_email(state: &AppState, invitation: &OrganizationInvitation, inviter_id: i64) -> Result<()> {
+    let (org_name, inviter): (String, String) = sqlx::query_as(
+        "SELECT o.display_name, u.username FROM organizations o, users u WHERE o.id = $1 AND u.id = $2",
+    )
+    .bind(invitation.organization_id)
+    .bind(inviter_id)
+    .fetch_one(&state.db_pool)
+    .await?;
+
+    let secret = state.config.auth.jwt_secret.expose_secret().as_bytes();
+    let token = sign_invitation_token(secret, invitation.id, invitation.expires_at, &invitation.email);
+    let link = format!("{}/{}", state.config.invitations.accept_url.trim_end_matches('/'), token);
+
+    state
+        .email_service
+        .send_organization_invitation_email(
+            &invitation.email,
+            &org_name,
+            &inviter,
+            &invitation.role,
+            &link,
+            state.config.invitations.expiry_hours,
+        )
+        .await
+}
+
+// Internal database functions
+async fn ensure_can_manage_members(pool: &PgPool, org_id: i64, user_id: i64) -> Result<()> {
+    let user_role = get_user_role_in_org(pool, org_id, user_id).await?;
+    if !user_role.map(|r| r.can_manage_members()).unwrap_or(false) {
+        bail!("Insufficient permissions to manage invitations");
+    }
+    Ok(())
+}
+
+async fn create_invitation_internal(
+    pool: &PgPool,
+    org_id: i64,
+    inviter_id: i64,
+    req: &CreateInvitationRequest,
+    expiry_hours: i64,
+) -> Result<OrganizationInvitation> {
+    let inviter_role = get_user_role_in_org(pool, org_id, inviter_id).await?;
+    match inviter_role {
+        Some(role) if role.can_manage_members() && role.can_change_role_to(&req.role) => {}
+        Some(role) if role.can_manage_members() => bail!("Insufficient permissions to invite as {}", req.role),
+        _ => bail!("Insufficient permissions to invite members"),
+    }
+
+    let already_member: bool = sqlx::query_scalar(
+        "SELECT EXISTS(
+            SELECT 1 FROM organization_members m
+            JOIN users u ON u.id = m.user_id
+            WHERE m.organization_id = $1 AND LOWER(u.email) = LOWER($2)
+         )",
+    )
+    .bind(org_id)
+    .bind(&req.email)
+    .fetch_one(pool)
+    .await?;
+    if already_member {
+        bail!("{} is already a member of this organization", req.email);
+    }
+
+    let expires_at = Utc::now() + Duration::hours(expiry_hours);
+    let invitation = sqlx::query_as::<_, OrganizationInvitation>(&format!(
+        "INSERT INTO organization_invitations (organization_id, email, role, invited_by, expires_at)
+         VALUES ($1, $2, $3, $4, $5)
+         ON CONFLICT (organization_id, LOWER(email)) WHERE status = 'pending'
+         DO UPDATE SET role = EXCLUDED.role, invited_by = EXCLUDED.invited_by,
+                       expires_at = EXCLUDED.expires_at, created_at = CURRENT_TIMESTAMP
+         RETURNING {}",
+        INVITATION_COLUMNS
+    ))
+    .bind(org_id)
+    .bind(&req.email)
+    .bind(req.role.to_string())
+    .bind(inviter_id)
+    .bind(expires_at)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(invitation)
+}
+
+async fn list_invitations_internal(pool: &PgPool, org_id: i64, user_id: i64) -> Result<Vec<OrganizationInvitation>> {
+    ensure_can_manage_members(pool, org_id, user_id).await?;
+
+    let invitations = sqlx::query_as::<_, OrganizationInvitation>(&format!(
+        "SELECT {} FROM organization_invitations
+         WHERE organization_id = $1 AND status = 'pending' AND expires_at > NOW()
+         ORDER BY created_at DESC",
+        INVITATION_COLUMNS
+    ))
+    .bind(org_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(invitations)
+}
+
+async fn revoke_invitation_internal(pool: &PgPool, org_id: i64, invitation_id: i64, user_id: i64) -> Result<()> {
+    ensure_can_manage_members(pool, org_id, user_id).await?;
+
+    let result = sqlx::query(
+        "UPDATE organization_invitations SET status = 'revoked', responded_at = NOW()
+         WHERE id = $1 AND organization_id = $2 AND status = 'pending'",
+    )
+    .bind(invitation_id)
+    .bind(org_id)
+    .execute(pool)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        bail!("No pending invitation with that ID");
+    }
+    Ok(())
+}
+
+async fn respond_to_invitation(pool: &PgPool, invitation_id: i64, status: &str) -> Result<()> {
+    let result = sqlx::query(
+        "UPDATE organization_invitations SET status = $2, responded_at = NOW()
+         WHERE id = $1 AND status = 'pending'",
+    )
+    .bind(invitation_id)
+    .bind(status)
+    .execute(pool)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        bail!("Invitation is no longer pending");
+    }
+    Ok(())
+}
+
+async fn accept_invitation_internal(
+    pool: &PgPool,
+    invitation: &OrganizationInvitation,
+    user_id: i64,
+) -> Result<OrganizationMember> {
+    if invitation.status != "pending" {
+        bail!("Invitation is no longer pending");
+    }
+
+    let (username, email): (String, String) = sqlx::query_as("SELECT username, email FROM users WHERE id = $1")
+        .bind(user_id)
+        .fetch_one(pool)
+        .await
+        .context("User not found")?;
+    if !email.eq_ignore_ascii_case(&invitation.email) {
+        bail!("This invitation was sent to a different email address");
+    }
+    if let Some(exceeded) = crate::quota::check_members(pool, invitation.organization_id).await? {
+        return Err(exceeded.into());
+    }
+
+    let mut tx = pool.begin().await?;
+
+    // Claim the invitation first so two concurrent accepts cannot both create a membership
+    let claimed = sqlx::query(
+        "UPDATE organization_invitations SET status = 'accepted', responded_at = NOW()
+         WHERE id = $1 AND status = 'pending'",
+    )
+    .bind(invitation.id)
+    .execute(&mut *tx)
+    .await?;
+    if claimed.rows_affected() == 0 {
+        bail!("Invitation is no longer pending");
+    }
+
+    let (member_id, joined_at): (i64, DateTime<Utc>) = sqlx::query_as(
+        "INSERT INTO organization_members (organization_id, user_id, role, invited_at, invited_by)
+         VALUES ($1, $2, $3, $4, $5)
+         ON CONFLICT (organization_id, user_id) DO NOTHING
+         RETURNING id, joined_at",
+    )
+    .bind(invitation.organization_id)
+    .bind(user_id)
+    .bind(&invitation.role)
+    .bind(invitation.created_at)
+    .bind(invitation.invited_by)
+    .fetch_optional(&mut *tx)
+    .await?
+    .context("You are already a member of this organization")?;
+
+    tx.commit().await?;
+
+    Ok(OrganizationMember {
+        id: member_id,
+        organization_id: invitation.organization_id,
+        user_id,
+        role: invitation.role.clone(),
+        joined_at,
+        invited_at: Some(invitation.created_at),
+        invited_by: invitation.invited_by,
+        username,
+        email,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn invitation(expires_at: DateTime<Utc>) -> OrganizationInvitation {
+        OrganizationInvitation {
+            id: 42,
+            organization_id: 7,
+            email: "Dev@Example.com".to_string(),
+            role: "developer".to_string(),
+            invited_by: Some(1),
+            status: "pending".to_string(),
+            expires_at,
+            responded_at: None,
+            created_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn token_round_trip_and_tampering() {
+        let secret = b"test-secret";
+        let now = Utc::now();
+        let invite = invitation(now + Duration::hours(1));
+        let token = sign_invitation_token(secret, invite.id, invite.expires_at, "dev@example.com");
+
+        assert!(verify_invitation_token(secret, &token, &invite, now));
+        assert!(!verify_invitation_token(b"other-secret", &token, &invite, now));
+        assert!(!verify_invitation_token(secret, &token, &invite, now + Duration::hours(2)));
+        assert!(!verify_invitation_token(secret, &token.replacen("42.", "43.", 1), &invite, now));
+
+        // A refreshed invitation has a new expiry, so the old link no longer verifies
+        let refreshed = invitation(now + Duration::hours(3));
+        assert!(!verify_invitation_token(secret, &token, &refreshed, now));
+        assert!(!verify_invitation_token(secret, "garbage", &invite, now));
+    }
+}
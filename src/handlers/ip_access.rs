@@ -0,0 +1,503 @@
+// src/handlers/ip_access.rs - CIDR allow/deny rules for registry traffic
+use std::{
+    collections::HashMap,
+    net::{IpAddr, SocketAddr},
+};
+
+use anyhow::{bail, Result};
+use axum::{
+    extract::{ConnectInfo, Path, Request, State},
+    http::{HeaderMap, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use ipnet::IpNet;
+use sqlx::PgPool;
+
+use axum_extra::headers::{Authorization, authorization::Bearer};
+use axum_extra::TypedHeader;
+use secrecy::ExposeSecret;
+use crate::auth::extract_user_id;
+
+use crate::{
+    handlers::{admin::AdminUser, organizations::get_user_role_in_org},
+    log_stream::LogEvent,
+    models::ip_access_rule::{CreateIpAccessRuleRequest, IpAccessRule, IpRuleOperation},
+    AppState,
+};
+
+const IP_RULE_SELECT: &str = "SELECT id, organization_id, cidr::text AS cidr, action, operation, description, created_by, created_at
+     FROM ip_access_rules";
+
+/// Middleware for the registry router: rejects requests whose client address is not admitted
+/// by the global rules and by the rules of the organization owning the repository.
+///
+/// Reads (GET/HEAD) are checked against `pull` rules, everything else against `push` rules.
+pub async fn enforce_ip_access_rules(
+    State(state): State<AppState>,
+    connect_info: Option<ConnectInfo<SocketAddr>>,
+    path: Option<Path<HashMap<String, String>>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if !state.config.ip_access.enabled {
+        return next.run(request).await;
+    }
+
+    let peer = connect_info.map(|ConnectInfo(addr)| addr.ip());
+    let ip = match client_ip(peer, request.headers(), &state.config.ip_access.trusted_proxies) {
+        Some(ip) => ip,
+        None => return next.run(request).await,
+    };
+
+    let operation = match *request.method() {
+        Method::GET | Method::HEAD => IpRuleOperation::Pull,
+        _ => IpRuleOperation::Push,
+    };
+    let namespace = path.and_then(|Path(params)| {
+        params
+            .get("org")
+            .cloned()
+            .or_else(|| params.get("name").and_then(|name| name.split_once('/')).map(|(ns, _)| ns.to_string()))
+    });
+
+    match is_ip_allowed(&state.db_pool, ip, namespace.as_deref(), operation).await {
+        Ok(true) => next.run(request).await,
+        Ok(false) => {
+            tracing::warn!("{} from {} blocked by IP access rules", operation, ip);
+            registry_error(StatusCode::FORBIDDEN, "DENIED", "Access from this network is not allowed")
+        }
+        Err(e) => {
+            // Fail closed: an unreadable policy must not let blocked networks through
+            tracing::error!("Error evaluating IP access rules: {}", e);
+            registry_error(StatusCode::INTERNAL_SERVER_ERROR, "UNKNOWN", "Internal server error")
+        }
+    }
+}
+
+/// Resolve the client address of a request.
+///
+/// `X-Forwarded-For` and `X-Real-IP` are only honored when the direct peer is one of
+/// `trusted_proxies`; the client is then the rightmost forwarded hop that is not itself
+/// a trusted proxy.
+pub fn client_ip(peer: Option<IpAddr>, headers: &HeaderMap, trusted_proxies: &[String]) -> Option<IpAddr> {
+    let proxies: Vec<IpNet> = trusted_proxies.iter().filter_map(|p| parse_network(p)).collect();
+    let is_trusted = |ip: &IpAddr| proxies.iter().any(|net| net.contains(ip));
+
+    let peer = peer?;
+    if !is_trusted(&peer) {
+        return Some(peer);
+    }
+
+    let forwarded: Vec<IpAddr> = headers
+        .get_all("x-forwarded-for")
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .filter_map(|hop| hop.trim().parse().ok())
+        .collect();
+    if let Some(first) = forwarded.first() {
+        return Some(forwarded.iter().rev().find(|ip| !is_trusted(ip)).copied().unwrap_or(*first));
+    }
+
+    headers
+        .get("x-real-ip")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.trim().parse().ok())
+        .or(Some(peer))
+}
+
+/// Parse a CIDR network, treating a bare address as a single-host network
+pub fn parse_network(value: &str) -> Option<IpNet> {
+    let value = value.trim();
+    value
+        .parse::<IpNet>()
+        .ok()
+        .or_else(|| value.parse::<IpAddr>().ok().map(IpNet::from))
+}
+
+/// Evaluate the global rules and those of `namespace`'s organization for `ip`.
+///
+/// Within each scope a matching deny rule wins, and once a scope has allow rules the
+/// address must match one of them. Both scopes must admit the address.
+pub async fn is_ip_allowed(
+    pool: &PgPool,
+    ip: IpAddr,
+    namespace: Option<&str>,
+    operation: IpRuleOperation,
+) -> Result<bool, sqlx::Error> {
+    let rules = sqlx::query_as::<_, (bool, String, bool)>(
+        "SELECT organization_id IS NULL, action, $1::inet <<= cidr
+         FROM ip_access_rules
+         WHERE (organization_id IS NULL OR organization_id = (SELECT id FROM organizations WHERE name = $2))
+           AND operation IN ('all', $3)",
+    )
+    .bind(ip.to_string())
+    .bind(namespace)
+    .bind(operation.to_string())
+    .fetch_all(pool)
+    .await?;
+
+    let scope_allows = |global: bool| {
+        let scoped: Vec<_> = rules.iter().filter(|(is_global, _, _)| *is_global == global).collect();
+        if scoped.iter().any(|(_, action, matches)| *matches && action == "deny") {
+            return false;
+        }
+        let allows: Vec<_> = scoped.iter().filter(|(_, action, _)| action == "allow").collect();
+        allows.is_empty() || allows.iter().any(|(_, _, matches)| *matches)
+    };
+
+    Ok(scope_allows(true) && scope_allows(false))
+}
+
+/// List the IP access rules of an organization
+#[utoipa::path(
+    get,
+    path = "/api/v1/organizations/{id}/ip-rules",
+    tag = "organizations",
+    params(
+        ("id" = i64, Path, description = "Organization ID")
+    ),
+    responses(
+        (status = 200, description = "IP access rules retrieved successfully", body = Vec<IpAccessRule>),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Not an owner of the organization")
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn list_ip_rules(
+    State(state): State<AppState>,
+    auth: Option<TypedHeader<Authorization<Bearer>>>,
+    Path(id): Path<i64>,
+) -> impl IntoResponse {
+    let user_id = match authenticate(&state, auth).await {
+        Ok(id) => id,
+        Err(response) => return response,
+    };
+
+    let rules = match ensure_can_manage_ip_rules(&state.db_pool, id, user_id).await {
+        Ok(()) => list_rules(&state.db_pool, Some(id)).await,
+        Err(e) => Err(e),
+    };
+    match rules {
+        Ok(rules) => (StatusCode::OK, Json(serde_json::to_value(&rules).unwrap_or_default())),
+        Err(e) => rule_error("Failed to list IP access rules", e),
+    }
+}
+
+/// Add an IP access rule to an organization
+#[utoipa::path(
+    post,
+    path = "/api/v1/organizations/{id}/ip-rules",
+    tag = "organizations",
+    params(
+        ("id" = i64, Path, description = "Organization ID")
+    ),
+    request_body = CreateIpAccessRuleRequest,
+    responses(
+        (status = 201, description = "IP access rule created successfully", body = IpAccessRule),
+        (status = 400, description = "Invalid CIDR or duplicate rule"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Not an owner of the organization")
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn create_ip_rule(
+    State(state): State<AppState>,
+    auth: Option<TypedHeader<Authorization<Bearer>>>,
+    Path(id): Path<i64>,
+    Json(req): Json<CreateIpAccessRuleRequest>,
+) -> impl IntoResponse {
+    let user_id = match authenticate(&state, auth).await {
+        Ok(id) => id,
+        Err(response) => return response,
+    };
+
+    let rule = match ensure_can_manage_ip_rules(&state.db_pool, id, user_id).await {
+        Ok(()) => insert_rule(&state.db_pool, Some(id), user_id, &req).await,
+        Err(e) => Err(e),
+    };
+    match rule {
+        Ok(rule) => {
+            state.log_stream.publish(
+                LogEvent::audit("ip_rule.create", Some(user_id), None)
+                    .with_organization(id)
+                    .with_detail(format!("organization {}: {} {} ({})", id, rule.action, rule.cidr, rule.operation)),
+            );
+            (StatusCode::CREATED, Json(serde_json::to_value(&rule).unwrap_or_default()))
+        }
+        Err(e) => rule_error("Failed to create IP access rule", e),
+    }
+}
+
+/// Remove an IP access rule from an organization
+#[utoipa::path(
+    delete,
+    path = "/api/v1/organizations/{id}/ip-rules/{rule_id}",
+    tag = "organizations",
+    params(
+        ("id" = i64, Path, description = "Organization ID"),
+        ("rule_id" = i64, Path, description = "IP access rule ID")
+    ),
+    responses(
+        (status = 204, description = "IP access rule deleted successfully"),
+        (status = 400, description = "Rule not found"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Not an owner of the organization")
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn delete_ip_rule(
+    State(state): State<AppState>,
+    auth: Option<TypedHeader<Authorization<Bearer>>>,
+    Path((id, rule_id)): Path<(i64, i64)>,
+) -> impl IntoResponse {
+    let user_id = match authenticate(&state, auth).await {
+        Ok(id) => id,
+        Err(response) => return response,
+    };
+
+    let deleted = match ensure_can_manage_ip_rules(&state.db_pool, id, user_id).await {
+        Ok(()) => remove_rule(&state.db_pool, Some(id), rule_id).await,
+        Err(e) => Err(e),
+    };
+    match deleted {
+        Ok(_) => {
+            state.log_stream.publish(
+                LogEvent::audit("ip_rule.delete", Some(user_id), None)
+                    .with_organization(id)
+                    .with_detail(format!("organization {}: rule {}", id, rule_id)),
+            );
+            (StatusCode::NO_CONTENT, Json(serde_json::json!({})))
+        }
+        Err(e) => rule_error("Failed to delete IP access rule", e),
+    }
+}
+
+/// List the registry-wide IP access rules
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/ip-rules",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Global IP access rules", body = Vec<IpAccessRule>),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Not a registry administrator")
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn list_global_ip_rules(State(state): State<AppState>, _admin: AdminUser) -> impl IntoResponse {
+    match list_rules(&state.db_pool, None).await {
+        Ok(rules) => (StatusCode::OK, Json(serde_json::to_value(&rules).unwrap_or_default())),
+        Err(e) => rule_error("Failed to list IP access rules", e),
+    }
+}
+
+/// Add a registry-wide IP access rule, applying to every repository
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/ip-rules",
+    tag = "admin",
+    request_body = CreateIpAccessRuleRequest,
+    responses(
+        (status = 201, description = "IP access rule created successfully", body = IpAccessRule),
+        (status = 400, description = "Invalid CIDR or duplicate rule"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Not a registry administrator")
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn create_global_ip_rule(
+    State(state): State<AppState>,
+    admin: AdminUser,
+    Json(req): Json<CreateIpAccessRuleRequest>,
+) -> impl IntoResponse {
+    match insert_rule(&state.db_pool, None, admin.user_id, &req).await {
+        Ok(rule) => {
+            state.log_stream.publish(
+                LogEvent::audit("ip_rule.create", Some(admin.user_id), None)
+                    .with_detail(format!("global: {} {} ({})", rule.action, rule.cidr, rule.operation)),
+            );
+            (StatusCode::CREATED, Json(serde_json::to_value(&rule).unwrap_or_default()))
+        }
+        Err(e) => rule_error("Failed to create IP access rule", e),
+    }
+}
+
+/// Remove a registry-wide IP access rule
+#[utoipa::path(
+    delete,
+    path = "/api/v1/admin/ip-rules/{rule_id}",
+    tag = "admin",
+    params(
+        ("rule_id" = i64, Path, description = "IP access rule ID")
+    ),
+    responses(
+        (status = 204, description = "IP access rule deleted successfully"),
+        (status = 400, description = "Rule not found"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Not a registry administrator")
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn delete_global_ip_rule(
+    State(state): State<AppState>,
+    admin: AdminUser,
+    Path(rule_id): Path<i64>,
+) -> impl IntoResponse {
+    match remove_rule(&state.db_pool, None, rule_id).await {
+        Ok(_) => {
+            state.log_stream.publish(
+                LogEvent::audit("ip_rule.delete", Some(admin.user_id), None)
+                    .with_detail(format!("global: rule {}", rule_id)),
+            );
+            (StatusCode::NO_CONTENT, Json(serde_json::json!({})))
+        }
+        Err(e) => rule_error("Failed to delete IP access rule", e),
+    }
+}
+
+async fn authenticate(
+    state: &AppState,
+    auth: Option<TypedHeader<Authorization<Bearer>>>,
+) -> Result<i64, (StatusCode, Json<serde_json::Value>)> {
+    extract_user_id(auth, state.config.auth.jwt_secret.expose_secret().as_bytes(), &state.db_pool)
+        .await
+        .map_err(|status| {
+            (
+                status,
+                Json(serde_json::json!({
+                    "error": "Unauthorized"
+                })),
+            )
+        })
+}
+
+/// The caller may not manage the rules of an organization
+#[derive(Debug)]
+struct NotPermitted;
+
+impl std::fmt::Display for NotPermitted {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Only organization owners can manage IP access rules")
+    }
+}
+
+impl std::error::Error for NotPermitted {}
+
+/// 403 for permission failures, 400 for everything else
+fn rule_error(context: &str, e: anyhow::Error) -> (StatusCode, Json<serde_json::Value>) {
+    tracing::error!("{}: {}", context, e);
+    let status = if e.downcast_ref::<NotPermitted>().is_some() { StatusCode::FORBIDDEN } else { StatusCode::BAD_REQUEST };
+    (
+        status,
+        Json(serde_json::json!({
+            "error": e.to_string()
+        })),
+    )
+}
+
+fn registry_error(status: StatusCode, code: &str, message: &str) -> Response {
+    (status, Json(serde_json::json!({
+        "errors": [{
+            "code": code,
+            "message": message,
+            "detail": {}
+        }]
+    }))).into_response()
+}
+
+// Internal database functions; `org_id` is `None` for the global rules
+/// Network policy is as sensitive as deleting the organization: owners only
+async fn ensure_can_manage_ip_rules(pool: &PgPool, org_id: i64, user_id: i64) -> Result<()> {
+    let user_role = get_user_role_in_org(pool, org_id, user_id).await?;
+    if !user_role.map(|r| r.can_manage_ip_rules()).unwrap_or(false) {
+        return Err(NotPermitted.into());
+    }
+    Ok(())
+}
+
+async fn list_rules(pool: &PgPool, org_id: Option<i64>) -> Result<Vec<IpAccessRule>> {
+    let query = format!("{} WHERE organization_id IS NOT DISTINCT FROM $1 ORDER BY created_at, id", IP_RULE_SELECT);
+    let rules = sqlx::query_as::<_, IpAccessRule>(&query)
+        .bind(org_id)
+        .fetch_all(pool)
+        .await?;
+    Ok(rules)
+}
+
+async fn insert_rule(
+    pool: &PgPool,
+    org_id: Option<i64>,
+    user_id: i64,
+    req: &CreateIpAccessRuleRequest,
+) -> Result<IpAccessRule> {
+    let network = match parse_network(&req.cidr) {
+        // Store the network address so "10.1.2.3/8" and "10.0.0.0/8" are the same rule
+        Some(network) => network.trunc(),
+        None => bail!("Invalid CIDR: {}", req.cidr),
+    };
+    let operation = req.operation.unwrap_or(IpRuleOperation::All);
+
+    let exists = sqlx::query_scalar::<_, bool>(
+        "SELECT EXISTS(SELECT 1 FROM ip_access_rules
+                       WHERE organization_id IS NOT DISTINCT FROM $1
+                         AND cidr = $2::cidr AND action = $3 AND operation = $4)",
+    )
+    .bind(org_id)
+    .bind(network.to_string())
+    .bind(req.action.to_string())
+    .bind(operation.to_string())
+    .fetch_one(pool)
+    .await?;
+    if exists {
+        bail!("An identical IP access rule already exists");
+    }
+
+    let id = sqlx::query_scalar::<_, i64>(
+        "INSERT INTO ip_access_rules (organization_id, cidr, action, operation, description, created_by)
+         VALUES ($1, $2::cidr, $3, $4, $5, $6)
+         RETURNING id",
+    )
+    .bind(org_id)
+    .bind(network.to_string())
+    .bind(req.action.to_string())
+    .bind(operation.to_string())
+    .bind(&req.description)
+    .bind(user_id)
+    .fetch_one(pool)
+    .await?;
+
+    let query = format!("{} WHERE id = $1", IP_RULE_SELECT);
+    let rule = sqlx::query_as::<_, IpAccessRule>(&query)
+        .bind(id)
+        .fetch_one(pool)
+        .await?;
+    Ok(rule)
+}
+
+async fn remove_rule(pool: &PgPool, org_id: Option<i64>, rule_id: i64) -> Result<()> {
+    let result = sqlx::query("DELETE FROM ip_access_rules WHERE id = $1 AND organization_id IS NOT DISTINCT FROM $2")
+        .bind(rule_id)
+        .bind(org_id)
+        .execute(pool)
+        .await?;
+
+    if result.rows_affected() == 0 {
+        bail!("IP access rule not found");
+    }
+    Ok(())
+}
@@ -0,0 +1,264 @@
+// src/handlers/jobs.rs - Status of the background jobs run for pushed images
+//
+// The queue itself is `crate::jobs`.
+use axum::{
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use axum_extra::headers::{authorization::Bearer, Authorization};
+use axum_extra::TypedHeader;
+use secrecy::ExposeSecret;
+use serde_json::json;
+use sqlx::PgPool;
+
+use crate::{
+    auth::extract_user_id_dual,
+    handlers::admin::AdminUser,
+    handlers::docker_auth::check_repository_permission,
+    handlers::tag_cleanup::{find_repository, internal_error, repository_not_found},
+    log_stream::LogEvent,
+    models::api_key::ApiKeyScope,
+    models::job::{Job, JobCount, JobKind, JobPage, JobQuery},
+    AppState,
+};
+
+const JOB_COLUMNS: &str = "j.id, j.kind, o.name || '/' || r.name AS repository, j.manifest_digest, j.status, j.attempts, \
+                           j.max_attempts, j.last_error, j.run_after, j.created_at, j.started_at, j.finished_at";
+
+const JOB_STATUSES: &[&str] = &["queued", "running", "succeeded", "failed"];
+
+/// Background jobs of a repository
+///
+/// Scans and other work queued for the repository's pushed images, newest first, with how many
+/// jobs of each kind are in each status. Requires pull access.
+#[utoipa::path(
+    get,
+    path = "/api/v1/repos/{namespace}/{repo_name}/jobs",
+    params(
+        ("namespace" = String, Path, description = "Organization namespace"),
+        ("repo_name" = String, Path, description = "Repository name"),
+        JobQuery
+    ),
+    responses(
+        (status = 200, description = "One page of jobs", body = JobPage),
+        (status = 400, description = "Invalid status or kind"),
+        (status = 401, description = "Authentication required"),
+        (status = 404, description = "Repository not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "repositories",
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn list_repository_jobs(
+    Path((namespace, repo_name)): Path<(String, String)>,
+    State(state): State<AppState>,
+    Query(query): Query<JobQuery>,
+    headers: HeaderMap,
+    auth: Option<TypedHeader<Authorization<Bearer>>>,
+) -> Response {
+    if let Err(response) = validate_query(&query) {
+        return response;
+    }
+    let secret = state.config.auth.jwt_secret.expose_secret().as_bytes();
+    let user_id = match extract_user_id_dual(auth, &headers, ApiKeyScope::Read, secret, &state.db_pool, state.cache.as_ref()).await {
+        Ok(id) => id,
+        Err(status) => return (status, Json(json!({"error": "Authentication required"}))).into_response(),
+    };
+    match check_repository_permission(&user_id.to_string(), &namespace, &repo_name, "pull", &state).await {
+        Ok(true) => {}
+        Ok(false) => return repository_not_found(&namespace, &repo_name),
+        Err(e) => return internal_error(e),
+    }
+    let repository_id = match find_repository(&state, &namespace, &repo_name).await {
+        Ok(Some((repository_id, _))) => repository_id,
+        Ok(None) => return repository_not_found(&namespace, &repo_name),
+        Err(e) => return internal_error(e),
+    };
+
+    match load_jobs(&state.db_pool, Some(repository_id), &query).await {
+        Ok(page) => (StatusCode::OK, Json(json!(page))).into_response(),
+        Err(e) => internal_error(e),
+    }
+}
+
+/// Background jobs of the whole registry
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/jobs",
+    tag = "admin",
+    params(JobQuery),
+    responses(
+        (status = 200, description = "One page of jobs", body = JobPage),
+        (status = 400, description = "Invalid status or kind"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Not a registry administrator"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn list_jobs(
+    State(state): State<AppState>,
+    _admin: AdminUser,
+    Query(query): Query<JobQuery>,
+) -> Response {
+    if let Err(response) = validate_query(&query) {
+        return response;
+    }
+    match load_jobs(&state.db_pool, None, &query).await {
+        Ok(page) => (StatusCode::OK, Json(json!(page))).into_response(),
+        Err(e) => internal_error(e),
+    }
+}
+
+/// Retry a failed background job
+///
+/// The job is queued again with a fresh set of attempts and runs as soon as a worker is free.
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/jobs/{id}/retry",
+    tag = "admin",
+    params(
+        ("id" = i64, Path, description = "Job ID")
+    ),
+    responses(
+        (status = 200, description = "Job queued again", body = Job),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Not a registry administrator"),
+        (status = 404, description = "Job not found"),
+        (status = 409, description = "The job has not failed, or the same work is already queued"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn retry_job(
+    Path(id): Path<i64>,
+    State(state): State<AppState>,
+    admin: AdminUser,
+) -> Response {
+    let status = sqlx::query_scalar::<_, String>("SELECT status FROM background_jobs WHERE id = $1")
+        .bind(id)
+        .fetch_optional(&state.db_pool)
+        .await;
+    match status {
+        Ok(Some(status)) if status == "failed" => {}
+        Ok(Some(_)) => {
+            return (StatusCode::CONFLICT, Json(json!({
+                "error": "Only failed jobs can be retried"
+            }))).into_response()
+        }
+        Ok(None) => return (StatusCode::NOT_FOUND, Json(json!({"error": "Job not found"}))).into_response(),
+        Err(e) => return internal_error(e),
+    }
+
+    let result = sqlx::query(
+        "UPDATE background_jobs SET
+             status = 'queued', attempts = 0, max_attempts = $2, run_after = CURRENT_TIMESTAMP,
+             started_at = NULL, finished_at = NULL
+         WHERE id = $1 AND status = 'failed'",
+    )
+    .bind(id)
+    .bind(state.config.jobs.max_attempts)
+    .execute(&state.db_pool)
+    .await;
+    match result {
+        Ok(_) => {}
+        // Another job for the same manifest was queued since this one failed
+        Err(sqlx::Error::Database(e)) if e.is_unique_violation() => {
+            return (StatusCode::CONFLICT, Json(json!({
+                "error": "The same job is already queued for this manifest"
+            }))).into_response()
+        }
+        Err(e) => return internal_error(e),
+    }
+
+    let job = sqlx::query_as::<_, Job>(&format!(
+        "SELECT {} FROM background_jobs j
+         JOIN repositories r ON r.id = j.repository_id
+         JOIN organizations o ON o.id = r.organization_id
+         WHERE j.id = $1",
+        JOB_COLUMNS
+    ))
+    .bind(id)
+    .fetch_one(&state.db_pool)
+    .await;
+    match job {
+        Ok(job) => {
+            state.log_stream.publish(
+                LogEvent::audit("job.retry", Some(admin.user_id), Some(job.repository.clone()))
+                    .with_detail(format!("{} {} of {}", job.kind, job.id, job.manifest_digest)),
+            );
+            (StatusCode::OK, Json(json!(job))).into_response()
+        }
+        Err(e) => internal_error(e),
+    }
+}
+
+fn validate_query(query: &JobQuery) -> Result<(), Response> {
+    if let Some(status) = query.status.as_deref() {
+        if !JOB_STATUSES.contains(&status) {
+            return Err((StatusCode::BAD_REQUEST, Json(json!({
+                "error": format!("Invalid status: {}", status)
+            }))).into_response());
+        }
+    }
+    if let Some(kind) = query.kind.as_deref() {
+        if let Err(e) = kind.parse::<JobKind>() {
+            return Err((StatusCode::BAD_REQUEST, Json(json!({"error": e}))).into_response());
+        }
+    }
+    Ok(())
+}
+
+async fn load_jobs(pool: &PgPool, repository_id: Option<i64>, query: &JobQuery) -> Result<JobPage, sqlx::Error> {
+    let limit = query.limit.unwrap_or(30).clamp(1, 100);
+    let kind = query.kind.as_deref().and_then(|kind| kind.parse::<JobKind>().ok()).map(|kind| kind.to_string());
+
+    // One extra row tells whether there is another page
+    let mut jobs = sqlx::query_as::<_, Job>(&format!(
+        "SELECT {} FROM background_jobs j
+         JOIN repositories r ON r.id = j.repository_id
+         JOIN organizations o ON o.id = r.organization_id
+         WHERE ($1::BIGINT IS NULL OR j.repository_id = $1)
+           AND ($2::TEXT IS NULL OR j.status = $2)
+           AND ($3::TEXT IS NULL OR j.kind = $3)
+           AND ($4::BIGINT IS NULL OR j.id < $4)
+         ORDER BY j.id DESC
+         LIMIT $5",
+        JOB_COLUMNS
+    ))
+    .bind(repository_id)
+    .bind(query.status.as_deref())
+    .bind(kind)
+    .bind(query.before)
+    .bind(limit + 1)
+    .fetch_all(pool)
+    .await?;
+
+    let next_before = if jobs.len() as i64 > limit {
+        jobs.truncate(limit as usize);
+        jobs.last().map(|job| job.id)
+    } else {
+        None
+    };
+
+    let counts = sqlx::query_as::<_, JobCount>(
+        "SELECT kind, status, COUNT(*) AS count
+         FROM background_jobs
+         WHERE $1::BIGINT IS NULL OR repository_id = $1
+         GROUP BY kind, status
+         ORDER BY kind, status",
+    )
+    .bind(repository_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(JobPage { jobs, next_before, counts })
+}
@@ -0,0 +1,262 @@
+// src/handlers/log_tail.rs - Live tail of audit and access-log events over Server-Sent Events
+use std::{
+    collections::HashMap,
+    convert::Infallible,
+    net::SocketAddr,
+    time::Instant,
+};
+
+use axum::{
+    extract::{ConnectInfo, MatchedPath, Path, Query, Request, State},
+    http::{header::AUTHORIZATION, HeaderMap, Method, StatusCode},
+    middleware::Next,
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
+    Json,
+};
+use axum_extra::headers::{authorization::Bearer, Authorization};
+use axum_extra::TypedHeader;
+use base64::Engine;
+use futures::{future, stream, Stream, StreamExt};
+use secrecy::ExposeSecret;
+use serde::Deserialize;
+use serde_json::json;
+use tokio::sync::broadcast::{self, error::RecvError};
+use utoipa::IntoParams;
+
+use crate::{
+    auth::{extract_user_id, verify_token},
+    handlers::ip_access::client_ip,
+    log_stream::{LogEvent, LogEventKind, LogFilter},
+    AppState,
+};
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct TailQuery {
+    /// Only events of this kind (`audit` or `access`)
+    pub kind: Option<LogEventKind>,
+    /// Only events by this username
+    pub user: Option<String>,
+    /// Only events for this `namespace/repository`, or every repository of a namespace
+    pub repo: Option<String>,
+    /// Only this action, or actions under a dotted prefix (`manifest` matches `manifest.push`)
+    pub action: Option<String>,
+    /// Number of recent matching events to send before live ones (default 100)
+    pub replay: Option<usize>,
+}
+
+/// Stream audit and access-log events as they happen
+///
+/// Each event is sent as an SSE message whose `event` field is the kind (`audit` or
+/// `access`) and whose data is the JSON event. A `lagged` message carrying a count means
+/// the client fell behind and that many events were skipped.
+#[utoipa::path(
+    get,
+    path = "/api/v1/logs/tail",
+    tag = "logs",
+    params(TailQuery),
+    responses(
+        (status = 200, description = "text/event-stream of log events", body = LogEvent),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Not a log tail operator or administrator"),
+        (status = 404, description = "Filtered user not found")
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn tail_logs(
+    State(state): State<AppState>,
+    auth: Option<TypedHeader<Authorization<Bearer>>>,
+    Query(query): Query<TailQuery>,
+) -> Response {
+    let user_id = match extract_user_id(auth, state.config.auth.jwt_secret.expose_secret().as_bytes(), &state.db_pool).await {
+        Ok(id) => id,
+        Err(status) => return (status, Json(json!({ "error": "Unauthorized" }))).into_response(),
+    };
+
+    // Registry administrators may always tail logs
+    let is_admin = match crate::handlers::admin::find_admin(&state, user_id).await {
+        Ok(admin) => admin.is_some(),
+        Err(e) => {
+            return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({
+                "error": format!("Database error: {}", e)
+            }))).into_response()
+        }
+    };
+
+    match crate::database::queries::get_user_by_id(&state.db_pool, user_id).await {
+        Ok(Some(_)) if is_admin => {}
+        Ok(Some(user)) if state.config.log_tail.operators.contains(&user.username) => {}
+        Ok(_) => {
+            return (StatusCode::FORBIDDEN, Json(json!({
+                "error": "Only log tail operators and administrators can stream logs"
+            }))).into_response()
+        }
+        Err(e) => {
+            return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({
+                "error": format!("Database error: {}", e)
+            }))).into_response()
+        }
+    }
+
+    // Events carry user IDs, and a login name only when the credential had one
+    let filtered_user_id = match &query.user {
+        Some(username) => {
+            match sqlx::query_scalar::<_, i64>("SELECT id FROM users WHERE username = $1")
+                .bind(username)
+                .fetch_optional(&state.db_pool)
+                .await
+            {
+                Ok(Some(id)) => Some(id),
+                Ok(None) => {
+                    return (StatusCode::NOT_FOUND, Json(json!({
+                        "error": format!("User '{}' not found", username)
+                    }))).into_response()
+                }
+                Err(e) => {
+                    return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({
+                        "error": format!("Database error: {}", e)
+                    }))).into_response()
+                }
+            }
+        }
+        None => None,
+    };
+
+    let filter = LogFilter {
+        kind: query.kind,
+        user_id: filtered_user_id,
+        username: query.user,
+        repository: query.repo,
+        action: query.action,
+    };
+    let (replayed, receiver) = state.log_stream.subscribe(&filter, query.replay.unwrap_or(100));
+
+    Sse::new(event_stream(replayed, receiver, filter))
+        .keep_alive(KeepAlive::default())
+        .into_response()
+}
+
+fn event_stream(
+    replayed: Vec<LogEvent>,
+    receiver: broadcast::Receiver<LogEvent>,
+    filter: LogFilter,
+) -> impl Stream<Item = Result<Event, Infallible>> {
+    let live = stream::unfold(receiver, |mut receiver| async move {
+        match receiver.recv().await {
+            Ok(event) => Some((Ok(event), receiver)),
+            Err(RecvError::Lagged(skipped)) => Some((Err(skipped), receiver)),
+            Err(RecvError::Closed) => None,
+        }
+    });
+
+    stream::iter(replayed.into_iter().map(Ok))
+        .chain(live)
+        .filter_map(move |item| {
+            let event = match item {
+                Ok(event) if filter.matches(&event) => Some(to_sse(&event)),
+                Ok(_) => None,
+                Err(skipped) => Some(Event::default().event("lagged").data(skipped.to_string())),
+            };
+            future::ready(event.map(Ok))
+        })
+}
+
+fn to_sse(event: &LogEvent) -> Event {
+    let kind = match event.kind {
+        LogEventKind::Audit => "audit",
+        LogEventKind::Access => "access",
+    };
+    Event::default()
+        .event(kind)
+        .id(event.id.to_string())
+        .data(serde_json::to_string(event).unwrap_or_default())
+}
+
+/// Middleware publishing an access event for every request once its response is ready
+pub async fn record_access_log(
+    State(state): State<AppState>,
+    matched_path: Option<MatchedPath>,
+    path: Option<Path<HashMap<String, String>>>,
+    connect_info: Option<ConnectInfo<SocketAddr>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let started = Instant::now();
+    let method = request.method().clone();
+    let uri_path = request.uri().path().to_string();
+    let (user_id, username) = identify_principal(request.headers(), &state);
+    let peer = connect_info.map(|ConnectInfo(addr)| addr.ip());
+    let ip = client_ip(peer, request.headers(), &state.config.ip_access.trusted_proxies);
+    let repository = path.and_then(|Path(params)| repository_from_params(&params));
+
+    let response = next.run(request).await;
+
+    let route = matched_path.as_ref().map(|p| p.as_str()).unwrap_or(&uri_path);
+    let mut event = LogEvent::access(access_action(&method, route), method.as_str(), &uri_path, response.status().as_u16());
+    event.user_id = user_id;
+    event.username = username;
+    event.repository = repository;
+    event.client_ip = ip.map(|ip| match state.config.retention.anonymize_ips {
+        true => crate::retention::anonymize_ip(ip).to_string(),
+        false => ip.to_string(),
+    });
+    event.duration_ms = Some(started.elapsed().as_millis() as u64);
+    state.log_stream.publish(event);
+
+    response
+}
+
+/// `registry.pull`/`registry.push`/`registry.delete` for `/v2/` traffic, `api.<method>` otherwise
+fn access_action(method: &Method, route: &str) -> String {
+    if route.starts_with("/v2") {
+        let operation = match *method {
+            Method::GET | Method::HEAD => "pull",
+            Method::DELETE => "delete",
+            _ => "push",
+        };
+        format!("registry.{}", operation)
+    } else {
+        format!("api.{}", method.as_str().to_lowercase())
+    }
+}
+
+/// Registry routes use `:org`/`:name`, repository API routes `:namespace`/`:repo_name`
+pub(crate) fn repository_from_params(params: &HashMap<String, String>) -> Option<String> {
+    match (params.get("org"), params.get("name"), params.get("namespace"), params.get("repo_name")) {
+        (Some(org), Some(name), _, _) => Some(format!("{}/{}", org, name)),
+        (None, Some(name), _, _) if name.contains('/') => Some(name.clone()),
+        (_, _, Some(namespace), Some(repo)) => Some(format!("{}/{}", namespace, repo)),
+        _ => None,
+    }
+}
+
+/// Who made a request, without touching the database: the user ID of a session token,
+/// or the login name given to `docker login`. API keys are not resolved.
+pub(crate) fn identify_principal(headers: &HeaderMap, state: &AppState) -> (Option<i64>, Option<String>) {
+    let auth_str = match headers.get(AUTHORIZATION).and_then(|v| v.to_str().ok()) {
+        Some(value) => value,
+        None => return (None, None),
+    };
+
+    if let Some(token) = auth_str.strip_prefix("Bearer ") {
+        let user_id = verify_token(token, state.config.auth.jwt_secret.expose_secret().as_bytes())
+            .ok()
+            .and_then(|claims| claims.sub.parse().ok());
+        return (user_id, None);
+    }
+
+    if let Some(encoded) = auth_str.strip_prefix("Basic ") {
+        let username = base64::prelude::BASE64_STANDARD
+            .decode(encoded)
+            .ok()
+            .and_then(|decoded| String::from_utf8(decoded).ok())
+            .and_then(|decoded| decoded.split_once(':').map(|(user, _)| user.to_string()));
+        return (None, username);
+    }
+
+    (None, None)
+}
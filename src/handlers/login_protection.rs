@@ -0,0 +1,165 @@
+// src/handlers/login_protection.rs - Failed login tracking, backoff and temporary account lockout
+//
+// Applies to the web login and to registry Basic credentials alike. Login names that match no
+// account are counted and locked like accounts, so a locked response does not reveal which
+// names exist.
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use axum::{
+    http::{HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+
+use crate::{database::models::User, retention::loggable_ip, AppState};
+
+/// Who a login attempt was for
+pub enum LoginAccount<'a> {
+    User(&'a User),
+    /// A login name or email matching no account
+    Unknown(&'a str),
+}
+
+impl LoginAccount<'_> {
+    fn key(&self) -> String {
+        match self {
+            LoginAccount::User(user) => format!("user:{}", user.id),
+            LoginAccount::Unknown(login) => format!("name:{}", login.to_lowercase()),
+        }
+    }
+}
+
+/// Reject the attempt if the client address or the account is currently blocked.
+///
+/// Counters live in the registry cache; without one, logins are not limited.
+pub async fn check_login_allowed(state: &AppState, ip: &str, account: Option<&LoginAccount<'_>>) -> Result<(), Response> {
+    let cache = match &state.cache {
+        Some(cache) => cache,
+        None => return Ok(()),
+    };
+    let settings = &state.config.login_protection;
+
+    let ip_failures = cache.get_counter(&ip_failures_key(ip)).await.unwrap_or(0);
+    if ip_failures >= settings.ip_max_failed_attempts {
+        tracing::warn!("Login blocked for {} after {} failed attempts", loggable_ip(&state.config.retention, ip), ip_failures);
+        return Err(locked(
+            "Too many failed login attempts from this address; try again later",
+            settings.lockout_seconds,
+        ));
+    }
+
+    if let Some(account) = account {
+        if let Some(unlock_at) = cache.get_counter(&lock_key(&account.key())).await {
+            let remaining = unlock_at.saturating_sub(now()).max(1);
+            tracing::warn!("Login refused for locked account {}", account.key());
+            return Err(locked(
+                "Account temporarily locked after too many failed login attempts",
+                remaining,
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// Count a failed attempt against the address and, when known, the account; lock the account
+/// and notify its owner, if it has one, once the threshold is reached. Sleeps for an
+/// exponentially growing delay so each further guess costs more.
+pub async fn record_login_failure(state: &AppState, ip: &str, account: Option<&LoginAccount<'_>>) {
+    let cache = match &state.cache {
+        Some(cache) => cache,
+        None => return,
+    };
+    let settings = &state.config.login_protection;
+    let window = Duration::from_secs(settings.lockout_seconds);
+
+    let ip_failures = cache.increment_counter(&ip_failures_key(ip), window).await;
+    let mut failures = ip_failures;
+
+    if let Some(account) = account {
+        let key = account.key();
+        let user_failures = cache.increment_counter(&failures_key(&key), window).await;
+        failures = failures.max(user_failures);
+
+        if user_failures >= settings.max_failed_attempts {
+            cache.set_counter(&lock_key(&key), now() + settings.lockout_seconds, window).await;
+            cache.delete_counter(&failures_key(&key)).await;
+            tracing::warn!("Locked account {} after {} failed login attempts", key, user_failures);
+
+            if let LoginAccount::User(user) = account {
+                // Notify in the background so a slow mail server does not hold up the response
+                let email_service = state.email_service.clone();
+                let (email, username) = (user.email.clone(), user.username.clone());
+                let ip = loggable_ip(&state.config.retention, ip);
+                let lockout_minutes = settings.lockout_seconds.div_ceil(60);
+                tokio::spawn(async move {
+                    if let Err(e) = email_service
+                        .send_account_locked_email(&email, &username, user_failures, &ip, lockout_minutes)
+                        .await
+                    {
+                        tracing::warn!("Failed to send account lockout email: {}", e);
+                    }
+                });
+            }
+        }
+    }
+
+    tokio::time::sleep(failure_delay(failures, settings.base_delay_ms, settings.max_delay_ms)).await;
+}
+
+/// Forget the account's failures after a successful login; the address keeps its count
+pub async fn record_login_success(state: &AppState, user_id: i64) {
+    if let Some(cache) = &state.cache {
+        cache.delete_counter(&failures_key(&format!("user:{}", user_id))).await;
+    }
+}
+
+/// `base_ms` doubled for each failure after the first, capped at `max_ms`
+fn failure_delay(failures: u64, base_ms: u64, max_ms: u64) -> Duration {
+    let exponent = failures.saturating_sub(1).min(32) as u32;
+    Duration::from_millis(base_ms.saturating_mul(1u64 << exponent).min(max_ms))
+}
+
+fn ip_failures_key(ip: &str) -> String {
+    format!("login:failures:ip:{}", ip)
+}
+
+fn failures_key(account: &str) -> String {
+    format!("login:failures:{}", account)
+}
+
+/// Holds the unix time at which the lock ends
+fn lock_key(account: &str) -> String {
+    format!("login:lock:{}", account)
+}
+
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs()
+}
+
+fn locked(message: &str, retry_after: u64) -> Response {
+    let mut response = (
+        StatusCode::TOO_MANY_REQUESTS,
+        Json(serde_json::json!({
+            "error": message,
+            "retry_after": retry_after
+        })),
+    )
+        .into_response();
+    response.headers_mut().insert("Retry-After", HeaderValue::from(retry_after));
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::failure_delay;
+    use std::time::Duration;
+
+    #[test]
+    fn delay_doubles_up_to_the_cap() {
+        assert_eq!(failure_delay(1, 250, 5000), Duration::from_millis(250));
+        assert_eq!(failure_delay(3, 250, 5000), Duration::from_millis(1000));
+        assert_eq!(failure_delay(10, 250, 5000), Duration::from_millis(5000));
+        assert_eq!(failure_delay(u64::MAX, 250, 5000), Duration::from_millis(5000));
+    }
+}
@@ -1,8 +1,54 @@
 // Handlers module
+pub mod admin;
+pub mod api_scopes;
+pub mod api_usage;
+pub mod audit_export;
 pub mod auth;
+pub mod avatars;
+pub mod base_images;
+pub mod bootstrap;
+pub mod collaborators;
+pub mod conditional;
+pub mod digests;
+pub mod events;
+pub mod insights;
+pub mod invitations;
 pub mod docker_auth;
 pub mod docker_registry_v2;
+pub mod features;
+pub mod federation;
+pub mod images;
+pub mod ip_access;
+pub mod legal_holds;
+pub mod log_tail;
+pub mod login_protection;
+pub mod model_registry;
+pub mod notifications;
 // pub mod docker_registry_v2_optimized; // Already merged into docker_registry_v2.rs
+pub mod organization_secrets;
+pub mod organization_webhooks;
 pub mod organizations;
+pub mod push_hooks;
+pub mod quarantine;
+pub mod quota_tiers;
+pub mod rate_limit;
+pub mod registry_auth;
 pub mod repositories;
+pub mod repository_redirects;
+pub mod repository_stats;
+pub mod repository_webhooks;
+pub mod sbom;
+pub mod secret_findings;
+pub mod security_policies;
+pub mod security_summary;
+pub mod standby;
 pub mod storage;
+pub mod tag_cleanup;
+pub mod tags;
+pub mod takedowns;
+pub mod teams;
+pub mod transfer_limits;
+pub mod watches;
+pub mod vulnerabilities;
+pub mod jobs;
+pub mod webhooks;
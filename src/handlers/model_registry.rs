@@ -0,0 +1,669 @@
+// src/handlers/model_registry.rs - Model cards and lineage for ML model artifacts
+//
+// Both are stored as OCI referrer artifacts of the model version they describe, so they are
+// pulled, mirrored and garbage collected with it, and visible through `/v2/.../referrers`.
+use std::collections::HashMap;
+
+use axum::{
+    extract::{Path, Query, State},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use axum_extra::headers::{authorization::Bearer, Authorization};
+use axum_extra::TypedHeader;
+use bytes::Bytes;
+use chrono::{DateTime, Utc};
+use pulldown_cmark::{html, Event, Options, Parser, Tag};
+use secrecy::ExposeSecret;
+use serde_json::json;
+use sha2::{Digest, Sha256};
+
+use crate::{
+    auth::extract_user_id_dual,
+    handlers::docker_auth::check_repository_permission,
+    handlers::docker_registry_v2::{get_repository_blob, load_manifest_content, put_manifest_impl},
+    log_stream::LogEvent,
+    models::{
+        api_key::ApiKeyScope,
+        model_artifact::{
+            AttachModelCardRequest, CreateLineageRequest, LineageLink, ModelCard, ModelCardMetadata, ModelCardQuery,
+            ModelLineage,
+        },
+    },
+    AppState,
+};
+
+pub const MODEL_CARD_ARTIFACT_TYPE: &str = "application/vnd.aerugo.model.card.v1+json";
+pub const MODEL_LINEAGE_ARTIFACT_TYPE: &str = "application/vnd.aerugo.model.lineage.v1+json";
+
+const OCI_MANIFEST: &str = "application/vnd.oci.image.manifest.v1+json";
+const OCI_EMPTY: &str = "application/vnd.oci.empty.v1+json";
+const ANNOTATION_PARENT_REPOSITORY: &str = "dev.aerugo.model.parent.repository";
+const ANNOTATION_PARENT_DIGEST: &str = "dev.aerugo.model.parent.digest";
+const ANNOTATION_RELATION: &str = "dev.aerugo.model.relation";
+
+/// A manifest an artifact is attached to: a model version for cards and lineage links, an image
+/// for SBOMs
+pub(crate) struct Subject {
+    pub repository_id: i64,
+    pub digest: String,
+    pub media_type: String,
+    pub size: i64,
+}
+
+/// Attach a model card to a model version
+///
+/// The card replaces any earlier one for the version; earlier cards stay available as referrers.
+#[utoipa::path(
+    put,
+    path = "/api/v1/repos/{namespace}/{repo_name}/models/{reference}/card",
+    params(
+        ("namespace" = String, Path, description = "Organization namespace"),
+        ("repo_name" = String, Path, description = "Repository name"),
+        ("reference" = String, Path, description = "Tag or digest of the model version")
+    ),
+    request_body = AttachModelCardRequest,
+    responses(
+        (status = 201, description = "Card attached", body = ModelCard),
+        (status = 401, description = "Authentication required"),
+        (status = 403, description = "No push permission on the repository"),
+        (status = 404, description = "Repository or model version not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "models",
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn attach_model_card(
+    Path((namespace, repo_name, reference)): Path<(String, String, String)>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    auth: Option<TypedHeader<Authorization<Bearer>>>,
+    Json(request): Json<AttachModelCardRequest>,
+) -> Response {
+    let user_id = match authorize(&state, auth, &headers, ApiKeyScope::Push, &namespace, &repo_name, "push").await {
+        Ok(user_id) => user_id,
+        Err(response) => return response,
+    };
+    let subject = match resolve_subject(&state, &namespace, &repo_name, &reference).await {
+        Ok(subject) => subject,
+        Err(response) => return response,
+    };
+
+    let name = format!("{}/{}", namespace, repo_name);
+    let card = json!({ "metadata": request.metadata, "markdown": request.markdown });
+    let card_bytes = match serde_json::to_vec(&card) {
+        Ok(bytes) => bytes,
+        Err(e) => return internal_error(e),
+    };
+
+    let digest = match push_artifact(
+        &state,
+        &name,
+        &subject,
+        MODEL_CARD_ARTIFACT_TYPE,
+        Some(Bytes::from(card_bytes)),
+        json!({}),
+        Some(user_id),
+    )
+    .await
+    {
+        Ok(digest) => digest,
+        Err(response) => return response,
+    };
+
+    state.log_stream.publish(
+        LogEvent::audit("model.card", Some(user_id), Some(name)).with_detail(format!("{} -> {}", subject.digest, digest)),
+    );
+
+    let response = ModelCard {
+        subject: subject.digest,
+        digest,
+        html: render_markdown(&request.markdown),
+        metadata: request.metadata,
+        markdown: request.markdown,
+        created_at: Utc::now(),
+    };
+    (StatusCode::CREATED, Json(response)).into_response()
+}
+
+/// Get the model card of a model version, rendered to HTML
+#[utoipa::path(
+    get,
+    path = "/api/v1/repos/{namespace}/{repo_name}/models/{reference}/card",
+    params(
+        ("namespace" = String, Path, description = "Organization namespace"),
+        ("repo_name" = String, Path, description = "Repository name"),
+        ("reference" = String, Path, description = "Tag or digest of the model version"),
+        ModelCardQuery
+    ),
+    responses(
+        (status = 200, description = "The latest card of the version; an HTML page with `format=html`", body = ModelCard),
+        (status = 401, description = "Authentication required"),
+        (status = 404, description = "Repository, model version or card not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "models",
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn get_model_card(
+    Path((namespace, repo_name, reference)): Path<(String, String, String)>,
+    Query(query): Query<ModelCardQuery>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    auth: Option<TypedHeader<Authorization<Bearer>>>,
+) -> Response {
+    if let Err(response) = authorize(&state, auth, &headers, ApiKeyScope::Read, &namespace, &repo_name, "pull").await {
+        return response;
+    }
+    let subject = match resolve_subject(&state, &namespace, &repo_name, &reference).await {
+        Ok(subject) => subject,
+        Err(response) => return response,
+    };
+
+    let latest = match sqlx::query_as::<_, (String, DateTime<Utc>)>(
+        "SELECT digest, created_at FROM manifests
+         WHERE repository_id = $1 AND subject_digest = $2 AND artifact_type = $3
+         ORDER BY created_at DESC, id DESC
+         LIMIT 1",
+    )
+    .bind(subject.repository_id)
+    .bind(&subject.digest)
+    .bind(MODEL_CARD_ARTIFACT_TYPE)
+    .fetch_optional(&state.db_pool)
+    .await
+    {
+        Ok(Some(latest)) => latest,
+        Ok(None) => {
+            return (StatusCode::NOT_FOUND, Json(json!({
+                "error": format!("No model card is attached to '{}/{}:{}'", namespace, repo_name, reference)
+            }))).into_response()
+        }
+        Err(e) => return internal_error(e),
+    };
+    let (digest, created_at) = latest;
+
+    let name = format!("{}/{}", namespace, repo_name);
+    let (metadata, markdown) = match load_card(&state, &name, &digest).await {
+        Ok(card) => card,
+        Err(e) => return internal_error(e),
+    };
+    let html = render_markdown(&markdown);
+
+    if query.format.as_deref() == Some("html") {
+        return card_page(&name, &reference, &html);
+    }
+
+    Json(ModelCard {
+        subject: subject.digest,
+        digest,
+        metadata,
+        markdown,
+        html,
+        created_at,
+    })
+    .into_response()
+}
+
+/// Record that a model version derives from another one
+#[utoipa::path(
+    post,
+    path = "/api/v1/repos/{namespace}/{repo_name}/models/{reference}/lineage",
+    params(
+        ("namespace" = String, Path, description = "Organization namespace"),
+        ("repo_name" = String, Path, description = "Repository name"),
+        ("reference" = String, Path, description = "Tag or digest of the derived model version")
+    ),
+    request_body = CreateLineageRequest,
+    responses(
+        (status = 201, description = "Lineage link recorded", body = LineageLink),
+        (status = 400, description = "Missing relation or a version derived from itself"),
+        (status = 401, description = "Authentication required"),
+        (status = 403, description = "No push permission on the repository or no pull permission on the parent"),
+        (status = 404, description = "Repository or model version not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "models",
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn create_model_lineage(
+    Path((namespace, repo_name, reference)): Path<(String, String, String)>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    auth: Option<TypedHeader<Authorization<Bearer>>>,
+    Json(request): Json<CreateLineageRequest>,
+) -> Response {
+    let relation = request.relation.trim().to_lowercase();
+    if relation.is_empty() {
+        return (StatusCode::BAD_REQUEST, Json(json!({
+            "error": "relation is required, e.g. 'fine-tuned-from'"
+        }))).into_response()
+    }
+
+    let user_id = match authorize(&state, auth, &headers, ApiKeyScope::Push, &namespace, &repo_name, "push").await {
+        Ok(user_id) => user_id,
+        Err(response) => return response,
+    };
+    let subject = match resolve_subject(&state, &namespace, &repo_name, &reference).await {
+        Ok(subject) => subject,
+        Err(response) => return response,
+    };
+
+    let name = format!("{}/{}", namespace, repo_name);
+    let parent_repository = request.parent_repository.clone().unwrap_or_else(|| name.clone());
+    let Some((parent_namespace, parent_name)) = parent_repository.split_once('/') else {
+        return (StatusCode::BAD_REQUEST, Json(json!({
+            "error": "parent_repository must be namespace/name"
+        }))).into_response()
+    };
+
+    // Naming a parent reveals it exists, so it must be readable
+    match check_repository_permission(&user_id.to_string(), parent_namespace, parent_name, "pull", &state).await {
+        Ok(true) => {}
+        Ok(false) => return version_not_found(parent_namespace, parent_name, &request.parent_reference),
+        Err(e) => return internal_error(e),
+    }
+    let parent = match resolve_subject(&state, parent_namespace, parent_name, &request.parent_reference).await {
+        Ok(parent) => parent,
+        Err(response) => return response,
+    };
+
+    if parent.digest == subject.digest {
+        return (StatusCode::BAD_REQUEST, Json(json!({
+            "error": "A model version cannot derive from itself"
+        }))).into_response()
+    }
+
+    let annotations = json!({
+        ANNOTATION_PARENT_REPOSITORY: parent_repository,
+        ANNOTATION_PARENT_DIGEST: parent.digest,
+        ANNOTATION_RELATION: relation,
+    });
+    let digest = match push_artifact(&state, &name, &subject, MODEL_LINEAGE_ARTIFACT_TYPE, None, annotations, Some(user_id)).await {
+        Ok(digest) => digest,
+        Err(response) => return response,
+    };
+
+    state.log_stream.publish(
+        LogEvent::audit("model.lineage", Some(user_id), Some(name))
+            .with_detail(format!("{} {} {}@{}", subject.digest, relation, parent_repository, parent.digest)),
+    );
+
+    let link = LineageLink {
+        repository: parent_repository,
+        digest: parent.digest,
+        relation,
+        artifact_digest: digest,
+        created_at: Utc::now(),
+    };
+    (StatusCode::CREATED, Json(link)).into_response()
+}
+
+/// List the versions a model version derives from and the versions derived from it
+#[utoipa::path(
+    get,
+    path = "/api/v1/repos/{namespace}/{repo_name}/models/{reference}/lineage",
+    params(
+        ("namespace" = String, Path, description = "Organization namespace"),
+        ("repo_name" = String, Path, description = "Repository name"),
+        ("reference" = String, Path, description = "Tag or digest of the model version")
+    ),
+    responses(
+        (status = 200, description = "Parents and children of the version", body = ModelLineage),
+        (status = 401, description = "Authentication required"),
+        (status = 404, description = "Repository or model version not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "models",
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn get_model_lineage(
+    Path((namespace, repo_name, reference)): Path<(String, String, String)>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    auth: Option<TypedHeader<Authorization<Bearer>>>,
+) -> Response {
+    let user_id = match authorize(&state, auth, &headers, ApiKeyScope::Read, &namespace, &repo_name, "pull").await {
+        Ok(user_id) => user_id,
+        Err(response) => return response,
+    };
+    let subject = match resolve_subject(&state, &namespace, &repo_name, &reference).await {
+        Ok(subject) => subject,
+        Err(response) => return response,
+    };
+    let name = format!("{}/{}", namespace, repo_name);
+
+    let parents = match sqlx::query_as::<_, (String, Option<String>, Option<String>, Option<String>, DateTime<Utc>)>(
+        "SELECT digest, annotations->>$3, annotations->>$4, annotations->>$5, created_at
+         FROM manifests
+         WHERE repository_id = $1 AND subject_digest = $2 AND artifact_type = $6
+         ORDER BY created_at",
+    )
+    .bind(subject.repository_id)
+    .bind(&subject.digest)
+    .bind(ANNOTATION_PARENT_REPOSITORY)
+    .bind(ANNOTATION_PARENT_DIGEST)
+    .bind(ANNOTATION_RELATION)
+    .bind(MODEL_LINEAGE_ARTIFACT_TYPE)
+    .fetch_all(&state.db_pool)
+    .await
+    {
+        Ok(rows) => rows,
+        Err(e) => return internal_error(e),
+    };
+
+    let children = match sqlx::query_as::<_, (String, String, String, Option<String>, DateTime<Utc>)>(
+        "SELECT o.name || '/' || r.name, m.subject_digest, m.digest, m.annotations->>$3, m.created_at
+         FROM manifests m
+         JOIN repositories r ON r.id = m.repository_id
+         JOIN organizations o ON o.id = r.organization_id
+         WHERE m.artifact_type = $4
+           AND m.subject_digest IS NOT NULL
+           AND m.annotations->>$5 = $1
+           AND m.annotations->>$6 = $2
+         ORDER BY m.created_at",
+    )
+    .bind(&subject.digest)
+    .bind(&name)
+    .bind(ANNOTATION_RELATION)
+    .bind(MODEL_LINEAGE_ARTIFACT_TYPE)
+    .bind(ANNOTATION_PARENT_DIGEST)
+    .bind(ANNOTATION_PARENT_REPOSITORY)
+    .fetch_all(&state.db_pool)
+    .await
+    {
+        Ok(rows) => rows,
+        Err(e) => return internal_error(e),
+    };
+
+    let parents = parents
+        .into_iter()
+        .filter_map(|(artifact_digest, repository, digest, relation, created_at)| {
+            Some(LineageLink {
+                repository: repository?,
+                digest: digest?,
+                relation: relation.unwrap_or_default(),
+                artifact_digest,
+                created_at,
+            })
+        })
+        .collect();
+
+    // Derived versions may live in repositories the caller cannot see
+    let mut readable: HashMap<String, bool> = HashMap::new();
+    let mut visible_children = Vec::new();
+    for (repository, digest, artifact_digest, relation, created_at) in children {
+        let allowed = match readable.get(&repository) {
+            Some(allowed) => *allowed,
+            None => {
+                let (child_namespace, child_name) = repository.split_once('/').unwrap_or(("", repository.as_str()));
+                let allowed = match check_repository_permission(&user_id.to_string(), child_namespace, child_name, "pull", &state).await {
+                    Ok(allowed) => allowed,
+                    Err(e) => return internal_error(e),
+                };
+                readable.insert(repository.clone(), allowed);
+                allowed
+            }
+        };
+        if allowed {
+            visible_children.push(LineageLink {
+                repository,
+                digest,
+                relation: relation.unwrap_or_default(),
+                artifact_digest,
+                created_at,
+            });
+        }
+    }
+
+    Json(ModelLineage {
+        digest: subject.digest,
+        parents,
+        children: visible_children,
+    })
+    .into_response()
+}
+
+/// Authenticate the caller and check their permission on the repository.
+/// Repositories the caller may not read are reported as missing.
+pub(crate) async fn authorize(
+    state: &AppState,
+    auth: Option<TypedHeader<Authorization<Bearer>>>,
+    headers: &HeaderMap,
+    scope: ApiKeyScope,
+    namespace: &str,
+    repo_name: &str,
+    operation: &str,
+) -> Result<i64, Response> {
+    let secret = state.config.auth.jwt_secret.expose_secret().as_bytes();
+    let user_id = match extract_user_id_dual(auth, headers, scope, secret, &state.db_pool, state.cache.as_ref()).await {
+        Ok(id) => id,
+        Err(StatusCode::FORBIDDEN) => {
+            return Err((StatusCode::FORBIDDEN, Json(json!({
+                "error": format!("API key is not scoped for {} access", scope)
+            }))).into_response())
+        }
+        Err(_) => {
+            return Err((StatusCode::UNAUTHORIZED, Json(json!({
+                "error": "Authentication required"
+            }))).into_response())
+        }
+    };
+
+    let uid = user_id.to_string();
+    match check_repository_permission(&uid, namespace, repo_name, operation, state).await {
+        Ok(true) => return Ok(user_id),
+        Ok(false) => {}
+        Err(e) => return Err(internal_error(e)),
+    }
+    if operation == "pull" {
+        return Err(repository_not_found(namespace, repo_name));
+    }
+    match check_repository_permission(&uid, namespace, repo_name, "pull", state).await {
+        Ok(true) => Err((StatusCode::FORBIDDEN, Json(json!({
+            "error": format!("You don't have permission to push to '{}/{}'", namespace, repo_name)
+        }))).into_response()),
+        Ok(false) => Err(repository_not_found(namespace, repo_name)),
+        Err(e) => Err(internal_error(e)),
+    }
+}
+
+/// Resolve a tag or digest to the manifest of a model version
+async fn resolve_subject(state: &AppState, namespace: &str, repo_name: &str, reference: &str) -> Result<Subject, Response> {
+    let row = sqlx::query_as::<_, (i64, String, String, i64)>(
+        "SELECT r.id, m.digest, m.media_type, m.size
+         FROM repositories r
+         JOIN organizations o ON o.id = r.organization_id
+         JOIN manifests m ON m.repository_id = r.id
+         WHERE o.name = $1 AND r.name = $2
+           AND (m.digest = $3 OR m.id = (SELECT t.manifest_id FROM tags t WHERE t.repository_id = r.id AND t.name = $3))
+         LIMIT 1",
+    )
+    .bind(namespace)
+    .bind(repo_name)
+    .bind(reference)
+    .fetch_optional(&state.db_pool)
+    .await
+    .map_err(internal_error)?;
+
+    match row {
+        Some((repository_id, digest, media_type, size)) => Ok(Subject { repository_id, digest, media_type, size }),
+        None => Err(version_not_found(namespace, repo_name, reference)),
+    }
+}
+
+/// Store an optional JSON payload and push an OCI artifact manifest referring to `subject`.
+/// Returns the artifact's digest.
+pub(crate) async fn push_artifact(
+    state: &AppState,
+    name: &str,
+    subject: &Subject,
+    artifact_type: &str,
+    payload: Option<Bytes>,
+    annotations: serde_json::Value,
+    user_id: Option<i64>,
+) -> Result<String, Response> {
+    let empty = store_blob(state, name, subject.repository_id, OCI_EMPTY, Bytes::from_static(b"{}")).await?;
+    let layer = match payload {
+        Some(payload) => store_blob(state, name, subject.repository_id, artifact_type, payload).await?,
+        None => empty.clone(),
+    };
+
+    let mut annotations = annotations;
+    if let Some(map) = annotations.as_object_mut() {
+        map.insert("org.opencontainers.image.created".to_string(), json!(Utc::now().to_rfc3339()));
+    }
+
+    let manifest = json!({
+        "schemaVersion": 2,
+        "mediaType": OCI_MANIFEST,
+        "artifactType": artifact_type,
+        "config": empty,
+        "layers": [layer],
+        "subject": {
+            "mediaType": subject.media_type,
+            "digest": subject.digest,
+            "size": subject.size,
+        },
+        "annotations": annotations,
+    });
+    let body = manifest.to_string();
+    let digest = format!("sha256:{}", hex::encode(Sha256::digest(body.as_bytes())));
+
+    let mut headers = HeaderMap::new();
+    headers.insert(header::CONTENT_TYPE, HeaderValue::from_static(OCI_MANIFEST));
+    let response = put_manifest_impl(state, name, &digest, headers, body, user_id).await.into_response();
+    if !response.status().is_success() {
+        return Err(response);
+    }
+    Ok(digest)
+}
+
+/// Store a blob in a repository and return its descriptor
+async fn store_blob(
+    state: &AppState,
+    name: &str,
+    repository_id: i64,
+    media_type: &str,
+    content: Bytes,
+) -> Result<serde_json::Value, Response> {
+    let digest = format!("sha256:{}", hex::encode(Sha256::digest(&content)));
+    let size = content.len() as i64;
+
+    state
+        .storage
+        .put_blob(&format!("{}/{}", name, digest), content)
+        .await
+        .map_err(internal_error)?;
+    sqlx::query(
+        "INSERT INTO manifests (repository_id, digest, media_type, size)
+         VALUES ($1, $2, $3, $4)
+         ON CONFLICT (repository_id, digest) DO NOTHING",
+    )
+    .bind(repository_id)
+    .bind(&digest)
+    .bind(media_type)
+    .bind(size)
+    .execute(&state.db_pool)
+    .await
+    .map_err(internal_error)?;
+
+    Ok(json!({ "mediaType": media_type, "digest": digest, "size": size }))
+}
+
+/// Metadata and Markdown of a stored card artifact
+async fn load_card(state: &AppState, name: &str, digest: &str) -> anyhow::Result<(ModelCardMetadata, String)> {
+    let manifest = load_manifest_content(state, name, digest)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("model card manifest {} is missing", digest))?;
+    let manifest: serde_json::Value = serde_json::from_slice(&manifest)?;
+    let layer = manifest
+        .pointer("/layers/0/digest")
+        .and_then(|d| d.as_str())
+        .ok_or_else(|| anyhow::anyhow!("model card {} has no content layer", digest))?;
+
+    let content = get_repository_blob(state, name, layer)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("model card content {} is missing", layer))?;
+    let card: serde_json::Value = serde_json::from_slice(&content)?;
+    let metadata = serde_json::from_value(card.get("metadata").cloned().unwrap_or_default()).unwrap_or_default();
+    let markdown = card.get("markdown").and_then(|m| m.as_str()).unwrap_or_default().to_string();
+    Ok((metadata, markdown))
+}
+
+/// Render Markdown to HTML that is safe to embed: raw HTML is shown as text and
+/// `javascript:` links are dropped
+fn render_markdown(markdown: &str) -> String {
+    let events = Parser::new_ext(markdown, Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH).map(|event| match event {
+        Event::Html(raw) => Event::Text(raw),
+        Event::Start(Tag::Link(kind, url, title)) if is_script_url(&url) => Event::Start(Tag::Link(kind, "#".into(), title)),
+        Event::Start(Tag::Image(kind, url, title)) if is_script_url(&url) => Event::Start(Tag::Image(kind, "#".into(), title)),
+        other => other,
+    });
+
+    let mut rendered = String::new();
+    html::push_html(&mut rendered, events);
+    rendered
+}
+
+fn is_script_url(url: &str) -> bool {
+    let url = url.trim().to_ascii_lowercase();
+    url.starts_with("javascript:") || url.starts_with("vbscript:") || url.starts_with("data:text/html")
+}
+
+fn card_page(name: &str, reference: &str, body: &str) -> Response {
+    let title = format!("{}:{}", name, reference).replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;");
+    let page = format!(
+        "<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"><title>{}</title></head><body>\n{}</body></html>\n",
+        title, body
+    );
+    (
+        [
+            (header::CONTENT_TYPE, "text/html; charset=utf-8"),
+            (header::CONTENT_SECURITY_POLICY, "default-src 'none'; img-src https: data:; style-src 'unsafe-inline'"),
+        ],
+        page,
+    )
+        .into_response()
+}
+
+fn repository_not_found(namespace: &str, repo_name: &str) -> Response {
+    (StatusCode::NOT_FOUND, Json(json!({
+        "error": format!("Repository '{}/{}' not found", namespace, repo_name)
+    }))).into_response()
+}
+
+fn version_not_found(namespace: &str, repo_name: &str, reference: &str) -> Response {
+    (StatusCode::NOT_FOUND, Json(json!({
+        "error": format!("Model version '{}/{}:{}' not found", namespace, repo_name, reference)
+    }))).into_response()
+}
+
+fn internal_error(e: impl std::fmt::Display) -> Response {
+    (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({
+        "error": format!("Internal error: {}", e)
+    }))).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::render_markdown;
+
+    #[test]
+    fn renders_markdown_without_active_content() {
+        let html = render_markdown("# Llama\n\n<script>alert(1)</script>\n\n[docs](javascript:alert(1)) [site](https://example.com)");
+        assert!(html.contains("<h1>Llama</h1>"));
+        assert!(!html.contains("<script>"));
+        assert!(html.contains("&lt;script&gt;"));
+        assert!(html.contains("href=\"#\""));
+        assert!(html.contains("href=\"https://example.com\""));
+    }
+}
@@ -0,0 +1,78 @@
+// src/handlers/notifications.rs - Feeds finished registry requests to distribution-style notifications
+use std::{collections::HashMap, net::SocketAddr};
+
+use axum::{
+    extract::{ConnectInfo, Path, Request, State},
+    http::{header, HeaderMap},
+    middleware::Next,
+    response::Response,
+};
+
+use crate::{
+    handlers::ip_access::client_ip,
+    handlers::log_tail::{identify_principal, repository_from_params},
+    notifications::{RegistryRequest, RequestRecord},
+    AppState,
+};
+
+/// Middleware reporting manifest and blob traffic to the endpoints of `NOTIFICATIONS_CONFIG_FILE`
+pub async fn notify_registry_events(
+    State(state): State<AppState>,
+    path: Option<Path<HashMap<String, String>>>,
+    connect_info: Option<ConnectInfo<SocketAddr>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if !state.notifier.is_enabled() {
+        return next.run(request).await;
+    }
+    let Some(repository) = path.and_then(|Path(params)| repository_from_params(&params)) else {
+        return next.run(request).await;
+    };
+
+    let headers = request.headers();
+    let method = request.method().clone();
+    let uri_path = request.uri().path().to_string();
+    let query = request.uri().query().map(str::to_string);
+    let (user_id, username) = identify_principal(headers, &state);
+    let peer = connect_info.map(|ConnectInfo(addr)| addr.ip());
+    let addr = client_ip(peer, headers, &state.config.ip_access.trusted_proxies).map(|ip| match state.config.retention.anonymize_ips {
+        true => crate::retention::anonymize_ip(ip).to_string(),
+        false => ip.to_string(),
+    });
+    let host = header_value(headers, "X-Forwarded-Host")
+        .or_else(|| header_value(headers, header::HOST.as_str()))
+        .unwrap_or_default();
+    let scheme = header_value(headers, "X-Forwarded-Proto").unwrap_or_else(|| "http".to_string());
+    let record = RequestRecord {
+        id: header_value(headers, "X-Request-Id").unwrap_or_else(|| uuid::Uuid::new_v4().to_string()),
+        addr: addr.unwrap_or_default(),
+        host: host.clone(),
+        method: method.to_string(),
+        useragent: header_value(headers, header::USER_AGENT.as_str()).unwrap_or_default(),
+    };
+
+    let response = next.run(request).await;
+
+    state.notifier.observe(
+        &state,
+        RegistryRequest {
+            method,
+            path: uri_path,
+            query,
+            status: response.status(),
+            repository,
+            digest: header_value(response.headers(), "Docker-Content-Digest"),
+            length: header_value(response.headers(), header::CONTENT_LENGTH.as_str()).and_then(|v| v.parse().ok()),
+            base_url: format!("{}://{}", scheme, host),
+            request: record,
+            user_id,
+            username,
+        },
+    );
+    response
+}
+
+fn header_value(headers: &HeaderMap, name: &str) -> Option<String> {
+    headers.get(name).and_then(|v| v.to_str().ok()).map(str::to_string)
+}
@@ -0,0 +1,319 @@
+// src/handlers/organization_secrets.rs - Secret variables of an organization for build jobs
+//
+// Values go in and never come out through the API: they are only decrypted by
+// `crate::secrets::job_secrets` for the organization's build jobs.
+use anyhow::{bail, Result};
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+use axum_extra::headers::{authorization::Bearer, Authorization};
+use axum_extra::TypedHeader;
+use secrecy::ExposeSecret;
+use sqlx::PgPool;
+
+use crate::{
+    auth::extract_user_id_dual,
+    handlers::organizations::get_user_role_in_org,
+    log_stream::LogEvent,
+    models::api_key::ApiKeyScope,
+    models::organization_secret::{OrganizationSecret, PutOrganizationSecretRequest},
+    secrets::{self, SecretCipher},
+    AppState,
+};
+
+/// Secrets per organization
+const MAX_SECRETS: i64 = 100;
+
+/// List the names of an organization's secrets
+///
+/// Values are never returned. Owners and maintainers only.
+#[utoipa::path(
+    get,
+    path = "/api/v1/organizations/{id}/secrets",
+    tag = "organizations",
+    params(
+        ("id" = i64, Path, description = "Organization ID")
+    ),
+    responses(
+        (status = 200, description = "Secrets of the organization, without values", body = Vec<OrganizationSecret>),
+        (status = 400, description = "Insufficient permissions"),
+        (status = 401, description = "Unauthorized"),
+        (status = 503, description = "SECRETS_ENCRYPTION_KEY is not configured")
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn list_organization_secrets(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    auth: Option<TypedHeader<Authorization<Bearer>>>,
+    Path(id): Path<i64>,
+) -> impl IntoResponse {
+    let secret = state.config.auth.jwt_secret.expose_secret().as_bytes();
+    let user_id = match extract_user_id_dual(auth, &headers, ApiKeyScope::Read, secret, &state.db_pool, state.cache.as_ref()).await {
+        Ok(id) => id,
+        Err(status) => {
+            return (
+                status,
+                Json(serde_json::json!({
+                    "error": "Unauthorized"
+                })),
+            );
+        }
+    };
+    if let Err(response) = cipher(&state) {
+        return response;
+    }
+
+    match list_secrets_internal(&state.db_pool, id, user_id).await {
+        Ok(secrets) => (
+            StatusCode::OK,
+            Json(serde_json::json!({
+                "secrets": secrets
+            })),
+        ),
+        Err(e) => (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({
+                "error": e.to_string()
+            })),
+        ),
+    }
+}
+
+/// Create or replace a secret of an organization
+///
+/// `name` is the environment variable the value is injected as in the organization's build
+/// jobs: upper case letters, digits and underscores, not starting with a digit or `AERUGO_`.
+/// Owners and maintainers only.
+#[utoipa::path(
+    put,
+    path = "/api/v1/organizations/{id}/secrets/{name}",
+    tag = "organizations",
+    params(
+        ("id" = i64, Path, description = "Organization ID"),
+        ("name" = String, Path, description = "Secret name")
+    ),
+    request_body = PutOrganizationSecretRequest,
+    responses(
+        (status = 200, description = "Secret stored", body = OrganizationSecret),
+        (status = 400, description = "Invalid name or value, or insufficient permissions"),
+        (status = 401, description = "Unauthorized"),
+        (status = 503, description = "SECRETS_ENCRYPTION_KEY is not configured")
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn put_organization_secret(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    auth: Option<TypedHeader<Authorization<Bearer>>>,
+    Path((id, name)): Path<(i64, String)>,
+    Json(req): Json<PutOrganizationSecretRequest>,
+) -> impl IntoResponse {
+    let secret = state.config.auth.jwt_secret.expose_secret().as_bytes();
+    let user_id = match extract_user_id_dual(auth, &headers, ApiKeyScope::Admin, secret, &state.db_pool, state.cache.as_ref()).await {
+        Ok(id) => id,
+        Err(status) => {
+            return (
+                status,
+                Json(serde_json::json!({
+                    "error": "Unauthorized"
+                })),
+            );
+        }
+    };
+    let cipher = match cipher(&state) {
+        Ok(cipher) => cipher,
+        Err(response) => return response,
+    };
+
+    match put_secret_internal(&state.db_pool, &cipher, id, &name, &req.value, user_id).await {
+        Ok(stored) => {
+            state.log_stream.publish(
+                LogEvent::audit("organization.secret.put", Some(user_id), None)
+                    .with_organization(id)
+                    .with_detail(format!("organization {} secret {}", id, name)),
+            );
+            (StatusCode::OK, Json(serde_json::to_value(&stored).unwrap_or_default()))
+        }
+        Err(e) => (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({
+                "error": e.to_string()
+            })),
+        ),
+    }
+}
+
+/// Delete a secret of an organization
+#[utoipa::path(
+    delete,
+    path = "/api/v1/organizations/{id}/secrets/{name}",
+    tag = "organizations",
+    params(
+        ("id" = i64, Path, description = "Organization ID"),
+        ("name" = String, Path, description = "Secret name")
+    ),
+    responses(
+        (status = 200, description = "Secret deleted"),
+        (status = 400, description = "Secret not found or insufficient permissions"),
+        (status = 401, description = "Unauthorized")
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn delete_organization_secret(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    auth: Option<TypedHeader<Authorization<Bearer>>>,
+    Path((id, name)): Path<(i64, String)>,
+) -> impl IntoResponse {
+    let secret = state.config.auth.jwt_secret.expose_secret().as_bytes();
+    let user_id = match extract_user_id_dual(auth, &headers, ApiKeyScope::Admin, secret, &state.db_pool, state.cache.as_ref()).await {
+        Ok(id) => id,
+        Err(status) => {
+            return (
+                status,
+                Json(serde_json::json!({
+                    "error": "Unauthorized"
+                })),
+            );
+        }
+    };
+
+    match delete_secret_internal(&state.db_pool, id, &name, user_id).await {
+        Ok(()) => {
+            state.log_stream.publish(
+                LogEvent::audit("organization.secret.delete", Some(user_id), None)
+                    .with_organization(id)
+                    .with_detail(format!("organization {} secret {}", id, name)),
+            );
+            (
+                StatusCode::OK,
+                Json(serde_json::json!({
+                    "message": "Secret deleted"
+                })),
+            )
+        }
+        Err(e) => (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({
+                "error": e.to_string()
+            })),
+        ),
+    }
+}
+
+fn cipher(state: &AppState) -> Result<SecretCipher, (StatusCode, Json<serde_json::Value>)> {
+    match SecretCipher::from_settings(&state.config.secrets) {
+        Ok(Some(cipher)) => Ok(cipher),
+        Ok(None) => Err((
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(serde_json::json!({
+                "error": "Organization secrets are disabled: SECRETS_ENCRYPTION_KEY is not configured"
+            })),
+        )),
+        Err(e) => {
+            tracing::error!("Invalid secrets encryption key: {}", e);
+            Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({
+                    "error": "Internal server error"
+                })),
+            ))
+        }
+    }
+}
+
+// Internal database functions
+async fn ensure_can_manage_secrets(pool: &PgPool, org_id: i64, user_id: i64) -> Result<()> {
+    let user_role = get_user_role_in_org(pool, org_id, user_id).await?;
+    if !user_role.map(|r| r.can_manage_organization()).unwrap_or(false) {
+        bail!("Insufficient permissions to manage organization secrets");
+    }
+    Ok(())
+}
+
+async fn list_secrets_internal(pool: &PgPool, org_id: i64, user_id: i64) -> Result<Vec<OrganizationSecret>> {
+    ensure_can_manage_secrets(pool, org_id, user_id).await?;
+
+    let secrets = sqlx::query_as::<_, OrganizationSecret>(
+        "SELECT name, created_by, created_at, updated_by, updated_at
+         FROM organization_secrets
+         WHERE organization_id = $1
+         ORDER BY name",
+    )
+    .bind(org_id)
+    .fetch_all(pool)
+    .await?;
+    Ok(secrets)
+}
+
+async fn put_secret_internal(
+    pool: &PgPool,
+    cipher: &SecretCipher,
+    org_id: i64,
+    name: &str,
+    value: &str,
+    user_id: i64,
+) -> Result<OrganizationSecret> {
+    ensure_can_manage_secrets(pool, org_id, user_id).await?;
+    if !secrets::is_valid_name(name) {
+        bail!("Invalid secret name '{}': use upper case letters, digits and underscores, not starting with a digit or AERUGO_", name);
+    }
+    if value.is_empty() || value.len() > secrets::MAX_SECRET_BYTES {
+        bail!("Secret values must be 1 to {} bytes", secrets::MAX_SECRET_BYTES);
+    }
+
+    let count = sqlx::query_scalar::<_, i64>(
+        "SELECT COUNT(*) FROM organization_secrets WHERE organization_id = $1 AND name <> $2",
+    )
+    .bind(org_id)
+    .bind(name)
+    .fetch_one(pool)
+    .await?;
+    if count >= MAX_SECRETS {
+        bail!("An organization can have at most {} secrets", MAX_SECRETS);
+    }
+
+    let (ciphertext, nonce) = cipher.seal(org_id, name, value)?;
+    let stored = sqlx::query_as::<_, OrganizationSecret>(
+        "INSERT INTO organization_secrets (organization_id, name, ciphertext, nonce, created_by, updated_by)
+         VALUES ($1, $2, $3, $4, $5, $5)
+         ON CONFLICT (organization_id, name) DO UPDATE SET
+             ciphertext = EXCLUDED.ciphertext,
+             nonce = EXCLUDED.nonce,
+             updated_by = EXCLUDED.updated_by,
+             updated_at = CURRENT_TIMESTAMP
+         RETURNING name, created_by, created_at, updated_by, updated_at",
+    )
+    .bind(org_id)
+    .bind(name)
+    .bind(ciphertext)
+    .bind(nonce)
+    .bind(user_id)
+    .fetch_one(pool)
+    .await?;
+    Ok(stored)
+}
+
+async fn delete_secret_internal(pool: &PgPool, org_id: i64, name: &str, user_id: i64) -> Result<()> {
+    ensure_can_manage_secrets(pool, org_id, user_id).await?;
+
+    let result = sqlx::query("DELETE FROM organization_secrets WHERE organization_id = $1 AND name = $2")
+        .bind(org_id)
+        .bind(name)
+        .execute(pool)
+        .await?;
+    if result.rows_affected() == 0 {
+        bail!("Secret not found");
+    }
+    Ok(())
+}
@@ -0,0 +1,670 @@
+// src/handlers/organization_webhooks.rs - Webhook endpoints registered by an organization
+//
+// Deliveries are made by `crate::webhooks::delivery` for audit events of any repository in the
+// organization; these handlers manage the endpoints and expose their recorded delivery attempts.
+use anyhow::{bail, Result};
+use axum::{
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+use axum_extra::headers::{authorization::Bearer, Authorization};
+use axum_extra::TypedHeader;
+use secrecy::ExposeSecret;
+use sqlx::PgPool;
+
+use crate::{
+    auth::extract_user_id_dual,
+    handlers::organizations::get_user_role_in_org,
+    log_stream::LogEvent,
+    models::api_key::ApiKeyScope,
+    models::webhook::{
+        CreateOrganizationWebhookRequest, OrganizationWebhook, UpdateOrganizationWebhookRequest, WebhookDelivery,
+        WebhookDeliveryPage, WebhookDeliveryQuery,
+    },
+    webhooks::delivery,
+    AppState,
+};
+
+/// Webhooks per organization
+const MAX_WEBHOOKS: i64 = 20;
+
+const WEBHOOK_COLUMNS: &str = "id, organization_id, url, events, format, active, secret IS NOT NULL AS has_secret,
+     created_by, created_at, updated_at, last_delivery_at, last_delivery_status, last_delivery_error";
+
+const DELIVERY_COLUMNS: &str = "id, guid::TEXT AS guid, event, attempt, redelivery, status_code, duration_ms, response_body,
+     error, delivered_at, payload";
+
+/// Register a webhook for events in every repository of an organization
+///
+/// Each matching audit event is POSTed as JSON and signed like every outgoing webhook; see
+/// `GET /api/v1/webhooks/signing-keys`. Owners only.
+#[utoipa::path(
+    post,
+    path = "/api/v1/organizations/{id}/webhooks",
+    tag = "organizations",
+    params(
+        ("id" = i64, Path, description = "Organization ID")
+    ),
+    request_body = CreateOrganizationWebhookRequest,
+    responses(
+        (status = 201, description = "Webhook registered", body = OrganizationWebhook),
+        (status = 400, description = "Invalid URL or event filter, or not an owner"),
+        (status = 401, description = "Unauthorized")
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn create_organization_webhook(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    auth: Option<TypedHeader<Authorization<Bearer>>>,
+    Path(id): Path<i64>,
+    Json(req): Json<CreateOrganizationWebhookRequest>,
+) -> impl IntoResponse {
+    let secret = state.config.auth.jwt_secret.expose_secret().as_bytes();
+    let user_id = match extract_user_id_dual(auth, &headers, ApiKeyScope::Push, secret, &state.db_pool, state.cache.as_ref()).await {
+        Ok(id) => id,
+        Err(status) => {
+            return (
+                status,
+                Json(serde_json::json!({
+                    "error": "Unauthorized"
+                })),
+            );
+        }
+    };
+
+    match create_webhook_internal(&state.db_pool, id, user_id, req).await {
+        Ok(webhook) => {
+            state.log_stream.publish(
+                LogEvent::audit("organization.webhook.create", Some(user_id), None)
+                    .with_organization(id)
+                    .with_detail(format!("organization {} webhook {} -> {}", id, webhook.id, webhook.url)),
+            );
+            (StatusCode::CREATED, Json(serde_json::to_value(&webhook).unwrap_or_default()))
+        }
+        Err(e) => (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({
+                "error": e.to_string()
+            })),
+        ),
+    }
+}
+
+/// List an organization's webhooks with the outcome of their last delivery
+#[utoipa::path(
+    get,
+    path = "/api/v1/organizations/{id}/webhooks",
+    tag = "organizations",
+    params(
+        ("id" = i64, Path, description = "Organization ID")
+    ),
+    responses(
+        (status = 200, description = "Webhooks of the organization", body = Vec<OrganizationWebhook>),
+        (status = 400, description = "Not an owner"),
+        (status = 401, description = "Unauthorized")
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn list_organization_webhooks(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    auth: Option<TypedHeader<Authorization<Bearer>>>,
+    Path(id): Path<i64>,
+) -> impl IntoResponse {
+    let secret = state.config.auth.jwt_secret.expose_secret().as_bytes();
+    let user_id = match extract_user_id_dual(auth, &headers, ApiKeyScope::Read, secret, &state.db_pool, state.cache.as_ref()).await {
+        Ok(id) => id,
+        Err(status) => {
+            return (
+                status,
+                Json(serde_json::json!({
+                    "error": "Unauthorized"
+                })),
+            );
+        }
+    };
+
+    match list_webhooks_internal(&state.db_pool, id, user_id).await {
+        Ok(webhooks) => (
+            StatusCode::OK,
+            Json(serde_json::json!({
+                "webhooks": webhooks
+            })),
+        ),
+        Err(e) => (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({
+                "error": e.to_string()
+            })),
+        ),
+    }
+}
+
+/// Get one webhook of an organization
+#[utoipa::path(
+    get,
+    path = "/api/v1/organizations/{id}/webhooks/{webhook_id}",
+    tag = "organizations",
+    params(
+        ("id" = i64, Path, description = "Organization ID"),
+        ("webhook_id" = i64, Path, description = "Webhook ID")
+    ),
+    responses(
+        (status = 200, description = "Webhook", body = OrganizationWebhook),
+        (status = 400, description = "Webhook not found or not an owner"),
+        (status = 401, description = "Unauthorized")
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn get_organization_webhook(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    auth: Option<TypedHeader<Authorization<Bearer>>>,
+    Path((id, webhook_id)): Path<(i64, i64)>,
+) -> impl IntoResponse {
+    let secret = state.config.auth.jwt_secret.expose_secret().as_bytes();
+    let user_id = match extract_user_id_dual(auth, &headers, ApiKeyScope::Read, secret, &state.db_pool, state.cache.as_ref()).await {
+        Ok(id) => id,
+        Err(status) => {
+            return (
+                status,
+                Json(serde_json::json!({
+                    "error": "Unauthorized"
+                })),
+            );
+        }
+    };
+
+    match get_webhook_internal(&state.db_pool, id, webhook_id, user_id).await {
+        Ok(webhook) => (StatusCode::OK, Json(serde_json::to_value(&webhook).unwrap_or_default())),
+        Err(e) => (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({
+                "error": e.to_string()
+            })),
+        ),
+    }
+}
+
+/// Change a webhook's URL, secret, event filter or whether it is active
+#[utoipa::path(
+    put,
+    path = "/api/v1/organizations/{id}/webhooks/{webhook_id}",
+    tag = "organizations",
+    params(
+        ("id" = i64, Path, description = "Organization ID"),
+        ("webhook_id" = i64, Path, description = "Webhook ID")
+    ),
+    request_body = UpdateOrganizationWebhookRequest,
+    responses(
+        (status = 200, description = "Webhook updated", body = OrganizationWebhook),
+        (status = 400, description = "Invalid URL or event filter, webhook not found or not an owner"),
+        (status = 401, description = "Unauthorized")
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn update_organization_webhook(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    auth: Option<TypedHeader<Authorization<Bearer>>>,
+    Path((id, webhook_id)): Path<(i64, i64)>,
+    Json(req): Json<UpdateOrganizationWebhookRequest>,
+) -> impl IntoResponse {
+    let secret = state.config.auth.jwt_secret.expose_secret().as_bytes();
+    let user_id = match extract_user_id_dual(auth, &headers, ApiKeyScope::Push, secret, &state.db_pool, state.cache.as_ref()).await {
+        Ok(id) => id,
+        Err(status) => {
+            return (
+                status,
+                Json(serde_json::json!({
+                    "error": "Unauthorized"
+                })),
+            );
+        }
+    };
+
+    match update_webhook_internal(&state.db_pool, id, webhook_id, user_id, req).await {
+        Ok(webhook) => {
+            state.log_stream.publish(
+                LogEvent::audit("organization.webhook.update", Some(user_id), None)
+                    .with_organization(id)
+                    .with_detail(format!("organization {} webhook {}", id, webhook_id)),
+            );
+            (StatusCode::OK, Json(serde_json::to_value(&webhook).unwrap_or_default()))
+        }
+        Err(e) => (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({
+                "error": e.to_string()
+            })),
+        ),
+    }
+}
+
+/// Remove a webhook from an organization
+#[utoipa::path(
+    delete,
+    path = "/api/v1/organizations/{id}/webhooks/{webhook_id}",
+    tag = "organizations",
+    params(
+        ("id" = i64, Path, description = "Organization ID"),
+        ("webhook_id" = i64, Path, description = "Webhook ID")
+    ),
+    responses(
+        (status = 200, description = "Webhook deleted"),
+        (status = 400, description = "Webhook not found or not an owner"),
+        (status = 401, description = "Unauthorized")
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn delete_organization_webhook(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    auth: Option<TypedHeader<Authorization<Bearer>>>,
+    Path((id, webhook_id)): Path<(i64, i64)>,
+) -> impl IntoResponse {
+    let secret = state.config.auth.jwt_secret.expose_secret().as_bytes();
+    let user_id = match extract_user_id_dual(auth, &headers, ApiKeyScope::Push, secret, &state.db_pool, state.cache.as_ref()).await {
+        Ok(id) => id,
+        Err(status) => {
+            return (
+                status,
+                Json(serde_json::json!({
+                    "error": "Unauthorized"
+                })),
+            );
+        }
+    };
+
+    match delete_webhook_internal(&state.db_pool, id, webhook_id, user_id).await {
+        Ok(()) => {
+            state.log_stream.publish(
+                LogEvent::audit("organization.webhook.delete", Some(user_id), None)
+                    .with_organization(id)
+                    .with_detail(format!("organization {} webhook {}", id, webhook_id)),
+            );
+            (
+                StatusCode::OK,
+                Json(serde_json::json!({
+                    "message": "Webhook deleted"
+                })),
+            )
+        }
+        Err(e) => (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({
+                "error": e.to_string()
+            })),
+        ),
+    }
+}
+
+/// Recent delivery attempts of a webhook, newest first
+///
+/// Each attempt keeps the payload, the response status and time, and the start of the response
+/// body, for 30 days. Owners only.
+#[utoipa::path(
+    get,
+    path = "/api/v1/organizations/{id}/webhooks/{webhook_id}/deliveries",
+    tag = "organizations",
+    params(
+        ("id" = i64, Path, description = "Organization ID"),
+        ("webhook_id" = i64, Path, description = "Webhook ID"),
+        WebhookDeliveryQuery
+    ),
+    responses(
+        (status = 200, description = "One page of delivery attempts", body = WebhookDeliveryPage),
+        (status = 400, description = "Webhook not found or not an owner"),
+        (status = 401, description = "Unauthorized")
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn list_organization_webhook_deliveries(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    auth: Option<TypedHeader<Authorization<Bearer>>>,
+    Path((id, webhook_id)): Path<(i64, i64)>,
+    Query(query): Query<WebhookDeliveryQuery>,
+) -> impl IntoResponse {
+    let secret = state.config.auth.jwt_secret.expose_secret().as_bytes();
+    let user_id = match extract_user_id_dual(auth, &headers, ApiKeyScope::Read, secret, &state.db_pool, state.cache.as_ref()).await {
+        Ok(id) => id,
+        Err(status) => {
+            return (
+                status,
+                Json(serde_json::json!({
+                    "error": "Unauthorized"
+                })),
+            );
+        }
+    };
+
+    let result = match get_webhook_internal(&state.db_pool, id, webhook_id, user_id).await {
+        Ok(_) => load_deliveries(&state.db_pool, false, webhook_id, &query).await,
+        Err(e) => Err(e),
+    };
+    match result {
+        Ok(page) => (StatusCode::OK, Json(serde_json::to_value(&page).unwrap_or_default())),
+        Err(e) => (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({
+                "error": e.to_string()
+            })),
+        ),
+    }
+}
+
+/// Send a recorded delivery again
+///
+/// The attempt's payload is POSTed once more, with the same `X-Aerugo-Delivery` ID, to the
+/// webhook's current URL and signed with its current secret. Responds with the new attempt.
+/// Owners only.
+#[utoipa::path(
+    post,
+    path = "/api/v1/organizations/{id}/webhooks/{webhook_id}/deliveries/{delivery_id}/redeliver",
+    tag = "organizations",
+    params(
+        ("id" = i64, Path, description = "Organization ID"),
+        ("webhook_id" = i64, Path, description = "Webhook ID"),
+        ("delivery_id" = i64, Path, description = "ID of the delivery attempt to resend")
+    ),
+    responses(
+        (status = 200, description = "The redelivery attempt", body = WebhookDelivery),
+        (status = 400, description = "Webhook or delivery not found, or not an owner"),
+        (status = 401, description = "Unauthorized")
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn redeliver_organization_webhook_delivery(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    auth: Option<TypedHeader<Authorization<Bearer>>>,
+    Path((id, webhook_id, delivery_id)): Path<(i64, i64, i64)>,
+) -> impl IntoResponse {
+    let secret = state.config.auth.jwt_secret.expose_secret().as_bytes();
+    let user_id = match extract_user_id_dual(auth, &headers, ApiKeyScope::Push, secret, &state.db_pool, state.cache.as_ref()).await {
+        Ok(id) => id,
+        Err(status) => {
+            return (
+                status,
+                Json(serde_json::json!({
+                    "error": "Unauthorized"
+                })),
+            );
+        }
+    };
+
+    let result = match get_webhook_internal(&state.db_pool, id, webhook_id, user_id).await {
+        Ok(_) => redeliver_internal(&state, false, webhook_id, delivery_id).await,
+        Err(e) => Err(e),
+    };
+    match result {
+        Ok(delivery) => {
+            tracing::info!("Delivery {} of webhook {} resent by user {}", delivery_id, webhook_id, user_id);
+            (StatusCode::OK, Json(serde_json::to_value(&delivery).unwrap_or_default()))
+        }
+        Err(e) => (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({
+                "error": e.to_string()
+            })),
+        ),
+    }
+}
+
+// Internal database functions
+
+/// A page of a webhook's delivery attempts; the caller has checked access to the webhook
+pub(crate) async fn load_deliveries(
+    pool: &PgPool,
+    repository_scoped: bool,
+    webhook_id: i64,
+    query: &WebhookDeliveryQuery,
+) -> Result<WebhookDeliveryPage> {
+    let limit = query.limit.unwrap_or(30).clamp(1, 100);
+    let column = if repository_scoped { "repository_webhook_id" } else { "organization_webhook_id" };
+
+    // One extra row tells whether there is another page
+    let mut deliveries = sqlx::query_as::<_, WebhookDelivery>(&format!(
+        "SELECT {} FROM webhook_deliveries
+         WHERE {} = $1 AND ($2::BIGINT IS NULL OR id < $2)
+         ORDER BY id DESC
+         LIMIT $3",
+        DELIVERY_COLUMNS, column
+    ))
+    .bind(webhook_id)
+    .bind(query.before)
+    .bind(limit + 1)
+    .fetch_all(pool)
+    .await?;
+
+    let next_before = if deliveries.len() as i64 > limit {
+        deliveries.truncate(limit as usize);
+        deliveries.last().map(|delivery| delivery.id)
+    } else {
+        None
+    };
+    Ok(WebhookDeliveryPage { deliveries, next_before })
+}
+
+/// Resend a delivery and read back the new attempt; the caller has checked access to the webhook
+pub(crate) async fn redeliver_internal(
+    state: &AppState,
+    repository_scoped: bool,
+    webhook_id: i64,
+    delivery_id: i64,
+) -> Result<WebhookDelivery> {
+    let Some(attempt_id) = delivery::redeliver(state, repository_scoped, webhook_id, delivery_id).await? else {
+        bail!("Delivery not found");
+    };
+
+    let delivery = sqlx::query_as::<_, WebhookDelivery>(&format!(
+        "SELECT {} FROM webhook_deliveries WHERE id = $1",
+        DELIVERY_COLUMNS
+    ))
+    .bind(attempt_id)
+    .fetch_one(&state.db_pool)
+    .await?;
+    Ok(delivery)
+}
+
+async fn ensure_can_manage_webhooks(pool: &PgPool, org_id: i64, user_id: i64) -> Result<()> {
+    let user_role = get_user_role_in_org(pool, org_id, user_id).await?;
+    if !user_role.map(|r| r.can_manage_webhooks()).unwrap_or(false) {
+        bail!("Only organization owners can manage webhooks");
+    }
+    Ok(())
+}
+
+pub(crate) fn validate_url(url: &str) -> Result<()> {
+    match url::Url::parse(url) {
+        Ok(parsed) if matches!(parsed.scheme(), "http" | "https") && parsed.host_str().is_some() => Ok(()),
+        _ => bail!("Webhook URL must be an absolute http or https URL"),
+    }
+}
+
+pub(crate) fn validate_format(format: &str) -> Result<&str> {
+    match format {
+        "json" | "slack" | "discord" | "cloudevents" => Ok(format),
+        _ => bail!("Webhook format must be json, slack, discord or cloudevents"),
+    }
+}
+
+/// Trimmed, deduplicated event filters; each is a dotted action name or prefix
+pub(crate) fn normalize_events(events: Vec<String>) -> Result<Vec<String>> {
+    let mut normalized: Vec<String> = Vec::with_capacity(events.len());
+    for event in events {
+        let event = event.trim().to_lowercase();
+        let valid = !event.is_empty()
+            && event.split('.').all(|part| {
+                !part.is_empty() && part.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_' || c == '-')
+            });
+        if !valid {
+            bail!("Invalid event filter '{}': use an action such as manifest.push or a prefix such as repository", event);
+        }
+        if !normalized.contains(&event) {
+            normalized.push(event);
+        }
+    }
+    Ok(normalized)
+}
+
+async fn create_webhook_internal(
+    pool: &PgPool,
+    org_id: i64,
+    user_id: i64,
+    req: CreateOrganizationWebhookRequest,
+) -> Result<OrganizationWebhook> {
+    ensure_can_manage_webhooks(pool, org_id, user_id).await?;
+    validate_url(req.url.trim())?;
+    let events = normalize_events(req.events)?;
+    let format = req.format.as_deref().map(validate_format).transpose()?.unwrap_or("json");
+
+    let count = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM organization_webhooks WHERE organization_id = $1")
+        .bind(org_id)
+        .fetch_one(pool)
+        .await?;
+    if count >= MAX_WEBHOOKS {
+        bail!("An organization can have at most {} webhooks", MAX_WEBHOOKS);
+    }
+
+    let webhook = sqlx::query_as::<_, OrganizationWebhook>(&format!(
+        "INSERT INTO organization_webhooks (organization_id, url, secret, events, format, active, created_by)
+         VALUES ($1, $2, NULLIF($3, ''), $4, $5, $6, $7)
+         RETURNING {}",
+        WEBHOOK_COLUMNS
+    ))
+    .bind(org_id)
+    .bind(req.url.trim())
+    .bind(req.secret)
+    .bind(&events)
+    .bind(format)
+    .bind(req.active.unwrap_or(true))
+    .bind(user_id)
+    .fetch_one(pool)
+    .await?;
+
+    tracing::info!("Webhook {} registered for organization {} by user {}", webhook.id, org_id, user_id);
+    Ok(webhook)
+}
+
+async fn list_webhooks_internal(pool: &PgPool, org_id: i64, user_id: i64) -> Result<Vec<OrganizationWebhook>> {
+    ensure_can_manage_webhooks(pool, org_id, user_id).await?;
+
+    let webhooks = sqlx::query_as::<_, OrganizationWebhook>(&format!(
+        "SELECT {} FROM organization_webhooks WHERE organization_id = $1 ORDER BY created_at",
+        WEBHOOK_COLUMNS
+    ))
+    .bind(org_id)
+    .fetch_all(pool)
+    .await?;
+    Ok(webhooks)
+}
+
+async fn get_webhook_internal(pool: &PgPool, org_id: i64, webhook_id: i64, user_id: i64) -> Result<OrganizationWebhook> {
+    ensure_can_manage_webhooks(pool, org_id, user_id).await?;
+
+    let webhook = sqlx::query_as::<_, OrganizationWebhook>(&format!(
+        "SELECT {} FROM organization_webhooks WHERE id = $1 AND organization_id = $2",
+        WEBHOOK_COLUMNS
+    ))
+    .bind(webhook_id)
+    .bind(org_id)
+    .fetch_optional(pool)
+    .await?;
+
+    match webhook {
+        Some(webhook) => Ok(webhook),
+        None => bail!("Webhook not found"),
+    }
+}
+
+async fn update_webhook_internal(
+    pool: &PgPool,
+    org_id: i64,
+    webhook_id: i64,
+    user_id: i64,
+    req: UpdateOrganizationWebhookRequest,
+) -> Result<OrganizationWebhook> {
+    ensure_can_manage_webhooks(pool, org_id, user_id).await?;
+    let url = req.url.map(|url| url.trim().to_string());
+    if let Some(url) = &url {
+        validate_url(url)?;
+    }
+    let events = req.events.map(normalize_events).transpose()?;
+    let format = req.format.as_deref().map(validate_format).transpose()?;
+
+    let webhook = sqlx::query_as::<_, OrganizationWebhook>(&format!(
+        "UPDATE organization_webhooks SET
+             url = COALESCE($3, url),
+             secret = CASE WHEN $4::TEXT IS NULL THEN secret ELSE NULLIF($4, '') END,
+             events = COALESCE($5, events),
+             active = COALESCE($6, active),
+             format = COALESCE($7, format),
+             updated_at = CURRENT_TIMESTAMP
+         WHERE id = $1 AND organization_id = $2
+         RETURNING {}",
+        WEBHOOK_COLUMNS
+    ))
+    .bind(webhook_id)
+    .bind(org_id)
+    .bind(url)
+    .bind(req.secret)
+    .bind(events)
+    .bind(req.active)
+    .bind(format)
+    .fetch_optional(pool)
+    .await?;
+
+    match webhook {
+        Some(webhook) => Ok(webhook),
+        None => bail!("Webhook not found"),
+    }
+}
+
+async fn delete_webhook_internal(pool: &PgPool, org_id: i64, webhook_id: i64, user_id: i64) -> Result<()> {
+    ensure_can_manage_webhooks(pool, org_id, user_id).await?;
+
+    let result = sqlx::query("DELETE FROM organization_webhooks WHERE id = $1 AND organization_id = $2")
+        .bind(webhook_id)
+        .bind(org_id)
+        .execute(pool)
+        .await?;
+    if result.rows_affected() == 0 {
+        bail!("Webhook not found");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn event_filters_are_normalized() {
+        let events = normalize_events(vec![" Manifest.Push ".into(), "repository".into(), "manifest.push".into()]).unwrap();
+        assert_eq!(events, vec!["manifest.push", "repository"]);
+
+        assert!(normalize_events(vec!["manifest.".into()]).is_err());
+        assert!(normalize_events(vec!["*".into()]).is_err());
+        assert!(validate_url("ftp://example.com/hook").is_err());
+        assert!(validate_url("https://hooks.example.com/aerugo").is_ok());
+    }
+}
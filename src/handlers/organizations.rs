@@ -1,11 +1,14 @@
 // src/handlers/organizations.rs - Fixed version with API key support
 use anyhow::{bail, Context, Result};
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
     http::{StatusCode, HeaderMap},
     response::IntoResponse,
     Json,
 };
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
 use sqlx::{FromRow, PgPool};
 use validator::Validate;
 use utoipa::ToSchema;
@@ -16,9 +19,13 @@ use secrecy::ExposeSecret;
 use crate::auth::{extract_user_id_dual, extract_user_id};
 
 use crate::{
+    log_stream::LogEvent,
+    models::api_key::ApiKeyScope,
     models::organizations::{
-        AddMemberRequest, CreateOrganizationRequest, Organization, OrganizationMember,
-        OrganizationRole, UpdateMemberRequest, UpdateOrganizationRequest,
+        AddMemberRequest, CreateOrganizationRequest, DeleteOrganizationRequest, MemberListQuery,
+        Organization, OrganizationDeletionPreview, OrganizationMember, OrganizationRole,
+        OrganizationSettings, OrganizationStats, OrganizationStatsQuery, UpdateMemberRequest,
+        UpdateOrganizationRequest, UpdateOrganizationSettingsRequest,
     },
     AppState,
 };
@@ -31,7 +38,7 @@ use crate::{
     request_body = CreateOrganizationRequest,
     responses(
         (status = 201, description = "Organization created successfully"),
-        (status = 400, description = "Validation failed or bad request"),
+        (status = 400, description = "Validation failed, invalid or reserved name, or bad request"),
         (status = 500, description = "Internal server error")
     ),
     security(
@@ -54,6 +61,14 @@ pub async fn create_organization(
             })),
         );
     }
+    if let Err(e) = crate::naming::validate_organization_name(&req.name, &state.config.naming) {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({
+                "error": e.to_string()
+            })),
+        );
+    }
 
     // Extract user ID from JWT or API key
     let secret = state.config.auth.jwt_secret.expose_secret().as_bytes();
@@ -61,6 +76,7 @@ pub async fn create_organization(
     let user_id = match extract_user_id_dual(
         auth, 
         &headers, 
+        ApiKeyScope::Admin,
         secret, 
         &state.db_pool, 
         state.cache.as_ref()
@@ -184,6 +200,7 @@ pub async fn update_organization(
     let user_id = match extract_user_id_dual(
         auth, 
         &headers, 
+        ApiKeyScope::Admin,
         secret, 
         &state.db_pool, 
         state.cache.as_ref()
@@ -218,7 +235,64 @@ pub async fn update_organization(
     }
 }
 
-// Delete organization
+/// Request a token confirming the deletion of an organization
+///
+/// Returns what the deletion would remove together with a short-lived token that
+/// `DELETE /api/v1/organizations/{id}` requires. Owners only.
+#[utoipa::path(
+    post,
+    path = "/api/v1/organizations/{id}/deletion-token",
+    tag = "organizations",
+    params(
+        ("id" = i64, Path, description = "Organization ID")
+    ),
+    responses(
+        (status = 200, description = "Deletion preview and confirmation token", body = OrganizationDeletionPreview),
+        (status = 400, description = "Not an owner, organization under legal hold, or the default organization"),
+        (status = 401, description = "Unauthorized")
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn request_organization_deletion(
+    State(state): State<AppState>,
+    auth: Option<TypedHeader<Authorization<Bearer>>>,
+    Path(id): Path<i64>,
+) -> impl IntoResponse {
+    let user_id = match extract_user_id(auth, state.config.auth.jwt_secret.expose_secret().as_bytes(), &state.db_pool).await {
+        Ok(id) => id,
+        Err(status) => {
+            return (
+                status,
+                Json(serde_json::json!({
+                    "error": "Unauthorized"
+                })),
+            );
+        }
+    };
+
+    let secret = state.config.auth.jwt_secret.expose_secret().as_bytes();
+    match preview_org_deletion_internal(&state.db_pool, secret, id, user_id).await {
+        Ok(preview) => (StatusCode::OK, Json(serde_json::to_value(&preview).unwrap_or_default())),
+        Err(e) => {
+            tracing::error!("Failed to prepare organization deletion: {}", e);
+            (
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({
+                    "error": e.to_string()
+                })),
+            )
+        }
+    }
+}
+
+/// Delete an organization
+///
+/// Requires the owner role and a token from `POST /api/v1/organizations/{id}/deletion-token`.
+/// The organization is soft-deleted and its name released; its repositories, tags, manifests,
+/// members, teams, invitations and IP rules are removed, and their stored blobs are queued
+/// for garbage collection.
 #[utoipa::path(
     delete,
     path = "/api/v1/organizations/{id}",
@@ -226,10 +300,11 @@ pub async fn update_organization(
     params(
         ("id" = i64, Path, description = "Organization ID")
     ),
+    request_body = DeleteOrganizationRequest,
     responses(
         (status = 204, description = "Organization deleted successfully"),
-        (status = 403, description = "Only owners can delete organizations"),
-        (status = 404, description = "Organization not found"),
+        (status = 400, description = "Missing, invalid or expired confirmation token, legal hold, or not an owner"),
+        (status = 401, description = "Unauthorized"),
         (status = 500, description = "Internal server error")
     ),
     security(
@@ -240,8 +315,9 @@ pub async fn delete_organization(
     State(state): State<AppState>,
     auth: Option<TypedHeader<Authorization<Bearer>>>,
     Path(id): Path<i64>,
+    req: Option<Json<DeleteOrganizationRequest>>,
 ) -> impl IntoResponse {
-    let user_id = match extract_user_id(auth, state.config.auth.jwt_secret.expose_secret().as_bytes()).await {
+    let user_id = match extract_user_id(auth, state.config.auth.jwt_secret.expose_secret().as_bytes(), &state.db_pool).await {
         Ok(id) => id,
         Err(status) => {
             return (
@@ -253,8 +329,33 @@ pub async fn delete_organization(
         }
     };
 
-    match delete_org_by_id_internal(&state.db_pool, id, user_id).await {
-        Ok(_) => (StatusCode::NO_CONTENT, Json(serde_json::json!({}))),
+    let Some(Json(req)) = req else {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({
+                "error": "confirmation_token is required; request one from POST /api/v1/organizations/{id}/deletion-token"
+            })),
+        );
+    };
+
+    let secret = state.config.auth.jwt_secret.expose_secret().as_bytes();
+    match delete_org_by_id_internal(&state.db_pool, secret, id, user_id, &req.confirmation_token).await {
+        Ok(deleted) => {
+            invalidate_deleted_org_caches(&state, &deleted).await;
+            tracing::debug!(
+                "Organization {} deleted by user {}: {} repositories, {} blobs queued for collection",
+                deleted.name, user_id, deleted.repositories.len(), deleted.queued_blobs
+            );
+            state.log_stream.publish(
+                LogEvent::audit("organization.delete", Some(user_id), None)
+                    .with_organization(id)
+                    .with_detail(format!(
+                        "org={} name={} repositories={} queued_blobs={}",
+                        id, deleted.name, deleted.repositories.len(), deleted.queued_blobs
+                    )),
+            );
+            (StatusCode::NO_CONTENT, Json(serde_json::json!({})))
+        }
         Err(e) => {
             tracing::error!("Failed to delete organization: {}", e);
             (
@@ -267,16 +368,209 @@ pub async fn delete_organization(
     }
 }
 
+/// Get an organization's repository defaults and storage quota
+///
+/// Also reports the bytes the organization currently stores. Members only.
+#[utoipa::path(
+    get,
+    path = "/api/v1/organizations/{id}/settings",
+    tag = "organizations",
+    params(
+        ("id" = i64, Path, description = "Organization ID")
+    ),
+    responses(
+        (status = 200, description = "Organization settings and current storage use", body = OrganizationSettings),
+        (status = 400, description = "Not a member of this organization"),
+        (status = 401, description = "Unauthorized")
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn get_organization_settings(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    auth: Option<TypedHeader<Authorization<Bearer>>>,
+    Path(id): Path<i64>,
+) -> impl IntoResponse {
+    let secret = state.config.auth.jwt_secret.expose_secret().as_bytes();
+    let user_id = match extract_user_id_dual(auth, &headers, ApiKeyScope::Read, secret, &state.db_pool, state.cache.as_ref()).await {
+        Ok(id) => id,
+        Err(status) => {
+            return (
+                status,
+                Json(serde_json::json!({
+                    "error": "Unauthorized"
+                })),
+            );
+        }
+    };
+
+    match get_org_settings_internal(&state.db_pool, id, user_id).await {
+        Ok((settings, used)) => (
+            StatusCode::OK,
+            Json(serde_json::json!({
+                "settings": settings,
+                "storage_used_bytes": used
+            })),
+        ),
+        Err(e) => (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({
+                "error": e.to_string()
+            })),
+        ),
+    }
+}
+
+/// Usage dashboard of an organization
+///
+/// Repository counts, storage use against the quota, pull and push counts over the last 1, 7
+/// and 30 days, a daily series and the most pulled repositories. Members only.
+#[utoipa::path(
+    get,
+    path = "/api/v1/organizations/{id}/stats",
+    tag = "organizations",
+    params(
+        ("id" = i64, Path, description = "Organization ID"),
+        OrganizationStatsQuery
+    ),
+    responses(
+        (status = 200, description = "Organization usage statistics", body = OrganizationStats),
+        (status = 400, description = "Not a member of this organization"),
+        (status = 401, description = "Unauthorized")
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn get_organization_stats(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    auth: Option<TypedHeader<Authorization<Bearer>>>,
+    Path(id): Path<i64>,
+    Query(query): Query<OrganizationStatsQuery>,
+) -> impl IntoResponse {
+    let secret = state.config.auth.jwt_secret.expose_secret().as_bytes();
+    let user_id = match extract_user_id_dual(auth, &headers, ApiKeyScope::Read, secret, &state.db_pool, state.cache.as_ref()).await {
+        Ok(id) => id,
+        Err(status) => {
+            return (
+                status,
+                Json(serde_json::json!({
+                    "error": "Unauthorized"
+                })),
+            );
+        }
+    };
+
+    let days = query.days.unwrap_or(30).clamp(1, 365);
+    match get_org_stats_internal(&state.db_pool, id, user_id, days).await {
+        Ok(stats) => (StatusCode::OK, Json(serde_json::json!(stats))),
+        Err(e) => (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({
+                "error": e.to_string()
+            })),
+        ),
+    }
+}
+
+/// Replace an organization's repository defaults and storage quota
+///
+/// Defaults apply to repositories created afterwards, including those created by `docker push`;
+/// existing repositories keep their settings. Owners and maintainers only.
+#[utoipa::path(
+    put,
+    path = "/api/v1/organizations/{id}/settings",
+    tag = "organizations",
+    params(
+        ("id" = i64, Path, description = "Organization ID")
+    ),
+    request_body = UpdateOrganizationSettingsRequest,
+    responses(
+        (status = 200, description = "Settings saved", body = OrganizationSettings),
+        (status = 400, description = "Validation failed or insufficient permissions"),
+        (status = 401, description = "Unauthorized")
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn update_organization_settings(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    auth: Option<TypedHeader<Authorization<Bearer>>>,
+    Path(id): Path<i64>,
+    Json(req): Json<UpdateOrganizationSettingsRequest>,
+) -> impl IntoResponse {
+    if let Err(validation_errors) = req.validate() {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({
+                "error": "Validation failed",
+                "details": validation_errors
+            })),
+        );
+    }
+
+    let secret = state.config.auth.jwt_secret.expose_secret().as_bytes();
+    let user_id = match extract_user_id_dual(auth, &headers, ApiKeyScope::Admin, secret, &state.db_pool, state.cache.as_ref()).await {
+        Ok(id) => id,
+        Err(status) => {
+            return (
+                status,
+                Json(serde_json::json!({
+                    "error": "Unauthorized"
+                })),
+            );
+        }
+    };
+
+    match update_org_settings_internal(&state.db_pool, id, req, user_id).await {
+        Ok(settings) => {
+            state.log_stream.publish(
+                LogEvent::audit("organization.settings", Some(user_id), None)
+                    .with_organization(id)
+                    .with_detail(format!(
+                        "org={} default_public={} keep_last={:?} retention_days={:?} quota_bytes={:?}",
+                        id,
+                        settings.default_repository_public,
+                        settings.default_tag_retention_keep_last,
+                        settings.default_tag_retention_days,
+                        settings.storage_quota_bytes
+                    )),
+            );
+            (
+                StatusCode::OK,
+                Json(serde_json::json!({
+                    "settings": settings
+                })),
+            )
+        }
+        Err(e) => {
+            tracing::error!("Failed to update organization settings: {}", e);
+            (
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({
+                    "error": e.to_string()
+                })),
+            )
+        }
+    }
+}
+
 // Get organization members
 #[utoipa::path(
     get,
     path = "/api/v1/organizations/{id}/members",
     tag = "organizations",
     params(
-        ("id" = i64, Path, description = "Organization ID")
+        ("id" = i64, Path, description = "Organization ID"),
+        MemberListQuery
     ),
     responses(
-        (status = 200, description = "Organization members retrieved successfully"),
+        (status = 200, description = "Organization members and their roles retrieved successfully"),
         (status = 403, description = "Access denied: not a member of this organization"),
         (status = 404, description = "Organization not found"),
         (status = 500, description = "Internal server error")
@@ -289,8 +583,9 @@ pub async fn get_organization_members(
     State(state): State<AppState>,
     auth: Option<TypedHeader<Authorization<Bearer>>>,
     Path(id): Path<i64>,
+    Query(query): Query<MemberListQuery>,
 ) -> impl IntoResponse {
-    let extracted_id = match extract_user_id(auth, state.config.auth.jwt_secret.expose_secret().as_bytes()).await {
+    let extracted_id = match extract_user_id(auth, state.config.auth.jwt_secret.expose_secret().as_bytes(), &state.db_pool).await {
         Ok(id) => id,
         Err(status) => {
             return (
@@ -303,7 +598,7 @@ pub async fn get_organization_members(
     };
     let user_id = Some(extracted_id);
 
-    match get_members_by_org_id_internal(&state.db_pool, id, user_id).await {
+    match get_members_by_org_id_internal(&state.db_pool, id, user_id, query.role.as_ref()).await {
         Ok(members) => (
             StatusCode::OK,
             Json(serde_json::json!({
@@ -334,7 +629,7 @@ pub async fn get_organization_members(
     responses(
         (status = 201, description = "Member added to organization successfully"),
         (status = 400, description = "User already a member or validation failed"),
-        (status = 403, description = "Insufficient permissions to add members"),
+        (status = 403, description = "Insufficient permissions to add members, or the tier's member limit reached"),
         (status = 404, description = "User or organization not found"),
         (status = 500, description = "Internal server error")
     ),
@@ -358,7 +653,7 @@ pub async fn add_organization_member(
         );
     }
 
-    let inviter_id = match extract_user_id(auth, state.config.auth.jwt_secret.expose_secret().as_bytes()).await {
+    let inviter_id = match extract_user_id(auth, state.config.auth.jwt_secret.expose_secret().as_bytes(), &state.db_pool).await {
         Ok(id) => id,
         Err(status) => {
             return (
@@ -379,6 +674,9 @@ pub async fn add_organization_member(
         ),
         Err(e) => {
             tracing::error!("Failed to add organization member: {}", e);
+            if let Some(exceeded) = e.downcast_ref::<crate::quota::QuotaExceeded>() {
+                return exceeded.api_error();
+            }
             (
                 StatusCode::BAD_REQUEST,
                 Json(serde_json::json!({
@@ -401,7 +699,7 @@ pub async fn add_organization_member(
     request_body = UpdateMemberRequest,
     responses(
         (status = 200, description = "Member role updated successfully"),
-        (status = 400, description = "Invalid role or validation failed"),
+        (status = 400, description = "Invalid role, validation failed, or the member is the last owner"),
         (status = 403, description = "Insufficient permissions to modify this member"),
         (status = 404, description = "Member or organization not found"),
         (status = 500, description = "Internal server error")
@@ -416,7 +714,7 @@ pub async fn update_member_role(
     Path((id, member_id)): Path<(i64, i64)>,
     Json(req): Json<UpdateMemberRequest>,
 ) -> impl IntoResponse {
-    let updater_id = match extract_user_id(auth, state.config.auth.jwt_secret.expose_secret().as_bytes()).await {
+    let updater_id = match extract_user_id(auth, state.config.auth.jwt_secret.expose_secret().as_bytes(), &state.db_pool).await {
         Ok(id) => id,
         Err(status) => {
             return (
@@ -431,12 +729,19 @@ pub async fn update_member_role(
     match update_member_role_by_org_id_internal(&state.db_pool, id, member_id, req, updater_id)
         .await
     {
-        Ok(member) => (
-            StatusCode::OK,
-            Json(serde_json::json!({
-                "member": member
-            })),
-        ),
+        Ok(member) => {
+            state.log_stream.publish(
+                LogEvent::audit("organization.member.role", Some(updater_id), None)
+                    .with_organization(id)
+                    .with_detail(format!("org={} member={} role={}", id, member_id, member.role)),
+            );
+            (
+                StatusCode::OK,
+                Json(serde_json::json!({
+                    "member": member
+                })),
+            )
+        }
         Err(e) => {
             tracing::error!("Failed to update member role: {}", e);
             (
@@ -460,6 +765,7 @@ pub async fn update_member_role(
     ),
     responses(
         (status = 204, description = "Member removed from organization successfully"),
+        (status = 400, description = "The member is the last owner of the organization"),
         (status = 403, description = "Insufficient permissions to remove this member"),
         (status = 404, description = "Member or organization not found"),
         (status = 500, description = "Internal server error")
@@ -473,7 +779,7 @@ pub async fn remove_organization_member(
     auth: Option<TypedHeader<Authorization<Bearer>>>,
     Path((id, member_id)): Path<(i64, i64)>,
 ) -> impl IntoResponse {
-    let remover_id = match extract_user_id(auth, state.config.auth.jwt_secret.expose_secret().as_bytes()).await {
+    let remover_id = match extract_user_id(auth, state.config.auth.jwt_secret.expose_secret().as_bytes(), &state.db_pool).await {
         Ok(id) => id,
         Err(status) => {
             return (
@@ -486,7 +792,14 @@ pub async fn remove_organization_member(
     };
 
     match remove_member_internal(&state.db_pool, id, member_id, remover_id).await {
-        Ok(_) => (StatusCode::NO_CONTENT, Json(serde_json::json!({}))),
+        Ok(_) => {
+            state.log_stream.publish(
+                LogEvent::audit("organization.member.remove", Some(remover_id), None)
+                    .with_organization(id)
+                    .with_detail(format!("org={} member={}", id, member_id)),
+            );
+            (StatusCode::NO_CONTENT, Json(serde_json::json!({})))
+        }
         Err(e) => {
             tracing::error!("Failed to remove organization member: {}", e);
             (
@@ -516,7 +829,7 @@ pub async fn list_user_organizations(
     State(state): State<AppState>,
     auth: Option<TypedHeader<Authorization<Bearer>>>,
 ) -> impl IntoResponse {
-    let user_id = match extract_user_id(auth, state.config.auth.jwt_secret.expose_secret().as_bytes()).await {
+    let user_id = match extract_user_id(auth, state.config.auth.jwt_secret.expose_secret().as_bytes(), &state.db_pool).await {
         Ok(id) => id,
         Err(status) => {
             return (
@@ -548,7 +861,7 @@ pub async fn list_user_organizations(
 }
 
 // Helper function to get user's role in organization
-async fn get_user_role_in_org(
+pub(crate) async fn get_user_role_in_org(
     pool: &PgPool,
     org_id: i64,
     user_id: i64,
@@ -564,15 +877,163 @@ async fn get_user_role_in_org(
         .fetch_optional(pool)
         .await?;
 
-    match result {
-        Some(row) => match row.role.as_str() {
-            "owner" => Ok(Some(OrganizationRole::Owner)),
-            "admin" => Ok(Some(OrganizationRole::Admin)),
-            "member" => Ok(Some(OrganizationRole::Member)),
-            _ => Ok(None),
-        },
-        None => Ok(None),
+    Ok(result.and_then(|row| row.role.parse::<OrganizationRole>().ok()))
+}
+
+/// Settings of an organization, or the defaults when it has never saved any
+pub(crate) async fn load_org_settings<'e, E>(executor: E, org_id: i64) -> Result<OrganizationSettings, sqlx::Error>
+where
+    E: sqlx::PgExecutor<'e>,
+{
+    let settings = sqlx::query_as::<_, OrganizationSettings>(
+        "SELECT organization_id, default_repository_public, default_tag_retention_keep_last,
+                default_tag_retention_days, storage_quota_bytes, download_bytes_per_second,
+                updated_by, updated_at
+         FROM organization_settings
+         WHERE organization_id = $1",
+    )
+    .bind(org_id)
+    .fetch_optional(executor)
+    .await?;
+    Ok(settings.unwrap_or_else(|| OrganizationSettings::defaults(org_id)))
+}
+
+/// Bytes of manifests and layers stored across an organization's repositories
+pub(crate) async fn org_storage_used<'e, E>(executor: E, org_id: i64) -> Result<i64, sqlx::Error>
+where
+    E: sqlx::PgExecutor<'e>,
+{
+    sqlx::query_scalar::<_, i64>(
+        "SELECT COALESCE(SUM(m.size), 0)::BIGINT
+         FROM manifests m
+         JOIN repositories r ON r.id = m.repository_id
+         WHERE r.organization_id = $1",
+    )
+    .bind(org_id)
+    .fetch_one(executor)
+    .await
+}
+
+async fn get_org_settings_internal(pool: &PgPool, org_id: i64, user_id: i64) -> Result<(OrganizationSettings, i64)> {
+    if get_user_role_in_org(pool, org_id, user_id).await?.is_none() {
+        bail!("Access denied: not a member of this organization");
+    }
+    let settings = load_org_settings(pool, org_id).await?;
+    let used = org_storage_used(pool, org_id).await?;
+    Ok((settings, used))
+}
+
+async fn get_org_stats_internal(pool: &PgPool, org_id: i64, user_id: i64, days: i32) -> Result<OrganizationStats> {
+    if get_user_role_in_org(pool, org_id, user_id).await?.is_none() {
+        bail!("Access denied: not a member of this organization");
+    }
+
+    let (repository_count, public_repository_count) = sqlx::query_as::<_, (i64, i64)>(
+        "SELECT COUNT(*), COUNT(*) FILTER (WHERE is_public) FROM repositories WHERE organization_id = $1",
+    )
+    .bind(org_id)
+    .fetch_one(pool)
+    .await?;
+    let storage_used_bytes = org_storage_used(pool, org_id).await?;
+    let storage_quota_bytes = load_org_settings(pool, org_id).await?.storage_quota_bytes;
+
+    let windows = sqlx::query_as::<_, crate::models::organizations::ActivityWindow>(
+        "SELECT w.days, COALESCE(SUM(a.pull_count), 0)::BIGINT AS pulls, COALESCE(SUM(a.push_count), 0)::BIGINT AS pushes
+         FROM UNNEST($2::INT[]) AS w(days)
+         LEFT JOIN (repository_activity_daily a JOIN repositories r ON r.id = a.repository_id AND r.organization_id = $1)
+           ON a.day > CURRENT_DATE - w.days
+         GROUP BY w.days
+         ORDER BY w.days",
+    )
+    .bind(org_id)
+    .bind(vec![1_i32, 7, 30])
+    .fetch_all(pool)
+    .await?;
+
+    let daily = sqlx::query_as::<_, crate::models::organizations::DailyActivity>(
+        "SELECT d::DATE AS day, COALESCE(SUM(a.pull_count), 0)::BIGINT AS pulls, COALESCE(SUM(a.push_count), 0)::BIGINT AS pushes
+         FROM generate_series(CURRENT_DATE - ($2 - 1), CURRENT_DATE, INTERVAL '1 day') AS d
+         LEFT JOIN (repository_activity_daily a JOIN repositories r ON r.id = a.repository_id AND r.organization_id = $1)
+           ON a.day = d::DATE
+         GROUP BY d
+         ORDER BY d",
+    )
+    .bind(org_id)
+    .bind(days)
+    .fetch_all(pool)
+    .await?;
+
+    let top_repositories = sqlx::query_as::<_, crate::models::organizations::RepositoryUsage>(
+        "SELECT r.name, r.is_public,
+                COALESCE(SUM(a.pull_count), 0)::BIGINT AS pulls,
+                COALESCE(SUM(a.push_count), 0)::BIGINT AS pushes,
+                (SELECT COALESCE(SUM(m.size), 0) FROM manifests m WHERE m.repository_id = r.id)::BIGINT AS storage_bytes
+         FROM repositories r
+         LEFT JOIN repository_activity_daily a ON a.repository_id = r.id AND a.day > CURRENT_DATE - $2
+         WHERE r.organization_id = $1
+         GROUP BY r.id
+         ORDER BY pulls DESC, pushes DESC, storage_bytes DESC, r.name
+         LIMIT 10",
+    )
+    .bind(org_id)
+    .bind(days)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(OrganizationStats {
+        organization_id: org_id,
+        repository_count,
+        public_repository_count,
+        storage_used_bytes,
+        storage_quota_bytes,
+        windows,
+        daily,
+        top_repositories,
+    })
+}
+
+async fn update_org_settings_internal(
+    pool: &PgPool,
+    org_id: i64,
+    req: UpdateOrganizationSettingsRequest,
+    user_id: i64,
+) -> Result<OrganizationSettings> {
+    let user_role = get_user_role_in_org(pool, org_id, user_id).await?;
+    if !user_role
+        .map(|r| r.can_manage_organization())
+        .unwrap_or(false)
+    {
+        bail!("Insufficient permissions to update organization settings");
     }
+
+    sqlx::query_as::<_, OrganizationSettings>(
+        "INSERT INTO organization_settings
+             (organization_id, default_repository_public, default_tag_retention_keep_last,
+              default_tag_retention_days, storage_quota_bytes, download_bytes_per_second,
+              updated_by, updated_at)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, CURRENT_TIMESTAMP)
+         ON CONFLICT (organization_id) DO UPDATE SET
+             default_repository_public = EXCLUDED.default_repository_public,
+             default_tag_retention_keep_last = EXCLUDED.default_tag_retention_keep_last,
+             default_tag_retention_days = EXCLUDED.default_tag_retention_days,
+             storage_quota_bytes = EXCLUDED.storage_quota_bytes,
+             download_bytes_per_second = EXCLUDED.download_bytes_per_second,
+             updated_by = EXCLUDED.updated_by,
+             updated_at = EXCLUDED.updated_at
+         RETURNING organization_id, default_repository_public, default_tag_retention_keep_last,
+                   default_tag_retention_days, storage_quota_bytes, download_bytes_per_second,
+                   updated_by, updated_at",
+    )
+    .bind(org_id)
+    .bind(req.default_repository_public)
+    .bind(req.default_tag_retention_keep_last)
+    .bind(req.default_tag_retention_days)
+    .bind(req.storage_quota_bytes)
+    .bind(req.download_bytes_per_second)
+    .bind(user_id)
+    .fetch_one(pool)
+    .await
+    .context("Failed to save organization settings")
 }
 
 // Internal database functions
@@ -626,7 +1087,7 @@ async fn get_org_by_id_internal(pool: &PgPool, org_id: i64) -> Result<Option<Org
     sqlx::query_as::<_, Organization>(
         "SELECT id, name, display_name, description, website_url, avatar_url, created_at, updated_at
          FROM organizations
-         WHERE id = $1"
+         WHERE id = $1 AND deleted_at IS NULL"
     )
     .bind(org_id)
     .fetch_optional(pool)
@@ -670,7 +1131,40 @@ async fn update_org_by_id_internal(
     .context("Organization not found")
 }
 
-async fn delete_org_by_id_internal(pool: &PgPool, org_id: i64, user_id: i64) -> Result<()> {
+/// How long a deletion confirmation token stays valid
+const DELETION_TOKEN_MINUTES: i64 = 10;
+
+/// The default organization holds repositories pushed without a namespace
+const DEFAULT_ORGANIZATION_ID: i64 = 1;
+
+fn deletion_mac(secret: &[u8], org_id: i64, user_id: i64, expires: i64) -> Hmac<Sha256> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret).expect("HMAC accepts keys of any length");
+    mac.update(format!("organization.delete.{}.{}.{}", org_id, user_id, expires).as_bytes());
+    mac
+}
+
+/// `{expires}.{hmac}`, bound to the organization and the owner who requested it
+fn sign_deletion_token(secret: &[u8], org_id: i64, user_id: i64, expires_at: DateTime<Utc>) -> String {
+    let expires = expires_at.timestamp();
+    let mac = deletion_mac(secret, org_id, user_id, expires);
+    format!("{}.{}", expires, hex::encode(mac.finalize().into_bytes()))
+}
+
+fn verify_deletion_token(secret: &[u8], token: &str, org_id: i64, user_id: i64, now: DateTime<Utc>) -> bool {
+    let Some((expires, mac)) = token.split_once('.') else {
+        return false;
+    };
+    let (Ok(expires), Ok(mac)) = (expires.parse::<i64>(), hex::decode(mac)) else {
+        return false;
+    };
+    expires > now.timestamp() && deletion_mac(secret, org_id, user_id, expires).verify_slice(&mac).is_ok()
+}
+
+async fn ensure_can_delete_org(pool: &PgPool, org_id: i64, user_id: i64) -> Result<()> {
+    if org_id == DEFAULT_ORGANIZATION_ID {
+        bail!("The default organization cannot be deleted");
+    }
+
     let user_role = get_user_role_in_org(pool, org_id, user_id).await?;
     if !user_role
         .map(|r| r.can_delete_organization())
@@ -679,22 +1173,165 @@ async fn delete_org_by_id_internal(pool: &PgPool, org_id: i64, user_id: i64) ->
         bail!("Only organization owners can delete organizations");
     }
 
-    let result = sqlx::query("DELETE FROM organizations WHERE id = $1")
+    if crate::handlers::legal_holds::is_under_legal_hold(pool, org_id).await? {
+        bail!("Organization is under legal hold; deletes are blocked until the hold is released");
+    }
+    Ok(())
+}
+
+async fn preview_org_deletion_internal(
+    pool: &PgPool,
+    secret: &[u8],
+    org_id: i64,
+    user_id: i64,
+) -> Result<OrganizationDeletionPreview> {
+    ensure_can_delete_org(pool, org_id, user_id).await?;
+
+    let (organization, repositories, tags, manifests, members): (String, i64, i64, i64, i64) = sqlx::query_as(
+        "SELECT o.name,
+                (SELECT COUNT(*) FROM repositories r WHERE r.organization_id = o.id),
+                (SELECT COUNT(*) FROM tags t JOIN repositories r ON r.id = t.repository_id WHERE r.organization_id = o.id),
+                (SELECT COUNT(*) FROM manifests m JOIN repositories r ON r.id = m.repository_id WHERE r.organization_id = o.id),
+                (SELECT COUNT(*) FROM organization_members om WHERE om.organization_id = o.id)
+         FROM organizations o
+         WHERE o.id = $1 AND o.deleted_at IS NULL",
+    )
+    .bind(org_id)
+    .fetch_optional(pool)
+    .await?
+    .context("Organization not found")?;
+
+    let expires_at = Utc::now() + chrono::Duration::minutes(DELETION_TOKEN_MINUTES);
+    Ok(OrganizationDeletionPreview {
+        organization,
+        repositories,
+        tags,
+        manifests,
+        members,
+        confirmation_token: sign_deletion_token(secret, org_id, user_id, expires_at),
+        expires_at,
+    })
+}
+
+/// What a deletion removed, for cache invalidation and the audit record
+struct DeletedOrganization {
+    name: String,
+    /// `org/repo` of every removed repository
+    repositories: Vec<String>,
+    former_members: Vec<i64>,
+    queued_blobs: u64,
+}
+
+async fn delete_org_by_id_internal(
+    pool: &PgPool,
+    secret: &[u8],
+    org_id: i64,
+    user_id: i64,
+    confirmation_token: &str,
+) -> Result<DeletedOrganization> {
+    if !verify_deletion_token(secret, confirmation_token, org_id, user_id, Utc::now()) {
+        bail!("Invalid or expired confirmation token");
+    }
+    ensure_can_delete_org(pool, org_id, user_id).await?;
+
+    let mut tx = pool.begin().await?;
+
+    // Lock the row so concurrent deletions and pushes that create repositories serialize
+    let name: String = sqlx::query_scalar(
+        "SELECT name FROM organizations WHERE id = $1 AND deleted_at IS NULL FOR UPDATE",
+    )
+    .bind(org_id)
+    .fetch_optional(&mut *tx)
+    .await?
+    .context("Organization not found")?;
+
+    let repositories: Vec<String> = sqlx::query_scalar(
+        "SELECT $2 || '/' || name FROM repositories WHERE organization_id = $1",
+    )
+    .bind(org_id)
+    .bind(&name)
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let former_members: Vec<i64> = sqlx::query_scalar(
+        "SELECT user_id FROM organization_members WHERE organization_id = $1",
+    )
+    .bind(org_id)
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let queued_blobs = crate::gc::enqueue_organization_blobs(&mut tx, org_id, &format!("organization {} deleted", name)).await?;
+
+    // Tags, manifests, uploads, mounts, collaborators and team grants cascade from repositories
+    sqlx::query("DELETE FROM repositories WHERE organization_id = $1")
+        .bind(org_id)
+        .execute(&mut *tx)
+        .await?;
+    // Team memberships cascade from teams
+    sqlx::query("DELETE FROM teams WHERE organization_id = $1")
+        .bind(org_id)
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query("DELETE FROM organization_members WHERE organization_id = $1")
+        .bind(org_id)
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query("DELETE FROM ip_access_rules WHERE organization_id = $1")
         .bind(org_id)
-        .execute(pool)
+        .execute(&mut *tx)
         .await?;
+    sqlx::query(
+        "UPDATE organization_invitations SET status = 'revoked', responded_at = NOW()
+         WHERE organization_id = $1 AND status = 'pending'",
+    )
+    .bind(org_id)
+    .execute(&mut *tx)
+    .await?;
 
-    if result.rows_affected() == 0 {
-        bail!("Organization not found");
-    }
+    // Keep the row for the audit trail but release the name
+    sqlx::query(
+        "UPDATE organizations
+         SET deleted_at = NOW(), deleted_by = $2, deleted_name = name,
+             name = LEFT('deleted-' || id || '-' || name, 255), updated_at = NOW()
+         WHERE id = $1",
+    )
+    .bind(org_id)
+    .bind(user_id)
+    .execute(&mut *tx)
+    .await?;
 
-    Ok(())
+    tx.commit().await?;
+
+    Ok(DeletedOrganization {
+        name,
+        repositories,
+        former_members,
+        queued_blobs,
+    })
+}
+
+async fn invalidate_deleted_org_caches(state: &AppState, deleted: &DeletedOrganization) {
+    let Some(cache) = &state.cache else {
+        return;
+    };
+
+    let mut results = vec![cache.invalidate("manifests").await, cache.invalidate_repositories().await];
+    for repository in &deleted.repositories {
+        results.push(cache.invalidate_tags(repository).await);
+    }
+    for user_id in &deleted.former_members {
+        results.push(cache.invalidate_user_permissions(&user_id.to_string()).await);
+    }
+    for e in results.into_iter().filter_map(|r| r.err()) {
+        tracing::warn!("Failed to invalidate cache after deleting {}: {}", deleted.name, e);
+    }
 }
 
 async fn get_members_by_org_id_internal(
     pool: &PgPool,
     org_id: i64,
     user_id: Option<i64>,
+    role: Option<&OrganizationRole>,
 ) -> Result<Vec<OrganizationMember>> {
     // Check if user has access to view members
     if let Some(uid) = user_id {
@@ -712,10 +1349,11 @@ async fn get_members_by_org_id_internal(
         FROM organization_members om
         JOIN users u ON om.user_id = u.id
         JOIN organizations o ON om.organization_id = o.id
-        WHERE o.id = $1
+        WHERE o.id = $1 AND ($2::TEXT IS NULL OR LOWER(om.role) = $2)
         ORDER BY om.joined_at ASC",
     )
     .bind(org_id)
+    .bind(role.map(|r| r.to_string()))
     .fetch_all(pool)
     .await
     .context("Failed to fetch organization members")
@@ -761,6 +1399,9 @@ async fn add_member_by_org_id_internal(
     if existing.is_some() {
         bail!("User is already a member of this organization");
     }
+    if let Some(exceeded) = crate::quota::check_members(pool, org_id).await? {
+        return Err(exceeded.into());
+    }
 
     // Add member
     let member_id: i64 = sqlx::query_scalar(
@@ -801,18 +1442,24 @@ async fn update_member_role_by_org_id_internal(
     let updater_role = get_user_role_in_org(pool, org_id, updater_id).await?;
     let target_current_role = get_user_role_in_org(pool, org_id, member_user_id).await?;
 
-    if let (Some(updater), Some(target)) = (updater_role, target_current_role) {
-        if !updater.can_change_role_to(&req.role) {
-            bail!("Insufficient permissions to assign this role");
-        }
-        if !updater.can_remove_member(&target) {
-            bail!("Insufficient permissions to modify this member");
+    let target = match (updater_role, target_current_role) {
+        (Some(updater), Some(target)) => {
+            if !updater.can_change_role_to(&req.role) {
+                bail!("Insufficient permissions to assign this role");
+            }
+            if !updater.can_remove_member(&target) {
+                bail!("Insufficient permissions to modify this member");
+            }
+            target
         }
-    } else {
-        bail!("Invalid member or insufficient permissions");
-    }
+        _ => bail!("Invalid member or insufficient permissions"),
+    };
+
+    let mut tx = pool.begin().await?;
 
-    // Organization ID is already provided
+    if target == OrganizationRole::Owner && req.role != OrganizationRole::Owner {
+        ensure_another_owner(&mut tx, org_id, member_user_id).await?;
+    }
 
     // Update the role
     sqlx::query(
@@ -821,9 +1468,11 @@ async fn update_member_role_by_org_id_internal(
     .bind(org_id)
     .bind(member_user_id)
     .bind(&req.role.to_string())
-    .execute(pool)
+    .execute(&mut *tx)
     .await?;
 
+    tx.commit().await?;
+
     // Fetch and return updated member info
     let member = sqlx::query_as::<_, OrganizationMember>(
         "SELECT 
@@ -854,28 +1503,64 @@ async fn remove_member_internal(
 
     // Allow self-removal for any role
     if remover_id != member_user_id {
-        if let (Some(remover), Some(target)) = (remover_role, target_role) {
-            if !remover.can_remove_member(&target) {
-                bail!("Insufficient permissions to remove this member");
-            }
-        } else {
-            bail!("Invalid member or insufficient permissions");
+        match (&remover_role, &target_role) {
+            (Some(remover), Some(target)) if remover.can_remove_member(target) => {}
+            (Some(_), Some(_)) => bail!("Insufficient permissions to remove this member"),
+            _ => bail!("Invalid member or insufficient permissions"),
         }
     }
 
-    // Organization ID is already provided
+    let mut tx = pool.begin().await?;
+
+    if target_role == Some(OrganizationRole::Owner) {
+        ensure_another_owner(&mut tx, org_id, member_user_id).await?;
+    }
 
     let result =
         sqlx::query("DELETE FROM organization_members WHERE organization_id = $1 AND user_id = $2")
             .bind(org_id)
             .bind(member_user_id)
-            .execute(pool)
+            .execute(&mut *tx)
             .await?;
 
     if result.rows_affected() == 0 {
         bail!("Member not found");
     }
 
+    // Team grants must not outlive organization membership
+    sqlx::query(
+        "DELETE FROM team_members tm
+         USING teams t
+         WHERE tm.team_id = t.id AND t.organization_id = $1 AND tm.user_id = $2",
+    )
+    .bind(org_id)
+    .bind(member_user_id)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Refuse to demote or remove `user_id` when no other owner would remain. Owner rows are
+/// locked until the transaction ends so two owners cannot step down concurrently.
+async fn ensure_another_owner(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    org_id: i64,
+    user_id: i64,
+) -> Result<()> {
+    let owners: Vec<i64> = sqlx::query_scalar(
+        "SELECT user_id FROM organization_members
+         WHERE organization_id = $1 AND LOWER(role) = 'owner'
+         FOR UPDATE",
+    )
+    .bind(org_id)
+    .fetch_all(&mut **tx)
+    .await?;
+
+    if owners.iter().all(|&owner| owner == user_id) {
+        bail!("Cannot remove or demote the last owner of the organization; promote another member to owner first");
+    }
     Ok(())
 }
 
@@ -896,3 +1581,22 @@ async fn list_user_orgs_internal(pool: &PgPool, user_id: i64) -> Result<Vec<Orga
     .await
     .context("Failed to fetch user organizations")
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deletion_token_is_bound_to_org_owner_and_expiry() {
+        let secret = b"test-secret";
+        let now = Utc::now();
+        let token = sign_deletion_token(secret, 7, 3, now + chrono::Duration::minutes(10));
+
+        assert!(verify_deletion_token(secret, &token, 7, 3, now));
+        assert!(!verify_deletion_token(secret, &token, 8, 3, now));
+        assert!(!verify_deletion_token(secret, &token, 7, 4, now));
+        assert!(!verify_deletion_token(secret, &token, 7, 3, now + chrono::Duration::minutes(11)));
+        assert!(!verify_deletion_token(b"other-secret", &token, 7, 3, now));
+        assert!(!verify_deletion_token(secret, "not-a-token", 7, 3, now));
+    }
+}
@@ -0,0 +1,416 @@
+// src/handlers/push_hooks.rs - Push hook endpoints registered by an organization
+//
+// The hooks are called by `crate::push_hooks::HttpPushHooks` during every manifest push to the
+// organization; these handlers only manage them.
+use anyhow::{bail, Result};
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+use axum_extra::headers::{authorization::Bearer, Authorization};
+use axum_extra::TypedHeader;
+use secrecy::ExposeSecret;
+use sqlx::PgPool;
+
+use crate::{
+    auth::extract_user_id_dual,
+    handlers::organizations::get_user_role_in_org,
+    log_stream::LogEvent,
+    models::api_key::ApiKeyScope,
+    models::push_hook::{CreatePushHookRequest, OrganizationPushHook, UpdatePushHookRequest},
+    AppState,
+};
+
+/// Push hooks per organization; each one adds its latency to every push
+const MAX_PUSH_HOOKS: i64 = 5;
+const DEFAULT_TIMEOUT_MS: i32 = 5000;
+const MIN_TIMEOUT_MS: i32 = 100;
+const MAX_TIMEOUT_MS: i32 = 30000;
+
+const PUSH_HOOK_COLUMNS: &str = "id, organization_id, name, url, timeout_ms, fail_open, active,
+     secret IS NOT NULL AS has_secret, created_by, created_at, updated_at, last_called_at, last_verdict, last_error";
+
+/// Register an endpoint that allows or denies each manifest pushed to the organization
+///
+/// The endpoint receives a signed JSON POST with the repository, reference, digest, manifest and
+/// image labels, and answers `{"allowed": bool, "reason": "..."}`; a denial rejects the push with
+/// the reason. Owners only.
+#[utoipa::path(
+    post,
+    path = "/api/v1/organizations/{id}/push-hooks",
+    tag = "organizations",
+    params(
+        ("id" = i64, Path, description = "Organization ID")
+    ),
+    request_body = CreatePushHookRequest,
+    responses(
+        (status = 201, description = "Push hook registered", body = OrganizationPushHook),
+        (status = 400, description = "Invalid name, URL or timeout, or not an owner"),
+        (status = 401, description = "Unauthorized")
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn create_push_hook(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    auth: Option<TypedHeader<Authorization<Bearer>>>,
+    Path(id): Path<i64>,
+    Json(req): Json<CreatePushHookRequest>,
+) -> impl IntoResponse {
+    let secret = state.config.auth.jwt_secret.expose_secret().as_bytes();
+    let user_id = match extract_user_id_dual(auth, &headers, ApiKeyScope::Push, secret, &state.db_pool, state.cache.as_ref()).await {
+        Ok(id) => id,
+        Err(status) => {
+            return (
+                status,
+                Json(serde_json::json!({
+                    "error": "Unauthorized"
+                })),
+            );
+        }
+    };
+
+    match create_push_hook_internal(&state.db_pool, id, user_id, req).await {
+        Ok(hook) => {
+            state.log_stream.publish(
+                LogEvent::audit("organization.push_hook.create", Some(user_id), None)
+                    .with_organization(id)
+                    .with_detail(format!("organization {} push hook {} -> {}", id, hook.id, hook.url)),
+            );
+            (StatusCode::CREATED, Json(serde_json::to_value(&hook).unwrap_or_default()))
+        }
+        Err(e) => (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({
+                "error": e.to_string()
+            })),
+        ),
+    }
+}
+
+/// List an organization's push hooks with their last verdict
+#[utoipa::path(
+    get,
+    path = "/api/v1/organizations/{id}/push-hooks",
+    tag = "organizations",
+    params(
+        ("id" = i64, Path, description = "Organization ID")
+    ),
+    responses(
+        (status = 200, description = "Push hooks of the organization", body = Vec<OrganizationPushHook>),
+        (status = 400, description = "Not an owner"),
+        (status = 401, description = "Unauthorized")
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn list_push_hooks(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    auth: Option<TypedHeader<Authorization<Bearer>>>,
+    Path(id): Path<i64>,
+) -> impl IntoResponse {
+    let secret = state.config.auth.jwt_secret.expose_secret().as_bytes();
+    let user_id = match extract_user_id_dual(auth, &headers, ApiKeyScope::Read, secret, &state.db_pool, state.cache.as_ref()).await {
+        Ok(id) => id,
+        Err(status) => {
+            return (
+                status,
+                Json(serde_json::json!({
+                    "error": "Unauthorized"
+                })),
+            );
+        }
+    };
+
+    match list_push_hooks_internal(&state.db_pool, id, user_id).await {
+        Ok(hooks) => (
+            StatusCode::OK,
+            Json(serde_json::json!({
+                "push_hooks": hooks
+            })),
+        ),
+        Err(e) => (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({
+                "error": e.to_string()
+            })),
+        ),
+    }
+}
+
+/// Change a push hook's name, URL, secret, timeout, failure mode or whether it is active
+#[utoipa::path(
+    put,
+    path = "/api/v1/organizations/{id}/push-hooks/{hook_id}",
+    tag = "organizations",
+    params(
+        ("id" = i64, Path, description = "Organization ID"),
+        ("hook_id" = i64, Path, description = "Push hook ID")
+    ),
+    request_body = UpdatePushHookRequest,
+    responses(
+        (status = 200, description = "Push hook updated", body = OrganizationPushHook),
+        (status = 400, description = "Invalid name, URL or timeout, push hook not found or not an owner"),
+        (status = 401, description = "Unauthorized")
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn update_push_hook(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    auth: Option<TypedHeader<Authorization<Bearer>>>,
+    Path((id, hook_id)): Path<(i64, i64)>,
+    Json(req): Json<UpdatePushHookRequest>,
+) -> impl IntoResponse {
+    let secret = state.config.auth.jwt_secret.expose_secret().as_bytes();
+    let user_id = match extract_user_id_dual(auth, &headers, ApiKeyScope::Push, secret, &state.db_pool, state.cache.as_ref()).await {
+        Ok(id) => id,
+        Err(status) => {
+            return (
+                status,
+                Json(serde_json::json!({
+                    "error": "Unauthorized"
+                })),
+            );
+        }
+    };
+
+    match update_push_hook_internal(&state.db_pool, id, hook_id, user_id, req).await {
+        Ok(hook) => {
+            state.log_stream.publish(
+                LogEvent::audit("organization.push_hook.update", Some(user_id), None)
+                    .with_organization(id)
+                    .with_detail(format!("organization {} push hook {}", id, hook_id)),
+            );
+            (StatusCode::OK, Json(serde_json::to_value(&hook).unwrap_or_default()))
+        }
+        Err(e) => (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({
+                "error": e.to_string()
+            })),
+        ),
+    }
+}
+
+/// Remove a push hook from an organization
+#[utoipa::path(
+    delete,
+    path = "/api/v1/organizations/{id}/push-hooks/{hook_id}",
+    tag = "organizations",
+    params(
+        ("id" = i64, Path, description = "Organization ID"),
+        ("hook_id" = i64, Path, description = "Push hook ID")
+    ),
+    responses(
+        (status = 200, description = "Push hook deleted"),
+        (status = 400, description = "Push hook not found or not an owner"),
+        (status = 401, description = "Unauthorized")
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn delete_push_hook(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    auth: Option<TypedHeader<Authorization<Bearer>>>,
+    Path((id, hook_id)): Path<(i64, i64)>,
+) -> impl IntoResponse {
+    let secret = state.config.auth.jwt_secret.expose_secret().as_bytes();
+    let user_id = match extract_user_id_dual(auth, &headers, ApiKeyScope::Push, secret, &state.db_pool, state.cache.as_ref()).await {
+        Ok(id) => id,
+        Err(status) => {
+            return (
+                status,
+                Json(serde_json::json!({
+                    "error": "Unauthorized"
+                })),
+            );
+        }
+    };
+
+    match delete_push_hook_internal(&state.db_pool, id, hook_id, user_id).await {
+        Ok(()) => {
+            state.log_stream.publish(
+                LogEvent::audit("organization.push_hook.delete", Some(user_id), None)
+                    .with_organization(id)
+                    .with_detail(format!("organization {} push hook {}", id, hook_id)),
+            );
+            (
+                StatusCode::OK,
+                Json(serde_json::json!({
+                    "message": "Push hook deleted"
+                })),
+            )
+        }
+        Err(e) => (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({
+                "error": e.to_string()
+            })),
+        ),
+    }
+}
+
+// Internal database functions
+async fn ensure_can_manage_push_hooks(pool: &PgPool, org_id: i64, user_id: i64) -> Result<()> {
+    let user_role = get_user_role_in_org(pool, org_id, user_id).await?;
+    if !user_role.map(|r| r.can_manage_webhooks()).unwrap_or(false) {
+        bail!("Only organization owners can manage push hooks");
+    }
+    Ok(())
+}
+
+fn validate_name(name: &str) -> Result<()> {
+    if name.is_empty() || name.len() > 100 {
+        bail!("Push hook name must be 1 to 100 characters long");
+    }
+    Ok(())
+}
+
+fn validate_url(url: &str) -> Result<()> {
+    match url::Url::parse(url) {
+        Ok(parsed) if matches!(parsed.scheme(), "http" | "https") && parsed.host_str().is_some() => Ok(()),
+        _ => bail!("Push hook URL must be an absolute http or https URL"),
+    }
+}
+
+fn validate_timeout(timeout_ms: i32) -> Result<()> {
+    if !(MIN_TIMEOUT_MS..=MAX_TIMEOUT_MS).contains(&timeout_ms) {
+        bail!("Push hook timeout must be between {} and {} milliseconds", MIN_TIMEOUT_MS, MAX_TIMEOUT_MS);
+    }
+    Ok(())
+}
+
+async fn create_push_hook_internal(
+    pool: &PgPool,
+    org_id: i64,
+    user_id: i64,
+    req: CreatePushHookRequest,
+) -> Result<OrganizationPushHook> {
+    ensure_can_manage_push_hooks(pool, org_id, user_id).await?;
+    validate_name(req.name.trim())?;
+    validate_url(req.url.trim())?;
+    let timeout_ms = req.timeout_ms.unwrap_or(DEFAULT_TIMEOUT_MS);
+    validate_timeout(timeout_ms)?;
+
+    let count = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM organization_push_hooks WHERE organization_id = $1")
+        .bind(org_id)
+        .fetch_one(pool)
+        .await?;
+    if count >= MAX_PUSH_HOOKS {
+        bail!("An organization can have at most {} push hooks", MAX_PUSH_HOOKS);
+    }
+
+    let hook = sqlx::query_as::<_, OrganizationPushHook>(&format!(
+        "INSERT INTO organization_push_hooks (organization_id, name, url, secret, timeout_ms, fail_open, active, created_by)
+         VALUES ($1, $2, $3, NULLIF($4, ''), $5, $6, $7, $8)
+         ON CONFLICT (organization_id, name) DO NOTHING
+         RETURNING {}",
+        PUSH_HOOK_COLUMNS
+    ))
+    .bind(org_id)
+    .bind(req.name.trim())
+    .bind(req.url.trim())
+    .bind(req.secret)
+    .bind(timeout_ms)
+    .bind(req.fail_open.unwrap_or(false))
+    .bind(req.active.unwrap_or(true))
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await?;
+
+    match hook {
+        Some(hook) => {
+            tracing::info!("Push hook {} registered for organization {} by user {}", hook.id, org_id, user_id);
+            Ok(hook)
+        }
+        None => bail!("A push hook named '{}' already exists", req.name.trim()),
+    }
+}
+
+async fn list_push_hooks_internal(pool: &PgPool, org_id: i64, user_id: i64) -> Result<Vec<OrganizationPushHook>> {
+    ensure_can_manage_push_hooks(pool, org_id, user_id).await?;
+
+    let hooks = sqlx::query_as::<_, OrganizationPushHook>(&format!(
+        "SELECT {} FROM organization_push_hooks WHERE organization_id = $1 ORDER BY id",
+        PUSH_HOOK_COLUMNS
+    ))
+    .bind(org_id)
+    .fetch_all(pool)
+    .await?;
+    Ok(hooks)
+}
+
+async fn update_push_hook_internal(
+    pool: &PgPool,
+    org_id: i64,
+    hook_id: i64,
+    user_id: i64,
+    req: UpdatePushHookRequest,
+) -> Result<OrganizationPushHook> {
+    ensure_can_manage_push_hooks(pool, org_id, user_id).await?;
+    let name = req.name.map(|name| name.trim().to_string());
+    if let Some(name) = &name {
+        validate_name(name)?;
+    }
+    let url = req.url.map(|url| url.trim().to_string());
+    if let Some(url) = &url {
+        validate_url(url)?;
+    }
+    if let Some(timeout_ms) = req.timeout_ms {
+        validate_timeout(timeout_ms)?;
+    }
+
+    let hook = sqlx::query_as::<_, OrganizationPushHook>(&format!(
+        "UPDATE organization_push_hooks SET
+             name = COALESCE($3, name),
+             url = COALESCE($4, url),
+             secret = CASE WHEN $5::TEXT IS NULL THEN secret ELSE NULLIF($5, '') END,
+             timeout_ms = COALESCE($6, timeout_ms),
+             fail_open = COALESCE($7, fail_open),
+             active = COALESCE($8, active),
+             updated_at = CURRENT_TIMESTAMP
+         WHERE id = $1 AND organization_id = $2
+         RETURNING {}",
+        PUSH_HOOK_COLUMNS
+    ))
+    .bind(hook_id)
+    .bind(org_id)
+    .bind(name)
+    .bind(url)
+    .bind(req.secret)
+    .bind(req.timeout_ms)
+    .bind(req.fail_open)
+    .bind(req.active)
+    .fetch_optional(pool)
+    .await?;
+
+    match hook {
+        Some(hook) => Ok(hook),
+        None => bail!("Push hook not found"),
+    }
+}
+
+async fn delete_push_hook_internal(pool: &PgPool, org_id: i64, hook_id: i64, user_id: i64) -> Result<()> {
+    ensure_can_manage_push_hooks(pool, org_id, user_id).await?;
+
+    let result = sqlx::query("DELETE FROM organization_push_hooks WHERE id = $1 AND organization_id = $2")
+        .bind(hook_id)
+        .bind(org_id)
+        .execute(pool)
+        .await?;
+    if result.rows_affected() == 0 {
+        bail!("Push hook not found");
+    }
+    Ok(())
+}
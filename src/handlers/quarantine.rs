@@ -0,0 +1,156 @@
+// src/handlers/quarantine.rs - Reviewing and approving quarantined images
+//
+// Manifests are quarantined and released by `crate::quarantine`.
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use axum_extra::headers::{authorization::Bearer, Authorization};
+use axum_extra::TypedHeader;
+use secrecy::ExposeSecret;
+use serde_json::json;
+
+use crate::{
+    auth::extract_user_id_dual,
+    handlers::docker_auth::check_repository_permission,
+    handlers::registry_auth::RepoAccess,
+    handlers::tag_cleanup::{find_repository, internal_error, repository_not_found},
+    models::api_key::ApiKeyScope,
+    models::quarantine::QuarantinedManifest,
+    quarantine::{self, Release},
+    AppState,
+};
+
+/// Images of a repository waiting in quarantine
+///
+/// Manifests pushed while the repository quarantines pushes that no completed scan or
+/// administrator has released yet, oldest first. Requires pull access.
+#[utoipa::path(
+    get,
+    path = "/api/v1/repos/{namespace}/{repo_name}/quarantine",
+    params(
+        ("namespace" = String, Path, description = "Organization namespace"),
+        ("repo_name" = String, Path, description = "Repository name")
+    ),
+    responses(
+        (status = 200, description = "Quarantined manifests", body = Vec<QuarantinedManifest>),
+        (status = 401, description = "Authentication required"),
+        (status = 404, description = "Repository not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "repositories",
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn list_quarantined_manifests(
+    Path((namespace, repo_name)): Path<(String, String)>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    auth: Option<TypedHeader<Authorization<Bearer>>>,
+) -> Response {
+    let secret = state.config.auth.jwt_secret.expose_secret().as_bytes();
+    let user_id = match extract_user_id_dual(auth, &headers, ApiKeyScope::Read, secret, &state.db_pool, state.cache.as_ref()).await {
+        Ok(id) => id,
+        Err(status) => return (status, Json(json!({"error": "Authentication required"}))).into_response(),
+    };
+    match check_repository_permission(&user_id.to_string(), &namespace, &repo_name, "pull", &state).await {
+        Ok(true) => {}
+        Ok(false) => return repository_not_found(&namespace, &repo_name),
+        Err(e) => return internal_error(e),
+    }
+    let repository_id = match find_repository(&state, &namespace, &repo_name).await {
+        Ok(Some((repository_id, _))) => repository_id,
+        Ok(None) => return repository_not_found(&namespace, &repo_name),
+        Err(e) => return internal_error(e),
+    };
+
+    let manifests = sqlx::query_as::<_, QuarantinedManifest>(
+        "SELECT q.manifest_digest AS digest,
+                ARRAY(SELECT t.name FROM tags t JOIN manifests m ON m.id = t.manifest_id
+                      WHERE t.repository_id = q.repository_id AND m.digest = q.manifest_digest
+                      ORDER BY t.name) AS tags,
+                q.quarantined_at, s.status AS scan_status
+         FROM manifest_quarantines q
+         LEFT JOIN vulnerability_scans s ON s.repository_id = q.repository_id AND s.manifest_digest = q.manifest_digest
+         WHERE q.repository_id = $1 AND q.status = 'quarantined'
+         ORDER BY q.quarantined_at",
+    )
+    .bind(repository_id)
+    .fetch_all(&state.db_pool)
+    .await;
+    match manifests {
+        Ok(manifests) => (StatusCode::OK, Json(json!(manifests))).into_response(),
+        Err(e) => internal_error(e),
+    }
+}
+
+/// Approve a quarantined image
+///
+/// Releases the manifest without waiting for its scan, so everyone with pull access can pull it.
+/// Requires delete access to the repository or registry administrator rights.
+#[utoipa::path(
+    post,
+    path = "/api/v1/repos/{namespace}/{repo_name}/quarantine/{digest}/release",
+    params(
+        ("namespace" = String, Path, description = "Organization namespace"),
+        ("repo_name" = String, Path, description = "Repository name"),
+        ("digest" = String, Path, description = "Manifest digest")
+    ),
+    responses(
+        (status = 200, description = "Manifest released"),
+        (status = 401, description = "Authentication required"),
+        (status = 403, description = "Not a repository administrator"),
+        (status = 404, description = "Repository not found or manifest not quarantined"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "repositories",
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn release_quarantined_manifest(
+    Path((namespace, repo_name, digest)): Path<(String, String, String)>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    auth: Option<TypedHeader<Authorization<Bearer>>>,
+) -> Response {
+    let secret = state.config.auth.jwt_secret.expose_secret().as_bytes();
+    let user_id = match extract_user_id_dual(auth, &headers, ApiKeyScope::Push, secret, &state.db_pool, state.cache.as_ref()).await {
+        Ok(id) => id,
+        Err(status) => return (status, Json(json!({"error": "Authentication required"}))).into_response(),
+    };
+    match check_repository_permission(&user_id.to_string(), &namespace, &repo_name, "pull", &state).await {
+        Ok(true) => {}
+        Ok(false) => return repository_not_found(&namespace, &repo_name),
+        Err(e) => return internal_error(e),
+    }
+    let access = RepoAccess { namespace: namespace.clone(), repository: repo_name.clone(), user: Some(user_id.to_string()) };
+    match quarantine::is_administrator(&state, &access).await {
+        Ok(true) => {}
+        Ok(false) => {
+            return (StatusCode::FORBIDDEN, Json(json!({
+                "error": "Only repository administrators can release quarantined images"
+            }))).into_response()
+        }
+        Err(e) => return internal_error(e),
+    }
+    let repository_id = match find_repository(&state, &namespace, &repo_name).await {
+        Ok(Some((repository_id, _))) => repository_id,
+        Ok(None) => return repository_not_found(&namespace, &repo_name),
+        Err(e) => return internal_error(e),
+    };
+
+    let repository = format!("{}/{}", namespace, repo_name);
+    match quarantine::release(&state, repository_id, &repository, &digest, Release::Approval, Some(user_id)).await {
+        Ok(true) => (StatusCode::OK, Json(json!({
+            "message": format!("{}@{} released from quarantine", repository, digest)
+        }))).into_response(),
+        Ok(false) => (StatusCode::NOT_FOUND, Json(json!({
+            "error": format!("{} is not quarantined in {}", digest, repository)
+        }))).into_response(),
+        Err(e) => internal_error(e),
+    }
+}
@@ -0,0 +1,398 @@
+// src/handlers/quota_tiers.rs - Quota tiers managed by administrators and assigned to organizations
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use axum_extra::headers::{authorization::Bearer, Authorization};
+use axum_extra::TypedHeader;
+use secrecy::ExposeSecret;
+use serde_json::json;
+use sqlx::PgPool;
+use validator::Validate;
+
+use crate::{
+    auth::extract_user_id_dual,
+    handlers::admin::{internal_error, AdminUser},
+    handlers::organizations::{get_user_role_in_org, org_storage_used},
+    log_stream::LogEvent,
+    models::api_key::ApiKeyScope,
+    models::quota_tier::{AssignQuotaTierRequest, OrganizationQuota, QuotaTier, QuotaTierRequest},
+    quota::{self, TIER_SELECT},
+    AppState,
+};
+
+/// List quota tiers
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/quota-tiers",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Tiers ordered by name", body = [QuotaTier]),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Not a registry administrator"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn list_quota_tiers(State(state): State<AppState>, _admin: AdminUser) -> Response {
+    match sqlx::query_as::<_, QuotaTier>(&format!("{} ORDER BY name", TIER_SELECT))
+        .fetch_all(&state.db_pool)
+        .await
+    {
+        Ok(tiers) => (StatusCode::OK, Json(tiers)).into_response(),
+        Err(e) => internal_error(e),
+    }
+}
+
+/// Create a quota tier
+///
+/// With `is_default` the tier replaces the current default and applies to every organization
+/// without a tier of its own.
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/quota-tiers",
+    tag = "admin",
+    request_body = QuotaTierRequest,
+    responses(
+        (status = 201, description = "Tier created", body = QuotaTier),
+        (status = 400, description = "Validation failed"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Not a registry administrator"),
+        (status = 409, description = "A tier with that name already exists"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn create_quota_tier(
+    State(state): State<AppState>,
+    admin: AdminUser,
+    Json(req): Json<QuotaTierRequest>,
+) -> Response {
+    if let Err(errors) = req.validate() {
+        return validation_failed(errors);
+    }
+
+    match save_tier(&state.db_pool, None, &req).await {
+        Ok(Some(tier)) => {
+            state.log_stream.publish(
+                LogEvent::audit("quota_tier.create", Some(admin.user_id), None)
+                    .with_detail(describe(&tier)),
+            );
+            (StatusCode::CREATED, Json(tier)).into_response()
+        }
+        Ok(None) => tier_not_found(),
+        Err(e) if is_unique_violation(&e) => duplicate_name(&req.name),
+        Err(e) => internal_error(e),
+    }
+}
+
+/// Replace a quota tier's name, description and limits
+///
+/// Takes effect immediately for every organization on the tier. Usage already over a lowered
+/// limit is kept; only further growth is rejected.
+#[utoipa::path(
+    put,
+    path = "/api/v1/admin/quota-tiers/{tier_id}",
+    tag = "admin",
+    params(("tier_id" = i64, Path, description = "Tier ID")),
+    request_body = QuotaTierRequest,
+    responses(
+        (status = 200, description = "Tier updated", body = QuotaTier),
+        (status = 400, description = "Validation failed"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Not a registry administrator"),
+        (status = 404, description = "Tier not found"),
+        (status = 409, description = "A tier with that name already exists"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn update_quota_tier(
+    State(state): State<AppState>,
+    admin: AdminUser,
+    Path(tier_id): Path<i64>,
+    Json(req): Json<QuotaTierRequest>,
+) -> Response {
+    if let Err(errors) = req.validate() {
+        return validation_failed(errors);
+    }
+
+    match save_tier(&state.db_pool, Some(tier_id), &req).await {
+        Ok(Some(tier)) => {
+            state.log_stream.publish(
+                LogEvent::audit("quota_tier.update", Some(admin.user_id), None)
+                    .with_detail(describe(&tier)),
+            );
+            (StatusCode::OK, Json(tier)).into_response()
+        }
+        Ok(None) => tier_not_found(),
+        Err(e) if is_unique_violation(&e) => duplicate_name(&req.name),
+        Err(e) => internal_error(e),
+    }
+}
+
+/// Delete a quota tier that no organization is on
+#[utoipa::path(
+    delete,
+    path = "/api/v1/admin/quota-tiers/{tier_id}",
+    tag = "admin",
+    params(("tier_id" = i64, Path, description = "Tier ID")),
+    responses(
+        (status = 204, description = "Tier deleted"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Not a registry administrator"),
+        (status = 404, description = "Tier not found"),
+        (status = 409, description = "Organizations are still assigned to the tier"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn delete_quota_tier(
+    State(state): State<AppState>,
+    admin: AdminUser,
+    Path(tier_id): Path<i64>,
+) -> Response {
+    match sqlx::query_scalar::<_, String>("DELETE FROM quota_tiers WHERE id = $1 RETURNING name")
+        .bind(tier_id)
+        .fetch_optional(&state.db_pool)
+        .await
+    {
+        Ok(Some(name)) => {
+            state.log_stream.publish(
+                LogEvent::audit("quota_tier.delete", Some(admin.user_id), None)
+                    .with_detail(format!("tier={} name={}", tier_id, name)),
+            );
+            StatusCode::NO_CONTENT.into_response()
+        }
+        Ok(None) => tier_not_found(),
+        Err(sqlx::Error::Database(e)) if e.is_foreign_key_violation() => {
+            (StatusCode::CONFLICT, Json(json!({
+                "error": "Organizations are still assigned to this tier; move them to another tier first"
+            }))).into_response()
+        }
+        Err(e) => internal_error(e),
+    }
+}
+
+/// Assign a quota tier to an organization
+///
+/// `{"tier": null}` removes the assignment, leaving the organization on the default tier.
+#[utoipa::path(
+    put,
+    path = "/api/v1/admin/organizations/{id}/quota-tier",
+    tag = "admin",
+    params(("id" = i64, Path, description = "Organization ID")),
+    request_body = AssignQuotaTierRequest,
+    responses(
+        (status = 200, description = "Tier assigned", body = OrganizationQuota),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Not a registry administrator"),
+        (status = 404, description = "Organization or tier not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn assign_quota_tier(
+    State(state): State<AppState>,
+    admin: AdminUser,
+    Path(org_id): Path<i64>,
+    Json(req): Json<AssignQuotaTierRequest>,
+) -> Response {
+    let tier_id = match &req.tier {
+        Some(name) => {
+            match sqlx::query_scalar::<_, i64>("SELECT id FROM quota_tiers WHERE name = $1")
+                .bind(name)
+                .fetch_optional(&state.db_pool)
+                .await
+            {
+                Ok(Some(id)) => Some(id),
+                Ok(None) => {
+                    return (StatusCode::NOT_FOUND, Json(json!({
+                        "error": format!("Quota tier '{}' not found", name)
+                    }))).into_response()
+                }
+                Err(e) => return internal_error(e),
+            }
+        }
+        None => None,
+    };
+
+    match sqlx::query("UPDATE organizations SET quota_tier_id = $2 WHERE id = $1 AND deleted_at IS NULL")
+        .bind(org_id)
+        .bind(tier_id)
+        .execute(&state.db_pool)
+        .await
+    {
+        Ok(result) if result.rows_affected() == 0 => {
+            return (StatusCode::NOT_FOUND, Json(json!({
+                "error": format!("Organization {} not found", org_id)
+            }))).into_response()
+        }
+        Ok(_) => {}
+        Err(e) => return internal_error(e),
+    }
+
+    state.log_stream.publish(
+        LogEvent::audit("organization.quota_tier.assign", Some(admin.user_id), None)
+            .with_organization(org_id)
+            .with_detail(format!("org={} tier={}", org_id, req.tier.as_deref().unwrap_or("default"))),
+    );
+    match organization_quota(&state.db_pool, org_id).await {
+        Ok(quota) => (StatusCode::OK, Json(quota)).into_response(),
+        Err(e) => internal_error(e),
+    }
+}
+
+/// An organization's quota tier and its usage of each limit
+///
+/// Members only.
+#[utoipa::path(
+    get,
+    path = "/api/v1/organizations/{id}/quota",
+    tag = "organizations",
+    params(("id" = i64, Path, description = "Organization ID")),
+    responses(
+        (status = 200, description = "Tier and usage", body = OrganizationQuota),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Not a member of this organization"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn get_organization_quota(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    auth: Option<TypedHeader<Authorization<Bearer>>>,
+    Path(org_id): Path<i64>,
+) -> Response {
+    let secret = state.config.auth.jwt_secret.expose_secret().as_bytes();
+    let user_id = match extract_user_id_dual(auth, &headers, ApiKeyScope::Read, secret, &state.db_pool, state.cache.as_ref()).await {
+        Ok(id) => id,
+        Err(status) => return (status, Json(json!({ "error": "Unauthorized" }))).into_response(),
+    };
+    match get_user_role_in_org(&state.db_pool, org_id, user_id).await {
+        Ok(Some(_)) => {}
+        Ok(None) => {
+            return (StatusCode::FORBIDDEN, Json(json!({
+                "error": "Access denied: not a member of this organization"
+            }))).into_response()
+        }
+        Err(e) => return internal_error(e),
+    }
+
+    match organization_quota(&state.db_pool, org_id).await {
+        Ok(quota) => (StatusCode::OK, Json(quota)).into_response(),
+        Err(e) => internal_error(e),
+    }
+}
+
+async fn organization_quota(pool: &PgPool, org_id: i64) -> Result<OrganizationQuota, sqlx::Error> {
+    let tier = quota::org_tier(pool, org_id).await?;
+    let storage_used_bytes = org_storage_used(pool, org_id).await?;
+    let (repository_count, member_count) = sqlx::query_as::<_, (i64, i64)>(
+        "SELECT (SELECT COUNT(*) FROM repositories WHERE organization_id = $1),
+                (SELECT COUNT(*) FROM organization_members WHERE organization_id = $1)",
+    )
+    .bind(org_id)
+    .fetch_one(pool)
+    .await?;
+    Ok(OrganizationQuota { organization_id: org_id, tier, storage_used_bytes, repository_count, member_count })
+}
+
+/// Insert a tier, or update `tier_id`; `None` when the tier to update does not exist
+async fn save_tier(pool: &PgPool, tier_id: Option<i64>, req: &QuotaTierRequest) -> Result<Option<QuotaTier>, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    // Only one tier can be the default
+    if req.is_default {
+        sqlx::query("UPDATE quota_tiers SET is_default = FALSE, updated_at = CURRENT_TIMESTAMP WHERE is_default AND id IS DISTINCT FROM $1")
+            .bind(tier_id)
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    let returning = "RETURNING id, name, description, max_storage_bytes, max_repositories, max_members,
+                   download_bytes_per_second, is_default, created_at, updated_at";
+    let query = match tier_id {
+        None => format!(
+            "INSERT INTO quota_tiers (name, description, max_storage_bytes, max_repositories, max_members,
+                                      download_bytes_per_second, is_default)
+             VALUES ($2, $3, $4, $5, $6, $7, $8)
+             {}",
+            returning
+        ),
+        Some(_) => format!(
+            "UPDATE quota_tiers
+             SET name = $2, description = $3, max_storage_bytes = $4, max_repositories = $5, max_members = $6,
+                 download_bytes_per_second = $7, is_default = $8, updated_at = CURRENT_TIMESTAMP
+             WHERE id = $1
+             {}",
+            returning
+        ),
+    };
+    let tier = sqlx::query_as::<_, QuotaTier>(&query)
+        .bind(tier_id)
+        .bind(req.name.trim())
+        .bind(&req.description)
+        .bind(req.max_storage_bytes)
+        .bind(req.max_repositories)
+        .bind(req.max_members)
+        .bind(req.download_bytes_per_second)
+        .bind(req.is_default)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+    if tier.is_some() {
+        tx.commit().await?;
+    }
+    Ok(tier)
+}
+
+fn describe(tier: &QuotaTier) -> String {
+    format!(
+        "tier={} name={} storage={:?} repositories={:?} members={:?} download={:?} default={}",
+        tier.id,
+        tier.name,
+        tier.max_storage_bytes,
+        tier.max_repositories,
+        tier.max_members,
+        tier.download_bytes_per_second,
+        tier.is_default
+    )
+}
+
+fn is_unique_violation(e: &sqlx::Error) -> bool {
+    matches!(e, sqlx::Error::Database(db) if db.is_unique_violation())
+}
+
+fn validation_failed(errors: validator::ValidationErrors) -> Response {
+    (StatusCode::BAD_REQUEST, Json(json!({
+        "error": "Validation failed",
+        "details": errors
+    }))).into_response()
+}
+
+fn duplicate_name(name: &str) -> Response {
+    (StatusCode::CONFLICT, Json(json!({
+        "error": format!("A quota tier named '{}' already exists", name.trim())
+    }))).into_response()
+}
+
+fn tier_not_found() -> Response {
+    (StatusCode::NOT_FOUND, Json(json!({
+        "error": "Quota tier not found"
+    }))).into_response()
+}
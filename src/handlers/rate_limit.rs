@@ -0,0 +1,200 @@
+// src/handlers/rate_limit.rs - Per-principal request rate limiting
+use std::{
+    net::SocketAddr,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use axum::{
+    extract::{ConnectInfo, OriginalUri, Request, State},
+    http::{header::AUTHORIZATION, HeaderMap, HeaderValue, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use base64::Engine;
+use secrecy::ExposeSecret;
+use serde_json::json;
+
+use crate::{
+    auth::{hash_api_key, verify_token},
+    handlers::ip_access::client_ip,
+    AppState,
+};
+
+/// Requests counted together against one limit
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RateLimitBucket {
+    Pull,
+    Push,
+    Auth,
+    Api,
+}
+
+impl RateLimitBucket {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RateLimitBucket::Pull => "pull",
+            RateLimitBucket::Push => "push",
+            RateLimitBucket::Auth => "auth",
+            RateLimitBucket::Api => "api",
+        }
+    }
+
+    /// Bucket for a request, or `None` for traffic that is never limited (health checks, UI assets)
+    pub fn classify(method: &Method, path: &str) -> Option<Self> {
+        if path == "/v2" || path.starts_with("/v2/") {
+            return Some(match *method {
+                Method::GET | Method::HEAD => RateLimitBucket::Pull,
+                _ => RateLimitBucket::Push,
+            });
+        }
+
+        const AUTH_ATTEMPTS: [&str; 5] = ["/login", "/register", "/refresh", "/forgot-password", "/verify-otp"];
+        if let Some(endpoint) = path.strip_prefix("/api/v1/auth") {
+            if *method == Method::POST && AUTH_ATTEMPTS.contains(&endpoint) {
+                return Some(RateLimitBucket::Auth);
+            }
+        }
+
+        path.starts_with("/api/").then_some(RateLimitBucket::Api)
+    }
+}
+
+/// Middleware rejecting requests once their principal has used up the bucket's limit for the
+/// current window. Authenticated callers are counted per user or API key, anonymous ones per
+/// client address; auth attempts are always counted per address.
+pub async fn enforce_rate_limit(
+    State(state): State<AppState>,
+    connect_info: Option<ConnectInfo<SocketAddr>>,
+    OriginalUri(uri): OriginalUri,
+    request: Request,
+    next: Next,
+) -> Response {
+    let settings = state.runtime_config.rate_limit();
+    let cache = match &state.cache {
+        Some(cache) if settings.enabled => cache,
+        _ => return next.run(request).await,
+    };
+    let bucket = match RateLimitBucket::classify(request.method(), uri.path()) {
+        Some(bucket) => bucket,
+        None => return next.run(request).await,
+    };
+    let limit = match bucket {
+        RateLimitBucket::Pull => settings.pull_limit,
+        RateLimitBucket::Push => settings.push_limit,
+        RateLimitBucket::Auth => settings.auth_limit,
+        RateLimitBucket::Api => settings.api_limit,
+    };
+
+    let peer = connect_info.map(|ConnectInfo(addr)| addr.ip());
+    let ip = client_ip(peer, request.headers(), &state.config.ip_access.trusted_proxies)
+        .map(|ip| ip.to_string())
+        .unwrap_or_else(|| "unknown".to_string());
+    let principal = match bucket {
+        RateLimitBucket::Auth => format!("ip:{}", ip),
+        _ => identify_principal(request.headers(), &state, &ip).await,
+    };
+
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+    let window_start = now - now % settings.window_seconds;
+    let key = format!("ratelimit:{}:{}:{}", bucket.as_str(), principal, window_start);
+    let hits = cache.increment_counter(&key, Duration::from_secs(settings.window_seconds)).await;
+
+    if hits > limit {
+        let retry_after = (window_start + settings.window_seconds - now).max(1);
+        tracing::warn!("Rate limit exceeded: {} {} ({} requests)", bucket.as_str(), principal, hits);
+        return too_many_requests(bucket, limit, retry_after);
+    }
+
+    next.run(request).await
+}
+
+/// Rate-limit key for a request's credential. Only credentials that can be checked without
+/// the database are trusted as-is; a docker login name is paired with the client address so
+/// a forged `Authorization` header cannot exhaust someone else's budget.
+pub(crate) async fn identify_principal(headers: &HeaderMap, state: &AppState, ip: &str) -> String {
+    if let Some(api_key) = headers.get("X-API-Key").and_then(|v| v.to_str().ok()) {
+        if api_key.starts_with("ak_") {
+            return key_principal(api_key, state, ip).await;
+        }
+    }
+
+    let auth_str = match headers.get(AUTHORIZATION).and_then(|v| v.to_str().ok()) {
+        Some(value) => value,
+        None => return format!("ip:{}", ip),
+    };
+
+    if let Some(token) = auth_str.strip_prefix("Bearer ") {
+        if token.starts_with("ak_") {
+            return key_principal(token, state, ip).await;
+        }
+        if let Ok(claims) = verify_token(token, state.config.auth.jwt_secret.expose_secret().as_bytes()) {
+            return format!("user:{}", claims.sub);
+        }
+    }
+
+    if let Some(encoded) = auth_str.strip_prefix("Basic ") {
+        let credentials = base64::prelude::BASE64_STANDARD
+            .decode(encoded)
+            .ok()
+            .and_then(|decoded| String::from_utf8(decoded).ok());
+        if let Some((username, password)) = credentials.as_deref().and_then(|c| c.split_once(':')) {
+            if password.starts_with("ak_") {
+                return key_principal(password, state, ip).await;
+            }
+            return format!("login:{}@{}", username, ip);
+        }
+    }
+
+    format!("ip:{}", ip)
+}
+
+/// A key gets its own budget once authentication has verified and cached it; until then it
+/// counts against the client address, so sending made-up keys does not buy fresh budgets
+async fn key_principal(api_key: &str, state: &AppState, ip: &str) -> String {
+    let key_hash = hash_api_key(api_key);
+    let verified = match &state.cache {
+        Some(cache) => cache.get_api_key_info(&key_hash).await.is_some(),
+        None => false,
+    };
+    if verified {
+        format!("key:{}", key_hash)
+    } else {
+        format!("ip:{}", ip)
+    }
+}
+
+fn too_many_requests(bucket: RateLimitBucket, limit: u64, retry_after: u64) -> Response {
+    let message = format!("Rate limit of {} {} requests exceeded; retry in {} seconds", limit, bucket.as_str(), retry_after);
+    let body = match bucket {
+        // Registry clients expect the distribution error format
+        RateLimitBucket::Pull | RateLimitBucket::Push => json!({
+            "errors": [{
+                "code": "TOOMANYREQUESTS",
+                "message": message,
+                "detail": {}
+            }]
+        }),
+        RateLimitBucket::Auth | RateLimitBucket::Api => json!({ "error": message }),
+    };
+
+    let mut response = (StatusCode::TOO_MANY_REQUESTS, Json(body)).into_response();
+    response.headers_mut().insert("Retry-After", HeaderValue::from(retry_after));
+    response.headers_mut().insert("X-RateLimit-Limit", HeaderValue::from(limit));
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classifies_requests_into_buckets() {
+        assert_eq!(RateLimitBucket::classify(&Method::HEAD, "/v2/acme/web/manifests/latest"), Some(RateLimitBucket::Pull));
+        assert_eq!(RateLimitBucket::classify(&Method::PATCH, "/v2/acme/web/blobs/uploads/123"), Some(RateLimitBucket::Push));
+        assert_eq!(RateLimitBucket::classify(&Method::POST, "/api/v1/auth/login"), Some(RateLimitBucket::Auth));
+        assert_eq!(RateLimitBucket::classify(&Method::GET, "/api/v1/auth/me"), Some(RateLimitBucket::Api));
+        assert_eq!(RateLimitBucket::classify(&Method::GET, "/health"), None);
+        assert_eq!(RateLimitBucket::classify(&Method::GET, "/v2beta"), None);
+    }
+}
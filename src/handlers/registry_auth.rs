@@ -0,0 +1,360 @@
+// src/handlers/registry_auth.rs - Shared authentication and repository authorization for registry handlers
+use std::{collections::HashMap, fmt, marker::PhantomData, net::SocketAddr};
+
+use async_trait::async_trait;
+use axum::{
+    extract::{ConnectInfo, FromRequestParts, Path},
+    http::{header::AUTHORIZATION, request::Parts, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use base64::Engine;
+
+use crate::{
+    auth::lookup_api_key,
+    handlers::docker_auth::{check_repository_permission, extract_user_from_auth, is_anonymous_pull_allowed},
+    log_stream::LogEvent,
+    models::api_key::{ApiResource, ResourceScope, ScopeLevel},
+    policies, quarantine, AppState,
+};
+
+/// Operation a registry request performs on a repository
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RegistryAction {
+    Pull,
+    Push,
+    Delete,
+}
+
+impl RegistryAction {
+    pub const ALL: [RegistryAction; 3] = [RegistryAction::Pull, RegistryAction::Push, RegistryAction::Delete];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RegistryAction::Pull => "pull",
+            RegistryAction::Push => "push",
+            RegistryAction::Delete => "delete",
+        }
+    }
+
+    /// API key scope needed to perform this action
+    pub fn required_scope(&self) -> ResourceScope {
+        let level = match self {
+            RegistryAction::Pull => ScopeLevel::Read,
+            RegistryAction::Push => ScopeLevel::Write,
+            RegistryAction::Delete => ScopeLevel::Admin,
+        };
+        ResourceScope::new(ApiResource::Repo, level)
+    }
+}
+
+impl fmt::Display for RegistryAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Who a registry request was made by, as resolved from its `Authorization` header
+#[derive(Debug, Clone)]
+pub struct AuthContext {
+    /// Principal ID: a user ID, or `org_<id>` for organization registry credentials. `None` when anonymous.
+    pub user: Option<String>,
+    /// Actions the credential itself may perform; repository permissions are checked on top of these
+    pub scopes: Vec<RegistryAction>,
+}
+
+#[async_trait]
+impl FromRequestParts<AppState> for AuthContext {
+    type Rejection = Response;
+
+    /// Missing credentials yield an anonymous context; invalid credentials are always rejected
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self, Self::Rejection> {
+        let peer = parts.extensions.get::<ConnectInfo<SocketAddr>>().map(|ConnectInfo(addr)| addr.ip());
+        let user = extract_user_from_auth(&parts.headers, peer, state, false).await?;
+        if let Some(user) = &user {
+            crate::logging::record_user(user);
+        }
+        let scopes = if user.is_some() { credential_scopes(&parts.headers, state).await } else { Vec::new() };
+        Ok(Self { user, scopes })
+    }
+}
+
+/// Actions an authenticated credential allows: all of them for passwords and session tokens,
+/// only those covered by its scopes for an API key given as the `docker login` password
+async fn credential_scopes(headers: &HeaderMap, state: &AppState) -> Vec<RegistryAction> {
+    let password = headers
+        .get(AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Basic "))
+        .and_then(|encoded| base64::prelude::BASE64_STANDARD.decode(encoded).ok())
+        .and_then(|decoded| String::from_utf8(decoded).ok())
+        .and_then(|decoded| decoded.split_once(':').map(|(_, password)| password.to_string()));
+
+    let api_key = match password {
+        Some(password) if password.starts_with("ak_") => password,
+        _ => return RegistryAction::ALL.to_vec(),
+    };
+
+    match lookup_api_key(&api_key, &state.db_pool, state.cache.as_ref()).await {
+        Ok((_, scopes)) => RegistryAction::ALL
+            .into_iter()
+            .filter(|action| action.required_scope().granted_by(&scopes))
+            .collect(),
+        // Authentication already succeeded, so this was an account password that merely looks like a key
+        Err(StatusCode::UNAUTHORIZED) => RegistryAction::ALL.to_vec(),
+        // The key's scopes could not be read; grant nothing rather than everything
+        Err(status) => {
+            tracing::error!("Could not load API key scopes ({}); allowing no registry actions", status);
+            Vec::new()
+        }
+    }
+}
+
+impl AuthContext {
+    pub fn is_anonymous(&self) -> bool {
+        self.user.is_none()
+    }
+
+    /// Numeric ID of an authenticated user; `None` for anonymous and organization credentials
+    pub fn user_id(&self) -> Option<i64> {
+        self.user.as_deref().and_then(|u| u.parse().ok())
+    }
+
+    /// The authenticated principal, or a 401 challenge
+    pub fn require_user(&self) -> Result<&str, Response> {
+        self.user.as_deref().ok_or_else(unauthorized)
+    }
+
+    /// Authorize `action` on repository `name` (`repo` or `namespace/repo`).
+    ///
+    /// Anonymous callers may only pull, and only from public `namespace/repo` repositories
+    /// when anonymous pulls are enabled.
+    pub async fn authorize(&self, state: &AppState, name: &str, action: RegistryAction) -> Result<RepoAccess, Response> {
+        crate::logging::record_repository(name);
+        let user_id = match &self.user {
+            Some(uid) => uid,
+            None => return self.authorize_anonymous(state, name, action).await,
+        };
+
+        let (namespace, repository) = parse_repository_name(name, user_id, state).await.map_err(|_| {
+            registry_error(StatusCode::BAD_REQUEST, "NAME_INVALID", "Invalid repository name format")
+        })?;
+
+        if !self.scopes.contains(&action) {
+            tracing::warn!("Credential for {} is not scoped for {} on {}/{}", user_id, action, namespace, repository);
+            return Err(denied(action));
+        }
+
+        match check_repository_permission(user_id, &namespace, &repository, action.as_str(), state).await {
+            Ok(true) => {
+                tracing::debug!("User {} has {} permission for {}/{}", user_id, action, namespace, repository);
+                Ok(RepoAccess { namespace, repository, user: self.user.clone() })
+            }
+            Ok(false) => {
+                tracing::warn!("User {} denied {} access to {}/{}", user_id, action, namespace, repository);
+                Err(denied(action))
+            }
+            Err(e) => {
+                tracing::error!("Error checking {} permission: {}", action, e);
+                Err(internal_error())
+            }
+        }
+    }
+
+    async fn authorize_anonymous(&self, state: &AppState, name: &str, action: RegistryAction) -> Result<RepoAccess, Response> {
+        // Anonymous callers have no user namespace, so only namespace/repo names qualify
+        let (namespace, repository) = match name.split_once('/') {
+            Some((ns, repo)) if action == RegistryAction::Pull => (ns, repo),
+            _ => return Err(unauthorized()),
+        };
+
+        match is_anonymous_pull_allowed(namespace, repository, state).await {
+            Ok(true) => {
+                tracing::debug!("Anonymous pull of public repository {}", name);
+                Ok(RepoAccess { namespace: namespace.to_string(), repository: repository.to_string(), user: None })
+            }
+            Ok(false) => Err(unauthorized()),
+            Err(e) => {
+                tracing::error!("Error checking anonymous access for {}: {}", name, e);
+                Err(internal_error())
+            }
+        }
+    }
+}
+
+/// A repository the caller has been authorized to act on
+#[derive(Debug, Clone)]
+pub struct RepoAccess {
+    pub namespace: String,
+    pub repository: String,
+    /// The authorized principal; `None` for anonymous pulls
+    pub user: Option<String>,
+}
+
+impl RepoAccess {
+    /// Numeric ID of the authorized user; `None` for anonymous and organization credentials
+    pub fn user_id(&self) -> Option<i64> {
+        self.user.as_deref().and_then(|u| u.parse().ok())
+    }
+}
+
+/// Type-level marker selecting the action a [`RequireRepoPermission`] guard checks
+pub trait RepoPermission: Send + Sync {
+    const ACTION: RegistryAction;
+}
+
+pub struct Pull;
+pub struct Push;
+pub struct Delete;
+
+impl RepoPermission for Pull {
+    const ACTION: RegistryAction = RegistryAction::Pull;
+}
+
+impl RepoPermission for Push {
+    const ACTION: RegistryAction = RegistryAction::Push;
+}
+
+impl RepoPermission for Delete {
+    const ACTION: RegistryAction = RegistryAction::Delete;
+}
+
+/// Extractor that rejects the request unless the caller may perform `P::ACTION` on the
+/// repository named by the route's `:name` (and optional `:org`) path parameters
+pub struct RequireRepoPermission<P: RepoPermission>(pub RepoAccess, pub PhantomData<P>);
+
+#[async_trait]
+impl<P: RepoPermission> FromRequestParts<AppState> for RequireRepoPermission<P> {
+    type Rejection = Response;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self, Self::Rejection> {
+        let auth = AuthContext::from_request_parts(parts, state).await?;
+
+        let Path(params) = Path::<HashMap<String, String>>::from_request_parts(parts, state)
+            .await
+            .map_err(IntoResponse::into_response)?;
+        let name = match (params.get("org"), params.get("name")) {
+            (Some(org), Some(name)) => format!("{}/{}", org, name),
+            (None, Some(name)) => name.clone(),
+            _ => return Err(registry_error(StatusCode::BAD_REQUEST, "NAME_INVALID", "Invalid repository name format")),
+        };
+
+        let access = auth.authorize(state, &name, P::ACTION).await?;
+        if parts.uri.path().contains("/manifests/") {
+            enforce_policies(state, &access, &params, P::ACTION).await?;
+        }
+        Ok(Self(access, PhantomData))
+    }
+}
+
+/// Refuse a pull of a quarantined manifest, and a manifest pull or push that violates a security
+/// policy of the repository
+async fn enforce_policies(
+    state: &AppState,
+    access: &RepoAccess,
+    params: &HashMap<String, String>,
+    action: RegistryAction,
+) -> Result<(), Response> {
+    let (Some(name), Some(reference)) = (params.get("name"), params.get("reference")) else {
+        return Ok(());
+    };
+    let namespace = params.get("org").map(String::as_str);
+    let violation = match action {
+        RegistryAction::Pull => match quarantine::check_pull(state, access, namespace, name, reference).await {
+            Ok(None) => policies::evaluate(&state.db_pool, namespace, name, action, reference).await,
+            held => held,
+        },
+        _ => policies::evaluate(&state.db_pool, namespace, name, action, reference).await,
+    };
+    match violation {
+        Ok(None) => Ok(()),
+        Ok(Some(violation)) => {
+            let repository = format!("{}/{}", access.namespace, access.repository);
+            tracing::warn!("{} of {}:{} denied by policy {}", action, repository, reference, violation.policy);
+            state.log_stream.publish(
+                LogEvent::audit("policy.deny", access.user_id(), Some(repository))
+                    .with_detail(format!("{} {}: {}", action, reference, violation.message)),
+            );
+            Err((
+                StatusCode::FORBIDDEN,
+                Json(serde_json::json!({
+                    "errors": [{
+                        "code": "DENIED",
+                        "message": violation.message,
+                        "detail": violation.detail,
+                    }]
+                })),
+            )
+                .into_response())
+        }
+        Err(e) => {
+            // Fail closed: a policy that could not be read must not let a violating image through
+            tracing::error!("Error evaluating security policies: {}", e);
+            Err(internal_error())
+        }
+    }
+}
+
+/// Split a repository name into (namespace, repository).
+/// Simple names like "hello-world" live in the caller's username namespace;
+/// namespaced names like "myorg/hello-world" use the explicit namespace, and any further
+/// components ("myorg/team/hello-world") belong to the repository name ("team/hello-world").
+pub(crate) async fn parse_repository_name(name: &str, user_id: &str, state: &AppState) -> Result<(String, String), String> {
+    match name.split_once('/') {
+        None => {
+            // Simple name like "hello-world" - use username as namespace
+            let user_id_int: i64 = user_id.parse().map_err(|_| "Invalid user ID".to_string())?;
+
+            // Fetch username from database
+            match crate::database::queries::get_user_by_id(&state.db_pool, user_id_int).await {
+                Ok(Some(user)) => {
+                    Ok((user.username, name.to_string()))
+                }
+                Ok(None) => {
+                    Err("User not found".to_string())
+                }
+                Err(_) => {
+                    Err("Database error".to_string())
+                }
+            }
+        }
+        Some((namespace, repository)) if !namespace.is_empty() && repository.split('/').all(|part| !part.is_empty()) => {
+            // Namespaced name like "myorg/hello-world" or "myorg/team/hello-world"
+            Ok((namespace.to_string(), repository.to_string()))
+        }
+        Some(_) => {
+            Err("Invalid repository name format".to_string())
+        }
+    }
+}
+
+fn registry_error(status: StatusCode, code: &str, message: &str) -> Response {
+    (status, Json(serde_json::json!({
+        "errors": [{
+            "code": code,
+            "message": message,
+            "detail": {}
+        }]
+    }))).into_response()
+}
+
+fn unauthorized() -> Response {
+    (
+        StatusCode::UNAUTHORIZED,
+        [("WWW-Authenticate", "Basic")],
+        registry_error(StatusCode::UNAUTHORIZED, "UNAUTHORIZED", "Authentication required"),
+    ).into_response()
+}
+
+fn denied(action: RegistryAction) -> Response {
+    let message = match action {
+        RegistryAction::Pull => "Insufficient permissions to pull from repository",
+        RegistryAction::Push => "Insufficient permissions to push to repository",
+        RegistryAction::Delete => "Insufficient permissions to delete from repository",
+    };
+    registry_error(StatusCode::FORBIDDEN, "DENIED", message)
+}
+
+fn internal_error() -> Response {
+    registry_error(StatusCode::INTERNAL_SERVER_ERROR, "UNKNOWN", "Internal server error")
+}
@@ -10,11 +10,17 @@ use axum_extra::headers::{authorization::Bearer, Authorization};
 use axum_extra::TypedHeader;
 use secrecy::ExposeSecret;
 use utoipa::{OpenApi, ToSchema};
+use std::collections::{HashMap, HashSet};
 
 use crate::{
     auth::{extract_user_id_dual, extract_user_id, verify_token},
     database::models::{Organization, Repository},
-    models::repository_with_org::RepositoryWithOrgRow,
+    handlers::docker_auth::check_repository_permission,
+    handlers::organizations::{load_org_settings, org_storage_used},
+    log_stream::LogEvent,
+    models::{api_key::ApiKeyScope, organizations::OrganizationRole, repository_with_org::RepositoryWithOrgRow},
+    naming::{self, NameError},
+    quota::{QuotaExceeded, QuotaLimit},
     AppState,
 };
 
@@ -22,14 +28,24 @@ use crate::{
 pub struct CreateRepositoryRequest {
     pub name: String,
     pub description: Option<String>,
-    pub is_public: bool,
+    /// Defaults to the organization's `default_repository_public` setting
+    #[serde(default)]
+    pub is_public: Option<bool>,
 }
 
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct UpdateRepositoryRequest {
+    /// Renames the repository; pulls of the old name are redirected for `REPOSITORY_REDIRECT_GRACE_DAYS`
     pub name: Option<String>,
     pub description: Option<String>,
     pub is_public: Option<bool>,
+    /// Rate each blob download from the repository is held to, overriding the organization's;
+    /// `0` removes the override
+    pub download_bytes_per_second: Option<i64>,
+    /// Refuse pulls of images without a verified cosign or Notation signature
+    pub require_signature: Option<bool>,
+    /// Hold newly pushed images back from pulls until they are scanned or approved
+    pub quarantine_pushes: Option<bool>,
 }
 
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
@@ -42,6 +58,9 @@ pub struct RepositoryResponse {
     pub created_by: Option<i64>,
     pub created_at: chrono::DateTime<chrono::Utc>,
     pub updated_at: chrono::DateTime<chrono::Utc>,
+    /// Pulls and pushes counted so far; batched counts can take `ACTIVITY_FLUSH_INTERVAL_SECONDS` to show
+    pub pull_count: i64,
+    pub push_count: i64,
     pub organization: OrganizationInfo,
 }
 
@@ -71,6 +90,28 @@ pub struct ListRepositoriesQuery {
     pub namespace: Option<String>,
 }
 
+fn invalid_name(e: NameError) -> Response {
+    (StatusCode::BAD_REQUEST, Json(json!({
+        "error": e.to_string()
+    }))).into_response()
+}
+
+/// Look up the caller's role in an organization, `None` if they are not a member
+async fn member_role<'e, E>(executor: E, org_id: i64, user_id: i64) -> Result<Option<OrganizationRole>, sqlx::Error>
+where
+    E: sqlx::Executor<'e, Database = sqlx::Postgres>,
+{
+    let role = sqlx::query_scalar::<_, String>(
+        "SELECT role FROM organization_members WHERE organization_id = $1 AND user_id = $2"
+    )
+    .bind(org_id)
+    .bind(user_id)
+    .fetch_optional(executor)
+    .await?;
+
+    Ok(role.and_then(|r| r.parse().ok()))
+}
+
 #[utoipa::path(
     get,
     path = "/api/v1/repos/repositories",
@@ -97,6 +138,7 @@ pub async fn list_repositories(
     let user_id = match extract_user_id_dual(
         auth, 
         &headers, 
+        ApiKeyScope::Read,
         secret, 
         &state.db_pool, 
         state.cache.as_ref()
@@ -133,7 +175,7 @@ pub async fn list_repositories(
         match sqlx::query_as::<_, RepositoryWithOrgRow>(
             r#"
             SELECT DISTINCT 
-                r.id, r.organization_id, r.name, r.description, r.is_public, r.created_by, r.created_at, r.updated_at,
+                r.id, r.organization_id, r.name, r.description, r.is_public, r.created_by, r.created_at, r.updated_at, r.pull_count, r.push_count,
                 o.id as org_id, o.name as org_name, o.display_name as org_display_name, o.description as org_description, o.website_url as org_website_url
             FROM repositories r
             JOIN organizations o ON r.organization_id = o.id
@@ -158,7 +200,7 @@ pub async fn list_repositories(
         match sqlx::query_as::<_, RepositoryWithOrgRow>(
             r#"
             SELECT DISTINCT 
-                r.id, r.organization_id, r.name, r.description, r.is_public, r.created_by, r.created_at, r.updated_at,
+                r.id, r.organization_id, r.name, r.description, r.is_public, r.created_by, r.created_at, r.updated_at, r.pull_count, r.push_count,
                 o.id as org_id, o.name as org_name, o.display_name as org_display_name, o.description as org_description, o.website_url as org_website_url
             FROM repositories r
             JOIN organizations o ON r.organization_id = o.id
@@ -189,6 +231,8 @@ pub async fn list_repositories(
             created_by: repo.created_by,
             created_at: repo.created_at,
             updated_at: repo.updated_at,
+            pull_count: repo.pull_count,
+            push_count: repo.push_count,
             organization: OrganizationInfo {
                 id: repo.org_id,
                 name: repo.org_name,
@@ -236,6 +280,7 @@ pub async fn list_repositories_by_namespace(
     let user_id = match extract_user_id_dual(
         auth, 
         &headers, 
+        ApiKeyScope::Read,
         secret, 
         &state.db_pool, 
         state.cache.as_ref()
@@ -272,7 +317,7 @@ pub async fn list_repositories_by_namespace(
     let repositories = match sqlx::query_as::<_, RepositoryWithOrgRow>(
         r#"
         SELECT DISTINCT 
-            r.id, r.organization_id, r.name, r.description, r.is_public, r.created_by, r.created_at, r.updated_at,
+            r.id, r.organization_id, r.name, r.description, r.is_public, r.created_by, r.created_at, r.updated_at, r.pull_count, r.push_count,
             o.id as org_id, o.name as org_name, o.display_name as org_display_name, o.description as org_description, o.website_url as org_website_url
         FROM repositories r
         JOIN organizations o ON r.organization_id = o.id
@@ -304,6 +349,8 @@ pub async fn list_repositories_by_namespace(
             created_by: repo.created_by,
             created_at: repo.created_at,
             updated_at: repo.updated_at,
+            pull_count: repo.pull_count,
+            push_count: repo.push_count,
             organization: OrganizationInfo {
                 id: repo.org_id,
                 name: repo.org_name,
@@ -327,6 +374,8 @@ pub async fn list_repositories_by_namespace(
     request_body = CreateRepositoryRequest,
     responses(
         (status = 200, description = "Repository creation temporarily disabled"),
+        (status = 400, description = "Invalid or reserved repository name"),
+        (status = 403, description = "Organization is at its tier's repository limit"),
         (status = 500, description = "Internal server error")
     ),
     security(
@@ -346,17 +395,27 @@ pub async fn create_repository(
     let user_id = match extract_user_id_dual(
         auth, 
         &headers, 
+        ApiKeyScope::Admin,
         secret, 
         &state.db_pool, 
         state.cache.as_ref()
     ).await {
         Ok(id) => id,
+        Err(StatusCode::FORBIDDEN) => {
+            return (StatusCode::FORBIDDEN, Json(json!({
+                "error": "API key is not scoped for admin access"
+            }))).into_response()
+        }
         Err(_) => {
             return (StatusCode::UNAUTHORIZED, Json(json!({
                 "error": "Authentication required"
             }))).into_response()
         }
     };
+
+    if let Err(e) = naming::validate_repository_name(&namespace, &request.name) {
+        return invalid_name(e)
+    }
     
     // First, find the organization by name
     let org = match sqlx::query_as::<_, Organization>(
@@ -378,6 +437,21 @@ pub async fn create_repository(
         }
     };
 
+    // Guests cannot create repositories
+    match member_role(&state.db_pool, org.id, user_id).await {
+        Ok(Some(role)) if role.can_create_repositories() => {}
+        Ok(_) => {
+            return (StatusCode::FORBIDDEN, Json(json!({
+                "error": format!("You don't have permission to create repositories in organization '{}'", namespace)
+            }))).into_response()
+        }
+        Err(e) => {
+            return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({
+                "error": format!("Database error checking permissions: {}", e)
+            }))).into_response()
+        }
+    }
+
     // Check if repository already exists in this organization
     let existing_repo = sqlx::query_scalar::<_, bool>(
         "SELECT EXISTS(SELECT 1 FROM repositories WHERE organization_id = $1 AND name = $2)"
@@ -401,6 +475,16 @@ pub async fn create_repository(
         _ => {} // Continue if repo doesn't exist
     }
 
+    match crate::quota::check_repositories(&state.db_pool, org.id).await {
+        Ok(None) => {}
+        Ok(Some(exceeded)) => return exceeded.api_error().into_response(),
+        Err(e) => {
+            return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({
+                "error": format!("Database error: {}", e)
+            }))).into_response()
+        }
+    }
+
     // Start a database transaction
     let mut tx = match state.db_pool.begin().await {
         Ok(tx) => tx,
@@ -411,17 +495,30 @@ pub async fn create_repository(
         }
     };
 
+    let org_settings = match load_org_settings(&mut *tx, org.id).await {
+        Ok(settings) => settings,
+        Err(e) => {
+            let _ = tx.rollback().await;
+            return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({
+                "error": format!("Database error loading organization settings: {}", e)
+            }))).into_response()
+        }
+    };
+
     // Create the repository
     let repository = match sqlx::query_as::<_, crate::database::models::Repository>(
-        "INSERT INTO repositories (organization_id, name, description, is_public, created_by, created_at, updated_at)
-         VALUES ($1, $2, $3, $4, $5, CURRENT_TIMESTAMP, CURRENT_TIMESTAMP)
+        "INSERT INTO repositories (organization_id, name, description, is_public, created_by, created_at, updated_at,
+                                   tag_retention_keep_last, tag_retention_days)
+         VALUES ($1, $2, $3, $4, $5, CURRENT_TIMESTAMP, CURRENT_TIMESTAMP, $6, $7)
          RETURNING *",
     )
     .bind(org.id)
     .bind(&request.name)
     .bind(&request.description)
-    .bind(request.is_public)
+    .bind(request.is_public.unwrap_or(org_settings.default_repository_public))
     .bind(user_id)
+    .bind(org_settings.default_tag_retention_keep_last)
+    .bind(org_settings.default_tag_retention_days)
     .fetch_one(&mut *tx)
     .await {
         Ok(repo) => repo,
@@ -440,6 +537,12 @@ pub async fn create_repository(
         }))).into_response()
     }
 
+    state.log_stream.publish(LogEvent::audit(
+        "repository.create",
+        Some(user_id),
+        Some(format!("{}/{}", org.name, repository.name)),
+    ));
+
     // Return the created repository
     let response = RepositoryResponse {
         id: repository.id,
@@ -450,6 +553,8 @@ pub async fn create_repository(
         created_by: repository.created_by,
         created_at: repository.created_at,
         updated_at: repository.updated_at,
+        pull_count: repository.pull_count,
+        push_count: repository.push_count,
         organization: OrganizationInfo {
             id: org.id,
             name: org.name,
@@ -491,7 +596,7 @@ pub async fn update_repository(
     Json(request): Json<UpdateRepositoryRequest>,
 ) -> Response {
     // Extract user ID from JWT token
-    let user_id = match extract_user_id(auth, state.config.auth.jwt_secret.expose_secret().as_bytes()).await {
+    let user_id = match extract_user_id(auth, state.config.auth.jwt_secret.expose_secret().as_bytes(), &state.db_pool).await {
         Ok(id) => id,
         Err(e) => {
             return (StatusCode::UNAUTHORIZED, Json(json!({
@@ -552,15 +657,9 @@ pub async fn update_repository(
     };
 
     // Check if user has permission to update the repository
-    // User must be organization member to update repositories
-    let is_member = match sqlx::query_scalar::<_, bool>(
-        "SELECT EXISTS(SELECT 1 FROM organization_members WHERE organization_id = $1 AND user_id = $2)"
-    )
-    .bind(org.id)
-    .bind(user_id)
-    .fetch_one(&mut *tx)
-    .await {
-        Ok(exists) => exists,
+    // Only owners and maintainers can change repository settings
+    let can_manage = match member_role(&mut *tx, org.id, user_id).await {
+        Ok(role) => role.map(|r| r.can_manage_repositories()).unwrap_or(false),
         Err(e) => {
             return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({
                 "error": format!("Database error checking permissions: {}", e)
@@ -568,7 +667,7 @@ pub async fn update_repository(
         }
     };
 
-    if !is_member {
+    if !can_manage {
         return (StatusCode::FORBIDDEN, Json(json!({
             "error": "You don't have permission to update repositories in this organization"
         }))).into_response()
@@ -583,11 +682,11 @@ pub async fn update_repository(
             }))).into_response()
         }
 
-        // Validate name format (allow alphanumeric, hyphens, underscores, dots)
-        if !name.chars().all(|c| c.is_alphanumeric() || c == '-' || c == '_' || c == '.') {
-            return (StatusCode::BAD_REQUEST, Json(json!({
-                "error": "Repository name can only contain letters, numbers, hyphens, underscores, and dots"
-            }))).into_response()
+        // Existing names predating the naming rules stay usable; only new names are checked
+        if name != &repository.name {
+            if let Err(e) = naming::validate_repository_name(&namespace, name) {
+                return invalid_name(e)
+            }
         }
 
         // Check if new name already exists in the organization (and it's not the current repository)
@@ -616,6 +715,39 @@ pub async fn update_repository(
         }
     }
 
+    if let Some(rate) = request.download_bytes_per_second {
+        if rate != 0 && rate < 1024 {
+            return (StatusCode::BAD_REQUEST, Json(json!({
+                "error": "download_bytes_per_second must be at least 1024, or 0 to remove the limit"
+            }))).into_response()
+        }
+    }
+
+    // A new name keeps the old one pulling through a redirect, like a transfer
+    let renamed = request.name.as_ref().is_some_and(|name| name != &repository.name);
+    let old_full_name = format!("{}/{}", namespace, repository.name);
+    if renamed {
+        if let Err(e) = keep_content_after_move(&mut tx, repository.id, &old_full_name, "repository renamed").await {
+            let _ = tx.rollback().await;
+            return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({
+                "error": format!("Failed to keep repository content under the new name: {}", e)
+            }))).into_response()
+        }
+        if let Err(e) = crate::handlers::repository_redirects::record_redirect(
+            &mut *tx,
+            &namespace,
+            &repository.name,
+            repository.id,
+            user_id,
+            state.config.redirects.grace_days,
+        ).await {
+            let _ = tx.rollback().await;
+            return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({
+                "error": format!("Failed to record redirect: {}", e)
+            }))).into_response()
+        }
+    }
+
     // Build dynamic update query based on provided fields
     let mut update_fields = Vec::new();
     let mut query_params = Vec::new();
@@ -639,6 +771,24 @@ pub async fn update_repository(
         param_counter += 1;
     }
 
+    if let Some(rate) = request.download_bytes_per_second {
+        update_fields.push(format!("download_bytes_per_second = NULLIF(${}, 0)", param_counter));
+        query_params.push(rate.to_string());
+        param_counter += 1;
+    }
+
+    if let Some(require_signature) = request.require_signature {
+        update_fields.push(format!("require_signature = ${}", param_counter));
+        query_params.push(require_signature.to_string());
+        param_counter += 1;
+    }
+
+    if let Some(quarantine_pushes) = request.quarantine_pushes {
+        update_fields.push(format!("quarantine_pushes = ${}", param_counter));
+        query_params.push(quarantine_pushes.to_string());
+        param_counter += 1;
+    }
+
     // Always update the updated_at timestamp
     update_fields.push("updated_at = CURRENT_TIMESTAMP".to_string());
 
@@ -667,6 +817,15 @@ pub async fn update_repository(
     if let Some(is_public) = request.is_public {
         query = query.bind(is_public);
     }
+    if let Some(rate) = request.download_bytes_per_second {
+        query = query.bind(rate);
+    }
+    if let Some(require_signature) = request.require_signature {
+        query = query.bind(require_signature);
+    }
+    if let Some(quarantine_pushes) = request.quarantine_pushes {
+        query = query.bind(quarantine_pushes);
+    }
     query = query.bind(repository.id);
 
     let updated_repository = match query.fetch_one(&mut *tx).await {
@@ -686,6 +845,25 @@ pub async fn update_repository(
         }))).into_response()
     }
 
+    if renamed {
+        let new_full_name = format!("{}/{}", namespace, updated_repository.name);
+        if let Some(cache) = &state.cache {
+            let results = [
+                cache.invalidate("manifests").await,
+                cache.invalidate_repositories().await,
+                cache.invalidate_tags(&old_full_name).await,
+            ];
+            for e in results.into_iter().filter_map(|r| r.err()) {
+                tracing::warn!("Failed to invalidate cache after renaming {}: {}", old_full_name, e);
+            }
+        }
+        state.log_stream.publish(
+            LogEvent::audit("repository.rename", Some(user_id), Some(new_full_name.clone()))
+                .with_detail(format!("from {}", old_full_name)),
+        );
+        tracing::info!("Renamed repository {} to {}", old_full_name, new_full_name);
+    }
+
     // Return the updated repository
     let response = RepositoryResponse {
         id: updated_repository.id,
@@ -696,6 +874,8 @@ pub async fn update_repository(
         created_by: updated_repository.created_by,
         created_at: updated_repository.created_at,
         updated_at: updated_repository.updated_at,
+        pull_count: updated_repository.pull_count,
+        push_count: updated_repository.push_count,
         organization: OrganizationInfo {
             id: org.id,
             name: org.name,
@@ -718,7 +898,7 @@ pub async fn update_repository(
     responses(
         (status = 200, description = "Repository deleted successfully"),
         (status = 401, description = "Authentication required"),
-        (status = 403, description = "Permission denied"),
+        (status = 403, description = "Permission denied, or deletes are disabled"),
         (status = 404, description = "Repository not found"),
         (status = 500, description = "Internal server error")
     ),
@@ -731,6 +911,12 @@ pub async fn delete_repository(
     State(state): State<AppState>,
     headers: axum::http::HeaderMap,
 ) -> Response {
+    if !state.features.enabled(crate::features::Feature::AllowDelete) {
+        return (StatusCode::FORBIDDEN, Json(json!({
+            "error": "Deletes are disabled on this registry"
+        }))).into_response();
+    }
+
     // Extract JWT token from Authorization header
     let auth_header = match headers.get("authorization") {
         Some(header) => header.to_str().unwrap_or(""),
@@ -750,7 +936,7 @@ pub async fn delete_repository(
     };
 
     // Verify JWT token and get user_id
-    let claims = match crate::auth::verify_token(token, state.config.auth.jwt_secret.expose_secret().as_bytes()) {
+    let claims = match crate::auth::verify_token_not_revoked(token, state.config.auth.jwt_secret.expose_secret().as_bytes(), &state.db_pool).await {
         Ok(claims) => claims,
         Err(_) => {
             return (StatusCode::UNAUTHORIZED, Json(json!({
@@ -820,15 +1006,9 @@ pub async fn delete_repository(
     };
 
     // Check if user has permission to delete the repository
-    // User must be organization member to delete repositories
-    let has_permission = match sqlx::query_scalar::<_, bool>(
-        "SELECT EXISTS(SELECT 1 FROM organization_members WHERE organization_id = $1 AND user_id = $2)"
-    )
-    .bind(org.id)
-    .bind(user_id)
-    .fetch_one(&mut *tx)
-    .await {
-        Ok(has_perm) => has_perm,
+    // Only owners and maintainers can delete repositories
+    let has_permission = match member_role(&mut *tx, org.id, user_id).await {
+        Ok(role) => role.map(|r| r.can_delete_repositories()).unwrap_or(false),
         Err(e) => {
             return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({
                 "error": format!("Permission check error: {}", e)
@@ -842,6 +1022,30 @@ pub async fn delete_repository(
         }))).into_response()
     }
 
+    match crate::handlers::legal_holds::is_under_legal_hold(&state.db_pool, org.id).await {
+        Ok(false) => {}
+        Ok(true) => {
+            return (StatusCode::FORBIDDEN, Json(json!({
+                "error": format!("Organization '{}' is under legal hold; deletes are blocked until the hold is released", namespace)
+            }))).into_response()
+        }
+        Err(e) => {
+            return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({
+                "error": format!("Legal hold check error: {}", e)
+            }))).into_response()
+        }
+    }
+
+    // The repository's own webhooks are deleted with it; they still hear about the deletion
+    let webhooks = match crate::webhooks::delivery::repository_targets(&mut *tx, repository.id).await {
+        Ok(webhooks) => webhooks,
+        Err(e) => {
+            return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({
+                "error": format!("Database error: {}", e)
+            }))).into_response()
+        }
+    };
+
     // Delete the repository
     match sqlx::query("DELETE FROM repositories WHERE id = $1")
         .bind(repository.id)
@@ -862,6 +1066,10 @@ pub async fn delete_repository(
         }))).into_response()
     }
 
+    let event = LogEvent::audit("repository.delete", Some(user_id), Some(format!("{}/{}", namespace, repo_name)));
+    crate::webhooks::delivery::deliver_to(&state, webhooks, event.clone());
+    state.log_stream.publish(event);
+
     // Return 200 OK with success message
     (StatusCode::OK, Json(json!({
         "message": format!("Repository '{}/{}' has been deleted successfully", namespace, repo_name)
@@ -896,6 +1104,7 @@ pub async fn get_repository(
     let user_id = match extract_user_id_dual(
         auth, 
         &headers, 
+        ApiKeyScope::Read,
         secret, 
         &state.db_pool, 
         state.cache.as_ref()
@@ -949,12 +1158,14 @@ pub async fn get_repository(
         }
     };
 
-    // Check if user has access to this repository (member of organization)
+    // Check if user has access to this repository (member of organization or collaborator)
     let has_access = match sqlx::query_scalar::<_, bool>(
-        "SELECT EXISTS(SELECT 1 FROM organization_members WHERE organization_id = $1 AND user_id = $2)"
+        "SELECT EXISTS(SELECT 1 FROM organization_members WHERE organization_id = $1 AND user_id = $2)
+             OR EXISTS(SELECT 1 FROM repository_collaborators WHERE repository_id = $3 AND user_id = $2)"
     )
     .bind(org.id)
     .bind(user_id)
+    .bind(repository.id)
     .fetch_one(&state.db_pool)
     .await {
         Ok(has_access) => has_access,
@@ -1000,6 +1211,8 @@ pub async fn get_repository(
         created_by: repository.created_by,
         created_at: repository.created_at,
         updated_at: repository.updated_at,
+        pull_count: repository.pull_count,
+        push_count: repository.push_count,
         organization: OrganizationInfo {
             id: org.id,
             name: org.name,
@@ -1040,7 +1253,7 @@ pub async fn list_public_repositories(
         match sqlx::query_as::<_, RepositoryWithOrgRow>(
             r#"
             SELECT 
-                r.id, r.organization_id, r.name, r.description, r.is_public, r.created_by, r.created_at, r.updated_at,
+                r.id, r.organization_id, r.name, r.description, r.is_public, r.created_by, r.created_at, r.updated_at, r.pull_count, r.push_count,
                 o.id as org_id, o.name as org_name, o.display_name as org_display_name, o.description as org_description, o.website_url as org_website_url
             FROM repositories r
             JOIN organizations o ON r.organization_id = o.id
@@ -1064,7 +1277,7 @@ pub async fn list_public_repositories(
         match sqlx::query_as::<_, RepositoryWithOrgRow>(
             r#"
             SELECT 
-                r.id, r.organization_id, r.name, r.description, r.is_public, r.created_by, r.created_at, r.updated_at,
+                r.id, r.organization_id, r.name, r.description, r.is_public, r.created_by, r.created_at, r.updated_at, r.pull_count, r.push_count,
                 o.id as org_id, o.name as org_name, o.display_name as org_display_name, o.description as org_description, o.website_url as org_website_url
             FROM repositories r
             JOIN organizations o ON r.organization_id = o.id
@@ -1094,6 +1307,8 @@ pub async fn list_public_repositories(
             created_by: repo.created_by,
             created_at: repo.created_at,
             updated_at: repo.updated_at,
+            pull_count: repo.pull_count,
+            push_count: repo.push_count,
             organization: OrganizationInfo {
                 id: repo.org_id,
                 name: repo.org_name,
@@ -1109,3 +1324,891 @@ pub async fn list_public_repositories(
         "total": response_repositories.len()
     }))).into_response()
 }
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct CloneRepositoryRequest {
+    /// Name of the new repository
+    pub name: String,
+    /// Organization to create the clone in (defaults to the source organization)
+    pub target_namespace: Option<String>,
+    /// Description for the clone (defaults to the source description)
+    pub description: Option<String>,
+    /// Visibility of the clone (defaults to the source visibility)
+    pub is_public: Option<bool>,
+    /// Tags to copy (defaults to every tag of the source repository)
+    pub tags: Option<Vec<String>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct CloneRepositoryResponse {
+    pub repository: RepositoryResponse,
+    /// Tags copied into the new repository
+    pub tags: Vec<String>,
+    /// Number of blobs mounted from the source repository
+    pub mounted_blobs: usize,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct SourceManifestRow {
+    id: i64,
+    digest: String,
+    media_type: String,
+    size: i64,
+    content: Option<String>,
+}
+
+/// Collect the digests a manifest or image index references
+fn referenced_digests(content: &str) -> Vec<String> {
+    let value: serde_json::Value = match serde_json::from_str(content) {
+        Ok(value) => value,
+        Err(_) => return Vec::new(),
+    };
+
+    let mut digests = Vec::new();
+    if let Some(digest) = value.pointer("/config/digest").and_then(|d| d.as_str()) {
+        digests.push(digest.to_string());
+    }
+    for field in ["layers", "manifests", "blobs"] {
+        if let Some(entries) = value.get(field).and_then(|e| e.as_array()) {
+            digests.extend(
+                entries
+                    .iter()
+                    .filter_map(|entry| entry.get("digest").and_then(|d| d.as_str()))
+                    .map(|d| d.to_string()),
+            );
+        }
+    }
+    digests
+}
+
+/// Duplicate a repository's metadata and tags into a new repository.
+///
+/// Content is never copied: every manifest, config and layer is mounted from
+/// the source repository's storage, so the clone is cheap regardless of size.
+#[utoipa::path(
+    post,
+    path = "/api/v1/repos/{namespace}/{repo_name}/clone",
+    params(
+        ("namespace" = String, Path, description = "Source organization namespace"),
+        ("repo_name" = String, Path, description = "Source repository name")
+    ),
+    request_body = CloneRepositoryRequest,
+    responses(
+        (status = 201, description = "Repository cloned successfully", body = CloneRepositoryResponse),
+        (status = 400, description = "Invalid repository name or tag selection"),
+        (status = 401, description = "Authentication required"),
+        (status = 403, description = "Not allowed to read the source or write the target organization, or a storage or repository limit exceeded"),
+        (status = 404, description = "Source repository or target organization not found"),
+        (status = 409, description = "Target repository already exists"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "repositories",
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn clone_repository(
+    Path((namespace, repo_name)): Path<(String, String)>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    auth: Option<TypedHeader<Authorization<Bearer>>>,
+    Json(request): Json<CloneRepositoryRequest>,
+) -> Response {
+    let secret = state.config.auth.jwt_secret.expose_secret().as_bytes();
+
+    let user_id = match extract_user_id_dual(
+        auth,
+        &headers,
+        ApiKeyScope::Admin,
+        secret,
+        &state.db_pool,
+        state.cache.as_ref()
+    ).await {
+        Ok(id) => id,
+        Err(StatusCode::FORBIDDEN) => {
+            return (StatusCode::FORBIDDEN, Json(json!({
+                "error": "API key is not scoped for admin access"
+            }))).into_response()
+        }
+        Err(_) => {
+            return (StatusCode::UNAUTHORIZED, Json(json!({
+                "error": "Authentication required"
+            }))).into_response()
+        }
+    };
+
+    let target_namespace = request.target_namespace.clone().unwrap_or_else(|| namespace.clone());
+    if let Err(e) = naming::validate_repository_name(&target_namespace, &request.name) {
+        return invalid_name(e)
+    }
+
+    // Resolve the source repository
+    let source_repo = match sqlx::query_as::<_, Repository>(
+        "SELECT r.* FROM repositories r
+         JOIN organizations o ON r.organization_id = o.id
+         WHERE o.name = $1 AND r.name = $2"
+    )
+    .bind(&namespace)
+    .bind(&repo_name)
+    .fetch_optional(&state.db_pool)
+    .await {
+        Ok(Some(repo)) => repo,
+        Ok(None) => {
+            return (StatusCode::NOT_FOUND, Json(json!({
+                "error": format!("Repository '{}/{}' not found", namespace, repo_name)
+            }))).into_response()
+        }
+        Err(e) => {
+            return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({
+                "error": format!("Database error: {}", e)
+            }))).into_response()
+        }
+    };
+
+    // Source must be pullable by the user: public, through their organization role, or through a
+    // team or direct collaborator grant
+    match check_repository_permission(&user_id.to_string(), &namespace, &repo_name, "pull", &state).await {
+        Ok(true) => {}
+        Ok(false) => {
+            return (StatusCode::FORBIDDEN, Json(json!({
+                "error": "You don't have permission to read this repository"
+            }))).into_response()
+        }
+        Err(e) => {
+            return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({
+                "error": format!("Database error checking permissions: {}", e)
+            }))).into_response()
+        }
+    }
+
+    // Resolve the target organization
+    let target_org = match sqlx::query_as::<_, Organization>(
+        "SELECT * FROM organizations WHERE name = $1"
+    )
+    .bind(&target_namespace)
+    .fetch_optional(&state.db_pool)
+    .await {
+        Ok(Some(org)) => org,
+        Ok(None) => {
+            return (StatusCode::NOT_FOUND, Json(json!({
+                "error": format!("Organization '{}' not found", target_namespace)
+            }))).into_response()
+        }
+        Err(e) => {
+            return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({
+                "error": format!("Database error: {}", e)
+            }))).into_response()
+        }
+    };
+
+    let can_create_in_target = match member_role(&state.db_pool, target_org.id, user_id).await {
+        Ok(role) => role.map(|r| r.can_create_repositories()).unwrap_or(false),
+        Err(e) => {
+            return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({
+                "error": format!("Database error checking permissions: {}", e)
+            }))).into_response()
+        }
+    };
+
+    if !can_create_in_target {
+        return (StatusCode::FORBIDDEN, Json(json!({
+            "error": "You don't have permission to create repositories in the target organization"
+        }))).into_response()
+    }
+
+    match crate::quota::check_repositories(&state.db_pool, target_org.id).await {
+        Ok(None) => {}
+        Ok(Some(exceeded)) => return exceeded.api_error().into_response(),
+        Err(e) => {
+            return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({
+                "error": format!("Database error: {}", e)
+            }))).into_response()
+        }
+    }
+    let target_tier = match crate::quota::org_tier(&state.db_pool, target_org.id).await {
+        Ok(tier) => tier,
+        Err(e) => {
+            return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({
+                "error": format!("Database error: {}", e)
+            }))).into_response()
+        }
+    };
+
+    // Select the tags to copy
+    let source_tags = match sqlx::query_as::<_, (String, i64)>(
+        "SELECT name, manifest_id FROM tags WHERE repository_id = $1 ORDER BY name"
+    )
+    .bind(source_repo.id)
+    .fetch_all(&state.db_pool)
+    .await {
+        Ok(tags) => tags,
+        Err(e) => {
+            return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({
+                "error": format!("Database error: {}", e)
+            }))).into_response()
+        }
+    };
+
+    let selected_tags: Vec<(String, i64)> = match &request.tags {
+        Some(wanted) => {
+            let missing: Vec<&String> = wanted
+                .iter()
+                .filter(|tag| !source_tags.iter().any(|(name, _)| name == *tag))
+                .collect();
+            if !missing.is_empty() {
+                return (StatusCode::BAD_REQUEST, Json(json!({
+                    "error": format!("Tags not found in source repository: {:?}", missing)
+                }))).into_response()
+            }
+            source_tags.into_iter().filter(|(name, _)| wanted.contains(name)).collect()
+        }
+        None => source_tags,
+    };
+
+    let source_full_name = format!("{}/{}", namespace, repo_name);
+
+    let mut tx = match state.db_pool.begin().await {
+        Ok(tx) => tx,
+        Err(e) => {
+            return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({
+                "error": format!("Failed to start transaction: {}", e)
+            }))).into_response()
+        }
+    };
+
+    let target_settings = match load_org_settings(&mut *tx, target_org.id).await {
+        Ok(settings) => settings,
+        Err(e) => {
+            let _ = tx.rollback().await;
+            return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({
+                "error": format!("Database error loading organization settings: {}", e)
+            }))).into_response()
+        }
+    };
+
+    let repository = match sqlx::query_as::<_, Repository>(
+        "INSERT INTO repositories (organization_id, name, description, is_public, created_by, created_at, updated_at,
+                                   tag_retention_keep_last, tag_retention_days)
+         VALUES ($1, $2, $3, $4, $5, CURRENT_TIMESTAMP, CURRENT_TIMESTAMP, $6, $7)
+         RETURNING *",
+    )
+    .bind(target_org.id)
+    .bind(&request.name)
+    .bind(request.description.as_ref().or(source_repo.description.as_ref()))
+    .bind(request.is_public.unwrap_or(source_repo.is_public))
+    .bind(user_id)
+    .bind(target_settings.default_tag_retention_keep_last)
+    .bind(target_settings.default_tag_retention_days)
+    .fetch_one(&mut *tx)
+    .await {
+        Ok(repo) => repo,
+        Err(e) => {
+            let _ = tx.rollback().await;
+            if e.to_string().contains("duplicate key") {
+                return (StatusCode::CONFLICT, Json(json!({
+                    "error": format!("Repository '{}' already exists in organization '{}'", request.name, target_namespace)
+                }))).into_response()
+            }
+            return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({
+                "error": format!("Failed to create repository: {}", e)
+            }))).into_response()
+        }
+    };
+
+    // Walk every manifest reachable from the selected tags, copying manifest
+    // rows and mounting each referenced digest from the source repository.
+    let mut pending: Vec<String> = Vec::new();
+    for (_, manifest_id) in &selected_tags {
+        match sqlx::query_scalar::<_, String>("SELECT digest FROM manifests WHERE id = $1")
+            .bind(manifest_id)
+            .fetch_one(&mut *tx)
+            .await
+        {
+            Ok(digest) => pending.push(digest),
+            Err(e) => {
+                let _ = tx.rollback().await;
+                return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({
+                    "error": format!("Database error: {}", e)
+                }))).into_response()
+            }
+        }
+    }
+
+    let mut manifest_ids: HashMap<i64, i64> = HashMap::new();
+    let mut mounted: HashSet<String> = HashSet::new();
+
+    while let Some(digest) = pending.pop() {
+        if !mounted.insert(digest.clone()) {
+            continue;
+        }
+
+        // Chain to the original location if the source itself is a clone
+        let mount_result = sqlx::query(
+            "INSERT INTO blob_mounts (repository_id, digest, source_key)
+             VALUES ($1, $2, COALESCE(
+                 (SELECT source_key FROM blob_mounts WHERE repository_id = $3 AND digest = $2),
+                 $4
+             ))
+             ON CONFLICT (repository_id, digest) DO NOTHING"
+        )
+        .bind(repository.id)
+        .bind(&digest)
+        .bind(source_repo.id)
+        .bind(format!("{}/{}", source_full_name, digest))
+        .execute(&mut *tx)
+        .await;
+
+        if let Err(e) = mount_result {
+            let _ = tx.rollback().await;
+            return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({
+                "error": format!("Failed to mount blob {}: {}", digest, e)
+            }))).into_response()
+        }
+
+        let source_manifest = match sqlx::query_as::<_, SourceManifestRow>(
+            "SELECT id, digest, media_type, size, content FROM manifests WHERE repository_id = $1 AND digest = $2"
+        )
+        .bind(source_repo.id)
+        .bind(&digest)
+        .fetch_optional(&mut *tx)
+        .await {
+            Ok(row) => row,
+            Err(e) => {
+                let _ = tx.rollback().await;
+                return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({
+                    "error": format!("Database error: {}", e)
+                }))).into_response()
+            }
+        };
+
+        let Some(source_manifest) = source_manifest else {
+            continue;
+        };
+
+        let new_manifest_id = match sqlx::query_scalar::<_, i64>(
+            "INSERT INTO manifests (repository_id, digest, media_type, size, content)
+             VALUES ($1, $2, $3, $4, $5)
+             RETURNING id"
+        )
+        .bind(repository.id)
+        .bind(&source_manifest.digest)
+        .bind(&source_manifest.media_type)
+        .bind(source_manifest.size)
+        .bind(&source_manifest.content)
+        .fetch_one(&mut *tx)
+        .await {
+            Ok(id) => id,
+            Err(e) => {
+                let _ = tx.rollback().await;
+                return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({
+                    "error": format!("Failed to copy manifest {}: {}", digest, e)
+                }))).into_response()
+            }
+        };
+        manifest_ids.insert(source_manifest.id, new_manifest_id);
+
+        // Only manifests and indexes reference further content; layer rows don't
+        if !(source_manifest.media_type.contains("manifest") || source_manifest.media_type.contains("index")) {
+            continue;
+        }
+
+        let content = match source_manifest.content {
+            Some(content) => Some(content),
+            None => crate::handlers::docker_registry_v2::load_manifest_content(&state, &source_full_name, &digest)
+                .await
+                .ok()
+                .flatten()
+                .and_then(|bytes| String::from_utf8(bytes.to_vec()).ok()),
+        };
+
+        if let Some(content) = content {
+            pending.extend(referenced_digests(&content));
+        }
+    }
+
+    // Copied rows count against the target organization's quota like pushed content does
+    if let Some((allowed, tier)) = crate::quota::storage_limit(target_settings.storage_quota_bytes, target_tier.as_ref()) {
+        match org_storage_used(&mut *tx, target_org.id).await {
+            Ok(used) if used > allowed => {
+                let _ = tx.rollback().await;
+                let exceeded = QuotaExceeded { limit: QuotaLimit::Storage, tier, allowed, current: used };
+                return exceeded.api_error().into_response()
+            }
+            Ok(_) => {}
+            Err(e) => {
+                let _ = tx.rollback().await;
+                return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({
+                    "error": format!("Database error: {}", e)
+                }))).into_response()
+            }
+        }
+    }
+
+    let mut cloned_tags = Vec::new();
+    for (tag_name, manifest_id) in &selected_tags {
+        let Some(new_manifest_id) = manifest_ids.get(manifest_id) else {
+            continue;
+        };
+
+        if let Err(e) = sqlx::query(
+            "INSERT INTO tags (repository_id, name, manifest_id, created_at, updated_at)
+             VALUES ($1, $2, $3, CURRENT_TIMESTAMP, CURRENT_TIMESTAMP)"
+        )
+        .bind(repository.id)
+        .bind(tag_name)
+        .bind(new_manifest_id)
+        .execute(&mut *tx)
+        .await
+        {
+            let _ = tx.rollback().await;
+            return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({
+                "error": format!("Failed to copy tag {}: {}", tag_name, e)
+            }))).into_response()
+        }
+        cloned_tags.push(tag_name.clone());
+    }
+
+    if let Err(e) = tx.commit().await {
+        return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({
+            "error": format!("Failed to commit transaction: {}", e)
+        }))).into_response()
+    }
+
+    if let Some(cache) = &state.cache {
+        if let Err(e) = cache.invalidate_repositories().await {
+            tracing::warn!("Failed to invalidate repository cache: {}", e);
+        }
+    }
+
+    tracing::info!(
+        "Cloned repository {} into {}/{} ({} tags, {} blobs mounted)",
+        source_full_name, target_namespace, request.name, cloned_tags.len(), mounted.len()
+    );
+
+    let response = CloneRepositoryResponse {
+        repository: RepositoryResponse {
+            id: repository.id,
+            organization_id: repository.organization_id,
+            name: repository.name,
+            description: repository.description,
+            is_public: repository.is_public,
+            created_by: repository.created_by,
+            created_at: repository.created_at,
+            updated_at: repository.updated_at,
+            pull_count: repository.pull_count,
+            push_count: repository.push_count,
+            organization: OrganizationInfo {
+                id: target_org.id,
+                name: target_org.name,
+                display_name: Some(target_org.display_name),
+                description: target_org.description,
+                website_url: target_org.website_url,
+            },
+        },
+        tags: cloned_tags,
+        mounted_blobs: mounted.len(),
+    };
+
+    (StatusCode::CREATED, Json(response)).into_response()
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct TransferRepositoryRequest {
+    /// Organization to move the repository to; the caller must own it
+    pub target_namespace: String,
+    /// New name in the target organization (defaults to the current name)
+    pub name: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct TransferRepositoryResponse {
+    pub repository: RepositoryResponse,
+    /// Former `namespace/name`, which keeps redirecting pulls until `redirect_expires_at`
+    pub redirected_from: String,
+    pub redirect_expires_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Move a repository, with its manifests, tags and collaborators, to another organization.
+///
+/// Stored content stays where it is and is mounted under the new name. Team grants belong to
+/// the old organization and are removed, as are unfinished uploads. Pulls of the old name are
+/// redirected to the new one for `REPOSITORY_REDIRECT_GRACE_DAYS`, unless a new repository
+/// takes the old name first.
+#[utoipa::path(
+    post,
+    path = "/api/v1/repos/{namespace}/{repo_name}/transfer",
+    params(
+        ("namespace" = String, Path, description = "Current organization namespace"),
+        ("repo_name" = String, Path, description = "Current repository name")
+    ),
+    request_body = TransferRepositoryRequest,
+    responses(
+        (status = 200, description = "Repository transferred", body = TransferRepositoryResponse),
+        (status = 400, description = "Invalid repository name or the repository is already there"),
+        (status = 401, description = "Authentication required"),
+        (status = 403, description = "Not allowed to remove the repository from its organization, not an owner of the target, legal hold, or a storage or repository limit exceeded"),
+        (status = 404, description = "Repository or target organization not found"),
+        (status = 409, description = "Target organization already has a repository with that name"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "repositories",
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn transfer_repository(
+    Path((namespace, repo_name)): Path<(String, String)>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    auth: Option<TypedHeader<Authorization<Bearer>>>,
+    Json(request): Json<TransferRepositoryRequest>,
+) -> Response {
+    let secret = state.config.auth.jwt_secret.expose_secret().as_bytes();
+
+    let user_id = match extract_user_id_dual(
+        auth,
+        &headers,
+        ApiKeyScope::Admin,
+        secret,
+        &state.db_pool,
+        state.cache.as_ref()
+    ).await {
+        Ok(id) => id,
+        Err(StatusCode::FORBIDDEN) => {
+            return (StatusCode::FORBIDDEN, Json(json!({
+                "error": "API key is not scoped for admin access"
+            }))).into_response()
+        }
+        Err(_) => {
+            return (StatusCode::UNAUTHORIZED, Json(json!({
+                "error": "Authentication required"
+            }))).into_response()
+        }
+    };
+
+    let new_name = request.name.clone().unwrap_or_else(|| repo_name.clone());
+    if new_name != repo_name {
+        if let Err(e) = naming::validate_repository_name(&request.target_namespace, &new_name) {
+            return invalid_name(e)
+        }
+    }
+
+    let repository = match sqlx::query_as::<_, Repository>(
+        "SELECT r.* FROM repositories r
+         JOIN organizations o ON r.organization_id = o.id
+         WHERE o.name = $1 AND r.name = $2"
+    )
+    .bind(&namespace)
+    .bind(&repo_name)
+    .fetch_optional(&state.db_pool)
+    .await {
+        Ok(Some(repo)) => repo,
+        Ok(None) => {
+            return (StatusCode::NOT_FOUND, Json(json!({
+                "error": format!("Repository '{}/{}' not found", namespace, repo_name)
+            }))).into_response()
+        }
+        Err(e) => {
+            return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({
+                "error": format!("Database error: {}", e)
+            }))).into_response()
+        }
+    };
+
+    // Moving a repository out removes it from its organization, like deleting it does
+    let can_remove = match member_role(&state.db_pool, repository.organization_id, user_id).await {
+        Ok(role) => role.map(|r| r.can_delete_repositories()).unwrap_or(false),
+        Err(e) => {
+            return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({
+                "error": format!("Database error checking permissions: {}", e)
+            }))).into_response()
+        }
+    };
+
+    if !can_remove {
+        return (StatusCode::FORBIDDEN, Json(json!({
+            "error": format!("You don't have permission to move repositories out of organization '{}'", namespace)
+        }))).into_response()
+    }
+
+    match crate::handlers::legal_holds::is_under_legal_hold(&state.db_pool, repository.organization_id).await {
+        Ok(false) => {}
+        Ok(true) => {
+            return (StatusCode::FORBIDDEN, Json(json!({
+                "error": format!("Organization '{}' is under legal hold; its repositories cannot be moved until the hold is released", namespace)
+            }))).into_response()
+        }
+        Err(e) => {
+            return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({
+                "error": format!("Legal hold check error: {}", e)
+            }))).into_response()
+        }
+    }
+
+    let target_org = match sqlx::query_as::<_, Organization>(
+        "SELECT * FROM organizations WHERE name = $1"
+    )
+    .bind(&request.target_namespace)
+    .fetch_optional(&state.db_pool)
+    .await {
+        Ok(Some(org)) => org,
+        Ok(None) => {
+            return (StatusCode::NOT_FOUND, Json(json!({
+                "error": format!("Organization '{}' not found", request.target_namespace)
+            }))).into_response()
+        }
+        Err(e) => {
+            return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({
+                "error": format!("Database error: {}", e)
+            }))).into_response()
+        }
+    };
+
+    if target_org.id == repository.organization_id {
+        return (StatusCode::BAD_REQUEST, Json(json!({
+            "error": format!("Repository '{}/{}' already belongs to organization '{}'", namespace, repo_name, target_org.name)
+        }))).into_response()
+    }
+
+    match member_role(&state.db_pool, target_org.id, user_id).await {
+        Ok(Some(OrganizationRole::Owner)) => {}
+        Ok(_) => {
+            return (StatusCode::FORBIDDEN, Json(json!({
+                "error": format!("Only owners of organization '{}' can transfer repositories into it", target_org.name)
+            }))).into_response()
+        }
+        Err(e) => {
+            return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({
+                "error": format!("Database error checking permissions: {}", e)
+            }))).into_response()
+        }
+    }
+
+    match crate::quota::check_repositories(&state.db_pool, target_org.id).await {
+        Ok(None) => {}
+        Ok(Some(exceeded)) => return exceeded.api_error().into_response(),
+        Err(e) => {
+            return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({
+                "error": format!("Database error: {}", e)
+            }))).into_response()
+        }
+    }
+    let target_tier = match crate::quota::org_tier(&state.db_pool, target_org.id).await {
+        Ok(tier) => tier,
+        Err(e) => {
+            return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({
+                "error": format!("Database error: {}", e)
+            }))).into_response()
+        }
+    };
+
+    let old_full_name = format!("{}/{}", namespace, repo_name);
+
+    let mut tx = match state.db_pool.begin().await {
+        Ok(tx) => tx,
+        Err(e) => {
+            return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({
+                "error": format!("Failed to start transaction: {}", e)
+            }))).into_response()
+        }
+    };
+
+    if let Err(e) = keep_content_after_move(&mut tx, repository.id, &old_full_name, "repository transferred").await {
+        let _ = tx.rollback().await;
+        return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({
+            "error": format!("Failed to keep repository content under the new name: {}", e)
+        }))).into_response()
+    }
+
+    // Teams are scoped to the old organization; collaborators are individual grants and stay
+    if let Err(e) = sqlx::query("DELETE FROM team_repositories WHERE repository_id = $1")
+        .bind(repository.id)
+        .execute(&mut *tx)
+        .await
+    {
+        let _ = tx.rollback().await;
+        return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({
+            "error": format!("Failed to remove team permissions: {}", e)
+        }))).into_response()
+    }
+
+    let moved = match sqlx::query_as::<_, Repository>(
+        "UPDATE repositories SET organization_id = $2, name = $3, updated_at = CURRENT_TIMESTAMP
+         WHERE id = $1
+         RETURNING *"
+    )
+    .bind(repository.id)
+    .bind(target_org.id)
+    .bind(&new_name)
+    .fetch_one(&mut *tx)
+    .await {
+        Ok(repo) => repo,
+        Err(e) => {
+            let _ = tx.rollback().await;
+            if e.to_string().contains("duplicate key") {
+                return (StatusCode::CONFLICT, Json(json!({
+                    "error": format!("Repository '{}' already exists in organization '{}'", new_name, target_org.name)
+                }))).into_response()
+            }
+            return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({
+                "error": format!("Failed to move repository: {}", e)
+            }))).into_response()
+        }
+    };
+
+    // The repository's content now counts against the target organization's quota
+    let target_settings = match load_org_settings(&mut *tx, target_org.id).await {
+        Ok(settings) => settings,
+        Err(e) => {
+            let _ = tx.rollback().await;
+            return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({
+                "error": format!("Database error loading organization settings: {}", e)
+            }))).into_response()
+        }
+    };
+    if let Some((allowed, tier)) = crate::quota::storage_limit(target_settings.storage_quota_bytes, target_tier.as_ref()) {
+        match org_storage_used(&mut *tx, target_org.id).await {
+            Ok(used) if used > allowed => {
+                let _ = tx.rollback().await;
+                let exceeded = QuotaExceeded { limit: QuotaLimit::Storage, tier, allowed, current: used };
+                return exceeded.api_error().into_response()
+            }
+            Ok(_) => {}
+            Err(e) => {
+                let _ = tx.rollback().await;
+                return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({
+                    "error": format!("Database error: {}", e)
+                }))).into_response()
+            }
+        }
+    }
+
+    let redirect_expires_at = match crate::handlers::repository_redirects::record_redirect(
+        &mut *tx,
+        &namespace,
+        &repo_name,
+        repository.id,
+        user_id,
+        state.config.redirects.grace_days,
+    ).await {
+        Ok(expires_at) => expires_at,
+        Err(e) => {
+            let _ = tx.rollback().await;
+            return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({
+                "error": format!("Failed to record redirect: {}", e)
+            }))).into_response()
+        }
+    };
+
+    let affected_users = match sqlx::query_scalar::<_, i64>(
+        "SELECT DISTINCT user_id FROM organization_members WHERE organization_id IN ($1, $2)"
+    )
+    .bind(repository.organization_id)
+    .bind(target_org.id)
+    .fetch_all(&mut *tx)
+    .await {
+        Ok(users) => users,
+        Err(e) => {
+            let _ = tx.rollback().await;
+            return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({
+                "error": format!("Database error: {}", e)
+            }))).into_response()
+        }
+    };
+
+    if let Err(e) = tx.commit().await {
+        return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({
+            "error": format!("Failed to commit transaction: {}", e)
+        }))).into_response()
+    }
+
+    let new_full_name = format!("{}/{}", target_org.name, moved.name);
+
+    if let Some(cache) = &state.cache {
+        let mut results = vec![
+            cache.invalidate("manifests").await,
+            cache.invalidate_repositories().await,
+            cache.invalidate_tags(&old_full_name).await,
+        ];
+        for affected in &affected_users {
+            results.push(cache.invalidate_user_permissions(&affected.to_string()).await);
+        }
+        for e in results.into_iter().filter_map(|r| r.err()) {
+            tracing::warn!("Failed to invalidate cache after transferring {}: {}", old_full_name, e);
+        }
+    }
+
+    state.log_stream.publish(
+        LogEvent::audit("repository.transfer", Some(user_id), Some(new_full_name.clone()))
+            .with_detail(format!("from {}", old_full_name)),
+    );
+
+    tracing::info!("Transferred repository {} to {}", old_full_name, new_full_name);
+
+    let response = TransferRepositoryResponse {
+        repository: RepositoryResponse {
+            id: moved.id,
+            organization_id: moved.organization_id,
+            name: moved.name,
+            description: moved.description,
+            is_public: moved.is_public,
+            created_by: moved.created_by,
+            created_at: moved.created_at,
+            updated_at: moved.updated_at,
+            pull_count: moved.pull_count,
+            push_count: moved.push_count,
+            organization: OrganizationInfo {
+                id: target_org.id,
+                name: target_org.name,
+                display_name: Some(target_org.display_name),
+                description: target_org.description,
+                website_url: target_org.website_url,
+            },
+        },
+        redirected_from: old_full_name,
+        redirect_expires_at,
+    };
+
+    (StatusCode::OK, Json(response)).into_response()
+}
+
+/// Keep a moved repository's content readable under its new name
+///
+/// Content stays under the old storage keys; it is mounted so reads under the new name and
+/// garbage collection both find it. Existing mounts already point at the original location.
+/// Unfinished uploads are staged under the old name and are abandoned.
+async fn keep_content_after_move(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    repository_id: i64,
+    old_full_name: &str,
+    reason: &str,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO blob_mounts (repository_id, digest, source_key)
+         SELECT $1, digest, $2 || '/' || digest FROM (
+             SELECT digest FROM manifests WHERE repository_id = $1
+             UNION
+             SELECT target_digest FROM blob_transcodes WHERE repository_id = $1
+         ) stored
+         ON CONFLICT (repository_id, digest) DO NOTHING"
+    )
+    .bind(repository_id)
+    .bind(old_full_name)
+    .execute(&mut **tx)
+    .await?;
+
+    sqlx::query(
+        "INSERT INTO blob_gc_queue (storage_key, reason)
+         SELECT unnest(chunk_keys), $2
+         FROM blob_uploads WHERE repository_id = $1 AND completed_at IS NULL"
+    )
+    .bind(repository_id)
+    .bind(reason)
+    .execute(&mut **tx)
+    .await?;
+    sqlx::query("UPDATE blob_uploads SET completed_at = NOW() WHERE repository_id = $1 AND completed_at IS NULL")
+        .bind(repository_id)
+        .execute(&mut **tx)
+        .await?;
+    Ok(())
+}
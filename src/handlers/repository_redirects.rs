@@ -0,0 +1,140 @@
+// src/handlers/repository_redirects.rs - Redirect pulls of a repository's former name
+use std::collections::HashMap;
+
+use axum::{
+    extract::{Path, Request, State},
+    http::{header::LOCATION, HeaderName, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use chrono::{DateTime, Utc};
+use sqlx::{PgExecutor, PgPool};
+
+use crate::AppState;
+
+/// Remember `old_namespace/old_name` as a former name of a repository for `grace_days`
+pub(crate) async fn record_redirect<'e, E: PgExecutor<'e>>(
+    executor: E,
+    old_namespace: &str,
+    old_name: &str,
+    repository_id: i64,
+    created_by: i64,
+    grace_days: i64,
+) -> Result<DateTime<Utc>, sqlx::Error> {
+    sqlx::query_scalar::<_, DateTime<Utc>>(
+        "INSERT INTO repository_redirects (old_namespace, old_name, repository_id, created_by, expires_at)
+         VALUES ($1, $2, $3, $4, NOW() + make_interval(days => $5))
+         RETURNING expires_at",
+    )
+    .bind(old_namespace)
+    .bind(old_name)
+    .bind(repository_id)
+    .bind(created_by)
+    .bind(grace_days as i32)
+    .fetch_one(executor)
+    .await
+}
+
+/// Current `namespace/name` of the repository that used to be called `name` in `namespace`
+/// (the default organization when `None`) and when the redirect expires, while it is unexpired
+/// and no repository has taken over the old name
+pub async fn find_redirect(
+    pool: &PgPool,
+    namespace: Option<&str>,
+    name: &str,
+) -> Result<Option<(String, DateTime<Utc>)>, sqlx::Error> {
+    sqlx::query_as::<_, (String, DateTime<Utc>)>(
+        "SELECT o.name || '/' || r.name, rr.expires_at
+         FROM repository_redirects rr
+         JOIN repositories r ON r.id = rr.repository_id
+         JOIN organizations o ON o.id = r.organization_id
+         WHERE rr.old_name = $2
+           AND rr.old_namespace = COALESCE($1, (SELECT name FROM organizations WHERE id = 1))
+           AND rr.expires_at > NOW()
+           AND NOT EXISTS (
+               SELECT 1 FROM repositories cur
+               JOIN organizations co ON co.id = cur.organization_id
+               WHERE co.name = rr.old_namespace AND cur.name = rr.old_name
+           )
+         ORDER BY rr.created_at DESC
+         LIMIT 1",
+    )
+    .bind(namespace)
+    .bind(name)
+    .fetch_optional(pool)
+    .await
+}
+
+/// Middleware answering pulls (GET/HEAD) of a moved repository's former name with a
+/// `307 Temporary Redirect` to the same path under its current name
+///
+/// The `Deprecation` and `Sunset` headers tell clients the old name stops working when the
+/// redirect expires.
+pub async fn redirect_moved_repositories(
+    State(state): State<AppState>,
+    path: Option<Path<HashMap<String, String>>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if !matches!(*request.method(), Method::GET | Method::HEAD) {
+        return next.run(request).await;
+    }
+    let Some(Path(params)) = path else {
+        return next.run(request).await;
+    };
+    let Some(name) = params.get("name") else {
+        return next.run(request).await;
+    };
+    let namespace = params.get("org").map(String::as_str);
+
+    let old_prefix = match namespace {
+        Some(org) => format!("/v2/{}/{}", org, name),
+        None => format!("/v2/{}", name),
+    };
+    let Some(rest) = request.uri().path().strip_prefix(&old_prefix) else {
+        return next.run(request).await;
+    };
+
+    match find_redirect(&state.db_pool, namespace, name).await {
+        Ok(Some((current, expires_at))) => {
+            let mut location = format!("/v2/{}{}", current, rest);
+            if let Some(query) = request.uri().query() {
+                location.push('?');
+                location.push_str(query);
+            }
+            tracing::debug!("Redirecting pull of moved repository {} to {}", old_prefix, location);
+            (
+                StatusCode::TEMPORARY_REDIRECT,
+                [
+                    (LOCATION, location),
+                    (HeaderName::from_static("deprecation"), "true".to_string()),
+                    (HeaderName::from_static("sunset"), http_date(expires_at)),
+                ],
+            )
+                .into_response()
+        }
+        Ok(None) => next.run(request).await,
+        Err(e) => {
+            // The repository may simply not exist; let the handler answer as it would
+            tracing::warn!("Error looking up repository redirects: {}", e);
+            next.run(request).await
+        }
+    }
+}
+
+/// IMF-fixdate, as HTTP date headers are written
+fn http_date(at: DateTime<Utc>) -> String {
+    at.format("%a, %d %b %Y %H:%M:%S GMT").to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn sunset_is_an_http_date() {
+        let at = Utc.with_ymd_and_hms(2025, 11, 6, 8, 49, 37).unwrap();
+        assert_eq!(http_date(at), "Thu, 06 Nov 2025 08:49:37 GMT");
+    }
+}
@@ -0,0 +1,134 @@
+// src/handlers/repository_stats.rs - Pull and push counts of a repository and its tags
+use axum::{
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use axum_extra::headers::{authorization::Bearer, Authorization};
+use axum_extra::TypedHeader;
+use chrono::{DateTime, Utc};
+use secrecy::ExposeSecret;
+use serde_json::json;
+
+use crate::{
+    auth::extract_user_id_dual,
+    handlers::docker_auth::check_repository_permission,
+    handlers::tag_cleanup::{find_repository, internal_error, repository_not_found},
+    models::api_key::ApiKeyScope,
+    models::organizations::{ActivityWindow, DailyActivity},
+    models::repository_stats::{RepositoryStats, RepositoryStatsQuery, TagUsage},
+    AppState,
+};
+
+/// Pull and push statistics of a repository
+///
+/// Running totals, counts over the last 1, 7 and 30 days, a daily series and the most pulled
+/// tags. Requires pull access.
+#[utoipa::path(
+    get,
+    path = "/api/v1/repos/{namespace}/{repo_name}/stats",
+    params(
+        ("namespace" = String, Path, description = "Organization namespace"),
+        ("repo_name" = String, Path, description = "Repository name"),
+        RepositoryStatsQuery
+    ),
+    responses(
+        (status = 200, description = "Repository statistics", body = RepositoryStats),
+        (status = 401, description = "Authentication required"),
+        (status = 404, description = "Repository not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "repositories",
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn get_repository_stats(
+    Path((namespace, repo_name)): Path<(String, String)>,
+    State(state): State<AppState>,
+    Query(query): Query<RepositoryStatsQuery>,
+    headers: HeaderMap,
+    auth: Option<TypedHeader<Authorization<Bearer>>>,
+) -> Response {
+    let secret = state.config.auth.jwt_secret.expose_secret().as_bytes();
+    let user_id = match extract_user_id_dual(auth, &headers, ApiKeyScope::Read, secret, &state.db_pool, state.cache.as_ref()).await {
+        Ok(id) => id,
+        Err(status) => return (status, Json(json!({"error": "Authentication required"}))).into_response(),
+    };
+    match check_repository_permission(&user_id.to_string(), &namespace, &repo_name, "pull", &state).await {
+        Ok(true) => {}
+        Ok(false) => return repository_not_found(&namespace, &repo_name),
+        Err(e) => return internal_error(e),
+    }
+    let (repository_id, _) = match find_repository(&state, &namespace, &repo_name).await {
+        Ok(Some(ids)) => ids,
+        Ok(None) => return repository_not_found(&namespace, &repo_name),
+        Err(e) => return internal_error(e),
+    };
+
+    let days = query.days.unwrap_or(30).clamp(1, 365);
+    let name = format!("{}/{}", namespace, repo_name);
+    match load_stats(&state, repository_id, name, days).await {
+        Ok(stats) => (StatusCode::OK, Json(json!(stats))).into_response(),
+        Err(e) => internal_error(e),
+    }
+}
+
+async fn load_stats(state: &AppState, repository_id: i64, repository: String, days: i32) -> anyhow::Result<RepositoryStats> {
+    let pool = &state.db_pool;
+
+    let (pull_count, push_count, last_pulled_at, last_pushed_at) =
+        sqlx::query_as::<_, (i64, i64, Option<DateTime<Utc>>, Option<DateTime<Utc>>)>(
+            "SELECT pull_count, push_count, last_pulled_at, last_pushed_at FROM repositories WHERE id = $1",
+        )
+        .bind(repository_id)
+        .fetch_one(pool)
+        .await?;
+
+    let windows = sqlx::query_as::<_, ActivityWindow>(
+        "SELECT w.days, COALESCE(SUM(a.pull_count), 0)::BIGINT AS pulls, COALESCE(SUM(a.push_count), 0)::BIGINT AS pushes
+         FROM UNNEST($2::INT[]) AS w(days)
+         LEFT JOIN repository_activity_daily a ON a.repository_id = $1 AND a.day > CURRENT_DATE - w.days
+         GROUP BY w.days
+         ORDER BY w.days",
+    )
+    .bind(repository_id)
+    .bind(vec![1_i32, 7, 30])
+    .fetch_all(pool)
+    .await?;
+
+    let daily = sqlx::query_as::<_, DailyActivity>(
+        "SELECT d::DATE AS day, COALESCE(a.pull_count, 0)::BIGINT AS pulls, COALESCE(a.push_count, 0)::BIGINT AS pushes
+         FROM generate_series(CURRENT_DATE - ($2 - 1), CURRENT_DATE, INTERVAL '1 day') AS d
+         LEFT JOIN repository_activity_daily a ON a.repository_id = $1 AND a.day = d::DATE
+         ORDER BY d",
+    )
+    .bind(repository_id)
+    .bind(days)
+    .fetch_all(pool)
+    .await?;
+
+    let top_tags = sqlx::query_as::<_, TagUsage>(
+        "SELECT t.name AS tag, m.digest, t.pull_count, t.push_count, t.last_pulled_at
+         FROM tags t
+         JOIN manifests m ON m.id = t.manifest_id
+         WHERE t.repository_id = $1
+         ORDER BY t.pull_count DESC, t.last_pulled_at DESC NULLS LAST, t.name
+         LIMIT 20",
+    )
+    .bind(repository_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(RepositoryStats {
+        repository,
+        pull_count,
+        push_count,
+        last_pulled_at,
+        last_pushed_at,
+        windows,
+        daily,
+        top_tags,
+    })
+}
@@ -0,0 +1,534 @@
+// src/handlers/repository_webhooks.rs - Webhook endpoints registered on a single repository
+//
+// Deliveries are made by `crate::webhooks::delivery` alongside the organization's webhooks;
+// these handlers manage the endpoints and expose their recorded delivery attempts.
+use anyhow::{bail, Result};
+use axum::{
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+use axum_extra::headers::{authorization::Bearer, Authorization};
+use axum_extra::TypedHeader;
+use secrecy::ExposeSecret;
+use sqlx::PgPool;
+
+use crate::{
+    auth::extract_user_id_dual,
+    handlers::organization_webhooks::{load_deliveries, normalize_events, redeliver_internal, validate_format, validate_url},
+    handlers::organizations::get_user_role_in_org,
+    log_stream::LogEvent,
+    models::api_key::ApiKeyScope,
+    models::webhook::{
+        CreateOrganizationWebhookRequest, RepositoryWebhook, UpdateOrganizationWebhookRequest, WebhookDelivery,
+        WebhookDeliveryPage, WebhookDeliveryQuery,
+    },
+    AppState,
+};
+
+/// Webhooks per repository
+const MAX_WEBHOOKS: i64 = 10;
+
+const WEBHOOK_COLUMNS: &str = "id, repository_id, url, events, format, active, secret IS NOT NULL AS has_secret,
+     created_by, created_at, updated_at, last_delivery_at, last_delivery_status, last_delivery_error";
+
+/// Register a webhook for events in one repository
+///
+/// Receives the same signed JSON POSTs as organization webhooks, for pushes (`manifest.push`),
+/// deletions (`tag.delete`, `manifest.delete`, `repository.delete`) and every other audit event
+/// of the repository. Owners and maintainers only.
+#[utoipa::path(
+    post,
+    path = "/api/v1/repos/{namespace}/{repo_name}/webhooks",
+    tag = "repositories",
+    params(
+        ("namespace" = String, Path, description = "Organization namespace"),
+        ("repo_name" = String, Path, description = "Repository name")
+    ),
+    request_body = CreateOrganizationWebhookRequest,
+    responses(
+        (status = 201, description = "Webhook registered", body = RepositoryWebhook),
+        (status = 400, description = "Invalid URL or event filter, repository not found or not allowed"),
+        (status = 401, description = "Unauthorized")
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn create_repository_webhook(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    auth: Option<TypedHeader<Authorization<Bearer>>>,
+    Path((namespace, repo_name)): Path<(String, String)>,
+    Json(req): Json<CreateOrganizationWebhookRequest>,
+) -> impl IntoResponse {
+    let secret = state.config.auth.jwt_secret.expose_secret().as_bytes();
+    let user_id = match extract_user_id_dual(auth, &headers, ApiKeyScope::Push, secret, &state.db_pool, state.cache.as_ref()).await {
+        Ok(id) => id,
+        Err(status) => {
+            return (
+                status,
+                Json(serde_json::json!({
+                    "error": "Unauthorized"
+                })),
+            );
+        }
+    };
+
+    match create_webhook_internal(&state.db_pool, &namespace, &repo_name, user_id, req).await {
+        Ok(webhook) => {
+            state.log_stream.publish(
+                LogEvent::audit("repository.webhook.create", Some(user_id), Some(format!("{}/{}", namespace, repo_name)))
+                    .with_detail(format!("webhook {} -> {}", webhook.id, webhook.url)),
+            );
+            (StatusCode::CREATED, Json(serde_json::to_value(&webhook).unwrap_or_default()))
+        }
+        Err(e) => (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({
+                "error": e.to_string()
+            })),
+        ),
+    }
+}
+
+/// List a repository's webhooks with the outcome of their last delivery
+#[utoipa::path(
+    get,
+    path = "/api/v1/repos/{namespace}/{repo_name}/webhooks",
+    tag = "repositories",
+    params(
+        ("namespace" = String, Path, description = "Organization namespace"),
+        ("repo_name" = String, Path, description = "Repository name")
+    ),
+    responses(
+        (status = 200, description = "Webhooks of the repository", body = Vec<RepositoryWebhook>),
+        (status = 400, description = "Repository not found or not allowed"),
+        (status = 401, description = "Unauthorized")
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn list_repository_webhooks(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    auth: Option<TypedHeader<Authorization<Bearer>>>,
+    Path((namespace, repo_name)): Path<(String, String)>,
+) -> impl IntoResponse {
+    let secret = state.config.auth.jwt_secret.expose_secret().as_bytes();
+    let user_id = match extract_user_id_dual(auth, &headers, ApiKeyScope::Read, secret, &state.db_pool, state.cache.as_ref()).await {
+        Ok(id) => id,
+        Err(status) => {
+            return (
+                status,
+                Json(serde_json::json!({
+                    "error": "Unauthorized"
+                })),
+            );
+        }
+    };
+
+    match list_webhooks_internal(&state.db_pool, &namespace, &repo_name, user_id).await {
+        Ok(webhooks) => (
+            StatusCode::OK,
+            Json(serde_json::json!({
+                "webhooks": webhooks
+            })),
+        ),
+        Err(e) => (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({
+                "error": e.to_string()
+            })),
+        ),
+    }
+}
+
+/// Change a repository webhook's URL, secret, event filter or whether it is active
+#[utoipa::path(
+    put,
+    path = "/api/v1/repos/{namespace}/{repo_name}/webhooks/{webhook_id}",
+    tag = "repositories",
+    params(
+        ("namespace" = String, Path, description = "Organization namespace"),
+        ("repo_name" = String, Path, description = "Repository name"),
+        ("webhook_id" = i64, Path, description = "Webhook ID")
+    ),
+    request_body = UpdateOrganizationWebhookRequest,
+    responses(
+        (status = 200, description = "Webhook updated", body = RepositoryWebhook),
+        (status = 400, description = "Invalid URL or event filter, webhook not found or not allowed"),
+        (status = 401, description = "Unauthorized")
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn update_repository_webhook(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    auth: Option<TypedHeader<Authorization<Bearer>>>,
+    Path((namespace, repo_name, webhook_id)): Path<(String, String, i64)>,
+    Json(req): Json<UpdateOrganizationWebhookRequest>,
+) -> impl IntoResponse {
+    let secret = state.config.auth.jwt_secret.expose_secret().as_bytes();
+    let user_id = match extract_user_id_dual(auth, &headers, ApiKeyScope::Push, secret, &state.db_pool, state.cache.as_ref()).await {
+        Ok(id) => id,
+        Err(status) => {
+            return (
+                status,
+                Json(serde_json::json!({
+                    "error": "Unauthorized"
+                })),
+            );
+        }
+    };
+
+    match update_webhook_internal(&state.db_pool, &namespace, &repo_name, webhook_id, user_id, req).await {
+        Ok(webhook) => {
+            state.log_stream.publish(
+                LogEvent::audit("repository.webhook.update", Some(user_id), Some(format!("{}/{}", namespace, repo_name)))
+                    .with_detail(format!("webhook {}", webhook_id)),
+            );
+            (StatusCode::OK, Json(serde_json::to_value(&webhook).unwrap_or_default()))
+        }
+        Err(e) => (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({
+                "error": e.to_string()
+            })),
+        ),
+    }
+}
+
+/// Remove a webhook from a repository
+#[utoipa::path(
+    delete,
+    path = "/api/v1/repos/{namespace}/{repo_name}/webhooks/{webhook_id}",
+    tag = "repositories",
+    params(
+        ("namespace" = String, Path, description = "Organization namespace"),
+        ("repo_name" = String, Path, description = "Repository name"),
+        ("webhook_id" = i64, Path, description = "Webhook ID")
+    ),
+    responses(
+        (status = 200, description = "Webhook deleted"),
+        (status = 400, description = "Webhook not found or not allowed"),
+        (status = 401, description = "Unauthorized")
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn delete_repository_webhook(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    auth: Option<TypedHeader<Authorization<Bearer>>>,
+    Path((namespace, repo_name, webhook_id)): Path<(String, String, i64)>,
+) -> impl IntoResponse {
+    let secret = state.config.auth.jwt_secret.expose_secret().as_bytes();
+    let user_id = match extract_user_id_dual(auth, &headers, ApiKeyScope::Push, secret, &state.db_pool, state.cache.as_ref()).await {
+        Ok(id) => id,
+        Err(status) => {
+            return (
+                status,
+                Json(serde_json::json!({
+                    "error": "Unauthorized"
+                })),
+            );
+        }
+    };
+
+    match delete_webhook_internal(&state.db_pool, &namespace, &repo_name, webhook_id, user_id).await {
+        Ok(()) => {
+            state.log_stream.publish(
+                LogEvent::audit("repository.webhook.delete", Some(user_id), Some(format!("{}/{}", namespace, repo_name)))
+                    .with_detail(format!("webhook {}", webhook_id)),
+            );
+            (
+                StatusCode::OK,
+                Json(serde_json::json!({
+                    "message": "Webhook deleted"
+                })),
+            )
+        }
+        Err(e) => (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({
+                "error": e.to_string()
+            })),
+        ),
+    }
+}
+
+/// Recent delivery attempts of a repository webhook, newest first
+///
+/// Each attempt keeps the payload, the response status and time, and the start of the response
+/// body, for 30 days. Owners and maintainers only.
+#[utoipa::path(
+    get,
+    path = "/api/v1/repos/{namespace}/{repo_name}/webhooks/{webhook_id}/deliveries",
+    tag = "repositories",
+    params(
+        ("namespace" = String, Path, description = "Organization namespace"),
+        ("repo_name" = String, Path, description = "Repository name"),
+        ("webhook_id" = i64, Path, description = "Webhook ID"),
+        WebhookDeliveryQuery
+    ),
+    responses(
+        (status = 200, description = "One page of delivery attempts", body = WebhookDeliveryPage),
+        (status = 400, description = "Webhook not found or not allowed"),
+        (status = 401, description = "Unauthorized")
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn list_repository_webhook_deliveries(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    auth: Option<TypedHeader<Authorization<Bearer>>>,
+    Path((namespace, repo_name, webhook_id)): Path<(String, String, i64)>,
+    Query(query): Query<WebhookDeliveryQuery>,
+) -> impl IntoResponse {
+    let secret = state.config.auth.jwt_secret.expose_secret().as_bytes();
+    let user_id = match extract_user_id_dual(auth, &headers, ApiKeyScope::Read, secret, &state.db_pool, state.cache.as_ref()).await {
+        Ok(id) => id,
+        Err(status) => {
+            return (
+                status,
+                Json(serde_json::json!({
+                    "error": "Unauthorized"
+                })),
+            );
+        }
+    };
+
+    let result = match manageable_webhook(&state.db_pool, &namespace, &repo_name, webhook_id, user_id).await {
+        Ok(()) => load_deliveries(&state.db_pool, true, webhook_id, &query).await,
+        Err(e) => Err(e),
+    };
+    match result {
+        Ok(page) => (StatusCode::OK, Json(serde_json::to_value(&page).unwrap_or_default())),
+        Err(e) => (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({
+                "error": e.to_string()
+            })),
+        ),
+    }
+}
+
+/// Send a recorded delivery of a repository webhook again
+///
+/// The attempt's payload is POSTed once more, with the same `X-Aerugo-Delivery` ID, to the
+/// webhook's current URL and signed with its current secret. Responds with the new attempt.
+/// Owners and maintainers only.
+#[utoipa::path(
+    post,
+    path = "/api/v1/repos/{namespace}/{repo_name}/webhooks/{webhook_id}/deliveries/{delivery_id}/redeliver",
+    tag = "repositories",
+    params(
+        ("namespace" = String, Path, description = "Organization namespace"),
+        ("repo_name" = String, Path, description = "Repository name"),
+        ("webhook_id" = i64, Path, description = "Webhook ID"),
+        ("delivery_id" = i64, Path, description = "ID of the delivery attempt to resend")
+    ),
+    responses(
+        (status = 200, description = "The redelivery attempt", body = WebhookDelivery),
+        (status = 400, description = "Webhook or delivery not found, or not allowed"),
+        (status = 401, description = "Unauthorized")
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn redeliver_repository_webhook_delivery(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    auth: Option<TypedHeader<Authorization<Bearer>>>,
+    Path((namespace, repo_name, webhook_id, delivery_id)): Path<(String, String, i64, i64)>,
+) -> impl IntoResponse {
+    let secret = state.config.auth.jwt_secret.expose_secret().as_bytes();
+    let user_id = match extract_user_id_dual(auth, &headers, ApiKeyScope::Push, secret, &state.db_pool, state.cache.as_ref()).await {
+        Ok(id) => id,
+        Err(status) => {
+            return (
+                status,
+                Json(serde_json::json!({
+                    "error": "Unauthorized"
+                })),
+            );
+        }
+    };
+
+    let result = match manageable_webhook(&state.db_pool, &namespace, &repo_name, webhook_id, user_id).await {
+        Ok(()) => redeliver_internal(&state, true, webhook_id, delivery_id).await,
+        Err(e) => Err(e),
+    };
+    match result {
+        Ok(delivery) => {
+            tracing::info!("Delivery {} of webhook {} resent by user {}", delivery_id, webhook_id, user_id);
+            (StatusCode::OK, Json(serde_json::to_value(&delivery).unwrap_or_default()))
+        }
+        Err(e) => (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({
+                "error": e.to_string()
+            })),
+        ),
+    }
+}
+
+// Internal database functions
+
+/// ID of the repository, when the user may manage its webhooks
+async fn manageable_repository(pool: &PgPool, namespace: &str, repo_name: &str, user_id: i64) -> Result<i64> {
+    let repository = sqlx::query_as::<_, (i64, i64)>(
+        "SELECT r.id, r.organization_id
+         FROM repositories r
+         JOIN organizations o ON o.id = r.organization_id
+         WHERE o.name = $1 AND r.name = $2",
+    )
+    .bind(namespace)
+    .bind(repo_name)
+    .fetch_optional(pool)
+    .await?;
+    let Some((repository_id, org_id)) = repository else {
+        bail!("Repository '{}/{}' not found", namespace, repo_name);
+    };
+
+    let user_role = get_user_role_in_org(pool, org_id, user_id).await?;
+    if !user_role.map(|r| r.can_manage_repositories()).unwrap_or(false) {
+        bail!("Only organization owners and maintainers can manage repository webhooks");
+    }
+    Ok(repository_id)
+}
+
+/// Whether the webhook belongs to the repository and the user may manage it
+async fn manageable_webhook(pool: &PgPool, namespace: &str, repo_name: &str, webhook_id: i64, user_id: i64) -> Result<()> {
+    let repository_id = manageable_repository(pool, namespace, repo_name, user_id).await?;
+
+    let exists = sqlx::query_scalar::<_, bool>("SELECT EXISTS(SELECT 1 FROM repository_webhooks WHERE id = $1 AND repository_id = $2)")
+        .bind(webhook_id)
+        .bind(repository_id)
+        .fetch_one(pool)
+        .await?;
+    if !exists {
+        bail!("Webhook not found");
+    }
+    Ok(())
+}
+
+async fn create_webhook_internal(
+    pool: &PgPool,
+    namespace: &str,
+    repo_name: &str,
+    user_id: i64,
+    req: CreateOrganizationWebhookRequest,
+) -> Result<RepositoryWebhook> {
+    let repository_id = manageable_repository(pool, namespace, repo_name, user_id).await?;
+    validate_url(req.url.trim())?;
+    let events = normalize_events(req.events)?;
+    let format = req.format.as_deref().map(validate_format).transpose()?.unwrap_or("json");
+
+    let count = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM repository_webhooks WHERE repository_id = $1")
+        .bind(repository_id)
+        .fetch_one(pool)
+        .await?;
+    if count >= MAX_WEBHOOKS {
+        bail!("A repository can have at most {} webhooks", MAX_WEBHOOKS);
+    }
+
+    let webhook = sqlx::query_as::<_, RepositoryWebhook>(&format!(
+        "INSERT INTO repository_webhooks (repository_id, url, secret, events, format, active, created_by)
+         VALUES ($1, $2, NULLIF($3, ''), $4, $5, $6, $7)
+         RETURNING {}",
+        WEBHOOK_COLUMNS
+    ))
+    .bind(repository_id)
+    .bind(req.url.trim())
+    .bind(req.secret)
+    .bind(&events)
+    .bind(format)
+    .bind(req.active.unwrap_or(true))
+    .bind(user_id)
+    .fetch_one(pool)
+    .await?;
+
+    tracing::info!("Webhook {} registered for repository {}/{} by user {}", webhook.id, namespace, repo_name, user_id);
+    Ok(webhook)
+}
+
+async fn list_webhooks_internal(pool: &PgPool, namespace: &str, repo_name: &str, user_id: i64) -> Result<Vec<RepositoryWebhook>> {
+    let repository_id = manageable_repository(pool, namespace, repo_name, user_id).await?;
+
+    let webhooks = sqlx::query_as::<_, RepositoryWebhook>(&format!(
+        "SELECT {} FROM repository_webhooks WHERE repository_id = $1 ORDER BY created_at",
+        WEBHOOK_COLUMNS
+    ))
+    .bind(repository_id)
+    .fetch_all(pool)
+    .await?;
+    Ok(webhooks)
+}
+
+async fn update_webhook_internal(
+    pool: &PgPool,
+    namespace: &str,
+    repo_name: &str,
+    webhook_id: i64,
+    user_id: i64,
+    req: UpdateOrganizationWebhookRequest,
+) -> Result<RepositoryWebhook> {
+    let repository_id = manageable_repository(pool, namespace, repo_name, user_id).await?;
+    let url = req.url.map(|url| url.trim().to_string());
+    if let Some(url) = &url {
+        validate_url(url)?;
+    }
+    let events = req.events.map(normalize_events).transpose()?;
+    let format = req.format.as_deref().map(validate_format).transpose()?;
+
+    let webhook = sqlx::query_as::<_, RepositoryWebhook>(&format!(
+        "UPDATE repository_webhooks SET
+             url = COALESCE($3, url),
+             secret = CASE WHEN $4::TEXT IS NULL THEN secret ELSE NULLIF($4, '') END,
+             events = COALESCE($5, events),
+             active = COALESCE($6, active),
+             format = COALESCE($7, format),
+             updated_at = CURRENT_TIMESTAMP
+         WHERE id = $1 AND repository_id = $2
+         RETURNING {}",
+        WEBHOOK_COLUMNS
+    ))
+    .bind(webhook_id)
+    .bind(repository_id)
+    .bind(url)
+    .bind(req.secret)
+    .bind(events)
+    .bind(req.active)
+    .bind(format)
+    .fetch_optional(pool)
+    .await?;
+
+    match webhook {
+        Some(webhook) => Ok(webhook),
+        None => bail!("Webhook not found"),
+    }
+}
+
+async fn delete_webhook_internal(pool: &PgPool, namespace: &str, repo_name: &str, webhook_id: i64, user_id: i64) -> Result<()> {
+    let repository_id = manageable_repository(pool, namespace, repo_name, user_id).await?;
+
+    let result = sqlx::query("DELETE FROM repository_webhooks WHERE id = $1 AND repository_id = $2")
+        .bind(webhook_id)
+        .bind(repository_id)
+        .execute(pool)
+        .await?;
+    if result.rows_affected() == 0 {
+        bail!("Webhook not found");
+    }
+    Ok(())
+}
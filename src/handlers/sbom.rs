@@ -0,0 +1,307 @@
+// src/handlers/sbom.rs - SBOM download, upload and package search
+//
+// SBOMs are referrer artifacts of the images they describe; generating and indexing them is
+// `crate::sbom`.
+use std::collections::HashMap;
+
+use axum::{
+    extract::{Path, Query, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use axum_extra::headers::{authorization::Bearer, Authorization};
+use axum_extra::TypedHeader;
+use bytes::Bytes;
+use secrecy::ExposeSecret;
+use serde_json::json;
+
+use crate::{
+    auth::extract_user_id_dual,
+    handlers::docker_auth::check_repository_permission,
+    handlers::model_registry::{authorize, push_artifact, Subject},
+    handlers::tag_cleanup::internal_error,
+    log_stream::LogEvent,
+    models::api_key::ApiKeyScope,
+    models::sbom::{PackageMatch, PackageSearchPage, PackageSearchQuery, SbomArtifact, SbomFormat, SbomQuery},
+    AppState,
+};
+
+/// Download the SBOM of an image
+///
+/// The newest SBOM attached to the image, generated by Aerugo or pushed by a client, as the
+/// SPDX or CycloneDX JSON document itself. Requires pull access.
+#[utoipa::path(
+    get,
+    path = "/api/v1/repos/{namespace}/{repo_name}/images/{reference}/sbom",
+    params(
+        ("namespace" = String, Path, description = "Organization namespace"),
+        ("repo_name" = String, Path, description = "Repository name"),
+        ("reference" = String, Path, description = "Tag or digest of the image"),
+        SbomQuery
+    ),
+    responses(
+        (status = 200, description = "The SBOM document, `application/spdx+json` or `application/vnd.cyclonedx+json`"),
+        (status = 400, description = "Invalid format"),
+        (status = 401, description = "Authentication required"),
+        (status = 404, description = "Repository, image or SBOM not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "repositories",
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn get_image_sbom(
+    Path((namespace, repo_name, reference)): Path<(String, String, String)>,
+    Query(query): Query<SbomQuery>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    auth: Option<TypedHeader<Authorization<Bearer>>>,
+) -> Response {
+    let artifact_types = match query.format.as_deref().map(str::parse::<SbomFormat>) {
+        Some(Ok(format)) => vec![format.artifact_type().to_string()],
+        Some(Err(e)) => return (StatusCode::BAD_REQUEST, Json(json!({"error": e}))).into_response(),
+        None => crate::sbom::artifact_types(),
+    };
+    if let Err(response) = authorize(&state, auth, &headers, ApiKeyScope::Read, &namespace, &repo_name, "pull").await {
+        return response;
+    }
+    let subject = match resolve_image(&state, &namespace, &repo_name, &reference).await {
+        Ok(subject) => subject,
+        Err(response) => return response,
+    };
+
+    let latest = sqlx::query_as::<_, (String, String)>(
+        "SELECT digest, artifact_type FROM manifests
+         WHERE repository_id = $1 AND subject_digest = $2 AND artifact_type = ANY($3)
+         ORDER BY created_at DESC, id DESC
+         LIMIT 1",
+    )
+    .bind(subject.repository_id)
+    .bind(&subject.digest)
+    .bind(artifact_types)
+    .fetch_optional(&state.db_pool)
+    .await;
+    let (digest, artifact_type) = match latest {
+        Ok(Some(latest)) => latest,
+        Ok(None) => {
+            return (StatusCode::NOT_FOUND, Json(json!({
+                "error": format!("No SBOM is attached to '{}/{}:{}'", namespace, repo_name, reference)
+            }))).into_response()
+        }
+        Err(e) => return internal_error(e),
+    };
+
+    let name = format!("{}/{}", namespace, repo_name);
+    let document = match crate::sbom::load_document(&state, &name, &digest).await {
+        Ok((_, document)) => document,
+        Err(e) => return internal_error(e),
+    };
+    let hex = subject.digest.split_once(':').map_or(subject.digest.as_str(), |(_, hex)| hex);
+    let format = SbomFormat::from_artifact_type(&artifact_type).map_or_else(|| "sbom".to_string(), |format| format.to_string());
+    let filename = format!("{}-{}.{}.json", repo_name, hex.get(..12).unwrap_or(hex), format);
+    (
+        [
+            (header::CONTENT_TYPE, artifact_type),
+            (header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}\"", filename)),
+        ],
+        document,
+    )
+        .into_response()
+}
+
+/// Attach an SBOM to an image
+///
+/// The body is an SPDX or CycloneDX JSON document. It is stored as a referrer of the image and
+/// its packages are indexed for search. Requires push access.
+#[utoipa::path(
+    put,
+    path = "/api/v1/repos/{namespace}/{repo_name}/images/{reference}/sbom",
+    params(
+        ("namespace" = String, Path, description = "Organization namespace"),
+        ("repo_name" = String, Path, description = "Repository name"),
+        ("reference" = String, Path, description = "Tag or digest of the image")
+    ),
+    request_body(content = String, description = "SPDX or CycloneDX JSON document", content_type = "application/json"),
+    responses(
+        (status = 201, description = "SBOM attached", body = SbomArtifact),
+        (status = 400, description = "Not an SPDX or CycloneDX JSON document"),
+        (status = 401, description = "Authentication required"),
+        (status = 403, description = "No push permission on the repository"),
+        (status = 404, description = "Repository or image not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "repositories",
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn upload_image_sbom(
+    Path((namespace, repo_name, reference)): Path<(String, String, String)>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    auth: Option<TypedHeader<Authorization<Bearer>>>,
+    body: Bytes,
+) -> Response {
+    let user_id = match authorize(&state, auth, &headers, ApiKeyScope::Push, &namespace, &repo_name, "push").await {
+        Ok(user_id) => user_id,
+        Err(response) => return response,
+    };
+    let (format, packages) = match crate::sbom::parse_document(&body) {
+        Ok(parsed) => parsed,
+        Err(e) => return (StatusCode::BAD_REQUEST, Json(json!({"error": e.to_string()}))).into_response(),
+    };
+    let subject = match resolve_image(&state, &namespace, &repo_name, &reference).await {
+        Ok(subject) => subject,
+        Err(response) => return response,
+    };
+
+    let name = format!("{}/{}", namespace, repo_name);
+    let digest = match push_artifact(&state, &name, &subject, format.artifact_type(), Some(body), json!({}), Some(user_id)).await {
+        Ok(digest) => digest,
+        Err(response) => return response,
+    };
+    // Also indexed by the `sbom_index` job the push queues; doing it here makes the packages
+    // searchable once this returns
+    if let Err(e) = crate::sbom::store_packages(&state.db_pool, subject.repository_id, &digest, &subject.digest, &packages).await {
+        return internal_error(e);
+    }
+
+    state.log_stream.publish(
+        LogEvent::audit("sbom.upload", Some(user_id), Some(name)).with_detail(format!("{} -> {}", subject.digest, digest)),
+    );
+
+    let response = SbomArtifact { subject: subject.digest, digest, format, packages: packages.len() };
+    (StatusCode::CREATED, Json(response)).into_response()
+}
+
+/// Find images containing a package
+///
+/// Images whose SBOM lists a package, e.g. `?name=openssl&version=3.0.1`, in the repositories
+/// the caller can pull from, oldest match first.
+#[utoipa::path(
+    get,
+    path = "/api/v1/sbom/packages",
+    params(PackageSearchQuery),
+    responses(
+        (status = 200, description = "One page of matching images", body = PackageSearchPage),
+        (status = 400, description = "Missing package name"),
+        (status = 401, description = "Authentication required"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "repositories",
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn search_packages(
+    State(state): State<AppState>,
+    Query(query): Query<PackageSearchQuery>,
+    headers: HeaderMap,
+    auth: Option<TypedHeader<Authorization<Bearer>>>,
+) -> Response {
+    let secret = state.config.auth.jwt_secret.expose_secret().as_bytes();
+    let user_id = match extract_user_id_dual(auth, &headers, ApiKeyScope::Read, secret, &state.db_pool, state.cache.as_ref()).await {
+        Ok(id) => id,
+        Err(status) => return (status, Json(json!({"error": "Authentication required"}))).into_response(),
+    };
+    let package = query.name.trim();
+    if package.is_empty() {
+        return (StatusCode::BAD_REQUEST, Json(json!({"error": "name is required"}))).into_response();
+    }
+    let limit = query.limit.unwrap_or(50).clamp(1, 200) as usize;
+
+    // Matches in repositories the caller cannot pull are dropped, so rows are read in batches
+    // until a page is full
+    let mut matches = Vec::new();
+    let mut allowed: HashMap<String, bool> = HashMap::new();
+    let mut after = query.after.unwrap_or(0);
+    let mut next_after = None;
+    loop {
+        let batch = sqlx::query_as::<_, PackageMatch>(
+            "SELECT p.id, o.name || '/' || r.name AS repository, p.subject_digest AS digest,
+                    ARRAY(
+                        SELECT t.name FROM tags t JOIN manifests m ON m.id = t.manifest_id
+                        WHERE t.repository_id = p.repository_id AND m.digest = p.subject_digest
+                        ORDER BY t.name
+                    ) AS tags,
+                    p.name, p.version, p.purl
+             FROM sbom_packages p
+             JOIN repositories r ON r.id = p.repository_id
+             JOIN organizations o ON o.id = r.organization_id
+             WHERE LOWER(p.name) = LOWER($1)
+               AND ($2::TEXT IS NULL OR p.version = $2)
+               AND ($3::TEXT IS NULL OR o.name = $3)
+               AND p.id > $4
+             ORDER BY p.id
+             LIMIT $5",
+        )
+        .bind(package)
+        .bind(query.version.as_deref())
+        .bind(query.org.as_deref())
+        .bind(after)
+        .bind(limit as i64)
+        .fetch_all(state.read_pool())
+        .await;
+        let batch = match batch {
+            Ok(batch) => batch,
+            Err(e) => return internal_error(e),
+        };
+        let exhausted = batch.len() < limit;
+
+        for found in batch {
+            after = found.id;
+            let can_pull = match allowed.get(&found.repository) {
+                Some(can_pull) => *can_pull,
+                None => {
+                    let (namespace, repo_name) = found.repository.split_once('/').unwrap_or(("", &found.repository));
+                    let can_pull = match check_repository_permission(&user_id.to_string(), namespace, repo_name, "pull", &state).await {
+                        Ok(can_pull) => can_pull,
+                        Err(e) => return internal_error(e),
+                    };
+                    allowed.insert(found.repository.clone(), can_pull);
+                    can_pull
+                }
+            };
+            if can_pull {
+                matches.push(found);
+                if matches.len() == limit {
+                    next_after = Some(after);
+                    break;
+                }
+            }
+        }
+        if next_after.is_some() || exhausted {
+            break;
+        }
+    }
+
+    (StatusCode::OK, Json(json!(PackageSearchPage { matches, next_after }))).into_response()
+}
+
+/// Resolve a tag or digest to the manifest of an image
+async fn resolve_image(state: &AppState, namespace: &str, repo_name: &str, reference: &str) -> Result<Subject, Response> {
+    let row = sqlx::query_as::<_, (i64, String, String, i64)>(
+        "SELECT r.id, m.digest, m.media_type, m.size
+         FROM repositories r
+         JOIN organizations o ON o.id = r.organization_id
+         JOIN manifests m ON m.repository_id = r.id
+         WHERE o.name = $1 AND r.name = $2
+           AND (m.digest = $3 OR m.id = (SELECT t.manifest_id FROM tags t WHERE t.repository_id = r.id AND t.name = $3))
+         LIMIT 1",
+    )
+    .bind(namespace)
+    .bind(repo_name)
+    .bind(reference)
+    .fetch_optional(&state.db_pool)
+    .await
+    .map_err(internal_error)?;
+
+    match row {
+        Some((repository_id, digest, media_type, size)) => Ok(Subject { repository_id, digest, media_type, size }),
+        None => Err((StatusCode::NOT_FOUND, Json(json!({
+            "error": format!("Image '{}/{}:{}' not found", namespace, repo_name, reference)
+        }))).into_response()),
+    }
+}
@@ -0,0 +1,84 @@
+// src/handlers/secret_findings.rs - Credentials found in pushed images
+//
+// Findings are recorded at push time by `crate::secret_scanning`.
+use axum::{
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use axum_extra::headers::{authorization::Bearer, Authorization};
+use axum_extra::TypedHeader;
+use secrecy::ExposeSecret;
+use serde_json::json;
+
+use crate::{
+    auth::extract_user_id_dual,
+    handlers::docker_auth::check_repository_permission,
+    handlers::tag_cleanup::{internal_error, repository_not_found},
+    models::api_key::ApiKeyScope,
+    models::secret_finding::{SecretFinding, SecretFindingQuery},
+    AppState,
+};
+
+/// Likely secrets found in a repository's images
+///
+/// Credentials found in the config or layers of images pushed to the repository, newest first,
+/// including those of rejected pushes. Only a redacted excerpt of each match is kept. Requires
+/// push access.
+#[utoipa::path(
+    get,
+    path = "/api/v1/repos/{namespace}/{repo_name}/secret-findings",
+    params(
+        ("namespace" = String, Path, description = "Organization namespace"),
+        ("repo_name" = String, Path, description = "Repository name"),
+        SecretFindingQuery
+    ),
+    responses(
+        (status = 200, description = "Secret findings", body = Vec<SecretFinding>),
+        (status = 401, description = "Authentication required"),
+        (status = 404, description = "Repository not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "repositories",
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn list_secret_findings(
+    Path((namespace, repo_name)): Path<(String, String)>,
+    State(state): State<AppState>,
+    Query(query): Query<SecretFindingQuery>,
+    headers: HeaderMap,
+    auth: Option<TypedHeader<Authorization<Bearer>>>,
+) -> Response {
+    let secret = state.config.auth.jwt_secret.expose_secret().as_bytes();
+    let user_id = match extract_user_id_dual(auth, &headers, ApiKeyScope::Read, secret, &state.db_pool, state.cache.as_ref()).await {
+        Ok(id) => id,
+        Err(status) => return (status, Json(json!({"error": "Authentication required"}))).into_response(),
+    };
+    // Where a secret sits in an image is for the people who build it
+    match check_repository_permission(&user_id.to_string(), &namespace, &repo_name, "push", &state).await {
+        Ok(true) => {}
+        Ok(false) => return repository_not_found(&namespace, &repo_name),
+        Err(e) => return internal_error(e),
+    }
+
+    let findings = sqlx::query_as::<_, SecretFinding>(
+        "SELECT f.id, f.manifest_digest, f.reference, f.kind, f.location, f.excerpt, f.rejected, f.found_at
+         FROM secret_findings f
+         JOIN organizations o ON o.id = f.organization_id
+         WHERE o.name = $1 AND f.repository = $1 || '/' || $2 AND ($3::TEXT IS NULL OR f.manifest_digest = $3)
+         ORDER BY f.found_at DESC, f.id
+         LIMIT 500",
+    )
+    .bind(&namespace)
+    .bind(&repo_name)
+    .bind(query.digest.as_deref())
+    .fetch_all(&state.db_pool)
+    .await;
+    match findings {
+        Ok(findings) => (StatusCode::OK, Json(json!(findings))).into_response(),
+        Err(e) => internal_error(e),
+    }
+}
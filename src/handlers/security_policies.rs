@@ -0,0 +1,454 @@
+// src/handlers/security_policies.rs - Security policies configured by an organization
+//
+// The policies are evaluated by `crate::policies` on every manifest pull and push; these
+// handlers only manage them.
+use anyhow::{bail, Result};
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+use axum_extra::headers::{authorization::Bearer, Authorization};
+use axum_extra::TypedHeader;
+use secrecy::ExposeSecret;
+use sqlx::PgPool;
+
+use crate::{
+    auth::extract_user_id_dual,
+    handlers::organizations::get_user_role_in_org,
+    log_stream::LogEvent,
+    models::api_key::ApiKeyScope,
+    models::security_policy::{CreateSecurityPolicyRequest, PolicyRule, SecurityPolicy, UpdateSecurityPolicyRequest},
+    models::vulnerability::Severity,
+    AppState,
+};
+
+/// Policies per organization; each one is evaluated on every pull or push it applies to
+const MAX_SECURITY_POLICIES: i64 = 50;
+const MAX_TAG_PATTERNS: usize = 20;
+
+const SECURITY_POLICY_SELECT: &str = "SELECT p.id, p.organization_id, p.repository_id, r.name AS repository, p.name,
+     p.rule, p.severity, p.tag_patterns, p.active, p.created_by, p.created_at, p.updated_at
+     FROM security_policies p
+     LEFT JOIN repositories r ON r.id = p.repository_id";
+
+/// Add a security policy to an organization
+///
+/// `block_severity` refuses pulls of images whose latest scan found a vulnerability of `severity`
+/// or worse, `require_signature` refuses pulls of images without a verified signature, and
+/// `deny_tags` refuses pushes to tags matching `tag_patterns`. Refused requests get `403 DENIED`
+/// naming the policy. Owners only.
+#[utoipa::path(
+    post,
+    path = "/api/v1/organizations/{id}/security-policies",
+    tag = "organizations",
+    params(
+        ("id" = i64, Path, description = "Organization ID")
+    ),
+    request_body = CreateSecurityPolicyRequest,
+    responses(
+        (status = 201, description = "Security policy created", body = SecurityPolicy),
+        (status = 400, description = "Invalid name, severity or tag patterns, unknown repository, or not an owner"),
+        (status = 401, description = "Unauthorized")
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn create_security_policy(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    auth: Option<TypedHeader<Authorization<Bearer>>>,
+    Path(id): Path<i64>,
+    Json(req): Json<CreateSecurityPolicyRequest>,
+) -> impl IntoResponse {
+    let secret = state.config.auth.jwt_secret.expose_secret().as_bytes();
+    let user_id = match extract_user_id_dual(auth, &headers, ApiKeyScope::Admin, secret, &state.db_pool, state.cache.as_ref()).await {
+        Ok(id) => id,
+        Err(status) => {
+            return (
+                status,
+                Json(serde_json::json!({
+                    "error": "Unauthorized"
+                })),
+            );
+        }
+    };
+
+    match create_security_policy_internal(&state.db_pool, id, user_id, req).await {
+        Ok(policy) => {
+            state.log_stream.publish(
+                LogEvent::audit("organization.security_policy.create", Some(user_id), policy.repository.clone())
+                    .with_organization(id)
+                    .with_detail(format!("organization {} security policy {} ({})", id, policy.name, policy.rule)),
+            );
+            (StatusCode::CREATED, Json(serde_json::to_value(&policy).unwrap_or_default()))
+        }
+        Err(e) => (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({
+                "error": e.to_string()
+            })),
+        ),
+    }
+}
+
+/// List an organization's security policies
+#[utoipa::path(
+    get,
+    path = "/api/v1/organizations/{id}/security-policies",
+    tag = "organizations",
+    params(
+        ("id" = i64, Path, description = "Organization ID")
+    ),
+    responses(
+        (status = 200, description = "Security policies of the organization", body = Vec<SecurityPolicy>),
+        (status = 400, description = "Not an owner"),
+        (status = 401, description = "Unauthorized")
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn list_security_policies(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    auth: Option<TypedHeader<Authorization<Bearer>>>,
+    Path(id): Path<i64>,
+) -> impl IntoResponse {
+    let secret = state.config.auth.jwt_secret.expose_secret().as_bytes();
+    let user_id = match extract_user_id_dual(auth, &headers, ApiKeyScope::Read, secret, &state.db_pool, state.cache.as_ref()).await {
+        Ok(id) => id,
+        Err(status) => {
+            return (
+                status,
+                Json(serde_json::json!({
+                    "error": "Unauthorized"
+                })),
+            );
+        }
+    };
+
+    match list_security_policies_internal(&state.db_pool, id, user_id).await {
+        Ok(policies) => (
+            StatusCode::OK,
+            Json(serde_json::json!({
+                "security_policies": policies
+            })),
+        ),
+        Err(e) => (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({
+                "error": e.to_string()
+            })),
+        ),
+    }
+}
+
+/// Change a security policy's name, severity, tag patterns or whether it is active
+#[utoipa::path(
+    put,
+    path = "/api/v1/organizations/{id}/security-policies/{policy_id}",
+    tag = "organizations",
+    params(
+        ("id" = i64, Path, description = "Organization ID"),
+        ("policy_id" = i64, Path, description = "Security policy ID")
+    ),
+    request_body = UpdateSecurityPolicyRequest,
+    responses(
+        (status = 200, description = "Security policy updated", body = SecurityPolicy),
+        (status = 400, description = "Invalid name, severity or tag patterns, policy not found or not an owner"),
+        (status = 401, description = "Unauthorized")
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn update_security_policy(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    auth: Option<TypedHeader<Authorization<Bearer>>>,
+    Path((id, policy_id)): Path<(i64, i64)>,
+    Json(req): Json<UpdateSecurityPolicyRequest>,
+) -> impl IntoResponse {
+    let secret = state.config.auth.jwt_secret.expose_secret().as_bytes();
+    let user_id = match extract_user_id_dual(auth, &headers, ApiKeyScope::Admin, secret, &state.db_pool, state.cache.as_ref()).await {
+        Ok(id) => id,
+        Err(status) => {
+            return (
+                status,
+                Json(serde_json::json!({
+                    "error": "Unauthorized"
+                })),
+            );
+        }
+    };
+
+    match update_security_policy_internal(&state.db_pool, id, policy_id, user_id, req).await {
+        Ok(policy) => {
+            state.log_stream.publish(
+                LogEvent::audit("organization.security_policy.update", Some(user_id), policy.repository.clone())
+                    .with_organization(id)
+                    .with_detail(format!("organization {} security policy {}", id, policy.name)),
+            );
+            (StatusCode::OK, Json(serde_json::to_value(&policy).unwrap_or_default()))
+        }
+        Err(e) => (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({
+                "error": e.to_string()
+            })),
+        ),
+    }
+}
+
+/// Remove a security policy from an organization
+#[utoipa::path(
+    delete,
+    path = "/api/v1/organizations/{id}/security-policies/{policy_id}",
+    tag = "organizations",
+    params(
+        ("id" = i64, Path, description = "Organization ID"),
+        ("policy_id" = i64, Path, description = "Security policy ID")
+    ),
+    responses(
+        (status = 200, description = "Security policy deleted"),
+        (status = 400, description = "Policy not found or not an owner"),
+        (status = 401, description = "Unauthorized")
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn delete_security_policy(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    auth: Option<TypedHeader<Authorization<Bearer>>>,
+    Path((id, policy_id)): Path<(i64, i64)>,
+) -> impl IntoResponse {
+    let secret = state.config.auth.jwt_secret.expose_secret().as_bytes();
+    let user_id = match extract_user_id_dual(auth, &headers, ApiKeyScope::Admin, secret, &state.db_pool, state.cache.as_ref()).await {
+        Ok(id) => id,
+        Err(status) => {
+            return (
+                status,
+                Json(serde_json::json!({
+                    "error": "Unauthorized"
+                })),
+            );
+        }
+    };
+
+    match delete_security_policy_internal(&state.db_pool, id, policy_id, user_id).await {
+        Ok(()) => {
+            state.log_stream.publish(
+                LogEvent::audit("organization.security_policy.delete", Some(user_id), None)
+                    .with_organization(id)
+                    .with_detail(format!("organization {} security policy {}", id, policy_id)),
+            );
+            (
+                StatusCode::OK,
+                Json(serde_json::json!({
+                    "message": "Security policy deleted"
+                })),
+            )
+        }
+        Err(e) => (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({
+                "error": e.to_string()
+            })),
+        ),
+    }
+}
+
+// Internal database functions
+async fn ensure_can_manage_security_policies(pool: &PgPool, org_id: i64, user_id: i64) -> Result<()> {
+    let user_role = get_user_role_in_org(pool, org_id, user_id).await?;
+    if !user_role.map(|r| r.can_manage_security_policies()).unwrap_or(false) {
+        bail!("Only organization owners can manage security policies");
+    }
+    Ok(())
+}
+
+fn validate_name(name: &str) -> Result<()> {
+    if name.is_empty() || name.len() > 100 {
+        bail!("Security policy name must be 1 to 100 characters long");
+    }
+    Ok(())
+}
+
+/// Check that a policy has the settings its rule needs, returning the normalized severity
+fn validate_rule(rule: PolicyRule, severity: Option<&str>, tag_patterns: &[String]) -> Result<Option<String>> {
+    match rule {
+        PolicyRule::BlockSeverity => {
+            let severity = match severity.map(str::parse::<Severity>) {
+                Some(Ok(severity)) if severity != Severity::Unknown => severity,
+                _ => bail!("block_severity needs a severity of CRITICAL, HIGH, MEDIUM or LOW"),
+            };
+            Ok(Some(severity.to_string()))
+        }
+        PolicyRule::DenyTags => {
+            if tag_patterns.is_empty() || tag_patterns.len() > MAX_TAG_PATTERNS {
+                bail!("deny_tags needs 1 to {} tag patterns", MAX_TAG_PATTERNS);
+            }
+            for pattern in tag_patterns {
+                let valid = !pattern.is_empty()
+                    && pattern.len() <= 128
+                    && pattern.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '-' | '*'));
+                if !valid {
+                    bail!("Invalid tag pattern: {}", pattern);
+                }
+            }
+            Ok(None)
+        }
+        PolicyRule::RequireSignature => Ok(None),
+    }
+}
+
+async fn load_security_policy(pool: &PgPool, org_id: i64, policy_id: i64) -> Result<Option<SecurityPolicy>> {
+    let query = format!("{} WHERE p.id = $1 AND p.organization_id = $2", SECURITY_POLICY_SELECT);
+    let policy = sqlx::query_as::<_, SecurityPolicy>(&query)
+        .bind(policy_id)
+        .bind(org_id)
+        .fetch_optional(pool)
+        .await?;
+    Ok(policy)
+}
+
+async fn create_security_policy_internal(
+    pool: &PgPool,
+    org_id: i64,
+    user_id: i64,
+    req: CreateSecurityPolicyRequest,
+) -> Result<SecurityPolicy> {
+    ensure_can_manage_security_policies(pool, org_id, user_id).await?;
+    let name = req.name.trim();
+    validate_name(name)?;
+    let tag_patterns: Vec<String> = req.tag_patterns.unwrap_or_default().iter().map(|p| p.trim().to_string()).collect();
+    let severity = validate_rule(req.rule, req.severity.as_deref(), &tag_patterns)?;
+
+    let repository_id = match req.repository.as_deref().map(str::trim) {
+        Some(repository) => {
+            let id = sqlx::query_scalar::<_, i64>("SELECT id FROM repositories WHERE organization_id = $1 AND name = $2")
+                .bind(org_id)
+                .bind(repository)
+                .fetch_optional(pool)
+                .await?;
+            match id {
+                Some(id) => Some(id),
+                None => bail!("Repository '{}' not found in the organization", repository),
+            }
+        }
+        None => None,
+    };
+
+    let count = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM security_policies WHERE organization_id = $1")
+        .bind(org_id)
+        .fetch_one(pool)
+        .await?;
+    if count >= MAX_SECURITY_POLICIES {
+        bail!("An organization can have at most {} security policies", MAX_SECURITY_POLICIES);
+    }
+
+    let id = sqlx::query_scalar::<_, i64>(
+        "INSERT INTO security_policies (organization_id, repository_id, name, rule, severity, tag_patterns, active, created_by)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+         ON CONFLICT (organization_id, name) DO NOTHING
+         RETURNING id",
+    )
+    .bind(org_id)
+    .bind(repository_id)
+    .bind(name)
+    .bind(req.rule.to_string())
+    .bind(severity)
+    .bind(&tag_patterns)
+    .bind(req.active.unwrap_or(true))
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await?;
+    let Some(id) = id else {
+        bail!("A security policy named '{}' already exists", name);
+    };
+
+    tracing::info!("Security policy {} ({}) created for organization {} by user {}", id, req.rule, org_id, user_id);
+    match load_security_policy(pool, org_id, id).await? {
+        Some(policy) => Ok(policy),
+        None => bail!("Security policy not found"),
+    }
+}
+
+async fn list_security_policies_internal(pool: &PgPool, org_id: i64, user_id: i64) -> Result<Vec<SecurityPolicy>> {
+    ensure_can_manage_security_policies(pool, org_id, user_id).await?;
+
+    let query = format!("{} WHERE p.organization_id = $1 ORDER BY p.id", SECURITY_POLICY_SELECT);
+    let policies = sqlx::query_as::<_, SecurityPolicy>(&query)
+        .bind(org_id)
+        .fetch_all(pool)
+        .await?;
+    Ok(policies)
+}
+
+async fn update_security_policy_internal(
+    pool: &PgPool,
+    org_id: i64,
+    policy_id: i64,
+    user_id: i64,
+    req: UpdateSecurityPolicyRequest,
+) -> Result<SecurityPolicy> {
+    ensure_can_manage_security_policies(pool, org_id, user_id).await?;
+    let Some(current) = load_security_policy(pool, org_id, policy_id).await? else {
+        bail!("Security policy not found");
+    };
+
+    let name = req.name.map(|name| name.trim().to_string()).unwrap_or(current.name);
+    validate_name(&name)?;
+    let rule = current.rule.parse::<PolicyRule>().map_err(anyhow::Error::msg)?;
+    let tag_patterns = match req.tag_patterns {
+        Some(patterns) => patterns.iter().map(|p| p.trim().to_string()).collect(),
+        None => current.tag_patterns,
+    };
+    let severity = validate_rule(rule, req.severity.as_deref().or(current.severity.as_deref()), &tag_patterns)?;
+
+    let updated = sqlx::query(
+        "UPDATE security_policies SET
+             name = $3,
+             severity = $4,
+             tag_patterns = $5,
+             active = COALESCE($6, active),
+             updated_at = CURRENT_TIMESTAMP
+         WHERE id = $1 AND organization_id = $2
+           AND NOT EXISTS (SELECT 1 FROM security_policies o WHERE o.organization_id = $2 AND o.name = $3 AND o.id <> $1)",
+    )
+    .bind(policy_id)
+    .bind(org_id)
+    .bind(&name)
+    .bind(severity)
+    .bind(&tag_patterns)
+    .bind(req.active)
+    .execute(pool)
+    .await?;
+    if updated.rows_affected() == 0 {
+        bail!("A security policy named '{}' already exists", name);
+    }
+
+    match load_security_policy(pool, org_id, policy_id).await? {
+        Some(policy) => Ok(policy),
+        None => bail!("Security policy not found"),
+    }
+}
+
+async fn delete_security_policy_internal(pool: &PgPool, org_id: i64, policy_id: i64, user_id: i64) -> Result<()> {
+    ensure_can_manage_security_policies(pool, org_id, user_id).await?;
+
+    let result = sqlx::query("DELETE FROM security_policies WHERE id = $1 AND organization_id = $2")
+        .bind(policy_id)
+        .bind(org_id)
+        .execute(pool)
+        .await?;
+    if result.rows_affected() == 0 {
+        bail!("Security policy not found");
+    }
+    Ok(())
+}
@@ -0,0 +1,341 @@
+// src/handlers/security_summary.rs - Security posture of an organization across its repositories
+//
+// Scans, signatures, quarantines and secret findings are recorded per repository by their own
+// modules; pull policies are evaluated the way `crate::policies` would on a pull, so the
+// summary counts the images a pull would be refused for today.
+use std::collections::HashMap;
+
+use anyhow::{bail, Result};
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+use axum_extra::headers::{authorization::Bearer, Authorization};
+use axum_extra::TypedHeader;
+use secrecy::ExposeSecret;
+use sqlx::{FromRow, PgPool};
+
+use crate::{
+    auth::extract_user_id_dual,
+    handlers::insights::percent_of,
+    handlers::organizations::get_user_role_in_org,
+    models::api_key::ApiKeyScope,
+    models::security_summary::{ImageSecurity, OrganizationSecuritySummary, RepositorySecurity},
+    models::vulnerability::SeverityCounts,
+    policies::{Image, OrganizationPolicies},
+    AppState,
+};
+
+#[derive(FromRow)]
+struct RepositoryRow {
+    id: i64,
+    name: String,
+    is_public: bool,
+    require_signature: bool,
+    quarantine_pushes: bool,
+}
+
+#[derive(FromRow)]
+struct ImageRow {
+    repository_id: i64,
+    digest: String,
+    signed: bool,
+    verified: bool,
+    quarantined: bool,
+    critical_count: Option<i32>,
+    high_count: Option<i32>,
+    medium_count: Option<i32>,
+    low_count: Option<i32>,
+    unknown_count: Option<i32>,
+}
+
+impl ImageRow {
+    /// Findings of the latest completed scan; `None` while the image has not been scanned
+    fn vulnerabilities(&self) -> Option<SeverityCounts> {
+        self.critical_count.map(|critical| {
+            let (high, medium, low, unknown) = (
+                self.high_count.unwrap_or(0),
+                self.medium_count.unwrap_or(0),
+                self.low_count.unwrap_or(0),
+                self.unknown_count.unwrap_or(0),
+            );
+            SeverityCounts { critical, high, medium, low, unknown, total: critical + high + medium + low + unknown }
+        })
+    }
+}
+
+/// Security posture of an organization
+///
+/// Vulnerability totals of the latest completed scans, scan and signature coverage, images a pull
+/// would be refused for by a security policy, quarantined images and recent secret findings,
+/// for the whole organization and per repository, worst first. Only tagged images are counted.
+/// Members only.
+#[utoipa::path(
+    get,
+    path = "/api/v1/organizations/{id}/security-summary",
+    tag = "organizations",
+    params(
+        ("id" = i64, Path, description = "Organization ID")
+    ),
+    responses(
+        (status = 200, description = "Security summary", body = OrganizationSecuritySummary),
+        (status = 400, description = "Not a member of the organization"),
+        (status = 401, description = "Unauthorized")
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn get_security_summary(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    auth: Option<TypedHeader<Authorization<Bearer>>>,
+    Path(id): Path<i64>,
+) -> impl IntoResponse {
+    let secret = state.config.auth.jwt_secret.expose_secret().as_bytes();
+    let user_id = match extract_user_id_dual(auth, &headers, ApiKeyScope::Read, secret, &state.db_pool, state.cache.as_ref()).await {
+        Ok(id) => id,
+        Err(status) => {
+            return (
+                status,
+                Json(serde_json::json!({
+                    "error": "Unauthorized"
+                })),
+            );
+        }
+    };
+
+    match get_security_summary_internal(&state.db_pool, id, user_id).await {
+        Ok(summary) => (StatusCode::OK, Json(serde_json::json!(summary))),
+        Err(e) => (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({
+                "error": e.to_string()
+            })),
+        ),
+    }
+}
+
+async fn get_security_summary_internal(pool: &PgPool, org_id: i64, user_id: i64) -> Result<OrganizationSecuritySummary> {
+    if get_user_role_in_org(pool, org_id, user_id).await?.is_none() {
+        bail!("Access denied: not a member of this organization");
+    }
+
+    let namespace = sqlx::query_scalar::<_, String>("SELECT name FROM organizations WHERE id = $1")
+        .bind(org_id)
+        .fetch_one(pool)
+        .await?;
+    let repositories = sqlx::query_as::<_, RepositoryRow>(
+        "SELECT id, name, is_public, require_signature, quarantine_pushes
+         FROM repositories
+         WHERE organization_id = $1
+         ORDER BY name",
+    )
+    .bind(org_id)
+    .fetch_all(pool)
+    .await?;
+    let images = sqlx::query_as::<_, ImageRow>(
+        "SELECT m.repository_id, m.digest,
+                EXISTS (
+                    SELECT 1 FROM manifests s
+                    WHERE s.repository_id = m.repository_id AND s.subject_digest = m.digest AND s.artifact_type = ANY($2)
+                )
+                OR EXISTS (
+                    SELECT 1 FROM tags st
+                    WHERE st.repository_id = m.repository_id AND st.name = REPLACE(m.digest, ':', '-') || '.sig'
+                ) AS signed,
+                EXISTS (
+                    SELECT 1 FROM signature_verifications v
+                    WHERE v.repository_id = m.repository_id AND v.manifest_digest = m.digest AND v.status = 'verified'
+                ) AS verified,
+                EXISTS (
+                    SELECT 1 FROM manifest_quarantines q
+                    WHERE q.repository_id = m.repository_id AND q.manifest_digest = m.digest AND q.status = 'quarantined'
+                ) AS quarantined,
+                s.critical_count, s.high_count, s.medium_count, s.low_count, s.unknown_count
+         FROM manifests m
+         JOIN repositories r ON r.id = m.repository_id
+         LEFT JOIN vulnerability_scans s
+             ON s.repository_id = m.repository_id AND s.manifest_digest = m.digest AND s.status = 'completed'
+         WHERE r.organization_id = $1 AND m.subject_digest IS NULL
+           AND EXISTS (SELECT 1 FROM tags t WHERE t.manifest_id = m.id AND t.name NOT LIKE 'sha256-%')",
+    )
+    .bind(org_id)
+    .bind(crate::signatures::signature_artifact_types())
+    .fetch_all(pool)
+    .await?;
+    let secret_findings: HashMap<String, i64> = sqlx::query_as::<_, (String, i64)>(
+        "SELECT repository, COUNT(*)
+         FROM secret_findings
+         WHERE organization_id = $1 AND found_at > CURRENT_TIMESTAMP - INTERVAL '30 days'
+         GROUP BY repository",
+    )
+    .bind(org_id)
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .collect();
+    let policies = OrganizationPolicies::load(pool, org_id).await?;
+
+    let mut per_repository: HashMap<i64, ImageSecurity> = HashMap::new();
+    let flags: HashMap<i64, (&str, bool)> = repositories
+        .iter()
+        .map(|repository| (repository.id, (repository.name.as_str(), repository.require_signature)))
+        .collect();
+    for row in images {
+        let Some(&(name, require_signature)) = flags.get(&row.repository_id) else {
+            continue;
+        };
+        let vulnerabilities = row.vulnerabilities();
+        let image = Image { digest: row.digest.clone(), signed: row.verified, vulnerabilities: vulnerabilities.clone() };
+        let noncompliant = policies
+            .pull_violation(row.repository_id, &format!("{}/{}", namespace, name), require_signature, &image)
+            .is_some();
+        tally(per_repository.entry(row.repository_id).or_default(), &row, vulnerabilities.as_ref(), noncompliant);
+    }
+
+    let mut totals = ImageSecurity::default();
+    let mut repositories: Vec<RepositorySecurity> = repositories
+        .into_iter()
+        .map(|repository| {
+            let full_name = format!("{}/{}", namespace, repository.name);
+            let mut images = per_repository.remove(&repository.id).unwrap_or_default();
+            add(&mut totals, &images);
+            with_coverage(&mut images);
+            RepositorySecurity {
+                secret_findings_30_days: secret_findings.get(&full_name).copied().unwrap_or(0),
+                repository: full_name,
+                is_public: repository.is_public,
+                require_signature: repository.require_signature,
+                quarantine_pushes: repository.quarantine_pushes,
+                images,
+            }
+        })
+        .collect();
+    with_coverage(&mut totals);
+    repositories.sort_by(|a, b| worst_first(&a.images, &b.images).then_with(|| a.repository.cmp(&b.repository)));
+
+    Ok(OrganizationSecuritySummary {
+        organization_id: org_id,
+        repository_count: repositories.len() as i64,
+        images: totals,
+        repositories,
+        active_policies: policies.len() as i64,
+        // Findings of repositories deleted since are still counted
+        secret_findings_30_days: secret_findings.values().sum(),
+        generated_at: chrono::Utc::now(),
+    })
+}
+
+/// Count one tagged image
+fn tally(images: &mut ImageSecurity, row: &ImageRow, vulnerabilities: Option<&SeverityCounts>, noncompliant: bool) {
+    images.tagged_images += 1;
+    if let Some(counts) = vulnerabilities {
+        images.scanned_images += 1;
+        add_counts(&mut images.vulnerabilities, counts);
+        if counts.critical + counts.high > 0 {
+            images.vulnerable_images += 1;
+        }
+    }
+    images.signed_images += i64::from(row.signed);
+    images.verified_images += i64::from(row.verified);
+    images.quarantined_images += i64::from(row.quarantined);
+    images.noncompliant_images += i64::from(noncompliant);
+}
+
+/// Add the counts of a repository to the organization's
+fn add(totals: &mut ImageSecurity, images: &ImageSecurity) {
+    totals.tagged_images += images.tagged_images;
+    totals.scanned_images += images.scanned_images;
+    add_counts(&mut totals.vulnerabilities, &images.vulnerabilities);
+    totals.vulnerable_images += images.vulnerable_images;
+    totals.signed_images += images.signed_images;
+    totals.verified_images += images.verified_images;
+    totals.noncompliant_images += images.noncompliant_images;
+    totals.quarantined_images += images.quarantined_images;
+}
+
+fn add_counts(totals: &mut SeverityCounts, counts: &SeverityCounts) {
+    totals.critical += counts.critical;
+    totals.high += counts.high;
+    totals.medium += counts.medium;
+    totals.low += counts.low;
+    totals.unknown += counts.unknown;
+    totals.total += counts.total;
+}
+
+fn with_coverage(images: &mut ImageSecurity) {
+    images.scan_coverage_percent = percent_of(images.scanned_images, images.tagged_images);
+    images.signature_coverage_percent = percent_of(images.signed_images, images.tagged_images);
+}
+
+/// Most critical findings first, then most high findings, then most images violating a policy
+fn worst_first(a: &ImageSecurity, b: &ImageSecurity) -> std::cmp::Ordering {
+    let key = |images: &ImageSecurity| {
+        (images.vulnerabilities.critical, images.vulnerabilities.high, images.noncompliant_images)
+    };
+    key(b).cmp(&key(a))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(signed: bool, verified: bool, critical: Option<i32>) -> ImageRow {
+        ImageRow {
+            repository_id: 1,
+            digest: format!("sha256:{}", "a".repeat(64)),
+            signed,
+            verified,
+            quarantined: false,
+            critical_count: critical,
+            high_count: critical.map(|_| 2),
+            medium_count: None,
+            low_count: None,
+            unknown_count: None,
+        }
+    }
+
+    #[test]
+    fn tagged_images_are_tallied_with_coverage() {
+        let mut images = ImageSecurity::default();
+        for (image, noncompliant) in [(row(true, true, Some(1)), false), (row(true, false, Some(0)), true), (row(false, false, None), true)] {
+            tally(&mut images, &image, image.vulnerabilities().as_ref(), noncompliant);
+        }
+        with_coverage(&mut images);
+
+        assert_eq!(images.tagged_images, 3);
+        assert_eq!(images.scanned_images, 2);
+        assert_eq!(images.vulnerabilities.critical, 1);
+        assert_eq!(images.vulnerabilities.high, 4);
+        assert_eq!(images.vulnerabilities.total, 5);
+        assert_eq!(images.vulnerable_images, 2);
+        assert_eq!((images.signed_images, images.verified_images), (2, 1));
+        assert_eq!(images.noncompliant_images, 2);
+        assert_eq!(images.scan_coverage_percent, Some(66.7));
+        assert_eq!(images.signature_coverage_percent, Some(66.7));
+
+        let mut empty = ImageSecurity::default();
+        with_coverage(&mut empty);
+        assert_eq!(empty.scan_coverage_percent, None);
+    }
+
+    #[test]
+    fn repositories_with_critical_findings_come_first() {
+        let critical = ImageSecurity {
+            vulnerabilities: SeverityCounts { critical: 1, total: 1, ..Default::default() },
+            ..Default::default()
+        };
+        let high = ImageSecurity {
+            vulnerabilities: SeverityCounts { high: 9, total: 9, ..Default::default() },
+            noncompliant_images: 3,
+            ..Default::default()
+        };
+        let noncompliant = ImageSecurity { noncompliant_images: 1, ..Default::default() };
+        let mut repositories = [&noncompliant, &ImageSecurity::default(), &high, &critical];
+        repositories.sort_by(|a, b| worst_first(a, b));
+        assert_eq!(repositories, [&critical, &high, &noncompliant, &ImageSecurity::default()]);
+    }
+}
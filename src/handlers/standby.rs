@@ -0,0 +1,154 @@
+// src/handlers/standby.rs - Read-only guard, status and promotion endpoints for warm standby instances
+//
+// The read-only guard also enforces the `readonly_mode` feature flag.
+use axum::{
+    extract::{OriginalUri, Request, State},
+    http::{HeaderValue, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use axum_extra::headers::{authorization::Bearer, Authorization};
+use axum_extra::TypedHeader;
+use secrecy::ExposeSecret;
+use serde::Deserialize;
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use utoipa::ToSchema;
+
+use crate::{
+    features::Feature,
+    log_stream::LogEvent,
+    standby::{PromotionError, StandbyStatus},
+    AppState,
+};
+
+const PROMOTE_PATH: &str = "/api/v1/standby/promote";
+
+/// Writes accepted in read-only mode, so an administrator can log in and turn it off
+const READONLY_MODE_EXEMPT: [&str; 3] = ["/api/v1/auth/login", "/api/v1/auth/refresh", "/api/v1/admin/features"];
+
+#[derive(Debug, Default, Deserialize, ToSchema)]
+pub struct PromoteRequest {
+    /// Skip the primary reachability and replication lag checks
+    #[serde(default)]
+    pub force: bool,
+}
+
+/// Middleware rejecting writes while this instance is an unpromoted standby or in read-only mode
+pub async fn enforce_read_only(
+    State(state): State<AppState>,
+    OriginalUri(uri): OriginalUri,
+    request: Request,
+    next: Next,
+) -> Response {
+    if matches!(*request.method(), Method::GET | Method::HEAD | Method::OPTIONS) {
+        return next.run(request).await;
+    }
+
+    let path = uri.path();
+    let message = if state.standby.is_read_only() && path != PROMOTE_PATH {
+        "This registry is a read-only standby; send writes to the primary"
+    } else if state.features.enabled(Feature::ReadonlyMode)
+        && !READONLY_MODE_EXEMPT.iter().any(|exempt| path.starts_with(exempt))
+    {
+        "This registry is in read-only mode"
+    } else {
+        return next.run(request).await;
+    };
+
+    let body = if path.starts_with("/v2") {
+        json!({
+            "errors": [{
+                "code": "UNAVAILABLE",
+                "message": message,
+                "detail": {}
+            }]
+        })
+    } else {
+        json!({ "error": message })
+    };
+
+    let mut response = (StatusCode::SERVICE_UNAVAILABLE, Json(body)).into_response();
+    response.headers_mut().insert("Retry-After", HeaderValue::from_static("30"));
+    response
+}
+
+/// Report this instance's role and replication state
+#[utoipa::path(
+    get,
+    path = "/api/v1/standby/status",
+    tag = "standby",
+    responses(
+        (status = 200, description = "Role, read-only state and latest replication sample", body = StandbyStatus)
+    )
+)]
+pub async fn standby_status(State(state): State<AppState>) -> Json<StandbyStatus> {
+    Json(state.standby.status())
+}
+
+/// Promote this standby to a writable primary
+///
+/// Requires `Authorization: Bearer <STANDBY_PROMOTION_TOKEN>`. Without `force`, promotion is
+/// refused while the primary still accepts connections or replication lag exceeds
+/// `STANDBY_MAX_PROMOTION_LAG_SECONDS`.
+#[utoipa::path(
+    post,
+    path = "/api/v1/standby/promote",
+    tag = "standby",
+    request_body(content = PromoteRequest, description = "Promotion options", content_type = "application/json"),
+    responses(
+        (status = 200, description = "Instance promoted", body = StandbyStatus),
+        (status = 401, description = "Missing or wrong promotion token"),
+        (status = 403, description = "Promotion is not enabled"),
+        (status = 409, description = "Not a standby, already promoted, or promotion is unsafe"),
+        (status = 500, description = "Promotion failed")
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn promote_standby(
+    State(state): State<AppState>,
+    auth: Option<TypedHeader<Authorization<Bearer>>>,
+    request: Option<Json<PromoteRequest>>,
+) -> Response {
+    let settings = &state.config.standby;
+    let expected = match &settings.promotion_token {
+        Some(token) => token,
+        None => {
+            return (StatusCode::FORBIDDEN, Json(json!({
+                "error": "Promotion is disabled; set STANDBY_PROMOTION_TOKEN to enable it"
+            }))).into_response()
+        }
+    };
+
+    // Compare digests so the check does not leak how much of the token matched
+    let authorized = auth.is_some_and(|TypedHeader(Authorization(bearer))| {
+        Sha256::digest(bearer.token().as_bytes()) == Sha256::digest(expected.expose_secret().as_bytes())
+    });
+    if !authorized {
+        return (StatusCode::UNAUTHORIZED, Json(json!({
+            "error": "Invalid promotion token"
+        }))).into_response();
+    }
+
+    let force = request.map(|Json(request)| request.force).unwrap_or_default();
+    match state.standby.promote(&state.db_pool, settings, force).await {
+        Ok(replication) => {
+            tracing::info!("Standby promoted to primary (mode {:?}, lag {:?}s, forced: {})", replication.mode, replication.lag_seconds, force);
+            state.log_stream.publish(
+                LogEvent::audit("standby.promote", None, None)
+                    .with_detail(format!("mode={:?} lag={:?} force={}", replication.mode, replication.lag_seconds, force)),
+            );
+            (StatusCode::OK, Json(state.standby.status())).into_response()
+        }
+        Err(e @ (PromotionError::NotStandby | PromotionError::AlreadyPromoted | PromotionError::Unsafe(_))) => {
+            (StatusCode::CONFLICT, Json(json!({ "error": e.to_string() }))).into_response()
+        }
+        Err(e @ PromotionError::Failed(_)) => {
+            tracing::error!("{}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({ "error": e.to_string() }))).into_response()
+        }
+    }
+}
@@ -107,7 +107,7 @@ pub async fn upload_blob(
             }))
         },
         Err(e) => {
-            eprintln!("Storage error: {}", e);
+            tracing::error!("Storage error: {}", e);
             Err(StatusCode::INTERNAL_SERVER_ERROR)
         }
     }
@@ -173,6 +173,13 @@ pub async fn delete_blob(
     State(state): State<AppState>,
     Path(digest): Path<String>
 ) -> Json<DeleteResponse> {
+    if !state.features.enabled(crate::features::Feature::AllowDelete) {
+        return Json(DeleteResponse {
+            success: false,
+            message: "Deletes are disabled on this registry".to_string(),
+        });
+    }
+
     let key = format!("blobs/{}", digest);
     
     let success = match state.storage.delete_blob(&key).await {
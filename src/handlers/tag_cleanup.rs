@@ -0,0 +1,207 @@
+// src/handlers/tag_cleanup.rs - Tag cleanup suggestions of a repository
+use axum::{
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use axum_extra::headers::{authorization::Bearer, Authorization};
+use axum_extra::TypedHeader;
+use secrecy::ExposeSecret;
+use serde_json::json;
+
+use crate::{
+    auth::extract_user_id_dual,
+    handlers::docker_auth::check_repository_permission,
+    handlers::organizations::get_user_role_in_org,
+    log_stream::LogEvent,
+    models::api_key::ApiKeyScope,
+    models::tag_cleanup::{SuggestedRetentionPolicy, TagCleanupQuery, TagCleanupSuggestions},
+    tag_cleanup::{analyze_repository, load_suggestions, store_suggestions},
+    AppState,
+};
+
+/// Tags suggested for removal, with the space they would free
+///
+/// Returns the latest background analysis, or computes one now with `?refresh=true` or when the
+/// repository was not analyzed yet. Requires pull access.
+#[utoipa::path(
+    get,
+    path = "/api/v1/repos/{namespace}/{repo_name}/cleanup-suggestions",
+    params(
+        ("namespace" = String, Path, description = "Organization namespace"),
+        ("repo_name" = String, Path, description = "Repository name"),
+        TagCleanupQuery
+    ),
+    responses(
+        (status = 200, description = "Cleanup suggestions", body = TagCleanupSuggestions),
+        (status = 401, description = "Authentication required"),
+        (status = 404, description = "Repository not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "repositories",
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn get_cleanup_suggestions(
+    Path((namespace, repo_name)): Path<(String, String)>,
+    State(state): State<AppState>,
+    Query(query): Query<TagCleanupQuery>,
+    headers: HeaderMap,
+    auth: Option<TypedHeader<Authorization<Bearer>>>,
+) -> Response {
+    let secret = state.config.auth.jwt_secret.expose_secret().as_bytes();
+    let user_id = match extract_user_id_dual(auth, &headers, ApiKeyScope::Read, secret, &state.db_pool, state.cache.as_ref()).await {
+        Ok(id) => id,
+        Err(status) => return (status, Json(json!({"error": "Authentication required"}))).into_response(),
+    };
+    match check_repository_permission(&user_id.to_string(), &namespace, &repo_name, "pull", &state).await {
+        Ok(true) => {}
+        Ok(false) => return repository_not_found(&namespace, &repo_name),
+        Err(e) => return internal_error(e),
+    }
+    let (repository_id, _) = match find_repository(&state, &namespace, &repo_name).await {
+        Ok(Some(ids)) => ids,
+        Ok(None) => return repository_not_found(&namespace, &repo_name),
+        Err(e) => return internal_error(e),
+    };
+
+    match current_suggestions(&state, repository_id, &namespace, &repo_name, query.refresh.unwrap_or(false)).await {
+        Ok(suggestions) => (StatusCode::OK, Json(json!(suggestions))).into_response(),
+        Err(e) => internal_error(e),
+    }
+}
+
+/// Adopt the suggested retention policy for the repository
+///
+/// Sets the repository's `tag_retention_keep_last` and `tag_retention_days` to the values of
+/// the current suggestions. Owners and maintainers only.
+#[utoipa::path(
+    post,
+    path = "/api/v1/repos/{namespace}/{repo_name}/cleanup-suggestions/accept",
+    params(
+        ("namespace" = String, Path, description = "Organization namespace"),
+        ("repo_name" = String, Path, description = "Repository name")
+    ),
+    responses(
+        (status = 200, description = "Retention policy applied", body = SuggestedRetentionPolicy),
+        (status = 401, description = "Authentication required"),
+        (status = 403, description = "Only owners and maintainers can change retention"),
+        (status = 404, description = "Repository not found"),
+        (status = 409, description = "Nothing to clean up, so there is no policy to apply"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "repositories",
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn accept_cleanup_suggestions(
+    Path((namespace, repo_name)): Path<(String, String)>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    auth: Option<TypedHeader<Authorization<Bearer>>>,
+) -> Response {
+    let secret = state.config.auth.jwt_secret.expose_secret().as_bytes();
+    let user_id = match extract_user_id_dual(auth, &headers, ApiKeyScope::Admin, secret, &state.db_pool, state.cache.as_ref()).await {
+        Ok(id) => id,
+        Err(status) => return (status, Json(json!({"error": "Authentication required"}))).into_response(),
+    };
+    let (repository_id, org_id) = match find_repository(&state, &namespace, &repo_name).await {
+        Ok(Some(ids)) => ids,
+        Ok(None) => return repository_not_found(&namespace, &repo_name),
+        Err(e) => return internal_error(e),
+    };
+    match get_user_role_in_org(&state.db_pool, org_id, user_id).await {
+        Ok(Some(role)) if role.can_manage_repositories() => {}
+        Ok(Some(_)) => {
+            return (StatusCode::FORBIDDEN, Json(json!({
+                "error": "You don't have permission to update repositories in this organization"
+            }))).into_response()
+        }
+        Ok(None) => return repository_not_found(&namespace, &repo_name),
+        Err(e) => return internal_error(e),
+    }
+
+    let suggestions = match current_suggestions(&state, repository_id, &namespace, &repo_name, false).await {
+        Ok(suggestions) => suggestions,
+        Err(e) => return internal_error(e),
+    };
+    let policy = suggestions.suggested_policy;
+    if policy.tag_retention_keep_last.is_none() && policy.tag_retention_days.is_none() {
+        return (StatusCode::CONFLICT, Json(json!({
+            "error": "No tags are suggested for cleanup, so there is no retention policy to apply"
+        }))).into_response();
+    }
+
+    let result = sqlx::query(
+        "UPDATE repositories
+         SET tag_retention_keep_last = $2, tag_retention_days = $3, updated_at = CURRENT_TIMESTAMP
+         WHERE id = $1",
+    )
+    .bind(repository_id)
+    .bind(policy.tag_retention_keep_last)
+    .bind(policy.tag_retention_days)
+    .execute(&state.db_pool)
+    .await;
+    if let Err(e) = result {
+        return internal_error(e);
+    }
+
+    state.log_stream.publish(
+        LogEvent::audit("repository.retention.update", Some(user_id), Some(format!("{}/{}", namespace, repo_name)))
+            .with_detail(format!(
+                "accepted cleanup suggestions: keep_last={:?} days={:?}",
+                policy.tag_retention_keep_last, policy.tag_retention_days
+            )),
+    );
+    (StatusCode::OK, Json(json!(policy))).into_response()
+}
+
+async fn current_suggestions(
+    state: &AppState,
+    repository_id: i64,
+    namespace: &str,
+    repo_name: &str,
+    refresh: bool,
+) -> anyhow::Result<TagCleanupSuggestions> {
+    if !refresh {
+        if let Some(stored) = load_suggestions(&state.db_pool, repository_id).await? {
+            return Ok(stored);
+        }
+    }
+    let name = format!("{}/{}", namespace, repo_name);
+    let suggestions = analyze_repository(&state.db_pool, repository_id, &name, state.config.tag_cleanup.stale_days).await?;
+    // A standby serves fresh results without keeping them
+    if !state.standby.is_read_only() {
+        store_suggestions(&state.db_pool, repository_id, &suggestions).await?;
+    }
+    Ok(suggestions)
+}
+
+/// (repository ID, organization ID)
+pub(crate) async fn find_repository(state: &AppState, namespace: &str, repo_name: &str) -> Result<Option<(i64, i64)>, sqlx::Error> {
+    sqlx::query_as::<_, (i64, i64)>(
+        "SELECT r.id, r.organization_id
+         FROM repositories r
+         JOIN organizations o ON o.id = r.organization_id
+         WHERE o.name = $1 AND r.name = $2",
+    )
+    .bind(namespace)
+    .bind(repo_name)
+    .fetch_optional(&state.db_pool)
+    .await
+}
+
+pub(crate) fn repository_not_found(namespace: &str, repo_name: &str) -> Response {
+    (StatusCode::NOT_FOUND, Json(json!({
+        "error": format!("Repository '{}/{}' not found", namespace, repo_name)
+    }))).into_response()
+}
+
+pub(crate) fn internal_error(e: impl std::fmt::Display) -> Response {
+    (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({
+        "error": format!("Internal error: {}", e)
+    }))).into_response()
+}
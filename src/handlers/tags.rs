@@ -0,0 +1,337 @@
+// src/handlers/tags.rs - Tag listing with the metadata the web UI shows
+use axum::{
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use axum_extra::headers::{authorization::Bearer, Authorization};
+use axum_extra::TypedHeader;
+use chrono::{DateTime, Utc};
+use secrecy::ExposeSecret;
+use serde_json::{json, Value};
+
+use crate::{
+    auth::extract_user_id_dual,
+    handlers::docker_auth::check_repository_permission,
+    handlers::docker_registry_v2::load_manifest_content,
+    handlers::tag_cleanup::{find_repository, internal_error, repository_not_found},
+    models::api_key::ApiKeyScope,
+    models::quarantine::QuarantineStatus,
+    models::signature::SignatureStatus,
+    models::tag_listing::{ListTagsQuery, Platform, TagDetail, TagPage},
+    AppState,
+};
+
+/// List a repository's tags with digest, size, platforms, who pushed them, signature status and quarantine
+///
+/// Sorted by push time (newest first) or by name. Requires pull access.
+#[utoipa::path(
+    get,
+    path = "/api/v1/repos/{namespace}/{repo_name}/tags",
+    params(
+        ("namespace" = String, Path, description = "Organization namespace"),
+        ("repo_name" = String, Path, description = "Repository name"),
+        ListTagsQuery
+    ),
+    responses(
+        (status = 200, description = "One page of tags", body = TagPage),
+        (status = 400, description = "Unknown sort or order"),
+        (status = 401, description = "Authentication required"),
+        (status = 404, description = "Repository not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "repositories",
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn list_repository_tags(
+    Path((namespace, repo_name)): Path<(String, String)>,
+    State(state): State<AppState>,
+    Query(query): Query<ListTagsQuery>,
+    headers: HeaderMap,
+    auth: Option<TypedHeader<Authorization<Bearer>>>,
+) -> Response {
+    let secret = state.config.auth.jwt_secret.expose_secret().as_bytes();
+    let user_id = match extract_user_id_dual(auth, &headers, ApiKeyScope::Read, secret, &state.db_pool, state.cache.as_ref()).await {
+        Ok(id) => id,
+        Err(status) => return (status, Json(json!({"error": "Authentication required"}))).into_response(),
+    };
+    let order_by = match order_clause(query.sort.as_deref(), query.order.as_deref()) {
+        Ok(order_by) => order_by,
+        Err(message) => return (StatusCode::BAD_REQUEST, Json(json!({"error": message}))).into_response(),
+    };
+    match check_repository_permission(&user_id.to_string(), &namespace, &repo_name, "pull", &state).await {
+        Ok(true) => {}
+        Ok(false) => return repository_not_found(&namespace, &repo_name),
+        Err(e) => return internal_error(e),
+    }
+    let (repository_id, _) = match find_repository(&state, &namespace, &repo_name).await {
+        Ok(Some(ids)) => ids,
+        Ok(None) => return repository_not_found(&namespace, &repo_name),
+        Err(e) => return internal_error(e),
+    };
+
+    let search = query.search.filter(|s| !s.is_empty()).map(|s| format!("%{}%", s));
+    let limit = query.limit.unwrap_or(50).clamp(1, 200);
+    let offset = query.offset.unwrap_or(0).max(0);
+    let name = format!("{}/{}", namespace, repo_name);
+    match load_tags(&state, repository_id, &name, search, order_by, limit, offset).await {
+        Ok(page) => (StatusCode::OK, Json(json!(page))).into_response(),
+        Err(e) => internal_error(e),
+    }
+}
+
+/// ORDER BY clause for the `sort` and `order` parameters; ties fall back to the tag name
+fn order_clause(sort: Option<&str>, order: Option<&str>) -> Result<&'static str, String> {
+    let sort = sort.unwrap_or("pushed");
+    let descending = match (sort, order) {
+        (_, Some("desc")) => true,
+        (_, Some("asc")) => false,
+        (_, Some(other)) => return Err(format!("Unknown order '{}', expected asc or desc", other)),
+        ("name", None) => false,
+        (_, None) => true,
+    };
+    match (sort, descending) {
+        ("pushed", true) => Ok("t.updated_at DESC, t.name"),
+        ("pushed", false) => Ok("t.updated_at ASC, t.name"),
+        ("name", true) => Ok("t.name DESC"),
+        ("name", false) => Ok("t.name ASC"),
+        (other, _) => Err(format!("Unknown sort '{}', expected pushed or name", other)),
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct TagRow {
+    name: String,
+    manifest_id: i64,
+    digest: String,
+    media_type: String,
+    compressed_size: Option<i64>,
+    platforms: Option<Value>,
+    pushed_at: DateTime<Utc>,
+    pushed_by: Option<String>,
+    signature_status: Option<String>,
+    signer: Option<String>,
+    verified_at: Option<DateTime<Utc>>,
+    has_signature: bool,
+    quarantine_status: Option<String>,
+    quarantined_at: Option<DateTime<Utc>>,
+    released_at: Option<DateTime<Utc>>,
+    release_reason: Option<String>,
+    released_by: Option<String>,
+}
+
+async fn load_tags(
+    state: &AppState,
+    repository_id: i64,
+    name: &str,
+    search: Option<String>,
+    order_by: &str,
+    limit: i64,
+    offset: i64,
+) -> anyhow::Result<TagPage> {
+    let pool = state.read_pool();
+    let total = sqlx::query_scalar::<_, i64>(
+        "SELECT COUNT(*) FROM tags WHERE repository_id = $1 AND ($2::TEXT IS NULL OR name ILIKE $2)",
+    )
+    .bind(repository_id)
+    .bind(&search)
+    .fetch_one(pool)
+    .await?;
+
+    let rows = sqlx::query_as::<_, TagRow>(&format!(
+        "SELECT t.name, m.id AS manifest_id, m.digest, m.media_type, m.compressed_size, m.platforms,
+                t.updated_at AS pushed_at, u.username AS pushed_by,
+                v.status AS signature_status, v.signer, v.verified_at,
+                EXISTS (
+                    SELECT 1 FROM manifests s
+                    WHERE s.repository_id = m.repository_id
+                      AND ((s.subject_digest = m.digest AND s.artifact_type = ANY($5))
+                           OR s.id = (SELECT st.manifest_id FROM tags st
+                                      WHERE st.repository_id = m.repository_id AND st.name = REPLACE(m.digest, ':', '-') || '.sig'))
+                ) AS has_signature,
+                q.status AS quarantine_status, q.quarantined_at, q.released_at, q.release_reason,
+                qu.username AS released_by
+         FROM tags t
+         JOIN manifests m ON m.id = t.manifest_id
+         LEFT JOIN users u ON u.id = t.pushed_by
+         LEFT JOIN signature_verifications v ON v.repository_id = m.repository_id AND v.manifest_digest = m.digest
+         LEFT JOIN manifest_quarantines q ON q.repository_id = m.repository_id AND q.manifest_digest = m.digest
+         LEFT JOIN users qu ON qu.id = q.released_by
+         WHERE t.repository_id = $1 AND ($2::TEXT IS NULL OR t.name ILIKE $2)
+         ORDER BY {}
+         LIMIT $3 OFFSET $4",
+        order_by
+    ))
+    .bind(repository_id)
+    .bind(&search)
+    .bind(limit)
+    .bind(offset)
+    .bind(crate::signatures::signature_artifact_types())
+    .fetch_all(pool)
+    .await?;
+
+    let mut tags = Vec::with_capacity(rows.len());
+    for row in rows {
+        let TagRow {
+            name: tag,
+            manifest_id,
+            digest,
+            media_type,
+            mut compressed_size,
+            platforms,
+            pushed_at,
+            pushed_by,
+            signature_status,
+            signer,
+            verified_at,
+            has_signature,
+            quarantine_status,
+            quarantined_at,
+            released_at,
+            release_reason,
+            released_by,
+        } = row;
+        let signature = SignatureStatus::from_parts(signature_status.as_deref(), signer, verified_at, has_signature);
+        let quarantine = quarantine_status.zip(quarantined_at).map(|(state, quarantined_at)| QuarantineStatus {
+            state,
+            quarantined_at,
+            released_at,
+            release_reason,
+            released_by,
+        });
+        let platforms = match platforms {
+            Some(platforms) => serde_json::from_value(platforms).unwrap_or_default(),
+            // Pushed before summaries were recorded
+            None => match load_manifest_content(state, name, &digest).await {
+                // A standby's database is a read-only replica
+                Ok(Some(content)) if state.standby.is_read_only() => {
+                    let (size, platforms) = summarize_manifest(&content);
+                    compressed_size = size;
+                    platforms
+                }
+                Ok(Some(content)) => {
+                    let (size, platforms) = record_manifest_summary(&state.db_pool, manifest_id, &content).await?;
+                    compressed_size = size;
+                    platforms
+                }
+                Ok(None) => Vec::new(),
+                Err(e) => {
+                    tracing::warn!("Failed to load manifest {} of {}: {}", digest, name, e);
+                    Vec::new()
+                }
+            },
+        };
+        tags.push(TagDetail {
+            name: tag,
+            digest,
+            media_type,
+            compressed_size,
+            platforms,
+            pushed_at,
+            pushed_by,
+            signature,
+            quarantine,
+        });
+    }
+
+    Ok(TagPage { total, limit, offset, tags })
+}
+
+/// Compressed size of an image manifest and the platforms of an index
+///
+/// The size of an index is left to `record_manifest_summary`, which adds up its platform images.
+pub(crate) fn summarize_manifest(content: &[u8]) -> (Option<i64>, Vec<Platform>) {
+    let Ok(manifest) = serde_json::from_slice::<Value>(content) else {
+        return (None, Vec::new());
+    };
+    if let Some(entries) = manifest.get("manifests").and_then(Value::as_array) {
+        let platforms = entries
+            .iter()
+            .filter_map(|entry| {
+                let platform = entry.get("platform")?;
+                let os = platform.get("os")?.as_str()?;
+                let architecture = platform.get("architecture")?.as_str()?;
+                // Build attestations are listed as unknown/unknown
+                if os == "unknown" || architecture == "unknown" {
+                    return None;
+                }
+                Some(Platform {
+                    os: os.to_string(),
+                    architecture: architecture.to_string(),
+                    variant: platform.get("variant").and_then(Value::as_str).map(str::to_string),
+                    digest: entry.get("digest")?.as_str()?.to_string(),
+                })
+            })
+            .collect();
+        return (None, platforms);
+    }
+
+    let size_of = |descriptor: &Value| descriptor.get("size").and_then(Value::as_i64).unwrap_or(0);
+    let config = manifest.get("config").map(size_of).unwrap_or(0);
+    let layers: i64 = manifest.get("layers").and_then(Value::as_array).map(|layers| layers.iter().map(size_of).sum()).unwrap_or(0);
+    (Some(config + layers), Vec::new())
+}
+
+/// Store the compressed size and platforms of a manifest; an index's size is the sum of its
+/// platform images already in the repository
+pub(crate) async fn record_manifest_summary<'e, E: sqlx::PgExecutor<'e>>(
+    executor: E,
+    manifest_id: i64,
+    content: &[u8],
+) -> Result<(Option<i64>, Vec<Platform>), sqlx::Error> {
+    let (size, platforms) = summarize_manifest(content);
+    let digests: Vec<&str> = platforms.iter().map(|p| p.digest.as_str()).collect();
+    let size = sqlx::query_scalar::<_, Option<i64>>(
+        "UPDATE manifests
+         SET platforms = $2,
+             compressed_size = COALESCE($3, (
+                 SELECT SUM(c.compressed_size)::BIGINT FROM manifests c
+                 WHERE c.repository_id = manifests.repository_id AND c.digest = ANY($4)
+             ))
+         WHERE id = $1
+         RETURNING compressed_size",
+    )
+    .bind(manifest_id)
+    .bind(json!(platforms))
+    .bind(size)
+    .bind(&digests)
+    .fetch_optional(executor)
+    .await?
+    .flatten();
+    Ok((size, platforms))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn summarizes_images_and_indexes() {
+        let image = br#"{"schemaVersion":2,"config":{"size":100},"layers":[{"size":1000},{"size":23}]}"#;
+        assert_eq!(summarize_manifest(image), (Some(1123), Vec::new()));
+
+        let index = br#"{"schemaVersion":2,"manifests":[
+            {"digest":"sha256:a","platform":{"os":"linux","architecture":"amd64"}},
+            {"digest":"sha256:b","platform":{"os":"linux","architecture":"arm64","variant":"v8"}},
+            {"digest":"sha256:c","platform":{"os":"unknown","architecture":"unknown"}}
+        ]}"#;
+        let (size, platforms) = summarize_manifest(index);
+        assert_eq!(size, None);
+        assert_eq!(platforms.len(), 2);
+        assert_eq!(platforms[1].variant.as_deref(), Some("v8"));
+
+        assert_eq!(summarize_manifest(b"not json"), (None, Vec::new()));
+    }
+
+    #[test]
+    fn sorts_by_push_time_or_name() {
+        assert_eq!(order_clause(None, None), Ok("t.updated_at DESC, t.name"));
+        assert_eq!(order_clause(Some("name"), None), Ok("t.name ASC"));
+        assert_eq!(order_clause(Some("name"), Some("desc")), Ok("t.name DESC"));
+        assert!(order_clause(Some("size"), None).is_err());
+        assert!(order_clause(None, Some("up")).is_err());
+    }
+}
@@ -0,0 +1,509 @@
+// src/handlers/takedowns.rs - Content moderation: takedowns, evidence snapshots and reinstatement
+use std::collections::HashMap;
+
+use anyhow::Result;
+use axum::{
+    extract::{Path, Query, Request, State},
+    http::{Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use bytes::Bytes;
+use serde_json::json;
+use sqlx::PgPool;
+
+use crate::{
+    handlers::admin::{internal_error, AdminUser},
+    log_stream::LogEvent,
+    models::content_takedown::{ContentTakedown, CreateTakedownRequest, ReinstateTakedownRequest, TakedownListQuery},
+    AppState,
+};
+
+const TAKEDOWN_SELECT: &str = "SELECT id, repository_id, repository, digest, reason, notice_reference, evidence_status,
+            evidence_prefix, evidence_error, created_by, created_at, reinstated_by, reinstated_at, reinstatement_note
+     FROM content_takedowns";
+
+/// Take down a repository or one digest within it
+///
+/// Pulls of the content fail with `451 Unavailable For Legal Reasons` until the takedown is
+/// reinstated. The content itself is kept: a copy is written to `takedowns/{id}/` as evidence
+/// in the background, and the owners of the organization are notified by email.
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/takedowns",
+    tag = "admin",
+    request_body = CreateTakedownRequest,
+    responses(
+        (status = 202, description = "Content taken down, evidence capture started", body = ContentTakedown),
+        (status = 400, description = "Missing reason"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Not a registry administrator"),
+        (status = 404, description = "Repository or digest not found"),
+        (status = 409, description = "The content is already taken down"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn create_takedown(
+    State(state): State<AppState>,
+    admin: AdminUser,
+    Json(req): Json<CreateTakedownRequest>,
+) -> Response {
+    if req.reason.trim().is_empty() {
+        return (StatusCode::BAD_REQUEST, Json(json!({
+            "error": "A reason is required to take content down"
+        }))).into_response();
+    }
+
+    let (namespace, name) = match req.repository.split_once('/') {
+        Some((namespace, name)) => (Some(namespace), name),
+        None => (None, req.repository.as_str()),
+    };
+    let repository = match find_repository(&state.db_pool, namespace, name).await {
+        Ok(Some(repository)) => repository,
+        Ok(None) => {
+            return (StatusCode::NOT_FOUND, Json(json!({
+                "error": format!("Repository '{}' not found", req.repository)
+            }))).into_response()
+        }
+        Err(e) => return internal_error(e),
+    };
+
+    if let Some(digest) = &req.digest {
+        match sqlx::query_scalar::<_, bool>("SELECT EXISTS(SELECT 1 FROM manifests WHERE repository_id = $1 AND digest = $2)")
+            .bind(repository.id)
+            .bind(digest)
+            .fetch_one(&state.db_pool)
+            .await
+        {
+            Ok(true) => {}
+            Ok(false) => {
+                return (StatusCode::NOT_FOUND, Json(json!({
+                    "error": format!("Digest {} not found in {}", digest, repository.full_name)
+                }))).into_response()
+            }
+            Err(e) => return internal_error(e),
+        }
+    }
+
+    let already = sqlx::query_scalar::<_, bool>(
+        "SELECT EXISTS(
+             SELECT 1 FROM content_takedowns
+             WHERE repository_id = $1 AND reinstated_at IS NULL AND (digest IS NULL OR digest IS NOT DISTINCT FROM $2)
+         )",
+    )
+    .bind(repository.id)
+    .bind(&req.digest)
+    .fetch_one(&state.db_pool)
+    .await;
+    match already {
+        Ok(false) => {}
+        Ok(true) => {
+            return (StatusCode::CONFLICT, Json(json!({
+                "error": "This content is already taken down"
+            }))).into_response()
+        }
+        Err(e) => return internal_error(e),
+    }
+
+    let takedown = match sqlx::query_as::<_, ContentTakedown>(
+        "INSERT INTO content_takedowns (repository_id, repository, digest, reason, notice_reference, created_by)
+         VALUES ($1, $2, $3, $4, $5, $6)
+         RETURNING id, repository_id, repository, digest, reason, notice_reference, evidence_status,
+                   evidence_prefix, evidence_error, created_by, created_at, reinstated_by, reinstated_at, reinstatement_note",
+    )
+    .bind(repository.id)
+    .bind(&repository.full_name)
+    .bind(&req.digest)
+    .bind(req.reason.trim())
+    .bind(&req.notice_reference)
+    .bind(admin.user_id)
+    .fetch_one(&state.db_pool)
+    .await
+    {
+        Ok(takedown) => takedown,
+        Err(e) => return internal_error(e),
+    };
+
+    tracing::info!("{} taken down by {}: {}", describe(&takedown), admin.username, takedown.reason);
+    state.log_stream.publish(
+        LogEvent::audit("content.takedown", Some(admin.user_id), Some(takedown.repository.clone())).with_detail(format!(
+            "takedown={} digest={} notice={}",
+            takedown.id,
+            takedown.digest.as_deref().unwrap_or("*"),
+            takedown.notice_reference.as_deref().unwrap_or("-")
+        )),
+    );
+
+    // Evidence capture and owner notification run in the background
+    let background_state = state.clone();
+    let background_takedown = takedown.clone();
+    let organization_id = repository.organization_id;
+    tokio::spawn(async move {
+        let state = background_state;
+        let takedown = background_takedown;
+        match capture_evidence(&state, &takedown).await {
+            Ok(prefix) => {
+                let _ = sqlx::query("UPDATE content_takedowns SET evidence_status = 'completed', evidence_prefix = $2 WHERE id = $1")
+                    .bind(takedown.id)
+                    .bind(&prefix)
+                    .execute(&state.db_pool)
+                    .await;
+            }
+            Err(e) => {
+                tracing::error!("Takedown {} evidence capture failed: {}", takedown.id, e);
+                let _ = sqlx::query("UPDATE content_takedowns SET evidence_status = 'failed', evidence_error = $2 WHERE id = $1")
+                    .bind(takedown.id)
+                    .bind(e.to_string())
+                    .execute(&state.db_pool)
+                    .await;
+            }
+        }
+
+        for (email, username) in organization_owners(&state.db_pool, organization_id).await.unwrap_or_default() {
+            if let Err(e) = state
+                .email_service
+                .send_content_takedown_email(&email, &username, &describe(&takedown), &takedown.reason, takedown.notice_reference.as_deref())
+                .await
+            {
+                tracing::warn!("Failed to notify {} of takedown {}: {}", username, takedown.id, e);
+            }
+        }
+    });
+
+    (StatusCode::ACCEPTED, Json(takedown)).into_response()
+}
+
+/// List takedowns, newest first
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/takedowns",
+    tag = "admin",
+    params(TakedownListQuery),
+    responses(
+        (status = 200, description = "Takedowns", body = Vec<ContentTakedown>),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Not a registry administrator"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn list_takedowns(
+    State(state): State<AppState>,
+    _admin: AdminUser,
+    Query(query): Query<TakedownListQuery>,
+) -> Response {
+    let sql = format!("{} WHERE NOT $1 OR reinstated_at IS NULL ORDER BY created_at DESC", TAKEDOWN_SELECT);
+    match sqlx::query_as::<_, ContentTakedown>(&sql)
+        .bind(query.active)
+        .fetch_all(&state.db_pool)
+        .await
+    {
+        Ok(takedowns) => (StatusCode::OK, Json(takedowns)).into_response(),
+        Err(e) => internal_error(e),
+    }
+}
+
+/// Get a takedown
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/takedowns/{id}",
+    tag = "admin",
+    params(("id" = i64, Path, description = "Takedown ID")),
+    responses(
+        (status = 200, description = "Takedown", body = ContentTakedown),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Not a registry administrator"),
+        (status = 404, description = "Takedown not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn get_takedown(
+    State(state): State<AppState>,
+    _admin: AdminUser,
+    Path(id): Path<i64>,
+) -> Response {
+    match sqlx::query_as::<_, ContentTakedown>(&format!("{} WHERE id = $1", TAKEDOWN_SELECT))
+        .bind(id)
+        .fetch_optional(&state.db_pool)
+        .await
+    {
+        Ok(Some(takedown)) => (StatusCode::OK, Json(takedown)).into_response(),
+        Ok(None) => takedown_not_found(id),
+        Err(e) => internal_error(e),
+    }
+}
+
+/// Reinstate taken-down content
+///
+/// Pulls are served again; the takedown record and its evidence are kept.
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/takedowns/{id}/reinstate",
+    tag = "admin",
+    params(("id" = i64, Path, description = "Takedown ID")),
+    request_body = ReinstateTakedownRequest,
+    responses(
+        (status = 200, description = "Content reinstated", body = ContentTakedown),
+        (status = 400, description = "Missing note"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Not a registry administrator"),
+        (status = 404, description = "Takedown not found or already reinstated"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn reinstate_takedown(
+    State(state): State<AppState>,
+    admin: AdminUser,
+    Path(id): Path<i64>,
+    Json(req): Json<ReinstateTakedownRequest>,
+) -> Response {
+    if req.note.trim().is_empty() {
+        return (StatusCode::BAD_REQUEST, Json(json!({
+            "error": "A note is required to reinstate content"
+        }))).into_response();
+    }
+
+    let takedown = match sqlx::query_as::<_, ContentTakedown>(
+        "UPDATE content_takedowns
+         SET reinstated_at = NOW(), reinstated_by = $2, reinstatement_note = $3
+         WHERE id = $1 AND reinstated_at IS NULL
+         RETURNING id, repository_id, repository, digest, reason, notice_reference, evidence_status,
+                   evidence_prefix, evidence_error, created_by, created_at, reinstated_by, reinstated_at, reinstatement_note",
+    )
+    .bind(id)
+    .bind(admin.user_id)
+    .bind(req.note.trim())
+    .fetch_optional(&state.db_pool)
+    .await
+    {
+        Ok(Some(takedown)) => takedown,
+        Ok(None) => {
+            return (StatusCode::NOT_FOUND, Json(json!({
+                "error": format!("No active takedown with ID {}", id)
+            }))).into_response()
+        }
+        Err(e) => return internal_error(e),
+    };
+
+    tracing::info!("{} reinstated by {}", describe(&takedown), admin.username);
+    state.log_stream.publish(
+        LogEvent::audit("content.reinstate", Some(admin.user_id), Some(takedown.repository.clone()))
+            .with_detail(format!("takedown={}", takedown.id)),
+    );
+
+    if let Some(repository_id) = takedown.repository_id {
+        let notify_state = state.clone();
+        let content = describe(&takedown);
+        let note = req.note.trim().to_string();
+        tokio::spawn(async move {
+            let organization_id = sqlx::query_scalar::<_, i64>("SELECT organization_id FROM repositories WHERE id = $1")
+                .bind(repository_id)
+                .fetch_optional(&notify_state.db_pool)
+                .await;
+            let Ok(Some(organization_id)) = organization_id else {
+                return;
+            };
+            for (email, username) in organization_owners(&notify_state.db_pool, organization_id).await.unwrap_or_default() {
+                if let Err(e) = notify_state.email_service.send_content_reinstated_email(&email, &username, &content, &note).await {
+                    tracing::warn!("Failed to notify {} of reinstatement: {}", username, e);
+                }
+            }
+        });
+    }
+
+    (StatusCode::OK, Json(takedown)).into_response()
+}
+
+/// Middleware for the registry router: answers pulls of taken-down content with a policy error
+/// instead of the content. Manifests requested by tag are checked against the digest the tag
+/// points to.
+pub async fn enforce_takedowns(
+    State(state): State<AppState>,
+    path: Option<Path<HashMap<String, String>>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if !matches!(*request.method(), Method::GET | Method::HEAD) || request.uri().path().contains("/referrers/") {
+        return next.run(request).await;
+    }
+    let Some(Path(params)) = path else {
+        return next.run(request).await;
+    };
+    let (Some(name), Some(reference)) = (params.get("name"), params.get("reference").or_else(|| params.get("digest"))) else {
+        return next.run(request).await;
+    };
+
+    match find_active_takedown(&state.db_pool, params.get("org").map(String::as_str), name, reference).await {
+        Ok(None) => next.run(request).await,
+        Ok(Some(takedown)) => {
+            tracing::info!("Pull of {}@{} refused by takedown {}", takedown.repository, reference, takedown.id);
+            (
+                StatusCode::UNAVAILABLE_FOR_LEGAL_REASONS,
+                Json(json!({
+                    "errors": [{
+                        "code": "DENIED",
+                        "message": format!("This content is unavailable: {}", takedown.reason),
+                        "detail": {
+                            "policy": "content-takedown",
+                            "takedown_id": takedown.id,
+                            "notice_reference": takedown.notice_reference,
+                        }
+                    }]
+                })),
+            )
+                .into_response()
+        }
+        Err(e) => {
+            // Fail closed: content under a takedown must not be served because the check failed
+            tracing::error!("Error checking content takedowns: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({
+                    "errors": [{ "code": "UNKNOWN", "message": "Internal server error", "detail": {} }]
+                })),
+            )
+                .into_response()
+        }
+    }
+}
+
+/// Active takedown covering `reference` (a tag or digest) in a repository, if any
+pub async fn find_active_takedown(
+    pool: &PgPool,
+    namespace: Option<&str>,
+    name: &str,
+    reference: &str,
+) -> Result<Option<ContentTakedown>> {
+    let Some(repository) = find_repository(pool, namespace, name).await? else {
+        return Ok(None);
+    };
+
+    let sql = format!(
+        "{} WHERE repository_id = $1 AND reinstated_at IS NULL
+           AND (digest IS NULL
+                OR digest = $2
+                OR digest = (SELECT m.digest FROM tags t JOIN manifests m ON m.id = t.manifest_id
+                             WHERE t.repository_id = $1 AND t.name = $2))
+         ORDER BY created_at
+         LIMIT 1",
+        TAKEDOWN_SELECT
+    );
+    let takedown = sqlx::query_as::<_, ContentTakedown>(&sql)
+        .bind(repository.id)
+        .bind(reference)
+        .fetch_optional(pool)
+        .await?;
+    Ok(takedown)
+}
+
+#[derive(sqlx::FromRow)]
+struct TakedownRepository {
+    id: i64,
+    organization_id: i64,
+    full_name: String,
+}
+
+/// Repositories without a namespace live in the default organization, as on push
+async fn find_repository(pool: &PgPool, namespace: Option<&str>, name: &str) -> Result<Option<TakedownRepository>, sqlx::Error> {
+    sqlx::query_as::<_, TakedownRepository>(
+        "SELECT r.id, r.organization_id, o.name || '/' || r.name AS full_name
+         FROM repositories r
+         JOIN organizations o ON o.id = r.organization_id
+         WHERE r.name = $2 AND (o.name = $1 OR ($1 IS NULL AND o.id = 1))",
+    )
+    .bind(namespace)
+    .bind(name)
+    .fetch_optional(pool)
+    .await
+}
+
+async fn organization_owners(pool: &PgPool, organization_id: i64) -> Result<Vec<(String, String)>, sqlx::Error> {
+    sqlx::query_as::<_, (String, String)>(
+        "SELECT u.email, u.username
+         FROM organization_members om JOIN users u ON u.id = om.user_id
+         WHERE om.organization_id = $1 AND LOWER(om.role) = 'owner' AND u.disabled_at IS NULL",
+    )
+    .bind(organization_id)
+    .fetch_all(pool)
+    .await
+}
+
+/// Copy the taken-down content into `takedowns/{id}/` with a `metadata.json` describing the
+/// takedown and the repository's tags at the time. Returns the prefix.
+async fn capture_evidence(state: &AppState, takedown: &ContentTakedown) -> Result<String> {
+    let prefix = format!("takedowns/{}", takedown.id);
+
+    let rows = sqlx::query_as::<_, (String, String)>(
+        "SELECT digest, media_type FROM manifests
+         WHERE repository_id = $1 AND ($2::VARCHAR IS NULL OR digest = $2)
+         ORDER BY digest",
+    )
+    .bind(takedown.repository_id)
+    .bind(&takedown.digest)
+    .fetch_all(&state.db_pool)
+    .await?;
+
+    let tags = sqlx::query_as::<_, (String, String)>(
+        "SELECT t.name, m.digest FROM tags t JOIN manifests m ON m.id = t.manifest_id
+         WHERE t.repository_id = $1 ORDER BY t.name",
+    )
+    .bind(takedown.repository_id)
+    .fetch_all(&state.db_pool)
+    .await?;
+
+    let mut captured = Vec::with_capacity(rows.len());
+    for (digest, media_type) in rows {
+        // Layer rows share the table with manifests but only live in object storage
+        let content = if media_type.contains("manifest") || media_type.contains("index") {
+            crate::handlers::docker_registry_v2::load_manifest_content(state, &takedown.repository, &digest).await?
+        } else {
+            crate::handlers::docker_registry_v2::get_repository_blob(state, &takedown.repository, &digest).await?
+        };
+        let Some(data) = content else {
+            tracing::warn!("Takedown {}: blob {}@{} missing from storage", takedown.id, takedown.repository, digest);
+            continue;
+        };
+        let size = data.len();
+        state.storage.put_blob(&format!("{}/blobs/{}", prefix, digest), data).await?;
+        captured.push(json!({ "digest": digest, "media_type": media_type, "size": size }));
+    }
+
+    let metadata = json!({
+        "takedown": takedown,
+        "tags": tags.iter().map(|(tag, digest)| json!({ "tag": tag, "digest": digest })).collect::<Vec<_>>(),
+        "blobs": captured,
+        "captured_at": chrono::Utc::now(),
+    });
+    state
+        .storage
+        .put_blob(&format!("{}/metadata.json", prefix), Bytes::from(serde_json::to_vec_pretty(&metadata)?))
+        .await?;
+
+    Ok(prefix)
+}
+
+/// `namespace/name` or `namespace/name@digest`
+fn describe(takedown: &ContentTakedown) -> String {
+    match &takedown.digest {
+        Some(digest) => format!("{}@{}", takedown.repository, digest),
+        None => takedown.repository.clone(),
+    }
+}
+
+fn takedown_not_found(id: i64) -> Response {
+    (StatusCode::NOT_FOUND, Json(json!({
+        "error": format!("Takedown {} not found", id)
+    }))).into_response()
+}
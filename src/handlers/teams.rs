@@ -0,0 +1,701 @@
+// src/handlers/teams.rs - Teams within organizations and their repository grants
+use anyhow::{bail, Result};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use sqlx::PgPool;
+
+use axum_extra::headers::{Authorization, authorization::Bearer};
+use axum_extra::TypedHeader;
+use secrecy::ExposeSecret;
+use crate::auth::extract_user_id;
+
+use crate::{
+    handlers::organizations::get_user_role_in_org,
+    log_stream::LogEvent,
+    models::team::{
+        CreateTeamRequest, SetTeamRepositoryRequest, Team, TeamDetails, TeamMember, TeamRepository,
+        UpdateTeamRequest,
+    },
+    AppState,
+};
+
+const TEAM_SELECT: &str = "SELECT t.id, t.organization_id, t.name, t.description, t.created_by, t.created_at, t.updated_at,
+            (SELECT COUNT(*) FROM team_members tm WHERE tm.team_id = t.id) AS member_count,
+            (SELECT COUNT(*) FROM team_repositories tr WHERE tr.team_id = t.id) AS repository_count
+     FROM teams t";
+
+const TEAM_MEMBER_SELECT: &str = "SELECT u.id AS user_id, u.username, u.email, tm.created_at AS added_at
+     FROM team_members tm
+     JOIN users u ON tm.user_id = u.id";
+
+const TEAM_REPOSITORY_SELECT: &str = "SELECT r.id AS repository_id, r.name AS repository_name, tr.permission, tr.updated_at
+     FROM team_repositories tr
+     JOIN repositories r ON tr.repository_id = r.id";
+
+/// List the teams of an organization
+#[utoipa::path(
+    get,
+    path = "/api/v1/organizations/{id}/teams",
+    tag = "organizations",
+    params(
+        ("id" = i64, Path, description = "Organization ID")
+    ),
+    responses(
+        (status = 200, description = "Teams retrieved successfully", body = Vec<Team>),
+        (status = 400, description = "Not a member of the organization"),
+        (status = 401, description = "Unauthorized")
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn list_teams(
+    State(state): State<AppState>,
+    auth: Option<TypedHeader<Authorization<Bearer>>>,
+    Path(id): Path<i64>,
+) -> impl IntoResponse {
+    let user_id = match authenticate(&state, auth).await {
+        Ok(id) => id,
+        Err(response) => return response,
+    };
+
+    match list_teams_internal(&state.db_pool, id, user_id).await {
+        Ok(teams) => (StatusCode::OK, Json(serde_json::to_value(&teams).unwrap_or_default())),
+        Err(e) => bad_request("Failed to list teams", e),
+    }
+}
+
+/// Create a team in an organization
+#[utoipa::path(
+    post,
+    path = "/api/v1/organizations/{id}/teams",
+    tag = "organizations",
+    params(
+        ("id" = i64, Path, description = "Organization ID")
+    ),
+    request_body = CreateTeamRequest,
+    responses(
+        (status = 201, description = "Team created successfully", body = Team),
+        (status = 400, description = "Validation failed, duplicate name, or insufficient permissions"),
+        (status = 401, description = "Unauthorized")
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn create_team(
+    State(state): State<AppState>,
+    auth: Option<TypedHeader<Authorization<Bearer>>>,
+    Path(id): Path<i64>,
+    Json(req): Json<CreateTeamRequest>,
+) -> impl IntoResponse {
+    let user_id = match authenticate(&state, auth).await {
+        Ok(id) => id,
+        Err(response) => return response,
+    };
+
+    match create_team_internal(&state.db_pool, id, user_id, &req).await {
+        Ok(team) => (StatusCode::CREATED, Json(serde_json::to_value(&team).unwrap_or_default())),
+        Err(e) => bad_request("Failed to create team", e),
+    }
+}
+
+/// Get a team with its members and repository grants
+#[utoipa::path(
+    get,
+    path = "/api/v1/organizations/{id}/teams/{team_id}",
+    tag = "organizations",
+    params(
+        ("id" = i64, Path, description = "Organization ID"),
+        ("team_id" = i64, Path, description = "Team ID")
+    ),
+    responses(
+        (status = 200, description = "Team retrieved successfully", body = TeamDetails),
+        (status = 400, description = "Team not found or not a member of the organization"),
+        (status = 401, description = "Unauthorized")
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn get_team(
+    State(state): State<AppState>,
+    auth: Option<TypedHeader<Authorization<Bearer>>>,
+    Path((id, team_id)): Path<(i64, i64)>,
+) -> impl IntoResponse {
+    let user_id = match authenticate(&state, auth).await {
+        Ok(id) => id,
+        Err(response) => return response,
+    };
+
+    match get_team_internal(&state.db_pool, id, team_id, user_id).await {
+        Ok(details) => (StatusCode::OK, Json(serde_json::to_value(&details).unwrap_or_default())),
+        Err(e) => bad_request("Failed to get team", e),
+    }
+}
+
+/// Rename a team or change its description
+#[utoipa::path(
+    put,
+    path = "/api/v1/organizations/{id}/teams/{team_id}",
+    tag = "organizations",
+    params(
+        ("id" = i64, Path, description = "Organization ID"),
+        ("team_id" = i64, Path, description = "Team ID")
+    ),
+    request_body = UpdateTeamRequest,
+    responses(
+        (status = 200, description = "Team updated successfully", body = Team),
+        (status = 400, description = "Validation failed, team not found, or insufficient permissions"),
+        (status = 401, description = "Unauthorized")
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn update_team(
+    State(state): State<AppState>,
+    auth: Option<TypedHeader<Authorization<Bearer>>>,
+    Path((id, team_id)): Path<(i64, i64)>,
+    Json(req): Json<UpdateTeamRequest>,
+) -> impl IntoResponse {
+    let user_id = match authenticate(&state, auth).await {
+        Ok(id) => id,
+        Err(response) => return response,
+    };
+
+    match update_team_internal(&state.db_pool, id, team_id, user_id, &req).await {
+        Ok(team) => (StatusCode::OK, Json(serde_json::to_value(&team).unwrap_or_default())),
+        Err(e) => bad_request("Failed to update team", e),
+    }
+}
+
+/// Delete a team; its members keep their organization membership
+#[utoipa::path(
+    delete,
+    path = "/api/v1/organizations/{id}/teams/{team_id}",
+    tag = "organizations",
+    params(
+        ("id" = i64, Path, description = "Organization ID"),
+        ("team_id" = i64, Path, description = "Team ID")
+    ),
+    responses(
+        (status = 204, description = "Team deleted successfully"),
+        (status = 400, description = "Team not found or insufficient permissions"),
+        (status = 401, description = "Unauthorized")
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn delete_team(
+    State(state): State<AppState>,
+    auth: Option<TypedHeader<Authorization<Bearer>>>,
+    Path((id, team_id)): Path<(i64, i64)>,
+) -> impl IntoResponse {
+    let user_id = match authenticate(&state, auth).await {
+        Ok(id) => id,
+        Err(response) => return response,
+    };
+
+    match delete_team_internal(&state.db_pool, id, team_id, user_id).await {
+        Ok(_) => (StatusCode::NO_CONTENT, Json(serde_json::json!({}))),
+        Err(e) => bad_request("Failed to delete team", e),
+    }
+}
+
+/// Add an organization member to a team
+#[utoipa::path(
+    put,
+    path = "/api/v1/organizations/{id}/teams/{team_id}/members/{member_id}",
+    tag = "organizations",
+    params(
+        ("id" = i64, Path, description = "Organization ID"),
+        ("team_id" = i64, Path, description = "Team ID"),
+        ("member_id" = i64, Path, description = "User ID of an organization member")
+    ),
+    responses(
+        (status = 200, description = "Member added to team", body = TeamMember),
+        (status = 400, description = "Team not found, user is not an organization member, or insufficient permissions"),
+        (status = 401, description = "Unauthorized")
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn add_team_member(
+    State(state): State<AppState>,
+    auth: Option<TypedHeader<Authorization<Bearer>>>,
+    Path((id, team_id, member_id)): Path<(i64, i64, i64)>,
+) -> impl IntoResponse {
+    let user_id = match authenticate(&state, auth).await {
+        Ok(id) => id,
+        Err(response) => return response,
+    };
+
+    match add_team_member_internal(&state.db_pool, id, team_id, member_id, user_id).await {
+        Ok(member) => {
+            state.log_stream.publish(
+                LogEvent::audit("organization.team.member.add", Some(user_id), None)
+                    .with_organization(id)
+                    .with_detail(format!("team={} member={}", team_id, member_id)),
+            );
+            (StatusCode::OK, Json(serde_json::to_value(&member).unwrap_or_default()))
+        }
+        Err(e) => bad_request("Failed to add team member", e),
+    }
+}
+
+/// Remove a member from a team
+#[utoipa::path(
+    delete,
+    path = "/api/v1/organizations/{id}/teams/{team_id}/members/{member_id}",
+    tag = "organizations",
+    params(
+        ("id" = i64, Path, description = "Organization ID"),
+        ("team_id" = i64, Path, description = "Team ID"),
+        ("member_id" = i64, Path, description = "User ID")
+    ),
+    responses(
+        (status = 204, description = "Member removed from team"),
+        (status = 400, description = "Not a team member or insufficient permissions"),
+        (status = 401, description = "Unauthorized")
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn remove_team_member(
+    State(state): State<AppState>,
+    auth: Option<TypedHeader<Authorization<Bearer>>>,
+    Path((id, team_id, member_id)): Path<(i64, i64, i64)>,
+) -> impl IntoResponse {
+    let user_id = match authenticate(&state, auth).await {
+        Ok(id) => id,
+        Err(response) => return response,
+    };
+
+    match remove_team_member_internal(&state.db_pool, id, team_id, member_id, user_id).await {
+        Ok(_) => {
+            state.log_stream.publish(
+                LogEvent::audit("organization.team.member.remove", Some(user_id), None)
+                    .with_organization(id)
+                    .with_detail(format!("team={} member={}", team_id, member_id)),
+            );
+            (StatusCode::NO_CONTENT, Json(serde_json::json!({})))
+        }
+        Err(e) => bad_request("Failed to remove team member", e),
+    }
+}
+
+/// Grant a team access to one of the organization's repositories
+#[utoipa::path(
+    put,
+    path = "/api/v1/organizations/{id}/teams/{team_id}/repositories/{repo_name}",
+    tag = "organizations",
+    params(
+        ("id" = i64, Path, description = "Organization ID"),
+        ("team_id" = i64, Path, description = "Team ID"),
+        ("repo_name" = String, Path, description = "Repository name within the organization")
+    ),
+    request_body = SetTeamRepositoryRequest,
+    responses(
+        (status = 200, description = "Repository grant set", body = TeamRepository),
+        (status = 400, description = "Team or repository not found, or insufficient permissions"),
+        (status = 401, description = "Unauthorized")
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn set_team_repository(
+    State(state): State<AppState>,
+    auth: Option<TypedHeader<Authorization<Bearer>>>,
+    Path((id, team_id, repo_name)): Path<(i64, i64, String)>,
+    Json(req): Json<SetTeamRepositoryRequest>,
+) -> impl IntoResponse {
+    let user_id = match authenticate(&state, auth).await {
+        Ok(id) => id,
+        Err(response) => return response,
+    };
+
+    match set_team_repository_internal(&state.db_pool, id, team_id, &repo_name, user_id, &req).await {
+        Ok(grant) => {
+            state.log_stream.publish(
+                LogEvent::audit("repository.team.set", Some(user_id), repository_path(&state.db_pool, id, &repo_name).await)
+                    .with_organization(id)
+                    .with_detail(format!("team={} {}", team_id, grant.permission)),
+            );
+            (StatusCode::OK, Json(serde_json::to_value(&grant).unwrap_or_default()))
+        }
+        Err(e) => bad_request("Failed to set team repository", e),
+    }
+}
+
+/// Revoke a team's access to a repository
+#[utoipa::path(
+    delete,
+    path = "/api/v1/organizations/{id}/teams/{team_id}/repositories/{repo_name}",
+    tag = "organizations",
+    params(
+        ("id" = i64, Path, description = "Organization ID"),
+        ("team_id" = i64, Path, description = "Team ID"),
+        ("repo_name" = String, Path, description = "Repository name within the organization")
+    ),
+    responses(
+        (status = 204, description = "Repository grant removed"),
+        (status = 400, description = "Grant not found or insufficient permissions"),
+        (status = 401, description = "Unauthorized")
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn remove_team_repository(
+    State(state): State<AppState>,
+    auth: Option<TypedHeader<Authorization<Bearer>>>,
+    Path((id, team_id, repo_name)): Path<(i64, i64, String)>,
+) -> impl IntoResponse {
+    let user_id = match authenticate(&state, auth).await {
+        Ok(id) => id,
+        Err(response) => return response,
+    };
+
+    match remove_team_repository_internal(&state.db_pool, id, team_id, &repo_name, user_id).await {
+        Ok(_) => {
+            state.log_stream.publish(
+                LogEvent::audit("repository.team.remove", Some(user_id), repository_path(&state.db_pool, id, &repo_name).await)
+                    .with_organization(id)
+                    .with_detail(format!("team={}", team_id)),
+            );
+            (StatusCode::NO_CONTENT, Json(serde_json::json!({})))
+        }
+        Err(e) => bad_request("Failed to remove team repository", e),
+    }
+}
+
+/// `namespace/repository` of an organization's repository, for audit events
+async fn repository_path(pool: &PgPool, organization_id: i64, repo_name: &str) -> Option<String> {
+    match sqlx::query_scalar::<_, String>("SELECT name FROM organizations WHERE id = $1")
+        .bind(organization_id)
+        .fetch_optional(pool)
+        .await
+    {
+        Ok(namespace) => namespace.map(|namespace| format!("{}/{}", namespace, repo_name)),
+        Err(e) => {
+            tracing::warn!("Failed to look up organization {}: {}", organization_id, e);
+            None
+        }
+    }
+}
+
+async fn authenticate(
+    state: &AppState,
+    auth: Option<TypedHeader<Authorization<Bearer>>>,
+) -> Result<i64, (StatusCode, Json<serde_json::Value>)> {
+    extract_user_id(auth, state.config.auth.jwt_secret.expose_secret().as_bytes(), &state.db_pool)
+        .await
+        .map_err(|status| {
+            (
+                status,
+                Json(serde_json::json!({
+                    "error": "Unauthorized"
+                })),
+            )
+        })
+}
+
+fn bad_request(context: &str, e: anyhow::Error) -> (StatusCode, Json<serde_json::Value>) {
+    tracing::error!("{}: {}", context, e);
+    (
+        StatusCode::BAD_REQUEST,
+        Json(serde_json::json!({
+            "error": e.to_string()
+        })),
+    )
+}
+
+// Internal database functions
+async fn ensure_org_member(pool: &PgPool, org_id: i64, user_id: i64) -> Result<()> {
+    if get_user_role_in_org(pool, org_id, user_id).await?.is_none() {
+        bail!("Not a member of this organization");
+    }
+    Ok(())
+}
+
+/// Team management follows member management: owners and maintainers
+async fn ensure_can_manage_teams(pool: &PgPool, org_id: i64, user_id: i64) -> Result<()> {
+    let user_role = get_user_role_in_org(pool, org_id, user_id).await?;
+    if !user_role.map(|r| r.can_manage_members()).unwrap_or(false) {
+        bail!("Insufficient permissions to manage teams");
+    }
+    Ok(())
+}
+
+async fn fetch_team(pool: &PgPool, org_id: i64, team_id: i64) -> Result<Team> {
+    let query = format!("{} WHERE t.id = $1 AND t.organization_id = $2", TEAM_SELECT);
+    let team = sqlx::query_as::<_, Team>(&query)
+        .bind(team_id)
+        .bind(org_id)
+        .fetch_optional(pool)
+        .await?;
+
+    match team {
+        Some(team) => Ok(team),
+        None => bail!("Team not found"),
+    }
+}
+
+fn validate_team_name(name: &str) -> Result<&str> {
+    let name = name.trim();
+    if name.is_empty() || name.len() > 100 {
+        bail!("Team name must be between 1 and 100 characters");
+    }
+    Ok(name)
+}
+
+async fn list_teams_internal(pool: &PgPool, org_id: i64, user_id: i64) -> Result<Vec<Team>> {
+    ensure_org_member(pool, org_id, user_id).await?;
+
+    let query = format!("{} WHERE t.organization_id = $1 ORDER BY t.name", TEAM_SELECT);
+    let teams = sqlx::query_as::<_, Team>(&query)
+        .bind(org_id)
+        .fetch_all(pool)
+        .await?;
+    Ok(teams)
+}
+
+async fn create_team_internal(pool: &PgPool, org_id: i64, user_id: i64, req: &CreateTeamRequest) -> Result<Team> {
+    ensure_can_manage_teams(pool, org_id, user_id).await?;
+    let name = validate_team_name(&req.name)?;
+
+    let existing = sqlx::query("SELECT id FROM teams WHERE organization_id = $1 AND name = $2")
+        .bind(org_id)
+        .bind(name)
+        .fetch_optional(pool)
+        .await?;
+    if existing.is_some() {
+        bail!("Team with name '{}' already exists", name);
+    }
+
+    let team_id = sqlx::query_scalar::<_, i64>(
+        "INSERT INTO teams (organization_id, name, description, created_by)
+         VALUES ($1, $2, $3, $4)
+         RETURNING id",
+    )
+    .bind(org_id)
+    .bind(name)
+    .bind(&req.description)
+    .bind(user_id)
+    .fetch_one(pool)
+    .await?;
+
+    fetch_team(pool, org_id, team_id).await
+}
+
+async fn get_team_internal(pool: &PgPool, org_id: i64, team_id: i64, user_id: i64) -> Result<TeamDetails> {
+    ensure_org_member(pool, org_id, user_id).await?;
+    let team = fetch_team(pool, org_id, team_id).await?;
+
+    let query = format!("{} WHERE tm.team_id = $1 ORDER BY u.username", TEAM_MEMBER_SELECT);
+    let members = sqlx::query_as::<_, TeamMember>(&query)
+        .bind(team_id)
+        .fetch_all(pool)
+        .await?;
+
+    let query = format!("{} WHERE tr.team_id = $1 ORDER BY r.name", TEAM_REPOSITORY_SELECT);
+    let repositories = sqlx::query_as::<_, TeamRepository>(&query)
+        .bind(team_id)
+        .fetch_all(pool)
+        .await?;
+
+    Ok(TeamDetails { team, members, repositories })
+}
+
+async fn update_team_internal(
+    pool: &PgPool,
+    org_id: i64,
+    team_id: i64,
+    user_id: i64,
+    req: &UpdateTeamRequest,
+) -> Result<Team> {
+    ensure_can_manage_teams(pool, org_id, user_id).await?;
+    let team = fetch_team(pool, org_id, team_id).await?;
+
+    let name = match &req.name {
+        Some(name) => validate_team_name(name)?,
+        None => team.name.as_str(),
+    };
+    if name != team.name {
+        let existing = sqlx::query("SELECT id FROM teams WHERE organization_id = $1 AND name = $2")
+            .bind(org_id)
+            .bind(name)
+            .fetch_optional(pool)
+            .await?;
+        if existing.is_some() {
+            bail!("Team with name '{}' already exists", name);
+        }
+    }
+
+    sqlx::query(
+        "UPDATE teams
+         SET name = $2, description = COALESCE($3, description), updated_at = CURRENT_TIMESTAMP
+         WHERE id = $1",
+    )
+    .bind(team_id)
+    .bind(name)
+    .bind(&req.description)
+    .execute(pool)
+    .await?;
+
+    fetch_team(pool, org_id, team_id).await
+}
+
+async fn delete_team_internal(pool: &PgPool, org_id: i64, team_id: i64, user_id: i64) -> Result<()> {
+    ensure_can_manage_teams(pool, org_id, user_id).await?;
+
+    let result = sqlx::query("DELETE FROM teams WHERE id = $1 AND organization_id = $2")
+        .bind(team_id)
+        .bind(org_id)
+        .execute(pool)
+        .await?;
+
+    if result.rows_affected() == 0 {
+        bail!("Team not found");
+    }
+    Ok(())
+}
+
+async fn add_team_member_internal(
+    pool: &PgPool,
+    org_id: i64,
+    team_id: i64,
+    member_user_id: i64,
+    user_id: i64,
+) -> Result<TeamMember> {
+    ensure_can_manage_teams(pool, org_id, user_id).await?;
+    fetch_team(pool, org_id, team_id).await?;
+
+    // Teams only regroup existing members; outsiders get access through collaborators
+    if get_user_role_in_org(pool, org_id, member_user_id).await?.is_none() {
+        bail!("User must be a member of the organization to join one of its teams");
+    }
+
+    sqlx::query(
+        "INSERT INTO team_members (team_id, user_id)
+         VALUES ($1, $2)
+         ON CONFLICT (team_id, user_id) DO NOTHING",
+    )
+    .bind(team_id)
+    .bind(member_user_id)
+    .execute(pool)
+    .await?;
+
+    let query = format!("{} WHERE tm.team_id = $1 AND tm.user_id = $2", TEAM_MEMBER_SELECT);
+    let member = sqlx::query_as::<_, TeamMember>(&query)
+        .bind(team_id)
+        .bind(member_user_id)
+        .fetch_one(pool)
+        .await?;
+    Ok(member)
+}
+
+async fn remove_team_member_internal(
+    pool: &PgPool,
+    org_id: i64,
+    team_id: i64,
+    member_user_id: i64,
+    user_id: i64,
+) -> Result<()> {
+    // Members may always leave a team themselves
+    if member_user_id != user_id {
+        ensure_can_manage_teams(pool, org_id, user_id).await?;
+    }
+    fetch_team(pool, org_id, team_id).await?;
+
+    let result = sqlx::query("DELETE FROM team_members WHERE team_id = $1 AND user_id = $2")
+        .bind(team_id)
+        .bind(member_user_id)
+        .execute(pool)
+        .await?;
+
+    if result.rows_affected() == 0 {
+        bail!("User is not a member of this team");
+    }
+    Ok(())
+}
+
+async fn set_team_repository_internal(
+    pool: &PgPool,
+    org_id: i64,
+    team_id: i64,
+    repo_name: &str,
+    user_id: i64,
+    req: &SetTeamRepositoryRequest,
+) -> Result<TeamRepository> {
+    ensure_can_manage_teams(pool, org_id, user_id).await?;
+    fetch_team(pool, org_id, team_id).await?;
+
+    let repository_id = sqlx::query_scalar::<_, i64>(
+        "SELECT id FROM repositories WHERE organization_id = $1 AND name = $2",
+    )
+    .bind(org_id)
+    .bind(repo_name)
+    .fetch_optional(pool)
+    .await?;
+
+    let Some(repository_id) = repository_id else {
+        bail!("Repository '{}' not found in this organization", repo_name);
+    };
+
+    sqlx::query(
+        "INSERT INTO team_repositories (team_id, repository_id, permission)
+         VALUES ($1, $2, $3)
+         ON CONFLICT (team_id, repository_id) DO UPDATE
+         SET permission = EXCLUDED.permission, updated_at = CURRENT_TIMESTAMP",
+    )
+    .bind(team_id)
+    .bind(repository_id)
+    .bind(req.permission.to_string())
+    .execute(pool)
+    .await?;
+
+    let query = format!("{} WHERE tr.team_id = $1 AND tr.repository_id = $2", TEAM_REPOSITORY_SELECT);
+    let grant = sqlx::query_as::<_, TeamRepository>(&query)
+        .bind(team_id)
+        .bind(repository_id)
+        .fetch_one(pool)
+        .await?;
+    Ok(grant)
+}
+
+async fn remove_team_repository_internal(
+    pool: &PgPool,
+    org_id: i64,
+    team_id: i64,
+    repo_name: &str,
+    user_id: i64,
+) -> Result<()> {
+    ensure_can_manage_teams(pool, org_id, user_id).await?;
+    fetch_team(pool, org_id, team_id).await?;
+
+    let result = sqlx::query(
+        "DELETE FROM team_repositories tr
+         USING repositories r
+         WHERE tr.repository_id = r.id AND tr.team_id = $1 AND r.organization_id = $2 AND r.name = $3",
+    )
+    .bind(team_id)
+    .bind(org_id)
+    .bind(repo_name)
+    .execute(pool)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        bail!("Team has no grant on repository '{}'", repo_name);
+    }
+    Ok(())
+}
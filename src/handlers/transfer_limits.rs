@@ -0,0 +1,284 @@
+// src/handlers/transfer_limits.rs - Concurrency limits on blob transfers and request timeouts
+//
+// Blob uploads and downloads hold storage connections, buffers and bandwidth for as long as they
+// run, so a burst of CI pushes or a crowd pulling the same image could exhaust the server while
+// each client stays under its rate limit. Each kind of transfer has a global cap and a per-user
+// cap on how many may run at once; a request over the user's cap gets 429, one arriving while the
+// server is saturated gets 503, both with `Retry-After`. A download counts until its body has
+// been sent, not just until the handler returns.
+//
+// Other requests are cut off with 503 once they take longer than `REQUEST_TIMEOUT_SECONDS`, and
+// upload requests once they take longer than `UPLOAD_TIMEOUT_SECONDS`.
+use std::{
+    collections::HashMap,
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use axum::{
+    body::Body,
+    extract::{ConnectInfo, Request, State},
+    http::{HeaderValue, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use futures::StreamExt;
+use serde_json::json;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+use crate::{
+    config::settings::ConcurrencySettings,
+    handlers::{ip_access::client_ip, rate_limit::identify_principal},
+    AppState,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Transfer {
+    Upload,
+    Download,
+}
+
+impl Transfer {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Transfer::Upload => "upload",
+            Transfer::Download => "download",
+        }
+    }
+
+    /// Transfer a request performs, or `None` for requests that move no blob data
+    pub fn classify(method: &Method, path: &str) -> Option<Self> {
+        if !path.starts_with("/v2/") {
+            return None;
+        }
+        if path.contains("/blobs/uploads") {
+            return matches!(*method, Method::POST | Method::PATCH | Method::PUT).then_some(Transfer::Upload);
+        }
+        (*method == Method::GET && path.contains("/blobs/sha256:")).then_some(Transfer::Download)
+    }
+}
+
+/// Which limit turned a transfer away
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Saturated {
+    /// The server already runs as many transfers of this kind as allowed
+    Global,
+    /// The caller already runs as many transfers of this kind as allowed
+    User,
+}
+
+/// Transfers in progress, globally and per principal
+pub struct TransferLimits {
+    uploads: Arc<Semaphore>,
+    downloads: Arc<Semaphore>,
+    max_uploads_per_user: usize,
+    max_downloads_per_user: usize,
+    active: Arc<Mutex<HashMap<(Transfer, String), usize>>>,
+}
+
+/// A running transfer; its slots are released when dropped
+pub struct TransferPermit {
+    _global: OwnedSemaphorePermit,
+    active: Arc<Mutex<HashMap<(Transfer, String), usize>>>,
+    key: (Transfer, String),
+}
+
+impl Drop for TransferPermit {
+    fn drop(&mut self) {
+        let mut active = self.active.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(count) = active.get_mut(&self.key) {
+            *count -= 1;
+            if *count == 0 {
+                active.remove(&self.key);
+            }
+        }
+    }
+}
+
+impl TransferLimits {
+    pub fn new(settings: &ConcurrencySettings) -> Self {
+        Self {
+            uploads: Arc::new(Semaphore::new(settings.max_uploads)),
+            downloads: Arc::new(Semaphore::new(settings.max_downloads)),
+            max_uploads_per_user: settings.max_uploads_per_user,
+            max_downloads_per_user: settings.max_downloads_per_user,
+            active: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Take a slot for a transfer by `principal`, without waiting
+    pub fn try_acquire(&self, transfer: Transfer, principal: &str) -> Result<TransferPermit, Saturated> {
+        let (semaphore, per_user) = match transfer {
+            Transfer::Upload => (&self.uploads, self.max_uploads_per_user),
+            Transfer::Download => (&self.downloads, self.max_downloads_per_user),
+        };
+        let key = (transfer, principal.to_string());
+
+        let mut active = self.active.lock().unwrap_or_else(|e| e.into_inner());
+        if active.get(&key).copied().unwrap_or(0) >= per_user {
+            return Err(Saturated::User);
+        }
+        let global = semaphore.clone().try_acquire_owned().map_err(|_| Saturated::Global)?;
+        *active.entry(key.clone()).or_insert(0) += 1;
+
+        Ok(TransferPermit { _global: global, active: self.active.clone(), key })
+    }
+
+    /// Transfers of a kind running right now
+    pub fn in_progress(&self, transfer: Transfer) -> usize {
+        self.active
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .filter(|((kind, _), _)| *kind == transfer)
+            .map(|(_, count)| count)
+            .sum()
+    }
+}
+
+/// Middleware holding blob transfers to the concurrency limits and every request to its timeout
+pub async fn enforce_transfer_limits(
+    State(state): State<AppState>,
+    connect_info: Option<ConnectInfo<SocketAddr>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let settings = &state.config.concurrency;
+    let transfer = Transfer::classify(request.method(), request.uri().path());
+
+    let permit = match transfer {
+        Some(transfer) => {
+            let peer = connect_info.map(|ConnectInfo(addr)| addr.ip());
+            let ip = client_ip(peer, request.headers(), &state.config.ip_access.trusted_proxies)
+                .map(|ip| ip.to_string())
+                .unwrap_or_else(|| "unknown".to_string());
+            let principal = identify_principal(request.headers(), &state, &ip).await;
+            match state.transfer_limits.try_acquire(transfer, &principal) {
+                Ok(permit) => Some(permit),
+                Err(saturated) => {
+                    tracing::warn!("Turned away {} by {}: {:?} limit reached", transfer.as_str(), principal, saturated);
+                    return saturated_response(transfer, saturated, settings);
+                }
+            }
+        }
+        None => None,
+    };
+
+    let timeout = match transfer {
+        Some(Transfer::Upload) => settings.upload_timeout_seconds,
+        _ => settings.request_timeout_seconds,
+    };
+    let response = if timeout == 0 {
+        next.run(request).await
+    } else {
+        match tokio::time::timeout(Duration::from_secs(timeout), next.run(request)).await {
+            Ok(response) => response,
+            Err(_) => {
+                tracing::warn!("Request timed out after {} seconds", timeout);
+                return unavailable(
+                    format!("Request did not complete within {} seconds", timeout),
+                    settings.retry_after_seconds,
+                );
+            }
+        }
+    };
+
+    match (transfer, permit) {
+        // The download runs until its body has been sent
+        (Some(Transfer::Download), Some(permit)) => {
+            let (parts, body) = response.into_parts();
+            let body = body.into_data_stream().map(move |frame| {
+                let _ = &permit;
+                frame
+            });
+            Response::from_parts(parts, Body::from_stream(body))
+        }
+        _ => response,
+    }
+}
+
+fn saturated_response(transfer: Transfer, saturated: Saturated, settings: &ConcurrencySettings) -> Response {
+    match saturated {
+        Saturated::User => {
+            let limit = match transfer {
+                Transfer::Upload => settings.max_uploads_per_user,
+                Transfer::Download => settings.max_downloads_per_user,
+            };
+            let message = format!("At most {} concurrent blob {}s are allowed per user", limit, transfer.as_str());
+            let body = json!({
+                "errors": [{
+                    "code": "TOOMANYREQUESTS",
+                    "message": message,
+                    "detail": { "limit": limit }
+                }]
+            });
+            let mut response = (StatusCode::TOO_MANY_REQUESTS, Json(body)).into_response();
+            response.headers_mut().insert("Retry-After", HeaderValue::from(settings.retry_after_seconds));
+            response
+        }
+        Saturated::Global => unavailable(
+            format!("Too many blob {}s in progress; retry later", transfer.as_str()),
+            settings.retry_after_seconds,
+        ),
+    }
+}
+
+fn unavailable(message: String, retry_after: u64) -> Response {
+    let body = json!({
+        "errors": [{
+            "code": "UNAVAILABLE",
+            "message": message,
+            "detail": {}
+        }]
+    });
+    let mut response = (StatusCode::SERVICE_UNAVAILABLE, Json(body)).into_response();
+    response.headers_mut().insert("Retry-After", HeaderValue::from(retry_after));
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings() -> ConcurrencySettings {
+        ConcurrencySettings {
+            max_uploads: 3,
+            max_downloads: 3,
+            max_uploads_per_user: 2,
+            max_downloads_per_user: 2,
+            retry_after_seconds: 5,
+            request_timeout_seconds: 60,
+            upload_timeout_seconds: 3600,
+        }
+    }
+
+    #[test]
+    fn classifies_blob_transfers() {
+        assert_eq!(Transfer::classify(&Method::PATCH, "/v2/acme/web/blobs/uploads/123"), Some(Transfer::Upload));
+        assert_eq!(Transfer::classify(&Method::POST, "/v2/acme/web/blobs/uploads/"), Some(Transfer::Upload));
+        assert_eq!(Transfer::classify(&Method::GET, "/v2/acme/web/blobs/uploads/123"), None);
+        assert_eq!(Transfer::classify(&Method::GET, "/v2/acme/web/blobs/sha256:abc"), Some(Transfer::Download));
+        assert_eq!(Transfer::classify(&Method::HEAD, "/v2/acme/web/blobs/sha256:abc"), None);
+        assert_eq!(Transfer::classify(&Method::GET, "/v2/acme/web/manifests/latest"), None);
+        assert_eq!(Transfer::classify(&Method::GET, "/api/v1/repos"), None);
+    }
+
+    #[test]
+    fn users_and_the_server_are_capped_separately() {
+        let limits = TransferLimits::new(&settings());
+        let first = limits.try_acquire(Transfer::Upload, "user:1").unwrap();
+        let _second = limits.try_acquire(Transfer::Upload, "user:1").unwrap();
+        assert_eq!(limits.try_acquire(Transfer::Upload, "user:1").err(), Some(Saturated::User));
+
+        let _third = limits.try_acquire(Transfer::Upload, "user:2").unwrap();
+        assert_eq!(limits.try_acquire(Transfer::Upload, "user:3").err(), Some(Saturated::Global));
+        // Downloads are counted on their own
+        assert!(limits.try_acquire(Transfer::Download, "user:1").is_ok());
+
+        drop(first);
+        assert_eq!(limits.in_progress(Transfer::Upload), 2);
+        assert!(limits.try_acquire(Transfer::Upload, "user:1").is_ok());
+    }
+}
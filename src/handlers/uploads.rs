@@ -44,7 +44,7 @@ pub async fn get_user_uploads(
             }))
         }
         Err(e) => {
-            eprintln!("Failed to get user uploads: {}", e);
+            tracing::error!("Failed to get user uploads: {}", e);
             Err(StatusCode::INTERNAL_SERVER_ERROR)
         }
     }
@@ -64,7 +64,7 @@ pub async fn get_repository_uploads(
     ).await {
         Ok(uploads) => Ok(Json(uploads)),
         Err(e) => {
-            eprintln!("Failed to get repository uploads: {}", e);
+            tracing::error!("Failed to get repository uploads: {}", e);
             Err(StatusCode::INTERNAL_SERVER_ERROR)
         }
     }
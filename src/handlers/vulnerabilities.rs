@@ -0,0 +1,111 @@
+// src/handlers/vulnerabilities.rs - Vulnerability scan results of tagged images
+//
+// Scans are run by `crate::scanning` as images are pushed.
+use axum::{
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use axum_extra::headers::{authorization::Bearer, Authorization};
+use axum_extra::TypedHeader;
+use secrecy::ExposeSecret;
+use serde_json::json;
+
+use crate::{
+    auth::extract_user_id_dual,
+    handlers::docker_auth::check_repository_permission,
+    handlers::tag_cleanup::{find_repository, internal_error, repository_not_found},
+    models::api_key::ApiKeyScope,
+    models::vulnerability::{Severity, TagVulnerabilities, VulnerabilityQuery},
+    AppState,
+};
+
+/// Vulnerabilities of the image a tag points to
+///
+/// The severity summary and findings of the latest scan of the tag's manifest. `scan` is absent
+/// when the manifest was never scanned, and findings are listed only once a scan completed.
+/// Requires pull access.
+#[utoipa::path(
+    get,
+    path = "/api/v1/repos/{namespace}/{repo_name}/tags/{tag}/vulnerabilities",
+    params(
+        ("namespace" = String, Path, description = "Organization namespace"),
+        ("repo_name" = String, Path, description = "Repository name"),
+        ("tag" = String, Path, description = "Tag name"),
+        VulnerabilityQuery
+    ),
+    responses(
+        (status = 200, description = "Scan results", body = TagVulnerabilities),
+        (status = 400, description = "Invalid min_severity"),
+        (status = 401, description = "Authentication required"),
+        (status = 404, description = "Repository or tag not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "repositories",
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn get_tag_vulnerabilities(
+    Path((namespace, repo_name, tag)): Path<(String, String, String)>,
+    State(state): State<AppState>,
+    Query(query): Query<VulnerabilityQuery>,
+    headers: HeaderMap,
+    auth: Option<TypedHeader<Authorization<Bearer>>>,
+) -> Response {
+    let min_severity = match query.min_severity.as_deref().map(str::parse::<Severity>).transpose() {
+        Ok(min_severity) => min_severity.unwrap_or(Severity::Unknown),
+        Err(e) => return (StatusCode::BAD_REQUEST, Json(json!({"error": e}))).into_response(),
+    };
+    let secret = state.config.auth.jwt_secret.expose_secret().as_bytes();
+    let user_id = match extract_user_id_dual(auth, &headers, ApiKeyScope::Read, secret, &state.db_pool, state.cache.as_ref()).await {
+        Ok(id) => id,
+        Err(status) => return (status, Json(json!({"error": "Authentication required"}))).into_response(),
+    };
+    match check_repository_permission(&user_id.to_string(), &namespace, &repo_name, "pull", &state).await {
+        Ok(true) => {}
+        Ok(false) => return repository_not_found(&namespace, &repo_name),
+        Err(e) => return internal_error(e),
+    }
+    let repository_id = match find_repository(&state, &namespace, &repo_name).await {
+        Ok(Some((repository_id, _))) => repository_id,
+        Ok(None) => return repository_not_found(&namespace, &repo_name),
+        Err(e) => return internal_error(e),
+    };
+
+    let digest = sqlx::query_scalar::<_, String>(
+        "SELECT m.digest FROM tags t JOIN manifests m ON m.id = t.manifest_id WHERE t.repository_id = $1 AND t.name = $2",
+    )
+    .bind(repository_id)
+    .bind(&tag)
+    .fetch_optional(&state.db_pool)
+    .await;
+    let digest = match digest {
+        Ok(Some(digest)) => digest,
+        Ok(None) => {
+            return (StatusCode::NOT_FOUND, Json(json!({
+                "error": format!("Tag '{}' not found in '{}/{}'", tag, namespace, repo_name)
+            }))).into_response()
+        }
+        Err(e) => return internal_error(e),
+    };
+
+    let scan = match crate::scanning::load_scan(&state.db_pool, repository_id, &digest).await {
+        Ok(scan) => scan,
+        Err(e) => return internal_error(e),
+    };
+    let vulnerabilities = match crate::scanning::load_findings(&state.db_pool, repository_id, &digest, min_severity).await {
+        Ok(vulnerabilities) => vulnerabilities,
+        Err(e) => return internal_error(e),
+    };
+
+    let result = TagVulnerabilities {
+        repository: format!("{}/{}", namespace, repo_name),
+        tag,
+        digest,
+        scan,
+        vulnerabilities,
+    };
+    (StatusCode::OK, Json(json!(result))).into_response()
+}
@@ -0,0 +1,330 @@
+// src/handlers/watches.rs - Watching repositories and choosing how pushes to them are emailed
+//
+// The emails themselves are queued and sent by `crate::watches`.
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use axum_extra::headers::{authorization::Bearer, Authorization};
+use axum_extra::TypedHeader;
+use chrono::{DateTime, Utc};
+use secrecy::ExposeSecret;
+use serde_json::json;
+
+use crate::{
+    auth::extract_user_id_dual,
+    handlers::docker_auth::check_repository_permission,
+    handlers::tag_cleanup::{find_repository, internal_error, repository_not_found},
+    models::api_key::ApiKeyScope,
+    models::watch::{NotificationPreferences, PushEmail, WatchStatus, WatchedRepository},
+    AppState,
+};
+
+/// Whether the current user watches a repository
+#[utoipa::path(
+    get,
+    path = "/api/v1/repos/{namespace}/{repo_name}/watch",
+    params(
+        ("namespace" = String, Path, description = "Organization namespace"),
+        ("repo_name" = String, Path, description = "Repository name")
+    ),
+    responses(
+        (status = 200, description = "Watch status", body = WatchStatus),
+        (status = 401, description = "Authentication required"),
+        (status = 404, description = "Repository not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "repositories",
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn get_watch(
+    Path((namespace, repo_name)): Path<(String, String)>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    auth: Option<TypedHeader<Authorization<Bearer>>>,
+) -> Response {
+    let (user_id, repository_id) = match watchable_repository(&state, auth, &headers, ApiKeyScope::Read, &namespace, &repo_name).await {
+        Ok(ids) => ids,
+        Err(response) => return response,
+    };
+
+    match watched_since(&state, user_id, repository_id).await {
+        Ok(since) => watch_status(since),
+        Err(e) => internal_error(e),
+    }
+}
+
+/// Watch a repository
+///
+/// Tags pushed to it by others are emailed to the user, immediately or in a daily digest as
+/// their notification preferences say. Requires pull access.
+#[utoipa::path(
+    put,
+    path = "/api/v1/repos/{namespace}/{repo_name}/watch",
+    params(
+        ("namespace" = String, Path, description = "Organization namespace"),
+        ("repo_name" = String, Path, description = "Repository name")
+    ),
+    responses(
+        (status = 200, description = "Watching", body = WatchStatus),
+        (status = 401, description = "Authentication required"),
+        (status = 404, description = "Repository not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "repositories",
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn watch_repository(
+    Path((namespace, repo_name)): Path<(String, String)>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    auth: Option<TypedHeader<Authorization<Bearer>>>,
+) -> Response {
+    let (user_id, repository_id) = match watchable_repository(&state, auth, &headers, ApiKeyScope::Push, &namespace, &repo_name).await {
+        Ok(ids) => ids,
+        Err(response) => return response,
+    };
+
+    let result = sqlx::query(
+        "INSERT INTO repository_watches (user_id, repository_id) VALUES ($1, $2)
+         ON CONFLICT (user_id, repository_id) DO NOTHING",
+    )
+    .bind(user_id)
+    .bind(repository_id)
+    .execute(&state.db_pool)
+    .await;
+    if let Err(e) = result {
+        return internal_error(e);
+    }
+
+    match watched_since(&state, user_id, repository_id).await {
+        Ok(since) => watch_status(since),
+        Err(e) => internal_error(e),
+    }
+}
+
+/// Stop watching a repository
+#[utoipa::path(
+    delete,
+    path = "/api/v1/repos/{namespace}/{repo_name}/watch",
+    params(
+        ("namespace" = String, Path, description = "Organization namespace"),
+        ("repo_name" = String, Path, description = "Repository name")
+    ),
+    responses(
+        (status = 200, description = "Not watching", body = WatchStatus),
+        (status = 401, description = "Authentication required"),
+        (status = 404, description = "Repository not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "repositories",
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn unwatch_repository(
+    Path((namespace, repo_name)): Path<(String, String)>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    auth: Option<TypedHeader<Authorization<Bearer>>>,
+) -> Response {
+    let (user_id, repository_id) = match watchable_repository(&state, auth, &headers, ApiKeyScope::Push, &namespace, &repo_name).await {
+        Ok(ids) => ids,
+        Err(response) => return response,
+    };
+
+    // Pushes still queued for the repository are dropped with the watch
+    let result = sqlx::query(
+        "WITH unwatched AS (
+             DELETE FROM repository_watches WHERE user_id = $1 AND repository_id = $2
+         )
+         DELETE FROM watch_notifications WHERE user_id = $1 AND repository_id = $2 AND sent_at IS NULL",
+    )
+    .bind(user_id)
+    .bind(repository_id)
+    .execute(&state.db_pool)
+    .await;
+
+    match result {
+        Ok(_) => watch_status(None),
+        Err(e) => internal_error(e),
+    }
+}
+
+/// Repositories the current user watches
+#[utoipa::path(
+    get,
+    path = "/api/v1/auth/me/watches",
+    responses(
+        (status = 200, description = "Watched repositories", body = Vec<WatchedRepository>),
+        (status = 401, description = "Authentication required"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn list_watches(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    auth: Option<TypedHeader<Authorization<Bearer>>>,
+) -> Response {
+    let secret = state.config.auth.jwt_secret.expose_secret().as_bytes();
+    let user_id = match extract_user_id_dual(auth, &headers, ApiKeyScope::Read, secret, &state.db_pool, state.cache.as_ref()).await {
+        Ok(id) => id,
+        Err(status) => return (status, Json(json!({"error": "Authentication required"}))).into_response(),
+    };
+
+    let watches = sqlx::query_as::<_, WatchedRepository>(
+        "SELECT r.id AS repository_id, o.name || '/' || r.name AS repository, w.created_at AS watched_since
+         FROM repository_watches w
+         JOIN repositories r ON r.id = w.repository_id
+         JOIN organizations o ON o.id = r.organization_id
+         WHERE w.user_id = $1
+         ORDER BY o.name, r.name",
+    )
+    .bind(user_id)
+    .fetch_all(&state.db_pool)
+    .await;
+
+    match watches {
+        Ok(watches) => (StatusCode::OK, Json(json!({ "watches": watches }))).into_response(),
+        Err(e) => internal_error(e),
+    }
+}
+
+/// The current user's notification preferences
+#[utoipa::path(
+    get,
+    path = "/api/v1/auth/me/notification-preferences",
+    responses(
+        (status = 200, description = "Notification preferences", body = NotificationPreferences),
+        (status = 401, description = "Authentication required"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn get_notification_preferences(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    auth: Option<TypedHeader<Authorization<Bearer>>>,
+) -> Response {
+    let secret = state.config.auth.jwt_secret.expose_secret().as_bytes();
+    let user_id = match extract_user_id_dual(auth, &headers, ApiKeyScope::Read, secret, &state.db_pool, state.cache.as_ref()).await {
+        Ok(id) => id,
+        Err(status) => return (status, Json(json!({"error": "Authentication required"}))).into_response(),
+    };
+
+    let push_email = sqlx::query_scalar::<_, String>("SELECT push_email FROM notification_preferences WHERE user_id = $1")
+        .bind(user_id)
+        .fetch_optional(&state.db_pool)
+        .await;
+
+    match push_email {
+        Ok(push_email) => {
+            let push_email = push_email.and_then(|value| value.parse().ok()).unwrap_or(PushEmail::Immediate);
+            (StatusCode::OK, Json(json!(NotificationPreferences { push_email }))).into_response()
+        }
+        Err(e) => internal_error(e),
+    }
+}
+
+/// Change how pushes to watched repositories are emailed
+///
+/// Switching to `digest` starts the first digest period; switching to `off` drops pushes still
+/// waiting to be emailed.
+#[utoipa::path(
+    put,
+    path = "/api/v1/auth/me/notification-preferences",
+    request_body = NotificationPreferences,
+    responses(
+        (status = 200, description = "Preferences saved", body = NotificationPreferences),
+        (status = 401, description = "Authentication required"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn update_notification_preferences(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    auth: Option<TypedHeader<Authorization<Bearer>>>,
+    Json(preferences): Json<NotificationPreferences>,
+) -> Response {
+    let secret = state.config.auth.jwt_secret.expose_secret().as_bytes();
+    let user_id = match extract_user_id_dual(auth, &headers, ApiKeyScope::Push, secret, &state.db_pool, state.cache.as_ref()).await {
+        Ok(id) => id,
+        Err(status) => return (status, Json(json!({"error": "Authentication required"}))).into_response(),
+    };
+
+    let result = sqlx::query(
+        "WITH dropped AS (
+             DELETE FROM watch_notifications WHERE $2 = 'off' AND user_id = $1 AND sent_at IS NULL
+         )
+         INSERT INTO notification_preferences (user_id, push_email, last_digest_at)
+         VALUES ($1, $2, CASE WHEN $2 = 'digest' THEN CURRENT_TIMESTAMP END)
+         ON CONFLICT (user_id) DO UPDATE SET
+             push_email = EXCLUDED.push_email,
+             last_digest_at = CASE
+                 WHEN notification_preferences.push_email = EXCLUDED.push_email THEN notification_preferences.last_digest_at
+                 ELSE EXCLUDED.last_digest_at
+             END,
+             updated_at = CURRENT_TIMESTAMP",
+    )
+    .bind(user_id)
+    .bind(preferences.push_email.to_string())
+    .execute(&state.db_pool)
+    .await;
+
+    match result {
+        Ok(_) => (StatusCode::OK, Json(json!(preferences))).into_response(),
+        Err(e) => internal_error(e),
+    }
+}
+
+/// The user and repository IDs, once the user is known to be allowed to pull the repository
+async fn watchable_repository(
+    state: &AppState,
+    auth: Option<TypedHeader<Authorization<Bearer>>>,
+    headers: &HeaderMap,
+    scope: ApiKeyScope,
+    namespace: &str,
+    repo_name: &str,
+) -> Result<(i64, i64), Response> {
+    let secret = state.config.auth.jwt_secret.expose_secret().as_bytes();
+    let user_id = match extract_user_id_dual(auth, headers, scope, secret, &state.db_pool, state.cache.as_ref()).await {
+        Ok(id) => id,
+        Err(status) => return Err((status, Json(json!({"error": "Authentication required"}))).into_response()),
+    };
+    match check_repository_permission(&user_id.to_string(), namespace, repo_name, "pull", state).await {
+        Ok(true) => {}
+        Ok(false) => return Err(repository_not_found(namespace, repo_name)),
+        Err(e) => return Err(internal_error(e)),
+    }
+    match find_repository(state, namespace, repo_name).await {
+        Ok(Some((repository_id, _))) => Ok((user_id, repository_id)),
+        Ok(None) => Err(repository_not_found(namespace, repo_name)),
+        Err(e) => Err(internal_error(e)),
+    }
+}
+
+async fn watched_since(state: &AppState, user_id: i64, repository_id: i64) -> Result<Option<DateTime<Utc>>, sqlx::Error> {
+    sqlx::query_scalar::<_, DateTime<Utc>>("SELECT created_at FROM repository_watches WHERE user_id = $1 AND repository_id = $2")
+        .bind(user_id)
+        .bind(repository_id)
+        .fetch_optional(&state.db_pool)
+        .await
+}
+
+fn watch_status(watched_since: Option<DateTime<Utc>>) -> Response {
+    (StatusCode::OK, Json(json!(WatchStatus { watching: watched_since.is_some(), watched_since }))).into_response()
+}
@@ -0,0 +1,20 @@
+// src/handlers/webhooks.rs - Public webhook verification material
+use axum::{extract::State, Json};
+
+use crate::{models::webhook::WebhookSigningKeys, AppState};
+
+/// Public keys for verifying Ed25519-signed webhook deliveries
+///
+/// Unauthenticated on purpose: receivers fetch these keys to verify `X-Aerugo-Signature-Ed25519`
+/// over `"{X-Aerugo-Webhook-Timestamp}.{body}"` without holding any shared secret.
+#[utoipa::path(
+    get,
+    path = "/api/v1/webhooks/signing-keys",
+    tag = "webhooks",
+    responses(
+        (status = 200, description = "Active and recently retired signing keys", body = WebhookSigningKeys)
+    )
+)]
+pub async fn get_signing_keys(State(state): State<AppState>) -> Json<WebhookSigningKeys> {
+    Json(state.webhook_signer.published_keys())
+}
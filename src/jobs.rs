@@ -0,0 +1,474 @@
+// src/jobs.rs - Background job queue for work done on pushed images
+//
+// Pushed images are scanned (`scan`), get an SBOM (`sbom`) and have their base image tracked
+// (`base_image`); pushed SBOM referrers have their packages indexed (`sbom_index`), and pushed
+// cosign signatures have the image they sign verified (`signature`).
+// Jobs live in `background_jobs`. The enqueuer follows the process's log stream like the other
+// event consumers and queues the jobs a pushed image needs; `JOBS_CONCURRENCY` workers per
+// instance claim due jobs with SKIP LOCKED, so any replica may run a job another one queued. A
+// failed attempt is retried with exponential backoff until `JOBS_MAX_ATTEMPTS` is reached, after
+// which the job stays `failed` and a `<kind>.failed` audit event is published. Each kind also
+// has its own limit on how many run at once on one instance, e.g. `SCAN_CONCURRENCY`.
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use sqlx::PgPool;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::{Notify, OwnedSemaphorePermit, Semaphore};
+
+use crate::config::settings::ScanSettings;
+use crate::log_stream::{LogEvent, LogEventKind, LogFilter};
+use crate::models::job::JobKind;
+use crate::models::sbom::SbomFormat;
+use crate::signatures::{signed_digest, COSIGN_SIGNATURE_ARTIFACT_TYPE, NOTATION_SIGNATURE_ARTIFACT_TYPE};
+use crate::webhooks::chat::short_digest;
+use crate::AppState;
+
+/// How often idle workers look for due jobs queued by other instances or retried
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+const MAINTENANCE_INTERVAL: Duration = Duration::from_secs(60);
+/// Delay before the first retry, doubled for each one after it
+const RETRY_BASE: Duration = Duration::from_secs(30);
+const RETRY_MAX: Duration = Duration::from_secs(3600);
+/// Days finished jobs are kept
+const FINISHED_RETENTION_DAYS: i32 = 7;
+
+/// A job claimed by a worker
+#[derive(Debug, sqlx::FromRow)]
+struct ClaimedJob {
+    id: i64,
+    kind: String,
+    repository_id: i64,
+    repository: String,
+    manifest_digest: String,
+    attempts: i32,
+    max_attempts: i32,
+}
+
+/// Queue jobs for pushed images and run them
+pub fn spawn_job_workers(state: AppState) {
+    let wake = Arc::new(Notify::new());
+    let limits: Arc<HashMap<JobKind, Arc<Semaphore>>> = Arc::new(
+        JobKind::ALL
+            .into_iter()
+            .map(|kind| (kind, Arc::new(Semaphore::new(kind_concurrency(&state, kind)))))
+            .collect(),
+    );
+
+    let enqueuer_state = state.clone();
+    let enqueuer_wake = wake.clone();
+    tokio::spawn(async move {
+        let state = enqueuer_state;
+        let filter = LogFilter { kind: Some(LogEventKind::Audit), action: Some("manifest.push".to_string()), ..Default::default() };
+        let (_, mut events) = state.log_stream.subscribe(&filter, 0);
+        loop {
+            let event = match events.recv().await {
+                Ok(event) => event,
+                Err(RecvError::Lagged(skipped)) => {
+                    tracing::warn!("Job enqueuer fell behind, {} events not queued", skipped);
+                    continue;
+                }
+                Err(RecvError::Closed) => break,
+            };
+            if event.kind != LogEventKind::Audit || event.action != "manifest.push" || state.standby.is_read_only() {
+                continue;
+            }
+            match queue_push(&state, &event).await {
+                Ok(queued) => (0..queued).for_each(|_| enqueuer_wake.notify_one()),
+                Err(e) => tracing::error!("Failed to queue jobs for a push: {}", e),
+            }
+        }
+    });
+
+    for _ in 0..state.config.jobs.concurrency {
+        let state = state.clone();
+        let wake = wake.clone();
+        let limits = limits.clone();
+        tokio::spawn(async move {
+            loop {
+                if state.standby.is_read_only() {
+                    tokio::time::sleep(POLL_INTERVAL).await;
+                    continue;
+                }
+                // Only kinds still below their limit on this instance are claimed
+                let mut permits: HashMap<JobKind, OwnedSemaphorePermit> = limits
+                    .iter()
+                    .filter_map(|(kind, limit)| limit.clone().try_acquire_owned().ok().map(|permit| (*kind, permit)))
+                    .collect();
+                let kinds: Vec<String> = permits.keys().map(|kind| kind.to_string()).collect();
+                let claimed = match kinds.is_empty() {
+                    true => Ok(None),
+                    false => claim(&state.db_pool, &kinds).await,
+                };
+                match claimed {
+                    Ok(Some(job)) => {
+                        let permit = job.kind.parse().ok().and_then(|kind: JobKind| permits.remove(&kind));
+                        drop(permits);
+                        run(&state, job).await;
+                        drop(permit);
+                    }
+                    Ok(None) => {
+                        drop(permits);
+                        tokio::select! {
+                            _ = wake.notified() => {}
+                            _ = tokio::time::sleep(POLL_INTERVAL) => {}
+                        }
+                    }
+                    Err(e) => {
+                        drop(permits);
+                        tracing::error!("Failed to claim a background job: {}", e);
+                        tokio::time::sleep(POLL_INTERVAL).await;
+                    }
+                }
+            }
+        });
+    }
+
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(MAINTENANCE_INTERVAL);
+        loop {
+            interval.tick().await;
+            if state.standby.is_read_only() {
+                continue;
+            }
+            if let Err(e) = maintain(&state).await {
+                tracing::error!("Failed to maintain the job queue: {}", e);
+            }
+        }
+    });
+}
+
+fn kind_concurrency(state: &AppState, kind: JobKind) -> usize {
+    match kind {
+        // Both run Trivy
+        JobKind::Scan | JobKind::Sbom => state.config.scanning.concurrency,
+        JobKind::SbomIndex | JobKind::Signature | JobKind::BaseImage => state.config.jobs.concurrency,
+    }
+}
+
+/// The kinds of job a pushed manifest gets: images are scanned, get an SBOM and have their base
+/// tracked as configured, SBOM referrers are indexed, cosign and Notation signature referrers have
+/// their subject verified, other referrers get nothing
+fn kinds_for_push(
+    settings: &ScanSettings,
+    verify_signatures: bool,
+    track_bases: bool,
+    subject_digest: Option<&str>,
+    artifact_type: Option<&str>,
+) -> Vec<JobKind> {
+    match (subject_digest, artifact_type) {
+        (None, None) => JobKind::ALL
+            .into_iter()
+            .filter(|kind| match kind {
+                JobKind::Scan => settings.enabled,
+                JobKind::Sbom => settings.sbom_enabled,
+                JobKind::BaseImage => track_bases,
+                JobKind::SbomIndex | JobKind::Signature => false,
+            })
+            .collect(),
+        (Some(_), Some(artifact_type)) if SbomFormat::from_artifact_type(artifact_type).is_some() => vec![JobKind::SbomIndex],
+        (Some(_), Some(COSIGN_SIGNATURE_ARTIFACT_TYPE | NOTATION_SIGNATURE_ARTIFACT_TYPE)) if verify_signatures => {
+            vec![JobKind::Signature]
+        }
+        _ => Vec::new(),
+    }
+}
+
+/// A pushed manifest
+#[derive(Debug, PartialEq)]
+enum Pushed<'a> {
+    /// An image or referrer, by digest
+    Manifest(&'a str),
+    /// A cosign signature stored as a `sha256-<hex>.sig` tag, by the digest it signs
+    SignatureTag(String),
+}
+
+/// What a push event pushed; attestations and SBOMs stored as other `sha256-<hex>.*` tags by
+/// older clients get no jobs
+fn pushed_manifest(event: &LogEvent) -> Option<Pushed<'_>> {
+    let (reference, digest) = event.detail.as_deref()?.split_once(" -> ")?;
+    if !reference.starts_with("sha256-") {
+        return Some(Pushed::Manifest(digest));
+    }
+    signed_digest(reference).map(Pushed::SignatureTag)
+}
+
+/// Queue the jobs of a push, returning how many were queued
+async fn queue_push(state: &AppState, event: &LogEvent) -> Result<usize> {
+    let (Some(repository), Some(pushed)) = (event.repository.as_deref(), pushed_manifest(event)) else {
+        return Ok(0);
+    };
+    let verify_signatures = state.config.signatures.enabled();
+    let digest = match &pushed {
+        Pushed::Manifest(digest) => *digest,
+        Pushed::SignatureTag(_) if !verify_signatures => return Ok(0),
+        Pushed::SignatureTag(signed) => signed.as_str(),
+    };
+    let (namespace, repo_name) = match repository.split_once('/') {
+        Some((namespace, repo_name)) => (Some(namespace), repo_name),
+        None => (None, repository),
+    };
+    let manifest = sqlx::query_as::<_, (i64, Option<String>, Option<String>)>(
+        "SELECT r.id, m.subject_digest, m.artifact_type
+         FROM repositories r
+         JOIN organizations o ON o.id = r.organization_id
+         JOIN manifests m ON m.repository_id = r.id AND m.digest = $3
+         WHERE r.name = $2 AND (o.name = $1 OR ($1 IS NULL AND o.id = 1))",
+    )
+    .bind(namespace)
+    .bind(repo_name)
+    .bind(digest)
+    .fetch_optional(&state.db_pool)
+    .await?;
+    let Some((repository_id, subject_digest, artifact_type)) = manifest else {
+        return Ok(0);
+    };
+    let kinds = match &pushed {
+        Pushed::Manifest(_) => kinds_for_push(
+            &state.config.scanning,
+            verify_signatures,
+            state.config.base_images.enabled,
+            subject_digest.as_deref(),
+            artifact_type.as_deref(),
+        ),
+        Pushed::SignatureTag(_) => vec![JobKind::Signature],
+    };
+
+    let mut queued = 0;
+    for kind in kinds {
+        // A signature referrer has the image it signs verified
+        let target = match (&pushed, kind, subject_digest.as_deref()) {
+            (Pushed::Manifest(_), JobKind::Signature, Some(subject)) => subject,
+            _ => digest,
+        };
+        if enqueue(&state.db_pool, kind, repository_id, target, state.config.jobs.max_attempts).await?.is_some() {
+            queued += 1;
+        }
+    }
+    Ok(queued)
+}
+
+/// Queue a job unless one of the same kind is already pending for the manifest
+pub async fn enqueue(
+    pool: &PgPool,
+    kind: JobKind,
+    repository_id: i64,
+    digest: &str,
+    max_attempts: i32,
+) -> Result<Option<i64>, sqlx::Error> {
+    sqlx::query_scalar::<_, i64>(
+        "INSERT INTO background_jobs (kind, repository_id, manifest_digest, max_attempts)
+         VALUES ($1, $2, $3, $4)
+         ON CONFLICT (kind, repository_id, manifest_digest) WHERE status IN ('queued', 'running') DO NOTHING
+         RETURNING id",
+    )
+    .bind(kind.to_string())
+    .bind(repository_id)
+    .bind(digest)
+    .bind(max_attempts)
+    .fetch_optional(pool)
+    .await
+}
+
+async fn claim(pool: &PgPool, kinds: &[String]) -> Result<Option<ClaimedJob>, sqlx::Error> {
+    sqlx::query_as::<_, ClaimedJob>(
+        "UPDATE background_jobs j SET status = 'running', attempts = j.attempts + 1, started_at = CURRENT_TIMESTAMP
+         FROM repositories r
+         JOIN organizations o ON o.id = r.organization_id
+         WHERE r.id = j.repository_id
+           AND j.id = (
+               SELECT id FROM background_jobs
+               WHERE status = 'queued' AND run_after <= CURRENT_TIMESTAMP AND kind = ANY($1)
+               ORDER BY run_after, id
+               LIMIT 1
+               FOR UPDATE SKIP LOCKED
+           )
+         RETURNING j.id, j.kind, j.repository_id, o.name || '/' || r.name AS repository, j.manifest_digest,
+                   j.attempts, j.max_attempts",
+    )
+    .bind(kinds)
+    .fetch_optional(pool)
+    .await
+}
+
+async fn run(state: &AppState, job: ClaimedJob) {
+    let timeout = Duration::from_secs(state.config.jobs.timeout_seconds);
+    let result = match tokio::time::timeout(timeout, execute(state, &job)).await {
+        Ok(result) => result,
+        Err(_) => Err(anyhow!("Timed out after {} seconds", timeout.as_secs())),
+    };
+    if let Err(e) = finish(state, &job, result).await {
+        tracing::error!("Failed to record the outcome of job {}: {}", job.id, e);
+    }
+}
+
+async fn execute(state: &AppState, job: &ClaimedJob) -> Result<()> {
+    let kind: JobKind = job.kind.parse().map_err(|e: String| anyhow!(e))?;
+    match kind {
+        JobKind::Scan => {
+            if !state.config.scanning.enabled {
+                return Err(anyhow!("Vulnerability scanning is disabled"));
+            }
+            crate::scanning::scan_manifest(state, job.repository_id, &job.repository, &job.manifest_digest).await
+        }
+        JobKind::Sbom => {
+            if !state.config.scanning.sbom_enabled {
+                return Err(anyhow!("SBOM generation is disabled"));
+            }
+            crate::sbom::generate(state, job.repository_id, &job.repository, &job.manifest_digest).await
+        }
+        JobKind::SbomIndex => crate::sbom::index(state, job.repository_id, &job.repository, &job.manifest_digest).await,
+        JobKind::Signature => {
+            crate::signatures::verify_manifest(state, job.repository_id, &job.repository, &job.manifest_digest).await
+        }
+        JobKind::BaseImage => {
+            if !state.config.base_images.enabled {
+                return Err(anyhow!("Base image tracking is disabled"));
+            }
+            crate::base_images::track(state, job.repository_id, &job.repository, &job.manifest_digest).await
+        }
+    }
+}
+
+async fn finish(state: &AppState, job: &ClaimedJob, result: Result<()>) -> Result<()> {
+    let error = match result {
+        Ok(()) => {
+            sqlx::query("UPDATE background_jobs SET status = 'succeeded', last_error = NULL, finished_at = CURRENT_TIMESTAMP WHERE id = $1")
+                .bind(job.id)
+                .execute(&state.db_pool)
+                .await?;
+            return Ok(());
+        }
+        Err(e) => e.to_string(),
+    };
+
+    if job.attempts < job.max_attempts {
+        let delay = retry_delay(job.attempts);
+        tracing::warn!(
+            "Job {} ({} of {}@{}) failed on attempt {}, retrying in {}s: {}",
+            job.id, job.kind, job.repository, job.manifest_digest, job.attempts, delay.as_secs(), error
+        );
+        sqlx::query(
+            "UPDATE background_jobs SET status = 'queued', last_error = $2,
+                 run_after = CURRENT_TIMESTAMP + make_interval(secs => $3)
+             WHERE id = $1",
+        )
+        .bind(job.id)
+        .bind(&error)
+        .bind(delay.as_secs_f64())
+        .execute(&state.db_pool)
+        .await?;
+        return Ok(());
+    }
+
+    tracing::error!(
+        "Job {} ({} of {}@{}) failed after {} attempts: {}",
+        job.id, job.kind, job.repository, job.manifest_digest, job.attempts, error
+    );
+    sqlx::query("UPDATE background_jobs SET status = 'failed', last_error = $2, finished_at = CURRENT_TIMESTAMP WHERE id = $1")
+        .bind(job.id)
+        .bind(&error)
+        .execute(&state.db_pool)
+        .await?;
+    state.log_stream.publish(
+        LogEvent::audit(&format!("{}.failed", job.kind), None, Some(job.repository.clone()))
+            .with_detail(format!("{}: {}", short_digest(&job.manifest_digest), error)),
+    );
+    Ok(())
+}
+
+/// Delay before retrying a job whose `attempt`th attempt failed
+fn retry_delay(attempt: i32) -> Duration {
+    let doublings = attempt.saturating_sub(1).clamp(0, 16) as u32;
+    RETRY_BASE.saturating_mul(1 << doublings).min(RETRY_MAX)
+}
+
+/// Requeue jobs whose worker went away and drop old finished jobs
+async fn maintain(state: &AppState) -> Result<()> {
+    // A live worker gives up on an attempt at the timeout; one still running well past it is lost
+    let stale_after = state.config.jobs.timeout_seconds as f64 + MAINTENANCE_INTERVAL.as_secs_f64();
+    let requeued = sqlx::query(
+        "UPDATE background_jobs SET
+             status = CASE WHEN attempts >= max_attempts THEN 'failed' ELSE 'queued' END,
+             finished_at = CASE WHEN attempts >= max_attempts THEN CURRENT_TIMESTAMP END,
+             last_error = 'The worker running the job stopped',
+             run_after = CURRENT_TIMESTAMP
+         WHERE status = 'running' AND started_at < CURRENT_TIMESTAMP - make_interval(secs => $1)",
+    )
+    .bind(stale_after)
+    .execute(&state.db_pool)
+    .await?
+    .rows_affected();
+    if requeued > 0 {
+        tracing::warn!("Recovered {} background jobs from stopped workers", requeued);
+    }
+
+    sqlx::query("DELETE FROM background_jobs WHERE finished_at < CURRENT_TIMESTAMP - make_interval(days => $1)")
+        .bind(FINISHED_RETENTION_DAYS)
+        .execute(&state.db_pool)
+        .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn retries_back_off_exponentially() {
+        assert_eq!(retry_delay(1), Duration::from_secs(30));
+        assert_eq!(retry_delay(2), Duration::from_secs(60));
+        assert_eq!(retry_delay(4), Duration::from_secs(240));
+        assert_eq!(retry_delay(10), RETRY_MAX);
+        assert_eq!(retry_delay(i32::MAX), RETRY_MAX);
+    }
+
+    #[test]
+    fn images_and_sboms_get_their_own_jobs() {
+        let mut settings = ScanSettings {
+            enabled: true,
+            trivy_path: "trivy".to_string(),
+            trivy_server_url: None,
+            trivy_token: None,
+            registry_host: "localhost:8080".to_string(),
+            registry_username: None,
+            registry_password: None,
+            registry_insecure: false,
+            timeout_seconds: 600,
+            concurrency: 2,
+            sbom_enabled: false,
+            sbom_format: "cyclonedx".to_string(),
+        };
+        assert_eq!(kinds_for_push(&settings, true, false, None, None), [JobKind::Scan]);
+        settings.sbom_enabled = true;
+        assert_eq!(kinds_for_push(&settings, true, false, None, None), [JobKind::Scan, JobKind::Sbom]);
+        assert_eq!(kinds_for_push(&settings, true, true, None, None), [JobKind::Scan, JobKind::Sbom, JobKind::BaseImage]);
+
+        let sbom = kinds_for_push(&settings, true, true, Some("sha256:abc"), Some("application/vnd.cyclonedx+json"));
+        assert_eq!(sbom, [JobKind::SbomIndex]);
+        let signature = kinds_for_push(&settings, true, true, Some("sha256:abc"), Some(COSIGN_SIGNATURE_ARTIFACT_TYPE));
+        assert_eq!(signature, [JobKind::Signature]);
+        assert!(kinds_for_push(&settings, false, true, Some("sha256:abc"), Some(COSIGN_SIGNATURE_ARTIFACT_TYPE)).is_empty());
+        let notation = kinds_for_push(&settings, true, true, Some("sha256:abc"), Some(NOTATION_SIGNATURE_ARTIFACT_TYPE));
+        assert_eq!(notation, [JobKind::Signature]);
+        let attestation = kinds_for_push(&settings, true, true, Some("sha256:abc"), Some("application/vnd.dsse.envelope.v1+json"));
+        assert!(attestation.is_empty());
+    }
+
+    #[test]
+    fn signature_tags_verify_the_image_they_sign() {
+        let push = LogEvent::audit("manifest.push", Some(1), Some("acme/web".to_string())).with_detail("v1 -> sha256:abc");
+        assert_eq!(pushed_manifest(&push), Some(Pushed::Manifest("sha256:abc")));
+
+        let hex = "2c26b46b68ffc68ff99b453c1d30413413422d706483bfa0f98a5e886266e7ae";
+        let signature = LogEvent::audit("manifest.push", Some(1), Some("acme/web".to_string()))
+            .with_detail(format!("sha256-{}.sig -> sha256:def", hex));
+        assert_eq!(pushed_manifest(&signature), Some(Pushed::SignatureTag(format!("sha256:{}", hex))));
+
+        let attestation = LogEvent::audit("manifest.push", Some(1), Some("acme/web".to_string()))
+            .with_detail(format!("sha256-{}.att -> sha256:def", hex));
+        assert_eq!(pushed_manifest(&attestation), None);
+    }
+}
@@ -1,24 +1,62 @@
 use sqlx::PgPool;
 use std::sync::Arc;
-use std::collections::HashMap;
-use tokio::sync::RwLock;
 use axum::{Router, response::Html, http::{StatusCode, Uri}};
+use tower::Layer;
 use axum::routing::get;
 use tower_http::services::{ServeDir, ServeFile};
 use utoipa::OpenApi;
 use utoipa_swagger_ui::SwaggerUi;
 
+pub mod activity;
 pub mod auth;
+pub mod bandwidth;
+pub mod base_images;
+pub mod bootstrap;
 pub mod cache;
+pub mod cache_warmup;
+pub mod cdn;
+pub mod cloudevents;
+pub mod compression;
 pub mod config;
 pub mod database;
 pub mod db;
 pub mod email;
+pub mod error_reporting;
+pub mod event_stream;
+pub mod events;
+pub mod features;
+pub mod federation;
+pub mod gc;
 pub mod handlers;
+pub mod jobs;
+pub mod listeners;
+pub mod log_stream;
+pub mod logging;
 pub mod models;
+pub mod naming;
+pub mod notifications;
 pub mod openapi;
+pub mod policies;
+pub mod push_hooks;
+pub mod quarantine;
+pub mod quota;
+pub mod read_replica;
+pub mod retention;
 pub mod routes;
+pub mod sbom;
+pub mod scanning;
+pub mod secret_scanning;
+pub mod secrets;
+pub mod signatures;
+pub mod standby;
+pub mod startup;
 pub mod storage;
+pub mod tag_cleanup;
+pub mod tls;
+pub mod transcode;
+pub mod uploads;
+pub mod watches;
+pub mod webhooks;
 
 #[derive(Clone)]
 pub struct AppState {
@@ -26,8 +64,26 @@ pub struct AppState {
     pub config: config::Settings,
     pub storage: Arc<dyn storage::Storage>,
     pub cache: Option<Arc<cache::RegistryCache>>,
-    pub manifest_cache: Arc<RwLock<HashMap<String, String>>>, // digest -> content
     pub email_service: Arc<email::EmailService>,
+    pub webhook_signer: Arc<webhooks::WebhookSigner>,
+    pub log_stream: Arc<log_stream::LogStream>,
+    pub standby: Arc<standby::Standby>,
+    pub federation: Arc<federation::Federation>,
+    pub push_validators: Arc<push_hooks::PushValidators>,
+    pub notifier: Arc<notifications::Notifier>,
+    pub transfer_limits: Arc<handlers::transfer_limits::TransferLimits>,
+    pub runtime_config: Arc<config::reload::RuntimeConfig>,
+    pub features: Arc<features::FeatureFlags>,
+    pub read_replica: Arc<read_replica::ReadReplica>,
+}
+
+impl AppState {
+    /// Pool for heavy read-only queries (catalog, tag lists, searches): the read replica while it
+    /// is usable, otherwise the primary. Queries whose results must include the caller's own
+    /// recent writes, such as resolving a manifest to pull, stay on `db_pool`.
+    pub fn read_pool(&self) -> &PgPool {
+        self.read_replica.pool(&self.db_pool)
+    }
 }
 
 // Function to detect correct paths for static files
@@ -102,14 +158,35 @@ pub async fn create_app(state: AppState) -> Router {
     
     // API routes with state
     let api_router = Router::new()
-        .nest("/api/v1", routes::api::api_router())
+        .nest(
+            "/api/v1",
+            routes::api::api_router()
+                .layer(compression::response_layer(&state.config.compression))
+                .layer(compression::request_layer(&state.config.compression)),
+        )
         // Docker Registry V2 API routes - direct routes to avoid nesting conflicts
-        .merge(routes::docker_registry_v2::docker_registry_v2_router())
+        .merge(
+            routes::docker_registry_v2::docker_registry_v2_router(&state.config.compression)
+                // Layers and model weights are far larger than axum's 2 MB default
+                .layer(axum::extract::DefaultBodyLimit::max(state.config.uploads.max_request_bytes))
+                .route_layer(axum::middleware::from_fn_with_state(state.clone(), handlers::notifications::notify_registry_events))
+                .route_layer(axum::middleware::from_fn_with_state(state.clone(), handlers::takedowns::enforce_takedowns))
+                .route_layer(axum::middleware::from_fn_with_state(state.clone(), handlers::repository_redirects::redirect_moved_repositories))
+                .route_layer(axum::middleware::from_fn_with_state(state.clone(), handlers::ip_access::enforce_ip_access_rules)),
+        )
         // Health and monitoring endpoints  
         .merge(routes::health::health_router())
         // Serve Swagger UI
         .merge(SwaggerUi::new("/docs").url("/api-docs/openapi.json", openapi))
-        .layer(tower_http::trace::TraceLayer::new_for_http())
+        .layer(axum::middleware::from_fn_with_state(state.clone(), handlers::transfer_limits::enforce_transfer_limits))
+        .layer(axum::middleware::from_fn_with_state(state.clone(), handlers::api_scopes::enforce_api_key_scopes))
+        .layer(axum::middleware::from_fn_with_state(state.clone(), handlers::api_usage::track_api_usage))
+        .layer(axum::middleware::from_fn_with_state(state.clone(), handlers::rate_limit::enforce_rate_limit))
+        .layer(axum::middleware::from_fn_with_state(state.clone(), handlers::standby::enforce_read_only))
+        .layer(axum::middleware::from_fn_with_state(state.clone(), handlers::log_tail::record_access_log))
+        // Inside the request span, so a panic is logged with the request's context and answered 500
+        .layer(tower_http::catch_panic::CatchPanicLayer::new())
+        .layer(axum::middleware::from_fn(logging::request_span))
         .layer(tower_http::cors::CorsLayer::permissive())
         .with_state(state);
 
@@ -124,7 +201,12 @@ pub async fn create_app(state: AppState) -> Router {
         .fallback(spa_fallback);
 
     // Combine everything
-    Router::new()
+    let app = Router::new()
         .merge(api_router)
-        .merge(static_router)
+        .merge(static_router);
+
+    // Hierarchical repository names must be rewritten before routing, so wrap the whole app
+    Router::new().fallback_service(
+        axum::middleware::map_request(routes::docker_registry_v2::nest_repository_paths).layer(app)
+    )
 }
\ No newline at end of file
@@ -0,0 +1,276 @@
+// src/listeners.rs - Serving the app on its TCP addresses and Unix sockets
+//
+// The server listens on `LISTEN_ADDRESS`, or on every entry of `LISTENERS`: TCP addresses and
+// Unix sockets, each serving all routes or only part of them, e.g. the registry API on a public
+// port and the management API on a private one, or a socket for a reverse proxy on the same
+// host. TCP listeners use TLS when it is configured; Unix sockets always speak plain HTTP.
+// Connections over a Unix socket count as coming from 127.0.0.1, so a proxy in front of one is
+// trusted for `X-Forwarded-For` by listing that address in `TRUSTED_PROXIES`.
+use std::future::Future;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use anyhow::{bail, Context, Result};
+use axum::{
+    extract::{Request, State},
+    http::StatusCode,
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Router,
+};
+use axum_server::Handle;
+use tokio::{sync::watch, task::JoinSet};
+
+use crate::config::settings::{ListenerSettings, Settings};
+use crate::tls::Tls;
+
+/// How long open connections get to finish once shutdown starts
+const SHUTDOWN_GRACE: Duration = Duration::from_secs(30);
+
+/// Probes answered on every listener, so each can be health-checked on its own
+const PROBE_PATHS: [&str; 4] = ["/health", "/healthz", "/readyz", "/startupz"];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ListenAddress {
+    Tcp(SocketAddr),
+    Unix(PathBuf),
+}
+
+/// Which routes a listener serves
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ListenerRoutes {
+    All,
+    /// The `/v2` registry API
+    Registry,
+    /// Everything but the registry API: `/api/v1`, the web UI and the API docs
+    Management,
+}
+
+impl ListenerRoutes {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ListenerRoutes::All => "all",
+            ListenerRoutes::Registry => "registry",
+            ListenerRoutes::Management => "management",
+        }
+    }
+
+    pub fn serves(self, path: &str) -> bool {
+        if PROBE_PATHS.contains(&path) {
+            return true;
+        }
+        let registry = path == "/v2" || path.starts_with("/v2/");
+        match self {
+            ListenerRoutes::All => true,
+            ListenerRoutes::Registry => registry,
+            ListenerRoutes::Management => !registry,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Listener {
+    pub address: ListenAddress,
+    pub routes: ListenerRoutes,
+}
+
+impl Listener {
+    pub fn parse(settings: &ListenerSettings) -> Result<Self> {
+        let address = match settings.address.strip_prefix("unix:") {
+            Some(path) if !path.is_empty() => ListenAddress::Unix(PathBuf::from(path)),
+            Some(_) => bail!("Listener '{}' has no socket path", settings.address),
+            None => ListenAddress::Tcp(
+                settings
+                    .address
+                    .parse()
+                    .with_context(|| format!("Invalid listen address '{}'", settings.address))?,
+            ),
+        };
+        let routes = match settings.routes.as_str() {
+            "all" => ListenerRoutes::All,
+            "registry" => ListenerRoutes::Registry,
+            "management" => ListenerRoutes::Management,
+            other => bail!("Listener '{}' has unknown routes '{}'", settings.address, other),
+        };
+        Ok(Self { address, routes })
+    }
+}
+
+/// Serve `app` on every configured listener until `shutdown` completes. Fails as soon as any
+/// listener does, e.g. when its address is already in use.
+pub async fn serve(settings: &Settings, app: Router, shutdown: impl Future<Output = ()> + Send + 'static) -> Result<()> {
+    let listeners = settings
+        .server
+        .listeners
+        .iter()
+        .map(Listener::parse)
+        .collect::<Result<Vec<_>>>()?;
+    let tls = crate::tls::configure(&settings.tls).await?;
+
+    let (stop, stopped) = watch::channel(false);
+    let mut handles = Vec::new();
+    let mut servers = JoinSet::new();
+    for listener in listeners {
+        let app = app
+            .clone()
+            .layer(axum::middleware::from_fn_with_state(listener.routes, restrict_routes));
+        match listener.address {
+            ListenAddress::Tcp(addr) => {
+                let handle = Handle::new();
+                handles.push(handle.clone());
+                servers.spawn(serve_tcp(addr, app, tls.clone(), handle, listener.routes));
+            }
+            #[cfg(unix)]
+            ListenAddress::Unix(path) => {
+                servers.spawn(serve_unix(path, app, settings.server.unix_socket_mode, stopped.clone(), listener.routes));
+            }
+            #[cfg(not(unix))]
+            ListenAddress::Unix(path) => bail!("Cannot listen on {}: Unix sockets are not supported here", path.display()),
+        }
+    }
+
+    tokio::spawn(async move {
+        shutdown.await;
+        for handle in handles {
+            handle.graceful_shutdown(Some(SHUTDOWN_GRACE));
+        }
+        let _ = stop.send(true);
+    });
+
+    while let Some(served) = servers.join_next().await {
+        served.context("Listener task failed")??;
+    }
+    Ok(())
+}
+
+async fn serve_tcp(addr: SocketAddr, app: Router, tls: Option<Tls>, handle: Handle, routes: ListenerRoutes) -> Result<()> {
+    let service = app.into_make_service_with_connect_info::<SocketAddr>();
+    let scheme = if tls.is_some() { "https" } else { "http" };
+    tracing::info!("Listening on {}://{} for {} routes", scheme, addr, routes.as_str());
+    match tls {
+        None => axum_server::bind(addr).handle(handle).serve(service).await,
+        Some(Tls::Files(config)) => axum_server::bind_rustls(addr, config).handle(handle).serve(service).await,
+        Some(Tls::Acme(acceptor)) => axum_server::bind(addr).acceptor(acceptor).handle(handle).serve(service).await,
+    }
+    .with_context(|| format!("Failed to serve on {}", addr))
+}
+
+#[cfg(unix)]
+async fn serve_unix(
+    path: PathBuf,
+    app: Router,
+    mode: u32,
+    mut stopped: watch::Receiver<bool>,
+    routes: ListenerRoutes,
+) -> Result<()> {
+    use std::net::Ipv4Addr;
+    use std::os::unix::fs::PermissionsExt;
+
+    use axum::{extract::ConnectInfo, Extension};
+    use hyper_util::{
+        rt::{TokioExecutor, TokioIo},
+        server::{conn::auto, graceful::GracefulShutdown},
+        service::TowerToHyperService,
+    };
+    use tokio::net::UnixListener;
+
+    remove_stale_socket(&path)?;
+    let listener = UnixListener::bind(&path).with_context(|| format!("Failed to bind {}", path.display()))?;
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(mode))
+        .with_context(|| format!("Failed to set permissions of {}", path.display()))?;
+    tracing::info!("Listening on unix:{} for {} routes", path.display(), routes.as_str());
+
+    let app = app.layer(Extension(ConnectInfo(SocketAddr::from((Ipv4Addr::LOCALHOST, 0)))));
+    let graceful = GracefulShutdown::new();
+    loop {
+        tokio::select! {
+            accepted = listener.accept() => {
+                let stream = match accepted {
+                    Ok((stream, _)) => stream,
+                    Err(e) => {
+                        tracing::warn!("Failed to accept a connection on {}: {}", path.display(), e);
+                        continue;
+                    }
+                };
+                let connection = auto::Builder::new(TokioExecutor::new())
+                    .serve_connection_with_upgrades(TokioIo::new(stream), TowerToHyperService::new(app.clone()))
+                    .into_owned();
+                let connection = graceful.watch(connection);
+                tokio::spawn(async move {
+                    if let Err(e) = connection.await {
+                        tracing::debug!("Unix socket connection ended with an error: {}", e);
+                    }
+                });
+            }
+            _ = stopped.changed() => break,
+        }
+    }
+
+    drop(listener);
+    let _ = std::fs::remove_file(&path);
+    if tokio::time::timeout(SHUTDOWN_GRACE, graceful.shutdown()).await.is_err() {
+        tracing::warn!("Connections on {} still open after {:?} are closed", path.display(), SHUTDOWN_GRACE);
+    }
+    Ok(())
+}
+
+/// Remove a socket left behind by a previous run; any other file in the way is an error
+#[cfg(unix)]
+fn remove_stale_socket(path: &std::path::Path) -> Result<()> {
+    use std::os::unix::fs::FileTypeExt;
+
+    match std::fs::symlink_metadata(path) {
+        Ok(metadata) if metadata.file_type().is_socket() => {
+            std::fs::remove_file(path).with_context(|| format!("Failed to remove stale socket {}", path.display()))
+        }
+        Ok(_) => bail!("{} exists and is not a socket", path.display()),
+        Err(_) => Ok(()),
+    }
+}
+
+/// 404 for requests to routes the listener does not serve
+async fn restrict_routes(State(routes): State<ListenerRoutes>, request: Request, next: Next) -> Response {
+    if routes.serves(request.uri().path()) {
+        next.run(request).await
+    } else {
+        StatusCode::NOT_FOUND.into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn listener(address: &str, routes: &str) -> Result<Listener> {
+        Listener::parse(&ListenerSettings { address: address.to_string(), routes: routes.to_string() })
+    }
+
+    #[test]
+    fn parses_tcp_and_unix_listeners() {
+        assert_eq!(
+            listener("0.0.0.0:5000", "registry").unwrap(),
+            Listener { address: ListenAddress::Tcp("0.0.0.0:5000".parse().unwrap()), routes: ListenerRoutes::Registry }
+        );
+        assert_eq!(
+            listener("unix:/run/aerugo/aerugo.sock", "all").unwrap().address,
+            ListenAddress::Unix(PathBuf::from("/run/aerugo/aerugo.sock"))
+        );
+        assert!(listener("unix:", "all").is_err());
+        assert!(listener("localhost", "all").is_err());
+        assert!(listener("127.0.0.1:9000", "admin").is_err());
+    }
+
+    #[test]
+    fn listeners_serve_their_routes_and_the_probes() {
+        assert!(ListenerRoutes::Registry.serves("/v2/"));
+        assert!(ListenerRoutes::Registry.serves("/v2/acme/web/manifests/latest"));
+        assert!(!ListenerRoutes::Registry.serves("/api/v1/repos"));
+        assert!(!ListenerRoutes::Registry.serves("/v2beta"));
+        assert!(ListenerRoutes::Management.serves("/api/v1/repos"));
+        assert!(ListenerRoutes::Management.serves("/"));
+        assert!(!ListenerRoutes::Management.serves("/v2/_catalog"));
+        assert!(ListenerRoutes::Registry.serves("/readyz"));
+        assert!(ListenerRoutes::Management.serves("/healthz"));
+    }
+}
@@ -0,0 +1,260 @@
+// src/log_stream.rs - In-process fan-out of audit and access-log events for live tailing
+//
+// Every event is kept in a bounded ring buffer (so a new subscriber can replay recent
+// history) and broadcast to live subscribers. The stream itself is an operator's `tail -f` and
+// each replica only sees its own traffic; audit events are persisted separately by the event
+// recorder (see `events`), which keeps the durable history behind the activity feeds.
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+use utoipa::ToSchema;
+
+/// Where an event came from
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum LogEventKind {
+    /// A state change made through the API or registry, e.g. `manifest.push`
+    Audit,
+    /// One HTTP request, recorded after its response was produced
+    Access,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct LogEvent {
+    /// Monotonic per-process sequence number
+    pub id: u64,
+    pub timestamp: DateTime<Utc>,
+    pub kind: LogEventKind,
+    /// Dotted action name: `manifest.push`, `repository.delete`, `registry.pull`, `api.get`, ...
+    pub action: String,
+    pub user_id: Option<i64>,
+    /// Login name, when the credential carried one (docker login)
+    pub username: Option<String>,
+    /// `namespace/repository` the event concerns
+    pub repository: Option<String>,
+    /// Organization the event concerns, for organization-level events without a repository
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub organization_id: Option<i64>,
+    pub method: Option<String>,
+    pub path: Option<String>,
+    pub status: Option<u16>,
+    pub duration_ms: Option<u64>,
+    pub client_ip: Option<String>,
+    pub detail: Option<String>,
+}
+
+impl LogEvent {
+    /// An audit event; the id and timestamp are assigned when published
+    pub fn audit(action: &str, user_id: Option<i64>, repository: Option<String>) -> Self {
+        Self {
+            id: 0,
+            timestamp: Utc::now(),
+            kind: LogEventKind::Audit,
+            action: action.to_string(),
+            user_id,
+            username: None,
+            repository,
+            organization_id: None,
+            method: None,
+            path: None,
+            status: None,
+            duration_ms: None,
+            client_ip: None,
+            detail: None,
+        }
+    }
+
+    /// An access event for a request that produced `status`
+    pub fn access(action: String, method: &str, path: &str, status: u16) -> Self {
+        Self {
+            kind: LogEventKind::Access,
+            method: Some(method.to_string()),
+            path: Some(path.to_string()),
+            status: Some(status),
+            ..Self::audit(&action, None, None)
+        }
+    }
+
+    pub fn with_organization(mut self, organization_id: i64) -> Self {
+        self.organization_id = Some(organization_id);
+        self
+    }
+
+    pub fn with_detail(mut self, detail: impl Into<String>) -> Self {
+        self.detail = Some(detail.into());
+        self
+    }
+}
+
+/// Server-side filter applied to replayed and live events
+#[derive(Debug, Clone, Default)]
+pub struct LogFilter {
+    pub kind: Option<LogEventKind>,
+    /// Matches the event's user ID
+    pub user_id: Option<i64>,
+    /// Matches the event's login name
+    pub username: Option<String>,
+    /// Exact `namespace/repository`, or a bare namespace matching all of its repositories
+    pub repository: Option<String>,
+    /// Exact action, or a prefix ending at a dot: `manifest` matches `manifest.push`
+    pub action: Option<String>,
+}
+
+impl LogFilter {
+    pub fn matches(&self, event: &LogEvent) -> bool {
+        if self.kind.is_some_and(|kind| kind != event.kind) {
+            return false;
+        }
+
+        if self.user_id.is_some() || self.username.is_some() {
+            let by_id = self.user_id.is_some() && self.user_id == event.user_id;
+            let by_name = self.username.is_some() && self.username == event.username;
+            if !by_id && !by_name {
+                return false;
+            }
+        }
+
+        if let Some(repository) = &self.repository {
+            match &event.repository {
+                Some(r) if r == repository || r.starts_with(&format!("{}/", repository)) => {}
+                _ => return false,
+            }
+        }
+
+        if let Some(action) = &self.action {
+            if event.action != *action && !event.action.starts_with(&format!("{}.", action)) {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+struct Recent {
+    next_id: u64,
+    events: VecDeque<LogEvent>,
+}
+
+/// Ring buffer of recent events plus a broadcast channel to live subscribers
+pub struct LogStream {
+    sender: broadcast::Sender<LogEvent>,
+    recent: Mutex<Recent>,
+    capacity: usize,
+}
+
+impl LogStream {
+    /// `capacity` bounds both the replay buffer and how far a slow subscriber may fall behind
+    pub fn new(capacity: usize) -> Self {
+        let capacity = capacity.max(1);
+        let (sender, _) = broadcast::channel(capacity);
+        Self {
+            sender,
+            recent: Mutex::new(Recent { next_id: 1, events: VecDeque::with_capacity(capacity) }),
+            capacity,
+        }
+    }
+
+    pub fn publish(&self, mut event: LogEvent) {
+        let mut recent = self.recent.lock().unwrap_or_else(|e| e.into_inner());
+        event.id = recent.next_id;
+        recent.next_id += 1;
+
+        if recent.events.len() == self.capacity {
+            recent.events.pop_front();
+        }
+        recent.events.push_back(event.clone());
+
+        // Sending under the lock keeps replay and live delivery free of gaps and duplicates;
+        // an error only means nobody is tailing right now
+        let _ = self.sender.send(event);
+    }
+
+    /// Drop buffered events published before `cutoff` so they are no longer replayed
+    pub fn prune_before(&self, cutoff: DateTime<Utc>) -> usize {
+        let mut recent = self.recent.lock().unwrap_or_else(|e| e.into_inner());
+        let before = recent.events.len();
+        while recent.events.front().is_some_and(|event| event.timestamp < cutoff) {
+            recent.events.pop_front();
+        }
+        before - recent.events.len()
+    }
+
+    /// Subscribe to live events, returning up to `replay` of the most recent matching events first
+    pub fn subscribe(&self, filter: &LogFilter, replay: usize) -> (Vec<LogEvent>, broadcast::Receiver<LogEvent>) {
+        let recent = self.recent.lock().unwrap_or_else(|e| e.into_inner());
+        let receiver = self.sender.subscribe();
+
+        let mut replayed: Vec<LogEvent> = recent
+            .events
+            .iter()
+            .rev()
+            .filter(|event| filter.matches(event))
+            .take(replay)
+            .cloned()
+            .collect();
+        replayed.reverse();
+
+        (replayed, receiver)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replay_is_bounded_and_filtered() {
+        let stream = LogStream::new(3);
+        for repo in ["acme/web", "acme/api", "other/web", "acme/web"] {
+            stream.publish(LogEvent::audit("manifest.push", Some(1), Some(repo.to_string())));
+        }
+
+        let filter = LogFilter { repository: Some("acme".to_string()), ..Default::default() };
+        let (replayed, _) = stream.subscribe(&filter, 10);
+        let ids: Vec<u64> = replayed.iter().map(|e| e.id).collect();
+        // Event 1 fell out of the buffer and event 3 is in another namespace
+        assert_eq!(ids, vec![2, 4]);
+    }
+
+    #[test]
+    fn live_events_follow_replay() {
+        let stream = LogStream::new(10);
+        stream.publish(LogEvent::audit("repository.create", Some(1), None));
+        let (replayed, mut receiver) = stream.subscribe(&LogFilter::default(), 10);
+        stream.publish(LogEvent::audit("repository.delete", Some(1), None));
+
+        assert_eq!(replayed.len(), 1);
+        assert_eq!(receiver.try_recv().unwrap().id, 2);
+    }
+
+    #[test]
+    fn action_filter_matches_dotted_prefixes() {
+        let filter = LogFilter { action: Some("manifest".to_string()), ..Default::default() };
+        assert!(filter.matches(&LogEvent::audit("manifest.push", None, None)));
+        assert!(!filter.matches(&LogEvent::audit("manifests.push", None, None)));
+
+        let filter = LogFilter { user_id: Some(7), username: Some("alice".to_string()), ..Default::default() };
+        let mut event = LogEvent::access("registry.pull".to_string(), "GET", "/v2/", 200);
+        assert!(!filter.matches(&event));
+        event.username = Some("alice".to_string());
+        assert!(filter.matches(&event));
+    }
+
+    #[test]
+    fn pruned_events_are_not_replayed() {
+        let stream = LogStream::new(10);
+        let mut old = LogEvent::audit("repository.create", Some(1), None);
+        old.timestamp = Utc::now() - chrono::Duration::days(30);
+        stream.publish(old);
+        stream.publish(LogEvent::audit("repository.delete", Some(1), None));
+
+        assert_eq!(stream.prune_before(Utc::now() - chrono::Duration::days(7)), 1);
+        let (replayed, _) = stream.subscribe(&LogFilter::default(), 10);
+        assert_eq!(replayed.len(), 1);
+        assert_eq!(replayed[0].action, "repository.delete");
+    }
+}
@@ -1,49 +1,104 @@
 use aerugo::{create_app, AppState};
 use aerugo::config::Settings;
 use aerugo::storage::{Storage, s3::S3Storage};
-use aerugo::cache::{RegistryCache, CacheConfig};
+use aerugo::cache::RegistryCache;
 use anyhow::{Result, Context};
 use std::sync::Arc;
 use std::time::Duration;
 use std::process::{Command, Stdio};
 use secrecy::ExposeSecret;
+use clap::{Args, Parser, Subcommand};
+
+/// Aerugo container registry. Without a command, runs the server.
+#[derive(Parser)]
+#[command(name = "aerugo", version)]
+struct Cli {
+    /// Apply pending migrations and exit; same as `migrate --up`
+    #[arg(long)]
+    migrate_only: bool,
+    /// Create the configured administrator and default organization and exit
+    #[arg(long)]
+    bootstrap: bool,
+    #[command(subcommand)]
+    command: Option<Commands>,
+}
+
+#[derive(Subcommand)]
+enum Commands {
+    /// Check, apply or revert database migrations without starting the server
+    Migrate(MigrateArgs),
+}
+
+#[derive(Args, Default)]
+#[group(multiple = false)]
+struct MigrateArgs {
+    /// Report the schema version; fails when migrations are pending or the schema is incompatible
+    #[arg(long)]
+    check: bool,
+    /// Apply pending migrations (the default)
+    #[arg(long)]
+    up: bool,
+    /// Revert the N most recently applied migrations
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u32).range(1..))]
+    down: Option<u32>,
+}
 
 #[tokio::main]
 async fn main() -> Result<()> {
+    let cli = Cli::parse();
+
     // Load configuration
     let settings = Settings::load().expect("Failed to load configuration");
-    settings.validate_all().expect("Invalid configuration");
+    settings.validate_all().context("Invalid configuration")?;
+
+    // Initialize logging
+    aerugo::logging::init(&settings.server, &settings.error_reporting)?;
 
-    // Initialize tracing
-    tracing_subscriber::fmt::init();
+    // `migrate` changes the schema and exits, for controlled upgrades with DATABASE_AUTO_MIGRATE off
+    if let Some(Commands::Migrate(args)) = &cli.command {
+        return migrate(&settings, args).await;
+    }
+    if cli.migrate_only {
+        return migrate(&settings, &MigrateArgs::default()).await;
+    }
+
+    // `--bootstrap` creates the configured administrator and default organization and exits
+    if cli.bootstrap {
+        return bootstrap_only(&settings).await;
+    }
 
     // Start frontend development server in debug mode
     // Disabled to serve static files via backend instead
     // #[cfg(debug_assertions)]
     // start_frontend_dev_server();
 
-    println!("🚀 Starting Aerugo Container Registry");
+    tracing::info!("Starting Aerugo Container Registry");
     if cfg!(debug_assertions) {
-        println!("🔧 Development Mode");
-        println!("🔗 Full Application: http://localhost:8080");
-        println!("🔗 API Docs: http://localhost:8080/docs");
+        tracing::info!("Development Mode");
+        tracing::info!("Full Application: http://localhost:8080");
+        tracing::info!("API Docs: http://localhost:8080/docs");
     } else {
-        println!("🏭 Production Mode");  
-        println!("🔗 Full Application: http://localhost:8080");
-        println!("🔗 API Docs: http://localhost:8080/docs");
+        tracing::info!("Production Mode");  
+        tracing::info!("Full Application: http://localhost:8080");
+        tracing::info!("API Docs: http://localhost:8080/docs");
     }
-    println!();
 
     // Initialize database connection and run migrations
-    println!("Initializing database connection and running migrations...");
+    tracing::info!("Initializing database connection and running migrations...");
     let db_pool = aerugo::db::create_pool(&settings)
         .await
         .context("Failed to create database pool and run migrations")?;
     
-    println!("Database connection and migrations completed successfully");
+    tracing::info!("Database connection and migrations completed successfully");
+
+    // Heavy reads go to the replica once the monitor has found it usable
+    let read_replica = aerugo::db::connect_read_replica(&settings)?;
+    if read_replica.is_some() {
+        tracing::info!("Read replica configured; catalog, tag, manifest and search reads will use it");
+    }
 
     // Initialize S3 storage
-    println!("Initializing S3 storage...");
+    tracing::info!("Initializing S3 storage...");
     let s3_config = aerugo::storage::s3::S3Config {
         endpoint: settings.storage.endpoint.clone(),
         bucket: settings.storage.bucket_name().to_string(),
@@ -58,100 +113,242 @@ async fn main() -> Result<()> {
         part_size: Some(8 * 1024 * 1024), // 8MB
     };
     
+    let backoff = aerugo::startup::Backoff::new(&settings.startup);
     let storage: Arc<dyn Storage> = Arc::new(
-        S3Storage::new(&s3_config)
+        S3Storage::connect(&s3_config, &backoff)
             .await
-            .expect("Failed to initialize S3 storage")
+            .context("Failed to initialize S3 storage")?
     );
-    println!("S3 storage initialized successfully");
+    tracing::info!("S3 storage initialized successfully");
 
     // Initialize cache
-    println!("Initializing cache layer...");
-    let cache_config = CacheConfig {
-        redis_url: Some(settings.cache.redis_url.clone()),
-        manifest_ttl: Duration::from_secs(settings.cache.ttl_seconds),
-        blob_metadata_ttl: Duration::from_secs(settings.cache.ttl_seconds * 2), // 2x longer for blob metadata
-        repository_ttl: Duration::from_secs(60), // 1 minute for repo lists
-        tag_ttl: Duration::from_secs(120), // 2 minutes for tag lists
-        // Authentication cache TTLs
-        auth_token_ttl: Duration::from_secs(900), // 15 minutes
-        permission_ttl: Duration::from_secs(300), // 5 minutes
-        session_ttl: Duration::from_secs(1800), // 30 minutes
-        max_memory_entries: 10000,
-        enable_redis: true,
-        enable_memory: true,
-    };
-    
-    let cache = match RegistryCache::new(cache_config).await {
+    tracing::info!("Initializing cache layer...");
+    let cache = match RegistryCache::new(settings.cache.cache_config()).await {
         Ok(cache) => {
-            println!("Cache initialized successfully (Redis + Memory)");
+            tracing::info!("Cache initialized successfully (Redis + Memory)");
             Some(Arc::new(cache))
         },
         Err(e) => {
-            println!("Warning: Failed to initialize cache: {}. Continuing without cache.", e);
+            tracing::warn!("Failed to initialize cache: {}. Continuing without cache.", e);
             None
         }
     };
 
     // Initialize email service
-    println!("Initializing email service...");
+    tracing::info!("Initializing email service...");
     let email_service = match aerugo::email::EmailService::new(settings.email.clone()) {
         Ok(service) => {
             if settings.email.test_mode {
-                println!("Email service initialized in TEST MODE");
+                tracing::info!("Email service initialized in TEST MODE");
             } else {
-                println!("Email service initialized with SMTP: {}:{}", 
+                tracing::info!("Email service initialized with SMTP: {}:{}", 
                     settings.email.smtp_host, settings.email.smtp_port);
             }
             Arc::new(service)
         },
-        Err(e) => {
+        Err(e) if settings.startup.email_required => {
             return Err(anyhow::anyhow!("Failed to initialize email service: {}", e));
         }
+        Err(e) => {
+            tracing::warn!("Failed to initialize email service: {:#}. Continuing without email.", e);
+            Arc::new(aerugo::email::EmailService::disabled(settings.email.clone()))
+        }
     };
 
+    // Load the key used to sign outgoing webhooks
+    let webhook_signer = Arc::new(
+        aerugo::webhooks::WebhookSigner::load(&db_pool, &settings.webhooks, &settings.secrets)
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to load webhook signing key: {}", e))?
+    );
+    tracing::info!("Webhook signing key loaded: {}", webhook_signer.key_id());
+
     // Create shared application state
     let state = AppState {
         db_pool: db_pool.clone(),
         config: settings.clone(),
         storage,
         cache,
-        manifest_cache: Arc::new(tokio::sync::RwLock::new(std::collections::HashMap::new())),
         email_service,
+        webhook_signer,
+        log_stream: Arc::new(aerugo::log_stream::LogStream::new(settings.log_tail.buffer_size)),
+        standby: Arc::new(aerugo::standby::Standby::new(&settings.standby)),
+        federation: Arc::new(aerugo::federation::Federation::new(&settings.federation)),
+        push_validators: Arc::new(aerugo::push_hooks::PushValidators::new()),
+        notifier: Arc::new(aerugo::notifications::Notifier::new(&settings.notifications, &settings.server)),
+        transfer_limits: Arc::new(aerugo::handlers::transfer_limits::TransferLimits::new(&settings.concurrency)),
+        runtime_config: Arc::new(aerugo::config::reload::RuntimeConfig::new(&settings)),
+        features: Arc::new(aerugo::features::FeatureFlags::new(&settings.features)),
+        read_replica: Arc::new(aerugo::read_replica::ReadReplica::new(read_replica)),
     };
-    println!("Application state created successfully");
+    tracing::info!("Application state created successfully");
+
+    // Re-compress gzip layers to zstd in the background when enabled
+    aerugo::transcode::spawn_transcoder(state.clone());
+
+    // Follow the primary's replication state while running as a warm standby
+    aerugo::standby::spawn_standby_monitor(state.clone());
+
+    // Route reads to the read replica while it is reachable and caught up
+    aerugo::read_replica::spawn_read_replica_monitor(state.clone());
+
+    // Apply the feature flag overrides set by administrators, and pick up later ones
+    if let Err(e) = state.features.refresh(&db_pool).await {
+        tracing::warn!("Failed to load feature flag overrides: {}", e);
+    }
+    aerugo::features::spawn_feature_refresher(state.clone());
+
+    // Delete blobs queued by organization deletion and other cleanups
+    aerugo::gc::spawn_blob_gc(state.clone());
+
+    // Deliver repository events to organization webhooks
+    aerugo::webhooks::delivery::spawn_webhook_dispatcher(state.clone());
+
+    // Recompute tag cleanup suggestions from pull statistics
+    aerugo::tag_cleanup::spawn_tag_cleanup_analyzer(state.clone());
+
+    // Purge the downstream CDN when tags move or content is removed
+    aerugo::cdn::spawn_cdn_purger(state.clone());
+
+    // Write pull and push counts buffered in the cache
+    aerugo::activity::spawn_activity_flusher(state.clone());
+
+    // Drop entries other replicas invalidate from the memory cache
+    aerugo::cache::spawn_invalidation_listener(state.clone());
+
+    // Load popular repositories' tags and manifests into the cache
+    aerugo::cache_warmup::spawn_cache_warmup(state.clone());
+
+    // Record audit events in the event history
+    aerugo::events::spawn_event_recorder(state.clone());
+
+    // Send distribution-style notifications to the endpoints of NOTIFICATIONS_CONFIG_FILE
+    aerugo::notifications::spawn_notification_senders(state.clone());
+
+    // Reload the log level, cache TTLs, rate limits and notification endpoints on SIGHUP
+    aerugo::config::reload::spawn_sighup_listener(state.clone());
+
+    // Publish audit events to NATS JetStream or Kafka
+    aerugo::event_stream::spawn_event_publisher(state.clone());
 
-    // Start background task to cleanup expired API keys
+    // Email tags pushed to watched repositories
+    aerugo::watches::spawn_watch_notifier(state.clone());
+
+    // Queue and run background jobs for pushed images (vulnerability scans, SBOMs, signatures)
+    aerugo::jobs::spawn_job_workers(state.clone());
+
+    // Start background task to cleanup expired API keys and refresh tokens and enforce data retention
     let cleanup_db_pool = db_pool.clone();
+    let cleanup_log_stream = state.log_stream.clone();
+    let retention = settings.retention.clone();
     tokio::spawn(async move {
         let mut interval = tokio::time::interval(Duration::from_secs(3600)); // Run every hour
         loop {
             interval.tick().await;
-            if let Err(e) = aerugo::handlers::auth::cleanup_expired_api_keys(&cleanup_db_pool).await {
+            let grace_days = retention.deleted_data_grace_days;
+            if let Err(e) = aerugo::handlers::auth::cleanup_expired_api_keys(&cleanup_db_pool, grace_days).await {
                 tracing::error!("Failed to cleanup expired API keys: {}", e);
             }
+            if let Err(e) = aerugo::handlers::auth::cleanup_expired_refresh_tokens(&cleanup_db_pool, grace_days).await {
+                tracing::error!("Failed to cleanup expired refresh tokens: {}", e);
+            }
+            if let Err(e) = aerugo::auth::cleanup_expired_revocations(&cleanup_db_pool, grace_days).await {
+                tracing::error!("Failed to cleanup expired token revocations: {}", e);
+            }
+            if let Err(e) = aerugo::handlers::api_usage::cleanup_old_api_usage(&cleanup_db_pool, retention.usage_retention_days).await {
+                tracing::error!("Failed to cleanup old API usage: {}", e);
+            }
+            if let Err(e) = aerugo::events::cleanup_old_events(&cleanup_db_pool, retention.event_retention_days).await {
+                tracing::error!("Failed to cleanup old events: {}", e);
+            }
+            if let Err(e) = aerugo::webhooks::delivery::cleanup_old_deliveries(&cleanup_db_pool).await {
+                tracing::error!("Failed to cleanup old webhook deliveries: {}", e);
+            }
+            let cutoff = chrono::Utc::now() - chrono::Duration::days(retention.audit_log_retention_days);
+            cleanup_log_stream.prune_before(cutoff);
         }
     });
-    println!("Background API key cleanup task started");
+    tracing::info!("Background API key and refresh token cleanup task started");
 
     // Create application using lib.rs
     let app = create_app(state).await;
-    println!("Application created successfully");
-
-    // Run server
-    println!("Preparing to start server...");
-    let listen_address = settings.server.bind_address.clone();
-    println!("Listen address: {}", listen_address);
-    let addr: std::net::SocketAddr = listen_address.parse()?;
-    println!("Parsed address: {}", addr);
-    
-    println!("Creating TCP listener...");
-    let listener = tokio::net::TcpListener::bind(addr).await?;
-    println!("TCP listener created successfully");
-    
-    tracing::info!("listening on {}", addr);
-    println!("Starting axum server...");
-    axum::serve(listener, app).await?;
+    tracing::info!("Application created successfully");
+
+    // Run server on every listener, terminating TLS itself when a certificate or ACME is configured
+    tracing::info!("Starting axum server...");
+    aerugo::listeners::serve(&settings, app, std::future::pending()).await?;
+    Ok(())
+}
+
+async fn migrate(settings: &Settings, args: &MigrateArgs) -> Result<()> {
+    let db_pool = aerugo::db::connect_pool(settings)
+        .await
+        .context("Failed to connect to database")?;
+
+    if args.check {
+        return migrate_check(&db_pool).await;
+    }
+    if let Some(count) = args.down {
+        return migrate_down(settings, &db_pool, count as usize).await;
+    }
+    migrate_up(&db_pool).await
+}
+
+async fn migrate_check(db_pool: &sqlx::PgPool) -> Result<()> {
+    let status = aerugo::db::check_schema_compatibility(db_pool).await?;
+    let current = status.current_version.map(|v| v.to_string()).unwrap_or_else(|| "none".to_string());
+    if status.pending > 0 {
+        anyhow::bail!(
+            "{} pending migration(s): schema version {}, this release expects {}",
+            status.pending,
+            current,
+            aerugo::db::max_supported_schema_version()
+        );
+    }
+    println!("✅ Database schema is up to date (version {})", current);
+    Ok(())
+}
+
+async fn migrate_up(db_pool: &sqlx::PgPool) -> Result<()> {
+    println!("🗄️  Running database migrations only");
+    let status = aerugo::db::check_schema_compatibility(db_pool).await?;
+    if status.pending == 0 {
+        println!("✅ Database schema is up to date (version {})", aerugo::db::max_supported_schema_version());
+        return Ok(());
+    }
+
+    println!("Applying {} pending migration(s)...", status.pending);
+    aerugo::db::run_migrations(db_pool).await?;
+    println!("✅ Database schema migrated to version {}", aerugo::db::max_supported_schema_version());
+    Ok(())
+}
+
+async fn migrate_down(settings: &Settings, db_pool: &sqlx::PgPool, count: usize) -> Result<()> {
+    println!("⏪ Reverting the last {} migration(s)", count);
+    let reverted = aerugo::db::revert_migrations(db_pool, count).await?;
+    for version in &reverted {
+        println!("  reverted {}", version);
+    }
+
+    let status = aerugo::db::check_schema_compatibility(db_pool).await?;
+    println!(
+        "✅ Database schema is at version {}",
+        status.current_version.map(|v| v.to_string()).unwrap_or_else(|| "none".to_string())
+    );
+    if settings.database.auto_migrate {
+        println!("⚠️  DATABASE_AUTO_MIGRATE is enabled: this release will re-apply them when it starts");
+    }
+    Ok(())
+}
+
+async fn bootstrap_only(settings: &Settings) -> Result<()> {
+    println!("🌱 Bootstrapping instance");
+    let db_pool = aerugo::db::create_pool(settings)
+        .await
+        .context("Failed to create database pool and run migrations")?;
+
+    let report = aerugo::bootstrap::run(&db_pool, settings).await?;
+    println!("✅ Bootstrap complete: {}", report.summary());
     Ok(())
 }
 
@@ -1,5 +1,183 @@
 use chrono::{NaiveDateTime, DateTime, Utc};
 use serde::{Serialize, Deserialize};
+use utoipa::ToSchema;
+
+/// What an API key may be used for across the whole API. Scopes are cumulative: `push`
+/// includes `read`, `admin` includes everything a user session can do. Keys may instead
+/// hold [`ResourceScope`]s that are limited to one area of the management API.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ApiKeyScope {
+    /// Read-only API calls and image pulls
+    Read,
+    /// Read access plus image pushes
+    Push,
+    /// Full access, including managing organizations, repositories and keys
+    Admin,
+}
+
+impl std::fmt::Display for ApiKeyScope {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ApiKeyScope::Read => write!(f, "read"),
+            ApiKeyScope::Push => write!(f, "push"),
+            ApiKeyScope::Admin => write!(f, "admin"),
+        }
+    }
+}
+
+impl std::str::FromStr for ApiKeyScope {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "read" => Ok(ApiKeyScope::Read),
+            "push" => Ok(ApiKeyScope::Push),
+            "admin" => Ok(ApiKeyScope::Admin),
+            _ => Err(format!("Invalid API key scope: {}", s)),
+        }
+    }
+}
+
+impl ApiKeyScope {
+    /// Whether a key holding `scopes` (as stored) may act at the `required` level.
+    /// Resource scopes count at their level here (`write` as `push`); which resource they
+    /// cover is checked per route by `enforce_api_key_scopes`. Unknown stored values grant nothing.
+    pub fn granted_by(required: ApiKeyScope, scopes: &[String]) -> bool {
+        scopes.iter().any(|s| match s.parse::<ApiKeyScope>() {
+            Ok(scope) => scope >= required,
+            Err(_) => s
+                .parse::<ResourceScope>()
+                .is_ok_and(|scope| scope.level.as_key_scope() >= required),
+        })
+    }
+
+    /// Whether `scope` is a value an API key may be created with
+    pub fn is_valid(scope: &str) -> bool {
+        scope.parse::<ApiKeyScope>().is_ok() || scope.parse::<ResourceScope>().is_ok()
+    }
+}
+
+/// Area of the management API a resource scope is limited to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ApiResource {
+    /// Organizations, their members, teams, invitations and IP rules
+    Org,
+    /// Repositories, collaborators and blob storage; also `docker pull`/`push`/delete
+    Repo,
+    /// Webhook configuration and signing keys
+    Webhook,
+    /// Usage and storage statistics
+    Stats,
+    /// The caller's own account, sessions and API keys
+    User,
+    /// Registry administration: users, cache, standby, logs and federation peers
+    Registry,
+}
+
+impl ApiResource {
+    pub const ALL: [ApiResource; 6] = [
+        ApiResource::Org,
+        ApiResource::Repo,
+        ApiResource::Webhook,
+        ApiResource::Stats,
+        ApiResource::User,
+        ApiResource::Registry,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ApiResource::Org => "org",
+            ApiResource::Repo => "repo",
+            ApiResource::Webhook => "webhook",
+            ApiResource::Stats => "stats",
+            ApiResource::User => "user",
+            ApiResource::Registry => "registry",
+        }
+    }
+}
+
+/// Access level of a resource scope. Levels are cumulative: `write` includes `read`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ScopeLevel {
+    Read,
+    Write,
+    Admin,
+}
+
+impl ScopeLevel {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ScopeLevel::Read => "read",
+            ScopeLevel::Write => "write",
+            ScopeLevel::Admin => "admin",
+        }
+    }
+
+    /// Whole-API scope at the same level
+    pub fn as_key_scope(&self) -> ApiKeyScope {
+        match self {
+            ScopeLevel::Read => ApiKeyScope::Read,
+            ScopeLevel::Write => ApiKeyScope::Push,
+            ScopeLevel::Admin => ApiKeyScope::Admin,
+        }
+    }
+}
+
+/// A scope limited to one resource, written `resource:level` (e.g. `org:read`, `repo:admin`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResourceScope {
+    pub resource: ApiResource,
+    pub level: ScopeLevel,
+}
+
+impl ResourceScope {
+    pub const fn new(resource: ApiResource, level: ScopeLevel) -> Self {
+        Self { resource, level }
+    }
+
+    /// Whether a key holding `scopes` (as stored) covers this scope. Whole-API scopes cover
+    /// every resource at their level, with `push` also covering `repo:write`.
+    pub fn granted_by(&self, scopes: &[String]) -> bool {
+        scopes.iter().any(|s| match s.parse::<ApiKeyScope>() {
+            Ok(ApiKeyScope::Admin) => true,
+            Ok(ApiKeyScope::Push) => {
+                self.level == ScopeLevel::Read
+                    || (self.resource == ApiResource::Repo && self.level == ScopeLevel::Write)
+            }
+            Ok(ApiKeyScope::Read) => self.level == ScopeLevel::Read,
+            Err(_) => s
+                .parse::<ResourceScope>()
+                .is_ok_and(|held| held.resource == self.resource && held.level >= self.level),
+        })
+    }
+}
+
+impl std::fmt::Display for ResourceScope {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.resource.as_str(), self.level.as_str())
+    }
+}
+
+impl std::str::FromStr for ResourceScope {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let lower = s.to_lowercase();
+        let (resource, level) = lower
+            .split_once(':')
+            .ok_or_else(|| format!("Invalid API key scope: {}", s))?;
+        let resource = ApiResource::ALL
+            .into_iter()
+            .find(|r| r.as_str() == resource)
+            .ok_or_else(|| format!("Invalid API key scope: {}", s))?;
+        let level = [ScopeLevel::Read, ScopeLevel::Write, ScopeLevel::Admin]
+            .into_iter()
+            .find(|l| l.as_str() == level)
+            .ok_or_else(|| format!("Invalid API key scope: {}", s))?;
+        Ok(Self { resource, level })
+    }
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ApiKey {
@@ -7,6 +185,7 @@ pub struct ApiKey {
     pub user_id: i64,
     pub name: Option<String>,
     pub key_hash: String,
+    pub scopes: Vec<String>,
     pub expires_at: Option<NaiveDateTime>,
     pub last_used_at: Option<NaiveDateTime>,
     pub is_active: Option<bool>,
@@ -20,3 +199,41 @@ pub struct NewApiKey {
     pub key_hash: String,
     pub expires_at: Option<NaiveDateTime>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{ApiKeyScope, ApiResource, ResourceScope, ScopeLevel};
+
+    #[test]
+    fn scopes_are_cumulative() {
+        let push = vec!["push".to_string()];
+        assert!(ApiKeyScope::granted_by(ApiKeyScope::Read, &push));
+        assert!(ApiKeyScope::granted_by(ApiKeyScope::Push, &push));
+        assert!(!ApiKeyScope::granted_by(ApiKeyScope::Admin, &push));
+        assert!(!ApiKeyScope::granted_by(ApiKeyScope::Read, &["bogus".to_string()]));
+    }
+
+    #[test]
+    fn resource_scopes_are_limited_to_their_resource() {
+        let stats = vec!["stats:read".to_string()];
+        assert!(ResourceScope::new(ApiResource::Stats, ScopeLevel::Read).granted_by(&stats));
+        assert!(!ResourceScope::new(ApiResource::Repo, ScopeLevel::Read).granted_by(&stats));
+        assert!(!ResourceScope::new(ApiResource::Stats, ScopeLevel::Write).granted_by(&stats));
+
+        let repo_admin = vec!["repo:admin".to_string()];
+        assert!(ResourceScope::new(ApiResource::Repo, ScopeLevel::Write).granted_by(&repo_admin));
+        assert!(!ResourceScope::new(ApiResource::Org, ScopeLevel::Read).granted_by(&repo_admin));
+        assert!(ApiKeyScope::granted_by(ApiKeyScope::Admin, &repo_admin));
+    }
+
+    #[test]
+    fn whole_api_scopes_cover_every_resource_at_their_level() {
+        let push = vec!["push".to_string()];
+        assert!(ResourceScope::new(ApiResource::Org, ScopeLevel::Read).granted_by(&push));
+        assert!(ResourceScope::new(ApiResource::Repo, ScopeLevel::Write).granted_by(&push));
+        assert!(!ResourceScope::new(ApiResource::Webhook, ScopeLevel::Write).granted_by(&push));
+        assert!(ResourceScope::new(ApiResource::Registry, ScopeLevel::Admin).granted_by(&["admin".to_string()]));
+        assert!("Webhook:Write".parse::<ResourceScope>().is_ok());
+        assert!("webhook:delete".parse::<ResourceScope>().is_err());
+    }
+}
@@ -0,0 +1,49 @@
+use chrono::{NaiveDate, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::ToSchema;
+
+/// Usage totals for one credential over the requested window
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct CredentialUsage {
+    /// `api_key` or `session`
+    pub credential_type: String,
+    /// API key ID, or the session's refresh token family ID
+    pub credential_id: String,
+    /// API key name or session device name
+    pub label: Option<String>,
+    /// Whether the credential can still be used
+    pub active: bool,
+    pub request_count: i64,
+    pub error_count: i64,
+    /// Number of distinct endpoints called
+    pub endpoint_count: i64,
+    pub last_seen_at: Option<NaiveDateTime>,
+}
+
+/// Requests made by a credential on one day
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct DailyUsage {
+    pub day: NaiveDate,
+    pub request_count: i64,
+    pub error_count: i64,
+}
+
+/// Requests made by a credential to one endpoint
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct EndpointUsage {
+    /// HTTP method and route template
+    pub endpoint: String,
+    pub request_count: i64,
+    pub error_count: i64,
+    pub last_seen_at: Option<NaiveDateTime>,
+}
+
+/// Daily and per-endpoint breakdown for one credential
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CredentialUsageDetail {
+    pub credential_type: String,
+    pub credential_id: String,
+    pub days: Vec<DailyUsage>,
+    pub endpoints: Vec<EndpointUsage>,
+}
@@ -0,0 +1,60 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::{IntoParams, ToSchema};
+
+#[derive(Debug, Serialize, Deserialize, Clone, FromRow, ToSchema)]
+pub struct ContentTakedown {
+    /// Unique takedown ID
+    pub id: i64,
+    /// Repository taken down, `None` once it has been deleted
+    pub repository_id: Option<i64>,
+    /// Repository name (namespace/name) at the time of the takedown
+    pub repository: String,
+    /// Digest taken down; `None` takes down the whole repository
+    pub digest: Option<String>,
+    /// Why the content was taken down, shown to clients that try to pull it
+    pub reason: String,
+    /// External reference such as a DMCA notice or ticket number
+    pub notice_reference: Option<String>,
+    /// Evidence snapshot state: capturing, completed or failed
+    pub evidence_status: String,
+    /// Storage prefix the evidence snapshot was written to
+    pub evidence_prefix: Option<String>,
+    /// Error message if capturing the evidence failed
+    pub evidence_error: Option<String>,
+    /// Administrator who took the content down
+    pub created_by: Option<i64>,
+    pub created_at: DateTime<Utc>,
+    /// Administrator who reinstated the content
+    pub reinstated_by: Option<i64>,
+    /// When the content was reinstated (None while the takedown is active)
+    pub reinstated_at: Option<DateTime<Utc>>,
+    /// Why the content was reinstated
+    pub reinstatement_note: Option<String>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateTakedownRequest {
+    /// Repository to act on, as namespace/name
+    pub repository: String,
+    /// Take down only this digest (a manifest or a layer); omit to take down the whole repository
+    pub digest: Option<String>,
+    /// Why the content is being taken down; shown to clients that try to pull it
+    pub reason: String,
+    /// External reference such as a DMCA notice or ticket number
+    pub notice_reference: Option<String>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct ReinstateTakedownRequest {
+    /// Why the content is being reinstated (counter-notice, withdrawn claim, ...)
+    pub note: String,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct TakedownListQuery {
+    /// Only list takedowns that have not been reinstated (default false)
+    #[serde(default)]
+    pub active: bool,
+}
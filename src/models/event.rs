@@ -0,0 +1,82 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::{IntoParams, ToSchema};
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct EventQuery {
+    /// Events per page (default 50, at most 200)
+    pub limit: Option<i64>,
+    /// Only events older than this ID; pass the previous page's `next_before`
+    pub before: Option<i64>,
+    /// Exact action, or a prefix ending at a dot: `manifest` matches `manifest.push`
+    pub action: Option<String>,
+}
+
+/// One recorded registry event
+#[derive(Debug, Serialize, FromRow, ToSchema)]
+pub struct RegistryEvent {
+    pub id: i64,
+    /// Dotted action name: `manifest.push`, `manifest.pull`, `tag.delete`, `repository.collaborator.set`, ...
+    pub action: String,
+    pub occurred_at: DateTime<Utc>,
+    pub user_id: Option<i64>,
+    pub username: Option<String>,
+    /// `namespace/repository` at the time of the event
+    pub repository: Option<String>,
+    pub detail: Option<String>,
+}
+
+/// One page of events, newest first
+#[derive(Debug, Serialize, ToSchema)]
+pub struct EventPage {
+    pub events: Vec<RegistryEvent>,
+    /// `before` of the next page, absent on the last page
+    pub next_before: Option<i64>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct EventStreamQuery {
+    /// Only events of this organization and its repositories
+    pub org: Option<String>,
+    /// Only events of this `namespace/repository`
+    pub repo: Option<String>,
+    /// Exact action, or a prefix ending at a dot: `manifest` matches `manifest.push`
+    pub action: Option<String>,
+    /// Number of recent events to send before live ones (default 0, at most 100)
+    pub replay: Option<usize>,
+    /// `native` (default) or `cloudevents`
+    pub format: Option<StreamFormat>,
+}
+
+/// Data of each message of the live event stream
+#[derive(Debug, Clone, Copy, Default, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum StreamFormat {
+    /// An event shaped like the event history's
+    #[default]
+    Native,
+    /// The same event as the `data` of a CloudEvents 1.0 structured-mode envelope
+    Cloudevents,
+}
+
+/// Output of an event export
+#[derive(Debug, Clone, Copy, Default, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    /// A header row, then one row per event
+    #[default]
+    Csv,
+    /// One JSON event per line (NDJSON)
+    Json,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct AuditExportQuery {
+    /// Only events at or after this time (RFC 3339)
+    pub from: Option<DateTime<Utc>>,
+    /// Only events before this time (RFC 3339)
+    pub to: Option<DateTime<Utc>>,
+    /// `csv` (default) or `json`
+    pub format: Option<ExportFormat>,
+}
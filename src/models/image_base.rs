@@ -0,0 +1,31 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::models::vulnerability::SeverityCounts;
+
+/// The base an image was built from, and whether rebuilding it is recommended
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ImageBase {
+    pub digest: String,
+    /// Tags pointing at the image, directly or through an index
+    pub tags: Vec<String>,
+    /// The base as the image names it, e.g. `docker.io/library/alpine:3.20`
+    pub base_name: Option<String>,
+    /// How the base was identified: `annotation`, `label` or `layers`
+    pub source: String,
+    /// `namespace/repository` of the base when it is stored in this registry
+    pub base_repository: Option<String>,
+    pub base_tag: Option<String>,
+    /// Digest of the image built on
+    pub base_digest: Option<String>,
+    /// Digest the base tag points at now
+    pub current_base_digest: Option<String>,
+    /// Findings of the latest completed scan of the image built on
+    pub base_vulnerabilities: Option<SeverityCounts>,
+    pub rebuild_recommended: bool,
+    /// `base_updated` when the base tag points at another image, `base_vulnerable` when the image
+    /// built on has vulnerabilities of `BASE_IMAGE_ADVISORY_SEVERITY` or worse
+    pub reasons: Vec<String>,
+    pub detected_at: DateTime<Utc>,
+}
@@ -0,0 +1,77 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+use crate::models::tag_listing::Platform;
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct ImageDetailQuery {
+    /// Platform to show when the reference is an index, as `os/architecture[/variant]`
+    /// (default `linux/amd64`, or the index's first platform)
+    pub platform: Option<String>,
+}
+
+/// Everything an image inspector shows about one image
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ImageDetail {
+    /// `namespace/name`
+    pub repository: String,
+    /// Digest of the image manifest shown
+    pub digest: String,
+    pub media_type: String,
+    /// Digest of the index the image was picked from, when the reference was an index
+    pub index_digest: Option<String>,
+    /// Platforms of that index
+    pub platforms: Vec<Platform>,
+    pub os: Option<String>,
+    pub architecture: Option<String>,
+    pub variant: Option<String>,
+    pub created: Option<DateTime<Utc>>,
+    pub author: Option<String>,
+    pub config: RunConfig,
+    pub layers: Vec<ImageLayer>,
+    /// Build steps, oldest first, including those that added no layer
+    pub history: Vec<HistoryEntry>,
+    /// Bytes of the config and layers as stored
+    pub total_compressed_size: i64,
+}
+
+/// How a container of the image runs, from the image config
+#[derive(Debug, Default, Serialize, Deserialize, ToSchema)]
+pub struct RunConfig {
+    #[serde(rename(deserialize = "Entrypoint"), default)]
+    pub entrypoint: Option<Vec<String>>,
+    #[serde(rename(deserialize = "Cmd"), default)]
+    pub cmd: Option<Vec<String>>,
+    #[serde(rename(deserialize = "Env"), default)]
+    pub env: Option<Vec<String>>,
+    #[serde(rename(deserialize = "WorkingDir"), default)]
+    pub working_dir: Option<String>,
+    #[serde(rename(deserialize = "User"), default)]
+    pub user: Option<String>,
+    #[serde(rename(deserialize = "ExposedPorts"), default)]
+    pub exposed_ports: Option<BTreeMap<String, serde_json::Value>>,
+    #[serde(rename(deserialize = "Labels"), default)]
+    pub labels: Option<BTreeMap<String, String>>,
+}
+
+#[derive(Debug, PartialEq, Serialize, ToSchema)]
+pub struct ImageLayer {
+    pub digest: String,
+    pub media_type: String,
+    pub size: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct HistoryEntry {
+    #[serde(default)]
+    pub created: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub created_by: Option<String>,
+    #[serde(default)]
+    pub comment: Option<String>,
+    #[serde(default)]
+    pub empty_layer: bool,
+}
@@ -0,0 +1,69 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::ToSchema;
+
+/// Whether a matching client address is admitted or rejected
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum IpRuleAction {
+    Allow,
+    Deny,
+}
+
+impl std::fmt::Display for IpRuleAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            IpRuleAction::Allow => write!(f, "allow"),
+            IpRuleAction::Deny => write!(f, "deny"),
+        }
+    }
+}
+
+/// Registry traffic a rule applies to
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum IpRuleOperation {
+    /// Pulls and pushes
+    All,
+    /// Manifest, blob, and tag reads
+    Pull,
+    /// Uploads, manifest writes, and deletes
+    Push,
+}
+
+impl std::fmt::Display for IpRuleOperation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            IpRuleOperation::All => write!(f, "all"),
+            IpRuleOperation::Pull => write!(f, "pull"),
+            IpRuleOperation::Push => write!(f, "push"),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, FromRow, ToSchema)]
+pub struct IpAccessRule {
+    pub id: i64,
+    /// Owning organization; `None` for rules that apply to the whole registry
+    pub organization_id: Option<i64>,
+    /// Network in CIDR notation, e.g. `10.0.0.0/8`
+    pub cidr: String,
+    /// `allow` or `deny`
+    pub action: String,
+    /// `all`, `pull` or `push`
+    pub operation: String,
+    pub description: Option<String>,
+    pub created_by: Option<i64>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateIpAccessRuleRequest {
+    /// Network in CIDR notation; a bare address matches only that host
+    pub cidr: String,
+    pub action: IpRuleAction,
+    /// Traffic the rule applies to (default: all)
+    pub operation: Option<IpRuleOperation>,
+    pub description: Option<String>,
+}
@@ -0,0 +1,104 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::{IntoParams, ToSchema};
+
+/// Work the background workers do for a pushed manifest
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum JobKind {
+    /// Vulnerability scan with Trivy
+    Scan,
+    /// SBOM generated with Trivy and attached as a referrer
+    Sbom,
+    /// Packages of a pushed SBOM referrer indexed for search
+    SbomIndex,
+    /// Cosign signatures of an image verified against the configured keys and keyless policy
+    Signature,
+    /// Base image of a pushed image identified, and images built on an earlier image of its tag advised
+    BaseImage,
+}
+
+impl JobKind {
+    pub const ALL: [JobKind; 5] = [JobKind::Scan, JobKind::Sbom, JobKind::SbomIndex, JobKind::Signature, JobKind::BaseImage];
+}
+
+impl std::fmt::Display for JobKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            JobKind::Scan => write!(f, "scan"),
+            JobKind::Sbom => write!(f, "sbom"),
+            JobKind::SbomIndex => write!(f, "sbom_index"),
+            JobKind::Signature => write!(f, "signature"),
+            JobKind::BaseImage => write!(f, "base_image"),
+        }
+    }
+}
+
+impl std::str::FromStr for JobKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "scan" => Ok(JobKind::Scan),
+            "sbom" => Ok(JobKind::Sbom),
+            "sbom_index" => Ok(JobKind::SbomIndex),
+            "signature" => Ok(JobKind::Signature),
+            "base_image" => Ok(JobKind::BaseImage),
+            _ => Err(format!("Invalid job kind: {}", s)),
+        }
+    }
+}
+
+/// A queued, running or finished background job
+#[derive(Debug, Clone, Serialize, FromRow, ToSchema)]
+pub struct Job {
+    pub id: i64,
+    /// `scan`, `sbom`, `sbom_index`, `signature` or `base_image`
+    pub kind: String,
+    /// `namespace/repository`
+    pub repository: String,
+    pub manifest_digest: String,
+    /// `queued`, `running`, `succeeded` or `failed`
+    pub status: String,
+    /// Attempts started so far
+    pub attempts: i32,
+    pub max_attempts: i32,
+    /// Why the latest attempt failed
+    pub last_error: Option<String>,
+    /// When a queued job is next picked up
+    pub run_after: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct JobQuery {
+    /// `queued`, `running`, `succeeded` or `failed`
+    pub status: Option<String>,
+    /// `scan`, `sbom`, `sbom_index`, `signature` or `base_image`
+    pub kind: Option<String>,
+    /// Jobs per page (default 30, at most 100)
+    pub limit: Option<i64>,
+    /// Only jobs older than this ID; pass the previous page's `next_before`
+    pub before: Option<i64>,
+}
+
+/// Jobs of one status
+#[derive(Debug, Clone, Serialize, FromRow, ToSchema)]
+pub struct JobCount {
+    pub kind: String,
+    pub status: String,
+    pub count: i64,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct JobPage {
+    /// Newest first
+    pub jobs: Vec<Job>,
+    /// `before` of the next page, absent on the last page
+    pub next_before: Option<i64>,
+    /// Jobs per kind and status of the listed repository or the whole queue, ignoring the other filters
+    pub counts: Vec<JobCount>,
+}
@@ -0,0 +1,74 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::ToSchema;
+
+#[derive(Debug, Serialize, Deserialize, Clone, FromRow, ToSchema)]
+pub struct LegalHold {
+    /// Unique legal hold ID
+    pub id: i64,
+    /// Organization under hold
+    pub organization_id: i64,
+    /// Why the hold was placed (case number, request reference, ...)
+    pub reason: String,
+    /// Whether deletes, transfers and tag overwrites in the organization are rejected while the
+    /// hold is active
+    pub block_deletes: bool,
+    /// Export state: exporting, completed, incomplete (blobs missing from storage) or failed
+    pub status: String,
+    /// Storage prefix the export was written to
+    pub export_prefix: Option<String>,
+    /// Number of blobs included in the export
+    pub blob_count: i64,
+    /// Total size of exported blobs in bytes
+    pub total_size: i64,
+    /// Error message if the export failed
+    pub error: Option<String>,
+    /// User who placed the hold
+    pub created_by: Option<i64>,
+    /// When the hold was placed
+    pub created_at: DateTime<Utc>,
+    /// When the export finished
+    pub completed_at: Option<DateTime<Utc>>,
+    /// User who released the hold
+    pub released_by: Option<i64>,
+    /// When the hold was released (None while active)
+    pub released_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateLegalHoldRequest {
+    /// Why the hold is being placed
+    pub reason: String,
+    /// Reject deletes, transfers and tag overwrites in the organization until the hold is
+    /// released (default: true)
+    pub block_deletes: Option<bool>,
+}
+
+/// One exported blob in the legal hold export manifest
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct LegalHoldExportEntry {
+    /// Repository the blob belongs to (namespace/name)
+    pub repository: String,
+    /// Content digest as recorded by the registry
+    pub digest: String,
+    /// Media type recorded for the blob
+    pub media_type: String,
+    /// Size in bytes of the exported content
+    pub size: u64,
+    /// SHA-256 checksum computed over the exported bytes
+    pub sha256: String,
+    /// Whether the computed checksum matches the recorded digest
+    pub verified: bool,
+}
+
+/// A blob recorded by the registry that was missing from storage during export
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct LegalHoldMissingBlob {
+    /// Repository the blob belongs to (namespace/name)
+    pub repository: String,
+    /// Content digest as recorded by the registry
+    pub digest: String,
+    /// Media type recorded for the blob
+    pub media_type: String,
+}
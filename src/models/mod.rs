@@ -4,3 +4,32 @@ pub mod repository;
 pub mod repository_with_org;
 pub mod user;
 pub mod api_key;
+pub mod refresh_token;
+pub mod legal_hold;
+pub mod api_usage;
+pub mod repository_collaborator;
+pub mod webhook;
+pub mod team;
+pub mod ip_access_rule;
+pub mod organization_invitation;
+pub mod content_takedown;
+pub mod model_artifact;
+pub mod organization_secret;
+pub mod tag_cleanup;
+pub mod quota_tier;
+pub mod push_hook;
+pub mod repository_insights;
+pub mod repository_stats;
+pub mod tag_listing;
+pub mod image_detail;
+pub mod event;
+pub mod watch;
+pub mod vulnerability;
+pub mod job;
+pub mod sbom;
+pub mod signature;
+pub mod security_policy;
+pub mod quarantine;
+pub mod secret_finding;
+pub mod image_base;
+pub mod security_summary;
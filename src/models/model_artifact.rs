@@ -0,0 +1,85 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+/// Structured part of a model card
+#[derive(Debug, Serialize, Deserialize, Clone, Default, ToSchema)]
+pub struct ModelCardMetadata {
+    /// Framework the weights are saved for, e.g. `pytorch`, `onnx`, `gguf`
+    pub framework: Option<String>,
+    /// SPDX license identifier or license name
+    pub license: Option<String>,
+    /// Task the model performs, e.g. `text-generation`
+    pub task: Option<String>,
+    /// Number of parameters
+    pub parameters: Option<i64>,
+    /// Datasets the model was trained or evaluated on
+    #[serde(default)]
+    pub datasets: Vec<String>,
+    /// Evaluation results by metric name
+    #[serde(default)]
+    pub metrics: BTreeMap<String, f64>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct AttachModelCardRequest {
+    #[serde(default)]
+    pub metadata: ModelCardMetadata,
+    /// Free-form description in Markdown
+    #[serde(default)]
+    pub markdown: String,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ModelCard {
+    /// Manifest digest of the model version the card describes
+    pub subject: String,
+    /// Digest of the card artifact
+    pub digest: String,
+    pub metadata: ModelCardMetadata,
+    pub markdown: String,
+    /// `markdown` rendered to HTML; raw HTML in the source is escaped
+    pub html: String,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct ModelCardQuery {
+    /// `html` returns the rendered card as a page instead of JSON
+    pub format: Option<String>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateLineageRequest {
+    /// Repository of the parent model as namespace/name (defaults to this repository)
+    pub parent_repository: Option<String>,
+    /// Tag or digest of the parent model version
+    pub parent_reference: String,
+    /// How this version derives from the parent, e.g. `fine-tuned-from`, `quantized-from`
+    pub relation: String,
+}
+
+/// One edge between two model versions
+#[derive(Debug, Serialize, ToSchema)]
+pub struct LineageLink {
+    /// Repository of the other model version as namespace/name
+    pub repository: String,
+    /// Manifest digest of the other model version
+    pub digest: String,
+    pub relation: String,
+    /// Digest of the lineage artifact recording the link
+    pub artifact_digest: String,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ModelLineage {
+    /// Manifest digest of the model version
+    pub digest: String,
+    /// Versions this one derives from
+    pub parents: Vec<LineageLink>,
+    /// Versions derived from this one, in repositories the caller can pull from
+    pub children: Vec<LineageLink>,
+}
@@ -0,0 +1,43 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::ToSchema;
+use validator::Validate;
+
+use super::organizations::OrganizationRole;
+
+/// An invitation to join an organization, sent to an email address
+#[derive(Debug, Serialize, Deserialize, Clone, FromRow, ToSchema)]
+pub struct OrganizationInvitation {
+    pub id: i64,
+    pub organization_id: i64,
+    pub email: String,
+    /// Role the membership is created with on acceptance
+    pub role: String,
+    pub invited_by: Option<i64>,
+    /// `pending`, `accepted`, `declined` or `revoked`
+    pub status: String,
+    pub expires_at: DateTime<Utc>,
+    pub responded_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// What the holder of an invite link is shown before responding
+#[derive(Debug, Serialize, Deserialize, Clone, FromRow, ToSchema)]
+pub struct InvitationPreview {
+    pub organization_id: i64,
+    pub organization_name: String,
+    pub organization_display_name: String,
+    pub email: String,
+    pub role: String,
+    pub invited_by_username: Option<String>,
+    pub status: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct CreateInvitationRequest {
+    #[validate(email)]
+    pub email: String,
+    pub role: OrganizationRole,
+}
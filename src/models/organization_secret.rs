@@ -0,0 +1,20 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::ToSchema;
+
+/// A secret variable of an organization; its value is never returned
+#[derive(Debug, Clone, Serialize, FromRow, ToSchema)]
+pub struct OrganizationSecret {
+    /// Environment variable name the value is injected as
+    pub name: String,
+    pub created_by: Option<i64>,
+    pub created_at: DateTime<Utc>,
+    pub updated_by: Option<i64>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct PutOrganizationSecretRequest {
+    pub value: String,
+}
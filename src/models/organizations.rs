@@ -1,9 +1,9 @@
 // src/models/organization.rs
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 use validator::Validate;
-use utoipa::ToSchema;
+use utoipa::{IntoParams, ToSchema};
 
 #[derive(Debug, Serialize, Deserialize, Clone, FromRow, ToSchema)]
 pub struct Organization {
@@ -27,7 +27,7 @@ pub struct Organization {
 
 #[derive(Debug, Deserialize, Validate, ToSchema)]
 pub struct CreateOrganizationRequest {
-    /// Organization name (3-50 characters, URL-friendly)
+    /// Organization name (3-50 lowercase letters and digits, separated by `.`, `_`, `__` or `-`; not a reserved name)
     #[validate(length(min = 3, max = 50))]
     pub name: String,
     /// Display name (1-100 characters)
@@ -70,19 +70,36 @@ pub struct OrganizationMember {
     pub email: String,
 }
 
+/// Role of a member within an organization.
+///
+/// Permission matrix:
+///
+/// | Action                         | Owner | Maintainer | Developer | Guest |
+/// |--------------------------------|-------|------------|-----------|-------|
+/// | Pull (including private repos) |   ✓   |     ✓      |     ✓     |   ✓   |
+/// | Push / create repositories     |   ✓   |     ✓      |     ✓     |       |
+/// | Update repository settings     |   ✓   |     ✓      |           |       |
+/// | Delete repositories/manifests  |   ✓   |     ✓      |           |       |
+/// | Manage members and org profile |   ✓   |     ✓      |           |       |
+/// | Delete org, legal holds        |   ✓   |            |           |       |
+/// | Organization webhooks          |   ✓   |            |           |       |
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, ToSchema)]
 pub enum OrganizationRole {
     Owner,
-    Admin,
-    Member,
+    #[serde(alias = "Admin")]
+    Maintainer,
+    #[serde(alias = "Member")]
+    Developer,
+    Guest,
 }
 
 impl std::fmt::Display for OrganizationRole {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             OrganizationRole::Owner => write!(f, "owner"),
-            OrganizationRole::Admin => write!(f, "admin"),
-            OrganizationRole::Member => write!(f, "member"),
+            OrganizationRole::Maintainer => write!(f, "maintainer"),
+            OrganizationRole::Developer => write!(f, "developer"),
+            OrganizationRole::Guest => write!(f, "guest"),
         }
     }
 }
@@ -93,8 +110,10 @@ impl std::str::FromStr for OrganizationRole {
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s.to_lowercase().as_str() {
             "owner" => Ok(OrganizationRole::Owner),
-            "admin" => Ok(OrganizationRole::Admin),
-            "member" => Ok(OrganizationRole::Member),
+            // "admin" and "member" are the pre-RBAC role names
+            "maintainer" | "admin" => Ok(OrganizationRole::Maintainer),
+            "developer" | "member" => Ok(OrganizationRole::Developer),
+            "guest" => Ok(OrganizationRole::Guest),
             _ => Err(format!("Invalid organization role: {}", s)),
         }
     }
@@ -112,32 +131,189 @@ pub struct UpdateMemberRequest {
     pub role: OrganizationRole,
 }
 
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct MemberListQuery {
+    /// Only return members with this role
+    pub role: Option<OrganizationRole>,
+}
+
+/// What deleting an organization removes, returned with the token that confirms it
+#[derive(Debug, Serialize, ToSchema)]
+pub struct OrganizationDeletionPreview {
+    pub organization: String,
+    pub repositories: i64,
+    pub tags: i64,
+    pub manifests: i64,
+    pub members: i64,
+    /// Pass back in `DELETE /api/v1/organizations/{id}` before `expires_at`
+    pub confirmation_token: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct DeleteOrganizationRequest {
+    pub confirmation_token: String,
+}
+
+/// Defaults applied to repositories created in an organization, and its storage limit
+#[derive(Debug, Serialize, Clone, FromRow, ToSchema)]
+pub struct OrganizationSettings {
+    pub organization_id: i64,
+    /// Visibility of new repositories when the creator does not choose one, including
+    /// repositories created implicitly by `docker push`
+    pub default_repository_public: bool,
+    /// Number of most recent tags new repositories keep; unset keeps all
+    pub default_tag_retention_keep_last: Option<i32>,
+    /// Age in days after which new repositories drop tags; unset keeps them indefinitely
+    pub default_tag_retention_days: Option<i32>,
+    /// Total bytes of manifests and layers the organization may store; unset is unlimited
+    pub storage_quota_bytes: Option<i64>,
+    /// Rate each blob download from the organization is held to unless the repository sets
+    /// its own; unset falls back to `BANDWIDTH_DOWNLOAD_BYTES_PER_SECOND`
+    pub download_bytes_per_second: Option<i64>,
+    pub updated_by: Option<i64>,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+impl OrganizationSettings {
+    /// Settings of an organization that has never saved any
+    pub fn defaults(organization_id: i64) -> Self {
+        Self {
+            organization_id,
+            default_repository_public: false,
+            default_tag_retention_keep_last: None,
+            default_tag_retention_days: None,
+            storage_quota_bytes: None,
+            download_bytes_per_second: None,
+            updated_by: None,
+            updated_at: None,
+        }
+    }
+}
+
+/// Replaces all of an organization's settings; omitted limits are removed
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct UpdateOrganizationSettingsRequest {
+    pub default_repository_public: bool,
+    #[validate(range(min = 1, max = 10000))]
+    pub default_tag_retention_keep_last: Option<i32>,
+    #[validate(range(min = 1, max = 3650))]
+    pub default_tag_retention_days: Option<i32>,
+    #[validate(range(min = 0))]
+    pub storage_quota_bytes: Option<i64>,
+    #[validate(range(min = 1024))]
+    pub download_bytes_per_second: Option<i64>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct OrganizationStatsQuery {
+    /// Days covered by the daily series and the top repositories, counting today (default 30, at most 365)
+    pub days: Option<i32>,
+}
+
+/// Usage dashboard of an organization
+#[derive(Debug, Serialize, ToSchema)]
+pub struct OrganizationStats {
+    pub organization_id: i64,
+    pub repository_count: i64,
+    pub public_repository_count: i64,
+    /// Bytes of manifests and layers stored across the organization's repositories
+    pub storage_used_bytes: i64,
+    /// Storage quota from the organization settings; unset is unlimited
+    pub storage_quota_bytes: Option<i64>,
+    /// Pull and push totals over the last 1, 7 and 30 days
+    pub windows: Vec<ActivityWindow>,
+    /// Pulls and pushes per day, oldest first, including days without activity
+    pub daily: Vec<DailyActivity>,
+    /// Most pulled repositories over the requested period
+    pub top_repositories: Vec<RepositoryUsage>,
+}
+
+#[derive(Debug, Serialize, FromRow, ToSchema)]
+pub struct ActivityWindow {
+    pub days: i32,
+    pub pulls: i64,
+    pub pushes: i64,
+}
+
+#[derive(Debug, Serialize, FromRow, ToSchema)]
+pub struct DailyActivity {
+    pub day: NaiveDate,
+    pub pulls: i64,
+    pub pushes: i64,
+}
+
+#[derive(Debug, Serialize, FromRow, ToSchema)]
+pub struct RepositoryUsage {
+    pub name: String,
+    pub is_public: bool,
+    pub pulls: i64,
+    pub pushes: i64,
+    pub storage_bytes: i64,
+}
+
 impl OrganizationRole {
+    pub fn can_pull(&self) -> bool {
+        true
+    }
+
+    pub fn can_push(&self) -> bool {
+        !matches!(self, OrganizationRole::Guest)
+    }
+
+    pub fn can_create_repositories(&self) -> bool {
+        !matches!(self, OrganizationRole::Guest)
+    }
+
+    pub fn can_manage_repositories(&self) -> bool {
+        matches!(self, OrganizationRole::Owner | OrganizationRole::Maintainer)
+    }
+
+    pub fn can_delete_repositories(&self) -> bool {
+        matches!(self, OrganizationRole::Owner | OrganizationRole::Maintainer)
+    }
+
     pub fn can_manage_members(&self) -> bool {
-        matches!(self, OrganizationRole::Owner | OrganizationRole::Admin)
+        matches!(self, OrganizationRole::Owner | OrganizationRole::Maintainer)
     }
 
     pub fn can_manage_organization(&self) -> bool {
-        matches!(self, OrganizationRole::Owner | OrganizationRole::Admin)
+        matches!(self, OrganizationRole::Owner | OrganizationRole::Maintainer)
     }
 
     pub fn can_delete_organization(&self) -> bool {
         matches!(self, OrganizationRole::Owner)
     }
 
+    pub fn can_manage_ip_rules(&self) -> bool {
+        matches!(self, OrganizationRole::Owner)
+    }
+
+    pub fn can_manage_webhooks(&self) -> bool {
+        matches!(self, OrganizationRole::Owner)
+    }
+
+    pub fn can_export_audit_log(&self) -> bool {
+        matches!(self, OrganizationRole::Owner)
+    }
+
+    pub fn can_manage_security_policies(&self) -> bool {
+        matches!(self, OrganizationRole::Owner)
+    }
+
     pub fn can_remove_member(&self, target_role: &OrganizationRole) -> bool {
         match self {
             OrganizationRole::Owner => true,
-            OrganizationRole::Admin => !matches!(target_role, OrganizationRole::Owner),
-            OrganizationRole::Member => false,
+            OrganizationRole::Maintainer => !matches!(target_role, OrganizationRole::Owner),
+            OrganizationRole::Developer | OrganizationRole::Guest => false,
         }
     }
 
     pub fn can_change_role_to(&self, target_role: &OrganizationRole) -> bool {
         match self {
             OrganizationRole::Owner => true,
-            OrganizationRole::Admin => !matches!(target_role, OrganizationRole::Owner),
-            OrganizationRole::Member => false,
+            OrganizationRole::Maintainer => !matches!(target_role, OrganizationRole::Owner),
+            OrganizationRole::Developer | OrganizationRole::Guest => false,
         }
     }
 }
@@ -0,0 +1,62 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::ToSchema;
+
+/// An HTTP endpoint asked to allow or deny each manifest pushed to an organization
+#[derive(Debug, Clone, Serialize, FromRow, ToSchema)]
+pub struct OrganizationPushHook {
+    pub id: i64,
+    pub organization_id: i64,
+    /// Shown to clients whose push the hook denies
+    pub name: String,
+    pub url: String,
+    /// How long a push waits for the verdict
+    pub timeout_ms: i32,
+    /// Whether pushes are accepted when the endpoint fails to give a verdict
+    pub fail_open: bool,
+    pub active: bool,
+    /// Whether calls carry `X-Aerugo-Signature-256`; the secret itself is never returned
+    pub has_secret: bool,
+    pub created_by: Option<i64>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub last_called_at: Option<DateTime<Utc>>,
+    /// `allow`, `deny` or `error`
+    pub last_verdict: Option<String>,
+    pub last_error: Option<String>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreatePushHookRequest {
+    pub name: String,
+    /// `http` or `https` endpoint receiving a POST per manifest push
+    pub url: String,
+    /// Shared secret for the HMAC signature header
+    pub secret: Option<String>,
+    /// Defaults to 5000, between 100 and 30000
+    pub timeout_ms: Option<i32>,
+    /// Defaults to false: pushes are denied while the endpoint is unavailable
+    pub fail_open: Option<bool>,
+    /// Defaults to true
+    pub active: Option<bool>,
+}
+
+/// Fields left out are unchanged; an empty `secret` removes it
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct UpdatePushHookRequest {
+    pub name: Option<String>,
+    pub url: Option<String>,
+    pub secret: Option<String>,
+    pub timeout_ms: Option<i32>,
+    pub fail_open: Option<bool>,
+    pub active: Option<bool>,
+}
+
+/// What a push hook endpoint answers
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct PushHookVerdict {
+    pub allowed: bool,
+    /// Why the push was denied; passed on to the client
+    pub reason: Option<String>,
+}
@@ -0,0 +1,28 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::FromRow;
+use utoipa::ToSchema;
+
+/// Whether a pushed image is held back from pulls
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct QuarantineStatus {
+    /// `quarantined` or `released`
+    pub state: String,
+    pub quarantined_at: DateTime<Utc>,
+    pub released_at: Option<DateTime<Utc>>,
+    /// `scan` or `approval`
+    pub release_reason: Option<String>,
+    /// Username of the administrator who approved the image
+    pub released_by: Option<String>,
+}
+
+/// A manifest waiting in quarantine
+#[derive(Debug, Clone, Serialize, FromRow, ToSchema)]
+pub struct QuarantinedManifest {
+    pub digest: String,
+    /// Tags pointing at the manifest
+    pub tags: Vec<String>,
+    pub quarantined_at: DateTime<Utc>,
+    /// Status of its vulnerability scan: `running` or `failed`; absent until the scan starts
+    pub scan_status: Option<String>,
+}
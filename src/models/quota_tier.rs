@@ -0,0 +1,62 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::ToSchema;
+use validator::Validate;
+
+/// A plan of limits assigned to organizations; unset limits are unlimited
+#[derive(Debug, Serialize, Deserialize, Clone, FromRow, ToSchema)]
+pub struct QuotaTier {
+    pub id: i64,
+    /// Unique name shown to users when a limit is hit, e.g. `free` or `team`
+    pub name: String,
+    pub description: Option<String>,
+    /// Total bytes of manifests and layers across the organization's repositories
+    pub max_storage_bytes: Option<i64>,
+    pub max_repositories: Option<i64>,
+    pub max_members: Option<i64>,
+    /// Highest rate a blob download from the organization may run at
+    pub download_bytes_per_second: Option<i64>,
+    /// Whether the tier applies to organizations without one of their own
+    pub is_default: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Creates a tier, or replaces all of its fields; omitted limits are unlimited
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct QuotaTierRequest {
+    #[validate(length(min = 1, max = 64))]
+    pub name: String,
+    #[validate(length(max = 500))]
+    pub description: Option<String>,
+    #[validate(range(min = 0))]
+    pub max_storage_bytes: Option<i64>,
+    #[validate(range(min = 0))]
+    pub max_repositories: Option<i64>,
+    /// Counts every member including owners, so at least 1
+    #[validate(range(min = 1))]
+    pub max_members: Option<i64>,
+    #[validate(range(min = 1024))]
+    pub download_bytes_per_second: Option<i64>,
+    /// Make this the default tier, replacing the current default
+    #[serde(default)]
+    pub is_default: bool,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct AssignQuotaTierRequest {
+    /// Name of the tier; `null` falls back to the default tier
+    pub tier: Option<String>,
+}
+
+/// An organization's tier and how much of each limit it uses
+#[derive(Debug, Serialize, ToSchema)]
+pub struct OrganizationQuota {
+    pub organization_id: i64,
+    /// Tier assigned to the organization, or the default tier; `None` is unlimited
+    pub tier: Option<QuotaTier>,
+    pub storage_used_bytes: i64,
+    pub repository_count: i64,
+    pub member_count: i64,
+}
@@ -0,0 +1,21 @@
+use chrono::NaiveDateTime;
+use serde::{Serialize, Deserialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+/// Opaque refresh token row. Only the SHA-256 hash of the token is stored;
+/// every token issued to the same device shares a `family_id` so the whole
+/// rotation chain can be revoked at once.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct RefreshToken {
+    pub id: i64,
+    pub user_id: i64,
+    pub family_id: Uuid,
+    pub token_hash: String,
+    pub device_name: Option<String>,
+    pub expires_at: NaiveDateTime,
+    pub revoked_at: Option<NaiveDateTime>,
+    pub replaced_by: Option<i64>,
+    pub last_used_at: Option<NaiveDateTime>,
+    pub created_at: Option<NaiveDateTime>,
+}
@@ -0,0 +1,74 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::ToSchema;
+
+/// Access granted to a single repository, independent of organization membership
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum CollaboratorPermission {
+    /// Pull images
+    Pull,
+    /// Pull and push images
+    Push,
+    /// Pull, push, delete, and manage collaborators
+    Admin,
+}
+
+impl std::fmt::Display for CollaboratorPermission {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CollaboratorPermission::Pull => write!(f, "pull"),
+            CollaboratorPermission::Push => write!(f, "push"),
+            CollaboratorPermission::Admin => write!(f, "admin"),
+        }
+    }
+}
+
+impl std::str::FromStr for CollaboratorPermission {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "pull" => Ok(CollaboratorPermission::Pull),
+            "push" => Ok(CollaboratorPermission::Push),
+            "admin" => Ok(CollaboratorPermission::Admin),
+            _ => Err(format!("Invalid collaborator permission: {}", s)),
+        }
+    }
+}
+
+impl CollaboratorPermission {
+    /// Whether this grant allows a registry operation ("pull", "push", "delete")
+    pub fn allows(&self, operation: &str) -> bool {
+        match operation {
+            "pull" => true,
+            "push" => *self >= CollaboratorPermission::Push,
+            "delete" => *self == CollaboratorPermission::Admin,
+            _ => false,
+        }
+    }
+
+    pub fn can_manage_collaborators(&self) -> bool {
+        *self == CollaboratorPermission::Admin
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, FromRow, ToSchema)]
+pub struct RepositoryCollaborator {
+    pub id: i64,
+    pub repository_id: i64,
+    pub user_id: i64,
+    pub permission: String,
+    pub granted_by: Option<i64>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    // User details (from JOIN)
+    pub username: String,
+    pub email: String,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct SetCollaboratorRequest {
+    pub permission: CollaboratorPermission,
+}
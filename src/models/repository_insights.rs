@@ -0,0 +1,81 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::models::organizations::DailyActivity;
+use crate::models::vulnerability::ScanSummary;
+
+/// Everything the repository overview shows, gathered in one response
+#[derive(Debug, Serialize, ToSchema)]
+pub struct RepositoryInsights {
+    /// `namespace/name`
+    pub repository: String,
+    pub pulls: PullTrend,
+    pub storage: StorageFootprint,
+    pub stale_tags: StaleTags,
+    pub signatures: SignatureCoverage,
+    pub policy: PolicyCompliance,
+    /// The most recently completed vulnerability scan of any of the repository's images
+    pub latest_scan: Option<ScanSummary>,
+    pub generated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PullTrend {
+    pub last_7_days: i64,
+    pub previous_7_days: i64,
+    pub last_30_days: i64,
+    /// Change of the last 7 days over the 7 before them; absent when there were no pulls before
+    pub change_percent: Option<f64>,
+    /// Pulls and pushes of the last 30 days, oldest first
+    pub daily: Vec<DailyActivity>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct StorageFootprint {
+    pub manifest_count: i64,
+    pub tag_count: i64,
+    /// Bytes of manifests and layers stored for the repository
+    pub total_bytes: i64,
+    /// Manifests no tag points to, counting neither indexes' children nor signatures
+    pub untagged_manifest_count: i64,
+    pub untagged_bytes: i64,
+    /// Usage of the whole organization and the limit it is held to, if any
+    pub organization_used_bytes: i64,
+    pub organization_limit_bytes: Option<i64>,
+}
+
+/// Summary of the latest tag cleanup analysis
+#[derive(Debug, Serialize, ToSchema)]
+pub struct StaleTags {
+    pub count: usize,
+    pub reclaimable_bytes: i64,
+    /// Absent until the repository is first analyzed
+    pub computed_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SignatureCoverage {
+    /// Manifests at least one tag points to
+    pub tagged_manifests: i64,
+    /// Tagged manifests with a Cosign or Notation signature, as a referrer or a `sha256-<hex>.sig` tag
+    pub signed_manifests: i64,
+    /// Absent when nothing is tagged
+    pub coverage_percent: Option<f64>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PolicyCompliance {
+    /// No tags outside the retention policy, storage within its limit and no active takedowns
+    pub compliant: bool,
+    pub tag_retention_keep_last: Option<i32>,
+    pub tag_retention_days: Option<i32>,
+    /// Tags the retention policy would remove
+    pub tags_outside_retention: i64,
+    pub storage_limit_exceeded: bool,
+    pub active_takedowns: i64,
+    /// Whether the organization is under a legal hold
+    pub legal_hold: bool,
+    /// Active push hooks validating pushes to the organization
+    pub push_hooks: i64,
+}
@@ -0,0 +1,43 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::{IntoParams, ToSchema};
+
+use crate::models::organizations::{ActivityWindow, DailyActivity};
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct RepositoryStatsQuery {
+    /// Days covered by the daily series, counting today (default 30, at most 365)
+    pub days: Option<i32>,
+}
+
+/// Popularity of a repository
+///
+/// Counts are written in batches, so they can trail the registry by up to
+/// `ACTIVITY_FLUSH_INTERVAL_SECONDS`.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct RepositoryStats {
+    /// `namespace/name`
+    pub repository: String,
+    /// Manifest pulls since counting began
+    pub pull_count: i64,
+    /// Manifest pushes since counting began
+    pub push_count: i64,
+    pub last_pulled_at: Option<DateTime<Utc>>,
+    pub last_pushed_at: Option<DateTime<Utc>>,
+    /// Pull and push totals over the last 1, 7 and 30 days
+    pub windows: Vec<ActivityWindow>,
+    /// Pulls and pushes per day, oldest first, including days without activity
+    pub daily: Vec<DailyActivity>,
+    /// Most pulled tags since counting began
+    pub top_tags: Vec<TagUsage>,
+}
+
+#[derive(Debug, Serialize, FromRow, ToSchema)]
+pub struct TagUsage {
+    pub tag: String,
+    pub digest: String,
+    pub pull_count: i64,
+    pub push_count: i64,
+    pub last_pulled_at: Option<DateTime<Utc>>,
+}
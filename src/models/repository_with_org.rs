@@ -36,6 +36,10 @@ pub struct RepositoryWithOrgRow {
     pub created_by: Option<i64>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    #[sqlx(default)]
+    pub pull_count: i64,
+    #[sqlx(default)]
+    pub push_count: i64,
     
     // Organization fields
     pub org_id: i64,
@@ -0,0 +1,112 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::{IntoParams, ToSchema};
+
+/// An SBOM document format
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum SbomFormat {
+    /// SPDX 2.x JSON, artifact type `application/spdx+json`
+    Spdx,
+    /// CycloneDX JSON, artifact type `application/vnd.cyclonedx+json`
+    Cyclonedx,
+}
+
+impl SbomFormat {
+    pub const ALL: [SbomFormat; 2] = [SbomFormat::Spdx, SbomFormat::Cyclonedx];
+
+    /// Artifact type of an SBOM referrer in this format, also the media type of its layer
+    pub fn artifact_type(self) -> &'static str {
+        match self {
+            SbomFormat::Spdx => "application/spdx+json",
+            SbomFormat::Cyclonedx => "application/vnd.cyclonedx+json",
+        }
+    }
+
+    pub fn from_artifact_type(artifact_type: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|format| format.artifact_type() == artifact_type)
+    }
+}
+
+impl std::fmt::Display for SbomFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SbomFormat::Spdx => write!(f, "spdx"),
+            SbomFormat::Cyclonedx => write!(f, "cyclonedx"),
+        }
+    }
+}
+
+impl std::str::FromStr for SbomFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "spdx" => Ok(SbomFormat::Spdx),
+            "cyclonedx" => Ok(SbomFormat::Cyclonedx),
+            _ => Err(format!("Invalid SBOM format: {}", s)),
+        }
+    }
+}
+
+/// A package listed in an SBOM
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, ToSchema)]
+pub struct SbomPackage {
+    pub name: String,
+    pub version: Option<String>,
+    /// Package URL, e.g. `pkg:apk/alpine/openssl@3.1.4-r5`
+    pub purl: Option<String>,
+}
+
+/// An SBOM stored as a referrer of an image
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct SbomArtifact {
+    /// The image the SBOM describes
+    pub subject: String,
+    /// Digest of the SBOM's referrer manifest
+    pub digest: String,
+    pub format: SbomFormat,
+    pub packages: usize,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct SbomQuery {
+    /// `spdx` or `cyclonedx`; the newest SBOM of any format when unset
+    pub format: Option<String>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct PackageSearchQuery {
+    /// Package name, matched exactly but case-insensitively
+    pub name: String,
+    /// Package version, matched exactly
+    pub version: Option<String>,
+    /// Only images in this organization
+    pub org: Option<String>,
+    /// Matches per page (default 50, at most 200)
+    pub limit: Option<i64>,
+    /// Only matches after this one; pass the previous page's `next_after`
+    pub after: Option<i64>,
+}
+
+/// An image whose SBOM lists a package
+#[derive(Debug, Clone, Serialize, FromRow, ToSchema)]
+pub struct PackageMatch {
+    pub id: i64,
+    /// `namespace/repository`
+    pub repository: String,
+    /// Digest of the image
+    pub digest: String,
+    /// Tags pointing to the image
+    pub tags: Vec<String>,
+    pub name: String,
+    pub version: Option<String>,
+    pub purl: Option<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PackageSearchPage {
+    pub matches: Vec<PackageMatch>,
+    /// `after` of the next page, absent on the last page
+    pub next_after: Option<i64>,
+}
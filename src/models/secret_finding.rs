@@ -0,0 +1,29 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::{IntoParams, ToSchema};
+
+/// A likely credential found in a pushed image
+#[derive(Debug, Clone, Serialize, FromRow, ToSchema)]
+pub struct SecretFinding {
+    pub id: i64,
+    pub manifest_digest: String,
+    /// Tag or digest the image was pushed by
+    pub reference: String,
+    /// `aws_access_key_id`, `aws_secret_access_key`, `private_key`, `github_token`, `gitlab_token`,
+    /// `slack_token`, `stripe_secret_key` or `google_api_key`
+    pub kind: String,
+    /// `config:<JSON pointer>` for the image config, `<layer digest>:<path>:<line>` for a layer file
+    pub location: String,
+    /// The start of the match, the rest masked
+    pub excerpt: String,
+    /// Whether the push was rejected
+    pub rejected: bool,
+    pub found_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct SecretFindingQuery {
+    /// Only findings in this manifest
+    pub digest: Option<String>,
+}
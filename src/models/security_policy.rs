@@ -0,0 +1,84 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::ToSchema;
+
+/// What a security policy checks
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PolicyRule {
+    /// Refuse pulls of images whose latest scan found a vulnerability of `severity` or worse
+    BlockSeverity,
+    /// Refuse pulls of images without a verified signature
+    RequireSignature,
+    /// Refuse pushes to tags matching one of `tag_patterns`
+    DenyTags,
+}
+
+impl std::fmt::Display for PolicyRule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PolicyRule::BlockSeverity => write!(f, "block_severity"),
+            PolicyRule::RequireSignature => write!(f, "require_signature"),
+            PolicyRule::DenyTags => write!(f, "deny_tags"),
+        }
+    }
+}
+
+impl std::str::FromStr for PolicyRule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "block_severity" => Ok(PolicyRule::BlockSeverity),
+            "require_signature" => Ok(PolicyRule::RequireSignature),
+            "deny_tags" => Ok(PolicyRule::DenyTags),
+            _ => Err(format!("Invalid policy rule: {}", s)),
+        }
+    }
+}
+
+/// A rule checked on manifest pulls or pushes in an organization's repositories
+#[derive(Debug, Clone, Serialize, FromRow, ToSchema)]
+pub struct SecurityPolicy {
+    pub id: i64,
+    pub organization_id: i64,
+    /// The one repository the policy applies to; `None` for all of the organization's
+    pub repository_id: Option<i64>,
+    pub repository: Option<String>,
+    /// Shown to clients the policy denies
+    pub name: String,
+    /// `block_severity`, `require_signature` or `deny_tags`
+    pub rule: String,
+    /// `CRITICAL`, `HIGH`, `MEDIUM` or `LOW`, for `block_severity`
+    pub severity: Option<String>,
+    /// Tag patterns such as `latest` or `dev-*`, for `deny_tags`
+    pub tag_patterns: Vec<String>,
+    pub active: bool,
+    pub created_by: Option<i64>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateSecurityPolicyRequest {
+    pub name: String,
+    pub rule: PolicyRule,
+    /// Repository of the organization the policy is limited to; all of them when absent
+    pub repository: Option<String>,
+    /// Required for `block_severity`
+    pub severity: Option<String>,
+    /// Required for `deny_tags`; `*` matches any run of characters
+    pub tag_patterns: Option<Vec<String>>,
+    /// Defaults to true
+    pub active: Option<bool>,
+}
+
+/// Fields left out are unchanged; the rule and repository of a policy are fixed
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct UpdateSecurityPolicyRequest {
+    pub name: Option<String>,
+    pub severity: Option<String>,
+    pub tag_patterns: Option<Vec<String>>,
+    pub active: Option<bool>,
+}
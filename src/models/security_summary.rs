@@ -0,0 +1,56 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::models::vulnerability::SeverityCounts;
+
+/// Scan results, signature coverage and policy compliance of every repository of an organization
+#[derive(Debug, Serialize, ToSchema)]
+pub struct OrganizationSecuritySummary {
+    pub organization_id: i64,
+    pub repository_count: i64,
+    /// Totals over all repositories
+    pub images: ImageSecurity,
+    /// Repositories with their own totals, worst first: most critical, then high findings, then
+    /// images violating a policy
+    pub repositories: Vec<RepositorySecurity>,
+    /// Active security policies of the organization
+    pub active_policies: i64,
+    /// Secrets found in images pushed to the organization during the last 30 days
+    pub secret_findings_30_days: i64,
+    pub generated_at: DateTime<Utc>,
+}
+
+/// What is known about the tagged images of a repository or organization
+#[derive(Debug, Clone, Default, PartialEq, Serialize, ToSchema)]
+pub struct ImageSecurity {
+    /// Images at least one tag points to, not counting signatures and other referrers
+    pub tagged_images: i64,
+    /// Tagged images with a completed vulnerability scan
+    pub scanned_images: i64,
+    pub scan_coverage_percent: Option<f64>,
+    /// Findings of the latest completed scans of the tagged images
+    pub vulnerabilities: SeverityCounts,
+    /// Scanned images with at least one critical or high finding
+    pub vulnerable_images: i64,
+    /// Tagged images with a signature stored next to them
+    pub signed_images: i64,
+    pub signature_coverage_percent: Option<f64>,
+    /// Tagged images whose signature was verified against the trust policy
+    pub verified_images: i64,
+    /// Tagged images a pull would be refused for by a security policy or `require_signature`
+    pub noncompliant_images: i64,
+    /// Images held back until scanned or approved
+    pub quarantined_images: i64,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct RepositorySecurity {
+    /// `namespace/name`
+    pub repository: String,
+    pub is_public: bool,
+    pub require_signature: bool,
+    pub quarantine_pushes: bool,
+    pub images: ImageSecurity,
+    pub secret_findings_30_days: i64,
+}
@@ -0,0 +1,57 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+/// Whether an image carries a cosign or Notation signature the registry trusts
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum SignatureState {
+    /// No signature is attached
+    Unsigned,
+    /// A signature is attached but has not been verified yet
+    Pending,
+    /// A signature verified against a configured key, keyless policy or Notation trust policy
+    Verified,
+    /// Signatures are attached but none verified
+    Invalid,
+}
+
+impl std::fmt::Display for SignatureState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SignatureState::Unsigned => write!(f, "unsigned"),
+            SignatureState::Pending => write!(f, "pending"),
+            SignatureState::Verified => write!(f, "verified"),
+            SignatureState::Invalid => write!(f, "invalid"),
+        }
+    }
+}
+
+/// Signature verification status of an image
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct SignatureStatus {
+    pub state: SignatureState,
+    /// The key or keyless identity the signature matched
+    pub signer: Option<String>,
+    /// When the signature was last verified
+    pub verified_at: Option<DateTime<Utc>>,
+}
+
+impl SignatureStatus {
+    /// Status from a stored verification (`verified` or `invalid`, with signer and time) and
+    /// whether a signature is attached
+    pub fn from_parts(
+        status: Option<&str>,
+        signer: Option<String>,
+        verified_at: Option<DateTime<Utc>>,
+        has_signature: bool,
+    ) -> Self {
+        let state = match status {
+            Some("verified") => SignatureState::Verified,
+            Some(_) => SignatureState::Invalid,
+            None if has_signature => SignatureState::Pending,
+            None => SignatureState::Unsigned,
+        };
+        SignatureStatus { state, signer, verified_at }
+    }
+}
@@ -0,0 +1,44 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+/// A tag suggested for removal
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct TagCleanupCandidate {
+    pub tag: String,
+    /// Manifest digest the tag points to
+    pub digest: String,
+    /// Why the tag is suggested, e.g. `superseded by 1.4.3`
+    pub reason: String,
+    /// When the tag was last pushed
+    pub updated_at: DateTime<Utc>,
+    pub last_pulled_at: Option<DateTime<Utc>>,
+    pub pull_count: i64,
+    /// Bytes freed by removing this tag alone
+    pub reclaimable_bytes: i64,
+}
+
+/// Retention settings that would keep the tags the suggestions keep
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SuggestedRetentionPolicy {
+    pub tag_retention_keep_last: Option<i32>,
+    pub tag_retention_days: Option<i32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct TagCleanupSuggestions {
+    /// `namespace/name`
+    pub repository: String,
+    pub tag_count: i64,
+    pub candidates: Vec<TagCleanupCandidate>,
+    /// Bytes freed by removing every candidate, counting layers shared with kept tags once
+    pub estimated_savings_bytes: i64,
+    pub suggested_policy: SuggestedRetentionPolicy,
+    pub computed_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct TagCleanupQuery {
+    /// Recompute now instead of returning the last background analysis
+    pub refresh: Option<bool>,
+}
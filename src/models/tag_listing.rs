@@ -0,0 +1,61 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+use crate::models::quarantine::QuarantineStatus;
+use crate::models::signature::SignatureStatus;
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct ListTagsQuery {
+    /// Case-insensitive substring of the tag name
+    pub search: Option<String>,
+    /// `pushed` (default) or `name`
+    pub sort: Option<String>,
+    /// `desc` (default for `pushed`) or `asc` (default for `name`)
+    pub order: Option<String>,
+    /// Page size (default 50, at most 200)
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+/// One page of a repository's tags
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TagPage {
+    /// Tags matching the search, across all pages
+    pub total: i64,
+    pub limit: i64,
+    pub offset: i64,
+    pub tags: Vec<TagDetail>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TagDetail {
+    pub name: String,
+    pub digest: String,
+    pub media_type: String,
+    /// Bytes of the config and layers as stored, summed over the platforms of an index;
+    /// absent when the manifest content is unavailable
+    pub compressed_size: Option<i64>,
+    /// Platforms of an image index, without attestation manifests; empty for single images
+    pub platforms: Vec<Platform>,
+    /// When the tag last moved
+    pub pushed_at: DateTime<Utc>,
+    /// Username of whoever last pushed the tag; absent for tags copied by clones or pushed before
+    /// it was recorded
+    pub pushed_by: Option<String>,
+    /// Whether the image has a verified cosign or Notation signature
+    pub signature: SignatureStatus,
+    /// Whether the image is held back from pulls; absent unless it was pushed while the repository
+    /// quarantined pushes
+    pub quarantine: Option<QuarantineStatus>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct Platform {
+    pub os: String,
+    pub architecture: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub variant: Option<String>,
+    /// Digest of the platform's image manifest
+    pub digest: String,
+}
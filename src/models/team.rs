@@ -0,0 +1,62 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::ToSchema;
+
+use super::repository_collaborator::CollaboratorPermission;
+
+/// A group of organization members that is granted repository access as a unit
+#[derive(Debug, Serialize, Deserialize, Clone, FromRow, ToSchema)]
+pub struct Team {
+    pub id: i64,
+    pub organization_id: i64,
+    pub name: String,
+    pub description: Option<String>,
+    pub created_by: Option<i64>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub member_count: i64,
+    pub repository_count: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, FromRow, ToSchema)]
+pub struct TeamMember {
+    pub user_id: i64,
+    pub username: String,
+    pub email: String,
+    pub added_at: DateTime<Utc>,
+}
+
+/// A repository the team has been granted access to
+#[derive(Debug, Serialize, Deserialize, Clone, FromRow, ToSchema)]
+pub struct TeamRepository {
+    pub repository_id: i64,
+    pub repository_name: String,
+    pub permission: String,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct TeamDetails {
+    #[serde(flatten)]
+    pub team: Team,
+    pub members: Vec<TeamMember>,
+    pub repositories: Vec<TeamRepository>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateTeamRequest {
+    pub name: String,
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct UpdateTeamRequest {
+    pub name: Option<String>,
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct SetTeamRepositoryRequest {
+    pub permission: CollaboratorPermission,
+}
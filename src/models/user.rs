@@ -19,6 +19,8 @@ pub struct UserResponse {
     pub username: String,
     /// Email address
     pub email: String,
+    /// Uploaded avatar, served from `/api/v1/avatars/`
+    pub avatar_url: Option<String>,
     /// When the user was created
     pub created_at: chrono::DateTime<chrono::Utc>,
 }
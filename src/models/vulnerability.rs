@@ -0,0 +1,104 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::{IntoParams, ToSchema};
+
+/// Severity of a vulnerability as the scanner rates it, most severe first
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, ToSchema)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum Severity {
+    Critical,
+    High,
+    Medium,
+    Low,
+    Unknown,
+}
+
+impl std::fmt::Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Severity::Critical => write!(f, "CRITICAL"),
+            Severity::High => write!(f, "HIGH"),
+            Severity::Medium => write!(f, "MEDIUM"),
+            Severity::Low => write!(f, "LOW"),
+            Severity::Unknown => write!(f, "UNKNOWN"),
+        }
+    }
+}
+
+impl std::str::FromStr for Severity {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_uppercase().as_str() {
+            "CRITICAL" => Ok(Severity::Critical),
+            "HIGH" => Ok(Severity::High),
+            "MEDIUM" => Ok(Severity::Medium),
+            "LOW" => Ok(Severity::Low),
+            "UNKNOWN" => Ok(Severity::Unknown),
+            _ => Err(format!("Invalid severity: {}", s)),
+        }
+    }
+}
+
+/// Vulnerabilities found per severity
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, ToSchema)]
+pub struct SeverityCounts {
+    pub critical: i32,
+    pub high: i32,
+    pub medium: i32,
+    pub low: i32,
+    pub unknown: i32,
+    pub total: i32,
+}
+
+/// The latest scan of a manifest
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ScanSummary {
+    pub digest: String,
+    /// `running`, `completed` or `failed`
+    pub status: String,
+    /// The scanner used, `trivy`
+    pub scanner: String,
+    pub summary: SeverityCounts,
+    /// Why the scan failed
+    pub error: Option<String>,
+    pub started_at: DateTime<Utc>,
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+/// A vulnerability found in a package of an image
+#[derive(Debug, Clone, Serialize, FromRow, ToSchema)]
+pub struct Vulnerability {
+    /// CVE or advisory ID, e.g. `CVE-2024-3094`
+    pub vulnerability_id: String,
+    pub package_name: String,
+    pub installed_version: String,
+    /// Absent when no fixed version is known
+    pub fixed_version: Option<String>,
+    pub severity: String,
+    pub title: Option<String>,
+    pub primary_url: Option<String>,
+    /// The OS or file the package was found in, e.g. `alpine 3.19` or `app/package-lock.json`
+    pub target: String,
+}
+
+/// Scan results of the manifest a tag points to
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct TagVulnerabilities {
+    /// `namespace/repository`
+    pub repository: String,
+    pub tag: String,
+    pub digest: String,
+    /// Absent when the manifest was never scanned
+    pub scan: Option<ScanSummary>,
+    /// Most severe first
+    pub vulnerabilities: Vec<Vulnerability>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct VulnerabilityQuery {
+    /// Only list vulnerabilities of this severity or worse (`critical`, `high`, `medium`, `low`);
+    /// the summary always counts all of them
+    pub min_severity: Option<String>,
+}
@@ -0,0 +1,72 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::ToSchema;
+
+/// When a user is emailed about tags pushed to repositories they watch
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum PushEmail {
+    /// Emailed within about a minute of a push
+    Immediate,
+    /// One email a day listing every push since the last
+    Digest,
+    /// No push emails
+    Off,
+}
+
+impl std::fmt::Display for PushEmail {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PushEmail::Immediate => write!(f, "immediate"),
+            PushEmail::Digest => write!(f, "digest"),
+            PushEmail::Off => write!(f, "off"),
+        }
+    }
+}
+
+impl std::str::FromStr for PushEmail {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "immediate" => Ok(PushEmail::Immediate),
+            "digest" => Ok(PushEmail::Digest),
+            "off" => Ok(PushEmail::Off),
+            _ => Err(format!("Invalid push email preference: {}", s)),
+        }
+    }
+}
+
+/// A user's notification preferences
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct NotificationPreferences {
+    pub push_email: PushEmail,
+}
+
+/// Whether the current user watches a repository
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct WatchStatus {
+    pub watching: bool,
+    pub watched_since: Option<DateTime<Utc>>,
+}
+
+/// A repository the current user watches
+#[derive(Debug, Clone, Serialize, FromRow, ToSchema)]
+pub struct WatchedRepository {
+    pub repository_id: i64,
+    /// `namespace/repository`
+    pub repository: String,
+    pub watched_since: DateTime<Utc>,
+}
+
+/// A tag push waiting to be emailed to a watcher
+#[derive(Debug, Clone, FromRow)]
+pub struct WatchedPush {
+    pub id: i64,
+    pub repository: String,
+    pub tag: String,
+    pub digest: String,
+    pub pushed_by: Option<String>,
+    pub pushed_at: DateTime<Utc>,
+}
@@ -0,0 +1,141 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::{IntoParams, ToSchema};
+
+/// A public key receivers can use to verify `X-Aerugo-Signature-Ed25519`
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct WebhookSigningKey {
+    /// Matches the `keyid` parameter of the signature header
+    pub key_id: String,
+    /// Always `ed25519`
+    pub algorithm: String,
+    /// Raw 32-byte public key, base64-encoded
+    pub public_key: String,
+    /// The same key as a SubjectPublicKeyInfo PEM block
+    pub public_key_pem: String,
+    pub created_at: Option<DateTime<Utc>>,
+    /// Set on keys that no longer sign new deliveries but may still appear on retried ones
+    pub retired_at: Option<DateTime<Utc>>,
+}
+
+/// Keys published for webhook signature verification
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct WebhookSigningKeys {
+    /// Key currently used for new deliveries
+    pub active_key_id: String,
+    pub keys: Vec<WebhookSigningKey>,
+}
+
+/// A webhook endpoint receiving events from every repository of an organization
+#[derive(Debug, Clone, Serialize, FromRow, ToSchema)]
+pub struct OrganizationWebhook {
+    pub id: i64,
+    pub organization_id: i64,
+    pub url: String,
+    /// Action filters such as `manifest.push` or `repository`; empty receives every event
+    pub events: Vec<String>,
+    /// `json` for the signed JSON payload, `cloudevents` for it in a CloudEvents envelope, or
+    /// `slack` / `discord` for a templated chat message
+    pub format: String,
+    pub active: bool,
+    /// Whether deliveries carry `X-Aerugo-Signature-256`; the secret itself is never returned
+    pub has_secret: bool,
+    pub created_by: Option<i64>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub last_delivery_at: Option<DateTime<Utc>>,
+    /// HTTP status of the last delivery, absent when it failed before a response
+    pub last_delivery_status: Option<i32>,
+    pub last_delivery_error: Option<String>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateOrganizationWebhookRequest {
+    /// `http` or `https` endpoint receiving a POST per event
+    pub url: String,
+    /// Shared secret for the HMAC signature header
+    pub secret: Option<String>,
+    /// Action filters; an action matches itself and the dotted prefixes before it
+    #[serde(default)]
+    pub events: Vec<String>,
+    /// `json` (default), `cloudevents`, or `slack` / `discord` for a Slack incoming webhook or
+    /// Discord webhook URL
+    pub format: Option<String>,
+    /// Defaults to true
+    pub active: Option<bool>,
+}
+
+/// Fields left out are unchanged; an empty `secret` removes it
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct UpdateOrganizationWebhookRequest {
+    pub url: Option<String>,
+    pub secret: Option<String>,
+    pub events: Option<Vec<String>>,
+    pub format: Option<String>,
+    pub active: Option<bool>,
+}
+
+/// A webhook endpoint receiving events from one repository
+#[derive(Debug, Clone, Serialize, FromRow, ToSchema)]
+pub struct RepositoryWebhook {
+    pub id: i64,
+    pub repository_id: i64,
+    pub url: String,
+    /// Action filters such as `manifest.push` or `tag`; empty receives every event
+    pub events: Vec<String>,
+    /// `json` for the signed JSON payload, `cloudevents` for it in a CloudEvents envelope, or
+    /// `slack` / `discord` for a templated chat message
+    pub format: String,
+    pub active: bool,
+    /// Whether deliveries carry `X-Aerugo-Signature-256`; the secret itself is never returned
+    pub has_secret: bool,
+    pub created_by: Option<i64>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub last_delivery_at: Option<DateTime<Utc>>,
+    /// HTTP status of the last delivery, absent when it failed before a response
+    pub last_delivery_status: Option<i32>,
+    pub last_delivery_error: Option<String>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct WebhookDeliveryQuery {
+    /// Attempts per page (default 30, at most 100)
+    pub limit: Option<i64>,
+    /// Only attempts older than this ID; pass the previous page's `next_before`
+    pub before: Option<i64>,
+}
+
+/// One attempt to deliver an event to a webhook
+#[derive(Debug, Serialize, FromRow, ToSchema)]
+pub struct WebhookDelivery {
+    /// Used to redeliver the attempt's payload
+    pub id: i64,
+    /// Sent as `X-Aerugo-Delivery`; shared by the retries and redeliveries of one event
+    pub guid: String,
+    /// Sent as `X-Aerugo-Event`
+    pub event: String,
+    /// 1 for the first attempt, counting retries after it
+    pub attempt: i32,
+    /// Sent on request through the redeliver endpoint
+    pub redelivery: bool,
+    /// HTTP status of the response, absent when the request failed before one
+    pub status_code: Option<i32>,
+    pub duration_ms: i32,
+    /// The first 2 KiB of the response body
+    pub response_body: Option<String>,
+    pub error: Option<String>,
+    pub delivered_at: DateTime<Utc>,
+    /// The JSON body that was POSTed
+    #[schema(value_type = Object)]
+    pub payload: serde_json::Value,
+}
+
+/// One page of delivery attempts, newest first
+#[derive(Debug, Serialize, ToSchema)]
+pub struct WebhookDeliveryPage {
+    pub deliveries: Vec<WebhookDelivery>,
+    /// `before` of the next page, absent on the last page
+    pub next_before: Option<i64>,
+}
@@ -0,0 +1,146 @@
+// src/naming.rs - Organization and repository name rules
+//
+// Names follow the distribution spec: lowercase path components matching
+// `[a-z0-9]+((\.|_|__|-+)[a-z0-9]+)*`, separated by `/`, at most 255 characters in full.
+// Organization names are additionally checked against `NAMING_RESERVED_NAMES`, so nobody can
+// claim `v2`, `api` and similar names that collide with routes, and repository components
+// cannot be the keywords the registry API uses to split `/v2/<name>/...` paths.
+use std::fmt;
+
+use crate::config::settings::NamingSettings;
+
+/// Longest full repository name, `namespace/name` included
+pub const MAX_REPOSITORY_NAME_LENGTH: usize = 255;
+pub const MIN_ORGANIZATION_NAME_LENGTH: usize = 3;
+pub const MAX_ORGANIZATION_NAME_LENGTH: usize = 50;
+
+/// Names reserved when `NAMING_RESERVED_NAMES` is not set
+pub const DEFAULT_RESERVED_NAMES: &[&str] = &[
+    "_catalog", "admin", "api", "assets", "auth", "docs", "explore", "health", "login", "logout", "metrics",
+    "organizations", "repos", "search", "settings", "static", "token", "users", "v1", "v2",
+];
+
+/// Path segments of the registry API; a repository component named like one would make
+/// `/v2/<name>/...` ambiguous
+const ROUTE_KEYWORDS: &[&str] = &["blobs", "manifests", "tags", "uploads", "referrers", "_catalog"];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NameError {
+    Length { min: usize, max: usize },
+    Invalid(String),
+    Reserved(String),
+}
+
+impl fmt::Display for NameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NameError::Length { min, max } => write!(f, "Name must be {} to {} characters long", min, max),
+            NameError::Invalid(component) => write!(
+                f,
+                "'{}' is not a valid name: use lowercase letters and digits, separated by single '.', \
+                 single or double '_', or one or more '-'",
+                component
+            ),
+            NameError::Reserved(name) => write!(f, "'{}' is reserved and cannot be used as a name", name),
+        }
+    }
+}
+
+impl std::error::Error for NameError {}
+
+/// Check a new organization name
+pub fn validate_organization_name(name: &str, settings: &NamingSettings) -> Result<(), NameError> {
+    if !(MIN_ORGANIZATION_NAME_LENGTH..=MAX_ORGANIZATION_NAME_LENGTH).contains(&name.len()) {
+        return Err(NameError::Length { min: MIN_ORGANIZATION_NAME_LENGTH, max: MAX_ORGANIZATION_NAME_LENGTH });
+    }
+    if !is_valid_component(name) {
+        return Err(NameError::Invalid(name.to_string()));
+    }
+    if settings.reserved_names.iter().any(|reserved| reserved.eq_ignore_ascii_case(name)) {
+        return Err(NameError::Reserved(name.to_string()));
+    }
+    Ok(())
+}
+
+/// Check a new repository name within `namespace`; `name` may have several `/`-separated
+/// components
+pub fn validate_repository_name(namespace: &str, name: &str) -> Result<(), NameError> {
+    // `namespace/` counts towards the limit
+    let max = MAX_REPOSITORY_NAME_LENGTH.saturating_sub(namespace.len() + 1);
+    if name.is_empty() || name.len() > max {
+        return Err(NameError::Length { min: 1, max });
+    }
+    for component in name.split('/') {
+        if !is_valid_component(component) {
+            return Err(NameError::Invalid(component.to_string()));
+        }
+        if ROUTE_KEYWORDS.contains(&component) {
+            return Err(NameError::Reserved(component.to_string()));
+        }
+    }
+    Ok(())
+}
+
+/// Whether `component` matches `[a-z0-9]+((\.|_|__|-+)[a-z0-9]+)*`
+pub fn is_valid_component(component: &str) -> bool {
+    let bytes = component.as_bytes();
+    let alnum = |b: u8| b.is_ascii_lowercase() || b.is_ascii_digit();
+    if !bytes.first().is_some_and(|&b| alnum(b)) || !bytes.last().is_some_and(|&b| alnum(b)) {
+        return false;
+    }
+
+    let mut i = 0;
+    while i < bytes.len() {
+        if alnum(bytes[i]) {
+            i += 1;
+            continue;
+        }
+        // A separator run, always followed by an alphanumeric since the last byte is one
+        let start = i;
+        while i < bytes.len() && !alnum(bytes[i]) {
+            i += 1;
+        }
+        let separator = &component[start..i];
+        let allowed = matches!(separator, "." | "_" | "__") || separator.bytes().all(|b| b == b'-');
+        if !allowed {
+            return false;
+        }
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings() -> NamingSettings {
+        NamingSettings { reserved_names: DEFAULT_RESERVED_NAMES.iter().map(|s| s.to_string()).collect() }
+    }
+
+    #[test]
+    fn components_follow_the_distribution_spec() {
+        for valid in ["app", "my-app", "my--app", "my_app", "my__app", "v1.2", "a0"] {
+            assert!(is_valid_component(valid), "{} should be valid", valid);
+        }
+        for invalid in ["", "App", "-app", "app-", "my___app", "my._app", "my..app", "_catalog", "a b", "ünï"] {
+            assert!(!is_valid_component(invalid), "{} should be invalid", invalid);
+        }
+    }
+
+    #[test]
+    fn reserved_organization_names_are_rejected() {
+        assert_eq!(validate_organization_name("v2", &settings()), Err(NameError::Length { min: 3, max: 50 }));
+        assert_eq!(validate_organization_name("api", &settings()), Err(NameError::Reserved("api".to_string())));
+        assert_eq!(validate_organization_name("acme", &settings()), Ok(()));
+        assert!(validate_organization_name("Acme", &settings()).is_err());
+    }
+
+    #[test]
+    fn repository_names_are_checked_per_component() {
+        assert_eq!(validate_repository_name("acme", "team/api"), Ok(()));
+        assert_eq!(validate_repository_name("acme", "team/manifests"), Err(NameError::Reserved("manifests".to_string())));
+        assert!(validate_repository_name("acme", "team//app").is_err());
+        assert!(validate_repository_name("acme", &"a".repeat(251)).is_err());
+        assert_eq!(validate_repository_name("acme", &"a".repeat(250)), Ok(()));
+    }
+}
@@ -0,0 +1,572 @@
+// src/notifications.rs - docker/distribution-compatible registry notifications
+//
+// Endpoints are configured with the `notifications` section of a distribution config file
+// (`NOTIFICATIONS_CONFIG_FILE`) and receive the same Envelope/Event JSON distribution sends, so
+// listeners written for docker/distribution work unchanged. Manifest pulls, pushes and deletes,
+// blob pulls, pushes and mounts are observed on the registry routes once their response is
+// ready. Each endpoint has its own queue and sender: a failing endpoint is retried right away
+// up to `threshold` times and then every `backoff`, holding back later events like
+// distribution does, while other endpoints carry on.
+//
+// A configuration reload can replace the endpoints. New endpoints start with empty queues; what
+// replaced ones still have queued is sent out, unless the endpoint is failing.
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+
+use anyhow::{bail, Result};
+use axum::http::{Method, StatusCode};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::sync::mpsc;
+
+use crate::config::settings::{NotificationEndpoint, NotificationSettings, ServerSettings};
+use crate::AppState;
+
+pub const ENVELOPE_MEDIA_TYPE: &str = "application/vnd.docker.distribution.events.v1+json";
+/// Media type distribution reports for blobs
+const BLOB_MEDIA_TYPE: &str = "application/octet-stream";
+/// Events waiting per endpoint; more are dropped while an endpoint is down
+const QUEUE_CAPACITY: usize = 10_000;
+/// Events sent in one envelope
+const MAX_BATCH: usize = 100;
+
+/// Body of one notification request
+#[derive(Debug, Serialize)]
+pub struct Envelope {
+    pub events: Vec<Event>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Event {
+    pub id: String,
+    pub timestamp: DateTime<Utc>,
+    /// `push`, `pull`, `mount` or `delete`
+    pub action: &'static str,
+    pub target: Target,
+    pub request: RequestRecord,
+    pub actor: ActorRecord,
+    pub source: SourceRecord,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Target {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub media_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub digest: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub size: Option<i64>,
+    /// Same as `size`; kept by distribution for older listeners
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub length: Option<i64>,
+    pub repository: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from_repository: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tag: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub references: Vec<Descriptor>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Descriptor {
+    pub media_type: String,
+    pub digest: String,
+    pub size: i64,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RequestRecord {
+    pub id: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub addr: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub host: String,
+    pub method: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub useragent: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ActorRecord {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SourceRecord {
+    pub addr: String,
+    #[serde(rename = "instanceID")]
+    pub instance_id: String,
+}
+
+/// A finished registry request, as seen by the notification middleware
+#[derive(Debug, Clone)]
+pub struct RegistryRequest {
+    pub method: Method,
+    pub path: String,
+    pub query: Option<String>,
+    pub status: StatusCode,
+    /// `namespace/repository`
+    pub repository: String,
+    /// `Docker-Content-Digest` of the response
+    pub digest: Option<String>,
+    /// `Content-Length` of a pulled blob
+    pub length: Option<i64>,
+    /// Scheme and host the client addressed, for target URLs
+    pub base_url: String,
+    pub request: RequestRecord,
+    pub user_id: Option<i64>,
+    pub username: Option<String>,
+}
+
+/// What a successful registry request did, in distribution's terms
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Operation {
+    Manifest { action: &'static str, reference: String },
+    Blob { action: &'static str, from_repository: Option<String> },
+}
+
+fn classify(request: &RegistryRequest) -> Option<Operation> {
+    if !request.status.is_success() {
+        return None;
+    }
+    if let Some((_, reference)) = request.path.rsplit_once("/manifests/") {
+        let action = match request.method {
+            Method::GET => "pull",
+            Method::PUT => "push",
+            Method::DELETE => "delete",
+            _ => return None,
+        };
+        return Some(Operation::Manifest { action, reference: reference.to_string() });
+    }
+    if request.path.contains("/blobs/uploads/") {
+        return match request.method {
+            // Upload completed
+            Method::PUT => Some(Operation::Blob { action: "push", from_repository: None }),
+            // A 201 to an upload start is a cross-repository mount
+            Method::POST if request.status == StatusCode::CREATED => {
+                let from = request.query.as_deref().and_then(|query| {
+                    url::form_urlencoded::parse(query.as_bytes())
+                        .find(|(key, _)| key == "from")
+                        .map(|(_, value)| value.into_owned())
+                });
+                Some(Operation::Blob { action: "mount", from_repository: from })
+            }
+            _ => None,
+        };
+    }
+    if request.path.contains("/blobs/") {
+        return match request.method {
+            Method::GET => Some(Operation::Blob { action: "pull", from_repository: None }),
+            Method::DELETE => Some(Operation::Blob { action: "delete", from_repository: None }),
+            _ => None,
+        };
+    }
+    None
+}
+
+struct Endpoint {
+    settings: NotificationEndpoint,
+    sender: mpsc::Sender<Event>,
+    receiver: Mutex<Option<mpsc::Receiver<Event>>>,
+}
+
+impl Endpoint {
+    fn wants(&self, event: &Event) -> bool {
+        let ignored_action = self.settings.ignore.actions.iter().any(|action| action == event.action);
+        let ignored_media_type = event.target.media_type.as_ref().is_some_and(|media_type| {
+            self.settings.ignoredmediatypes.contains(media_type) || self.settings.ignore.mediatypes.contains(media_type)
+        });
+        !ignored_action && !ignored_media_type
+    }
+}
+
+/// Configured endpoints and their queues
+pub struct Notifier {
+    endpoints: RwLock<Arc<Vec<Endpoint>>>,
+    include_references: AtomicBool,
+    source: SourceRecord,
+}
+
+impl Notifier {
+    pub fn new(settings: &NotificationSettings, server: &ServerSettings) -> Self {
+        let addr = match std::env::var("HOSTNAME") {
+            Ok(hostname) if !hostname.is_empty() => format!("{}:{}", hostname, server.port),
+            _ => server.address(),
+        };
+        Self {
+            endpoints: RwLock::new(Arc::new(endpoints(settings))),
+            include_references: AtomicBool::new(settings.events.includereferences),
+            source: SourceRecord { addr, instance_id: uuid::Uuid::new_v4().to_string() },
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        !self.endpoints().is_empty()
+    }
+
+    fn endpoints(&self) -> Arc<Vec<Endpoint>> {
+        self.endpoints.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// Send events to the endpoints of `settings` from now on. Events already queued for the
+    /// previous endpoints are still delivered to them.
+    pub fn replace_endpoints(&self, settings: &NotificationSettings) {
+        let endpoints = Arc::new(endpoints(settings));
+        spawn_senders(&endpoints);
+        self.include_references.store(settings.events.includereferences, Ordering::Relaxed);
+        *self.endpoints.write().unwrap_or_else(|e| e.into_inner()) = endpoints;
+    }
+
+    /// Queue the event for a finished registry request, if it is one listeners are told about
+    pub fn observe(&self, state: &AppState, request: RegistryRequest) {
+        if !self.is_enabled() {
+            return;
+        }
+        let Some(operation) = classify(&request) else {
+            return;
+        };
+        let state = state.clone();
+        tokio::spawn(async move {
+            match build_event(&state, request, operation).await {
+                Ok(event) => state.notifier.enqueue(event),
+                Err(e) => tracing::warn!("Failed to build registry notification: {}", e),
+            }
+        });
+    }
+
+    fn enqueue(&self, event: Event) {
+        for endpoint in self.endpoints().iter().filter(|endpoint| endpoint.wants(&event)) {
+            if endpoint.sender.try_send(event.clone()).is_err() {
+                tracing::warn!("Notification queue of endpoint {} is full, dropping {} event", endpoint.settings.name, event.action);
+            }
+        }
+    }
+}
+
+async fn build_event(state: &AppState, request: RegistryRequest, operation: Operation) -> Result<Event> {
+    let name = request.repository.clone();
+    let mut target = Target { repository: name.clone(), ..Default::default() };
+    let action = match operation {
+        Operation::Manifest { action, reference } => {
+            let is_digest = reference.contains(':');
+            let digest = request.digest.clone().or_else(|| is_digest.then(|| reference.clone()));
+            if !is_digest {
+                target.tag = Some(reference);
+            }
+            if let Some(digest) = &digest {
+                if action != "delete" {
+                    if let Some((media_type, size)) = manifest_summary(state, &name, digest).await? {
+                        target.media_type = Some(media_type);
+                        target.size = Some(size);
+                        target.length = Some(size);
+                    }
+                    if state.notifier.include_references.load(Ordering::Relaxed) {
+                        target.references = references(state, &name, digest).await;
+                    }
+                }
+                target.url = Some(format!("{}/v2/{}/manifests/{}", request.base_url, name, digest));
+            }
+            target.digest = digest;
+            action
+        }
+        Operation::Blob { action, from_repository } => {
+            let Some(digest) = request.digest.clone() else {
+                bail!("{} of a blob in {} without Docker-Content-Digest", action, name);
+            };
+            target.media_type = Some(BLOB_MEDIA_TYPE.to_string());
+            target.size = request.length;
+            target.length = request.length;
+            target.url = Some(format!("{}/v2/{}/blobs/{}", request.base_url, name, digest));
+            target.digest = Some(digest);
+            target.from_repository = from_repository;
+            action
+        }
+    };
+
+    let actor = match (request.username, request.user_id) {
+        (Some(username), _) => Some(username),
+        (None, Some(user_id)) => {
+            sqlx::query_scalar::<_, String>("SELECT username FROM users WHERE id = $1")
+                .bind(user_id)
+                .fetch_optional(&state.db_pool)
+                .await?
+        }
+        (None, None) => None,
+    };
+
+    Ok(Event {
+        id: uuid::Uuid::new_v4().to_string(),
+        timestamp: Utc::now(),
+        action,
+        target,
+        request: request.request,
+        actor: ActorRecord { name: actor },
+        source: state.notifier.source.clone(),
+    })
+}
+
+/// Media type and size of a manifest stored in the repository called `name`
+async fn manifest_summary(state: &AppState, name: &str, digest: &str) -> Result<Option<(String, i64)>, sqlx::Error> {
+    let (namespace, repo_name) = match name.split_once('/') {
+        Some((namespace, repo_name)) => (Some(namespace), repo_name),
+        None => (None, name),
+    };
+    sqlx::query_as::<_, (String, i64)>(
+        "SELECT m.media_type, m.size
+         FROM manifests m
+         JOIN repositories r ON r.id = m.repository_id
+         JOIN organizations o ON o.id = r.organization_id
+         WHERE m.digest = $3 AND r.name = $2 AND (o.name = $1 OR ($1 IS NULL AND o.id = 1))",
+    )
+    .bind(namespace)
+    .bind(repo_name)
+    .bind(digest)
+    .fetch_optional(&state.db_pool)
+    .await
+}
+
+/// Config and layers of an image manifest, or the manifests of an index
+async fn references(state: &AppState, name: &str, digest: &str) -> Vec<Descriptor> {
+    match crate::handlers::docker_registry_v2::load_manifest_content(state, name, digest).await {
+        Ok(Some(content)) => referenced_descriptors(&content),
+        Ok(None) => Vec::new(),
+        Err(e) => {
+            tracing::warn!("Failed to load manifest {} of {}: {}", digest, name, e);
+            Vec::new()
+        }
+    }
+}
+
+fn referenced_descriptors(content: &[u8]) -> Vec<Descriptor> {
+    let Ok(manifest) = serde_json::from_slice::<Value>(content) else {
+        return Vec::new();
+    };
+    let config = manifest.get("config").cloned().into_iter();
+    let listed = ["layers", "manifests"]
+        .into_iter()
+        .filter_map(|key| manifest.get(key).and_then(Value::as_array).cloned())
+        .flatten();
+    config
+        .chain(listed)
+        .filter_map(|descriptor| serde_json::from_value(descriptor).ok())
+        .collect()
+}
+
+/// Send each endpoint's queued events, in order, until they are accepted
+pub fn spawn_notification_senders(state: AppState) {
+    spawn_senders(&state.notifier.endpoints());
+}
+
+/// The enabled endpoints of `settings`, with empty queues
+fn endpoints(settings: &NotificationSettings) -> Vec<Endpoint> {
+    settings
+        .endpoints
+        .iter()
+        .filter(|endpoint| !endpoint.disabled)
+        .map(|endpoint| {
+            let (sender, receiver) = mpsc::channel(QUEUE_CAPACITY);
+            Endpoint { settings: endpoint.clone(), sender, receiver: Mutex::new(Some(receiver)) }
+        })
+        .collect()
+}
+
+/// Start the sender of each endpoint not sending yet. A sender stops once its endpoint has been
+/// replaced and its queue is empty, or when a send fails after the replacement.
+fn spawn_senders(endpoints: &[Endpoint]) {
+    for endpoint in endpoints {
+        let Some(mut receiver) = endpoint.receiver.lock().unwrap_or_else(|e| e.into_inner()).take() else {
+            continue;
+        };
+        let settings = endpoint.settings.clone();
+        tokio::spawn(async move {
+            let client = match reqwest::Client::builder()
+                .timeout(settings.timeout)
+                .user_agent(concat!("aerugo-notifications/", env!("CARGO_PKG_VERSION")))
+                .build()
+            {
+                Ok(client) => client,
+                Err(e) => {
+                    tracing::error!("Notifications to {} disabled: {}", settings.name, e);
+                    return;
+                }
+            };
+
+            while let Some(event) = receiver.recv().await {
+                let mut events = vec![event];
+                while events.len() < MAX_BATCH {
+                    match receiver.try_recv() {
+                        Ok(event) => events.push(event),
+                        Err(_) => break,
+                    }
+                }
+                let envelope = Envelope { events };
+
+                let mut failures = 0_u32;
+                while let Err(e) = send(&client, &settings, &envelope).await {
+                    failures += 1;
+                    tracing::warn!("Notification to {} failed ({} in a row): {:#}", settings.name, failures, e);
+                    if receiver.is_closed() {
+                        tracing::warn!("Endpoint {} was replaced, dropping its undelivered events", settings.name);
+                        return;
+                    }
+                    if failures >= settings.threshold {
+                        tokio::time::sleep(settings.backoff).await;
+                    }
+                }
+            }
+        });
+    }
+}
+
+async fn send(client: &reqwest::Client, endpoint: &NotificationEndpoint, envelope: &Envelope) -> Result<()> {
+    let mut request = client
+        .post(&endpoint.url)
+        .header(reqwest::header::CONTENT_TYPE, ENVELOPE_MEDIA_TYPE)
+        .body(serde_json::to_vec(envelope)?);
+    for (name, values) in &endpoint.headers {
+        for value in values {
+            request = request.header(name.as_str(), value.as_str());
+        }
+    }
+    let status = request.send().await?.status();
+    // Distribution counts redirects as delivered too
+    if !(status.is_success() || status.is_redirection()) {
+        bail!("endpoint answered {}", status);
+    }
+    Ok(())
+}
+
+/// Parse a Go duration such as `300ms`, `1.5s` or `1h30m`
+pub fn parse_go_duration(text: &str) -> Option<std::time::Duration> {
+    let text = text.trim();
+    if text == "0" {
+        return Some(std::time::Duration::ZERO);
+    }
+    let mut rest = text;
+    let mut nanos = 0_f64;
+    while !rest.is_empty() {
+        let number_end = rest.find(|c: char| !(c.is_ascii_digit() || c == '.'))?;
+        let value: f64 = rest[..number_end].parse().ok()?;
+        rest = &rest[number_end..];
+        let unit_end = rest.find(|c: char| c.is_ascii_digit() || c == '.').unwrap_or(rest.len());
+        let scale = match &rest[..unit_end] {
+            "ns" => 1.0,
+            "us" | "µs" => 1e3,
+            "ms" => 1e6,
+            "s" => 1e9,
+            "m" => 60e9,
+            "h" => 3600e9,
+            _ => return None,
+        };
+        nanos += value * scale;
+        rest = &rest[unit_end..];
+    }
+    (!text.is_empty()).then(|| std::time::Duration::from_nanos(nanos as u64))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn request(method: Method, path: &str, status: StatusCode) -> RegistryRequest {
+        RegistryRequest {
+            method,
+            path: path.to_string(),
+            query: None,
+            status,
+            repository: "acme/web".to_string(),
+            digest: Some("sha256:abc".to_string()),
+            length: None,
+            base_url: "https://registry.example.com".to_string(),
+            request: RequestRecord::default(),
+            user_id: None,
+            username: None,
+        }
+    }
+
+    #[test]
+    fn parses_go_durations() {
+        assert_eq!(parse_go_duration("1s"), Some(Duration::from_secs(1)));
+        assert_eq!(parse_go_duration("500ms"), Some(Duration::from_millis(500)));
+        assert_eq!(parse_go_duration("1h30m"), Some(Duration::from_secs(5400)));
+        assert_eq!(parse_go_duration("1.5s"), Some(Duration::from_millis(1500)));
+        assert_eq!(parse_go_duration("0"), Some(Duration::ZERO));
+        assert_eq!(parse_go_duration("10"), None);
+        assert_eq!(parse_go_duration("1d"), None);
+        assert_eq!(parse_go_duration(""), None);
+    }
+
+    #[test]
+    fn classifies_registry_requests() {
+        assert_eq!(
+            classify(&request(Method::GET, "/v2/acme/web/manifests/latest", StatusCode::OK)),
+            Some(Operation::Manifest { action: "pull", reference: "latest".to_string() })
+        );
+        assert_eq!(
+            classify(&request(Method::DELETE, "/v2/acme/web/manifests/sha256:abc", StatusCode::ACCEPTED)),
+            Some(Operation::Manifest { action: "delete", reference: "sha256:abc".to_string() })
+        );
+        assert_eq!(classify(&request(Method::HEAD, "/v2/acme/web/manifests/latest", StatusCode::OK)), None);
+        assert_eq!(classify(&request(Method::GET, "/v2/acme/web/manifests/latest", StatusCode::NOT_FOUND)), None);
+        assert_eq!(
+            classify(&request(Method::PUT, "/v2/acme/web/blobs/uploads/5f1c", StatusCode::CREATED)),
+            Some(Operation::Blob { action: "push", from_repository: None })
+        );
+        assert_eq!(classify(&request(Method::PATCH, "/v2/acme/web/blobs/uploads/5f1c", StatusCode::ACCEPTED)), None);
+        // Starting an upload is not an event, mounting a blob is
+        assert_eq!(classify(&request(Method::POST, "/v2/acme/web/blobs/uploads/", StatusCode::ACCEPTED)), None);
+        let mut mount = request(Method::POST, "/v2/acme/web/blobs/uploads/", StatusCode::CREATED);
+        mount.query = Some("mount=sha256%3Aabc&from=acme%2Fbase".to_string());
+        assert_eq!(classify(&mount), Some(Operation::Blob { action: "mount", from_repository: Some("acme/base".to_string()) }));
+        assert_eq!(
+            classify(&request(Method::GET, "/v2/acme/web/blobs/sha256:abc", StatusCode::OK)),
+            Some(Operation::Blob { action: "pull", from_repository: None })
+        );
+        assert_eq!(classify(&request(Method::GET, "/v2/acme/web/tags/list", StatusCode::OK)), None);
+    }
+
+    #[test]
+    fn lists_referenced_descriptors() {
+        let image = br#"{"schemaVersion":2,
+            "config":{"mediaType":"application/vnd.oci.image.config.v1+json","digest":"sha256:c","size":7},
+            "layers":[{"mediaType":"application/vnd.oci.image.layer.v1.tar+gzip","digest":"sha256:l","size":42}]}"#;
+        let references = referenced_descriptors(image);
+        assert_eq!(references.len(), 2);
+        assert_eq!(references[1].digest, "sha256:l");
+        assert!(referenced_descriptors(b"not json").is_empty());
+    }
+
+    #[test]
+    fn serializes_like_distribution() {
+        let event = Event {
+            id: "e1".to_string(),
+            timestamp: Utc::now(),
+            action: "push",
+            target: Target {
+                media_type: Some("application/vnd.oci.image.manifest.v1+json".to_string()),
+                digest: Some("sha256:abc".to_string()),
+                repository: "acme/web".to_string(),
+                tag: Some("latest".to_string()),
+                ..Default::default()
+            },
+            request: RequestRecord { id: "r1".to_string(), method: "PUT".to_string(), ..Default::default() },
+            actor: ActorRecord { name: Some("alice".to_string()) },
+            source: SourceRecord { addr: "registry:5000".to_string(), instance_id: "i1".to_string() },
+        };
+        let json = serde_json::to_value(Envelope { events: vec![event] }).unwrap();
+        let event = &json["events"][0];
+        assert_eq!(event["target"]["mediaType"], "application/vnd.oci.image.manifest.v1+json");
+        assert_eq!(event["target"]["tag"], "latest");
+        assert!(event["target"].get("references").is_none());
+        assert_eq!(event["source"]["instanceID"], "i1");
+        assert_eq!(event["actor"]["name"], "alice");
+    }
+}
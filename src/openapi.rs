@@ -3,16 +3,56 @@ use utoipa::Modify;
 use utoipa::openapi::security::{SecurityScheme, Http, HttpAuthScheme};
 
 use crate::handlers::{
+    admin,
+    api_usage,
+    audit_export,
     auth,
+    avatars,
+    base_images,
+    bootstrap,
+    collaborators,
+    digests,
     docker_registry_v2,
+    events,
+    features,
+    federation,
+    images,
+    insights,
+    invitations,
+    ip_access,
+    jobs,
+    legal_holds,
+    log_tail,
+    model_registry,
+    organization_secrets,
+    organization_webhooks,
     organizations,
+    push_hooks,
+    quarantine,
+    quota_tiers,
     repositories,
+    repository_stats,
+    repository_webhooks,
+    sbom,
+    secret_findings,
+    security_policies,
+    security_summary,
+    standby,
+    tag_cleanup,
+    tags,
+    takedowns,
+    teams,
+    vulnerabilities,
+    watches,
+    webhooks,
 };
 use crate::models::{
     user::UserResponse,
     organizations::{
         Organization, CreateOrganizationRequest, UpdateOrganizationRequest,
         AddMemberRequest, UpdateMemberRequest, OrganizationMember,
+        OrganizationDeletionPreview, DeleteOrganizationRequest,
+        OrganizationSettings, UpdateOrganizationSettingsRequest,
     },
     repository::{Repository as RepositoryModel, CreateRepositoryRequest, RepositoryDetailsResponse},
 };
@@ -50,7 +90,19 @@ impl Modify for SecurityAddon {
         auth::register,
         auth::login,
         auth::me, 
+        avatars::upload_user_avatar,
+        avatars::delete_user_avatar,
+        watches::list_watches,
+        watches::get_notification_preferences,
+        watches::update_notification_preferences,
+        avatars::upload_organization_avatar,
+        avatars::delete_organization_avatar,
+        avatars::get_avatar,
         auth::refresh,
+        auth::list_sessions,
+        auth::revoke_session,
+        api_usage::list_credential_usage,
+        api_usage::get_credential_usage,
         auth::change_password,
         auth::forgot_password,
         auth::verify_otp_and_reset,
@@ -64,10 +116,97 @@ impl Modify for SecurityAddon {
         organizations::list_user_organizations,
         organizations::update_organization,
         organizations::delete_organization,
+        organizations::request_organization_deletion,
+        organizations::get_organization_settings,
+        organizations::get_organization_stats,
+        events::list_organization_events,
+        audit_export::export_audit_log,
+        organization_secrets::list_organization_secrets,
+        organization_secrets::put_organization_secret,
+        organization_secrets::delete_organization_secret,
+        organization_webhooks::create_organization_webhook,
+        organization_webhooks::list_organization_webhooks,
+        organization_webhooks::get_organization_webhook,
+        organization_webhooks::update_organization_webhook,
+        organization_webhooks::delete_organization_webhook,
+        organization_webhooks::list_organization_webhook_deliveries,
+        organization_webhooks::redeliver_organization_webhook_delivery,
+        repository_webhooks::create_repository_webhook,
+        repository_webhooks::list_repository_webhooks,
+        repository_webhooks::update_repository_webhook,
+        repository_webhooks::delete_repository_webhook,
+        repository_webhooks::list_repository_webhook_deliveries,
+        repository_webhooks::redeliver_repository_webhook_delivery,
+        push_hooks::create_push_hook,
+        push_hooks::list_push_hooks,
+        push_hooks::update_push_hook,
+        push_hooks::delete_push_hook,
+        security_policies::create_security_policy,
+        security_policies::list_security_policies,
+        security_policies::update_security_policy,
+        security_policies::delete_security_policy,
+        security_summary::get_security_summary,
+        organizations::update_organization_settings,
         organizations::get_organization_members,
         organizations::add_organization_member,
         organizations::update_member_role,
         organizations::remove_organization_member,
+        invitations::create_invitation,
+        invitations::list_invitations,
+        invitations::revoke_invitation,
+        invitations::get_invitation,
+        invitations::accept_invitation,
+        invitations::decline_invitation,
+        legal_holds::create_legal_hold,
+        legal_holds::list_legal_holds,
+        legal_holds::get_legal_hold_manifest,
+        legal_holds::release_legal_hold,
+        teams::list_teams,
+        teams::create_team,
+        teams::get_team,
+        teams::update_team,
+        teams::delete_team,
+        teams::add_team_member,
+        teams::remove_team_member,
+        teams::set_team_repository,
+        teams::remove_team_repository,
+        ip_access::list_ip_rules,
+        ip_access::create_ip_rule,
+        ip_access::delete_ip_rule,
+        ip_access::list_global_ip_rules,
+        ip_access::create_global_ip_rule,
+        ip_access::delete_global_ip_rule,
+        log_tail::tail_logs,
+        standby::standby_status,
+        standby::promote_standby,
+        admin::list_users,
+        admin::disable_user,
+        admin::enable_user,
+        admin::set_user_admin,
+        admin::storage_stats,
+        admin::cache_stats,
+        admin::flush_cache,
+        admin::retention_policy,
+        admin::reload_config,
+        features::list_features,
+        features::set_feature,
+        features::clear_feature,
+        jobs::list_jobs,
+        jobs::retry_job,
+        bootstrap::bootstrap,
+        takedowns::create_takedown,
+        takedowns::list_takedowns,
+        takedowns::get_takedown,
+        takedowns::reinstate_takedown,
+        quota_tiers::list_quota_tiers,
+        quota_tiers::create_quota_tier,
+        quota_tiers::update_quota_tier,
+        quota_tiers::delete_quota_tier,
+        quota_tiers::assign_quota_tier,
+        quota_tiers::get_organization_quota,
+        federation::federation_index,
+        federation::federated_search,
+        federation::list_peers,
 
         // Repository endpoints
         repositories::create_repository,
@@ -77,6 +216,37 @@ impl Modify for SecurityAddon {
         repositories::list_public_repositories,
         repositories::get_repository,
         repositories::delete_repository,
+        repositories::clone_repository,
+        repositories::transfer_repository,
+        collaborators::list_collaborators,
+        collaborators::set_collaborator,
+        collaborators::remove_collaborator,
+        digests::resolve_digest,
+        model_registry::attach_model_card,
+        model_registry::get_model_card,
+        model_registry::create_model_lineage,
+        model_registry::get_model_lineage,
+        tag_cleanup::get_cleanup_suggestions,
+        tag_cleanup::accept_cleanup_suggestions,
+        insights::get_repository_insights,
+        repository_stats::get_repository_stats,
+        events::list_repository_events,
+        jobs::list_repository_jobs,
+        quarantine::list_quarantined_manifests,
+        quarantine::release_quarantined_manifest,
+        secret_findings::list_secret_findings,
+        base_images::list_image_bases,
+        events::stream_events,
+        watches::get_watch,
+        watches::watch_repository,
+        watches::unwatch_repository,
+        tags::list_repository_tags,
+        vulnerabilities::get_tag_vulnerabilities,
+        sbom::get_image_sbom,
+        sbom::upload_image_sbom,
+        sbom::search_packages,
+        images::get_image_detail,
+        webhooks::get_signing_keys,
 
         // Docker Registry V2 API endpoints
         docker_registry_v2::get_catalog,
@@ -92,6 +262,7 @@ impl Modify for SecurityAddon {
         docker_registry_v2::get_upload_status,
         docker_registry_v2::cancel_blob_upload,
         docker_registry_v2::list_tags,
+        docker_registry_v2::get_referrers,
         docker_registry_v2::list_blobs,
         docker_registry_v2::list_blobs_namespaced,
     ),
@@ -102,12 +273,18 @@ impl Modify for SecurityAddon {
             auth::RegisterRequest,
             auth::LoginRequest,
             auth::RefreshRequest,
+            auth::SessionResponse,
+            crate::models::api_usage::CredentialUsage,
+            crate::models::api_usage::CredentialUsageDetail,
+            crate::models::api_usage::DailyUsage,
+            crate::models::api_usage::EndpointUsage,
             auth::AuthResponse,
             auth::ChangePasswordRequest,
             auth::ForgotPasswordRequest,
             auth::VerifyOtpRequest,
             auth::ApiKeyResponse,     
             auth::CreateApiKeyRequest,
+            crate::models::api_key::ApiKeyScope,
             auth::CreateApiKeyResponse,
             auth::DeleteApiKeyResponse,
             auth::ApiKeyErrorResponse, 
@@ -119,6 +296,138 @@ impl Modify for SecurityAddon {
             AddMemberRequest,
             UpdateMemberRequest,
             OrganizationMember,
+            OrganizationDeletionPreview,
+            DeleteOrganizationRequest,
+            OrganizationSettings,
+            UpdateOrganizationSettingsRequest,
+            crate::models::organizations::OrganizationStats,
+            crate::models::organizations::ActivityWindow,
+            crate::models::organizations::DailyActivity,
+            crate::models::organizations::RepositoryUsage,
+            crate::models::organization_secret::OrganizationSecret,
+            crate::models::organization_secret::PutOrganizationSecretRequest,
+            crate::models::webhook::OrganizationWebhook,
+            crate::models::webhook::CreateOrganizationWebhookRequest,
+            crate::models::webhook::UpdateOrganizationWebhookRequest,
+            crate::models::webhook::RepositoryWebhook,
+            crate::models::webhook::WebhookDelivery,
+            crate::models::webhook::WebhookDeliveryPage,
+            crate::models::push_hook::OrganizationPushHook,
+            crate::models::push_hook::CreatePushHookRequest,
+            crate::models::push_hook::UpdatePushHookRequest,
+            crate::models::push_hook::PushHookVerdict,
+            crate::models::security_policy::SecurityPolicy,
+            crate::models::security_policy::PolicyRule,
+            crate::models::security_policy::CreateSecurityPolicyRequest,
+            crate::models::security_policy::UpdateSecurityPolicyRequest,
+            crate::models::security_summary::OrganizationSecuritySummary,
+            crate::models::security_summary::ImageSecurity,
+            crate::models::security_summary::RepositorySecurity,
+            crate::models::organization_invitation::OrganizationInvitation,
+            crate::models::organization_invitation::InvitationPreview,
+            crate::models::organization_invitation::CreateInvitationRequest,
+            crate::models::legal_hold::LegalHold,
+            crate::models::legal_hold::CreateLegalHoldRequest,
+            crate::models::legal_hold::LegalHoldExportEntry,
+            crate::models::legal_hold::LegalHoldMissingBlob,
+            crate::models::team::Team,
+            crate::models::team::TeamMember,
+            crate::models::team::TeamRepository,
+            crate::models::team::TeamDetails,
+            crate::models::team::CreateTeamRequest,
+            crate::models::team::UpdateTeamRequest,
+            crate::models::team::SetTeamRepositoryRequest,
+            crate::models::ip_access_rule::IpAccessRule,
+            crate::models::ip_access_rule::IpRuleAction,
+            crate::models::ip_access_rule::IpRuleOperation,
+            crate::models::ip_access_rule::CreateIpAccessRuleRequest,
+            crate::handlers::digests::ResolvedDigest,
+            crate::models::model_artifact::ModelCardMetadata,
+            crate::models::model_artifact::AttachModelCardRequest,
+            crate::models::model_artifact::ModelCard,
+            crate::models::model_artifact::CreateLineageRequest,
+            crate::models::model_artifact::LineageLink,
+            crate::models::model_artifact::ModelLineage,
+            crate::models::tag_cleanup::TagCleanupSuggestions,
+            crate::models::tag_cleanup::TagCleanupCandidate,
+            crate::models::tag_cleanup::SuggestedRetentionPolicy,
+            crate::models::repository_insights::RepositoryInsights,
+            crate::models::repository_insights::PullTrend,
+            crate::models::repository_insights::StorageFootprint,
+            crate::models::repository_insights::StaleTags,
+            crate::models::repository_insights::SignatureCoverage,
+            crate::models::repository_insights::PolicyCompliance,
+            crate::models::repository_stats::RepositoryStats,
+            crate::models::repository_stats::TagUsage,
+            crate::models::event::EventPage,
+            crate::models::event::RegistryEvent,
+            crate::models::event::ExportFormat,
+            crate::models::event::StreamFormat,
+            crate::models::watch::PushEmail,
+            crate::models::watch::NotificationPreferences,
+            crate::models::watch::WatchStatus,
+            crate::models::watch::WatchedRepository,
+            crate::models::job::JobKind,
+            crate::models::job::Job,
+            crate::models::job::JobCount,
+            crate::models::job::JobPage,
+            crate::models::vulnerability::Severity,
+            crate::models::vulnerability::SeverityCounts,
+            crate::models::vulnerability::ScanSummary,
+            crate::models::vulnerability::Vulnerability,
+            crate::models::vulnerability::TagVulnerabilities,
+            crate::models::sbom::SbomFormat,
+            crate::models::sbom::SbomPackage,
+            crate::models::sbom::SbomArtifact,
+            crate::models::sbom::PackageMatch,
+            crate::models::sbom::PackageSearchPage,
+            crate::models::tag_listing::TagPage,
+            crate::models::tag_listing::TagDetail,
+            crate::models::tag_listing::Platform,
+            crate::models::signature::SignatureState,
+            crate::models::signature::SignatureStatus,
+            crate::models::quarantine::QuarantineStatus,
+            crate::models::quarantine::QuarantinedManifest,
+            crate::models::secret_finding::SecretFinding,
+            crate::models::image_base::ImageBase,
+            crate::models::image_detail::ImageDetail,
+            crate::models::image_detail::RunConfig,
+            crate::models::image_detail::ImageLayer,
+            crate::models::image_detail::HistoryEntry,
+            crate::log_stream::LogEvent,
+            crate::log_stream::LogEventKind,
+            crate::standby::StandbyStatus,
+            crate::standby::ReplicationStatus,
+            crate::standby::ReplicationMode,
+            standby::PromoteRequest,
+            admin::AdminUserSummary,
+            admin::SetAdminRequest,
+            admin::StorageStats,
+            crate::cache::CacheStats,
+            crate::cache::MemoryCacheStats,
+            crate::cache::CategoryStats,
+            crate::retention::RetentionPolicy,
+            crate::config::reload::ConfigReload,
+            crate::features::Feature,
+            crate::features::FeatureFlag,
+            crate::handlers::features::FeatureOverrideRequest,
+            crate::bootstrap::BootstrapReport,
+            crate::bootstrap::BootstrapStep,
+            crate::models::content_takedown::ContentTakedown,
+            crate::models::content_takedown::CreateTakedownRequest,
+            crate::models::content_takedown::ReinstateTakedownRequest,
+            crate::models::quota_tier::QuotaTier,
+            crate::models::quota_tier::QuotaTierRequest,
+            crate::models::quota_tier::AssignQuotaTierRequest,
+            crate::models::quota_tier::OrganizationQuota,
+            crate::federation::IndexEntry,
+            crate::federation::IndexResponse,
+            crate::federation::FederatedHit,
+            crate::federation::PeerQueryStatus,
+            crate::federation::PeerOutcome,
+            crate::federation::FederatedSearchResponse,
+            crate::federation::PeerStatus,
+            crate::retention::RetentionSetting,
 
             // Repository schemas
             RepositoryModel,
@@ -133,6 +442,15 @@ impl Modify for SecurityAddon {
             repositories::RepositoryDetailsResponse,
             repositories::RepositoryStats,
             repositories::ListRepositoriesQuery,
+            repositories::CloneRepositoryRequest,
+            repositories::CloneRepositoryResponse,
+            repositories::TransferRepositoryRequest,
+            repositories::TransferRepositoryResponse,
+            crate::models::repository_collaborator::RepositoryCollaborator,
+            crate::models::repository_collaborator::SetCollaboratorRequest,
+            crate::models::repository_collaborator::CollaboratorPermission,
+            crate::models::webhook::WebhookSigningKeys,
+            crate::models::webhook::WebhookSigningKey,
             
             // Docker Registry V2 API schemas
             ApiVersionResponse,
@@ -149,6 +467,12 @@ impl Modify for SecurityAddon {
         (name = "auth", description = "Authentication endpoints"),
         (name = "organizations", description = "Organization management endpoints"),
         (name = "repositories", description = "Repository management endpoints"),
+        (name = "models", description = "Model cards and lineage for ML model artifacts"),
+        (name = "webhooks", description = "Webhook signature verification"),
+        (name = "logs", description = "Live audit and access-log tailing"),
+        (name = "standby", description = "Warm standby status and promotion"),
+        (name = "admin", description = "Registry administration"),
+        (name = "federation", description = "Repository index and search across peer registries"),
         (name = "docker-registry-v2", description = "Docker Registry V2 API - OCI Distribution Specification"),
     ),
       modifiers(&SecurityAddon)  // 👈 add this to get Bearer Auth
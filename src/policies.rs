@@ -0,0 +1,378 @@
+// src/policies.rs - Security policies checked on manifest pulls and pushes
+//
+// Organizations attach rules to all of their repositories or to one of them
+// (`security_policies`); a repository's `require_signature` flag is a built-in rule of the same
+// kind. `RequireRepoPermission` evaluates them once the caller is authorized, so every manifest
+// GET, HEAD and PUT goes through the same checks. Pulls are checked against what is known about
+// the stored image - its latest completed vulnerability scan and its signature verification -
+// and pushes against the tag. Referrers and `sha256-*` tags are never refused, so signatures and
+// SBOMs stay available to clients checking an image.
+use anyhow::Result;
+use serde_json::{json, Value};
+use sqlx::PgPool;
+
+use crate::handlers::registry_auth::RegistryAction;
+use crate::models::security_policy::PolicyRule;
+use crate::models::vulnerability::{Severity, SeverityCounts};
+use crate::webhooks::chat::short_digest;
+
+/// Name reported for a repository's `require_signature` flag
+const SIGNATURE_REQUIRED: &str = "signature-required";
+
+/// A policy a request violates
+#[derive(Debug, Clone, PartialEq)]
+pub struct Violation {
+    pub policy: String,
+    /// Why the request was refused, for the client
+    pub message: String,
+    /// The `detail` of the registry error
+    pub detail: Value,
+}
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+struct Policy {
+    /// `None` for policies applying to every repository of the organization
+    repository_id: Option<i64>,
+    name: String,
+    rule: String,
+    severity: Option<String>,
+    tag_patterns: Vec<String>,
+}
+
+/// What a pull rule needs to know about an image
+pub(crate) struct Image {
+    pub(crate) digest: String,
+    /// Whether a signature of the image was verified
+    pub(crate) signed: bool,
+    /// Findings of the latest completed scan; `None` while the image has not been scanned
+    pub(crate) vulnerabilities: Option<SeverityCounts>,
+}
+
+/// The active policies of an organization, for checking stored images outside of a request
+pub(crate) struct OrganizationPolicies(Vec<Policy>);
+
+impl OrganizationPolicies {
+    pub(crate) async fn load(pool: &PgPool, organization_id: i64) -> Result<Self> {
+        let policies = sqlx::query_as::<_, Policy>(
+            "SELECT repository_id, name, rule, severity, tag_patterns FROM security_policies
+             WHERE organization_id = $1 AND active
+             ORDER BY id",
+        )
+        .bind(organization_id)
+        .fetch_all(pool)
+        .await?;
+        Ok(Self(policies))
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// The first policy a pull of `image` from `repository` would violate, counting the
+    /// repository's `require_signature` flag
+    pub(crate) fn pull_violation(
+        &self,
+        repository_id: i64,
+        repository: &str,
+        require_signature: bool,
+        image: &Image,
+    ) -> Option<Violation> {
+        let applicable: Vec<Policy> = require_signature
+            .then(signature_required)
+            .into_iter()
+            .chain(
+                self.0
+                    .iter()
+                    .filter(|policy| policy.repository_id.unwrap_or(repository_id) == repository_id)
+                    .cloned(),
+            )
+            .collect();
+        check_pull(&applicable, repository, image)
+    }
+}
+
+/// The first policy that `action` on `reference` in repository `name` violates, if any.
+/// Unknown repositories and references are let through for the handler to answer.
+pub async fn evaluate(
+    pool: &PgPool,
+    namespace: Option<&str>,
+    name: &str,
+    action: RegistryAction,
+    reference: &str,
+) -> Result<Option<Violation>> {
+    // Signatures, attestations and SBOMs stored as tags by older clients
+    if reference.starts_with("sha256-") {
+        return Ok(None);
+    }
+    match action {
+        RegistryAction::Pull => evaluate_pull(pool, namespace, name, reference).await,
+        // Pushes by digest carry no tag
+        RegistryAction::Push if !reference.contains(':') => evaluate_push(pool, namespace, name, reference).await,
+        _ => Ok(None),
+    }
+}
+
+async fn evaluate_pull(pool: &PgPool, namespace: Option<&str>, name: &str, reference: &str) -> Result<Option<Violation>> {
+    let manifest = sqlx::query_as::<_, (i64, i64, bool, String, bool)>(
+        "SELECT r.id, r.organization_id, r.require_signature, m.digest, m.subject_digest IS NOT NULL
+         FROM repositories r
+         JOIN organizations o ON o.id = r.organization_id
+         JOIN manifests m ON m.repository_id = r.id
+         WHERE r.name = $2 AND (o.name = $1 OR ($1 IS NULL AND o.id = 1))
+           AND (m.digest = $3 OR m.id = (SELECT t.manifest_id FROM tags t WHERE t.repository_id = r.id AND t.name = $3))
+         LIMIT 1",
+    )
+    .bind(namespace)
+    .bind(name)
+    .bind(reference)
+    .fetch_optional(pool)
+    .await?;
+    let Some((repository_id, organization_id, require_signature, digest, is_referrer)) = manifest else {
+        return Ok(None);
+    };
+    if is_referrer {
+        return Ok(None);
+    }
+
+    let mut policies = load_policies(pool, organization_id, Some(repository_id)).await?;
+    if require_signature {
+        policies.insert(0, signature_required());
+    }
+    let checks_pulls = |policy: &Policy| {
+        matches!(policy.rule.parse::<PolicyRule>(), Ok(PolicyRule::BlockSeverity | PolicyRule::RequireSignature))
+    };
+    if !policies.iter().any(checks_pulls) {
+        return Ok(None);
+    }
+
+    let (signed, critical, high, medium, low) =
+        sqlx::query_as::<_, (bool, Option<i32>, Option<i32>, Option<i32>, Option<i32>)>(
+            "SELECT EXISTS (
+                        SELECT 1 FROM signature_verifications v
+                        WHERE v.repository_id = $1 AND v.manifest_digest = $2 AND v.status = 'verified'
+                    ),
+                    s.critical_count, s.high_count, s.medium_count, s.low_count
+             FROM (SELECT 1) AS image
+             LEFT JOIN vulnerability_scans s
+                 ON s.repository_id = $1 AND s.manifest_digest = $2 AND s.status = 'completed'",
+        )
+        .bind(repository_id)
+        .bind(&digest)
+        .fetch_one(pool)
+        .await?;
+    let vulnerabilities = critical.map(|critical| {
+        let (high, medium, low) = (high.unwrap_or(0), medium.unwrap_or(0), low.unwrap_or(0));
+        SeverityCounts { critical, high, medium, low, unknown: 0, total: critical + high + medium + low }
+    });
+
+    let image = Image { digest, signed, vulnerabilities };
+    let repository = namespace.map(|namespace| format!("{}/{}", namespace, name)).unwrap_or_else(|| name.to_string());
+    Ok(check_pull(&policies, &repository, &image))
+}
+
+async fn evaluate_push(pool: &PgPool, namespace: Option<&str>, name: &str, tag: &str) -> Result<Option<Violation>> {
+    // The repository may not exist until this push creates it; organization-wide rules still apply
+    let repository = sqlx::query_as::<_, (i64, Option<i64>)>(
+        "SELECT o.id, (SELECT r.id FROM repositories r WHERE r.organization_id = o.id AND r.name = $2)
+         FROM organizations o
+         WHERE o.name = $1 OR ($1 IS NULL AND o.id = 1)
+         LIMIT 1",
+    )
+    .bind(namespace)
+    .bind(name)
+    .fetch_optional(pool)
+    .await?;
+    let Some((organization_id, repository_id)) = repository else {
+        return Ok(None);
+    };
+
+    let policies = load_policies(pool, organization_id, repository_id).await?;
+    Ok(check_push(&policies, tag))
+}
+
+/// Active policies of an organization that apply to a repository, oldest first
+async fn load_policies(pool: &PgPool, organization_id: i64, repository_id: Option<i64>) -> Result<Vec<Policy>> {
+    let policies = sqlx::query_as::<_, Policy>(
+        "SELECT repository_id, name, rule, severity, tag_patterns FROM security_policies
+         WHERE organization_id = $1 AND active AND (repository_id IS NULL OR repository_id = $2)
+         ORDER BY id",
+    )
+    .bind(organization_id)
+    .bind(repository_id)
+    .fetch_all(pool)
+    .await?;
+    Ok(policies)
+}
+
+/// A repository's `require_signature` flag as a policy
+fn signature_required() -> Policy {
+    Policy {
+        repository_id: None,
+        name: SIGNATURE_REQUIRED.to_string(),
+        rule: PolicyRule::RequireSignature.to_string(),
+        severity: None,
+        tag_patterns: Vec::new(),
+    }
+}
+
+/// The first policy a pull of `image` violates
+fn check_pull(policies: &[Policy], repository: &str, image: &Image) -> Option<Violation> {
+    policies.iter().find_map(|policy| match policy.rule.parse::<PolicyRule>() {
+        Ok(PolicyRule::RequireSignature) if !image.signed => Some(Violation {
+            policy: policy.name.clone(),
+            message: format!("{} requires images with a verified signature", repository),
+            detail: json!({
+                "policy": policy.name,
+                "rule": PolicyRule::RequireSignature,
+                "digest": image.digest,
+            }),
+        }),
+        Ok(PolicyRule::BlockSeverity) => {
+            let threshold = policy.severity.as_deref()?.parse::<Severity>().ok()?;
+            let count = at_least(image.vulnerabilities.as_ref()?, threshold);
+            (count > 0).then(|| Violation {
+                policy: policy.name.clone(),
+                message: format!(
+                    "{}@{} has {} vulnerabilities of severity {} or worse",
+                    repository,
+                    short_digest(&image.digest),
+                    count,
+                    threshold
+                ),
+                detail: json!({
+                    "policy": policy.name,
+                    "rule": PolicyRule::BlockSeverity,
+                    "digest": image.digest,
+                    "severity": threshold,
+                    "count": count,
+                }),
+            })
+        }
+        _ => None,
+    })
+}
+
+/// The first policy a push to `tag` violates
+fn check_push(policies: &[Policy], tag: &str) -> Option<Violation> {
+    policies.iter().find_map(|policy| {
+        if policy.rule.parse::<PolicyRule>() != Ok(PolicyRule::DenyTags) {
+            return None;
+        }
+        let pattern = policy.tag_patterns.iter().find(|pattern| tag_matches(pattern, tag))?;
+        Some(Violation {
+            policy: policy.name.clone(),
+            message: format!("Pushing tag {} is not allowed", tag),
+            detail: json!({
+                "policy": policy.name,
+                "rule": PolicyRule::DenyTags,
+                "tag": tag,
+                "pattern": pattern,
+            }),
+        })
+    })
+}
+
+/// Vulnerabilities of `threshold` severity or worse
+pub(crate) fn at_least(counts: &SeverityCounts, threshold: Severity) -> i32 {
+    [
+        (Severity::Critical, counts.critical),
+        (Severity::High, counts.high),
+        (Severity::Medium, counts.medium),
+        (Severity::Low, counts.low),
+    ]
+    .into_iter()
+    .filter(|(severity, _)| *severity <= threshold)
+    .map(|(_, count)| count)
+    .sum()
+}
+
+/// Whether `tag` matches a pattern in which `*` stands for any run of characters
+pub fn tag_matches(pattern: &str, tag: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let Some(mut rest) = tag.strip_prefix(parts.next().unwrap_or_default()) else {
+        return false;
+    };
+    let parts: Vec<&str> = parts.collect();
+    let Some((last, middle)) = parts.split_last() else {
+        return rest.is_empty();
+    };
+    for part in middle {
+        match rest.find(part) {
+            Some(start) => rest = &rest[start + part.len()..],
+            None => return false,
+        }
+    }
+    rest.ends_with(last)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(name: &str, rule: PolicyRule) -> Policy {
+        Policy { repository_id: None, name: name.to_string(), rule: rule.to_string(), severity: None, tag_patterns: Vec::new() }
+    }
+
+    fn image(signed: bool, vulnerabilities: Option<SeverityCounts>) -> Image {
+        Image { digest: format!("sha256:{}", "a".repeat(64)), signed, vulnerabilities }
+    }
+
+    #[test]
+    fn tag_patterns_match_whole_tags() {
+        assert!(tag_matches("latest", "latest"));
+        assert!(!tag_matches("latest", "latest-1"));
+        assert!(tag_matches("dev-*", "dev-1234"));
+        assert!(!tag_matches("dev-*", "v1-dev-1"));
+        assert!(tag_matches("*-rc*", "v2-rc1"));
+        assert!(tag_matches("*", "anything"));
+        assert!(!tag_matches("a*a", "a"));
+    }
+
+    #[test]
+    fn unsigned_images_violate_signature_rules() {
+        let policies = [policy("signed-only", PolicyRule::RequireSignature)];
+        assert!(check_pull(&policies, "acme/web", &image(true, None)).is_none());
+        let violation = check_pull(&policies, "acme/web", &image(false, None)).unwrap();
+        assert_eq!(violation.policy, "signed-only");
+        assert_eq!(violation.detail["rule"], "require_signature");
+    }
+
+    #[test]
+    fn severity_rules_count_worse_findings_and_let_unscanned_images_through() {
+        let mut block = policy("no-high", PolicyRule::BlockSeverity);
+        block.severity = Some("HIGH".to_string());
+        let policies = [block];
+
+        assert!(check_pull(&policies, "acme/web", &image(false, None)).is_none());
+        let medium = SeverityCounts { medium: 3, total: 3, ..Default::default() };
+        assert!(check_pull(&policies, "acme/web", &image(false, Some(medium))).is_none());
+        let critical = SeverityCounts { critical: 1, high: 2, total: 3, ..Default::default() };
+        let violation = check_pull(&policies, "acme/web", &image(false, Some(critical))).unwrap();
+        assert_eq!(violation.detail["count"], 3);
+        assert_eq!(violation.detail["severity"], "HIGH");
+    }
+
+    #[test]
+    fn tag_rules_only_apply_to_pushes() {
+        let mut deny = policy("no-latest", PolicyRule::DenyTags);
+        deny.tag_patterns = vec!["latest".to_string()];
+        let policies = [deny];
+
+        assert_eq!(check_push(&policies, "latest").unwrap().detail["pattern"], "latest");
+        assert!(check_push(&policies, "v1.0").is_none());
+        assert!(check_pull(&policies, "acme/web", &image(false, None)).is_none());
+    }
+
+    #[test]
+    fn organization_policies_apply_to_their_repositories_and_signature_flags() {
+        let mut scoped = policy("web-signed", PolicyRule::RequireSignature);
+        scoped.repository_id = Some(1);
+        let policies = OrganizationPolicies(vec![scoped]);
+
+        assert_eq!(policies.pull_violation(1, "acme/web", false, &image(false, None)).unwrap().policy, "web-signed");
+        assert!(policies.pull_violation(2, "acme/api", false, &image(false, None)).is_none());
+        let flagged = policies.pull_violation(2, "acme/api", true, &image(false, None)).unwrap();
+        assert_eq!(flagged.policy, SIGNATURE_REQUIRED);
+        assert!(policies.pull_violation(2, "acme/api", true, &image(true, None)).is_none());
+    }
+}
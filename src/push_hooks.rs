@@ -0,0 +1,323 @@
+// src/push_hooks.rs - Custom validation of manifest pushes
+//
+// Every manifest PUT is checked by the registered `PushValidator`s before anything is stored;
+// the first one to deny rejects the push with its reason. The registry always includes
+// `HttpPushHooks`, which asks the endpoints an organization registered under
+// `/api/v1/organizations/{id}/push-hooks`, so organizations can enforce naming conventions or
+// required labels without changes to the registry, and `crate::secret_scanning::SecretScanner`,
+// which does nothing unless `SECRET_SCAN_ENABLED` is set. Deployments embedding the registry can add
+// their own validators with `PushValidators::register`.
+use std::collections::BTreeMap;
+use std::sync::{Arc, OnceLock, RwLock};
+use std::time::Duration;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::Value;
+use sqlx::{FromRow, PgPool};
+
+use crate::models::push_hook::PushHookVerdict;
+use crate::webhooks::egress::EgressPolicy;
+use crate::AppState;
+
+pub const EVENT: &str = "manifest.push.validate";
+
+/// A manifest about to be accepted
+#[derive(Debug, Clone, Serialize)]
+pub struct ManifestPush {
+    /// `None` when the push is about to create the organization
+    pub organization_id: Option<i64>,
+    /// Full `namespace/repository` name
+    pub repository: String,
+    /// Tag or digest the manifest is pushed by
+    pub reference: String,
+    pub digest: String,
+    pub media_type: String,
+    /// The manifest as pushed, `null` when it is not JSON
+    pub manifest: Value,
+    pub user_id: Option<i64>,
+}
+
+impl ManifestPush {
+    /// Labels of the image config, when the manifest has a config blob that is already stored
+    pub async fn config_labels(&self, state: &AppState) -> BTreeMap<String, String> {
+        config_labels(state, &self.repository, &self.manifest).await
+    }
+}
+
+/// Labels of the config of an image manifest of `repository`, empty when it has none or the
+/// config blob is not stored
+pub async fn config_labels(state: &AppState, repository: &str, manifest: &Value) -> BTreeMap<String, String> {
+    let Some(config_digest) = manifest.pointer("/config/digest").and_then(Value::as_str) else {
+        return BTreeMap::new();
+    };
+    let key = format!("{}/{}", repository, config_digest);
+    let Ok(Some(config)) = state.storage.get_blob(&key).await else {
+        return BTreeMap::new();
+    };
+    serde_json::from_slice::<Value>(&config)
+        .ok()
+        .and_then(|config| config.pointer("/config/Labels").cloned())
+        .and_then(|labels| serde_json::from_value(labels).ok())
+        .unwrap_or_default()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Verdict {
+    Allow,
+    Deny(String),
+}
+
+/// A check every manifest push has to pass
+#[async_trait]
+pub trait PushValidator: Send + Sync {
+    /// Shown with the reason of a denial
+    fn name(&self) -> &str;
+
+    /// An error rejects the push like a denial, but is reported as a server error
+    async fn validate(&self, state: &AppState, push: &ManifestPush) -> Result<Verdict>;
+}
+
+/// A push rejected by a validator
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rejection {
+    pub validator: String,
+    pub reason: String,
+}
+
+/// The validators manifest pushes go through, in registration order
+pub struct PushValidators {
+    validators: RwLock<Vec<Arc<dyn PushValidator>>>,
+}
+
+impl Default for PushValidators {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PushValidators {
+    /// The built-in organization push hooks and secret scanner
+    pub fn new() -> Self {
+        let validators: Vec<Arc<dyn PushValidator>> =
+            vec![Arc::new(HttpPushHooks::new()), Arc::new(crate::secret_scanning::SecretScanner)];
+        Self { validators: RwLock::new(validators) }
+    }
+
+    /// Add a validator run after the ones already registered
+    pub fn register(&self, validator: Arc<dyn PushValidator>) {
+        self.validators.write().expect("push validator lock poisoned").push(validator);
+    }
+
+    /// The first denial, if any validator denies the push
+    pub async fn check(&self, state: &AppState, push: &ManifestPush) -> Result<Option<Rejection>> {
+        let validators = self.validators.read().expect("push validator lock poisoned").clone();
+        for validator in validators {
+            if let Verdict::Deny(reason) = validator.validate(state, push).await? {
+                return Ok(Some(Rejection { validator: validator.name().to_string(), reason }));
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// Body POSTed to push hook endpoints
+#[derive(Debug, Serialize)]
+pub struct PushHookPayload<'a> {
+    pub delivery_id: String,
+    /// Always `manifest.push.validate`, also sent as `X-Aerugo-Event`
+    pub event: &'static str,
+    pub timestamp: DateTime<Utc>,
+    pub repository: &'a str,
+    pub reference: &'a str,
+    pub digest: &'a str,
+    pub media_type: &'a str,
+    pub manifest: &'a Value,
+    /// Labels of the image config, empty when it has none or is not stored yet
+    pub labels: &'a BTreeMap<String, String>,
+    pub user_id: Option<i64>,
+}
+
+#[derive(FromRow)]
+struct Hook {
+    id: i64,
+    name: String,
+    url: String,
+    secret: Option<String>,
+    timeout_ms: i32,
+    fail_open: bool,
+}
+
+/// Asks each active push hook of the pushing organization for a verdict. Hooks are only called
+/// at addresses `webhooks::egress` permits.
+pub struct HttpPushHooks {
+    /// Built on the first push, from the settings it is checked with
+    client: OnceLock<reqwest::Client>,
+}
+
+impl Default for HttpPushHooks {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HttpPushHooks {
+    pub fn new() -> Self {
+        Self { client: OnceLock::new() }
+    }
+
+    fn client(&self, state: &AppState) -> &reqwest::Client {
+        self.client.get_or_init(|| {
+            EgressPolicy::new(&state.config.webhooks)
+                .client_builder()
+                .user_agent(concat!("aerugo-push-hooks/", env!("CARGO_PKG_VERSION")))
+                .build()
+                .expect("HTTP client configuration is static")
+        })
+    }
+
+    async fn call(&self, state: &AppState, hook: &Hook, body: &[u8], delivery_id: &str) -> Result<PushHookVerdict, String> {
+        EgressPolicy::new(&state.config.webhooks)
+            .check_url(&hook.url)
+            .map_err(|e| format!("Refused to call: {}", e))?;
+        let signature = state.webhook_signer.sign(body, hook.secret.as_deref());
+        let mut request = self
+            .client(state)
+            .post(&hook.url)
+            .timeout(Duration::from_millis(hook.timeout_ms.max(0) as u64))
+            .header("Content-Type", "application/json")
+            .header(crate::webhooks::delivery::EVENT_HEADER, EVENT)
+            .header(crate::webhooks::delivery::DELIVERY_HEADER, delivery_id);
+        for (name, value) in signature.headers() {
+            request = request.header(name, value);
+        }
+
+        let response = request.body(body.to_vec()).send().await.map_err(|e| e.to_string())?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(format!("Endpoint responded with {}", status));
+        }
+        response
+            .json::<PushHookVerdict>()
+            .await
+            .map_err(|e| format!("Endpoint did not answer with a verdict: {}", e))
+    }
+}
+
+#[async_trait]
+impl PushValidator for HttpPushHooks {
+    fn name(&self) -> &str {
+        "push-hooks"
+    }
+
+    async fn validate(&self, state: &AppState, push: &ManifestPush) -> Result<Verdict> {
+        let Some(org_id) = push.organization_id else {
+            return Ok(Verdict::Allow);
+        };
+        let hooks = sqlx::query_as::<_, Hook>(
+            "SELECT id, name, url, secret, timeout_ms, fail_open
+             FROM organization_push_hooks
+             WHERE organization_id = $1 AND active
+             ORDER BY id",
+        )
+        .bind(org_id)
+        .fetch_all(&state.db_pool)
+        .await?;
+        if hooks.is_empty() {
+            return Ok(Verdict::Allow);
+        }
+
+        let labels = push.config_labels(state).await;
+        let delivery_id = uuid::Uuid::new_v4().to_string();
+        let payload = PushHookPayload {
+            delivery_id: delivery_id.clone(),
+            event: EVENT,
+            timestamp: Utc::now(),
+            repository: &push.repository,
+            reference: &push.reference,
+            digest: &push.digest,
+            media_type: &push.media_type,
+            manifest: &push.manifest,
+            labels: &labels,
+            user_id: push.user_id,
+        };
+        let body = serde_json::to_vec(&payload)?;
+
+        // One after the other, so the first denial is the one reported
+        for hook in hooks {
+            let outcome = self.call(state, &hook, &body, &delivery_id).await;
+            let verdict = verdict_for(&hook.name, hook.fail_open, &outcome);
+            let recorded = match &outcome {
+                Ok(v) if v.allowed => ("allow", None),
+                Ok(_) => ("deny", None),
+                Err(e) => ("error", Some(e.as_str())),
+            };
+            if let Err(e) = record_outcome(&state.db_pool, hook.id, recorded.0, recorded.1).await {
+                tracing::error!("Failed to record push hook {} outcome: {}", hook.id, e);
+            }
+            if let Err(e) = &outcome {
+                tracing::warn!("Push hook {} failed for {}@{}: {}", hook.id, push.repository, push.digest, e);
+            }
+            if verdict != Verdict::Allow {
+                return Ok(verdict);
+            }
+        }
+        Ok(Verdict::Allow)
+    }
+}
+
+/// Verdict for one hook's answer, or its failure to answer
+fn verdict_for(name: &str, fail_open: bool, outcome: &Result<PushHookVerdict, String>) -> Verdict {
+    match outcome {
+        Ok(verdict) if verdict.allowed => Verdict::Allow,
+        Ok(verdict) => Verdict::Deny(format!(
+            "{}: {}",
+            name,
+            verdict.reason.as_deref().filter(|r| !r.trim().is_empty()).unwrap_or("push denied")
+        )),
+        Err(_) if fail_open => Verdict::Allow,
+        Err(_) => Verdict::Deny(format!("{}: push hook unavailable, try again later", name)),
+    }
+}
+
+async fn record_outcome(pool: &PgPool, hook_id: i64, verdict: &str, error: Option<&str>) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "UPDATE organization_push_hooks
+         SET last_called_at = CURRENT_TIMESTAMP, last_verdict = $2, last_error = $3
+         WHERE id = $1",
+    )
+    .bind(hook_id)
+    .bind(verdict)
+    .bind(error)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn answer(allowed: bool, reason: Option<&str>) -> Result<PushHookVerdict, String> {
+        Ok(PushHookVerdict { allowed, reason: reason.map(str::to_string) })
+    }
+
+    #[test]
+    fn verdicts_follow_the_hook_answer() {
+        assert_eq!(verdict_for("labels", false, &answer(true, None)), Verdict::Allow);
+        assert_eq!(
+            verdict_for("labels", false, &answer(false, Some("missing org.opencontainers.image.source"))),
+            Verdict::Deny("labels: missing org.opencontainers.image.source".to_string())
+        );
+        assert_eq!(verdict_for("labels", true, &answer(false, Some(" "))), Verdict::Deny("labels: push denied".to_string()));
+    }
+
+    #[test]
+    fn unreachable_hooks_follow_fail_open() {
+        let failed = Err("connection refused".to_string());
+        assert_eq!(verdict_for("labels", true, &failed), Verdict::Allow);
+        assert!(matches!(verdict_for("labels", false, &failed), Verdict::Deny(_)));
+    }
+}
@@ -0,0 +1,245 @@
+// src/quarantine.rs - Holding newly pushed images back from pulls
+//
+// Repositories with `quarantine_pushes` set record each image manifest pushed to them in
+// `manifest_quarantines` before the push is acknowledged. `RequireRepoPermission` refuses pulls
+// of a quarantined manifest to everyone but repository administrators and registry
+// administrators, until the manifest's vulnerability scan completes or an administrator approves
+// it. Signatures, SBOMs and model metadata attached to a held image are not held themselves, so
+// they can be added meanwhile; anything that could be run is, whatever it claims to be.
+use anyhow::Result;
+use serde_json::{json, Value};
+use sqlx::PgExecutor;
+
+use crate::handlers::admin::find_admin;
+use crate::handlers::docker_auth::check_repository_permission;
+use crate::handlers::model_registry::{MODEL_CARD_ARTIFACT_TYPE, MODEL_LINEAGE_ARTIFACT_TYPE};
+use crate::handlers::registry_auth::RepoAccess;
+use crate::log_stream::LogEvent;
+use crate::models::sbom::SbomFormat;
+use crate::policies::Violation;
+use crate::signatures::{signed_digest, COSIGN_SIGNATURE_ARTIFACT_TYPE, NOTATION_SIGNATURE_ARTIFACT_TYPE};
+use crate::webhooks::chat::short_digest;
+use crate::AppState;
+
+/// Config media types of images a container runtime can run
+const IMAGE_CONFIG_MEDIA_TYPES: [&str; 2] =
+    ["application/vnd.oci.image.config.v1+json", "application/vnd.docker.container.image.v1+json"];
+/// Layer media type of the payloads in a cosign `.sig` tag
+const COSIGN_SIMPLESIGNING_MEDIA_TYPE: &str = "application/vnd.dev.cosign.simplesigning.v1+json";
+
+/// How a manifest left quarantine
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Release {
+    /// Its vulnerability scan completed
+    Scan,
+    /// An administrator approved it
+    Approval,
+}
+
+impl Release {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Release::Scan => "scan",
+            Release::Approval => "approval",
+        }
+    }
+}
+
+/// Quarantine a pushed manifest if its repository quarantines pushes, returning whether it was.
+/// A digest that was released before stays released.
+pub async fn hold<'e, E: PgExecutor<'e>>(executor: E, repository_id: i64, digest: &str) -> Result<bool> {
+    let held = sqlx::query(
+        "INSERT INTO manifest_quarantines (repository_id, manifest_digest)
+         SELECT id, $2 FROM repositories WHERE id = $1 AND quarantine_pushes
+         ON CONFLICT (repository_id, manifest_digest) DO NOTHING",
+    )
+    .bind(repository_id)
+    .bind(digest)
+    .execute(executor)
+    .await?;
+    Ok(held.rows_affected() > 0)
+}
+
+/// Whether a manifest pushed to `reference` is held in a repository that quarantines pushes. Only
+/// referrers of a known non-runnable kind without an image config, and cosign signatures under
+/// the `sha256-<hex>.sig` tag of the manifest they sign, go unheld; a `subject` or a `sha256-`
+/// tag alone does not exempt an image.
+pub fn holds(reference: &str, manifest: &[u8]) -> bool {
+    let Ok(manifest) = serde_json::from_slice::<Value>(manifest) else {
+        return true;
+    };
+    let config_type = manifest.pointer("/config/mediaType").and_then(Value::as_str);
+    let layer_types: Vec<Option<&str>> = manifest
+        .get("layers")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .map(|layer| layer.get("mediaType").and_then(Value::as_str))
+        .collect();
+
+    // Legacy cosign signatures carry an image config, but their layers are signing payloads
+    if signed_digest(reference).is_some() {
+        return layer_types.is_empty() || layer_types.iter().any(|t| *t != Some(COSIGN_SIMPLESIGNING_MEDIA_TYPE));
+    }
+
+    if manifest.pointer("/subject/digest").is_none() || config_type.is_some_and(|t| IMAGE_CONFIG_MEDIA_TYPES.contains(&t)) {
+        return true;
+    }
+    let artifact_type = manifest.get("artifactType").and_then(Value::as_str).or(config_type);
+    !artifact_type.is_some_and(is_exempt_artifact_type)
+}
+
+/// Referrers that describe or sign an image rather than being one
+fn is_exempt_artifact_type(artifact_type: &str) -> bool {
+    matches!(
+        artifact_type,
+        COSIGN_SIGNATURE_ARTIFACT_TYPE | NOTATION_SIGNATURE_ARTIFACT_TYPE | MODEL_CARD_ARTIFACT_TYPE | MODEL_LINEAGE_ARTIFACT_TYPE
+    ) || SbomFormat::from_artifact_type(artifact_type).is_some()
+}
+
+/// Release a quarantined manifest, returning whether it was held. Publishes `quarantine.release`.
+pub async fn release(
+    state: &AppState,
+    repository_id: i64,
+    repository: &str,
+    digest: &str,
+    how: Release,
+    released_by: Option<i64>,
+) -> Result<bool> {
+    let released = sqlx::query(
+        "UPDATE manifest_quarantines
+         SET status = 'released', released_at = CURRENT_TIMESTAMP, release_reason = $3, released_by = $4
+         WHERE repository_id = $1 AND manifest_digest = $2 AND status = 'quarantined'",
+    )
+    .bind(repository_id)
+    .bind(digest)
+    .bind(how.as_str())
+    .bind(released_by)
+    .execute(&state.db_pool)
+    .await?;
+    if released.rows_affected() == 0 {
+        return Ok(false);
+    }
+
+    tracing::info!("Released {}@{} from quarantine ({})", repository, digest, how.as_str());
+    state.log_stream.publish(
+        LogEvent::audit("quarantine.release", released_by, Some(repository.to_string()))
+            .with_detail(format!("{}: released by {}", short_digest(digest), how.as_str())),
+    );
+    Ok(true)
+}
+
+/// The refusal of a pull of a quarantined manifest, unless the caller administers the repository.
+/// Unknown repositories and references are let through for the handler to answer.
+pub async fn check_pull(
+    state: &AppState,
+    access: &RepoAccess,
+    namespace: Option<&str>,
+    name: &str,
+    reference: &str,
+) -> Result<Option<Violation>> {
+    let held = sqlx::query_scalar::<_, String>(
+        "SELECT m.digest
+         FROM repositories r
+         JOIN organizations o ON o.id = r.organization_id
+         JOIN manifests m ON m.repository_id = r.id
+         JOIN manifest_quarantines q ON q.repository_id = r.id AND q.manifest_digest = m.digest AND q.status = 'quarantined'
+         WHERE r.name = $2 AND (o.name = $1 OR ($1 IS NULL AND o.id = 1))
+           AND (m.digest = $3 OR m.id = (SELECT t.manifest_id FROM tags t WHERE t.repository_id = r.id AND t.name = $3))
+         LIMIT 1",
+    )
+    .bind(namespace)
+    .bind(name)
+    .bind(reference)
+    .fetch_optional(&state.db_pool)
+    .await?;
+    let Some(digest) = held else {
+        return Ok(None);
+    };
+    if is_administrator(state, access).await? {
+        return Ok(None);
+    }
+
+    Ok(Some(Violation {
+        policy: "quarantine".to_string(),
+        message: format!("{} is quarantined until it is scanned or approved", short_digest(&digest)),
+        detail: json!({
+            "policy": "quarantine",
+            "digest": digest,
+        }),
+    }))
+}
+
+/// Whether the caller may pull quarantined manifests and release them: anyone who may delete from
+/// the repository, and registry administrators
+pub async fn is_administrator(state: &AppState, access: &RepoAccess) -> Result<bool> {
+    let Some(user) = access.user.as_deref() else {
+        return Ok(false);
+    };
+    if check_repository_permission(user, &access.namespace, &access.repository, "delete", state).await? {
+        return Ok(true);
+    }
+    match access.user_id() {
+        Some(user_id) => Ok(find_admin(state, user_id).await?.is_some()),
+        None => Ok(false),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const IMAGE: &str = r#"{
+        "schemaVersion": 2,
+        "config": {"mediaType": "application/vnd.oci.image.config.v1+json", "digest": "sha256:c", "size": 7},
+        "layers": [{"mediaType": "application/vnd.oci.image.layer.v1.tar+gzip", "digest": "sha256:l", "size": 9}]
+    }"#;
+    const SIGNED: &str = "sha256-4bcff63911fcb4448bd4fdacec207030997caf25e9bea4045fa6c8c44de311d1.sig";
+
+    fn referrer(artifact_type: &str, config_type: &str) -> String {
+        json!({
+            "schemaVersion": 2,
+            "artifactType": artifact_type,
+            "config": {"mediaType": config_type, "digest": "sha256:e", "size": 2},
+            "layers": [{"mediaType": artifact_type, "digest": "sha256:p", "size": 5}],
+            "subject": {"mediaType": "application/vnd.oci.image.manifest.v1+json", "digest": "sha256:s", "size": 3},
+        })
+        .to_string()
+    }
+
+    #[test]
+    fn images_are_held_whatever_their_tag_or_subject() {
+        assert!(holds("latest", IMAGE.as_bytes()));
+        assert!(holds("sha256-x", IMAGE.as_bytes()));
+        assert!(holds(SIGNED, IMAGE.as_bytes()));
+
+        let mut with_subject: Value = serde_json::from_str(IMAGE).unwrap();
+        with_subject["subject"] = json!({"digest": "sha256:s"});
+        assert!(holds("latest", with_subject.to_string().as_bytes()));
+        with_subject["artifactType"] = json!(COSIGN_SIGNATURE_ARTIFACT_TYPE);
+        assert!(holds("latest", with_subject.to_string().as_bytes()));
+
+        assert!(holds("latest", b"not a manifest"));
+    }
+
+    #[test]
+    fn signatures_sboms_and_model_metadata_are_not_held() {
+        let empty = "application/vnd.oci.empty.v1+json";
+        assert!(!holds("sha256:abc", referrer(COSIGN_SIGNATURE_ARTIFACT_TYPE, empty).as_bytes()));
+        assert!(!holds("sha256:abc", referrer(NOTATION_SIGNATURE_ARTIFACT_TYPE, empty).as_bytes()));
+        assert!(!holds("sha256:abc", referrer("application/spdx+json", empty).as_bytes()));
+        assert!(!holds("sha256:abc", referrer(MODEL_CARD_ARTIFACT_TYPE, empty).as_bytes()));
+        assert!(holds("sha256:abc", referrer("application/vnd.example.anything", empty).as_bytes()));
+        assert!(holds("sha256:abc", referrer("application/spdx+json", IMAGE_CONFIG_MEDIA_TYPES[1]).as_bytes()));
+
+        let signature = json!({
+            "schemaVersion": 2,
+            "config": {"mediaType": IMAGE_CONFIG_MEDIA_TYPES[0], "digest": "sha256:c", "size": 7},
+            "layers": [{"mediaType": COSIGN_SIMPLESIGNING_MEDIA_TYPE, "digest": "sha256:p", "size": 5}],
+        })
+        .to_string();
+        assert!(!holds(SIGNED, signature.as_bytes()));
+        assert!(holds("sha256-x", signature.as_bytes()));
+        assert!(holds("latest", signature.as_bytes()));
+    }
+}
@@ -0,0 +1,247 @@
+// src/quota.rs - Quota tiers: per-organization limits on storage, repositories and members
+//
+// An organization is held to the tier a registry administrator assigned it, or to the default
+// tier when it has none. Requests that would go over a limit are rejected with a payload naming
+// the limit, the tier, the allowed amount and the current usage, so clients can tell which one
+// was hit. The tier's download rate is applied by `crate::bandwidth`.
+use std::fmt;
+
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde_json::{json, Value};
+use sqlx::PgPool;
+
+use crate::handlers::organizations::{load_org_settings, org_storage_used};
+use crate::models::quota_tier::QuotaTier;
+
+pub(crate) const TIER_SELECT: &str = "SELECT id, name, description, max_storage_bytes, max_repositories, max_members,
+            download_bytes_per_second, is_default, created_at, updated_at
+     FROM quota_tiers";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuotaLimit {
+    Storage,
+    Repositories,
+    Members,
+}
+
+impl QuotaLimit {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            QuotaLimit::Storage => "storage",
+            QuotaLimit::Repositories => "repositories",
+            QuotaLimit::Members => "members",
+        }
+    }
+}
+
+/// A limit a request would go over
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuotaExceeded {
+    pub limit: QuotaLimit,
+    /// Tier the limit comes from; `None` for the storage quota an organization set itself
+    pub tier: Option<String>,
+    pub allowed: i64,
+    pub current: i64,
+}
+
+impl fmt::Display for QuotaExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Some(tier) = &self.tier else {
+            return write!(
+                f,
+                "Organization storage quota exceeded: {} of {} bytes used; delete images or ask an owner to raise the quota",
+                self.current, self.allowed
+            );
+        };
+        match self.limit {
+            QuotaLimit::Storage => write!(
+                f,
+                "Storage limit of the '{}' tier reached: {} of {} bytes used; delete images or ask an administrator for a larger tier",
+                tier, self.current, self.allowed
+            ),
+            QuotaLimit::Repositories => write!(
+                f,
+                "Repository limit of the '{}' tier reached: the organization has {} of {} repositories",
+                tier, self.current, self.allowed
+            ),
+            QuotaLimit::Members => write!(
+                f,
+                "Member limit of the '{}' tier reached: the organization has {} of {} members",
+                tier, self.current, self.allowed
+            ),
+        }
+    }
+}
+
+impl std::error::Error for QuotaExceeded {}
+
+impl QuotaExceeded {
+    pub fn detail(&self) -> Value {
+        json!({
+            "limit": self.limit.as_str(),
+            "tier": self.tier,
+            "allowed": self.allowed,
+            "current": self.current
+        })
+    }
+
+    /// `403` in the management API's error format
+    pub fn api_error(&self) -> (StatusCode, Json<Value>) {
+        (
+            StatusCode::FORBIDDEN,
+            Json(json!({
+                "error": self.to_string(),
+                "quota": self.detail()
+            })),
+        )
+    }
+
+    /// `403` in the registry API's error format
+    pub fn registry_error(&self) -> Response {
+        (
+            StatusCode::FORBIDDEN,
+            Json(json!({
+                "errors": [{
+                    "code": "DENIED",
+                    "message": self.to_string(),
+                    "detail": self.detail()
+                }]
+            })),
+        )
+            .into_response()
+    }
+}
+
+/// Tier assigned to an organization, or the default tier when it has none
+pub async fn org_tier(pool: &PgPool, org_id: i64) -> Result<Option<QuotaTier>, sqlx::Error> {
+    sqlx::query_as::<_, QuotaTier>(&format!(
+        "{} WHERE id = (SELECT quota_tier_id FROM organizations WHERE id = $1)
+            OR (is_default AND (SELECT quota_tier_id FROM organizations WHERE id = $1) IS NULL)
+         LIMIT 1",
+        TIER_SELECT
+    ))
+    .bind(org_id)
+    .fetch_optional(pool)
+    .await
+}
+
+/// Storage limit of an organization: the tighter of its own quota and its tier's, with the
+/// tier's name when the tier's is the one that applies
+pub fn storage_limit(own_quota: Option<i64>, tier: Option<&QuotaTier>) -> Option<(i64, Option<String>)> {
+    let tier_limit = tier.and_then(|t| t.max_storage_bytes.map(|max| (max, Some(t.name.clone()))));
+    match (own_quota, tier_limit) {
+        (Some(own), Some((max, name))) if max < own => Some((max, name)),
+        (Some(own), _) => Some((own, None)),
+        (None, tier_limit) => tier_limit,
+    }
+}
+
+/// Whether an organization using `used` bytes can take `incoming` more: it must be under its
+/// limit and stay within it
+pub fn storage_exceeded(
+    own_quota: Option<i64>,
+    tier: Option<&QuotaTier>,
+    used: i64,
+    incoming: i64,
+) -> Option<QuotaExceeded> {
+    let (allowed, tier) = storage_limit(own_quota, tier)?;
+    if used < allowed && used.saturating_add(incoming) <= allowed {
+        return None;
+    }
+    Some(QuotaExceeded { limit: QuotaLimit::Storage, tier, allowed, current: used })
+}
+
+/// Whether an organization can store `incoming` more bytes
+pub async fn check_storage(pool: &PgPool, org_id: i64, incoming: i64) -> Result<Option<QuotaExceeded>, sqlx::Error> {
+    let own_quota = load_org_settings(pool, org_id).await?.storage_quota_bytes;
+    let tier = org_tier(pool, org_id).await?;
+    if storage_limit(own_quota, tier.as_ref()).is_none() {
+        return Ok(None);
+    }
+    let used = org_storage_used(pool, org_id).await?;
+    Ok(storage_exceeded(own_quota, tier.as_ref(), used, incoming))
+}
+
+/// Whether an organization can have one more repository
+pub async fn check_repositories(pool: &PgPool, org_id: i64) -> Result<Option<QuotaExceeded>, sqlx::Error> {
+    let Some(tier) = org_tier(pool, org_id).await? else {
+        return Ok(None);
+    };
+    let Some(allowed) = tier.max_repositories else {
+        return Ok(None);
+    };
+    let current = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM repositories WHERE organization_id = $1")
+        .bind(org_id)
+        .fetch_one(pool)
+        .await?;
+    Ok(count_exceeded(QuotaLimit::Repositories, &tier, allowed, current))
+}
+
+/// Whether an organization can have one more member
+pub async fn check_members(pool: &PgPool, org_id: i64) -> Result<Option<QuotaExceeded>, sqlx::Error> {
+    let Some(tier) = org_tier(pool, org_id).await? else {
+        return Ok(None);
+    };
+    let Some(allowed) = tier.max_members else {
+        return Ok(None);
+    };
+    let current = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM organization_members WHERE organization_id = $1")
+        .bind(org_id)
+        .fetch_one(pool)
+        .await?;
+    Ok(count_exceeded(QuotaLimit::Members, &tier, allowed, current))
+}
+
+fn count_exceeded(limit: QuotaLimit, tier: &QuotaTier, allowed: i64, current: i64) -> Option<QuotaExceeded> {
+    (current >= allowed).then(|| QuotaExceeded { limit, tier: Some(tier.name.clone()), allowed, current })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tier(max_storage_bytes: Option<i64>) -> QuotaTier {
+        QuotaTier {
+            id: 1,
+            name: "free".to_string(),
+            description: None,
+            max_storage_bytes,
+            max_repositories: Some(3),
+            max_members: Some(5),
+            download_bytes_per_second: None,
+            is_default: true,
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+        }
+    }
+
+    #[test]
+    fn tighter_storage_limit_applies() {
+        assert_eq!(storage_limit(Some(100), Some(&tier(Some(50)))), Some((50, Some("free".to_string()))));
+        assert_eq!(storage_limit(Some(100), Some(&tier(Some(500)))), Some((100, None)));
+        assert_eq!(storage_limit(None, Some(&tier(Some(500)))), Some((500, Some("free".to_string()))));
+        assert_eq!(storage_limit(None, Some(&tier(None))), None);
+        assert_eq!(storage_limit(None, None), None);
+    }
+
+    #[test]
+    fn storage_is_exceeded_at_or_beyond_the_limit() {
+        let tier = tier(Some(100));
+        assert_eq!(storage_exceeded(None, Some(&tier), 60, 40), None);
+        assert!(storage_exceeded(None, Some(&tier), 60, 41).is_some());
+        // A full organization cannot start new uploads
+        assert!(storage_exceeded(None, Some(&tier), 100, 0).is_some());
+    }
+
+    #[test]
+    fn payload_names_the_limit_hit() {
+        let exceeded = count_exceeded(QuotaLimit::Repositories, &tier(None), 3, 3).unwrap();
+        assert_eq!(exceeded.detail(), json!({"limit": "repositories", "tier": "free", "allowed": 3, "current": 3}));
+        assert!(exceeded.to_string().contains("'free' tier"));
+        assert_eq!(count_exceeded(QuotaLimit::Members, &tier(None), 5, 4), None);
+    }
+}
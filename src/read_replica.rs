@@ -0,0 +1,112 @@
+// src/read_replica.rs - Sending heavy read-only queries to a Postgres replica
+//
+// With `DATABASE_READ_URL` set, the catalog, tag lists and searches read from the replica
+// through `AppState::read_pool`, while everything else, including manifest GET and HEAD
+// resolution that must see a push just made, and every write, stays on the primary. A monitor
+// checks the replica every `DATABASE_READ_CHECK_INTERVAL_SECONDS`; reads go back to the primary
+// while the replica cannot be reached or trails the primary by more than
+// `DATABASE_READ_MAX_LAG_SECONDS`, and return to it once it has caught up.
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use sqlx::PgPool;
+
+use crate::config::settings::DatabaseSettings;
+use crate::standby::{replication_status, ReplicationMode};
+use crate::AppState;
+
+/// The replica pool and whether reads currently go to it
+pub struct ReadReplica {
+    pool: Option<PgPool>,
+    available: AtomicBool,
+}
+
+impl ReadReplica {
+    /// Reads start on the primary until the monitor's first check succeeds
+    pub fn new(pool: Option<PgPool>) -> Self {
+        Self { pool, available: AtomicBool::new(false) }
+    }
+
+    /// The pool for read-only queries: the replica while it is usable, otherwise `primary`
+    pub fn pool<'a>(&'a self, primary: &'a PgPool) -> &'a PgPool {
+        match &self.pool {
+            Some(replica) if self.available.load(Ordering::Acquire) => replica,
+            _ => primary,
+        }
+    }
+
+    pub fn is_available(&self) -> bool {
+        self.available.load(Ordering::Acquire)
+    }
+
+    /// Record the result of a check, logging when reads move between the replica and the primary
+    fn set_available(&self, available: bool, reason: &str) {
+        let was_available = self.available.swap(available, Ordering::AcqRel);
+        match (was_available, available) {
+            (false, true) => tracing::info!("Read replica is available; routing reads to it"),
+            (true, false) => tracing::warn!("Read replica unusable ({}); routing reads to the primary", reason),
+            _ => {}
+        }
+    }
+}
+
+/// Whether a sample of the replica allows reading from it, or why not
+fn usable(mode: ReplicationMode, lag_seconds: Option<f64>, max_lag_seconds: u64) -> Result<(), String> {
+    if mode != ReplicationMode::Physical {
+        // A logical subscriber or a plain copy has no lag to measure; trust it to be current
+        return Ok(());
+    }
+    match lag_seconds {
+        Some(lag) if lag > max_lag_seconds as f64 => Err(format!("{:.1}s behind the primary", lag)),
+        Some(_) => Ok(()),
+        None => Err("replication lag unknown".to_string()),
+    }
+}
+
+async fn check(pool: &PgPool, settings: &DatabaseSettings) -> Result<(), String> {
+    let timeout = Duration::from_secs(settings.read_check_interval_seconds.max(1));
+    match tokio::time::timeout(timeout, replication_status(pool, None)).await {
+        Ok(Ok(status)) => usable(status.mode, status.lag_seconds, settings.read_max_lag_seconds),
+        Ok(Err(e)) => Err(format!("{:#}", e)),
+        Err(_) => Err("check timed out".to_string()),
+    }
+}
+
+/// Check the replica's reachability and lag, moving reads between it and the primary
+pub fn spawn_read_replica_monitor(state: AppState) {
+    let Some(pool) = state.read_replica.pool.clone() else {
+        return;
+    };
+    let settings = state.config.database.clone();
+
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(settings.read_check_interval_seconds));
+        loop {
+            interval.tick().await;
+            match check(&pool, &settings).await {
+                Ok(()) => state.read_replica.set_available(true, ""),
+                Err(reason) => {
+                    if !state.read_replica.is_available() {
+                        tracing::debug!("Read replica still unusable: {}", reason);
+                    }
+                    state.read_replica.set_available(false, &reason);
+                }
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lagging_or_unknown_replicas_are_not_read_from() {
+        assert!(usable(ReplicationMode::Physical, Some(0.0), 10).is_ok());
+        assert!(usable(ReplicationMode::Physical, Some(10.0), 10).is_ok());
+        assert_eq!(usable(ReplicationMode::Physical, Some(12.5), 10), Err("12.5s behind the primary".to_string()));
+        assert!(usable(ReplicationMode::Physical, None, 10).is_err());
+        assert!(usable(ReplicationMode::Logical, None, 10).is_ok());
+        assert!(usable(ReplicationMode::None, None, 10).is_ok());
+    }
+}
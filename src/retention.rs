@@ -0,0 +1,114 @@
+// src/retention.rs - Data retention policy and client address anonymization
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::config::settings::RetentionSettings;
+
+/// One retention or privacy setting, described so operators can audit the policy without
+/// reading the deployment's environment
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct RetentionSetting {
+    pub name: &'static str,
+    /// Environment variable that configures the setting
+    pub env: &'static str,
+    #[schema(value_type = Object)]
+    pub value: serde_json::Value,
+    #[schema(value_type = Object)]
+    pub default: serde_json::Value,
+    pub description: &'static str,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct RetentionPolicy {
+    pub settings: Vec<RetentionSetting>,
+}
+
+impl RetentionPolicy {
+    pub fn describe(settings: &RetentionSettings) -> Self {
+        Self {
+            settings: vec![
+                RetentionSetting {
+                    name: "audit_log_retention_days",
+                    env: "RETENTION_AUDIT_LOG_DAYS",
+                    value: settings.audit_log_retention_days.into(),
+                    default: 7.into(),
+                    description: "Audit and access events older than this many days are dropped from the in-memory log replay buffer",
+                },
+                RetentionSetting {
+                    name: "usage_retention_days",
+                    env: "RETENTION_USAGE_DAYS",
+                    value: settings.usage_retention_days.into(),
+                    default: 90.into(),
+                    description: "Daily API usage statistics older than this many days are deleted",
+                },
+                RetentionSetting {
+                    name: "event_retention_days",
+                    env: "RETENTION_EVENT_DAYS",
+                    value: settings.event_retention_days.into(),
+                    default: 365.into(),
+                    description: "Registry events older than this many days are deleted from the repository and organization event history",
+                },
+                RetentionSetting {
+                    name: "deleted_data_grace_days",
+                    env: "RETENTION_DELETED_DATA_GRACE_DAYS",
+                    value: settings.deleted_data_grace_days.into(),
+                    default: 0.into(),
+                    description: "Expired API keys, refresh tokens and token revocations are kept this many days before they are deleted",
+                },
+                RetentionSetting {
+                    name: "anonymize_ips",
+                    env: "RETENTION_ANONYMIZE_IPS",
+                    value: settings.anonymize_ips.into(),
+                    default: false.into(),
+                    description: "Client addresses in access logs and account notifications are truncated to their /24 (IPv4) or /48 (IPv6) network",
+                },
+            ],
+        }
+    }
+}
+
+/// Zero the host part of an address: the last octet of IPv4, everything after the /48 of IPv6.
+/// IPv4-mapped IPv6 addresses are treated as IPv4.
+pub fn anonymize_ip(ip: IpAddr) -> IpAddr {
+    match ip {
+        IpAddr::V4(v4) => {
+            let [a, b, c, _] = v4.octets();
+            IpAddr::V4(Ipv4Addr::new(a, b, c, 0))
+        }
+        IpAddr::V6(v6) => match v6.to_ipv4_mapped() {
+            Some(v4) => anonymize_ip(IpAddr::V4(v4)),
+            None => {
+                let s = v6.segments();
+                IpAddr::V6(Ipv6Addr::new(s[0], s[1], s[2], 0, 0, 0, 0, 0))
+            }
+        },
+    }
+}
+
+/// A client address as it may be recorded under the configured policy. Values that are not
+/// addresses (e.g. `unknown`) are returned unchanged.
+pub fn loggable_ip(settings: &RetentionSettings, ip: &str) -> String {
+    match ip.parse::<IpAddr>() {
+        Ok(addr) if settings.anonymize_ips => anonymize_ip(addr).to_string(),
+        _ => ip.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::anonymize_ip;
+    use std::net::IpAddr;
+
+    fn anonymized(ip: &str) -> String {
+        anonymize_ip(ip.parse::<IpAddr>().unwrap()).to_string()
+    }
+
+    #[test]
+    fn truncates_host_bits() {
+        assert_eq!(anonymized("203.0.113.77"), "203.0.113.0");
+        assert_eq!(anonymized("2001:db8:85a3:8d3:1319:8a2e:370:7348"), "2001:db8:85a3::");
+        assert_eq!(anonymized("::ffff:198.51.100.9"), "198.51.100.0");
+    }
+}
@@ -0,0 +1,35 @@
+use axum::{
+    routing::{delete, get, post, put},
+    Router,
+};
+
+use crate::handlers::{admin, features, ip_access, jobs, legal_holds, quota_tiers, takedowns};
+use crate::AppState;
+
+pub fn admin_router() -> Router<AppState> {
+    Router::new()
+        .route("/users", get(admin::list_users))
+        .route("/users/:user_id/disable", post(admin::disable_user))
+        .route("/users/:user_id/enable", post(admin::enable_user))
+        .route("/users/:user_id/admin", put(admin::set_user_admin))
+        .route("/stats/storage", get(admin::storage_stats))
+        .route("/cache/stats", get(admin::cache_stats))
+        .route("/cache/flush", post(admin::flush_cache))
+        .route("/retention", get(admin::retention_policy))
+        .route("/config/reload", post(admin::reload_config))
+        .route("/features", get(features::list_features))
+        .route("/features/:name", put(features::set_feature).delete(features::clear_feature))
+        .route("/jobs", get(jobs::list_jobs))
+        .route("/jobs/:id/retry", post(jobs::retry_job))
+        .route("/takedowns", get(takedowns::list_takedowns).post(takedowns::create_takedown))
+        .route("/takedowns/:id", get(takedowns::get_takedown))
+        .route("/takedowns/:id/reinstate", post(takedowns::reinstate_takedown))
+        .route("/quota-tiers", get(quota_tiers::list_quota_tiers).post(quota_tiers::create_quota_tier))
+        .route("/quota-tiers/:tier_id", put(quota_tiers::update_quota_tier).delete(quota_tiers::delete_quota_tier))
+        .route("/organizations/:id/quota-tier", put(quota_tiers::assign_quota_tier))
+        .route("/organizations/:id/legal-holds", get(legal_holds::list_legal_holds).post(legal_holds::create_legal_hold))
+        .route("/organizations/:id/legal-holds/:hold_id/manifest", get(legal_holds::get_legal_hold_manifest))
+        .route("/organizations/:id/legal-holds/:hold_id/release", post(legal_holds::release_legal_hold))
+        .route("/ip-rules", get(ip_access::list_global_ip_rules).post(ip_access::create_global_ip_rule))
+        .route("/ip-rules/:rule_id", delete(ip_access::delete_global_ip_rule))
+}
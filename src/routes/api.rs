@@ -1,7 +1,7 @@
 use crate::handlers;
 use crate::AppState;
 use axum::{
-    routing::get,
+    routing::{get, post},
     Router,
 };
 
@@ -11,8 +11,28 @@ pub fn api_router() -> Router<AppState> {
         .nest("/auth", super::auth::auth_router())
         // Mount organization routes under /organizations prefix
         .nest("/organizations", super::organizations::organization_router())
+        // Mount invite link routes under /invitations prefix
+        .nest("/invitations", super::invitations::invitation_router())
         // Mount storage routes under /storage prefix
         .nest("/storage", super::storage::routes())
         // Mount repository management routes under /repos prefix
         .nest("/repos", super::repositories::repository_router())
+        // Mount webhook verification routes under /webhooks prefix
+        .nest("/webhooks", super::webhooks::webhook_router())
+        // Avatars are public files linked from user and organization profiles
+        .route("/avatars/:kind/:id/:file", get(handlers::avatars::get_avatar))
+        // Live registry events for the frontend, scoped to what the user may see
+        .route("/events/stream", get(handlers::events::stream_events))
+        // Which images contain a package, from their SBOMs
+        .route("/sbom/packages", get(handlers::sbom::search_packages))
+        // Mount live log tailing under /logs prefix
+        .nest("/logs", super::logs::logs_router())
+        // Mount warm standby status and promotion under /standby prefix
+        .nest("/standby", super::standby::standby_router())
+        // First-run setup, guarded by BOOTSTRAP_TOKEN
+        .route("/bootstrap", post(handlers::bootstrap::bootstrap))
+        // Mount registry administration under /admin prefix
+        .nest("/admin", super::admin::admin_router())
+        // Mount repository index and federated search under /federation prefix
+        .nest("/federation", super::federation::federation_router())
 }
@@ -2,7 +2,7 @@ use axum::{
     routing::{post, get, put, delete},
     Router,
 };
-use crate::handlers::auth;
+use crate::handlers::{api_usage, auth, avatars, watches};
 use crate::AppState;
 
 pub fn auth_router() -> Router<AppState> {
@@ -11,10 +11,20 @@ pub fn auth_router() -> Router<AppState> {
         .route("/login", post(auth::login))
         .route("/logout", post(auth::logout))
         .route("/me", get(auth::me))
+        .route("/me/avatar", put(avatars::upload_user_avatar).delete(avatars::delete_user_avatar))
+        .route("/me/watches", get(watches::list_watches))
+        .route(
+            "/me/notification-preferences",
+            get(watches::get_notification_preferences).put(watches::update_notification_preferences),
+        )
         .route("/api-keys", get(auth::get_user_api_keys))
         .route("/api-keys", post(auth::create_api_key))
         .route("/api-keys/:id", delete(auth::delete_api_key))
         .route("/refresh", post(auth::refresh))
+        .route("/sessions", get(auth::list_sessions))
+        .route("/sessions/:id", delete(auth::revoke_session))
+        .route("/usage", get(api_usage::list_credential_usage))
+        .route("/usage/:credential_type/:credential_id", get(api_usage::get_credential_usage))
         .route("/change-password", put(auth::change_password))
         .route("/forgot-password", post(auth::forgot_password))
         .route("/verify-otp", post(auth::verify_otp_and_reset))
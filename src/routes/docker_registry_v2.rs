@@ -1,10 +1,13 @@
 // Docker Registry V2 API routes
 use axum::{
+    extract::Request,
+    http::Uri,
     routing::{get, post, put, patch, delete, any},
     Router, response::Redirect,
 };
 
 use crate::{
+    config::settings::CompressionSettings,
     handlers::docker_registry_v2,
     AppState,
 };
@@ -14,9 +17,46 @@ async fn redirect_to_v2() -> Redirect {
     Redirect::permanent("/v2/")
 }
 
+/// Suffixes that end the repository name in a registry path
+const REPOSITORY_PATH_MARKERS: [&str; 4] = ["/manifests/", "/blobs/", "/tags/list", "/referrers/"];
+
+/// Rewrite a registry path whose repository name has more than two components
+/// (`/v2/org/team/app/manifests/latest`) so everything after the namespace becomes a single
+/// percent-encoded segment (`/v2/org/team%2Fapp/manifests/latest`) that the `:org/:name`
+/// routes match; the `Path` extractor decodes it back to `team/app`.
+/// Returns `None` for paths that need no rewriting.
+pub fn encode_nested_repository_path(path: &str) -> Option<String> {
+    let rest = path.strip_prefix("/v2/")?;
+    let end = REPOSITORY_PATH_MARKERS.iter().filter_map(|marker| rest.rfind(marker)).max()?;
+    let (namespace, repository) = rest[..end].split_once('/')?;
+
+    if !repository.contains('/') || namespace.is_empty() || repository.split('/').any(str::is_empty) {
+        return None;
+    }
+
+    Some(format!("/v2/{}/{}{}", namespace, repository.replace('/', "%2F"), &rest[end..]))
+}
+
+/// Request mapper applied before routing so hierarchical repository names reach the registry handlers
+pub async fn nest_repository_paths(mut request: Request) -> Request {
+    if let Some(path) = encode_nested_repository_path(request.uri().path()) {
+        let path_and_query = match request.uri().query() {
+            Some(query) => format!("{}?{}", path, query),
+            None => path,
+        };
+        if let Ok(uri) = path_and_query.parse::<Uri>() {
+            *request.uri_mut() = uri;
+        }
+    }
+    request
+}
+
 /// Creates the Docker Registry V2 API router
 /// All routes are prefixed with /v2 and follow the Docker Registry V2 specification
-pub fn docker_registry_v2_router() -> Router<AppState> {
+/// Registry API routes. Listings are compressed as configured in `compression`; blobs and
+/// manifests are always sent as stored.
+pub fn docker_registry_v2_router(compression: &CompressionSettings) -> Router<AppState> {
+    let compressed = crate::compression::response_layer(compression);
     Router::new()
         
         // Docker Registry V2 version check - both /v2 and /v2/
@@ -24,14 +64,15 @@ pub fn docker_registry_v2_router() -> Router<AppState> {
         .route("/v2/", get(docker_registry_v2::version_check))
         
         // Repository catalog
-        .route("/v2/_catalog", get(docker_registry_v2::get_catalog))
+        .route("/v2/_catalog", get(docker_registry_v2::get_catalog).layer(compressed.clone()))
         
         // Use more specific patterns for Docker registry endpoints
-        // These patterns should handle both simple names and namespaced names like org/repo
+        // These patterns should handle both simple names and namespaced names like org/repo;
+        // deeper names (org/team/repo) arrive with the tail encoded by `nest_repository_paths`
         
         // Tag listing endpoints - handles simple names and namespaced names
-        .route("/v2/:name/tags/list", get(docker_registry_v2::list_tags))
-        .route("/v2/:org/:name/tags/list", get(docker_registry_v2::list_tags_namespaced))
+        .route("/v2/:name/tags/list", get(docker_registry_v2::list_tags).layer(compressed.clone()))
+        .route("/v2/:org/:name/tags/list", get(docker_registry_v2::list_tags_namespaced).layer(compressed.clone()))
         
         // Manifest operations - simple names
         .route("/v2/:name/manifests/:reference", 
@@ -49,6 +90,10 @@ pub fn docker_registry_v2_router() -> Router<AppState> {
                 .delete(docker_registry_v2::delete_manifest_namespaced)
         )
         
+        // OCI referrers (manifests whose subject is the given digest)
+        .route("/v2/:name/referrers/:digest", get(docker_registry_v2::get_referrers).layer(compressed.clone()))
+        .route("/v2/:org/:name/referrers/:digest", get(docker_registry_v2::get_referrers_namespaced).layer(compressed.clone()))
+        
         // Blob operations for simple names
         .route("/v2/:name/blobs/:digest", 
             get(docker_registry_v2::get_blob)
@@ -60,8 +105,8 @@ pub fn docker_registry_v2_router() -> Router<AppState> {
         )
         
         // List all blobs in repository (custom API - not Docker Registry V2 standard)
-        .route("/v2/:name/blobs/", get(docker_registry_v2::list_blobs))
-        .route("/v2/:org/:name/blobs/", get(docker_registry_v2::list_blobs_namespaced))
+        .route("/v2/:name/blobs/", get(docker_registry_v2::list_blobs).layer(compressed.clone()))
+        .route("/v2/:org/:name/blobs/", get(docker_registry_v2::list_blobs_namespaced).layer(compressed))
         
         // Blob upload operations for simple names
         .route("/v2/:name/blobs/uploads/", post(docker_registry_v2::start_blob_upload))
@@ -83,4 +128,45 @@ pub fn docker_registry_v2_router() -> Router<AppState> {
                 .put(docker_registry_v2::complete_blob_upload_namespaced)
                 .delete(docker_registry_v2::cancel_blob_upload_namespaced)
         )
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::encode_nested_repository_path;
+
+    #[test]
+    fn leaves_one_and_two_component_names_alone() {
+        assert_eq!(encode_nested_repository_path("/v2/app/manifests/latest"), None);
+        assert_eq!(encode_nested_repository_path("/v2/org/app/blobs/uploads/"), None);
+        assert_eq!(encode_nested_repository_path("/v2/_catalog"), None);
+        assert_eq!(encode_nested_repository_path("/api/v1/repos/org/team/app"), None);
+    }
+
+    #[test]
+    fn encodes_components_after_the_namespace() {
+        assert_eq!(
+            encode_nested_repository_path("/v2/org/team/project/app/manifests/latest").as_deref(),
+            Some("/v2/org/team%2Fproject%2Fapp/manifests/latest")
+        );
+        assert_eq!(
+            encode_nested_repository_path("/v2/org/team/app/blobs/uploads/1234").as_deref(),
+            Some("/v2/org/team%2Fapp/blobs/uploads/1234")
+        );
+        assert_eq!(
+            encode_nested_repository_path("/v2/org/team/app/tags/list").as_deref(),
+            Some("/v2/org/team%2Fapp/tags/list")
+        );
+    }
+
+    #[test]
+    fn uses_the_last_marker_when_components_look_like_routes() {
+        assert_eq!(
+            encode_nested_repository_path("/v2/org/blobs/app/manifests/v1").as_deref(),
+            Some("/v2/org/blobs%2Fapp/manifests/v1")
+        );
+    }
+
+    #[test]
+    fn rejects_empty_components() {
+        assert_eq!(encode_nested_repository_path("/v2/org//app/manifests/latest"), None);
+    }
+}
@@ -0,0 +1,11 @@
+use axum::{routing::get, Router};
+
+use crate::handlers::federation;
+use crate::AppState;
+
+pub fn federation_router() -> Router<AppState> {
+    Router::new()
+        .route("/index", get(federation::federation_index))
+        .route("/search", get(federation::federated_search))
+        .route("/peers", get(federation::list_peers))
+}
@@ -1,19 +1,40 @@
+// Probes for orchestrators:
+//
+// - `/healthz` (and the older `/health`) is liveness: the process answers, nothing else is checked,
+//   so a database outage does not get every replica restarted.
+// - `/readyz` is readiness: the database and object storage must answer within
+//   `HEALTH_CHECK_TIMEOUT_MS`, and Redis too with `HEALTH_REQUIRE_CACHE`. A replica failing it is
+//   taken out of the load balancer until its dependencies are back.
+// - `/startupz` fails until the first readiness check has passed and succeeds from then on, so a
+//   slow first start is not mistaken for a hung process.
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
 use axum::{
     routing::get,
     Router,
     Json,
-    response::IntoResponse,
-    http::StatusCode,
+    response::{IntoResponse, Response},
+    http::{header, StatusCode},
     extract::State,
 };
+use serde::Serialize;
 use serde_json::json;
 
 use crate::AppState;
 
+/// Set once a readiness check has passed
+static STARTED: AtomicBool = AtomicBool::new(false);
+
 pub fn health_router() -> Router<AppState> {
     Router::new()
         .route("/health", get(check_health))
+        .route("/healthz", get(check_health))
+        .route("/readyz", get(readiness))
+        .route("/startupz", get(startup))
         .route("/health/cache", get(cache_stats))
+        .route("/metrics", get(prometheus_metrics))
 }
 
 async fn check_health() -> impl IntoResponse {
@@ -22,6 +43,111 @@ async fn check_health() -> impl IntoResponse {
     })))
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum CheckStatus {
+    Ok,
+    Failed,
+    /// Not configured, so not checked
+    Disabled,
+}
+
+#[derive(Debug, Serialize)]
+struct DependencyCheck {
+    status: CheckStatus,
+    latency_ms: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+impl DependencyCheck {
+    fn disabled() -> Self {
+        Self { status: CheckStatus::Disabled, latency_ms: 0, error: None }
+    }
+
+    /// Passed or not needed
+    fn passed(&self) -> bool {
+        self.status != CheckStatus::Failed
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct Readiness {
+    database: DependencyCheck,
+    storage: DependencyCheck,
+    cache: DependencyCheck,
+}
+
+impl Readiness {
+    fn ready(&self, require_cache: bool) -> bool {
+        self.database.passed() && self.storage.passed() && (self.cache.passed() || !require_cache)
+    }
+}
+
+async fn check(timeout: Duration, probe: impl Future<Output = anyhow::Result<()>>) -> DependencyCheck {
+    let started = Instant::now();
+    let error = match tokio::time::timeout(timeout, probe).await {
+        Ok(Ok(())) => None,
+        Ok(Err(e)) => Some(e.to_string()),
+        Err(_) => Some(format!("no answer within {} ms", timeout.as_millis())),
+    };
+    DependencyCheck {
+        status: if error.is_none() { CheckStatus::Ok } else { CheckStatus::Failed },
+        latency_ms: started.elapsed().as_millis() as u64,
+        error,
+    }
+}
+
+/// Check every dependency at once
+async fn check_dependencies(state: &AppState) -> Readiness {
+    let timeout = Duration::from_millis(state.config.health.check_timeout_ms);
+    let database = check(timeout, async {
+        sqlx::query("SELECT 1").execute(&state.db_pool).await?;
+        Ok::<_, anyhow::Error>(())
+    });
+    let storage = check(timeout, state.storage.health_check());
+    let cache = async {
+        match &state.cache {
+            Some(cache) => check(timeout, cache.health_check()).await,
+            None => DependencyCheck::disabled(),
+        }
+    };
+    let (database, storage, cache) = tokio::join!(database, storage, cache);
+    Readiness { database, storage, cache }
+}
+
+async fn readiness(State(state): State<AppState>) -> Response {
+    let checks = check_dependencies(&state).await;
+    let ready = checks.ready(state.config.health.require_cache);
+    if ready {
+        STARTED.store(true, Ordering::Relaxed);
+    } else {
+        tracing::warn!("Readiness check failed: {:?}", checks);
+    }
+    let status = if ready { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE };
+    let body = json!({
+        "status": if ready { "ready" } else { "not_ready" },
+        "checks": checks,
+    });
+    (status, Json(body)).into_response()
+}
+
+async fn startup(State(state): State<AppState>) -> Response {
+    if STARTED.load(Ordering::Relaxed) {
+        return (StatusCode::OK, Json(json!({ "status": "started" }))).into_response();
+    }
+    readiness(State(state)).await
+}
+
+/// Cache counters in the Prometheus text exposition format
+async fn prometheus_metrics(State(state): State<AppState>) -> impl IntoResponse {
+    let body = match &state.cache {
+        Some(cache) => cache.get_stats().await.to_prometheus(),
+        None => String::new(),
+    };
+    ([(header::CONTENT_TYPE, "text/plain; version=0.0.4")], body)
+}
+
 async fn cache_stats(State(state): State<AppState>) -> impl IntoResponse {
     if let Some(cache) = &state.cache {
         let stats = cache.get_stats().await;
@@ -36,3 +162,30 @@ async fn cache_stats(State(state): State<AppState>) -> impl IntoResponse {
         })))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(status: CheckStatus) -> DependencyCheck {
+        DependencyCheck { status, latency_ms: 1, error: None }
+    }
+
+    #[test]
+    fn redis_is_only_required_when_configured() {
+        let checks = Readiness {
+            database: result(CheckStatus::Ok),
+            storage: result(CheckStatus::Ok),
+            cache: result(CheckStatus::Failed),
+        };
+        assert!(checks.ready(false));
+        assert!(!checks.ready(true));
+
+        let checks = Readiness {
+            database: result(CheckStatus::Failed),
+            storage: result(CheckStatus::Ok),
+            cache: result(CheckStatus::Disabled),
+        };
+        assert!(!checks.ready(false));
+    }
+}
@@ -0,0 +1,15 @@
+use axum::{
+    routing::{get, post},
+    Router,
+};
+
+use crate::handlers::invitations;
+use crate::AppState;
+
+/// Endpoints reached from an emailed invite link, addressed by its signed token
+pub fn invitation_router() -> Router<AppState> {
+    Router::new()
+        .route("/:token", get(invitations::get_invitation))
+        .route("/:token/accept", post(invitations::accept_invitation))
+        .route("/:token/decline", post(invitations::decline_invitation))
+}
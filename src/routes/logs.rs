@@ -0,0 +1,9 @@
+use axum::{routing::get, Router};
+
+use crate::handlers::log_tail;
+use crate::AppState;
+
+pub fn logs_router() -> Router<AppState> {
+    Router::new()
+        .route("/tail", get(log_tail::tail_logs))
+}
@@ -1,8 +1,14 @@
 // Routes module
+pub mod admin;
 pub mod api;
 pub mod auth;
 pub mod docker_registry_v2;
+pub mod federation;
 pub mod health;
+pub mod invitations;
+pub mod logs;
 pub mod organizations;
 pub mod repositories;
+pub mod standby;
 pub mod storage;
+pub mod webhooks;
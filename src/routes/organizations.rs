@@ -1,4 +1,4 @@
-use crate::handlers::organizations;
+use crate::handlers::{audit_export, avatars, events, invitations, ip_access, organization_secrets, organization_webhooks, organizations, push_hooks, quota_tiers, security_policies, security_summary, teams};
 use crate::AppState;
 use axum::{
     routing::{delete, get, post, put},
@@ -13,6 +13,68 @@ pub fn organization_router() -> Router<AppState> {
         .route("/:id", get(organizations::get_organization))
         .route("/:id", put(organizations::update_organization))
         .route("/:id", delete(organizations::delete_organization))
+        .route(
+            "/:id/deletion-token",
+            post(organizations::request_organization_deletion),
+        )
+        .route(
+            "/:id/avatar",
+            put(avatars::upload_organization_avatar).delete(avatars::delete_organization_avatar),
+        )
+        // Repository defaults and storage quota
+        .route(
+            "/:id/settings",
+            get(organizations::get_organization_settings).put(organizations::update_organization_settings),
+        )
+        // Usage dashboard
+        .route("/:id/stats", get(organizations::get_organization_stats))
+        // Event history of the organization and its repositories
+        .route("/:id/events", get(events::list_organization_events))
+        .route("/:id/audit/export", get(audit_export::export_audit_log))
+        // Quota tier and usage of its limits
+        .route("/:id/quota", get(quota_tiers::get_organization_quota))
+        // Secret variables injected into build jobs
+        .route("/:id/secrets", get(organization_secrets::list_organization_secrets))
+        .route(
+            "/:id/secrets/:name",
+            put(organization_secrets::put_organization_secret).delete(organization_secrets::delete_organization_secret),
+        )
+        // Webhooks for events in any repository of the organization
+        .route(
+            "/:id/webhooks",
+            get(organization_webhooks::list_organization_webhooks).post(organization_webhooks::create_organization_webhook),
+        )
+        .route(
+            "/:id/webhooks/:webhook_id",
+            get(organization_webhooks::get_organization_webhook)
+                .put(organization_webhooks::update_organization_webhook)
+                .delete(organization_webhooks::delete_organization_webhook),
+        )
+        .route(
+            "/:id/webhooks/:webhook_id/deliveries",
+            get(organization_webhooks::list_organization_webhook_deliveries),
+        )
+        .route(
+            "/:id/webhooks/:webhook_id/deliveries/:delivery_id/redeliver",
+            post(organization_webhooks::redeliver_organization_webhook_delivery),
+        )
+        // Endpoints allowing or denying manifest pushes
+        .route("/:id/push-hooks", get(push_hooks::list_push_hooks).post(push_hooks::create_push_hook))
+        .route(
+            "/:id/push-hooks/:hook_id",
+            put(push_hooks::update_push_hook).delete(push_hooks::delete_push_hook),
+        )
+        // Rules refusing manifest pulls and pushes
+        .route(
+            "/:id/security-policies",
+            get(security_policies::list_security_policies).post(security_policies::create_security_policy),
+        )
+        .route(
+            "/:id/security-policies/:policy_id",
+            put(security_policies::update_security_policy).delete(security_policies::delete_security_policy),
+        )
+        // Scans, signatures and policy compliance across all repositories
+        .route("/:id/security-summary", get(security_summary::get_security_summary))
         // Member management
         .route(
             "/:id/members",
@@ -30,4 +92,43 @@ pub fn organization_router() -> Router<AppState> {
             "/:id/members/:member_id",
             delete(organizations::remove_organization_member),
         )
+        // Invitations
+        .route(
+            "/:id/invitations",
+            get(invitations::list_invitations),
+        )
+        .route(
+            "/:id/invitations",
+            post(invitations::create_invitation),
+        )
+        .route(
+            "/:id/invitations/:invitation_id",
+            delete(invitations::revoke_invitation),
+        )
+        // Teams
+        .route("/:id/teams", get(teams::list_teams))
+        .route("/:id/teams", post(teams::create_team))
+        .route("/:id/teams/:team_id", get(teams::get_team))
+        .route("/:id/teams/:team_id", put(teams::update_team))
+        .route("/:id/teams/:team_id", delete(teams::delete_team))
+        .route(
+            "/:id/teams/:team_id/members/:member_id",
+            put(teams::add_team_member),
+        )
+        .route(
+            "/:id/teams/:team_id/members/:member_id",
+            delete(teams::remove_team_member),
+        )
+        .route(
+            "/:id/teams/:team_id/repositories/:repo_name",
+            put(teams::set_team_repository),
+        )
+        .route(
+            "/:id/teams/:team_id/repositories/:repo_name",
+            delete(teams::remove_team_repository),
+        )
+        // IP access rules
+        .route("/:id/ip-rules", get(ip_access::list_ip_rules))
+        .route("/:id/ip-rules", post(ip_access::create_ip_rule))
+        .route("/:id/ip-rules/:rule_id", delete(ip_access::delete_ip_rule))
 }
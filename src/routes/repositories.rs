@@ -4,6 +4,26 @@ use axum::{
 };
 
 use crate::{
+    handlers::base_images::list_image_bases,
+    handlers::collaborators::{list_collaborators, remove_collaborator, set_collaborator},
+    handlers::digests::resolve_digest,
+    handlers::events::list_repository_events,
+    handlers::images::get_image_detail,
+    handlers::insights::get_repository_insights,
+    handlers::jobs::list_repository_jobs,
+    handlers::quarantine::{list_quarantined_manifests, release_quarantined_manifest},
+    handlers::repository_stats::get_repository_stats,
+    handlers::repository_webhooks::{
+        create_repository_webhook, delete_repository_webhook, list_repository_webhook_deliveries, list_repository_webhooks,
+        redeliver_repository_webhook_delivery, update_repository_webhook,
+    },
+    handlers::model_registry::{attach_model_card, create_model_lineage, get_model_card, get_model_lineage},
+    handlers::sbom::{get_image_sbom, upload_image_sbom},
+    handlers::secret_findings::list_secret_findings,
+    handlers::tag_cleanup::{accept_cleanup_suggestions, get_cleanup_suggestions},
+    handlers::tags::list_repository_tags,
+    handlers::vulnerabilities::get_tag_vulnerabilities,
+    handlers::watches::{get_watch, unwatch_repository, watch_repository},
     handlers::repositories::{
         list_repositories,
         list_repositories_by_namespace,
@@ -12,6 +32,8 @@ use crate::{
         update_repository,
         delete_repository,
         get_repository,
+        clone_repository,
+        transfer_repository,
     },
     AppState,
 };
@@ -25,4 +47,33 @@ pub fn repository_router() -> Router<AppState> {
         .route("/:namespace/repositories/:repo_name", get(get_repository))  // Get repository details
         .route("/:namespace/:repo_name", put(update_repository))
         .route("/:namespace/:repo_name", delete(delete_repository))
+        .route("/:namespace/:repo_name/clone", post(clone_repository))
+        .route("/:namespace/:repo_name/transfer", post(transfer_repository))
+        .route("/:namespace/:repo_name/collaborators", get(list_collaborators))
+        .route("/:namespace/:repo_name/collaborators/:username", put(set_collaborator).delete(remove_collaborator))
+        .route("/:namespace/:repo_name/webhooks", get(list_repository_webhooks).post(create_repository_webhook))
+        .route("/:namespace/:repo_name/webhooks/:webhook_id", put(update_repository_webhook).delete(delete_repository_webhook))
+        .route("/:namespace/:repo_name/webhooks/:webhook_id/deliveries", get(list_repository_webhook_deliveries))
+        .route(
+            "/:namespace/:repo_name/webhooks/:webhook_id/deliveries/:delivery_id/redeliver",
+            post(redeliver_repository_webhook_delivery),
+        )
+        .route("/:namespace/:repo_name/tags", get(list_repository_tags))
+        .route("/:namespace/:repo_name/tags/:tag/vulnerabilities", get(get_tag_vulnerabilities))
+        .route("/:namespace/:repo_name/images/:reference/sbom", get(get_image_sbom).put(upload_image_sbom))
+        .route("/:namespace/:repo_name/images/:reference", get(get_image_detail))
+        .route("/:namespace/:repo_name/digests/:prefix", get(resolve_digest))
+        .route("/:namespace/:repo_name/cleanup-suggestions", get(get_cleanup_suggestions))
+        .route("/:namespace/:repo_name/cleanup-suggestions/accept", post(accept_cleanup_suggestions))
+        .route("/:namespace/:repo_name/insights", get(get_repository_insights))
+        .route("/:namespace/:repo_name/stats", get(get_repository_stats))
+        .route("/:namespace/:repo_name/events", get(list_repository_events))
+        .route("/:namespace/:repo_name/jobs", get(list_repository_jobs))
+        .route("/:namespace/:repo_name/quarantine", get(list_quarantined_manifests))
+        .route("/:namespace/:repo_name/quarantine/:digest/release", post(release_quarantined_manifest))
+        .route("/:namespace/:repo_name/secret-findings", get(list_secret_findings))
+        .route("/:namespace/:repo_name/base-images", get(list_image_bases))
+        .route("/:namespace/:repo_name/watch", get(get_watch).put(watch_repository).delete(unwatch_repository))
+        .route("/:namespace/:repo_name/models/:reference/card", get(get_model_card).put(attach_model_card))
+        .route("/:namespace/:repo_name/models/:reference/lineage", get(get_model_lineage).post(create_model_lineage))
 }
\ No newline at end of file
@@ -0,0 +1,13 @@
+use axum::{
+    routing::{get, post},
+    Router,
+};
+
+use crate::handlers::standby;
+use crate::AppState;
+
+pub fn standby_router() -> Router<AppState> {
+    Router::new()
+        .route("/status", get(standby::standby_status))
+        .route("/promote", post(standby::promote_standby))
+}
@@ -0,0 +1,9 @@
+use axum::{routing::get, Router};
+
+use crate::handlers::webhooks;
+use crate::AppState;
+
+pub fn webhook_router() -> Router<AppState> {
+    Router::new()
+        .route("/signing-keys", get(webhooks::get_signing_keys))
+}
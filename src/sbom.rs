@@ -0,0 +1,239 @@
+// src/sbom.rs - Software bills of materials attached to images as referrers
+//
+// An SBOM is an OCI referrer artifact of the image it describes, with artifact type
+// `application/spdx+json` or `application/vnd.cyclonedx+json`, whether an `sbom` job generated
+// it with Trivy or a client pushed it (`oras attach`, or the upload endpoint). It is pulled,
+// mirrored and garbage collected with the image like any referrer. The packages each SBOM lists
+// are indexed in `sbom_packages` by `sbom_index` jobs, so images can be found by what they contain.
+use anyhow::{anyhow, bail, Context, Result};
+use bytes::Bytes;
+use serde_json::{json, Value};
+use sqlx::PgPool;
+
+use crate::handlers::docker_registry_v2::{get_repository_blob, load_manifest_content};
+use crate::handlers::model_registry::{push_artifact, Subject};
+use crate::models::sbom::{SbomFormat, SbomPackage};
+use crate::AppState;
+
+/// Artifact types of SBOM referrers
+pub fn artifact_types() -> Vec<String> {
+    SbomFormat::ALL.iter().map(|format| format.artifact_type().to_string()).collect()
+}
+
+/// Generate an SBOM of a pushed image with Trivy, attach it and index its packages, unless the
+/// image already has an SBOM
+pub async fn generate(state: &AppState, repository_id: i64, repository: &str, digest: &str) -> Result<()> {
+    let settings = &state.config.scanning;
+    let format: SbomFormat = settings.sbom_format.parse().map_err(|e: String| anyhow!(e))?;
+
+    let subject = sqlx::query_as::<_, (String, i64, bool)>(
+        "SELECT m.media_type, m.size,
+                EXISTS (
+                    SELECT 1 FROM manifests s
+                    WHERE s.repository_id = m.repository_id AND s.subject_digest = m.digest AND s.artifact_type = ANY($3)
+                )
+         FROM manifests m
+         WHERE m.repository_id = $1 AND m.digest = $2",
+    )
+    .bind(repository_id)
+    .bind(digest)
+    .bind(artifact_types())
+    .fetch_optional(&state.db_pool)
+    .await?;
+    let subject = match subject {
+        Some((_, _, true)) | None => return Ok(()),
+        Some((media_type, size, false)) => Subject { repository_id, digest: digest.to_string(), media_type, size },
+    };
+
+    let image = format!("{}/{}@{}", settings.registry_host, repository, digest);
+    tracing::info!("Generating a {} SBOM of {}", format, image);
+    let trivy_format = match format {
+        SbomFormat::Spdx => "spdx-json",
+        SbomFormat::Cyclonedx => "cyclonedx",
+    };
+    let document = crate::scanning::run_trivy(settings, &image, &["--format", trivy_format]).await?;
+    let (_, packages) = parse_document(&document)?;
+
+    let sbom_digest = push_artifact(state, repository, &subject, format.artifact_type(), Some(Bytes::from(document)), json!({}), None)
+        .await
+        .map_err(|response| anyhow!("Storing the SBOM failed with {}", response.status()))?;
+    store_packages(&state.db_pool, repository_id, &sbom_digest, digest, &packages).await?;
+    Ok(())
+}
+
+/// Index the packages of an SBOM referrer pushed to a repository
+pub async fn index(state: &AppState, repository_id: i64, repository: &str, sbom_digest: &str) -> Result<()> {
+    let (subject, document) = load_document(state, repository, sbom_digest).await?;
+    let (_, packages) = parse_document(&document)?;
+    store_packages(&state.db_pool, repository_id, sbom_digest, &subject, &packages).await
+}
+
+/// The subject digest and document of an SBOM referrer
+pub async fn load_document(state: &AppState, repository: &str, sbom_digest: &str) -> Result<(String, Bytes)> {
+    let manifest = load_manifest_content(state, repository, sbom_digest)
+        .await?
+        .ok_or_else(|| anyhow!("SBOM manifest {} is missing", sbom_digest))?;
+    let manifest: Value = serde_json::from_slice(&manifest)?;
+    let subject = manifest
+        .pointer("/subject/digest")
+        .and_then(Value::as_str)
+        .ok_or_else(|| anyhow!("SBOM manifest {} has no subject", sbom_digest))?
+        .to_string();
+    // The document is the layer of an SBOM media type, or the only layer
+    let layers = manifest.get("layers").and_then(Value::as_array).cloned().unwrap_or_default();
+    let layer = layers
+        .iter()
+        .find(|layer| layer.get("mediaType").and_then(Value::as_str).and_then(SbomFormat::from_artifact_type).is_some())
+        .or_else(|| layers.first())
+        .and_then(|layer| layer.get("digest"))
+        .and_then(Value::as_str)
+        .ok_or_else(|| anyhow!("SBOM manifest {} has no layers", sbom_digest))?;
+
+    let document = get_repository_blob(state, repository, layer)
+        .await?
+        .ok_or_else(|| anyhow!("SBOM document {} is missing", layer))?;
+    Ok((subject, document))
+}
+
+/// The format of an SPDX or CycloneDX JSON document and the packages it lists, each once
+pub fn parse_document(document: &[u8]) -> Result<(SbomFormat, Vec<SbomPackage>)> {
+    let document: Value = serde_json::from_slice(document).context("The SBOM is not JSON")?;
+    let (format, mut packages) = if document.get("bomFormat").and_then(Value::as_str) == Some("CycloneDX") {
+        let mut packages = Vec::new();
+        cyclonedx_components(document.get("components"), &mut packages);
+        (SbomFormat::Cyclonedx, packages)
+    } else if document.get("spdxVersion").is_some() {
+        let packages = document
+            .get("packages")
+            .and_then(Value::as_array)
+            .map(|packages| packages.iter().filter_map(spdx_package).collect())
+            .unwrap_or_default();
+        (SbomFormat::Spdx, packages)
+    } else {
+        bail!("Not an SPDX or CycloneDX JSON document");
+    };
+    packages.sort_by(|a, b| (&a.name, &a.version, &a.purl).cmp(&(&b.name, &b.version, &b.purl)));
+    packages.dedup();
+    Ok((format, packages))
+}
+
+/// Components and their nested components
+fn cyclonedx_components(components: Option<&Value>, packages: &mut Vec<SbomPackage>) {
+    for component in components.and_then(Value::as_array).into_iter().flatten() {
+        if let Some(name) = component.get("name").and_then(Value::as_str).filter(|name| !name.is_empty()) {
+            packages.push(SbomPackage {
+                name: name.to_string(),
+                version: text(component.get("version")),
+                purl: text(component.get("purl")),
+            });
+        }
+        cyclonedx_components(component.get("components"), packages);
+    }
+}
+
+fn spdx_package(package: &Value) -> Option<SbomPackage> {
+    let name = package.get("name").and_then(Value::as_str).filter(|name| !name.is_empty())?;
+    let purl = package
+        .get("externalRefs")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .find(|reference| reference.get("referenceType").and_then(Value::as_str) == Some("purl"))
+        .and_then(|reference| text(reference.get("referenceLocator")));
+    Some(SbomPackage { name: name.to_string(), version: text(package.get("versionInfo")), purl })
+}
+
+fn text(value: Option<&Value>) -> Option<String> {
+    value.and_then(Value::as_str).filter(|text| !text.is_empty()).map(str::to_string)
+}
+
+/// Replace the indexed packages of an SBOM
+pub async fn store_packages(
+    pool: &PgPool,
+    repository_id: i64,
+    sbom_digest: &str,
+    subject_digest: &str,
+    packages: &[SbomPackage],
+) -> Result<()> {
+    let mut tx = pool.begin().await?;
+    let manifest_id = sqlx::query_scalar::<_, i64>("SELECT id FROM manifests WHERE repository_id = $1 AND digest = $2")
+        .bind(repository_id)
+        .bind(sbom_digest)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| anyhow!("SBOM manifest {} is not stored", sbom_digest))?;
+    sqlx::query("DELETE FROM sbom_packages WHERE sbom_manifest_id = $1")
+        .bind(manifest_id)
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query(
+        "INSERT INTO sbom_packages (repository_id, sbom_manifest_id, subject_digest, name, version, purl)
+         SELECT $1, $2, $3, * FROM UNNEST($4::TEXT[], $5::TEXT[], $6::TEXT[])",
+    )
+    .bind(repository_id)
+    .bind(manifest_id)
+    .bind(subject_digest)
+    .bind(packages.iter().map(|p| p.name.clone()).collect::<Vec<_>>())
+    .bind(packages.iter().map(|p| p.version.clone()).collect::<Vec<_>>())
+    .bind(packages.iter().map(|p| p.purl.clone()).collect::<Vec<_>>())
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cyclonedx_components_are_listed_with_nested_ones() {
+        let document = br#"{
+            "bomFormat": "CycloneDX",
+            "specVersion": "1.5",
+            "metadata": {"component": {"name": "localhost:8080/acme/web", "type": "container"}},
+            "components": [
+                {"name": "openssl", "version": "3.0.1", "purl": "pkg:apk/alpine/openssl@3.0.1"},
+                {"name": "app", "components": [{"name": "left-pad", "version": "1.3.0"}]},
+                {"name": "openssl", "version": "3.0.1", "purl": "pkg:apk/alpine/openssl@3.0.1"}
+            ]
+        }"#;
+        let (format, packages) = parse_document(document).unwrap();
+        assert_eq!(format, SbomFormat::Cyclonedx);
+        let names: Vec<&str> = packages.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, ["app", "left-pad", "openssl"]);
+        assert_eq!(packages[2].purl.as_deref(), Some("pkg:apk/alpine/openssl@3.0.1"));
+        assert_eq!(packages[0].version, None);
+    }
+
+    #[test]
+    fn spdx_packages_take_their_purl_from_external_refs() {
+        let document = br#"{
+            "spdxVersion": "SPDX-2.3",
+            "packages": [
+                {"name": "openssl", "versionInfo": "3.0.1", "externalRefs": [
+                    {"referenceCategory": "SECURITY", "referenceType": "cpe23Type", "referenceLocator": "cpe:2.3:a:openssl"},
+                    {"referenceCategory": "PACKAGE-MANAGER", "referenceType": "purl", "referenceLocator": "pkg:deb/debian/openssl@3.0.1"}
+                ]},
+                {"name": "", "versionInfo": "1"}
+            ]
+        }"#;
+        let (format, packages) = parse_document(document).unwrap();
+        assert_eq!(format, SbomFormat::Spdx);
+        assert_eq!(
+            packages,
+            [SbomPackage {
+                name: "openssl".to_string(),
+                version: Some("3.0.1".to_string()),
+                purl: Some("pkg:deb/debian/openssl@3.0.1".to_string()),
+            }]
+        );
+    }
+
+    #[test]
+    fn other_documents_are_rejected() {
+        assert!(parse_document(br#"{"hello": "world"}"#).is_err());
+        assert!(parse_document(b"openssl 3.0.1").is_err());
+        assert_eq!(SbomFormat::from_artifact_type("application/spdx+json"), Some(SbomFormat::Spdx));
+    }
+}
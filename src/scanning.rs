@@ -0,0 +1,423 @@
+// src/scanning.rs - Vulnerability scanning of pushed images with Trivy
+//
+// Pushed images are scanned by `scan` jobs of the background queue (`crate::jobs`), at most
+// `SCAN_CONCURRENCY` at a time per instance, by running `trivy image` against the registry itself
+// - with Trivy's own database, or against a Trivy server in client/server mode. A digest pushed
+// under several tags is scanned once. Findings replace those of an earlier scan, and each result
+// is published as a `scan.complete` audit event for webhooks and the history; the queue publishes
+// `scan.failed` once a scan runs out of attempts. A completed scan releases the image from
+// quarantine and advises rebuilding the images built on it when it is vulnerable.
+use std::process::Stdio;
+use std::time::Duration;
+
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Utc};
+use secrecy::ExposeSecret;
+use serde::Deserialize;
+use sqlx::PgPool;
+use tokio::process::Command;
+
+use crate::config::settings::ScanSettings;
+use crate::log_stream::LogEvent;
+use crate::models::vulnerability::{ScanSummary, Severity, SeverityCounts, Vulnerability};
+use crate::quarantine::{self, Release};
+use crate::webhooks::chat::short_digest;
+use crate::AppState;
+
+const SCANNER: &str = "trivy";
+/// Characters of Trivy's error output kept on a failed scan
+const ERROR_LIMIT: usize = 1000;
+
+/// Scan one manifest unless it was scanned already; the job queue makes sure only one scan of a
+/// manifest runs at a time
+pub async fn scan_manifest(state: &AppState, repository_id: i64, repository: &str, digest: &str) -> Result<()> {
+    let settings = &state.config.scanning;
+    let scan_id = sqlx::query_scalar::<_, i64>(
+        "INSERT INTO vulnerability_scans (repository_id, manifest_digest, status, scanner)
+         VALUES ($1, $2, 'running', $3)
+         ON CONFLICT (repository_id, manifest_digest) DO UPDATE SET
+             status = 'running', scanner = EXCLUDED.scanner, error = NULL,
+             started_at = CURRENT_TIMESTAMP, completed_at = NULL
+         WHERE vulnerability_scans.status <> 'completed'
+         RETURNING id",
+    )
+    .bind(repository_id)
+    .bind(digest)
+    .bind(SCANNER)
+    .fetch_optional(&state.db_pool)
+    .await?;
+    let Some(scan_id) = scan_id else {
+        return Ok(());
+    };
+
+    let image = format!("{}/{}@{}", settings.registry_host, repository, digest);
+    tracing::info!("Scanning {} for vulnerabilities", image);
+    let report = run_trivy(settings, &image, &["--format", "json", "--scanners", "vuln"])
+        .await
+        .and_then(|stdout| serde_json::from_slice::<TrivyReport>(&stdout).context("Trivy printed an unreadable report"));
+    match report {
+        Ok(report) => {
+            let vulnerabilities = report.vulnerabilities();
+            let counts = count(&vulnerabilities);
+            store_findings(&state.db_pool, scan_id, &vulnerabilities, &counts).await?;
+            state.log_stream.publish(
+                LogEvent::audit("scan.complete", None, Some(repository.to_string()))
+                    .with_detail(format!("{}: {}", short_digest(digest), describe(&counts))),
+            );
+            quarantine::release(state, repository_id, repository, digest, Release::Scan, None).await?;
+            if let Err(e) = crate::base_images::advise_vulnerable(state, repository_id, repository, digest, &counts).await {
+                tracing::error!("Failed to advise images built on {}@{}: {}", repository, digest, e);
+            }
+        }
+        Err(e) => {
+            let error = e.to_string();
+            sqlx::query(
+                "UPDATE vulnerability_scans SET status = 'failed', error = $2, completed_at = CURRENT_TIMESTAMP WHERE id = $1",
+            )
+            .bind(scan_id)
+            .bind(&error)
+            .execute(&state.db_pool)
+            .await?;
+            return Err(e);
+        }
+    }
+    Ok(())
+}
+
+/// Run `trivy image` on an image of this registry and return what it printed
+pub(crate) async fn run_trivy(settings: &ScanSettings, image: &str, args: &[&str]) -> Result<Vec<u8>> {
+    let mut command = Command::new(&settings.trivy_path);
+    command
+        .args(["image", "--quiet", "--timeout"])
+        .arg(format!("{}s", settings.timeout_seconds))
+        .args(args);
+    if let Some(server) = &settings.trivy_server_url {
+        command.arg("--server").arg(server);
+    }
+    if settings.registry_insecure {
+        command.arg("--insecure");
+    }
+    // Credentials go through the environment to stay out of the process list
+    if let Some(token) = &settings.trivy_token {
+        command.env("TRIVY_TOKEN", token.expose_secret());
+    }
+    if let Some(username) = &settings.registry_username {
+        command.env("TRIVY_USERNAME", username);
+    }
+    if let Some(password) = &settings.registry_password {
+        command.env("TRIVY_PASSWORD", password.expose_secret());
+    }
+    command.arg(image).stdin(Stdio::null()).kill_on_drop(true);
+
+    // Trivy enforces its own timeout; this one only catches a process that hangs past it
+    let limit = Duration::from_secs(settings.timeout_seconds + 30);
+    let output = tokio::time::timeout(limit, command.output())
+        .await
+        .context("Trivy did not finish in time")?
+        .with_context(|| format!("Failed to run {}", settings.trivy_path))?;
+    if !output.status.success() {
+        // The cause is at the end of Trivy's log
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let stderr = stderr.trim();
+        let start = stderr.char_indices().rev().nth(ERROR_LIMIT - 1).map(|(i, _)| i).unwrap_or(0);
+        bail!("Trivy exited with {}: {}", output.status, &stderr[start..]);
+    }
+    Ok(output.stdout)
+}
+
+async fn store_findings(pool: &PgPool, scan_id: i64, vulnerabilities: &[Vulnerability], counts: &SeverityCounts) -> Result<()> {
+    let mut tx = pool.begin().await?;
+    sqlx::query("DELETE FROM vulnerability_findings WHERE scan_id = $1")
+        .bind(scan_id)
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query(
+        "INSERT INTO vulnerability_findings
+             (scan_id, vulnerability_id, package_name, installed_version, fixed_version, severity, title, primary_url, target)
+         SELECT $1, * FROM UNNEST($2::TEXT[], $3::TEXT[], $4::TEXT[], $5::TEXT[], $6::TEXT[], $7::TEXT[], $8::TEXT[], $9::TEXT[])",
+    )
+    .bind(scan_id)
+    .bind(vulnerabilities.iter().map(|v| v.vulnerability_id.clone()).collect::<Vec<_>>())
+    .bind(vulnerabilities.iter().map(|v| v.package_name.clone()).collect::<Vec<_>>())
+    .bind(vulnerabilities.iter().map(|v| v.installed_version.clone()).collect::<Vec<_>>())
+    .bind(vulnerabilities.iter().map(|v| v.fixed_version.clone()).collect::<Vec<_>>())
+    .bind(vulnerabilities.iter().map(|v| v.severity.clone()).collect::<Vec<_>>())
+    .bind(vulnerabilities.iter().map(|v| v.title.clone()).collect::<Vec<_>>())
+    .bind(vulnerabilities.iter().map(|v| v.primary_url.clone()).collect::<Vec<_>>())
+    .bind(vulnerabilities.iter().map(|v| v.target.clone()).collect::<Vec<_>>())
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query(
+        "UPDATE vulnerability_scans SET
+             status = 'completed', critical_count = $2, high_count = $3, medium_count = $4,
+             low_count = $5, unknown_count = $6, completed_at = CURRENT_TIMESTAMP
+         WHERE id = $1",
+    )
+    .bind(scan_id)
+    .bind(counts.critical)
+    .bind(counts.high)
+    .bind(counts.medium)
+    .bind(counts.low)
+    .bind(counts.unknown)
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+#[derive(sqlx::FromRow)]
+struct ScanRow {
+    manifest_digest: String,
+    status: String,
+    scanner: String,
+    critical_count: i32,
+    high_count: i32,
+    medium_count: i32,
+    low_count: i32,
+    unknown_count: i32,
+    error: Option<String>,
+    started_at: DateTime<Utc>,
+    completed_at: Option<DateTime<Utc>>,
+}
+
+impl From<ScanRow> for ScanSummary {
+    fn from(row: ScanRow) -> Self {
+        let total = row.critical_count + row.high_count + row.medium_count + row.low_count + row.unknown_count;
+        ScanSummary {
+            digest: row.manifest_digest,
+            status: row.status,
+            scanner: row.scanner,
+            summary: SeverityCounts {
+                critical: row.critical_count,
+                high: row.high_count,
+                medium: row.medium_count,
+                low: row.low_count,
+                unknown: row.unknown_count,
+                total,
+            },
+            error: row.error,
+            started_at: row.started_at,
+            completed_at: row.completed_at,
+        }
+    }
+}
+
+const SCAN_COLUMNS: &str = "manifest_digest, status, scanner, critical_count, high_count, medium_count, low_count, \
+                            unknown_count, error, started_at, completed_at";
+
+/// The scan of a manifest, whatever its status
+pub async fn load_scan(pool: &PgPool, repository_id: i64, digest: &str) -> Result<Option<ScanSummary>, sqlx::Error> {
+    let row = sqlx::query_as::<_, ScanRow>(&format!(
+        "SELECT {} FROM vulnerability_scans WHERE repository_id = $1 AND manifest_digest = $2",
+        SCAN_COLUMNS
+    ))
+    .bind(repository_id)
+    .bind(digest)
+    .fetch_optional(pool)
+    .await?;
+    Ok(row.map(ScanSummary::from))
+}
+
+/// The most recently completed scan of any manifest of a repository
+pub async fn latest_scan(pool: &PgPool, repository_id: i64) -> Result<Option<ScanSummary>, sqlx::Error> {
+    let row = sqlx::query_as::<_, ScanRow>(&format!(
+        "SELECT {} FROM vulnerability_scans
+         WHERE repository_id = $1 AND status = 'completed'
+         ORDER BY completed_at DESC
+         LIMIT 1",
+        SCAN_COLUMNS
+    ))
+    .bind(repository_id)
+    .fetch_optional(pool)
+    .await?;
+    Ok(row.map(ScanSummary::from))
+}
+
+/// Findings of a manifest's completed scan of `min_severity` or worse, most severe first
+pub async fn load_findings(
+    pool: &PgPool,
+    repository_id: i64,
+    digest: &str,
+    min_severity: Severity,
+) -> Result<Vec<Vulnerability>, sqlx::Error> {
+    let severities: Vec<String> = [Severity::Critical, Severity::High, Severity::Medium, Severity::Low, Severity::Unknown]
+        .into_iter()
+        .filter(|severity| *severity <= min_severity)
+        .map(|severity| severity.to_string())
+        .collect();
+    sqlx::query_as::<_, Vulnerability>(
+        "SELECT f.vulnerability_id, f.package_name, f.installed_version, f.fixed_version, f.severity,
+                f.title, f.primary_url, f.target
+         FROM vulnerability_findings f
+         JOIN vulnerability_scans s ON s.id = f.scan_id
+         WHERE s.repository_id = $1 AND s.manifest_digest = $2 AND s.status = 'completed'
+           AND f.severity = ANY($3)
+         ORDER BY array_position(ARRAY['CRITICAL', 'HIGH', 'MEDIUM', 'LOW', 'UNKNOWN'], f.severity),
+                  f.vulnerability_id, f.package_name",
+    )
+    .bind(repository_id)
+    .bind(digest)
+    .bind(severities)
+    .fetch_all(pool)
+    .await
+}
+
+/// The parts of Trivy's JSON report that are stored
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct TrivyReport {
+    #[serde(default)]
+    results: Option<Vec<TrivyResult>>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct TrivyResult {
+    target: String,
+    #[serde(default)]
+    vulnerabilities: Option<Vec<TrivyVulnerability>>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct TrivyVulnerability {
+    #[serde(rename = "VulnerabilityID")]
+    vulnerability_id: String,
+    pkg_name: String,
+    #[serde(default)]
+    installed_version: String,
+    fixed_version: Option<String>,
+    severity: String,
+    title: Option<String>,
+    #[serde(rename = "PrimaryURL")]
+    primary_url: Option<String>,
+}
+
+impl TrivyReport {
+    /// Every finding once, most severe first
+    fn vulnerabilities(self) -> Vec<Vulnerability> {
+        let mut vulnerabilities: Vec<Vulnerability> = self
+            .results
+            .unwrap_or_default()
+            .into_iter()
+            .flat_map(|result| {
+                let target = result.target;
+                result.vulnerabilities.unwrap_or_default().into_iter().map(move |v| Vulnerability {
+                    vulnerability_id: v.vulnerability_id,
+                    package_name: v.pkg_name,
+                    installed_version: v.installed_version,
+                    fixed_version: v.fixed_version.filter(|version| !version.is_empty()),
+                    severity: v.severity.parse().unwrap_or(Severity::Unknown).to_string(),
+                    title: v.title.filter(|title| !title.is_empty()),
+                    primary_url: v.primary_url.filter(|url| !url.is_empty()),
+                    target: target.clone(),
+                })
+            })
+            .collect();
+        vulnerabilities.sort_by(|a, b| {
+            (severity_of(a), &a.vulnerability_id, &a.package_name, &a.installed_version, &a.target)
+                .cmp(&(severity_of(b), &b.vulnerability_id, &b.package_name, &b.installed_version, &b.target))
+        });
+        vulnerabilities.dedup_by(|a, b| {
+            a.vulnerability_id == b.vulnerability_id
+                && a.package_name == b.package_name
+                && a.installed_version == b.installed_version
+                && a.target == b.target
+        });
+        vulnerabilities
+    }
+}
+
+fn severity_of(vulnerability: &Vulnerability) -> Severity {
+    vulnerability.severity.parse().unwrap_or(Severity::Unknown)
+}
+
+fn count(vulnerabilities: &[Vulnerability]) -> SeverityCounts {
+    let mut counts = SeverityCounts::default();
+    for vulnerability in vulnerabilities {
+        match severity_of(vulnerability) {
+            Severity::Critical => counts.critical += 1,
+            Severity::High => counts.high += 1,
+            Severity::Medium => counts.medium += 1,
+            Severity::Low => counts.low += 1,
+            Severity::Unknown => counts.unknown += 1,
+        }
+        counts.total += 1;
+    }
+    counts
+}
+
+/// `2 critical, 5 high`, naming only the severities found
+fn describe(counts: &SeverityCounts) -> String {
+    let parts: Vec<String> = [
+        (counts.critical, "critical"),
+        (counts.high, "high"),
+        (counts.medium, "medium"),
+        (counts.low, "low"),
+        (counts.unknown, "unknown"),
+    ]
+    .into_iter()
+    .filter(|(count, _)| *count > 0)
+    .map(|(count, severity)| format!("{} {}", count, severity))
+    .collect();
+    match parts.is_empty() {
+        true => "no vulnerabilities".to_string(),
+        false => parts.join(", "),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const REPORT: &str = r#"{
+        "SchemaVersion": 2,
+        "ArtifactName": "localhost:8080/acme/web@sha256:abc",
+        "Results": [
+            {
+                "Target": "alpine 3.19",
+                "Class": "os-pkgs",
+                "Vulnerabilities": [
+                    {"VulnerabilityID": "CVE-2024-0002", "PkgName": "openssl", "InstalledVersion": "3.1.4-r0",
+                     "FixedVersion": "3.1.4-r5", "Severity": "HIGH", "Title": "openssl: flaw",
+                     "PrimaryURL": "https://avd.aquasec.com/nvd/cve-2024-0002"},
+                    {"VulnerabilityID": "CVE-2024-0001", "PkgName": "busybox", "InstalledVersion": "1.36.1-r15",
+                     "FixedVersion": "", "Severity": "CRITICAL"},
+                    {"VulnerabilityID": "CVE-2024-0002", "PkgName": "openssl", "InstalledVersion": "3.1.4-r0",
+                     "FixedVersion": "3.1.4-r5", "Severity": "HIGH"}
+                ]
+            },
+            {"Target": "app/package-lock.json", "Class": "lang-pkgs", "Vulnerabilities": null},
+            {
+                "Target": "app/requirements.txt",
+                "Class": "lang-pkgs",
+                "Vulnerabilities": [
+                    {"VulnerabilityID": "GHSA-xxxx", "PkgName": "requests", "InstalledVersion": "2.0.0", "Severity": "NEGLIGIBLE"}
+                ]
+            }
+        ]
+    }"#;
+
+    #[test]
+    fn trivy_reports_are_flattened_most_severe_first() {
+        let report: TrivyReport = serde_json::from_str(REPORT).unwrap();
+        let vulnerabilities = report.vulnerabilities();
+        let ids: Vec<&str> = vulnerabilities.iter().map(|v| v.vulnerability_id.as_str()).collect();
+        assert_eq!(ids, ["CVE-2024-0001", "CVE-2024-0002", "GHSA-xxxx"]);
+        assert_eq!(vulnerabilities[0].fixed_version, None);
+        assert_eq!(vulnerabilities[1].fixed_version.as_deref(), Some("3.1.4-r5"));
+        assert_eq!(vulnerabilities[1].target, "alpine 3.19");
+        assert_eq!(vulnerabilities[2].severity, "UNKNOWN");
+
+        let counts = count(&vulnerabilities);
+        assert_eq!(counts, SeverityCounts { critical: 1, high: 1, medium: 0, low: 0, unknown: 1, total: 3 });
+        assert_eq!(describe(&counts), "1 critical, 1 high, 1 unknown");
+        assert_eq!(describe(&SeverityCounts::default()), "no vulnerabilities");
+    }
+
+    #[test]
+    fn clean_images_have_no_results() {
+        let report: TrivyReport = serde_json::from_str(r#"{"SchemaVersion": 2, "ArtifactName": "x"}"#).unwrap();
+        assert!(report.vulnerabilities().is_empty());
+    }
+}
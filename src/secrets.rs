@@ -0,0 +1,205 @@
+// src/secrets.rs - Encrypted organization secrets for build jobs
+//
+// Values are sealed with ChaCha20-Poly1305 under `SECRETS_ENCRYPTION_KEY`, with the
+// organization and secret name as associated data. They are only opened by `job_secrets`, which
+// hands a build job its environment together with a masker that hides the values in the job's
+// log. The same key also encrypts the webhook signing key kept in the database.
+use std::collections::BTreeMap;
+
+use anyhow::{anyhow, bail, Context, Result};
+use base64::Engine;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use secrecy::{ExposeSecret, Secret};
+use sqlx::PgPool;
+
+use crate::config::settings::SecretsSettings;
+
+/// Longest accepted secret value
+pub const MAX_SECRET_BYTES: usize = 64 * 1024;
+/// Shortest value that is masked in logs; shorter ones would garble unrelated output
+const MIN_MASKED_LEN: usize = 4;
+const MASK: &str = "***";
+
+/// Encrypts and decrypts organization secret values
+pub struct SecretCipher {
+    cipher: ChaCha20Poly1305,
+}
+
+impl SecretCipher {
+    /// `None` when no encryption key is configured
+    pub fn from_settings(settings: &SecretsSettings) -> Result<Option<Self>> {
+        let Some(key) = &settings.encryption_key else {
+            return Ok(None);
+        };
+        let bytes = base64::prelude::BASE64_STANDARD
+            .decode(key.expose_secret().trim())
+            .context("SECRETS_ENCRYPTION_KEY must be base64")?;
+        if bytes.len() != 32 {
+            bail!("SECRETS_ENCRYPTION_KEY must be 32 bytes, got {}", bytes.len());
+        }
+        Ok(Some(Self { cipher: ChaCha20Poly1305::new(Key::from_slice(&bytes)) }))
+    }
+
+    /// Returns (ciphertext, nonce)
+    pub fn seal(&self, org_id: i64, name: &str, value: &str) -> Result<(Vec<u8>, Vec<u8>)> {
+        self.seal_bytes(&associated_data(org_id, name), value.as_bytes())
+            .with_context(|| format!("Failed to encrypt secret {}", name))
+    }
+
+    pub fn open(&self, org_id: i64, name: &str, ciphertext: &[u8], nonce: &[u8]) -> Result<Secret<String>> {
+        let plaintext = self
+            .open_bytes(&associated_data(org_id, name), ciphertext, nonce)
+            .with_context(|| format!("Secret {} cannot be decrypted", name))?;
+        Ok(Secret::new(String::from_utf8(plaintext).context("Secret is not UTF-8")?))
+    }
+
+    /// Seal any value bound to `aad`; returns (ciphertext, nonce)
+    pub fn seal_bytes(&self, aad: &[u8], msg: &[u8]) -> Result<(Vec<u8>, Vec<u8>)> {
+        let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = self.cipher.encrypt(&nonce, Payload { msg, aad }).map_err(|_| anyhow!("Encryption failed"))?;
+        Ok((ciphertext, nonce.to_vec()))
+    }
+
+    pub fn open_bytes(&self, aad: &[u8], ciphertext: &[u8], nonce: &[u8]) -> Result<Vec<u8>> {
+        if nonce.len() != 12 {
+            bail!("Corrupt nonce");
+        }
+        self.cipher
+            .decrypt(Nonce::from_slice(nonce), Payload { msg: ciphertext, aad })
+            .map_err(|_| anyhow!("Value cannot be decrypted with the configured key"))
+    }
+}
+
+fn associated_data(org_id: i64, name: &str) -> Vec<u8> {
+    format!("aerugo:organization:{}:secret:{}", org_id, name).into_bytes()
+}
+
+/// Whether `name` is usable as an environment variable name
+pub fn is_valid_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    name.len() <= 128
+        && chars.next().is_some_and(|c| c.is_ascii_uppercase() || c == '_')
+        && chars.all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_')
+        // Reserved for variables the build system sets itself
+        && !name.starts_with("AERUGO_")
+}
+
+/// Decrypted secrets of one organization, for a single build job
+pub struct JobSecrets {
+    vars: Vec<(String, Secret<String>)>,
+}
+
+impl JobSecrets {
+    /// Name and value of every secret, to be set in the job's environment, e.g. with
+    /// `Command::envs`
+    pub fn env(&self) -> BTreeMap<&str, &str> {
+        self.vars.iter().map(|(name, value)| (name.as_str(), value.expose_secret().as_str())).collect()
+    }
+
+    /// `line` with every secret value of at least four characters replaced by `***`
+    pub fn mask(&self, line: &str) -> String {
+        let mut values: Vec<&str> = self
+            .vars
+            .iter()
+            .map(|(_, value)| value.expose_secret().as_str())
+            .filter(|value| value.len() >= MIN_MASKED_LEN)
+            .collect();
+        // Longest first, so a value containing another is masked as a whole
+        values.sort_by_key(|value| std::cmp::Reverse(value.len()));
+
+        let mut masked = line.to_string();
+        for value in values {
+            masked = masked.replace(value, MASK);
+        }
+        masked
+    }
+}
+
+/// Decrypt every secret of an organization for a build job
+pub async fn job_secrets(pool: &PgPool, cipher: &SecretCipher, org_id: i64) -> Result<JobSecrets> {
+    let rows = sqlx::query_as::<_, (String, Vec<u8>, Vec<u8>)>(
+        "SELECT name, ciphertext, nonce FROM organization_secrets WHERE organization_id = $1 ORDER BY name",
+    )
+    .bind(org_id)
+    .fetch_all(pool)
+    .await?;
+
+    open_all(cipher, org_id, rows)
+}
+
+/// Decrypt stored `(name, ciphertext, nonce)` rows; one that fails to open fails the job
+fn open_all(cipher: &SecretCipher, org_id: i64, rows: Vec<(String, Vec<u8>, Vec<u8>)>) -> Result<JobSecrets> {
+    let vars = rows
+        .into_iter()
+        .map(|(name, ciphertext, nonce)| {
+            let value = cipher
+                .open(org_id, &name, &ciphertext, &nonce)
+                .with_context(|| format!("Failed to decrypt secret {}", name))?;
+            Ok((name, value))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(JobSecrets { vars })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cipher() -> SecretCipher {
+        let settings = SecretsSettings {
+            encryption_key: Some(Secret::new(base64::prelude::BASE64_STANDARD.encode([7_u8; 32]))),
+        };
+        SecretCipher::from_settings(&settings).unwrap().unwrap()
+    }
+
+    #[test]
+    fn sealed_values_are_bound_to_their_secret() {
+        let cipher = cipher();
+        let (ciphertext, nonce) = cipher.seal(1, "NPM_TOKEN", "s3cret-value").unwrap();
+
+        let opened = cipher.open(1, "NPM_TOKEN", &ciphertext, &nonce).unwrap();
+        assert_eq!(opened.expose_secret(), "s3cret-value");
+        assert!(cipher.open(2, "NPM_TOKEN", &ciphertext, &nonce).is_err());
+        assert!(cipher.open(1, "OTHER_TOKEN", &ciphertext, &nonce).is_err());
+    }
+
+    #[test]
+    fn stored_secrets_open_into_the_job_environment() {
+        let cipher = cipher();
+        let row = |org_id: i64, name: &str, value: &str| {
+            let (ciphertext, nonce) = cipher.seal(org_id, name, value).unwrap();
+            (name.to_string(), ciphertext, nonce)
+        };
+
+        let secrets = open_all(&cipher, 1, vec![row(1, "NPM_TOKEN", "npm-abc"), row(1, "REGISTRY_PASSWORD", "hunter22")]).unwrap();
+        let env = secrets.env();
+        assert_eq!(env.len(), 2);
+        assert_eq!(env["NPM_TOKEN"], "npm-abc");
+        assert_eq!(env["REGISTRY_PASSWORD"], "hunter22");
+
+        // A value sealed for another organization does not open
+        assert!(open_all(&cipher, 1, vec![row(2, "NPM_TOKEN", "npm-abc")]).is_err());
+    }
+
+    #[test]
+    fn values_are_masked_in_log_lines() {
+        let secrets = JobSecrets {
+            vars: vec![
+                ("TOKEN".to_string(), Secret::new("abcd1234".to_string())),
+                ("PREFIX".to_string(), Secret::new("abcd".to_string())),
+                ("FLAG".to_string(), Secret::new("1".to_string())),
+            ],
+        };
+        assert_eq!(secrets.mask("login abcd1234 then abcd, retry 1"), "login *** then ***, retry 1");
+    }
+
+    #[test]
+    fn names_must_be_environment_variables() {
+        assert!(is_valid_name("REGISTRY_PASSWORD"));
+        assert!(is_valid_name("_TOKEN2"));
+        assert!(!is_valid_name("2TOKEN"));
+        assert!(!is_valid_name("npm-token"));
+        assert!(!is_valid_name("AERUGO_JOB_ID"));
+    }
+}
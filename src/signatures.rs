@@ -0,0 +1,548 @@
+// src/signatures.rs - Verification of cosign and Notation image signatures
+//
+// Cosign stores a signature either as a `sha256-<hex>.sig` tag or, with OCI 1.1 referrers, as an
+// artifact of type `application/vnd.dev.cosign.artifact.sig.v1+json` whose subject is the image.
+// Each layer of the signature manifest is a signed payload naming the image digest, with the
+// signature (and, for keyless signing, the Fulcio certificate and Rekor bundle) in annotations.
+// Notation (Notary Project) signatures are referrers of type `application/vnd.cncf.notary.signature`
+// whose single layer is a JWS or COSE envelope over the image manifest's descriptor.
+// Pushing a signature queues a `signature` job for its image; the job reads the signatures from
+// storage and runs `cosign verify-blob` against each configured public key and the keyless
+// policy, or `notation blob verify` against the configured trust policy on the stored manifest,
+// so nothing is pulled back through the registry. The result is kept per manifest digest in
+// `signature_verifications` and published as a `signature.verified` or `signature.invalid` audit
+// event; repositories requiring signatures refuse pulls of anything not verified.
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+
+use anyhow::{anyhow, bail, Context, Result};
+use base64::Engine;
+use bytes::Bytes;
+use serde_json::{json, Value};
+use sqlx::PgPool;
+use tokio::process::Command;
+
+use crate::config::settings::SignatureSettings;
+use crate::handlers::docker_registry_v2::{get_repository_blob, load_manifest_content};
+use crate::log_stream::LogEvent;
+use crate::webhooks::chat::short_digest;
+use crate::AppState;
+
+/// Artifact type of cosign signatures pushed as referrers
+pub const COSIGN_SIGNATURE_ARTIFACT_TYPE: &str = "application/vnd.dev.cosign.artifact.sig.v1+json";
+/// Artifact type of Notation signatures
+pub const NOTATION_SIGNATURE_ARTIFACT_TYPE: &str = "application/vnd.cncf.notary.signature";
+
+const ANNOTATION_SIGNATURE: &str = "dev.cosignproject.cosign/signature";
+const ANNOTATION_CERTIFICATE: &str = "dev.sigstore.cosign/certificate";
+const ANNOTATION_CHAIN: &str = "dev.sigstore.cosign/chain";
+const ANNOTATION_BUNDLE: &str = "dev.sigstore.cosign/bundle";
+/// Characters of cosign's error output kept on an invalid signature
+const ERROR_LIMIT: usize = 500;
+
+/// Artifact types of signature referrers
+pub fn signature_artifact_types() -> Vec<String> {
+    vec![COSIGN_SIGNATURE_ARTIFACT_TYPE.to_string(), NOTATION_SIGNATURE_ARTIFACT_TYPE.to_string()]
+}
+
+/// The tag cosign stores the signature of a manifest under
+pub fn signature_tag(digest: &str) -> String {
+    format!("{}.sig", digest.replacen(':', "-", 1))
+}
+
+/// The manifest a `sha256-<hex>.sig` tag signs
+pub fn signed_digest(tag: &str) -> Option<String> {
+    let hex = tag.strip_prefix("sha256-")?.strip_suffix(".sig")?;
+    (hex.len() == 64 && hex.bytes().all(|b| b.is_ascii_hexdigit())).then(|| format!("sha256:{}", hex))
+}
+
+/// A cosign signature found in a signature manifest
+#[derive(Clone)]
+struct Signature {
+    payload: Vec<u8>,
+    signature: String,
+    certificate: Option<String>,
+    chain: Option<String>,
+    bundle: Option<Value>,
+}
+
+/// A Notation signature envelope
+struct NotationSignature {
+    envelope: Vec<u8>,
+    /// `jws` or `cose`
+    format: &'static str,
+}
+
+/// Verify the signatures attached to a manifest and record the outcome. Errors are returned only
+/// when a signature could not be read or a verifier run, so the job is retried; signatures that
+/// fail to verify are a result.
+pub async fn verify_manifest(state: &AppState, repository_id: i64, repository: &str, digest: &str) -> Result<()> {
+    let settings = &state.config.signatures;
+    if !settings.enabled() {
+        bail!("No public keys, keyless policy or Notation trust policy are configured");
+    }
+
+    let manifests = sqlx::query_as::<_, (String, Option<String>)>(
+        "SELECT m.digest, m.artifact_type FROM manifests m
+         WHERE m.repository_id = $1
+           AND ((m.subject_digest = $2 AND m.artifact_type = ANY($3))
+                OR m.id = (SELECT t.manifest_id FROM tags t WHERE t.repository_id = $1 AND t.name = $4))
+         ORDER BY m.created_at DESC",
+    )
+    .bind(repository_id)
+    .bind(digest)
+    .bind(signature_artifact_types())
+    .bind(signature_tag(digest))
+    .fetch_all(&state.db_pool)
+    .await?;
+
+    let mut signatures = Vec::new();
+    let mut envelopes = Vec::new();
+    for (manifest, artifact_type) in &manifests {
+        match artifact_type.as_deref() {
+            Some(NOTATION_SIGNATURE_ARTIFACT_TYPE) => envelopes.extend(load_envelopes(state, repository, manifest).await?),
+            _ => signatures.extend(load_signatures(state, repository, manifest, digest).await?),
+        }
+    }
+    let found = signatures.len() + envelopes.len();
+    if found == 0 {
+        tracing::info!("No signature of {}@{} to verify", repository, digest);
+        return Ok(());
+    }
+
+    let mut errors = Vec::new();
+    let mut signer = None;
+    'signatures: for signature in &signatures {
+        for policy in policies(settings, signature) {
+            match verify_cosign(settings, signature, &policy).await? {
+                Ok(()) => {
+                    signer = Some(policy.signer());
+                    break 'signatures;
+                }
+                Err(error) => errors.push(error),
+            }
+        }
+    }
+    if let (None, Some(config_home), false) = (&signer, &settings.notation_config_home, envelopes.is_empty()) {
+        let (manifest, media_type) = load_subject(state, repository_id, repository, digest).await?;
+        for envelope in &envelopes {
+            match verify_notation(settings, config_home, &manifest, &media_type, envelope).await? {
+                Ok(()) => {
+                    let policy = settings.notation_policy_name.as_deref().unwrap_or("(global)");
+                    signer = Some(format!("notation trust policy {}", policy));
+                    break;
+                }
+                Err(error) => errors.push(error),
+            }
+        }
+    }
+
+    let children = match signer {
+        Some(_) => index_children(state, repository, digest).await?,
+        None => Vec::new(),
+    };
+    let error = match (&signer, errors.is_empty()) {
+        (Some(_), _) => None,
+        (None, true) => Some("No configured key, keyless policy or trust policy applies to the signatures".to_string()),
+        (None, false) => Some(errors.join("; ")),
+    };
+    record(&state.db_pool, repository_id, digest, signer.as_deref(), error.as_deref(), &children).await?;
+
+    let event = match &signer {
+        Some(signer) => LogEvent::audit("signature.verified", None, Some(repository.to_string()))
+            .with_detail(format!("{}: signed by {}", short_digest(digest), signer)),
+        None => LogEvent::audit("signature.invalid", None, Some(repository.to_string()))
+            .with_detail(format!("{}: {} signatures, none verified", short_digest(digest), found)),
+    };
+    state.log_stream.publish(event);
+    Ok(())
+}
+
+/// Signatures in a signature manifest whose payload names `digest`
+async fn load_signatures(state: &AppState, repository: &str, manifest: &str, digest: &str) -> Result<Vec<Signature>> {
+    let content = load_manifest_content(state, repository, manifest)
+        .await?
+        .ok_or_else(|| anyhow!("Signature manifest {} is missing", manifest))?;
+    let content: Value = serde_json::from_slice(&content)?;
+
+    let mut signatures = Vec::new();
+    for layer in content.get("layers").and_then(Value::as_array).into_iter().flatten() {
+        let annotation = |key: &str| layer.pointer(&format!("/annotations/{}", key.replace('/', "~1"))).and_then(Value::as_str);
+        let (Some(signature), Some(payload_digest)) = (annotation(ANNOTATION_SIGNATURE), layer.get("digest").and_then(Value::as_str)) else {
+            continue;
+        };
+        let payload = get_repository_blob(state, repository, payload_digest)
+            .await?
+            .ok_or_else(|| anyhow!("Signature payload {} is missing", payload_digest))?;
+        // A signature copied from another image does not sign this one
+        if signed_image(&payload).as_deref() != Some(digest) {
+            continue;
+        }
+        signatures.push(Signature {
+            payload: payload.to_vec(),
+            signature: signature.to_string(),
+            certificate: annotation(ANNOTATION_CERTIFICATE).map(str::to_string),
+            chain: annotation(ANNOTATION_CHAIN).map(str::to_string),
+            bundle: annotation(ANNOTATION_BUNDLE).and_then(|bundle| serde_json::from_str(bundle).ok()),
+        });
+    }
+    Ok(signatures)
+}
+
+/// Envelopes in a Notation signature manifest
+async fn load_envelopes(state: &AppState, repository: &str, manifest: &str) -> Result<Vec<NotationSignature>> {
+    let content = load_manifest_content(state, repository, manifest)
+        .await?
+        .ok_or_else(|| anyhow!("Signature manifest {} is missing", manifest))?;
+    let content: Value = serde_json::from_slice(&content)?;
+
+    let mut envelopes = Vec::new();
+    for (envelope_digest, format) in envelope_layers(&content) {
+        let envelope = get_repository_blob(state, repository, envelope_digest)
+            .await?
+            .ok_or_else(|| anyhow!("Signature envelope {} is missing", envelope_digest))?;
+        envelopes.push(NotationSignature { envelope: envelope.to_vec(), format });
+    }
+    Ok(envelopes)
+}
+
+/// Digest and envelope format of each JWS or COSE layer of a Notation signature manifest
+fn envelope_layers(content: &Value) -> Vec<(&str, &'static str)> {
+    let mut layers = Vec::new();
+    for layer in content.get("layers").and_then(Value::as_array).into_iter().flatten() {
+        let format = match layer.get("mediaType").and_then(Value::as_str) {
+            Some("application/jose+json") => "jws",
+            Some("application/cose") => "cose",
+            _ => continue,
+        };
+        if let Some(digest) = layer.get("digest").and_then(Value::as_str) {
+            layers.push((digest, format));
+        }
+    }
+    layers
+}
+
+/// Content and media type of the signed manifest, which Notation signatures cover
+async fn load_subject(state: &AppState, repository_id: i64, repository: &str, digest: &str) -> Result<(Bytes, String)> {
+    let media_type = sqlx::query_scalar::<_, String>("SELECT media_type FROM manifests WHERE repository_id = $1 AND digest = $2")
+        .bind(repository_id)
+        .bind(digest)
+        .fetch_optional(&state.db_pool)
+        .await?
+        .ok_or_else(|| anyhow!("Manifest {} is not stored", digest))?;
+    let content = load_manifest_content(state, repository, digest)
+        .await?
+        .ok_or_else(|| anyhow!("Manifest {} is missing", digest))?;
+    Ok((content, media_type))
+}
+
+/// The image digest a cosign simple-signing payload names
+fn signed_image(payload: &[u8]) -> Option<String> {
+    let payload: Value = serde_json::from_slice(payload).ok()?;
+    payload.pointer("/critical/image/docker-manifest-digest")?.as_str().map(str::to_string)
+}
+
+/// What a signature is checked against
+enum Policy {
+    Key(String),
+    Keyless { identity: String, issuer: String },
+}
+
+impl Policy {
+    fn signer(&self) -> String {
+        match self {
+            Policy::Key(key) => key.clone(),
+            Policy::Keyless { identity, issuer } => format!("{} ({})", identity, issuer),
+        }
+    }
+}
+
+/// Keys apply to signatures without a certificate, the keyless policy to those with one
+fn policies(settings: &SignatureSettings, signature: &Signature) -> Vec<Policy> {
+    match (&signature.certificate, &settings.keyless_identity, &settings.keyless_issuer) {
+        (Some(_), Some(identity), Some(issuer)) => vec![Policy::Keyless { identity: identity.clone(), issuer: issuer.clone() }],
+        (Some(_), _, _) => Vec::new(),
+        (None, _, _) => settings.public_keys.iter().cloned().map(Policy::Key).collect(),
+    }
+}
+
+/// A scratch directory for the files a verification hands to cosign or notation, removed on drop
+struct WorkDir(PathBuf);
+
+impl WorkDir {
+    async fn create() -> Result<Self> {
+        let dir = std::env::temp_dir().join(format!("aerugo-verify-{}", uuid::Uuid::new_v4()));
+        tokio::fs::create_dir(&dir).await?;
+        Ok(WorkDir(dir))
+    }
+
+    async fn write(&self, name: &str, content: impl AsRef<[u8]>) -> Result<PathBuf> {
+        let path = self.0.join(name);
+        tokio::fs::write(&path, content).await?;
+        Ok(path)
+    }
+}
+
+impl Drop for WorkDir {
+    fn drop(&mut self) {
+        if let Err(e) = std::fs::remove_dir_all(&self.0) {
+            tracing::warn!("Failed to remove {}: {}", self.0.display(), e);
+        }
+    }
+}
+
+/// Run `cosign verify-blob` on one signature; the inner result is the verification outcome
+async fn verify_cosign(settings: &SignatureSettings, signature: &Signature, policy: &Policy) -> Result<Result<(), String>> {
+    let dir = WorkDir::create().await?;
+    let mut command = Command::new(&settings.cosign_path);
+    command.arg("verify-blob").arg("--signature").arg(dir.write("signature", &signature.signature).await?);
+    match policy {
+        Policy::Key(key) => {
+            command.arg("--key").arg(key);
+        }
+        Policy::Keyless { identity, issuer } => {
+            command.arg("--certificate-identity-regexp").arg(identity).arg("--certificate-oidc-issuer").arg(issuer);
+            if let Some(certificate) = &signature.certificate {
+                command.arg("--certificate").arg(dir.write("certificate.pem", certificate).await?);
+            }
+            if let Some(chain) = &signature.chain {
+                command.arg("--certificate-chain").arg(dir.write("chain.pem", chain).await?);
+            }
+            if let Some(rekor) = &settings.rekor_url {
+                command.arg("--rekor-url").arg(rekor);
+            }
+        }
+    }
+    // The Rekor entry recorded at signing proves the signature was logged without asking Rekor;
+    // key-based signatures made without the log are accepted on the key alone
+    match (&signature.bundle, policy) {
+        (Some(rekor_bundle), _) => {
+            let bundle = json!({
+                "base64Signature": signature.signature,
+                "cert": signature.certificate.as_ref().map(|c| base64::prelude::BASE64_STANDARD.encode(c)),
+                "rekorBundle": rekor_bundle,
+            });
+            command.arg("--bundle").arg(dir.write("bundle.json", serde_json::to_vec(&bundle)?).await?);
+        }
+        (None, Policy::Key(_)) => {
+            command.arg("--insecure-ignore-tlog=true");
+        }
+        (None, Policy::Keyless { .. }) => {}
+    }
+    command.arg(dir.write("payload.json", &signature.payload).await?);
+    run_check(command, settings, &settings.cosign_path).await.map(|outcome| outcome.map_err(|e| format!("{}: {}", policy.signer(), e)))
+}
+
+/// Run `notation blob verify` on one envelope against the signed manifest's content, with the
+/// trust policy and trust store under `NOTATION_CONFIG_HOME`
+async fn verify_notation(
+    settings: &SignatureSettings,
+    config_home: &str,
+    manifest: &[u8],
+    media_type: &str,
+    envelope: &NotationSignature,
+) -> Result<Result<(), String>> {
+    let dir = WorkDir::create().await?;
+    let blob = dir.write("manifest.json", manifest).await?;
+    // notation tells the envelope format from the signature file's name
+    let signature = dir.write(&format!("manifest.json.{}.sig", envelope.format), &envelope.envelope).await?;
+
+    let command = notation_command(settings, config_home, media_type, &signature, &blob);
+    run_check(command, settings, &settings.notation_path).await.map(|outcome| outcome.map_err(|e| format!("notation: {}", e)))
+}
+
+/// `notation blob verify` of `blob` against `signature`, reading its configuration from
+/// `config_home`
+fn notation_command(settings: &SignatureSettings, config_home: &str, media_type: &str, signature: &Path, blob: &Path) -> Command {
+    let mut command = Command::new(&settings.notation_path);
+    command
+        .args(["blob", "verify", "--media-type", media_type, "--signature"])
+        .arg(signature)
+        .env("XDG_CONFIG_HOME", config_home);
+    if let Some(policy) = &settings.notation_policy_name {
+        command.arg("--policy-name").arg(policy);
+    }
+    command.arg(blob);
+    command
+}
+
+/// Run a verification; the inner error is the tail of what a failed one printed
+async fn run_check(mut command: Command, settings: &SignatureSettings, program: &str) -> Result<Result<(), String>> {
+    command.stdin(Stdio::null()).kill_on_drop(true);
+    let output = tokio::time::timeout(Duration::from_secs(settings.timeout_seconds), command.output())
+        .await
+        .with_context(|| format!("{} did not finish in time", program))?
+        .with_context(|| format!("Failed to run {}", program))?;
+    if output.status.success() {
+        return Ok(Ok(()));
+    }
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let stderr = stderr.trim();
+    let start = stderr.char_indices().rev().nth(ERROR_LIMIT - 1).map(|(i, _)| i).unwrap_or(0);
+    Ok(Err(stderr[start..].to_string()))
+}
+
+/// Platform images of an index, which its signature covers
+async fn index_children(state: &AppState, repository: &str, digest: &str) -> Result<Vec<String>> {
+    let Some(content) = load_manifest_content(state, repository, digest).await? else {
+        return Ok(Vec::new());
+    };
+    let content: Value = serde_json::from_slice(&content)?;
+    Ok(content
+        .get("manifests")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.get("digest").and_then(Value::as_str).map(str::to_string))
+        .collect())
+}
+
+/// Store the outcome for a manifest and the platform images a verified index covers
+async fn record(
+    pool: &PgPool,
+    repository_id: i64,
+    digest: &str,
+    signer: Option<&str>,
+    error: Option<&str>,
+    children: &[String],
+) -> Result<()> {
+    let status = if signer.is_some() { "verified" } else { "invalid" };
+    let mut tx = pool.begin().await?;
+    sqlx::query(
+        "INSERT INTO signature_verifications (repository_id, manifest_digest, status, signer, error)
+         VALUES ($1, $2, $3, $4, $5)
+         ON CONFLICT (repository_id, manifest_digest) DO UPDATE SET
+             status = EXCLUDED.status, signer = EXCLUDED.signer, index_digest = NULL,
+             error = EXCLUDED.error, verified_at = CURRENT_TIMESTAMP",
+    )
+    .bind(repository_id)
+    .bind(digest)
+    .bind(status)
+    .bind(signer)
+    .bind(error)
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query("DELETE FROM signature_verifications WHERE repository_id = $1 AND index_digest = $2")
+        .bind(repository_id)
+        .bind(digest)
+        .execute(&mut *tx)
+        .await?;
+    // A platform image with a verified signature of its own keeps it
+    sqlx::query(
+        "INSERT INTO signature_verifications (repository_id, manifest_digest, status, signer, index_digest)
+         SELECT $1, child, 'verified', $3, $2 FROM UNNEST($4::TEXT[]) AS child
+         ON CONFLICT (repository_id, manifest_digest) DO UPDATE SET
+             status = 'verified', signer = EXCLUDED.signer, index_digest = EXCLUDED.index_digest,
+             error = NULL, verified_at = CURRENT_TIMESTAMP
+         WHERE signature_verifications.status <> 'verified'",
+    )
+    .bind(repository_id)
+    .bind(digest)
+    .bind(signer)
+    .bind(children)
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIGEST: &str = "sha256:2c26b46b68ffc68ff99b453c1d30413413422d706483bfa0f98a5e886266e7ae";
+
+    #[test]
+    fn signature_tags_name_the_signed_digest() {
+        let tag = signature_tag(DIGEST);
+        assert_eq!(tag, "sha256-2c26b46b68ffc68ff99b453c1d30413413422d706483bfa0f98a5e886266e7ae.sig");
+        assert_eq!(signed_digest(&tag).as_deref(), Some(DIGEST));
+        assert_eq!(signed_digest("sha256-2c26b46b.sig"), None);
+        assert_eq!(signed_digest("sha256-2c26b46b68ffc68ff99b453c1d30413413422d706483bfa0f98a5e886266e7ae.att"), None);
+    }
+
+    #[test]
+    fn payloads_name_the_image_they_sign() {
+        let payload = format!(
+            r#"{{"critical":{{"identity":{{"docker-reference":"localhost:8080/acme/web"}},"image":{{"docker-manifest-digest":"{}"}},"type":"cosign container image signature"}},"optional":null}}"#,
+            DIGEST
+        );
+        assert_eq!(signed_image(payload.as_bytes()).as_deref(), Some(DIGEST));
+        assert_eq!(signed_image(b"{}"), None);
+    }
+
+    fn settings() -> SignatureSettings {
+        SignatureSettings {
+            cosign_path: "cosign".to_string(),
+            public_keys: vec!["/etc/aerugo/cosign.pub".to_string()],
+            keyless_identity: Some("^https://github.com/acme/".to_string()),
+            keyless_issuer: Some("https://token.actions.githubusercontent.com".to_string()),
+            rekor_url: None,
+            notation_path: "notation".to_string(),
+            notation_config_home: None,
+            notation_policy_name: None,
+            timeout_seconds: 60,
+        }
+    }
+
+    #[test]
+    fn keys_check_keyed_signatures_and_the_keyless_policy_certificates() {
+        let settings = settings();
+        let keyed = Signature { payload: Vec::new(), signature: "MEUC".to_string(), certificate: None, chain: None, bundle: None };
+        let keyless = Signature { certificate: Some("-----BEGIN CERTIFICATE-----".to_string()), ..keyed.clone() };
+
+        let signers: Vec<String> = policies(&settings, &keyed).iter().map(Policy::signer).collect();
+        assert_eq!(signers, ["/etc/aerugo/cosign.pub"]);
+        let signers: Vec<String> = policies(&settings, &keyless).iter().map(Policy::signer).collect();
+        assert_eq!(signers, ["^https://github.com/acme/ (https://token.actions.githubusercontent.com)"]);
+
+        let keys_only = SignatureSettings { keyless_identity: None, keyless_issuer: None, ..settings };
+        assert!(policies(&keys_only, &keyless).is_empty());
+    }
+
+    #[test]
+    fn jws_and_cose_layers_are_notation_envelopes() {
+        let manifest = json!({
+            "mediaType": "application/vnd.oci.image.manifest.v1+json",
+            "artifactType": "application/vnd.cncf.notary.signature",
+            "layers": [
+                {"mediaType": "application/jose+json", "digest": "sha256:aaaa"},
+                {"mediaType": "application/vnd.oci.image.layer.v1.tar+gzip", "digest": "sha256:bbbb"},
+                {"mediaType": "application/cose", "digest": "sha256:cccc"},
+                {"mediaType": "application/jose+json"}
+            ]
+        });
+        assert_eq!(envelope_layers(&manifest), [("sha256:aaaa", "jws"), ("sha256:cccc", "cose")]);
+        assert!(envelope_layers(&json!({"layers": []})).is_empty());
+        assert!(envelope_layers(&json!({})).is_empty());
+    }
+
+    #[test]
+    fn notation_verifies_the_manifest_with_the_configured_trust_policy() {
+        let (signature, blob) = (Path::new("/tmp/w/manifest.json.jws.sig"), Path::new("/tmp/w/manifest.json"));
+        let media_type = "application/vnd.oci.image.manifest.v1+json";
+
+        let command = notation_command(&settings(), "/etc/aerugo/notation", media_type, signature, blob);
+        let command = command.as_std();
+        assert_eq!(command.get_program(), "notation");
+        let args: Vec<_> = command.get_args().collect();
+        assert_eq!(
+            args,
+            ["blob", "verify", "--media-type", media_type, "--signature", "/tmp/w/manifest.json.jws.sig", "/tmp/w/manifest.json"]
+        );
+        let envs: Vec<_> = command.get_envs().collect();
+        assert_eq!(envs, [(std::ffi::OsStr::new("XDG_CONFIG_HOME"), Some(std::ffi::OsStr::new("/etc/aerugo/notation")))]);
+
+        let settings = SignatureSettings {
+            notation_path: "/usr/local/bin/notation".to_string(),
+            notation_policy_name: Some("production".to_string()),
+            ..settings()
+        };
+        let command = notation_command(&settings, "/etc/aerugo/notation", media_type, signature, blob);
+        let command = command.as_std();
+        assert_eq!(command.get_program(), "/usr/local/bin/notation");
+        let args: Vec<_> = command.get_args().collect();
+        assert_eq!(&args[6..], ["--policy-name", "production", "/tmp/w/manifest.json"]);
+    }
+}
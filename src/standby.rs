@@ -0,0 +1,335 @@
+// src/standby.rs - Warm standby: a read-only follower of a primary registry that can be promoted
+//
+// Metadata reaches the standby through PostgreSQL replication: either the database is a
+// streaming (physical) replica of the primary's, or it receives changes through a logical
+// replication subscription. Blobs are expected to live in storage shared with, or replicated
+// from, the primary. This module samples the replication lag, keeps the instance read-only
+// while it follows the primary, and performs the promotion when the primary is lost.
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::PgPool;
+use utoipa::ToSchema;
+
+use crate::config::settings::StandbySettings;
+use crate::AppState;
+
+/// How the standby's database receives the primary's changes
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ReplicationMode {
+    /// The database is a streaming replica in recovery
+    Physical,
+    /// The database applies changes from a logical replication subscription
+    Logical,
+    /// No replication detected; the database is writable and not following anything
+    None,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ReplicationStatus {
+    pub mode: ReplicationMode,
+    /// How far the applied metadata trails the primary; `None` when it cannot be determined
+    pub lag_seconds: Option<f64>,
+    /// Commit time of the last change applied from the primary
+    pub last_applied_at: Option<DateTime<Utc>>,
+    /// Whether the WAL receiver or subscription worker is currently connected to the primary
+    pub streaming: bool,
+    pub checked_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct StandbyStatus {
+    /// `standby` while following a primary, `primary` otherwise
+    pub role: String,
+    /// Whether writes are currently rejected
+    pub read_only: bool,
+    pub promoted_at: Option<DateTime<Utc>>,
+    /// Latest sample taken by the monitor
+    pub replication: Option<ReplicationStatus>,
+    /// Whether the configured primary accepted a connection at the latest sample
+    pub primary_reachable: Option<bool>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum PromotionError {
+    #[error("This instance is not configured as a standby")]
+    NotStandby,
+    #[error("This instance has already been promoted")]
+    AlreadyPromoted,
+    #[error("Promotion refused: {0}")]
+    Unsafe(String),
+    #[error("Promotion failed: {0}")]
+    Failed(#[from] anyhow::Error),
+}
+
+#[derive(Default)]
+struct Sample {
+    replication: Option<ReplicationStatus>,
+    primary_reachable: Option<bool>,
+    promoted_at: Option<DateTime<Utc>>,
+}
+
+/// Role of this instance, shared by the read-only guard, the monitor and the promotion endpoint
+pub struct Standby {
+    enabled: bool,
+    read_only: AtomicBool,
+    sample: Mutex<Sample>,
+    promotion: tokio::sync::Mutex<()>,
+}
+
+impl Standby {
+    pub fn new(settings: &StandbySettings) -> Self {
+        Self {
+            enabled: settings.enabled,
+            read_only: AtomicBool::new(settings.enabled),
+            sample: Mutex::new(Sample::default()),
+            promotion: tokio::sync::Mutex::new(()),
+        }
+    }
+
+    /// Whether writes must be rejected: a standby that has not been promoted yet
+    pub fn is_read_only(&self) -> bool {
+        self.read_only.load(Ordering::Acquire)
+    }
+
+    pub fn status(&self) -> StandbyStatus {
+        let sample = self.sample.lock().unwrap_or_else(|e| e.into_inner());
+        let read_only = self.is_read_only();
+        StandbyStatus {
+            role: if read_only { "standby" } else { "primary" }.to_string(),
+            read_only,
+            promoted_at: sample.promoted_at,
+            replication: sample.replication.clone(),
+            primary_reachable: sample.primary_reachable,
+        }
+    }
+
+    fn record(&self, replication: Option<ReplicationStatus>, primary_reachable: Option<bool>) {
+        let mut sample = self.sample.lock().unwrap_or_else(|e| e.into_inner());
+        sample.replication = replication;
+        sample.primary_reachable = primary_reachable;
+    }
+
+    /// Make this standby the writable primary.
+    ///
+    /// Unless `force` is set, refuses while the primary still accepts connections (two writable
+    /// registries would diverge) or while the replication lag is unknown or above the limit
+    /// (recent pushes would be lost).
+    pub async fn promote(&self, pool: &PgPool, settings: &StandbySettings, force: bool) -> Result<ReplicationStatus, PromotionError> {
+        // One promotion at a time; a second request waits and then sees AlreadyPromoted
+        let _guard = self.promotion.lock().await;
+        if !self.enabled {
+            return Err(PromotionError::NotStandby);
+        }
+        if !self.is_read_only() {
+            return Err(PromotionError::AlreadyPromoted);
+        }
+
+        let status = replication_status(pool, settings.subscription_name.as_deref()).await?;
+
+        if !force {
+            if let Some(primary_url) = &settings.primary_url {
+                if primary_reachable(primary_url).await {
+                    return Err(PromotionError::Unsafe(format!(
+                        "the primary at {} still accepts connections; stop it first or force the promotion",
+                        primary_url
+                    )));
+                }
+            }
+            match status.lag_seconds {
+                Some(lag) if lag <= settings.max_promotion_lag_seconds as f64 => {}
+                Some(lag) => {
+                    return Err(PromotionError::Unsafe(format!(
+                        "replication lag is {:.1}s, above the {}s limit",
+                        lag, settings.max_promotion_lag_seconds
+                    )))
+                }
+                None => return Err(PromotionError::Unsafe("replication lag is unknown".to_string())),
+            }
+        }
+
+        match status.mode {
+            ReplicationMode::Physical => {
+                let promoted = sqlx::query_scalar::<_, bool>("SELECT pg_promote(true, 60)")
+                    .fetch_one(pool)
+                    .await
+                    .context("Failed to promote the database replica")?;
+                if !promoted {
+                    return Err(anyhow::anyhow!("the database replica did not finish promoting within 60 seconds").into());
+                }
+            }
+            ReplicationMode::Logical => {
+                // `replication_status` found the subscription, so the name is a real identifier
+                let name = settings.subscription_name.as_deref().unwrap_or_default();
+                sqlx::query(&format!("ALTER SUBSCRIPTION \"{}\" DISABLE", name.replace('"', "\"\"")))
+                    .execute(pool)
+                    .await
+                    .context("Failed to stop the replication subscription")?;
+                sync_sequences(pool).await?;
+            }
+            ReplicationMode::None => {}
+        }
+
+        self.read_only.store(false, Ordering::Release);
+        let mut sample = self.sample.lock().unwrap_or_else(|e| e.into_inner());
+        sample.promoted_at = Some(Utc::now());
+        sample.replication = Some(status.clone());
+        Ok(status)
+    }
+}
+
+/// Sample how the database follows the primary
+pub async fn replication_status(pool: &PgPool, subscription_name: Option<&str>) -> Result<ReplicationStatus> {
+    let in_recovery = sqlx::query_scalar::<_, bool>("SELECT pg_is_in_recovery()")
+        .fetch_one(pool)
+        .await
+        .context("Failed to query recovery state")?;
+
+    if in_recovery {
+        // An idle primary sends no transactions, so a replica that has replayed everything
+        // it received is caught up however old its last transaction is
+        let (last_applied_at, lag_seconds, streaming) = sqlx::query_as::<_, (Option<DateTime<Utc>>, Option<f64>, bool)>(
+            "SELECT pg_last_xact_replay_timestamp(),
+                    CASE WHEN pg_last_wal_receive_lsn() = pg_last_wal_replay_lsn() THEN 0
+                         ELSE EXTRACT(EPOCH FROM NOW() - pg_last_xact_replay_timestamp()) END::FLOAT8,
+                    EXISTS(SELECT 1 FROM pg_stat_wal_receiver WHERE status = 'streaming')",
+        )
+        .fetch_one(pool)
+        .await
+        .context("Failed to query streaming replication state")?;
+
+        return Ok(ReplicationStatus {
+            mode: ReplicationMode::Physical,
+            lag_seconds,
+            last_applied_at,
+            streaming,
+            checked_at: Utc::now(),
+        });
+    }
+
+    if let Some(name) = subscription_name {
+        // `latest_end_time` advances with the primary's keepalives even when nothing changes
+        let (last_applied_at, lag_seconds, streaming) = sqlx::query_as::<_, (Option<DateTime<Utc>>, Option<f64>, bool)>(
+            "SELECT latest_end_time,
+                    EXTRACT(EPOCH FROM NOW() - latest_end_time)::FLOAT8,
+                    pid IS NOT NULL
+             FROM pg_stat_subscription
+             WHERE subname = $1 AND relid IS NULL",
+        )
+        .bind(name)
+        .fetch_optional(pool)
+        .await
+        .context("Failed to query logical replication state")?
+        .with_context(|| format!("Replication subscription '{}' does not exist", name))?;
+
+        return Ok(ReplicationStatus {
+            mode: ReplicationMode::Logical,
+            lag_seconds,
+            last_applied_at,
+            streaming,
+            checked_at: Utc::now(),
+        });
+    }
+
+    Ok(ReplicationStatus {
+        mode: ReplicationMode::None,
+        lag_seconds: None,
+        last_applied_at: None,
+        streaming: false,
+        checked_at: Utc::now(),
+    })
+}
+
+/// Whether the primary at `url` accepts TCP connections
+pub async fn primary_reachable(url: &str) -> bool {
+    let address = match url::Url::parse(url) {
+        Ok(url) => match (url.host_str(), url.port_or_known_default()) {
+            (Some(host), Some(port)) => format!("{}:{}", host, port),
+            _ => return false,
+        },
+        Err(_) => return false,
+    };
+
+    matches!(
+        tokio::time::timeout(Duration::from_secs(3), tokio::net::TcpStream::connect(&address)).await,
+        Ok(Ok(_))
+    )
+}
+
+/// Logical replication copies rows but not sequences; move every column-owned sequence past
+/// the highest replicated value so new rows do not collide with the primary's IDs
+async fn sync_sequences(pool: &PgPool) -> Result<()> {
+    sqlx::query(
+        r#"DO $$
+        DECLARE r record;
+        BEGIN
+            FOR r IN
+                SELECT s.oid::regclass::text AS seq, n.nspname AS nsp, t.relname AS tbl, a.attname AS col
+                FROM pg_class s
+                JOIN pg_depend d ON d.objid = s.oid AND d.classid = 'pg_class'::regclass AND d.deptype IN ('a', 'i')
+                JOIN pg_class t ON t.oid = d.refobjid
+                JOIN pg_namespace n ON n.oid = t.relnamespace
+                JOIN pg_attribute a ON a.attrelid = t.oid AND a.attnum = d.refobjsubid
+                WHERE s.relkind = 'S' AND n.nspname = current_schema()
+            LOOP
+                EXECUTE format('SELECT setval(%L, COALESCE((SELECT MAX(%I) FROM %I.%I), 0) + 1, false)',
+                               r.seq, r.col, r.nsp, r.tbl);
+            END LOOP;
+        END $$"#,
+    )
+    .execute(pool)
+    .await
+    .context("Failed to advance sequences past replicated rows")?;
+    Ok(())
+}
+
+/// Sample replication lag and the primary's reachability until the instance is promoted
+pub fn spawn_standby_monitor(state: AppState) {
+    let settings = state.config.standby.clone();
+    if !settings.enabled {
+        return;
+    }
+
+    tracing::info!(
+        "Starting as a read-only standby (subscription: {}, primary: {})",
+        settings.subscription_name.as_deref().unwrap_or("none"),
+        settings.primary_url.as_deref().unwrap_or("not configured")
+    );
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(settings.monitor_interval_seconds));
+        while state.standby.is_read_only() {
+            interval.tick().await;
+
+            let replication = match replication_status(&state.db_pool, settings.subscription_name.as_deref()).await {
+                Ok(status) => {
+                    if !status.streaming {
+                        tracing::warn!("Standby is not receiving changes from the primary");
+                    }
+                    if let Some(lag) = status.lag_seconds.filter(|lag| *lag > settings.max_promotion_lag_seconds as f64) {
+                        tracing::warn!("Standby replication lag is {:.1}s", lag);
+                    }
+                    Some(status)
+                }
+                Err(e) => {
+                    tracing::error!("Failed to sample replication state: {}", e);
+                    None
+                }
+            };
+
+            let reachable = match &settings.primary_url {
+                Some(url) => Some(primary_reachable(url).await),
+                None => None,
+            };
+
+            // A promotion may have finished while sampling; its record wins
+            if state.standby.is_read_only() {
+                state.standby.record(replication, reachable);
+            }
+        }
+    });
+}
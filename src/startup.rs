@@ -0,0 +1,121 @@
+// src/startup.rs - Waiting for dependencies while the server starts
+//
+// In a container deployment the registry often starts before PostgreSQL or the object store
+// accept connections. Rather than exiting and crash-looping, startup retries connecting to each
+// required dependency with exponential backoff: the first retry after
+// `STARTUP_RETRY_INITIAL_DELAY_MS`, each following one after twice the previous delay, up to
+// `STARTUP_RETRY_MAX_DELAY_MS`, for at most `STARTUP_RETRY_ATTEMPTS` attempts. Delays are
+// jittered so replicas starting together do not retry in lockstep.
+use std::future::Future;
+use std::time::Duration;
+
+use anyhow::Result;
+
+use crate::config::settings::StartupSettings;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Backoff {
+    pub attempts: u32,
+    pub initial_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl Backoff {
+    pub fn new(settings: &StartupSettings) -> Self {
+        Self {
+            attempts: settings.retry_attempts,
+            initial_delay: Duration::from_millis(settings.retry_initial_delay_ms),
+            max_delay: Duration::from_millis(settings.retry_max_delay_ms),
+        }
+    }
+
+    /// Delay after the given failed attempt, counting from 1, before jitter
+    pub fn base_delay(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        self.initial_delay.saturating_mul(factor).min(self.max_delay)
+    }
+
+    /// Between half and all of the base delay
+    fn delay(&self, attempt: u32) -> Duration {
+        let base = self.base_delay(attempt);
+        base / 2 + base.mul_f64(rand::random::<f64>() / 2.0)
+    }
+}
+
+/// Run `connect` until it succeeds or the attempts are used up, returning the last error then
+pub async fn retry<T, F, Fut>(dependency: &str, backoff: &Backoff, mut connect: F) -> Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T>>,
+{
+    let mut attempt = 1;
+    loop {
+        match connect().await {
+            Ok(connected) => {
+                if attempt > 1 {
+                    tracing::info!("{} available after {} attempts", dependency, attempt);
+                }
+                return Ok(connected);
+            }
+            Err(e) if attempt < backoff.attempts => {
+                let delay = backoff.delay(attempt);
+                tracing::warn!(
+                    "{} unavailable (attempt {}/{}): {:#}; retrying in {:.1}s",
+                    dependency,
+                    attempt,
+                    backoff.attempts,
+                    e,
+                    delay.as_secs_f64()
+                );
+                tokio::time::sleep(delay).await;
+                attempt += 1;
+            }
+            Err(e) => return Err(e.context(format!("{} unavailable after {} attempts", dependency, attempt))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    fn backoff(attempts: u32) -> Backoff {
+        Backoff { attempts, initial_delay: Duration::from_millis(500), max_delay: Duration::from_secs(4) }
+    }
+
+    #[test]
+    fn delays_double_up_to_the_maximum() {
+        let delays: Vec<_> = (1..=6).map(|attempt| backoff(10).base_delay(attempt).as_millis()).collect();
+        assert_eq!(delays, vec![500, 1000, 2000, 4000, 4000, 4000]);
+        assert_eq!(backoff(10).base_delay(100), Duration::from_secs(4));
+        for attempt in 1..=6 {
+            let delay = backoff(10).delay(attempt);
+            assert!(delay >= backoff(10).base_delay(attempt) / 2 && delay <= backoff(10).base_delay(attempt));
+        }
+    }
+
+    #[tokio::test]
+    async fn retries_until_the_dependency_comes_up_or_attempts_run_out() {
+        let immediate = |attempts| Backoff { attempts, initial_delay: Duration::ZERO, max_delay: Duration::ZERO };
+        let calls = AtomicU32::new(0);
+        let connected = retry("database", &immediate(5), || async {
+            match calls.fetch_add(1, Ordering::SeqCst) {
+                0 | 1 => Err(anyhow::anyhow!("connection refused")),
+                _ => Ok("pool"),
+            }
+        })
+        .await;
+        assert_eq!(connected.unwrap(), "pool");
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+
+        let calls = AtomicU32::new(0);
+        let failed = retry("storage", &immediate(3), || async {
+            calls.fetch_add(1, Ordering::SeqCst);
+            Err::<(), _>(anyhow::anyhow!("connection refused"))
+        })
+        .await;
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+        assert!(format!("{:#}", failed.unwrap_err()).starts_with("storage unavailable after 3 attempts"));
+    }
+}
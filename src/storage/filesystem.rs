@@ -59,6 +59,36 @@ impl Storage for FilesystemStorage {
         Ok(())
     }
 
+    async fn compose_blob(&self, digest: &str, parts: &[String]) -> Result<()> {
+        let path = self.blob_path(digest);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+
+        // The first part becomes the blob and the others are appended to it, under a temporary
+        // name until complete
+        let mut partial = path.clone().into_os_string();
+        partial.push(".partial");
+        let partial = PathBuf::from(partial);
+        let Some((first, rest)) = parts.split_first() else {
+            fs::write(path, b"").await?;
+            return Ok(());
+        };
+        fs::rename(self.blob_path(first), &partial).await?;
+        let mut file = fs::OpenOptions::new().append(true).open(&partial).await?;
+        for part in rest {
+            let mut data = fs::File::open(self.blob_path(part)).await?;
+            tokio::io::copy(&mut data, &mut file).await?;
+        }
+        file.sync_all().await?;
+        fs::rename(&partial, path).await?;
+
+        for part in rest {
+            fs::remove_file(self.blob_path(part)).await?;
+        }
+        Ok(())
+    }
+
     async fn get_blob(&self, digest: &str) -> Result<Option<Bytes>> {
         let path = self.blob_path(digest);
         match fs::read(path).await {
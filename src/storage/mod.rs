@@ -28,6 +28,10 @@ pub trait Storage: Send + Sync + 'static {
         data: Box<dyn AsyncRead + Send + Unpin>,
     ) -> Result<()>;
 
+    /// Create a blob from the concatenation of the blobs at `parts`, in order, and delete the
+    /// parts. The data is joined inside the backend rather than read back through the registry.
+    async fn compose_blob(&self, key: &str, parts: &[String]) -> Result<()>;
+
     /// Get a blob by its key
     async fn get_blob(&self, key: &str) -> Result<Option<Bytes>>;
 
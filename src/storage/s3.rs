@@ -1,4 +1,5 @@
 use super::{BlobMetadata, Storage, StorageConfig};
+use crate::startup::{retry, Backoff};
 use anyhow::{Context, Result};
 use async_trait::async_trait;
 use aws_config::{retry::RetryConfig, Region};
@@ -12,6 +13,11 @@ use tokio::io::AsyncRead;
 use tokio_util::io::ReaderStream;
 use tracing::{error, warn};
 
+/// Smallest part S3 accepts in a multipart upload, except for the last one
+const MIN_PART_SIZE: u64 = 5 * 1024 * 1024;
+/// Largest object or part S3 copies in one request
+const MAX_COPY_SIZE: u64 = 5 * 1024 * 1024 * 1024;
+
 pub struct S3Storage {
     client: S3Client,
     bucket: String,
@@ -71,6 +77,19 @@ pub enum S3AuthMethod {
 }
 
 impl S3Storage {
+    /// Create the client and wait, as configured by `STARTUP_RETRY_*`, until the bucket answers
+    pub async fn connect(config: &S3Config, backoff: &Backoff) -> Result<Self> {
+        retry("Object storage", backoff, || async {
+            let storage = Self::new(config).await?;
+            storage
+                .health_check()
+                .await
+                .with_context(|| format!("Bucket {} is not reachable", config.bucket))?;
+            Ok::<_, anyhow::Error>(storage)
+        })
+        .await
+    }
+
     pub async fn new(config: &S3Config) -> Result<Self> {
         let region = Region::new(config.region.clone());
 
@@ -136,6 +155,140 @@ impl S3Storage {
         }
     }
 
+    async fn part_size_of(&self, key: &str) -> Result<u64> {
+        match self.get_blob_metadata(key).await? {
+            Some(metadata) => Ok(metadata.size),
+            None => Err(anyhow::anyhow!("Blob {} to compose from does not exist", key)),
+        }
+    }
+
+    /// Bytes `start..end` of a blob
+    async fn get_range(&self, key: &str, start: u64, end: u64) -> Result<Vec<u8>> {
+        let response = self
+            .client
+            .get_object()
+            .bucket(&self.bucket)
+            .key(self.make_key(key))
+            .range(format!("bytes={}-{}", start, end - 1))
+            .send()
+            .await
+            .context("Failed to read blob range")?;
+        Ok(response.body.collect().await?.into_bytes().to_vec())
+    }
+
+    /// Join `parts` into `key` with a multipart upload. Parts of at least the minimum part size
+    /// are copied inside S3; smaller ones, which S3 only accepts as the last part, are read and
+    /// sent together with what follows them until a part is large enough, so at most twice the
+    /// minimum part size is held in memory.
+    async fn compose_multipart(&self, key: &str, upload_id: &str, parts: &[String]) -> Result<()> {
+        let mut completed = Vec::new();
+        let mut pending: Vec<u8> = Vec::new();
+
+        for (index, part) in parts.iter().enumerate() {
+            let size = self.part_size_of(part).await?;
+            let last = index + 1 == parts.len();
+            let mut offset = 0;
+
+            if !pending.is_empty() || (size < MIN_PART_SIZE && !last) {
+                let take = if pending.is_empty() { size } else { (MIN_PART_SIZE - pending.len() as u64).min(size) };
+                if take > 0 {
+                    pending.extend(self.get_range(part, 0, take).await?);
+                }
+                offset = take;
+                // A remainder too small to stand as a part of its own is read as well
+                if size - offset < MIN_PART_SIZE && !last {
+                    if offset < size {
+                        pending.extend(self.get_range(part, offset, size).await?);
+                    }
+                    offset = size;
+                }
+                if pending.len() as u64 >= MIN_PART_SIZE || (last && offset == size) {
+                    let data = std::mem::take(&mut pending);
+                    let part_number = completed.len() as i32 + 1;
+                    completed.push(self.upload_part(key, upload_id, part_number, data).await?);
+                }
+            }
+
+            // Copy the rest in as few pieces as S3 allows, all of a similar size
+            let remaining = size - offset;
+            if remaining > 0 {
+                let pieces = remaining.div_ceil(MAX_COPY_SIZE);
+                let piece_size = remaining.div_ceil(pieces);
+                let mut start = offset;
+                while start < size {
+                    let end = (start + piece_size).min(size);
+                    let part_number = completed.len() as i32 + 1;
+                    let copied = self
+                        .client
+                        .upload_part_copy()
+                        .bucket(&self.bucket)
+                        .key(key)
+                        .upload_id(upload_id)
+                        .part_number(part_number)
+                        .copy_source(format!("{}/{}", self.bucket, self.make_key(part)))
+                        .copy_source_range(format!("bytes={}-{}", start, end - 1))
+                        .send()
+                        .await
+                        .context("Failed to copy part")?;
+                    let e_tag = copied
+                        .copy_part_result
+                        .and_then(|result| result.e_tag)
+                        .context("Copied part has no ETag")?;
+                    completed.push(
+                        aws_sdk_s3::types::CompletedPart::builder()
+                            .e_tag(e_tag)
+                            .part_number(part_number)
+                            .build(),
+                    );
+                    start = end;
+                }
+            }
+        }
+        if !pending.is_empty() {
+            let part_number = completed.len() as i32 + 1;
+            completed.push(self.upload_part(key, upload_id, part_number, pending).await?);
+        }
+
+        self.client
+            .complete_multipart_upload()
+            .bucket(&self.bucket)
+            .key(key)
+            .upload_id(upload_id)
+            .multipart_upload(
+                aws_sdk_s3::types::CompletedMultipartUpload::builder()
+                    .set_parts(Some(completed))
+                    .build(),
+            )
+            .send()
+            .await
+            .context("Failed to complete multipart upload")?;
+        Ok(())
+    }
+
+    async fn upload_part(
+        &self,
+        key: &str,
+        upload_id: &str,
+        part_number: i32,
+        data: Vec<u8>,
+    ) -> Result<aws_sdk_s3::types::CompletedPart> {
+        let uploaded = self
+            .client
+            .upload_part()
+            .bucket(&self.bucket)
+            .key(key)
+            .upload_id(upload_id)
+            .part_number(part_number)
+            .body(ByteStream::from(data))
+            .send()
+            .await
+            .context("Failed to upload part")?;
+        Ok(aws_sdk_s3::types::CompletedPart::builder()
+            .e_tag(uploaded.e_tag.context("Uploaded part has no ETag")?)
+            .part_number(part_number)
+            .build())
+    }
+
     async fn abort_multipart_upload(&self, key: &str, upload_id: &str) -> Result<()> {
         match self
             .client
@@ -292,6 +445,48 @@ impl Storage for S3Storage {
         Ok(())
     }
 
+    async fn compose_blob(&self, key: &str, parts: &[String]) -> Result<()> {
+        let storage_key = self.make_key(key);
+        let single = match parts {
+            [part] => Some((part, self.part_size_of(part).await?)),
+            _ => None,
+        };
+
+        match single {
+            // One part within the copy limit is copied as a whole
+            Some((part, size)) if size <= MAX_COPY_SIZE => {
+                self.client
+                    .copy_object()
+                    .bucket(&self.bucket)
+                    .key(&storage_key)
+                    .copy_source(format!("{}/{}", self.bucket, self.make_key(part)))
+                    .send()
+                    .await
+                    .context("Failed to copy blob")?;
+            }
+            _ => {
+                let multipart = self
+                    .client
+                    .create_multipart_upload()
+                    .bucket(&self.bucket)
+                    .key(&storage_key)
+                    .send()
+                    .await
+                    .context("Failed to initiate multipart upload")?;
+                let upload_id = multipart.upload_id().context("Multipart upload has no id")?;
+                if let Err(e) = self.compose_multipart(&storage_key, upload_id, parts).await {
+                    self.abort_multipart_upload(&storage_key, upload_id).await?;
+                    return Err(e);
+                }
+            }
+        }
+
+        for part in parts {
+            self.delete_blob(part).await?;
+        }
+        Ok(())
+    }
+
     async fn get_blob(&self, key: &str) -> Result<Option<Bytes>> {
         let storage_key = self.make_key(key);
         match self
@@ -0,0 +1,398 @@
+// src/tag_cleanup.rs - Tag cleanup suggestions from pull statistics, tag age and versions
+//
+// A background job periodically looks at every repository's tags and suggests the ones that
+// can go: patch releases superseded by a newer patch of the same minor version, pre-releases
+// of versions that were released, and tags nobody pushed or pulled for `TAG_CLEANUP_STALE_DAYS`.
+// Tags pulled within that window and well-known moving tags such as `latest` are always kept.
+// Nothing is deleted here; owners review the suggestions and may accept them as the
+// repository's retention policy.
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use sqlx::{FromRow, PgPool};
+
+use crate::models::tag_cleanup::{SuggestedRetentionPolicy, TagCleanupCandidate, TagCleanupSuggestions};
+use crate::AppState;
+
+/// Moving tags that are never suggested
+const PROTECTED_TAGS: &[&str] = &["latest", "stable", "main", "master", "release", "edge", "nightly"];
+
+/// A tag with the pull statistics of the manifest it points to
+#[derive(Debug, Clone, FromRow)]
+pub struct TagStats {
+    pub name: String,
+    pub digest: String,
+    pub updated_at: DateTime<Utc>,
+    pub last_pulled_at: Option<DateTime<Utc>>,
+    pub pull_count: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Version {
+    major: u64,
+    minor: u64,
+    patch: u64,
+    pre_release: bool,
+}
+
+/// `1.2.3`, `v1.2.3`, `1.2.3-rc.1` and `1.2.3+build`; anything else is not a version
+fn parse_version(tag: &str) -> Option<Version> {
+    let tag = tag.strip_prefix('v').unwrap_or(tag);
+    let tag = tag.split_once('+').map_or(tag, |(version, _)| version);
+    let (core, pre_release) = match tag.split_once('-') {
+        Some((core, pre)) if !pre.is_empty() => (core, true),
+        Some(_) => return None,
+        None => (tag, false),
+    };
+    let mut parts = core.split('.').map(|part| part.parse::<u64>().ok());
+    let version = Version {
+        major: parts.next()??,
+        minor: parts.next()??,
+        patch: parts.next()??,
+        pre_release,
+    };
+    parts.next().is_none().then_some(version)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CleanupReason {
+    /// A newer patch of the same minor version exists
+    Superseded(String),
+    /// The version it precedes was released
+    PreRelease(String),
+    /// Neither pushed nor pulled within the stale window
+    Stale { days: i64, last_pulled_at: Option<DateTime<Utc>> },
+}
+
+impl std::fmt::Display for CleanupReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CleanupReason::Superseded(by) => write!(f, "superseded by {}", by),
+            CleanupReason::PreRelease(version) => write!(f, "pre-release of released {}", version),
+            CleanupReason::Stale { days, last_pulled_at: Some(at) } => {
+                write!(f, "not pushed in {} days, last pulled {}", days, at.format("%Y-%m-%d"))
+            }
+            CleanupReason::Stale { days, last_pulled_at: None } => {
+                write!(f, "not pushed in {} days and never pulled", days)
+            }
+        }
+    }
+}
+
+/// Tags to suggest for removal, as indexes into `tags` with the reason
+pub fn select_candidates(tags: &[TagStats], now: DateTime<Utc>, stale_days: i64) -> Vec<(usize, CleanupReason)> {
+    let window = chrono::Duration::days(stale_days);
+    let versions: Vec<Option<Version>> = tags.iter().map(|t| parse_version(&t.name)).collect();
+
+    // Newest patch of every released minor version, and every released version
+    let mut newest_patch: HashMap<(u64, u64), (u64, &str)> = HashMap::new();
+    let mut released: HashSet<(u64, u64, u64)> = HashSet::new();
+    for (tag, version) in tags.iter().zip(&versions) {
+        if let Some(v) = version.as_ref().filter(|v| !v.pre_release) {
+            released.insert((v.major, v.minor, v.patch));
+            let entry = newest_patch.entry((v.major, v.minor)).or_insert((v.patch, tag.name.as_str()));
+            if v.patch > entry.0 {
+                *entry = (v.patch, tag.name.as_str());
+            }
+        }
+    }
+
+    let mut candidates = Vec::new();
+    for (index, (tag, version)) in tags.iter().zip(&versions).enumerate() {
+        if PROTECTED_TAGS.contains(&tag.name.as_str()) {
+            continue;
+        }
+        if tag.last_pulled_at.is_some_and(|at| now - at < window) {
+            continue;
+        }
+
+        let reason = match version {
+            Some(v) if !v.pre_release => match newest_patch.get(&(v.major, v.minor)) {
+                Some((patch, newest)) if *patch > v.patch => Some(CleanupReason::Superseded(newest.to_string())),
+                // The newest patch of a minor version stays however old it is
+                _ => None,
+            },
+            Some(v) if released.contains(&(v.major, v.minor, v.patch)) => {
+                Some(CleanupReason::PreRelease(format!("{}.{}.{}", v.major, v.minor, v.patch)))
+            }
+            _ if now - tag.updated_at >= window => Some(CleanupReason::Stale {
+                days: (now - tag.updated_at).num_days(),
+                last_pulled_at: tag.last_pulled_at,
+            }),
+            _ => None,
+        };
+        if let Some(reason) = reason {
+            candidates.push((index, reason));
+        }
+    }
+    candidates
+}
+
+/// Manifests and the content they reference, for estimating freed space
+#[derive(Debug, Default)]
+pub struct ContentGraph {
+    sizes: HashMap<String, i64>,
+    references: HashMap<String, Vec<String>>,
+}
+
+impl ContentGraph {
+    /// `rows` are (digest, size, manifest JSON) of everything stored in a repository
+    pub fn new(rows: Vec<(String, i64, Option<String>)>) -> Self {
+        let mut graph = Self::default();
+        for (digest, size, content) in rows {
+            if let Some(manifest) = content.and_then(|c| serde_json::from_str::<serde_json::Value>(&c).ok()) {
+                let config = manifest.get("config").into_iter();
+                let layers = manifest.get("layers").and_then(|l| l.as_array()).into_iter().flatten();
+                let children = manifest.get("manifests").and_then(|m| m.as_array()).into_iter().flatten();
+                let referenced = config
+                    .chain(layers)
+                    .chain(children)
+                    .filter_map(|d| d.get("digest").and_then(|d| d.as_str()).map(str::to_string))
+                    .collect();
+                graph.references.insert(digest.clone(), referenced);
+            }
+            graph.sizes.insert(digest, size);
+        }
+        graph
+    }
+
+    fn reachable<'a>(&self, roots: impl IntoIterator<Item = &'a str>) -> HashSet<String> {
+        let mut seen = HashSet::new();
+        let mut pending: Vec<String> = roots.into_iter().map(str::to_string).collect();
+        while let Some(digest) = pending.pop() {
+            if let Some(referenced) = self.references.get(&digest) {
+                pending.extend(referenced.iter().filter(|d| !seen.contains(*d)).cloned());
+            }
+            seen.insert(digest);
+        }
+        seen
+    }
+
+    /// Bytes held only by `removed`, not by anything `kept` references
+    pub fn freed_bytes<'a>(
+        &self,
+        removed: impl IntoIterator<Item = &'a str>,
+        kept: impl IntoIterator<Item = &'a str>,
+    ) -> i64 {
+        let kept = self.reachable(kept);
+        self.reachable(removed)
+            .difference(&kept)
+            .map(|digest| self.sizes.get(digest).copied().unwrap_or(0))
+            .sum()
+    }
+}
+
+/// Compute the cleanup suggestions of one repository
+pub async fn analyze_repository(pool: &PgPool, repository_id: i64, repository: &str, stale_days: i64) -> Result<TagCleanupSuggestions> {
+    let tags = sqlx::query_as::<_, TagStats>(
+        "SELECT t.name, m.digest, t.updated_at, m.last_pulled_at, m.pull_count
+         FROM tags t
+         JOIN manifests m ON m.id = t.manifest_id
+         WHERE t.repository_id = $1
+         ORDER BY t.updated_at DESC",
+    )
+    .bind(repository_id)
+    .fetch_all(pool)
+    .await?;
+
+    let rows = sqlx::query_as::<_, (String, i64, Option<String>)>(
+        "SELECT digest, size, content FROM manifests WHERE repository_id = $1",
+    )
+    .bind(repository_id)
+    .fetch_all(pool)
+    .await?;
+    let graph = ContentGraph::new(rows);
+
+    let now = Utc::now();
+    let selected = select_candidates(&tags, now, stale_days);
+    let removed: HashSet<usize> = selected.iter().map(|(index, _)| *index).collect();
+
+    let candidates: Vec<TagCleanupCandidate> = selected
+        .iter()
+        .map(|(index, reason)| {
+            let tag = &tags[*index];
+            let others = tags.iter().enumerate().filter(|(i, _)| i != index).map(|(_, t)| t.digest.as_str());
+            TagCleanupCandidate {
+                tag: tag.name.clone(),
+                digest: tag.digest.clone(),
+                reason: reason.to_string(),
+                updated_at: tag.updated_at,
+                last_pulled_at: tag.last_pulled_at,
+                pull_count: tag.pull_count,
+                reclaimable_bytes: graph.freed_bytes([tag.digest.as_str()], others),
+            }
+        })
+        .collect();
+    let kept_digests = tags
+        .iter()
+        .enumerate()
+        .filter(|(index, _)| !removed.contains(index))
+        .map(|(_, tag)| tag.digest.as_str());
+    let estimated_savings_bytes = graph.freed_bytes(candidates.iter().map(|c| c.digest.as_str()), kept_digests);
+
+    // Keeping as many of the newest tags as the suggestions keep, plus the stale window when
+    // tags were suggested for being unused, approximates the suggestions as a standing policy
+    let kept = tags.len() - removed.len();
+    let suggested_policy = SuggestedRetentionPolicy {
+        tag_retention_keep_last: (!selected.is_empty()).then(|| kept.max(1) as i32),
+        tag_retention_days: selected
+            .iter()
+            .any(|(_, reason)| matches!(reason, CleanupReason::Stale { .. }))
+            .then_some(stale_days as i32),
+    };
+
+    Ok(TagCleanupSuggestions {
+        repository: repository.to_string(),
+        tag_count: tags.len() as i64,
+        candidates,
+        estimated_savings_bytes,
+        suggested_policy,
+        computed_at: now,
+    })
+}
+
+/// Keep the suggestions as the repository's latest analysis
+pub async fn store_suggestions(pool: &PgPool, repository_id: i64, suggestions: &TagCleanupSuggestions) -> Result<()> {
+    sqlx::query(
+        "INSERT INTO tag_cleanup_suggestions (repository_id, suggestions, computed_at)
+         VALUES ($1, $2, $3)
+         ON CONFLICT (repository_id) DO UPDATE
+         SET suggestions = EXCLUDED.suggestions, computed_at = EXCLUDED.computed_at",
+    )
+    .bind(repository_id)
+    .bind(serde_json::to_value(suggestions)?)
+    .bind(suggestions.computed_at)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// The repository's latest stored analysis
+pub async fn load_suggestions(pool: &PgPool, repository_id: i64) -> Result<Option<TagCleanupSuggestions>> {
+    let stored = sqlx::query_scalar::<_, serde_json::Value>(
+        "SELECT suggestions FROM tag_cleanup_suggestions WHERE repository_id = $1",
+    )
+    .bind(repository_id)
+    .fetch_optional(pool)
+    .await?;
+    Ok(stored.map(serde_json::from_value).transpose()?)
+}
+
+async fn run_analysis_pass(state: &AppState) -> Result<usize> {
+    let repositories = sqlx::query_as::<_, (i64, String)>(
+        "SELECT r.id, o.name || '/' || r.name
+         FROM repositories r
+         JOIN organizations o ON o.id = r.organization_id
+         ORDER BY r.id",
+    )
+    .fetch_all(&state.db_pool)
+    .await?;
+
+    let stale_days = state.config.tag_cleanup.stale_days;
+    let mut analyzed = 0;
+    for (repository_id, name) in repositories {
+        let result = async {
+            let suggestions = analyze_repository(&state.db_pool, repository_id, &name, stale_days).await?;
+            store_suggestions(&state.db_pool, repository_id, &suggestions).await
+        }
+        .await;
+        match result {
+            Ok(()) => analyzed += 1,
+            Err(e) => tracing::warn!("Tag cleanup analysis of {} failed: {}", name, e),
+        }
+    }
+    Ok(analyzed)
+}
+
+/// Recompute every repository's cleanup suggestions on `TAG_CLEANUP_ANALYSIS_INTERVAL_SECONDS`
+pub fn spawn_tag_cleanup_analyzer(state: AppState) {
+    let interval_seconds = state.config.tag_cleanup.analysis_interval_seconds;
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(interval_seconds));
+        loop {
+            interval.tick().await;
+            // Stored suggestions are writes; a standby reads the primary's through replication
+            if state.standby.is_read_only() {
+                continue;
+            }
+            match run_analysis_pass(&state).await {
+                Ok(analyzed) => tracing::info!("Tag cleanup suggestions updated for {} repositories", analyzed),
+                Err(e) => tracing::error!("Tag cleanup analysis failed: {}", e),
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tag(name: &str, digest: &str, age_days: i64, pulled_days_ago: Option<i64>) -> TagStats {
+        let now = Utc::now();
+        TagStats {
+            name: name.to_string(),
+            digest: digest.to_string(),
+            updated_at: now - chrono::Duration::days(age_days),
+            last_pulled_at: pulled_days_ago.map(|days| now - chrono::Duration::days(days)),
+            pull_count: pulled_days_ago.map_or(0, |_| 1),
+        }
+    }
+
+    #[test]
+    fn versions_are_parsed_with_prefixes_and_pre_releases() {
+        assert_eq!(parse_version("v1.2.3"), Some(Version { major: 1, minor: 2, patch: 3, pre_release: false }));
+        assert!(parse_version("1.2.3-rc.1").unwrap().pre_release);
+        assert!(parse_version("1.2.3+build.5").is_some());
+        assert_eq!(parse_version("1.2"), None);
+        assert_eq!(parse_version("1.2.3.4"), None);
+        assert_eq!(parse_version("sha-1a2b3c"), None);
+    }
+
+    #[test]
+    fn suggests_superseded_pre_released_and_stale_tags() {
+        let tags = vec![
+            tag("latest", "sha256:a", 400, None),
+            tag("1.4.3", "sha256:a", 5, Some(1)),
+            tag("1.4.2", "sha256:b", 20, None),
+            tag("1.4.1", "sha256:c", 30, Some(2)),
+            tag("1.3.9", "sha256:d", 300, None),
+            tag("1.4.3-rc.1", "sha256:e", 6, None),
+            tag("2.0.0-beta.1", "sha256:f", 3, None),
+            tag("pr-17", "sha256:g", 120, Some(100)),
+            tag("pr-18", "sha256:h", 10, None),
+        ];
+        let reasons: Vec<(String, CleanupReason)> = select_candidates(&tags, Utc::now(), 90)
+            .into_iter()
+            .map(|(index, reason)| (tags[index].name.clone(), reason))
+            .collect();
+
+        let names: Vec<&str> = reasons.iter().map(|(name, _)| name.as_str()).collect();
+        // 1.4.1 was pulled recently, 1.3.9 is the newest of its minor version and
+        // 2.0.0-beta.1 has no release yet
+        assert_eq!(names, vec!["1.4.2", "1.4.3-rc.1", "pr-17"]);
+        assert_eq!(reasons[0].1, CleanupReason::Superseded("1.4.3".to_string()));
+        assert_eq!(reasons[1].1, CleanupReason::PreRelease("1.4.3".to_string()));
+        assert!(matches!(reasons[2].1, CleanupReason::Stale { days: 120, .. }));
+    }
+
+    #[test]
+    fn shared_layers_are_not_counted_as_freed() {
+        let image = |layers: &[&str]| {
+            let layers: Vec<_> = layers.iter().map(|d| serde_json::json!({"digest": d})).collect();
+            Some(serde_json::json!({"config": {"digest": "sha256:cfg"}, "layers": layers}).to_string())
+        };
+        let graph = ContentGraph::new(vec![
+            ("sha256:old".to_string(), 1, image(&["sha256:base", "sha256:app1"])),
+            ("sha256:new".to_string(), 1, image(&["sha256:base", "sha256:app2"])),
+            ("sha256:cfg".to_string(), 10, None),
+            ("sha256:base".to_string(), 1000, None),
+            ("sha256:app1".to_string(), 100, None),
+            ("sha256:app2".to_string(), 200, None),
+        ]);
+
+        assert_eq!(graph.freed_bytes(["sha256:old"], ["sha256:new"]), 101);
+        assert_eq!(graph.freed_bytes(["sha256:old"], ["sha256:old"]), 0);
+    }
+}
@@ -0,0 +1,103 @@
+// src/tls.rs - Native TLS for the TCP listeners
+//
+// Docker clients refuse plain HTTP for anything but `localhost` unless every client lists the
+// registry as insecure, so a registry needs HTTPS. Small deployments can have Aerugo terminate
+// TLS itself with rustls instead of running a reverse proxy: from a PEM certificate and key
+// (`TLS_CERT_PATH`, `TLS_KEY_PATH`), or with certificates obtained and renewed from Let's Encrypt
+// (`TLS_ACME_DOMAINS`). Certificate files are checked every `TLS_RELOAD_INTERVAL_SECONDS` and
+// reloaded when they change, so a renewed certificate is served without a restart; a file that
+// fails to load leaves the previous certificate in place.
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use anyhow::{Context, Result};
+use axum_server::tls_rustls::RustlsConfig;
+use futures::StreamExt;
+use rustls_acme::{caches::DirCache, AcmeConfig};
+
+use crate::config::settings::TlsSettings;
+
+/// How connections on a TCP listener are secured
+#[derive(Clone)]
+pub enum Tls {
+    /// A certificate loaded from files, reloaded when they change
+    Files(RustlsConfig),
+    /// Certificates obtained from Let's Encrypt
+    Acme(rustls_acme::axum::AxumAcceptor),
+}
+
+/// TLS for the TCP listeners, or `None` when it is not configured. Starts the certificate
+/// reloader or the ACME client.
+pub async fn configure(tls: &TlsSettings) -> Result<Option<Tls>> {
+    if !tls.enabled() {
+        return Ok(None);
+    }
+    if let (Some(cert), Some(key)) = (&tls.cert_path, &tls.key_path) {
+        let config = RustlsConfig::from_pem_file(cert, key)
+            .await
+            .with_context(|| format!("Failed to load TLS certificate {} and key {}", cert, key))?;
+        spawn_certificate_reloader(config.clone(), tls);
+        tracing::info!("Serving TLS with certificate {}", cert);
+        return Ok(Some(Tls::Files(config)));
+    }
+    tracing::info!("Serving TLS with ACME certificates for {}", tls.acme_domains.join(", "));
+    Ok(Some(Tls::Acme(acme_acceptor(tls))))
+}
+
+/// Reload the certificate whenever either file changes
+fn spawn_certificate_reloader(config: RustlsConfig, tls: &TlsSettings) {
+    let (Some(cert), Some(key)) = (tls.cert_path.clone(), tls.key_path.clone()) else {
+        return;
+    };
+    let (cert, key) = (PathBuf::from(cert), PathBuf::from(key));
+    let interval = Duration::from_secs(tls.reload_interval_seconds);
+
+    tokio::spawn(async move {
+        let mut loaded = modified(&cert, &key);
+        let mut ticker = tokio::time::interval(interval);
+        ticker.tick().await;
+        loop {
+            ticker.tick().await;
+            let current = modified(&cert, &key);
+            if current == loaded {
+                continue;
+            }
+            // A failed load is retried on the next tick, e.g. when the key was not yet replaced
+            match config.reload_from_pem_file(&cert, &key).await {
+                Ok(()) => {
+                    tracing::info!("Reloaded TLS certificate {}", cert.display());
+                    loaded = current;
+                }
+                Err(e) => tracing::warn!("Keeping the current TLS certificate; failed to load {}: {}", cert.display(), e),
+            }
+        }
+    });
+}
+
+/// Latest modification time of the two files; `None` when either cannot be read
+fn modified(cert: &Path, key: &Path) -> Option<SystemTime> {
+    let cert = std::fs::metadata(cert).and_then(|m| m.modified()).ok()?;
+    let key = std::fs::metadata(key).and_then(|m| m.modified()).ok()?;
+    Some(cert.max(key))
+}
+
+/// Acceptor answering TLS-ALPN-01 challenges and serving the certificates it obtains, which
+/// are renewed in the background
+fn acme_acceptor(tls: &TlsSettings) -> rustls_acme::axum::AxumAcceptor {
+    let mut state = AcmeConfig::new(tls.acme_domains.clone())
+        .contact(tls.acme_email.iter().map(|email| format!("mailto:{}", email)))
+        .cache(DirCache::new(tls.acme_cache_dir.clone()))
+        .directory_lets_encrypt(tls.acme_production)
+        .state();
+    let acceptor = state.axum_acceptor(state.default_rustls_config());
+
+    tokio::spawn(async move {
+        while let Some(event) = state.next().await {
+            match event {
+                Ok(event) => tracing::info!("ACME: {:?}", event),
+                Err(e) => tracing::warn!("ACME certificate request failed: {}", e),
+            }
+        }
+    });
+    acceptor
+}
@@ -0,0 +1,329 @@
+// src/transcode.rs - Background gzip -> zstd layer transcoding
+//
+// For each gzip-layered image manifest the service writes an OCI image manifest whose
+// layers are zstd re-compressions of the originals, with `subject` pointing back at
+// the source manifest. The source is never modified, so existing tags and digests keep
+// serving gzip to old clients; zstd-capable clients find the smaller variant through
+// `GET /v2/<name>/referrers/<digest>?artifactType=application/vnd.aerugo.image.zstd-variant.v1+json`.
+use anyhow::{Context, Result};
+use bytes::Bytes;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::time::Duration;
+
+use crate::handlers::docker_registry_v2::{get_repository_blob, load_manifest_content};
+use crate::AppState;
+
+/// `artifactType` of the zstd variant manifests, for filtering referrers
+pub const ZSTD_VARIANT_ARTIFACT_TYPE: &str = "application/vnd.aerugo.image.zstd-variant.v1+json";
+/// Manifest annotation holding the digest of the gzip manifest a variant was made from
+pub const SOURCE_MANIFEST_ANNOTATION: &str = "io.aerugo.transcode.source-manifest";
+/// Layer annotation holding the digest of the gzip layer a zstd layer was made from
+pub const SOURCE_LAYER_ANNOTATION: &str = "io.aerugo.transcode.source-digest";
+
+const OCI_MANIFEST: &str = "application/vnd.oci.image.manifest.v1+json";
+const OCI_CONFIG: &str = "application/vnd.oci.image.config.v1+json";
+const OCI_LAYER_TAR: &str = "application/vnd.oci.image.layer.v1.tar";
+const OCI_LAYER_GZIP: &str = "application/vnd.oci.image.layer.v1.tar+gzip";
+const OCI_LAYER_ZSTD: &str = "application/vnd.oci.image.layer.v1.tar+zstd";
+const DOCKER_MANIFEST: &str = "application/vnd.docker.distribution.manifest.v2+json";
+const DOCKER_CONFIG: &str = "application/vnd.docker.container.image.v1+json";
+const DOCKER_LAYER_TAR: &str = "application/vnd.docker.image.rootfs.diff.tar";
+const DOCKER_LAYER_GZIP: &str = "application/vnd.docker.image.rootfs.diff.tar.gzip";
+
+/// Start the periodic transcoding pass if `BLOB_TRANSCODE_ENABLED` is set
+pub fn spawn_transcoder(state: AppState) {
+    let settings = state.config.transcode.clone();
+    if !settings.enabled {
+        return;
+    }
+
+    tracing::info!(
+        "Blob transcoding enabled: zstd level {}, every {}s, {} manifests per pass",
+        settings.zstd_level, settings.interval_seconds, settings.batch_size
+    );
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(settings.interval_seconds));
+        loop {
+            interval.tick().await;
+            match run_transcode_pass(&state).await {
+                Ok(0) => {}
+                Ok(count) => tracing::info!("Transcoding pass processed {} manifests", count),
+                Err(e) => tracing::error!("Transcoding pass failed: {}", e),
+            }
+        }
+    });
+}
+
+#[derive(sqlx::FromRow)]
+struct PendingManifest {
+    id: i64,
+    repository_id: i64,
+    /// `org/repo`
+    full_name: String,
+    /// Bare repository name, for repositories of the default organization pushed without a namespace
+    short_name: Option<String>,
+    digest: String,
+    media_type: String,
+    size: i64,
+}
+
+enum Outcome {
+    Completed(String),
+    Skipped(&'static str),
+}
+
+/// Transcode the next batch of manifests that have not been looked at yet.
+/// Each manifest is attempted once; its outcome is recorded in `manifest_transcodes`.
+pub async fn run_transcode_pass(state: &AppState) -> Result<usize> {
+    let pending = sqlx::query_as::<_, PendingManifest>(
+        "SELECT m.id, m.repository_id, o.name || '/' || r.name AS full_name,
+                CASE WHEN o.id = 1 THEN r.name END AS short_name,
+                m.digest, m.media_type, m.size
+         FROM manifests m
+         JOIN repositories r ON m.repository_id = r.id
+         JOIN organizations o ON r.organization_id = o.id
+         LEFT JOIN manifest_transcodes mt ON mt.manifest_id = m.id
+         WHERE mt.manifest_id IS NULL
+           AND m.subject_digest IS NULL
+           AND m.media_type IN ($1, $2)
+         ORDER BY m.created_at
+         LIMIT $3",
+    )
+    .bind(DOCKER_MANIFEST)
+    .bind(OCI_MANIFEST)
+    .bind(state.config.transcode.batch_size)
+    .fetch_all(&state.db_pool)
+    .await
+    .context("Failed to list manifests to transcode")?;
+
+    for manifest in &pending {
+        let (status, variant_digest, detail) = match transcode_manifest(state, manifest).await {
+            Ok(Outcome::Completed(digest)) => {
+                tracing::info!("Added zstd variant {} for {}@{}", digest, manifest.full_name, manifest.digest);
+                ("completed", Some(digest), None)
+            }
+            Ok(Outcome::Skipped(reason)) => ("skipped", None, Some(reason.to_string())),
+            Err(e) => {
+                tracing::warn!("Failed to transcode {}@{}: {:#}", manifest.full_name, manifest.digest, e);
+                ("failed", None, Some(format!("{:#}", e)))
+            }
+        };
+
+        sqlx::query(
+            "INSERT INTO manifest_transcodes (manifest_id, status, variant_digest, detail)
+             VALUES ($1, $2, $3, $4)
+             ON CONFLICT (manifest_id) DO NOTHING",
+        )
+        .bind(manifest.id)
+        .bind(status)
+        .bind(variant_digest)
+        .bind(detail)
+        .execute(&state.db_pool)
+        .await?;
+    }
+
+    Ok(pending.len())
+}
+
+async fn transcode_manifest(state: &AppState, manifest: &PendingManifest) -> Result<Outcome> {
+    // Variants are stored under whichever repository name the source was pushed with
+    let (name, content) = load_manifest(state, manifest).await?;
+    let source: Value = serde_json::from_slice(&content).context("Manifest is not valid JSON")?;
+
+    let layers = source
+        .get("layers")
+        .and_then(|l| l.as_array())
+        .context("Manifest has no layers")?;
+    if !layers.iter().any(|l| is_gzip_layer(media_type_of(l))) {
+        return Ok(Outcome::Skipped("no gzip layers"));
+    }
+    if layers.iter().any(|l| l.get("urls").is_some()) {
+        return Ok(Outcome::Skipped("manifest references foreign layers"));
+    }
+
+    let mut variant_layers = Vec::with_capacity(layers.len());
+    let mut bytes_saved = 0i64;
+    for layer in layers {
+        if !is_gzip_layer(media_type_of(layer)) {
+            variant_layers.push(with_oci_media_type(layer));
+            continue;
+        }
+
+        let digest = layer.get("digest").and_then(|d| d.as_str()).context("Layer has no digest")?;
+        let size = layer.get("size").and_then(|s| s.as_i64()).unwrap_or(0);
+
+        match transcode_layer(state, manifest.repository_id, &name, digest, size).await? {
+            Some((zstd_digest, zstd_size)) => {
+                bytes_saved += size - zstd_size;
+                let mut zstd_layer = layer.clone();
+                zstd_layer["mediaType"] = json!(OCI_LAYER_ZSTD);
+                zstd_layer["digest"] = json!(zstd_digest);
+                zstd_layer["size"] = json!(zstd_size);
+                let mut annotations = annotations_of(layer);
+                annotations[SOURCE_LAYER_ANNOTATION] = json!(digest);
+                zstd_layer["annotations"] = annotations;
+                variant_layers.push(zstd_layer);
+            }
+            // zstd was not smaller; the variant keeps the gzip layer
+            None => variant_layers.push(with_oci_media_type(layer)),
+        }
+    }
+
+    if bytes_saved <= 0 {
+        return Ok(Outcome::Skipped("zstd did not reduce any layer"));
+    }
+
+    let mut config = source.get("config").cloned().context("Manifest has no config")?;
+    if media_type_of(&config) == DOCKER_CONFIG {
+        config["mediaType"] = json!(OCI_CONFIG);
+    }
+
+    let mut annotations = annotations_of(&source);
+    annotations[SOURCE_MANIFEST_ANNOTATION] = json!(manifest.digest);
+
+    let variant = json!({
+        "schemaVersion": 2,
+        "mediaType": OCI_MANIFEST,
+        "artifactType": ZSTD_VARIANT_ARTIFACT_TYPE,
+        "config": config,
+        "layers": variant_layers,
+        "subject": {
+            "mediaType": manifest.media_type,
+            "digest": manifest.digest,
+            "size": manifest.size,
+        },
+        "annotations": annotations,
+    });
+
+    let body = serde_json::to_string(&variant)?;
+    let variant_digest = format!("sha256:{}", hex::encode(Sha256::digest(body.as_bytes())));
+
+    state
+        .storage
+        .put_blob(&format!("{}/{}", name, variant_digest), Bytes::from(body.clone()))
+        .await
+        .context("Failed to store variant manifest")?;
+
+    sqlx::query(
+        "INSERT INTO manifests (repository_id, digest, media_type, size, content, subject_digest, artifact_type, annotations)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8::jsonb)
+         ON CONFLICT (repository_id, digest) DO NOTHING",
+    )
+    .bind(manifest.repository_id)
+    .bind(&variant_digest)
+    .bind(OCI_MANIFEST)
+    .bind(body.len() as i64)
+    .bind(&body)
+    .bind(&manifest.digest)
+    .bind(ZSTD_VARIANT_ARTIFACT_TYPE)
+    .bind(annotations.to_string())
+    .execute(&state.db_pool)
+    .await?;
+
+    Ok(Outcome::Completed(variant_digest))
+}
+
+/// Re-compress one gzip layer, reusing an earlier conversion of the same blob.
+/// Returns `None` when zstd does not produce a smaller blob.
+async fn transcode_layer(
+    state: &AppState,
+    repository_id: i64,
+    name: &str,
+    source_digest: &str,
+    source_size: i64,
+) -> Result<Option<(String, i64)>> {
+    let existing = sqlx::query_as::<_, (String, i64)>(
+        "SELECT target_digest, target_size FROM blob_transcodes WHERE repository_id = $1 AND source_digest = $2",
+    )
+    .bind(repository_id)
+    .bind(source_digest)
+    .fetch_optional(&state.db_pool)
+    .await?;
+    if existing.is_some() {
+        return Ok(existing);
+    }
+
+    let data = get_repository_blob(state, name, source_digest)
+        .await?
+        .with_context(|| format!("Layer {} not found in storage", source_digest))?;
+
+    let level = state.config.transcode.zstd_level;
+    let compressed = tokio::task::spawn_blocking(move || gzip_to_zstd(&data, level))
+        .await?
+        .with_context(|| format!("Failed to re-compress layer {}", source_digest))?;
+
+    let target_size = compressed.len() as i64;
+    if target_size >= source_size {
+        return Ok(None);
+    }
+    let target_digest = format!("sha256:{}", hex::encode(Sha256::digest(&compressed)));
+
+    state
+        .storage
+        .put_blob(&format!("{}/{}", name, target_digest), Bytes::from(compressed))
+        .await
+        .context("Failed to store zstd layer")?;
+
+    sqlx::query(
+        "INSERT INTO blob_transcodes (repository_id, source_digest, source_size, target_digest, target_size)
+         VALUES ($1, $2, $3, $4, $5)
+         ON CONFLICT (repository_id, source_digest) DO NOTHING",
+    )
+    .bind(repository_id)
+    .bind(source_digest)
+    .bind(source_size)
+    .bind(&target_digest)
+    .bind(target_size)
+    .execute(&state.db_pool)
+    .await?;
+
+    Ok(Some((target_digest, target_size)))
+}
+
+/// Find the manifest bytes and the repository name they are stored under
+async fn load_manifest(state: &AppState, manifest: &PendingManifest) -> Result<(String, Bytes)> {
+    let names = std::iter::once(&manifest.full_name).chain(manifest.short_name.as_ref());
+    for name in names {
+        if let Some(content) = load_manifest_content(state, name, &manifest.digest).await? {
+            return Ok((name.clone(), content));
+        }
+    }
+    anyhow::bail!("Manifest content not found in storage")
+}
+
+fn gzip_to_zstd(data: &[u8], level: i32) -> Result<Vec<u8>> {
+    // The uncompressed tar is unchanged, so the config's diff_ids stay valid
+    let mut decoder = flate2::read::MultiGzDecoder::new(data);
+    let mut encoder = zstd::stream::Encoder::new(Vec::new(), level)?;
+    std::io::copy(&mut decoder, &mut encoder)?;
+    Ok(encoder.finish()?)
+}
+
+fn media_type_of(descriptor: &Value) -> &str {
+    descriptor.get("mediaType").and_then(|m| m.as_str()).unwrap_or_default()
+}
+
+fn annotations_of(value: &Value) -> Value {
+    value
+        .get("annotations")
+        .filter(|a| a.is_object())
+        .cloned()
+        .unwrap_or_else(|| json!({}))
+}
+
+fn is_gzip_layer(media_type: &str) -> bool {
+    media_type == DOCKER_LAYER_GZIP || media_type == OCI_LAYER_GZIP
+}
+
+/// Docker layer media types are not valid inside an OCI manifest
+fn with_oci_media_type(layer: &Value) -> Value {
+    let oci_media_type = match media_type_of(layer) {
+        DOCKER_LAYER_GZIP => OCI_LAYER_GZIP,
+        DOCKER_LAYER_TAR => OCI_LAYER_TAR,
+        _ => return layer.clone(),
+    };
+    let mut layer = layer.clone();
+    layer["mediaType"] = json!(oci_media_type);
+    layer
+}
@@ -0,0 +1,313 @@
+// src/uploads.rs - Chunked blob uploads streamed to storage
+//
+// Each PATCH of an upload session, and the body of the PUT closing it, is streamed to storage as
+// a chunk object of its own while a running SHA-256 is fed with the same bytes, so memory use is
+// bounded by the storage backend's part size however large the chunk. The chunk keys, the bytes
+// received and the hash state are kept on the `blob_uploads` row, so the next chunk may arrive at
+// any replica and the digest of the blob is known the moment the client completes the upload.
+// Completion then has the storage backend join the chunks into the blob in place, see
+// `Storage::compose_blob`, so the blob never travels through the registry a second time.
+use std::io;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+use anyhow::{Context, Result};
+use axum::body::Body;
+use bytes::Bytes;
+use futures::StreamExt;
+use sha2::digest::generic_array::GenericArray;
+use sqlx::PgPool;
+use tokio_util::io::StreamReader;
+
+use crate::storage::Storage;
+
+/// Initial SHA-256 hash value (FIPS 180-4, 5.3.3)
+const SHA256_IV: [u32; 8] = [
+    0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
+];
+
+/// SHA-256 whose state can be saved between requests and resumed on any replica
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sha256State {
+    state: [u32; 8],
+    len: u64,
+    /// Bytes of an incomplete block, always fewer than 64
+    pending: Vec<u8>,
+}
+
+impl Default for Sha256State {
+    fn default() -> Self {
+        Self { state: SHA256_IV, len: 0, pending: Vec::with_capacity(64) }
+    }
+}
+
+impl Sha256State {
+    pub fn update(&mut self, mut data: &[u8]) {
+        self.len += data.len() as u64;
+        if !self.pending.is_empty() {
+            let take = (64 - self.pending.len()).min(data.len());
+            self.pending.extend_from_slice(&data[..take]);
+            data = &data[take..];
+            if self.pending.len() < 64 {
+                return;
+            }
+            compress(&mut self.state, &self.pending);
+            self.pending.clear();
+        }
+        let whole = data.len() - data.len() % 64;
+        compress(&mut self.state, &data[..whole]);
+        self.pending.extend_from_slice(&data[whole..]);
+    }
+
+    /// Bytes hashed so far
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// `sha256:<hex>` of everything hashed so far
+    pub fn digest(&self) -> String {
+        let mut state = self.state;
+        let mut last = self.pending.clone();
+        last.push(0x80);
+        while last.len() % 64 != 56 {
+            last.push(0);
+        }
+        last.extend_from_slice(&(self.len * 8).to_be_bytes());
+        compress(&mut state, &last);
+
+        let digest: Vec<u8> = state.iter().flat_map(|word| word.to_be_bytes()).collect();
+        format!("sha256:{}", hex::encode(digest))
+    }
+
+    /// Hash state, length and pending bytes, as stored in `blob_uploads.sha256_state`
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(40 + self.pending.len());
+        for word in self.state {
+            bytes.extend_from_slice(&word.to_be_bytes());
+        }
+        bytes.extend_from_slice(&self.len.to_be_bytes());
+        bytes.extend_from_slice(&self.pending);
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() < 40 {
+            return None;
+        }
+        let mut state = [0u32; 8];
+        for (word, chunk) in state.iter_mut().zip(bytes[..32].chunks_exact(4)) {
+            *word = u32::from_be_bytes(chunk.try_into().ok()?);
+        }
+        let len = u64::from_be_bytes(bytes[32..40].try_into().ok()?);
+        let pending = bytes[40..].to_vec();
+        if pending.len() as u64 != len % 64 {
+            return None;
+        }
+        Some(Self { state, len, pending })
+    }
+}
+
+/// Compress whole 64-byte blocks into `state`
+fn compress(state: &mut [u32; 8], blocks: &[u8]) {
+    for block in blocks.chunks_exact(64) {
+        sha2::compress256(state, std::slice::from_ref(GenericArray::from_slice(block)));
+    }
+}
+
+/// An unfinished upload session as stored in `blob_uploads`
+#[derive(Debug)]
+pub struct UploadProgress {
+    pub repository_id: i64,
+    pub uploaded_bytes: i64,
+    pub chunk_keys: Vec<String>,
+    pub sha256: Sha256State,
+}
+
+/// Load an upload session of `repository_id` still accepting chunks: unfinished and younger than
+/// `expiry_seconds`. A session started in another repository is not found, so pushing to one
+/// repository does not give access to the uploads of another.
+pub async fn load_progress(
+    pool: &PgPool,
+    repository_id: i64,
+    uuid: &str,
+    expiry_seconds: i64,
+) -> Result<Option<UploadProgress>> {
+    let row = sqlx::query_as::<_, (i64, i64, Vec<String>, Option<Vec<u8>>)>(
+        "SELECT repository_id, uploaded_bytes, chunk_keys, sha256_state
+         FROM blob_uploads
+         WHERE uuid = $1 AND repository_id = $3 AND completed_at IS NULL
+         AND created_at > NOW() - make_interval(secs => $2)",
+    )
+    .bind(uuid)
+    .bind(expiry_seconds as f64)
+    .bind(repository_id)
+    .fetch_optional(pool)
+    .await
+    .context("Failed to load blob upload")?;
+
+    row.map(|(repository_id, uploaded_bytes, chunk_keys, sha256_state)| {
+        let sha256 = match sha256_state {
+            Some(bytes) => Sha256State::from_bytes(&bytes).context("Corrupt upload hash state")?,
+            None => Sha256State::default(),
+        };
+        Ok(UploadProgress { repository_id, uploaded_bytes, chunk_keys, sha256 })
+    })
+    .transpose()
+}
+
+/// Append a stored chunk to an upload session. Returns false, recording nothing, when another
+/// request added a chunk since `progress` was loaded.
+pub async fn record_chunk(
+    pool: &PgPool,
+    uuid: &str,
+    progress: &UploadProgress,
+    chunk_key: &str,
+    sha256: &Sha256State,
+) -> Result<bool> {
+    let updated = sqlx::query(
+        "UPDATE blob_uploads
+         SET uploaded_bytes = $3, chunk_keys = array_append(chunk_keys, $4), sha256_state = $5
+         WHERE uuid = $1 AND repository_id = $6 AND uploaded_bytes = $2 AND completed_at IS NULL",
+    )
+    .bind(uuid)
+    .bind(progress.uploaded_bytes)
+    .bind(sha256.len() as i64)
+    .bind(chunk_key)
+    .bind(sha256.to_bytes())
+    .bind(progress.repository_id)
+    .execute(pool)
+    .await
+    .context("Failed to record upload chunk")?;
+
+    Ok(updated.rows_affected() == 1)
+}
+
+/// Storage key of a new chunk of an upload. Chunks get keys of their own so that a request
+/// losing a race to `record_chunk` cannot overwrite the winner's data.
+pub fn chunk_key(name: &str, uuid: &str) -> String {
+    format!("repositories/{}/uploads/{}/{}", name, uuid, uuid::Uuid::new_v4())
+}
+
+#[derive(Debug)]
+pub enum ChunkError {
+    /// More than the allowed number of bytes arrived
+    TooLarge { limit: u64 },
+    Storage(anyhow::Error),
+}
+
+/// Stream a request body to `key`, feeding `sha256` as it goes. Returns None without storing
+/// anything when the body is empty. A partially written chunk may be left behind on error.
+pub async fn store_chunk(
+    storage: &dyn Storage,
+    key: &str,
+    body: Body,
+    content_length: Option<u64>,
+    sha256: Sha256State,
+    limit: u64,
+) -> Result<Option<Sha256State>, ChunkError> {
+    let mut frames = body.into_data_stream();
+    let first = loop {
+        match frames.next().await {
+            Some(Ok(frame)) if frame.is_empty() => continue,
+            Some(Ok(frame)) => break frame,
+            Some(Err(e)) => return Err(ChunkError::Storage(e.into())),
+            None => return Ok(None),
+        }
+    };
+
+    let initial = sha256.len();
+    let hashed = Arc::new(Mutex::new(sha256));
+    let too_large = Arc::new(AtomicBool::new(false));
+    let (hasher, exceeded) = (hashed.clone(), too_large.clone());
+    let frames = futures::stream::iter([Ok(first)])
+        .chain(frames)
+        .map(move |frame: Result<Bytes, axum::Error>| {
+            let frame = frame.map_err(io::Error::other)?;
+            let mut sha256 = hasher.lock().unwrap_or_else(|e| e.into_inner());
+            if sha256.len() - initial + frame.len() as u64 > limit {
+                exceeded.store(true, Ordering::Relaxed);
+                return Err(io::Error::new(io::ErrorKind::InvalidData, "chunk exceeds the size limit"));
+            }
+            sha256.update(&frame);
+            Ok(frame)
+        });
+
+    // An unknown length makes S3 stream the chunk in multipart parts instead of buffering it
+    let reader = StreamReader::new(Box::pin(frames));
+    let stored = storage
+        .put_blob_streaming(key, content_length.unwrap_or(u64::MAX), Box::new(reader))
+        .await;
+
+    match stored {
+        Ok(()) => Ok(Some(hashed.lock().unwrap_or_else(|e| e.into_inner()).clone())),
+        Err(_) if too_large.load(Ordering::Relaxed) => Err(ChunkError::TooLarge { limit }),
+        Err(e) => Err(ChunkError::Storage(e)),
+    }
+}
+
+/// Close sessions left unfinished for longer than `expiry_seconds`, queueing their chunks for
+/// the blob collector. Returns the number of chunks queued.
+pub async fn close_expired_sessions(pool: &PgPool, expiry_seconds: i64) -> Result<u64> {
+    let queued = sqlx::query(
+        "WITH expired AS (
+             UPDATE blob_uploads SET completed_at = NOW()
+             WHERE completed_at IS NULL AND created_at <= NOW() - make_interval(secs => $1)
+             RETURNING chunk_keys
+         )
+         INSERT INTO blob_gc_queue (storage_key, reason)
+         SELECT unnest(chunk_keys), 'upload_expired' FROM expired",
+    )
+    .bind(expiry_seconds as f64)
+    .execute(pool)
+    .await
+    .context("Failed to close expired blob uploads")?;
+
+    Ok(queued.rows_affected())
+}
+
+/// Delete the chunks of a finished or abandoned upload
+pub async fn delete_chunks(storage: &dyn Storage, chunk_keys: &[String]) {
+    for chunk_key in chunk_keys {
+        if let Err(e) = storage.delete_blob(chunk_key).await {
+            tracing::warn!("Failed to delete upload chunk {}: {}", chunk_key, e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sha2::{Digest, Sha256};
+
+    #[test]
+    fn resumed_hashes_match_one_pass_hashes() {
+        let data: Vec<u8> = (0..1000u32).map(|i| (i * 31 % 251) as u8).collect();
+        for split in [0, 1, 55, 56, 63, 64, 65, 128, 500, 1000] {
+            let mut first = Sha256State::default();
+            first.update(&data[..split]);
+            let mut resumed = Sha256State::from_bytes(&first.to_bytes()).unwrap();
+            resumed.update(&data[split..]);
+
+            assert_eq!(resumed.len(), data.len() as u64);
+            assert_eq!(resumed.digest(), format!("sha256:{}", hex::encode(Sha256::digest(&data))), "split at {}", split);
+        }
+        assert_eq!(
+            Sha256State::default().digest(),
+            "sha256:e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+    }
+
+    #[test]
+    fn inconsistent_saved_states_are_rejected() {
+        let mut sha256 = Sha256State::default();
+        sha256.update(b"abc");
+        let mut bytes = sha256.to_bytes();
+        bytes.push(0);
+        assert!(Sha256State::from_bytes(&bytes).is_none());
+        assert!(Sha256State::from_bytes(&bytes[..20]).is_none());
+    }
+}
@@ -0,0 +1,202 @@
+// src/watches.rs - Email notifications for tags pushed to watched repositories
+//
+// The queuer follows the process's log stream like the other event consumers and, for every tag
+// push, queues a row in `watch_notifications` for each watcher who may still pull the repository,
+// except the pusher. The mailer drains that queue every minute through `EmailService`: users who
+// prefer a digest get theirs at most once a day. Rows are claimed with SKIP LOCKED, so replicas
+// share the queue without emailing a push twice.
+use std::time::Duration;
+
+use anyhow::Result;
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::handlers::docker_auth::check_repository_permission;
+use crate::log_stream::{LogEvent, LogEventKind, LogFilter};
+use crate::models::watch::WatchedPush;
+use crate::AppState;
+
+const SEND_INTERVAL: Duration = Duration::from_secs(60);
+/// Hours between two digests of one user
+const DIGEST_INTERVAL_HOURS: i32 = 24;
+/// Pushes per email
+const MAX_PUSHES_PER_EMAIL: i64 = 100;
+/// Days sent rows are kept
+const SENT_RETENTION_DAYS: i32 = 7;
+
+/// Queue tag pushes for watchers and email them
+pub fn spawn_watch_notifier(state: AppState) {
+    let queuer_state = state.clone();
+    tokio::spawn(async move {
+        let state = queuer_state;
+        let filter = LogFilter { kind: Some(LogEventKind::Audit), action: Some("manifest.push".to_string()), ..Default::default() };
+        let (_, mut events) = state.log_stream.subscribe(&filter, 0);
+        loop {
+            let event = match events.recv().await {
+                Ok(event) => event,
+                Err(RecvError::Lagged(skipped)) => {
+                    tracing::warn!("Watch notifier fell behind, {} events not queued", skipped);
+                    continue;
+                }
+                Err(RecvError::Closed) => break,
+            };
+            if event.kind != LogEventKind::Audit || event.action != "manifest.push" || state.standby.is_read_only() {
+                continue;
+            }
+            if let Err(e) = queue_push(&state, &event).await {
+                tracing::error!("Failed to queue watch notifications: {}", e);
+            }
+        }
+    });
+
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(SEND_INTERVAL);
+        loop {
+            interval.tick().await;
+            if state.standby.is_read_only() {
+                continue;
+            }
+            if let Err(e) = send_due(&state).await {
+                tracing::error!("Failed to send watch notifications: {}", e);
+            }
+        }
+    });
+}
+
+/// The tag and digest of a push by tag; pushes by digest create no tag and are not announced
+fn pushed_tag(event: &LogEvent) -> Option<(&str, &str)> {
+    let (tag, digest) = event.detail.as_deref()?.split_once(" -> ")?;
+    (tag != digest).then_some((tag, digest))
+}
+
+async fn queue_push(state: &AppState, event: &LogEvent) -> Result<()> {
+    let (Some((tag, digest)), Some(repository)) = (pushed_tag(event), event.repository.as_deref()) else {
+        return Ok(());
+    };
+    let (namespace, repo_name) = match repository.split_once('/') {
+        Some((namespace, repo_name)) => (Some(namespace), repo_name),
+        None => (None, repository),
+    };
+
+    let watchers = sqlx::query_as::<_, (i64, i64, String)>(
+        "SELECT w.user_id, r.id, o.name
+         FROM repository_watches w
+         JOIN repositories r ON r.id = w.repository_id
+         JOIN organizations o ON o.id = r.organization_id
+         LEFT JOIN notification_preferences p ON p.user_id = w.user_id
+         WHERE r.name = $2 AND (o.name = $1 OR ($1 IS NULL AND o.id = 1))
+           AND COALESCE(p.push_email, 'immediate') <> 'off'
+           AND w.user_id IS DISTINCT FROM $3",
+    )
+    .bind(namespace)
+    .bind(repo_name)
+    .bind(event.user_id)
+    .fetch_all(&state.db_pool)
+    .await?;
+
+    for (user_id, repository_id, org_name) in watchers {
+        // Watchers who lost access since they started watching hear nothing more
+        if !check_repository_permission(&user_id.to_string(), &org_name, repo_name, "pull", state).await.unwrap_or(false) {
+            continue;
+        }
+        sqlx::query(
+            "INSERT INTO watch_notifications (user_id, repository_id, repository, tag, digest, pushed_by, pushed_at)
+             VALUES ($1, $2, $3, $4, $5, $6, $7)",
+        )
+        .bind(user_id)
+        .bind(repository_id)
+        .bind(repository)
+        .bind(tag)
+        .bind(digest)
+        .bind(event.user_id)
+        .bind(event.timestamp)
+        .execute(&state.db_pool)
+        .await?;
+    }
+    Ok(())
+}
+
+/// Email every user whose queued pushes are due: immediately, or once their digest interval passed
+async fn send_due(state: &AppState) -> Result<()> {
+    let recipients = sqlx::query_as::<_, (i64, String, String, bool)>(
+        "SELECT u.id, u.email, u.username, COALESCE(p.push_email, 'immediate') = 'digest'
+         FROM users u
+         LEFT JOIN notification_preferences p ON p.user_id = u.id
+         WHERE EXISTS (SELECT 1 FROM watch_notifications n WHERE n.user_id = u.id AND n.sent_at IS NULL)
+           AND (COALESCE(p.push_email, 'immediate') = 'immediate'
+                OR (p.push_email = 'digest'
+                    AND (p.last_digest_at IS NULL
+                         OR p.last_digest_at <= CURRENT_TIMESTAMP - make_interval(hours => $1))))",
+    )
+    .bind(DIGEST_INTERVAL_HOURS)
+    .fetch_all(&state.db_pool)
+    .await?;
+
+    for (user_id, email, username, digest) in recipients {
+        if let Err(e) = send_to(state, user_id, &email, &username, digest).await {
+            tracing::error!("Failed to email watched pushes to user {}: {}", user_id, e);
+        }
+    }
+
+    sqlx::query("DELETE FROM watch_notifications WHERE sent_at < CURRENT_TIMESTAMP - make_interval(days => $1)")
+        .bind(SENT_RETENTION_DAYS)
+        .execute(&state.db_pool)
+        .await?;
+    Ok(())
+}
+
+async fn send_to(state: &AppState, user_id: i64, email: &str, username: &str, digest: bool) -> Result<()> {
+    // Claimed before sending and released again if the email fails
+    let mut pushes = sqlx::query_as::<_, WatchedPush>(
+        "UPDATE watch_notifications n SET sent_at = CURRENT_TIMESTAMP
+         WHERE n.id IN (
+             SELECT id FROM watch_notifications
+             WHERE user_id = $1 AND sent_at IS NULL
+             ORDER BY id
+             LIMIT $2
+             FOR UPDATE SKIP LOCKED
+         )
+         RETURNING n.id, n.repository, n.tag, n.digest,
+                   (SELECT username FROM users WHERE id = n.pushed_by) AS pushed_by, n.pushed_at",
+    )
+    .bind(user_id)
+    .bind(MAX_PUSHES_PER_EMAIL)
+    .fetch_all(&state.db_pool)
+    .await?;
+    if pushes.is_empty() {
+        return Ok(());
+    }
+    pushes.sort_by_key(|push| push.id);
+
+    if let Err(e) = state.email_service.send_watched_pushes_email(email, username, &pushes, digest).await {
+        let ids: Vec<i64> = pushes.iter().map(|push| push.id).collect();
+        sqlx::query("UPDATE watch_notifications SET sent_at = NULL WHERE id = ANY($1)")
+            .bind(&ids)
+            .execute(&state.db_pool)
+            .await?;
+        return Err(e);
+    }
+
+    if digest {
+        sqlx::query("UPDATE notification_preferences SET last_digest_at = CURRENT_TIMESTAMP WHERE user_id = $1")
+            .bind(user_id)
+            .execute(&state.db_pool)
+            .await?;
+    }
+    tracing::info!("Emailed {} watched pushes to user {}", pushes.len(), user_id);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_pushes_by_tag_are_announced() {
+        let push = LogEvent::audit("manifest.push", Some(1), Some("acme/web".to_string())).with_detail("v1.2 -> sha256:abc");
+        assert_eq!(pushed_tag(&push), Some(("v1.2", "sha256:abc")));
+
+        let by_digest = LogEvent::audit("manifest.push", Some(1), Some("acme/web".to_string())).with_detail("sha256:abc -> sha256:abc");
+        assert_eq!(pushed_tag(&by_digest), None);
+        assert_eq!(pushed_tag(&LogEvent::audit("manifest.push", Some(1), None)), None);
+    }
+}
@@ -0,0 +1,151 @@
+// src/webhooks/chat.rs - Slack and Discord message bodies for webhooks of those formats
+//
+// Chat webhooks receive a short human-readable line per event instead of the JSON payload:
+// pushes, tag, manifest and repository deletions and scan results have their own templates,
+// every other event falls back to its action name and detail.
+use serde_json::{json, Value};
+
+use crate::log_stream::LogEvent;
+
+/// The format of a webhook that is not the plain JSON payload
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChatFormat {
+    /// A Slack incoming webhook, posted `{"text": ...}` in mrkdwn
+    Slack,
+    /// A Discord webhook, posted `{"content": ...}` in Discord markdown
+    Discord,
+}
+
+impl ChatFormat {
+    /// The chat format named by a webhook's `format`, `None` for `json`
+    pub fn parse(format: &str) -> Option<Self> {
+        match format {
+            "slack" => Some(ChatFormat::Slack),
+            "discord" => Some(ChatFormat::Discord),
+            _ => None,
+        }
+    }
+
+    fn bold(self, text: &str) -> String {
+        match self {
+            ChatFormat::Slack => format!("*{}*", self.escape(text)),
+            ChatFormat::Discord => format!("**{}**", self.escape(text)),
+        }
+    }
+
+    fn code(self, text: &str) -> String {
+        format!("`{}`", self.escape(&text.replace('`', "'")))
+    }
+
+    /// Slack requires `&`, `<` and `>` as entities; Discord must not turn names into mentions
+    fn escape(self, text: &str) -> String {
+        match self {
+            ChatFormat::Slack => text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;"),
+            ChatFormat::Discord => text.replace('@', "@\u{200b}"),
+        }
+    }
+
+    /// The request body posted for an event
+    pub fn body(self, event: &LogEvent, actor: Option<&str>) -> Value {
+        let text = self.message(event, actor);
+        match self {
+            ChatFormat::Slack => json!({ "text": text }),
+            // Mentions are never resolved, whatever the detail contains
+            ChatFormat::Discord => json!({
+                "content": text,
+                "username": "Aerugo",
+                "allowed_mentions": { "parse": [] }
+            }),
+        }
+    }
+
+    /// One line describing an event
+    pub fn message(self, event: &LogEvent, actor: Option<&str>) -> String {
+        let actor = self.bold(actor.unwrap_or("Someone"));
+        let repository = event.repository.as_deref().unwrap_or_default();
+        let detail = event.detail.as_deref().unwrap_or_default();
+        let reference = |separator: char, reference: &str| self.code(&format!("{}{}{}", repository, separator, reference));
+
+        match event.action.as_str() {
+            "manifest.push" => match detail.split_once(" -> ") {
+                Some((tag, digest)) if tag != digest => {
+                    format!("{} pushed {} ({})", actor, reference(':', tag), self.code(short_digest(digest)))
+                }
+                Some((_, digest)) => format!("{} pushed {}", actor, reference('@', digest)),
+                None => format!("{} pushed to {}", actor, self.code(repository)),
+            },
+            "tag.delete" => format!("{} deleted tag {}", actor, reference(':', detail)),
+            "manifest.delete" => format!("{} deleted manifest {}", actor, reference('@', detail)),
+            "repository.delete" => format!("{} deleted repository {}", actor, self.code(repository)),
+            action if action == "scan" || action.starts_with("scan.") => match detail.is_empty() {
+                true => format!("Vulnerability scan of {} finished", self.code(repository)),
+                false => format!("Vulnerability scan of {}: {}", self.code(repository), self.escape(detail)),
+            },
+            action => {
+                let subject = match repository.is_empty() {
+                    true => String::new(),
+                    false => format!(" on {}", self.code(repository)),
+                };
+                match detail.is_empty() {
+                    true => format!("{}: {}{}", actor, self.code(action), subject),
+                    false => format!("{}: {}{} ({})", actor, self.code(action), subject, self.escape(detail)),
+                }
+            }
+        }
+    }
+}
+
+/// `sha256:` and the first 12 hex digits
+pub(crate) fn short_digest(digest: &str) -> &str {
+    let end = digest.find(':').map(|colon| colon + 13).unwrap_or(12);
+    digest.get(..end).unwrap_or(digest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(action: &str, detail: &str) -> LogEvent {
+        LogEvent::audit(action, Some(7), Some("acme/web".to_string())).with_detail(detail)
+    }
+
+    #[test]
+    fn pushes_name_the_tag_and_digest() {
+        let push = event("manifest.push", "latest -> sha256:0123456789abcdef0123");
+        assert_eq!(
+            ChatFormat::Slack.message(&push, Some("alice")),
+            "*alice* pushed `acme/web:latest` (`sha256:0123456789ab`)"
+        );
+        assert_eq!(
+            ChatFormat::Discord.message(&push, Some("alice")),
+            "**alice** pushed `acme/web:latest` (`sha256:0123456789ab`)"
+        );
+
+        let by_digest = event("manifest.push", "sha256:abc -> sha256:abc");
+        assert_eq!(ChatFormat::Slack.message(&by_digest, None), "*Someone* pushed `acme/web@sha256:abc`");
+    }
+
+    #[test]
+    fn deletions_and_scans_have_templates() {
+        assert_eq!(
+            ChatFormat::Slack.message(&event("tag.delete", "latest"), Some("bob")),
+            "*bob* deleted tag `acme/web:latest`"
+        );
+        assert_eq!(
+            ChatFormat::Slack.message(&event("scan.complete", "2 critical, 5 high"), None),
+            "Vulnerability scan of `acme/web`: 2 critical, 5 high"
+        );
+        assert_eq!(
+            ChatFormat::Slack.message(&event("repository.rename", "acme/site"), Some("bob")),
+            "*bob*: `repository.rename` on `acme/web` (acme/site)"
+        );
+    }
+
+    #[test]
+    fn text_is_escaped_for_each_format() {
+        let event = event("repository.update", "<!channel> & @everyone");
+        assert!(ChatFormat::Slack.message(&event, None).ends_with("(&lt;!channel&gt; &amp; @everyone)"));
+        assert!(ChatFormat::Discord.message(&event, None).ends_with("(<!channel> & @\u{200b}everyone)"));
+        assert_eq!(ChatFormat::Discord.body(&event, None)["allowed_mentions"]["parse"], json!([]));
+    }
+}
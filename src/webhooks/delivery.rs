@@ -0,0 +1,507 @@
+// src/webhooks/delivery.rs - Delivery of audit events to organization and repository webhooks
+//
+// The dispatcher follows the process's log stream, so every replica delivers the events it
+// produced itself. Each delivery is a signed JSON POST, retried with exponential backoff on
+// connection errors and 5xx responses; the outcome of the last attempt is kept on the webhook,
+// and every attempt is recorded in `webhook_deliveries` with the payload so it can be resent.
+// Requests only go to addresses `egress` permits and redirects are not followed, so the start of
+// the response kept with each attempt for debugging always comes from the webhook's own host.
+// Slack and Discord webhooks get a message from `chat` in place of the JSON payload, and
+// `cloudevents` webhooks get the payload wrapped in a CloudEvents envelope.
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::{FromRow, PgExecutor, PgPool};
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::cloudevents::{self, CloudEvent};
+use crate::log_stream::{LogEvent, LogEventKind, LogFilter};
+use crate::webhooks::chat::ChatFormat;
+use crate::webhooks::egress::EgressPolicy;
+use crate::AppState;
+
+pub const EVENT_HEADER: &str = "X-Aerugo-Event";
+pub const DELIVERY_HEADER: &str = "X-Aerugo-Delivery";
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+/// Retries after the first attempt; see `retry_delay`
+const MAX_RETRIES: u32 = 5;
+/// Bytes of each response body kept with the attempt
+const RESPONSE_BODY_LIMIT: usize = 2048;
+/// Days recorded attempts are kept
+pub const DELIVERY_RETENTION_DAYS: i32 = 30;
+
+/// Body of one delivery
+#[derive(Debug, Serialize)]
+pub struct WebhookPayload<'a> {
+    /// Unique per delivery, also sent as `X-Aerugo-Delivery`
+    pub delivery_id: String,
+    /// Dotted action name, also sent as `X-Aerugo-Event`
+    pub event: &'a str,
+    pub timestamp: DateTime<Utc>,
+    pub organization: &'a str,
+    /// `namespace/repository` the event concerns
+    pub repository: &'a str,
+    pub user_id: Option<i64>,
+    pub detail: Option<&'a str>,
+}
+
+/// A webhook an event is delivered to
+#[derive(FromRow)]
+pub struct Target {
+    id: i64,
+    url: String,
+    secret: Option<String>,
+    events: Vec<String>,
+    /// `json`, `slack`, `discord` or `cloudevents`
+    format: String,
+    organization: String,
+    /// Registered on a repository rather than its organization
+    repository_scoped: bool,
+}
+
+/// Result of one POST to a webhook
+struct AttemptOutcome {
+    status: Option<u16>,
+    error: Option<String>,
+    /// Connection errors and 5xx responses are worth retrying
+    retry: bool,
+    /// Row recorded in `webhook_deliveries`, absent when recording failed
+    record_id: Option<i64>,
+}
+
+/// Wait before retry `retry` (0-based): 1s, 4s, 16s, 64s, 256s
+fn retry_delay(retry: u32) -> Duration {
+    Duration::from_secs(4_u64.pow(retry))
+}
+
+fn http_client(state: &AppState) -> reqwest::Client {
+    EgressPolicy::new(&state.config.webhooks)
+        .client_builder()
+        .timeout(REQUEST_TIMEOUT)
+        .user_agent(concat!("aerugo-webhooks/", env!("CARGO_PKG_VERSION")))
+        .build()
+        .expect("HTTP client configuration is static")
+}
+
+/// Whether an event passes a webhook's filters: an empty list takes everything, otherwise an
+/// entry matches the action itself and the dotted prefixes before it
+pub fn event_matches(filters: &[String], action: &str) -> bool {
+    filters.is_empty()
+        || filters.iter().any(|filter| {
+            let filter = LogFilter { action: Some(filter.clone()), ..Default::default() };
+            filter.matches(&LogEvent::audit(action, None, None))
+        })
+}
+
+/// Deliver the audit events of repositories to their webhooks and those of their organizations
+pub fn spawn_webhook_dispatcher(state: AppState) {
+    let client = http_client(&state);
+
+    tokio::spawn(async move {
+        let filter = LogFilter { kind: Some(LogEventKind::Audit), ..Default::default() };
+        let (_, mut events) = state.log_stream.subscribe(&filter, 0);
+        loop {
+            let event = match events.recv().await {
+                Ok(event) => event,
+                Err(RecvError::Lagged(skipped)) => {
+                    tracing::warn!("Webhook dispatcher fell behind, {} events not delivered", skipped);
+                    continue;
+                }
+                Err(RecvError::Closed) => break,
+            };
+            if event.kind != LogEventKind::Audit || event.repository.is_none() {
+                continue;
+            }
+            // Delivery outcomes are writes; a standby has no events of its own to deliver
+            if state.standby.is_read_only() {
+                continue;
+            }
+
+            let state = state.clone();
+            let client = client.clone();
+            tokio::spawn(async move {
+                if let Err(e) = dispatch(&state, &client, Arc::new(event)).await {
+                    tracing::error!("Failed to dispatch webhooks: {}", e);
+                }
+            });
+        }
+    });
+}
+
+async fn dispatch(state: &AppState, client: &reqwest::Client, event: Arc<LogEvent>) -> Result<()> {
+    let Some(repository) = event.repository.as_deref() else {
+        return Ok(());
+    };
+    let (namespace, repo_name) = match repository.split_once('/') {
+        Some((namespace, repo_name)) => (Some(namespace), repo_name),
+        None => (None, repository),
+    };
+
+    let targets = sqlx::query_as::<_, Target>(
+        "SELECT w.id, w.url, w.secret, w.events, w.format, o.name AS organization, false AS repository_scoped
+         FROM organization_webhooks w
+         JOIN organizations o ON o.id = w.organization_id
+         WHERE w.active AND (o.name = $1 OR ($1 IS NULL AND o.id = 1))
+         UNION ALL
+         SELECT w.id, w.url, w.secret, w.events, w.format, o.name AS organization, true AS repository_scoped
+         FROM repository_webhooks w
+         JOIN repositories r ON r.id = w.repository_id
+         JOIN organizations o ON o.id = r.organization_id
+         WHERE w.active AND r.name = $2 AND (o.name = $1 OR ($1 IS NULL AND o.id = 1))",
+    )
+    .bind(namespace)
+    .bind(repo_name)
+    .fetch_all(&state.db_pool)
+    .await?;
+
+    deliver_all(state, client, targets, event);
+    Ok(())
+}
+
+/// Active webhooks registered on a repository, read before the repository is deleted: they are
+/// deleted with it, so the dispatcher cannot find them for the deletion event
+pub async fn repository_targets<'e, E: PgExecutor<'e>>(executor: E, repository_id: i64) -> Result<Vec<Target>, sqlx::Error> {
+    sqlx::query_as::<_, Target>(
+        "SELECT w.id, w.url, w.secret, w.events, w.format, o.name AS organization, true AS repository_scoped
+         FROM repository_webhooks w
+         JOIN repositories r ON r.id = w.repository_id
+         JOIN organizations o ON o.id = r.organization_id
+         WHERE w.active AND w.repository_id = $1",
+    )
+    .bind(repository_id)
+    .fetch_all(executor)
+    .await
+}
+
+/// Deliver an event to webhooks looked up beforehand with `repository_targets`
+pub fn deliver_to(state: &AppState, targets: Vec<Target>, event: LogEvent) {
+    if targets.is_empty() || state.standby.is_read_only() {
+        return;
+    }
+    deliver_all(state, &http_client(state), targets, Arc::new(event));
+}
+
+fn deliver_all(state: &AppState, client: &reqwest::Client, targets: Vec<Target>, event: Arc<LogEvent>) {
+    for target in targets.into_iter().filter(|t| event_matches(&t.events, &event.action)) {
+        let state = state.clone();
+        let client = client.clone();
+        let event = event.clone();
+        tokio::spawn(async move {
+            deliver(&state, &client, &target, &event).await;
+        });
+    }
+}
+
+async fn deliver(state: &AppState, client: &reqwest::Client, target: &Target, event: &LogEvent) {
+    let payload = WebhookPayload {
+        delivery_id: uuid::Uuid::new_v4().to_string(),
+        event: &event.action,
+        timestamp: event.timestamp,
+        organization: &target.organization,
+        repository: event.repository.as_deref().unwrap_or_default(),
+        user_id: event.user_id,
+        detail: event.detail.as_deref(),
+    };
+    let body = match ChatFormat::parse(&target.format) {
+        Some(format) => {
+            let actor = match event.user_id {
+                Some(user_id) => username(&state.db_pool, user_id).await,
+                None => None,
+            };
+            serde_json::to_vec(&format.body(event, actor.as_deref()))
+        }
+        None if target.format == "cloudevents" => serde_json::to_vec(&CloudEvent::new(
+            payload.delivery_id.clone(),
+            &event.action,
+            event.timestamp,
+            event.repository.as_deref(),
+            event.organization_id,
+            &payload,
+        )),
+        None => serde_json::to_vec(&payload),
+    };
+    let body = match body {
+        Ok(body) => body,
+        Err(e) => {
+            tracing::error!("Failed to serialize webhook payload: {}", e);
+            return;
+        }
+    };
+
+    let mut retries = 0;
+    let outcome = loop {
+        let outcome = send_attempt(state, client, target, &event.action, &payload.delivery_id, &body, retries as i32 + 1, false).await;
+        if !outcome.retry || retries == MAX_RETRIES {
+            break outcome;
+        }
+        tokio::time::sleep(retry_delay(retries)).await;
+        retries += 1;
+    };
+    let (status, error) = (outcome.status, outcome.error);
+
+    if let Some(error) = &error {
+        tracing::warn!("Webhook {} delivery of {} failed: {}", target.id, event.action, error);
+    }
+    if let Err(e) = record_outcome(&state.db_pool, target, status, error.as_deref()).await {
+        tracing::error!("Failed to record webhook {} delivery: {}", target.id, e);
+    }
+}
+
+/// The name chat messages show for a user; the event goes out without it if the lookup fails
+async fn username(pool: &PgPool, user_id: i64) -> Option<String> {
+    match sqlx::query_scalar::<_, String>("SELECT username FROM users WHERE id = $1").bind(user_id).fetch_optional(pool).await {
+        Ok(username) => username,
+        Err(e) => {
+            tracing::warn!("Failed to look up user {} for a chat webhook message: {}", user_id, e);
+            None
+        }
+    }
+}
+
+/// POST a delivery body once and record the attempt
+#[allow(clippy::too_many_arguments)]
+async fn send_attempt(
+    state: &AppState,
+    client: &reqwest::Client,
+    target: &Target,
+    event: &str,
+    guid: &str,
+    body: &[u8],
+    attempt: i32,
+    redelivery: bool,
+) -> AttemptOutcome {
+    // Signed per attempt so the timestamp stays fresh for receivers checking it
+    let signature = state.webhook_signer.sign(body, target.secret.as_deref());
+    let mut request = client
+        .post(&target.url)
+        .header("Content-Type", content_type(&target.format))
+        .header(EVENT_HEADER, event)
+        .header(DELIVERY_HEADER, guid);
+    for (name, value) in signature.headers() {
+        request = request.header(name, value);
+    }
+
+    let started = Instant::now();
+    let (status, response_body, error, retry) = match EgressPolicy::new(&state.config.webhooks).check_url(&target.url) {
+        Err(e) => (None, None, Some(format!("Refused to deliver: {}", e)), false),
+        Ok(()) => match request.body(body.to_vec()).send().await {
+            Ok(response) => {
+                let status = response.status();
+                let response_body = response_snippet(response).await;
+                match status.is_success() {
+                    true => (Some(status.as_u16()), response_body, None, false),
+                    false => (
+                        Some(status.as_u16()),
+                        response_body,
+                        Some(format!("Receiver responded with {}", status)),
+                        status.is_server_error(),
+                    ),
+                }
+            }
+            Err(e) => (None, None, Some(e.to_string()), true),
+        },
+    };
+    let duration_ms = started.elapsed().as_millis().min(i32::MAX as u128) as i32;
+
+    let record = RecordedAttempt {
+        guid,
+        event,
+        body,
+        attempt,
+        redelivery,
+        status,
+        duration_ms,
+        response_body: response_body.as_deref(),
+        error: error.as_deref(),
+    };
+    let record_id = match record_attempt(&state.db_pool, target, &record).await {
+        Ok(id) => id,
+        Err(e) => {
+            tracing::error!("Failed to record webhook {} delivery attempt: {}", target.id, e);
+            None
+        }
+    };
+    AttemptOutcome { status, error, retry, record_id }
+}
+
+fn content_type(format: &str) -> &'static str {
+    match format {
+        "cloudevents" => cloudevents::CONTENT_TYPE,
+        _ => "application/json",
+    }
+}
+
+/// The start of a response body, read no further than `RESPONSE_BODY_LIMIT`
+async fn response_snippet(mut response: reqwest::Response) -> Option<String> {
+    let mut bytes = Vec::new();
+    while bytes.len() < RESPONSE_BODY_LIMIT {
+        match response.chunk().await {
+            Ok(Some(chunk)) => bytes.extend_from_slice(&chunk),
+            _ => break,
+        }
+    }
+    snippet(bytes)
+}
+
+/// At most `RESPONSE_BODY_LIMIT` bytes of a body as text, invalid UTF-8 replaced
+fn snippet(mut bytes: Vec<u8>) -> Option<String> {
+    if bytes.is_empty() {
+        return None;
+    }
+    bytes.truncate(RESPONSE_BODY_LIMIT);
+    Some(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+struct RecordedAttempt<'a> {
+    guid: &'a str,
+    event: &'a str,
+    body: &'a [u8],
+    attempt: i32,
+    redelivery: bool,
+    status: Option<u16>,
+    duration_ms: i32,
+    response_body: Option<&'a str>,
+    error: Option<&'a str>,
+}
+
+/// Insert an attempt; nothing is recorded for a webhook deleted in the meantime, such as one of a
+/// repository whose deletion is being delivered
+async fn record_attempt(pool: &PgPool, target: &Target, record: &RecordedAttempt<'_>) -> Result<Option<i64>, sqlx::Error> {
+    let (table, column) = match target.repository_scoped {
+        true => ("repository_webhooks", "repository_webhook_id"),
+        false => ("organization_webhooks", "organization_webhook_id"),
+    };
+    let payload: serde_json::Value = serde_json::from_slice(record.body).unwrap_or_default();
+    sqlx::query_scalar::<_, i64>(&format!(
+        "INSERT INTO webhook_deliveries
+             ({}, guid, event, payload, attempt, redelivery, status_code, duration_ms, response_body, error)
+         SELECT w.id, $2::UUID, $3, $4, $5, $6, $7, $8, $9, $10
+         FROM {} w
+         WHERE w.id = $1
+         RETURNING id",
+        column, table
+    ))
+    .bind(target.id)
+    .bind(record.guid)
+    .bind(record.event)
+    .bind(payload)
+    .bind(record.attempt)
+    .bind(record.redelivery)
+    .bind(record.status.map(i32::from))
+    .bind(record.duration_ms)
+    .bind(record.response_body)
+    .bind(record.error)
+    .fetch_optional(pool)
+    .await
+}
+
+/// Send a recorded delivery once more, to the webhook's current URL and with its current secret
+///
+/// Returns the new attempt's ID, or `None` when the webhook has no such delivery.
+pub async fn redeliver(state: &AppState, repository_scoped: bool, webhook_id: i64, id: i64) -> Result<Option<i64>> {
+    let target = sqlx::query_as::<_, Target>(match repository_scoped {
+        true => {
+            "SELECT w.id, w.url, w.secret, w.events, w.format, o.name AS organization, true AS repository_scoped
+             FROM repository_webhooks w
+             JOIN repositories r ON r.id = w.repository_id
+             JOIN organizations o ON o.id = r.organization_id
+             WHERE w.id = $1"
+        }
+        false => {
+            "SELECT w.id, w.url, w.secret, w.events, w.format, o.name AS organization, false AS repository_scoped
+             FROM organization_webhooks w
+             JOIN organizations o ON o.id = w.organization_id
+             WHERE w.id = $1"
+        }
+    })
+    .bind(webhook_id)
+    .fetch_optional(&state.db_pool)
+    .await?;
+    let Some(target) = target else {
+        return Ok(None);
+    };
+
+    let column = if repository_scoped { "repository_webhook_id" } else { "organization_webhook_id" };
+    let delivery = sqlx::query_as::<_, (String, String, serde_json::Value)>(&format!(
+        "SELECT guid::TEXT, event, payload FROM webhook_deliveries WHERE id = $1 AND {} = $2",
+        column
+    ))
+    .bind(id)
+    .bind(webhook_id)
+    .fetch_optional(&state.db_pool)
+    .await?;
+    let Some((guid, event, payload)) = delivery else {
+        return Ok(None);
+    };
+
+    let body = serde_json::to_vec(&payload)?;
+    let outcome = send_attempt(state, &http_client(state), &target, &event, &guid, &body, 1, true).await;
+    if let Err(e) = record_outcome(&state.db_pool, &target, outcome.status, outcome.error.as_deref()).await {
+        tracing::error!("Failed to record webhook {} delivery: {}", target.id, e);
+    }
+    match outcome.record_id {
+        Some(id) => Ok(Some(id)),
+        None => anyhow::bail!("The redelivery was sent but could not be recorded"),
+    }
+}
+
+/// Delete recorded attempts older than `DELIVERY_RETENTION_DAYS`
+pub async fn cleanup_old_deliveries(pool: &PgPool) -> Result<i64, sqlx::Error> {
+    let result = sqlx::query("DELETE FROM webhook_deliveries WHERE delivered_at < CURRENT_TIMESTAMP - make_interval(days => $1)")
+        .bind(DELIVERY_RETENTION_DAYS)
+        .execute(pool)
+        .await?;
+
+    tracing::info!("Cleaned up {} expired webhook delivery attempts", result.rows_affected());
+    Ok(result.rows_affected() as i64)
+}
+
+async fn record_outcome(pool: &PgPool, target: &Target, status: Option<u16>, error: Option<&str>) -> Result<(), sqlx::Error> {
+    let table = if target.repository_scoped { "repository_webhooks" } else { "organization_webhooks" };
+    sqlx::query(&format!(
+        "UPDATE {}
+         SET last_delivery_at = CURRENT_TIMESTAMP, last_delivery_status = $2, last_delivery_error = $3
+         WHERE id = $1",
+        table
+    ))
+    .bind(target.id)
+    .bind(status.map(i32::from))
+    .bind(error)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn filters_match_actions_and_dotted_prefixes() {
+        assert!(event_matches(&[], "manifest.push"));
+
+        let filters = vec!["manifest.push".to_string(), "repository".to_string()];
+        assert!(event_matches(&filters, "manifest.push"));
+        assert!(event_matches(&filters, "repository.transfer"));
+        assert!(!event_matches(&filters, "manifest.delete"));
+        assert!(!event_matches(&filters, "repositoryx.create"));
+    }
+
+    #[test]
+    fn retries_back_off_exponentially() {
+        let delays: Vec<u64> = (0..MAX_RETRIES).map(|retry| retry_delay(retry).as_secs()).collect();
+        assert_eq!(delays, vec![1, 4, 16, 64, 256]);
+    }
+
+    #[test]
+    fn response_snippets_are_bounded_and_lossy() {
+        assert_eq!(snippet(Vec::new()), None);
+        assert_eq!(snippet(b"accepted".to_vec()).as_deref(), Some("accepted"));
+        assert_eq!(snippet(vec![b'a'; RESPONSE_BODY_LIMIT * 3]).map(|s| s.len()), Some(RESPONSE_BODY_LIMIT));
+        assert_eq!(snippet(vec![b'o', b'k', 0xff]).as_deref(), Some("ok\u{fffd}"));
+    }
+}
@@ -0,0 +1,154 @@
+// src/webhooks/egress.rs - Keeping webhook and push hook requests away from internal services
+//
+// Webhook and push hook URLs are chosen by organization owners, so requests to them could
+// otherwise reach what only the registry can: cloud metadata endpoints, databases, admin
+// interfaces. Every address a URL's host resolves to is checked when the request is sent, so a
+// name that starts resolving elsewhere after it was registered is caught too. Loopback,
+// link-local, private, shared and unique local addresses are refused unless they are in
+// `WEBHOOK_ALLOWED_NETWORKS`. Redirects are not followed, as they would skip the check.
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+
+use anyhow::{bail, Result};
+use hyper::client::connect::dns::Name;
+use ipnet::IpNet;
+use reqwest::dns::{Addrs, Resolve, Resolving};
+
+use crate::config::settings::WebhookSettings;
+use crate::handlers::ip_access::parse_network;
+
+/// Which addresses outgoing hook requests may connect to
+#[derive(Debug, Clone, Default)]
+pub struct EgressPolicy {
+    allowed: Arc<Vec<IpNet>>,
+}
+
+impl EgressPolicy {
+    pub fn new(settings: &WebhookSettings) -> Self {
+        Self { allowed: Arc::new(settings.allowed_networks.iter().filter_map(|n| parse_network(n)).collect()) }
+    }
+
+    pub fn permits(&self, ip: IpAddr) -> bool {
+        !is_internal(ip) || self.allowed.iter().any(|net| net.contains(&ip))
+    }
+
+    /// Refuse a URL whose host is a literal address that is not permitted; names are checked as
+    /// they are resolved
+    pub fn check_url(&self, url: &str) -> Result<()> {
+        let url = url::Url::parse(url)?;
+        let ip = match url.host() {
+            Some(url::Host::Ipv4(ip)) => IpAddr::V4(ip),
+            Some(url::Host::Ipv6(ip)) => IpAddr::V6(ip),
+            _ => return Ok(()),
+        };
+        if !self.permits(ip) {
+            bail!("{} is an internal address", ip);
+        }
+        Ok(())
+    }
+
+    /// A client that only connects to permitted addresses and does not follow redirects
+    pub fn client_builder(&self) -> reqwest::ClientBuilder {
+        reqwest::Client::builder()
+            .redirect(reqwest::redirect::Policy::none())
+            .dns_resolver(Arc::new(PolicyResolver { policy: self.clone() }))
+    }
+}
+
+/// Resolves through the system resolver, keeping only the addresses the policy permits
+struct PolicyResolver {
+    policy: EgressPolicy,
+}
+
+impl Resolve for PolicyResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let policy = self.policy.clone();
+        Box::pin(async move {
+            let host = name.as_str();
+            let permitted: Vec<SocketAddr> =
+                tokio::net::lookup_host((host, 0)).await?.filter(|addr| policy.permits(addr.ip())).collect();
+            if permitted.is_empty() {
+                return Err(format!("{} resolves only to internal addresses", host).into());
+            }
+            Ok(Box::new(permitted.into_iter()) as Addrs)
+        })
+    }
+}
+
+/// Addresses of the host itself, its private networks and other networks not reachable from
+/// the internet
+fn is_internal(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [first, second, ..] = ip.octets();
+            ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || ip.is_broadcast()
+                || ip.is_multicast()
+                || first == 0
+                // Shared address space of carrier-grade NAT, 100.64.0.0/10
+                || (first == 100 && second & 0xc0 == 64)
+        }
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(mapped) => is_internal(IpAddr::V4(mapped)),
+            None => {
+                let first = ip.segments()[0];
+                ip.is_loopback()
+                    || ip.is_unspecified()
+                    || ip.is_multicast()
+                    // Unique local fc00::/7 and link-local fe80::/10
+                    || first & 0xfe00 == 0xfc00
+                    || first & 0xffc0 == 0xfe80
+            }
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(allowed: &[&str]) -> EgressPolicy {
+        EgressPolicy::new(&WebhookSettings {
+            signing_key: None,
+            allowed_networks: allowed.iter().map(|n| n.to_string()).collect(),
+        })
+    }
+
+    #[test]
+    fn internal_addresses_are_refused_unless_allowed() {
+        let policy = policy(&[]);
+        for internal in [
+            "127.0.0.1", "10.1.2.3", "172.16.0.1", "192.168.1.1", "169.254.169.254", "100.64.0.1", "0.0.0.0",
+            "::1", "fd00::1", "fe80::1", "::ffff:169.254.169.254",
+        ] {
+            assert!(!policy.permits(internal.parse().unwrap()), "{} permitted", internal);
+        }
+        assert!(policy.permits("93.184.216.34".parse().unwrap()));
+        assert!(policy.permits("2606:2800:220:1::1".parse().unwrap()));
+
+        let policy = self::policy(&["10.1.0.0/16", "fd00::1"]);
+        assert!(policy.permits("10.1.2.3".parse().unwrap()));
+        assert!(policy.permits("fd00::1".parse().unwrap()));
+        assert!(!policy.permits("10.2.0.1".parse().unwrap()));
+    }
+
+    #[test]
+    fn literal_addresses_in_urls_are_checked() {
+        let policy = policy(&[]);
+        assert!(policy.check_url("http://169.254.169.254/latest/meta-data/").is_err());
+        assert!(policy.check_url("http://[::1]:8080/hook").is_err());
+        assert!(policy.check_url("https://93.184.216.34/hook").is_ok());
+        assert!(policy.check_url("https://hooks.example.com/aerugo").is_ok());
+    }
+
+    #[tokio::test]
+    async fn names_resolving_to_internal_addresses_are_refused() {
+        let resolver = PolicyResolver { policy: policy(&[]) };
+        assert!(resolver.resolve("localhost".parse().unwrap()).await.is_err());
+
+        let resolver = PolicyResolver { policy: policy(&["127.0.0.0/8", "::1"]) };
+        assert!(resolver.resolve("localhost".parse().unwrap()).await.is_ok());
+    }
+}
@@ -0,0 +1,7 @@
+// src/webhooks/mod.rs - Outgoing webhook support
+pub mod chat;
+pub mod delivery;
+pub mod egress;
+pub mod signing;
+
+pub use signing::{WebhookSignature, WebhookSigner};
@@ -0,0 +1,348 @@
+// src/webhooks/signing.rs - HMAC and Ed25519 signatures for outgoing webhook payloads
+//
+// Every delivery carries `X-Aerugo-Webhook-Timestamp` and is signed over `"{timestamp}.{body}"`:
+// - `X-Aerugo-Signature-Ed25519: keyid=<key_id>,sig=<base64>` with the instance key, always
+// - `X-Aerugo-Signature-256: sha256=<hex>` with the receiver's shared secret, when it has one
+//
+// Receivers verifying the Ed25519 header only need the public keys from
+// `GET /api/v1/webhooks/signing-keys`, so no secret has to be handed to each of them.
+use anyhow::{bail, Context, Result};
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use ed25519_dalek::{Signer, SigningKey};
+use hmac::{Hmac, Mac};
+use secrecy::ExposeSecret;
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+
+use crate::config::settings::{SecretsSettings, WebhookSettings};
+use crate::models::webhook::{WebhookSigningKey, WebhookSigningKeys};
+use crate::secrets::SecretCipher;
+
+pub const TIMESTAMP_HEADER: &str = "X-Aerugo-Webhook-Timestamp";
+pub const HMAC_SIGNATURE_HEADER: &str = "X-Aerugo-Signature-256";
+pub const ED25519_SIGNATURE_HEADER: &str = "X-Aerugo-Signature-Ed25519";
+
+/// How long a retired database key stays published
+const RETIRED_KEY_GRACE_DAYS: i32 = 7;
+
+/// DER prefix of an Ed25519 SubjectPublicKeyInfo (RFC 8410); the raw key follows
+const ED25519_SPKI_PREFIX: [u8; 12] = [0x30, 0x2a, 0x30, 0x05, 0x06, 0x03, 0x2b, 0x65, 0x70, 0x03, 0x21, 0x00];
+
+/// Signatures for one delivery, ready to be sent as headers
+#[derive(Debug, Clone)]
+pub struct WebhookSignature {
+    pub timestamp: i64,
+    pub key_id: String,
+    pub ed25519: String,
+    pub hmac_sha256: Option<String>,
+}
+
+impl WebhookSignature {
+    pub fn headers(&self) -> Vec<(&'static str, String)> {
+        let mut headers = vec![
+            (TIMESTAMP_HEADER, self.timestamp.to_string()),
+            (ED25519_SIGNATURE_HEADER, format!("keyid={},sig={}", self.key_id, self.ed25519)),
+        ];
+        if let Some(hmac) = &self.hmac_sha256 {
+            headers.push((HMAC_SIGNATURE_HEADER, format!("sha256={}", hmac)));
+        }
+        headers
+    }
+}
+
+/// The instance's webhook signing key plus every key receivers may still see
+pub struct WebhookSigner {
+    key_id: String,
+    signing_key: SigningKey,
+    published: Vec<WebhookSigningKey>,
+}
+
+impl WebhookSigner {
+    /// Use `WEBHOOK_SIGNING_KEY` when configured, otherwise the active key stored in the
+    /// database, generating it on first start so every replica signs with the same key. Stored
+    /// keys are encrypted with `SECRETS_ENCRYPTION_KEY`; without it no key is stored and this
+    /// process signs with a key of its own.
+    pub async fn load(pool: &PgPool, settings: &WebhookSettings, secrets: &SecretsSettings) -> Result<Self> {
+        if let Some(seed) = &settings.signing_key {
+            let signing_key = decode_seed(seed.expose_secret())
+                .context("WEBHOOK_SIGNING_KEY must be a base64-encoded 32-byte Ed25519 seed")?;
+            let key_id = key_id_for(&signing_key);
+            let published = vec![public_key_info(&signing_key, None, None)];
+            return Ok(Self { key_id, signing_key, published });
+        }
+
+        let cipher = SecretCipher::from_settings(secrets)?;
+        if let Some(cipher) = &cipher {
+            seal_plaintext_keys(pool, cipher).await?;
+        }
+
+        let mut keys = fetch_published_keys(pool).await?;
+        if !keys.iter().any(|k| k.retired_at.is_none()) {
+            let signing_key = SigningKey::generate(&mut rand::rngs::OsRng);
+            let key_id = key_id_for(&signing_key);
+            let Some(cipher) = &cipher else {
+                tracing::warn!(
+                    "Neither WEBHOOK_SIGNING_KEY nor SECRETS_ENCRYPTION_KEY is set; webhook signing key {} is not \
+                     stored, so other replicas sign with different keys and a restart replaces it",
+                    key_id
+                );
+                let published = vec![public_key_info(&signing_key, None, None)];
+                return Ok(Self { key_id, signing_key, published });
+            };
+
+            let (ciphertext, nonce) = cipher.seal_bytes(&key_aad(&key_id), &signing_key.to_bytes())?;
+            // A concurrent replica may win the race; either way we re-read the single active key
+            sqlx::query(
+                "INSERT INTO webhook_signing_keys (key_id, public_key, private_key_ciphertext, private_key_nonce)
+                 VALUES ($1, $2, $3, $4)
+                 ON CONFLICT DO NOTHING",
+            )
+            .bind(&key_id)
+            .bind(base64::prelude::BASE64_STANDARD.encode(signing_key.verifying_key().as_bytes()))
+            .bind(ciphertext)
+            .bind(nonce)
+            .execute(pool)
+            .await
+            .context("Failed to store webhook signing key")?;
+
+            tracing::info!("Generated webhook signing key {}", key_id);
+            keys = fetch_published_keys(pool).await?;
+        }
+
+        let mut active = None;
+        let mut published = Vec::with_capacity(keys.len());
+        for key in keys {
+            let signing_key = key.open(cipher.as_ref())?;
+            if key.retired_at.is_none() {
+                active = Some(signing_key.clone());
+            }
+            published.push(public_key_info(&signing_key, Some(key.created_at), key.retired_at));
+        }
+
+        let Some(signing_key) = active else {
+            bail!("No active webhook signing key found");
+        };
+        Ok(Self { key_id: key_id_for(&signing_key), signing_key, published })
+    }
+
+    pub fn key_id(&self) -> &str {
+        &self.key_id
+    }
+
+    pub fn published_keys(&self) -> WebhookSigningKeys {
+        WebhookSigningKeys {
+            active_key_id: self.key_id.clone(),
+            keys: self.published.clone(),
+        }
+    }
+
+    /// Sign a payload now; pass the receiver's shared secret to also include the HMAC header
+    pub fn sign(&self, payload: &[u8], hmac_secret: Option<&str>) -> WebhookSignature {
+        self.sign_at(payload, hmac_secret, Utc::now().timestamp())
+    }
+
+    pub fn sign_at(&self, payload: &[u8], hmac_secret: Option<&str>, timestamp: i64) -> WebhookSignature {
+        let content = signed_content(timestamp, payload);
+        let ed25519 = base64::prelude::BASE64_STANDARD.encode(self.signing_key.sign(&content).to_bytes());
+        let hmac_sha256 = hmac_secret.map(|secret| {
+            let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes())
+                .expect("HMAC accepts keys of any length");
+            mac.update(&content);
+            hex::encode(mac.finalize().into_bytes())
+        });
+
+        WebhookSignature {
+            timestamp,
+            key_id: self.key_id.clone(),
+            ed25519,
+            hmac_sha256,
+        }
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct StoredSigningKey {
+    key_id: String,
+    /// Base64 seed of keys stored before they were encrypted
+    private_key: Option<String>,
+    private_key_ciphertext: Option<Vec<u8>>,
+    private_key_nonce: Option<Vec<u8>>,
+    created_at: DateTime<Utc>,
+    retired_at: Option<DateTime<Utc>>,
+}
+
+impl StoredSigningKey {
+    fn open(&self, cipher: Option<&SecretCipher>) -> Result<SigningKey> {
+        let corrupt = || format!("Stored webhook signing key {} is corrupt", self.key_id);
+        match (&self.private_key_ciphertext, &self.private_key_nonce, &self.private_key) {
+            (Some(ciphertext), Some(nonce), _) => {
+                let Some(cipher) = cipher else {
+                    bail!("Webhook signing key {} is encrypted but SECRETS_ENCRYPTION_KEY is not set", self.key_id);
+                };
+                let seed = cipher.open_bytes(&key_aad(&self.key_id), ciphertext, nonce).with_context(corrupt)?;
+                seed_key(seed).with_context(corrupt)
+            }
+            (None, None, Some(private_key)) => {
+                tracing::warn!(
+                    "Webhook signing key {} is stored unencrypted; set SECRETS_ENCRYPTION_KEY to encrypt it",
+                    self.key_id
+                );
+                decode_seed(private_key).with_context(corrupt)
+            }
+            _ => bail!(corrupt()),
+        }
+    }
+}
+
+async fn fetch_published_keys(pool: &PgPool) -> Result<Vec<StoredSigningKey>> {
+    let keys = sqlx::query_as::<_, StoredSigningKey>(
+        "SELECT key_id, private_key, private_key_ciphertext, private_key_nonce, created_at, retired_at
+         FROM webhook_signing_keys
+         WHERE retired_at IS NULL OR retired_at > NOW() - make_interval(days => $1)
+         ORDER BY created_at DESC",
+    )
+    .bind(RETIRED_KEY_GRACE_DAYS)
+    .fetch_all(pool)
+    .await
+    .context("Failed to load webhook signing keys")?;
+    Ok(keys)
+}
+
+/// Encrypt keys stored in plaintext by earlier versions
+async fn seal_plaintext_keys(pool: &PgPool, cipher: &SecretCipher) -> Result<()> {
+    let plaintext: Vec<(String, String)> =
+        sqlx::query_as("SELECT key_id, private_key FROM webhook_signing_keys WHERE private_key IS NOT NULL")
+            .fetch_all(pool)
+            .await
+            .context("Failed to load webhook signing keys")?;
+
+    for (key_id, private_key) in plaintext {
+        let seed = decode_seed(&private_key)
+            .with_context(|| format!("Stored webhook signing key {} is corrupt", key_id))?
+            .to_bytes();
+        let (ciphertext, nonce) = cipher.seal_bytes(&key_aad(&key_id), &seed)?;
+        // Another replica may have sealed it meanwhile; its ciphertext is kept
+        sqlx::query(
+            "UPDATE webhook_signing_keys
+             SET private_key = NULL, private_key_ciphertext = $2, private_key_nonce = $3
+             WHERE key_id = $1 AND private_key IS NOT NULL",
+        )
+        .bind(&key_id)
+        .bind(ciphertext)
+        .bind(nonce)
+        .execute(pool)
+        .await
+        .context("Failed to encrypt webhook signing key")?;
+        tracing::info!("Encrypted stored webhook signing key {}", key_id);
+    }
+    Ok(())
+}
+
+/// Binds an encrypted seed to its key, so ciphertexts cannot be swapped between rows
+fn key_aad(key_id: &str) -> Vec<u8> {
+    format!("aerugo:webhook-signing-key:{}", key_id).into_bytes()
+}
+
+fn signed_content(timestamp: i64, payload: &[u8]) -> Vec<u8> {
+    let mut content = format!("{}.", timestamp).into_bytes();
+    content.extend_from_slice(payload);
+    content
+}
+
+fn decode_seed(encoded: &str) -> Result<SigningKey> {
+    seed_key(base64::prelude::BASE64_STANDARD.decode(encoded.trim())?)
+}
+
+fn seed_key(bytes: Vec<u8>) -> Result<SigningKey> {
+    let seed: [u8; 32] = bytes
+        .try_into()
+        .map_err(|b: Vec<u8>| anyhow::anyhow!("expected 32 bytes, got {}", b.len()))?;
+    Ok(SigningKey::from_bytes(&seed))
+}
+
+/// Short fingerprint of the public key, stable across restarts and replicas
+fn key_id_for(signing_key: &SigningKey) -> String {
+    hex::encode(&Sha256::digest(signing_key.verifying_key().as_bytes())[..8])
+}
+
+fn public_key_info(
+    signing_key: &SigningKey,
+    created_at: Option<DateTime<Utc>>,
+    retired_at: Option<DateTime<Utc>>,
+) -> WebhookSigningKey {
+    let engine = base64::prelude::BASE64_STANDARD;
+    let public_key = signing_key.verifying_key();
+    let mut der = ED25519_SPKI_PREFIX.to_vec();
+    der.extend_from_slice(public_key.as_bytes());
+
+    WebhookSigningKey {
+        key_id: key_id_for(signing_key),
+        algorithm: "ed25519".to_string(),
+        public_key: engine.encode(public_key.as_bytes()),
+        public_key_pem: format!("-----BEGIN PUBLIC KEY-----\n{}\n-----END PUBLIC KEY-----\n", engine.encode(der)),
+        created_at,
+        retired_at,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::{Signature, Verifier};
+    use secrecy::Secret;
+
+    fn signer() -> WebhookSigner {
+        let signing_key = SigningKey::from_bytes(&[3_u8; 32]);
+        let published = vec![public_key_info(&signing_key, None, None)];
+        WebhookSigner { key_id: key_id_for(&signing_key), signing_key, published }
+    }
+
+    #[test]
+    fn signatures_verify_with_the_published_key() {
+        let signer = signer();
+        let signature = signer.sign_at(b"{\"event\":\"push\"}", Some("shared"), 1_700_000_000);
+        let content = b"1700000000.{\"event\":\"push\"}";
+
+        let published = &signer.published_keys().keys[0];
+        assert_eq!(published.key_id, signature.key_id);
+        let public_key: [u8; 32] =
+            base64::prelude::BASE64_STANDARD.decode(&published.public_key).unwrap().try_into().unwrap();
+        let verifying_key = ed25519_dalek::VerifyingKey::from_bytes(&public_key).unwrap();
+        let sig = Signature::from_slice(&base64::prelude::BASE64_STANDARD.decode(&signature.ed25519).unwrap()).unwrap();
+        assert!(verifying_key.verify(content, &sig).is_ok());
+        assert!(verifying_key.verify(b"1700000001.{\"event\":\"push\"}", &sig).is_err());
+
+        let mut mac = Hmac::<Sha256>::new_from_slice(b"shared").unwrap();
+        mac.update(content);
+        assert_eq!(signature.hmac_sha256.as_deref(), Some(hex::encode(mac.finalize().into_bytes()).as_str()));
+        assert!(signer.sign_at(content, None, 0).hmac_sha256.is_none());
+    }
+
+    #[test]
+    fn stored_keys_are_encrypted_and_bound_to_their_id() {
+        let cipher = SecretCipher::from_settings(&SecretsSettings {
+            encryption_key: Some(Secret::new(base64::prelude::BASE64_STANDARD.encode([9_u8; 32]))),
+        })
+        .unwrap()
+        .unwrap();
+        let signing_key = SigningKey::from_bytes(&[3_u8; 32]);
+        let key_id = key_id_for(&signing_key);
+        let (ciphertext, nonce) = cipher.seal_bytes(&key_aad(&key_id), &signing_key.to_bytes()).unwrap();
+        assert_ne!(&ciphertext[..32], &signing_key.to_bytes()[..]);
+
+        let mut stored = StoredSigningKey {
+            key_id,
+            private_key: None,
+            private_key_ciphertext: Some(ciphertext),
+            private_key_nonce: Some(nonce),
+            created_at: Utc::now(),
+            retired_at: None,
+        };
+        assert_eq!(stored.open(Some(&cipher)).unwrap().to_bytes(), signing_key.to_bytes());
+        assert!(stored.open(None).is_err());
+
+        stored.key_id = "0000000000000000".to_string();
+        assert!(stored.open(Some(&cipher)).is_err());
+    }
+}
@@ -0,0 +1,200 @@
+#!/usr/bin/env python3
+"""
+Load-test harness for Aerugo's hot paths with baseline tracking.
+
+Seeds a repository with one image by pushing it through the registry API, then hammers
+manifest resolution, blob streaming and authenticated API calls from concurrent workers and
+reports throughput and latency percentiles per scenario.
+
+    python tests/load_test.py --username alice --api-key ak_... --repo alice/load-test \\
+        --save-baseline tests/load_baseline.json
+    python tests/load_test.py --username alice --api-key ak_... --repo alice/load-test \\
+        --baseline tests/load_baseline.json --max-regression 0.2
+
+With --baseline, exits non-zero when a scenario's p95 latency grows or its throughput drops by
+more than --max-regression compared to the saved run. The API key needs the push scope.
+"""
+
+import argparse
+import hashlib
+import json
+import statistics
+import sys
+import threading
+import time
+from concurrent.futures import ThreadPoolExecutor
+from typing import Callable, Dict, List
+
+import requests
+
+try:
+    from config import SERVER_URL
+except ImportError:
+    SERVER_URL = "http://localhost:8080"
+
+
+def digest_of(data: bytes) -> str:
+    return f"sha256:{hashlib.sha256(data).hexdigest()}"
+
+
+class LoadTest:
+    def __init__(self, args):
+        self.base_url = args.url.rstrip("/")
+        self.repo = args.repo
+        self.tag = args.tag
+        self.session_auth = (args.username, args.api_key)
+        self.api_headers = {"X-API-Key": args.api_key}
+        self.layer = b"\x5a" * args.layer_size
+        self.layer_digest = digest_of(self.layer)
+        self.local = threading.local()
+
+    def session(self) -> requests.Session:
+        # One connection pool per worker, like a real client
+        if not hasattr(self.local, "session"):
+            self.local.session = requests.Session()
+            self.local.session.auth = self.session_auth
+        return self.local.session
+
+    def push_blob(self, data: bytes) -> str:
+        digest = digest_of(data)
+        response = self.session().post(f"{self.base_url}/v2/{self.repo}/blobs/uploads/", timeout=30)
+        response.raise_for_status()
+        uuid = response.headers["Docker-Upload-UUID"]
+        response = self.session().put(
+            f"{self.base_url}/v2/{self.repo}/blobs/uploads/{uuid}?digest={digest}", data=data, timeout=120
+        )
+        response.raise_for_status()
+        return digest
+
+    def seed(self):
+        """Push a single-layer image to the repository under the configured tag"""
+        config = json.dumps({"architecture": "amd64", "os": "linux",
+                             "rootfs": {"type": "layers", "diff_ids": [self.layer_digest]}}).encode()
+        config_digest = self.push_blob(config)
+        self.push_blob(self.layer)
+        manifest = json.dumps({
+            "schemaVersion": 2,
+            "mediaType": "application/vnd.docker.distribution.manifest.v2+json",
+            "config": {"mediaType": "application/vnd.docker.container.image.v1+json",
+                       "size": len(config), "digest": config_digest},
+            "layers": [{"mediaType": "application/vnd.docker.image.rootfs.diff.tar.gzip",
+                        "size": len(self.layer), "digest": self.layer_digest}],
+        }, separators=(",", ":")).encode()
+        response = self.session().put(
+            f"{self.base_url}/v2/{self.repo}/manifests/{self.tag}", data=manifest, timeout=30,
+            headers={"Content-Type": "application/vnd.docker.distribution.manifest.v2+json"},
+        )
+        response.raise_for_status()
+        print(f"Seeded {self.repo}:{self.tag} ({len(self.layer)} byte layer)")
+
+    def scenarios(self) -> Dict[str, Callable[[], requests.Response]]:
+        accept = {"Accept": "application/vnd.docker.distribution.manifest.v2+json"}
+        manifest_url = f"{self.base_url}/v2/{self.repo}/manifests/{self.tag}"
+        return {
+            "manifest_get": lambda: self.session().get(manifest_url, headers=accept, timeout=30),
+            "manifest_head": lambda: self.session().head(manifest_url, headers=accept, timeout=30),
+            "blob_get": lambda: self.session().get(
+                f"{self.base_url}/v2/{self.repo}/blobs/{self.layer_digest}", timeout=120),
+            "auth_me": lambda: requests.get(
+                f"{self.base_url}/api/v1/auth/me", headers=self.api_headers, timeout=30),
+        }
+
+    def run(self, name: str, request: Callable[[], requests.Response], duration: float, concurrency: int) -> Dict:
+        latencies: List[float] = []
+        errors = 0
+        lock = threading.Lock()
+        deadline = time.monotonic() + duration
+
+        def worker():
+            nonlocal errors
+            while time.monotonic() < deadline:
+                started = time.perf_counter()
+                try:
+                    ok = request().status_code < 400
+                except requests.RequestException:
+                    ok = False
+                elapsed = (time.perf_counter() - started) * 1000
+                with lock:
+                    latencies.append(elapsed)
+                    errors += 0 if ok else 1
+
+        with ThreadPoolExecutor(max_workers=concurrency) as pool:
+            for _ in range(concurrency):
+                pool.submit(worker)
+
+        if not latencies:
+            return {"requests": 0, "errors": errors, "rps": 0.0, "p50_ms": 0.0, "p95_ms": 0.0, "p99_ms": 0.0}
+        cuts = statistics.quantiles(latencies, n=100) if len(latencies) > 1 else latencies * 99
+        result = {
+            "requests": len(latencies),
+            "errors": errors,
+            "rps": round(len(latencies) / duration, 1),
+            "p50_ms": round(cuts[49], 2),
+            "p95_ms": round(cuts[94], 2),
+            "p99_ms": round(cuts[98], 2),
+        }
+        print(f"{name:<15} {result['rps']:>9.1f} req/s  p50 {result['p50_ms']:>8.2f} ms  "
+              f"p95 {result['p95_ms']:>8.2f} ms  p99 {result['p99_ms']:>8.2f} ms  errors {errors}")
+        return result
+
+
+def compare(results: Dict, baseline: Dict, max_regression: float) -> List[str]:
+    regressions = []
+    for name, result in results.items():
+        previous = baseline.get(name)
+        if not previous:
+            continue
+        if previous["p95_ms"] > 0 and result["p95_ms"] > previous["p95_ms"] * (1 + max_regression):
+            regressions.append(f"{name}: p95 {previous['p95_ms']} ms -> {result['p95_ms']} ms")
+        if previous["rps"] > 0 and result["rps"] < previous["rps"] * (1 - max_regression):
+            regressions.append(f"{name}: throughput {previous['rps']} -> {result['rps']} req/s")
+    return regressions
+
+
+def main() -> int:
+    parser = argparse.ArgumentParser(description=__doc__, formatter_class=argparse.RawDescriptionHelpFormatter)
+    parser.add_argument("--url", default=SERVER_URL)
+    parser.add_argument("--username", required=True)
+    parser.add_argument("--api-key", required=True)
+    parser.add_argument("--repo", required=True, help="namespace/repository the key may push to")
+    parser.add_argument("--tag", default="load-test")
+    parser.add_argument("--layer-size", type=int, default=4 << 20, help="layer size in bytes")
+    parser.add_argument("--duration", type=float, default=15.0, help="seconds per scenario")
+    parser.add_argument("--concurrency", type=int, default=16)
+    parser.add_argument("--scenario", action="append", help="run only these scenarios")
+    parser.add_argument("--skip-seed", action="store_true", help="reuse an image pushed by an earlier run")
+    parser.add_argument("--save-baseline", metavar="FILE")
+    parser.add_argument("--baseline", metavar="FILE")
+    parser.add_argument("--max-regression", type=float, default=0.2)
+    args = parser.parse_args()
+
+    test = LoadTest(args)
+    if not args.skip_seed:
+        test.seed()
+
+    results = {}
+    for name, request in test.scenarios().items():
+        if args.scenario and name not in args.scenario:
+            continue
+        results[name] = test.run(name, request, args.duration, args.concurrency)
+
+    if args.save_baseline:
+        with open(args.save_baseline, "w") as f:
+            json.dump(results, f, indent=2, sort_keys=True)
+        print(f"Baseline saved to {args.save_baseline}")
+
+    if args.baseline:
+        with open(args.baseline) as f:
+            regressions = compare(results, json.load(f), args.max_regression)
+        if regressions:
+            print("Performance regressions:")
+            for regression in regressions:
+                print(f"  {regression}")
+            return 1
+        print(f"No scenario regressed by more than {args.max_regression:.0%}")
+
+    return 0
+
+
+if __name__ == "__main__":
+    sys.exit(main())
@@ -327,24 +327,39 @@ class AuthTests(BaseTestCase):
             self.logger.error(f"Failed to login user: {login_response.text}")
             raise AssertionError("Could not login user for refresh test")
         
-        old_token = login_response.json()["token"]
-        user.token = old_token
+        login_data = login_response.json()
+        self.verify_json_structure(login_data, ["token", "refresh_token", "expires_in"])
+        user.token = login_data["token"]
+        old_refresh_token = login_data["refresh_token"]
         test_data_manager.track_user(user.__dict__)
         
         # Refresh the token
         self.logger.info("Attempting to refresh token")
         refresh_response = self.make_request("POST", "/auth/refresh", {
-            "token": old_token
+            "refresh_token": old_refresh_token
         })
         
         self.assert_response(refresh_response, 200, "Token refresh failed")
         
         data = refresh_response.json()
-        self.verify_json_structure(data, ["token"])
+        self.verify_json_structure(data, ["token", "refresh_token", "expires_in"])
         new_token = data["token"]
+        assert data["refresh_token"] != old_refresh_token, "Refresh token was not rotated"
         
         self.logger.info("Token refreshed successfully")
         
+        # Replaying the rotated refresh token must fail and revoke the chain
+        self.logger.info("Replaying rotated refresh token")
+        replay_response = self.make_request("POST", "/auth/refresh", {
+            "refresh_token": old_refresh_token
+        })
+        self.assert_response(replay_response, 401, "Rotated refresh token was accepted")
+        
+        chain_response = self.make_request("POST", "/auth/refresh", {
+            "refresh_token": data["refresh_token"]
+        })
+        self.assert_response(chain_response, 401, "Refresh chain was not revoked after reuse")
+        
         # Verify the new token works
         self.logger.info("Verifying new token with protected endpoint")
         verify_response = self.make_request("GET", "/auth/me", token=new_token)
@@ -363,7 +378,7 @@ class AuthTests(BaseTestCase):
         user = TEST_USERS[0]
         
         # Try to logout (this endpoint might not exist yet)
-        response = self.make_request("POST", "/auth/logout", token=user.token)
+        response = self.make_request("POST", "/auth/logout", {"token": user.token}, token=user.token)
         
         if response.status_code == 404:
             self.logger.info("Logout endpoint not implemented - skipping")
@@ -373,6 +388,14 @@ class AuthTests(BaseTestCase):
             # After logout, token should be invalid
             response = self.make_request("GET", "/auth/me", token=user.token)
             self.assert_response(response, 401, "Token should be invalid after logout")
+            
+            # Log back in so later tests sharing this user get a live token
+            login_response = self.make_request("POST", "/auth/login", {
+                "email": user.email,
+                "password": user.password
+            })
+            self.assert_response(login_response, 200, "Re-login after logout failed")
+            user.token = login_response.json()["token"]
             self.logger.info("✅ Logout test passed")
         else:
             self.logger.warning(f"Unexpected logout response: {response.status_code}")
@@ -524,10 +547,10 @@ class AuthTests(BaseTestCase):
         """Test refresh with invalid tokens"""
         self.logger.info("Testing refresh invalid tokens")
         
-        invalid_tokens = ["", "invalid", "eyJhbGciOiJIUzI1NiIsInR5cCI6IkpXVCJ9.invalid"]
+        invalid_tokens = ["", "invalid", "rt_invalid", "eyJhbGciOiJIUzI1NiIsInR5cCI6IkpXVCJ9.invalid"]
         
         for token in invalid_tokens:
-            response = self.make_request("POST", "/auth/refresh", {"token": token})
+            response = self.make_request("POST", "/auth/refresh", {"refresh_token": token})
             self.assert_response(response, 401, f"Invalid refresh token: {token[:20]}")
         
         self.logger.info("✅ Invalid refresh test passed")
@@ -1080,6 +1103,97 @@ class AuthTests(BaseTestCase):
         
         self.logger.info("✅ Invalid email format test passed")
     
+    def test_api_key_usage_analytics(self):
+        """Test that API key requests show up in the usage rollups"""
+        self.logger.info("Testing API key usage analytics")
+        
+        import random
+        import string
+        import time
+        
+        session_id = ''.join(random.choices(string.ascii_lowercase + string.digits, k=8))
+        register_response = self.make_request("POST", "/auth/register", {
+            "username": f'usage_user_{session_id}',
+            "email": f'usage_{session_id}@example.com',
+            "password": 'usagepass123'
+        })
+        self.assert_response(register_response, 201, "Registration failed")
+        token = register_response.json()["token"]
+        
+        key_response = self.make_request("POST", "/auth/api-keys", {"name": "usage-test"}, token=token)
+        self.assert_response(key_response, 201, "API key creation failed")
+        key = key_response.json()
+        
+        for _ in range(3):
+            me_response = self.make_request("GET", "/auth/me", headers={"X-API-Key": key["api_key"]})
+            self.assert_response(me_response, 200, "API key request failed")
+        
+        # Usage is recorded in the background
+        time.sleep(1)
+        
+        usage_response = self.make_request("GET", "/auth/usage", token=token)
+        self.assert_response(usage_response, 200, "Failed to fetch usage")
+        entries = [u for u in usage_response.json()
+                   if u["credential_type"] == "api_key" and u["credential_id"] == str(key["id"])]
+        assert len(entries) == 1, "API key missing from usage summary"
+        assert entries[0]["request_count"] >= 3, f"Expected at least 3 requests, got {entries[0]['request_count']}"
+        assert entries[0]["active"] is True
+        
+        detail_response = self.make_request("GET", f"/auth/usage/api_key/{key['id']}", token=token)
+        self.assert_response(detail_response, 200, "Failed to fetch usage detail")
+        endpoints = [e["endpoint"] for e in detail_response.json()["endpoints"]]
+        assert "GET /api/v1/auth/me" in endpoints, f"Unexpected endpoints: {endpoints}"
+        
+        self.logger.info("✅ API key usage analytics test passed")
+    
+    def test_api_key_scopes_and_expiration(self):
+        """Test that API keys are limited to their scopes and expiry bounds"""
+        self.logger.info("Testing API key scopes and expiration")
+        
+        import random
+        import string
+        
+        session_id = ''.join(random.choices(string.ascii_lowercase + string.digits, k=8))
+        register_response = self.make_request("POST", "/auth/register", {
+            "username": f'scope_user_{session_id}',
+            "email": f'scope_{session_id}@example.com',
+            "password": 'scopepass123'
+        })
+        self.assert_response(register_response, 201, "Registration failed")
+        token = register_response.json()["token"]
+        
+        # Keys are read-only unless asked otherwise
+        key_response = self.make_request("POST", "/auth/api-keys", {"name": "read-only", "expires_in_days": 7}, token=token)
+        self.assert_response(key_response, 201, "API key creation failed")
+        read_key = key_response.json()
+        assert read_key["scopes"] == ["read"], f"Unexpected scopes: {read_key['scopes']}"
+        
+        me_response = self.make_request("GET", "/auth/me", headers={"X-API-Key": read_key["api_key"]})
+        self.assert_response(me_response, 200, "Read-only key should read")
+        
+        org_response = self.make_request("POST", "/organizations", {
+            "name": f"scopeorg{session_id}",
+            "display_name": "Scope Org",
+            "description": "Created with a read-only key"
+        }, headers={"X-API-Key": read_key["api_key"]})
+        self.assert_response(org_response, 403, "Read-only key should not create organizations")
+        
+        mint_response = self.make_request("POST", "/auth/api-keys", {"name": "escalate", "scopes": ["admin"]},
+                                          token=read_key["api_key"])
+        self.assert_response(mint_response, 403, "Read-only key should not create keys")
+        
+        admin_response = self.make_request("POST", "/auth/api-keys", {"name": "admin", "scopes": ["admin"]}, token=token)
+        self.assert_response(admin_response, 201, "Admin key creation failed")
+        assert admin_response.json()["scopes"] == ["admin"]
+        
+        too_long = self.make_request("POST", "/auth/api-keys", {"name": "forever", "expires_in_days": 100000}, token=token)
+        self.assert_response(too_long, 400, "Expiry beyond the maximum should be rejected")
+        
+        no_scopes = self.make_request("POST", "/auth/api-keys", {"name": "empty", "scopes": []}, token=token)
+        self.assert_response(no_scopes, 400, "Empty scopes should be rejected")
+        
+        self.logger.info("✅ API key scopes and expiration test passed")
+    
     def run_all_tests(self):
         """Run all authentication tests"""
         self.logger.info("=== Running Auth Tests ===")
@@ -1095,6 +1209,7 @@ class AuthTests(BaseTestCase):
         self.test_registration_validation()
         self.test_token_refresh()
         self.test_logout()
+        self.test_api_key_usage_analytics()
         
         # Additional edge case tests
         self.test_registration_invalid_email_formats()
@@ -1121,5 +1236,6 @@ class AuthTests(BaseTestCase):
         self.test_forgot_password_mismatch_confirmation()
         self.test_forgot_password_short_password()
         self.test_forgot_password_invalid_email_format()
+        self.test_api_key_scopes_and_expiration()
         
         self.logger.info("✅ All auth tests passed")
@@ -66,8 +66,12 @@ mod tests {
             permission_ttl: Duration::from_secs(300),
             session_ttl: Duration::from_secs(1800),
             max_memory_entries: 10000,
+            manifest_memory_bytes: 64 * 1024 * 1024,
             enable_redis: false,
             enable_memory: true,
+            redis_pool_size: 10,
+            redis_connect_timeout: Duration::from_secs(1),
+            redis_command_timeout: Duration::from_millis(500),
         };
 
         assert_eq!(config.auth_token_ttl, Duration::from_secs(900));
@@ -16,6 +16,7 @@ import gzip
 import time
 import uuid
 import pytest
+from concurrent.futures import ThreadPoolExecutor
 from config import get_docker_registry_auth, SERVER_URL
 
 
@@ -152,6 +153,64 @@ class TestDockerPushWorkflow:
         
         print("✅ Blob upload error scenarios tested!")
 
+    def test_blob_upload_requires_authentication(self):
+        """Test that every step of a blob upload rejects anonymous callers"""
+        print("\n🔒 Testing anonymous blob upload rejection...")
+
+        upload_url = f"{self.base_url}/v2/{self.test_repo}/blobs/uploads/"
+        response = requests.post(upload_url, timeout=10)
+        print(f"   Anonymous upload start: {response.status_code}")
+        assert response.status_code == 401, f"Expected 401, got {response.status_code}"
+        assert "WWW-Authenticate" in response.headers
+
+        upload_uuid, _ = self._start_blob_upload()
+        assert upload_uuid is not None, "Failed to start authenticated upload"
+
+        chunk_url = f"{self.base_url}/v2/{self.test_repo}/blobs/uploads/{upload_uuid}"
+        response = requests.patch(chunk_url, data=b"anonymous chunk", timeout=10)
+        print(f"   Anonymous chunk upload: {response.status_code}")
+        assert response.status_code == 401, f"Expected 401, got {response.status_code}"
+
+        response = requests.delete(chunk_url, timeout=10)
+        print(f"   Anonymous upload cancel: {response.status_code}")
+        assert response.status_code == 401, f"Expected 401, got {response.status_code}"
+
+        print("✅ Anonymous blob uploads rejected!")
+
+    def test_upload_sessions_are_scoped_to_their_repository(self):
+        """Test that an upload session cannot be reached through another repository's URL"""
+        print("\n🔒 Testing upload sessions across repositories...")
+
+        other_repo = f"{self.test_repo}-other"
+        response = requests.post(f"{self.base_url}/v2/{other_repo}/blobs/uploads/", headers=self.auth_headers, timeout=10)
+        assert response.status_code in [201, 202], f"Failed to start upload: {response.status_code} - {response.text}"
+        other_uuid = response.headers.get("Docker-Upload-UUID")
+
+        data = b"Blob uploaded to another repository" * 10
+        digest = f"sha256:{hashlib.sha256(data).hexdigest()}"
+        foreign_url = f"{self.base_url}/v2/{self.test_repo}/blobs/uploads/{other_uuid}"
+        for method, url in [
+            ("GET", foreign_url),
+            ("PATCH", foreign_url),
+            ("PUT", f"{foreign_url}?digest={digest}"),
+            ("DELETE", foreign_url),
+        ]:
+            response = requests.request(method, url, headers=self.auth_headers, data=data, timeout=10)
+            print(f"   {method} through {self.test_repo}: {response.status_code}")
+            assert response.status_code == 404, f"{method} reached another repository's upload: {response.status_code}"
+            assert response.json()["errors"][0]["code"] == "BLOB_UPLOAD_UNKNOWN"
+
+        # The session is untouched and still completes in its own repository only
+        complete_url = f"{self.base_url}/v2/{other_repo}/blobs/uploads/{other_uuid}?digest={digest}"
+        response = requests.put(complete_url, headers=self.auth_headers, data=data, timeout=10)
+        assert response.status_code == 201, f"Upload completion failed: {response.status_code} - {response.text}"
+        response = requests.head(f"{self.base_url}/v2/{other_repo}/blobs/{digest}", headers=self.auth_headers, timeout=10)
+        assert response.status_code == 200
+        response = requests.head(f"{self.base_url}/v2/{self.test_repo}/blobs/{digest}", headers=self.auth_headers, timeout=10)
+        assert response.status_code == 404
+
+        print("✅ Upload sessions scoped to their repository!")
+
     def test_manifest_upload_scenarios(self):
         """Test various manifest upload scenarios"""
         print("\n📋 Testing manifest upload scenarios...")
@@ -199,6 +258,37 @@ class TestDockerPushWorkflow:
         
         print("✅ Manifest upload scenarios tested!")
 
+    def test_concurrent_pushes_to_one_tag(self):
+        """Test that concurrent pushes to one tag all succeed and pulls see the last one"""
+        print("\n🏁 Testing concurrent pushes to one tag...")
+
+        self._push_blob(self.config_data, self.config_digest, "config blob")
+        self._push_blob(self.sample_layer_data, self.layer_digest, "layer blob")
+
+        def variant(i: int) -> bytes:
+            manifest = json.loads(self._create_manifest())
+            manifest["annotations"] = {"test.push": str(i)}
+            return json.dumps(manifest, separators=(',', ':')).encode('utf-8')
+
+        variants = [variant(i) for i in range(8)]
+        with ThreadPoolExecutor(max_workers=len(variants)) as pool:
+            digests = list(pool.map(self._push_manifest, variants))
+
+        # The tag ends up on one of the pushed manifests
+        assert self._pulled_digest() in digests
+
+        # Once the race is over, a pull sees the next push rather than a manifest cached during the race
+        final_digest = self._push_manifest(variant(len(variants)))
+        for _ in range(3):
+            assert self._pulled_digest() == final_digest
+
+        tags_url = f"{self.base_url}/v2/{self.test_repo}/tags/list"
+        response = requests.get(tags_url, headers=self.auth_headers, timeout=10)
+        assert response.status_code == 200
+        assert response.json().get("tags", []).count(self.test_tag) == 1
+
+        print("✅ Concurrent pushes to one tag handled!")
+
     def _push_blob(self, data: bytes, expected_digest: str, blob_type: str) -> str:
         """Push a blob using the complete workflow"""
         print(f"   📤 Pushing {blob_type} ({len(data)} bytes, {expected_digest})")
@@ -310,6 +400,14 @@ class TestDockerPushWorkflow:
         print(f"   ✅ Manifest pushed: {manifest_digest}")
         return manifest_digest
 
+    def _pulled_digest(self) -> str:
+        """Digest of the manifest the test tag currently resolves to"""
+        manifest_url = f"{self.base_url}/v2/{self.test_repo}/manifests/{self.test_tag}"
+        headers = {**self.auth_headers, 'Accept': 'application/vnd.docker.distribution.manifest.v2+json'}
+        response = requests.get(manifest_url, headers=headers, timeout=10)
+        assert response.status_code == 200, f"Manifest pull failed: {response.status_code} - {response.text}"
+        return f"sha256:{hashlib.sha256(response.content).hexdigest()}"
+
     def _verify_image_complete(self):
         """Verify the complete image is available"""
         # Test catalog contains our repo
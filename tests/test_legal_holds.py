@@ -0,0 +1,267 @@
+#!/usr/bin/env python3
+"""
+Legal Hold Test
+Tests placing and releasing legal holds on an organization:
+1. Only registry administrators can place, list and release holds, not the organization's owners
+2. A hold exports the organization's content and lists it in the export manifest
+3. Deletes and pushes moving an existing tag are refused while a blocking hold is active
+4. Releasing the hold allows them again
+
+Holds are an administrator operation: set TEST_ADMIN_USERNAME and TEST_ADMIN_PASSWORD to a user
+listed in the server's ADMIN_USERNAMES (it is registered if it does not exist yet).
+"""
+
+import os
+import requests
+import json
+import hashlib
+import time
+import uuid
+import pytest
+from config import SERVER_URL, API_BASE
+
+
+def register(prefix: str) -> dict:
+    """Register a fresh user and return its bearer auth headers"""
+    name = f"{prefix}{uuid.uuid4().hex[:8]}"
+    response = requests.post(
+        f"{API_BASE}/auth/register",
+        json={"username": name, "email": f"{name}@example.com", "password": f"pass-{name}"},
+        timeout=10
+    )
+    assert response.status_code == 201, f"Registration failed: {response.status_code} - {response.text}"
+    return {"Authorization": f"Bearer {response.json()['token']}"}
+
+
+def admin_headers() -> dict:
+    """Bearer auth headers of the registry administrator named by the environment"""
+    username = os.getenv("TEST_ADMIN_USERNAME")
+    password = os.getenv("TEST_ADMIN_PASSWORD")
+    if not username or not password:
+        pytest.skip("TEST_ADMIN_USERNAME and TEST_ADMIN_PASSWORD are not set")
+
+    response = requests.post(f"{API_BASE}/auth/login", json={"username": username, "password": password}, timeout=10)
+    if response.status_code != 200:
+        response = requests.post(
+            f"{API_BASE}/auth/register",
+            json={"username": username, "email": f"{username}@example.com", "password": password},
+            timeout=10
+        )
+        assert response.status_code == 201, f"Admin registration failed: {response.status_code} - {response.text}"
+    return {"Authorization": f"Bearer {response.json()['token']}"}
+
+
+class TestLegalHolds:
+    """Test legal hold placement, export, delete and overwrite blocking and release"""
+
+    def setup_method(self):
+        """Setup an organization with one pushed image"""
+        self.base_url = SERVER_URL
+        self.admin_headers = admin_headers()
+        self.owner_headers = register("holdowner")
+
+        self.org = f"legalhold{int(time.time())}{uuid.uuid4().hex[:4]}"
+        response = requests.post(
+            f"{API_BASE}/organizations",
+            headers=self.owner_headers,
+            json={"name": self.org, "display_name": "Legal Hold Test"},
+            timeout=10
+        )
+        assert response.status_code == 201, f"Organization creation failed: {response.status_code} - {response.text}"
+        self.org_id = response.json()["organization"]["id"]
+        self.holds_url = f"{API_BASE}/admin/organizations/{self.org_id}/legal-holds"
+        self.test_repo = f"{self.org}/evidence"
+
+        self.layer_data = b"Legal hold test layer" * 100
+        self.layer_digest = self._push_blob(self.layer_data)
+        self.config_data = json.dumps({
+            "architecture": "amd64",
+            "os": "linux",
+            "rootfs": {"type": "layers", "diff_ids": [self.layer_digest]}
+        }).encode('utf-8')
+        self.config_digest = self._push_blob(self.config_data)
+        self.manifest_digest = self._push_manifest("latest", self._image())
+        print(f"🧪 Testing legal holds on {self.org}")
+
+    def test_owners_cannot_manage_holds(self):
+        """Test that the organization's own owner can neither place nor lift a hold"""
+        print("\n⚖️ Testing hold management by an organization owner...")
+
+        response = requests.post(self.holds_url, headers=self.owner_headers, json={"reason": "Self-imposed"}, timeout=10)
+        assert response.status_code == 403, f"Owner placed a hold: {response.status_code} - {response.text}"
+
+        hold = self._place_hold({"reason": "Case 2024-018"})
+        response = requests.get(self.holds_url, headers=self.owner_headers, timeout=10)
+        assert response.status_code == 403
+        response = requests.post(f"{self.holds_url}/{hold['id']}/release", headers=self.owner_headers, timeout=10)
+        assert response.status_code == 403
+        assert self._holds()[0]["released_at"] is None
+
+        self._release(hold["id"])
+        print("✅ Owner refused, administrator allowed!")
+
+    def test_hold_exports_content_and_blocks_deletes_until_released(self):
+        """Test that a hold exports the organization and blocks deletes until it is released"""
+        print("\n⚖️ Testing a blocking legal hold...")
+
+        hold = self._place_hold({"reason": "Case 2024-017"})
+        assert hold["block_deletes"] is True
+        assert hold["released_at"] is None
+
+        hold = self._wait_for_export(hold["id"])
+        assert hold["status"] == "completed", f"Export did not complete: {hold}"
+        assert hold["error"] is None
+
+        response = requests.get(f"{self.holds_url}/{hold['id']}/manifest", headers=self.admin_headers, timeout=10)
+        assert response.status_code == 200, f"Manifest fetch failed: {response.status_code} - {response.text}"
+        manifest = response.json()
+        assert manifest["missing_count"] == 0
+        assert manifest["missing"] == []
+        exported = {entry["digest"]: entry for entry in manifest["blobs"]}
+        assert self.manifest_digest in exported
+        assert exported[self.manifest_digest]["verified"] is True
+        print(f"   ✅ Export holds {manifest['blob_count']} blobs")
+
+        assert self._delete_repository() == 403
+        print("   ✅ Repository delete refused during the hold")
+
+        self._release(hold["id"])
+
+        # A released hold cannot be released twice
+        response = requests.post(f"{self.holds_url}/{hold['id']}/release", headers=self.admin_headers, timeout=10)
+        assert response.status_code == 400
+
+        assert self._delete_repository() == 200
+        print("✅ Deletes allowed again after release!")
+
+    def test_hold_blocks_tag_overwrites(self):
+        """Test that a push moving an existing tag is refused while a blocking hold is active"""
+        print("\n⚖️ Testing tag overwrites under a legal hold...")
+
+        hold = self._place_hold({"reason": "Case 2024-019"})
+
+        assert self._push_manifest_status("latest", self._image(variant="replacement")) == 403
+        assert self._pulled_digest("latest") == self.manifest_digest
+        print("   ✅ Moving 'latest' refused")
+
+        # Pushing the same manifest again and adding new tags leave held metadata alone
+        assert self._push_manifest_status("latest", self._image()) in [201, 202]
+        assert self._push_manifest_status("v2", self._image(variant="replacement")) in [201, 202]
+
+        self._release(hold["id"])
+        assert self._push_manifest_status("latest", self._image(variant="replacement")) in [201, 202]
+        assert self._pulled_digest("latest") != self.manifest_digest
+        print("✅ Tag overwrites allowed again after release!")
+
+    def test_non_blocking_hold_allows_deletes(self):
+        """Test that a hold placed with block_deletes false only exports"""
+        print("\n⚖️ Testing a non-blocking legal hold...")
+
+        hold = self._place_hold({"reason": "Preservation request", "block_deletes": False})
+        assert hold["block_deletes"] is False
+        self._wait_for_export(hold["id"])
+
+        assert self._delete_repository() == 200
+        print("✅ Delete allowed under a non-blocking hold!")
+
+    def test_hold_requires_a_reason(self):
+        """Test that a hold without a reason is refused"""
+        response = requests.post(self.holds_url, headers=self.admin_headers, json={"reason": "  "}, timeout=10)
+        assert response.status_code == 400
+        assert self._holds() == []
+
+    def _image(self, variant: str = "original") -> dict:
+        """An OCI image manifest; the variant annotation gives each push its own digest"""
+        return {
+            "schemaVersion": 2,
+            "mediaType": "application/vnd.oci.image.manifest.v1+json",
+            "config": {
+                "mediaType": "application/vnd.oci.image.config.v1+json",
+                "size": len(self.config_data),
+                "digest": self.config_digest
+            },
+            "layers": [{
+                "mediaType": "application/vnd.oci.image.layer.v1.tar+gzip",
+                "size": len(self.layer_data),
+                "digest": self.layer_digest
+            }],
+            "annotations": {"test.variant": variant}
+        }
+
+    def _place_hold(self, body: dict) -> dict:
+        """Place a legal hold as the administrator and return it"""
+        response = requests.post(self.holds_url, headers=self.admin_headers, json=body, timeout=10)
+        assert response.status_code == 202, f"Placing hold failed: {response.status_code} - {response.text}"
+        return response.json()
+
+    def _release(self, hold_id: int):
+        """Release a legal hold as the administrator"""
+        response = requests.post(f"{self.holds_url}/{hold_id}/release", headers=self.admin_headers, timeout=10)
+        assert response.status_code == 200, f"Release failed: {response.status_code} - {response.text}"
+        assert response.json()["released_at"] is not None
+
+    def _holds(self) -> list:
+        """Legal holds of the organization"""
+        response = requests.get(self.holds_url, headers=self.admin_headers, timeout=10)
+        assert response.status_code == 200, f"Listing holds failed: {response.status_code} - {response.text}"
+        return response.json()["legal_holds"]
+
+    def _wait_for_export(self, hold_id: int) -> dict:
+        """Poll the hold until its export has finished"""
+        for _ in range(30):
+            hold = next(h for h in self._holds() if h["id"] == hold_id)
+            if hold["status"] != "exporting":
+                return hold
+            time.sleep(1)
+        pytest.fail(f"Export of legal hold {hold_id} did not finish")
+
+    def _delete_repository(self) -> int:
+        """Status of a repository delete"""
+        response = requests.delete(f"{API_BASE}/repos/{self.test_repo}", headers=self.owner_headers, timeout=10)
+        print(f"   Delete of {self.test_repo}: {response.status_code}")
+        return response.status_code
+
+    def _pulled_digest(self, reference: str) -> str:
+        """Digest a manifest pull is answered with"""
+        response = requests.get(
+            f"{self.base_url}/v2/{self.test_repo}/manifests/{reference}",
+            headers={**self.owner_headers, 'Accept': 'application/vnd.oci.image.manifest.v1+json'},
+            timeout=10
+        )
+        assert response.status_code == 200, f"Pull failed: {response.status_code} - {response.text}"
+        return f"sha256:{hashlib.sha256(response.content).hexdigest()}"
+
+    def _push_blob(self, data: bytes) -> str:
+        """Push a blob in one request and return its digest"""
+        digest = f"sha256:{hashlib.sha256(data).hexdigest()}"
+        response = requests.post(f"{self.base_url}/v2/{self.test_repo}/blobs/uploads/", headers=self.owner_headers, timeout=10)
+        assert response.status_code in [201, 202], f"Failed to start upload: {response.status_code} - {response.text}"
+        upload_uuid = response.headers.get("Docker-Upload-UUID")
+
+        complete_url = f"{self.base_url}/v2/{self.test_repo}/blobs/uploads/{upload_uuid}?digest={digest}"
+        response = requests.put(complete_url, headers=self.owner_headers, data=data, timeout=10)
+        assert response.status_code in [201, 202], f"Failed to complete upload: {response.status_code} - {response.text}"
+        return digest
+
+    def _push_manifest_status(self, reference: str, manifest: dict) -> int:
+        """Status of a manifest push"""
+        body = json.dumps(manifest, separators=(',', ':')).encode('utf-8')
+        response = requests.put(
+            f"{self.base_url}/v2/{self.test_repo}/manifests/{reference}",
+            headers={**self.owner_headers, 'Content-Type': manifest["mediaType"]},
+            data=body,
+            timeout=10
+        )
+        print(f"   Push of {reference}: {response.status_code}")
+        return response.status_code
+
+    def _push_manifest(self, reference: str, manifest: dict) -> str:
+        """Push a manifest and return its digest"""
+        status = self._push_manifest_status(reference, manifest)
+        assert status in [201, 202], f"Manifest push failed: {status}"
+        body = json.dumps(manifest, separators=(',', ':')).encode('utf-8')
+        return f"sha256:{hashlib.sha256(body).hexdigest()}"
+
+
+if __name__ == "__main__":
+    pytest.main([__file__, "-v", "-s"])
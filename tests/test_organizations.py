@@ -319,7 +319,7 @@ class OrganizationTests(BaseTestCase):
         # Add member
         add_data = {
             "email": member.email,
-            "role": "Member"
+            "role": "Developer"
         }
         response = self.make_request("POST", f"/organizations/{org_id}/members", data=add_data, token=owner.token)
         self.assert_response(response, 201, "Failed to add member")
@@ -330,7 +330,7 @@ class OrganizationTests(BaseTestCase):
         self.verify_json_structure(added_member, ["id", "user_id", "role", "username", "email"])
         
         assert added_member["email"] == member.email
-        assert added_member["role"] == "member"
+        assert added_member["role"] == "developer"
         member_id = added_member["user_id"]
         
         # Try to add existing member
@@ -360,7 +360,7 @@ class OrganizationTests(BaseTestCase):
         self.current_org_id = org_id
         
         # Add member
-        add_data = {"email": member.email, "role": "Member"}
+        add_data = {"email": member.email, "role": "Developer"}
         add_response = self.make_request("POST", f"/organizations/{org_id}/members", data=add_data, token=owner.token)
         self.assert_response(add_response, 201)
         
@@ -405,20 +405,20 @@ class OrganizationTests(BaseTestCase):
         self.current_org_id = org_id
         
         # Add member
-        add_data = {"email": member.email, "role": "Member"}
+        add_data = {"email": member.email, "role": "Developer"}
         add_response = self.make_request("POST", f"/organizations/{org_id}/members", data=add_data, token=owner.token)
         self.assert_response(add_response, 201)
         member_user_id = add_response.json()["member"]["user_id"]
         
-        # Update role to admin
-        update_data = {"role": "Admin"}
+        # Update role to maintainer
+        update_data = {"role": "Maintainer"}
         response = self.make_request("PUT", f"/organizations/{org_id}/members/{member_user_id}", data=update_data, token=owner.token)
         self.assert_response(response, 200, "Failed to update role")
         
         data = response.json()
         self.verify_json_structure(data, ["member"])
         updated_member = data["member"]
-        assert updated_member["role"] == "admin"
+        assert updated_member["role"] == "maintainer"
         
         self.logger.info("✅ Update member role test passed")
     
@@ -443,7 +443,7 @@ class OrganizationTests(BaseTestCase):
         self.current_org_id = org_id
         
         # Add member
-        add_data = {"email": member.email, "role": "Member"}
+        add_data = {"email": member.email, "role": "Developer"}
         add_response = self.make_request("POST", f"/organizations/{org_id}/members", data=add_data, token=owner.token)
         self.assert_response(add_response, 201)
         member_user_id = add_response.json()["member"]["user_id"]
@@ -492,6 +492,133 @@ class OrganizationTests(BaseTestCase):
         
         self.logger.info("✅ Permissions test passed")
     
+    def test_teams(self):
+        """Test team CRUD, membership, and repository grants"""
+        self.logger.info("Testing teams")
+        
+        owner = self.create_dynamic_owner()
+        self.current_owner = owner
+        member = self.create_dynamic_member()
+        
+        session_id = ''.join(random.choices(string.ascii_lowercase + string.digits, k=6))
+        org_data = {
+            "name": f"teamorg_{session_id}",
+            "display_name": f"Team Org {session_id}",
+            "description": "Org for teams test"
+        }
+        create_response = self.make_request("POST", "/organizations", data=org_data, token=owner.token)
+        self.assert_response(create_response, 201)
+        org_id = create_response.json()["organization"]["id"]
+        org_name = org_data["name"]
+        
+        repo_name = f"teamrepo_{session_id}"
+        repo_response = self.make_request("POST", f"/repos/{org_name}", data={
+            "name": repo_name,
+            "description": "Repo granted to a team",
+            "is_public": False
+        }, token=owner.token)
+        self.assert_response(repo_response, 201)
+        
+        # Only organization members can join teams
+        team_response = self.make_request("POST", f"/organizations/{org_id}/teams",
+                                          data={"name": "backend", "description": "Backend team"}, token=owner.token)
+        self.assert_response(team_response, 201, "Failed to create team")
+        team_id = team_response.json()["id"]
+        
+        duplicate = self.make_request("POST", f"/organizations/{org_id}/teams", data={"name": "backend"}, token=owner.token)
+        self.assert_response(duplicate, 400, "Duplicate team names should be rejected")
+        
+        add_data = {"email": member.email, "role": "Guest"}
+        add_response = self.make_request("POST", f"/organizations/{org_id}/members", data=add_data, token=owner.token)
+        self.assert_response(add_response, 201)
+        member_user_id = add_response.json()["member"]["user_id"]
+        
+        join = self.make_request("PUT", f"/organizations/{org_id}/teams/{team_id}/members/{member_user_id}", token=owner.token)
+        self.assert_response(join, 200, "Failed to add team member")
+        
+        grant = self.make_request("PUT", f"/organizations/{org_id}/teams/{team_id}/repositories/{repo_name}",
+                                  data={"permission": "push"}, token=owner.token)
+        self.assert_response(grant, 200, "Failed to grant repository to team")
+        assert grant.json()["permission"] == "push"
+        
+        details = self.make_request("GET", f"/organizations/{org_id}/teams/{team_id}", token=member.token)
+        self.assert_response(details, 200)
+        data = details.json()
+        assert data["member_count"] == 1
+        assert [m["user_id"] for m in data["members"]] == [member_user_id]
+        assert [r["repository_name"] for r in data["repositories"]] == [repo_name]
+        
+        # Guests cannot manage teams
+        forbidden = self.make_request("POST", f"/organizations/{org_id}/teams", data={"name": "rogue"}, token=member.token)
+        self.assert_response(forbidden, 400, "Guests should not create teams")
+        
+        # Leaving the organization drops team membership
+        remove = self.make_request("DELETE", f"/organizations/{org_id}/members/{member_user_id}", token=owner.token)
+        self.assert_response(remove, 204)
+        details = self.make_request("GET", f"/organizations/{org_id}/teams/{team_id}", token=owner.token)
+        self.assert_response(details, 200)
+        assert details.json()["member_count"] == 0
+        
+        delete = self.make_request("DELETE", f"/organizations/{org_id}/teams/{team_id}", token=owner.token)
+        self.assert_response(delete, 204, "Failed to delete team")
+        
+        self.logger.info("✅ Teams test passed")
+    
+    def test_ip_access_rules(self):
+        """Test organization IP allow/deny rule management"""
+        self.logger.info("Testing IP access rules")
+        
+        owner = self.create_dynamic_owner()
+        self.current_owner = owner
+        member = self.create_dynamic_member()
+        
+        session_id = ''.join(random.choices(string.ascii_lowercase + string.digits, k=6))
+        org_data = {
+            "name": f"iporg_{session_id}",
+            "display_name": f"IP Org {session_id}",
+            "description": "Org for IP access rules test"
+        }
+        create_response = self.make_request("POST", "/organizations", data=org_data, token=owner.token)
+        self.assert_response(create_response, 201)
+        org_id = create_response.json()["organization"]["id"]
+        
+        # Host bits are dropped so equivalent networks are stored once
+        rule_response = self.make_request("POST", f"/organizations/{org_id}/ip-rules", data={
+            "cidr": "198.51.100.7/24",
+            "action": "deny",
+            "operation": "push",
+            "description": "Block pushes from the office guest network"
+        }, token=owner.token)
+        self.assert_response(rule_response, 201, "Failed to create IP rule")
+        rule = rule_response.json()
+        assert rule["cidr"] == "198.51.100.0/24"
+        assert rule["action"] == "deny"
+        assert rule["operation"] == "push"
+        
+        duplicate = self.make_request("POST", f"/organizations/{org_id}/ip-rules",
+                                      data={"cidr": "198.51.100.0/24", "action": "deny", "operation": "push"}, token=owner.token)
+        self.assert_response(duplicate, 400, "Duplicate IP rules should be rejected")
+        
+        invalid = self.make_request("POST", f"/organizations/{org_id}/ip-rules",
+                                    data={"cidr": "not-a-network", "action": "allow"}, token=owner.token)
+        self.assert_response(invalid, 400, "Invalid CIDR should be rejected")
+        
+        listing = self.make_request("GET", f"/organizations/{org_id}/ip-rules", token=owner.token)
+        self.assert_response(listing, 200)
+        assert [r["id"] for r in listing.json()] == [rule["id"]]
+        
+        # Only owners manage network policy
+        add_response = self.make_request("POST", f"/organizations/{org_id}/members",
+                                         data={"email": member.email, "role": "Maintainer"}, token=owner.token)
+        self.assert_response(add_response, 201)
+        forbidden = self.make_request("GET", f"/organizations/{org_id}/ip-rules", token=member.token)
+        self.assert_response(forbidden, 403, "Maintainers should not manage IP rules")
+        
+        delete = self.make_request("DELETE", f"/organizations/{org_id}/ip-rules/{rule['id']}", token=owner.token)
+        self.assert_response(delete, 204, "Failed to delete IP rule")
+        
+        self.logger.info("✅ IP access rules test passed")
+    
     def run_all_tests(self):
         """Run all organization tests"""
         self.logger.info("=== Running Organization Tests ===")
@@ -507,5 +634,7 @@ class OrganizationTests(BaseTestCase):
         self.test_update_member_role()
         self.test_remove_organization_member()
         self.test_organization_permissions()
+        self.test_teams()
+        self.test_ip_access_rules()
         
         self.logger.info("✅ All organization tests passed")
@@ -0,0 +1,184 @@
+#!/usr/bin/env python3
+"""
+Push Quarantine Test
+Tests that images pushed to a repository with quarantine_pushes set are held back:
+1. A quarantined tag is refused to users who do not administer the repository
+2. Images pushed under a sha256- tag are held like any other tag
+3. Images carrying a subject field are held unless they are signature or SBOM referrers
+"""
+
+import requests
+import json
+import hashlib
+import time
+import uuid
+import pytest
+from config import SERVER_URL, API_BASE
+
+
+def register(prefix: str) -> dict:
+    """Register a fresh user and return its bearer auth headers"""
+    name = f"{prefix}{uuid.uuid4().hex[:8]}"
+    response = requests.post(
+        f"{API_BASE}/auth/register",
+        json={"username": name, "email": f"{name}@example.com", "password": f"pass-{name}"},
+        timeout=10
+    )
+    assert response.status_code == 201, f"Registration failed: {response.status_code} - {response.text}"
+    return {"Authorization": f"Bearer {response.json()['token']}"}
+
+
+class TestPushQuarantine:
+    """Test that quarantined pushes cannot be pulled around"""
+
+    def setup_method(self):
+        """Setup an organization whose repository quarantines pushes"""
+        self.base_url = SERVER_URL
+        self.owner_headers = register("qowner")
+        self.other_headers = register("qother")
+
+        self.org = f"quarantine{int(time.time())}{uuid.uuid4().hex[:4]}"
+        response = requests.post(
+            f"{API_BASE}/organizations",
+            headers=self.owner_headers,
+            json={"name": self.org, "display_name": "Quarantine Test"},
+            timeout=10
+        )
+        assert response.status_code == 201, f"Organization creation failed: {response.status_code} - {response.text}"
+        self.test_repo = f"{self.org}/held"
+
+        self.layer_data = b"Quarantine test layer" * 100
+        self.layer_digest = self._push_blob(self.layer_data)
+        self.config_data = json.dumps({
+            "architecture": "amd64",
+            "os": "linux",
+            "rootfs": {"type": "layers", "diff_ids": [self.layer_digest]}
+        }).encode('utf-8')
+        self.config_digest = self._push_blob(self.config_data)
+
+        # Pushed before quarantine is switched on, so it can be pulled and referred to
+        self.seed_digest = self._push_manifest("seed", self._image())
+
+        response = requests.put(
+            f"{API_BASE}/repos/{self.test_repo}",
+            headers=self.owner_headers,
+            json={"is_public": True, "quarantine_pushes": True},
+            timeout=10
+        )
+        assert response.status_code == 200, f"Enabling quarantine failed: {response.status_code} - {response.text}"
+        print(f"🧪 Testing quarantine in {self.test_repo}")
+
+    def test_quarantined_tag_is_refused_to_non_admins(self):
+        """Test that a held tag is only served to repository administrators"""
+        print("\n🔒 Testing pull of a quarantined tag...")
+
+        self._push_manifest("latest", self._image(variant="latest"))
+
+        assert self._pull("seed", self.other_headers) == 200
+        assert self._pull("latest", self.other_headers) == 403
+        assert self._pull("latest", self.owner_headers) == 200
+
+        print("✅ Quarantined tag refused to non-admins!")
+
+    def test_sha256_tags_are_held(self):
+        """Test that an image pushed under a sha256- tag does not skip quarantine"""
+        print("\n🔒 Testing an image pushed under a sha256- tag...")
+
+        digest = self._push_manifest("sha256-x", self._image(variant="sha256-tag"))
+
+        assert self._pull("sha256-x", self.other_headers) == 403
+        assert self._pull(digest, self.other_headers) == 403
+
+        print("✅ sha256- tagged image held!")
+
+    def test_images_with_a_subject_are_held(self):
+        """Test that adding a subject to an image does not skip quarantine"""
+        print("\n🔒 Testing an image carrying a subject...")
+
+        image = self._image(variant="subject")
+        image["subject"] = {
+            "mediaType": "application/vnd.oci.image.manifest.v1+json",
+            "digest": self.seed_digest,
+            "size": 1
+        }
+        image["artifactType"] = "application/vnd.dev.cosign.artifact.sig.v1+json"
+        self._push_manifest("with-subject", image)
+
+        assert self._pull("with-subject", self.other_headers) == 403
+
+        # A real signature referrer, without an image config, is not held
+        signature = {
+            "schemaVersion": 2,
+            "mediaType": "application/vnd.oci.image.manifest.v1+json",
+            "artifactType": "application/vnd.dev.cosign.artifact.sig.v1+json",
+            "config": {"mediaType": "application/vnd.oci.empty.v1+json", "digest": self._push_blob(b"{}"), "size": 2},
+            "layers": [{
+                "mediaType": "application/vnd.dev.cosign.simplesigning.v1+json",
+                "digest": self._push_blob(b"{\"critical\":{}}"),
+                "size": len(b"{\"critical\":{}}")
+            }],
+            "subject": {"mediaType": "application/vnd.oci.image.manifest.v1+json", "digest": self.seed_digest, "size": 1}
+        }
+        body = json.dumps(signature, separators=(',', ':')).encode('utf-8')
+        digest = f"sha256:{hashlib.sha256(body).hexdigest()}"
+        self._push_manifest(digest, signature)
+        assert self._pull(digest, self.other_headers) == 200
+
+        print("✅ Image with a subject held, signature referrer let through!")
+
+    def _image(self, variant: str = "seed") -> dict:
+        """An OCI image manifest; the variant annotation gives each push its own digest"""
+        return {
+            "schemaVersion": 2,
+            "mediaType": "application/vnd.oci.image.manifest.v1+json",
+            "config": {
+                "mediaType": "application/vnd.oci.image.config.v1+json",
+                "size": len(self.config_data),
+                "digest": self.config_digest
+            },
+            "layers": [{
+                "mediaType": "application/vnd.oci.image.layer.v1.tar+gzip",
+                "size": len(self.layer_data),
+                "digest": self.layer_digest
+            }],
+            "annotations": {"test.variant": variant}
+        }
+
+    def _push_blob(self, data: bytes) -> str:
+        """Push a blob in one request and return its digest"""
+        digest = f"sha256:{hashlib.sha256(data).hexdigest()}"
+        response = requests.post(f"{self.base_url}/v2/{self.test_repo}/blobs/uploads/", headers=self.owner_headers, timeout=10)
+        assert response.status_code in [201, 202], f"Failed to start upload: {response.status_code} - {response.text}"
+        upload_uuid = response.headers.get("Docker-Upload-UUID")
+
+        complete_url = f"{self.base_url}/v2/{self.test_repo}/blobs/uploads/{upload_uuid}?digest={digest}"
+        response = requests.put(complete_url, headers=self.owner_headers, data=data, timeout=10)
+        assert response.status_code in [201, 202], f"Failed to complete upload: {response.status_code} - {response.text}"
+        return digest
+
+    def _push_manifest(self, reference: str, manifest: dict) -> str:
+        """Push a manifest and return its digest"""
+        body = json.dumps(manifest, separators=(',', ':')).encode('utf-8')
+        response = requests.put(
+            f"{self.base_url}/v2/{self.test_repo}/manifests/{reference}",
+            headers={**self.owner_headers, 'Content-Type': manifest["mediaType"]},
+            data=body,
+            timeout=10
+        )
+        assert response.status_code in [201, 202], f"Manifest push failed: {response.status_code} - {response.text}"
+        print(f"   ✅ Pushed {reference}")
+        return f"sha256:{hashlib.sha256(body).hexdigest()}"
+
+    def _pull(self, reference: str, headers: dict) -> int:
+        """Status of a manifest pull"""
+        response = requests.get(
+            f"{self.base_url}/v2/{self.test_repo}/manifests/{reference}",
+            headers={**headers, 'Accept': 'application/vnd.oci.image.manifest.v1+json'},
+            timeout=10
+        )
+        print(f"   Pull of {reference}: {response.status_code}")
+        return response.status_code
+
+
+if __name__ == "__main__":
+    pytest.main([__file__, "-v", "-s"])
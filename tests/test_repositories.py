@@ -8,13 +8,18 @@ sys.path.insert(0, os.path.dirname(os.path.abspath(__file__)))
 
 try:
     from base_test import BaseTestCase, test_data_manager
-    from config import TEST_USERS, TestUser
+    from config import TEST_USERS, TestUser, SERVER_URL
 except ImportError:
     from .base_test import BaseTestCase, test_data_manager
-    from .config import TEST_USERS, TestUser
+    from .config import TEST_USERS, TestUser, SERVER_URL
 
+import hashlib
+import json
 import random
 import string
+import time
+
+import requests
 
 
 class RepositoryTests(BaseTestCase):
@@ -306,6 +311,299 @@ class RepositoryTests(BaseTestCase):
         
         self.logger.info("✅ Delete repository test passed")
     
+    def test_clone_repository(self):
+        """Test cloning a repository into the same organization"""
+        self.logger.info("Testing clone repository")
+        
+        owner = self.create_dynamic_owner()
+        self.current_owner = owner
+        self.create_dynamic_org(owner)
+        org_name = self.current_org["name"]
+        
+        session_id = ''.join(random.choices(string.ascii_lowercase + string.digits, k=6))
+        repo_data = {
+            "name": f"clonesrc_{session_id}",
+            "description": "Clone source",
+            "is_public": False
+        }
+        create_response = self.make_request("POST", f"/repos/{org_name}", data=repo_data, token=owner.token)
+        self.assert_response(create_response, 201)
+        
+        clone_data = {"name": f"clonedst_{session_id}"}
+        response = self.make_request("POST", f"/repos/{org_name}/{repo_data['name']}/clone", data=clone_data, token=owner.token)
+        self.assert_response(response, 201, "Failed to clone repository")
+        
+        data = response.json()
+        self.verify_json_structure(data, ["repository", "tags", "mounted_blobs"])
+        assert data["repository"]["name"] == clone_data["name"]
+        assert data["repository"]["description"] == repo_data["description"]
+        assert data["repository"]["is_public"] == repo_data["is_public"]
+        
+        # Cloning onto an existing name conflicts
+        conflict_response = self.make_request("POST", f"/repos/{org_name}/{repo_data['name']}/clone", data=clone_data, token=owner.token)
+        self.assert_response(conflict_response, 409, "Duplicate clone should conflict")
+        
+        # Unknown tags are rejected
+        bad_tags = {"name": f"clonebad_{session_id}", "tags": ["does-not-exist"]}
+        bad_response = self.make_request("POST", f"/repos/{org_name}/{repo_data['name']}/clone", data=bad_tags, token=owner.token)
+        self.assert_response(bad_response, 400, "Unknown tag selection should be rejected")
+        
+        self.logger.info("✅ Clone repository test passed")
+    
+    def test_clone_repository_with_collaborator_access(self):
+        """Test that a pull grant on a private repository is enough to clone it"""
+        self.logger.info("Testing clone with collaborator access")
+        
+        owner = self.create_dynamic_owner()
+        self.current_owner = owner
+        self.create_dynamic_org(owner)
+        source_org = self.current_org["name"]
+        
+        session_id = ''.join(random.choices(string.ascii_lowercase + string.digits, k=6))
+        source_repo = f"sharedsrc_{session_id}"
+        create_response = self.make_request("POST", f"/repos/{source_org}", data={
+            "name": source_repo,
+            "description": "Private repo shared with a collaborator",
+            "is_public": False
+        }, token=owner.token)
+        self.assert_response(create_response, 201)
+        
+        collaborator = self.create_dynamic_member()
+        target_org = self.create_dynamic_org(collaborator)["name"]
+        clone_data = {"name": f"sharedcopy_{session_id}", "target_namespace": target_org}
+        
+        denied = self.make_request("POST", f"/repos/{source_org}/{source_repo}/clone", data=clone_data, token=collaborator.token)
+        self.assert_response(denied, 403, "Clone without access should be refused")
+        
+        grant = self.make_request("PUT", f"/repos/{source_org}/{source_repo}/collaborators/{collaborator.username}",
+                                  data={"permission": "pull"}, token=owner.token)
+        self.assert_response(grant, 200, "Failed to add collaborator")
+        
+        response = self.make_request("POST", f"/repos/{source_org}/{source_repo}/clone", data=clone_data, token=collaborator.token)
+        self.assert_response(response, 201, "Pull collaborator should clone the repository")
+        assert response.json()["repository"]["name"] == clone_data["name"]
+        
+        self.logger.info("✅ Clone with collaborator access test passed")
+    
+    def test_clone_keeps_content_after_source_removal(self):
+        """Test that deleting the source and collecting its storage keys leaves the clone readable"""
+        self.logger.info("Testing clone content after source removal")
+        
+        owner = self.create_dynamic_owner()
+        self.current_owner = owner
+        self.create_dynamic_org(owner)
+        org_name = self.current_org["name"]
+        
+        session_id = ''.join(random.choices(string.ascii_lowercase + string.digits, k=6))
+        source_repo = f"gcsrc_{session_id}"
+        clone_repo = f"gccopy_{session_id}"
+        create_response = self.make_request("POST", f"/repos/{org_name}", data={
+            "name": source_repo,
+            "description": "Clone source to be deleted",
+            "is_public": False
+        }, token=owner.token)
+        self.assert_response(create_response, 201)
+        
+        layer = f"layer of {source_repo}".encode("utf-8") * 64
+        layer_digest = self._push_blob(owner, f"{org_name}/{source_repo}", layer)
+        config = json.dumps({"architecture": "amd64", "os": "linux", "rootfs": {"type": "layers", "diff_ids": [layer_digest]}}).encode("utf-8")
+        config_digest = self._push_blob(owner, f"{org_name}/{source_repo}", config)
+        manifest = json.dumps({
+            "schemaVersion": 2,
+            "mediaType": "application/vnd.oci.image.manifest.v1+json",
+            "config": {"mediaType": "application/vnd.oci.image.config.v1+json", "size": len(config), "digest": config_digest},
+            "layers": [{"mediaType": "application/vnd.oci.image.layer.v1.tar+gzip", "size": len(layer), "digest": layer_digest}]
+        }, separators=(',', ':')).encode("utf-8")
+        push = requests.put(
+            f"{SERVER_URL}/v2/{org_name}/{source_repo}/manifests/latest",
+            headers={"Authorization": f"Bearer {owner.token}", "Content-Type": "application/vnd.oci.image.manifest.v1+json"},
+            data=manifest,
+            timeout=30
+        )
+        assert push.status_code in [201, 202], f"Failed to push manifest: {push.status_code} - {push.text}"
+        manifest_digest = f"sha256:{hashlib.sha256(manifest).hexdigest()}"
+        
+        clone = self.make_request("POST", f"/repos/{org_name}/{source_repo}/clone", data={"name": clone_repo}, token=owner.token)
+        self.assert_response(clone, 201, "Failed to clone repository")
+        assert clone.json()["mounted_blobs"] == 3
+        
+        delete_response = self.make_request("DELETE", f"/repos/{org_name}/{source_repo}", token=owner.token)
+        self.assert_response(delete_response, 200, "Failed to delete the source repository")
+        self._assert_clone_readable(owner, f"{org_name}/{clone_repo}", {layer_digest: layer, config_digest: config}, manifest)
+        
+        # Queue the source's storage keys as removed content is queued, and wait for a collection pass
+        source_keys = [f"{org_name}/{source_repo}/{digest}" for digest in (manifest_digest, config_digest, layer_digest)]
+        conn = self.get_db_connection()
+        try:
+            with conn.cursor() as cur:
+                cur.execute(
+                    "INSERT INTO blob_gc_queue (storage_key, reason) SELECT unnest(%s::TEXT[]), 'clone source test' RETURNING id",
+                    (source_keys,)
+                )
+                queued = [row[0] for row in cur.fetchall()]
+            conn.commit()
+            
+            deadline = time.time() + int(os.getenv("GC_INTERVAL_SECONDS", "300")) + 30
+            while True:
+                with conn.cursor() as cur:
+                    cur.execute("SELECT last_error FROM blob_gc_queue WHERE id = ANY(%s) AND processed_at IS NOT NULL", (queued,))
+                    processed = [row[0] for row in cur.fetchall()]
+                conn.commit()
+                if len(processed) == len(queued) or time.time() > deadline:
+                    break
+                time.sleep(2)
+        finally:
+            conn.close()
+        
+        assert len(processed) == len(queued), "Garbage collection did not process the queued keys"
+        assert all(error == "retained: still referenced" for error in processed), f"Mounted keys were collected: {processed}"
+        self._assert_clone_readable(owner, f"{org_name}/{clone_repo}", {layer_digest: layer, config_digest: config}, manifest)
+        
+        self.logger.info("✅ Clone content after source removal test passed")
+    
+    def _push_blob(self, user, repository: str, data: bytes) -> str:
+        """Push a blob in one request and return its digest"""
+        digest = f"sha256:{hashlib.sha256(data).hexdigest()}"
+        headers = {"Authorization": f"Bearer {user.token}"}
+        start = requests.post(f"{SERVER_URL}/v2/{repository}/blobs/uploads/", headers=headers, timeout=30)
+        assert start.status_code in [201, 202], f"Failed to start upload: {start.status_code} - {start.text}"
+        upload_uuid = start.headers.get("Docker-Upload-UUID")
+        
+        complete = requests.put(f"{SERVER_URL}/v2/{repository}/blobs/uploads/{upload_uuid}?digest={digest}",
+                                headers=headers, data=data, timeout=30)
+        assert complete.status_code in [201, 202], f"Failed to complete upload: {complete.status_code} - {complete.text}"
+        return digest
+    
+    def _assert_clone_readable(self, user, repository: str, blobs: dict, manifest: bytes):
+        """Pull the clone's manifest and blobs and compare them with what was pushed"""
+        headers = {"Authorization": f"Bearer {user.token}"}
+        pulled = requests.get(f"{SERVER_URL}/v2/{repository}/manifests/latest",
+                              headers={**headers, "Accept": "application/vnd.oci.image.manifest.v1+json"}, timeout=30)
+        assert pulled.status_code == 200, f"Manifest pull from the clone failed: {pulled.status_code} - {pulled.text}"
+        assert pulled.content == manifest
+        for digest, data in blobs.items():
+            blob = requests.get(f"{SERVER_URL}/v2/{repository}/blobs/{digest}", headers=headers, timeout=30)
+            assert blob.status_code == 200, f"Blob {digest} missing from the clone: {blob.status_code}"
+            assert blob.content == data
+    
+    def test_repository_role_permissions(self):
+        """Test that organization roles gate repository create and delete"""
+        self.logger.info("Testing repository role permissions")
+        
+        owner = self.create_dynamic_owner()
+        self.current_owner = owner
+        self.create_dynamic_org(owner)
+        org_name = self.current_org["name"]
+        
+        developer = self.create_dynamic_member()
+        guest = self.create_dynamic_member()
+        for user, role in ((developer, "Developer"), (guest, "Guest")):
+            add_data = {"email": user.email, "role": role}
+            add_response = self.make_request("POST", f"/organizations/{self.current_org_id}/members", data=add_data, token=owner.token)
+            self.assert_response(add_response, 201, f"Failed to add {role.lower()}")
+        
+        session_id = ''.join(random.choices(string.ascii_lowercase + string.digits, k=6))
+        
+        # Guests are read-only
+        guest_repo = {"name": f"guestrepo_{session_id}", "description": "Guest repo", "is_public": False}
+        guest_response = self.make_request("POST", f"/repos/{org_name}", data=guest_repo, token=guest.token)
+        self.assert_response(guest_response, 403, "Guest should not create repositories")
+        
+        # Developers can create but not delete
+        dev_repo = {"name": f"devrepo_{session_id}", "description": "Developer repo", "is_public": False}
+        dev_response = self.make_request("POST", f"/repos/{org_name}", data=dev_repo, token=developer.token)
+        self.assert_response(dev_response, 201, "Developer should create repositories")
+        
+        delete_response = self.make_request("DELETE", f"/repos/{org_name}/{dev_repo['name']}", token=developer.token)
+        self.assert_response(delete_response, 403, "Developer should not delete repositories")
+        
+        # Owners can delete
+        owner_delete = self.make_request("DELETE", f"/repos/{org_name}/{dev_repo['name']}", token=owner.token)
+        self.assert_response(owner_delete, 200, "Owner should delete repositories")
+        
+        self.logger.info("✅ Repository role permissions test passed")
+    
+    def test_repository_collaborators(self):
+        """Test granting and revoking repository-level access"""
+        self.logger.info("Testing repository collaborators")
+        
+        owner = self.create_dynamic_owner()
+        self.current_owner = owner
+        self.create_dynamic_org(owner)
+        org_name = self.current_org["name"]
+        outsider = self.create_dynamic_member()
+        
+        session_id = ''.join(random.choices(string.ascii_lowercase + string.digits, k=6))
+        repo_name = f"collabrepo_{session_id}"
+        create_response = self.make_request("POST", f"/repos/{org_name}", data={
+            "name": repo_name,
+            "description": "Private repo shared with a collaborator",
+            "is_public": False
+        }, token=owner.token)
+        self.assert_response(create_response, 201)
+        
+        # Private repo is hidden from non-members
+        hidden = self.make_request("GET", f"/repos/{org_name}/repositories/{repo_name}", token=outsider.token)
+        self.assert_response(hidden, 404, "Private repo should be hidden before the grant")
+        
+        grant = self.make_request("PUT", f"/repos/{org_name}/{repo_name}/collaborators/{outsider.username}",
+                                  data={"permission": "pull"}, token=owner.token)
+        self.assert_response(grant, 200, "Failed to add collaborator")
+        assert grant.json()["permission"] == "pull"
+        
+        visible = self.make_request("GET", f"/repos/{org_name}/repositories/{repo_name}", token=outsider.token)
+        self.assert_response(visible, 200, "Collaborator should see the repository")
+        
+        listing = self.make_request("GET", f"/repos/{org_name}/{repo_name}/collaborators", token=owner.token)
+        self.assert_response(listing, 200)
+        assert outsider.username in [c["username"] for c in listing.json()]
+        
+        # Pull-only collaborators cannot manage grants
+        escalate = self.make_request("PUT", f"/repos/{org_name}/{repo_name}/collaborators/{outsider.username}",
+                                     data={"permission": "admin"}, token=outsider.token)
+        self.assert_response(escalate, 403, "Pull collaborator should not manage grants")
+        
+        revoke = self.make_request("DELETE", f"/repos/{org_name}/{repo_name}/collaborators/{outsider.username}", token=owner.token)
+        self.assert_response(revoke, 204, "Failed to remove collaborator")
+        
+        hidden_again = self.make_request("GET", f"/repos/{org_name}/repositories/{repo_name}", token=outsider.token)
+        self.assert_response(hidden_again, 404, "Private repo should be hidden after revocation")
+        
+        self.logger.info("✅ Repository collaborators test passed")
+    
+    def test_resolve_digest_prefix(self):
+        """Test abbreviated digest resolution validation and access"""
+        self.logger.info("Testing digest prefix resolution")
+        
+        owner = self.create_dynamic_owner()
+        self.current_owner = owner
+        self.create_dynamic_org(owner)
+        org_name = self.current_org["name"]
+        outsider = self.create_dynamic_member()
+        
+        session_id = ''.join(random.choices(string.ascii_lowercase + string.digits, k=6))
+        repo_name = f"digestrepo_{session_id}"
+        create_response = self.make_request("POST", f"/repos/{org_name}", data={
+            "name": repo_name,
+            "description": "Repo for digest prefix lookups",
+            "is_public": False
+        }, token=owner.token)
+        self.assert_response(create_response, 201)
+        
+        too_short = self.make_request("GET", f"/repos/{org_name}/{repo_name}/digests/abc", token=owner.token)
+        self.assert_response(too_short, 400, "Prefixes shorter than 4 characters should be rejected")
+        
+        not_hex = self.make_request("GET", f"/repos/{org_name}/{repo_name}/digests/sha256:zzzz", token=owner.token)
+        self.assert_response(not_hex, 400, "Non-hex prefixes should be rejected")
+        
+        missing = self.make_request("GET", f"/repos/{org_name}/{repo_name}/digests/sha256:deadbeef", token=owner.token)
+        self.assert_response(missing, 404, "Unknown prefixes should not resolve")
+        
+        hidden = self.make_request("GET", f"/repos/{org_name}/{repo_name}/digests/deadbeef", token=outsider.token)
+        self.assert_response(hidden, 404, "Private repositories should be hidden from outsiders")
+        
+        self.logger.info("✅ Digest prefix resolution test passed")
+    
     # def test_set_repository_permissions(self):
     #     """Test setting repository permissions"""
     #     self.logger.info("Testing set repository permissions")
@@ -317,7 +615,7 @@ class RepositoryTests(BaseTestCase):
     #     member = self.create_dynamic_member()
         
     #     # Add member to org first
-    #     add_data = {"email": member.email, "role": "Member"}
+    #     add_data = {"email": member.email, "role": "Developer"}
     #     add_response = self.make_request("POST", f"/organizations/{self.current_org_id}/members", data=add_data, token=owner.token)
     #     self.assert_response(add_response, 201)
         
@@ -393,6 +691,12 @@ class RepositoryTests(BaseTestCase):
         self.test_list_repositories()
         self.test_get_repository()
         self.test_delete_repository()
+        self.test_clone_repository()
+        self.test_clone_repository_with_collaborator_access()
+        self.test_clone_keeps_content_after_source_removal()
+        self.test_repository_role_permissions()
+        self.test_repository_collaborators()
+        self.test_resolve_digest_prefix()
         # self.test_set_repository_permissions()
         # self.test_repository_permissions()
         